@@ -0,0 +1,64 @@
+//! `angstrom archive-rpc`, serves the archived rounds of the validators
+//! without running a node.
+//!
+//! The archive node reads everything from the directories the validators
+//! write into (`--order-archive-dir` and `--clearing-reports-dir`), it takes
+//! no part in order intake or the p2p network, so analytical traffic can be
+//! served away from the validators.
+use std::{net::SocketAddr, path::PathBuf};
+
+use angstrom_rpc::{
+    api::{ArchiveApiServer, ClearingApiServer},
+    start_archive_server, ArchiveApi, ClearingApi
+};
+use angstrom_types::orders::{ClearingReportStore, OrderArchive};
+use clap::Parser;
+use reth::args::LogArgs;
+
+use crate::cli::{init_tracing, LogFormat};
+
+pub const ARCHIVE_RPC_COMMAND: &str = "archive-rpc";
+
+/// Serves the archived books, solutions and clearing reports over rpc.
+#[derive(Debug, Parser)]
+#[clap(name = ARCHIVE_RPC_COMMAND)]
+pub struct ArchiveRpcCommand {
+    /// directory the validators archive their rounds into
+    #[clap(long)]
+    pub archive_dir:          PathBuf,
+    /// directory the validators write their clearing reports into, the
+    /// clearing namespace isn't served if unset
+    #[clap(long)]
+    pub clearing_reports_dir: Option<PathBuf>,
+    #[clap(long, default_value = "127.0.0.1:8548")]
+    pub addr:                 SocketAddr,
+    #[clap(long, value_enum, default_value_t = LogFormat::Terminal)]
+    pub log_format:           LogFormat,
+    #[clap(flatten)]
+    pub logs:                 LogArgs
+}
+
+impl ArchiveRpcCommand {
+    pub fn run(self) -> eyre::Result<()> {
+        // reth isn't there to install a subscriber for us
+        let log_dir = self.logs.log_file_directory.join(ARCHIVE_RPC_COMMAND);
+        let _tracing = init_tracing(&self.logs, log_dir.as_ref(), None, self.log_format)?;
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(self.serve())
+    }
+
+    async fn serve(self) -> eyre::Result<()> {
+        let mut methods = ArchiveApi::new(OrderArchive::new(self.archive_dir)).into_rpc();
+        if let Some(dir) = self.clearing_reports_dir {
+            methods.merge(ClearingApi::new(ClearingReportStore::new(Some(dir))).into_rpc())?;
+        }
+
+        let server = start_archive_server(self.addr, methods).await?;
+        tracing::info!(addr = %self.addr, "serving the archive");
+        server.stopped().await;
+
+        Ok(())
+    }
+}
@@ -1,42 +1,406 @@
-use std::path::PathBuf;
+use std::{
+    ffi::OsString,
+    net::SocketAddr,
+    path::{Path, PathBuf}
+};
 
 use alloy_primitives::Address;
-use angstrom_metrics::initialize_prometheus_metrics;
-use angstrom_types::contract_bindings::angstrom::Angstrom::PoolKey;
+use angstrom_metrics::{
+    initialize_health_endpoint, initialize_prometheus_metrics, otlp_layer, OtlpConfig, OtlpGuard
+};
+use angstrom_network::PinnedPeer;
+use angstrom_types::{
+    consensus::{ProposalCommittee, ProtocolGenesis},
+    contract_bindings::angstrom::Angstrom::PoolKey,
+    orders::{InvariantMode, ORDER_ARCHIVE_BLOCKS_KEPT},
+    primitive::PeerId
+};
+use consensus::{AngstromValidator, ConsensusTiming};
 use eyre::Context;
+use reth::args::LogArgs;
 use serde::Deserialize;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation}
+};
+use tracing_subscriber::{
+    layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry
+};
 use url::Url;
+use validation::order::state::{bond::SearcherBondConfig, hooks::HookAllowlist};
 
 #[derive(Debug, Clone, Default, clap::Args)]
 pub struct AngstromConfig {
     #[clap(long)]
-    pub mev_guard:           bool,
+    pub mev_guard: bool,
+    /// follows the consensus rounds without taking part in them. the orders
+    /// of every block are still matched and its proposal verified, with all
+    /// of the metrics and rpcs, but nothing is signed or submitted. the node
+    /// doesn't need to be a validator, validators admit it as a peer they
+    /// only send to
+    #[clap(long)]
+    pub observer: bool,
     #[clap(long)]
     pub secret_key_location: PathBuf,
+    /// key the node stakes with, if it isn't the one at
+    /// `--secret-key-location`. The node signs a binding of its network key
+    /// with it, so that it keeps its reputation when the network key rotates
+    #[clap(long)]
+    pub staking_key_location: Option<PathBuf>,
     #[clap(long)]
-    pub angstrom_addr:       Option<Address>,
+    pub angstrom_addr: Option<Address>,
     #[clap(long)]
-    pub pool_manager_addr:   Option<Address>,
+    pub pool_manager_addr: Option<Address>,
     #[clap(long)]
-    pub node_config:         PathBuf,
+    pub node_config: PathBuf,
     /// enables the metrics
     #[clap(long, default_value = "false", global = true)]
-    pub metrics:             bool,
+    pub metrics: bool,
     /// spawns the prometheus metrics exporter at the specified port
     /// Default: 6969
     #[clap(long, default_value = "6969", global = true)]
-    pub metrics_port:        u16,
+    pub metrics_port: u16,
+    /// spawns the health and readiness endpoints at the specified port
+    #[clap(long)]
+    pub health_port: Option<u16>,
+    /// seconds a module can hold up a block transition before it's reported
+    /// as stalled
+    #[clap(long, default_value = "24")]
+    pub block_sync_stall_timeout_secs: u64,
     #[clap(short, long, default_value = "https://rpc.flashbots.net")]
-    pub mev_boost_endpoints: Vec<Url>
+    pub mev_boost_endpoints: Vec<Url>,
+    /// milliseconds a relay has to accept a bundle before it counts as
+    /// rejecting it
+    #[clap(long, default_value = "500")]
+    pub relay_timeout_ms: u64,
+    /// sends bundles that every relay rejected or timed out on to the public
+    /// mempool of the node, so the block isn't lost to a relay outage. only
+    /// the leader does, and only with bundles that don't revert
+    #[clap(long)]
+    pub public_mempool_fallback: bool,
+    /// priority fee of bundles sent to the public mempool, in percent of the
+    /// estimated one
+    #[clap(long, default_value = "200")]
+    pub fallback_priority_fee_pct: u64,
+    /// the priority fee of bundles sent to the public mempool is kept at or
+    /// above this, in wei
+    #[clap(long, default_value = "1000000000")]
+    pub fallback_min_priority_fee: u128,
+    /// the priority fee of bundles sent to the public mempool is kept at or
+    /// below this, in wei
+    #[clap(long)]
+    pub fallback_max_priority_fee: Option<u128>,
+    /// rpc endpoint the state reads of validation are retried against when
+    /// the database of the node fails them, at the block the node is at
+    #[clap(long)]
+    pub validation_fallback_rpc: Option<Url>,
+    /// caps the bytes/sec of order gossip sent to a single peer. consensus
+    /// messages are never throttled
+    #[clap(long)]
+    pub max_peer_outbound_bytes_per_sec: Option<u64>,
+    /// caps the bytes/sec of order gossip taken from a single peer. gossip
+    /// over the cap is dropped and v2 peers are asked to back off until the
+    /// next second. consensus messages are never throttled
+    #[clap(long)]
+    pub max_peer_inbound_bytes_per_sec: Option<u64>,
+    /// validator peers, as `enode://<id>@<ip>:<port>`, the node always stays
+    /// connected to. they're redialed with exponential backoff whenever they
+    /// drop, whatever discovery finds
+    #[clap(long, value_delimiter = ',')]
+    pub pinned_peers: Vec<PinnedPeer>,
+    /// seconds a pinned peer can be unreachable before it's alerted on
+    #[clap(long, default_value = "120")]
+    pub pinned_peer_alert_after_secs: u64,
+    /// seconds between the checks of the price and tick of every synced pool
+    /// against the chain. pools that diverged are resynced
+    #[clap(long, default_value = "60")]
+    pub pool_integrity_check_secs: u64,
+    /// peer id of the hot standby every accepted order and cancellation is
+    /// mirrored to, so it takes over with a warm pool
+    #[clap(long)]
+    pub mirror_orders_to: Option<PeerId>,
+    /// peer id of the primary this node is the standby of. Mirrored order
+    /// flow of any other peer is dropped
+    #[clap(long)]
+    pub mirror_orders_from: Option<PeerId>,
+    /// writes the clearing report of every block into the directory, next to
+    /// serving them over rpc
+    #[clap(long)]
+    pub clearing_reports_dir: Option<PathBuf>,
+    /// archives the books and solution of every round into the directory,
+    /// for `angstrom archive-rpc` to serve
+    #[clap(long)]
+    pub order_archive_dir: Option<PathBuf>,
+    /// blocks the archived rounds are kept for before they're removed
+    #[clap(long, default_value_t = ORDER_ARCHIVE_BLOCKS_KEPT)]
+    pub order_archive_blocks: u64,
+    /// writes the call frames, revert data and storage of every failed bundle
+    /// simulation into the directory, named by the trace id that's logged
+    #[clap(long)]
+    pub sim_trace_dir: Option<PathBuf>,
+    /// keeps the key handovers of the validators in the file, so a restarted
+    /// node still knows the keys they rotated to
+    #[clap(long)]
+    pub key_handovers_path: Option<PathBuf>,
+    /// share of the key of the proposal committee of the node config, if
+    /// the node is a member of it
+    #[clap(long)]
+    pub committee_key_share_location: Option<PathBuf>,
+    /// takes the fee of each pool from its matched surplus and gives the
+    /// protocol this share of it, in millionths. the rest is donated to the
+    /// LPs. no fees are taken if unset
+    #[clap(long)]
+    pub protocol_fee_share_e6: Option<u32>,
+    /// keeps the books of the matching engine sorted across rounds and only
+    /// applies the orders that changed, instead of rebuilding them each round.
+    /// the books come out the same either way
+    #[clap(long)]
+    pub incremental_books: bool,
+    /// rejects orders of an address that already has this many open orders
+    #[clap(long)]
+    pub max_open_orders_per_account: Option<usize>,
+    /// rejects orders that take the combined size of an address's open orders
+    /// in a pool over this, in the pool's token0
+    #[clap(long)]
+    pub max_account_notional_per_pool: Option<u128>,
+    /// once the limit order pool is full, evicts the parked order that pays
+    /// the least per unit of gas to admit a new one
+    #[clap(long)]
+    pub evict_parked_orders: bool,
+    /// once the limit order pool is full, evicts the oldest order smaller than
+    /// this, in the pool's token0, to admit a larger one
+    #[clap(long)]
+    pub dust_order_notional: Option<u128>,
+    /// percent of its max size a full limit order pool is evicted down to
+    #[clap(long, default_value_t = order_pool::LOW_WATERMARK_PCT_DEFAULT)]
+    pub admission_low_watermark_pct: usize,
+    /// order validations the validator can have queued before new orders from
+    /// the rpc are turned away
+    #[clap(long, default_value_t = validation::queue::DEFAULT_VALIDATION_QUEUE_LIMIT)]
+    pub validation_queue_limit: usize,
+    /// how long submitters turned away by a full validation queue are told to
+    /// wait, in milliseconds
+    #[clap(
+        long,
+        default_value_t = validation::queue::DEFAULT_VALIDATION_RETRY_AFTER.as_millis() as u64
+    )]
+    pub validation_retry_after_ms: u64,
+    /// unix socket of an out of process solver the rounds are matched with.
+    /// the round fails when the solver does or is too slow
+    #[clap(long)]
+    pub external_matcher_ipc: Option<PathBuf>,
+    /// how long the external solver has to answer, in milliseconds
+    #[clap(long, default_value_t = 1500, requires = "external_matcher_ipc")]
+    pub external_matcher_timeout_ms: u64,
+    /// serves the operator admin namespace (`angstromAdmin`) at the address.
+    /// the server is plain http, bind it to a private interface and leave
+    /// mTLS to a proxy in front of it. the stdout logs are filtered as reth
+    /// would until the filter is set over the admin rpc, see [`init_tracing`]
+    #[clap(long, requires = "admin_rpc_token_file")]
+    pub admin_rpc_addr: Option<SocketAddr>,
+    /// file holding the bearer token every admin request has to carry
+    #[clap(long)]
+    pub admin_rpc_token_file: Option<PathBuf>,
+    /// serves the order namespace to the public at the address. every call
+    /// needs one of the api keys of `--public-rpc-keys` in the `x-api-key`
+    /// header and counts against its rate limit
+    #[clap(long, requires = "public_rpc_keys")]
+    pub public_rpc_addr: Option<SocketAddr>,
+    /// toml file with the api keys of the public rpc, their rate limits and
+    /// the methods they may call
+    #[clap(long)]
+    pub public_rpc_keys: Option<PathBuf>,
+    /// toml file with the external order flow auction feeds orders are pulled
+    /// from. their orders are validated like any other
+    #[clap(long)]
+    pub order_flow_feeds: Option<PathBuf>,
+    /// lets the operator register webhooks (`angstrom_registerWebhook`) that
+    /// fills, expiries and our landed bundles are posted to. only served on
+    /// the rpc of the node, never on the public one
+    #[clap(long)]
+    pub webhooks: bool,
+    /// propagates orders with the time they entered the network and records
+    /// how long they took to arrive. peers learn which orders entered the
+    /// network at this node, only meant for networks that measure gossip
+    /// latency
+    #[clap(long)]
+    pub propagation_tracing: bool,
+    /// pushes new orders in full to this many random peers and only
+    /// announces their hashes to the rest, which pull the ones they miss.
+    /// Unset pushes them to every peer
+    #[clap(long)]
+    pub gossip_fanout: Option<usize>,
+    /// keeps the hashes of recently filled and cancelled orders in the file,
+    /// so a restarted node doesn't accept or propagate them again
+    #[clap(long)]
+    pub settled_orders_path: Option<PathBuf>,
+    /// how long the hashes of filled orders are remembered for, in seconds
+    #[clap(long, default_value_t = order_pool::FILLED_ORDERS_TTL_DEFAULT.as_secs())]
+    pub filled_orders_ttl_secs: u64,
+    /// most hashes of filled orders remembered, the ones closest to expiry
+    /// are forgotten first past it
+    #[clap(long, default_value_t = order_pool::FILLED_ORDERS_CAPACITY_DEFAULT)]
+    pub filled_orders_capacity: usize,
+    /// checks the consistency of the order storage and the books after every
+    /// change. `report` logs violations and counts them in the metrics,
+    /// `panic` stops the node. meant for debug and staging deployments
+    #[clap(long, default_value_t = InvariantMode::Disabled)]
+    pub invariant_checks: InvariantMode,
+    /// format of the stdout logs. `json` emits one object per line with the
+    /// span fields (`order_hash`, `round_id`) attached to every event
+    #[clap(long, value_enum, default_value_t = LogFormat::Terminal, global = true)]
+    pub log_format: LogFormat,
+    /// exports the order, solve and round spans to the otlp collector at the
+    /// given endpoint, e.g. `http://localhost:4317`. the logs are written as
+    /// reth would, see [`init_tracing`]
+    #[clap(long)]
+    pub otlp_endpoint: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Terminal,
+    Json
+}
+
+/// reth installs the tracing subscriber before our args are handed to us, so
+/// `--log-format json` is forwarded as reth's `--log.stdout.format json`.
+pub fn with_log_format_args(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args = args.into_iter().collect::<Vec<_>>();
+
+    let wants_json = args.iter().enumerate().any(|(i, arg)| {
+        arg == "--log-format=json"
+            || (arg == "--log-format" && args.get(i + 1).is_some_and(|next| next == "json"))
+    });
+    if wants_json {
+        args.extend(["--log.stdout.format".into(), "json".into()]);
+    }
+
+    args
+}
+
+/// Replaces the filter of the logs of the subscriber [`init_tracing`]
+/// installs.
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Our tracing subscriber. Exports traces for as long as it's held, flushing
+/// the remaining ones on drop.
+pub struct NodeTracing {
+    pub log_filter: LogFilterHandle,
+    _log_file:      Option<WorkerGuard>,
+    // the guard is dropped before the runtime it flushes on
+    _otlp:          Option<(OtlpGuard, tokio::runtime::Runtime)>
+}
+
+/// reth only installs its tracing subscriber if none is set yet, so ours is
+/// installed ahead of it when we need more than it offers: exporting spans to
+/// an otlp endpoint or reloading the log filter. It keeps to the log options
+/// of reth: the stdout logs are filtered by the verbosity, `RUST_LOG` and
+/// `--log.stdout.filter`, and the file logs are written into `log_dir` with
+/// `--log.file.filter`, rotated daily rather than by size. Nothing is logged
+/// to journald. The spans are exported from a runtime of their own as the one
+/// of the node isn't up yet.
+pub fn init_tracing(
+    logs: &LogArgs,
+    log_dir: &Path,
+    otlp_endpoint: Option<&str>,
+    log_format: LogFormat
+) -> eyre::Result<NodeTracing> {
+    let (otlp, otlp_export) = match otlp_endpoint {
+        Some(endpoint) => {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()?;
+            let (otlp, guard) = {
+                let _enter = runtime.enter();
+                otlp_layer(&OtlpConfig::new(endpoint))?
+            };
+            (Some(otlp), Some((guard, runtime)))
+        }
+        None => (None, None)
+    };
+
+    let stdout_filter = EnvFilter::builder()
+        .with_default_directive(logs.verbosity.directive())
+        .from_env_lossy();
+    let (filter, log_filter) = reload::Layer::new(
+        logs.log_stdout_filter
+            .split(',')
+            .filter_map(|directive| directive.parse().ok())
+            .fold(stdout_filter, EnvFilter::add_directive)
+    );
+    let stdout = match log_format {
+        LogFormat::Terminal => tracing_subscriber::fmt::layer().with_target(true).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed()
+    };
+
+    let (file, log_file) = if logs.log_file_max_files > 0 {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("reth.log")
+            .max_log_files(logs.log_file_max_files)
+            .build(log_dir)?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        let file = tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(writer)
+            .with_filter(EnvFilter::builder().parse_lossy(&logs.log_file_filter));
+        (Some(file), Some(guard))
+    } else {
+        (None, None)
+    };
+
+    tracing_subscriber::registry()
+        .with(stdout.with_filter(filter))
+        .with(file)
+        .with(otlp)
+        .try_init()?;
+
+    Ok(NodeTracing { log_filter, _log_file: log_file, _otlp: otlp_export })
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct NodeConfig {
-    pub secret_key:           String,
-    pub angstrom_address:     Address,
-    pub periphery_addr:       Address,
+    pub secret_key: String,
+    pub angstrom_address: Address,
+    pub periphery_addr: Address,
     pub pool_manager_address: Address,
-    pub pools:                Vec<PoolKey>
+    pub pools: Vec<PoolKey>,
+    /// the staked validator set, every validator has to run with the same set
+    pub validators: Vec<ValidatorEntry>,
+    /// the parameters of the protocol, every validator has to run with the
+    /// same genesis
+    #[serde(default)]
+    pub genesis: ProtocolGenesis,
+    /// bond searchers have to post for their orders to be accepted
+    #[serde(default)]
+    pub searcher_bond: Option<SearcherBondConfig>,
+    /// hooks composable orders can call, with the prefixes their payloads
+    /// can start with. hooks aren't checked if unset
+    #[serde(default)]
+    pub hook_allowlist: Option<HookAllowlist>,
+    /// waits, timeouts and budgets of the consensus rounds, reloadable over
+    /// the admin rpc
+    #[serde(default)]
+    pub consensus_timing: ConsensusTiming,
+    /// validators that co-sign the proposals with a threshold key. the
+    /// leader only submits the proposal of a round once enough of them signed
+    /// it
+    #[serde(default)]
+    pub proposal_committee: Option<ProposalCommittee>,
+    /// halts the matching of a pool in the rounds its clearing price is
+    /// further than this, in millionths, from the AMM spot price. disabled if
+    /// unset
+    #[serde(default)]
+    pub circuit_breaker_threshold_e6: Option<u32>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidatorEntry {
+    pub peer_id:      PeerId,
+    pub voting_power: u64
 }
 
 impl NodeConfig {
@@ -53,8 +417,41 @@ impl NodeConfig {
         let node_config: NodeConfig = toml::from_str(&toml_content)
             .wrap_err_with(|| format!("Could not deserialize config file {:?}", config_path))?;
 
+        node_config.validator_set(None)?;
+
         Ok(node_config)
     }
+
+    /// The validators consensus rounds run with. `us` has to be one of them,
+    /// if given.
+    pub fn validator_set(&self, us: Option<PeerId>) -> eyre::Result<Vec<AngstromValidator>> {
+        if self.validators.is_empty() {
+            return Err(eyre::eyre!("the node config has no validators"))
+        }
+        let mut peers = std::collections::HashSet::new();
+        if let Some(entry) = self
+            .validators
+            .iter()
+            .find(|entry| !peers.insert(entry.peer_id))
+        {
+            return Err(eyre::eyre!("validator {} is listed twice", entry.peer_id))
+        }
+        if let Some(us) = us.filter(|us| !peers.contains(us)) {
+            return Err(eyre::eyre!("we ({us}) aren't in the validator set"))
+        }
+
+        Ok(self
+            .validators
+            .iter()
+            .map(|entry| AngstromValidator::new(entry.peer_id, entry.voting_power))
+            .collect())
+    }
+}
+
+pub async fn init_health(health_port: u16) {
+    let _ = initialize_health_endpoint(health_port)
+        .await
+        .inspect_err(|e| eprintln!("failed to start health endpoint - {:?}", e));
 }
 
 pub async fn init_metrics(metrics_port: u16) {
@@ -62,3 +459,46 @@ pub async fn init_metrics(metrics_port: u16) {
         .await
         .inspect_err(|e| eprintln!("failed to start metrics endpoint - {:?}", e));
 }
+
+#[cfg(test)]
+mod tests {
+    use consensus::WeightedRoundRobin;
+
+    use super::*;
+
+    fn node_config(validators: &[(PeerId, u64)]) -> NodeConfig {
+        let validators = validators
+            .iter()
+            .map(|(peer_id, voting_power)| {
+                format!("[[validators]]\npeer_id = \"{peer_id}\"\nvoting_power = {voting_power}\n")
+            })
+            .collect::<String>();
+        toml::from_str(&format!(
+            "secret_key = \"\"\nangstrom_address = \"{0}\"\nperiphery_addr = \
+             \"{0}\"\npool_manager_address = \"{0}\"\npools = []\n{validators}",
+            Address::ZERO
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn rounds_are_led_by_the_whole_validator_set() {
+        let (us, them) = (PeerId::random(), PeerId::random());
+        let config = node_config(&[(us, 100), (them, 100)]);
+        let validators = config.validator_set(Some(us)).unwrap();
+        assert_eq!(validators.len(), 2);
+
+        let mut leaders = WeightedRoundRobin::new(validators, 0);
+        let chosen = (1..=4)
+            .filter_map(|block| leaders.choose_proposer(block))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(chosen, [us, them].into());
+    }
+
+    #[test]
+    fn rejects_validator_sets_without_us() {
+        let config = node_config(&[(PeerId::random(), 100), (PeerId::random(), 100)]);
+        assert!(config.validator_set(Some(PeerId::random())).is_err());
+        assert!(node_config(&[]).validator_set(None).is_err());
+    }
+}
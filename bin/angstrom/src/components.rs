@@ -1,59 +1,87 @@
 //! CLI definition and entrypoint to executable
 
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use alloy::{
     self,
     eips::{BlockId, BlockNumberOrTag},
+    primitives::{Address, B256},
     providers::{network::Ethereum, Provider, ProviderBuilder}
 };
 use alloy_chains::Chain;
 use angstrom_eth::{
+    fork::ForkTracker,
     handle::{Eth, EthCommand},
     manager::{EthDataCleanser, EthEvent}
 };
+use angstrom_metrics::node_health;
 use angstrom_network::{
     manager::StromConsensusEvent,
-    pool_manager::{OrderCommand, PoolHandle},
-    NetworkBuilder as StromNetworkBuilder, NetworkOrderEvent, PoolManagerBuilder, StatusState,
-    VerificationSidecar
+    pool_manager::{GossipMode, OrderCommand, OrderMirrorConfig, PoolHandle},
+    NetworkBuilder as StromNetworkBuilder, NetworkOrderEvent, PinnedPeersConfig, PinnedPeersTask,
+    PoolManagerBuilder, StatusState, StromNetworkHandle, VerificationSidecar
 };
+use angstrom_rpc::types::AdminCommand;
 use angstrom_types::{
-    block_sync::{BlockSyncProducer, GlobalBlockSync},
+    block_sync::{BlockSyncConsumer, BlockSyncProducer, GlobalBlockSync},
+    consensus::{threshold::KeyShare, InclusionFairnessStore, ValidatorPerformanceStore},
     contract_bindings::controller_v_1::ControllerV1,
-    contract_payloads::angstrom::{AngstromPoolConfigStore, UniswapAngstromRegistry},
-    mev_boost::MevBoostProvider,
-    primitive::{AngstromSigner, PeerId, UniswapPoolRegistry},
+    contract_payloads::angstrom::{
+        AngstromPoolConfigStore, ContractVersion, UniswapAngstromRegistry
+    },
+    mev_boost::{MevBoostProvider, PriorityFeePolicy},
+    orders::{CircuitBreaker, ClearingReportStore, GasReconciliationStore, OrderArchive},
+    primitive::{AngstromSigner, PeerId, PoolId, UniswapPoolRegistry},
     reth_db_wrapper::RethDbWrapper
 };
-use consensus::{AngstromValidator, ConsensusManager, ManagerNetworkDeps};
-use matching_engine::{configure_uniswap_manager, manager::MatcherCommand, MatchingManager};
-use order_pool::{order_storage::OrderStorage, PoolConfig, PoolManagerUpdate};
+use angstrom_utils::clock::Clock;
+use consensus::{
+    rounds::KeySchedule, ConsensusManager, ConsensusTiming, ManagerNetworkDeps, SignerUpdate
+};
+use matching_engine::{
+    configure_uniswap_manager,
+    manager::{MatcherCommand, MatcherHandle},
+    matcher::MatcherBackend,
+    uniswap_pool, IpcMatcherHandle, IpcSolver, MatcherOptions, MatchingManager
+};
+use order_pool::{
+    order_storage::OrderStorage, AccountLimits, AdmissionPolicy, PoolConfig, PoolManagerUpdate
+};
 use reth::{
     api::NodeAddOns,
     builder::FullNodeComponents,
     chainspec::ChainSpec,
     primitives::EthPrimitives,
-    providers::{BlockNumReader, CanonStateSubscriptions},
+    providers::{BlockNumReader, CanonStateSubscriptions, HeaderProvider},
     tasks::TaskExecutor
 };
 use reth_metrics::common::mpsc::{UnboundedMeteredReceiver, UnboundedMeteredSender};
 use reth_node_builder::{node::FullNodeTypes, rpc::RethRpcAddOns, FullNode, NodeTypes};
 use reth_provider::BlockReader;
-use tokio::sync::mpsc::{
-    channel, unbounded_channel, Receiver, Sender, UnboundedReceiver, UnboundedSender
+use tokio::sync::{
+    mpsc::{channel, unbounded_channel, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+    oneshot
 };
+use tracing_subscriber::EnvFilter;
+use uniswap_v4::uniswap::pool_manager::{PoolResyncHandle, SyncedUniswapPools};
 use validation::{
-    common::TokenPriceGenerator,
+    common::{FallbackStateDb, RpcStateDb, TokenPriceGenerator},
     init_validation,
     order::state::pools::AngstromPoolsTracker,
+    queue::ValidationQueue,
     validator::{ValidationClient, ValidationRequest}
 };
 
-use crate::{cli::NodeConfig, AngstromConfig};
+use crate::{
+    cli::{LogFilterHandle, NodeConfig},
+    AngstromConfig
+};
 
 pub fn init_network_builder(
     secret_key: AngstromSigner,
+    staking_key: Option<AngstromSigner>,
+    observer: bool,
+    genesis: B256,
     eth_handle: UnboundedReceiver<EthEvent>
 ) -> eyre::Result<StromNetworkBuilder> {
     let public_key = secret_key.id();
@@ -65,12 +93,186 @@ pub fn init_network_builder(
         timestamp: 0
     };
 
-    let verification =
-        VerificationSidecar { status: state, has_sent: false, has_received: false, secret_key };
+    let verification = VerificationSidecar {
+        status: state,
+        has_sent: false,
+        has_received: false,
+        secret_key,
+        staking_key,
+        observer,
+        genesis,
+        observers: Default::default()
+    };
 
     Ok(StromNetworkBuilder::new(verification, eth_handle))
 }
 
+const HEALTH_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Periodically pushes the state of the modules that don't report on their own
+/// into the node health.
+async fn report_health<P: Provider>(
+    provider: Arc<P>,
+    block_sync: GlobalBlockSync,
+    stall_timeout: Duration,
+    network: StromNetworkHandle,
+    order_storage: Arc<OrderStorage>,
+    validators: HashSet<PeerId>,
+    registry: UniswapAngstromRegistry,
+    uniswap_pools: SyncedUniswapPools
+) {
+    let health = node_health();
+    let mut interval = tokio::time::interval(HEALTH_REPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        if let Ok(chain_tip) = provider.get_block_number().await {
+            health.set_block_sync_status(&block_sync.status(), chain_tip, stall_timeout);
+        }
+
+        let connected_validators = network
+            .peer_bandwidth()
+            .await
+            .map(|peers| {
+                peers
+                    .keys()
+                    .filter(|peer| validators.contains(peer))
+                    .count()
+            })
+            .unwrap_or_default();
+        health.set_network(network.peer_count(), connected_validators, validators.len());
+
+        health.set_order_pool_depth(order_storage.get_all_orders().total_orders());
+
+        // pools that failed to load are retried in the background
+        health.set_unavailable_pools(
+            registry
+                .uniswap_pools()
+                .pools()
+                .keys()
+                .filter(|pool_id| !uniswap_pools.contains(pool_id))
+                .map(|pool_id| pool_id.to_string())
+                .collect()
+        );
+    }
+}
+
+/// Raises an alarm once per transition that a module holds up for longer than
+/// the timeout.
+async fn watch_block_sync_stalls(block_sync: GlobalBlockSync, stall_timeout: Duration) {
+    let mut interval = tokio::time::interval(HEALTH_REPORT_INTERVAL);
+    let mut alarmed = None;
+    loop {
+        interval.tick().await;
+
+        let status = block_sync.status();
+        let stalled = status.stalled_modules(stall_timeout);
+        if stalled.is_empty() || alarmed == status.pending {
+            continue
+        }
+        tracing::error!(
+            ?stalled,
+            pending = ?status.pending,
+            pending_for_ms = ?status.pending_for_ms,
+            "modules are stalling the block transition"
+        );
+        alarmed = status.pending;
+    }
+}
+
+/// Moves the pools whose fee or tick spacing were changed on chain onto their
+/// new key, in the registry, the synced uniswap pools and the matcher. The
+/// pool is matched again once it's loaded under its new key.
+async fn follow_pool_params(
+    mut events: UnboundedReceiver<EthEvent>,
+    registry: UniswapAngstromRegistry,
+    uniswap_pools: PoolResyncHandle<PoolId>,
+    matcher: MatcherHandle,
+    pool_manager_address: Address
+) {
+    while let Some(event) = events.recv().await {
+        let EthEvent::PoolParamsUpdated { pool, previous } = event else { continue };
+        let previous = PoolId::from(previous);
+        let pool_id = registry.update_pool(&previous, pool);
+        matcher.move_pool(previous, pool_id).await;
+
+        let loaded = match uniswap_pool(pool_id, &registry.uniswap_pools(), pool_manager_address) {
+            Ok(pool) => uniswap_pools
+                .replace(previous, pool_id, pool)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string())
+        };
+        match loaded {
+            Ok(block) => tracing::info!(?previous, ?pool_id, block, "moved pool onto its new key"),
+            Err(error) => {
+                tracing::error!(?previous, ?pool_id, %error, "failed to load pool under its new key")
+            }
+        }
+    }
+}
+
+/// Carries out the commands of the admin rpc on the running modules.
+async fn serve_admin_commands(
+    mut commands: UnboundedReceiver<AdminCommand>,
+    pool: DefaultPoolHandle,
+    network: StromNetworkHandle,
+    uniswap_pools: PoolResyncHandle<PoolId>,
+    signer_updates: UnboundedSender<SignerUpdate>,
+    timing_updates: UnboundedSender<ConsensusTiming>,
+    block_sync: GlobalBlockSync,
+    log_filter: Option<LogFilterHandle>
+) {
+    while let Some(command) = commands.recv().await {
+        tracing::info!(?command, "admin command");
+        match command {
+            AdminCommand::PauseOrderIntake(paused) => pool.pause_order_intake(paused),
+            AdminCommand::BanPeer(peer_id) => network.ban_peer(peer_id),
+            AdminCommand::UnbanPeer(peer_id) => network.unban_peer(peer_id),
+            AdminCommand::ResyncPool(pool_id, tx) => {
+                let res = uniswap_pools.resync(pool_id).await;
+                let _ = tx.send(res.map_err(|e| e.to_string()));
+            }
+            AdminCommand::RotateSigningKey(path, tx) => {
+                let signer = match crate::get_secret_key(&path) {
+                    Ok(signer) => signer,
+                    Err(e) => {
+                        let _ = tx.send(Err(e.to_string()));
+                        continue
+                    }
+                };
+                let (rotation_tx, rotation_rx) = oneshot::channel();
+                let _ = signer_updates.send((signer, rotation_tx));
+                let res = match rotation_rx.await {
+                    Ok(Some(rotation)) => Ok(rotation),
+                    Ok(None) => {
+                        Err("the key is in use or the last handover isn't done yet".to_string())
+                    }
+                    Err(_) => Err("consensus isn't running".to_string())
+                };
+                let _ = tx.send(res);
+            }
+            AdminCommand::BlockSyncStatus(tx) => {
+                let _ = tx.send(Ok(block_sync.status()));
+            }
+            AdminCommand::SetConsensusTiming(timing) => {
+                let _ = timing_updates.send(timing);
+            }
+            AdminCommand::SetLogFilter(filter, tx) => {
+                let res = match log_filter.as_ref() {
+                    Some(log_filter) => EnvFilter::try_new(filter)
+                        .map_err(|e| e.to_string())
+                        .and_then(|filter| log_filter.reload(filter).map_err(|e| e.to_string())),
+                    None => {
+                        Err("the logs are filtered by reth, which can't reload them".to_string())
+                    }
+                };
+                let _ = tx.send(res);
+            }
+        }
+    }
+}
+
 pub type DefaultPoolHandle = PoolHandle;
 type DefaultOrderCommand = OrderCommand;
 
@@ -85,8 +287,11 @@ pub struct StromHandles {
     pub orderpool_tx: UnboundedSender<DefaultOrderCommand>,
     pub orderpool_rx: UnboundedReceiver<DefaultOrderCommand>,
 
-    pub validator_tx: UnboundedSender<ValidationRequest>,
-    pub validator_rx: UnboundedReceiver<ValidationRequest>,
+    pub validator_tx:     UnboundedSender<ValidationRequest>,
+    pub validator_rx:     UnboundedReceiver<ValidationRequest>,
+    /// order validations the validator has yet to return, shared by its
+    /// clients
+    pub validation_queue: ValidationQueue,
 
     pub eth_handle_tx: Option<UnboundedSender<EthEvent>>,
     pub eth_handle_rx: Option<UnboundedReceiver<EthEvent>>,
@@ -110,6 +315,28 @@ impl StromHandles {
     }
 }
 
+const CONTRACT_VERSION_PROBES: usize = 5;
+
+/// Probes the version of the angstrom contract, retrying the rpc errors that
+/// don't tell the version apart.
+async fn probe_contract_version<P: Provider>(
+    angstrom_address: Address,
+    provider: &P
+) -> eyre::Result<ContractVersion> {
+    let mut attempt = 1;
+    loop {
+        match ContractVersion::probe(angstrom_address, provider).await {
+            Ok(version) => return Ok(version),
+            Err(e) if attempt < CONTRACT_VERSION_PROBES => {
+                tracing::warn!(attempt, error = %e, "failed to probe the contract version");
+                attempt += 1;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            Err(e) => return Err(eyre::eyre!(e))
+        }
+    }
+}
+
 pub fn initialize_strom_handles() -> StromHandles {
     let (eth_tx, eth_rx) = channel(100);
     let (matching_tx, matching_rx) = channel(100);
@@ -130,6 +357,7 @@ pub fn initialize_strom_handles() -> StromHandles {
         orderpool_rx,
         validator_tx,
         validator_rx,
+        validation_queue: ValidationQueue::default(),
         pool_manager_tx,
         consensus_tx_op,
         consensus_rx_op,
@@ -146,8 +374,16 @@ pub async fn initialize_strom_components<Node, AddOns>(
     mut handles: StromHandles,
     network_builder: StromNetworkBuilder,
     node: FullNode<Node, AddOns>,
-    executor: &TaskExecutor
-) where
+    executor: &TaskExecutor,
+    clearing_reports: ClearingReportStore,
+    gas_reconciliations: GasReconciliationStore,
+    validator_performance: ValidatorPerformanceStore,
+    inclusion_fairness: InclusionFairnessStore,
+    circuit_breaker: CircuitBreaker,
+    admin_commands: UnboundedReceiver<AdminCommand>,
+    log_filter: Option<LogFilterHandle>
+) -> eyre::Result<()>
+where
     Node: FullNodeComponents
         + FullNodeTypes<Types: NodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives>>,
     Node::Provider: BlockReader<
@@ -158,6 +394,14 @@ pub async fn initialize_strom_components<Node, AddOns>(
     AddOns: NodeAddOns<Node> + RethRpcAddOns<Node>
 {
     let node_config = NodeConfig::load_from_config(Some(config.node_config)).unwrap();
+    node_config
+        .consensus_timing
+        .validate()
+        .expect("inconsistent consensus timing in the node config");
+    // observers follow the rounds without being in the set
+    let validators = node_config
+        .validator_set((!config.observer).then(|| signer.id()))
+        .expect("invalid validator set in the node config");
     let node_address = signer.address();
 
     // NOTE:
@@ -172,8 +416,16 @@ pub async fn initialize_strom_components<Node, AddOns>(
         .unwrap()
         .into();
 
-    let mev_boost_provider =
-        MevBoostProvider::new_from_urls(querying_provider.clone(), &config.mev_boost_endpoints);
+    let mut mev_boost_provider =
+        MevBoostProvider::new_from_urls(querying_provider.clone(), &config.mev_boost_endpoints)
+            .with_relay_timeout(Duration::from_millis(config.relay_timeout_ms));
+    if config.public_mempool_fallback {
+        mev_boost_provider = mev_boost_provider.with_public_fallback(PriorityFeePolicy {
+            multiplier_pct:   config.fallback_priority_fee_pct,
+            min_priority_fee: config.fallback_min_priority_fee,
+            max_priority_fee: config.fallback_max_priority_fee
+        });
+    }
 
     tracing::info!(target: "angstrom::startup-sequence", "waiting for the next block to continue startup sequence. \
         this is done to ensure all modules start on the same state and we don't hit the rare  \
@@ -202,9 +454,34 @@ pub async fn initialize_strom_components<Node, AddOns>(
         .unwrap()
     );
 
-    let uniswap_registry: UniswapPoolRegistry = node_config.pools.into();
+    let pools = node_config
+        .genesis
+        .hook_policy
+        .clone()
+        .with_allowed(node_config.angstrom_address)
+        .admit(node_config.pools);
+    let uniswap_registry = node_config
+        .genesis
+        .pool_matching
+        .iter()
+        .fold(UniswapPoolRegistry::from(pools), |registry, entry| {
+            registry.with_matching_config(entry.pool_id, entry.config)
+        });
+    let matching_configs = uniswap_registry.matching_configs();
     let uni_ang_registry =
         UniswapAngstromRegistry::new(uniswap_registry.clone(), pool_config_store.clone());
+    let pool_params_registry = uni_ang_registry.clone();
+
+    let contract_version =
+        probe_contract_version(node_config.angstrom_address, &querying_provider).await?;
+    if contract_version.is_known() {
+        tracing::info!(?contract_version, "detected the angstrom contract version");
+    } else {
+        tracing::error!(
+            ?contract_version,
+            "unknown angstrom contract version, no bundles will be built for it"
+        );
+    }
 
     let periphery_c = ControllerV1::new(node_config.periphery_addr, querying_provider.clone());
     let node_set = periphery_c
@@ -229,11 +506,12 @@ pub async fn initialize_strom_components<Node, AddOns>(
         pool_config_store.clone(),
         global_block_sync.clone(),
         node_set,
+        Some(ForkTracker::new(node.config.chain.clone())),
         vec![handles.eth_handle_tx.take().unwrap()]
     )
     .unwrap();
 
-    let uniswap_pool_manager = configure_uniswap_manager(
+    let (uniswap_pool_manager, unavailable_pools) = configure_uniswap_manager(
         querying_provider.clone(),
         eth_handle.subscribe_cannon_state_notifications().await,
         uniswap_registry,
@@ -242,8 +520,20 @@ pub async fn initialize_strom_components<Node, AddOns>(
         node_config.pool_manager_address
     )
     .await;
+    if !unavailable_pools.is_empty() {
+        tracing::warn!(count = unavailable_pools.len(), "starting with unavailable pools");
+    }
+    node_health().set_unavailable_pools(
+        unavailable_pools
+            .iter()
+            .map(|error| error.pool_id().to_string())
+            .collect()
+    );
+    let uniswap_pool_manager = uniswap_pool_manager
+        .with_integrity_check(Duration::from_secs(config.pool_integrity_check_secs));
 
     let uniswap_pools = uniswap_pool_manager.pools();
+    let uniswap_resync = uniswap_pool_manager.resync_handle();
     executor.spawn(Box::pin(uniswap_pool_manager));
     let price_generator =
         TokenPriceGenerator::new(querying_provider.clone(), block_id, uniswap_pools.clone(), None)
@@ -251,34 +541,80 @@ pub async fn initialize_strom_components<Node, AddOns>(
             .expect("failed to start token price generator");
 
     let block_height = node.provider.best_block_number().unwrap();
+    let block_timestamp = node
+        .provider
+        .header_by_number(block_height)
+        .unwrap()
+        .expect("the best block has a header")
+        .timestamp;
+
+    let mut validation_db = FallbackStateDb::new(RethDbWrapper::new(node.provider.clone()));
+    if let Some(url) = config.validation_fallback_rpc.clone() {
+        let remote = ProviderBuilder::new().on_http(url);
+        validation_db = validation_db.with_remote(RpcStateDb::new(
+            Arc::new(remote),
+            RethDbWrapper::new(node.provider.clone())
+        ));
+    }
 
     init_validation(
-        RethDbWrapper::new(node.provider.clone()),
+        validation_db,
         block_height,
+        node.config.chain.chain().id(),
         node_config.angstrom_address,
         node_address,
         // Because this is incapsulated under the orderpool syncer. this is the only case
         // we can use the raw stream.
         node.provider.canonical_state_stream(),
+        node.provider.canonical_state_stream(),
         uniswap_pools.clone(),
         price_generator,
         pool_config_store.clone(),
+        node_config.searcher_bond.clone(),
+        node_config.hook_allowlist.clone(),
+        config.sim_trace_dir.clone(),
         handles.validator_rx
     );
 
-    let validation_handle = ValidationClient(handles.validator_tx.clone());
+    let validation_handle =
+        ValidationClient(handles.validator_tx.clone(), handles.validation_queue.clone());
 
     let network_handle = network_builder
-        .with_pool_manager(handles.pool_tx)
+        .with_pool_manager(handles.pool_tx.clone())
         .with_consensus_manager(handles.consensus_tx_op)
         .build_handle(executor.clone(), node.provider.clone());
 
-    let pool_config = PoolConfig::default();
+    if !config.pinned_peers.is_empty() {
+        let pinned_peers = PinnedPeersConfig::new(config.pinned_peers.clone())
+            .with_alert_after(Duration::from_secs(config.pinned_peer_alert_after_secs));
+        executor.spawn(Box::pin(PinnedPeersTask::new(
+            node.network.clone(),
+            pinned_peers,
+            network_handle.subscribe_network_events(),
+            Clock::system()
+        )));
+    }
+
+    let pool_config = PoolConfig {
+        settled_orders_path: config.settled_orders_path.clone(),
+        filled_orders_ttl: Duration::from_secs(config.filled_orders_ttl_secs),
+        filled_orders_capacity: config.filled_orders_capacity,
+        account_limits: AccountLimits {
+            max_open_orders:       config.max_open_orders_per_account,
+            max_notional_per_pool: config.max_account_notional_per_pool
+        },
+        admission: AdmissionPolicy {
+            evict_parked:      config.evict_parked_orders,
+            dust_notional:     config.dust_order_notional,
+            low_watermark_pct: config.admission_low_watermark_pct
+        },
+        ..Default::default()
+    };
     let order_storage = Arc::new(OrderStorage::new(&pool_config));
     let angstrom_pool_tracker =
         AngstromPoolsTracker::new(node_config.angstrom_address, pool_config_store.clone());
 
-    let _pool_handle = PoolManagerBuilder::new(
+    let pool_handle = PoolManagerBuilder::new(
         validation_handle.clone(),
         Some(order_storage.clone()),
         network_handle.clone(),
@@ -287,6 +623,16 @@ pub async fn initialize_strom_components<Node, AddOns>(
         global_block_sync.clone()
     )
     .with_config(pool_config)
+    .with_propagation_tracing(config.propagation_tracing)
+    .with_gossip_mode(
+        config
+            .gossip_fanout
+            .map_or(GossipMode::Eager, |fanout| GossipMode::Lazy { fanout })
+    )
+    .with_order_mirror(OrderMirrorConfig {
+        standby: config.mirror_orders_to,
+        primary: config.mirror_orders_from
+    })
     .build_with_channels(
         executor.clone(),
         handles.orderpool_tx,
@@ -295,15 +641,56 @@ pub async fn initialize_strom_components<Node, AddOns>(
         handles.pool_manager_tx
     );
 
-    // TODO load the stakes from Eigen using node.provider
-    let validators = vec![
-        AngstromValidator::new(PeerId::default(), 100),
-        AngstromValidator::new(PeerId::default(), 200),
-        AngstromValidator::new(PeerId::default(), 300),
-    ];
+    let stall_timeout = Duration::from_secs(config.block_sync_stall_timeout_secs);
+    executor.spawn(Box::pin(watch_block_sync_stalls(global_block_sync.clone(), stall_timeout)));
+    if config.health_port.is_some() {
+        executor.spawn(Box::pin(report_health(
+            querying_provider.clone(),
+            global_block_sync.clone(),
+            stall_timeout,
+            network_handle.clone(),
+            order_storage.clone(),
+            validators
+                .iter()
+                .map(|v| v.peer_id)
+                .filter(|peer_id| *peer_id != signer.id())
+                .collect(),
+            uni_ang_registry.clone(),
+            uniswap_pools.clone()
+        )));
+    }
 
     // spinup matching engine
-    let matching_handle = MatchingManager::spawn(executor.clone(), validation_handle.clone());
+    let matching_handle = MatchingManager::spawn_with_options(
+        executor.clone(),
+        validation_handle.clone(),
+        MatcherOptions {
+            circuit_breaker: circuit_breaker
+                .with_threshold(node_config.circuit_breaker_threshold_e6),
+            incremental_books: config.incremental_books,
+            backends: node_config
+                .genesis
+                .lp_pools
+                .iter()
+                .map(|pool_id| (*pool_id, MatcherBackend::Lp))
+                .collect(),
+            self_trade: node_config.genesis.self_trade,
+            matching: matching_configs,
+            ..Default::default()
+        }
+    );
+    let external_matcher = config.external_matcher_ipc.clone().map(|socket| {
+        IpcSolver::new(socket)
+            .with_timeout(Duration::from_millis(config.external_matcher_timeout_ms))
+    });
+    executor.spawn(Box::pin(follow_pool_params(
+        eth_handle.subscribe_network().into_inner(),
+        pool_params_registry,
+        uniswap_resync.clone(),
+        matching_handle.clone(),
+        node_config.pool_manager_address
+    )));
+    let matching_handle = IpcMatcherHandle::new(matching_handle, external_matcher);
 
     let manager = ConsensusManager::new(
         ManagerNetworkDeps::new(
@@ -321,9 +708,72 @@ pub async fn initialize_strom_components<Node, AddOns>(
         mev_boost_provider,
         matching_handle,
         global_block_sync.clone()
-    );
+    )
+    .with_clearing_reports(clearing_reports)
+    .with_gas_reconciliations(gas_reconciliations)
+    .with_validator_performance(validator_performance)
+    .with_inclusion_fairness(inclusion_fairness)
+    .with_order_pool(handles.pool_tx)
+    .with_contract_version(contract_version)
+    .with_block_timestamp(block_timestamp)
+    .with_order_validator(validation_handle.clone())
+    .with_timing(node_config.consensus_timing);
+    let manager = match config.protocol_fee_share_e6 {
+        Some(share) => manager.with_surplus_fees(share),
+        None => manager
+    };
+    let manager = match config.order_archive_dir.clone() {
+        Some(dir) => manager
+            .with_order_archive(OrderArchive::new(dir).with_retention(config.order_archive_blocks)),
+        None => manager
+    };
+    let manager = match config.key_handovers_path.clone() {
+        Some(path) => manager
+            .with_key_schedule(KeySchedule::load(path).expect("failed to load the key handovers")),
+        None => manager
+    };
+    let manager = match node_config.proposal_committee.clone() {
+        Some(committee) => {
+            let key_share = config
+                .committee_key_share_location
+                .as_deref()
+                .map(|path| KeyShare::load(path).expect("failed to load the committee key share"));
+            tracing::info!(
+                members = committee.members().len(),
+                threshold = committee.threshold(),
+                member = key_share.is_some(),
+                "proposals are co-signed by the committee before they are submitted"
+            );
+            manager.with_proposal_committee(committee, key_share)
+        }
+        None => manager
+    };
+    let manager = if config.observer {
+        tracing::info!("observing the consensus rounds, nothing will be signed or submitted");
+        manager.with_observer_mode()
+    } else {
+        manager
+    };
+    let (signer_tx, signer_rx) = unbounded_channel();
+    let (timing_tx, timing_rx) = unbounded_channel();
+    let manager = manager
+        .with_signer_updates(signer_rx)
+        .with_timing_updates(timing_rx);
+
+    executor.spawn(Box::pin(serve_admin_commands(
+        admin_commands,
+        pool_handle,
+        network_handle.clone(),
+        uniswap_resync,
+        signer_tx,
+        timing_tx,
+        global_block_sync.clone(),
+        log_filter
+    )));
 
     let _consensus_handle = executor.spawn_critical("consensus", Box::pin(manager));
     // ensure no more modules can be added to block sync.
     global_block_sync.finalize_modules();
+
+    Ok(())
 }
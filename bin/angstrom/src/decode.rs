@@ -0,0 +1,217 @@
+//! `angstrom decode-bundle`, decodes a bundle payload for debugging settlement
+//! failures.
+use std::path::PathBuf;
+
+use alloy::{
+    primitives::{Address, Bytes, PrimitiveSignature, B256, U256},
+    sol_types::SolCall
+};
+use angstrom_types::{
+    contract_bindings::angstrom::Angstrom,
+    contract_payloads::{angstrom::AngstromBundle, Signature}
+};
+use clap::Parser;
+use eyre::Context;
+use pade::PadeDecode;
+
+pub const DECODE_BUNDLE_COMMAND: &str = "decode-bundle";
+
+/// Decodes a angstrom bundle and prints every order along with its hash.
+#[derive(Debug, Clone, Parser)]
+#[clap(name = DECODE_BUNDLE_COMMAND)]
+pub struct DecodeBundleCommand {
+    /// hex encoded `execute` calldata or raw pade encoded bundle
+    #[clap(required_unless_present = "file", conflicts_with = "file")]
+    pub payload: Option<String>,
+    /// file containing the hex encoded payload
+    #[clap(long)]
+    pub file:    Option<PathBuf>,
+    /// block the bundle was built for, flash order hashes depend on it
+    #[clap(long, default_value = "0")]
+    pub block:   u64
+}
+
+impl DecodeBundleCommand {
+    pub fn run(self) -> eyre::Result<()> {
+        let payload = match (self.payload, self.file) {
+            (Some(payload), _) => payload,
+            (None, Some(file)) => std::fs::read_to_string(&file)
+                .wrap_err_with(|| format!("Could not read payload file {:?}", file))?,
+            (None, None) => eyre::bail!("either a payload or --file is required")
+        };
+
+        let bundle = decode_bundle(&payload)?;
+        print_bundle(&bundle, self.block)?;
+
+        Ok(())
+    }
+}
+
+/// Decodes a bundle from hex, accepting both the `execute` calldata and the
+/// bare pade payload.
+pub fn decode_bundle(payload: &str) -> eyre::Result<AngstromBundle> {
+    let bytes: Bytes = payload
+        .trim()
+        .parse()
+        .wrap_err("payload is not valid hex")?;
+
+    let pade_payload = if bytes.starts_with(&Angstrom::executeCall::SELECTOR) {
+        let (encoded,): (Bytes,) = Angstrom::executeCall::abi_decode(&bytes, true)
+            .wrap_err("failed to abi decode execute calldata")?
+            .into();
+        encoded
+    } else {
+        bytes
+    };
+
+    let mut slice: &[u8] = pade_payload.as_ref();
+    let bundle = AngstromBundle::pade_decode(&mut slice, None)
+        .map_err(|e| eyre::eyre!("failed to pade decode bundle - {:?}", e))?;
+    if !slice.is_empty() {
+        eyre::bail!("{} trailing bytes after bundle", slice.len());
+    }
+
+    Ok(bundle)
+}
+
+/// Signer of the order, [`None`] if the signature is malformed.
+fn recover_signer(signature: &Signature, hash: B256) -> Option<Address> {
+    match signature {
+        Signature::Contract { from, .. } => Some(*from),
+        Signature::Ecdsa { v, r, s } => {
+            PrimitiveSignature::new(U256::from_be_slice(&**r), U256::from_be_slice(&**s), *v == 28)
+                .recover_address_from_prehash(&hash)
+                .ok()
+        }
+    }
+}
+
+/// Checks that every pair and order points at assets and pairs the bundle
+/// carries, hashing the orders indexes into them unchecked.
+fn check_indices(bundle: &AngstromBundle) -> eyre::Result<()> {
+    for (i, pair) in bundle.pairs.iter().enumerate() {
+        for index in [pair.index0, pair.index1] {
+            if index as usize >= bundle.assets.len() {
+                eyre::bail!("pair {i} references missing asset {index}");
+            }
+        }
+    }
+    for (i, order) in bundle.top_of_block_orders.iter().enumerate() {
+        if order.pairs_index as usize >= bundle.pairs.len() {
+            eyre::bail!("top of block order {i} references missing pair {}", order.pairs_index);
+        }
+    }
+    for (i, order) in bundle.user_orders.iter().enumerate() {
+        if order.pair_index as usize >= bundle.pairs.len() {
+            eyre::bail!("user order {i} references missing pair {}", order.pair_index);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_bundle(bundle: &AngstromBundle, block: u64) -> eyre::Result<()> {
+    check_indices(bundle)?;
+
+    println!("assets ({}):", bundle.assets.len());
+    for (i, asset) in bundle.assets.iter().enumerate() {
+        println!(
+            "  [{i}] {} save: {} take: {} settle: {}",
+            asset.addr, asset.save, asset.take, asset.settle
+        );
+    }
+
+    println!("pairs ({}):", bundle.pairs.len());
+    for (i, pair) in bundle.pairs.iter().enumerate() {
+        println!(
+            "  [{i}] {} / {} store index: {} price 1/0: {}",
+            bundle.assets[pair.index0 as usize].addr,
+            bundle.assets[pair.index1 as usize].addr,
+            pair.store_index,
+            pair.price_1over0
+        );
+    }
+
+    println!("pool updates ({}):", bundle.pool_updates.len());
+    for (i, update) in bundle.pool_updates.iter().enumerate() {
+        println!("  [{i}] {:?}", update);
+    }
+
+    println!("top of block orders ({}):", bundle.top_of_block_orders.len());
+    for (i, order) in bundle.top_of_block_orders.iter().enumerate() {
+        let hash = order.order_hash(&bundle.pairs, &bundle.assets, block);
+        println!("  [{i}] hash: {hash} signer: {:?}", recover_signer(&order.signature, hash));
+        println!(
+            "      pair: {} zero_for_1: {} quantity in: {} quantity out: {} gas used asset0: {}",
+            order.pairs_index,
+            order.zero_for_1,
+            order.quantity_in,
+            order.quantity_out,
+            order.gas_used_asset_0
+        );
+    }
+
+    println!("user orders ({}):", bundle.user_orders.len());
+    for (i, order) in bundle.user_orders.iter().enumerate() {
+        let hash = order.order_hash(&bundle.pairs, &bundle.assets, block);
+        println!("  [{i}] hash: {hash} signer: {:?}", recover_signer(&order.signature, hash));
+        println!(
+            "      pair: {} zero_for_one: {} exact_in: {} standing: {} quantities: {:?} min \
+             price: {} extra fee asset0: {} pool fee asset0: {}",
+            order.pair_index,
+            order.zero_for_one,
+            order.exact_in,
+            order.standing_validation.is_some(),
+            order.order_quantities,
+            order.min_price,
+            order.extra_fee_asset0,
+            order.pool_fee_asset0
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::hex;
+    use angstrom_types::contract_payloads::{Asset, Pair};
+    use pade::PadeEncode;
+
+    use super::*;
+
+    fn bundle() -> AngstromBundle {
+        AngstromBundle {
+            assets:              vec![],
+            pairs:               vec![Pair::default()],
+            pool_updates:        vec![],
+            top_of_block_orders: vec![],
+            user_orders:         vec![]
+        }
+    }
+
+    #[test]
+    fn decodes_raw_and_calldata() {
+        let encoded = bundle().pade_encode();
+
+        let raw = decode_bundle(&hex::encode_prefixed(&encoded)).unwrap();
+        assert_eq!(raw.pairs.len(), 1);
+
+        let calldata = Angstrom::executeCall::new((encoded.into(),)).abi_encode();
+        let from_calldata = decode_bundle(&hex::encode(calldata)).unwrap();
+        assert_eq!(from_calldata.pairs.len(), 1);
+
+        assert!(decode_bundle("not hex").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        // the pair points at assets the bundle doesn't carry
+        let malformed = bundle();
+        assert!(print_bundle(&malformed, 0).is_err());
+
+        let mut wellformed = bundle();
+        wellformed.assets.push(Asset::default());
+        assert!(print_bundle(&wellformed, 0).is_ok());
+    }
+}
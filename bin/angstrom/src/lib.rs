@@ -2,32 +2,88 @@
 //!
 //! ## Feature Flags
 
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration
+};
 
 use alloy::signers::local::PrivateKeySigner;
-use angstrom_metrics::METRICS_ENABLED;
-use angstrom_network::AngstromNetworkBuilder;
-use angstrom_rpc::{api::OrderApiServer, OrderApi};
-use angstrom_types::primitive::AngstromSigner;
+use angstrom_metrics::{INVARIANT_MODE, METRICS_ENABLED};
+use angstrom_network::{AngstromNetworkBuilder, BandwidthLimits};
+use angstrom_rpc::{
+    api::{
+        CircuitBreakerApiServer, ClearingApiServer, OrderApiServer, ValidatorsApiServer,
+        WebhookApiServer
+    },
+    ofa::{run_order_feed, AngstromOrderFormat, OrderFeedsConfig},
+    start_admin_server, start_gateway_server,
+    types::GatewayConfig,
+    webhooks::{run_webhook_dispatcher, WebhookDispatcher, WebhookRegistry},
+    AdminApi, CircuitBreakerApi, ClearingApi, Gateway, OrderApi, ValidatorsApi, WebhookApi
+};
+use angstrom_types::{
+    consensus::{InclusionFairnessStore, ValidatorPerformanceStore},
+    orders::{CircuitBreaker, ClearingReportStore, GasReconciliationStore},
+    primitive::AngstromSigner
+};
 use clap::Parser;
-use cli::AngstromConfig;
-use reth::{chainspec::EthereumChainSpecParser, cli::Cli};
+use cli::{AngstromConfig, NodeConfig};
+use eyre::WrapErr;
+use reth::{
+    chainspec::EthereumChainSpecParser,
+    cli::{Cli, Commands}
+};
 use reth_node_builder::{Node, NodeHandle};
 use reth_node_ethereum::{node::EthereumAddOns, EthereumNode};
-use validation::validator::ValidationClient;
+use validation::{queue::ValidationQueue, validator::ValidationClient};
 
 use crate::components::{
     init_network_builder, initialize_strom_components, initialize_strom_handles
 };
 
+pub mod archive;
 pub mod cli;
 pub mod components;
+pub mod decode;
 
 /// Convenience function for parsing CLI options, set up logging and run the
 /// chosen command.
 #[inline]
 pub fn run() -> eyre::Result<()> {
-    Cli::<EthereumChainSpecParser, AngstromConfig>::parse().run(|builder, args| async move {
+    // reth owns the cli so our standalone tools are dispatched before it
+    if std::env::args().nth(1).as_deref() == Some(decode::DECODE_BUNDLE_COMMAND) {
+        return decode::DecodeBundleCommand::parse_from(std::env::args().skip(1)).run()
+    }
+    if std::env::args().nth(1).as_deref() == Some(archive::ARCHIVE_RPC_COMMAND) {
+        return archive::ArchiveRpcCommand::parse_from(std::env::args().skip(1)).run()
+    }
+
+    let cli = Cli::<EthereumChainSpecParser, AngstromConfig>::parse_from(
+        cli::with_log_format_args(std::env::args_os())
+    );
+    // flushes the remaining traces once the node exits
+    let node_tracing = match &cli.command {
+        Commands::Node(command)
+            if command.ext.otlp_endpoint.is_some() || command.ext.admin_rpc_addr.is_some() =>
+        {
+            // reth writes its logs into a directory of the chain
+            let log_dir = cli
+                .logs
+                .log_file_directory
+                .join(cli.chain.chain.to_string());
+            Some(cli::init_tracing(
+                &cli.logs,
+                log_dir.as_ref(),
+                command.ext.otlp_endpoint.as_deref(),
+                command.ext.log_format
+            )?)
+        }
+        _ => None
+    };
+    let log_filter = node_tracing
+        .as_ref()
+        .map(|node_tracing| node_tracing.log_filter.clone());
+    cli.run(|builder, args| async move {
         let executor = builder.task_executor().clone();
 
         if args.metrics {
@@ -36,41 +92,193 @@ pub fn run() -> eyre::Result<()> {
         } else {
             METRICS_ENABLED.set(false).unwrap();
         }
+        INVARIANT_MODE.set(args.invariant_checks).unwrap();
+
+        if let Some(health_port) = args.health_port {
+            executor.spawn_critical("health", crate::cli::init_health(health_port));
+        }
 
         let secret_key = get_secret_key(&args.secret_key_location)?;
+        let staking_key = args
+            .staking_key_location
+            .as_ref()
+            .map(get_secret_key)
+            .transpose()?;
+
+        let (admin_tx, admin_rx) = tokio::sync::mpsc::unbounded_channel();
+        if let Some(admin_rpc_addr) = args.admin_rpc_addr {
+            let token = get_admin_token(args.admin_rpc_token_file.as_ref())?;
+            let server =
+                start_admin_server(admin_rpc_addr, &token, AdminApi::new(admin_tx)).await?;
+            executor.spawn_critical("admin rpc", async move { server.stopped().await });
+        }
+
+        let gateway = args
+            .public_rpc_addr
+            .map(|addr| {
+                let config = get_gateway_config(args.public_rpc_keys.as_deref())?;
+                eyre::Ok((addr, Gateway::new(config)?))
+            })
+            .transpose()?;
 
         let mut channels = initialize_strom_handles();
-        let mut network =
-            init_network_builder(secret_key.clone(), channels.eth_handle_rx.take().unwrap())?;
-        let protocol_handle = network.build_protocol_handler();
+        channels.validation_queue = ValidationQueue::new(
+            args.validation_queue_limit,
+            Duration::from_millis(args.validation_retry_after_ms)
+        );
+        // peers are only admitted on the same genesis
+        let genesis = NodeConfig::load_from_config(Some(args.node_config.clone()))?
+            .genesis
+            .hash();
+        let mut network = init_network_builder(
+            secret_key.clone(),
+            staking_key.clone(),
+            args.observer,
+            genesis,
+            channels.eth_handle_rx.take().unwrap()
+        )?
+        .with_bandwidth_limits(BandwidthLimits {
+            max_outbound_bytes_per_peer: args.max_peer_outbound_bytes_per_sec,
+            max_inbound_bytes_per_peer:  args.max_peer_inbound_bytes_per_sec
+        });
+        let protocol_handles = network.build_protocol_handlers();
 
         // for rpc
         let pool = channels.get_pool_handle();
+
+        if let Some(path) = args.order_flow_feeds.as_deref() {
+            for config in get_order_feeds_config(path)?.feeds {
+                let feed = config.build()?;
+                let pool = pool.clone();
+                executor.spawn(Box::pin(async move {
+                    run_order_feed(pool, feed, AngstromOrderFormat).await;
+                }));
+            }
+        }
+        let clearing_reports = ClearingReportStore::new(args.clearing_reports_dir.clone());
+        let rpc_clearing_reports = clearing_reports.clone();
+        let gas_reconciliations = GasReconciliationStore::default();
+        let rpc_gas_reconciliations = gas_reconciliations.clone();
+        let webhooks = args.webhooks.then(WebhookRegistry::default);
+        if let Some(registry) = webhooks.clone() {
+            let (pool, landed) = (pool.clone(), gas_reconciliations.subscribe());
+            executor.spawn(Box::pin(async move {
+                run_webhook_dispatcher(pool, landed, WebhookDispatcher::new(registry)).await;
+            }));
+        }
+        let validator_performance = ValidatorPerformanceStore::default();
+        let rpc_validator_performance = validator_performance.clone();
+        let inclusion_fairness = InclusionFairnessStore::default();
+        let rpc_inclusion_fairness = inclusion_fairness.clone();
+        // the threshold comes with the node config, the rpc only shares the trips
+        let circuit_breaker = CircuitBreaker::default();
+        let rpc_circuit_breaker = circuit_breaker.clone();
         let executor_clone = executor.clone();
-        let validation_client = ValidationClient(channels.validator_tx.clone());
+        let validation_client =
+            ValidationClient(channels.validator_tx.clone(), channels.validation_queue.clone());
         let NodeHandle { node, node_exit_future } = builder
             .with_types::<EthereumNode>()
             .with_components(
                 EthereumNode::default()
                     .components_builder()
-                    .network(AngstromNetworkBuilder::new(protocol_handle))
+                    .network(AngstromNetworkBuilder::new(protocol_handles))
             )
             .with_add_ons::<EthereumAddOns<_>>(Default::default())
             .extend_rpc_modules(move |rpc_context| {
+                if let Some((addr, gateway)) = gateway {
+                    // only the order namespace, reth's and the operator methods stay private
+                    let order_api = OrderApi::new(
+                        pool.clone(),
+                        executor_clone.clone(),
+                        validation_client.clone()
+                    );
+                    // webhooks make the node post to urls of the callers choice,
+                    // only the operator registers them
+                    let methods = order_api.into_rpc();
+                    executor_clone.spawn_critical("public rpc", async move {
+                        let server = start_gateway_server(addr, gateway, methods)
+                            .await
+                            .expect("failed to start the public rpc");
+                        server.stopped().await
+                    });
+                }
+
                 let order_api = OrderApi::new(pool.clone(), executor_clone, validation_client);
                 rpc_context.modules.merge_configured(order_api.into_rpc())?;
+                let clearing_api = ClearingApi::new(rpc_clearing_reports)
+                    .with_gas_reconciliations(rpc_gas_reconciliations);
+                rpc_context
+                    .modules
+                    .merge_configured(clearing_api.into_rpc())?;
+                let validators_api =
+                    ValidatorsApi::new(rpc_validator_performance, rpc_inclusion_fairness);
+                rpc_context
+                    .modules
+                    .merge_configured(validators_api.into_rpc())?;
+                let circuit_breaker_api = CircuitBreakerApi::new(rpc_circuit_breaker);
+                rpc_context
+                    .modules
+                    .merge_configured(circuit_breaker_api.into_rpc())?;
+                if let Some(registry) = webhooks {
+                    rpc_context
+                        .modules
+                        .merge_configured(WebhookApi::new(registry).into_rpc())?;
+                }
 
                 Ok(())
             })
             .launch()
             .await?;
 
-        initialize_strom_components(args, secret_key, channels, network, node, &executor).await;
+        initialize_strom_components(
+            args,
+            // consensus messages are signed as the validator
+            staking_key.unwrap_or(secret_key),
+            channels,
+            network,
+            node,
+            &executor,
+            clearing_reports,
+            gas_reconciliations,
+            validator_performance,
+            inclusion_fairness,
+            circuit_breaker,
+            admin_rx,
+            log_filter
+        )
+        .await?;
 
         node_exit_future.await
     })
 }
 
+fn get_admin_token(token_path: Option<&PathBuf>) -> eyre::Result<String> {
+    let token_path = token_path.ok_or_else(|| eyre::eyre!("the admin rpc needs a token file"))?;
+    let token = std::fs::read_to_string(token_path)?.trim().to_string();
+    if token.is_empty() {
+        return Err(eyre::eyre!("the admin token file at {:?} is empty", token_path))
+    }
+
+    Ok(token)
+}
+
+fn get_gateway_config(path: Option<&Path>) -> eyre::Result<GatewayConfig> {
+    let path = path.ok_or_else(|| eyre::eyre!("the public rpc needs an api key file"))?;
+    let content = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read api key file {:?}", path))?;
+
+    toml::from_str(&content)
+        .wrap_err_with(|| format!("Could not deserialize api key file {:?}", path))
+}
+
+fn get_order_feeds_config(path: &Path) -> eyre::Result<OrderFeedsConfig> {
+    let content = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Could not read order feeds file {:?}", path))?;
+
+    toml::from_str(&content)
+        .wrap_err_with(|| format!("Could not deserialize order feeds file {:?}", path))
+}
+
 fn get_secret_key(sk_path: &PathBuf) -> eyre::Result<AngstromSigner> {
     let exists = sk_path.try_exists();
 
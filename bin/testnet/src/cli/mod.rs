@@ -1,7 +1,9 @@
 pub mod devnet;
 pub mod e2e_orders;
 pub mod testnet;
-use angstrom_metrics::{initialize_prometheus_metrics, METRICS_ENABLED};
+use angstrom_metrics::{
+    initialize_prometheus_metrics, otlp_layer, OtlpConfig, OtlpGuard, METRICS_ENABLED
+};
 use clap::{ArgAction, Parser, Subcommand};
 use devnet::DevnetCli;
 use e2e_orders::End2EndOrdersCli;
@@ -19,7 +21,7 @@ use crate::{run_devnet, run_testnet, simulations::e2e_orders::run_e2e_orders};
 pub struct AngstromTestnetCli {
     /// testnet or devnet commands
     #[clap(subcommand)]
-    pub command:      TestnetSubcommmand,
+    pub command:       TestnetSubcommmand,
     /// Set the minimum log level.
     ///
     /// -v      Errors
@@ -28,20 +30,24 @@ pub struct AngstromTestnetCli {
     /// -vvvv   Debug
     /// -vvvvv  Traces
     #[clap(short = 'v', long, action = ArgAction::Count, default_value_t = 3, help_heading = "Display", global = true)]
-    pub verbosity:    u8,
+    pub verbosity:     u8,
     /// enables the metrics
     #[clap(long, default_value = "false", global = true)]
-    pub metrics:      bool,
+    pub metrics:       bool,
     /// spawns the prometheus metrics exporter at the specified port
     /// Default: 6969
     #[clap(long, default_value = "6969", global = true)]
-    pub metrics_port: u16
+    pub metrics_port:  u16,
+    /// exports traces to the otlp collector at the given endpoint
+    #[clap(long, global = true)]
+    pub otlp_endpoint: Option<String>
 }
 
 impl AngstromTestnetCli {
     pub async fn run_all(executor: TaskExecutor) -> eyre::Result<()> {
         let this = Self::parse();
-        this.init_tracing();
+        // flushes the remaining traces once the command exits
+        let _otlp = this.init_tracing()?;
 
         if this.metrics
             && initialize_prometheus_metrics(this.metrics_port)
@@ -58,8 +64,22 @@ impl AngstromTestnetCli {
         this.command.run_command(executor).await
     }
 
-    fn init_tracing(&self) {
-        init_tracing(self.verbosity);
+    fn init_tracing(&self) -> eyre::Result<Option<OtlpGuard>> {
+        let mut layers = tracing_layers(self.verbosity);
+        let guard = self
+            .otlp_endpoint
+            .as_ref()
+            .map(|endpoint| {
+                let config = OtlpConfig::new(endpoint).with_service_name("angstrom-testnet");
+                let (layer, guard) = otlp_layer(&config)?;
+                layers.push(layer.boxed());
+                eyre::Ok(guard)
+            })
+            .transpose()?;
+
+        tracing_subscriber::registry().with(layers).init();
+
+        Ok(guard)
     }
 }
 
@@ -84,6 +104,12 @@ impl TestnetSubcommmand {
 }
 
 pub fn init_tracing(verbosity: u8) {
+    tracing_subscriber::registry()
+        .with(tracing_layers(verbosity))
+        .init();
+}
+
+fn tracing_layers(verbosity: u8) -> Vec<Box<dyn Layer<Registry> + Send + Sync>> {
     let level = match verbosity - 1 {
         0 => Level::ERROR,
         1 => Level::WARN,
@@ -92,7 +118,7 @@ pub fn init_tracing(verbosity: u8) {
         _ => Level::TRACE
     };
 
-    let layers = vec![
+    vec![
         layer_builder(format!("testnet={level}")),
         layer_builder(format!("devnet={level}")),
         layer_builder(format!("angstrom_rpc={level}")),
@@ -103,9 +129,7 @@ pub fn init_tracing(verbosity: u8) {
         layer_builder(format!("consensus={level}")),
         layer_builder(format!("validation={level}")),
         layer_builder(format!("order_pool={level}")),
-    ];
-
-    tracing_subscriber::registry().with(layers).init();
+    ]
 }
 
 fn layer_builder(filter_str: String) -> Box<dyn Layer<Registry> + Send + Sync> {
@@ -24,6 +24,10 @@ pub(crate) async fn run_devnet(executor: TaskExecutor, cli: DevnetCli) -> eyre::
     testnet.check_block(16);
     testnet.send_pooled_orders(vec![]);
     debug!("added pooled orders to state machine");
+    testnet.send_random_orders(None);
+    testnet.advance_block();
+    testnet.check_block(17);
+    testnet.check_solution_equality(17);
 
     testnet.run().await;
 
@@ -45,6 +45,7 @@ fn end_to_end_agent<'a>(
         let mut generator = OrderGenerator::new(
             agent_config.uniswap_pools.clone(),
             agent_config.current_block,
+            agent_config.domain.clone(),
             7..10,
             0.1..0.6
         );
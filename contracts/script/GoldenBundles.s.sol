@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import {Script} from "forge-std/Script.sol";
+import {Bundle} from "test/_reference/Bundle.sol";
+import {Asset} from "test/_reference/Asset.sol";
+import {Pair} from "test/_reference/Pair.sol";
+import {PoolUpdate, RewardsUpdate} from "test/_reference/PoolUpdate.sol";
+import {
+    TopOfBlockOrder,
+    ExactStandingOrder,
+    PartialFlashOrder,
+    OrderMeta
+} from "test/_reference/OrderTypes.sol";
+import {UserOrder, UserOrderLib} from "test/_reference/UserOrder.sol";
+import {PriceAB} from "src/types/Price.sol";
+import {PoolConfigStore, PoolConfigStoreLib} from "src/libraries/PoolConfigStore.sol";
+
+/// @dev Encodes the bundles of `crates/types/src/contract_payloads/golden.rs` with the reference
+/// encoder and writes them to the golden vectors the node's encoding is compared against. Rerun
+/// only when the payload format changes: `forge script script/GoldenBundles.s.sol`
+contract GoldenBundlesScript is Script {
+    string constant OUT_DIR = "../crates/types/src/contract_payloads/golden/";
+    uint256 constant RAY = 1e27;
+    /// @dev Store index of the traded pair.
+    uint256 constant STORE_INDEX = 5;
+
+    function run() public {
+        PoolConfigStore store = _store();
+        _write("empty", _empty(), store);
+        _write("top_of_block", _topOfBlock(), store);
+        _write("user_orders", _userOrders(), store);
+    }
+
+    function _empty() internal pure returns (Bundle memory bundle) {}
+
+    function _topOfBlock() internal pure returns (Bundle memory bundle) {
+        bundle = _tradingBundle();
+        bundle.poolUpdates = new PoolUpdate[](1);
+        bundle.poolUpdates[0] =
+            PoolUpdate(_repeat(0x11), _repeat(0x22), 1_000, _currentOnly(7));
+
+        bundle.toBOrders = new TopOfBlockOrder[](1);
+        bundle.toBOrders[0] = TopOfBlockOrder({
+            quantityIn: 100,
+            quantityOut: 90,
+            maxGasAsset0: 5,
+            useInternal: false,
+            assetIn: _repeat(0x11),
+            assetOut: _repeat(0x22),
+            recipient: _repeat(0x33),
+            validForBlock: 0,
+            meta: _ecdsa(27, 0xaa, 0xbb),
+            gasUsedAsset0: 4
+        });
+    }
+
+    function _userOrders() internal pure returns (Bundle memory bundle) {
+        bundle = _tradingBundle();
+        uint128[] memory quantities = new uint128[](3);
+        (quantities[0], quantities[1], quantities[2]) = (1, 2, 3);
+        bundle.poolUpdates = new PoolUpdate[](1);
+        bundle.poolUpdates[0] = PoolUpdate(
+            _repeat(0x22), _repeat(0x11), 2_500, RewardsUpdate(false, 0, -120, 1e18, quantities)
+        );
+
+        bundle.userOrders = new UserOrder[](2);
+        // exact standing order with a recipient, hook and contract signature
+        bundle.userOrders[0] = UserOrderLib.from(
+            ExactStandingOrder({
+                refId: 7,
+                exactIn: true,
+                amount: 500,
+                maxExtraFeeAsset0: 10,
+                minPrice: RAY + 1,
+                useInternal: true,
+                assetIn: _repeat(0x22),
+                assetOut: _repeat(0x11),
+                recipient: _repeat(0x44),
+                hook: _repeat(0x66),
+                hookPayload: hex"deadbeef",
+                nonce: 3,
+                deadline: 1_700_000_000,
+                meta: OrderMeta({isEcdsa: false, from: _repeat(0x55), signature: hex"010203"}),
+                extraFeeAsset0: 9,
+                poolFeeAsset0: 1
+            })
+        );
+        // partial flash order signed by an EOA
+        bundle.userOrders[1] = UserOrderLib.from(
+            PartialFlashOrder({
+                refId: 0,
+                minAmountIn: 1,
+                maxAmountIn: 1_000,
+                maxExtraFeeAsset0: 0,
+                minPrice: 2 * RAY,
+                useInternal: false,
+                assetIn: _repeat(0x11),
+                assetOut: _repeat(0x22),
+                recipient: address(0),
+                hook: address(0),
+                hookPayload: "",
+                validForBlock: 0,
+                meta: _ecdsa(28, 0xcc, 0xdd),
+                amountFilled: 600,
+                extraFeeAsset0: 0,
+                poolFeeAsset0: 0
+            })
+        );
+    }
+
+    /// @dev The assets and pair every non empty bundle trades.
+    function _tradingBundle() internal pure returns (Bundle memory bundle) {
+        bundle.assets = new Asset[](2);
+        bundle.assets[0] = Asset(_repeat(0x11), 1, 2, 3);
+        bundle.assets[1] = Asset(_repeat(0x22), 0, 0, 0);
+        bundle.pairs = new Pair[](1);
+        bundle.pairs[0] = Pair(_repeat(0x11), _repeat(0x22), PriceAB.wrap(RAY));
+    }
+
+    /// @dev Config store with the traded pair at `STORE_INDEX`, after unrelated pairs.
+    function _store() internal returns (PoolConfigStore store) {
+        for (uint160 i = 1; i <= STORE_INDEX; i++) {
+            (address asset0, address asset1) = (address(2 * i), address(2 * i + 1));
+            store = store.setIntoNew(
+                PoolConfigStoreLib.keyFromAssetsUnchecked(asset0, asset1), asset0, asset1, 60, 0
+            );
+        }
+        (address asset0, address asset1) = (_repeat(0x11), _repeat(0x22));
+        store = store.setIntoNew(
+            PoolConfigStoreLib.keyFromAssetsUnchecked(asset0, asset1), asset0, asset1, 60, 0
+        );
+    }
+
+    function _write(string memory name, Bundle memory bundle, PoolConfigStore store) internal {
+        vm.writeFile(
+            string.concat(OUT_DIR, name, ".hex"),
+            string.concat(vm.toString(bundle.encode(PoolConfigStore.unwrap(store))), "\n")
+        );
+    }
+
+    function _currentOnly(uint128 amount) internal pure returns (RewardsUpdate memory) {
+        return RewardsUpdate(true, amount, 0, 0, new uint128[](0));
+    }
+
+    function _ecdsa(uint8 v, uint8 r, uint8 s) internal pure returns (OrderMeta memory) {
+        return OrderMeta({
+            isEcdsa: true,
+            from: address(0),
+            signature: abi.encodePacked(v, _repeat32(r), _repeat32(s))
+        });
+    }
+
+    /// @dev The address with every byte set to `b`.
+    function _repeat(uint8 b) internal pure returns (address) {
+        return address(uint160(type(uint160).max / 0xff * b));
+    }
+
+    function _repeat32(uint8 b) internal pure returns (bytes32) {
+        return bytes32(type(uint256).max / 0xff * b);
+    }
+}
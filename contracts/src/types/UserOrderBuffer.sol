@@ -177,13 +177,17 @@ library UserOrderBufferLib {
             self.quantity_or_maxQuantityIn = quantity;
         }
 
+        // The gas & referral fee and the pool fee are charged together, within the max.
         uint128 extraFeeAsset0;
         {
             uint128 maxExtraFeeAsset0;
+            uint128 poolFeeAsset0;
             (reader, maxExtraFeeAsset0) = reader.readU128();
             (reader, extraFeeAsset0) = reader.readU128();
-            if (extraFeeAsset0 > maxExtraFeeAsset0) revert GasAboveMax();
+            (reader, poolFeeAsset0) = reader.readU128();
+            if (uint256(extraFeeAsset0) + poolFeeAsset0 > maxExtraFeeAsset0) revert GasAboveMax();
             self.maxExtraFeeAsset0 = maxExtraFeeAsset0;
+            extraFeeAsset0 += poolFeeAsset0;
         }
 
         if (variant.zeroForOne()) {
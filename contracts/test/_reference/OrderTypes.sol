@@ -41,6 +41,7 @@ struct PartialStandingOrder {
     OrderMeta meta;
     uint128 amountFilled;
     uint128 extraFeeAsset0;
+    uint128 poolFeeAsset0;
 }
 
 struct ExactStandingOrder {
@@ -59,6 +60,7 @@ struct ExactStandingOrder {
     uint40 deadline;
     OrderMeta meta;
     uint128 extraFeeAsset0;
+    uint128 poolFeeAsset0;
 }
 
 struct PartialFlashOrder {
@@ -77,6 +79,7 @@ struct PartialFlashOrder {
     OrderMeta meta;
     uint128 amountFilled;
     uint128 extraFeeAsset0;
+    uint128 poolFeeAsset0;
 }
 
 struct ExactFlashOrder {
@@ -94,6 +97,7 @@ struct ExactFlashOrder {
     uint64 validForBlock;
     OrderMeta meta;
     uint128 extraFeeAsset0;
+    uint128 poolFeeAsset0;
 }
 
 struct TopOfBlockOrder {
@@ -235,6 +239,7 @@ library OrdersLib {
             bytes16(order.amountFilled),
             bytes16(order.maxExtraFeeAsset0),
             bytes16(order.extraFeeAsset0),
+            bytes16(order.poolFeeAsset0),
             _encodeSig(order.meta)
         );
     }
@@ -271,6 +276,7 @@ library OrdersLib {
             bytes16(order.amount),
             bytes16(order.maxExtraFeeAsset0),
             bytes16(order.extraFeeAsset0),
+            bytes16(order.poolFeeAsset0),
             _encodeSig(order.meta)
         );
     }
@@ -307,6 +313,7 @@ library OrdersLib {
             bytes16(order.amountFilled),
             bytes16(order.maxExtraFeeAsset0),
             bytes16(order.extraFeeAsset0),
+            bytes16(order.poolFeeAsset0),
             _encodeSig(order.meta)
         );
     }
@@ -339,6 +346,7 @@ library OrdersLib {
             bytes16(order.amount),
             bytes16(order.maxExtraFeeAsset0),
             bytes16(order.extraFeeAsset0),
+            bytes16(order.poolFeeAsset0),
             _encodeSig(order.meta)
         );
     }
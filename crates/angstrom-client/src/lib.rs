@@ -0,0 +1,213 @@
+//! A typed async client for the `angstrom` rpc namespace of a node.
+//!
+//! The methods are the ones generated from the rpc traits of `angstrom-rpc`,
+//! so they can't drift from what the node serves. [`AngstromClient`] adds
+//! order submission that signs locally on top of them, everything else is
+//! reached through the re-exported `*ApiClient` traits.
+
+use std::{
+    collections::HashSet,
+    ops::Deref,
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use alloy::{signers::local::PrivateKeySigner, sol_types::Eip712Domain};
+use alloy_primitives::{Address, B256};
+use angstrom_types::{
+    orders::OrderStatus,
+    primitive::{angstrom_domain, OrderPoolNewOrderResult},
+    sol_bindings::{grouped_orders::AllOrders, RawPoolOrder}
+};
+use jsonrpsee::{
+    core::client::{Subscription, SubscriptionClientT},
+    http_client::{HttpClient, HttpClientBuilder},
+    ws_client::{WsClient, WsClientBuilder}
+};
+use thiserror::Error;
+
+mod signing;
+pub use angstrom_rpc::{
+    api::*,
+    types::{OrderSubscriptionFilter, OrderSubscriptionKind, OrderSubscriptionResult}
+};
+pub use angstrom_types::sol_bindings::builder::*;
+pub use signing::*;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Rpc(#[from] jsonrpsee::core::ClientError),
+    #[error("failed to sign: {0}")]
+    Signer(#[from] alloy::signers::Error)
+}
+
+/// The deployment of the angstrom contract the node runs against, orders are
+/// only valid when signed for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientConfig {
+    pub chain_id:         u64,
+    pub angstrom_address: Address
+}
+
+impl ClientConfig {
+    /// The domain orders for the deployment are signed in.
+    pub fn domain(&self) -> Eip712Domain {
+        angstrom_domain(self.chain_id, self.angstrom_address)
+    }
+}
+
+/// A client of the rpc of an angstrom node, over http or a websocket.
+#[derive(Debug, Clone)]
+pub struct AngstromClient<C> {
+    client: C,
+    /// the domain orders are signed in
+    domain: Eip712Domain
+}
+
+impl AngstromClient<HttpClient> {
+    pub fn connect_http(url: impl AsRef<str>, config: ClientConfig) -> Result<Self, ClientError> {
+        Ok(Self::new(HttpClientBuilder::default().build(url)?, config))
+    }
+}
+
+impl AngstromClient<WsClient> {
+    pub async fn connect_ws(
+        url: impl AsRef<str>,
+        config: ClientConfig
+    ) -> Result<Self, ClientError> {
+        Ok(Self::new(WsClientBuilder::default().build(url).await?, config))
+    }
+}
+
+impl<C> AngstromClient<C> {
+    pub fn new(client: C, config: ClientConfig) -> Self {
+        Self { client, domain: config.domain() }
+    }
+
+    pub fn domain(&self) -> &Eip712Domain {
+        &self.domain
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    /// Signs the order with `signer` for the deployment of the client.
+    pub fn sign(
+        &self,
+        signer: &PrivateKeySigner,
+        order: impl Into<AllOrders>
+    ) -> Result<AllOrders, alloy::signers::Error> {
+        let mut order = order.into();
+        order.sign_in(signer, &self.domain)?;
+        Ok(order)
+    }
+}
+
+impl<C: SubscriptionClientT + Send + Sync> AngstromClient<C> {
+    /// Signs the order with `signer` and submits it.
+    pub async fn send_signed_order(
+        &self,
+        signer: &PrivateKeySigner,
+        order: impl Into<AllOrders>
+    ) -> Result<OrderPoolNewOrderResult, ClientError> {
+        Ok(self.client.send_order(self.sign(signer, order)?).await?)
+    }
+
+    /// Signs the order with `signer` and submits it privately, it's only
+    /// included in the rounds the node leads.
+    pub async fn send_signed_private_order(
+        &self,
+        signer: &PrivateKeySigner,
+        order: impl Into<AllOrders>
+    ) -> Result<OrderPoolNewOrderResult, ClientError> {
+        Ok(self
+            .client
+            .send_private_order(self.sign(signer, order)?)
+            .await?)
+    }
+
+    /// Signs the standing order with `signer` and submits it with its price
+    /// pegged `offset_bps` away from the AMM mid. Its signed price is the worst
+    /// the peg can resolve to.
+    pub async fn send_signed_pegged_order(
+        &self,
+        signer: &PrivateKeySigner,
+        order: impl Into<AllOrders>,
+        offset_bps: i32
+    ) -> Result<OrderPoolNewOrderResult, ClientError> {
+        let order = self.sign(signer, order)?;
+        let peg = price_peg(signer, order.order_hash(), offset_bps)?;
+        Ok(self.client.send_pegged_order(order, peg).await?)
+    }
+
+    /// Signs the tranches of an iceberg order with `signer` and submits them,
+    /// only the first is shown until it's filled.
+    pub async fn send_signed_iceberg_order<O: Into<AllOrders>>(
+        &self,
+        signer: &PrivateKeySigner,
+        tranches: impl IntoIterator<Item = O>
+    ) -> Result<Vec<OrderPoolNewOrderResult>, ClientError> {
+        let tranches = tranches
+            .into_iter()
+            .map(|tranche| self.sign(signer, tranche))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.client.send_iceberg_order(tranches).await?)
+    }
+
+    /// Cancels the order with `order_id` of the signer, returns whether the
+    /// node had it.
+    pub async fn cancel_signed_order(
+        &self,
+        signer: &PrivateKeySigner,
+        order_id: B256
+    ) -> Result<bool, ClientError> {
+        Ok(self
+            .client
+            .cancel_order(cancel_order_request(signer, order_id)?)
+            .await?)
+    }
+
+    /// Cancels every resting order of the signer placed until now, returns the
+    /// hashes of the cancelled orders.
+    pub async fn cancel_all_signed_orders(
+        &self,
+        signer: &PrivateKeySigner,
+        valid_until: u64
+    ) -> Result<Vec<B256>, ClientError> {
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Ok(self
+            .client
+            .cancel_all_orders(cancel_all_orders_request(signer, issued_at, valid_until)?)
+            .await?)
+    }
+
+    pub async fn status_of(&self, order_hash: B256) -> Result<Option<OrderStatus>, ClientError> {
+        Ok(self.client.order_status(order_hash).await?)
+    }
+
+    /// Subscribes to the updates of `kind` of the orders matching `filters`,
+    /// all orders if there are none. Only served over a websocket.
+    pub async fn order_updates(
+        &self,
+        kind: impl IntoIterator<Item = OrderSubscriptionKind>,
+        filters: impl IntoIterator<Item = OrderSubscriptionFilter>
+    ) -> Result<Subscription<OrderSubscriptionResult>, ClientError> {
+        let kind = kind.into_iter().collect::<HashSet<_>>();
+        let filters = filters.into_iter().collect::<HashSet<_>>();
+        Ok(self.client.subscribe_orders(kind, filters).await?)
+    }
+}
+
+/// The generated clients of every rpc trait are reached through the inner
+/// client.
+impl<C> Deref for AngstromClient<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
@@ -0,0 +1,108 @@
+use alloy::signers::{local::PrivateKeySigner, SignerSync};
+use alloy_primitives::B256;
+use angstrom_types::orders::{CancelAllOrdersRequest, CancelOrderRequest, PricePeg};
+
+/// Cancels the order with `order_id` of the signer.
+///
+/// The request is signed over the `CancelOrder` domain separated digest.
+/// Nodes still accept cancels signed over the bare
+/// `keccak256(abi.encode(user, order_id))` until
+/// [`LEGACY_CANCEL_DIGEST_SUNSET`], SDKs signing those have to move over
+/// before then.
+///
+/// [`LEGACY_CANCEL_DIGEST_SUNSET`]: angstrom_types::orders::LEGACY_CANCEL_DIGEST_SUNSET
+pub fn cancel_order_request(
+    signer: &PrivateKeySigner,
+    order_id: B256
+) -> Result<CancelOrderRequest, alloy::signers::Error> {
+    let user_address = signer.address();
+    let hash = CancelOrderRequest::signing_payload(user_address, order_id);
+    Ok(CancelOrderRequest { signature: signer.sign_hash_sync(&hash)?, user_address, order_id })
+}
+
+/// Pegs the price of the order with `order_hash` of the signer `offset_bps`
+/// away from the AMM mid.
+pub fn price_peg(
+    signer: &PrivateKeySigner,
+    order_hash: B256,
+    offset_bps: i32
+) -> Result<PricePeg, alloy::signers::Error> {
+    let hash = PricePeg::signing_payload(order_hash, offset_bps);
+    Ok(PricePeg { order_hash, offset_bps, signature: signer.sign_hash_sync(&hash)? })
+}
+
+/// Cancels every resting order of the signer received by the unix timestamp
+/// `issued_at`, the request is accepted until the unix timestamp
+/// `valid_until`.
+pub fn cancel_all_orders_request(
+    signer: &PrivateKeySigner,
+    issued_at: u64,
+    valid_until: u64
+) -> Result<CancelAllOrdersRequest, alloy::signers::Error> {
+    let user_address = signer.address();
+    let hash = CancelAllOrdersRequest::signing_payload(user_address, issued_at, valid_until);
+    Ok(CancelAllOrdersRequest {
+        signature: signer.sign_hash_sync(&hash)?,
+        user_address,
+        issued_at,
+        valid_until
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Address;
+    use angstrom_types::{
+        primitive::angstrom_domain,
+        sol_bindings::{
+            builder::SignOrder,
+            grouped_orders::{AllOrders, StandingVariants},
+            rpc_orders::{ExactStandingOrder, TopOfBlockOrder},
+            RawPoolOrder
+        }
+    };
+
+    use super::*;
+
+    #[test]
+    fn signs_orders_the_node_accepts() {
+        let signer = PrivateKeySigner::random();
+        let domain = angstrom_domain(1, Address::repeat_byte(1));
+        let mut order = AllOrders::Standing(StandingVariants::Exact(ExactStandingOrder::default()));
+        assert!(!order.is_valid_signature_in(&domain));
+
+        order.sign_in(&signer, &domain).unwrap();
+        assert!(order.is_valid_signature_in(&domain));
+        assert_eq!(order.from(), signer.address());
+
+        // signed for another chain
+        let other = angstrom_domain(2, Address::repeat_byte(1));
+        let mut order = AllOrders::TOB(TopOfBlockOrder::default());
+        order.sign_in(&signer, &other).unwrap();
+        assert!(order.is_valid_signature_in(&other));
+        assert!(!order.is_valid_signature_in(&domain));
+    }
+
+    #[test]
+    fn signs_cancellations() {
+        let signer = PrivateKeySigner::random();
+        assert!(cancel_order_request(&signer, B256::repeat_byte(1))
+            .unwrap()
+            .is_valid());
+        assert!(cancel_all_orders_request(&signer, 0, u64::MAX)
+            .unwrap()
+            .is_valid());
+    }
+
+    #[test]
+    fn signs_pegs() {
+        let signer = PrivateKeySigner::random();
+        let mut order = AllOrders::Standing(StandingVariants::Exact(ExactStandingOrder::default()));
+        order
+            .sign_in(&signer, &angstrom_domain(1, Address::ZERO))
+            .unwrap();
+
+        let peg = price_peg(&signer, order.order_hash(), 20).unwrap();
+        assert_eq!(peg.validate(&order), Ok(()));
+    }
+}
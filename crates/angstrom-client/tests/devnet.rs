@@ -0,0 +1,71 @@
+//! Runs the client against the rpc of a devnet node, to catch it drifting from
+//! what the node serves. The node is at `ANGSTROM_DEVNET_WS`, by default the
+//! first node of `testnet --devnet`, its contract at
+//! `ANGSTROM_DEVNET_ADDRESS` on chain `ANGSTROM_DEVNET_CHAIN_ID`.
+
+use alloy::signers::local::PrivateKeySigner;
+use angstrom_client::{AngstromClient, ClientConfig, OrderApiClient, OrderSubscriptionKind};
+use angstrom_types::{
+    primitive::TESTNET_ANGSTROM_ADDRESS,
+    sol_bindings::{grouped_orders::AllOrders, RawPoolOrder}
+};
+use futures::StreamExt;
+use jsonrpsee::ws_client::WsClient;
+use testing_tools::type_generator::orders::UserOrderBuilder;
+
+async fn devnet_client() -> AngstromClient<WsClient> {
+    let url =
+        std::env::var("ANGSTROM_DEVNET_WS").unwrap_or_else(|_| "ws://127.0.0.1:4200".to_string());
+    let config = ClientConfig {
+        chain_id:         std::env::var("ANGSTROM_DEVNET_CHAIN_ID")
+            .map_or(1, |chain_id| chain_id.parse().expect("invalid chain id")),
+        angstrom_address: std::env::var("ANGSTROM_DEVNET_ADDRESS")
+            .map_or(TESTNET_ANGSTROM_ADDRESS, |address| address.parse().expect("invalid address"))
+    };
+    AngstromClient::connect_ws(url, config)
+        .await
+        .expect("no devnet node to connect to")
+}
+
+#[tokio::test]
+#[ignore]
+async fn submits_signed_orders() {
+    let client = devnet_client().await;
+    let signer = PrivateKeySigner::random();
+    let order = UserOrderBuilder::new().is_exact(true).amount(1000).build();
+    let signed: AllOrders = client.sign(&signer, order.clone()).unwrap();
+
+    let mut new_orders = client
+        .order_updates([OrderSubscriptionKind::NewOrders], [])
+        .await
+        .unwrap();
+    let result = client.send_signed_order(&signer, order).await.unwrap();
+
+    let status = client.status_of(signed.order_hash()).await.unwrap();
+    assert_eq!(status.is_some(), result.is_valid());
+    if result.is_valid() {
+        assert!(new_orders.next().await.unwrap().is_ok());
+        assert!(client
+            .cancel_signed_order(&signer, signed.order_hash())
+            .await
+            .unwrap());
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn reaches_the_generated_clients() {
+    let client = devnet_client().await;
+    let signer = PrivateKeySigner::random();
+
+    assert!(client
+        .pending_order(signer.address())
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(client
+        .cancel_all_signed_orders(&signer, u64::MAX)
+        .await
+        .unwrap()
+        .is_empty());
+}
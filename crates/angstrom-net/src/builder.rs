@@ -1,11 +1,15 @@
 //! Builder structs for messages.
 
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
-use alloy::{primitives::Address, signers::SignerSync};
+use alloy::{
+    primitives::{Address, B256},
+    signers::SignerSync
+};
 use alloy_chains::Chain;
 use angstrom_eth::manager::EthEvent;
 use angstrom_types::primitive::{AngstromSigner, PeerId};
+use angstrom_utils::clock::Clock;
 use futures::FutureExt;
 use parking_lot::RwLock;
 use reth_metrics::common::mpsc::{MeteredPollSender, UnboundedMeteredSender};
@@ -14,9 +18,10 @@ use tokio::sync::mpsc::{Receiver, UnboundedReceiver};
 use tokio_util::sync::PollSender;
 
 use crate::{
-    manager::StromConsensusEvent, state::StromState, types::status::StatusState, NetworkOrderEvent,
-    Status, StromNetworkHandle, StromNetworkManager, StromProtocolHandler, StromSessionManager,
-    StromSessionMessage, Swarm, VerificationSidecar
+    manager::StromConsensusEvent, state::StromState, types::status::StatusState, BandwidthLimits,
+    NetworkOrderEvent, StakeBinding, Status, StromNetworkHandle, StromNetworkManager,
+    StromProtocolHandler, StromSessionManager, StromSessionMessage, StromVersion, Swarm,
+    VerificationSidecar, DEFAULT_RESUMPTION_WINDOW
 };
 
 pub struct NetworkBuilder {
@@ -25,8 +30,11 @@ pub struct NetworkBuilder {
     session_manager_rx:   Option<Receiver<StromSessionMessage>>,
     eth_handle:           UnboundedReceiver<EthEvent>,
 
-    validator_set: Arc<RwLock<HashSet<Address>>>,
-    verification:  VerificationSidecar
+    validator_set:     Arc<RwLock<HashSet<Address>>>,
+    verification:      VerificationSidecar,
+    bandwidth_limits:  BandwidthLimits,
+    resumption_window: Duration,
+    clock:             Clock
 }
 
 impl NetworkBuilder {
@@ -37,7 +45,10 @@ impl NetworkBuilder {
             to_consensus_manager: None,
             session_manager_rx: None,
             eth_handle,
-            validator_set: Default::default()
+            validator_set: Default::default(),
+            bandwidth_limits: BandwidthLimits::default(),
+            resumption_window: DEFAULT_RESUMPTION_WINDOW,
+            clock: Clock::system()
         }
     }
 
@@ -54,21 +65,50 @@ impl NetworkBuilder {
         self
     }
 
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth_limits = limits;
+        self
+    }
+
+    pub fn with_resumption_window(mut self, window: Duration) -> Self {
+        self.resumption_window = window;
+        self
+    }
+
+    /// Runs the sessions, their throttles and the session resumption on
+    /// `clock`.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn with_validator_set(mut self, validator_set: Arc<RwLock<HashSet<Address>>>) -> Self {
         self.validator_set = validator_set;
         self
     }
 
-    pub fn build_protocol_handler(&mut self) -> StromProtocolHandler {
+    /// Builds a protocol handler for every version of the strom protocol we
+    /// speak, so peers that haven't upgraded yet keep their sessions.
+    pub fn build_protocol_handlers(&mut self) -> Vec<StromProtocolHandler> {
         let (session_manager_tx, session_manager_rx) = tokio::sync::mpsc::channel(100);
-        let protocol = StromProtocolHandler::new(
-            MeteredPollSender::new(PollSender::new(session_manager_tx), "session manager"),
-            self.verification.clone(),
-            self.validator_set.clone()
-        );
+        let protocols = StromVersion::ALL
+            .into_iter()
+            .map(|version| {
+                StromProtocolHandler::new(
+                    MeteredPollSender::new(
+                        PollSender::new(session_manager_tx.clone()),
+                        "session manager"
+                    ),
+                    self.verification.clone(),
+                    self.validator_set.clone()
+                )
+                .with_version(version)
+                .with_clock(self.clock.clone())
+            })
+            .collect();
         self.session_manager_rx = Some(session_manager_rx);
 
-        protocol
+        protocols
     }
 
     /// builds the network spawning it on its own thread, returning the
@@ -80,7 +120,9 @@ impl NetworkBuilder {
         db: DB
     ) -> StromNetworkHandle {
         let state = StromState::new(db, self.validator_set.clone());
-        let sessions = StromSessionManager::new(self.session_manager_rx.take().unwrap());
+        let sessions = StromSessionManager::new(self.session_manager_rx.take().unwrap())
+            .with_bandwidth_limits(self.bandwidth_limits)
+            .with_clock(self.clock.clone());
         let swarm = Swarm::new(sessions, state);
 
         let network = StromNetworkManager::new(
@@ -88,7 +130,9 @@ impl NetworkBuilder {
             self.eth_handle,
             self.to_pool_manager,
             self.to_consensus_manager
-        );
+        )
+        .with_resumption_window(self.resumption_window)
+        .with_clock(self.clock);
 
         let handle = network.get_handle();
         tp.spawn_critical("strom network", network.boxed());
@@ -100,24 +144,44 @@ impl NetworkBuilder {
 /// Builder for [`Status`] messages.
 #[derive(Debug)]
 pub struct StatusBuilder {
-    state: StatusState
+    state:    StatusState,
+    binding:  Option<StakeBinding>,
+    observer: bool,
+    genesis:  B256,
+    /// version of the session the status is sent on
+    protocol: StromVersion,
+    clock:    Clock
 }
 
 impl StatusBuilder {
     pub fn new(peer: PeerId) -> StatusBuilder {
-        Self { state: StatusState::new(peer) }
+        Self {
+            state:    StatusState::new(peer),
+            binding:  None,
+            observer: false,
+            genesis:  B256::ZERO,
+            protocol: StromVersion::LATEST,
+            clock:    Clock::system()
+        }
     }
 
     /// Consumes the type and creates the actual [`Status`] message, Signing the
     /// payload
     pub fn build(mut self, key: &AngstromSigner) -> Status {
-        // set state timestamp to now;
-        self.state.timestamp_now();
+        self.state.timestamp = self.clock.unix_now().as_millis();
 
-        let message = self.state.to_message();
+        let message = self
+            .state
+            .to_message(self.protocol, self.genesis, self.observer);
         let sig = key.sign_hash_sync(&message).unwrap();
 
-        Status { state: self.state, signature: sig }
+        Status {
+            state:     self.state,
+            signature: sig,
+            binding:   self.binding,
+            observer:  self.observer,
+            genesis:   self.genesis
+        }
     }
 
     /// Sets the protocol version.
@@ -126,6 +190,37 @@ impl StatusBuilder {
         self
     }
 
+    /// Sets the version of the session the status is sent on, which picks the
+    /// digest it is signed over.
+    pub fn protocol(mut self, version: StromVersion) -> Self {
+        self.protocol = version;
+        self
+    }
+
+    /// Sets the binding of the node's key to the staking key of its validator.
+    pub fn stake_binding(mut self, binding: Option<StakeBinding>) -> Self {
+        self.binding = binding;
+        self
+    }
+
+    /// Marks the node as an observer, which validators only send to.
+    pub fn observer(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Sets the hash of the protocol genesis the node runs with.
+    pub fn genesis(mut self, genesis: B256) -> Self {
+        self.genesis = genesis;
+        self
+    }
+
+    /// Sets the clock the status is timestamped by.
+    pub fn clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Sets the chain id.
     pub fn chain(mut self, chain: Chain) -> Self {
         self.state.chain = chain.id();
@@ -135,6 +230,13 @@ impl StatusBuilder {
 
 impl From<StatusState> for StatusBuilder {
     fn from(value: StatusState) -> Self {
-        Self { state: value }
+        Self {
+            state:    value,
+            binding:  None,
+            observer: false,
+            genesis:  B256::ZERO,
+            protocol: StromVersion::LATEST,
+            clock:    Clock::system()
+        }
     }
 }
@@ -10,12 +10,12 @@ use reth_transaction_pool::PoolTransaction;
 
 /// A basic ethereum payload service.
 pub struct AngstromNetworkBuilder<I: IntoRlpxSubProtocol + Send> {
-    custom_protocol: I
+    custom_protocols: Vec<I>
 }
 
 impl<I: IntoRlpxSubProtocol + Send> AngstromNetworkBuilder<I> {
-    pub fn new(protocol: I) -> Self {
-        Self { custom_protocol: protocol }
+    pub fn new(protocols: Vec<I>) -> Self {
+        Self { custom_protocols: protocols }
     }
 }
 
@@ -36,7 +36,9 @@ where
         pool: Pool
     ) -> eyre::Result<NetworkHandle> {
         let mut network_config = ctx.network_config()?;
-        network_config.extra_protocols.push(self.custom_protocol);
+        for protocol in self.custom_protocols {
+            network_config.extra_protocols.push(protocol);
+        }
 
         let network = NetworkManager::builder(network_config).await?;
         let handle = ctx.start_network(network, pool);
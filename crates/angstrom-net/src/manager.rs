@@ -1,16 +1,21 @@
 use std::{
+    collections::HashSet,
     future::Future,
     pin::Pin,
     sync::{atomic::AtomicUsize, Arc},
-    task::{Context, Poll}
+    task::{Context, Poll},
+    time::Duration
 };
 
 use alloy::primitives::BlockNumber;
 use angstrom_eth::manager::EthEvent;
 use angstrom_types::{
-    consensus::{PreProposal, PreProposalAggregation, Proposal},
+    consensus::{
+        BundleHandoff, KeyHandover, PreProposal, PreProposalAggregation, Proposal, ProposalShare
+    },
     primitive::PeerId
 };
+use angstrom_utils::clock::Clock;
 use futures::StreamExt;
 use reth_eth_wire::DisconnectReason;
 use reth_metrics::common::mpsc::UnboundedMeteredSender;
@@ -18,7 +23,10 @@ use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::error;
 
-use crate::{NetworkOrderEvent, StromMessage, StromNetworkHandleMsg, Swarm, SwarmEvent};
+use crate::{
+    NetworkOrderEvent, Resumed, SessionResume, SessionResumption, StromMessage,
+    StromNetworkHandleMsg, StromVersion, Swarm, SwarmEvent, TracedOrder
+};
 #[allow(unused_imports)]
 use crate::{StromNetworkConfig, StromNetworkHandle, StromSessionManager};
 
@@ -33,6 +41,11 @@ pub struct StromNetworkManager<DB> {
 
     event_listeners:  Vec<UnboundedSender<StromNetworkEvent>>,
     swarm:            Swarm<DB>,
+    /// peers the operator banned, their sessions are dropped
+    banned_peers:     HashSet<PeerId>,
+    /// consensus messages to replay to peers that resume a dropped session
+    resumption:       SessionResumption,
+    clock:            Clock,
     /// This is updated via internal events and shared via `Arc` with the
     /// [`NetworkHandle`] Updated by the `NetworkWorker` and loaded by the
     /// `NetworkService`.
@@ -60,10 +73,26 @@ impl<DB: Unpin> StromNetworkManager<DB> {
             from_handle_rx: rx.into(),
             to_pool_manager,
             to_consensus_manager,
+            banned_peers: HashSet::new(),
+            resumption: SessionResumption::default(),
+            clock: Clock::system(),
             event_listeners: Vec::new()
         }
     }
 
+    /// How long after a session dropped the peer can still resume it, and be
+    /// replayed the consensus messages it missed.
+    pub fn with_resumption_window(mut self, window: Duration) -> Self {
+        self.resumption = SessionResumption::new(window);
+        self
+    }
+
+    /// Takes the time the resumption window runs on from `clock`.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn install_consensus_manager(&mut self, tx: UnboundedMeteredSender<StromConsensusEvent>) {
         self.to_consensus_manager = Some(tx);
     }
@@ -116,6 +145,7 @@ impl<DB: Unpin> StromNetworkManager<DB> {
         match msg {
             StromNetworkHandleMsg::SubscribeEvents(tx) => self.event_listeners.push(tx),
             StromNetworkHandleMsg::SendStromMessage { peer_id, msg } => {
+                self.resumption.sent(peer_id, &msg, self.clock.now());
                 self.swarm.sessions_mut().send_message(&peer_id, msg)
             }
             StromNetworkHandleMsg::Shutdown(tx) => {
@@ -131,17 +161,59 @@ impl<DB: Unpin> StromNetworkManager<DB> {
             StromNetworkHandleMsg::RemovePeer(peer_id) => {
                 self.swarm.state_mut().peers_mut().remove_peer(peer_id);
             }
-            StromNetworkHandleMsg::ReputationChange(peer_id, kind) => self
-                .swarm
-                .state_mut()
-                .peers_mut()
-                .change_weight(peer_id, kind),
+            StromNetworkHandleMsg::ReputationChange(peer_id, kind) => {
+                let identity = self
+                    .swarm
+                    .sessions_mut()
+                    .identity(&peer_id)
+                    .unwrap_or(peer_id);
+                self.swarm
+                    .state_mut()
+                    .peers_mut()
+                    .change_weight(identity, kind)
+            }
             StromNetworkHandleMsg::BroadcastStromMessage { msg } => {
+                self.resumption.broadcast(&msg, self.clock.now());
                 self.swarm_mut().sessions_mut().broadcast_message(msg);
             }
             StromNetworkHandleMsg::DisconnectPeer(id, reason) => {
                 self.swarm_mut().sessions_mut().disconnect(id, reason);
             }
+            StromNetworkHandleMsg::BanPeer(peer_id) => {
+                tracing::info!(?peer_id, "banning peer");
+                self.banned_peers.insert(peer_id);
+                self.swarm.state_mut().peers_mut().remove_peer(peer_id);
+                self.swarm
+                    .sessions_mut()
+                    .disconnect(peer_id, Some(DisconnectReason::DisconnectRequested));
+            }
+            StromNetworkHandleMsg::UnbanPeer(peer_id) => {
+                if self.banned_peers.remove(&peer_id) {
+                    tracing::info!(?peer_id, "unbanned peer");
+                }
+            }
+            StromNetworkHandleMsg::PeerBandwidth(tx) => {
+                let _ = tx.send(self.swarm_mut().sessions_mut().bandwidth());
+            }
+        }
+    }
+
+    fn on_session_resume(&mut self, peer_id: PeerId, resume: SessionResume) {
+        let Resumed { replay, reissue } = self.resumption.resume(peer_id, resume);
+        if !replay.is_empty() {
+            tracing::debug!(
+                ?peer_id,
+                messages = replay.len(),
+                "replaying missed consensus messages"
+            );
+        }
+
+        let sessions = self.swarm.sessions_mut();
+        for msg in replay {
+            sessions.send_message(&peer_id, msg);
+        }
+        if let Some(reissue) = reissue {
+            sessions.send_message(&peer_id, StromMessage::ResumeSession(reissue));
         }
     }
 
@@ -182,13 +254,29 @@ impl<DB: Unpin> Future for StromNetworkManager<DB> {
                         self.swarm().state().add_validator(addr);
                     }
                     EthEvent::RemovedNode(addr) => {
-                        self.swarm_mut().state_mut().remove_validator(addr);
+                        self.swarm_mut().remove_validator(addr);
                     }
                     _ => {}
                 }
             }
 
             if let Poll::Ready(Some(event)) = self.swarm.poll_next_unpin(cx) {
+                if let SwarmEvent::ValidMessage { peer_id, .. }
+                | SwarmEvent::SessionEstablished { peer_id, .. } = &event
+                {
+                    if self.banned_peers.contains(peer_id) {
+                        let peer_id = *peer_id;
+                        self.swarm
+                            .sessions_mut()
+                            .disconnect(peer_id, Some(DisconnectReason::DisconnectRequested));
+                        continue
+                    }
+                }
+
+                if let SwarmEvent::ValidMessage { peer_id, msg } = &event {
+                    self.resumption.received(*peer_id, msg);
+                }
+
                 match event {
                     SwarmEvent::ValidMessage { peer_id, msg } => match msg {
                         StromMessage::PrePropose(p) => {
@@ -206,30 +294,122 @@ impl<DB: Unpin> Future for StromNetworkManager<DB> {
                                 let _ = tx.send(StromConsensusEvent::Proposal(peer_id, a));
                             });
                         }
+                        StromMessage::BundleHandoff(h) => {
+                            self.to_consensus_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(StromConsensusEvent::BundleHandoff(peer_id, h));
+                            });
+                        }
+                        StromMessage::KeyHandover(h) => {
+                            self.to_consensus_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(StromConsensusEvent::KeyHandover(peer_id, h));
+                            });
+                        }
+                        StromMessage::ProposalShare(s) => {
+                            self.to_consensus_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(StromConsensusEvent::ProposalShare(peer_id, s));
+                            });
+                        }
                         StromMessage::PropagatePooledOrders(a) => {
                             self.to_pool_manager.as_ref().inspect(|tx| {
                                 let _ = tx
                                     .send(NetworkOrderEvent::IncomingOrders { peer_id, orders: a });
                             });
                         }
+                        StromMessage::PropagateVersionedOrders(envelopes) => {
+                            let orders = envelopes
+                                .iter()
+                                .filter_map(|envelope| {
+                                    envelope
+                                        .open()
+                                        .inspect_err(|error| {
+                                            tracing::debug!(?peer_id, %error, "dropping order")
+                                        })
+                                        .ok()
+                                })
+                                .collect::<Vec<_>>();
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ =
+                                    tx.send(NetworkOrderEvent::IncomingOrders { peer_id, orders });
+                            });
+                        }
+                        StromMessage::PropagateTracedOrders(traced) => {
+                            let orders = traced
+                                .into_iter()
+                                .filter_map(|TracedOrder { order, trace }| {
+                                    order
+                                        .open()
+                                        .inspect_err(|error| {
+                                            tracing::debug!(?peer_id, %error, "dropping order")
+                                        })
+                                        .ok()
+                                        .map(|order| (order, trace))
+                                })
+                                .collect::<Vec<_>>();
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(NetworkOrderEvent::IncomingTracedOrders {
+                                    peer_id,
+                                    orders
+                                });
+                            });
+                        }
+                        StromMessage::AnnounceOrders(hashes) => {
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(NetworkOrderEvent::OrderAnnouncements {
+                                    peer_id,
+                                    hashes
+                                });
+                            });
+                        }
+                        StromMessage::RequestOrders(hashes) => {
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ =
+                                    tx.send(NetworkOrderEvent::OrderRequest { peer_id, hashes });
+                            });
+                        }
                         StromMessage::OrderCancellation(a) => {
                             self.to_pool_manager.as_ref().inspect(|tx| {
                                 let _ =
                                     tx.send(NetworkOrderEvent::CancelOrder { peer_id, request: a });
                             });
                         }
-                        StromMessage::Status(_) => {}
+                        StromMessage::OrderCancelAll(a) => {
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ = tx.send(NetworkOrderEvent::CancelAllOrders {
+                                    peer_id,
+                                    request: a
+                                });
+                            });
+                        }
+                        StromMessage::ResumeSession(resume) => {
+                            self.on_session_resume(peer_id, resume)
+                        }
+                        StromMessage::MirrorOrderFlow(flow) => {
+                            self.to_pool_manager.as_ref().inspect(|tx| {
+                                let _ =
+                                    tx.send(NetworkOrderEvent::MirroredOrderFlow { peer_id, flow });
+                            });
+                        }
+                        // backoffs are taken by the session manager
+                        StromMessage::Status(_) | StromMessage::Backoff(_) => {}
                     },
                     SwarmEvent::Disconnected { peer_id } => {
+                        self.resumption.disconnected(peer_id, self.clock.now());
                         self.notify_listeners(StromNetworkEvent::SessionClosed {
                             peer_id,
                             reason: None
                         })
                     }
-                    SwarmEvent::SessionEstablished { peer_id } => {
+                    SwarmEvent::SessionEstablished { peer_id, version } => {
                         self.num_active_peers
                             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-                        self.notify_listeners(StromNetworkEvent::SessionEstablished { peer_id })
+                        let resume = self.resumption.established(peer_id, self.clock.now());
+                        self.swarm
+                            .sessions_mut()
+                            .send_message(&peer_id, StromMessage::ResumeSession(resume));
+                        self.notify_listeners(StromNetworkEvent::SessionEstablished {
+                            peer_id,
+                            version
+                        })
                     }
                 }
             }
@@ -255,6 +435,8 @@ pub enum StromNetworkEvent {
     },
     /// Established a new session with the given peer.
     SessionEstablished {
+        /// Version of the strom protocol the session runs on
+        version: StromVersion,
         /// The identifier of the peer to which a session was established.
         peer_id: PeerId /* #[cfg(feature = "testnet")]
                          * initial_state: Option<angstrom_types::testnet::InitialTestnetState> */
@@ -269,7 +451,10 @@ pub enum StromNetworkEvent {
 pub enum StromConsensusEvent {
     PreProposal(PeerId, PreProposal),
     PreProposalAgg(PeerId, PreProposalAggregation),
-    Proposal(PeerId, Proposal)
+    Proposal(PeerId, Proposal),
+    BundleHandoff(PeerId, BundleHandoff),
+    KeyHandover(PeerId, KeyHandover),
+    ProposalShare(PeerId, ProposalShare)
 }
 
 impl StromConsensusEvent {
@@ -277,7 +462,10 @@ impl StromConsensusEvent {
         match self {
             StromConsensusEvent::PreProposal(..) => "PreProposal",
             StromConsensusEvent::PreProposalAgg(..) => "PreProposalAggregation",
-            StromConsensusEvent::Proposal(..) => "Proposal"
+            StromConsensusEvent::Proposal(..) => "Proposal",
+            StromConsensusEvent::BundleHandoff(..) => "BundleHandoff",
+            StromConsensusEvent::KeyHandover(..) => "KeyHandover",
+            StromConsensusEvent::ProposalShare(..) => "ProposalShare"
         }
     }
 
@@ -285,7 +473,10 @@ impl StromConsensusEvent {
         match self {
             StromConsensusEvent::PreProposal(peer_id, _)
             | StromConsensusEvent::Proposal(peer_id, _)
-            | StromConsensusEvent::PreProposalAgg(peer_id, _) => *peer_id
+            | StromConsensusEvent::PreProposalAgg(peer_id, _)
+            | StromConsensusEvent::BundleHandoff(peer_id, _)
+            | StromConsensusEvent::KeyHandover(peer_id, _)
+            | StromConsensusEvent::ProposalShare(peer_id, _) => *peer_id
         }
     }
 
@@ -293,7 +484,10 @@ impl StromConsensusEvent {
         match self {
             StromConsensusEvent::PreProposal(_, pre_proposal) => pre_proposal.source,
             StromConsensusEvent::PreProposalAgg(_, pre_proposal) => pre_proposal.source,
-            StromConsensusEvent::Proposal(_, proposal) => proposal.source
+            StromConsensusEvent::Proposal(_, proposal) => proposal.source,
+            StromConsensusEvent::BundleHandoff(_, handoff) => handoff.source,
+            StromConsensusEvent::KeyHandover(_, handover) => handover.source,
+            StromConsensusEvent::ProposalShare(_, share) => share.source
         }
     }
 
@@ -301,7 +495,10 @@ impl StromConsensusEvent {
         match self {
             StromConsensusEvent::PreProposal(_, PreProposal { block_height, .. }) => *block_height,
             StromConsensusEvent::PreProposalAgg(_, p) => p.block_height,
-            StromConsensusEvent::Proposal(_, Proposal { block_height, .. }) => *block_height
+            StromConsensusEvent::Proposal(_, Proposal { block_height, .. }) => *block_height,
+            StromConsensusEvent::BundleHandoff(_, handoff) => handoff.block_height,
+            StromConsensusEvent::KeyHandover(_, handover) => handover.block_height,
+            StromConsensusEvent::ProposalShare(_, share) => share.block_height
         }
     }
 }
@@ -314,7 +511,10 @@ impl From<StromConsensusEvent> for StromMessage {
             }
             StromConsensusEvent::PreProposalAgg(_, agg) => StromMessage::PreProposeAgg(agg),
 
-            StromConsensusEvent::Proposal(_, proposal) => StromMessage::Propose(proposal)
+            StromConsensusEvent::Proposal(_, proposal) => StromMessage::Propose(proposal),
+            StromConsensusEvent::BundleHandoff(_, handoff) => StromMessage::BundleHandoff(handoff),
+            StromConsensusEvent::KeyHandover(_, handover) => StromMessage::KeyHandover(handover),
+            StromConsensusEvent::ProposalShare(_, share) => StromMessage::ProposalShare(share)
         }
     }
 }
@@ -1,7 +1,13 @@
-use std::sync::{atomic::AtomicUsize, Arc};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicUsize, Arc}
+};
 
+use alloy::primitives::B256;
 use angstrom_types::{
-    orders::CancelOrderRequest, primitive::PeerId, sol_bindings::grouped_orders::AllOrders
+    orders::{CancelAllOrdersRequest, CancelOrderRequest},
+    primitive::PeerId,
+    sol_bindings::grouped_orders::AllOrders
 };
 use reth_metrics::common::mpsc::UnboundedMeteredSender;
 use reth_network::DisconnectReason;
@@ -11,7 +17,10 @@ use tokio::sync::{
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
-use crate::{ReputationChangeKind, StromMessage, StromNetworkEvent};
+use crate::{
+    BandwidthSnapshot, MirroredOrderFlow, PropagationTrace, ReputationChangeKind, StromMessage,
+    StromNetworkEvent
+};
 
 //TODO:
 // 1) Implement the order pool manager
@@ -73,6 +82,26 @@ impl StromNetworkHandle {
         self.send_to_network_manager(StromNetworkHandleMsg::RemovePeer(peer))
     }
 
+    /// Disconnects the peer and refuses any further session with it until it
+    /// is unbanned.
+    pub fn ban_peer(&self, peer: PeerId) {
+        self.send_to_network_manager(StromNetworkHandleMsg::BanPeer(peer))
+    }
+
+    pub fn unban_peer(&self, peer: PeerId) {
+        self.send_to_network_manager(StromNetworkHandleMsg::UnbanPeer(peer))
+    }
+
+    /// Returns the traffic sent to and received from every connected peer,
+    /// per message type.
+    pub async fn peer_bandwidth(
+        &self
+    ) -> Result<HashMap<PeerId, BandwidthSnapshot>, oneshot::error::RecvError> {
+        let (tx, rx) = oneshot::channel();
+        self.send_to_network_manager(StromNetworkHandleMsg::PeerBandwidth(tx));
+        rx.await
+    }
+
     pub fn peer_count(&self) -> usize {
         self.inner
             .num_active_peers
@@ -91,8 +120,43 @@ struct StromNetworkInner {
 /// All events related to orders emitted by the network.
 #[derive(Debug, Clone, PartialEq)]
 pub enum NetworkOrderEvent {
-    IncomingOrders { peer_id: PeerId, orders: Vec<AllOrders> },
-    CancelOrder { peer_id: PeerId, request: CancelOrderRequest }
+    IncomingOrders {
+        peer_id: PeerId,
+        orders:  Vec<AllOrders>
+    },
+    /// orders that were propagated with their trace
+    IncomingTracedOrders {
+        peer_id: PeerId,
+        orders:  Vec<(AllOrders, PropagationTrace)>
+    },
+    /// hashes of orders the peer has
+    OrderAnnouncements {
+        peer_id: PeerId,
+        hashes:  Vec<B256>
+    },
+    /// announced orders the peer wants
+    OrderRequest {
+        peer_id: PeerId,
+        hashes:  Vec<B256>
+    },
+    CancelOrder {
+        peer_id: PeerId,
+        request: CancelOrderRequest
+    },
+    CancelAllOrders {
+        peer_id: PeerId,
+        request: CancelAllOrdersRequest
+    },
+    /// order flow the peer mirrors to us
+    MirroredOrderFlow {
+        peer_id: PeerId,
+        flow:    MirroredOrderFlow
+    },
+    /// orders of late pre-proposals we don't have, no peer sent them to us so
+    /// none is blamed for the invalid ones
+    CarriedOverOrders {
+        orders: Vec<AllOrders>
+    }
 }
 
 #[derive(Debug)]
@@ -102,6 +166,10 @@ pub enum StromNetworkHandleMsg {
     RemovePeer(PeerId),
     /// Disconnect a connection to a peer if it exists.
     DisconnectPeer(PeerId, Option<DisconnectReason>),
+    /// Disconnects the peer and refuses its sessions from now on.
+    BanPeer(PeerId),
+    /// Accepts sessions of a banned peer again.
+    UnbanPeer(PeerId),
 
     /// Sends the strom message to a single peer.
     SendStromMessage {
@@ -116,6 +184,8 @@ pub enum StromNetworkHandleMsg {
 
     /// Apply a reputation change to the given peer.
     ReputationChange(PeerId, ReputationChangeKind),
+    /// Returns the traffic of every active session.
+    PeerBandwidth(oneshot::Sender<HashMap<PeerId, BandwidthSnapshot>>),
     /// Gracefully shutdown network
     Shutdown(oneshot::Sender<()>)
 }
@@ -6,7 +6,7 @@ use reth_network_peers::PeerId;
 use tracing::trace;
 
 pub use super::reputation::ReputationChangeWeights;
-use super::reputation::{is_banned_reputation, ReputationChangeKind};
+use super::reputation::{is_banned_reputation, ReputationChangeKind, DEFAULT_REPUTATION};
 
 /// Maintains the state of _all_ the peers known to the network.
 ///
@@ -55,6 +55,20 @@ impl PeersManager {
             .push_back(PeerAction::PeerRemoved(peer_id));
     }
 
+    /// Marks `identity` as connected, returning whether it's banned. A peer is
+    /// tracked by the staking key of its validator rather than its network
+    /// key, so regenerating the latter doesn't shed its reputation.
+    pub fn connected(&mut self, identity: PeerId) -> bool {
+        let peer = self.peers.entry(identity).or_insert(Peer {
+            reputation: DEFAULT_REPUTATION,
+            kind:       PeerKind::Basic,
+            connected:  false
+        });
+        peer.connected = true;
+
+        peer.is_banned() || self.ban_list.is_banned_peer(&identity)
+    }
+
     pub fn change_weight(&mut self, peer_id: PeerId, weight: ReputationChangeKind) {
         if let Some(outcome) = self
             .peers
@@ -1,6 +1,8 @@
 //! Peer related implementations
 
 pub mod manager;
+pub mod pinned;
 mod reputation;
 pub use manager::*;
+pub use pinned::*;
 pub use reputation::ReputationChangeKind;
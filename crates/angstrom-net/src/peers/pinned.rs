@@ -0,0 +1,288 @@
+//! Validator peers the node keeps a session with, whatever discovery finds.
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+    time::{Duration, Instant}
+};
+
+use angstrom_metrics::PinnedPeerMetricsWrapper;
+use angstrom_types::primitive::PeerId;
+use angstrom_utils::clock::{Clock, Interval};
+use futures::StreamExt;
+use reth_network_api::Peers;
+use reth_network_peers::{NodeRecord, NodeRecordParseError};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::StromNetworkEvent;
+
+/// How often the pinned peers are checked on.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A peer to stay connected to, given as `enode://<id>@<ip>:<port>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinnedPeer {
+    pub id:   PeerId,
+    pub addr: SocketAddr
+}
+
+impl FromStr for PinnedPeer {
+    type Err = NodeRecordParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let record = NodeRecord::from_str(s)?;
+        Ok(Self { id: record.id, addr: record.tcp_addr() })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedPeersConfig {
+    pub peers:           Vec<PinnedPeer>,
+    /// wait before the first redial of a peer that dropped, doubled on every
+    /// redial that doesn't connect
+    pub initial_backoff: Duration,
+    pub max_backoff:     Duration,
+    /// how long a peer can be unreachable before it's alerted on
+    pub alert_after:     Duration
+}
+
+impl PinnedPeersConfig {
+    pub fn new(peers: Vec<PinnedPeer>) -> Self {
+        Self {
+            peers,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            alert_after: Duration::from_secs(120)
+        }
+    }
+
+    pub fn with_alert_after(mut self, alert_after: Duration) -> Self {
+        self.alert_after = alert_after;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct PeerState {
+    addr:              SocketAddr,
+    connected:         bool,
+    backoff:           Duration,
+    next_dial:         Instant,
+    unreachable_since: Instant,
+    alerted:           bool
+}
+
+/// When to dial the pinned peers, with exponential backoff for the ones that
+/// don't connect.
+#[derive(Debug)]
+pub struct PinnedPeers {
+    config: PinnedPeersConfig,
+    peers:  HashMap<PeerId, PeerState>
+}
+
+impl PinnedPeers {
+    pub fn new(config: PinnedPeersConfig, now: Instant) -> Self {
+        let peers = config
+            .peers
+            .iter()
+            .map(|peer| {
+                let state = PeerState {
+                    addr:              peer.addr,
+                    connected:         false,
+                    backoff:           config.initial_backoff,
+                    next_dial:         now,
+                    unreachable_since: now,
+                    alerted:           false
+                };
+                (peer.id, state)
+            })
+            .collect();
+
+        Self { config, peers }
+    }
+
+    pub fn peers(&self) -> &[PinnedPeer] {
+        &self.config.peers
+    }
+
+    pub fn connected(&mut self, peer_id: PeerId) {
+        let Some(peer) = self.peers.get_mut(&peer_id) else { return };
+        if peer.alerted {
+            tracing::info!(?peer_id, "pinned peer is reachable again");
+        }
+        peer.connected = true;
+        peer.alerted = false;
+        peer.backoff = self.config.initial_backoff;
+    }
+
+    /// Redials `peer_id` right away, if it's pinned.
+    pub fn disconnected(&mut self, peer_id: PeerId, now: Instant) {
+        let Some(peer) = self.peers.get_mut(&peer_id) else { return };
+        peer.connected = false;
+        peer.next_dial = now;
+        peer.unreachable_since = now;
+    }
+
+    /// The peers to dial at `now`.
+    pub fn due(&mut self, now: Instant) -> Vec<(PeerId, SocketAddr)> {
+        let max_backoff = self.config.max_backoff;
+        self.peers
+            .iter_mut()
+            .filter(|(_, peer)| !peer.connected && peer.next_dial <= now)
+            .map(|(peer_id, peer)| {
+                peer.next_dial = now + peer.backoff;
+                peer.backoff = (peer.backoff * 2).min(max_backoff);
+                (*peer_id, peer.addr)
+            })
+            .collect()
+    }
+
+    /// Alerts on the peers that just went past the alert threshold, returning
+    /// how many are past it.
+    pub fn check_unreachable(&mut self, now: Instant) -> usize {
+        let mut unreachable = 0;
+        for (peer_id, peer) in self.peers.iter_mut() {
+            let unreachable_for = now.saturating_duration_since(peer.unreachable_since);
+            if peer.connected || unreachable_for <= self.config.alert_after {
+                continue
+            }
+
+            unreachable += 1;
+            if !peer.alerted {
+                tracing::warn!(
+                    ?peer_id,
+                    addr = %peer.addr,
+                    ?unreachable_for,
+                    "pinned peer is unreachable"
+                );
+                peer.alerted = true;
+            }
+        }
+
+        unreachable
+    }
+}
+
+/// Keeps the node connected to its [`PinnedPeers`].
+pub struct PinnedPeersTask<N> {
+    network:        N,
+    pinned:         PinnedPeers,
+    network_events: UnboundedReceiverStream<StromNetworkEvent>,
+    interval:       Interval,
+    clock:          Clock,
+    metrics:        PinnedPeerMetricsWrapper
+}
+
+impl<N: Peers> PinnedPeersTask<N> {
+    pub fn new(
+        network: N,
+        config: PinnedPeersConfig,
+        network_events: UnboundedReceiverStream<StromNetworkEvent>,
+        clock: Clock
+    ) -> Self {
+        let pinned = PinnedPeers::new(config, clock.now());
+        // trusted peers are never dropped for their reputation
+        for peer in pinned.peers() {
+            network.add_trusted_peer(peer.id, peer.addr);
+        }
+
+        Self {
+            network,
+            pinned,
+            network_events,
+            interval: clock.interval(CHECK_INTERVAL),
+            clock,
+            metrics: PinnedPeerMetricsWrapper::new()
+        }
+    }
+}
+
+// the network is never pinned
+impl<N> Unpin for PinnedPeersTask<N> {}
+
+impl<N: Peers> Future for PinnedPeersTask<N> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        while let Poll::Ready(event) = this.network_events.poll_next_unpin(cx) {
+            match event {
+                Some(StromNetworkEvent::SessionEstablished { peer_id, .. }) => {
+                    this.pinned.connected(peer_id)
+                }
+                Some(StromNetworkEvent::SessionClosed { peer_id, .. }) => {
+                    this.pinned.disconnected(peer_id, this.clock.now())
+                }
+                Some(_) => {}
+                None => return Poll::Ready(())
+            }
+        }
+
+        while this.interval.poll_tick(cx).is_ready() {
+            let now = this.clock.now();
+            for (peer_id, addr) in this.pinned.due(now) {
+                tracing::debug!(?peer_id, %addr, "dialing pinned peer");
+                this.metrics.dialed();
+                this.network.connect_peer(peer_id, addr);
+            }
+            this.metrics.unreachable(this.pinned.check_unreachable(now));
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pinned(now: Instant) -> (PinnedPeers, PeerId) {
+        let peer = PinnedPeer { id: PeerId::random(), addr: "127.0.0.1:30303".parse().unwrap() };
+        let config = PinnedPeersConfig::new(vec![peer]);
+        (PinnedPeers::new(config, now), peer.id)
+    }
+
+    #[test]
+    fn backs_off_exponentially_until_connected() {
+        let now = Instant::now();
+        let (mut pinned, peer) = pinned(now);
+        let secs = |secs| now + Duration::from_secs(secs);
+
+        assert_eq!(pinned.due(now).len(), 1);
+        assert!(pinned.due(now).is_empty());
+        assert_eq!(pinned.due(secs(1)).len(), 1);
+        assert!(pinned.due(secs(2)).is_empty());
+        assert_eq!(pinned.due(secs(3)).len(), 1);
+
+        pinned.connected(peer);
+        assert!(pinned.due(secs(100)).is_empty());
+
+        // a dropped peer is redialed right away, with the backoff reset
+        pinned.disconnected(peer, secs(100));
+        assert_eq!(pinned.due(secs(100)).len(), 1);
+        assert_eq!(pinned.due(secs(101)).len(), 1);
+    }
+
+    #[test]
+    fn alerts_on_peers_unreachable_past_the_threshold() {
+        let now = Instant::now();
+        let (mut pinned, peer) = pinned(now);
+
+        assert_eq!(pinned.check_unreachable(now + Duration::from_secs(120)), 0);
+        assert_eq!(pinned.check_unreachable(now + Duration::from_secs(121)), 1);
+
+        pinned.connected(peer);
+        assert_eq!(pinned.check_unreachable(now + Duration::from_secs(200)), 0);
+    }
+
+    #[test]
+    fn parses_enodes() {
+        let id = PeerId::random();
+        let peer: PinnedPeer = format!("enode://{id:x}@10.3.58.6:30303").parse().unwrap();
+        assert_eq!(peer, PinnedPeer { id, addr: "10.3.58.6:30303".parse().unwrap() });
+    }
+}
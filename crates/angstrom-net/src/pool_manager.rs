@@ -1,24 +1,31 @@
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap},
     num::NonZeroUsize,
     pin::Pin,
     sync::Arc,
-    task::{Context, Poll, Waker}
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant}
 };
 
 use alloy::primitives::{Address, FixedBytes, B256};
 use angstrom_eth::manager::EthEvent;
+use angstrom_metrics::PropagationMetricsWrapper;
 use angstrom_types::{
     block_sync::BlockSyncConsumer,
-    orders::{CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus},
+    orders::{
+        CancelAllOrdersRequest, CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus,
+        PoolAnalytics, PricePeg
+    },
     primitive::{NewInitializedPool, OrderPoolNewOrderResult, PeerId, PoolId},
     sol_bindings::grouped_orders::AllOrders
 };
+use angstrom_utils::clock::{Clock, Interval};
 use futures::{Future, FutureExt, StreamExt};
 use order_pool::{
     order_storage::OrderStorage, OrderIndexer, OrderPoolHandle, PoolConfig, PoolInnerEvent,
     PoolManagerUpdate
 };
+use rand::seq::SliceRandom;
 use reth_metrics::common::mpsc::UnboundedMeteredReceiver;
 use reth_tasks::TaskSpawner;
 use tokio::sync::{
@@ -30,13 +37,50 @@ use validation::order::{
     state::pools::AngstromPoolsTracker, OrderValidationResults, OrderValidatorHandle
 };
 
-use crate::{LruCache, NetworkOrderEvent, StromMessage, StromNetworkEvent, StromNetworkHandle};
+use crate::{
+    LruCache, MirroredOrderFlow, NetworkOrderEvent, PropagationTrace, StromMessage, StromMessageID,
+    StromNetworkEvent, StromNetworkHandle, StromVersion, TracedOrder
+};
 
 const MODULE_NAME: &str = "Order Pool";
 
 /// Cache limit of transactions to keep track of for a single peer.
 const PEER_ORDER_CACHE_LIMIT: usize = 1024 * 10;
 
+/// Most orders a peer can pull with a single request.
+const MAX_PULLED_ORDERS: usize = 256;
+
+/// How long a peer has to send the orders we pulled from it before they are
+/// pulled from another peer that announced them.
+const PULL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Most peers kept to pull an announced order from once the first one didn't
+/// send it.
+const MAX_PULL_FALLBACKS: usize = 4;
+
+/// How new orders are pushed to the peers that haven't seen them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GossipMode {
+    /// every peer is sent the full order
+    #[default]
+    Eager,
+    /// `fanout` random peers are sent the full order, the rest only its hash.
+    /// They pull it if they don't have it by then, on a well connected mesh
+    /// nearly all of them do
+    Lazy { fanout: usize }
+}
+
+/// Mirroring of the accepted order flow between a primary and its hot
+/// standby, over their authenticated strom session. Pin the two nodes to each
+/// other so the session is kept up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderMirrorConfig {
+    /// standby every accepted order and cancellation is mirrored to
+    pub standby: Option<PeerId>,
+    /// primary whose mirrored order flow is taken
+    pub primary: Option<PeerId>
+}
+
 /// Api to interact with [`PoolManager`] task.
 #[derive(Debug, Clone)]
 pub struct PoolHandle {
@@ -48,16 +92,33 @@ pub struct PoolHandle {
 pub enum OrderCommand {
     // new orders
     NewOrder(OrderOrigin, AllOrders, tokio::sync::oneshot::Sender<OrderValidationResults>),
+    NewPeggedOrder(AllOrders, PricePeg, tokio::sync::oneshot::Sender<OrderValidationResults>),
+    /// the tranches of an iceberg order, with a result sender per tranche
+    NewIcebergOrder(Vec<AllOrders>, Vec<tokio::sync::oneshot::Sender<OrderValidationResults>>),
     CancelOrder(CancelOrderRequest, tokio::sync::oneshot::Sender<bool>),
+    /// cancels every order of the user, responds with the cancelled order
+    /// hashes or [`None`] if the request wasn't valid
+    CancelAllOrders(CancelAllOrdersRequest, tokio::sync::oneshot::Sender<Option<Vec<B256>>>),
     PendingOrders(Address, tokio::sync::oneshot::Sender<Vec<AllOrders>>),
     OrdersByPool(FixedBytes<32>, OrderLocation, tokio::sync::oneshot::Sender<Vec<AllOrders>>),
-    OrderStatus(B256, tokio::sync::oneshot::Sender<Option<OrderStatus>>)
+    OrderStatus(B256, tokio::sync::oneshot::Sender<Option<OrderStatus>>),
+    /// analytics of the pool, with its pending limit orders split into the
+    /// given amount of price buckets
+    PoolAnalytics(PoolId, usize, tokio::sync::oneshot::Sender<PoolAnalytics>),
+    /// stops or resumes taking new orders from the rpc and the network
+    PauseIntake(bool)
 }
 
 impl PoolHandle {
     fn send(&self, cmd: OrderCommand) -> Result<(), SendError<OrderCommand>> {
         self.manager_tx.send(cmd)
     }
+
+    /// While paused, new orders are rejected. Cancellations and the orders
+    /// already in the pool are unaffected.
+    pub fn pause_order_intake(&self, paused: bool) {
+        let _ = self.send(OrderCommand::PauseIntake(paused));
+    }
 }
 
 impl OrderPoolHandle for PoolHandle {
@@ -71,6 +132,28 @@ impl OrderPoolHandle for PoolHandle {
         rx.map(Into::into)
     }
 
+    fn new_pegged_order(
+        &self,
+        order: AllOrders,
+        peg: PricePeg
+    ) -> impl Future<Output = OrderPoolNewOrderResult> + Send {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let _ = self.send(OrderCommand::NewPeggedOrder(order, peg, tx));
+        rx.map(Into::into)
+    }
+
+    fn new_iceberg_order(
+        &self,
+        tranches: Vec<AllOrders>
+    ) -> impl Future<Output = Vec<OrderPoolNewOrderResult>> + Send {
+        let (txs, rxs): (Vec<_>, Vec<_>) = tranches
+            .iter()
+            .map(|_| tokio::sync::oneshot::channel())
+            .unzip();
+        let _ = self.send(OrderCommand::NewIcebergOrder(tranches, txs));
+        futures::future::join_all(rxs.into_iter().map(|rx| rx.map(Into::into)))
+    }
+
     fn subscribe_orders(&self) -> BroadcastStream<PoolManagerUpdate> {
         BroadcastStream::new(self.pool_manager_tx.subscribe())
     }
@@ -101,6 +184,17 @@ impl OrderPoolHandle for PoolHandle {
         rx.map(|v| v.ok().flatten())
     }
 
+    fn fetch_pool_analytics(
+        &self,
+        pool_id: PoolId,
+        price_buckets: usize
+    ) -> impl Future<Output = PoolAnalytics> + Send {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let _ = self.send(OrderCommand::PoolAnalytics(pool_id, price_buckets, tx));
+
+        rx.map(|v| v.unwrap_or_default())
+    }
+
     fn pending_orders(&self, sender: Address) -> impl Future<Output = Vec<AllOrders>> + Send {
         let (tx, rx) = tokio::sync::oneshot::channel();
         let _ = self.send(OrderCommand::PendingOrders(sender, tx)).is_ok();
@@ -112,6 +206,15 @@ impl OrderPoolHandle for PoolHandle {
         let _ = self.send(OrderCommand::CancelOrder(req, tx));
         rx.map(|res| res.unwrap_or(false))
     }
+
+    fn cancel_all_orders(
+        &self,
+        req: CancelAllOrdersRequest
+    ) -> impl Future<Output = Option<Vec<B256>>> + Send {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let _ = self.send(OrderCommand::CancelAllOrders(req, tx));
+        rx.map(|res| res.ok().flatten())
+    }
 }
 
 pub struct PoolManagerBuilder<V, GlobalSync>
@@ -126,7 +229,11 @@ where
     strom_network_events: UnboundedReceiverStream<StromNetworkEvent>,
     eth_network_events:   UnboundedReceiverStream<EthEvent>,
     order_events:         UnboundedMeteredReceiver<NetworkOrderEvent>,
-    config:               PoolConfig
+    config:               PoolConfig,
+    clock:                Clock,
+    propagation_tracing:  bool,
+    gossip:               GossipMode,
+    mirror:               OrderMirrorConfig
 }
 
 impl<V, GlobalSync> PoolManagerBuilder<V, GlobalSync>
@@ -150,7 +257,11 @@ where
             network_handle,
             validator,
             order_storage,
-            config: Default::default()
+            config: Default::default(),
+            clock: Clock::system(),
+            propagation_tracing: false,
+            gossip: GossipMode::default(),
+            mirror: OrderMirrorConfig::default()
         }
     }
 
@@ -159,6 +270,29 @@ where
         self
     }
 
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Propagates orders with a [`PropagationTrace`] and records how long the
+    /// traced orders of other nodes took to arrive. Tells peers which orders
+    /// entered the network here, so it's off by default.
+    pub fn with_propagation_tracing(mut self, enabled: bool) -> Self {
+        self.propagation_tracing = enabled;
+        self
+    }
+
+    pub fn with_gossip_mode(mut self, gossip: GossipMode) -> Self {
+        self.gossip = gossip;
+        self
+    }
+
+    pub fn with_order_mirror(mut self, mirror: OrderMirrorConfig) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
     pub fn with_storage(mut self, order_storage: Arc<OrderStorage>) -> Self {
         let _ = self.order_storage.insert(order_storage);
         self
@@ -172,36 +306,60 @@ where
         pool_storage: AngstromPoolsTracker,
         pool_manager_tx: tokio::sync::broadcast::Sender<PoolManagerUpdate>
     ) -> PoolHandle {
+        let handle =
+            PoolHandle { manager_tx: tx.clone(), pool_manager_tx: pool_manager_tx.clone() };
+        task_spawner.spawn_critical(
+            "transaction manager",
+            Box::pin(self.into_manager(rx, pool_storage, pool_manager_tx))
+        );
+
+        handle
+    }
+
+    fn into_manager(
+        self,
+        rx: UnboundedReceiver<OrderCommand>,
+        pool_storage: AngstromPoolsTracker,
+        pool_manager_tx: tokio::sync::broadcast::Sender<PoolManagerUpdate>
+    ) -> PoolManager<V, GlobalSync> {
         let rx = UnboundedReceiverStream::new(rx);
         let order_storage = self
             .order_storage
             .unwrap_or_else(|| Arc::new(OrderStorage::new(&self.config)));
-        let handle =
-            PoolHandle { manager_tx: tx.clone(), pool_manager_tx: pool_manager_tx.clone() };
         let inner = OrderIndexer::new(
             self.validator.clone(),
             order_storage.clone(),
             0,
             pool_manager_tx.clone(),
             pool_storage
+        )
+        .with_clock(self.clock.clone())
+        .with_filled_orders_capacity(self.config.filled_orders_capacity)
+        .with_settled_orders(
+            self.config.settled_orders_path.clone(),
+            self.config.filled_orders_ttl
         );
         self.global_sync.register(MODULE_NAME);
 
-        task_spawner.spawn_critical(
-            "transaction manager",
-            Box::pin(PoolManager {
-                eth_network_events:   self.eth_network_events,
-                strom_network_events: self.strom_network_events,
-                order_events:         self.order_events,
-                peer_to_info:         HashMap::default(),
-                order_indexer:        inner,
-                network:              self.network_handle,
-                command_rx:           rx,
-                global_sync:          self.global_sync
-            })
-        );
-
-        handle
+        PoolManager {
+            eth_network_events:   self.eth_network_events,
+            strom_network_events: self.strom_network_events,
+            order_events:         self.order_events,
+            peer_to_info:         HashMap::default(),
+            intake_paused:        false,
+            tracer:               self
+                .propagation_tracing
+                .then(|| PropagationTracer::new(self.clock.clone())),
+            gossip:               self.gossip,
+            pulls:                OrderPulls::new(self.clock.clone()),
+            pull_retries:         self.clock.interval(PULL_TIMEOUT),
+            mirror:               self.mirror,
+            filled_orders_gc:     self.clock.interval(self.config.filled_orders_gc_interval),
+            order_indexer:        inner,
+            network:              self.network_handle,
+            command_rx:           rx,
+            global_sync:          self.global_sync
+        }
     }
 
     pub fn build<TP: TaskSpawner>(
@@ -223,6 +381,12 @@ where
             0,
             pool_manager_tx.clone(),
             pool_storage
+        )
+        .with_clock(self.clock.clone())
+        .with_filled_orders_capacity(self.config.filled_orders_capacity)
+        .with_settled_orders(
+            self.config.settled_orders_path.clone(),
+            self.config.filled_orders_ttl
         );
 
         task_spawner.spawn_critical(
@@ -232,6 +396,15 @@ where
                 strom_network_events: self.strom_network_events,
                 order_events:         self.order_events,
                 peer_to_info:         HashMap::default(),
+                intake_paused:        false,
+                tracer:               self
+                    .propagation_tracing
+                    .then(|| PropagationTracer::new(self.clock.clone())),
+                gossip:               self.gossip,
+                pulls:                OrderPulls::new(self.clock.clone()),
+                pull_retries:         self.clock.interval(PULL_TIMEOUT),
+                mirror:               self.mirror,
+                filled_orders_gc:     self.clock.interval(self.config.filled_orders_gc_interval),
                 order_indexer:        inner,
                 network:              self.network_handle,
                 command_rx:           rx,
@@ -265,7 +438,19 @@ where
     /// Incoming events from the ProtocolManager.
     order_events:         UnboundedMeteredReceiver<NetworkOrderEvent>,
     /// All the connected peers.
-    peer_to_info:         HashMap<PeerId, StromPeer>
+    peer_to_info:         HashMap<PeerId, StromPeer>,
+    /// set by the operator to stop taking new orders
+    intake_paused:        bool,
+    /// set if the operator opted into propagation tracing
+    tracer:               Option<PropagationTracer>,
+    gossip:               GossipMode,
+    /// announced orders that are being pulled from a peer
+    pulls:                OrderPulls,
+    /// pulls the orders again whose peer didn't send them in time
+    pull_retries:         Interval,
+    mirror:               OrderMirrorConfig,
+    /// forgets the filled orders whose ttl passed between blocks
+    filled_orders_gc:     Interval
 }
 
 impl<V, GlobalSync> PoolManager<V, GlobalSync>
@@ -275,16 +460,46 @@ where
 {
     fn on_command(&mut self, cmd: OrderCommand) {
         match cmd {
-            OrderCommand::NewOrder(_, order, validation_response) => self
+            OrderCommand::NewOrder(_, order, validation_response)
+            | OrderCommand::NewPeggedOrder(order, _, validation_response)
+                if self.intake_paused =>
+            {
+                let _ =
+                    validation_response.send(OrderValidationResults::Invalid(order.order_hash()));
+            }
+            OrderCommand::NewIcebergOrder(tranches, validation_responses) if self.intake_paused => {
+                tranches.iter().zip(validation_responses).for_each(
+                    |(tranche, validation_response)| {
+                        let _ = validation_response
+                            .send(OrderValidationResults::Invalid(tranche.order_hash()));
+                    }
+                );
+            }
+            OrderCommand::NewOrder(origin, order, validation_response) => self
                 .order_indexer
-                .new_rpc_order(OrderOrigin::External, order, validation_response),
+                .new_rpc_order(origin, order, validation_response),
+            OrderCommand::NewPeggedOrder(order, peg, validation_response) => self
+                .order_indexer
+                .new_pegged_rpc_order(order, peg, validation_response),
+            OrderCommand::NewIcebergOrder(tranches, validation_responses) => self
+                .order_indexer
+                .new_iceberg_rpc_order(tranches, validation_responses),
             OrderCommand::CancelOrder(req, receiver) => {
                 let res = self.order_indexer.cancel_order(&req);
                 if res {
+                    self.mirror_to_standby(MirroredOrderFlow::Cancel(req.clone()));
                     self.broadcast_cancel_to_peers(req);
                 }
                 let _ = receiver.send(res);
             }
+            OrderCommand::CancelAllOrders(req, receiver) => {
+                let res = self.order_indexer.cancel_all_orders(&req);
+                if res.is_some() {
+                    self.mirror_to_standby(MirroredOrderFlow::CancelAll(req.clone()));
+                    self.broadcast_cancel_all_to_peers(req);
+                }
+                let _ = receiver.send(res);
+            }
             OrderCommand::PendingOrders(from, receiver) => {
                 let res = self.order_indexer.pending_orders_for_address(from);
                 let _ = receiver.send(res.into_iter().map(|o| o.order).collect());
@@ -298,12 +513,22 @@ where
                 let res = self.order_indexer.orders_by_pool(pool_id, location);
                 let _ = tx.send(res);
             }
+            OrderCommand::PoolAnalytics(pool_id, price_buckets, tx) => {
+                let _ = tx.send(self.order_indexer.pool_analytics(pool_id, price_buckets));
+            }
+            OrderCommand::PauseIntake(paused) => {
+                tracing::info!(paused, "order intake");
+                self.intake_paused = paused;
+            }
         }
     }
 
     fn on_eth_event(&mut self, eth: EthEvent, waker: Waker) {
         match eth {
             EthEvent::NewBlockTransitions { block_number, filled_orders, address_changeset } => {
+                if let Some(tracer) = self.tracer.as_mut() {
+                    tracer.new_block();
+                }
                 self.order_indexer.start_new_block_processing(
                     block_number,
                     filled_orders,
@@ -331,71 +556,238 @@ where
             EthEvent::RemovedPool { pool } => {
                 self.order_indexer.remove_pool(pool.into());
             }
+            EthEvent::PoolParamsUpdated { pool, previous } => {
+                let (id, previous_id): (PoolId, PoolId) = (pool.clone().into(), previous.into());
+                tracing::info!(?previous_id, ?id, "pool parameters changed");
+
+                // orders signed for the previous pool can't settle anymore, the
+                // uniswap pool itself is moved onto the new key by the node
+                self.order_indexer.remove_pool(previous_id);
+                self.order_indexer.new_pool(NewInitializedPool {
+                    currency_in: pool.currency0,
+                    currency_out: pool.currency1,
+                    id
+                });
+            }
             EthEvent::AddedNode(_) => {}
             EthEvent::RemovedNode(_) => {}
             EthEvent::NewBlock(_) => {}
+            EthEvent::ForkIdChanged { block_number, fork_id } => {
+                let dropped = self.order_indexer.invalidate_all_orders();
+                tracing::warn!(
+                    block_number,
+                    ?fork_id,
+                    dropped = dropped.len(),
+                    "fork id changed, dropped all resting orders"
+                );
+            }
         }
     }
 
     fn on_network_order_event(&mut self, event: NetworkOrderEvent) {
         match event {
-            NetworkOrderEvent::IncomingOrders { peer_id, orders } => {
-                orders.into_iter().for_each(|order| {
-                    self.peer_to_info
-                        .get_mut(&peer_id)
-                        .map(|peer| peer.orders.insert(order.order_hash()));
-
-                    self.order_indexer.new_network_order(
-                        peer_id,
-                        OrderOrigin::External,
-                        order.clone()
-                    );
-                });
+            NetworkOrderEvent::IncomingOrders { peer_id, orders } => self.on_incoming_orders(
+                peer_id,
+                orders.into_iter().map(|order| (order, None)).collect()
+            ),
+            NetworkOrderEvent::IncomingTracedOrders { peer_id, orders } => self.on_incoming_orders(
+                peer_id,
+                orders
+                    .into_iter()
+                    .map(|(order, trace)| (order, Some(trace)))
+                    .collect()
+            ),
+            NetworkOrderEvent::OrderAnnouncements { peer_id, hashes } => {
+                self.on_order_announcements(peer_id, hashes)
+            }
+            NetworkOrderEvent::OrderRequest { peer_id, hashes } => {
+                self.on_order_request(peer_id, hashes)
             }
             NetworkOrderEvent::CancelOrder { request, .. } => {
                 let res = self.order_indexer.cancel_order(&request);
                 if res {
+                    self.mirror_to_standby(MirroredOrderFlow::Cancel(request.clone()));
                     self.broadcast_cancel_to_peers(request);
                 }
             }
+            NetworkOrderEvent::CancelAllOrders { peer_id, request } => {
+                if self.order_indexer.cancel_all_orders(&request).is_some() {
+                    self.mirror_to_standby(MirroredOrderFlow::CancelAll(request.clone()));
+                    // the sender already has it
+                    if let Some(peer) = self.peer_to_info.get_mut(&peer_id) {
+                        peer.cancellations.insert(request.request_hash());
+                    }
+                    self.broadcast_cancel_all_to_peers(request);
+                }
+            }
+            NetworkOrderEvent::CarriedOverOrders { orders } => {
+                if self.intake_paused {
+                    return
+                }
+                for order in orders {
+                    self.order_indexer.new_carried_over_order(order);
+                }
+            }
+            NetworkOrderEvent::MirroredOrderFlow { peer_id, flow } => {
+                self.on_mirrored_order_flow(peer_id, flow)
+            }
+        }
+    }
+
+    /// Takes the order flow of our primary as if it was gossiped to us.
+    fn on_mirrored_order_flow(&mut self, peer_id: PeerId, flow: MirroredOrderFlow) {
+        if self.mirror.primary != Some(peer_id) {
+            tracing::debug!(
+                ?peer_id,
+                "dropping order flow mirrored by a peer that isn't our primary"
+            );
+            self.network
+                .peer_reputation_change(peer_id, crate::ReputationChangeKind::BadMessage);
+            return
+        }
+
+        let event = match flow {
+            MirroredOrderFlow::Orders(envelopes) => NetworkOrderEvent::IncomingOrders {
+                peer_id,
+                orders: envelopes
+                    .iter()
+                    .filter_map(|envelope| {
+                        envelope
+                            .open()
+                            .inspect_err(
+                                |error| tracing::debug!(?peer_id, %error, "dropping mirrored order")
+                            )
+                            .ok()
+                    })
+                    .collect()
+            },
+            MirroredOrderFlow::Cancel(request) => {
+                NetworkOrderEvent::CancelOrder { peer_id, request }
+            }
+            MirroredOrderFlow::CancelAll(request) => {
+                NetworkOrderEvent::CancelAllOrders { peer_id, request }
+            }
+        };
+        self.on_network_order_event(event);
+    }
+
+    /// Sends `flow` to our standby, whether or not it heard of it through
+    /// gossip already.
+    fn mirror_to_standby(&self, flow: MirroredOrderFlow) {
+        if let Some(standby) = self.mirror.standby {
+            self.network
+                .send_message(standby, StromMessage::MirrorOrderFlow(flow));
+        }
+    }
+
+    fn on_incoming_orders(
+        &mut self,
+        peer_id: PeerId,
+        orders: Vec<(AllOrders, Option<PropagationTrace>)>
+    ) {
+        if self.intake_paused {
+            tracing::trace!(?peer_id, orders = orders.len(), "intake paused, dropping orders");
+            return
+        }
+
+        for (order, trace) in orders {
+            let order_hash = order.order_hash();
+            self.pulls.arrived(&order_hash);
+            self.peer_to_info
+                .get_mut(&peer_id)
+                .map(|peer| peer.orders.insert(order_hash));
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.arrived(order_hash, trace);
+            }
+
+            self.order_indexer
+                .new_network_order(peer_id, OrderOrigin::External, order);
+        }
+    }
+
+    /// Pulls the announced orders that are new to us. Orders that are already
+    /// being pulled from another peer are pulled from this one if that peer
+    /// doesn't send them.
+    fn on_order_announcements(&mut self, peer_id: PeerId, hashes: Vec<B256>) {
+        if self.intake_paused {
+            return
+        }
+
+        let mut peer = self.peer_to_info.get_mut(&peer_id);
+        let missing = hashes
+            .into_iter()
+            .take(MAX_PULLED_ORDERS)
+            .inspect(|hash| {
+                if let Some(peer) = peer.as_mut() {
+                    peer.orders.insert(*hash);
+                }
+            })
+            .filter(|hash| {
+                !self.order_indexer.knows_order(hash) && self.pulls.announced(*hash, peer_id)
+            })
+            .collect::<Vec<_>>();
+
+        self.pull_orders(HashMap::from([(peer_id, missing)]));
+    }
+
+    fn pull_orders(&mut self, pulls: HashMap<PeerId, Vec<B256>>) {
+        for (peer_id, mut hashes) in pulls {
+            // orders can still reach us pushed by a peer or over the rpc
+            hashes.retain(|hash| !self.order_indexer.knows_order(hash));
+            if hashes.is_empty() {
+                continue
+            }
+
+            tracing::trace!(?peer_id, orders = hashes.len(), "pulling announced orders");
+            for chunk in hashes.chunks(MAX_PULLED_ORDERS) {
+                self.network
+                    .send_message(peer_id, StromMessage::RequestOrders(chunk.to_vec()));
+            }
         }
     }
 
+    fn on_order_request(&mut self, peer_id: PeerId, hashes: Vec<B256>) {
+        let orders = hashes
+            .into_iter()
+            .take(MAX_PULLED_ORDERS)
+            .filter_map(|hash| self.order_indexer.order_by_hash(hash))
+            .collect::<Vec<_>>();
+        if orders.is_empty() {
+            return
+        }
+
+        if let Some(peer) = self.peer_to_info.get_mut(&peer_id) {
+            orders.iter().for_each(|order| {
+                peer.orders.insert(order.order_hash());
+            });
+        }
+        self.network.send_message(
+            peer_id,
+            StromMessage::PropagateVersionedOrders(orders.iter().map(Into::into).collect())
+        );
+    }
+
     fn on_network_event(&mut self, event: StromNetworkEvent) {
         match event {
-            StromNetworkEvent::SessionEstablished { peer_id } => {
+            StromNetworkEvent::SessionEstablished { peer_id, version } => {
                 // insert a new peer into the peerset
-                self.peer_to_info.insert(
-                    peer_id,
-                    StromPeer {
-                        orders:        LruCache::new(
-                            NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()
-                        ),
-                        cancellations: LruCache::new(
-                            NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()
-                        )
-                    }
-                );
+                self.peer_to_info.insert(peer_id, StromPeer::new(version));
             }
             StromNetworkEvent::SessionClosed { peer_id, .. } => {
                 // remove the peer
                 self.peer_to_info.remove(&peer_id);
+                let pulls = self.pulls.peer_left(peer_id);
+                self.pull_orders(pulls);
             }
             StromNetworkEvent::PeerRemoved(peer_id) => {
                 self.peer_to_info.remove(&peer_id);
+                let pulls = self.pulls.peer_left(peer_id);
+                self.pull_orders(pulls);
             }
             StromNetworkEvent::PeerAdded(peer_id) => {
-                self.peer_to_info.insert(
-                    peer_id,
-                    StromPeer {
-                        orders:        LruCache::new(
-                            NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()
-                        ),
-                        cancellations: LruCache::new(
-                            NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()
-                        )
-                    }
-                );
+                // the version is only known once the session is established
+                self.peer_to_info
+                    .insert(peer_id, StromPeer::new(StromVersion::Strom1));
             }
         }
     }
@@ -423,6 +815,11 @@ where
             })
             .collect::<Vec<_>>();
 
+        if !valid_orders.is_empty() {
+            self.mirror_to_standby(MirroredOrderFlow::Orders(
+                valid_orders.iter().map(Into::into).collect()
+            ));
+        }
         self.broadcast_orders_to_peers(valid_orders);
     }
 
@@ -438,19 +835,61 @@ where
         }
     }
 
+    fn broadcast_cancel_all_to_peers(&mut self, cancel: CancelAllOrdersRequest) {
+        let request_hash = cancel.request_hash();
+        for (peer_id, info) in self.peer_to_info.iter_mut() {
+            if !info.cancellations.contains(&request_hash) {
+                self.network
+                    .send_message(*peer_id, StromMessage::OrderCancelAll(cancel.clone()));
+
+                info.cancellations.insert(request_hash);
+            }
+        }
+    }
+
     fn broadcast_orders_to_peers(&mut self, valid_orders: Vec<AllOrders>) {
+        let mut announcements = HashMap::<PeerId, Vec<B256>>::new();
         for order in valid_orders.iter() {
-            for (peer_id, info) in self.peer_to_info.iter_mut() {
-                let order_hash = order.order_hash();
-                if !info.orders.contains(&order_hash) {
-                    self.network.send_message(
-                        *peer_id,
-                        StromMessage::PropagatePooledOrders(vec![order.clone()])
-                    );
-                    info.orders.insert(order_hash);
+            let order_hash = order.order_hash();
+            let message = match self
+                .tracer
+                .as_mut()
+                .and_then(|tracer| tracer.take(order_hash))
+            {
+                Some(trace) => StromMessage::PropagateTracedOrders(vec![TracedOrder {
+                    order: order.into(),
+                    trace
+                }]),
+                None => StromMessage::PropagateVersionedOrders(vec![order.into()])
+            };
+
+            let mut peers = self
+                .peer_to_info
+                .iter_mut()
+                .filter(|(_, info)| !info.orders.contains(&order_hash))
+                .collect::<Vec<_>>();
+            let eager = match self.gossip {
+                GossipMode::Eager => peers.len(),
+                GossipMode::Lazy { fanout } => {
+                    peers.shuffle(&mut rand::thread_rng());
+                    fanout
+                }
+            };
+            for (idx, (peer_id, info)) in peers.into_iter().enumerate() {
+                // peers that can't pull announced orders are always pushed them
+                if idx < eager || !info.version.exchanges(StromMessageID::AnnounceOrders) {
+                    self.network.send_message(*peer_id, message.clone());
+                } else {
+                    announcements.entry(*peer_id).or_default().push(order_hash);
                 }
+                info.orders.insert(order_hash);
             }
         }
+
+        for (peer_id, hashes) in announcements {
+            self.network
+                .send_message(peer_id, StromMessage::AnnounceOrders(hashes));
+        }
     }
 }
 
@@ -471,34 +910,60 @@ where
                 cx.waker().wake_by_ref();
                 break;
             }
+            // the manager is only woken again by what it's waiting on once
+            // nothing is left to take, so a logical clock can move on
+            let mut progress = false;
+
             // pull all eth events
             while let Poll::Ready(Some(eth)) = this.eth_network_events.poll_next_unpin(cx) {
+                progress = true;
                 this.on_eth_event(eth, cx.waker().clone());
             }
 
             // drain network/peer related events
             while let Poll::Ready(Some(event)) = this.strom_network_events.poll_next_unpin(cx) {
+                progress = true;
                 this.on_network_event(event);
             }
 
+            if this.filled_orders_gc.poll_tick(cx).is_ready() {
+                progress = true;
+                this.order_indexer.collect_filled_orders();
+            }
+
+            if let Poll::Ready(now) = this.pull_retries.poll_tick(cx) {
+                progress = true;
+                let pulls = this.pulls.expire(now);
+                this.pull_orders(pulls);
+            }
+
             // poll underlying pool. This is the validation process that's being polled
             while let Poll::Ready(Some(orders)) = this.order_indexer.poll_next_unpin(cx) {
+                progress = true;
                 this.on_pool_events(orders, || cx.waker().clone());
             }
 
-            // halt dealing with these till we have synced
-            if this.global_sync.can_operate() {
-                // drain commands
-                while let Poll::Ready(Some(cmd)) = this.command_rx.poll_next_unpin(cx) {
-                    this.on_command(cmd);
-                    cx.waker().wake_by_ref();
-                }
+            // halt dealing with these till we have synced, nothing wakes us once
+            // we have
+            if !this.global_sync.can_operate() {
+                cx.waker().wake_by_ref();
+                break;
+            }
 
-                // drain incoming transaction events
-                while let Poll::Ready(Some(event)) = this.order_events.poll_next_unpin(cx) {
-                    this.on_network_order_event(event);
-                    cx.waker().wake_by_ref();
-                }
+            // drain commands
+            while let Poll::Ready(Some(cmd)) = this.command_rx.poll_next_unpin(cx) {
+                progress = true;
+                this.on_command(cmd);
+            }
+
+            // drain incoming transaction events
+            while let Poll::Ready(Some(event)) = this.order_events.poll_next_unpin(cx) {
+                progress = true;
+                this.on_network_order_event(event);
+            }
+
+            if !progress {
+                break;
             }
         }
 
@@ -516,10 +981,359 @@ pub enum NetworkTransactionEvent {
     IncomingOrders { peer_id: PeerId, msg: Vec<AllOrders> }
 }
 
+/// Traces of the network orders that are being validated, these are
+/// propagated once they are valid.
+struct PropagationTracer {
+    clock:   Clock,
+    /// the trace every order arrived with first, none for orders of peers that
+    /// don't trace
+    pending: HashMap<B256, Option<PropagationTrace>>,
+    metrics: PropagationMetricsWrapper
+}
+
+impl PropagationTracer {
+    fn new(clock: Clock) -> Self {
+        Self { clock, pending: HashMap::default(), metrics: PropagationMetricsWrapper::new() }
+    }
+
+    fn arrived(&mut self, order_hash: B256, trace: Option<PropagationTrace>) {
+        if let Some(trace) = trace {
+            let delay = trace.delay(self.clock.unix_now());
+            // the sender relayed it once more
+            self.metrics.arrived(trace.hops.saturating_add(1), delay);
+        }
+        self.pending.entry(order_hash).or_insert(trace);
+    }
+
+    /// The trace to propagate the order with. Orders that didn't arrive over
+    /// the network entered it here.
+    fn take(&mut self, order_hash: B256) -> Option<PropagationTrace> {
+        match self.pending.remove(&order_hash) {
+            Some(trace) => trace.and_then(|trace| trace.relayed()),
+            None => Some(PropagationTrace::new(self.clock.unix_now()))
+        }
+    }
+
+    /// Orders are validated well within a block, whatever is left was invalid.
+    fn new_block(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Announced orders that are being pulled, with the other peers that announced
+/// them in case the peer they are pulled from doesn't send them.
+struct OrderPulls {
+    clock:   Clock,
+    pending: HashMap<B256, Pull>
+}
+
+struct Pull {
+    peer:         PeerId,
+    requested_at: Instant,
+    /// peers to pull the order from next, in the order they announced it
+    fallbacks:    Vec<PeerId>
+}
+
+impl OrderPulls {
+    fn new(clock: Clock) -> Self {
+        Self { clock, pending: HashMap::default() }
+    }
+
+    /// Whether to pull the order from the peer that announced it. Orders that
+    /// are already being pulled keep the peer as a fallback instead.
+    fn announced(&mut self, order_hash: B256, peer_id: PeerId) -> bool {
+        if self.pending.len() >= PEER_ORDER_CACHE_LIMIT && !self.pending.contains_key(&order_hash) {
+            return false
+        }
+
+        match self.pending.entry(order_hash) {
+            Entry::Occupied(mut entry) => {
+                let pull = entry.get_mut();
+                if pull.peer != peer_id
+                    && !pull.fallbacks.contains(&peer_id)
+                    && pull.fallbacks.len() < MAX_PULL_FALLBACKS
+                {
+                    pull.fallbacks.push(peer_id);
+                }
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Pull {
+                    peer:         peer_id,
+                    requested_at: self.clock.now(),
+                    fallbacks:    vec![]
+                });
+                true
+            }
+        }
+    }
+
+    fn arrived(&mut self, order_hash: &B256) {
+        self.pending.remove(order_hash);
+    }
+
+    /// The orders to pull again, by the peer to pull them from, as their peer
+    /// didn't send them in time.
+    fn expire(&mut self, now: Instant) -> HashMap<PeerId, Vec<B256>> {
+        self.retry(now, |pull| now.saturating_duration_since(pull.requested_at) >= PULL_TIMEOUT)
+    }
+
+    /// The orders to pull again, by the peer to pull them from, as the peer
+    /// they were pulled from left.
+    fn peer_left(&mut self, peer_id: PeerId) -> HashMap<PeerId, Vec<B256>> {
+        for pull in self.pending.values_mut() {
+            pull.fallbacks.retain(|fallback| *fallback != peer_id);
+        }
+        let now = self.clock.now();
+        self.retry(now, |pull| pull.peer == peer_id)
+    }
+
+    /// Moves the pulls that `failed` on to their next fallback, pulls without
+    /// one are given up on until the order is announced again.
+    fn retry(
+        &mut self,
+        now: Instant,
+        failed: impl Fn(&Pull) -> bool
+    ) -> HashMap<PeerId, Vec<B256>> {
+        let mut retries = HashMap::<PeerId, Vec<B256>>::new();
+        self.pending.retain(|order_hash, pull| {
+            if !failed(pull) {
+                return true
+            }
+            if pull.fallbacks.is_empty() {
+                tracing::debug!(?order_hash, "no peer sent the announced order");
+                return false
+            }
+
+            pull.peer = pull.fallbacks.remove(0);
+            pull.requested_at = now;
+            retries.entry(pull.peer).or_default().push(*order_hash);
+            true
+        });
+
+        retries
+    }
+}
+
 /// Tracks a single peer
 #[derive(Debug)]
 struct StromPeer {
     /// Keeps track of transactions that we know the peer has seen.
     orders:        LruCache<B256>,
-    cancellations: LruCache<B256>
+    cancellations: LruCache<B256>,
+    /// Version of the strom protocol of the session with the peer
+    version:       StromVersion
+}
+
+impl StromPeer {
+    fn new(version: StromVersion) -> Self {
+        Self {
+            orders: LruCache::new(NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()),
+            cancellations: LruCache::new(NonZeroUsize::new(PEER_ORDER_CACHE_LIMIT).unwrap()),
+            version
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use alloy::primitives::PrimitiveSignature;
+    use angstrom_types::{
+        block_sync::GlobalBlockSync, contract_payloads::angstrom::AngstromPoolConfigStore
+    };
+    use reth_metrics::common::mpsc::{metered_unbounded_channel, UnboundedMeteredSender};
+    use testing_tools::{mocks::validator::MockValidator, simulation::Simulation};
+
+    use super::*;
+    use crate::{ReputationChangeKind, StromNetworkHandleMsg};
+
+    /// A pool manager along with what it sends to the network and the sender
+    /// of the order events the network would feed it.
+    fn pool_manager(
+        mirror: OrderMirrorConfig,
+        clock: Clock
+    ) -> (
+        PoolManager<MockValidator, GlobalBlockSync>,
+        UnboundedReceiver<StromNetworkHandleMsg>,
+        UnboundedMeteredSender<NetworkOrderEvent>
+    ) {
+        let (network_tx, mut network_rx) = unbounded_channel();
+        let network = StromNetworkHandle::new(
+            Arc::new(AtomicUsize::new(0)),
+            UnboundedMeteredSender::new(network_tx, "test network")
+        );
+        let (_, eth_events) = unbounded_channel();
+        let (order_events_tx, order_events) = metered_unbounded_channel("test orders");
+        let (_, commands) = unbounded_channel();
+        let (pool_manager_tx, _) = broadcast::channel(100);
+        let pools =
+            AngstromPoolsTracker::new(Address::ZERO, Arc::new(AngstromPoolConfigStore::default()));
+
+        let manager = PoolManagerBuilder::new(
+            MockValidator::default(),
+            None,
+            network,
+            UnboundedReceiverStream::new(eth_events),
+            order_events,
+            GlobalBlockSync::new(0)
+        )
+        .with_order_mirror(mirror)
+        .with_clock(clock)
+        .into_manager(commands, pools, pool_manager_tx);
+        // the subscription to the network events
+        assert!(matches!(network_rx.try_recv(), Ok(StromNetworkHandleMsg::SubscribeEvents(_))));
+
+        (manager, network_rx, order_events_tx)
+    }
+
+    fn cancel_all() -> CancelAllOrdersRequest {
+        CancelAllOrdersRequest {
+            signature:    PrimitiveSignature::test_signature(),
+            user_address: Address::random(),
+            issued_at:    1,
+            valid_until:  2
+        }
+    }
+
+    #[tokio::test]
+    async fn mirrors_order_flow_to_the_standby() {
+        let standby = PeerId::random();
+        let (manager, mut network, _) = pool_manager(
+            OrderMirrorConfig { standby: Some(standby), primary: None },
+            Clock::system()
+        );
+
+        let cancel = cancel_all();
+        manager.mirror_to_standby(MirroredOrderFlow::CancelAll(cancel.clone()));
+        match network.try_recv().unwrap() {
+            StromNetworkHandleMsg::SendStromMessage {
+                peer_id,
+                msg: StromMessage::MirrorOrderFlow(MirroredOrderFlow::CancelAll(mirrored))
+            } => {
+                assert_eq!(peer_id, standby);
+                assert_eq!(mirrored, cancel);
+            }
+            msg => panic!("unexpected message {msg:?}")
+        }
+
+        // nothing is mirrored without a standby
+        let (manager, mut network, _) = pool_manager(OrderMirrorConfig::default(), Clock::system());
+        manager.mirror_to_standby(MirroredOrderFlow::CancelAll(cancel));
+        assert!(network.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn takes_mirrored_order_flow_from_the_primary_only() {
+        let primary = PeerId::random();
+        let (mut manager, mut network, _) = pool_manager(
+            OrderMirrorConfig { standby: None, primary: Some(primary) },
+            Clock::system()
+        );
+
+        let stranger = PeerId::random();
+        manager.on_mirrored_order_flow(stranger, MirroredOrderFlow::CancelAll(cancel_all()));
+        assert!(matches!(
+            network.try_recv(),
+            Ok(StromNetworkHandleMsg::ReputationChange(peer, ReputationChangeKind::BadMessage))
+                if peer == stranger
+        ));
+
+        manager.on_mirrored_order_flow(primary, MirroredOrderFlow::CancelAll(cancel_all()));
+        assert!(network.try_recv().is_err());
+    }
+
+    #[test]
+    fn pulls_from_the_next_announcer_once_the_logical_clock_passes_the_timeout() {
+        let mut sim = Simulation::new(Duration::from_secs(1_700_000_000));
+        let (manager, mut network, order_events) =
+            sim.enter(|| pool_manager(OrderMirrorConfig::default(), sim.clock()));
+        sim.spawn("pool manager", manager);
+
+        let (order, first, second) = (B256::random(), PeerId::random(), PeerId::random());
+        for peer_id in [first, second] {
+            order_events
+                .send(NetworkOrderEvent::OrderAnnouncements { peer_id, hashes: vec![order] })
+                .unwrap();
+        }
+        let mut pulled = || {
+            std::iter::from_fn(|| network.try_recv().ok())
+                .filter_map(|msg| match msg {
+                    StromNetworkHandleMsg::SendStromMessage {
+                        peer_id,
+                        msg: StromMessage::RequestOrders(hashes)
+                    } => Some((peer_id, hashes)),
+                    _ => None
+                })
+                .collect::<Vec<_>>()
+        };
+
+        sim.run_until_idle();
+        assert_eq!(pulled(), vec![(first, vec![order])]);
+
+        sim.run_for(PULL_TIMEOUT - Duration::from_millis(1));
+        assert!(pulled().is_empty());
+        sim.run_for(Duration::from_millis(1));
+        assert_eq!(pulled(), vec![(second, vec![order])]);
+    }
+
+    #[test]
+    fn relays_the_trace_an_order_arrived_with() {
+        let mut tracer = PropagationTracer::new(Clock::system());
+        let (relayed, untraced, local) = (B256::random(), B256::random(), B256::random());
+        let trace = PropagationTrace { origin_ms: 1, hops: 2 };
+
+        tracer.arrived(relayed, Some(trace));
+        tracer.arrived(untraced, None);
+
+        assert_eq!(tracer.take(relayed), Some(PropagationTrace { origin_ms: 1, hops: 3 }));
+        assert_eq!(tracer.take(untraced), None);
+        assert_eq!(tracer.take(local).map(|trace| trace.hops), Some(0));
+    }
+
+    #[test]
+    fn pulls_from_the_next_announcer_when_the_first_doesnt_send() {
+        let mut pulls = OrderPulls::new(Clock::system());
+        let (order, first, second, third) =
+            (B256::random(), PeerId::random(), PeerId::random(), PeerId::random());
+
+        assert!(pulls.announced(order, first));
+        assert!(!pulls.announced(order, second));
+        assert!(!pulls.announced(order, third));
+        // announcing it again doesn't queue the peer twice
+        assert!(!pulls.announced(order, second));
+
+        let now = Instant::now();
+        assert!(pulls.expire(now).is_empty());
+        assert_eq!(pulls.expire(now + PULL_TIMEOUT), HashMap::from([(second, vec![order])]));
+
+        // the peer we pull it from leaving moves on right away
+        assert_eq!(pulls.peer_left(second), HashMap::from([(third, vec![order])]));
+        assert!(pulls.expire(Instant::now() + PULL_TIMEOUT).is_empty());
+        assert!(pulls.pending.is_empty());
+
+        // given up on, so the next announcement pulls it again
+        assert!(pulls.announced(order, first));
+        pulls.arrived(&order);
+        assert!(pulls.expire(Instant::now() + PULL_TIMEOUT).is_empty());
+        assert!(pulls.announced(order, second));
+    }
+
+    #[test]
+    fn forgets_the_announcers_that_left() {
+        let mut pulls = OrderPulls::new(Clock::system());
+        let (order, first, second, third) =
+            (B256::random(), PeerId::random(), PeerId::random(), PeerId::random());
+
+        assert!(pulls.announced(order, first));
+        assert!(!pulls.announced(order, second));
+        assert!(!pulls.announced(order, third));
+        assert!(pulls.peer_left(second).is_empty());
+
+        assert_eq!(
+            pulls.expire(Instant::now() + PULL_TIMEOUT),
+            HashMap::from([(third, vec![order])])
+        );
+    }
 }
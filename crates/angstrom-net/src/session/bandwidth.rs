@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc
+    },
+    time::{Duration, Instant}
+};
+
+use crate::StromMessageID;
+
+const MESSAGE_KINDS: usize = StromMessageID::Backoff as usize + 1;
+
+const ALL_MESSAGE_IDS: [StromMessageID; MESSAGE_KINDS] = [
+    StromMessageID::Status,
+    StromMessageID::PrePropose,
+    StromMessageID::PreProposeAgg,
+    StromMessageID::Propose,
+    StromMessageID::PropagatePooledOrders,
+    StromMessageID::OrderCancellation,
+    StromMessageID::OrderCancelAll,
+    StromMessageID::PropagateVersionedOrders,
+    StromMessageID::BundleHandoff,
+    StromMessageID::PropagateTracedOrders,
+    StromMessageID::AnnounceOrders,
+    StromMessageID::RequestOrders,
+    StromMessageID::ResumeSession,
+    StromMessageID::MirrorOrderFlow,
+    StromMessageID::KeyHandover,
+    StromMessageID::ProposalShare,
+    StromMessageID::Backoff
+];
+
+/// The window over which per-peer rate caps are enforced.
+const THROTTLE_WINDOW: Duration = Duration::from_secs(1);
+
+/// The longest a peer can ask us to hold our gossip for with a single
+/// [`StromMessage::Backoff`](crate::StromMessage::Backoff).
+pub const MAX_PEER_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Counts the bytes sent to and received from a single peer, per message type.
+///
+/// The meter is shared between the session, which records the traffic, and the
+/// [`StromSessionManager`](crate::StromSessionManager), which reads it.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthMeter {
+    inner: Arc<BandwidthMeterInner>
+}
+
+#[derive(Debug, Default)]
+struct BandwidthMeterInner {
+    inbound:   [AtomicU64; MESSAGE_KINDS],
+    outbound:  [AtomicU64; MESSAGE_KINDS],
+    /// messages that were not sent because the peer was throttled
+    throttled: AtomicU64,
+    /// messages of the peer that were dropped over our inbound cap
+    dropped:   AtomicU64
+}
+
+impl BandwidthMeter {
+    pub fn record_inbound(&self, id: StromMessageID, bytes: usize) {
+        self.inner.inbound[id as usize].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_outbound(&self, id: StromMessageID, bytes: usize) {
+        self.inner.outbound[id as usize].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_throttled(&self) {
+        self.inner.throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self) {
+        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inbound(&self, id: StromMessageID) -> u64 {
+        self.inner.inbound[id as usize].load(Ordering::Relaxed)
+    }
+
+    pub fn outbound(&self, id: StromMessageID) -> u64 {
+        self.inner.outbound[id as usize].load(Ordering::Relaxed)
+    }
+
+    pub fn total_inbound(&self) -> u64 {
+        ALL_MESSAGE_IDS.iter().map(|id| self.inbound(*id)).sum()
+    }
+
+    pub fn total_outbound(&self) -> u64 {
+        ALL_MESSAGE_IDS.iter().map(|id| self.outbound(*id)).sum()
+    }
+
+    pub fn snapshot(&self) -> BandwidthSnapshot {
+        BandwidthSnapshot {
+            inbound:   ALL_MESSAGE_IDS
+                .iter()
+                .map(|id| (*id, self.inbound(*id)))
+                .collect(),
+            outbound:  ALL_MESSAGE_IDS
+                .iter()
+                .map(|id| (*id, self.outbound(*id)))
+                .collect(),
+            throttled: self.inner.throttled.load(Ordering::Relaxed),
+            dropped:   self.inner.dropped.load(Ordering::Relaxed)
+        }
+    }
+}
+
+/// Point in time view of a [`BandwidthMeter`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BandwidthSnapshot {
+    /// bytes received, per message type
+    pub inbound:   HashMap<StromMessageID, u64>,
+    /// bytes sent, per message type
+    pub outbound:  HashMap<StromMessageID, u64>,
+    /// amount of messages that were held back because of the peer's cap or
+    /// its backoff
+    pub throttled: u64,
+    /// amount of messages of the peer that were dropped over our inbound cap
+    pub dropped:   u64
+}
+
+impl BandwidthSnapshot {
+    pub fn total_inbound(&self) -> u64 {
+        self.inbound.values().sum()
+    }
+
+    pub fn total_outbound(&self) -> u64 {
+        self.outbound.values().sum()
+    }
+}
+
+/// Throttle state of a session: the caps of both directions and the backoff
+/// the peer asked us for.
+#[derive(Debug)]
+pub struct PeerThrottles {
+    /// our gossip to the peer
+    pub outbound: PeerThrottle,
+    /// the gossip of the peer
+    pub inbound:  PeerThrottle,
+    /// the peer asked us to hold our gossip until then
+    held_until:   Option<Instant>
+}
+
+impl PeerThrottles {
+    pub fn new(meter: &BandwidthMeter, now: Instant) -> Self {
+        Self {
+            outbound:   PeerThrottle::new(meter.total_outbound(), now),
+            inbound:    PeerThrottle::new(meter.total_inbound(), now),
+            held_until: None
+        }
+    }
+
+    /// Holds our gossip to the peer for `duration`, at most
+    /// [`MAX_PEER_BACKOFF`].
+    pub fn hold(&mut self, now: Instant, duration: Duration) {
+        self.held_until = Some(now + duration.min(MAX_PEER_BACKOFF));
+    }
+
+    /// Whether the peer asked us to hold our gossip.
+    pub fn is_held(&self, now: Instant) -> bool {
+        self.held_until.is_some_and(|until| now < until)
+    }
+}
+
+/// Enforces a byte/sec cap of one direction of a single peer.
+///
+/// Once a peer has used up its budget for the current window we back off from
+/// sending it order gossip, or drop the gossip it sends us, until the next
+/// window starts. Consensus messages are never throttled.
+#[derive(Debug)]
+pub struct PeerThrottle {
+    window_start:       Instant,
+    window_start_bytes: u64,
+    backing_off:        bool
+}
+
+impl PeerThrottle {
+    /// Starts a window at `total` bytes, the traffic of the direction so far.
+    pub fn new(total: u64, now: Instant) -> Self {
+        Self { window_start: now, window_start_bytes: total, backing_off: false }
+    }
+
+    /// Returns true if the traffic of the direction, `total` bytes so far, is
+    /// below the cap in the current window.
+    pub fn has_capacity(&mut self, now: Instant, total: u64, max_bytes_per_sec: u64) -> bool {
+        let elapsed = now.duration_since(self.window_start);
+        if elapsed >= THROTTLE_WINDOW {
+            self.window_start = now;
+            self.window_start_bytes = total;
+            self.backing_off = false;
+        }
+
+        let sent_in_window = total.saturating_sub(self.window_start_bytes);
+        if sent_in_window < max_bytes_per_sec {
+            return true
+        }
+
+        if !self.backing_off {
+            tracing::debug!(
+                sent_in_window,
+                max_bytes_per_sec,
+                "peer reached its bandwidth cap, backing off gossip until next window"
+            );
+            self.backing_off = true;
+        }
+
+        false
+    }
+
+    pub fn is_backing_off(&self) -> bool {
+        self.backing_off
+    }
+
+    /// The time until the next window starts.
+    pub fn window_left(&self, now: Instant) -> Duration {
+        THROTTLE_WINDOW.saturating_sub(now.saturating_duration_since(self.window_start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meter_tracks_per_message_type() {
+        let meter = BandwidthMeter::default();
+        meter.record_outbound(StromMessageID::PropagatePooledOrders, 100);
+        meter.record_outbound(StromMessageID::Propose, 20);
+        meter.record_inbound(StromMessageID::PrePropose, 7);
+
+        let snapshot = meter.snapshot();
+        assert_eq!(snapshot.outbound[&StromMessageID::PropagatePooledOrders], 100);
+        assert_eq!(snapshot.outbound[&StromMessageID::Propose], 20);
+        assert_eq!(snapshot.total_outbound(), 120);
+        assert_eq!(snapshot.total_inbound(), 7);
+        assert_eq!(meter.clone().total_outbound(), 120);
+    }
+
+    #[test]
+    fn throttle_resets_after_window() {
+        let meter = BandwidthMeter::default();
+        let start = Instant::now();
+        let mut throttle = PeerThrottle::new(meter.total_outbound(), start);
+
+        assert!(throttle.has_capacity(start, meter.total_outbound(), 100));
+        meter.record_outbound(StromMessageID::PropagatePooledOrders, 100);
+        assert!(!throttle.has_capacity(start, meter.total_outbound(), 100));
+        assert!(throttle.is_backing_off());
+        assert_eq!(
+            throttle.window_left(start + Duration::from_millis(400)),
+            THROTTLE_WINDOW - Duration::from_millis(400)
+        );
+
+        assert!(throttle.has_capacity(start + THROTTLE_WINDOW, meter.total_outbound(), 100));
+        assert!(!throttle.is_backing_off());
+    }
+
+    #[test]
+    fn backoff_of_the_peer_is_capped() {
+        let now = Instant::now();
+        let mut throttles = PeerThrottles::new(&BandwidthMeter::default(), now);
+        assert!(!throttles.is_held(now));
+
+        throttles.hold(now, Duration::from_millis(500));
+        assert!(throttles.is_held(now + Duration::from_millis(499)));
+        assert!(!throttles.is_held(now + Duration::from_millis(500)));
+
+        throttles.hold(now, Duration::from_secs(3600));
+        assert!(!throttles.is_held(now + MAX_PEER_BACKOFF));
+    }
+}
@@ -35,7 +35,9 @@ pub struct SessionsConfig {
     pub session_event_buffer: usize,
     /// Limits to enforce.
     pub limits: SessionLimits,
-    pub protocol_breach_request_timeout: Duration
+    pub protocol_breach_request_timeout: Duration,
+    /// Per-peer bandwidth caps.
+    pub bandwidth_limits: BandwidthLimits
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
@@ -45,6 +47,21 @@ pub struct SessionLimits {
     max_outbound: u32
 }
 
+/// Per-peer bandwidth caps enforced by the
+/// [StromSessionManager](crate::session::StromSessionManager).
+#[derive(Debug, Default, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BandwidthLimits {
+    /// Max bytes/sec sent to a single peer before we back off from sending it
+    /// order gossip. Consensus messages are not affected. `None` disables the
+    /// cap.
+    pub max_outbound_bytes_per_peer: Option<u64>,
+    /// Max bytes/sec of order gossip taken from a single peer. Gossip over the
+    /// cap is dropped and the peer is asked to back off with a
+    /// [`StromMessage::Backoff`](crate::StromMessage::Backoff). `None` disables
+    /// the cap.
+    pub max_inbound_bytes_per_peer:  Option<u64>
+}
+
 impl Default for SessionLimits {
     fn default() -> Self {
         Self { max_inbound: MAX_STROM_INBOUND_PEERS, max_outbound: MAX_STROM_OUTBOUND_PEERS }
@@ -67,7 +84,8 @@ impl Default for SessionsConfig {
             session_event_buffer: ((MAX_STROM_INBOUND_PEERS + MAX_STROM_OUTBOUND_PEERS) * 2)
                 as usize,
             limits: SessionLimits::default(),
-            protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT
+            protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT,
+            bandwidth_limits: BandwidthLimits::default()
         }
     }
 }
@@ -83,6 +101,12 @@ impl SessionsConfig {
         self.session_event_buffer = n;
         self
     }
+
+    /// Sets the per-peer bandwidth caps.
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth_limits = limits;
+        self
+    }
 }
 
 /// Keeps track of all sessions.
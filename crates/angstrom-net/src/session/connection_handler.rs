@@ -1,10 +1,9 @@
 use std::{collections::HashSet, net::SocketAddr, pin::Pin};
 
-use alloy::{
-    primitives::{keccak256, Address},
-    rlp::BytesMut
-};
+use alloy::{primitives::Address, rlp::BytesMut};
+use angstrom_metrics::SessionMetricsWrapper;
 use angstrom_types::primitive::PeerId;
+use angstrom_utils::clock::Clock;
 use futures::{stream::Empty, Stream, StreamExt};
 use reth_eth_wire::{
     capability::SharedCapabilities, multiplex::ProtocolConnection, protocol::Protocol,
@@ -15,17 +14,14 @@ use reth_network::{
     protocol::{ConnectionHandler, OnNotSupported},
     Direction
 };
-use tokio::{
-    sync::mpsc,
-    time::{Duration, Instant}
-};
+use tokio::{sync::mpsc, time::Duration};
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
     errors::StromStreamError,
-    session::handle::StromSessionHandle,
+    session::{handle::StromSessionHandle, BandwidthMeter},
     types::message::{StromMessage, StromProtocolMessage},
-    StromSession, VerificationSidecar
+    StromSession, StromVersion, VerificationSidecar, STROM_CAPABILITY_NAME
 };
 
 pub enum PossibleStromSession {
@@ -55,45 +51,56 @@ pub struct StromConnectionHandler {
     pub session_command_buffer: usize,
     pub socket_addr: SocketAddr,
     pub side_car: VerificationSidecar,
-    pub validator_set: HashSet<Address>
+    pub validator_set: HashSet<Address>,
+    pub metrics: SessionMetricsWrapper,
+    pub version: StromVersion,
+    pub clock: Clock
 }
 
 impl ConnectionHandler for StromConnectionHandler {
     type Connection = PossibleStromSession;
 
     fn protocol(&self) -> Protocol {
-        StromProtocolMessage::protocol()
+        StromProtocolMessage::protocol(self.version)
     }
 
     fn on_unsupported_by_peer(
         self,
-        _supported: &SharedCapabilities,
+        supported: &SharedCapabilities,
         _direction: Direction,
         _peer_id: PeerId
     ) -> OnNotSupported {
+        // the session runs on the handler of another version
+        if supported
+            .iter_caps()
+            .any(|cap| cap.name() == STROM_CAPABILITY_NAME)
+        {
+            return OnNotSupported::KeepAlive
+        }
+
         OnNotSupported::Disconnect
     }
 
-    // this occurs after the eth handshake occured
+    // this occurs after the eth handshake occured. Whether the peer is a
+    // validator is checked in the status exchange, as the key it stakes with
+    // can differ from its network key
     fn into_connection(
         self,
         direction: Direction,
         peer_id: PeerId,
         conn: ProtocolConnection
     ) -> Self::Connection {
-        let hash = keccak256(peer_id);
-        let validator_address = Address::from_slice(&hash[12..]);
-        if !self.validator_set.contains(&validator_address) {
-            return PossibleStromSession::Invalid(futures::stream::empty())
-        }
-
         let (tx, rx) = mpsc::channel(self.session_command_buffer);
+        let bandwidth = BandwidthMeter::default();
 
         let handle = StromSessionHandle {
             direction,
             remote_id: peer_id,
-            established: Instant::now(),
-            commands_to_session: tx
+            identity: peer_id,
+            established: self.clock.now().into(),
+            commands_to_session: tx,
+            bandwidth: bandwidth.clone(),
+            version: self.version
         };
 
         PossibleStromSession::Session(StromSession::new(
@@ -103,7 +110,12 @@ impl ConnectionHandler for StromConnectionHandler {
             self.to_session_manager,
             self.protocol_breach_request_timeout,
             self.side_car,
-            handle
+            handle,
+            bandwidth,
+            self.validator_set,
+            self.version,
+            self.metrics,
+            self.clock
         ))
     }
 }
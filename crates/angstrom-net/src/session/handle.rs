@@ -2,7 +2,11 @@ use angstrom_types::primitive::PeerId;
 use reth_network::Direction;
 use tokio::{sync::mpsc, time::Instant};
 
-use crate::{session::DisconnectReason, types::message::StromMessage};
+use crate::{
+    session::{BandwidthMeter, DisconnectReason},
+    types::message::StromMessage,
+    StromVersion
+};
 /// Commands that can be sent to the spawned session.
 //TODO: Create a subvariant of messages only for bidirectional messages received during an active
 // session
@@ -25,14 +29,29 @@ pub struct StromSessionHandle {
     pub(crate) direction:           Direction,
     /// The identifier of the remote peer
     pub(crate) remote_id:           PeerId,
+    /// The staking key the peer is accounted for by, see
+    /// [`StakeBinding`](crate::StakeBinding)
+    pub(crate) identity:            PeerId,
     /// The timestamp when the session has been established.
     pub(crate) established:         Instant,
     /// Sender half of the command channel used send commands _to_ the spawned
     /// session
-    pub(crate) commands_to_session: mpsc::Sender<SessionCommand>
+    pub(crate) commands_to_session: mpsc::Sender<SessionCommand>,
+    /// Traffic sent to and received from the peer
+    pub(crate) bandwidth:           BandwidthMeter,
+    /// Version of the strom protocol the session runs on
+    pub(crate) version:             StromVersion
 }
 
 impl StromSessionHandle {
+    pub fn bandwidth(&self) -> &BandwidthMeter {
+        &self.bandwidth
+    }
+
+    pub fn version(&self) -> StromVersion {
+        self.version
+    }
+
     /// Sends a disconnect command to the session.
     pub fn disconnect(&self, reason: Option<DisconnectReason>) {
         // Note: we clone the sender which ensures the channel has capacity to send the
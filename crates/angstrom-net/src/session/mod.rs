@@ -6,6 +6,15 @@ pub use handle::*;
 pub mod protocol_handler;
 pub use protocol_handler::*;
 
+pub mod bandwidth;
+pub use bandwidth::*;
+
+pub mod outbound;
+pub use outbound::*;
+
+pub mod resumption;
+pub use resumption::*;
+
 pub mod strom;
 use futures::Stream;
 pub use strom::*;
@@ -18,17 +27,23 @@ use std::{
     fmt::Debug,
     net::SocketAddr,
     pin::Pin,
-    sync::{atomic::AtomicU64, Arc}
+    sync::{atomic::AtomicU64, Arc},
+    time::{Duration, Instant}
 };
 
+use alloy::primitives::Address;
 use angstrom_types::primitive::PeerId;
+use angstrom_utils::clock::Clock;
 pub use connection_handler::*;
 use futures::task::Poll;
 use reth_eth_wire::DisconnectReason;
 use reth_network::Direction;
 use tracing::warn;
 
-use crate::{errors::StromStreamError, StromMessage, StromProtocolMessage};
+use crate::{
+    errors::StromStreamError, state::validator_address, StromMessage, StromMessageClass,
+    StromProtocolMessage, StromVersion
+};
 
 #[derive(Debug)]
 pub struct StromSessionManager {
@@ -38,17 +53,45 @@ pub struct StromSessionManager {
     /// Channel to receive the session handle upon initialization from the
     /// connection handler This channel is also used to receive messages
     /// from the session
-    from_sessions: mpsc::Receiver<StromSessionMessage>
+    from_sessions:    mpsc::Receiver<StromSessionMessage>,
+    /// Per-peer bandwidth caps
+    bandwidth_limits: BandwidthLimits,
+    /// Throttle state of every active session
+    throttles:        HashMap<PeerId, PeerThrottles>,
+    clock:            Clock
 }
 
 impl StromSessionManager {
     pub fn new(from_sessions: mpsc::Receiver<StromSessionMessage>) -> Self {
-        Self { from_sessions, active_sessions: HashMap::default() }
+        Self {
+            from_sessions,
+            active_sessions: HashMap::default(),
+            bandwidth_limits: BandwidthLimits::default(),
+            throttles: HashMap::default(),
+            clock: Clock::system()
+        }
+    }
+
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth_limits = limits;
+        self
+    }
+
+    /// Takes the time the throttle windows and backoffs run on from `clock`.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
     }
 
     /// Sends a message to the peer's session
     pub fn send_message(&mut self, peer_id: &PeerId, msg: StromMessage) {
+        let now = self.clock.now();
         if let Some(session) = self.active_sessions.get_mut(peer_id) {
+            if !Self::has_capacity(&self.bandwidth_limits, &mut self.throttles, session, &msg, now)
+            {
+                return
+            }
+
             let _ = session
                 .commands_to_session
                 .try_send(SessionCommand::Message(msg));
@@ -56,15 +99,118 @@ impl StromSessionManager {
     }
 
     pub fn broadcast_message(&mut self, msg: StromMessage) {
-        self.active_sessions.values_mut().for_each(|cmd| {
-            let _ = cmd
+        let now = self.clock.now();
+        self.active_sessions.values_mut().for_each(|session| {
+            if !Self::has_capacity(&self.bandwidth_limits, &mut self.throttles, session, &msg, now)
+            {
+                return
+            }
+
+            let _ = session
                 .commands_to_session
                 .try_send(SessionCommand::Message(msg.clone()));
         })
     }
 
+    /// The staking key `peer_id` is accounted for by, if it has a session.
+    pub fn identity(&self, peer_id: &PeerId) -> Option<PeerId> {
+        self.active_sessions
+            .get(peer_id)
+            .map(|session| session.identity)
+    }
+
+    /// Returns the traffic of every active session.
+    pub fn bandwidth(&self) -> HashMap<PeerId, BandwidthSnapshot> {
+        self.active_sessions
+            .iter()
+            .map(|(peer_id, session)| (*peer_id, session.bandwidth.snapshot()))
+            .collect()
+    }
+
+    /// Checks the peer's bandwidth cap. Consensus and mirrored messages always
+    /// go through, gossip is held back while the peer is over its cap or asked
+    /// us to back off.
+    fn has_capacity(
+        limits: &BandwidthLimits,
+        throttles: &mut HashMap<PeerId, PeerThrottles>,
+        session: &StromSessionHandle,
+        msg: &StromMessage,
+        now: Instant
+    ) -> bool {
+        if msg.message_id().class() != StromMessageClass::Gossip {
+            return true
+        }
+
+        let throttle = throttles
+            .entry(session.remote_id)
+            .or_insert_with(|| PeerThrottles::new(&session.bandwidth, now));
+        let has_capacity = !throttle.is_held(now)
+            && limits
+                .max_outbound_bytes_per_peer
+                .map_or(true, |max_bytes_per_sec| {
+                    throttle.outbound.has_capacity(
+                        now,
+                        session.bandwidth.total_outbound(),
+                        max_bytes_per_sec
+                    )
+                });
+
+        if !has_capacity {
+            session.bandwidth.record_throttled();
+        }
+
+        has_capacity
+    }
+
+    /// Takes a message of the peer. A backoff holds our gossip to the peer,
+    /// gossip over our inbound cap is dropped and the peer is asked to back off
+    /// until the next window.
+    fn on_message(&mut self, peer_id: PeerId, message: StromMessage) -> Option<SessionEvent> {
+        let Some(session) = self.active_sessions.get(&peer_id) else {
+            return Some(SessionEvent::ValidMessage { peer_id, message })
+        };
+        let now = self.clock.now();
+        let throttle = self
+            .throttles
+            .entry(peer_id)
+            .or_insert_with(|| PeerThrottles::new(&session.bandwidth, now));
+
+        if let StromMessage::Backoff(millis) = message {
+            tracing::debug!(?peer_id, millis, "peer asked us to back off");
+            throttle.hold(now, Duration::from_millis(millis));
+            return None
+        }
+
+        let Some(max_bytes_per_sec) = self.bandwidth_limits.max_inbound_bytes_per_peer else {
+            return Some(SessionEvent::ValidMessage { peer_id, message })
+        };
+        if message.message_id().class() != StromMessageClass::Gossip {
+            return Some(SessionEvent::ValidMessage { peer_id, message })
+        }
+
+        let was_backing_off = throttle.inbound.is_backing_off();
+        if throttle
+            .inbound
+            .has_capacity(now, session.bandwidth.total_inbound(), max_bytes_per_sec)
+        {
+            return Some(SessionEvent::ValidMessage { peer_id, message })
+        }
+
+        session.bandwidth.record_dropped();
+        if !was_backing_off {
+            let backoff =
+                StromMessage::Backoff(throttle.inbound.window_left(now).as_millis() as u64);
+            let _ = session
+                .commands_to_session
+                .try_send(SessionCommand::Message(backoff));
+        }
+
+        None
+    }
+
     // Removes the Session handle if it exists.
     fn remove_session(&mut self, id: &PeerId) -> Option<StromSessionHandle> {
+        self.throttles.remove(id);
         let session = self.active_sessions.remove(id)?;
         Some(session)
     }
@@ -77,51 +223,93 @@ impl StromSessionManager {
     }
 
     pub fn disconnect(&mut self, id: PeerId, reason: Option<DisconnectReason>) {
-        if let Some(session) = self.active_sessions.remove(&id) {
+        if let Some(session) = self.remove_session(&id) {
             session.disconnect(reason)
         }
     }
 
+    /// Disconnects the sessions accounted for by the staking key of the
+    /// validator at `addr`.
+    pub fn disconnect_validator(&mut self, addr: Address) {
+        let peers = self
+            .active_sessions
+            .values()
+            .filter(|session| validator_address(session.identity) == addr)
+            .map(|session| session.remote_id)
+            .collect::<Vec<_>>();
+        for peer_id in peers {
+            tracing::debug!(?peer_id, ?addr, "disconnecting removed validator");
+            self.disconnect(peer_id, Some(DisconnectReason::DisconnectRequested));
+        }
+    }
+
     fn poll_session_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<SessionEvent>> {
-        self.from_sessions.poll_recv(cx).map(|msg| {
+        loop {
+            let Some(msg) = std::task::ready!(self.from_sessions.poll_recv(cx)) else {
+                return Poll::Ready(None)
+            };
             tracing::trace!(?msg, "got msg from session");
-            msg.and_then(|msg_inner| match msg_inner {
-                StromSessionMessage::Disconnected { peer_id } => {
-                    self.remove_session(&peer_id);
-                    Some(SessionEvent::Disconnected { peer_id })
-                }
-                StromSessionMessage::Established { handle } => {
-                    if self.active_sessions.contains_key(&handle.remote_id) {
-                        warn!(peer_id=?handle.remote_id, "got duplicate connection");
-                        // disconnect
-                        handle.disconnect(None);
-
-                        return None
-                    }
-
-                    let event = SessionEvent::SessionEstablished {
-                        peer_id:   handle.remote_id,
-                        direction: handle.direction,
-                        timeout:   Arc::new(AtomicU64::new(40))
-                    };
-                    self.active_sessions.insert(handle.remote_id, handle);
-
-                    Some(event)
-                }
-                StromSessionMessage::ClosedOnConnectionError { peer_id, error } => {
-                    Some(SessionEvent::OutgoingConnectionError { peer_id, error })
-                }
-                StromSessionMessage::ValidMessage { peer_id, message } => {
-                    Some(SessionEvent::ValidMessage { peer_id, message })
-                }
-                StromSessionMessage::BadMessage { peer_id } => {
-                    Some(SessionEvent::BadMessage { peer_id })
+            // messages that don't surface as an event mustn't end the stream
+            if let Some(event) = self.on_session_msg(msg) {
+                return Poll::Ready(Some(event))
+            }
+        }
+    }
+
+    fn on_session_msg(&mut self, msg: StromSessionMessage) -> Option<SessionEvent> {
+        match msg {
+            StromSessionMessage::Disconnected { peer_id } => {
+                self.remove_session(&peer_id);
+                Some(SessionEvent::Disconnected { peer_id })
+            }
+            StromSessionMessage::Established { handle } => {
+                if self.active_sessions.contains_key(&handle.remote_id) {
+                    warn!(peer_id=?handle.remote_id, "got duplicate connection");
+                    // disconnect
+                    handle.disconnect(None);
+
+                    return None
                 }
-                StromSessionMessage::ProtocolBreach { peer_id } => {
-                    Some(SessionEvent::ProtocolBreach { peer_id })
+                // a validator that regenerated its network key is the same peer
+                if self
+                    .active_sessions
+                    .values()
+                    .any(|session| session.identity == handle.identity)
+                {
+                    warn!(
+                        peer_id=?handle.remote_id,
+                        identity=?handle.identity,
+                        "got another connection of the same staker"
+                    );
+                    handle.disconnect(None);
+
+                    return None
                 }
-            })
-        })
+
+                let event = SessionEvent::SessionEstablished {
+                    peer_id:   handle.remote_id,
+                    identity:  handle.identity,
+                    direction: handle.direction,
+                    version:   handle.version,
+                    timeout:   Arc::new(AtomicU64::new(40))
+                };
+                self.active_sessions.insert(handle.remote_id, handle);
+
+                Some(event)
+            }
+            StromSessionMessage::ClosedOnConnectionError { peer_id, error } => {
+                Some(SessionEvent::OutgoingConnectionError { peer_id, error })
+            }
+            StromSessionMessage::ValidMessage { peer_id, message } => {
+                self.on_message(peer_id, message)
+            }
+            StromSessionMessage::BadMessage { peer_id } => {
+                Some(SessionEvent::BadMessage { peer_id })
+            }
+            StromSessionMessage::ProtocolBreach { peer_id } => {
+                Some(SessionEvent::ProtocolBreach { peer_id })
+            }
+        }
     }
 }
 
@@ -142,8 +330,12 @@ pub enum SessionEvent {
     SessionEstablished {
         /// The remote node's public key
         peer_id:   PeerId,
+        /// The staking key the remote node is accounted for by
+        identity:  PeerId,
         /// The direction of the session, either `Inbound` or `Outgoing`
         direction: Direction,
+        /// Version of the strom protocol the session runs on
+        version:   StromVersion,
         /// The maximum time that the session waits for a response from the peer
         /// before timing out the connection
         timeout:   Arc<AtomicU64>
@@ -197,3 +389,44 @@ pub enum SessionEvent {
         peer_id: PeerId
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::primitive::AngstromSigner;
+
+    use super::*;
+
+    fn session(identity: PeerId) -> (StromSessionHandle, mpsc::Receiver<SessionCommand>) {
+        let (commands_to_session, commands) = mpsc::channel(1);
+        let handle = StromSessionHandle {
+            direction: Direction::Incoming,
+            remote_id: PeerId::random(),
+            identity,
+            established: tokio::time::Instant::now(),
+            commands_to_session,
+            bandwidth: BandwidthMeter::default(),
+            version: StromVersion::LATEST
+        };
+
+        (handle, commands)
+    }
+
+    #[test]
+    fn disconnects_the_bound_node_of_a_removed_validator() {
+        let (removed, kept) = (AngstromSigner::random(), AngstromSigner::random());
+        let mut sessions = StromSessionManager::new(mpsc::channel(1).1);
+        // the node connects with a network key its validator's stake is bound to
+        let (bound, mut bound_commands) = session(removed.id());
+        let (other, mut other_commands) = session(kept.id());
+        let other_id = other.remote_id;
+        for handle in [bound, other] {
+            sessions.active_sessions.insert(handle.remote_id, handle);
+        }
+
+        sessions.disconnect_validator(validator_address(removed.id()));
+
+        assert_eq!(sessions.active_sessions.keys().collect::<Vec<_>>(), vec![&other_id]);
+        assert!(matches!(bound_commands.try_recv(), Ok(SessionCommand::Disconnect { .. })));
+        assert!(other_commands.try_recv().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant}
+};
+
+use crate::{StromMessage, StromMessageClass};
+
+/// Max amount of gossip messages a single session will hold onto before it
+/// starts dropping new ones.
+pub const MAX_QUEUED_GOSSIP_MESSAGES: usize = 256;
+
+/// Outbound messages of a session that are waiting to be written to the wire,
+/// split by [`StromMessageClass`]. Consensus messages always preempt gossip so
+/// that a burst of order propagation can't delay a round.
+#[derive(Debug, Default)]
+pub struct OutboundQueues {
+    consensus: VecDeque<(Instant, StromMessage)>,
+    mirror:    VecDeque<(Instant, StromMessage)>,
+    gossip:    VecDeque<(Instant, StromMessage)>
+}
+
+impl OutboundQueues {
+    /// Queues the message in its class at `now`. Returns false if the message
+    /// was dropped because the gossip queue is full.
+    pub fn push(&mut self, msg: StromMessage, now: Instant) -> bool {
+        match msg.message_id().class() {
+            StromMessageClass::Consensus => self.consensus.push_back((now, msg)),
+            StromMessageClass::Mirror => self.mirror.push_back((now, msg)),
+            StromMessageClass::Gossip => {
+                if self.gossip.len() >= MAX_QUEUED_GOSSIP_MESSAGES {
+                    return false
+                }
+                self.gossip.push_back((now, msg))
+            }
+        }
+
+        true
+    }
+
+    /// Pops the next message to send along with its class and how long it
+    /// waited in the queue up to `now`.
+    pub fn pop(&mut self, now: Instant) -> Option<(StromMessageClass, Duration, StromMessage)> {
+        let (class, (queued_at, msg)) = self
+            .consensus
+            .pop_front()
+            .map(|entry| (StromMessageClass::Consensus, entry))
+            .or_else(|| {
+                self.mirror
+                    .pop_front()
+                    .map(|entry| (StromMessageClass::Mirror, entry))
+            })
+            .or_else(|| {
+                self.gossip
+                    .pop_front()
+                    .map(|entry| (StromMessageClass::Gossip, entry))
+            })?;
+
+        Some((class, now.saturating_duration_since(queued_at), msg))
+    }
+
+    pub fn len(&self, class: StromMessageClass) -> usize {
+        match class {
+            StromMessageClass::Consensus => self.consensus.len(),
+            StromMessageClass::Mirror => self.mirror.len(),
+            StromMessageClass::Gossip => self.gossip.len()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.consensus.is_empty() && self.mirror.is_empty() && self.gossip.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::consensus::Proposal;
+
+    use super::*;
+    use crate::MirroredOrderFlow;
+
+    #[test]
+    fn consensus_preempts_gossip() {
+        let (mut queues, now) = (OutboundQueues::default(), Instant::now());
+        assert!(queues.push(StromMessage::PropagatePooledOrders(vec![]), now));
+        assert!(queues.push(StromMessage::PropagatePooledOrders(vec![]), now));
+        assert!(queues.push(StromMessage::Propose(Proposal::default()), now));
+
+        assert_eq!(queues.len(StromMessageClass::Consensus), 1);
+        assert_eq!(queues.len(StromMessageClass::Gossip), 2);
+
+        let (class, queued_for, msg) = queues.pop(now + Duration::from_millis(5)).unwrap();
+        assert_eq!(class, StromMessageClass::Consensus);
+        assert_eq!(queued_for, Duration::from_millis(5));
+        assert!(matches!(msg, StromMessage::Propose(_)));
+
+        let (class, ..) = queues.pop(now).unwrap();
+        assert_eq!(class, StromMessageClass::Gossip);
+        let (class, ..) = queues.pop(now).unwrap();
+        assert_eq!(class, StromMessageClass::Gossip);
+
+        assert!(queues.pop(now).is_none());
+        assert!(queues.is_empty());
+    }
+
+    #[test]
+    fn gossip_is_bounded() {
+        let (mut queues, now) = (OutboundQueues::default(), Instant::now());
+        for _ in 0..MAX_QUEUED_GOSSIP_MESSAGES {
+            assert!(queues.push(StromMessage::PropagatePooledOrders(vec![]), now));
+        }
+        assert!(!queues.push(StromMessage::PropagatePooledOrders(vec![]), now));
+        // consensus and mirrored messages are never dropped
+        assert!(queues.push(StromMessage::Propose(Proposal::default()), now));
+        assert!(queues.push(StromMessage::MirrorOrderFlow(MirroredOrderFlow::Orders(vec![])), now));
+    }
+
+    #[test]
+    fn mirrored_order_flow_goes_ahead_of_gossip() {
+        let (mut queues, now) = (OutboundQueues::default(), Instant::now());
+        assert!(queues.push(StromMessage::PropagatePooledOrders(vec![]), now));
+        assert!(queues.push(StromMessage::MirrorOrderFlow(MirroredOrderFlow::Orders(vec![])), now));
+        assert!(queues.push(StromMessage::Propose(Proposal::default()), now));
+
+        let classes = std::iter::from_fn(|| queues.pop(now).map(|(class, ..)| class));
+        assert_eq!(
+            classes.collect::<Vec<_>>(),
+            vec![
+                StromMessageClass::Consensus,
+                StromMessageClass::Mirror,
+                StromMessageClass::Gossip
+            ]
+        );
+    }
+}
@@ -1,17 +1,22 @@
 use std::{collections::HashSet, fmt::Debug, net::SocketAddr, sync::Arc};
 
 use alloy::primitives::Address;
+use angstrom_metrics::SessionMetricsWrapper;
 use angstrom_types::primitive::PeerId;
+use angstrom_utils::clock::Clock;
 use parking_lot::RwLock;
 use reth_metrics::common::mpsc::MeteredPollSender;
 use reth_network::protocol::ProtocolHandler;
 use tokio::time::Duration;
 
-use crate::{StromConnectionHandler, StromSessionMessage, VerificationSidecar};
+use crate::{StromConnectionHandler, StromSessionMessage, StromVersion, VerificationSidecar};
 
 const SESSION_COMMAND_BUFFER: usize = 100;
 /// The protocol handler that is used to announce the strom capability upon
 /// successfully establishing a hello handshake on an incoming tcp connection.
+///
+/// There is a handler per [`StromVersion`] we speak, the session runs on the
+/// handler of the highest version the peer shares with us.
 #[derive(Debug)]
 pub struct StromProtocolHandler {
     /// When a new connection is created, the conection handler will use
@@ -21,7 +26,13 @@ pub struct StromProtocolHandler {
     /// details for verifying status messages
     sidecar:            VerificationSidecar,
     // the set of current validators
-    validators:         Arc<RwLock<HashSet<Address>>>
+    validators:         Arc<RwLock<HashSet<Address>>>,
+    /// shared by all sessions created by this handler
+    metrics:            SessionMetricsWrapper,
+    /// version of the strom capability the handler announces
+    version:            StromVersion,
+    /// time source of the sessions
+    clock:              Clock
 }
 
 impl ProtocolHandler for StromProtocolHandler {
@@ -34,7 +45,10 @@ impl ProtocolHandler for StromProtocolHandler {
             protocol_breach_request_timeout: Duration::from_secs(15),
             session_command_buffer: SESSION_COMMAND_BUFFER,
             socket_addr,
-            validator_set: self.validators.read().clone()
+            validator_set: self.validators.read().clone(),
+            metrics: self.metrics.clone(),
+            version: self.version,
+            clock: self.clock.clone()
         })
     }
 
@@ -51,7 +65,10 @@ impl ProtocolHandler for StromProtocolHandler {
             session_command_buffer: SESSION_COMMAND_BUFFER,
             socket_addr,
             side_car: self.sidecar.clone(),
-            validator_set: self.validators.read().clone()
+            validator_set: self.validators.read().clone(),
+            metrics: self.metrics.clone(),
+            version: self.version,
+            clock: self.clock.clone()
         })
     }
 }
@@ -62,6 +79,25 @@ impl StromProtocolHandler {
         sidecar: VerificationSidecar,
         validators: Arc<RwLock<HashSet<Address>>>
     ) -> Self {
-        Self { to_session_manager, validators, sidecar }
+        Self {
+            to_session_manager,
+            validators,
+            sidecar,
+            metrics: SessionMetricsWrapper::new(),
+            version: StromVersion::LATEST,
+            clock: Clock::system()
+        }
+    }
+
+    /// Announces the given version of the strom capability instead of the
+    /// latest.
+    pub fn with_version(mut self, version: StromVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
     }
 }
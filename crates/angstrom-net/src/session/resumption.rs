@@ -0,0 +1,377 @@
+//! Resumption of validator sessions that drop mid round.
+//!
+//! Both sides of a session hand each other a token once it is established
+//! and log the consensus messages they send during it. When the session drops
+//! and the peer reconnects within the resumption window, it resumes the
+//! session with the token and tells us how many of its consensus messages it
+//! got, and we replay the ones of the current height it missed instead of
+//! waiting for the round to resend them.
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant}
+};
+
+use alloy::primitives::{BlockNumber, B256};
+use angstrom_types::primitive::PeerId;
+use serde::{Deserialize, Serialize};
+
+use crate::StromMessage;
+
+/// How long a dropped session can still be resumed for.
+pub const DEFAULT_RESUMPTION_WINDOW: Duration = Duration::from_secs(12);
+
+/// Most consensus messages logged for a single session.
+const MAX_LOGGED_MESSAGES: usize = 256;
+
+/// Sent by both sides of a session once it is established.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionResume {
+    /// resumes the session of the sender when it drops
+    pub token:    B256,
+    /// token of the session of the receiver the sender resumes
+    pub resumes:  Option<B256>,
+    /// consensus messages of the resumed session the sender got
+    pub received: u64
+}
+
+/// Outcome of a [`SessionResume`] of a peer.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Resumed {
+    /// consensus messages the peer missed, in the order they were sent
+    pub replay:  Vec<StromMessage>,
+    /// a new session for the peer, if it didn't resume the one we offered
+    pub reissue: Option<SessionResume>
+}
+
+/// Consensus messages sent to a peer during a session.
+#[derive(Debug)]
+struct OutboundLog {
+    token:           B256,
+    /// consensus messages sent during the session
+    sent:            u64,
+    /// sent messages the peer won't count, because they were of a past height
+    /// when it was replayed the rest
+    skipped:         u64,
+    /// messages before this one were replayed already, a peer resuming again
+    /// isn't replayed them twice
+    replayed_to:     u64,
+    messages:        VecDeque<(u64, BlockNumber, StromMessage)>,
+    disconnected_at: Option<Instant>
+}
+
+impl OutboundLog {
+    fn new() -> Self {
+        Self {
+            token:           B256::random(),
+            sent:            0,
+            skipped:         0,
+            replayed_to:     0,
+            messages:        VecDeque::new(),
+            disconnected_at: None
+        }
+    }
+
+    fn is_resumable(&self, window: Duration, now: Instant) -> bool {
+        self.disconnected_at
+            .map_or(true, |at| now.saturating_duration_since(at) <= window)
+    }
+
+    fn push(&mut self, height: BlockNumber, msg: &StromMessage) {
+        if self.messages.len() == MAX_LOGGED_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((self.sent, height, msg.clone()));
+        self.sent += 1;
+    }
+
+    /// The messages the peer missed after it got `received` of them. The
+    /// count is the peer's, so it's clamped to what was sent and what was
+    /// replayed already.
+    fn replay(&mut self, received: u64, height: BlockNumber) -> Vec<StromMessage> {
+        let received = received.min(self.sent);
+        let from = received.saturating_add(self.skipped).max(self.replayed_to);
+        let replay = self
+            .messages
+            .iter()
+            .filter(|(seq, msg_height, _)| *seq >= from && *msg_height == height)
+            .map(|(.., msg)| msg.clone())
+            .collect::<Vec<_>>();
+        self.skipped = self
+            .sent
+            .saturating_sub(received.saturating_add(replay.len() as u64));
+        self.replayed_to = self.sent;
+
+        replay
+    }
+}
+
+/// Session of a peer we count the consensus messages of.
+#[derive(Debug)]
+struct InboundSession {
+    token:    B256,
+    received: u64
+}
+
+/// Resumption state of the sessions of every peer.
+#[derive(Debug)]
+pub struct SessionResumption {
+    window:   Duration,
+    /// highest height a consensus message was sent for
+    height:   BlockNumber,
+    outbound: HashMap<PeerId, OutboundLog>,
+    inbound:  HashMap<PeerId, InboundSession>
+}
+
+impl Default for SessionResumption {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESUMPTION_WINDOW)
+    }
+}
+
+impl SessionResumption {
+    pub fn new(window: Duration) -> Self {
+        Self { window, height: 0, outbound: HashMap::new(), inbound: HashMap::new() }
+    }
+
+    /// The [`SessionResume`] to send `peer_id` once its session is
+    /// established. Keeps the session that dropped if it's still resumable.
+    pub fn established(&mut self, peer_id: PeerId, now: Instant) -> SessionResume {
+        let log = self
+            .outbound
+            .entry(peer_id)
+            .or_insert_with(OutboundLog::new);
+        if log.disconnected_at.is_none() || !log.is_resumable(self.window, now) {
+            *log = OutboundLog::new();
+        }
+        log.disconnected_at = None;
+
+        let inbound = self.inbound.get(&peer_id);
+        SessionResume {
+            token:    log.token,
+            resumes:  inbound.map(|session| session.token),
+            received: inbound.map_or(0, |session| session.received)
+        }
+    }
+
+    pub fn disconnected(&mut self, peer_id: PeerId, now: Instant) {
+        if let Some(log) = self.outbound.get_mut(&peer_id) {
+            log.disconnected_at = Some(now);
+        }
+        self.prune(now);
+    }
+
+    /// Takes the [`SessionResume`] of `peer_id`.
+    pub fn resume(&mut self, peer_id: PeerId, resume: SessionResume) -> Resumed {
+        let inbound = self
+            .inbound
+            .entry(peer_id)
+            .or_insert(InboundSession { token: resume.token, received: 0 });
+        if inbound.token != resume.token {
+            *inbound = InboundSession { token: resume.token, received: 0 };
+        }
+
+        let Some(log) = self.outbound.get_mut(&peer_id) else { return Resumed::default() };
+        if resume.resumes == Some(log.token) {
+            return Resumed { replay: log.replay(resume.received, self.height), reissue: None }
+        }
+
+        // the peer lost our session, so our count of what it got is off. The
+        // new one resumes the session of the peer, which doesn't reissue it
+        *log = OutboundLog::new();
+        Resumed {
+            replay:  vec![],
+            reissue: Some(SessionResume {
+                token:    log.token,
+                resumes:  Some(inbound.token),
+                received: inbound.received
+            })
+        }
+    }
+
+    /// Logs a consensus message sent to `peer_id`.
+    pub fn sent(&mut self, peer_id: PeerId, msg: &StromMessage, now: Instant) {
+        let Some(height) = self.advance_height(msg) else { return };
+        if let Some(log) = self
+            .outbound
+            .get_mut(&peer_id)
+            .filter(|log| log.is_resumable(self.window, now))
+        {
+            log.push(height, msg);
+        }
+    }
+
+    /// Logs a consensus message broadcast to every peer, including the ones
+    /// whose session dropped but can still be resumed.
+    pub fn broadcast(&mut self, msg: &StromMessage, now: Instant) {
+        let Some(height) = self.advance_height(msg) else { return };
+        self.prune(now);
+        self.outbound
+            .values_mut()
+            .for_each(|log| log.push(height, msg));
+    }
+
+    /// Counts a consensus message received from `peer_id`.
+    pub fn received(&mut self, peer_id: PeerId, msg: &StromMessage) {
+        if consensus_height(msg).is_none() {
+            return
+        }
+        if let Some(session) = self.inbound.get_mut(&peer_id) {
+            session.received += 1;
+        }
+    }
+
+    /// Forgets the sessions of the peers that can't resume them anymore, both
+    /// ways.
+    fn prune(&mut self, now: Instant) {
+        let window = self.window;
+        self.outbound.retain(|_, log| log.is_resumable(window, now));
+        let outbound = &self.outbound;
+        self.inbound
+            .retain(|peer_id, _| outbound.contains_key(peer_id));
+    }
+
+    /// Height of `msg` if it's a consensus message. Messages of a new height
+    /// drop the logged ones of the past heights.
+    fn advance_height(&mut self, msg: &StromMessage) -> Option<BlockNumber> {
+        let height = consensus_height(msg)?;
+        if height > self.height {
+            self.height = height;
+            self.outbound.values_mut().for_each(|log| {
+                log.messages
+                    .retain(|(_, msg_height, _)| *msg_height >= height)
+            });
+        }
+
+        Some(height)
+    }
+}
+
+fn consensus_height(msg: &StromMessage) -> Option<BlockNumber> {
+    match msg {
+        StromMessage::PrePropose(p) => Some(p.block_height),
+        StromMessage::PreProposeAgg(p) => Some(p.block_height),
+        StromMessage::Propose(p) => Some(p.block_height),
+        StromMessage::BundleHandoff(h) => Some(h.block_height),
+        StromMessage::KeyHandover(h) => Some(h.block_height),
+        StromMessage::ProposalShare(s) => Some(s.block_height),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::consensus::PreProposal;
+
+    use super::*;
+
+    fn pre_proposal(block_height: BlockNumber) -> StromMessage {
+        StromMessage::PrePropose(PreProposal { block_height, ..Default::default() })
+    }
+
+    /// Connects two peers, returning how `a` and `b` resumed the session of
+    /// the other.
+    fn connect(
+        a: (&mut SessionResumption, PeerId),
+        b: (&mut SessionResumption, PeerId),
+        now: Instant
+    ) -> (Resumed, Resumed) {
+        let to_b = a.0.established(b.1, now);
+        let to_a = b.0.established(a.1, now);
+        (a.0.resume(b.1, to_a), b.0.resume(a.1, to_b))
+    }
+
+    #[test]
+    fn replays_the_messages_of_the_height_the_peer_missed() {
+        let (mut a, mut b) = (SessionResumption::default(), SessionResumption::default());
+        let (a_id, b_id) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+        connect((&mut a, a_id), (&mut b, b_id), now);
+
+        // b gets the first message before the session drops
+        a.broadcast(&pre_proposal(1), now);
+        b.received(a_id, &pre_proposal(1));
+        a.broadcast(&pre_proposal(2), now);
+        a.disconnected(b_id, now);
+        a.broadcast(&pre_proposal(2), now);
+        b.disconnected(a_id, now);
+
+        let (to_b, to_a) = connect((&mut a, a_id), (&mut b, b_id), now + Duration::from_secs(1));
+
+        // the message of the past height isn't replayed
+        assert_eq!(to_b, Resumed { replay: vec![pre_proposal(2), pre_proposal(2)], reissue: None });
+        assert_eq!(to_a, Resumed::default());
+    }
+
+    #[test]
+    fn replay_is_clamped_to_what_was_sent() {
+        let (mut a, mut b) = (SessionResumption::default(), SessionResumption::default());
+        let (a_id, b_id) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+        connect((&mut a, a_id), (&mut b, b_id), now);
+        a.broadcast(&pre_proposal(1), now);
+        a.disconnected(b_id, now);
+
+        let resume = b.established(a_id, now);
+        let claimed = SessionResume { received: u64::MAX, ..resume.clone() };
+        assert!(a.resume(b_id, claimed).replay.is_empty());
+
+        // the peer counts as having got what was sent, not more
+        a.broadcast(&pre_proposal(1), now);
+        assert_eq!(a.resume(b_id, resume).replay, vec![pre_proposal(1)]);
+    }
+
+    #[test]
+    fn resuming_again_doesnt_replay_twice() {
+        let (mut a, mut b) = (SessionResumption::default(), SessionResumption::default());
+        let (a_id, b_id) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+        connect((&mut a, a_id), (&mut b, b_id), now);
+        a.broadcast(&pre_proposal(1), now);
+        a.disconnected(b_id, now);
+
+        let resume = b.established(a_id, now);
+        assert_eq!(a.resume(b_id, resume.clone()).replay, vec![pre_proposal(1)]);
+        assert!(a.resume(b_id, resume.clone()).replay.is_empty());
+
+        // what is sent after the replay is replayed when the session drops again
+        a.broadcast(&pre_proposal(1), now);
+        assert_eq!(a.resume(b_id, resume).replay, vec![pre_proposal(1)]);
+    }
+
+    #[test]
+    fn forgets_the_sessions_of_peers_that_cant_resume() {
+        let mut a = SessionResumption::new(Duration::from_secs(1));
+        let peer = PeerId::random();
+        let now = Instant::now();
+        let resume = SessionResumption::default().established(PeerId::random(), now);
+        a.established(peer, now);
+        a.resume(peer, resume);
+        assert!(a.inbound.contains_key(&peer));
+
+        a.disconnected(peer, now);
+        a.broadcast(&pre_proposal(1), now + Duration::from_secs(2));
+        assert!(a.outbound.is_empty());
+        assert!(a.inbound.is_empty());
+    }
+
+    #[test]
+    fn expired_sessions_start_over() {
+        let mut a = SessionResumption::new(Duration::from_secs(1));
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        let first = a.established(peer, now);
+        a.broadcast(&pre_proposal(1), now);
+        a.disconnected(peer, now);
+        let second = a.established(peer, now + Duration::from_secs(2));
+        assert_ne!(first.token, second.token);
+
+        let resumed = a.resume(
+            peer,
+            SessionResume { token: B256::random(), resumes: Some(first.token), received: 0 }
+        );
+        assert!(resumed.replay.is_empty());
+        let reissue = resumed.reissue.unwrap();
+        assert_ne!(reissue.token, second.token);
+    }
+}
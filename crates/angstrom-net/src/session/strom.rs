@@ -1,14 +1,22 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     fmt::Debug,
     ops::Deref,
     pin::Pin,
-    time::{SystemTime, UNIX_EPOCH}
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    time::Instant
 };
 
-use alloy::rlp::{BytesMut, Encodable};
+use alloy::{
+    primitives::{Address, B256},
+    rlp::BytesMut
+};
+use angstrom_metrics::SessionMetricsWrapper;
 use angstrom_types::primitive::{AngstromSigner, PeerId};
-use angstrom_utils::{GenericExt, PollFlatten};
+use angstrom_utils::{clock::Clock, PollFlatten};
 use futures::{
     task::{Context, Poll},
     Stream, StreamExt
@@ -19,33 +27,86 @@ use tokio::time::Duration;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::PollSender;
 
-use super::handle::SessionCommand;
+use super::{bandwidth::BandwidthMeter, handle::SessionCommand, outbound::OutboundQueues};
 use crate::{
+    state::validator_address,
     types::{
         message::StromProtocolMessage,
-        status::{Status, StatusState}
+        status::{Status, StatusState, STAKE_BINDING_TTL_SECS}
     },
-    StatusBuilder, StromMessage, StromSessionHandle, StromSessionMessage
+    StakeBinding, StatusBuilder, StromMessage, StromMessageID, StromSessionHandle,
+    StromSessionMessage, StromVersion
 };
 
 const STATUS_TIMESTAMP_TIMEOUT_MS: u128 = 1500;
 
+/// Most observers admitted at once. Observers are sent everything but vouch
+/// for nothing, so they don't get to take up every session.
+pub const MAX_OBSERVERS: usize = 32;
+
+/// Most orders requests an observer can make per second, the rest are dropped.
+const MAX_OBSERVER_REQUESTS_PER_SEC: u32 = 10;
+
+/// Observer sessions admitted at the moment, shared by every session.
+#[derive(Debug, Clone, Default)]
+pub struct ObserverSlots(Arc<AtomicUsize>);
+
+impl ObserverSlots {
+    /// Takes a slot for an observer, false if they are all taken.
+    fn acquire(&self) -> bool {
+        self.0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| {
+                (taken < MAX_OBSERVERS).then_some(taken + 1)
+            })
+            .is_ok()
+    }
+
+    fn release(&self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
 /// holds the state we need to verify the new peer
 #[derive(Clone)]
 pub struct VerificationSidecar {
     pub secret_key:   AngstromSigner,
     pub status:       StatusState,
     pub has_sent:     bool,
-    pub has_received: bool
+    pub has_received: bool,
+    /// key of our validator if it stakes with another key, it vouches for
+    /// `secret_key` in every status we send
+    pub staking_key:  Option<AngstromSigner>,
+    /// we follow the rounds without being a validator
+    pub observer:     bool,
+    /// hash of the protocol genesis we run with
+    pub genesis:      B256,
+    /// observers admitted by every session
+    pub observers:    ObserverSlots
 }
 
 impl VerificationSidecar {
-    pub fn make_status_message(&mut self, peer: PeerId) -> Status {
+    pub fn make_status_message(
+        &mut self,
+        peer: PeerId,
+        version: StromVersion,
+        clock: &Clock
+    ) -> Status {
         if self.has_sent {
             panic!("can only send the status message once");
         }
 
-        StatusBuilder::from(self.status.with_peer(peer)).build(&self.secret_key)
+        let now = clock.unix_now().as_secs();
+        let binding = self.staking_key.as_ref().map(|staking_key| {
+            StakeBinding::new(staking_key, self.secret_key.id(), now + STAKE_BINDING_TTL_SECS)
+        });
+
+        StatusBuilder::from(self.status.with_peer(peer))
+            .stake_binding(binding)
+            .observer(self.observer)
+            .genesis(self.genesis)
+            .protocol(version)
+            .clock(clock.clone())
+            .build(&self.secret_key)
     }
 
     pub fn is_verified(&self) -> bool {
@@ -82,7 +143,23 @@ pub struct StromSession {
     /// has sent the handle to the receiver
     pending_handle: Option<StromSessionHandle>,
     /// buffer for pending messages
-    outbound_buffer: VecDeque<StromSessionMessage>
+    outbound_buffer: VecDeque<StromSessionMessage>,
+    /// messages from the manager waiting to be sent to the peer
+    outbound_queues: OutboundQueues,
+    /// traffic accounting, shared with the session handle
+    bandwidth: BandwidthMeter,
+    /// the validator set the peer has to stake in
+    validators: HashSet<Address>,
+    /// version of the strom protocol we negotiated with the peer
+    version: StromVersion,
+    /// the peer is an observer, it's sent messages but can only ask for the
+    /// orders announced to it
+    receive_only: bool,
+    /// start of the current second and the orders requests the observer made
+    /// in it
+    observer_requests: (Instant, u32),
+    metrics: SessionMetricsWrapper,
+    clock: Clock
 }
 
 impl StromSession {
@@ -93,7 +170,12 @@ impl StromSession {
         to_session_manager: MeteredPollSender<StromSessionMessage>,
         protocol_breach_request_timeout: Duration,
         verification_sidecar: VerificationSidecar,
-        handle: StromSessionHandle
+        handle: StromSessionHandle,
+        bandwidth: BandwidthMeter,
+        validators: HashSet<Address>,
+        version: StromVersion,
+        metrics: SessionMetricsWrapper,
+        clock: Clock
     ) -> Self {
         Self {
             verification_sidecar,
@@ -104,8 +186,27 @@ impl StromSession {
             protocol_breach_request_timeout,
             terminate_message: None,
             pending_handle: Some(handle),
-            outbound_buffer: VecDeque::default()
+            outbound_buffer: VecDeque::default(),
+            outbound_queues: OutboundQueues::default(),
+            bandwidth,
+            validators,
+            version,
+            receive_only: false,
+            observer_requests: (clock.now(), 0),
+            metrics,
+            clock
+        }
+    }
+
+    /// Counts an orders request of the observer, false if it's over its rate.
+    fn observer_may_request(&mut self, now: Instant) -> bool {
+        let (since, requests) = &mut self.observer_requests;
+        if now.saturating_duration_since(*since) >= Duration::from_secs(1) {
+            (*since, *requests) = (now, 0);
         }
+        *requests += 1;
+
+        *requests <= MAX_OBSERVER_REQUESTS_PER_SEC
     }
 
     /// Report back that this session has been closed.
@@ -159,42 +260,85 @@ impl StromSession {
     }
 
     fn poll_commands(&mut self, cx: &mut Context<'_>) -> Option<Poll<Option<BytesMut>>> {
-        self.commands_rx
-            .poll_next_unpin(cx)
-            .map(|inner| {
-                inner.map_or_else(
-                    || Poll::Ready(None),
-                    |msg| match msg {
-                        SessionCommand::Disconnect { .. } => self.emit_disconnect(cx),
-                        SessionCommand::Message(msg) => {
-                            let msg = StromProtocolMessage {
-                                message_id: msg.message_id(),
-                                message:    msg
-                            };
-                            let mut buf = BytesMut::new();
-
-                            msg.encode(&mut buf);
-                            Poll::Ready(Some(buf))
-                        }
+        // drain all commands that are ready so that consensus messages that are
+        // behind a burst of order gossip in the channel get sent first.
+        loop {
+            match self.commands_rx.poll_next_unpin(cx) {
+                Poll::Ready(Some(SessionCommand::Disconnect { .. })) => {
+                    return Some(self.emit_disconnect(cx))
+                }
+                Poll::Ready(Some(SessionCommand::Message(msg))) => {
+                    if !self.outbound_queues.push(msg, self.clock.now()) {
+                        tracing::debug!(peer=?self.remote_peer_id, "gossip queue full, dropping message");
                     }
-                )
-            })
-            .flatten()
-            .some_if(|f| f.is_ready())
+                }
+                Poll::Ready(None) => {
+                    if self.outbound_queues.is_empty() {
+                        return Some(Poll::Ready(None))
+                    }
+                    break
+                }
+                Poll::Pending => break
+            }
+        }
+
+        let (class, queued_for, msg) = self.outbound_queues.pop(self.clock.now())?;
+        self.metrics.queueing_delay(class.as_str(), queued_for);
+
+        let message_id = msg.message_id();
+        let Some(msg) = StromProtocolMessage::for_version(msg, self.version) else {
+            tracing::trace!(
+                peer=?self.remote_peer_id,
+                ?message_id,
+                version=?self.version,
+                "the peer's version has no such message, dropping it"
+            );
+            // the queues might hold more
+            cx.waker().wake_by_ref();
+            return None
+        };
+        let mut buf = BytesMut::new();
+        msg.encode_for(self.version, &mut buf);
+        self.bandwidth.record_outbound(msg.message_id, buf.len());
+
+        Some(Poll::Ready(Some(buf)))
     }
 
     fn poll_incoming(&mut self, cx: &mut Context<'_>) -> Option<Poll<Option<BytesMut>>> {
         // processes incoming messages until there are none left or the stream closes
         while let Poll::Ready(msg) = self.conn.poll_next_unpin(cx).map(|data| {
             data.map(|bytes| {
-                let msg = StromProtocolMessage::decode_message(&mut bytes.deref());
-
-                let msg = msg
-                    .map(|m| StromSessionMessage::ValidMessage {
-                        peer_id: self.remote_peer_id,
-                        message: m
-                    })
-                    .unwrap_or(StromSessionMessage::BadMessage { peer_id: self.remote_peer_id });
+                let msg =
+                    match StromProtocolMessage::decode_message(self.version, &mut bytes.deref()) {
+                        Ok(Some(msg))
+                            if self.receive_only
+                                && (msg.message_id != StromMessageID::RequestOrders
+                                    || !self.observer_may_request(self.clock.now())) =>
+                        {
+                            tracing::trace!(
+                                peer=?self.remote_peer_id,
+                                message_id=?msg.message_id,
+                                "ignoring a message of an observer"
+                            );
+                            return
+                        }
+                        Ok(Some(msg)) => {
+                            self.bandwidth.record_inbound(msg.message_id, bytes.len());
+                            StromSessionMessage::ValidMessage {
+                                peer_id: self.remote_peer_id,
+                                message: msg
+                            }
+                        }
+                        Ok(None) => {
+                            tracing::trace!(
+                                peer=?self.remote_peer_id,
+                                version=?self.version,
+                                "ignoring a message we don't exchange with the peer's version"
+                            );
+                            return
+                        }
+                        Err(_) => StromSessionMessage::BadMessage { peer_id: self.remote_peer_id }
+                    };
                 self.outbound_buffer.push_back(msg);
             })
             .ok_or_else(|| {
@@ -212,17 +356,20 @@ impl StromSession {
 
     fn poll_verification(&mut self, cx: &mut Context<'_>) -> Poll<Option<BytesMut>> {
         if !self.verification_sidecar.has_sent {
-            let msg = StromMessage::Status(
-                self.verification_sidecar
-                    .make_status_message(self.remote_peer_id)
-            );
+            let msg = StromMessage::Status(self.verification_sidecar.make_status_message(
+                self.remote_peer_id,
+                self.version,
+                &self.clock
+            ));
             // mark our status as sent.
             self.verification_sidecar.has_sent = true;
 
-            let msg = StromProtocolMessage { message_id: msg.message_id(), message: msg };
+            let msg = StromProtocolMessage::for_version(msg, self.version)
+                .expect("every version has the status");
 
             let mut buf = BytesMut::new();
-            msg.encode(&mut buf);
+            msg.encode_for(self.version, &mut buf);
+            self.bandwidth.record_outbound(msg.message_id, buf.len());
 
             return Poll::Ready(Some(buf))
         }
@@ -234,23 +381,33 @@ impl StromSession {
                 // status. if its not we want to disconnect which will be polled.
                 self.verification_sidecar.has_received = true;
 
-                msg.map(|bytes| {
-                    let msg = StromProtocolMessage::decode_message(&mut bytes.deref());
-
-                    msg.map_or(false, |msg| {
-                        // first message has to be status
-                        if let StromMessage::Status(status) = msg.message {
-                            tracing::debug!(?status, peer=?self.remote_peer_id, "decoded status message");
-
-                            self.verify_incoming_status(status)
-                        } else {
-                            false
-                        }
-                    })
+                msg.and_then(|bytes| {
+                    let msg =
+                        StromProtocolMessage::decode_message(self.version, &mut bytes.deref())
+                            .ok()??;
+
+                    // first message has to be status
+                    if let StromMessage::Status(status) = msg.message {
+                        tracing::debug!(
+                            ?status,
+                            peer=?self.remote_peer_id,
+                            "decoded status message"
+                        );
+
+                        self.verify_incoming_status(status)
+                    } else {
+                        None
+                    }
+                })
+                // if none, i.e verification failed. then we disconnect
+                .map(|identity| {
+                    if let Some(handle) = self.pending_handle.as_mut() {
+                        handle.identity = identity;
+                    }
+                    // the session is established next
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
                 })
-                // if false, i.e verification failed. then we disconnect
-                .filter(|f| *f)
-                .map(|_| Poll::Pending)
                 .unwrap_or_else(|| {
                     tracing::debug!(
                         "empty bytes when trying to verify message from peer: {:?}",
@@ -272,19 +429,72 @@ impl StromSession {
         }
     }
 
-    fn verify_incoming_status(&self, status: Status) -> bool {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
+    /// Verifies the status of the peer, returning the identity it is
+    /// accounted for by. That's the staking key of its validator if it sent a
+    /// [`StakeBinding`], its own key otherwise.
+    fn verify_incoming_status(&mut self, status: Status) -> Option<PeerId> {
+        let current_time = self.clock.unix_now().as_millis();
 
         let status_time = status.state.timestamp + STATUS_TIMESTAMP_TIMEOUT_MS;
-        let verification = status.verify();
-        if verification.is_err() {
-            return false
+        let (binding, observer) = (status.binding.clone(), status.observer);
+        let genesis = status.genesis;
+        let signer = status.verify(self.version).ok()?;
+        if current_time > status_time || signer != self.remote_peer_id {
+            return None
+        }
+        // v1 peers don't send their genesis, they aren't sent consensus messages
+        // either
+        if self.version != StromVersion::Strom1 && genesis != self.verification_sidecar.genesis {
+            tracing::debug!(peer=?self.remote_peer_id, ?genesis, "peer is on another genesis");
+            return None
         }
 
-        current_time <= status_time && verification.unwrap() == self.remote_peer_id
+        let identity = match binding {
+            Some(binding) => binding.staker(self.remote_peer_id, (current_time / 1000) as u64)?,
+            None => self.remote_peer_id
+        };
+        match admit(&self.validators, identity, observer) {
+            Some(Admission::Validator) => {}
+            Some(Admission::Observer) => {
+                if !self.verification_sidecar.observers.acquire() {
+                    tracing::debug!(peer=?self.remote_peer_id, "no slot left for the observer");
+                    return None
+                }
+                tracing::debug!(peer=?self.remote_peer_id, "admitting observer as receive-only");
+                self.receive_only = true;
+            }
+            None => {
+                tracing::debug!(peer=?self.remote_peer_id, ?identity, "peer isn't a validator");
+                return None
+            }
+        }
+
+        Some(identity)
+    }
+}
+
+/// How a peer whose status we verified takes part in the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Validator,
+    /// only sent messages, see [`Status::observer`]
+    Observer
+}
+
+/// Admits validators, and peers that aren't one if they said they observe.
+fn admit(validators: &HashSet<Address>, identity: PeerId, observer: bool) -> Option<Admission> {
+    if validators.contains(&validator_address(identity)) {
+        return Some(Admission::Validator)
+    }
+
+    observer.then_some(Admission::Observer)
+}
+
+impl Drop for StromSession {
+    fn drop(&mut self) {
+        if self.receive_only {
+            self.verification_sidecar.observers.release();
+        }
     }
 }
 
@@ -292,14 +502,15 @@ impl Stream for StromSession {
     type Item = BytesMut;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.pending_handle.is_some() && self.poll_init_connection(cx).is_some() {
-            return Poll::Pending
-        }
-
         if !self.verification_sidecar.is_verified() {
             return self.poll_verification(cx)
         }
 
+        // the manager only learns about the session once the peer is verified
+        if self.pending_handle.is_some() && self.poll_init_connection(cx).is_some() {
+            return Poll::Pending
+        }
+
         // if the session is terminate we have to send the termination message before we
         // can close
         if let Some(terminate) = self.poll_terminate_message(cx) {
@@ -321,3 +532,29 @@ impl Stream for StromSession {
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observers_take_a_slot_each() {
+        let slots = ObserverSlots::default();
+        assert!((0..MAX_OBSERVERS).all(|_| slots.acquire()));
+        assert!(!slots.clone().acquire());
+
+        slots.release();
+        assert!(slots.acquire());
+    }
+
+    #[test]
+    fn observers_are_admitted_as_receive_only() {
+        let (validator, other) = (AngstromSigner::random(), AngstromSigner::random());
+        let validators = HashSet::from([validator.address()]);
+
+        assert_eq!(admit(&validators, validator.id(), false), Some(Admission::Validator));
+        assert_eq!(admit(&validators, validator.id(), true), Some(Admission::Validator));
+        assert_eq!(admit(&validators, other.id(), true), Some(Admission::Observer));
+        assert_eq!(admit(&validators, other.id(), false), None);
+    }
+}
@@ -1,6 +1,9 @@
 use std::{collections::HashSet, sync::Arc, task::Context};
 
-use alloy::{primitives::Address, sol};
+use alloy::{
+    primitives::{keccak256, Address},
+    sol
+};
 use angstrom_types::primitive::PeerId;
 use parking_lot::RwLock;
 use reth_network::DisconnectReason;
@@ -15,14 +18,13 @@ sol! {
 pub struct StromState<DB> {
     peers_manager: PeersManager,
 
-    _db:          DB,
-    active_peers: HashSet<PeerId>,
-    validators:   Arc<RwLock<HashSet<Address>>>
+    _db:        DB,
+    validators: Arc<RwLock<HashSet<Address>>>
 }
 
 impl<DB> StromState<DB> {
     pub fn new(_db: DB, validators: Arc<RwLock<HashSet<Address>>>) -> Self {
-        Self { peers_manager: PeersManager::new(), _db, validators, active_peers: HashSet::new() }
+        Self { peers_manager: PeersManager::new(), _db, validators }
     }
 
     pub fn peers_mut(&mut self) -> &mut PeersManager {
@@ -33,27 +35,16 @@ impl<DB> StromState<DB> {
         self.validators.write_arc().insert(addr);
     }
 
+    /// Stops admitting the validator, its sessions are closed by the
+    /// [`Swarm`](crate::Swarm).
     pub fn remove_validator(&mut self, addr: Address) {
         self.validators.write_arc().remove(&addr);
-        // check active peer_id. if we are connected to this old validator
-        // we will remove them
-        if let Some(id) = self.active_peers.iter().find(|peer| {
-            let digest = alloy::primitives::keccak256(peer);
-            let this_addr = Address::from_slice(&digest[12..]);
-            this_addr == addr
-        }) {
-            self.peers_manager.remove_peer(*id);
-        }
     }
 
     pub fn validators(&self) -> Arc<RwLock<HashSet<Address>>> {
         self.validators.clone()
     }
 
-    pub fn is_active_peer(&self, peer_id: PeerId) -> bool {
-        self.active_peers.contains(&peer_id)
-    }
-
     pub fn poll(&mut self, _cx: &mut Context<'_>) -> Option<StateEvent> {
         self.peers_manager.poll().map(|action| match action {
             crate::PeerAction::Disconnect { peer_id, reason } => {
@@ -69,6 +60,12 @@ impl<DB> StromState<DB> {
     }
 }
 
+/// The address a validator is registered under, for the staking key a peer
+/// is accounted for by.
+pub fn validator_address(identity: PeerId) -> Address {
+    Address::from_slice(&keccak256(identity)[12..])
+}
+
 #[derive(Debug)]
 pub enum StateEvent {
     /// Disconnect an existing connection.
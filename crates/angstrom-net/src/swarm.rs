@@ -3,6 +3,7 @@ use std::{
     task::{Context, Poll}
 };
 
+use alloy::primitives::Address;
 use angstrom_types::primitive::PeerId;
 use futures::{Stream, StreamExt};
 
@@ -10,7 +11,7 @@ use crate::{
     session::StromSessionManager,
     state::{StateEvent, StromState},
     types::message::StromMessage,
-    SessionEvent
+    SessionEvent, StromVersion
 };
 
 #[derive(Debug)]
@@ -39,6 +40,13 @@ impl<DB: Unpin> Swarm<DB> {
         &mut self.sessions
     }
 
+    /// Stops admitting the validator and closes the sessions of its nodes,
+    /// whichever network key they connected with.
+    pub fn remove_validator(&mut self, addr: Address) {
+        self.state.remove_validator(addr);
+        self.sessions.disconnect_validator(addr);
+    }
+
     // pub(crate) fn remove_peer(&mut self, peer_id: PeerId, kind: PeerKind) {
     //     match kind {
     //         PeerKind::Basic => self.state.peers_mut().remove_peer(peer_id),
@@ -51,17 +59,23 @@ impl<DB: Unpin> Swarm<DB> {
     fn on_session_event(&mut self, event: SessionEvent) -> Option<SwarmEvent> {
         match event {
             SessionEvent::BadMessage { peer_id } => {
+                let identity = self.sessions.identity(&peer_id).unwrap_or(peer_id);
                 self.state
                     .peers_mut()
-                    .change_weight(peer_id, crate::ReputationChangeKind::BadMessage);
+                    .change_weight(identity, crate::ReputationChangeKind::BadMessage);
                 None
             }
             SessionEvent::ValidMessage { peer_id, message } => {
                 Some(SwarmEvent::ValidMessage { peer_id, msg: message.message })
             }
             SessionEvent::Disconnected { peer_id } => Some(SwarmEvent::Disconnected { peer_id }),
-            SessionEvent::SessionEstablished { peer_id, .. } => {
-                Some(SwarmEvent::SessionEstablished { peer_id })
+            SessionEvent::SessionEstablished { peer_id, identity, version, .. } => {
+                if self.state.peers_mut().connected(identity) {
+                    tracing::debug!(?peer_id, ?identity, "disconnecting banned staker");
+                    self.sessions.disconnect(peer_id, None);
+                    return None
+                }
+                Some(SwarmEvent::SessionEstablished { peer_id, version })
             }
             _ => None
         }
@@ -94,7 +108,7 @@ impl<DB: Unpin> Stream for Swarm<DB> {
 }
 
 pub enum SwarmEvent {
-    SessionEstablished { peer_id: PeerId },
+    SessionEstablished { peer_id: PeerId, version: StromVersion },
     ValidMessage { peer_id: PeerId, msg: StromMessage },
     Disconnected { peer_id: PeerId }
 }
@@ -1,30 +1,34 @@
 #![allow(missing_docs)]
 use std::{fmt::Debug, sync::Arc};
 
-use alloy::rlp::{Buf, BufMut, Decodable, Encodable};
+use alloy::{
+    primitives::B256,
+    rlp::{Buf, BufMut, Decodable, Encodable}
+};
 use angstrom_types::{
-    consensus::{PreProposal, PreProposalAggregation, Proposal},
-    orders::CancelOrderRequest,
+    consensus::{
+        BundleHandoff, KeyHandover, PreProposal, PreProposalAggregation, Proposal, ProposalShare
+    },
+    orders::{CancelAllOrdersRequest, CancelOrderRequest, OrderEnvelope},
     sol_bindings::grouped_orders::AllOrders
 };
-use reth_eth_wire::{protocol::Protocol, Capability};
+use reth_eth_wire::protocol::Protocol;
 use reth_network_p2p::error::RequestError;
 use serde::{Deserialize, Serialize};
 
+use super::{v1, MirroredOrderFlow, TracedOrder};
 use crate::errors::StromStreamError;
 /// Result alias for result of a request.
 pub type RequestResult<T> = Result<T, RequestError>;
-use crate::Status;
+use crate::{SessionResume, Status, StromVersion};
 
 /// [`MAX_MESSAGE_SIZE`] is the maximum cap on the size of a protocol message.
 // https://github.com/ethereum/go-ethereum/blob/30602163d5d8321fbc68afdcbbaf2362b2641bde/eth/protocols/eth/protocol.go#L50
 pub const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
 
-const STROM_CAPABILITY: Capability = Capability::new_static("strom", 1);
-const STROM_PROTOCOL: Protocol = Protocol::new(STROM_CAPABILITY, 5);
 /// Represents message IDs for eth protocol messages.
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StromMessageID {
     Status            = 0,
     /// Consensus
@@ -33,7 +37,81 @@ pub enum StromMessageID {
     Propose           = 3,
     /// Propagation messages that broadcast new orders to all peers
     PropagatePooledOrders = 4,
-    OrderCancellation = 5
+    OrderCancellation = 5,
+    OrderCancelAll    = 6,
+    /// Same as [`StromMessageID::PropagatePooledOrders`] but with every order
+    /// wrapped in a versioned envelope
+    PropagateVersionedOrders = 7,
+    /// Consensus, hands the leaders bundle to the backup submitters
+    BundleHandoff     = 8,
+    /// Same as [`StromMessageID::PropagateVersionedOrders`] but with the
+    /// propagation trace of every order
+    PropagateTracedOrders = 9,
+    /// Hashes of new orders, for peers that aren't pushed the full orders
+    AnnounceOrders    = 10,
+    /// Pulls announced orders the peer hasn't seen yet
+    RequestOrders     = 11,
+    /// Consensus, resumes a session that dropped
+    ResumeSession     = 12,
+    /// Order flow a node mirrors to its standby
+    MirrorOrderFlow   = 13,
+    /// Consensus, hands a validator over to a new signing key
+    KeyHandover       = 14,
+    /// Consensus, a committee member co-signing the proposal
+    ProposalShare     = 15,
+    /// Asks the peer to hold its gossip, sent with the consensus messages so
+    /// it isn't queued behind the gossip it holds back
+    Backoff           = 16
+}
+
+impl StromMessageID {
+    /// Returns the priority class the message is sent with.
+    pub const fn class(&self) -> StromMessageClass {
+        match self {
+            StromMessageID::Status
+            | StromMessageID::PrePropose
+            | StromMessageID::PreProposeAgg
+            | StromMessageID::Propose
+            | StromMessageID::BundleHandoff
+            | StromMessageID::KeyHandover
+            | StromMessageID::ProposalShare
+            | StromMessageID::Backoff => StromMessageClass::Consensus,
+            StromMessageID::PropagatePooledOrders
+            | StromMessageID::OrderCancellation
+            | StromMessageID::OrderCancelAll
+            | StromMessageID::PropagateVersionedOrders
+            | StromMessageID::PropagateTracedOrders
+            | StromMessageID::AnnounceOrders
+            | StromMessageID::RequestOrders
+            | StromMessageID::ResumeSession => StromMessageClass::Gossip,
+            StromMessageID::MirrorOrderFlow => StromMessageClass::Mirror
+        }
+    }
+}
+
+/// Priority classes for outbound messages. Within a session, queued
+/// [`StromMessageClass::Consensus`] messages are always sent before any queued
+/// [`StromMessageClass::Mirror`] messages, and those before any queued
+/// [`StromMessageClass::Gossip`] messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StromMessageClass {
+    /// Status and consensus round messages
+    Consensus,
+    /// Order flow mirrored to a standby. Never throttled or dropped, a standby
+    /// that misses some of it takes over with orders missing
+    Mirror,
+    /// Order propagation and cancellation
+    Gossip
+}
+
+impl StromMessageClass {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            StromMessageClass::Consensus => "consensus",
+            StromMessageClass::Mirror => "mirror",
+            StromMessageClass::Gossip => "gossip"
+        }
+    }
 }
 
 impl Encodable for StromMessageID {
@@ -56,6 +134,17 @@ impl Decodable for StromMessageID {
             3 => StromMessageID::PrePropose,
             4 => StromMessageID::PropagatePooledOrders,
             5 => StromMessageID::OrderCancellation,
+            6 => StromMessageID::OrderCancelAll,
+            7 => StromMessageID::PropagateVersionedOrders,
+            8 => StromMessageID::BundleHandoff,
+            9 => StromMessageID::PropagateTracedOrders,
+            10 => StromMessageID::AnnounceOrders,
+            11 => StromMessageID::RequestOrders,
+            12 => StromMessageID::ResumeSession,
+            13 => StromMessageID::MirrorOrderFlow,
+            14 => StromMessageID::KeyHandover,
+            15 => StromMessageID::ProposalShare,
+            16 => StromMessageID::Backoff,
             _ => return Err(alloy::rlp::Error::Custom("Invalid message ID"))
         };
         buf.advance(1);
@@ -71,12 +160,49 @@ pub struct StromProtocolMessage {
 }
 
 impl StromProtocolMessage {
-    pub fn decode_message(buf: &mut &[u8]) -> Result<Self, StromStreamError> {
+    /// The message as it's sent to a peer on `version`, none if the version
+    /// has no counterpart of it.
+    pub fn for_version(message: StromMessage, version: StromVersion) -> Option<Self> {
+        let message = match version {
+            StromVersion::Strom1 => v1::downgrade(message)?,
+            StromVersion::Strom2 => message
+        };
+
+        Some(StromProtocolMessage { message_id: message.message_id(), message })
+    }
+
+    /// Decodes a message of a peer on `version`, none if it's one we don't
+    /// exchange with peers on that version.
+    pub fn decode_message(
+        version: StromVersion,
+        buf: &mut &[u8]
+    ) -> Result<Option<Self>, StromStreamError> {
         let message_id: StromMessageID = Decodable::decode(buf)?;
+        if !version.exchanges(message_id) {
+            return Ok(None)
+        }
+
         let data: Vec<u8> = Decodable::decode(buf)?;
-        let message: StromMessage = bincode::deserialize(&data).unwrap();
+        let message = match version {
+            StromVersion::Strom1 => v1::decode(message_id, &data)?,
+            StromVersion::Strom2 => {
+                bincode::deserialize(&data).map_err(|_| StromStreamError::InvalidMessageError)?
+            }
+        };
+
+        Ok(Some(StromProtocolMessage { message_id, message }))
+    }
 
-        Ok(StromProtocolMessage { message_id, message })
+    /// Encodes a message [`StromProtocolMessage::for_version`] returned for a
+    /// peer on `version`.
+    pub fn encode_for(&self, version: StromVersion, out: &mut dyn BufMut) {
+        match version {
+            StromVersion::Strom1 => {
+                Encodable::encode(&self.message_id, out);
+                Encodable::encode(&v1::encode(&self.message), out);
+            }
+            StromVersion::Strom2 => self.encode(out)
+        }
     }
 }
 
@@ -89,9 +215,9 @@ impl Encodable for StromProtocolMessage {
 }
 
 impl StromProtocolMessage {
-    /// Returns the protocol for the `Strom` protocol.
-    pub const fn protocol() -> Protocol {
-        STROM_PROTOCOL
+    /// Returns the protocol for the `Strom` protocol of the given version.
+    pub const fn protocol(version: StromVersion) -> Protocol {
+        version.protocol()
     }
 }
 
@@ -121,7 +247,36 @@ pub enum StromMessage {
 
     /// Propagation messages that broadcast new orders to all peers
     PropagatePooledOrders(Vec<AllOrders>),
-    OrderCancellation(CancelOrderRequest)
+    OrderCancellation(CancelOrderRequest),
+    /// Cancels all orders of a user, instead of sending one cancellation per
+    /// order
+    OrderCancelAll(CancelAllOrdersRequest),
+    /// Propagation of orders that survives changes to the order layout, older
+    /// peers still send [`StromMessage::PropagatePooledOrders`]
+    PropagateVersionedOrders(Vec<OrderEnvelope>),
+    /// The leaders bundle for the backup submitters of the round
+    BundleHandoff(BundleHandoff),
+    /// Propagation of orders with their trace, only sent by nodes that opted
+    /// into propagation tracing
+    PropagateTracedOrders(Vec<TracedOrder>),
+    /// Hashes of new orders the sender has, peers pull the ones they are
+    /// missing with [`StromMessage::RequestOrders`]
+    AnnounceOrders(Vec<B256>),
+    /// Asks for the announced orders with these hashes, they are sent as
+    /// [`StromMessage::PropagateVersionedOrders`]
+    RequestOrders(Vec<B256>),
+    /// Offers the receiver a token to resume the session with if it drops, and
+    /// resumes the session of the receiver that dropped before
+    ResumeSession(SessionResume),
+    /// Order flow of the sender, for the standby it mirrors it to
+    MirrorOrderFlow(MirroredOrderFlow),
+    /// Announces the key a validator signs with from the activation block on
+    KeyHandover(KeyHandover),
+    /// Signature of a committee member over the proposal it verified
+    ProposalShare(ProposalShare),
+    /// Asks the receiver to hold its gossip to us for this many milliseconds,
+    /// sent once it went over our inbound cap
+    Backoff(u64)
 }
 impl StromMessage {
     /// Returns the message's ID.
@@ -132,7 +287,18 @@ impl StromMessage {
             StromMessage::PreProposeAgg(_) => StromMessageID::PreProposeAgg,
             StromMessage::Propose(_) => StromMessageID::Propose,
             StromMessage::PropagatePooledOrders(_) => StromMessageID::PropagatePooledOrders,
-            StromMessage::OrderCancellation(_) => StromMessageID::OrderCancellation
+            StromMessage::OrderCancellation(_) => StromMessageID::OrderCancellation,
+            StromMessage::OrderCancelAll(_) => StromMessageID::OrderCancelAll,
+            StromMessage::PropagateVersionedOrders(_) => StromMessageID::PropagateVersionedOrders,
+            StromMessage::BundleHandoff(_) => StromMessageID::BundleHandoff,
+            StromMessage::PropagateTracedOrders(_) => StromMessageID::PropagateTracedOrders,
+            StromMessage::AnnounceOrders(_) => StromMessageID::AnnounceOrders,
+            StromMessage::RequestOrders(_) => StromMessageID::RequestOrders,
+            StromMessage::ResumeSession(_) => StromMessageID::ResumeSession,
+            StromMessage::MirrorOrderFlow(_) => StromMessageID::MirrorOrderFlow,
+            StromMessage::KeyHandover(_) => StromMessageID::KeyHandover,
+            StromMessage::ProposalShare(_) => StromMessageID::ProposalShare,
+            StromMessage::Backoff(_) => StromMessageID::Backoff
         }
     }
 }
@@ -153,7 +319,10 @@ pub enum StromBroadcastMessage {
     PreProposeAgg(Arc<PreProposalAggregation>),
     // Order Broadcast
     PropagatePooledOrders(Arc<Vec<AllOrders>>),
-    OrderCancellation(Arc<CancelOrderRequest>)
+    OrderCancellation(Arc<CancelOrderRequest>),
+    OrderCancelAll(Arc<CancelAllOrdersRequest>),
+    PropagateVersionedOrders(Arc<Vec<OrderEnvelope>>),
+    BundleHandoff(Arc<BundleHandoff>)
 }
 
 impl StromBroadcastMessage {
@@ -166,7 +335,12 @@ impl StromBroadcastMessage {
             StromBroadcastMessage::PropagatePooledOrders(_) => {
                 StromMessageID::PropagatePooledOrders
             }
-            StromBroadcastMessage::OrderCancellation(_) => StromMessageID::OrderCancellation
+            StromBroadcastMessage::OrderCancellation(_) => StromMessageID::OrderCancellation,
+            StromBroadcastMessage::OrderCancelAll(_) => StromMessageID::OrderCancelAll,
+            StromBroadcastMessage::PropagateVersionedOrders(_) => {
+                StromMessageID::PropagateVersionedOrders
+            }
+            StromBroadcastMessage::BundleHandoff(_) => StromMessageID::BundleHandoff
         }
     }
 }
@@ -0,0 +1,13 @@
+//! Mirroring of the order flow of a node to its hot standby.
+use angstrom_types::orders::{CancelAllOrdersRequest, CancelOrderRequest, OrderEnvelope};
+use serde::{Deserialize, Serialize};
+
+/// Order flow the primary accepted, streamed to its standby so that the
+/// standby takes over with a warm pool. The standby only takes it from its
+/// primary, whichever peer sends it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MirroredOrderFlow {
+    Orders(Vec<OrderEnvelope>),
+    Cancel(CancelOrderRequest),
+    CancelAll(CancelAllOrdersRequest)
+}
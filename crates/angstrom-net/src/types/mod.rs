@@ -10,3 +10,11 @@ pub mod broadcast;
 
 pub mod status;
 pub use status::*;
+
+pub mod trace;
+pub use trace::*;
+
+pub mod mirror;
+pub use mirror::*;
+
+mod v1;
@@ -1,17 +1,14 @@
-use std::{
-    fmt::{Debug, Display},
-    time::{SystemTime, UNIX_EPOCH}
-};
+use std::fmt::{Debug, Display};
 
 use alloy::{
-    primitives::{keccak256, FixedBytes},
+    primitives::{keccak256, FixedBytes, B256},
     rlp::{BufMut, BytesMut},
-    signers::Signature
+    signers::{Signature, SignerSync}
 };
-use angstrom_types::primitive::{AngstromSigner, PeerId};
+use angstrom_types::primitive::{AngstromSigner, PeerId, SigningDomain};
 use serde::{Deserialize, Serialize};
 
-use crate::StatusBuilder;
+use crate::{StatusBuilder, StromVersion};
 
 /// The status message is used in the strom protocol to ensure that the
 /// connecting peer is using the same protocol version and is on the same chain.
@@ -21,7 +18,17 @@ use crate::StatusBuilder;
 pub struct Status {
     pub state:     StatusState,
     /// the signature over all state fields concatenated
-    pub signature: Signature
+    pub signature: Signature,
+    /// set by nodes whose validator stakes with another key than the node
+    /// signs with
+    pub binding:   Option<StakeBinding>,
+    /// set by observers, nodes that follow the rounds without being a
+    /// validator. validators admit them as receive-only peers. signed along
+    /// with the state on v2
+    pub observer:  bool,
+    /// hash of the protocol genesis the node runs with, peers on another one
+    /// aren't admitted
+    pub genesis:   B256
 }
 
 impl Status {
@@ -30,9 +37,9 @@ impl Status {
         StatusBuilder::new(peer_id)
     }
 
-    /// returns true if the signature is valid
-    pub fn verify(self) -> Result<PeerId, alloy::signers::Error> {
-        let message = self.state.to_message();
+    /// returns true if the signature is valid for a session on `version`
+    pub fn verify(self, version: StromVersion) -> Result<PeerId, alloy::signers::Error> {
+        let message = self.state.to_message(version, self.genesis, self.observer);
         let key = self.signature.recover_from_prehash(&message).unwrap();
 
         Ok(AngstromSigner::public_key_to_peer_id(&key))
@@ -63,6 +70,55 @@ impl Debug for Status {
     }
 }
 
+/// How long a [`StakeBinding`] vouches for the node's key, in seconds.
+pub const STAKE_BINDING_TTL_SECS: u64 = 5 * 60;
+
+/// The staking key of a validator vouching for the network key of its node.
+///
+/// Peers account for the node by the staking key, so a validator keeps its
+/// reputation when it rotates the network key and can't shed a bad one by
+/// generating a fresh key either. Nodes sign a fresh binding for every status,
+/// so a network key the validator dropped is vouched for until the last
+/// binding it was sent with expires at most.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeBinding {
+    /// network key of the node
+    pub peer:      PeerId,
+    /// unix time in seconds from which the binding no longer vouches for
+    /// `peer`
+    pub expires:   u64,
+    /// signature of the staking key over `peer` and `expires`
+    pub signature: Signature
+}
+
+impl StakeBinding {
+    pub fn new(staking_key: &AngstromSigner, peer: PeerId, expires: u64) -> Self {
+        let signature = staking_key
+            .sign_hash_sync(&Self::signing_hash(peer, expires))
+            .unwrap();
+        Self { peer, expires, signature }
+    }
+
+    /// The staking key that vouches for `peer` at the unix time `now`, none
+    /// if the binding is for another node or expired.
+    pub fn staker(&self, peer: PeerId, now: u64) -> Option<PeerId> {
+        if self.peer != peer || now >= self.expires {
+            return None
+        }
+        let key = self
+            .signature
+            .recover_from_prehash(&Self::signing_hash(peer, self.expires))
+            .ok()?;
+
+        Some(AngstromSigner::public_key_to_peer_id(&key))
+    }
+
+    fn signing_hash(peer: PeerId, expires: u64) -> B256 {
+        SigningDomain::StakeBinding
+            .signing_hash(&[peer.as_slice(), &expires.to_be_bytes()].concat())
+    }
+}
+
 #[derive(Default, Copy, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StatusState {
     /// The current protocol version.
@@ -89,22 +145,95 @@ impl StatusState {
         self
     }
 
-    /// creates message for signing.
-    /// keccak256(version || peer || timestamp)
-    pub fn to_message(&self) -> FixedBytes<32> {
-        let mut buf = BytesMut::with_capacity(113);
+    /// creates message for signing on a session of `version`.
+    /// keccak256(status tag hash || version || chain || peer || timestamp ||
+    /// genesis || observer)
+    ///
+    /// v1 peers sign it without the tag hash, the genesis and the observer
+    /// flag, as before the status was domain separated.
+    pub fn to_message(
+        &self,
+        version: StromVersion,
+        genesis: B256,
+        observer: bool
+    ) -> FixedBytes<32> {
+        let mut buf = BytesMut::with_capacity(146);
         buf.put_u8(self.version);
         buf.put_u64(self.chain);
         buf.put(self.peer.0.as_ref());
         buf.put_u128(self.timestamp);
 
-        keccak256(buf)
+        match version {
+            StromVersion::Strom1 => keccak256(buf),
+            StromVersion::Strom2 => {
+                buf.put(genesis.as_slice());
+                buf.put_u8(observer as u8);
+                SigningDomain::Status.signing_hash(&buf)
+            }
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use angstrom_utils::clock::{Clock, LogicalClock};
+
+    use super::*;
+
+    #[test]
+    fn binding_only_vouches_for_its_node() {
+        let (staking_key, node) = (AngstromSigner::random(), AngstromSigner::random());
+        let binding = StakeBinding::new(&staking_key, node.id(), 100);
+
+        assert_eq!(binding.staker(node.id(), 99), Some(staking_key.id()));
+        assert_eq!(binding.staker(PeerId::random(), 99), None);
+        assert_eq!(binding.staker(node.id(), 100), None);
+
+        // the expiry is signed over
+        let extended = StakeBinding { expires: 200, ..binding };
+        assert_ne!(extended.staker(node.id(), 100), Some(staking_key.id()));
+    }
+
+    #[test]
+    fn status_is_signed_over_the_digest_of_the_session_version() {
+        let signer = AngstromSigner::random();
+        for version in StromVersion::ALL {
+            let status = StatusBuilder::new(PeerId::random())
+                .protocol(version)
+                .build(&signer);
+            assert_eq!(status.clone().verify(version).unwrap(), signer.id());
+
+            let other = StromVersion::ALL
+                .into_iter()
+                .find(|other| *other != version);
+            assert_ne!(status.verify(other.unwrap()).unwrap(), signer.id());
+        }
+    }
+
+    #[test]
+    fn status_is_signed_over_the_genesis_and_observer_flag() {
+        let signer = AngstromSigner::random();
+        let status = StatusBuilder::new(PeerId::random())
+            .genesis(B256::repeat_byte(1))
+            .build(&signer);
+        assert_eq!(status.clone().verify(StromVersion::Strom2).unwrap(), signer.id());
+
+        let swapped = Status { genesis: B256::repeat_byte(2), ..status.clone() };
+        assert_ne!(swapped.verify(StromVersion::Strom2).unwrap(), signer.id());
+        let observing = Status { observer: true, ..status };
+        assert_ne!(observing.verify(StromVersion::Strom2).unwrap(), signer.id());
+    }
+
+    #[test]
+    fn status_is_stamped_by_its_clock() {
+        let clock = LogicalClock::new(Duration::from_secs(1_000));
+        clock.advance(Duration::from_millis(250));
 
-    pub fn timestamp_now(&mut self) {
-        self.timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
+        let status = StatusBuilder::new(PeerId::random())
+            .clock(Clock::logical(clock))
+            .build(&AngstromSigner::random());
+        assert_eq!(status.state.timestamp, 1_000_250);
     }
 }
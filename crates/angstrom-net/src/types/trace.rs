@@ -0,0 +1,71 @@
+//! Opt-in tracing of how fast orders spread through the network.
+use std::time::Duration;
+
+use angstrom_types::orders::OrderEnvelope;
+use serde::{Deserialize, Serialize};
+
+/// Traces aren't relayed past this many hops.
+pub const MAX_TRACED_HOPS: u8 = 16;
+
+/// When an order entered the network and how often it was relayed since.
+///
+/// A trace without hops tells the receiving peer that the order entered the
+/// network at the sender, which is why nodes only trace orders when their
+/// operator opts in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropagationTrace {
+    /// unix time (ms) the order entered the network at
+    pub origin_ms: u64,
+    /// peers that relayed the order before the sender
+    pub hops:      u8
+}
+
+impl PropagationTrace {
+    pub fn new(origin: Duration) -> Self {
+        Self { origin_ms: origin.as_millis() as u64, hops: 0 }
+    }
+
+    /// The trace to relay the order with, none once it went too far.
+    pub fn relayed(&self) -> Option<Self> {
+        let hops = self
+            .hops
+            .checked_add(1)
+            .filter(|hops| *hops < MAX_TRACED_HOPS)?;
+        Some(Self { hops, ..*self })
+    }
+
+    /// Time the order took to arrive at `now`, zero if the clocks of the nodes
+    /// disagree by more than that.
+    pub fn delay(&self, now: Duration) -> Duration {
+        now.saturating_sub(Duration::from_millis(self.origin_ms))
+    }
+}
+
+/// An order propagated along with its trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TracedOrder {
+    pub order: OrderEnvelope,
+    pub trace: PropagationTrace
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_relaying_at_the_hop_limit() {
+        let mut trace = PropagationTrace::new(Duration::from_secs(1));
+        for hops in 1..MAX_TRACED_HOPS {
+            trace = trace.relayed().unwrap();
+            assert_eq!(trace.hops, hops);
+        }
+        assert_eq!(trace.relayed(), None);
+    }
+
+    #[test]
+    fn delay_is_never_negative() {
+        let trace = PropagationTrace::new(Duration::from_millis(1_500));
+        assert_eq!(trace.delay(Duration::from_millis(1_750)), Duration::from_millis(250));
+        assert_eq!(trace.delay(Duration::from_millis(1_000)), Duration::ZERO);
+    }
+}
@@ -0,0 +1,134 @@
+//! The messages of [`StromVersion::Strom1`](crate::StromVersion) in the layout
+//! v1 peers exchange them in.
+//!
+//! v1 encodes its messages with bincode like we do, so a message is the index
+//! of its variant, which is its [`StromMessageID`], followed by its fields.
+//! Only the status differs from ours, it has no stake binding, observer flag or
+//! genesis.
+use alloy::signers::Signature;
+use angstrom_types::{orders::OrderEnvelope, sol_bindings::grouped_orders::AllOrders};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::StromStreamError, Status, StatusState, StromMessage, StromMessageID};
+
+/// [`Status`] of v1.
+#[derive(Serialize, Deserialize)]
+struct StatusV1 {
+    state:     StatusState,
+    signature: Signature
+}
+
+/// The message we send a v1 peer instead, none if v1 has no counterpart.
+/// Orders are sent without their envelope and trace.
+pub(crate) fn downgrade(message: StromMessage) -> Option<StromMessage> {
+    match message {
+        StromMessage::Status(_)
+        | StromMessage::PropagatePooledOrders(_)
+        | StromMessage::OrderCancellation(_) => Some(message),
+        StromMessage::PropagateVersionedOrders(envelopes) => {
+            Some(StromMessage::PropagatePooledOrders(open(envelopes.iter())))
+        }
+        StromMessage::PropagateTracedOrders(traced) => Some(StromMessage::PropagatePooledOrders(
+            open(traced.iter().map(|traced| &traced.order))
+        )),
+        _ => None
+    }
+}
+
+fn open<'a>(envelopes: impl Iterator<Item = &'a OrderEnvelope>) -> Vec<AllOrders> {
+    envelopes
+        .filter_map(|envelope| envelope.open().ok())
+        .collect()
+}
+
+/// Encodes a message [`downgrade`] returned.
+pub(crate) fn encode(message: &StromMessage) -> Vec<u8> {
+    let id = message.message_id() as u32;
+    match message {
+        StromMessage::Status(status) => {
+            bincode::serialize(&(id, StatusV1 { state: status.state, signature: status.signature }))
+        }
+        StromMessage::PropagatePooledOrders(orders) => bincode::serialize(&(id, orders)),
+        StromMessage::OrderCancellation(cancel) => bincode::serialize(&(id, cancel)),
+        message => unreachable!("{:?} isn't a v1 message", message.message_id())
+    }
+    .unwrap()
+}
+
+/// Decodes a message of a v1 peer the version exchanges.
+pub(crate) fn decode(id: StromMessageID, data: &[u8]) -> Result<StromMessage, StromStreamError> {
+    let message = match id {
+        StromMessageID::Status => decode_variant::<StatusV1>(id, data).map(|status| {
+            StromMessage::Status(Status {
+                state:     status.state,
+                signature: status.signature,
+                binding:   None,
+                observer:  false,
+                genesis:   Default::default()
+            })
+        }),
+        StromMessageID::PropagatePooledOrders => {
+            decode_variant(id, data).map(StromMessage::PropagatePooledOrders)
+        }
+        StromMessageID::OrderCancellation => {
+            decode_variant(id, data).map(StromMessage::OrderCancellation)
+        }
+        _ => None
+    };
+
+    message.ok_or(StromStreamError::InvalidMessageError)
+}
+
+fn decode_variant<T: for<'de> Deserialize<'de>>(id: StromMessageID, data: &[u8]) -> Option<T> {
+    let (variant, fields) = bincode::deserialize::<(u32, T)>(data).ok()?;
+    (variant == id as u32).then_some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::B256, rlp::BytesMut};
+    use angstrom_types::primitive::{AngstromSigner, PeerId};
+
+    use super::*;
+    use crate::{StakeBinding, StatusBuilder, StromProtocolMessage, StromVersion};
+
+    #[test]
+    fn status_is_exchanged_without_the_binding_and_genesis() {
+        let (signer, staking_key) = (AngstromSigner::random(), AngstromSigner::random());
+        let status = StatusBuilder::new(PeerId::random())
+            .stake_binding(Some(StakeBinding::new(&staking_key, signer.id(), u64::MAX)))
+            .genesis(B256::repeat_byte(1))
+            .protocol(StromVersion::Strom1)
+            .build(&signer);
+
+        let message = downgrade(StromMessage::Status(status.clone())).unwrap();
+        let decoded = decode(StromMessageID::Status, &encode(&message)).unwrap();
+        assert_eq!(status.clone().verify(StromVersion::Strom1).unwrap(), signer.id());
+        assert_eq!(
+            decoded,
+            StromMessage::Status(Status { binding: None, genesis: B256::ZERO, ..status })
+        );
+    }
+
+    #[test]
+    fn v2_only_messages_are_not_sent() {
+        assert_eq!(downgrade(StromMessage::AnnounceOrders(vec![])), None);
+        assert_eq!(downgrade(StromMessage::Backoff(1_000)), None);
+        assert_eq!(
+            downgrade(StromMessage::PropagateVersionedOrders(vec![])),
+            Some(StromMessage::PropagatePooledOrders(vec![]))
+        );
+    }
+
+    #[test]
+    fn consensus_messages_of_v1_peers_are_ignored() {
+        let message = StromMessage::PrePropose(Default::default());
+        assert!(StromProtocolMessage::for_version(message.clone(), StromVersion::Strom1).is_none());
+
+        let message = StromProtocolMessage::for_version(message, StromVersion::Strom2).unwrap();
+        let mut buf = BytesMut::new();
+        message.encode_for(StromVersion::Strom2, &mut buf);
+        let decoded = StromProtocolMessage::decode_message(StromVersion::Strom1, &mut buf.as_ref());
+        assert!(matches!(decoded, Ok(None)));
+    }
+}
@@ -1,29 +1,75 @@
-//! Support for representing the version of the `eth`.
+//! Support for representing the version of the `strom`.
 //! [`Capability`](crate::capability::Capability)
 //! and [Protocol](crate::protocol::Protocol).
 
 use std::str::FromStr;
 
+use reth_eth_wire::{protocol::Protocol, Capability};
+
+use crate::StromMessageID;
+
+/// Name of the capability every version of the strom protocol is announced
+/// under.
+pub const STROM_CAPABILITY_NAME: &str = "strom";
+
 /// Error thrown when failed to parse a valid [`StromVersion`].
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
-#[error("Unknown eth protocol version: {0}")]
+#[error("Unknown strom protocol version: {0}")]
 pub struct ParseVersionError(String);
 
-/// The `eth` protocol version.
+/// The `strom` protocol version, negotiated as the version of the `strom`
+/// capability. Nodes advertise every version they speak and the session runs
+/// on the highest one both sides share.
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StromVersion {
-    /// The `strom` protocol version 0
-    Strom0 = 0
+    /// The `strom` protocol version 1, the status, the consensus messages and
+    /// the gossip of orders and their cancellations
+    Strom1 = 1,
+    /// The `strom` protocol version 2. Adds the messages from
+    /// [`StromMessageID::OrderCancelAll`] on, the stake binding of the status
+    /// and signs the consensus messages over their canonical encoding, with
+    /// the solutions of a proposal in their canonical order
+    Strom2 = 2
 }
 
 impl StromVersion {
-    /// The latest known eth version
-    pub const LATEST: StromVersion = StromVersion::Strom0;
+    /// Every version we speak, the oldest first
+    pub const ALL: [StromVersion; 2] = [StromVersion::Strom1, StromVersion::Strom2];
+    /// The latest known strom version
+    pub const LATEST: StromVersion = StromVersion::Strom2;
 
     /// Returns the total number of messages the protocol version supports.
     pub const fn total_messages(&self) -> u8 {
-        7
+        match self {
+            // v1 peers reserve one message less than they have
+            StromVersion::Strom1 => 5,
+            StromVersion::Strom2 => StromMessageID::Backoff as u8 + 1
+        }
+    }
+
+    pub const fn capability(&self) -> Capability {
+        Capability::new_static(STROM_CAPABILITY_NAME, *self as usize)
+    }
+
+    pub const fn protocol(&self) -> Protocol {
+        Protocol::new(self.capability(), self.total_messages())
+    }
+
+    /// Whether the message is exchanged with peers on this version. Consensus
+    /// messages of v1 are signed over their bincode encoding, which we don't
+    /// verify, so only the status and the order gossip are exchanged with v1
+    /// peers.
+    pub const fn exchanges(&self, id: StromMessageID) -> bool {
+        match self {
+            StromVersion::Strom1 => matches!(
+                id,
+                StromMessageID::Status
+                    | StromMessageID::PropagatePooledOrders
+                    | StromMessageID::OrderCancellation
+            ),
+            StromVersion::Strom2 => true
+        }
     }
 }
 
@@ -34,7 +80,8 @@ impl TryFrom<&str> for StromVersion {
     #[inline]
     fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
-            "0" => Ok(StromVersion::Strom0),
+            "1" => Ok(StromVersion::Strom1),
+            "2" => Ok(StromVersion::Strom2),
             _ => Err(ParseVersionError(s.to_string()))
         }
     }
@@ -47,7 +94,8 @@ impl TryFrom<u8> for StromVersion {
     #[inline]
     fn try_from(u: u8) -> Result<Self, Self::Error> {
         match u {
-            0 => Ok(StromVersion::Strom0),
+            1 => Ok(StromVersion::Strom1),
+            2 => Ok(StromVersion::Strom2),
             _ => Err(ParseVersionError(u.to_string()))
         }
     }
@@ -73,7 +121,8 @@ impl From<StromVersion> for &'static str {
     #[inline]
     fn from(v: StromVersion) -> &'static str {
         match v {
-            StromVersion::Strom0 => "0"
+            StromVersion::Strom1 => "1",
+            StromVersion::Strom2 => "2"
         }
     }
 }
@@ -83,16 +132,34 @@ mod test {
     use std::{convert::TryFrom, string::ToString};
 
     use super::{ParseVersionError, StromVersion};
+    use crate::StromMessageID;
 
     #[test]
     fn test_eth_version_try_from_str() {
-        assert_eq!(StromVersion::Strom0, StromVersion::try_from("0").unwrap());
+        assert_eq!(StromVersion::Strom1, StromVersion::try_from("1").unwrap());
+        assert_eq!(StromVersion::Strom2, StromVersion::try_from("2").unwrap());
         assert_eq!(Err(ParseVersionError("69".to_string())), StromVersion::try_from("69"));
     }
 
     #[test]
     fn test_eth_version_from_str() {
-        assert_eq!(StromVersion::Strom0, "0".parse().unwrap());
+        assert_eq!(StromVersion::Strom2, "2".parse().unwrap());
         assert_eq!(Err(ParseVersionError("69".to_string())), "69".parse::<StromVersion>());
     }
+
+    #[test]
+    fn consensus_is_only_exchanged_on_v2() {
+        // v1 proposals aren't in the canonical order of their solutions
+        for id in [
+            StromMessageID::PrePropose,
+            StromMessageID::PreProposeAgg,
+            StromMessageID::Propose,
+            StromMessageID::ProposalShare,
+            // v1 peers don't know they can be asked to back off
+            StromMessageID::Backoff
+        ] {
+            assert!(!StromVersion::Strom1.exchanges(id));
+            assert!(StromVersion::Strom2.exchanges(id));
+        }
+    }
 }
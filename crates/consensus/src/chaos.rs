@@ -0,0 +1,136 @@
+//! Faults the testnet scenarios inject into consensus, so that leader failover
+//! and the evidence collected on it can be drilled deterministically. Only
+//! built with the `chaos` feature.
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, Stream, StreamExt};
+use parking_lot::Mutex;
+
+use crate::rounds::ConsensusMessage;
+
+#[derive(Debug, Default)]
+struct Faults {
+    drop_next_proposal: bool,
+    aggregation_delay:  Duration,
+    refuse_to_sign:     bool
+}
+
+/// Arms the faults of a node, shared between the scenario and the node's
+/// consensus.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosHooks {
+    faults: Arc<Mutex<Faults>>
+}
+
+impl ChaosHooks {
+    /// Drops the next proposal we lead, as if the leader went down before
+    /// broadcasting it.
+    pub fn drop_next_proposal(&self) {
+        self.faults.lock().drop_next_proposal = true;
+    }
+
+    /// Holds back our pre-proposal aggregations for `delay`, zero sends them
+    /// right away again.
+    pub fn delay_aggregation(&self, delay: Duration) {
+        self.faults.lock().aggregation_delay = delay;
+    }
+
+    /// Stops us from sending anything we sign until it's turned off again.
+    pub fn refuse_to_sign(&self, refuse: bool) {
+        self.faults.lock().refuse_to_sign = refuse;
+    }
+
+    /// Disarms every fault.
+    pub fn clear(&self) {
+        *self.faults.lock() = Faults::default();
+    }
+}
+
+/// Applies the armed [`ChaosHooks`] to the messages consensus sends.
+#[derive(Default)]
+pub(crate) struct Chaos {
+    hooks:   ChaosHooks,
+    delayed: FuturesUnordered<BoxFuture<'static, ConsensusMessage>>
+}
+
+impl Chaos {
+    pub(crate) fn new(hooks: ChaosHooks) -> Self {
+        Self { hooks, delayed: FuturesUnordered::new() }
+    }
+
+    pub(crate) fn hooks(&self) -> &ChaosHooks {
+        &self.hooks
+    }
+
+    /// The message to send in place of `msg`, if any.
+    pub(crate) fn intercept(&mut self, msg: ConsensusMessage) -> Option<ConsensusMessage> {
+        let mut faults = self.hooks.faults.lock();
+        // every consensus message carries our signature
+        if faults.refuse_to_sign {
+            tracing::warn!(?msg, "chaos: refusing to sign");
+            return None
+        }
+
+        match msg {
+            ConsensusMessage::PropagateProposal(_) if faults.drop_next_proposal => {
+                tracing::warn!("chaos: dropping the proposal");
+                faults.drop_next_proposal = false;
+                None
+            }
+            ConsensusMessage::PropagatePreProposalAgg(_) if !faults.aggregation_delay.is_zero() => {
+                let delay = faults.aggregation_delay;
+                tracing::warn!(?delay, "chaos: delaying the pre-proposal aggregation");
+                self.delayed
+                    .push(tokio::time::sleep(delay).map(move |_| msg).boxed());
+                None
+            }
+            msg => Some(msg)
+        }
+    }
+}
+
+impl Stream for Chaos {
+    type Item = ConsensusMessage;
+
+    /// Delayed messages once they are due.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.delayed.poll_next_unpin(cx) {
+            Poll::Ready(Some(msg)) => Poll::Ready(Some(msg)),
+            _ => Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::consensus::PreProposal;
+    use testing_tools::type_generator::consensus::proposal::ProposalBuilder;
+
+    use super::*;
+
+    #[test]
+    fn drops_only_the_next_proposal() {
+        let mut chaos = Chaos::default();
+        let proposal = || ConsensusMessage::PropagateProposal(ProposalBuilder::new().build());
+
+        chaos.hooks().drop_next_proposal();
+        assert!(chaos.intercept(proposal()).is_none());
+        assert!(chaos.intercept(proposal()).is_some());
+    }
+
+    #[test]
+    fn refuses_to_sign_until_turned_off() {
+        let mut chaos = Chaos::default();
+        let pre_proposal = || ConsensusMessage::PropagatePreProposal(PreProposal::default());
+
+        chaos.hooks().refuse_to_sign(true);
+        assert!(chaos.intercept(pre_proposal()).is_none());
+        chaos.hooks().clear();
+        assert!(chaos.intercept(pre_proposal()).is_some());
+    }
+}
@@ -1,14 +1,24 @@
+#[cfg(any(test, feature = "chaos"))]
+mod chaos;
 mod leader_selection;
 mod manager;
+mod timing;
+#[cfg(feature = "testnet")]
+mod verifications;
 
+#[cfg(any(test, feature = "chaos"))]
+pub use chaos::ChaosHooks;
 pub use manager::*;
+pub use timing::*;
+#[cfg(feature = "testnet")]
+pub use verifications::{ProposalVerification, ProposalVerifications};
 pub mod rounds;
 
 use std::pin::Pin;
 
 use angstrom_types::consensus::{PreProposal, Proposal};
 use futures::Stream;
-pub use leader_selection::AngstromValidator;
+pub use leader_selection::{AngstromValidator, WeightedRoundRobin};
 
 #[derive(Debug, Clone)]
 pub enum ConsensusMessage {
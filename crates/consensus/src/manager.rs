@@ -12,27 +12,47 @@ use alloy::{
     providers::Provider
 };
 use angstrom_metrics::ConsensusMetricsWrapper;
-use angstrom_network::{manager::StromConsensusEvent, StromMessage, StromNetworkHandle};
+use angstrom_network::{
+    manager::StromConsensusEvent, NetworkOrderEvent, StromMessage, StromNetworkEvent,
+    StromNetworkHandle
+};
 use angstrom_types::{
-    block_sync::BlockSyncConsumer, contract_payloads::angstrom::UniswapAngstromRegistry,
-    mev_boost::MevBoostProvider, primitive::AngstromSigner
+    block_sync::BlockSyncConsumer,
+    consensus::{
+        threshold::KeyShare, InclusionFairnessStore, ProposalCommittee, ValidatorPerformanceStore
+    },
+    contract_payloads::angstrom::{ContractVersion, UniswapAngstromRegistry},
+    mev_boost::MevBoostProvider,
+    orders::{ClearingReportStore, GasReconciliationStore, OrderArchive},
+    primitive::AngstromSigner,
+    sol_bindings::grouped_orders::AllOrders
 };
+use angstrom_utils::clock::Clock;
 use futures::StreamExt;
 use matching_engine::MatchingEngineHandle;
 use order_pool::order_storage::OrderStorage;
-use reth_metrics::common::mpsc::UnboundedMeteredReceiver;
+use reth_metrics::common::mpsc::{UnboundedMeteredReceiver, UnboundedMeteredSender};
 use reth_provider::{CanonStateNotification, CanonStateNotifications};
-use tokio_stream::wrappers::BroadcastStream;
+use tokio::sync::{mpsc::UnboundedReceiver, oneshot};
+use tokio_stream::wrappers::{BroadcastStream, UnboundedReceiverStream};
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
+use validation::order::OrderValidatorHandle;
 
 use crate::{
     leader_selection::WeightedRoundRobin,
-    rounds::{ConsensusMessage, RoundStateMachine, SharedRoundState},
-    AngstromValidator
+    rounds::{
+        ConsensusMessage, KeyRotation, KeySchedule, LeaderOrderChecker, RoundStateMachine,
+        SharedRoundState
+    },
+    AngstromValidator, ConsensusTiming
 };
 
 const MODULE_NAME: &str = "Consensus";
 
+/// A key to rotate to, answered with the scheduled rotation or none if it was
+/// refused.
+pub type SignerUpdate = (AngstromSigner, oneshot::Sender<Option<KeyRotation>>);
+
 pub struct ConsensusManager<P, Matching, BlockSync> {
     current_height:         BlockNumber,
     leader_selection:       WeightedRoundRobin,
@@ -40,10 +60,18 @@ pub struct ConsensusManager<P, Matching, BlockSync> {
     canonical_block_stream: BroadcastStream<CanonStateNotification>,
     strom_consensus_event:  UnboundedMeteredReceiver<StromConsensusEvent>,
     network:                StromNetworkHandle,
+    /// sessions with peers, which are sent the key handovers we know of
+    network_events:         UnboundedReceiverStream<StromNetworkEvent>,
     block_sync:             BlockSync,
+    /// keys the operator rotated to
+    signer_updates:         Option<UnboundedReceiver<SignerUpdate>>,
+    /// times the operator reloaded
+    timing_updates:         Option<UnboundedReceiver<ConsensusTiming>>,
 
     /// Track broadcasted messages to avoid rebroadcasting
-    broadcasted_messages: HashSet<StromConsensusEvent>
+    broadcasted_messages: HashSet<StromConsensusEvent>,
+    #[cfg(any(test, feature = "chaos"))]
+    chaos:                crate::chaos::Chaos
 }
 
 impl<P, Matching, BlockSync> ConsensusManager<P, Matching, BlockSync>
@@ -91,24 +119,190 @@ where
                 matching_engine
             )),
             block_sync,
+            network_events: network.subscribe_network_events(),
             network,
+            signer_updates: None,
+            timing_updates: None,
             canonical_block_stream: wrapped_broadcast_stream,
-            broadcasted_messages: HashSet::new()
+            broadcasted_messages: HashSet::new(),
+            #[cfg(any(test, feature = "chaos"))]
+            chaos: Default::default()
         }
     }
 
+    /// Shares the clearing reports of the rounds through the given store.
+    pub fn with_clearing_reports(mut self, reports: ClearingReportStore) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_clearing_reports(reports);
+        self
+    }
+
+    /// Reconciles the gas charged to the orders of our landed bundles into
+    /// the given store.
+    pub fn with_gas_reconciliations(mut self, reconciliations: GasReconciliationStore) -> Self {
+        self.consensus_round_state = self
+            .consensus_round_state
+            .with_gas_reconciliations(reconciliations);
+        self
+    }
+
+    /// Schedules the key handovers with `schedule`, see
+    /// [`RoundStateMachine::with_key_schedule`].
+    pub fn with_key_schedule(mut self, schedule: KeySchedule) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_key_schedule(schedule);
+        self
+    }
+
+    /// Validates the orders of late pre-proposals, see
+    /// [`RoundStateMachine::with_order_pool`].
+    pub fn with_order_pool(
+        mut self,
+        order_pool: UnboundedMeteredSender<NetworkOrderEvent>
+    ) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_order_pool(order_pool);
+        self
+    }
+
+    /// Archives the rounds, see [`RoundStateMachine::with_order_archive`].
+    pub fn with_order_archive(mut self, archive: OrderArchive) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_order_archive(archive);
+        self
+    }
+
+    /// Records the performance of the validators into the given store.
+    pub fn with_validator_performance(mut self, performance: ValidatorPerformanceStore) -> Self {
+        self.consensus_round_state = self
+            .consensus_round_state
+            .with_validator_performance(performance);
+        self
+    }
+
+    /// Records the inclusion fairness of the leaders into the given store.
+    pub fn with_inclusion_fairness(mut self, fairness: InclusionFairnessStore) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_inclusion_fairness(fairness);
+        self
+    }
+
+    /// Takes the fee of every pool from its matched surplus, see
+    /// [`RoundStateMachine::with_surplus_fees`].
+    pub fn with_surplus_fees(mut self, protocol_share_e6: u32) -> Self {
+        self.consensus_round_state = self
+            .consensus_round_state
+            .with_surplus_fees(protocol_share_e6);
+        self
+    }
+
+    /// Rotates the signing key to the ones received, see
+    /// [`RoundStateMachine::rotate_signer`].
+    pub fn with_signer_updates(mut self, updates: UnboundedReceiver<SignerUpdate>) -> Self {
+        self.signer_updates = Some(updates);
+        self
+    }
+
+    /// Times the rounds by `timing`, see [`RoundStateMachine::with_timing`].
+    pub fn with_timing(mut self, timing: ConsensusTiming) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_timing(timing);
+        self
+    }
+
+    /// Takes on the times received from the next round on, see
+    /// [`RoundStateMachine::set_timing`].
+    pub fn with_timing_updates(mut self, updates: UnboundedReceiver<ConsensusTiming>) -> Self {
+        self.timing_updates = Some(updates);
+        self
+    }
+
+    /// Checks the private orders of the round leaders with `validator`, see
+    /// [`RoundStateMachine::with_leader_order_checker`].
+    pub fn with_order_validator<V: OrderValidatorHandle<Order = AllOrders>>(
+        mut self,
+        validator: V
+    ) -> Self {
+        self.consensus_round_state = self
+            .consensus_round_state
+            .with_leader_order_checker(LeaderOrderChecker::new(validator));
+        self
+    }
+
+    /// See [`RoundStateMachine::with_block_timestamp`].
+    pub fn with_block_timestamp(mut self, timestamp: u64) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_block_timestamp(timestamp);
+        self
+    }
+
+    /// Version of the deployed contract, see
+    /// [`RoundStateMachine::with_contract_version`].
+    pub fn with_contract_version(mut self, version: ContractVersion) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_contract_version(version);
+        self
+    }
+
+    /// Follows the rounds without signing or submitting anything, see
+    /// [`RoundStateMachine::with_observer_mode`].
+    pub fn with_observer_mode(mut self) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_observer_mode();
+        self
+    }
+
+    /// Committee the proposals are co-signed by, see
+    /// [`RoundStateMachine::with_proposal_committee`].
+    pub fn with_proposal_committee(
+        mut self,
+        committee: ProposalCommittee,
+        key_share: Option<KeyShare>
+    ) -> Self {
+        self.consensus_round_state = self
+            .consensus_round_state
+            .with_proposal_committee(committee, key_share);
+        self
+    }
+
+    /// Clock the rounds take the time from, see
+    /// [`RoundStateMachine::with_clock`].
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.consensus_round_state = self.consensus_round_state.with_clock(clock);
+        self
+    }
+
+    /// Injects the faults armed through `hooks` into the rounds.
+    #[cfg(any(test, feature = "chaos"))]
+    pub fn with_chaos_hooks(mut self, hooks: crate::ChaosHooks) -> Self {
+        self.chaos = crate::chaos::Chaos::new(hooks);
+        self
+    }
+
+    #[cfg(any(test, feature = "chaos"))]
+    pub fn chaos_hooks(&self) -> &crate::ChaosHooks {
+        self.chaos.hooks()
+    }
+
+    /// See [`RoundStateMachine::proposal_verifications`].
+    #[cfg(feature = "testnet")]
+    pub fn proposal_verifications(&self) -> &crate::ProposalVerifications {
+        self.consensus_round_state.proposal_verifications()
+    }
+
     fn on_blockchain_state(&mut self, notification: CanonStateNotification, waker: Waker) {
-        tracing::info!("got new block_chain state");
         let new_block = notification.tip();
         self.current_height = new_block.number();
         let round_leader = self
             .leader_selection
             .choose_proposer(self.current_height)
             .unwrap();
-        tracing::info!(?round_leader, "selected new round leader");
+        tracing::info!(
+            round_id = self.current_height,
+            ?round_leader,
+            "got new block, selected new round leader"
+        );
 
-        self.consensus_round_state
-            .reset_round(self.current_height, round_leader);
+        // until slashing is in place, evidence is only surfaced to the operator
+        for evidence in self.consensus_round_state.take_evidence() {
+            tracing::warn!(offender = ?evidence.offender(), ?evidence, "collected evidence");
+        }
+        self.consensus_round_state.reset_round(
+            self.current_height,
+            new_block.timestamp,
+            round_leader
+        );
         self.broadcasted_messages.clear();
 
         self.block_sync
@@ -116,7 +310,11 @@ where
     }
 
     fn on_network_event(&mut self, event: StromConsensusEvent) {
-        if self.current_height != event.block_height() {
+        // handovers stay valid after the block they're announced in, peers
+        // send them to validators that joined since
+        if self.current_height != event.block_height()
+            && !matches!(event, StromConsensusEvent::KeyHandover(..))
+        {
             tracing::warn!(
                 event_block_height=%event.block_height(),
                 msg_sender=%event.sender(),
@@ -129,7 +327,27 @@ where
         self.consensus_round_state.handle_message(event);
     }
 
+    /// Sends the key handovers we know of to a peer we connected to, it
+    /// otherwise wouldn't know the keys the validators that rotated before it
+    /// joined sign with.
+    fn on_session(&self, event: StromNetworkEvent) {
+        let StromNetworkEvent::SessionEstablished { peer_id, .. } = event else { return };
+        for handover in self.consensus_round_state.key_handovers() {
+            self.network
+                .send_message(peer_id, StromMessage::KeyHandover(handover.clone()));
+        }
+    }
+
     fn on_round_event(&mut self, event: ConsensusMessage) {
+        #[cfg(any(test, feature = "chaos"))]
+        let Some(event) = self.chaos.intercept(event) else {
+            return
+        };
+
+        self.broadcast(event);
+    }
+
+    fn broadcast(&mut self, event: ConsensusMessage) {
         match event {
             ConsensusMessage::PropagateProposal(p) => {
                 self.network.broadcast_message(StromMessage::Propose(p))
@@ -139,7 +357,16 @@ where
             }
             ConsensusMessage::PropagatePreProposalAgg(p) => self
                 .network
-                .broadcast_message(StromMessage::PreProposeAgg(p))
+                .broadcast_message(StromMessage::PreProposeAgg(p)),
+            ConsensusMessage::PropagateBundleHandoff(h) => self
+                .network
+                .broadcast_message(StromMessage::BundleHandoff(h)),
+            ConsensusMessage::PropagateKeyHandover(h) => {
+                self.network.broadcast_message(StromMessage::KeyHandover(h))
+            }
+            ConsensusMessage::PropagateProposalShare(s) => self
+                .network
+                .broadcast_message(StromMessage::ProposalShare(s))
         }
     }
 }
@@ -155,10 +382,26 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        if let Some(updates) = this.signer_updates.as_mut() {
+            while let Poll::Ready(Some((signer, tx))) = updates.poll_recv(cx) {
+                let _ = tx.send(this.consensus_round_state.rotate_signer(signer));
+            }
+        }
+
+        if let Some(updates) = this.timing_updates.as_mut() {
+            while let Poll::Ready(Some(timing)) = updates.poll_recv(cx) {
+                this.consensus_round_state.set_timing(timing);
+            }
+        }
+
+        while let Poll::Ready(Some(event)) = this.network_events.poll_next_unpin(cx) {
+            this.on_session(event);
+        }
+
         while let Poll::Ready(Some(msg)) = this.canonical_block_stream.poll_next_unpin(cx) {
             match msg {
                 Ok(notification) => this.on_blockchain_state(notification, cx.waker().clone()),
-                Err(e) => tracing::error!("Error receiving chain state notification: {}", e)
+                Err(e) => tracing::error!(%e, "error receiving chain state notification")
             };
         }
 
@@ -170,6 +413,11 @@ where
             while let Poll::Ready(Some(msg)) = this.consensus_round_state.poll_next_unpin(cx) {
                 this.on_round_event(msg);
             }
+
+            #[cfg(any(test, feature = "chaos"))]
+            while let Poll::Ready(Some(msg)) = this.chaos.poll_next_unpin(cx) {
+                this.broadcast(msg);
+            }
         }
 
         Poll::Pending
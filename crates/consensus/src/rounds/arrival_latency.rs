@@ -0,0 +1,95 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant}
+};
+
+use angstrom_types::primitive::PeerId;
+
+/// Amount of rounds we keep latency samples for, per validator.
+const SAMPLES_PER_VALIDATOR: usize = 20;
+
+/// Tracks how long after our own pre-proposal the pre-proposals of the other
+/// validators arrive.
+///
+/// Arrivals are collected over the round and turned into samples once it
+/// ends, as pre-proposals can show up before we triggered ours.
+#[derive(Debug, Default)]
+pub struct ArrivalLatencies {
+    /// when we sent our pre-proposal this round
+    trigger:  Option<Instant>,
+    arrivals: HashMap<PeerId, Instant>,
+    samples:  HashMap<PeerId, VecDeque<Duration>>
+}
+
+impl ArrivalLatencies {
+    pub fn mark_trigger(&mut self, at: Instant) {
+        self.trigger.get_or_insert(at);
+    }
+
+    /// Only the first arrival of a validator per round counts.
+    pub fn record_arrival(&mut self, validator: PeerId, at: Instant) {
+        self.arrivals.entry(validator).or_insert(at);
+    }
+
+    /// Turns the arrivals of the round into samples. Arrivals before our
+    /// trigger have no latency. If we never triggered, the round is dropped.
+    pub fn finish_round(&mut self) {
+        let arrivals = std::mem::take(&mut self.arrivals);
+        let Some(trigger) = self.trigger.take() else { return };
+
+        for (validator, arrival) in arrivals {
+            let samples = self.samples.entry(validator).or_default();
+            if samples.len() == SAMPLES_PER_VALIDATOR {
+                samples.pop_front();
+            }
+            samples.push_back(arrival.saturating_duration_since(trigger));
+        }
+    }
+
+    /// The latency `percentile` percent of the samples are within. [`None`] if
+    /// there are no samples.
+    pub fn percentile(&self, percentile: u8) -> Option<Duration> {
+        let mut samples = self.samples.values().flatten().copied().collect::<Vec<_>>();
+        if samples.is_empty() {
+            return None
+        }
+        samples.sort_unstable();
+
+        // nearest rank
+        let rank = (samples.len() * percentile.min(100) as usize).div_ceil(100);
+        Some(samples[rank.saturating_sub(1)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_percentile_over_rounds() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let mut latencies = ArrivalLatencies::default();
+        assert_eq!(latencies.percentile(95), None);
+
+        let start = Instant::now();
+        for round in 0..10u64 {
+            let trigger = start + Duration::from_secs(round * 12);
+            latencies.record_arrival(a, trigger - Duration::from_millis(5));
+            latencies.mark_trigger(trigger);
+            latencies.record_arrival(b, trigger + Duration::from_millis(100 * (round + 1)));
+            // duplicates don't count
+            latencies.record_arrival(b, trigger + Duration::from_secs(10));
+            latencies.finish_round();
+        }
+
+        // a is always early, b is between 100ms and 1s late
+        assert_eq!(latencies.percentile(50), Some(Duration::ZERO));
+        assert_eq!(latencies.percentile(95), Some(Duration::from_millis(900)));
+        assert_eq!(latencies.percentile(80), Some(Duration::from_millis(600)));
+
+        // rounds we never triggered in are dropped
+        latencies.record_arrival(a, start + Duration::from_secs(1000));
+        latencies.finish_round();
+        assert_eq!(latencies.percentile(100), Some(Duration::from_millis(1000)));
+    }
+}
@@ -42,8 +42,8 @@ impl BidAggregationState {
         Self {
             received_pre_proposals: HashSet::default(),
             pre_proposals_aggregation: HashSet::default(),
+            start_time: transition_timeout.started_at(),
             transition_timeout,
-            start_time: Instant::now(),
             proposal: None,
             waker: None
         }
@@ -55,6 +55,10 @@ where
     P: Provider + 'static,
     Matching: MatchingEngineHandle
 {
+    fn name(&self) -> &'static str {
+        "BidAggregation"
+    }
+
     fn on_consensus_message(
         &mut self,
         handles: &mut SharedRoundState<P, Matching>,
@@ -82,6 +86,10 @@ where
                     self.waker.as_ref().inspect(|w| w.wake_by_ref());
                 }
             }
+            // handled by the round state machine regardless of the state
+            StromConsensusEvent::BundleHandoff(..)
+            | StromConsensusEvent::KeyHandover(..)
+            | StromConsensusEvent::ProposalShare(..) => {}
         }
     }
 
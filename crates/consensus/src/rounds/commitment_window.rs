@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use alloy::primitives::{BlockNumber, B256};
+
+/// Blocks an unfilled standing order stays committed to the matching engine
+/// for after it first reached quorum. 1 disables carry-over.
+pub const COMMITMENT_WINDOW_BLOCKS: u64 = 3;
+
+/// Priority of the standing orders of a round that were already up for
+/// matching in the rounds before it: the rounds since the block each first
+/// appeared in a quorum, for the orders still within their window.
+///
+/// The blocks come from the pre-proposals of the round, see
+/// [`VoteLedger::quorum_blocks`](super::VoteLedger::quorum_blocks),
+/// so every validator comes to the same priorities for the same round.
+pub fn carry_over_priority(
+    block_height: BlockNumber,
+    quorum_blocks: HashMap<B256, BlockNumber>,
+    window_blocks: u64
+) -> HashMap<B256, u64> {
+    quorum_blocks
+        .into_iter()
+        .map(|(hash, block)| (hash, block_height.saturating_sub(block)))
+        .filter(|(_, rounds)| (1..window_blocks).contains(rounds))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_orders_within_their_window() {
+        let hash = B256::repeat_byte;
+        let quorum_blocks = HashMap::from([(hash(1), 10), (hash(2), 11), (hash(3), 12)]);
+
+        assert_eq!(
+            carry_over_priority(12, quorum_blocks.clone(), 3),
+            HashMap::from([(hash(1), 2), (hash(2), 1)])
+        );
+        // the window of the first order ends with block 12
+        assert_eq!(
+            carry_over_priority(13, quorum_blocks.clone(), 3),
+            HashMap::from([(hash(2), 2), (hash(3), 1)])
+        );
+        assert!(carry_over_priority(12, quorum_blocks, 1).is_empty());
+    }
+}
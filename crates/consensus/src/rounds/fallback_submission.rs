@@ -0,0 +1,176 @@
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    task::Context,
+    time::{Duration, Instant}
+};
+
+use alloy::primitives::Bytes;
+use angstrom_types::{consensus::BundleHandoff, primitive::PeerId};
+use angstrom_utils::clock::{Clock, Sleep};
+use futures::FutureExt;
+
+/// Amount of backup submitters the leader hands its bundle to
+pub const FALLBACK_SUBMITTERS: usize = 2;
+/// How long the first backup waits for the leaders submission after receiving
+/// the handoff
+const FIRST_BACKUP_DEADLINE: Duration = Duration::from_millis(1500);
+/// Time between the deadlines of consecutive backups, so that only one of
+/// them takes over
+const BACKUP_STAGGER: Duration = Duration::from_millis(500);
+
+/// Decides if and when we submit the leaders bundle as a backup.
+///
+/// Once we got the leaders [`BundleHandoff`] and are one of its backups, we
+/// wait until our deadline. If neither the leader nor a backup before us
+/// announced a submission by then, we take over.
+#[derive(Default)]
+pub struct FallbackSubmitter {
+    /// the leaders handoff, only set if we are one of its backups
+    handoff:  Option<BundleHandoff>,
+    deadline: Option<(Instant, Pin<Box<Sleep>>)>,
+    /// the bundle was submitted, either by someone else or by us
+    done:     bool,
+    /// (source, submitted) of the handoffs we have seen this round
+    seen:     HashSet<(PeerId, bool)>,
+    clock:    Clock
+}
+
+impl FallbackSubmitter {
+    pub fn with_clock(clock: Clock) -> Self {
+        Self { clock, ..Default::default() }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::with_clock(self.clock.clone());
+    }
+
+    /// Tracks the handoff. The signature is expected to be checked by the
+    /// caller. Returns true if it's the first time we see it and it should be
+    /// relayed.
+    pub fn on_handoff(
+        &mut self,
+        handoff: &BundleHandoff,
+        me: PeerId,
+        leader: PeerId,
+        now: Instant
+    ) -> bool {
+        if !self.seen.insert((handoff.source, handoff.submitted)) {
+            return false
+        }
+
+        if handoff.submitted {
+            if self.is_submitter(handoff, leader) {
+                self.done = true;
+                self.deadline = None;
+            }
+            return true
+        }
+
+        if handoff.source != leader {
+            return false
+        }
+        if let Some(position) = handoff.backup_position(&me) {
+            if !self.done && self.handoff.is_none() {
+                let deadline = now + FIRST_BACKUP_DEADLINE + BACKUP_STAGGER * position as u32;
+                self.deadline = Some((deadline, Box::pin(self.clock.sleep_until(deadline))));
+                self.handoff = Some(handoff.clone());
+            }
+        }
+
+        true
+    }
+
+    /// Only the leader and its backups can announce a submission of the
+    /// leaders bundle. Without the leaders handoff we don't know the backups,
+    /// so only the leader is trusted.
+    fn is_submitter(&self, announcement: &BundleHandoff, leader: PeerId) -> bool {
+        match &self.handoff {
+            Some(handoff) => {
+                handoff.calldata == announcement.calldata
+                    && (announcement.source == leader
+                        || handoff.backup_position(&announcement.source).is_some())
+            }
+            None => announcement.source == leader
+        }
+    }
+
+    /// Returns the handoff to submit if our deadline passed without a
+    /// submission. Only ever returns it once.
+    pub fn take_expired(&mut self, now: Instant) -> Option<BundleHandoff> {
+        let (deadline, _) = self.deadline.as_ref()?;
+        if self.done || now < *deadline {
+            return None
+        }
+        self.done = true;
+        self.deadline = None;
+
+        self.handoff.clone()
+    }
+
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Option<BundleHandoff> {
+        let (_, sleep) = self.deadline.as_mut()?;
+        if sleep.poll_unpin(cx).is_pending() {
+            return None
+        }
+
+        self.take_expired(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::primitive::AngstromSigner;
+
+    use super::*;
+
+    fn handoff(leader: &AngstromSigner, backups: &[&AngstromSigner]) -> BundleHandoff {
+        BundleHandoff::new(
+            1,
+            leader,
+            backups.iter().map(|backup| backup.id()).collect(),
+            Bytes::from_static(&[1]),
+            false
+        )
+    }
+
+    #[tokio::test]
+    async fn backups_take_over_in_order() {
+        let (leader, first, second) =
+            (AngstromSigner::random(), AngstromSigner::random(), AngstromSigner::random());
+        let handoff = handoff(&leader, &[&first, &second]);
+        let now = Instant::now();
+
+        let mut submitter = FallbackSubmitter::default();
+        assert!(submitter.on_handoff(&handoff, second.id(), leader.id(), now));
+        assert!(!submitter.on_handoff(&handoff, second.id(), leader.id(), now));
+
+        assert_eq!(submitter.take_expired(now + FIRST_BACKUP_DEADLINE), None);
+        let expired = submitter.take_expired(now + FIRST_BACKUP_DEADLINE + BACKUP_STAGGER);
+        assert_eq!(expired, Some(handoff));
+        assert_eq!(submitter.take_expired(now + Duration::from_secs(10)), None);
+    }
+
+    #[tokio::test]
+    async fn announced_submission_cancels_takeover() {
+        let (leader, first, second) =
+            (AngstromSigner::random(), AngstromSigner::random(), AngstromSigner::random());
+        let handoff = handoff(&leader, &[&first, &second]);
+        let now = Instant::now();
+
+        let mut submitter = FallbackSubmitter::default();
+        submitter.on_handoff(&handoff, second.id(), leader.id(), now);
+
+        // a validator that isn't a backup can't stop us
+        let outsider = AngstromSigner::random();
+        submitter.on_handoff(&handoff.to_submitted(&outsider), second.id(), leader.id(), now);
+        assert!(submitter
+            .take_expired(now + Duration::from_secs(10))
+            .is_some());
+
+        submitter.reset();
+        submitter.on_handoff(&handoff, second.id(), leader.id(), now);
+        submitter.on_handoff(&handoff.to_submitted(&first), second.id(), leader.id(), now);
+        assert_eq!(submitter.take_expired(now + Duration::from_secs(10)), None);
+    }
+}
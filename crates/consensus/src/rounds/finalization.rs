@@ -4,9 +4,11 @@ use std::{
     task::{Context, Poll, Waker}
 };
 
-use alloy::providers::Provider;
+use alloy::{primitives::B256, providers::Provider};
+use angstrom_metrics::{node_health, RoundOutcome};
 use angstrom_network::manager::StromConsensusEvent;
-use angstrom_types::consensus::Proposal;
+use angstrom_types::{consensus::Proposal, orders::canonicalize_solutions};
+use angstrom_utils::clock::Sleep;
 use futures::{Future, FutureExt};
 use matching_engine::MatchingEngineHandle;
 
@@ -19,8 +21,16 @@ use super::{ConsensusState, SharedRoundState};
 /// off) where we will wait for proposals to be propagated (consensus states you
 /// have a day max). in which they will be verified and the round will
 /// officially close.
+///
+/// A verification that runs past the finalization budget is given up on, we
+/// don't sign over the proposal then.
 pub struct FinalizationState {
     verification_future: Pin<Box<dyn Future<Output = bool> + Send>>,
+    /// what we sign over if we are in the committee and the proposal checks
+    /// out
+    proposal_hash:       B256,
+    /// resolves once the verification ran out of time
+    budget:              Pin<Box<Sleep>>,
     completed:           bool
 }
 
@@ -34,42 +44,87 @@ impl FinalizationState {
         P: Provider + 'static,
         Matching: MatchingEngineHandle
     {
+        handles.close_pre_proposals();
+        let proposal_hash = proposal.hash();
+        let block_height = handles.block_height;
+        let budget = Box::pin(handles.clock.sleep(handles.timing.finalization_budget));
+
+        // the orders the pre-proposals forced have to be in the books of the
+        // solutions, whether they fill or not
+        let omitted = handles.omitted_forced_orders(&proposal);
+        if !omitted.is_empty() {
+            tracing::error!(
+                leader = ?proposal.source,
+                omitted = omitted.len(),
+                "proposal left out orders flagged for forced inclusion"
+            );
+            node_health().set_round_outcome(block_height, RoundOutcome::ForcedInclusionViolated);
+            waker.wake_by_ref();
+
+            return Self {
+                verification_future: futures::future::ready(false).boxed(),
+                proposal_hash,
+                budget,
+                completed: false
+            }
+        }
+
         let preproposal = proposal
             .preproposals()
             .clone()
             .into_iter()
             .collect::<HashSet<_>>();
 
+        #[cfg(feature = "testnet")]
+        let verifications = handles.verifications.clone();
         let future = handles
             .matching_engine_output(preproposal)
             .map(move |output| {
-                let (solution, _) = output.unwrap();
-
-                let mut proposal_solution = proposal.solutions.clone();
-                proposal_solution.sort();
+                let solution = match output {
+                    Ok((solution, _)) => solution,
+                    Err(e) => {
+                        tracing::error!(%e, "failed to solve the round to verify the proposal");
+                        node_health()
+                            .set_round_outcome(block_height, RoundOutcome::VerificationFailed);
+                        return false
+                    }
+                };
 
+                // the proposal is canonical once it's valid, our own solutions
+                // are compared in the same form
+                let proposal_solution = proposal.solutions.clone();
                 let mut verification_solution = solution;
-                verification_solution.sort();
+                canonicalize_solutions(&mut verification_solution);
 
-                if !proposal_solution
-                    .into_iter()
-                    .zip(verification_solution)
-                    .all(|(p, v)| p == v)
-                {
+                #[cfg(feature = "testnet")]
+                verifications.insert(
+                    block_height,
+                    crate::ProposalVerification {
+                        leader:   proposal.source,
+                        proposed: proposal_solution.clone(),
+                        computed: verification_solution.clone()
+                    }
+                );
+
+                // a leader leaving out a pool or adding one is a mismatch too
+                if proposal_solution != verification_solution {
                     tracing::error!(
+                        leader = ?proposal.source,
                         "Violation DETECTED. in future this will be related to slashing"
                     );
+                    node_health().set_round_outcome(block_height, RoundOutcome::ProposalMismatch);
                     return false
                 }
 
+                node_health().set_round_outcome(block_height, RoundOutcome::ProposalVerified);
                 true
             })
             .boxed();
 
         waker.wake_by_ref();
-        tracing::info!("finalization");
+        tracing::info!(block_height, "starting finalization");
 
-        Self { verification_future: future, completed: false }
+        Self { verification_future: future, proposal_hash, budget, completed: false }
     }
 }
 
@@ -78,18 +133,32 @@ where
     P: Provider + 'static,
     Matching: MatchingEngineHandle
 {
+    fn name(&self) -> &'static str {
+        "Finalization"
+    }
+
     fn on_consensus_message(
         &mut self,
-        _: &mut SharedRoundState<P, Matching>,
-        _: StromConsensusEvent
+        handles: &mut SharedRoundState<P, Matching>,
+        message: StromConsensusEvent
     ) {
         // no messages consensus related matter at this point. is just waiting
-        // to be reset.
+        // to be reset. Late pre-proposals are still buffered for the next round
+        // and late aggregations recorded against their source.
+        match message {
+            StromConsensusEvent::PreProposal(peer_id, pre_proposal) => {
+                handles.handle_late_pre_proposal(peer_id, pre_proposal);
+            }
+            StromConsensusEvent::PreProposalAgg(peer_id, pre_proposal_agg) => {
+                handles.handle_late_pre_proposal_aggregation(peer_id, pre_proposal_agg);
+            }
+            _ => {}
+        }
     }
 
     fn poll_transition(
         &mut self,
-        _: &mut SharedRoundState<P, Matching>,
+        handles: &mut SharedRoundState<P, Matching>,
         cx: &mut Context<'_>
     ) -> Poll<Option<Box<dyn ConsensusState<P, Matching>>>> {
         if self.completed {
@@ -97,7 +166,21 @@ where
         }
 
         if let Poll::Ready(result) = self.verification_future.poll_unpin(cx) {
-            tracing::info!(%result, "consensus result");
+            tracing::info!(verified = result, "consensus result");
+            if result {
+                handles.sign_proposal_share(self.proposal_hash);
+            } else {
+                handles.round_performance.record_failed_proposal();
+            }
+            self.completed = true;
+            return Poll::Ready(None)
+        }
+
+        if self.budget.as_mut().poll(cx).is_ready() {
+            tracing::warn!(
+                budget = handles.timing.finalization_budget.as_millis(),
+                "proposal verification ran past its budget, not signing"
+            );
             self.completed = true;
             return Poll::Ready(None)
         }
@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf}
+};
+
+use alloy::primitives::{Address, BlockNumber};
+use angstrom_types::{consensus::KeyHandover, primitive::PeerId};
+use serde::{Deserialize, Serialize};
+
+/// A handover of our consensus messages to a new key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotation {
+    /// the peer id we are known by, which the handover doesn't change
+    pub validator_id:     PeerId,
+    /// the address of the new key
+    pub signing_key:      Address,
+    /// the block the new key is signed with from
+    pub activation_block: BlockNumber
+}
+
+/// The keys the validators sign their consensus messages with.
+///
+/// A validator keeps the peer id it's known by in the validator set, its
+/// handovers only change the key its messages are signed with. During the
+/// grace window of a handover both keys are valid. A validator hands over one
+/// key at a time, a new handover is only accepted if it's announced once the
+/// grace window of the last one ended.
+///
+/// Every handover of a validator is kept, not only its last one, so that a
+/// validator that joins later can follow the keys from the one in the
+/// validator set to the one signed with today.
+///
+/// The handovers are kept in a file if one is given, a restarted node would
+/// otherwise no longer know the keys the validators rotated to.
+#[derive(Debug, Default)]
+pub struct KeySchedule {
+    /// handovers of every validator that rotated its key, oldest first
+    handovers: HashMap<PeerId, Vec<KeyHandover>>,
+    path:      Option<PathBuf>
+}
+
+impl KeySchedule {
+    /// Loads the handovers kept in the file, which the accepted ones are
+    /// written to from then on. A missing file is an empty schedule.
+    pub fn load(path: PathBuf) -> std::io::Result<Self> {
+        let handovers = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<Vec<(PeerId, Vec<KeyHandover>)>>(&bytes)?
+                .into_iter()
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e)
+        };
+
+        Ok(Self { handovers, path: Some(path) })
+    }
+
+    /// Replaces the file, a crash halfway leaves the previous one intact.
+    fn store(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let handovers = self.handovers.iter().collect::<Vec<_>>();
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&handovers)?)?;
+
+        std::fs::rename(tmp, path)
+    }
+
+    /// The last handover of `validator`, if it rotated its key.
+    pub fn handover_of(&self, validator: PeerId) -> Option<&KeyHandover> {
+        self.handovers
+            .get(&validator)
+            .and_then(|handovers| handovers.last())
+    }
+
+    /// Every handover we know of, those of a validator in the order they have
+    /// to be accepted in.
+    pub fn handovers(&self) -> impl Iterator<Item = &KeyHandover> {
+        self.handovers.values().flatten()
+    }
+
+    /// The keys `validator` can sign with at `block`.
+    pub fn keys_of(&self, validator: PeerId, block: BlockNumber) -> Vec<PeerId> {
+        match self.handover_of(validator) {
+            None => vec![validator],
+            Some(handover) if block < handover.activation_block => vec![handover.source],
+            Some(handover) if block < handover.grace_end() => {
+                vec![handover.new_key, handover.source]
+            }
+            Some(handover) => vec![handover.new_key]
+        }
+    }
+
+    pub fn is_key_of(&self, validator: PeerId, key: PeerId, block: BlockNumber) -> bool {
+        self.keys_of(validator, block).contains(&key)
+    }
+
+    /// The validator that signs with `key` at `block`, if any.
+    pub fn validator_of(
+        &self,
+        key: PeerId,
+        validators: impl IntoIterator<Item = PeerId>,
+        block: BlockNumber
+    ) -> Option<PeerId> {
+        validators
+            .into_iter()
+            .find(|validator| self.is_key_of(*validator, key, block))
+    }
+
+    /// Schedules the handover of a validator, returns whether it's a new
+    /// one that should be relayed. A handover is accepted from the block it's
+    /// announced in on, also once it activated, so that validators that
+    /// joined since can still learn it. Whether it follows the last one
+    /// early enough is judged by the block it's announced in, which every
+    /// validator agrees on no matter when it received it.
+    pub fn on_handover(
+        &mut self,
+        handover: &KeyHandover,
+        block: BlockNumber,
+        validators: impl IntoIterator<Item = PeerId> + Clone
+    ) -> bool {
+        if !handover.is_valid(&block) {
+            return false
+        }
+        let Some(validator) = validators
+            .clone()
+            .into_iter()
+            .find(|validator| self.latest_key(*validator) == handover.source)
+        else {
+            return false
+        };
+        if self
+            .handovers
+            .handover_of(validator)
+            .is_some_and(|last| handover.block_height < last.grace_end())
+        {
+            return false
+        }
+        // a key can't sign for two validators
+        if validators
+            .into_iter()
+            .any(|other| other == handover.new_key || self.latest_key(other) == handover.new_key)
+        {
+            return false
+        }
+
+        self.handovers
+            .entry(validator)
+            .or_default()
+            .push(handover.clone());
+        if let Some(path) = self.path.as_deref() {
+            if let Err(error) = self.store(path) {
+                tracing::warn!(?path, %error, "failed to store the key handovers");
+            }
+        }
+
+        true
+    }
+
+    /// The key the validator signs with once its handovers are done.
+    fn latest_key(&self, validator: PeerId) -> PeerId {
+        self.handover_of(validator)
+            .map_or(validator, |handover| handover.new_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::{
+        consensus::{KEY_HANDOVER_GRACE_BLOCKS, KEY_HANDOVER_LEAD_BLOCKS},
+        primitive::AngstromSigner
+    };
+
+    use super::*;
+
+    #[test]
+    fn both_keys_are_valid_during_the_grace_window() {
+        let (validator, other) = (AngstromSigner::random(), AngstromSigner::random());
+        let validators = [validator.id(), other.id()];
+        let (first, second) = (AngstromSigner::random(), AngstromSigner::random());
+        let activation = 10 + KEY_HANDOVER_LEAD_BLOCKS;
+
+        let mut schedule = KeySchedule::default();
+        let handover = KeyHandover::new(10, &validator, &first, activation);
+        assert!(schedule.on_handover(&handover, 10, validators));
+        assert!(!schedule.on_handover(&handover, 10, validators));
+
+        assert_eq!(schedule.keys_of(validator.id(), activation - 1), vec![validator.id()]);
+        assert_eq!(schedule.keys_of(validator.id(), activation), vec![first.id(), validator.id()]);
+        let grace_end = activation + KEY_HANDOVER_GRACE_BLOCKS;
+        assert_eq!(schedule.keys_of(validator.id(), grace_end), vec![first.id()]);
+        assert_eq!(schedule.validator_of(first.id(), validators, grace_end), Some(validator.id()));
+        assert_eq!(schedule.validator_of(validator.id(), validators, grace_end), None);
+
+        // one handover at a time, and only from the current key
+        let early = KeyHandover::new(11, &first, &second, 11 + KEY_HANDOVER_LEAD_BLOCKS);
+        assert!(!schedule.on_handover(&early, 11, validators));
+        let stale =
+            KeyHandover::new(grace_end, &validator, &second, grace_end + KEY_HANDOVER_LEAD_BLOCKS);
+        assert!(!schedule.on_handover(&stale, grace_end, validators));
+        let next =
+            KeyHandover::new(grace_end, &first, &second, grace_end + KEY_HANDOVER_LEAD_BLOCKS);
+        assert!(schedule.on_handover(&next, grace_end, validators));
+
+        // can't take over the key of another validator
+        let taken = KeyHandover::new(10, &other, &validator, activation);
+        assert!(!schedule.on_handover(&taken, 10, validators));
+    }
+
+    #[test]
+    fn handovers_are_accepted_once_announced_and_survive_restarts() {
+        let (validator, new) = (AngstromSigner::random(), AngstromSigner::random());
+        let validators = [validator.id()];
+        let activation = 10 + KEY_HANDOVER_LEAD_BLOCKS;
+        let handover = KeyHandover::new(10, &validator, &new, activation);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key_handovers.json");
+        let mut schedule = KeySchedule::load(path.clone()).unwrap();
+        assert!(!schedule.on_handover(&handover, 9, validators));
+        assert!(schedule.on_handover(&handover, activation - 1, validators));
+
+        let restarted = KeySchedule::load(path).unwrap();
+        assert_eq!(restarted.handover_of(validator.id()), Some(&handover));
+        assert_eq!(restarted.keys_of(validator.id(), activation), vec![new.id(), validator.id()]);
+    }
+
+    #[test]
+    fn late_joiners_follow_the_handovers_of_the_others() {
+        let validator = AngstromSigner::random();
+        let validators = [validator.id()];
+        let (first, second) = (AngstromSigner::random(), AngstromSigner::random());
+        let first_end = 10 + KEY_HANDOVER_LEAD_BLOCKS + KEY_HANDOVER_GRACE_BLOCKS;
+        let handovers = [
+            KeyHandover::new(10, &validator, &first, 10 + KEY_HANDOVER_LEAD_BLOCKS),
+            KeyHandover::new(first_end, &first, &second, first_end + KEY_HANDOVER_LEAD_BLOCKS)
+        ];
+        let mut known = KeySchedule::default();
+        assert!(known.on_handover(&handovers[0], 10, validators));
+        assert!(known.on_handover(&handovers[1], first_end, validators));
+
+        // joins long after both activated and is sent what the others know
+        let block = first_end + 100;
+        let mut joiner = KeySchedule::default();
+        assert!(!joiner.on_handover(&handovers[1], block, validators));
+        for handover in known.handovers() {
+            assert!(joiner.on_handover(handover, block, validators));
+        }
+        assert_eq!(joiner.keys_of(validator.id(), block), vec![second.id()]);
+        assert_eq!(joiner.validator_of(second.id(), validators, block), Some(validator.id()));
+
+        // handovers it already knows aren't relayed again
+        for handover in &handovers {
+            assert!(!joiner.on_handover(handover, block + 1, validators));
+        }
+
+        // a handover announced within the grace window of the last one
+        // stays refused, no matter how late it's received
+        let third = AngstromSigner::random();
+        let early = KeyHandover::new(
+            first_end + 1,
+            &second,
+            &third,
+            first_end + 1 + KEY_HANDOVER_LEAD_BLOCKS
+        );
+        assert!(!joiner.on_handover(&early, block, validators));
+    }
+}
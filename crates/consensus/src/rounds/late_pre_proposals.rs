@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant}
+};
+
+use alloy::primitives::{BlockNumber, B256, U256};
+use angstrom_types::{
+    consensus::PreProposal,
+    orders::{OrderId, OrderSet},
+    primitive::PeerId,
+    sol_bindings::{grouped_orders::GroupedVanillaOrder, rpc_orders::TopOfBlockOrder}
+};
+
+/// Buffers valid pre-proposals that arrived after the round stopped collecting
+/// them.
+///
+/// They can't count towards the current round anymore, but the orders they
+/// carry are likely to still be fillable. Once the round is over the orders
+/// that are still valid are carried over into the next round.
+#[derive(Debug, Default)]
+pub struct LatePreProposals {
+    block_height:  BlockNumber,
+    /// when the round stopped accepting pre-proposals
+    closed_at:     Option<Instant>,
+    pre_proposals: HashMap<PeerId, PreProposal>
+}
+
+impl LatePreProposals {
+    pub fn new(block_height: BlockNumber) -> Self {
+        Self { block_height, ..Default::default() }
+    }
+
+    /// Marks the point at which pre-proposals stop being on time. Only the
+    /// first call has an effect.
+    pub fn close(&mut self, at: Instant) {
+        self.closed_at.get_or_insert(at);
+    }
+
+    pub fn len(&self) -> usize {
+        self.pre_proposals.len()
+    }
+
+    /// Buffers the pre-proposal and returns how late it was. Returns [`None`]
+    /// if the round is still open, the pre-proposal is for a different height
+    /// or one from the same source is already buffered.
+    pub fn insert(&mut self, pre_proposal: PreProposal, now: Instant) -> Option<Duration> {
+        let closed_at = self.closed_at?;
+        if pre_proposal.block_height != self.block_height
+            || self.pre_proposals.contains_key(&pre_proposal.source)
+        {
+            return None
+        }
+        self.pre_proposals.insert(pre_proposal.source, pre_proposal);
+
+        Some(now.saturating_duration_since(closed_at))
+    }
+
+    /// Moves the buffer to `new_block` and returns the deduplicated orders of
+    /// the buffered pre-proposals that are still valid at it, by the source
+    /// of the pre-proposal. Orders for which `is_known` returns true are
+    /// already in our pool and are skipped.
+    pub fn carry_over(
+        &mut self,
+        new_block: BlockNumber,
+        timestamp: u64,
+        is_known: impl Fn(B256) -> bool
+    ) -> Vec<(PeerId, OrderSet<GroupedVanillaOrder, TopOfBlockOrder>)> {
+        let pre_proposals = std::mem::take(&mut self.pre_proposals);
+        self.block_height = new_block;
+        self.closed_at = None;
+
+        let mut seen = HashSet::new();
+        let mut keep = |id: &OrderId| {
+            Self::still_valid(id, new_block, timestamp)
+                && !is_known(id.hash)
+                && seen.insert(id.hash)
+        };
+
+        pre_proposals
+            .into_iter()
+            .map(|(source, pre_proposal)| {
+                let limit = pre_proposal
+                    .limit
+                    .into_iter()
+                    .filter(|order| keep(&order.order_id))
+                    .collect();
+                let searcher = pre_proposal
+                    .searcher
+                    .into_iter()
+                    .filter(|order| keep(&order.order_id))
+                    .collect();
+                (source, OrderSet { limit, searcher })
+            })
+            .filter(|(_, orders)| orders.total_orders() != 0)
+            .collect()
+    }
+
+    fn still_valid(id: &OrderId, block: BlockNumber, timestamp: u64) -> bool {
+        id.flash_block
+            .map_or(true, |flash_block| flash_block >= block)
+            && id
+                .deadline
+                .map_or(true, |deadline| deadline >= U256::from(timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::{
+        primitive::AngstromSigner, sol_bindings::grouped_orders::OrderWithStorageData
+    };
+
+    use super::*;
+
+    fn order(hash: u8, id: OrderId) -> OrderWithStorageData<GroupedVanillaOrder> {
+        OrderWithStorageData {
+            order_id: OrderId { hash: B256::repeat_byte(hash), ..id },
+            ..Default::default()
+        }
+    }
+
+    fn pre_proposal(
+        sk: &AngstromSigner,
+        limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>
+    ) -> PreProposal {
+        PreProposal::generate_pre_proposal(1, sk, limit, vec![])
+    }
+
+    #[test]
+    fn buffers_only_once_closed() {
+        let sk = AngstromSigner::random();
+        let mut late = LatePreProposals::new(1);
+        let start = Instant::now();
+
+        assert_eq!(late.insert(pre_proposal(&sk, vec![]), start), None);
+        late.close(start);
+        late.close(start + Duration::from_secs(5));
+
+        let lateness = late.insert(pre_proposal(&sk, vec![]), start + Duration::from_millis(300));
+        assert_eq!(lateness, Some(Duration::from_millis(300)));
+        assert_eq!(late.insert(pre_proposal(&sk, vec![]), start), None);
+        assert_eq!(late.len(), 1);
+    }
+
+    #[test]
+    fn carries_over_still_valid_orders() {
+        let (a, b) = (AngstromSigner::random(), AngstromSigner::random());
+        let mut late = LatePreProposals::new(1);
+        late.close(Instant::now());
+
+        let flash = OrderId { flash_block: Some(1), ..Default::default() };
+        let expired = OrderId { deadline: Some(U256::from(99)), ..Default::default() };
+        let standing = OrderId { deadline: Some(U256::from(100)), ..Default::default() };
+        late.insert(
+            pre_proposal(&a, vec![order(1, flash), order(2, expired), order(3, standing)]),
+            Instant::now()
+        );
+        late.insert(
+            pre_proposal(&b, vec![order(3, standing), order(4, Default::default())]),
+            Instant::now()
+        );
+
+        let carried = late.carry_over(2, 100, |hash| hash == B256::repeat_byte(4));
+        let hashes = carried
+            .iter()
+            .flat_map(|(_, orders)| orders.limit.iter())
+            .map(|order| order.order_id.hash)
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, vec![B256::repeat_byte(3)]);
+
+        // the next round is open again
+        assert_eq!(late.len(), 0);
+        let next = PreProposal::generate_pre_proposal(2, &a, vec![], vec![]);
+        assert_eq!(late.insert(next, Instant::now()), None);
+    }
+}
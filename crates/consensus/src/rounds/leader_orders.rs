@@ -0,0 +1,231 @@
+use std::{collections::HashSet, sync::Arc};
+
+use alloy::primitives::B256;
+use angstrom_types::sol_bindings::{
+    grouped_orders::{AllOrders, GroupedVanillaOrder, OrderWithStorageData},
+    rpc_orders::TopOfBlockOrder
+};
+use futures::future::join_all;
+use validation::order::{OrderValidationResults, OrderValidatorHandle, ValidationFuture};
+
+type CheckOrder = dyn Fn(AllOrders) -> ValidationFuture<'static> + Send + Sync;
+
+/// Checks the orders only the leader of the round vouches for.
+///
+/// Private and pegged orders aren't gossiped, so they go into the round on the
+/// vote of the leader alone, along with whatever storage data it claims for
+/// them. Every node validates them against its own view of the chain instead
+/// and matches its own copy of the ones that hold up, along with their peg if
+/// it was signed for them. The check only reads the state on chain, so the
+/// nodes come to the same orders for the same block.
+#[derive(Clone)]
+pub struct LeaderOrderChecker(Arc<CheckOrder>);
+
+impl std::fmt::Debug for LeaderOrderChecker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LeaderOrderChecker").finish_non_exhaustive()
+    }
+}
+
+impl LeaderOrderChecker {
+    pub fn new<V: OrderValidatorHandle<Order = AllOrders>>(validator: V) -> Self {
+        Self(Arc::new(move |order| {
+            let validator = validator.clone();
+            Box::pin(async move { validator.check_order(order).await })
+        }))
+    }
+
+    /// Replaces the `unvouched` limit orders by our own copy of them, the ones
+    /// that don't validate are dropped. The other orders are kept as they are.
+    pub async fn check_limit(
+        &self,
+        orders: Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+        unvouched: &HashSet<B256>
+    ) -> Vec<OrderWithStorageData<GroupedVanillaOrder>> {
+        self.check(orders, unvouched, |order| match order {
+            AllOrders::Standing(order) => Some(GroupedVanillaOrder::Standing(order)),
+            AllOrders::Flash(order) => Some(GroupedVanillaOrder::KillOrFill(order)),
+            AllOrders::TOB(_) => None
+        })
+        .await
+    }
+
+    /// Like [`Self::check_limit`], for top of block orders.
+    pub async fn check_searcher(
+        &self,
+        orders: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        unvouched: &HashSet<B256>
+    ) -> Vec<OrderWithStorageData<TopOfBlockOrder>> {
+        self.check(orders, unvouched, |order| match order {
+            AllOrders::TOB(order) => Some(order),
+            _ => None
+        })
+        .await
+    }
+
+    async fn check<O: Clone + Into<AllOrders>>(
+        &self,
+        orders: Vec<OrderWithStorageData<O>>,
+        unvouched: &HashSet<B256>,
+        from_all: impl Fn(AllOrders) -> Option<O>
+    ) -> Vec<OrderWithStorageData<O>> {
+        let orders = orders
+            .into_iter()
+            .filter(|order| !unvouched.contains(&order.order_id.hash) || peg_holds(order))
+            .collect::<Vec<_>>();
+        let checks = join_all(
+            orders
+                .iter()
+                .filter(|order| unvouched.contains(&order.order_id.hash))
+                .map(|order| (self.0)(order.order.clone().into()))
+        )
+        .await;
+        let mut checks = checks.into_iter();
+
+        orders
+            .into_iter()
+            .filter_map(|claimed| {
+                if !unvouched.contains(&claimed.order_id.hash) {
+                    return Some(claimed)
+                }
+                let ours = match checks.next()? {
+                    OrderValidationResults::Valid(ours)
+                        if ours.is_currently_valid
+                            && ours.order_id.hash == claimed.order_id.hash =>
+                    {
+                        ours.try_map_inner(|order| {
+                            from_all(order).ok_or_else(|| eyre::eyre!("order changed its kind"))
+                        })
+                        .ok()
+                    }
+                    _ => None
+                };
+                let Some(ours) = ours else {
+                    tracing::debug!(
+                        order_hash = ?claimed.order_id.hash,
+                        "dropping a private order of the leader that doesn't validate"
+                    );
+                    return None
+                };
+
+                // the flags are the leader's, the peg and the reward were
+                // verified already
+                Some(OrderWithStorageData {
+                    is_private: claimed.is_private,
+                    peg: claimed.peg,
+                    tob_reward: claimed.tob_reward,
+                    ..ours
+                })
+            })
+            .collect()
+    }
+}
+
+/// Whether the peg the leader attached to the order, if any, was signed for it
+/// by its signer.
+fn peg_holds<O: Clone + Into<AllOrders>>(order: &OrderWithStorageData<O>) -> bool {
+    let Some(peg) = order.peg.as_ref() else { return true };
+    peg.validate(&order.order.clone().into())
+        .inspect_err(|error| {
+            tracing::debug!(
+                order_hash = ?order.order_id.hash,
+                %error,
+                "dropping a pegged order of the leader with an invalid peg"
+            )
+        })
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::SignerSync;
+    use angstrom_types::{orders::PricePeg, primitive::AngstromSigner, sol_bindings::RawPoolOrder};
+    use testing_tools::{
+        mocks::validator::MockValidator, type_generator::orders::UserOrderBuilder
+    };
+
+    use super::*;
+
+    fn private_order(signer: &AngstromSigner) -> OrderWithStorageData<GroupedVanillaOrder> {
+        let mut order = UserOrderBuilder::new()
+            .standing()
+            .exact()
+            .amount(100)
+            .signing_key(Some(signer.clone()))
+            .with_storage()
+            .build();
+        order.is_private = true;
+        order
+    }
+
+    fn peg(signer: &AngstromSigner, order_hash: B256, offset_bps: i32) -> PricePeg {
+        let hash = PricePeg::signing_payload(order_hash, offset_bps);
+        PricePeg { order_hash, offset_bps, signature: signer.sign_hash_sync(&hash).unwrap() }
+    }
+
+    fn validates_as(
+        validator: &MockValidator,
+        order: &OrderWithStorageData<GroupedVanillaOrder>,
+        edit: impl FnOnce(&mut OrderWithStorageData<AllOrders>)
+    ) {
+        let mut ours = order
+            .clone()
+            .try_map_inner(|order| Ok(AllOrders::from(order)))
+            .unwrap();
+        ours.is_private = false;
+        edit(&mut ours);
+        validator.add_order(order.from(), OrderValidationResults::Valid(ours));
+    }
+
+    #[tokio::test]
+    async fn matches_our_copy_of_the_unvouched_orders() {
+        let validator = MockValidator::default();
+        let honest = private_order(&AngstromSigner::random());
+        let unfunded = private_order(&AngstromSigner::random());
+        let unknown = private_order(&AngstromSigner::random());
+        let vouched = private_order(&AngstromSigner::random());
+        validates_as(&validator, &honest, |_| ());
+        validates_as(&validator, &unfunded, |order| order.is_currently_valid = false);
+
+        // the leader claims more volume than the order has
+        let mut claimed = honest.clone();
+        claimed.priority_data.volume *= 10;
+        let unvouched = [&honest, &unfunded, &unknown]
+            .map(|order| order.order_id.hash)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let checked = LeaderOrderChecker::new(validator)
+            .check_limit(vec![claimed, unfunded, unknown, vouched.clone()], &unvouched)
+            .await;
+
+        // orders the state can't support or we can't validate are dropped,
+        // the ones others vouched for aren't checked
+        assert_eq!(checked, vec![honest, vouched]);
+    }
+
+    #[tokio::test]
+    async fn drops_pegs_the_signer_of_the_order_didnt_sign() {
+        let validator = MockValidator::default();
+        let signers = [(); 3].map(|_| AngstromSigner::random());
+        let [mut honest, mut forged, mut misdirected] = signers.each_ref().map(private_order);
+        for order in [&honest, &forged, &misdirected] {
+            validates_as(&validator, order, |_| ());
+        }
+
+        honest.peg = Some(peg(&signers[0], honest.order_id.hash, 10));
+        // signed by the leader instead of the signer of the order
+        forged.peg = Some(peg(&AngstromSigner::random(), forged.order_id.hash, 10));
+        // signed by the signer of the order, but for another one of its orders
+        misdirected.peg = Some(peg(&signers[2], B256::repeat_byte(1), 10));
+        let unvouched = [&honest, &forged, &misdirected]
+            .map(|order| order.order_id.hash)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let checked = LeaderOrderChecker::new(validator)
+            .check_limit(vec![honest.clone(), forged, misdirected], &unvouched)
+            .await;
+
+        // only the order with the peg its signer signed is matched, pegged
+        assert_eq!(checked, vec![honest]);
+    }
+}
@@ -7,36 +7,72 @@ use std::{
 };
 
 use alloy::{
-    primitives::{Address, BlockNumber, FixedBytes},
-    providers::Provider
+    network::TransactionBuilder,
+    primitives::{Address, BlockNumber, Bytes, FixedBytes, B256},
+    providers::Provider,
+    rpc::types::TransactionRequest
 };
-use angstrom_metrics::ConsensusMetricsWrapper;
-use angstrom_network::manager::StromConsensusEvent;
+use angstrom_metrics::{node_health, BundleBuildingMetricsWrapper, ConsensusMetricsWrapper};
+use angstrom_network::{manager::StromConsensusEvent, NetworkOrderEvent};
 use angstrom_types::{
-    consensus::{PreProposal, PreProposalAggregation, Proposal},
-    contract_payloads::angstrom::{BundleGasDetails, UniswapAngstromRegistry},
+    consensus::{
+        threshold::KeyShare, BundleHandoff, Evidence, InclusionAudit, InclusionFairnessStore,
+        KeyHandover, PreProposal, PreProposalAggregation, Proposal, ProposalCommittee,
+        ProposalShare, ValidatorPerformanceStore, KEY_HANDOVER_LEAD_BLOCKS
+    },
+    contract_payloads::{
+        angstrom::{ContractVersion, UniswapAngstromRegistry},
+        fees::FeeConfig,
+        tob::{ToBOutcome, TOB_REWARD_TOLERANCE_E6}
+    },
     matching::uniswap::PoolSnapshot,
-    mev_boost::MevBoostProvider,
-    orders::PoolSolution,
+    mev_boost::{MevBoostProvider, Submission, SubmissionPath},
+    orders::{ArchivedRound, ClearingReportStore, GasReconciliationStore, OrderArchive, OrderSet},
     primitive::{AngstromSigner, PeerId},
-    sol_bindings::grouped_orders::OrderWithStorageData
+    sol_bindings::{
+        grouped_orders::{AllOrders, GroupedVanillaOrder, OrderWithStorageData},
+        rpc_orders::TopOfBlockOrder
+    }
 };
+use angstrom_utils::clock::Clock;
+use arrival_latency::ArrivalLatencies;
 use bid_aggregation::BidAggregationState;
-use futures::{future::BoxFuture, FutureExt, Stream};
+use commitment_window::{carry_over_priority, COMMITMENT_WINDOW_BLOCKS};
+use fallback_submission::{FallbackSubmitter, FALLBACK_SUBMITTERS};
+use futures::{future::BoxFuture, FutureExt, Stream, StreamExt};
 use itertools::Itertools;
-use matching_engine::MatchingEngineHandle;
-use order_pool::order_storage::OrderStorage;
-use preproposal_wait_trigger::{LastRoundInfo, PreProposalWaitTrigger};
+pub use key_schedule::{KeyRotation, KeySchedule};
+use late_pre_proposals::LatePreProposals;
+pub use leader_orders::LeaderOrderChecker;
+use matching_engine::{MatchingEngineHandle, PartialSolutions, SolutionCache, SolveFuture};
+use order_pool::{order_set_diff::OrderSetMirror, order_storage::OrderStorage};
+use preproposal_wait_trigger::{LastRoundInfo, PreProposalWaitTrigger, ARRIVAL_LATENCY_PERCENTILE};
+use proposal_certification::ProposalCertification;
+use reth_metrics::common::mpsc::UnboundedMeteredSender;
+use round_performance::RoundPerformance;
+use tracing::Span;
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
+pub use vote_ledger::VoteLedger;
 
-use crate::AngstromValidator;
+use crate::{AngstromValidator, ConsensusTiming};
 
+mod arrival_latency;
 mod bid_aggregation;
+mod commitment_window;
+mod fallback_submission;
 mod finalization;
+mod key_schedule;
+mod late_pre_proposals;
+mod leader_orders;
 mod pre_proposal;
 mod pre_proposal_aggregation;
 mod preproposal_wait_trigger;
 mod proposal;
+mod proposal_certification;
+mod round_performance;
+#[cfg(test)]
+mod test_kit;
+mod vote_ledger;
 
 type PollTransition<P, Matching> = Poll<Option<Box<dyn ConsensusState<P, Matching>>>>;
 
@@ -45,6 +81,9 @@ where
     P: Provider,
     Matching: MatchingEngineHandle
 {
+    /// name of the state, for logs and tests
+    fn name(&self) -> &'static str;
+
     fn on_consensus_message(
         &mut self,
         handles: &mut SharedRoundState<P, Matching>,
@@ -70,7 +109,13 @@ pub struct RoundStateMachine<P, Matching> {
     /// for consensus, on a new block we wait a duration of time before signing
     /// our pre-proposal. this is the time
     consensus_wait_duration: PreProposalWaitTrigger,
-    shared_state:            SharedRoundState<P, Matching>
+    /// times taken on when the next round starts
+    pending_timing:          Option<ConsensusTiming>,
+    shared_state:            SharedRoundState<P, Matching>,
+    /// everything logged while progressing the round, including the bundle
+    /// build and submission, is recorded under this span so that it can be
+    /// correlated by `round_id`
+    round_span:              Span
 }
 
 impl<P, Matching> RoundStateMachine<P, Matching>
@@ -81,17 +126,26 @@ where
     pub fn new(shared_state: SharedRoundState<P, Matching>) -> Self {
         let mut consensus_wait_duration =
             PreProposalWaitTrigger::new(shared_state.order_storage.clone());
+        let round_span = Self::round_span(shared_state.block_height, shared_state.round_leader);
 
         Self {
             current_state: Box::new(BidAggregationState::new(
-                consensus_wait_duration.update_for_new_round(None)
+                consensus_wait_duration.update_for_new_round(None, None)
             )),
             consensus_wait_duration,
-            shared_state
+            pending_timing: None,
+            shared_state,
+            round_span
         }
     }
 
-    pub fn reset_round(&mut self, new_block: u64, new_leader: PeerId) {
+    fn round_span(block_height: BlockNumber, leader: PeerId) -> Span {
+        tracing::info_span!(parent: None, "consensus_round", round_id = block_height, ?leader)
+    }
+
+    /// Starts the round of `new_block`, `block_timestamp` is the one of its
+    /// header.
+    pub fn reset_round(&mut self, new_block: u64, block_timestamp: u64, new_leader: PeerId) {
         // grab the last round info if we were the leader.
         let info = self.current_state.last_round_info();
 
@@ -101,18 +155,242 @@ where
         if info.is_none() && self.shared_state.i_am_leader() {
             self.consensus_wait_duration.reset_before_submission();
         }
+        if let Some(timing) = self.pending_timing.take() {
+            self.apply_timing(timing);
+        }
 
+        self.shared_state.record_round_performance();
+        self.shared_state.audit_round_inclusion();
+        self.shared_state.commit_round_proposal();
+        if self
+            .shared_state
+            .pending_signer
+            .as_ref()
+            .is_some_and(|(activation, _)| *activation <= new_block)
+        {
+            let (_, signer) = self.shared_state.pending_signer.take().unwrap();
+            tracing::info!(address = ?signer.address(), "rotated the signing key");
+            self.shared_state.signer = signer;
+        }
         self.shared_state.block_height = new_block;
+        self.shared_state.block_timestamp = block_timestamp;
         self.shared_state.round_leader = new_leader;
+        self.shared_state.reannounce_key_handover();
+        self.shared_state.vote_ledger.reset(new_block);
+        self.shared_state.fallback.reset();
+        self.shared_state.fallback_submission = None;
+        self.shared_state.carry_over_late_pre_proposals(new_block);
+        if let Some(certification) = self.shared_state.proposal_certification.as_mut() {
+            certification.reset(new_block);
+        }
+        self.round_span = Self::round_span(new_block, new_leader);
+
+        self.shared_state.arrival_latencies.finish_round();
+        let arrival_latency = self
+            .shared_state
+            .arrival_latencies
+            .percentile(ARRIVAL_LATENCY_PERCENTILE);
+
+        self.current_state = Box::new(BidAggregationState::new(
+            self.consensus_wait_duration
+                .update_for_new_round(info, arrival_latency)
+        ));
+    }
+
+    /// Hands our consensus messages over to the given key. The handover is
+    /// announced to the other validators now and the key is signed with from
+    /// [`KEY_HANDOVER_LEAD_BLOCKS`] blocks on, until then the current key
+    /// stays in use. We stay known by the peer id of the key we started with,
+    /// but the key itself is retired with the handover: our bundles are sent
+    /// from the new key too, so it has to be a node of the angstrom contract
+    /// by then.
+    ///
+    /// None if the key is in use or our last handover isn't done yet.
+    pub fn rotate_signer(&mut self, signer: AngstromSigner) -> Option<KeyRotation> {
+        self.shared_state.schedule_key_handover(signer)
+    }
+
+    /// Schedules the key handovers of the validators with `schedule`, see
+    /// [`KeySchedule::load`].
+    pub fn with_key_schedule(mut self, schedule: KeySchedule) -> Self {
+        self.shared_state.key_schedule = schedule;
+        self
+    }
+
+    /// Validates the orders of late pre-proposals with the order pool, so
+    /// that they can go into the next round. They are dropped without it.
+    pub fn with_order_pool(
+        mut self,
+        order_pool: UnboundedMeteredSender<NetworkOrderEvent>
+    ) -> Self {
+        self.shared_state.order_pool = Some(order_pool);
+        self
+    }
+
+    /// Where the clearing reports of the rounds are stored.
+    pub fn with_clearing_reports(mut self, reports: ClearingReportStore) -> Self {
+        self.shared_state.clearing_reports = reports;
+        self
+    }
+
+    /// Where the gas charged to the orders of our landed bundles is
+    /// reconciled.
+    pub fn with_gas_reconciliations(mut self, reconciliations: GasReconciliationStore) -> Self {
+        self.shared_state.gas_reconciliations = reconciliations;
+        self
+    }
+
+    /// Archives the books and solutions of every round that got a proposal,
+    /// for archive nodes to serve.
+    pub fn with_order_archive(mut self, archive: OrderArchive) -> Self {
+        self.shared_state.order_archive = Some(archive);
+        self
+    }
+
+    /// Where the performance of the validators is recorded.
+    pub fn with_validator_performance(mut self, performance: ValidatorPerformanceStore) -> Self {
+        self.shared_state.validator_performance = performance;
+        self
+    }
+
+    /// Where the inclusion fairness of the leaders is recorded.
+    pub fn with_inclusion_fairness(mut self, fairness: InclusionFairnessStore) -> Self {
+        self.shared_state.inclusion_fairness = fairness;
+        self
+    }
+
+    /// Takes the configured fee of every pool from its matched surplus when
+    /// building bundles, `protocol_share_e6` of it goes to the protocol and
+    /// the rest to the LPs.
+    pub fn with_surplus_fees(mut self, protocol_share_e6: u32) -> Self {
+        self.shared_state.protocol_fee_share_e6 = Some(protocol_share_e6);
+        self
+    }
+
+    /// Version of the deployed contract. No bundles are built for a version
+    /// this node doesn't know.
+    pub fn with_contract_version(mut self, version: ContractVersion) -> Self {
+        self.shared_state.contract_version = version;
+        self
+    }
+
+    /// Checks the private orders only the leader of a round vouches for with
+    /// the validator, see [`LeaderOrderChecker`]. Without it they are left
+    /// out of the rounds.
+    pub fn with_leader_order_checker(mut self, checker: LeaderOrderChecker) -> Self {
+        self.shared_state.leader_orders = Some(checker);
+        self
+    }
+
+    /// Timestamp of the block the first round builds on, the later ones take
+    /// it from the block they start with.
+    pub fn with_block_timestamp(mut self, timestamp: u64) -> Self {
+        self.shared_state.block_timestamp = timestamp;
+        self
+    }
+
+    /// Only submits and counts the proposal of a round once `threshold`
+    /// members of the committee verified and signed it. The leader collects
+    /// their shares before it submits the bundle, and the orders of a
+    /// proposal the committee didn't certify aren't committed to the next
+    /// rounds. We sign with `key_share` if we are a member.
+    pub fn with_proposal_committee(
+        mut self,
+        committee: ProposalCommittee,
+        key_share: Option<KeyShare>
+    ) -> Self {
+        self.shared_state.proposal_certification =
+            Some(ProposalCertification::new(committee, key_share, self.shared_state.block_height));
+        self
+    }
+
+    /// Follows the rounds without taking part in them. The orders of the
+    /// rounds are still matched and every proposal is verified, but nothing
+    /// is signed or submitted, so the node doesn't need to be a validator.
+    pub fn with_observer_mode(mut self) -> Self {
+        self.shared_state.observer = true;
+        self
+    }
 
+    /// Takes the time of the rounds, waits and timeouts included, from the
+    /// clock. The round in progress starts over.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.consensus_wait_duration = PreProposalWaitTrigger::with_clock(
+            self.shared_state.order_storage.clone(),
+            clock.clone()
+        )
+        .with_timing(self.shared_state.timing);
+        self.shared_state.fallback = FallbackSubmitter::with_clock(clock.clone());
+        self.shared_state.clock = clock;
         self.current_state = Box::new(BidAggregationState::new(
-            self.consensus_wait_duration.update_for_new_round(info)
+            self.consensus_wait_duration
+                .update_for_new_round(None, None)
         ));
+        self
+    }
+
+    /// Times the rounds by `timing`, the round in progress included.
+    pub fn with_timing(mut self, timing: ConsensusTiming) -> Self {
+        self.apply_timing(timing);
+        self
+    }
+
+    /// Takes on new times from the next round on, the round in progress keeps
+    /// the times it started with.
+    pub fn set_timing(&mut self, timing: ConsensusTiming) {
+        tracing::info!(?timing, "updating the consensus timing with the next round");
+        self.pending_timing = Some(timing);
+    }
+
+    fn apply_timing(&mut self, timing: ConsensusTiming) {
+        self.consensus_wait_duration.set_timing(timing);
+        self.shared_state.timing = timing;
+    }
+
+    /// The times of the round in progress.
+    pub fn timing(&self) -> ConsensusTiming {
+        self.shared_state.timing
+    }
+
+    /// What every validator attested to in the current round.
+    pub fn vote_ledger(&self) -> &VoteLedger {
+        &self.shared_state.vote_ledger
+    }
+
+    /// The solutions we computed verifying the proposals of the rounds.
+    #[cfg(feature = "testnet")]
+    pub fn proposal_verifications(&self) -> &crate::ProposalVerifications {
+        &self.shared_state.verifications
+    }
+
+    /// Every key handover we know of, see [`KeySchedule::handovers`].
+    pub fn key_handovers(&self) -> impl Iterator<Item = &KeyHandover> {
+        self.shared_state.key_schedule.handovers()
+    }
+
+    /// Evidence of misbehaving validators collected since the last call.
+    pub fn take_evidence(&mut self) -> Vec<Evidence> {
+        self.shared_state.vote_ledger.take_evidence()
     }
 
     pub fn handle_message(&mut self, event: StromConsensusEvent) {
-        self.current_state
-            .on_consensus_message(&mut self.shared_state, event);
+        let _round = self.round_span.enter();
+        // handoffs, key handovers and shares matter no matter which state we
+        // are in
+        match event {
+            StromConsensusEvent::BundleHandoff(peer_id, handoff) => {
+                self.shared_state.handle_bundle_handoff(peer_id, handoff)
+            }
+            StromConsensusEvent::KeyHandover(peer_id, handover) => {
+                self.shared_state.handle_key_handover(peer_id, handover)
+            }
+            StromConsensusEvent::ProposalShare(peer_id, share) => {
+                self.shared_state.handle_proposal_share(peer_id, share)
+            }
+            event => self
+                .current_state
+                .on_consensus_message(&mut self.shared_state, event)
+        }
     }
 }
 
@@ -125,14 +403,23 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
+        let _round = this.round_span.enter();
+        // keeps our copy of the orders warm ahead of the pre-proposal
+        this.shared_state.order_set.sync();
 
         if let Poll::Ready(Some(transitioned_state)) = this
             .current_state
             .poll_transition(&mut this.shared_state, cx)
         {
-            tracing::info!("transitioning to new round state");
+            tracing::info!(
+                i_am_leader = this.shared_state.i_am_leader(),
+                from = this.current_state.name(),
+                to = transitioned_state.name(),
+                "transitioning to new round state"
+            );
             this.current_state = transitioned_state;
         }
+        this.shared_state.poll_fallback_submission(cx);
 
         if let Some(message) = this.shared_state.messages.pop_front() {
             return Poll::Ready(Some(message))
@@ -143,18 +430,91 @@ where
 }
 
 pub struct SharedRoundState<P, Matching> {
-    block_height:     BlockNumber,
-    angstrom_address: Address,
-    matching_engine:  Matching,
-    signer:           AngstromSigner,
-    round_leader:     PeerId,
-    validators:       Vec<AngstromValidator>,
-    order_storage:    Arc<OrderStorage>,
-    _metrics:         ConsensusMetricsWrapper,
-    pool_registry:    UniswapAngstromRegistry,
-    uniswap_pools:    SyncedUniswapPools,
-    provider:         Arc<MevBoostProvider<P>>,
-    messages:         VecDeque<ConsensusMessage>
+    block_height:           BlockNumber,
+    /// timestamp of the block the round builds on. the books are ranked
+    /// against it instead of our clock, so every node ranks them the same
+    block_timestamp:        u64,
+    angstrom_address:       Address,
+    matching_engine:        Matching,
+    /// the key our consensus messages are signed and our bundles sent with,
+    /// rotated by handovers
+    signer:                 AngstromSigner,
+    /// the peer id we are known by in the validator set, the one of the key
+    /// we started with
+    validator_id:           PeerId,
+    /// key we switch to from the block of its handover on
+    pending_signer:         Option<(BlockNumber, AngstromSigner)>,
+    /// handovers of the validators to new keys, ours included
+    key_schedule:           KeySchedule,
+    round_leader:           PeerId,
+    validators:             Vec<AngstromValidator>,
+    order_storage:          Arc<OrderStorage>,
+    /// the pending limit orders of the storage, updated as they change so the
+    /// pre-proposal doesn't have to snapshot them at the deadline
+    order_set:              OrderSetMirror,
+    metrics:                ConsensusMetricsWrapper,
+    bundle_metrics:         BundleBuildingMetricsWrapper,
+    pool_registry:          UniswapAngstromRegistry,
+    uniswap_pools:          SyncedUniswapPools,
+    provider:               Arc<MevBoostProvider<P>>,
+    messages:               VecDeque<ConsensusMessage>,
+    vote_ledger:            VoteLedger,
+    late_pre_proposals:     LatePreProposals,
+    arrival_latencies:      ArrivalLatencies,
+    fallback:               FallbackSubmitter,
+    /// our submission of the leaders bundle as a backup
+    fallback_submission:    Option<(BundleHandoff, BoxFuture<'static, Submission>)>,
+    clearing_reports:       ClearingReportStore,
+    /// gas the orders of our landed bundles were charged against what the
+    /// bundles cost
+    gas_reconciliations:    GasReconciliationStore,
+    /// where the books and solutions of the rounds are archived, if anywhere
+    order_archive:          Option<OrderArchive>,
+    round_performance:      RoundPerformance,
+    validator_performance:  ValidatorPerformanceStore,
+    inclusion_fairness:     InclusionFairnessStore,
+    /// orders we had validated when the pre-proposals of the round closed,
+    /// the proposal is audited against them
+    cutoff_orders:          Option<HashSet<B256>>,
+    /// share of the surplus fees that goes to the protocol. no fees are taken
+    /// if unset
+    protocol_fee_share_e6:  Option<u32>,
+    /// version of the angstrom contract, bundles are only built for versions
+    /// we know the layout of
+    contract_version:       ContractVersion,
+    /// we only relay the messages of the rounds and verify their proposals,
+    /// never signing or submitting anything ourselves
+    observer:               bool,
+    /// committee the proposals have to be signed by, if any
+    proposal_certification: Option<ProposalCertification>,
+    /// checks the private orders of the leader, nobody else vouches for them
+    leader_orders:          Option<LeaderOrderChecker>,
+    /// books solved this round, the verification of our own proposal reuses
+    /// the solution it was built from
+    solution_cache:         SolutionCache,
+    /// the proposal of the round, its orders are committed to the next rounds
+    /// once the round is over
+    round_proposal:         Option<Proposal>,
+    /// pools, carry-over priorities and block timestamp the books of the round
+    /// were last solved against, the clearing reports of the proposal are built
+    /// on them
+    solved_books:           Option<SolvedBooks>,
+    /// the order pool the orders of last rounds late pre-proposals are
+    /// validated by before they can go into our next pre-proposal
+    order_pool:             Option<UnboundedMeteredSender<NetworkOrderEvent>>,
+    /// where the rounds take the time from
+    clock:                  Clock,
+    /// waits, timeouts and budgets of the rounds
+    timing:                 ConsensusTiming,
+    #[cfg(feature = "testnet")]
+    verifications:          crate::ProposalVerifications
+}
+
+/// What the books of a round were solved against besides the orders.
+struct SolvedBooks {
+    pools:      HashMap<FixedBytes<32>, (Address, Address, PoolSnapshot, u16)>,
+    carry_over: HashMap<B256, u64>,
+    timestamp:  u64
 }
 
 // contains shared impls
@@ -178,18 +538,49 @@ where
         matching_engine: Matching
     ) -> Self {
         Self {
+            validator_id: signer.id(),
             block_height,
+            block_timestamp: 0,
             angstrom_address,
             round_leader,
             validators,
+            order_set: OrderSetMirror::new(&order_storage),
             order_storage,
             pool_registry,
             uniswap_pools,
             signer,
-            _metrics: metrics,
+            metrics,
+            bundle_metrics: BundleBuildingMetricsWrapper::new(),
             matching_engine,
+            pending_signer: None,
+            key_schedule: KeySchedule::default(),
             messages: VecDeque::new(),
-            provider: Arc::new(provider)
+            provider: Arc::new(provider),
+            vote_ledger: VoteLedger::new(block_height),
+            late_pre_proposals: LatePreProposals::new(block_height),
+            arrival_latencies: ArrivalLatencies::default(),
+            fallback: FallbackSubmitter::default(),
+            fallback_submission: None,
+            clearing_reports: ClearingReportStore::default(),
+            gas_reconciliations: GasReconciliationStore::default(),
+            order_archive: None,
+            round_performance: RoundPerformance::default(),
+            validator_performance: ValidatorPerformanceStore::default(),
+            inclusion_fairness: InclusionFairnessStore::default(),
+            cutoff_orders: None,
+            leader_orders: None,
+            protocol_fee_share_e6: None,
+            contract_version: ContractVersion::default(),
+            observer: false,
+            proposal_certification: None,
+            solution_cache: SolutionCache::default(),
+            round_proposal: None,
+            solved_books: None,
+            order_pool: None,
+            clock: Clock::system(),
+            timing: ConsensusTiming::default(),
+            #[cfg(feature = "testnet")]
+            verifications: Default::default()
         }
     }
 
@@ -198,68 +589,328 @@ where
     }
 
     fn i_am_leader(&self) -> bool {
-        self.round_leader == self.signer.id()
+        !self.observer && self.round_leader == self.validator_id
+    }
+
+    /// The keys the leader of the round signs with.
+    fn leader_keys(&self) -> Vec<PeerId> {
+        self.key_schedule
+            .keys_of(self.round_leader, self.block_height)
+    }
+
+    /// The validator that signs with `key` this round.
+    fn validator_of(&self, key: PeerId) -> Option<PeerId> {
+        let validators = self.validators.iter().map(|v| v.peer_id);
+        self.key_schedule
+            .validator_of(key, validators, self.block_height)
     }
 
     fn two_thirds_of_validation_set(&self) -> usize {
         (2 * self.validators.len()).div_ceil(3)
     }
 
+    /// Snapshots of the pools bundles can be built for. Pools that aren't
+    /// synced yet and pools whose parameters were changed on chain since they
+    /// were configured are left out, the bundles skip the solutions of pools
+    /// without a snapshot.
     fn fetch_pool_snapshot(
         &self
     ) -> HashMap<FixedBytes<32>, (Address, Address, PoolSnapshot, u16)> {
         self.uniswap_pools
+            .all()
             .iter()
-            .map(|(key, pool)| {
-                tracing::info!(?key, "getting snapshot");
+            .filter_map(|(key, pool)| {
+                if !self.pool_registry.is_current(key) {
+                    tracing::warn!(pool_id = ?key, "pool parameters are stale, skipping the pool");
+                    return None
+                }
+                tracing::debug!(pool_id = ?key, "fetching pool snapshot");
                 let (token_a, token_b, snapshot) =
-                    pool.read().unwrap().fetch_pool_snapshot().unwrap();
-                let entry = self.pool_registry.get_ang_entry(key).unwrap();
+                    pool.read().unwrap().fetch_pool_snapshot().ok()?;
+                let entry = self.pool_registry.get_ang_entry(key)?;
 
-                (*key, (token_a, token_b, snapshot, entry.store_index as u16))
+                Some((*key, (token_a, token_b, snapshot, entry.store_index as u16)))
             })
             .collect::<HashMap<_, _>>()
     }
 
-    fn matching_engine_output(
+    /// Fees to take from the matched surplus of each pool, by the fee of the
+    /// pool in the angstrom config store.
+    fn fee_config(&self) -> FeeConfig {
+        let Some(protocol_share_e6) = self.protocol_fee_share_e6 else {
+            return FeeConfig::default()
+        };
+
+        self.uniswap_pools
+            .pool_ids()
+            .into_iter()
+            .filter_map(|key| Some((key, self.pool_registry.get_ang_entry(&key)?.fee_in_e6)))
+            .fold(FeeConfig::new(protocol_share_e6), |config, (pool_id, fee_e6)| {
+                config.with_pool_fee(pool_id, fee_e6)
+            })
+    }
+
+    /// The reward of a top of block order is claimed by whoever put it into
+    /// their pre-proposal. We simulate every order against the pool ourselves,
+    /// drop the ones that claim more than [`TOB_REWARD_TOLERANCE_E6`] allows
+    /// and rank the rest by the simulated reward.
+    fn verify_searcher_rewards(
         &self,
-        pre_proposal_aggregation: HashSet<PreProposalAggregation>
-    ) -> BoxFuture<'static, eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>> {
-        // fetch
-        let mut limit = Vec::new();
-        let mut searcher = Vec::new();
-
-        for pre_proposal_agg in pre_proposal_aggregation {
-            pre_proposal_agg.pre_proposals.into_iter().for_each(|pre| {
-                limit.extend(pre.limit);
-                searcher.extend(pre.searcher);
-            });
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pool_snapshots: &HashMap<FixedBytes<32>, (Address, Address, PoolSnapshot, u16)>
+    ) -> Vec<OrderWithStorageData<TopOfBlockOrder>> {
+        let offered = searcher.len();
+        let verified = searcher
+            .into_iter()
+            .filter_map(|mut order| {
+                let (_, _, snapshot, _) = pool_snapshots.get(&order.pool_id)?;
+                match ToBOutcome::verify_claimed_reward(&order, snapshot, TOB_REWARD_TOLERANCE_E6) {
+                    Ok(outcome) => {
+                        order.tob_reward = outcome.total_reward;
+                        Some(order)
+                    }
+                    Err(error) => {
+                        tracing::debug!(
+                            order_hash = ?order.order_id.hash,
+                            %error,
+                            "dropping top of block order"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let rejected = offered - verified.len();
+        if rejected != 0 {
+            self.metrics.add_rejected_tob_orders(rejected);
         }
 
-        let limit = self.filter_quorum_orders(limit);
-        let searcher = self.filter_quorum_orders(searcher);
+        verified
+    }
+
+    fn matching_engine_output(
+        &mut self,
+        pre_proposal_aggregation: HashSet<PreProposalAggregation>
+    ) -> SolveFuture {
+        self.solve_round(pre_proposal_aggregation, false).1
+    }
+
+    /// Like [`Self::matching_engine_output`], also streaming the solution of
+    /// every pool as soon as the matching engine solved its book.
+    fn streaming_matching_engine_output(
+        &mut self,
+        pre_proposal_aggregation: HashSet<PreProposalAggregation>
+    ) -> (PartialSolutions, SolveFuture) {
+        self.solve_round(pre_proposal_aggregation, true)
+    }
+
+    fn solve_round(
+        &mut self,
+        pre_proposal_aggregation: HashSet<PreProposalAggregation>,
+        streaming: bool
+    ) -> (PartialSolutions, SolveFuture) {
+        let pre_proposals = pre_proposal_aggregation
+            .into_iter()
+            .flat_map(|agg| agg.pre_proposals)
+            .collect::<Vec<_>>();
+        pre_proposals.iter().for_each(|pre| {
+            self.vote_ledger.record_pre_proposal(pre);
+        });
+
+        // every node has to come to the same set of orders for the same
+        // aggregations, so quorum is only counted over the votes they contain
+        let votes = VoteLedger::from_pre_proposals(self.block_height, &pre_proposals);
+        let (two_thirds, leader_keys) = (self.two_thirds_of_validation_set(), self.leader_keys());
+        let mut limit = votes.round_orders(two_thirds, &leader_keys, |pre| &pre.limit);
         let pool_snapshots = self.fetch_pool_snapshot();
+        let mut searcher = self.verify_searcher_rewards(
+            votes.round_orders(two_thirds, &leader_keys, |pre| &pre.searcher),
+            &pool_snapshots
+        );
+        // only the leader vouches for its private orders, every node checks
+        // them against its own state before matching them
+        let unvouched = votes.unvouched_orders(two_thirds, &leader_keys);
+        let checker = self.leader_orders.clone().filter(|_| !unvouched.is_empty());
+        if checker.is_none() {
+            limit.retain(|order| !unvouched.contains(&order.order_id.hash));
+            searcher.retain(|order| !unvouched.contains(&order.order_id.hash));
+        }
+        // pools that aren't synced can't be matched until they load
+        limit.retain(|order| pool_snapshots.contains_key(&order.pool_id));
+        searcher.retain(|order| pool_snapshots.contains_key(&order.pool_id));
+        // carried over by when the round's votes say the orders reached quorum,
+        // never by what we saw of the rounds before
+        let carry_over = carry_over_priority(
+            self.block_height,
+            votes.quorum_blocks(two_thirds),
+            COMMITMENT_WINDOW_BLOCKS
+        );
+
+        let (block_height, timestamp) = (self.block_height, self.block_timestamp);
+        let fingerprint = SolutionCache::fingerprint(
+            block_height,
+            self.matching_engine.config_fingerprint(),
+            &limit,
+            &searcher,
+            &pool_snapshots,
+            &carry_over
+        );
+        self.solved_books = Some(SolvedBooks {
+            pools: pool_snapshots.clone(),
+            carry_over: carry_over.clone(),
+            timestamp
+        });
+
+        if let Some(solution) = self.solution_cache.get(&fingerprint) {
+            tracing::debug!(?fingerprint, "reusing the solution of an identical book");
+            return (futures::stream::empty().boxed(), futures::future::ready(Ok(solution)).boxed())
+        }
 
         let matcher = self.matching_engine.clone();
+        let cache = self.solution_cache.clone();
+
+        let (partial, solving) = match checker {
+            // the books are only known once the orders are checked, so they
+            // aren't streamed
+            Some(checker) => {
+                let solving = async move {
+                    let limit = checker.check_limit(limit, &unvouched).await;
+                    let searcher = checker.check_searcher(searcher, &unvouched).await;
+                    matcher
+                        .solve_pools(limit, searcher, pool_snapshots, carry_over, timestamp)
+                        .await
+                };
+                (futures::stream::empty().boxed(), solving.boxed())
+            }
+            None if streaming => matcher.solve_pools_streaming(
+                limit,
+                searcher,
+                pool_snapshots,
+                carry_over,
+                timestamp
+            ),
+            None => {
+                let solving = async move {
+                    matcher
+                        .solve_pools(limit, searcher, pool_snapshots, carry_over, timestamp)
+                        .await
+                };
+                (futures::stream::empty().boxed(), solving.boxed())
+            }
+        };
+
+        let solved = async move {
+            solving
+                .await
+                .inspect(|solution| cache.insert(fingerprint, solution.clone()))
+        }
+        .boxed();
 
-        async move { matcher.solve_pools(limit, searcher, pool_snapshots).await }.boxed()
+        (partial, solved)
     }
 
-    fn filter_quorum_orders<O: Hash + Eq + Clone>(
-        &self,
-        input: Vec<OrderWithStorageData<O>>
-    ) -> Vec<OrderWithStorageData<O>> {
-        let two_thirds = self.two_thirds_of_validation_set();
-        input
-            .into_iter()
-            .fold(HashMap::new(), |mut acc, order| {
-                *acc.entry(order).or_insert(0) += 1;
-                acc
-            })
-            .into_iter()
-            .filter(|(_, count)| *count >= two_thirds)
-            .map(|(order, _)| order)
-            .collect()
+    /// Records how each validator took part in the round that just ended.
+    fn record_round_performance(&mut self) {
+        let (vote_ledger, key_schedule) = (&self.vote_ledger, &self.key_schedule);
+        let block_height = self.block_height;
+        let round = self.round_performance.finish_round(
+            self.validators.iter().map(|v| v.peer_id),
+            self.round_leader,
+            |validator| {
+                key_schedule
+                    .keys_of(*validator, block_height)
+                    .iter()
+                    .any(|key| vote_ledger.has_voted(key))
+            },
+            self.round_proposal.is_some()
+        );
+
+        round.iter().for_each(|(peer_id, participation)| {
+            self.metrics.record_validator_round(*peer_id, participation)
+        });
+        self.validator_performance
+            .record_round(self.block_height, round);
+    }
+
+    /// Audits which of the orders we had validated by the cutoff the proposal
+    /// of the round left out.
+    fn audit_round_inclusion(&mut self) {
+        let Some(expected) = self.cutoff_orders.take() else { return };
+        let Some(proposal) = self.round_proposal.as_ref() else { return };
+
+        let audit = InclusionAudit::new(self.round_leader, &expected, proposal);
+        if !audit.excluded.is_empty() {
+            let (leader, excluded, score) = (audit.leader, audit.excluded.len(), audit.score());
+            tracing::info!(?leader, excluded, score, "proposal left out orders");
+        }
+        self.metrics.record_inclusion_audit(&audit);
+        self.inclusion_fairness.record(audit);
+    }
+
+    /// Records the orders the rounds proposal offered and filled, and builds
+    /// its clearing reports.
+    fn commit_round_proposal(&mut self) {
+        let solved_books = self.solved_books.take();
+        let Some(proposal) = self.round_proposal.take() else { return };
+        if self.proposal_certification.is_some() && !self.is_certified(&proposal.hash()) {
+            let block_height = proposal.block_height;
+            tracing::warn!(block_height, "the committee didn't certify the proposal");
+            return
+        }
+
+        let votes = VoteLedger::from_pre_proposals(
+            proposal.block_height,
+            &proposal.flattened_pre_proposals()
+        );
+        let (two_thirds, leader_keys) = (self.two_thirds_of_validation_set(), self.leader_keys());
+        let offered = votes.round_orders(two_thirds, &leader_keys, |pre| &pre.limit);
+        let filled = proposal
+            .solutions
+            .iter()
+            .flat_map(|solution| solution.limit.iter())
+            .filter(|outcome| outcome.is_filled())
+            .map(|outcome| outcome.id.hash)
+            .collect::<HashSet<_>>();
+
+        self.order_storage.record_committed_round(
+            proposal.block_height,
+            offered.iter().map(|order| &order.order_id),
+            &filled
+        );
+
+        // the reports are off the critical path
+        if let Some(books) = solved_books {
+            let (reports, block_height) = (self.clearing_reports.clone(), proposal.block_height);
+            let (limit, solutions) = (offered.clone(), proposal.solutions.clone());
+            tokio::task::spawn_blocking(move || {
+                let built = matching_engine::clearing_reports(
+                    block_height,
+                    limit,
+                    &solutions,
+                    &books.pools,
+                    &books.carry_over,
+                    books.timestamp
+                );
+                reports.insert(block_height, built);
+            });
+        }
+
+        if let Some(archive) = self.order_archive.clone() {
+            let round = ArchivedRound {
+                block_number: proposal.block_height,
+                limit:        offered,
+                searcher:     votes.round_orders(two_thirds, &leader_keys, |pre| &pre.searcher),
+                solutions:    proposal.solutions
+            };
+            // off the critical path, like the clearing reports
+            tokio::task::spawn_blocking(move || {
+                if let Err(error) = archive.insert(&round) {
+                    let block_number = round.block_number;
+                    tracing::warn!(block_number, %error, "failed to archive round");
+                }
+            });
+        }
     }
 
     fn handle_pre_proposal_aggregation(
@@ -272,22 +923,408 @@ where
             peer_id,
             pre_proposal_agg,
             pre_proposal_agg_set,
-            |proposal, block| proposal.is_valid(block)
+            |state, agg| {
+                if !agg.is_valid(&state.block_height) {
+                    return false
+                }
+                // the aggregator isn't at fault if one of the pre-proposals
+                // equivocates, so the aggregation is still accepted
+                agg.pre_proposals.iter().for_each(|pre| {
+                    state.vote_ledger.record_pre_proposal(pre);
+                });
+                true
+            }
         )
     }
 
+    /// Pre-proposals that arrive once the round stopped collecting them can't
+    /// count towards it anymore. They are buffered so that their orders can be
+    /// carried over into the next round.
+    fn handle_late_pre_proposal(&mut self, peer_id: PeerId, pre_proposal: PreProposal) {
+        let Some(validator) = self
+            .validator_of(pre_proposal.source)
+            .filter(|_| pre_proposal.is_valid(&self.block_height))
+        else {
+            tracing::debug!(peer=?peer_id, "got a invalid late pre-proposal");
+            return
+        };
+        self.record_arrival(validator);
+        if self.has_voted(validator) {
+            tracing::trace!(peer=?peer_id, "got a duplicate late pre-proposal");
+            return
+        }
+        self.vote_ledger.record_pre_proposal(&pre_proposal);
+        self.round_performance.record_late_pre_proposal(validator);
+
+        let source = pre_proposal.source;
+        if let Some(lateness) = self
+            .late_pre_proposals
+            .insert(pre_proposal, self.clock.now())
+        {
+            tracing::debug!(peer=?peer_id, ?source, ?lateness, "buffered late pre-proposal");
+            self.metrics.record_late_pre_proposal(lateness.as_millis());
+        }
+    }
+
+    /// Aggregations that arrive once we moved on to the proposal don't count
+    /// anymore, they are only recorded against their source.
+    fn handle_late_pre_proposal_aggregation(
+        &mut self,
+        peer_id: PeerId,
+        pre_proposal_agg: PreProposalAggregation
+    ) {
+        let Some(validator) = self
+            .validator_of(pre_proposal_agg.source)
+            .filter(|_| pre_proposal_agg.is_valid(&self.block_height))
+        else {
+            tracing::debug!(peer=?peer_id, "got a invalid late pre-proposal aggregation");
+            return
+        };
+
+        self.round_performance.record_late_aggregation(validator);
+    }
+
+    fn record_arrival(&mut self, validator: PeerId) {
+        if validator != self.validator_id {
+            self.arrival_latencies
+                .record_arrival(validator, self.clock.now());
+        }
+    }
+
+    /// Whether we got a pre-proposal of the validator this round, signed by
+    /// any of its keys.
+    fn has_voted(&self, validator: PeerId) -> bool {
+        self.key_schedule
+            .keys_of(validator, self.block_height)
+            .iter()
+            .any(|key| self.vote_ledger.has_voted(key))
+    }
+
+    /// Orders the pre-proposals of the proposal flagged for inclusion that the
+    /// books of its solutions leave out. Only the proposal is read, every
+    /// validator comes to the same ones for the same proposal.
+    fn omitted_forced_orders(&self, proposal: &Proposal) -> Vec<B256> {
+        VoteLedger::from_pre_proposals(proposal.block_height, &proposal.flattened_pre_proposals())
+            .omitted_forced_orders(self.forced_inclusion_quorum(), &proposal.solutions)
+    }
+
+    /// Flags that force an order into the books. The leader picks which two
+    /// thirds of the pre-proposals make up its proposal, any two thirds it
+    /// picks hold this many of the flags on an order two thirds of the
+    /// validation set flagged.
+    fn forced_inclusion_quorum(&self) -> usize {
+        (2 * self.two_thirds_of_validation_set()).saturating_sub(self.validators.len())
+    }
+
+    /// Stops pre-proposals from counting towards this round.
+    fn close_pre_proposals(&mut self) {
+        self.late_pre_proposals.close(self.clock.now());
+        if self.cutoff_orders.is_none() {
+            let orders = self.order_storage.get_all_orders();
+            self.cutoff_orders = Some(
+                orders
+                    .limit
+                    .iter()
+                    .map(|order| order.order_id.hash)
+                    .chain(orders.searcher.iter().map(|order| order.order_id.hash))
+                    .collect()
+            );
+        }
+    }
+
+    /// Hands the orders of last rounds late pre-proposals we don't have to
+    /// the order pool. The ones that validate make it into our next
+    /// pre-proposal from the pool, without an order pool they're dropped.
+    /// Their source isn't blamed for the ones that don't, it only signed off
+    /// on them for a round that is over.
+    fn carry_over_late_pre_proposals(&mut self, new_block: BlockNumber) {
+        let late = self.late_pre_proposals.len();
+        let timestamp = self.clock.unix_now().as_secs();
+        let order_storage = self.order_storage.clone();
+        let carried = self
+            .late_pre_proposals
+            .carry_over(new_block, timestamp, |hash| {
+                order_storage.fetch_status_of_order(hash).is_some()
+            });
+
+        let carried_over = carried
+            .iter()
+            .map(|(_, orders)| orders.total_orders())
+            .sum::<usize>();
+        if carried_over == 0 {
+            return
+        }
+        let Some(order_pool) = self.order_pool.as_ref() else {
+            tracing::debug!(late, carried_over, "no order pool to validate late orders with");
+            return
+        };
+
+        tracing::info!(late, carried_over, "validating the orders of late pre-proposals");
+        self.metrics.add_carried_over_orders(carried_over);
+        let orders = carried
+            .into_iter()
+            .flat_map(|(_, orders)| {
+                orders
+                    .limit
+                    .into_iter()
+                    .map(|order| AllOrders::from(order.order))
+                    .chain(orders.searcher.into_iter().map(|order| order.order.into()))
+            })
+            .collect();
+        let _ = order_pool.send(NetworkOrderEvent::CarriedOverOrders { orders });
+    }
+
+    /// All orders for our pre-proposal.
+    fn pre_proposal_orders(&self) -> OrderSet<GroupedVanillaOrder, TopOfBlockOrder> {
+        OrderSet {
+            limit:    self.order_set.limit_orders(),
+            searcher: self.order_storage.top_tob_orders()
+        }
+    }
+
+    /// The validators after the leader, in peer id order, take over the
+    /// submission of its bundle if it fails to submit in time.
+    fn fallback_submitters(&self) -> Vec<PeerId> {
+        let validators = self
+            .validators
+            .iter()
+            .map(|v| v.peer_id)
+            .sorted()
+            .collect::<Vec<_>>();
+        let Some(leader) = validators.iter().position(|v| v == &self.round_leader) else {
+            return vec![]
+        };
+
+        validators
+            .iter()
+            .cycle()
+            .skip(leader + 1)
+            .take(FALLBACK_SUBMITTERS.min(validators.len() - 1))
+            .copied()
+            .collect()
+    }
+
+    /// Sends the `execute` call with the given calldata to the relays, or to
+    /// the public mempool if none of them takes it and the fallback is set.
+    /// Only the leader goes public, the backups only ever use the relays so
+    /// the bundle isn't in the public mempool more than once.
+    fn submit_bundle(
+        &self,
+        calldata: Bytes,
+        may_go_public: bool
+    ) -> BoxFuture<'static, Submission> {
+        let mut tx = TransactionRequest::default()
+            .with_to(self.angstrom_address)
+            .with_from(self.signer.address())
+            .with_input(calldata);
+
+        let provider = self.provider.clone();
+        let signer = self.signer.clone();
+
+        async move {
+            tracing::info!("populating bundle transaction");
+            provider
+                .populate_gas_nonce_chain_id(signer.address(), &mut tx)
+                .await;
+
+            provider.sign_and_send(signer, tx, may_go_public).await
+        }
+        .boxed()
+    }
+
+    fn handle_bundle_handoff(&mut self, peer_id: PeerId, handoff: BundleHandoff) {
+        let Some(validator) = self
+            .validator_of(handoff.source)
+            .filter(|_| handoff.is_valid(&self.block_height))
+        else {
+            tracing::debug!(peer=?peer_id, "got a invalid bundle handoff");
+            return
+        };
+
+        // the backups and the leader are known by their validator ids, no
+        // matter which key they sign with
+        let by_validator = BundleHandoff { source: validator, ..handoff.clone() };
+        if self.fallback.on_handoff(
+            &by_validator,
+            self.validator_id,
+            self.round_leader,
+            self.clock.now()
+        ) {
+            self.propagate_message(ConsensusMessage::PropagateBundleHandoff(handoff));
+        }
+    }
+
+    /// Announces the handover of our consensus messages to `signer`, which we
+    /// switch to once it activates.
+    fn schedule_key_handover(&mut self, signer: AngstromSigner) -> Option<KeyRotation> {
+        // the handover we announced before a restart
+        if let Some(handover) = self
+            .key_schedule
+            .handover_of(self.validator_id)
+            .filter(|handover| {
+                handover.new_key == signer.id() && self.block_height < handover.activation_block
+            })
+            .cloned()
+        {
+            let rotation = self.key_rotation(&signer, handover.activation_block);
+            self.pending_signer = Some((handover.activation_block, signer));
+            self.propagate_message(ConsensusMessage::PropagateKeyHandover(handover));
+            return Some(rotation)
+        }
+
+        let activation_block = self.block_height + KEY_HANDOVER_LEAD_BLOCKS;
+        let handover = KeyHandover::new(self.block_height, &self.signer, &signer, activation_block);
+        let validators = self.validators.iter().map(|v| v.peer_id);
+        if !self
+            .key_schedule
+            .on_handover(&handover, self.block_height, validators)
+        {
+            tracing::warn!(
+                address = ?signer.address(),
+                "can't hand over to the key, it's in use or the last handover isn't done yet"
+            );
+            return None
+        }
+
+        let address = signer.address();
+        tracing::info!(?address, activation_block, "handing over the signing key");
+        let rotation = self.key_rotation(&signer, activation_block);
+        self.pending_signer = Some((activation_block, signer));
+        self.propagate_message(ConsensusMessage::PropagateKeyHandover(handover));
+
+        Some(rotation)
+    }
+
+    fn key_rotation(&self, signer: &AngstromSigner, activation_block: BlockNumber) -> KeyRotation {
+        KeyRotation {
+            validator_id: self.validator_id,
+            signing_key: signer.address(),
+            activation_block
+        }
+    }
+
+    /// Announces our pending handover again every round until it activates,
+    /// so that validators that missed it or restarted since still switch
+    /// keys with us.
+    fn reannounce_key_handover(&mut self) {
+        if self.pending_signer.is_none() {
+            return
+        }
+        if let Some(handover) = self.key_schedule.handover_of(self.validator_id).cloned() {
+            self.propagate_message(ConsensusMessage::PropagateKeyHandover(handover));
+        }
+    }
+
+    /// Whether the committee certified the proposal with this hash.
+    fn is_certified(&self, proposal_hash: &B256) -> bool {
+        self.proposal_certification
+            .as_ref()
+            .is_some_and(|certification| certification.is_certified(proposal_hash))
+    }
+
+    /// Co-signs a proposal of the round we verified, if we are in the
+    /// committee.
+    fn sign_proposal_share(&mut self, proposal_hash: B256) {
+        if self.observer {
+            return
+        }
+        let Some(certification) = self.proposal_certification.as_mut() else { return };
+        let Some(share) = certification.sign(self.validator_id, proposal_hash) else { return };
+
+        self.propagate_message(ConsensusMessage::PropagateProposalShare(share));
+    }
+
+    fn handle_proposal_share(&mut self, peer_id: PeerId, share: ProposalShare) {
+        let Some(member) = self.validator_of(share.source) else {
+            tracing::debug!(peer=?peer_id, "got a proposal share of a non validator");
+            return
+        };
+        let Some(certification) = self.proposal_certification.as_mut() else { return };
+
+        if certification.on_share(member, share.clone()) {
+            self.propagate_message(ConsensusMessage::PropagateProposalShare(share));
+        } else {
+            tracing::trace!(peer=?peer_id, "got a invalid or duplicate proposal share");
+        }
+    }
+
+    fn handle_key_handover(&mut self, peer_id: PeerId, handover: KeyHandover) {
+        let validators = self.validators.iter().map(|v| v.peer_id);
+        if !self
+            .key_schedule
+            .on_handover(&handover, self.block_height, validators)
+        {
+            tracing::debug!(peer=?peer_id, "got a invalid or known key handover");
+            return
+        }
+
+        tracing::info!(
+            source = ?handover.source,
+            new_key = ?handover.new_key,
+            activation_block = handover.activation_block,
+            "validator is handing over its signing key"
+        );
+        self.propagate_message(ConsensusMessage::PropagateKeyHandover(handover));
+    }
+
+    /// Takes over the submission of the leaders bundle once our deadline as a
+    /// backup passed.
+    fn poll_fallback_submission(&mut self, cx: &mut Context<'_>) {
+        if self.observer {
+            return
+        }
+        if let Some(handoff) = self.fallback.poll_expired(cx) {
+            tracing::warn!(
+                leader = ?self.round_leader,
+                "leader didn't submit its bundle in time, submitting it as backup"
+            );
+            let submission = self.submit_bundle(handoff.calldata.clone(), false);
+            self.fallback_submission = Some((handoff, submission));
+        }
+
+        let Some((handoff, mut submission)) = self.fallback_submission.take() else { return };
+        match submission.poll_unpin(cx) {
+            Poll::Ready(Submission { tx_hash, path, accepted }) => {
+                tracing::info!(%tx_hash, %path, accepted, "submitted bundle as backup");
+                node_health().record_relay_submission(accepted && path == SubmissionPath::Relays);
+                self.bundle_metrics.record_submission(path, accepted);
+                if accepted {
+                    let announcement = handoff.to_submitted(&self.signer);
+                    self.propagate_message(ConsensusMessage::PropagateBundleHandoff(announcement));
+                }
+            }
+            Poll::Pending => self.fallback_submission = Some((handoff, submission))
+        }
+    }
+
     fn verify_proposal(&mut self, peer_id: PeerId, proposal: Proposal) -> Option<Proposal> {
-        if self.round_leader != peer_id {
-            tracing::debug!("got invalid proposal");
+        if self.round_leader != peer_id
+            || !self
+                .key_schedule
+                .is_key_of(self.round_leader, proposal.source, self.block_height)
+        {
+            tracing::debug!(
+                peer=?peer_id,
+                leader=?self.round_leader,
+                "got proposal from non leader"
+            );
             return None
         }
 
-        proposal.is_valid(&self.block_height).then(|| {
-            self.messages
-                .push_back(ConsensusMessage::PropagateProposal(proposal.clone()));
+        if !proposal.is_valid(&self.block_height) {
+            // proposals of other heights are just stale
+            if proposal.block_height == self.block_height {
+                self.round_performance.record_failed_proposal();
+            }
+            return None
+        }
 
-            proposal
-        })
+        self.vote_ledger.record_proposal(&proposal);
+        self.round_proposal = Some(proposal.clone());
+        self.messages
+            .push_back(ConsensusMessage::PropagateProposal(proposal.clone()));
+
+        Some(proposal)
     }
 
     fn handle_pre_proposal(
@@ -300,7 +1337,23 @@ where
             peer_id,
             pre_proposal,
             pre_proposal_set,
-            |proposal, block| proposal.is_valid(block)
+            // a conflicting pre-proposal is dropped so the source is only
+            // counted once
+            |state, pre| {
+                let Some(validator) = state
+                    .validator_of(pre.source)
+                    .filter(|_| pre.is_valid(&state.block_height))
+                else {
+                    return false
+                };
+                state.record_arrival(validator);
+                // in the grace window of a handover the validator can sign
+                // with two keys, only one of them gets to vote
+                if !state.vote_ledger.has_voted(&pre.source) && state.has_voted(validator) {
+                    return false
+                }
+                state.vote_ledger.record_pre_proposal(pre)
+            }
         )
     }
 
@@ -309,7 +1362,7 @@ where
         peer_id: PeerId,
         proposal: Pro,
         proposal_set: &mut HashSet<Pro>,
-        valid: impl FnOnce(&Pro, &BlockNumber) -> bool
+        valid: impl FnOnce(&mut Self, &Pro) -> bool
     ) where
         Pro: Into<ConsensusMessage> + Eq + Hash + Clone
     {
@@ -318,7 +1371,7 @@ where
             return
         }
         // ensure pre_proposal is valid
-        if !valid(&proposal, &self.block_height) {
+        if !valid(self, &proposal) {
             tracing::info!(peer=?peer_id,"got a invalid consensus message");
             return
         }
@@ -340,7 +1393,23 @@ where
 pub enum ConsensusMessage {
     PropagatePreProposal(PreProposal),
     PropagatePreProposalAgg(PreProposalAggregation),
-    PropagateProposal(Proposal)
+    PropagateProposal(Proposal),
+    PropagateBundleHandoff(BundleHandoff),
+    PropagateKeyHandover(KeyHandover),
+    PropagateProposalShare(ProposalShare)
+}
+
+impl ConsensusMessage {
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            Self::PropagatePreProposal(..) => "PreProposal",
+            Self::PropagatePreProposalAgg(..) => "PreProposalAggregation",
+            Self::PropagateProposal(..) => "Proposal",
+            Self::PropagateBundleHandoff(..) => "BundleHandoff",
+            Self::PropagateKeyHandover(..) => "KeyHandover",
+            Self::PropagateProposalShare(..) => "ProposalShare"
+        }
+    }
 }
 
 impl From<PreProposal> for ConsensusMessage {
@@ -358,7 +1427,7 @@ impl From<PreProposalAggregation> for ConsensusMessage {
 #[cfg(test)]
 pub mod tests {
     use std::{
-        collections::{HashMap, HashSet},
+        collections::HashSet,
         sync::Arc,
         task::{Context, Poll},
         time::{Duration, Instant}
@@ -366,7 +1435,7 @@ pub mod tests {
 
     use alloy::{
         primitives::Address,
-        providers::{fillers::*, network::Ethereum, ProviderBuilder, RootProvider, *}
+        providers::{network::Ethereum, ProviderBuilder}
     };
     use angstrom_metrics::ConsensusMetricsWrapper;
     use angstrom_network::manager::StromConsensusEvent;
@@ -387,11 +1456,12 @@ pub mod tests {
     use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 
     use super::{
-        pre_proposal::PreProposalState, ConsensusMessage, RoundStateMachine, SharedRoundState
+        pre_proposal::PreProposalState, test_kit::ProviderDef, ConsensusMessage, RoundStateMachine,
+        SharedRoundState
     };
     use crate::{
         rounds::{pre_proposal_aggregation::PreProposalAggregationState, ConsensusState},
-        AngstromValidator
+        AngstromValidator, ConsensusTiming
     };
 
     impl RoundStateMachine<ProviderDef, MockMatchingEngine> {
@@ -403,15 +1473,6 @@ pub mod tests {
         }
     }
 
-    type ProviderDef = FillProvider<
-        JoinFill<
-            Identity,
-            JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>
-        >,
-        RootProvider,
-        Ethereum
-    >;
-
     fn init_tracing() {
         let _ = tracing_subscriber::fmt()
             .with_env_filter(EnvFilter::from_default_env())
@@ -428,7 +1489,7 @@ pub mod tests {
         // Initialize test components
         let pool_store = Arc::new(AngstromPoolConfigStore::default());
         let (tx, _rx) = tokio::sync::mpsc::channel(2);
-        let uniswap_pools = SyncedUniswapPools::new(Arc::new(HashMap::new()), tx);
+        let uniswap_pools = SyncedUniswapPools::new(Default::default(), tx);
         let reg = UniswapPoolRegistry::default();
 
         let pool_registry = UniswapAngstromRegistry::new(reg, pool_store);
@@ -453,11 +1514,27 @@ pub mod tests {
             pool_registry,
             uniswap_pools,
             provider,
-            MockMatchingEngine {}
+            MockMatchingEngine::new()
         );
         RoundStateMachine::new(shared_state)
     }
 
+    #[tokio::test]
+    async fn new_timing_applies_from_the_next_round_on() {
+        let mut state_machine = setup_state_machine().await;
+        let timing = ConsensusTiming {
+            aggregation_timeout: Duration::from_millis(10_500),
+            ..Default::default()
+        };
+
+        state_machine.set_timing(timing);
+        assert_eq!(state_machine.timing(), ConsensusTiming::default());
+
+        let leader = state_machine.shared_state.round_leader;
+        state_machine.reset_round(2, 0, leader);
+        assert_eq!(state_machine.timing(), timing);
+    }
+
     #[tokio::test]
     async fn test_bid_aggregation_to_pre_proposal() {
         init_tracing();
@@ -584,7 +1661,7 @@ pub mod tests {
         let new_leader = PeerId::random();
 
         // Reset round with new block and leader
-        state_machine.reset_round(new_block, new_leader);
+        state_machine.reset_round(new_block, 1_700_000_012, new_leader);
 
         assert_eq!(state_machine.shared_state.block_height, new_block);
         assert_eq!(state_machine.shared_state.round_leader, new_leader);
@@ -43,14 +43,26 @@ impl PreProposalState {
         P: Provider + 'static,
         Matching: MatchingEngineHandle
     {
-        // generate my pre_proposal
-        let my_preproposal =
-            PreProposal::new(block_height, &handles.signer, handles.order_storage.get_all_orders());
+        handles.arrival_latencies.mark_trigger(handles.clock.now());
+        // observers only collect the pre_proposals of the validators
+        if !handles.observer {
+            // generate my pre_proposal
+            let orders = handles.pre_proposal_orders();
+            let first_seen = handles.order_storage.first_seen_blocks();
+            let my_preproposal = PreProposal::new(
+                block_height,
+                &handles.signer,
+                orders,
+                &first_seen,
+                handles.i_am_leader()
+            );
 
-        // propagate my pre_proposal
-        handles.propagate_message(ConsensusMessage::PropagatePreProposal(my_preproposal.clone()));
+            // propagate my pre_proposal
+            handles
+                .propagate_message(ConsensusMessage::PropagatePreProposal(my_preproposal.clone()));
 
-        pre_proposals.insert(my_preproposal);
+            pre_proposals.insert(my_preproposal);
+        }
 
         // ensure we get polled to start the checks for when we have 2f +1 pre_proposals
         // collected
@@ -66,6 +78,10 @@ where
     P: Provider + 'static,
     Matching: MatchingEngineHandle
 {
+    fn name(&self) -> &'static str {
+        "PreProposal"
+    }
+
     fn on_consensus_message(
         &mut self,
         handles: &mut SharedRoundState<P, Matching>,
@@ -92,6 +108,10 @@ where
                     self.waker.wake_by_ref();
                 }
             }
+            // handled by the round state machine regardless of the state
+            StromConsensusEvent::BundleHandoff(..)
+            | StromConsensusEvent::KeyHandover(..)
+            | StromConsensusEvent::ProposalShare(..) => {}
         }
     }
 
@@ -112,7 +132,11 @@ where
         let cur_preproposals = self.pre_proposals.len();
         let twthr = handles.two_thirds_of_validation_set();
         if cur_preproposals >= twthr {
-            tracing::info!("got two thrids, moving to pre proposal aggregation");
+            tracing::info!(
+                pre_proposals = cur_preproposals,
+                two_thirds = twthr,
+                "got two thirds, moving to pre proposal aggregation"
+            );
 
             return Poll::Ready(Some(Box::new(PreProposalAggregationState::new(
                 std::mem::take(&mut self.pre_proposals),
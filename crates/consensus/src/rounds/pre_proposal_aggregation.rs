@@ -21,11 +21,16 @@ use crate::rounds::{finalization::FinalizationState, proposal::ProposalState};
 /// this node is the leader and receives 2/3 pre_proposals_aggregation ->
 /// proposal state
 /// 2) this node isn't leader and receives the proposal -> finalization
+///
+/// A leader whose quorum of aggregations only arrives after the aggregation
+/// timeout doesn't propose, the bundle would be too late for the block.
 #[derive(Debug)]
 pub struct PreProposalAggregationState {
     pre_proposals_aggregation: HashSet<PreProposalAggregation>,
     proposal:                  Option<Proposal>,
     trigger_time:              Instant,
+    /// we got the quorum too late to propose
+    timed_out:                 bool,
     waker:                     Waker
 }
 
@@ -41,24 +46,29 @@ impl PreProposalAggregationState {
         P: Provider + 'static,
         Matching: MatchingEngineHandle
     {
-        // generate my pre_proposal aggregation
-        let my_preproposal_aggregation = PreProposalAggregation::new(
-            handles.block_height,
-            &handles.signer,
-            pre_proposals.into_iter().collect::<Vec<_>>()
-        );
+        handles.close_pre_proposals();
+
+        // observers only collect the aggregations of the validators
+        if !handles.observer {
+            // generate my pre_proposal aggregation
+            let my_preproposal_aggregation = PreProposalAggregation::new(
+                handles.block_height,
+                &handles.signer,
+                pre_proposals.into_iter().collect::<Vec<_>>()
+            );
 
-        // propagate my pre_proposal
-        handles.propagate_message(my_preproposal_aggregation.clone().into());
+            // propagate my pre_proposal
+            handles.propagate_message(my_preproposal_aggregation.clone().into());
 
-        pre_proposals_aggregation.insert(my_preproposal_aggregation);
+            pre_proposals_aggregation.insert(my_preproposal_aggregation);
+        }
 
         // ensure we get polled to start the checks for when we have 2f +1 pre_proposals
         // collected
         waker.wake_by_ref();
         tracing::info!("starting pre proposal aggregation");
 
-        Self { pre_proposals_aggregation, proposal: None, waker, trigger_time }
+        Self { pre_proposals_aggregation, proposal: None, waker, trigger_time, timed_out: false }
     }
 }
 
@@ -67,14 +77,18 @@ where
     P: Provider + 'static,
     Matching: MatchingEngineHandle
 {
+    fn name(&self) -> &'static str {
+        "PreProposalAggregation"
+    }
+
     fn on_consensus_message(
         &mut self,
         handles: &mut SharedRoundState<P, Matching>,
         message: StromConsensusEvent
     ) {
         match message {
-            StromConsensusEvent::PreProposal(..) => {
-                tracing::debug!("got a lagging pre-proposal");
+            StromConsensusEvent::PreProposal(peer_id, pre_proposal) => {
+                handles.handle_late_pre_proposal(peer_id, pre_proposal);
             }
             StromConsensusEvent::PreProposalAgg(peer_id, pre_proposal_agg) => handles
                 .handle_pre_proposal_aggregation(
@@ -88,6 +102,10 @@ where
                     self.waker.wake_by_ref();
                 }
             }
+            // handled by the round state machine regardless of the state
+            StromConsensusEvent::BundleHandoff(..)
+            | StromConsensusEvent::KeyHandover(..)
+            | StromConsensusEvent::ProposalShare(..) => {}
         }
     }
 
@@ -109,7 +127,17 @@ where
         let twthr = handles.two_thirds_of_validation_set();
 
         // if  we are the leader, then we will transition
-        if cur_preproposals_aggs >= twthr && handles.i_am_leader() {
+        if cur_preproposals_aggs >= twthr && handles.i_am_leader() && !self.timed_out {
+            let elapsed = handles.clock.now().duration_since(self.trigger_time);
+            if elapsed > handles.timing.aggregation_timeout {
+                tracing::warn!(
+                    elapsed = elapsed.as_millis(),
+                    timeout = handles.timing.aggregation_timeout.as_millis(),
+                    "aggregations reached a quorum after the timeout, not proposing"
+                );
+                self.timed_out = true;
+                return Poll::Pending
+            }
             tracing::info!(
                 ?cur_preproposals_aggs,
                 ?twthr,
@@ -5,23 +5,21 @@ use std::{
     time::{Duration, Instant}
 };
 
-use tokio::time::{interval, Interval};
+use angstrom_utils::clock::{Clock, Interval};
 
-use crate::rounds::OrderStorage;
+use crate::{rounds::OrderStorage, timing::ConsensusTiming};
 
-/// How soon we send our pre-proposal
-const DEFAULT_DURATION: Duration = Duration::from_secs(9);
 /// The frequency we adjust our duration estimate. we have it super frequent
 /// because its very low overhead to check
 const CHECK_INTERVAL: Duration = Duration::from_millis(1);
 /// How much to scale per order in the order pool
 const ORDER_SCALING: Duration = Duration::from_millis(10);
-/// How close we want to be to the creation of the ethereum block
-const TARGET_SUBMISSION_TIME_REM: Duration = Duration::from_millis(800);
-/// Eth block time
-const ETH_BLOCK_TIME: Duration = Duration::from_secs(12);
 /// The amount of the difference we scale by to reach
 const SCALING_REM_ADJUSTMENT: u32 = 3;
+/// Percentile of the pre-proposal arrival latency we make room for
+pub const ARRIVAL_LATENCY_PERCENTILE: u8 = 95;
+/// Headroom on top of the arrival latency
+const ARRIVAL_LATENCY_MARGIN: Duration = Duration::from_millis(250);
 
 /// When we should trigger to build our pre-proposals
 /// this is very important for maximizing how long we can
@@ -37,46 +35,104 @@ pub struct PreProposalWaitTrigger {
     /// to track our scaling
     order_storage:  Arc<OrderStorage>,
     /// Waker
-    check_interval: Interval
+    check_interval: Interval,
+    /// where the time is taken from
+    clock:          Clock,
+    /// the wait, the deadline we aim for and the block time
+    timing:         ConsensusTiming
 }
 
 impl Clone for PreProposalWaitTrigger {
     fn clone(&self) -> Self {
         Self {
             wait_duration:  self.wait_duration,
-            start_instant:  Instant::now(),
+            start_instant:  self.clock.now(),
             order_storage:  self.order_storage.clone(),
-            check_interval: interval(CHECK_INTERVAL)
+            check_interval: self.clock.interval(CHECK_INTERVAL),
+            clock:          self.clock.clone(),
+            timing:         self.timing
         }
     }
 }
 
 impl PreProposalWaitTrigger {
     pub fn new(order_storage: Arc<OrderStorage>) -> Self {
+        Self::with_clock(order_storage, Clock::system())
+    }
+
+    pub fn with_clock(order_storage: Arc<OrderStorage>, clock: Clock) -> Self {
+        let timing = ConsensusTiming::default();
         Self {
-            wait_duration: DEFAULT_DURATION,
+            wait_duration: timing.wait,
             order_storage,
-            start_instant: Instant::now(),
-            check_interval: interval(CHECK_INTERVAL)
+            start_instant: clock.now(),
+            check_interval: clock.interval(CHECK_INTERVAL),
+            clock,
+            timing
         }
     }
 
-    pub fn update_for_new_round(&mut self, info: Option<LastRoundInfo>) -> Self {
+    pub fn with_timing(mut self, timing: ConsensusTiming) -> Self {
+        self.set_timing(timing);
+        self
+    }
+
+    /// Takes on new times. The wait we adapted over the last rounds is
+    /// dropped for the new one, it's adapted again from the next round on.
+    pub fn set_timing(&mut self, timing: ConsensusTiming) {
+        self.wait_duration = timing.wait;
+        self.timing = timing;
+    }
+
+    /// `arrival_latency` is how long after our pre-proposal the ones of the
+    /// other validators arrive, see [`ARRIVAL_LATENCY_PERCENTILE`].
+    pub fn update_for_new_round(
+        &mut self,
+        info: Option<LastRoundInfo>,
+        arrival_latency: Option<Duration>
+    ) -> Self {
         if let Some(info) = info {
             self.update_wait_duration_base(info);
         }
+        if let Some(latency) = arrival_latency {
+            self.bound_by_arrival_latency(latency);
+        }
 
         self.clone()
     }
 
+    /// The pre-proposals of the other validators have to arrive before we need
+    /// to submit. So the slower the network, the earlier we have to trigger.
+    fn bound_by_arrival_latency(&mut self, latency: Duration) {
+        let max_wait = self
+            .timing
+            .proposal_deadline
+            .saturating_sub(latency + ARRIVAL_LATENCY_MARGIN)
+            .max(self.timing.min_wait);
+
+        if self.wait_duration > max_wait {
+            tracing::info!(
+                latency = latency.as_millis(),
+                trigger = max_wait.as_millis(),
+                "bounding wait duration by pre-proposal arrival latency"
+            );
+            self.wait_duration = max_wait;
+        }
+    }
+
+    /// When the wait started.
+    pub fn started_at(&self) -> Instant {
+        self.start_instant
+    }
+
     pub fn reset_before_submission(&mut self) {
         self.wait_duration = self
             .wait_duration
-            .saturating_sub(TARGET_SUBMISSION_TIME_REM);
+            .saturating_sub(self.timing.submission_headroom());
     }
 
     fn update_wait_duration_base(&mut self, info: LastRoundInfo) {
-        let base = ETH_BLOCK_TIME - TARGET_SUBMISSION_TIME_REM;
+        let base = self.timing.proposal_deadline;
 
         if info.time_to_complete < base && self.wait_duration < base {
             // if we overestimated the time, we will push our trigger back
@@ -109,7 +165,7 @@ impl Future for PreProposalWaitTrigger {
                 .wait_duration
                 .saturating_sub(ORDER_SCALING * order_cnt as u32);
 
-            if Instant::now().duration_since(self.start_instant) > target_resolve {
+            if self.clock.now().duration_since(self.start_instant) > target_resolve {
                 return Poll::Ready(())
             }
         }
@@ -126,3 +182,47 @@ pub struct LastRoundInfo {
     /// the start of the round to submitting the bundle
     pub time_to_complete: Duration
 }
+
+#[cfg(test)]
+mod tests {
+    use order_pool::PoolConfig;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_duration_bounded_by_arrival_latency() {
+        let mut trigger =
+            PreProposalWaitTrigger::new(Arc::new(OrderStorage::new(&PoolConfig::default())));
+
+        // a fast network leaves the default untouched
+        trigger.update_for_new_round(None, Some(Duration::from_millis(500)));
+        assert_eq!(trigger.wait_duration, ConsensusTiming::default().wait);
+
+        trigger.update_for_new_round(None, Some(Duration::from_secs(3)));
+        assert_eq!(trigger.wait_duration, Duration::from_millis(7950));
+
+        // never below the minimum
+        trigger.update_for_new_round(None, Some(Duration::from_secs(30)));
+        assert_eq!(trigger.wait_duration, ConsensusTiming::default().min_wait);
+    }
+
+    #[tokio::test]
+    async fn wait_duration_follows_the_timing() {
+        let timing = ConsensusTiming {
+            wait: Duration::from_secs(5),
+            proposal_deadline: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let mut trigger =
+            PreProposalWaitTrigger::new(Arc::new(OrderStorage::new(&PoolConfig::default())))
+                .with_timing(timing);
+        assert_eq!(trigger.wait_duration, Duration::from_secs(5));
+
+        trigger.update_for_new_round(None, Some(Duration::from_secs(6)));
+        assert_eq!(trigger.wait_duration, Duration::from_millis(3750));
+
+        // reloading starts over from the new wait
+        trigger.set_timing(ConsensusTiming::default());
+        assert_eq!(trigger.wait_duration, ConsensusTiming::default().wait);
+    }
+}
@@ -1,23 +1,37 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    pin::Pin,
     task::{Context, Poll, Waker},
     time::{Duration, Instant}
 };
 
 use alloy::{
-    network::TransactionBuilder, providers::Provider, rpc::types::TransactionRequest,
+    consensus::Transaction,
+    network::TransactionResponse,
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
     sol_types::SolCall
 };
+use angstrom_metrics::{node_health, RoundOutcome};
 use angstrom_network::manager::StromConsensusEvent;
 use angstrom_types::{
-    consensus::{PreProposalAggregation, Proposal},
+    consensus::{BundleHandoff, PreProposalAggregation, Proposal},
     contract_bindings::angstrom::Angstrom,
-    contract_payloads::angstrom::{AngstromBundle, BundleGasDetails},
-    orders::PoolSolution
+    contract_payloads::{
+        angstrom::{AngstromBundle, BundleGasDetails},
+        calldata_gas,
+        tob::ToBOutcome
+    },
+    matching::uniswap::PoolSnapshot,
+    mev_boost::{Submission, SubmissionPath},
+    orders::{GasReconciliation, PoolSolution},
+    primitive::PoolId
 };
-use futures::{future::BoxFuture, FutureExt, StreamExt};
-use matching_engine::MatchingEngineHandle;
-use pade::PadeEncode;
+use angstrom_utils::clock::Sleep;
+use futures::{future::BoxFuture, Future, FutureExt, StreamExt};
+use matching_engine::{MatchingEngineHandle, PartialSolutions};
+use tracing::Instrument;
 
 use super::{ConsensusState, SharedRoundState};
 use crate::rounds::{preproposal_wait_trigger::LastRoundInfo, ConsensusMessage};
@@ -31,8 +45,35 @@ type MatchingEngineFuture = BoxFuture<'static, eyre::Result<(Vec<PoolSolution>,
 /// it once its landed on chain. We only submit after it has landed on chain as
 /// in the case of inclusion games. the proposal will just be dropped and there
 /// is no need for others to verify.
+///
+/// Before submitting, the bundle is handed to the backup submitters of the
+/// round, which take over if we don't announce our submission in time.
+///
+/// With a proposal committee the proposal is propagated right away instead,
+/// and the bundle is only submitted once the committee certified the proposal.
+/// If it doesn't within the finalization budget, nothing is submitted.
+///
+/// The matching engine streams the solution of every pool as soon as its book
+/// is solved. The top of block orders of these are simulated while the other
+/// books are still being solved, so that building the bundle only reuses
+/// their outcomes.
 pub struct ProposalState {
     matching_engine_future: Option<MatchingEngineFuture>,
+    partial_solutions:      Option<PartialSolutions>,
+    /// the snapshot the bundle is built against, fetched ahead of the solve
+    pool_snapshot:          HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+    /// outcomes of the top of block orders of the streamed solutions
+    tob_outcomes:           HashMap<B256, ToBOutcome>,
+    /// sending the bundle to the relays
+    relay_future:           Option<BoxFuture<'static, Submission>>,
+    handoff:                Option<BundleHandoff>,
+    /// calldata of the bundle held back until the committee certified the
+    /// proposal, and when we give up on that
+    uncertified:            Option<(Bytes, tracing::Span, Pin<Box<Sleep>>)>,
+    /// the bundle we submit, the gas it was estimated at and the gas of each
+    /// of its orders, reconciled with its receipt once it landed
+    submitted:              Option<(AngstromBundle, BundleGasDetails, HashMap<B256, u64>)>,
+    /// waiting for the submitted bundle to land
     submission_future:      Option<BoxFuture<'static, bool>>,
     pre_proposal_aggs:      Vec<PreProposalAggregation>,
     proposal:               Option<Proposal>,
@@ -54,14 +95,21 @@ impl ProposalState {
     {
         // queue building future
         waker.wake_by_ref();
-        tracing::info!("proposal");
+        tracing::info!(pre_proposal_aggs = pre_proposal_aggregation.len(), "starting proposal");
+        let (partial_solutions, matching_engine_future) =
+            handles.streaming_matching_engine_output(pre_proposal_aggregation.clone());
 
         Self {
-            matching_engine_future: Some(
-                handles.matching_engine_output(pre_proposal_aggregation.clone())
-            ),
+            matching_engine_future: Some(matching_engine_future),
+            partial_solutions: Some(partial_solutions),
+            pool_snapshot: handles.fetch_pool_snapshot(),
+            tob_outcomes: HashMap::new(),
             last_round_info: None,
             pre_proposal_aggs: pre_proposal_aggregation.into_iter().collect::<Vec<_>>(),
+            relay_future: None,
+            handoff: None,
+            uncertified: None,
+            submitted: None,
             submission_future: None,
             proposal: None,
             trigger_time,
@@ -69,6 +117,17 @@ impl ProposalState {
         }
     }
 
+    /// Simulates the top of block order of a pool that was solved ahead of
+    /// the others.
+    fn prepare_pool(&mut self, solution: &PoolSolution) {
+        let Some(tob) = solution.searcher.as_ref() else { return };
+        let Some((_, _, snapshot, _)) = self.pool_snapshot.get(&solution.id) else { return };
+
+        if let Ok(outcome) = ToBOutcome::from_tob_and_snapshot(tob, snapshot) {
+            self.tob_outcomes.insert(tob.order_id.hash, outcome);
+        }
+    }
+
     fn try_build_proposal<P, Matching>(
         &mut self,
         result: eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>,
@@ -78,16 +137,16 @@ impl ProposalState {
         P: Provider + 'static,
         Matching: MatchingEngineHandle
     {
-        self.last_round_info = Some(LastRoundInfo {
-            time_to_complete: Instant::now().duration_since(self.trigger_time)
-        });
+        let time_to_complete = handles.clock.now().duration_since(self.trigger_time);
+        self.last_round_info = Some(LastRoundInfo { time_to_complete });
 
-        tracing::debug!("starting to build proposal");
+        tracing::debug!(?time_to_complete, "starting to build proposal");
         let Ok((pool_solution, gas_info)) = result.inspect_err(|e| {
             tracing::error!(err=%e,
                 "Failed to properly build proposal, THERE SHALL BE NO PROPOSAL THIS BLOCK :("
             );
         }) else {
+            node_health().set_round_outcome(handles.block_height, RoundOutcome::BuildFailed);
             return false
         };
 
@@ -99,55 +158,223 @@ impl ProposalState {
         );
 
         self.proposal = Some(proposal.clone());
-        let snapshot = handles.fetch_pool_snapshot();
-
-        let Ok(bundle) =
-            AngstromBundle::from_proposal(&proposal, gas_info, &snapshot).inspect_err(|e| {
-                tracing::error!(err=%e,
-                    "failed to encode angstrom bundle, THERE SHALL BE NO PROPOSAL THIS BLOCK :("
-                );
-            })
+        let estimate = gas_info.clone();
+        let snapshot = std::mem::take(&mut self.pool_snapshot);
+        let tob_outcomes = std::mem::take(&mut self.tob_outcomes);
+        tracing::debug!(
+            prepared = tob_outcomes.len(),
+            "reusing the streamed top of block outcomes"
+        );
+
+        let Ok((bundle, pool_fees)) = AngstromBundle::from_proposal_with_tob_outcomes(
+            &proposal,
+            gas_info,
+            &snapshot,
+            &handles.fee_config(),
+            &tob_outcomes
+        )
+        .inspect_err(|e| {
+            tracing::error!(err=%e,
+                "failed to encode angstrom bundle, THERE SHALL BE NO PROPOSAL THIS BLOCK :("
+            );
+        }) else {
+            node_health().set_round_outcome(handles.block_height, RoundOutcome::BuildFailed);
+            return false
+        };
+
+        pool_fees
+            .iter()
+            .filter(|fees| fees.total() > 0)
+            .for_each(|fees| tracing::debug!(?fees, "took fees from the matched surplus"));
+
+        let Ok(payload) = bundle
+            .encode_for(handles.contract_version)
+            .inspect_err(|e| tracing::error!(err=%e, "failed to encode angstrom bundle"))
         else {
+            node_health().set_round_outcome(handles.block_height, RoundOutcome::BuildFailed);
             return false
         };
+        let bundle_span = tracing::info_span!(
+            "bundle_submission",
+            pools = bundle.pairs.len(),
+            tob_orders = bundle.top_of_block_orders.len(),
+            user_orders = bundle.user_orders.len(),
+            payload_len = payload.len()
+        );
+        let encoded = Angstrom::executeCall::new((payload.into(),)).abi_encode();
+        handles.bundle_metrics.record_bundle(
+            handles.block_height,
+            encoded.len(),
+            calldata_gas(&encoded)
+        );
+        pool_fees.iter().for_each(|pool| {
+            handles.bundle_metrics.record_pool(
+                &pool.pool_id.to_string(),
+                pool.matched_volume,
+                pool.amm_surplus,
+                pool.searcher_reward
+            )
+        });
 
-        let encoded = Angstrom::executeCall::new((bundle.pade_encode().into(),)).abi_encode();
+        self.submitted = Some((bundle, estimate, proposal.order_gas_units()));
 
-        let mut tx = TransactionRequest::default()
-            .with_to(handles.angstrom_address)
-            .with_from(handles.signer.address())
-            .with_input(encoded);
+        if handles.proposal_certification.is_some() {
+            // the committee verifies the proposal before the bundle goes out
+            handles.sign_proposal_share(proposal.hash());
+            handles
+                .messages
+                .push_back(ConsensusMessage::PropagateProposal(proposal));
+            let deadline = Box::pin(handles.clock.sleep(handles.timing.finalization_budget));
+            self.uncertified = Some((encoded.into(), bundle_span, deadline));
+            self.waker.wake_by_ref();
+            return true
+        }
 
-        let provider = handles.provider.clone();
-        let signer = handles.signer.clone();
+        self.submit(encoded.into(), bundle_span, handles)
+    }
 
-        let submission_future = async move {
-            tracing::info!("building bundle");
-            provider
-                .populate_gas_nonce_chain_id(signer.address(), &mut tx)
-                .await;
-
-            let (hash, success) = provider.sign_and_send(signer, tx).await;
-            tracing::info!("submitted bundle");
-            if !success {
-                return false
-            }
+    /// Hands the bundle to the backups and sends it to the relays. Nothing is
+    /// submitted past the proposal deadline, the bundle would miss the block.
+    fn submit<P, Matching>(
+        &mut self,
+        calldata: Bytes,
+        bundle_span: tracing::Span,
+        handles: &mut SharedRoundState<P, Matching>
+    ) -> bool
+    where
+        P: Provider + 'static,
+        Matching: MatchingEngineHandle
+    {
+        let elapsed = handles.clock.now().duration_since(self.trigger_time);
+        if elapsed > handles.timing.proposal_deadline {
+            tracing::warn!(
+                elapsed = elapsed.as_millis(),
+                deadline = handles.timing.proposal_deadline.as_millis(),
+                "bundle is ready past the proposal deadline, not submitting"
+            );
+            node_health().set_round_outcome(handles.block_height, RoundOutcome::BuildFailed);
+            return false
+        }
+
+        let handoff = BundleHandoff::new(
+            handles.block_height,
+            &handles.signer,
+            handles.fallback_submitters(),
+            calldata,
+            false
+        );
+        handles.propagate_message(ConsensusMessage::PropagateBundleHandoff(handoff.clone()));
+
+        let relay_future = handles
+            .submit_bundle(handoff.calldata.clone(), true)
+            .instrument(bundle_span)
+            .boxed();
+
+        self.waker.wake_by_ref();
+        self.handoff = Some(handoff);
+        self.relay_future = Some(relay_future);
+        true
+    }
 
-            // wait for next block. then see if transaction landed
-            provider
+    /// Announces the submission to the backups and waits for the bundle to
+    /// land. If neither the relays nor the public mempool accepted it, the
+    /// backups take it over, so it's waited on all the same.
+    fn on_relay_result<P, Matching>(
+        &mut self,
+        Submission { tx_hash, path, accepted: success }: Submission,
+        handles: &mut SharedRoundState<P, Matching>
+    ) -> bool
+    where
+        P: Provider + 'static,
+        Matching: MatchingEngineHandle
+    {
+        tracing::info!(%tx_hash, %path, success, "submitted bundle");
+        node_health().record_relay_submission(success && path == SubmissionPath::Relays);
+        handles.bundle_metrics.record_submission(path, success);
+        let block_height = handles.block_height;
+        let Some(handoff) = self.handoff.take() else {
+            node_health().set_round_outcome(block_height, RoundOutcome::BuildFailed);
+            return false
+        };
+        if success {
+            let announcement = handoff.to_submitted(&handles.signer);
+            handles.propagate_message(ConsensusMessage::PropagateBundleHandoff(announcement));
+        } else {
+            tracing::warn!(%tx_hash, %path, "bundle wasn't accepted, leaving it to the backups");
+        }
+
+        let provider = handles.provider.clone();
+        let angstrom_address = handles.angstrom_address;
+        let calldata = handoff.calldata;
+        let submitted = self.submitted.take();
+        let reconciliations = handles.gas_reconciliations.clone();
+        let metrics = handles.bundle_metrics.clone();
+        let submission_future = async move {
+            // wait for next block. then see if the bundle landed, whether we or
+            // a backup submitted it
+            let block_hash = provider
                 .watch_blocks()
                 .await
                 .unwrap()
                 .with_poll_interval(Duration::from_millis(10))
                 .into_stream()
                 .next()
-                .await;
-
-            provider
-                .get_transaction_by_hash(hash)
                 .await
-                .unwrap()
-                .is_some()
+                .and_then(|hashes| hashes.first().copied());
+            let landed = match block_hash {
+                Some(block_hash) => provider
+                    .get_block_by_hash(block_hash, BlockTransactionsKind::Full)
+                    .await
+                    .ok()
+                    .flatten()
+                    .and_then(|block| {
+                        block
+                            .transactions
+                            .txns()
+                            .find(|tx| tx.to() == Some(angstrom_address) && *tx.input() == calldata)
+                            .map(|tx| tx.tx_hash())
+                    }),
+                None => None
+            };
+
+            let included = landed.is_some();
+            let outcome = match (included, success) {
+                (true, _) => RoundOutcome::BundleIncluded,
+                (false, true) => RoundOutcome::BundleNotIncluded,
+                (false, false) => RoundOutcome::BuildFailed
+            };
+            node_health().set_round_outcome(block_height, outcome);
+            tracing::info!(?landed, %path, included, "bundle submission finished");
+            // a backup's submission isn't ours to count against the path
+            if landed == Some(tx_hash) {
+                metrics.record_landed(path);
+            }
+
+            if let Some(((bundle, estimate, gas_units), hash)) = submitted.zip(landed) {
+                match provider.get_transaction_receipt(hash).await {
+                    Ok(Some(receipt)) => {
+                        let reconciliation = GasReconciliation::new(
+                            receipt.block_number.unwrap_or(block_height),
+                            hash,
+                            &bundle,
+                            &estimate,
+                            &gas_units,
+                            receipt.gas_used,
+                            receipt.effective_gas_price
+                        );
+                        let overcharge = reconciliation.total_overcharge();
+                        tracing::debug!(tx_hash = %hash, overcharge, "reconciled the bundle gas");
+                        metrics.record_gas_reconciliation(&reconciliation);
+                        reconciliations.insert(reconciliation);
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        tracing::warn!(tx_hash = %hash, %error, "failed to fetch bundle receipt")
+                    }
+                }
+            }
+
+            included
         }
         .boxed();
 
@@ -163,13 +390,27 @@ where
     P: Provider + 'static,
     Matching: MatchingEngineHandle
 {
+    fn name(&self) -> &'static str {
+        "Proposal"
+    }
+
     fn on_consensus_message(
         &mut self,
-        _: &mut SharedRoundState<P, Matching>,
-        _: StromConsensusEvent
+        handles: &mut SharedRoundState<P, Matching>,
+        message: StromConsensusEvent
     ) {
-        // No messages at this point can effect the consensus round and thus are
-        // ignored.
+        // No messages at this point can effect the consensus round. Late
+        // pre-proposals are still buffered for the next round and late
+        // aggregations recorded against their source.
+        match message {
+            StromConsensusEvent::PreProposal(peer_id, pre_proposal) => {
+                handles.handle_late_pre_proposal(peer_id, pre_proposal);
+            }
+            StromConsensusEvent::PreProposalAgg(peer_id, pre_proposal_agg) => {
+                handles.handle_late_pre_proposal_aggregation(peer_id, pre_proposal_agg);
+            }
+            _ => {}
+        }
     }
 
     fn poll_transition(
@@ -177,6 +418,21 @@ where
         handles: &mut SharedRoundState<P, Matching>,
         cx: &mut Context<'_>
     ) -> Poll<Option<Box<dyn ConsensusState<P, Matching>>>> {
+        // the partial solutions are all sent before the full output, so they're
+        // drained before it's polled
+        if let Some(mut partial) = self.partial_solutions.take() {
+            loop {
+                match partial.poll_next_unpin(cx) {
+                    Poll::Ready(Some(solution)) => self.prepare_pool(&solution),
+                    Poll::Ready(None) => break,
+                    Poll::Pending => {
+                        self.partial_solutions = Some(partial);
+                        break
+                    }
+                }
+            }
+        }
+
         if let Some(mut b_fut) = self.matching_engine_future.take() {
             match b_fut.poll_unpin(cx) {
                 Poll::Ready(state) => {
@@ -189,15 +445,50 @@ where
             }
         }
 
+        if let Some((calldata, bundle_span, mut deadline)) = self.uncertified.take() {
+            let proposal_hash = self
+                .proposal
+                .as_ref()
+                .map(Proposal::hash)
+                .unwrap_or_default();
+            if handles.is_certified(&proposal_hash) {
+                tracing::info!(%proposal_hash, "the committee certified the proposal");
+                if !self.submit(calldata, bundle_span, handles) {
+                    return Poll::Ready(None)
+                }
+            } else if deadline.as_mut().poll(cx).is_ready() {
+                tracing::warn!(%proposal_hash, "the committee didn't certify the proposal in time");
+                node_health().set_round_outcome(handles.block_height, RoundOutcome::BuildFailed);
+                return Poll::Ready(None)
+            } else {
+                self.uncertified = Some((calldata, bundle_span, deadline));
+            }
+        }
+
+        if let Some(mut r_fut) = self.relay_future.take() {
+            match r_fut.poll_unpin(cx) {
+                Poll::Ready(result) => {
+                    if !self.on_relay_result(result, handles) {
+                        return Poll::Ready(None)
+                    }
+                }
+                Poll::Pending => self.relay_future = Some(r_fut)
+            }
+        }
+
         if let Some(mut b_fut) = self.submission_future.take() {
             match b_fut.poll_unpin(cx) {
                 Poll::Ready(transaction_landed) => {
                     if transaction_landed {
                         let proposal = self.proposal.take().unwrap();
-                        handles
-                            .messages
-                            .push_back(ConsensusMessage::PropagateProposal(proposal));
-                        cx.waker().wake_by_ref();
+                        handles.round_proposal = Some(proposal.clone());
+                        // the committee got the proposal before it was submitted
+                        if handles.proposal_certification.is_none() {
+                            handles
+                                .messages
+                                .push_back(ConsensusMessage::PropagateProposal(proposal));
+                            cx.waker().wake_by_ref();
+                        }
                     }
                     return Poll::Ready(None)
                 }
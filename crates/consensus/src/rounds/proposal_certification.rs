@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use alloy::primitives::{BlockNumber, B256};
+use angstrom_types::{
+    consensus::{
+        threshold::{BlsSignature, KeyShare},
+        ProposalCommittee, ProposalShare, ThresholdCertificate
+    },
+    primitive::PeerId
+};
+
+/// Collects the shares of the committee on the proposals of the round.
+///
+/// Shares can arrive before the proposal they sign, so they are collected per
+/// proposal hash.
+#[derive(Debug)]
+pub struct ProposalCertification {
+    committee:    ProposalCommittee,
+    /// our share of the committee key, if we are a member
+    key_share:    Option<KeyShare>,
+    block_height: BlockNumber,
+    certificates: HashMap<B256, ThresholdCertificate>
+}
+
+impl ProposalCertification {
+    pub fn new(
+        committee: ProposalCommittee,
+        key_share: Option<KeyShare>,
+        block_height: BlockNumber
+    ) -> Self {
+        Self { committee, key_share, block_height, certificates: HashMap::new() }
+    }
+
+    pub fn reset(&mut self, block_height: BlockNumber) {
+        self.block_height = block_height;
+        self.certificates.clear();
+    }
+
+    /// Signs our share of the proposal, if we are a member of the committee.
+    pub fn sign(&mut self, member: PeerId, proposal_hash: B256) -> Option<ProposalShare> {
+        let key_share = self.key_share.as_ref()?;
+        if self.committee.index_of(&member) != Some(key_share.index) {
+            return None
+        }
+
+        let share = ProposalShare::new(self.block_height, member, key_share, proposal_hash);
+        self.on_share(member, share.clone());
+        Some(share)
+    }
+
+    /// Records the share of a committee member, returns whether it's a new
+    /// one that should be relayed.
+    pub fn on_share(&mut self, member: PeerId, share: ProposalShare) -> bool {
+        let block_height = self.block_height;
+        self.certificates
+            .entry(share.proposal_hash)
+            .or_insert_with(|| ThresholdCertificate::new(block_height, share.proposal_hash))
+            .add_share(&self.committee, member, share)
+    }
+
+    /// Whether the committee signed the proposal with this hash.
+    pub fn is_certified(&self, proposal_hash: &B256) -> bool {
+        self.signature(proposal_hash).is_some()
+    }
+
+    /// The signature of the committee over the proposal with this hash.
+    pub fn signature(&self, proposal_hash: &B256) -> Option<BlsSignature> {
+        self.certificates
+            .get(proposal_hash)
+            .and_then(|certificate| certificate.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testing_tools::committee::bootstrap_committee;
+
+    use super::*;
+
+    #[test]
+    fn certifies_once_the_threshold_signed() {
+        let bootstrap = bootstrap_committee(3, 2).unwrap();
+        let members = bootstrap.committee.members().to_vec();
+        let certification = |i: usize| {
+            ProposalCertification::new(
+                bootstrap.committee.clone(),
+                Some(bootstrap.key_shares[i].clone()),
+                1
+            )
+        };
+        let mut ours = certification(0);
+        let hash = B256::repeat_byte(1);
+
+        // a share is only ever signed for our own index
+        assert_eq!(ours.sign(members[1], hash), None);
+        let share = |i: usize| certification(i).sign(members[i], hash).unwrap();
+
+        assert!(!ours.on_share(PeerId::random(), share(1)));
+        assert!(ours.sign(members[0], hash).is_some());
+        assert!(!ours.is_certified(&hash));
+        assert!(ours.on_share(members[1], share(1)));
+        assert!(ours.is_certified(&hash));
+
+        ours.reset(2);
+        assert!(!ours.is_certified(&hash));
+        // shares of the last round don't count towards this one
+        assert!(!ours.on_share(members[2], share(2)));
+    }
+}
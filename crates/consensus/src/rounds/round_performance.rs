@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use angstrom_types::{consensus::RoundParticipation, primitive::PeerId};
+
+/// Collects the faults of the validators over the current round.
+#[derive(Debug, Default)]
+pub struct RoundPerformance {
+    late_pre_proposals: HashSet<PeerId>,
+    late_aggregations:  HashSet<PeerId>,
+    /// the leaders proposal failed our verification
+    failed_proposal:    bool
+}
+
+impl RoundPerformance {
+    pub fn record_late_pre_proposal(&mut self, validator: PeerId) {
+        self.late_pre_proposals.insert(validator);
+    }
+
+    pub fn record_late_aggregation(&mut self, validator: PeerId) {
+        self.late_aggregations.insert(validator);
+    }
+
+    pub fn record_failed_proposal(&mut self) {
+        self.failed_proposal = true;
+    }
+
+    /// Ends the round and returns how every validator took part in it.
+    /// `voted` tells if we got a pre-proposal of the validator, late ones
+    /// included, `proposed` if we saw the proposal of the leader.
+    pub fn finish_round(
+        &mut self,
+        validators: impl IntoIterator<Item = PeerId>,
+        leader: PeerId,
+        voted: impl Fn(&PeerId) -> bool,
+        proposed: bool
+    ) -> Vec<(PeerId, RoundParticipation)> {
+        let round = std::mem::take(self);
+
+        validators
+            .into_iter()
+            .map(|validator| {
+                let led = validator == leader;
+                let participation = RoundParticipation {
+                    led,
+                    missed_pre_proposal: !voted(&validator),
+                    late_pre_proposal: round.late_pre_proposals.contains(&validator),
+                    late_aggregation: round.late_aggregations.contains(&validator),
+                    missed_proposal: led && !proposed,
+                    failed_proposal: led && round.failed_proposal
+                };
+
+                (validator, participation)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_faults_to_validators() {
+        let (leader, late, absent) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let mut performance = RoundPerformance::default();
+        performance.record_late_pre_proposal(late);
+        performance.record_late_aggregation(late);
+        performance.record_failed_proposal();
+
+        let round =
+            performance.finish_round([leader, late, absent], leader, |v| v != &absent, true);
+        assert_eq!(
+            round,
+            vec![
+                (
+                    leader,
+                    RoundParticipation { led: true, failed_proposal: true, ..Default::default() }
+                ),
+                (
+                    late,
+                    RoundParticipation {
+                        late_pre_proposal: true,
+                        late_aggregation: true,
+                        ..Default::default()
+                    }
+                ),
+                (absent, RoundParticipation { missed_pre_proposal: true, ..Default::default() })
+            ]
+        );
+
+        // faults don't carry over into the next round
+        let round = performance.finish_round([leader], leader, |_| true, false);
+        assert_eq!(
+            round,
+            vec![(
+                leader,
+                RoundParticipation { led: true, missed_proposal: true, ..Default::default() }
+            )]
+        );
+    }
+}
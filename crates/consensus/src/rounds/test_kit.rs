@@ -0,0 +1,483 @@
+//! Scripted runs of the [`RoundStateMachine`].
+//!
+//! A [`RoundHarness`] drives a round among a set of validators we hold the
+//! keys of, with the [`MockMatchingEngine`] and a logical clock, so that edge
+//! cases of the round can be written down as the sequence of events that
+//! triggers them along with the messages and state transitions they should
+//! lead to.
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration
+};
+
+use alloy::{
+    primitives::{Address, BlockNumber},
+    providers::{fillers::*, network::Ethereum, ProviderBuilder, RootProvider}
+};
+use angstrom_metrics::ConsensusMetricsWrapper;
+use angstrom_network::manager::StromConsensusEvent;
+use angstrom_types::{
+    consensus::{KeyHandover, PreProposal, PreProposalAggregation, Proposal},
+    contract_payloads::angstrom::{AngstromPoolConfigStore, UniswapAngstromRegistry},
+    mev_boost::MevBoostProvider,
+    primitive::{AngstromSigner, PeerId, UniswapPoolRegistry}
+};
+use angstrom_utils::clock::{Clock, LogicalClock};
+use futures::{task::noop_waker_ref, StreamExt};
+use order_pool::{order_storage::OrderStorage, PoolConfig};
+use testing_tools::mocks::matching_engine::MockMatchingEngine;
+use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
+
+use super::{ConsensusMessage, RoundStateMachine, SharedRoundState};
+use crate::AngstromValidator;
+
+pub type ProviderDef = FillProvider<
+    JoinFill<
+        Identity,
+        JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>
+    >,
+    RootProvider,
+    Ethereum
+>;
+
+/// One step of a scripted round.
+#[derive(Debug)]
+pub enum Step {
+    /// hands the event to the round as if it came from the network
+    Deliver(StromConsensusEvent),
+    /// moves the clock of the round forward
+    Advance(Duration),
+    /// moves the clock to the next deadline the round waits on
+    NextDeadline,
+    /// starts the round of a new block
+    NewRound { block: BlockNumber, leader: usize }
+}
+
+/// A round we take part in as the first of `validators`. The other validators
+/// only act through the events of the script.
+pub struct RoundHarness {
+    machine:         RoundStateMachine<ProviderDef, MockMatchingEngine>,
+    matching_engine: MockMatchingEngine,
+    clock:           LogicalClock,
+    validators:      Vec<AngstromSigner>,
+    block:           BlockNumber,
+    emitted:         Vec<ConsensusMessage>,
+    transitions:     Vec<(&'static str, &'static str)>
+}
+
+impl RoundHarness {
+    /// The round of block 1 among `validators` equally weighted validators,
+    /// led by the one at index `leader`.
+    pub fn new(validators: usize, leader: usize) -> Self {
+        let validators = (0..validators.max(1))
+            .map(|_| AngstromSigner::random())
+            .collect::<Vec<_>>();
+        let block = 1;
+        let matching_engine = MockMatchingEngine::new();
+
+        let pool_store = Arc::new(AngstromPoolConfigStore::default());
+        let (tx, _rx) = tokio::sync::mpsc::channel(2);
+        let uniswap_pools = SyncedUniswapPools::new(Default::default(), tx);
+        let pool_registry =
+            UniswapAngstromRegistry::new(UniswapPoolRegistry::default(), pool_store);
+
+        // never reached, the round doesn't submit anything the tests look at
+        let querying_provider = ProviderBuilder::<_, _, Ethereum>::default()
+            .with_recommended_fillers()
+            .on_http("http://localhost:8545".parse().unwrap());
+        let provider = MevBoostProvider::new_from_raw(Arc::new(querying_provider), vec![]);
+
+        let shared_state = SharedRoundState::new(
+            block,
+            Address::ZERO,
+            Arc::new(OrderStorage::new(&PoolConfig::default())),
+            validators[0].clone(),
+            validators[leader].id(),
+            validators
+                .iter()
+                .map(|signer| AngstromValidator::new(signer.id(), 100))
+                .collect(),
+            ConsensusMetricsWrapper::new(),
+            pool_registry,
+            uniswap_pools,
+            provider,
+            matching_engine.clone()
+        );
+
+        let clock = LogicalClock::new(Duration::from_secs(1_700_000_000));
+        let machine =
+            RoundStateMachine::new(shared_state).with_clock(Clock::logical(clock.clone()));
+
+        let mut harness = Self {
+            machine,
+            matching_engine,
+            clock,
+            validators,
+            block,
+            emitted: vec![],
+            transitions: vec![]
+        };
+        harness.poll();
+        harness
+    }
+
+    /// Solves of the round take `latency` on the clock of the round.
+    pub fn with_matching_latency(mut self, latency: Duration) -> Self {
+        self.machine.shared_state.matching_engine = self
+            .matching_engine
+            .clone()
+            .with_latency(latency, Clock::logical(self.clock.clone()));
+        self
+    }
+
+    /// Follows the round as an observer, see
+    /// [`RoundStateMachine::with_observer_mode`].
+    pub fn observing(mut self) -> Self {
+        self.machine = self.machine.with_observer_mode();
+        self
+    }
+
+    /// The matching engine of the round, to queue its outputs on.
+    pub fn matching_engine(&self) -> &MockMatchingEngine {
+        &self.matching_engine
+    }
+
+    /// Runs the steps in order, polling the round after each of them.
+    pub fn run(&mut self, script: impl IntoIterator<Item = Step>) -> &mut Self {
+        for step in script {
+            let from = self.state();
+            match step {
+                Step::Deliver(event) => self.machine.handle_message(event),
+                Step::Advance(by) => self.clock.advance(by),
+                Step::NextDeadline => {
+                    self.clock.advance_to_next_deadline();
+                }
+                Step::NewRound { block, leader } => {
+                    self.block = block;
+                    let timestamp = self.clock.unix_now().as_secs();
+                    self.machine
+                        .reset_round(block, timestamp, self.validators[leader].id());
+                }
+            }
+            if from != self.state() {
+                self.transitions.push((from, self.state()));
+            }
+            self.poll();
+        }
+
+        self
+    }
+
+    /// Polls the round until it neither emits a message nor changes its state
+    /// anymore.
+    fn poll(&mut self) {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        loop {
+            let from = self.state();
+            let polled = self.machine.poll_next_unpin(&mut cx);
+            let to = self.state();
+            if from != to {
+                self.transitions.push((from, to));
+            }
+
+            match polled {
+                Poll::Ready(Some(message)) => self.emitted.push(message),
+                _ if from == to => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Name of the state the round is in.
+    pub fn state(&self) -> &'static str {
+        self.machine.current_state.name()
+    }
+
+    /// The state transitions of the round so far, as `(from, to)`.
+    pub fn transitions(&self) -> &[(&'static str, &'static str)] {
+        &self.transitions
+    }
+
+    /// Takes the messages the round emitted since the last call.
+    pub fn take_emitted(&mut self) -> Vec<ConsensusMessage> {
+        std::mem::take(&mut self.emitted)
+    }
+
+    /// Takes the types of the messages the round emitted since the last call.
+    pub fn take_emitted_types(&mut self) -> Vec<&'static str> {
+        self.take_emitted()
+            .iter()
+            .map(ConsensusMessage::message_type)
+            .collect()
+    }
+
+    pub fn machine(&self) -> &RoundStateMachine<ProviderDef, MockMatchingEngine> {
+        &self.machine
+    }
+
+    pub fn validator(&self, index: usize) -> &AngstromSigner {
+        &self.validators[index]
+    }
+
+    /// An empty pre-proposal of the current block by the validator at
+    /// `index`.
+    pub fn pre_proposal(&self, index: usize) -> PreProposal {
+        PreProposal::generate_pre_proposal(self.block, self.validator(index), vec![], vec![])
+    }
+
+    /// An aggregation of the current block by the validator at `index` over
+    /// the pre-proposals of the validators at `of`.
+    pub fn pre_proposal_agg(&self, index: usize, of: &[usize]) -> PreProposalAggregation {
+        let pre_proposals = of.iter().map(|i| self.pre_proposal(*i)).collect();
+        PreProposalAggregation::new(self.block, self.validator(index), pre_proposals)
+    }
+
+    /// A proposal of `block` without solutions by the validator at `index`,
+    /// over the aggregations of the validators at `of`.
+    pub fn proposal(&self, index: usize, block: BlockNumber, of: &[usize]) -> Proposal {
+        let aggregations = of.iter().map(|i| self.pre_proposal_agg(*i, of)).collect();
+        Proposal::generate_proposal(block, self.validator(index), aggregations, vec![])
+    }
+
+    /// The event of the validator at `index` sending `message`.
+    pub fn sent_by(&self, index: usize, message: impl Into<Sent>) -> Step {
+        message.into().by(self.validator(index).id())
+    }
+}
+
+/// A consensus message before it's given a sender.
+pub enum Sent {
+    PreProposal(PreProposal),
+    PreProposalAgg(PreProposalAggregation),
+    Proposal(Proposal),
+    KeyHandover(KeyHandover)
+}
+
+impl Sent {
+    pub fn by(self, peer_id: PeerId) -> Step {
+        Step::Deliver(match self {
+            Self::PreProposal(pre) => StromConsensusEvent::PreProposal(peer_id, pre),
+            Self::PreProposalAgg(agg) => StromConsensusEvent::PreProposalAgg(peer_id, agg),
+            Self::Proposal(proposal) => StromConsensusEvent::Proposal(peer_id, proposal),
+            Self::KeyHandover(handover) => StromConsensusEvent::KeyHandover(peer_id, handover)
+        })
+    }
+}
+
+impl From<PreProposal> for Sent {
+    fn from(value: PreProposal) -> Self {
+        Self::PreProposal(value)
+    }
+}
+
+impl From<PreProposalAggregation> for Sent {
+    fn from(value: PreProposalAggregation) -> Self {
+        Self::PreProposalAgg(value)
+    }
+}
+
+impl From<Proposal> for Sent {
+    fn from(value: Proposal) -> Self {
+        Self::Proposal(value)
+    }
+}
+
+impl From<KeyHandover> for Sent {
+    fn from(value: KeyHandover) -> Self {
+        Self::KeyHandover(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::consensus::KEY_HANDOVER_LEAD_BLOCKS;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn duplicate_pre_proposals_are_propagated_and_counted_once() {
+        // three of four pre-proposals are needed to move on
+        let mut round = RoundHarness::new(4, 0);
+        round.run([Step::NextDeadline]);
+        assert_eq!(round.state(), "PreProposal");
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal"]);
+
+        let duplicate = round.pre_proposal(1);
+        round.run([round.sent_by(1, duplicate.clone()), round.sent_by(1, duplicate)]);
+        assert_eq!(round.state(), "PreProposal");
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal"]);
+
+        round.run([round.sent_by(2, round.pre_proposal(2))]);
+        assert_eq!(round.state(), "PreProposalAggregation");
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal", "PreProposalAggregation"]);
+        assert_eq!(
+            round.transitions(),
+            &[("BidAggregation", "PreProposal"), ("PreProposal", "PreProposalAggregation")]
+        );
+    }
+
+    #[tokio::test]
+    async fn messages_of_peers_outside_the_validator_set_are_dropped() {
+        let mut round = RoundHarness::new(2, 0);
+        round.run([Step::NextDeadline]);
+        round.take_emitted();
+
+        let outsider = AngstromSigner::random();
+        let pre_proposal = PreProposal::generate_pre_proposal(1, &outsider, vec![], vec![]);
+        let aggregation = PreProposalAggregation::new(1, &outsider, vec![pre_proposal.clone()]);
+        round.run([
+            Sent::from(pre_proposal).by(outsider.id()),
+            Sent::from(aggregation).by(outsider.id())
+        ]);
+
+        assert_eq!(round.state(), "PreProposal");
+        assert!(round.take_emitted().is_empty());
+        assert!(!round.machine().vote_ledger().has_voted(&outsider.id()));
+    }
+
+    #[tokio::test]
+    async fn proposals_skip_to_finalization_unless_stale_or_not_by_the_leader() {
+        let mut round = RoundHarness::new(3, 1);
+
+        round.run([
+            round.sent_by(2, round.proposal(2, 1, &[0, 1, 2])),
+            round.sent_by(1, round.proposal(1, 0, &[0, 1, 2]))
+        ]);
+        assert_eq!(round.state(), "BidAggregation");
+        assert!(round.take_emitted().is_empty());
+
+        round.run([round.sent_by(1, round.proposal(1, 1, &[0, 1, 2]))]);
+        assert_eq!(round.state(), "Finalization");
+        assert_eq!(round.take_emitted_types(), vec!["Proposal"]);
+        assert_eq!(round.matching_engine().solves(), 1);
+
+        // the round is over once finalized, the deadline of the pre-proposal
+        // passing doesn't restart it
+        round.run([Step::NextDeadline, Step::Advance(Duration::from_secs(12))]);
+        assert_eq!(round.state(), "Finalization");
+        assert!(round.take_emitted().is_empty());
+        assert_eq!(round.transitions(), &[("BidAggregation", "Finalization")]);
+    }
+
+    #[tokio::test]
+    async fn observers_follow_the_round_without_signing() {
+        // we'd lead the round if we weren't only observing it
+        let mut round = RoundHarness::new(3, 0).observing();
+        round.run([Step::NextDeadline]);
+        assert_eq!(round.state(), "PreProposal");
+        assert!(round.take_emitted().is_empty());
+
+        // the pre-proposals of the validators are relayed and count
+        round.run([
+            round.sent_by(1, round.pre_proposal(1)),
+            round.sent_by(2, round.pre_proposal(2))
+        ]);
+        assert_eq!(round.state(), "PreProposalAggregation");
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal", "PreProposal"]);
+
+        // nor do we build a proposal once we got the aggregations
+        round.run([
+            round.sent_by(1, round.pre_proposal_agg(1, &[1, 2])),
+            round.sent_by(2, round.pre_proposal_agg(2, &[1, 2]))
+        ]);
+        assert_eq!(round.state(), "PreProposalAggregation");
+        assert_eq!(round.matching_engine().solves(), 0);
+        round.take_emitted();
+
+        // but verify the one of the leader
+        round.run([round.sent_by(0, round.proposal(0, 1, &[1, 2]))]);
+        assert_eq!(round.state(), "Finalization");
+        assert_eq!(round.take_emitted_types(), vec!["Proposal"]);
+        assert_eq!(round.matching_engine().solves(), 1);
+    }
+
+    #[tokio::test]
+    async fn late_pre_proposals_dont_count_towards_the_round() {
+        let mut round = RoundHarness::new(3, 1);
+        round.run([Step::NextDeadline, round.sent_by(1, round.pre_proposal(1))]);
+        assert_eq!(round.state(), "PreProposalAggregation");
+        round.take_emitted();
+
+        round.run([round.sent_by(2, round.pre_proposal(2))]);
+        assert_eq!(round.state(), "PreProposalAggregation");
+        assert!(round.take_emitted().is_empty());
+
+        round.run([Step::NewRound { block: 2, leader: 0 }]);
+        assert_eq!(round.state(), "BidAggregation");
+        assert_eq!(round.transitions().last(), Some(&("PreProposalAggregation", "BidAggregation")));
+    }
+
+    #[tokio::test]
+    async fn handed_over_keys_vote_once_during_the_grace_window() {
+        let mut round = RoundHarness::new(4, 0);
+        let new_key = AngstromSigner::random();
+        let activation = 1 + KEY_HANDOVER_LEAD_BLOCKS;
+        let handover = KeyHandover::new(1, round.validator(1), &new_key, activation);
+        round.run([round.sent_by(1, handover.clone()), round.sent_by(2, handover)]);
+        assert_eq!(round.take_emitted_types(), vec!["KeyHandover"]);
+
+        round.run([Step::NewRound { block: activation, leader: 0 }, Step::NextDeadline]);
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal"]);
+
+        // both keys are valid, only the first to arrive gets to vote
+        let by_new_key = PreProposal::generate_pre_proposal(activation, &new_key, vec![], vec![]);
+        round.run([round.sent_by(1, by_new_key), round.sent_by(1, round.pre_proposal(1))]);
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal"]);
+        assert!(!round
+            .machine()
+            .vote_ledger()
+            .has_voted(&round.validator(1).id()));
+    }
+
+    #[tokio::test]
+    async fn late_joiners_learn_handovers_that_already_activated() {
+        let mut round = RoundHarness::new(4, 0);
+        let new_key = AngstromSigner::random();
+        let activation = 1 + KEY_HANDOVER_LEAD_BLOCKS;
+        let handover = KeyHandover::new(1, round.validator(1), &new_key, activation);
+
+        // we joined long after the handover, a peer sends it to us
+        let block = activation + 10;
+        round.run([Step::NewRound { block, leader: 0 }]);
+        round.take_emitted();
+        round.run([round.sent_by(2, handover.clone()), round.sent_by(3, handover.clone())]);
+        assert_eq!(round.take_emitted_types(), vec!["KeyHandover"]);
+        assert_eq!(round.machine().key_handovers().collect::<Vec<_>>(), vec![&handover]);
+
+        round.run([Step::NextDeadline]);
+        round.take_emitted();
+        let by_new_key = PreProposal::generate_pre_proposal(block, &new_key, vec![], vec![]);
+        round.run([round.sent_by(1, by_new_key)]);
+        assert_eq!(round.take_emitted_types(), vec!["PreProposal"]);
+        assert!(round.machine().vote_ledger().has_voted(&new_key.id()));
+    }
+
+    #[tokio::test]
+    async fn rotated_keys_are_signed_with_once_the_handover_activates() {
+        let mut round = RoundHarness::new(2, 0);
+        let new_key = AngstromSigner::random();
+        let rotation = round.machine.rotate_signer(new_key.clone()).unwrap();
+        assert_eq!(rotation.validator_id, round.validator(0).id());
+        assert_eq!(rotation.activation_block, 1 + KEY_HANDOVER_LEAD_BLOCKS);
+        round.run([]);
+        assert_eq!(round.take_emitted_types(), vec!["KeyHandover"]);
+
+        let signed_by = |messages: Vec<ConsensusMessage>| {
+            messages.into_iter().find_map(|message| match message {
+                ConsensusMessage::PropagatePreProposal(pre) => Some(pre.source),
+                _ => None
+            })
+        };
+        round.run([Step::NewRound { block: 2, leader: 1 }, Step::NextDeadline]);
+        assert_eq!(signed_by(round.take_emitted()), Some(round.validator(0).id()));
+
+        let activation = 1 + KEY_HANDOVER_LEAD_BLOCKS;
+        round.run([Step::NewRound { block: activation, leader: 0 }, Step::NextDeadline]);
+        assert_eq!(signed_by(round.take_emitted()), Some(new_key.id()));
+        // we are still known as the leader by our first key, but the key
+        // itself is retired, our bundles are sent from the new one
+        assert!(round.machine.shared_state.i_am_leader());
+        assert_eq!(round.machine.shared_state.signer.address(), new_key.address());
+    }
+}
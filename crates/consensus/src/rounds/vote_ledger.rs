@@ -0,0 +1,637 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy::primitives::{BlockNumber, B256};
+use angstrom_types::{
+    consensus::{Evidence, PreProposal, PreProposalEquivocation, Proposal},
+    orders::PoolSolution,
+    primitive::PeerId,
+    sol_bindings::grouped_orders::OrderWithStorageData
+};
+
+/// Records what every validator attested to during a round.
+///
+/// A validators vote is the pre-proposal it signed for the height. If a
+/// validator signs two different pre-proposals for the same height, it
+/// equivocated. Both pre-proposals are kept as [`Evidence`] and none of its
+/// votes count towards quorum for the rest of the round.
+#[derive(Debug, Default)]
+pub struct VoteLedger {
+    block_height:  BlockNumber,
+    pre_proposals: HashMap<PeerId, PreProposal>,
+    equivocators:  HashSet<PeerId>,
+    /// solutions the leader signed in its proposal
+    solutions:     HashMap<PeerId, Vec<PoolSolution>>,
+    /// evidence collected that hasn't been taken yet. Survives resets
+    evidence:      Vec<Evidence>
+}
+
+impl VoteLedger {
+    pub fn new(block_height: BlockNumber) -> Self {
+        Self { block_height, ..Default::default() }
+    }
+
+    /// Builds the ledger purely from the given pre-proposals. Used when every
+    /// node needs to come to the same result from the same input.
+    pub fn from_pre_proposals<'a>(
+        block_height: BlockNumber,
+        pre_proposals: impl IntoIterator<Item = &'a PreProposal>
+    ) -> Self {
+        let mut ledger = Self::new(block_height);
+        pre_proposals.into_iter().for_each(|pre| {
+            ledger.record_pre_proposal(pre);
+        });
+
+        ledger
+    }
+
+    /// Clears all votes for the new height. Evidence that wasn't taken yet is
+    /// kept.
+    pub fn reset(&mut self, block_height: BlockNumber) {
+        self.block_height = block_height;
+        self.pre_proposals.clear();
+        self.equivocators.clear();
+        self.solutions.clear();
+    }
+
+    /// Records the pre-proposal as the vote of its source. Returns false if the
+    /// vote doesn't count, which is the case when it is for a different height
+    /// or the source equivocated.
+    ///
+    /// The signature is expected to be checked by the caller.
+    pub fn record_pre_proposal(&mut self, pre_proposal: &PreProposal) -> bool {
+        if pre_proposal.block_height != self.block_height {
+            return false
+        }
+        let source = pre_proposal.source;
+        if self.equivocators.contains(&source) {
+            return false
+        }
+
+        let Some(existing) = self.pre_proposals.get(&source) else {
+            self.pre_proposals.insert(source, pre_proposal.clone());
+            return true
+        };
+        if existing.content() == pre_proposal.content() {
+            return true
+        }
+
+        match PreProposalEquivocation::new(existing.clone(), pre_proposal.clone()) {
+            Ok(evidence) => {
+                tracing::warn!(
+                    validator = ?source,
+                    block_height = self.block_height,
+                    "validator signed two conflicting pre-proposals"
+                );
+                self.equivocators.insert(source);
+                self.pre_proposals.remove(&source);
+                self.evidence
+                    .push(Evidence::PreProposalEquivocation(evidence));
+            }
+            Err(error) => {
+                tracing::debug!(validator = ?source, %error, "pre-proposal is not valid evidence");
+            }
+        }
+
+        false
+    }
+
+    /// Records the solutions the proposer attested to along with the
+    /// pre-proposals they are built from.
+    pub fn record_proposal(&mut self, proposal: &Proposal) {
+        if proposal.block_height != self.block_height {
+            return
+        }
+        proposal.flattened_pre_proposals().iter().for_each(|pre| {
+            self.record_pre_proposal(pre);
+        });
+        self.solutions
+            .insert(proposal.source, proposal.solutions.clone());
+    }
+
+    /// Whether we have a vote from the validator this height, equivocating
+    /// ones included.
+    pub fn has_voted(&self, validator: &PeerId) -> bool {
+        self.pre_proposals.contains_key(validator) || self.equivocators.contains(validator)
+    }
+
+    pub fn is_equivocator(&self, validator: &PeerId) -> bool {
+        self.equivocators.contains(validator)
+    }
+
+    /// Hashes of all orders the validator attested to this height.
+    pub fn attested_orders(&self, validator: &PeerId) -> Option<HashSet<B256>> {
+        self.pre_proposals.get(validator).map(|pre| {
+            pre.limit
+                .iter()
+                .map(|order| order.order_id.hash)
+                .chain(pre.searcher.iter().map(|order| order.order_id.hash))
+                .collect()
+        })
+    }
+
+    pub fn attested_solutions(&self, validator: &PeerId) -> Option<&[PoolSolution]> {
+        self.solutions.get(validator).map(Vec::as_slice)
+    }
+
+    /// Orders that at least `threshold` distinct, honest validators attested
+    /// to.
+    pub fn quorum_orders<O: PartialEq + Clone>(
+        &self,
+        threshold: usize,
+        select: impl Fn(&PreProposal) -> &[OrderWithStorageData<O>]
+    ) -> Vec<OrderWithStorageData<O>> {
+        self.order_votes(select).quorum(threshold)
+    }
+
+    /// Orders that reached quorum along with the private orders of the round
+    /// leader, which signs its votes with one of `leader_keys`. An order is
+    /// only taken once, even if it's both.
+    pub fn round_orders<O: PartialEq + Clone>(
+        &self,
+        threshold: usize,
+        leader_keys: &[PeerId],
+        select: impl Fn(&PreProposal) -> &[OrderWithStorageData<O>]
+    ) -> Vec<OrderWithStorageData<O>> {
+        let mut orders = self.quorum_orders(threshold, &select);
+        let quorum = orders
+            .iter()
+            .map(|order| order.order_id.hash)
+            .collect::<HashSet<_>>();
+        orders.extend(
+            self.private_orders(leader_keys, &select)
+                .into_iter()
+                .filter(|order| !quorum.contains(&order.order_id.hash))
+        );
+
+        orders
+    }
+
+    /// Hashes of the private orders of the leader that didn't reach quorum,
+    /// nobody but the leader vouches for them.
+    pub fn unvouched_orders(&self, threshold: usize, leader_keys: &[PeerId]) -> HashSet<B256> {
+        let vouched = self
+            .quorum_orders(threshold, |pre| &pre.limit)
+            .into_iter()
+            .map(|order| order.order_id.hash)
+            .chain(
+                self.quorum_orders(threshold, |pre| &pre.searcher)
+                    .into_iter()
+                    .map(|order| order.order_id.hash)
+            )
+            .collect::<HashSet<_>>();
+
+        leader_keys
+            .iter()
+            .filter_map(|key| self.pre_proposals.get(key))
+            .flat_map(|pre| pre.private_orders())
+            .filter(|hash| !vouched.contains(hash))
+            .collect()
+    }
+
+    /// Orders the leader flagged as private to it. Only the leader holds
+    /// them, so they are included on its vote alone.
+    pub fn private_orders<O: Clone>(
+        &self,
+        leader_keys: &[PeerId],
+        select: impl Fn(&PreProposal) -> &[OrderWithStorageData<O>]
+    ) -> Vec<OrderWithStorageData<O>> {
+        leader_keys
+            .iter()
+            .filter_map(|key| self.pre_proposals.get(key))
+            .flat_map(|pre| {
+                let private = pre.private_orders();
+                select(pre)
+                    .iter()
+                    .filter(move |order| private.contains(&order.order_id.hash))
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// The distinct orders of the honest votes along with who attested to
+    /// them. The orders are borrowed from the pre-proposals, so an order
+    /// carried by every validator is held once.
+    pub fn order_votes<O: PartialEq>(
+        &self,
+        select: impl Fn(&PreProposal) -> &[OrderWithStorageData<O>]
+    ) -> OrderVotes<'_, O> {
+        let mut votes = OrderVotes { orders: HashMap::new() };
+        self.pre_proposals
+            .values()
+            .enumerate()
+            .for_each(|(voter, pre)| {
+                select(pre)
+                    .iter()
+                    .for_each(|order| votes.insert(voter, order))
+            });
+
+        votes
+    }
+
+    /// Limit orders that at least `threshold` distinct, honest validators
+    /// flagged for forced inclusion.
+    pub fn forced_orders(&self, threshold: usize) -> HashSet<B256> {
+        let mut flags: HashMap<B256, Voters> = HashMap::new();
+        self.pre_proposals
+            .values()
+            .enumerate()
+            .for_each(|(voter, pre)| {
+                pre.forced_orders()
+                    .for_each(|hash| flags.entry(hash).or_default().insert(voter))
+            });
+
+        flags
+            .into_iter()
+            .filter(|(_, voters)| voters.count() >= threshold)
+            .map(|(hash, _)| hash)
+            .collect()
+    }
+
+    /// The block each standing limit order first appeared in a quorum, the
+    /// earliest one by which at least `threshold` distinct, honest validators
+    /// attest to have seen it. Flash orders are only valid for their block and
+    /// have none.
+    pub fn quorum_blocks(&self, threshold: usize) -> HashMap<B256, BlockNumber> {
+        let mut seen: HashMap<B256, Vec<BlockNumber>> = HashMap::new();
+        self.pre_proposals.values().for_each(|pre| {
+            // a validator carrying an order twice still only attests once
+            let mut attested: HashMap<B256, BlockNumber> = HashMap::new();
+            pre.first_seen_blocks()
+                .filter(|(order, _)| order.order_id.flash_block.is_none())
+                .for_each(|(order, block)| {
+                    let first = attested.entry(order.order_id.hash).or_insert(block);
+                    *first = (*first).min(block);
+                });
+            attested
+                .into_iter()
+                .for_each(|(hash, block)| seen.entry(hash).or_default().push(block));
+        });
+
+        let threshold = threshold.max(1);
+        seen.into_iter()
+            .filter(|(_, blocks)| blocks.len() >= threshold)
+            .map(|(hash, mut blocks)| {
+                blocks.sort_unstable();
+                (hash, blocks[threshold - 1])
+            })
+            .collect()
+    }
+
+    /// Orders flagged by at least `threshold` validators that the books of the
+    /// solutions leave out, in hash order. Only the books of pools with a
+    /// solution are checked, a pool without one is left out as a whole and
+    /// caught by comparing the solutions.
+    pub fn omitted_forced_orders(&self, threshold: usize, solutions: &[PoolSolution]) -> Vec<B256> {
+        let forced = self.forced_orders(threshold);
+        if forced.is_empty() {
+            return vec![]
+        }
+
+        let solved = solutions
+            .iter()
+            .map(|solution| solution.id)
+            .collect::<HashSet<_>>();
+        let matched = solutions
+            .iter()
+            .flat_map(|solution| solution.limit.iter())
+            .map(|outcome| outcome.id.hash)
+            .collect::<HashSet<_>>();
+        let mut omitted = self
+            .pre_proposals
+            .values()
+            .flat_map(|pre| pre.limit.iter())
+            .filter(|order| solved.contains(&order.pool_id))
+            .map(|order| order.order_id.hash)
+            .filter(|hash| forced.contains(hash) && !matched.contains(hash))
+            .collect::<Vec<_>>();
+        omitted.sort_unstable();
+        omitted.dedup();
+
+        omitted
+    }
+
+    pub fn take_evidence(&mut self) -> Vec<Evidence> {
+        std::mem::take(&mut self.evidence)
+    }
+}
+
+/// The orders of a set of pre-proposals addressed by their hash. Orders with
+/// the same hash but different storage data are different votes.
+#[derive(Debug)]
+pub struct OrderVotes<'a, O> {
+    orders: HashMap<B256, Vec<(&'a OrderWithStorageData<O>, Voters)>>
+}
+
+impl<'a, O: PartialEq> OrderVotes<'a, O> {
+    fn insert(&mut self, voter: usize, order: &'a OrderWithStorageData<O>) {
+        let variants = self.orders.entry(order.order_id.hash).or_default();
+        match variants.iter_mut().find(|(known, _)| *known == order) {
+            Some((_, voters)) => voters.insert(voter),
+            None => {
+                let mut voters = Voters::default();
+                voters.insert(voter);
+                variants.push((order, voters));
+            }
+        }
+    }
+
+    /// Distinct validators that attested to the order.
+    pub fn votes(&self, order: &OrderWithStorageData<O>) -> usize {
+        self.orders
+            .get(&order.order_id.hash)
+            .and_then(|variants| variants.iter().find(|(known, _)| *known == order))
+            .map_or(0, |(_, voters)| voters.count())
+    }
+
+    /// Clones out the orders with at least `threshold` votes.
+    pub fn quorum(&self, threshold: usize) -> Vec<OrderWithStorageData<O>>
+    where
+        O: Clone
+    {
+        self.orders
+            .values()
+            .flatten()
+            .filter(|(_, voters)| voters.count() >= threshold)
+            .map(|(order, _)| (*order).clone())
+            .collect()
+    }
+}
+
+/// Bitmap of the voters of a round, by their index in the ledger.
+#[derive(Debug, Default, Clone)]
+struct Voters(Vec<u64>);
+
+impl Voters {
+    fn insert(&mut self, voter: usize) {
+        let (word, bit) = (voter / 64, voter % 64);
+        if self.0.len() <= word {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << bit;
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::U256, signers::SignerSync};
+    use angstrom_types::{
+        orders::{OrderFillState, OrderOutcome, OrderSet, PricePeg},
+        primitive::AngstromSigner,
+        sol_bindings::{grouped_orders::GroupedVanillaOrder, rpc_orders::TopOfBlockOrder}
+    };
+
+    use super::*;
+
+    fn order(hash: u8) -> OrderWithStorageData<GroupedVanillaOrder> {
+        let mut order = OrderWithStorageData::default();
+        order.order_id.hash = B256::repeat_byte(hash);
+        order
+    }
+
+    fn pre_proposal(
+        sk: &AngstromSigner,
+        limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>
+    ) -> PreProposal {
+        PreProposal::generate_pre_proposal(
+            1,
+            sk,
+            limit,
+            Vec::<OrderWithStorageData<TopOfBlockOrder>>::new()
+        )
+    }
+
+    #[test]
+    fn counts_distinct_validators() {
+        let (a, b, c) =
+            (AngstromSigner::random(), AngstromSigner::random(), AngstromSigner::random());
+        let pre_a = pre_proposal(&a, vec![order(1), order(2)]);
+        // the same pre-proposal seen through multiple aggregations only counts once
+        let ledger = VoteLedger::from_pre_proposals(
+            1,
+            [
+                &pre_a,
+                &pre_a,
+                &pre_proposal(&b, vec![order(1), order(1)]),
+                &pre_proposal(&c, vec![])
+            ]
+        );
+
+        let quorum = ledger.quorum_orders(2, |pre| &pre.limit);
+        assert_eq!(quorum, vec![order(1)]);
+        assert_eq!(
+            ledger.attested_orders(&a.id()).unwrap(),
+            HashSet::from([B256::repeat_byte(1), B256::repeat_byte(2)])
+        );
+    }
+
+    #[test]
+    fn addresses_orders_by_content() {
+        let signers = (0..70)
+            .map(|_| AngstromSigner::random())
+            .collect::<Vec<_>>();
+        let mut repriced = order(1);
+        repriced.priority_data.gas = U256::from(1);
+        let pre_proposals = signers
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| {
+                let variant = if i == 0 { repriced.clone() } else { order(1) };
+                pre_proposal(sk, vec![variant, order(2)])
+            })
+            .collect::<Vec<_>>();
+        let ledger = VoteLedger::from_pre_proposals(1, &pre_proposals);
+
+        let votes = ledger.order_votes(|pre| &pre.limit);
+        assert_eq!(votes.votes(&order(1)), 69);
+        assert_eq!(votes.votes(&repriced), 1);
+        assert_eq!(votes.votes(&order(2)), 70);
+        assert_eq!(votes.votes(&order(3)), 0);
+        assert_eq!(votes.quorum(70), vec![order(2)]);
+    }
+
+    #[test]
+    fn forces_orders_flagged_by_quorum() {
+        let (a, b, c) =
+            (AngstromSigner::random(), AngstromSigner::random(), AngstromSigner::random());
+        let flagging = |sk: &AngstromSigner, limit, forced| {
+            PreProposal::generate_with_forced_inclusion(1, sk, limit, vec![], forced)
+        };
+        let ledger = VoteLedger::from_pre_proposals(
+            1,
+            [
+                &flagging(&a, vec![order(1), order(2)], vec![B256::repeat_byte(1)]),
+                &flagging(&b, vec![order(1)], vec![B256::repeat_byte(1), B256::repeat_byte(1)]),
+                // a flag on an order the validator doesn't carry doesn't count
+                &flagging(&c, vec![order(1)], vec![B256::repeat_byte(2)])
+            ]
+        );
+
+        assert_eq!(ledger.forced_orders(2), HashSet::from([B256::repeat_byte(1)]));
+        assert!(ledger.forced_orders(3).is_empty());
+    }
+
+    #[test]
+    fn dates_orders_by_the_block_a_quorum_had_seen_them() {
+        let mut flash = order(3);
+        flash.order_id.flash_block = Some(10);
+        let seeing = |seen: [u64; 3]| {
+            let first_seen = HashMap::from([
+                (B256::repeat_byte(1), seen[0]),
+                (B256::repeat_byte(2), seen[1]),
+                (B256::repeat_byte(3), seen[2])
+            ]);
+            let orders =
+                OrderSet { limit: vec![order(1), order(2), flash.clone()], searcher: vec![] };
+            PreProposal::new(10, &AngstromSigner::random(), orders, &first_seen, false)
+        };
+        let ledger = VoteLedger::from_pre_proposals(
+            10,
+            &[seeing([5, 8, 9]), seeing([7, 9, 9]), seeing([1, 10, 9])]
+        );
+
+        // a validator claiming to have seen an order early doesn't move it
+        assert_eq!(
+            ledger.quorum_blocks(2),
+            HashMap::from([(B256::repeat_byte(1), 5), (B256::repeat_byte(2), 9)])
+        );
+        assert_eq!(ledger.quorum_blocks(3)[&B256::repeat_byte(1)], 7);
+    }
+
+    #[test]
+    fn finds_forced_orders_the_books_of_the_solutions_leave_out() {
+        let in_pool = |hash: u8, pool: u8| {
+            let mut order = order(hash);
+            order.pool_id = B256::repeat_byte(pool);
+            order
+        };
+        let limit = vec![in_pool(1, 10), in_pool(2, 10), in_pool(3, 20), in_pool(4, 10)];
+        let ledger = VoteLedger::from_pre_proposals(
+            1,
+            &[
+                vec![1, 2, 3, 4],
+                vec![1, 2, 3],
+                // the flags of a single validator don't force anything
+                vec![]
+            ]
+            .map(|forced| {
+                PreProposal::generate_with_forced_inclusion(
+                    1,
+                    &AngstromSigner::random(),
+                    limit.clone(),
+                    vec![],
+                    forced.into_iter().map(B256::repeat_byte).collect()
+                )
+            })
+        );
+        let solution = |pool: u8, orders: &[u8]| PoolSolution {
+            id: B256::repeat_byte(pool),
+            limit: orders
+                .iter()
+                .map(|hash| OrderOutcome {
+                    id:      in_pool(*hash, pool).order_id,
+                    outcome: OrderFillState::Unfilled
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        // orders in the book count whether they fill or not, the pool without
+        // a solution isn't checked
+        assert_eq!(
+            ledger.omitted_forced_orders(2, &[solution(10, &[1])]),
+            vec![B256::repeat_byte(2)]
+        );
+        assert!(ledger
+            .omitted_forced_orders(2, &[solution(10, &[1, 2])])
+            .is_empty());
+        assert_eq!(
+            ledger.omitted_forced_orders(2, &[solution(10, &[1, 2]), solution(20, &[])]),
+            vec![B256::repeat_byte(3)]
+        );
+    }
+
+    #[test]
+    fn takes_the_private_orders_of_the_leader_alone() {
+        let (leader, other) = (AngstromSigner::random(), AngstromSigner::random());
+        let flagging = |sk: &AngstromSigner, limit, private| {
+            PreProposal::generate_with_flags(1, sk, limit, vec![], vec![], private)
+        };
+        let ledger = VoteLedger::from_pre_proposals(
+            1,
+            [
+                &flagging(&leader, vec![order(1), order(2)], vec![B256::repeat_byte(2)]),
+                &flagging(&other, vec![order(1), order(3)], vec![B256::repeat_byte(3)])
+            ]
+        );
+
+        let mut orders = ledger.round_orders(2, &[leader.id()], |pre| &pre.limit);
+        orders.sort_by_key(|order| order.order_id.hash);
+        assert_eq!(orders, vec![order(1), order(2)]);
+        // only the leader vouches for its private order
+        assert_eq!(
+            ledger.unvouched_orders(2, &[leader.id()]),
+            HashSet::from([B256::repeat_byte(2)])
+        );
+        // nobody else can get an order in on its own vote
+        assert!(ledger
+            .private_orders(&[AngstromSigner::random().id()], |pre| &pre.limit)
+            .is_empty());
+    }
+
+    #[test]
+    fn pegged_orders_reach_the_rounds_their_node_leads() {
+        let (leader, other) = (AngstromSigner::random(), AngstromSigner::random());
+        let mut pegged = order(2);
+        let peg_hash = PricePeg::signing_payload(pegged.order_id.hash, 10);
+        pegged.peg = Some(PricePeg {
+            order_hash: pegged.order_id.hash,
+            offset_bps: 10,
+            signature:  leader.sign_hash_sync(&peg_hash).unwrap()
+        });
+        let orders = || OrderSet { limit: vec![order(1), pegged.clone()], searcher: vec![] };
+
+        // nobody else holds the pegged order, so it's only carried when we lead
+        assert_eq!(
+            PreProposal::new(1, &leader, orders(), &HashMap::new(), false).limit,
+            vec![order(1)]
+        );
+
+        let ledger = VoteLedger::from_pre_proposals(
+            1,
+            [
+                &PreProposal::new(1, &leader, orders(), &HashMap::new(), true),
+                &pre_proposal(&other, vec![order(1)])
+            ]
+        );
+        let mut round = ledger.round_orders(2, &[leader.id()], |pre| &pre.limit);
+        round.sort_by_key(|order| order.order_id.hash);
+        assert_eq!(round, vec![order(1), pegged]);
+    }
+
+    #[test]
+    fn detects_equivocation() {
+        let (a, b) = (AngstromSigner::random(), AngstromSigner::random());
+        let mut ledger = VoteLedger::new(1);
+
+        assert!(ledger.record_pre_proposal(&pre_proposal(&a, vec![order(1)])));
+        assert!(ledger.record_pre_proposal(&pre_proposal(&b, vec![order(1)])));
+        assert!(!ledger.record_pre_proposal(&pre_proposal(&a, vec![order(2)])));
+
+        assert!(ledger.is_equivocator(&a.id()));
+        assert!(ledger.quorum_orders(2, |pre| &pre.limit).is_empty());
+        // a third conflicting pre-proposal doesn't add more evidence
+        assert!(!ledger.record_pre_proposal(&pre_proposal(&a, vec![order(3)])));
+
+        let evidence = ledger.take_evidence();
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].offender(), Some(a.id()));
+
+        ledger.reset(2);
+        assert!(!ledger.is_equivocator(&a.id()));
+        assert!(ledger.take_evidence().is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use angstrom_types::primitive::BLOCK_TIME;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Times for the rounds. The `_ms` fields of the `[consensus_timing]` section
+/// of the node config, every one of them can be left out.
+///
+/// All but the finalization budget are taken from the start of the round, so
+/// they have to follow each other within the block:
+/// `min_wait <= wait < aggregation_timeout <= proposal_deadline < block_time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsensusTiming {
+    /// time between two blocks
+    #[serde(rename = "block_time_ms", with = "millis")]
+    pub block_time:          Duration,
+    /// how long we wait before sending our pre-proposal in the first round,
+    /// later rounds adapt it to how long the rounds take
+    #[serde(rename = "wait_ms", with = "millis")]
+    pub wait:                Duration,
+    /// the adapted wait never goes below this
+    #[serde(rename = "min_wait_ms", with = "millis")]
+    pub min_wait:            Duration,
+    /// the leader doesn't propose once the aggregations reach a quorum after
+    /// this, the bundle would miss the block
+    #[serde(rename = "aggregation_timeout_ms", with = "millis")]
+    pub aggregation_timeout: Duration,
+    /// by when the leader submits its bundle, a bundle that is ready later
+    /// isn't submitted
+    #[serde(rename = "proposal_deadline_ms", with = "millis")]
+    pub proposal_deadline:   Duration,
+    /// how long verifying the proposal of the leader may take, from the
+    /// arrival of the proposal on. We don't sign over proposals verified later
+    #[serde(rename = "finalization_budget_ms", with = "millis")]
+    pub finalization_budget: Duration
+}
+
+impl Default for ConsensusTiming {
+    fn default() -> Self {
+        Self {
+            block_time:          BLOCK_TIME,
+            wait:                Duration::from_secs(9),
+            min_wait:            Duration::from_secs(2),
+            aggregation_timeout: Duration::from_secs(11),
+            proposal_deadline:   Duration::from_millis(11_200),
+            finalization_budget: Duration::from_secs(6)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ConsensusTimingError {
+    #[error("the wait of {wait:?} is below the min wait of {min_wait:?}")]
+    WaitBelowMin { wait: Duration, min_wait: Duration },
+    #[error(
+        "the aggregation timeout of {timeout:?} doesn't leave time to aggregate after the wait"
+    )]
+    AggregationBeforeWait { timeout: Duration },
+    #[error("the aggregation timeout of {timeout:?} is past the proposal deadline")]
+    AggregationPastDeadline { timeout: Duration },
+    #[error("the proposal deadline of {deadline:?} isn't within the block")]
+    DeadlinePastBlock { deadline: Duration },
+    #[error("the finalization budget of {budget:?} isn't within the block")]
+    FinalizationBudget { budget: Duration }
+}
+
+impl ConsensusTiming {
+    /// Checks the times are consistent with each other.
+    pub fn validate(&self) -> Result<(), ConsensusTimingError> {
+        if self.wait < self.min_wait {
+            return Err(ConsensusTimingError::WaitBelowMin {
+                wait:     self.wait,
+                min_wait: self.min_wait
+            })
+        }
+        if self.aggregation_timeout <= self.wait {
+            return Err(ConsensusTimingError::AggregationBeforeWait {
+                timeout: self.aggregation_timeout
+            })
+        }
+        if self.aggregation_timeout > self.proposal_deadline {
+            return Err(ConsensusTimingError::AggregationPastDeadline {
+                timeout: self.aggregation_timeout
+            })
+        }
+        if self.proposal_deadline >= self.block_time {
+            return Err(ConsensusTimingError::DeadlinePastBlock { deadline: self.proposal_deadline })
+        }
+        if self.finalization_budget.is_zero() || self.finalization_budget > self.block_time {
+            return Err(ConsensusTimingError::FinalizationBudget {
+                budget: self.finalization_budget
+            })
+        }
+
+        Ok(())
+    }
+
+    /// What's left of the block after the proposal deadline.
+    pub fn submission_headroom(&self) -> Duration {
+        self.block_time.saturating_sub(self.proposal_deadline)
+    }
+}
+
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_the_times_against_each_other() {
+        let timing = ConsensusTiming::default();
+        assert_eq!(timing.validate(), Ok(()));
+        assert_eq!(timing.submission_headroom(), Duration::from_millis(800));
+
+        let late = ConsensusTiming { aggregation_timeout: Duration::from_secs(12), ..timing };
+        assert!(matches!(
+            late.validate(),
+            Err(ConsensusTimingError::AggregationPastDeadline { .. })
+        ));
+        let early = ConsensusTiming { aggregation_timeout: timing.wait, ..timing };
+        assert!(matches!(
+            early.validate(),
+            Err(ConsensusTimingError::AggregationBeforeWait { .. })
+        ));
+        let short = ConsensusTiming { wait: Duration::from_secs(1), ..timing };
+        assert!(matches!(short.validate(), Err(ConsensusTimingError::WaitBelowMin { .. })));
+        let unbounded = ConsensusTiming { finalization_budget: Duration::ZERO, ..timing };
+        assert!(matches!(
+            unbounded.validate(),
+            Err(ConsensusTimingError::FinalizationBudget { .. })
+        ));
+    }
+
+    #[test]
+    fn fills_the_missing_times_with_defaults() {
+        let timing: ConsensusTiming =
+            serde_json::from_str(r#"{"wait_ms": 8000, "proposal_deadline_ms": 11500}"#).unwrap();
+        assert_eq!(timing.wait, Duration::from_secs(8));
+        assert_eq!(timing.proposal_deadline, Duration::from_millis(11_500));
+        assert_eq!(timing.block_time, ConsensusTiming::default().block_time);
+        assert_eq!(serde_json::to_value(timing).unwrap()["wait_ms"], 8000);
+    }
+}
@@ -0,0 +1,41 @@
+//! The solutions every node computed when verifying the proposals of its
+//! rounds, for the devnet to assert they agree across nodes. Only built with
+//! the `testnet` feature.
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::primitives::BlockNumber;
+use angstrom_types::{orders::PoolSolution, primitive::PeerId};
+use parking_lot::Mutex;
+
+/// A node's verification of the proposal of a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalVerification {
+    pub leader:   PeerId,
+    /// solutions of the proposal, sorted
+    pub proposed: Vec<PoolSolution>,
+    /// solutions the node computed from the pre-proposals of the proposal,
+    /// sorted
+    pub computed: Vec<PoolSolution>
+}
+
+impl ProposalVerification {
+    pub fn passed(&self) -> bool {
+        self.proposed == self.computed
+    }
+}
+
+/// Verifications of the rounds a node finalized, by block.
+#[derive(Debug, Clone, Default)]
+pub struct ProposalVerifications {
+    rounds: Arc<Mutex<HashMap<BlockNumber, ProposalVerification>>>
+}
+
+impl ProposalVerifications {
+    pub fn get(&self, block: BlockNumber) -> Option<ProposalVerification> {
+        self.rounds.lock().get(&block).cloned()
+    }
+
+    pub(crate) fn insert(&self, block: BlockNumber, verification: ProposalVerification) {
+        self.rounds.lock().insert(block, verification);
+    }
+}
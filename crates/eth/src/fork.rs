@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use alloy::primitives::BlockNumber;
+use reth_chainspec::{ChainSpec, ForkId, Head};
+
+/// Follows the [EIP-2124](https://eips.ethereum.org/EIPS/eip-2124) fork id of
+/// the chain as its tip moves.
+///
+/// Standing orders stay valid for as long as their nonce is unused, a change of
+/// the fork id means the rules they were signed under might not hold anymore
+/// (or that we are following a different chain altogether).
+#[derive(Debug, Clone)]
+pub struct ForkTracker {
+    chain_spec: Arc<ChainSpec>,
+    fork_id:    Option<ForkId>
+}
+
+impl ForkTracker {
+    pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
+        Self { chain_spec, fork_id: None }
+    }
+
+    /// The fork id at the last tip seen.
+    pub fn fork_id(&self) -> Option<ForkId> {
+        self.fork_id
+    }
+
+    /// Moves to the new tip, returns the new fork id if it changed. The first
+    /// tip only sets where we start from.
+    pub fn on_tip(&mut self, number: BlockNumber, timestamp: u64) -> Option<ForkId> {
+        let fork_id = self
+            .chain_spec
+            .fork_id(&Head { number, timestamp, ..Default::default() });
+
+        match self.fork_id.replace(fork_id) {
+            Some(previous) if previous != fork_id => Some(fork_id),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_chainspec::MAINNET;
+
+    use super::*;
+
+    #[test]
+    fn reports_fork_id_changes_only() {
+        let mut tracker = ForkTracker::new(MAINNET.clone());
+        assert_eq!(tracker.on_tip(1_149_998, 0), None);
+        assert_eq!(tracker.on_tip(1_149_999, 0), None);
+        let before = tracker.fork_id().unwrap();
+
+        // homestead
+        let after = tracker.on_tip(1_150_000, 0).unwrap();
+        assert_ne!(before, after);
+        assert_eq!(tracker.on_tip(1_150_001, 0), None);
+
+        // a reorg back past the fork is a change as well
+        assert_eq!(tracker.on_tip(1_149_999, 0), Some(before));
+    }
+}
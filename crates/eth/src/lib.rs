@@ -1,2 +1,3 @@
+pub mod fork;
 pub mod handle;
 pub mod manager;
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{HashSet, VecDeque},
     ops::RangeInclusive,
     sync::Arc,
     task::{Context, Poll}
@@ -7,7 +7,10 @@ use std::{
 
 use alloy::{
     consensus::{BlockHeader, Transaction},
-    primitives::{aliases::I24, Address, BlockHash, BlockNumber, B256},
+    primitives::{
+        aliases::{I24, U24},
+        Address, BlockHash, BlockNumber, B256
+    },
     sol_types::SolEvent
 };
 use angstrom_types::{
@@ -16,12 +19,13 @@ use angstrom_types::{
         angstrom::Angstrom::PoolKey,
         controller_v_1::ControllerV1::{NodeAdded, NodeRemoved, PoolConfigured, PoolRemoved}
     },
-    contract_payloads::angstrom::{AngPoolConfigEntry, AngstromBundle, AngstromPoolConfigStore}
+    contract_payloads::angstrom::{AngstromBundle, AngstromPoolConfigStore}
 };
 use futures::Future;
 use futures_util::{FutureExt, StreamExt};
 use itertools::Itertools;
 use pade::PadeDecode;
+use reth_chainspec::ForkId;
 use reth_ethereum_primitives::{Block, Receipt, TransactionSigned};
 use reth_primitives_traits::RecoveredBlock;
 use reth_provider::{CanonStateNotification, CanonStateNotifications, Chain};
@@ -29,7 +33,10 @@ use reth_tasks::TaskSpawner;
 use tokio::sync::mpsc::{Receiver, Sender, UnboundedSender};
 use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
 
-use crate::handle::{EthCommand, EthHandle};
+use crate::{
+    fork::ForkTracker,
+    handle::{EthCommand, EthHandle}
+};
 
 alloy::sol!(
     event Transfer(address indexed _from, address indexed _to, uint256 _value);
@@ -38,6 +45,52 @@ alloy::sol!(
 
 const MAX_REORG_DEPTH: u64 = 150;
 
+/// Blocks worth of events a module that subscribes late is caught up with.
+const EVENT_REPLAY_BLOCKS: u64 = 16;
+
+/// Events of the last blocks, handed to modules that subscribe after they were
+/// sent out so that it doesn't matter in what order the modules are started.
+#[derive(Debug)]
+struct EventReplayBuffer {
+    blocks: u64,
+    tip:    BlockNumber,
+    events: VecDeque<(BlockNumber, EthEvent)>
+}
+
+impl EventReplayBuffer {
+    fn new(blocks: u64) -> Self {
+        Self { blocks, tip: 0, events: VecDeque::new() }
+    }
+
+    /// Moves the buffer to the new tip, drops the blocks that fell out of it.
+    fn set_tip(&mut self, tip: BlockNumber) {
+        self.tip = tip;
+        while self
+            .events
+            .front()
+            .is_some_and(|(block, _)| block + self.blocks <= tip)
+        {
+            self.events.pop_front();
+        }
+    }
+
+    fn push(&mut self, event: &EthEvent) {
+        // subscribers get the node set as it is now instead, and a fork only
+        // concerns the orders that were around when it happened
+        if matches!(
+            event,
+            EthEvent::AddedNode(_) | EthEvent::RemovedNode(_) | EthEvent::ForkIdChanged { .. }
+        ) {
+            return
+        }
+        self.events.push_back((self.tip, event.clone()));
+    }
+
+    fn events(&self) -> impl Iterator<Item = &EthEvent> + '_ {
+        self.events.iter().map(|(_, event)| event)
+    }
+}
+
 /// Listens for CanonStateNotifications and sends the appropriate updates to be
 /// executed by the order pool
 pub struct EthDataCleanser<Sync> {
@@ -57,7 +110,11 @@ pub struct EthDataCleanser<Sync> {
     /// updated by periphery contract.
     pool_store:        Arc<AngstromPoolConfigStore>,
     /// the set of currently active nodes.
-    node_set:          HashSet<Address>
+    node_set:          HashSet<Address>,
+    /// the events late subscribers are caught up with
+    replay:            EventReplayBuffer,
+    /// tracks the fork id of the chain, if we know its spec
+    fork_tracker:      Option<ForkTracker>
 }
 
 impl<Sync> EthDataCleanser<Sync>
@@ -75,6 +132,7 @@ where
         pool_store: Arc<AngstromPoolConfigStore>,
         sync: Sync,
         node_set: HashSet<Address>,
+        fork_tracker: Option<ForkTracker>,
         event_listeners: Vec<UnboundedSender<EthEvent>>
     ) -> anyhow::Result<EthHandle> {
         let stream = ReceiverStream::new(rx);
@@ -90,7 +148,9 @@ where
             block_sync: sync,
             pool_store,
             node_set,
-            event_listeners
+            event_listeners,
+            replay: EventReplayBuffer::new(EVENT_REPLAY_BLOCKS),
+            fork_tracker
         };
         // ensure we broadcast node set. will allow for proper connections
         // on the network side
@@ -113,13 +173,29 @@ where
     }
 
     fn send_events(&mut self, event: EthEvent) {
+        self.replay.push(&event);
         self.event_listeners
             .retain(|e| e.send(event.clone()).is_ok());
     }
 
+    /// Catches the listener up with the current node set and the events of
+    /// the last blocks before it gets the live ones.
+    fn add_listener(&mut self, listener: UnboundedSender<EthEvent>) {
+        let caught_up = self
+            .node_set
+            .iter()
+            .map(|node| EthEvent::AddedNode(*node))
+            .chain(self.replay.events().cloned())
+            .all(|event| listener.send(event).is_ok());
+
+        if caught_up {
+            self.event_listeners.push(listener);
+        }
+    }
+
     fn on_command(&mut self, command: EthCommand) {
         match command {
-            EthCommand::SubscribeEthNetworkEvents(tx) => self.event_listeners.push(tx),
+            EthCommand::SubscribeEthNetworkEvents(tx) => self.add_listener(tx),
             EthCommand::SubscribeCannon(tx) => {
                 let _ = tx.send(self.subscribe_cannon_notifications());
             }
@@ -135,6 +211,7 @@ where
     }
 
     fn handle_reorg(&mut self, old: Arc<impl ChainExt>, new: Arc<impl ChainExt>) {
+        self.replay.set_tip(new.tip_number());
         self.apply_periphery_logs(&new);
         // notify producer of reorg if one happened. NOTE: reth also calls this
         // on reverts
@@ -160,9 +237,11 @@ where
 
         self.send_events(transitions);
         self.send_events(reorged_orders);
+        self.check_fork_id(&new);
     }
 
     fn handle_commit(&mut self, new: Arc<impl ChainExt>) {
+        self.replay.set_tip(new.tip_number());
         // handle this first so the newest state is the first available
         self.apply_periphery_logs(&new);
 
@@ -179,6 +258,22 @@ where
             address_changeset: eoas
         };
         self.send_events(transitions);
+        self.check_fork_id(&new);
+    }
+
+    /// Lets everyone know if the tip moved us onto another fork id.
+    fn check_fork_id(&mut self, chain: &impl ChainExt) {
+        let block_number = chain.tip_number();
+        let Some(fork_id) = self
+            .fork_tracker
+            .as_mut()
+            .and_then(|tracker| tracker.on_tip(block_number, chain.tip_timestamp()))
+        else {
+            return
+        };
+
+        tracing::warn!(block_number, ?fork_id, "fork id changed");
+        self.send_events(EthEvent::ForkIdChanged { block_number, fork_id });
     }
 
     /// looks at all periphery contrct events updating the internal state +
@@ -216,36 +311,52 @@ where
                 if let Ok(added_pool) = PoolConfigured::decode_log(log, true) {
                     let asset0 = added_pool.asset0;
                     let asset1 = added_pool.asset1;
-                    let entry = AngPoolConfigEntry {
-                        pool_partial_key: AngstromPoolConfigStore::derive_store_key(asset0, asset1),
-                        tick_spacing:     added_pool.tickSpacing,
-                        fee_in_e6:        added_pool.bundleFee.to(),
-                        store_index:      self.pool_store.length()
-                    };
-
-                    let pool_key = PoolKey {
-                        currency1:   asset1,
-                        currency0:   asset0,
-                        fee:         added_pool.bundleFee,
-                        tickSpacing: I24::try_from_be_slice(&{
-                            let bytes = added_pool.tickSpacing.to_be_bytes();
-                            let mut a = [0u8; 3];
-                            a[1..3].copy_from_slice(&bytes);
-                            a
-                        })
-                        .unwrap(),
-                        hooks:       self.angstrom_address
-                    };
-
-                    self.pool_store.new_pool(asset0, asset1, entry);
+                    let pool_key =
+                        self.pool_key(asset0, asset1, added_pool.tickSpacing, added_pool.bundleFee);
+
+                    let previous = self.pool_store.configure_pool(
+                        asset0,
+                        asset1,
+                        added_pool.tickSpacing,
+                        added_pool.bundleFee.to()
+                    );
                     self.angstrom_tokens.insert(asset0);
                     self.angstrom_tokens.insert(asset1);
 
-                    self.send_events(EthEvent::NewPool { pool: pool_key });
+                    match previous {
+                        None => self.send_events(EthEvent::NewPool { pool: pool_key }),
+                        Some(previous)
+                            if previous.tick_spacing != added_pool.tickSpacing
+                                || previous.fee_in_e6 != added_pool.bundleFee.to::<u32>() =>
+                        {
+                            let previous_key = self.pool_key(
+                                asset0,
+                                asset1,
+                                previous.tick_spacing,
+                                U24::from(previous.fee_in_e6)
+                            );
+                            self.send_events(EthEvent::PoolParamsUpdated {
+                                pool:     pool_key,
+                                previous: previous_key
+                            });
+                        }
+                        // configured again with the parameters it had
+                        Some(_) => {}
+                    }
                 }
             });
     }
 
+    fn pool_key(&self, asset0: Address, asset1: Address, tick_spacing: u16, fee: U24) -> PoolKey {
+        PoolKey {
+            currency1: asset1,
+            currency0: asset0,
+            fee,
+            tickSpacing: I24::unchecked_from(tick_spacing),
+            hooks: self.angstrom_address
+        }
+    }
+
     fn fetch_filled_order<'a>(
         &'a self,
         chain: &'a impl ChainExt
@@ -328,14 +439,28 @@ pub enum EthEvent {
     RemovedPool {
         pool: PoolKey
     },
+    /// the fee or tick spacing of a pool were changed on chain, the pool keeps
+    /// its place in the config store but is now keyed by `pool`. Orders and
+    /// bundles for `previous` won't settle anymore
+    PoolParamsUpdated {
+        pool:     PoolKey,
+        previous: PoolKey
+    },
     AddedNode(Address),
-    RemovedNode(Address)
+    RemovedNode(Address),
+    /// the chain moved onto a new fork id at this block, orders seen before
+    /// can't be trusted to be valid on it
+    ForkIdChanged {
+        block_number: u64,
+        fork_id:      ForkId
+    }
 }
 
 #[auto_impl::auto_impl(&,Arc)]
 pub trait ChainExt {
     fn tip_number(&self) -> BlockNumber;
     fn tip_hash(&self) -> BlockHash;
+    fn tip_timestamp(&self) -> u64;
     fn receipts_by_block_hash(&self, block_hash: BlockHash) -> Option<Vec<&Receipt>>;
     fn tip_transactions(&self) -> impl Iterator<Item = &TransactionSigned> + '_;
     fn reorged_range(&self, new: impl ChainExt) -> Option<RangeInclusive<u64>>;
@@ -351,6 +476,10 @@ impl ChainExt for Chain {
         self.tip().hash()
     }
 
+    fn tip_timestamp(&self) -> u64 {
+        self.tip().timestamp
+    }
+
     fn receipts_by_block_hash(&self, block_hash: BlockHash) -> Option<Vec<&Receipt>> {
         self.receipts_by_block_hash(block_hash)
     }
@@ -423,6 +552,7 @@ pub mod test {
     pub struct MockChain<'a> {
         pub hash:         BlockHash,
         pub number:       BlockNumber,
+        pub timestamp:    u64,
         pub transactions: Vec<TransactionSigned>,
         pub receipts:     Vec<&'a Receipt>
     }
@@ -436,6 +566,10 @@ pub mod test {
             self.hash
         }
 
+        fn tip_timestamp(&self) -> u64 {
+            self.timestamp
+        }
+
         fn receipts_by_block_hash(&self, _: BlockHash) -> Option<Vec<&Receipt>> {
             Some(self.receipts.clone())
         }
@@ -469,7 +603,9 @@ pub mod test {
             canonical_updates: BroadcastStream::new(cannon_rx),
             block_sync:        GlobalBlockSync::new(1),
             cannon_sender:     tx,
-            pool_store:        Default::default()
+            pool_store:        Default::default(),
+            replay:            EventReplayBuffer::new(EVENT_REPLAY_BLOCKS),
+            fork_tracker:      None
         }
     }
 
@@ -509,8 +645,9 @@ pub mod test {
         let pair = vec![pair];
         let assets = vec![asset0, asset1];
 
-        let finalized_user_order = UserOrder::from_internal_order_max_gas(&user_order, &outcome, 0);
-        let finalized_tob = TopOfBlockOrder::of_max_gas(&t, 0);
+        let finalized_user_order =
+            UserOrder::from_internal_order_max_gas(&user_order, &outcome, 0).unwrap();
+        let finalized_tob = TopOfBlockOrder::of_max_gas(&t, 0).unwrap();
 
         let order_hashes = vec![
             finalized_user_order.order_hash(&pair, &assets, 0),
@@ -693,6 +830,75 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn test_fork_id_change_is_announced() {
+        let mut eth = setup_non_subscription_eth_manager(None);
+        eth.fork_tracker = Some(ForkTracker::new(reth_chainspec::MAINNET.clone()));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        eth.event_listeners.push(tx);
+
+        // the last block before homestead, and homestead
+        for number in [1_149_999, 1_150_000] {
+            let chain =
+                Arc::new(MockChain { number, hash: BlockHash::random(), ..Default::default() });
+            eth.handle_commit(chain);
+        }
+
+        let events = std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            events.last().unwrap(),
+            EthEvent::ForkIdChanged { block_number: 1_150_000, .. }
+        ));
+
+        // a late subscriber only gets the blocks
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        eth.on_command(EthCommand::SubscribeEthNetworkEvents(tx));
+        let replayed = std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(replayed.len(), 2);
+        assert!(replayed
+            .iter()
+            .all(|event| matches!(event, EthEvent::NewBlockTransitions { .. })));
+    }
+
+    #[test]
+    fn test_late_subscriber_is_caught_up() {
+        let mut eth = setup_non_subscription_eth_manager(None);
+        eth.replay = EventReplayBuffer::new(2);
+        let node = Address::random();
+        eth.node_set.insert(node);
+
+        for number in 100..=102 {
+            let chain =
+                Arc::new(MockChain { number, hash: BlockHash::random(), ..Default::default() });
+            eth.handle_commit(chain);
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        eth.on_command(EthCommand::SubscribeEthNetworkEvents(tx));
+
+        assert!(matches!(rx.try_recv().unwrap(), EthEvent::AddedNode(added) if added == node));
+        // only the blocks that are still in the buffer are replayed
+        for expected in [101, 102] {
+            match rx.try_recv().unwrap() {
+                EthEvent::NewBlockTransitions { block_number, .. } => {
+                    assert_eq!(block_number, expected)
+                }
+                event => panic!("unexpected event {event:?}")
+            }
+        }
+        assert!(rx.try_recv().is_err());
+
+        // and gets the live events afterwards
+        let chain =
+            Arc::new(MockChain { number: 103, hash: BlockHash::random(), ..Default::default() });
+        eth.handle_commit(chain);
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            EthEvent::NewBlockTransitions { block_number: 103, .. }
+        ));
+    }
+
     #[test]
     fn test_fetch_eoa_balance_approval_changes() {
         let ang_addr = Address::random();
@@ -906,6 +1112,87 @@ pub mod test {
         assert_eq!(eth.pool_store.length(), 0); // Should be removed
     }
 
+    #[test]
+    fn reconfigured_pools_keep_their_store_index() {
+        let ang_addr = Address::random();
+        let periphery_addr = Address::random();
+        let mut eth = setup_non_subscription_eth_manager(Some(ang_addr));
+        eth.periphery_address = periphery_addr;
+        eth.angstrom_address = ang_addr;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        eth.event_listeners.push(tx);
+
+        let (first0, first1) = (Address::random(), Address::random());
+        let (asset0, asset1) = (Address::random(), Address::random());
+        let fee = U24::try_from(3000).unwrap();
+        let configure = |asset0, asset1, tick_spacing, fee| PoolConfigured {
+            asset0,
+            asset1,
+            bundleFee: fee,
+            unlockedFee: fee,
+            tickSpacing: tick_spacing
+        };
+        let logs = vec![
+            Log {
+                address: periphery_addr,
+                data:    configure(first0, first1, 60, fee).encode_log_data()
+            },
+            Log {
+                address: periphery_addr,
+                data:    configure(asset0, asset1, 60, fee).encode_log_data()
+            },
+            Log {
+                address: periphery_addr,
+                data:    configure(asset0, asset1, 10, U24::from(500)).encode_log_data()
+            },
+            // configured again without changes
+            Log {
+                address: periphery_addr,
+                data:    configure(asset0, asset1, 10, U24::from(500)).encode_log_data()
+            },
+        ];
+
+        let mock_recip = Receipt { logs, ..Default::default() };
+        let mock_chain = Arc::new(MockChain { receipts: vec![&mock_recip], ..Default::default() });
+        eth.apply_periphery_logs(&*mock_chain);
+
+        assert_eq!(eth.pool_store.length(), 2);
+        let entry = eth.pool_store.get_entry(asset0, asset1).unwrap();
+        assert_eq!((entry.tick_spacing, entry.fee_in_e6, entry.store_index), (10, 500, 1));
+
+        assert!(matches!(rx.try_recv(), Ok(EthEvent::NewPool { .. })));
+        assert!(matches!(rx.try_recv(), Ok(EthEvent::NewPool { .. })));
+        let Ok(EthEvent::PoolParamsUpdated { pool, previous }) = rx.try_recv() else {
+            panic!("expected the parameters of the pool to be updated")
+        };
+        assert_eq!((previous.fee, previous.tickSpacing), (fee, I24::unchecked_from(60)));
+        assert_eq!((pool.fee, pool.tickSpacing), (U24::from(500), I24::unchecked_from(10)));
+        assert_eq!((pool.currency0, pool.hooks), (asset0, ang_addr));
+        assert!(rx.try_recv().is_err());
+
+        // the pool before it is removed, it moves into its place
+        let remove = PoolRemoved {
+            asset0:      first0,
+            asset1:      first1,
+            feeInE6:     fee,
+            tickSpacing: I24::unchecked_from(60)
+        };
+        let mock_recip = Receipt {
+            logs: vec![Log { address: periphery_addr, data: remove.encode_log_data() }],
+            ..Default::default()
+        };
+        let mock_chain = Arc::new(MockChain { receipts: vec![&mock_recip], ..Default::default() });
+        eth.apply_periphery_logs(&*mock_chain);
+
+        assert_eq!(
+            eth.pool_store
+                .get_entry(asset0, asset1)
+                .unwrap()
+                .store_index,
+            0
+        );
+    }
+
     #[test]
     fn test_non_angstrom_token_transfers() {
         let ang_addr = Address::random();
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use alloy::primitives::FixedBytes;
+use matching_engine::{
+    book::{incremental::IncrementalBook, BookOrder},
+    build_book_with_carry_over
+};
+use testing_tools::type_generator::book::generate_one_sided_book;
+
+const ORDER_COUNT: &[usize] = &[1_000, 10_000, 100_000];
+/// share of the book that changes between two rounds, in percent
+const CHURN_PERCENT: usize = 1;
+
+static CENTER_PRICE: f64 = 100_000_000.0;
+
+fn main() {
+    divan::main();
+}
+
+/// The orders of the book in the previous round and in the current one, which
+/// replaced the first [`CHURN_PERCENT`] of them.
+fn rounds(order_count: usize) -> (Vec<BookOrder>, Vec<BookOrder>) {
+    let pool_id = FixedBytes::<32>::random();
+    let previous = generate_one_sided_book(true, pool_id, order_count, CENTER_PRICE)
+        .bids()
+        .to_vec();
+    let churn = (order_count * CHURN_PERCENT / 100).max(1);
+    let replacements = generate_one_sided_book(true, pool_id, churn, CENTER_PRICE)
+        .bids()
+        .to_vec();
+    let current = replacements
+        .into_iter()
+        .chain(previous[churn..].iter().cloned())
+        .collect();
+
+    (previous, current)
+}
+
+#[divan::bench(consts = ORDER_COUNT)]
+fn full_rebuild<const N: usize>(bencher: divan::Bencher) {
+    bencher.with_inputs(|| rounds(N).1).bench_values(|current| {
+        let pool_id = current[0].pool_id;
+        build_book_with_carry_over(pool_id, None, current.into_iter().collect(), &HashMap::new())
+    });
+}
+
+#[divan::bench(consts = ORDER_COUNT)]
+fn incremental_sync<const N: usize>(bencher: divan::Bencher) {
+    bencher
+        .with_inputs(|| {
+            let (previous, current) = rounds(N);
+            let mut book = IncrementalBook::new(previous[0].pool_id);
+            book.rebuild(previous);
+            (book, current)
+        })
+        .bench_values(|(mut book, current)| {
+            book.sync(current, &HashMap::new(), 0);
+            book.book(None)
+        });
+}
@@ -0,0 +1,46 @@
+//! The volume matcher on deep books, taking the prices from the ladder of the
+//! book against converting them on every lookup. The ladder is built from
+//! scratch in every iteration, like it is for every book of a round. The
+//! matcher takes at most 1000 steps, so the books stay below 500 orders a
+//! side.
+//!
+//! `cargo bench -p matching-engine --bench price_ladder`
+use alloy::primitives::FixedBytes;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use matching_engine::{book::OrderBook, matcher::VolumeFillMatcher};
+use testing_tools::type_generator::book::generate_simple_cross_book;
+
+const DEPTHS: &[usize] = &[100, 250, 450];
+
+static CENTER_PRICE: f64 = 100_000_000.0;
+
+fn deep_books(c: &mut Criterion) {
+    let mut group = c.benchmark_group("volume_fill_matcher");
+    for &depth in DEPTHS {
+        let book = generate_simple_cross_book(FixedBytes::random(), depth, CENTER_PRICE);
+        let fresh = || book.retain_orders(|_| true);
+
+        group.bench_with_input(BenchmarkId::new("price_ladder", depth), &depth, |b, _| {
+            b.iter_batched(
+                fresh,
+                |book: OrderBook| VolumeFillMatcher::new(&book).run_match(),
+                BatchSize::LargeInput
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("converted_prices", depth), &depth, |b, _| {
+            b.iter_batched(
+                fresh,
+                |book: OrderBook| {
+                    VolumeFillMatcher::new(&book)
+                        .without_price_ladder()
+                        .run_match()
+                },
+                BatchSize::LargeInput
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, deep_books);
+criterion_main!(benches);
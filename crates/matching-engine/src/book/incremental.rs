@@ -0,0 +1,454 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet}
+};
+
+use alloy_primitives::{Address, B256};
+use angstrom_types::{matching::uniswap::PoolSnapshot, primitive::PoolId};
+
+use super::{
+    peg::{mid_price, resolve_peg},
+    sort::{compare_orders, TieBreak},
+    BookOrder, OrderBook
+};
+
+/// A change to the orders of a book.
+#[derive(Debug, Clone)]
+pub enum BookDelta {
+    Add(BookOrder),
+    Remove(B256),
+    /// the order was completely filled, a partial fill is a [`Self::Remove`]
+    /// followed by an [`Self::Add`] of what is left of the order
+    Fill(B256)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BookInconsistency {
+    #[error("order {0:?} is already in the book")]
+    DuplicateOrder(B256),
+    #[error("order {0:?} is not in the book")]
+    UnknownOrder(B256),
+    #[error("order {hash:?} belongs to pool {pool:?}")]
+    WrongPool { hash: B256, pool: PoolId }
+}
+
+/// The book of a pool kept sorted across rounds.
+///
+/// Instead of sorting every order again each round, only the orders that
+/// changed are sorted and merged into the sides. The sides are ranked by
+/// [`compare_orders`] like [`crate::build_book_at`] ranks them, so the book
+/// comes out the same as a rebuild.
+#[derive(Debug, Default)]
+pub struct IncrementalBook {
+    id:        PoolId,
+    bids:      Vec<BookOrder>,
+    asks:      Vec<BookOrder>,
+    /// side of every order in the book
+    orders:    HashMap<B256, bool>,
+    tie_break: TieBreak
+}
+
+impl IncrementalBook {
+    pub fn new(id: PoolId) -> Self {
+        Self { id, ..Default::default() }
+    }
+
+    pub fn id(&self) -> PoolId {
+        self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Applies the deltas in order. On an error the book is inconsistent and
+    /// has to be rebuilt, [`Self::sync`] does so.
+    pub fn apply(
+        &mut self,
+        deltas: impl IntoIterator<Item = BookDelta>
+    ) -> Result<(), BookInconsistency> {
+        let mut added = HashMap::<B256, BookOrder>::new();
+        let mut removed = HashSet::new();
+        for delta in deltas {
+            match delta {
+                BookDelta::Add(order) => {
+                    let hash = order.order_id.hash;
+                    if order.pool_id != self.id {
+                        return Err(BookInconsistency::WrongPool { hash, pool: order.pool_id })
+                    }
+                    if self.orders.insert(hash, order.is_bid).is_some() {
+                        return Err(BookInconsistency::DuplicateOrder(hash))
+                    }
+                    added.insert(hash, order);
+                }
+                BookDelta::Remove(hash) | BookDelta::Fill(hash) => {
+                    if self.orders.remove(&hash).is_none() {
+                        return Err(BookInconsistency::UnknownOrder(hash))
+                    }
+                    // an order added by the same batch never made it into the sides
+                    if added.remove(&hash).is_none() {
+                        removed.insert(hash);
+                    }
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            self.bids
+                .retain(|order| !removed.contains(&order.order_id.hash));
+            self.asks
+                .retain(|order| !removed.contains(&order.order_id.hash));
+        }
+        let (bids, asks): (Vec<_>, Vec<_>) = added.into_values().partition(|order| order.is_bid);
+        self.bids = Self::merge(std::mem::take(&mut self.bids), bids, |a, b| {
+            compare_orders(&self.tie_break, true, a, b)
+        });
+        self.asks = Self::merge(std::mem::take(&mut self.asks), asks, |a, b| {
+            compare_orders(&self.tie_break, false, a, b)
+        });
+
+        Ok(())
+    }
+
+    /// Brings the book to exactly the given orders, ranked like they are at
+    /// `timestamp`. Orders that changed since the last sync are replaced.
+    /// Falls back to a rebuild if the deltas don't apply.
+    pub fn sync(
+        &mut self,
+        orders: impl IntoIterator<Item = BookOrder>,
+        carry_over: &HashMap<B256, u64>,
+        timestamp: u64
+    ) {
+        let mut incoming = orders
+            .into_iter()
+            .map(|order| (order.order_id.hash, order))
+            .collect::<HashMap<_, _>>();
+        self.set_tie_break(TieBreak::at(incoming.values(), carry_over, timestamp));
+
+        let mut deltas = Vec::new();
+        for order in self.bids.iter().chain(&self.asks) {
+            let hash = order.order_id.hash;
+            match incoming.remove(&hash) {
+                Some(new) if new == *order => {}
+                Some(new) => deltas.extend([BookDelta::Remove(hash), BookDelta::Add(new)]),
+                None => deltas.push(BookDelta::Remove(hash))
+            }
+        }
+        deltas.extend(incoming.into_values().map(BookDelta::Add));
+
+        if let Err(error) = self.apply(deltas.clone()) {
+            tracing::warn!(pool_id = ?self.id, %error, "book deltas didn't apply, rebuilding");
+            // a failed apply leaves the sides untouched
+            let removed = deltas
+                .iter()
+                .filter_map(|delta| match delta {
+                    BookDelta::Remove(hash) => Some(*hash),
+                    _ => None
+                })
+                .collect::<HashSet<_>>();
+            let kept = std::mem::take(&mut self.bids)
+                .into_iter()
+                .chain(std::mem::take(&mut self.asks))
+                .filter(|order| !removed.contains(&order.order_id.hash));
+            let added = deltas.into_iter().filter_map(|delta| match delta {
+                BookDelta::Add(order) => Some(order),
+                _ => None
+            });
+            self.rebuild(kept.chain(added).collect::<Vec<_>>());
+        }
+    }
+
+    /// Sorts the given orders into a fresh book.
+    pub fn rebuild(&mut self, orders: impl IntoIterator<Item = BookOrder>) {
+        let orders = orders
+            .into_iter()
+            .filter(|order| order.pool_id == self.id)
+            .map(|order| (order.order_id.hash, order))
+            .collect::<HashMap<_, _>>();
+        self.orders = orders
+            .iter()
+            .map(|(hash, order)| (*hash, order.is_bid))
+            .collect();
+        let (mut bids, mut asks): (Vec<_>, Vec<_>) =
+            orders.into_values().partition(|order| order.is_bid);
+        bids.sort_by(|a, b| compare_orders(&self.tie_break, true, a, b));
+        asks.sort_by(|a, b| compare_orders(&self.tie_break, false, a, b));
+        self.bids = bids;
+        self.asks = asks;
+    }
+
+    /// A change in the carry-over of any order of the book, or an order
+    /// coming close to its deadline, re-sorts it.
+    fn set_tie_break(&mut self, tie_break: TieBreak) {
+        let changed = self.orders.keys().any(|hash| {
+            self.tie_break.carry_over.get(hash) != tie_break.carry_over.get(hash)
+                || self.tie_break.expiring.contains(hash) != tie_break.expiring.contains(hash)
+        });
+        self.tie_break = tie_break;
+        if changed {
+            self.bids
+                .sort_by(|a, b| compare_orders(&self.tie_break, true, a, b));
+            self.asks
+                .sort_by(|a, b| compare_orders(&self.tie_break, false, a, b));
+        }
+    }
+
+    /// Merges the sorted orders of a side with newly added ones.
+    fn merge(
+        side: Vec<BookOrder>,
+        mut added: Vec<BookOrder>,
+        cmp: impl Fn(&BookOrder, &BookOrder) -> Ordering
+    ) -> Vec<BookOrder> {
+        if added.is_empty() {
+            return side
+        }
+        added.sort_by(&cmp);
+
+        let mut merged = Vec::with_capacity(side.len() + added.len());
+        let mut side = side.into_iter().peekable();
+        let mut added = added.into_iter().peekable();
+        while let (Some(existing), Some(new)) = (side.peek(), added.peek()) {
+            let next =
+                if cmp(new, existing) == Ordering::Less { added.next() } else { side.next() };
+            merged.extend(next);
+        }
+        merged.extend(side);
+        merged.extend(added);
+
+        merged
+    }
+
+    pub fn book(&self, amm: Option<PoolSnapshot>) -> OrderBook {
+        // the sides already are in order, the book keeps them as they are
+        OrderBook::new(self.id, amm, self.bids.clone(), self.asks.clone(), None)
+    }
+}
+
+/// The books of all pools, each kept sorted across rounds.
+#[derive(Debug, Default)]
+pub struct IncrementalBooks {
+    books: HashMap<PoolId, IncrementalBook>
+}
+
+impl IncrementalBooks {
+    /// Syncs the book of every pool with orders to them and builds the books
+    /// for the round. Books of pools without orders are dropped.
+    pub fn sync(
+        &mut self,
+        limit: Vec<BookOrder>,
+        pool_snapshots: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: &HashMap<B256, u64>,
+        timestamp: u64
+    ) -> Vec<OrderBook> {
+        let by_pool = limit
+            .into_iter()
+            .fold(HashMap::<_, Vec<_>>::new(), |mut acc, order| {
+                acc.entry(order.pool_id).or_default().push(order);
+                acc
+            });
+        self.books.retain(|id, _| by_pool.contains_key(id));
+
+        by_pool
+            .into_iter()
+            .map(|(id, orders)| {
+                let book = self
+                    .books
+                    .entry(id)
+                    .or_insert_with(|| IncrementalBook::new(id));
+                let amm = pool_snapshots.get(&id).map(|pool| pool.2.clone());
+                // pegged orders move with the mid, they are synced at their price of the round
+                let mid = amm.as_ref().map(mid_price);
+                let orders = orders.into_iter().map(|order| resolve_peg(order, mid));
+                book.sync(orders, carry_over, timestamp);
+                book.book(amm)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{FixedBytes, Uint};
+    use angstrom_types::matching::Ray;
+    use proptest::{collection::vec, prelude::*};
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+    use crate::build_book_at;
+
+    fn order(pool_id: PoolId, is_bid: bool, price: u128) -> BookOrder {
+        UserOrderBuilder::new()
+            .partial()
+            .is_bid(is_bid)
+            .amount(10)
+            .min_price(Ray::from(Uint::from(price)))
+            .with_storage()
+            .is_bid(is_bid)
+            .pool_id(pool_id)
+            .build()
+    }
+
+    fn hashes(orders: &[BookOrder]) -> Vec<B256> {
+        orders.iter().map(|order| order.order_id.hash).collect()
+    }
+
+    fn rebuilt(id: PoolId, orders: Vec<BookOrder>) -> IncrementalBook {
+        let mut book = IncrementalBook::new(id);
+        book.rebuild(orders);
+        book
+    }
+
+    #[test]
+    fn deltas_match_a_rebuild() {
+        let id = FixedBytes::random();
+        let orders = (0..20)
+            .map(|i| order(id, i % 2 == 0, 1_000 + (i * 37 % 11) as u128))
+            .collect::<Vec<_>>();
+        let mut book = rebuilt(id, orders[..10].to_vec());
+
+        let deltas = orders[10..]
+            .iter()
+            .cloned()
+            .map(BookDelta::Add)
+            .chain([BookDelta::Remove(orders[3].order_id.hash)])
+            .chain([BookDelta::Fill(orders[12].order_id.hash)]);
+        book.apply(deltas).unwrap();
+
+        let remaining = orders
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 3 && *i != 12)
+            .map(|(_, order)| order.clone())
+            .collect::<Vec<_>>();
+        let expected = rebuilt(id, remaining);
+        assert_eq!(hashes(&book.bids), hashes(&expected.bids));
+        assert_eq!(hashes(&book.asks), hashes(&expected.asks));
+        assert_eq!(book.len(), 18);
+        assert!(book.book(None).invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn rejects_inconsistent_deltas() {
+        let id = FixedBytes::random();
+        let existing = order(id, true, 1_000);
+        let mut book = rebuilt(id, vec![existing.clone()]);
+
+        assert_eq!(
+            book.apply([BookDelta::Add(existing.clone())]),
+            Err(BookInconsistency::DuplicateOrder(existing.order_id.hash))
+        );
+        let unknown = B256::random();
+        assert_eq!(
+            rebuilt(id, vec![]).apply([BookDelta::Remove(unknown)]),
+            Err(BookInconsistency::UnknownOrder(unknown))
+        );
+        let other_pool = order(FixedBytes::random(), true, 1_000);
+        assert!(matches!(
+            rebuilt(id, vec![]).apply([BookDelta::Add(other_pool)]),
+            Err(BookInconsistency::WrongPool { .. })
+        ));
+    }
+
+    #[test]
+    fn sync_replaces_changed_orders_and_resorts_on_carry_over() {
+        let id = FixedBytes::random();
+        let (a, b) = (order(id, false, 1_000), order(id, false, 1_000));
+        let mut book = IncrementalBook::new(id);
+        book.sync([a.clone(), b.clone()], &HashMap::new(), 0);
+        assert_eq!(book.len(), 2);
+
+        // the order carried over the longest goes first on ties
+        let later = if hashes(&book.asks)[0] == a.order_id.hash { &b } else { &a };
+        book.sync([a.clone(), b.clone()], &HashMap::from([(later.order_id.hash, 3)]), 0);
+        assert_eq!(book.asks[0].order_id.hash, later.order_id.hash);
+
+        let mut changed = a.clone();
+        changed.valid_block += 1;
+        book.sync([changed.clone()], &HashMap::new(), 0);
+        assert_eq!(book.asks, vec![changed]);
+    }
+
+    #[test]
+    fn sync_resorts_as_orders_come_close_to_their_deadline() {
+        let id = FixedBytes::random();
+        let standing = |deadline: u64| {
+            UserOrderBuilder::new()
+                .standing()
+                .partial()
+                .amount(10)
+                .min_price(Ray::from(Uint::from(1_000)))
+                .deadline(Uint::from(deadline))
+                .with_storage()
+                .is_bid(false)
+                .pool_id(id)
+                .build()
+        };
+        let (first, second) = (standing(1_000), standing(2_000));
+        let mut book = IncrementalBook::new(id);
+        book.sync([first.clone(), second.clone()], &HashMap::new(), 0);
+
+        // the order about to expire goes ahead, however long the other one was
+        // carried over
+        let carried = HashMap::from([(second.order_id.hash, 3)]);
+        book.sync([first.clone(), second.clone()], &carried, 0);
+        assert_eq!(hashes(&book.asks), vec![second.order_id.hash, first.order_id.hash]);
+        book.sync([first.clone(), second.clone()], &carried, 990);
+        assert_eq!(hashes(&book.asks), vec![first.order_id.hash, second.order_id.hash]);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn syncs_to_the_book_a_rebuild_builds(
+            sides in vec((any::<bool>(), 0..3u128, 0..3u64), 12),
+            rounds in vec((any::<u16>(), vec(0..3u64, 12)), 1..6)
+        ) {
+            let id = FixedBytes::random();
+            // few prices, carry-overs and deadlines, so that most orders tie
+            let orders = sides
+                .into_iter()
+                .map(|(is_bid, price, deadline)| {
+                    UserOrderBuilder::new()
+                        .standing()
+                        .partial()
+                        .is_bid(is_bid)
+                        .amount(10)
+                        .min_price(Ray::from(Uint::from(1_000 + price)))
+                        .deadline(Uint::from(1_000 * (deadline + 1)))
+                        .with_storage()
+                        .is_bid(is_bid)
+                        .pool_id(id)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+
+            let mut book = IncrementalBook::new(id);
+            for (round, (included, carried)) in rounds.into_iter().enumerate() {
+                let orders = orders
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| included & (1 << i) != 0)
+                    .map(|(_, order)| order.clone())
+                    .collect::<Vec<_>>();
+                let carry_over = orders
+                    .iter()
+                    .zip(&carried)
+                    .filter(|(_, rounds)| **rounds > 0)
+                    .map(|(order, rounds)| (order.order_id.hash, *rounds))
+                    .collect::<HashMap<_, _>>();
+                let timestamp = round as u64 * 1_000;
+
+                book.sync(orders.clone(), &carry_over, timestamp);
+                let rebuilt =
+                    build_book_at(id, None, orders.into_iter().collect(), &carry_over, timestamp);
+                prop_assert_eq!(hashes(&book.bids), hashes(rebuilt.bids()));
+                prop_assert_eq!(hashes(&book.asks), hashes(rebuilt.asks()));
+            }
+        }
+    }
+}
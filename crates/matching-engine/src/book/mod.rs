@@ -1,6 +1,13 @@
 //! basic book impl so we can benchmark
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock
+};
+
+use angstrom_metrics::check_invariants;
 use angstrom_types::{
-    matching::uniswap::PoolSnapshot,
+    matching::{uniswap::PoolSnapshot, PoolMatchingConfig, Ray},
+    orders::{InvariantViolation, OrderOutcome},
     primitive::PoolId,
     sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
 };
@@ -10,15 +17,30 @@ use self::sort::SortStrategy;
 
 pub type BookOrder = OrderWithStorageData<GroupedVanillaOrder>;
 
+/// The prices of a book as the matcher compares them, the ones of the bids
+/// already inverted. Inverting a price is the most expensive part of walking
+/// the book, so it's done once per order instead of on every comparison.
+#[derive(Debug, Default)]
+pub struct PriceLadder {
+    pub bids: Vec<Ray>,
+    pub asks: Vec<Ray>
+}
+
+pub mod incremental;
 pub mod order;
+pub mod peg;
 pub mod sort;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct OrderBook {
-    id:   PoolId,
-    amm:  Option<PoolSnapshot>,
-    bids: Vec<BookOrder>,
-    asks: Vec<BookOrder>
+    id:     PoolId,
+    amm:    Option<PoolSnapshot>,
+    bids:   Vec<BookOrder>,
+    asks:   Vec<BookOrder>,
+    #[serde(default)]
+    config: PoolMatchingConfig,
+    #[serde(skip)]
+    ladder: OnceLock<PriceLadder>
 }
 
 impl OrderBook {
@@ -33,7 +55,73 @@ impl OrderBook {
         let strategy = sort.unwrap_or_default();
         strategy.sort_bids(&mut bids);
         strategy.sort_asks(&mut asks);
-        Self { id, amm, bids, asks }
+        let book = Self {
+            id,
+            amm,
+            bids,
+            asks,
+            config: PoolMatchingConfig::default(),
+            ladder: OnceLock::new()
+        };
+        // an unsorted book is in whatever order the caller built it in, only
+        // the sorted ones have to be in the order the matcher walks them
+        let sorted = !matches!(strategy, SortStrategy::Unsorted);
+        check_invariants("order_book", || {
+            let mut violations = book.invariant_violations();
+            if !sorted {
+                violations.retain(|violation| {
+                    !matches!(violation, InvariantViolation::UnsortedBook { .. })
+                });
+            }
+            violations
+        });
+
+        book
+    }
+
+    /// Matches the book with the config of its pool instead of the default.
+    pub fn with_matching_config(mut self, config: PoolMatchingConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn matching_config(&self) -> &PoolMatchingConfig {
+        &self.config
+    }
+
+    /// The same book without the AMM, for matching the orders only against
+    /// each other.
+    pub fn without_amm(&self) -> Self {
+        Self {
+            id:     self.id,
+            amm:    None,
+            bids:   self.bids.clone(),
+            asks:   self.asks.clone(),
+            config: self.config,
+            ladder: OnceLock::new()
+        }
+    }
+
+    /// The same book with only the orders `keep` returns true for.
+    pub fn retain_orders(&self, keep: impl Fn(&BookOrder) -> bool) -> Self {
+        Self {
+            id:     self.id,
+            amm:    self.amm.clone(),
+            bids:   self
+                .bids
+                .iter()
+                .filter(|order| keep(order))
+                .cloned()
+                .collect(),
+            asks:   self
+                .asks
+                .iter()
+                .filter(|order| keep(order))
+                .cloned()
+                .collect(),
+            config: self.config,
+            ladder: OnceLock::new()
+        }
     }
 
     pub fn id(&self) -> PoolId {
@@ -51,12 +139,98 @@ impl OrderBook {
     pub fn amm(&self) -> Option<&PoolSnapshot> {
         self.amm.as_ref()
     }
+
+    /// The prices of both sides, built on first use.
+    pub fn price_ladder(&self) -> &PriceLadder {
+        self.ladder.get_or_init(|| PriceLadder {
+            bids: self
+                .bids
+                .iter()
+                .map(|order| order.price_for_book_side(true))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .map(|order| order.price_for_book_side(false))
+                .collect()
+        })
+    }
+
+    /// Checks that every order is in the book once and on its side, and that
+    /// both sides go from the most to the least aggressive price, which is
+    /// the order the matcher walks them in.
+    pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+        let mut seen = HashSet::new();
+        let mut violations = self
+            .bids
+            .iter()
+            .map(|order| (order, true))
+            .chain(self.asks.iter().map(|order| (order, false)))
+            .filter_map(|(order, is_bid)| {
+                let hash = order.order_id.hash;
+                if !seen.insert(hash) {
+                    return Some(InvariantViolation::DuplicateOrder(hash))
+                }
+                (order.is_bid != is_bid).then_some(InvariantViolation::WrongSide(hash))
+            })
+            .collect::<Vec<_>>();
+
+        let unsorted_bids = self
+            .bids
+            .windows(2)
+            .find(|pair| pair[0].price_for_book_side(true) < pair[1].price_for_book_side(true));
+        let unsorted_asks = self
+            .asks
+            .windows(2)
+            .find(|pair| pair[0].price() > pair[1].price());
+        violations.extend(unsorted_bids.into_iter().chain(unsorted_asks).map(|pair| {
+            InvariantViolation::UnsortedBook { pool: self.id, hash: pair[1].order_id.hash }
+        }));
+
+        violations
+    }
+
+    /// Checks the outcomes of a solve against the book: only orders of the
+    /// book have an outcome, each at most one and none is filled over its
+    /// max quantity.
+    pub fn outcome_violations(&self, outcomes: &[OrderOutcome]) -> Vec<InvariantViolation> {
+        let orders = self
+            .bids
+            .iter()
+            .chain(&self.asks)
+            .map(|order| (order.order_id.hash, order))
+            .collect::<HashMap<_, _>>();
+
+        let mut seen = HashSet::new();
+        outcomes
+            .iter()
+            .filter_map(|outcome| {
+                let hash = outcome.id.hash;
+                let Some(order) = orders.get(&hash) else {
+                    return Some(InvariantViolation::UnknownOutcome(hash))
+                };
+                if !seen.insert(hash) {
+                    return Some(InvariantViolation::DuplicateOutcome(hash))
+                }
+
+                outcome
+                    .outcome
+                    .partial_q()
+                    .filter(|filled| *filled > order.max_q())
+                    .map(|_| InvariantViolation::Overfilled(hash))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use alloy::primitives::FixedBytes;
-    use angstrom_types::matching::{uniswap::LiqRange, SqrtPriceX96};
+    use alloy::primitives::{FixedBytes, Uint};
+    use angstrom_types::{
+        matching::{uniswap::LiqRange, Ray, SqrtPriceX96},
+        orders::OrderFillState
+    };
+    use testing_tools::type_generator::orders::UserOrderBuilder;
 
     use super::*;
 
@@ -72,4 +246,63 @@ mod test {
         .unwrap();
         OrderBook::new(FixedBytes::<32>::random(), Some(amm), bids, asks, None);
     }
+
+    fn ask(price: u128, amount: u128) -> BookOrder {
+        UserOrderBuilder::new()
+            .partial()
+            .ask()
+            .amount(amount)
+            .min_price(Ray::from(Uint::from(price)))
+            .with_storage()
+            .ask()
+            .build()
+    }
+
+    #[test]
+    fn detects_unsorted_and_duplicate_orders() {
+        let (low, high) = (ask(1_000, 10), ask(2_000, 10));
+        let sorted = OrderBook::new(
+            FixedBytes::random(),
+            None,
+            vec![],
+            vec![low.clone(), high.clone()],
+            None
+        );
+        assert!(sorted.invariant_violations().is_empty());
+
+        let book = OrderBook::new(
+            FixedBytes::random(),
+            None,
+            vec![],
+            vec![high.clone(), low.clone(), low.clone()],
+            None
+        );
+        assert_eq!(
+            book.invariant_violations(),
+            vec![
+                InvariantViolation::DuplicateOrder(low.order_id.hash),
+                InvariantViolation::UnsortedBook { pool: book.id(), hash: low.order_id.hash }
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_inconsistent_outcomes() {
+        let (order, other) = (ask(1_000, 10), ask(1_000, 20));
+        let book = OrderBook::new(FixedBytes::random(), None, vec![], vec![order.clone()], None);
+        let outcome = |id, outcome| OrderOutcome { id, outcome };
+
+        assert_eq!(
+            book.outcome_violations(&[
+                outcome(order.order_id, OrderFillState::PartialFill(order.max_q() + 1)),
+                outcome(order.order_id, OrderFillState::CompleteFill),
+                outcome(other.order_id, OrderFillState::Unfilled)
+            ]),
+            vec![
+                InvariantViolation::Overfilled(order.order_id.hash),
+                InvariantViolation::DuplicateOutcome(order.order_id.hash),
+                InvariantViolation::UnknownOutcome(other.order_id.hash)
+            ]
+        );
+    }
 }
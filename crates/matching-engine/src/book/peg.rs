@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use alloy_primitives::Address;
+use angstrom_types::{
+    matching::{uniswap::PoolSnapshot, Ray},
+    primitive::PoolId,
+    sol_bindings::{grouped_orders::GroupedVanillaOrder, RawPoolOrder}
+};
+
+use super::BookOrder;
+
+/// Mid price of the AMM that pegged orders are priced off.
+pub fn mid_price(amm: &PoolSnapshot) -> Ray {
+    Ray::from(amm.current_price())
+}
+
+/// Prices a pegged order for the given mid, see
+/// [`angstrom_types::orders::PricePeg`].
+///
+/// Only the copy of the order in the book is repriced, the order that is
+/// settled keeps the signed price, which the pegged one never is looser than.
+/// Without a mid, or with a peg that doesn't hold up, the order stays at its
+/// signed price.
+pub fn resolve_peg(mut order: BookOrder, mid: Option<Ray>) -> BookOrder {
+    let (Some(peg), Some(mid)) = (order.peg.as_ref(), mid) else { return order };
+    if !matches!(order.order, GroupedVanillaOrder::Standing(_)) {
+        return order
+    }
+    if let Err(error) = peg.validate_for(order.order_id.hash, order.from()) {
+        tracing::debug!(order_hash = ?order.order_id.hash, %error, "ignoring invalid peg");
+        return order
+    }
+
+    let price = peg.limit_price(mid, order.is_bid, order.limit_price());
+    order.order = order.order.with_limit_price(price);
+    order.priority_data.price = price;
+    order
+}
+
+/// Prices the pegged orders of every pool off the mid of its AMM.
+pub fn resolve_pegs(
+    limit: &[BookOrder],
+    pool_snapshots: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+) -> Vec<BookOrder> {
+    limit
+        .iter()
+        .map(|order| {
+            let mid = pool_snapshots
+                .get(&order.pool_id)
+                .map(|pool| mid_price(&pool.2));
+            resolve_peg(order.clone(), mid)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::SignerSync;
+    use alloy_primitives::{Uint, U256};
+    use angstrom_types::{orders::PricePeg, primitive::AngstromSigner};
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    fn pegged(signer: &AngstromSigner, is_bid: bool, offset_bps: i32) -> BookOrder {
+        let mut order = UserOrderBuilder::new()
+            .standing()
+            .partial()
+            .is_bid(is_bid)
+            .amount(10)
+            .min_price(Ray::from(Uint::from(1_u64)))
+            .signing_key(Some(signer.clone()))
+            .with_storage()
+            .is_bid(is_bid)
+            .build();
+        let order_hash = order.order_id.hash;
+        let hash = PricePeg::signing_payload(order_hash, offset_bps);
+        order.peg = Some(PricePeg {
+            order_hash,
+            offset_bps,
+            signature: signer.sign_hash_sync(&hash).unwrap()
+        });
+        order
+    }
+
+    #[test]
+    fn reprices_pegged_orders_off_the_mid() {
+        let signer = AngstromSigner::random();
+        let mid = Ray::from(U256::from(1_000_000));
+
+        let ask = resolve_peg(pegged(&signer, false, 100), Some(mid));
+        assert_eq!(ask.limit_price(), U256::from(1_010_000));
+        assert_eq!(ask.priority_data.price, U256::from(1_010_000));
+        assert_eq!(ask.price_for_book_side(false), Ray::from(U256::from(1_010_000)));
+
+        let bid = resolve_peg(pegged(&signer, true, 100), Some(mid));
+        assert!(bid.price_for_book_side(true) <= Ray::from(U256::from(990_000)));
+
+        // nothing to peg to
+        let order = pegged(&signer, false, 100);
+        assert_eq!(resolve_peg(order.clone(), None), order);
+    }
+
+    #[test]
+    fn keeps_the_signed_price_of_invalid_pegs() {
+        let signer = AngstromSigner::random();
+        let mid = Ray::from(U256::from(1_000_000));
+
+        let mut order = pegged(&signer, false, 100);
+        order.peg.as_mut().unwrap().offset_bps = -100;
+        assert_eq!(resolve_peg(order.clone(), Some(mid)), order);
+
+        let order = pegged(&AngstromSigner::random(), false, 100);
+        let mut other = pegged(&signer, false, 100);
+        other.peg = order.peg.clone();
+        assert_eq!(resolve_peg(other.clone(), Some(mid)), other);
+    }
+}
@@ -1,11 +1,108 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet}
+};
+
+use alloy_primitives::{B256, U256};
+use angstrom_types::{primitive::BLOCK_TIME, sol_bindings::RawPoolOrder};
+
 use super::BookOrder;
 
+/// Blocks ahead of its deadline a standing order is ranked ahead of equally
+/// priced orders, giving it a last chance to fill before it expires.
+pub const EXPIRY_PRIORITY_BLOCKS: u64 = 3;
+
+/// Whether the order is a standing order that expires within
+/// [`EXPIRY_PRIORITY_BLOCKS`] of `timestamp`, the one of the block the round
+/// builds on. Never the local time, or nodes would rank the books differently.
+pub fn expires_soon(order: &BookOrder, timestamp: u64) -> bool {
+    let horizon = U256::from(timestamp + EXPIRY_PRIORITY_BLOCKS * BLOCK_TIME.as_secs());
+    order.deadline().is_some_and(|deadline| deadline <= horizon)
+}
+
+/// How orders of equal priority are ranked: standing orders about to expire
+/// first, then the ones that were carried over for more rounds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TieBreak {
+    /// rounds each order was carried over for
+    pub carry_over: HashMap<B256, u64>,
+    /// standing orders that expire soon, see [`expires_soon`]
+    pub expiring:   HashSet<B256>
+}
+
+impl TieBreak {
+    /// Ranks `orders` with their carry-over and whether they expire soon after
+    /// `timestamp`.
+    pub fn at<'a>(
+        orders: impl IntoIterator<Item = &'a BookOrder>,
+        carry_over: &HashMap<B256, u64>,
+        timestamp: u64
+    ) -> Self {
+        let (mut carried, mut expiring) = (HashMap::new(), HashSet::new());
+        for order in orders {
+            let hash = order.order_id.hash;
+            if let Some(rounds) = carry_over.get(&hash) {
+                carried.insert(hash, *rounds);
+            }
+            if expires_soon(order, timestamp) {
+                expiring.insert(hash);
+            }
+        }
+
+        Self { carry_over: carried, expiring }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.carry_over.is_empty() && self.expiring.is_empty()
+    }
+
+    pub fn compare(&self, a: &BookOrder, b: &BookOrder) -> Ordering {
+        let expiring = |order: &BookOrder| self.expiring.contains(&order.order_id.hash);
+        let carried = |order: &BookOrder| {
+            self.carry_over
+                .get(&order.order_id.hash)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        expiring(b)
+            .cmp(&expiring(a))
+            .then_with(|| carried(b).cmp(&carried(a)))
+    }
+}
+
+/// How `a` ranks against `b` on a side of a book: by priority, the
+/// [`TieBreak`], the most aggressive price and lastly the order hash. No two
+/// orders rank equal, so every node comes to the same book however it got
+/// the orders.
+pub fn compare_orders(
+    tie_break: &TieBreak,
+    is_bid: bool,
+    a: &BookOrder,
+    b: &BookOrder
+) -> Ordering {
+    let by_price = if is_bid {
+        b.limit_price().cmp(&a.limit_price())
+    } else {
+        a.limit_price().cmp(&b.limit_price())
+    };
+
+    a.priority_data
+        .cmp(&b.priority_data)
+        .then_with(|| tie_break.compare(a, b))
+        .then(by_price)
+        .then_with(|| a.order_id.hash.cmp(&b.order_id.hash))
+}
+
 /// There are lots of different ways we can sort the orders we get in, so let's
 /// make this modular
 
 pub enum SortStrategy {
     Unsorted,
-    ByPriceByVolume
+    ByPriceByVolume,
+    /// Like [`SortStrategy::ByPriceByVolume`], with equally ranked orders in
+    /// the order of the [`TieBreak`]
+    ByPriceByVolumeWithTieBreak(TieBreak)
 }
 
 impl Default for SortStrategy {
@@ -16,20 +113,90 @@ impl Default for SortStrategy {
 
 impl SortStrategy {
     pub fn sort_bids(&self, bids: &mut [BookOrder]) {
-        if let Self::ByPriceByVolume = self {
-            // Sort by price and then by volume - highest price first, highest volume first
-            // for same price
-            // Because of price inversion, we're going to reverse the order of sorting for
-            // our bid prices
-            bids.sort_by(|a, b| a.priority_data.cmp(&b.priority_data));
-        }
+        // Sort by price and then by volume - highest price first, highest volume first
+        // for same price
+        // Because of price inversion, we're going to reverse the order of sorting for
+        // our bid prices
+        self.sort(bids, true);
     }
 
     pub fn sort_asks(&self, asks: &mut [BookOrder]) {
-        if let Self::ByPriceByVolume = self {
-            // Sort by price and then by volume - lowest price first, highest volume first
-            // for same price
-            asks.sort_by(|a, b| a.priority_data.cmp(&b.priority_data));
+        // Sort by price and then by volume - lowest price first, highest volume first
+        // for same price
+        self.sort(asks, false);
+    }
+
+    fn sort(&self, orders: &mut [BookOrder], is_bid: bool) {
+        match self {
+            Self::Unsorted => {}
+            Self::ByPriceByVolume => {
+                orders.sort_by(|a, b| compare_orders(&TieBreak::default(), is_bid, a, b))
+            }
+            Self::ByPriceByVolumeWithTieBreak(tie_break) => {
+                orders.sort_by(|a, b| compare_orders(tie_break, is_bid, a, b))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::aliases::U40;
+    use angstrom_types::sol_bindings::{
+        grouped_orders::{FlashVariants, GroupedVanillaOrder, StandingVariants},
+        rpc_orders::ExactStandingOrder
+    };
+
+    use super::*;
+
+    fn order(hash: u8) -> BookOrder {
+        let mut order = BookOrder::default();
+        order.order_id.hash = B256::repeat_byte(hash);
+        order
+    }
+
+    #[test]
+    fn carried_over_orders_go_first_on_ties() {
+        let mut orders = vec![order(1), order(2), order(3)];
+        let strategy = SortStrategy::ByPriceByVolumeWithTieBreak(TieBreak {
+            carry_over: HashMap::from([(B256::repeat_byte(2), 2), (B256::repeat_byte(3), 1)]),
+            ..Default::default()
+        });
+        strategy.sort_bids(&mut orders);
+
+        let hashes = orders
+            .iter()
+            .map(|order| order.order_id.hash[0])
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn expiring_standing_orders_go_first_on_ties() {
+        let standing = |hash: u8, deadline: u64| {
+            let mut order = order(hash);
+            order.order =
+                GroupedVanillaOrder::Standing(StandingVariants::Exact(ExactStandingOrder {
+                    deadline: U40::from(deadline),
+                    ..Default::default()
+                }));
+            order
+        };
+        let mut flash = order(3);
+        flash.order = GroupedVanillaOrder::KillOrFill(FlashVariants::Exact(Default::default()));
+
+        let timestamp = 1_000;
+        let soon = timestamp + EXPIRY_PRIORITY_BLOCKS * BLOCK_TIME.as_secs();
+        let mut orders = vec![standing(1, soon + 1), standing(2, soon), flash];
+        let tie_break =
+            TieBreak::at(&orders, &HashMap::from([(B256::repeat_byte(3), 5)]), timestamp);
+        assert_eq!(tie_break.expiring, HashSet::from([B256::repeat_byte(2)]));
+
+        SortStrategy::ByPriceByVolumeWithTieBreak(tie_break).sort_asks(&mut orders);
+        let hashes = orders
+            .iter()
+            .map(|order| order.order_id.hash[0])
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, vec![2, 3, 1]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex}
+};
+
+use alloy_primitives::{Address, BlockNumber, Keccak256, B256, U256};
+use angstrom_types::{
+    contract_payloads::angstrom::BundleGasDetails,
+    matching::uniswap::PoolSnapshot,
+    orders::PoolSolution,
+    primitive::PoolId,
+    sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
+};
+
+use crate::book::BookOrder;
+
+/// Amount of solved books that are kept around, a round rarely solves more
+/// than two distinct ones.
+const SOLUTION_CACHE_CAPACITY: usize = 4;
+
+pub type CachedSolution = (Vec<PoolSolution>, BundleGasDetails);
+
+/// Solutions of the books solved in the recent rounds, keyed by the
+/// fingerprint of everything that went into them.
+///
+/// The leader solves the book to build its proposal and then solves it again
+/// to verify that very proposal. Every other validator verifies the proposal
+/// against the book it solved for itself if the round made it that far. As
+/// long as the inputs are identical the matching is too, so the second solve
+/// can be skipped.
+#[derive(Debug, Clone, Default)]
+pub struct SolutionCache {
+    entries: Arc<Mutex<VecDeque<(B256, CachedSolution)>>>
+}
+
+impl SolutionCache {
+    /// Canonical hash of the inputs of a solve, `config` being the
+    /// [`MatchingEngineHandle::config_fingerprint`] of the matcher. Neither
+    /// the order the orders come in nor the iteration order of the maps
+    /// change it.
+    ///
+    /// [`MatchingEngineHandle::config_fingerprint`]: crate::MatchingEngineHandle::config_fingerprint
+    pub fn fingerprint(
+        block_number: BlockNumber,
+        config: B256,
+        limit: &[BookOrder],
+        searcher: &[OrderWithStorageData<TopOfBlockOrder>],
+        pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: &HashMap<B256, u64>
+    ) -> B256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(block_number.to_be_bytes());
+        hasher.update(config);
+
+        let mut limit = limit.iter().collect::<Vec<_>>();
+        limit.sort_unstable_by_key(|order| order.order_id.hash);
+        hasher.update((limit.len() as u64).to_be_bytes());
+        limit
+            .into_iter()
+            .for_each(|order| hash_order(&mut hasher, order));
+
+        let mut searcher = searcher.iter().collect::<Vec<_>>();
+        searcher.sort_unstable_by_key(|order| order.order_id.hash);
+        hasher.update((searcher.len() as u64).to_be_bytes());
+        searcher
+            .into_iter()
+            .for_each(|order| hash_order(&mut hasher, order));
+
+        let pools = pools.iter().collect::<BTreeMap<_, _>>();
+        hasher.update((pools.len() as u64).to_be_bytes());
+        for (pool_id, (token0, token1, snapshot, store_index)) in pools {
+            hasher.update(pool_id);
+            hasher.update(token0);
+            hasher.update(token1);
+            hasher.update(store_index.to_be_bytes());
+            let price = snapshot.current_price();
+            hasher.update(U256::from(price.as_sqrtpricex96()).to_be_bytes::<32>());
+            hasher.update(price.tick().to_be_bytes());
+            hasher.update((snapshot.ranges().len() as u64).to_be_bytes());
+            for range in snapshot.ranges() {
+                hasher.update(range.lower_tick().to_be_bytes());
+                hasher.update(range.upper_tick().to_be_bytes());
+                hasher.update(range.liquidity().to_be_bytes());
+            }
+        }
+
+        let carry_over = carry_over.iter().collect::<BTreeMap<_, _>>();
+        hasher.update((carry_over.len() as u64).to_be_bytes());
+        for (hash, rounds) in carry_over {
+            hasher.update(hash);
+            hasher.update(rounds.to_be_bytes());
+        }
+
+        hasher.finalize()
+    }
+
+    pub fn get(&self, fingerprint: &B256) -> Option<CachedSolution> {
+        self.entries
+            .lock()
+            .expect("poisoned")
+            .iter()
+            .find(|(key, _)| key == fingerprint)
+            .map(|(_, solution)| solution.clone())
+    }
+
+    pub fn insert(&self, fingerprint: B256, solution: CachedSolution) {
+        let mut entries = self.entries.lock().expect("poisoned");
+        if entries.iter().any(|(key, _)| *key == fingerprint) {
+            return
+        }
+        if entries.len() == SOLUTION_CACHE_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back((fingerprint, solution));
+    }
+}
+
+/// The order hash commits to everything the user signed, the rest is what
+/// validation attached to the order.
+fn hash_order<O>(hasher: &mut Keccak256, order: &OrderWithStorageData<O>) {
+    hasher.update(order.order_id.hash);
+    hasher.update(order.pool_id);
+    hasher.update(order.priority_data.price.to_be_bytes::<32>());
+    hasher.update(order.priority_data.volume.to_be_bytes());
+    hasher.update(order.priority_data.gas.to_be_bytes::<32>());
+    hasher.update(order.priority_data.gas_units.to_be_bytes());
+    hasher.update([order.is_bid as u8, order.is_currently_valid as u8, order.is_valid as u8]);
+    hasher.update(order.valid_block.to_be_bytes());
+    hasher.update(order.tob_reward.to_be_bytes::<32>());
+    match &order.peg {
+        Some(peg) => {
+            hasher.update([1]);
+            hasher.update(peg.offset_bps.to_be_bytes());
+        }
+        None => hasher.update([0])
+    }
+    hasher.update((order.invalidates.len() as u64).to_be_bytes());
+    order
+        .invalidates
+        .iter()
+        .for_each(|hash| hasher.update(hash));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(hash: B256) -> BookOrder {
+        let mut order = BookOrder::default();
+        order.order_id.hash = hash;
+        order
+    }
+
+    #[test]
+    fn fingerprint_ignores_input_order() {
+        let (a, b) = (order(B256::random()), order(B256::random()));
+        let carry_over = HashMap::from([(a.order_id.hash, 1), (b.order_id.hash, 2)]);
+        let pools = HashMap::new();
+
+        let config = B256::ZERO;
+
+        let forward = SolutionCache::fingerprint(
+            1,
+            config,
+            &[a.clone(), b.clone()],
+            &[],
+            &pools,
+            &carry_over
+        );
+        let backward = SolutionCache::fingerprint(
+            1,
+            config,
+            &[b.clone(), a.clone()],
+            &[],
+            &pools,
+            &carry_over
+        );
+        assert_eq!(forward, backward);
+
+        assert_ne!(
+            forward,
+            SolutionCache::fingerprint(
+                2,
+                config,
+                &[a.clone(), b.clone()],
+                &[],
+                &pools,
+                &carry_over
+            )
+        );
+        assert_ne!(
+            forward,
+            SolutionCache::fingerprint(1, config, &[a.clone()], &[], &pools, &carry_over)
+        );
+
+        // same orders, other matcher config
+        assert_ne!(
+            forward,
+            SolutionCache::fingerprint(
+                1,
+                B256::with_last_byte(1),
+                &[a.clone(), b.clone()],
+                &[],
+                &pools,
+                &carry_over
+            )
+        );
+
+        // same hash, other validated content
+        let mut revalidated = a.clone();
+        revalidated.priority_data.volume += 1;
+        assert_ne!(
+            forward,
+            SolutionCache::fingerprint(1, config, &[revalidated, b], &[], &pools, &carry_over)
+        );
+    }
+
+    #[test]
+    fn evicts_oldest_solution() {
+        let cache = SolutionCache::default();
+        let keys = (0..=SOLUTION_CACHE_CAPACITY)
+            .map(|_| B256::random())
+            .collect::<Vec<_>>();
+        for key in &keys {
+            cache.insert(*key, (vec![], BundleGasDetails::default()));
+        }
+
+        assert!(cache.get(&keys[0]).is_none());
+        assert!(keys[1..].iter().all(|key| cache.get(key).is_some()));
+    }
+}
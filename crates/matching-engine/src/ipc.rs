@@ -0,0 +1,595 @@
+//! Bridge to a matching engine that runs in its own process.
+//!
+//! Every solve is a single request over a fresh connection to a unix socket.
+//! Both directions are one frame, a big endian `u32` length followed by that
+//! many bytes of json. The solver only matches. The circuit breaker and the
+//! gas estimation still run in process on whatever it returns. If it fails,
+//! times out or returns solutions that don't hold up, the solve fails. The
+//! other nodes verify the proposal with their own solver, so solving in
+//! process instead would only come to a proposal they don't reproduce.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration
+};
+
+use alloy_primitives::{Address, B256};
+use angstrom_types::{
+    contract_payloads::angstrom::BundleGasDetails,
+    matching::{uniswap::PoolSnapshot, Ray},
+    orders::{NetAmmOrder, OrderOutcome, PoolSolution},
+    primitive::PoolId,
+    sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
+};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::UnixStream
+};
+
+use crate::{
+    book::BookOrder, manager::MatcherHandle, MatchingEngineHandle, PartialSolutions, SolveFuture
+};
+
+/// Version of the request and response layout, bumped on every breaking change.
+pub const IPC_PROTOCOL_VERSION: u32 = 2;
+/// Frames above this size are rejected without reading them.
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+pub const DEFAULT_IPC_SOLVE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// A pool as the solver sees it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolInput {
+    pub id:          PoolId,
+    pub token0:      Address,
+    pub token1:      Address,
+    pub snapshot:    PoolSnapshot,
+    pub store_index: u16
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveRequest {
+    pub version:    u32,
+    pub limit:      Vec<BookOrder>,
+    pub searcher:   Vec<OrderWithStorageData<TopOfBlockOrder>>,
+    pub pools:      Vec<PoolInput>,
+    /// rounds each order was carried over for
+    pub carry_over: HashMap<B256, u64>,
+    /// timestamp of the block the books are ranked at
+    pub timestamp:  u64
+}
+
+impl SolveRequest {
+    pub fn new(
+        limit: Vec<BookOrder>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
+    ) -> Self {
+        let mut pools = pools
+            .into_iter()
+            .map(|(id, (token0, token1, snapshot, store_index))| PoolInput {
+                id,
+                token0,
+                token1,
+                snapshot,
+                store_index
+            })
+            .collect::<Vec<_>>();
+        pools.sort_unstable_by_key(|pool| pool.id);
+
+        Self { version: IPC_PROTOCOL_VERSION, limit, searcher, pools, carry_over, timestamp }
+    }
+
+    /// The arguments of [`MatchingEngineHandle::solve_pools`] the request was
+    /// built from.
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self
+    ) -> (
+        Vec<BookOrder>,
+        Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        HashMap<B256, u64>,
+        u64
+    ) {
+        let pools = self
+            .pools
+            .into_iter()
+            .map(|pool| (pool.id, (pool.token0, pool.token1, pool.snapshot, pool.store_index)))
+            .collect();
+
+        (self.limit, self.searcher, pools, self.carry_over, self.timestamp)
+    }
+
+    /// Checks that the solutions only fill orders of this request, in the
+    /// pools they were sent for, and solve every pool at most once. Within a
+    /// pool the filled orders have to accept the clearing price, fill within
+    /// their bounds and balance with the AMM, see [`Self::check_fills`].
+    pub fn check_solutions(&self, solutions: &[PoolSolution]) -> eyre::Result<()> {
+        let pools = self
+            .pools
+            .iter()
+            .map(|pool| pool.id)
+            .collect::<HashSet<_>>();
+        let limit = self
+            .limit
+            .iter()
+            .map(|order| (order.order_id.hash, order))
+            .collect::<HashMap<_, _>>();
+        let searcher = self
+            .searcher
+            .iter()
+            .map(|order| (order.order_id.hash, order.pool_id))
+            .collect::<HashMap<_, _>>();
+
+        let mut solved = HashSet::new();
+        for solution in solutions {
+            if !pools.contains(&solution.id) {
+                eyre::bail!("solution for unknown pool {:?}", solution.id)
+            }
+            if !solved.insert(solution.id) {
+                eyre::bail!("pool {:?} was solved twice", solution.id)
+            }
+            if let Some(tob) = &solution.searcher {
+                if searcher.get(&tob.order_id.hash) != Some(&solution.id) {
+                    eyre::bail!("unknown searcher order {:?}", tob.order_id.hash)
+                }
+            }
+            let fills = solution
+                .limit
+                .iter()
+                .map(|outcome| match limit.get(&outcome.id.hash) {
+                    Some(order) if order.pool_id == solution.id => Ok((*order, outcome)),
+                    _ => Err(eyre::eyre!("unknown limit order {:?}", outcome.id.hash))
+                })
+                .collect::<eyre::Result<Vec<_>>>()?;
+            Self::check_fills(solution, &fills)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the fills of a pool. Every filled order has to accept the
+    /// clearing price and be filled within its bounds. The contract can't pay
+    /// out more of either token than the orders and the AMM put in, up to a
+    /// unit of rounding per fill. The order of a ring leg is paid by the ring
+    /// and left out of the balance.
+    fn check_fills(
+        solution: &PoolSolution,
+        fills: &[(&BookOrder, &OrderOutcome)]
+    ) -> eyre::Result<()> {
+        let ucp = solution.ucp;
+        let filled = fills
+            .iter()
+            .filter(|(_, outcome)| outcome.is_filled())
+            .collect::<Vec<_>>();
+        if filled.is_empty() {
+            return Ok(())
+        }
+        if ucp.is_zero() {
+            eyre::bail!("pool {:?} fills orders without a clearing price", solution.id)
+        }
+
+        let ring_order = solution.ring.as_ref().map(|leg| leg.order);
+        // (t0, t1) the contract takes in and pays out
+        let (mut taken, mut paid) = ((0u128, 0u128), (0u128, 0u128));
+        let add = |sum: &mut (u128, u128), t0: u128, t1: u128| -> eyre::Result<()> {
+            sum.0 = sum
+                .0
+                .checked_add(t0)
+                .ok_or_else(|| eyre::eyre!("t0 overflows"))?;
+            sum.1 = sum
+                .1
+                .checked_add(t1)
+                .ok_or_else(|| eyre::eyre!("t1 overflows"))?;
+            Ok(())
+        };
+        for (order, outcome) in &filled {
+            let hash = outcome.id.hash;
+            let limit_price = order.price_for_book_side(order.is_bid);
+            let accepts = if order.is_bid { ucp <= limit_price } else { ucp >= limit_price };
+            if !accepts {
+                eyre::bail!("order {hash:?} is filled past its limit price")
+            }
+            let quantity = outcome.fill_amount(order.max_q());
+            if let Some(partial) = outcome.outcome.partial_q() {
+                if !order.is_partial() || partial < order.min_q() || partial > order.max_q() {
+                    eyre::bail!("order {hash:?} is filled outside of its bounds")
+                }
+            }
+            if ring_order == Some(hash) {
+                continue
+            }
+
+            // bids that pay exactly and asks that receive exactly are in T1
+            let (t0, t1) = if order.is_bid == order.exact_in() {
+                (ucp.inverse_quantity(quantity, false), quantity)
+            } else {
+                (quantity, ucp.quantity(quantity, false))
+            };
+            if order.is_bid {
+                add(&mut taken, 0, t1)?;
+                add(&mut paid, t0, 0)?;
+            } else {
+                add(&mut taken, t0, 0)?;
+                add(&mut paid, 0, t1)?;
+            }
+        }
+        match solution.amm_quantity {
+            Some(NetAmmOrder::Buy(t0, t1)) => {
+                add(&mut taken, t0, 0)?;
+                add(&mut paid, 0, t1)?;
+            }
+            Some(NetAmmOrder::Sell(t0, t1)) => {
+                add(&mut taken, 0, t1)?;
+                add(&mut paid, t0, 0)?;
+            }
+            None => ()
+        }
+
+        let rounding = filled.len() as u128;
+        if paid.0 > taken.0.saturating_add(rounding) || paid.1 > taken.1.saturating_add(rounding) {
+            eyre::bail!("pool {:?} pays out more than it takes in", solution.id)
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SolveResponse {
+    Solved { solutions: Vec<PoolSolution> },
+    Failed { reason: String }
+}
+
+/// Client of an out of process solver.
+#[derive(Debug, Clone)]
+pub struct IpcSolver {
+    pub socket:  PathBuf,
+    /// how long a solve can take before it fails
+    pub timeout: Duration
+}
+
+impl IpcSolver {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket, timeout: DEFAULT_IPC_SOLVE_TIMEOUT }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends the request and returns the checked solutions.
+    pub async fn solve(&self, request: &SolveRequest) -> eyre::Result<Vec<PoolSolution>> {
+        let exchange = async {
+            let mut stream = UnixStream::connect(&self.socket).await?;
+            write_frame(&mut stream, request).await?;
+            read_frame::<_, SolveResponse>(&mut stream).await
+        };
+        let response = tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| eyre::eyre!("solver didn't respond within {:?}", self.timeout))??;
+
+        match response {
+            SolveResponse::Solved { solutions } => {
+                request.check_solutions(&solutions)?;
+                Ok(solutions)
+            }
+            SolveResponse::Failed { reason } => Err(eyre::eyre!("solver failed: {reason}"))
+        }
+    }
+}
+
+pub async fn write_frame<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    value: &T
+) -> eyre::Result<()> {
+    let bytes = serde_json::to_vec(value)?;
+    if bytes.len() > MAX_FRAME_LEN {
+        eyre::bail!("frame of {} bytes is above the limit", bytes.len())
+    }
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+pub async fn read_frame<R: AsyncRead + Unpin, T: for<'de> Deserialize<'de>>(
+    reader: &mut R
+) -> eyre::Result<T> {
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_FRAME_LEN {
+        eyre::bail!("frame of {len} bytes is above the limit")
+    }
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes).await?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Matching engine handle that solves with an out of process solver when one
+/// is configured and with the in process matching engine otherwise.
+#[derive(Debug, Clone)]
+pub struct IpcMatcherHandle {
+    local:  MatcherHandle,
+    solver: Option<IpcSolver>
+}
+
+impl IpcMatcherHandle {
+    pub fn new(local: MatcherHandle, solver: Option<IpcSolver>) -> Self {
+        Self { local, solver }
+    }
+}
+
+impl MatchingEngineHandle for IpcMatcherHandle {
+    fn solve_pools(
+        &self,
+        limit: Vec<BookOrder>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
+    ) -> BoxFuture<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>> {
+        let Some(solver) = self.solver.clone() else {
+            return self
+                .local
+                .solve_pools(limit, searcher, pools, carry_over, timestamp)
+        };
+        let local = self.local.clone();
+
+        Box::pin(async move {
+            let request = SolveRequest::new(limit, searcher, pools, carry_over, timestamp);
+            let solutions = solver.solve(&request).await.inspect_err(
+                |e| tracing::error!(%e, socket = %solver.socket.display(), "external solver failed")
+            )?;
+            let (limit, _, pools, ..) = request.into_parts();
+
+            local.finalize_solutions(limit, solutions, pools).await
+        })
+    }
+
+    /// The external solver returns all pools at once, so only solves in
+    /// process stream their solutions.
+    fn solve_pools_streaming(
+        &self,
+        limit: Vec<BookOrder>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
+    ) -> (PartialSolutions, SolveFuture) {
+        if self.solver.is_none() {
+            return self
+                .local
+                .solve_pools_streaming(limit, searcher, pools, carry_over, timestamp)
+        }
+        let this = self.clone();
+        let solved = Box::pin(async move {
+            this.solve_pools(limit, searcher, pools, carry_over, timestamp)
+                .await
+        });
+
+        (Box::pin(futures::stream::empty()), solved)
+    }
+
+    fn config_fingerprint(&self) -> B256 {
+        self.local.config_fingerprint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Uint;
+    use angstrom_types::orders::OrderFillState;
+    use futures::StreamExt;
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+    use tokio::net::UnixListener;
+
+    use super::*;
+    use crate::manager::{MatcherCommand, MatcherHandle};
+
+    fn socket_path() -> PathBuf {
+        std::env::temp_dir().join(format!("angstrom-solver-{}.sock", B256::random()))
+    }
+
+    fn ray(price: u128) -> Ray {
+        Ray::from(Uint::from(price))
+    }
+
+    /// An exact order for 10 T0 limited at `price`.
+    fn order(pool_id: PoolId, is_bid: bool, price: u128) -> BookOrder {
+        let builder = UserOrderBuilder::new()
+            .exact()
+            .is_bid(is_bid)
+            .amount(10)
+            .exact_in(!is_bid);
+        let builder =
+            if is_bid { builder.bid_min_price(ray(price)) } else { builder.min_price(ray(price)) };
+        let mut order = builder.with_storage().is_bid(is_bid).build();
+        order.pool_id = pool_id;
+        order.order_id.pool_id = pool_id;
+        order
+    }
+
+    /// A bid at 2 and an ask at 1 that cross.
+    fn request() -> SolveRequest {
+        let pool_id = PoolId::random();
+        let orders =
+            vec![order(pool_id, true, 2 * 10u128.pow(27)), order(pool_id, false, 10u128.pow(27))];
+        let pools = HashMap::from([(
+            pool_id,
+            (Address::random(), Address::random(), PoolSnapshot::default(), 0)
+        )]);
+
+        SolveRequest::new(orders, vec![], pools, HashMap::new(), 1_700_000_000)
+    }
+
+    /// Fills both orders of the request at 1.5.
+    fn solution(request: &SolveRequest) -> PoolSolution {
+        PoolSolution {
+            id: request.pools[0].id,
+            ucp: ray(15 * 10u128.pow(26)),
+            limit: request
+                .limit
+                .iter()
+                .map(|order| OrderOutcome {
+                    id:      order.order_id,
+                    outcome: OrderFillState::CompleteFill
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Answers a single request with whatever `respond` makes of it.
+    fn serve_once(
+        respond: impl FnOnce(SolveRequest) -> Option<SolveResponse> + Send + 'static
+    ) -> PathBuf {
+        let path = socket_path();
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let request = read_frame(&mut stream).await.unwrap();
+            match respond(request) {
+                Some(response) => write_frame(&mut stream, &response).await.unwrap(),
+                // hold the connection open without ever answering
+                None => std::future::pending().await
+            }
+        });
+
+        path
+    }
+
+    #[test]
+    fn request_round_trips_through_json() {
+        let request = request();
+        let bytes = serde_json::to_vec(&request).unwrap();
+        assert_eq!(serde_json::from_slice::<SolveRequest>(&bytes).unwrap(), request);
+
+        let (limit, searcher, pools, carry_over, timestamp) = request.clone().into_parts();
+        assert_eq!(SolveRequest::new(limit, searcher, pools, carry_over, timestamp), request);
+    }
+
+    #[tokio::test]
+    async fn solves_over_the_socket() {
+        let request = request();
+        let expected = solution(&request);
+        let reply = expected.clone();
+        let path = serve_once(move |received| {
+            assert_eq!(received.version, IPC_PROTOCOL_VERSION);
+            Some(SolveResponse::Solved { solutions: vec![reply] })
+        });
+
+        let solutions = IpcSolver::new(path.clone()).solve(&request).await.unwrap();
+        assert_eq!(solutions, vec![expected]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn times_out_on_a_stuck_solver() {
+        let path = serve_once(|_| None);
+        let solver = IpcSolver::new(path.clone()).with_timeout(Duration::from_millis(50));
+
+        assert!(solver.solve(&request()).await.is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_solutions_outside_the_request() {
+        let request = request();
+        let valid = solution(&request);
+        assert!(request.check_solutions(&[valid.clone()]).is_ok());
+        assert!(request
+            .check_solutions(&[valid.clone(), valid.clone()])
+            .is_err());
+
+        let unknown_pool = PoolSolution { id: PoolId::random(), ..valid.clone() };
+        assert!(request.check_solutions(&[unknown_pool]).is_err());
+
+        let mut unknown_order = valid;
+        unknown_order.limit[0].id.hash = B256::random();
+        assert!(request.check_solutions(&[unknown_order]).is_err());
+    }
+
+    #[test]
+    fn rejects_fills_that_dont_clear() {
+        let request = request();
+        let valid = solution(&request);
+
+        // the bid is limited at 2
+        let past_the_limit = PoolSolution { ucp: ray(3 * 10u128.pow(27)), ..valid.clone() };
+        assert!(request.check_solutions(&[past_the_limit]).is_err());
+
+        let unpriced = PoolSolution { ucp: Ray::default(), ..valid.clone() };
+        assert!(request.check_solutions(&[unpriced]).is_err());
+
+        // the ask is paid in T1 that no bid pays in
+        let mut one_sided = valid.clone();
+        one_sided.limit[0].outcome = OrderFillState::Unfilled;
+        assert!(request.check_solutions(&[one_sided.clone()]).is_err());
+
+        // unless the AMM pays it
+        one_sided.amm_quantity = Some(NetAmmOrder::Sell(10, 15));
+        assert!(request.check_solutions(&[one_sided]).is_ok());
+
+        let mut partial_exact = valid;
+        partial_exact.limit[0].outcome = OrderFillState::PartialFill(5);
+        assert!(request.check_solutions(&[partial_exact]).is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_the_solve_instead_of_solving_in_process() {
+        let path = serve_once(|_| None);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let handle = IpcMatcherHandle::new(
+            MatcherHandle::detached(tx),
+            Some(IpcSolver::new(path.clone()).with_timeout(Duration::from_millis(50)))
+        );
+
+        let (limit, searcher, pools, carry_over, timestamp) = request().into_parts();
+        let solved = handle
+            .solve_pools(limit, searcher, pools, carry_over, timestamp)
+            .await;
+        assert!(solved.is_err());
+        // nothing was sent to the in process matching engine
+        assert!(rx.try_recv().is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn streams_only_the_solves_in_process() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let handle = IpcMatcherHandle::new(MatcherHandle::detached(tx), None);
+
+        let (limit, searcher, pools, carry_over, timestamp) = request().into_parts();
+        let (_partial, solving) =
+            handle.solve_pools_streaming(limit, searcher, pools, carry_over, timestamp);
+        let solving = tokio::spawn(solving);
+        let Some(MatcherCommand::BuildProposal(.., partial, _)) = rx.recv().await else {
+            panic!("the solve went to the in process matching engine")
+        };
+        assert!(partial.is_some());
+        solving.abort();
+
+        // the external solver returns every pool at once, there's nothing to stream
+        let path = serve_once(|_| None);
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let handle = IpcMatcherHandle::new(
+            MatcherHandle::detached(tx),
+            Some(IpcSolver::new(path.clone()).with_timeout(Duration::from_millis(50)))
+        );
+        let (limit, searcher, pools, carry_over, timestamp) = request().into_parts();
+        let (mut partial, solving) =
+            handle.solve_pools_streaming(limit, searcher, pools, carry_over, timestamp);
+        assert!(solving.await.is_err());
+        assert!(partial.next().await.is_none());
+        let _ = std::fs::remove_file(path);
+    }
+}
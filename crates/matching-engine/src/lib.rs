@@ -1,56 +1,272 @@
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc
+    sync::Arc,
+    time::Duration
 };
 
 use alloy::providers::Provider;
-use alloy_primitives::{Address, BlockNumber};
+use alloy_primitives::{Address, BlockNumber, B256};
 use angstrom_types::{
     block_sync::BlockSyncConsumer,
     contract_payloads::angstrom::BundleGasDetails,
     matching::uniswap::PoolSnapshot,
-    orders::PoolSolution,
+    orders::{ClearingReport, PoolSolution},
     primitive::{PoolId, UniswapPoolRegistry},
     sol_bindings::{
         grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder, RawPoolOrder
     }
 };
-use book::{BookOrder, OrderBook};
+use book::{
+    peg,
+    sort::{SortStrategy, TieBreak},
+    BookOrder, OrderBook
+};
+use futures::{stream::BoxStream, FutureExt, StreamExt};
 use futures_util::future::BoxFuture;
 use reth_provider::CanonStateNotifications;
+use strategy::{MatchingStrategy, SimpleCheckpointStrategy};
+use thiserror::Error;
 use uniswap_v4::uniswap::{
-    pool::EnhancedUniswapPool, pool_data_loader::DataLoader, pool_manager::UniswapPoolManager,
+    pool::{EnhancedUniswapPool, PoolError},
+    pool_data_loader::DataLoader,
+    pool_manager::UniswapPoolManager,
     pool_providers::canonical_state_adapter::CanonicalStateAdapter
 };
 
 pub mod book;
+pub mod cache;
+pub mod ipc;
 pub mod manager;
 pub mod matcher;
 pub mod simulation;
 pub mod strategy;
 
-pub use manager::MatchingManager;
+pub use cache::SolutionCache;
+pub use ipc::{IpcMatcherHandle, IpcSolver};
+pub use manager::{MatcherOptions, MatchingManager};
 
 pub trait MatchingEngineHandle: Send + Sync + Clone + Unpin + 'static {
+    /// Solves the books of the pools. The books are ranked at `timestamp`,
+    /// the one of the block the round builds on, so every node ranks them the
+    /// same.
     fn solve_pools(
         &self,
         limit: Vec<BookOrder>,
         searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
-        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
     ) -> BoxFuture<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>>;
+
+    /// Solves like [`Self::solve_pools`] and streams the solution of every
+    /// pool as soon as its book is solved, ahead of the full output.
+    ///
+    /// The streamed solutions are the ones of the books alone, only the
+    /// solutions of the output went through the ring trades and the circuit
+    /// breaker. Handles that can't stream return an empty stream.
+    fn solve_pools_streaming(
+        &self,
+        limit: Vec<BookOrder>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
+    ) -> (PartialSolutions, SolveFuture) {
+        let this = self.clone();
+        let solved = async move {
+            this.solve_pools(limit, searcher, pools, carry_over, timestamp)
+                .await
+        }
+        .boxed();
+
+        (futures::stream::empty().boxed(), solved)
+    }
+
+    /// Hash of the configuration the books are matched with, solutions are
+    /// only reused while it stays the same. Handles without a configuration
+    /// of their own return zero.
+    fn config_fingerprint(&self) -> B256 {
+        B256::ZERO
+    }
 }
 
+/// Solutions of the pools as their books are solved, see
+/// [`MatchingEngineHandle::solve_pools_streaming`].
+pub type PartialSolutions = BoxStream<'static, PoolSolution>;
+
+pub type SolveFuture = BoxFuture<'static, eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>>;
+
 pub fn build_book(id: PoolId, amm: Option<PoolSnapshot>, orders: HashSet<BookOrder>) -> OrderBook {
-    let (mut bids, mut asks): (Vec<BookOrder>, Vec<BookOrder>) =
-        orders.into_iter().partition(|o| o.is_bid);
+    build_book_with_carry_over(id, amm, orders, &HashMap::new())
+}
+
+/// Builds the book with orders that were carried over from previous rounds
+/// ahead of equally ranked ones. `carry_over` holds the amount of rounds each
+/// order was carried over for.
+pub fn build_book_with_carry_over(
+    id: PoolId,
+    amm: Option<PoolSnapshot>,
+    orders: HashSet<BookOrder>,
+    carry_over: &HashMap<B256, u64>
+) -> OrderBook {
+    let carried = orders
+        .iter()
+        .filter_map(|o| Some((o.order_id.hash, *carry_over.get(&o.order_id.hash)?)))
+        .collect::<HashMap<_, _>>();
+    build_book_with_tie_break(
+        id,
+        amm,
+        orders,
+        TieBreak { carry_over: carried, ..Default::default() }
+    )
+}
+
+/// Builds the book like [`build_book_with_carry_over`], with the standing
+/// orders that expire within [`book::sort::EXPIRY_PRIORITY_BLOCKS`] of
+/// `timestamp` ahead of equally priced ones.
+pub fn build_book_at(
+    id: PoolId,
+    amm: Option<PoolSnapshot>,
+    orders: HashSet<BookOrder>,
+    carry_over: &HashMap<B256, u64>,
+    timestamp: u64
+) -> OrderBook {
+    let tie_break = TieBreak::at(&orders, carry_over, timestamp);
+    build_book_with_tie_break(id, amm, orders, tie_break)
+}
+
+fn build_book_with_tie_break(
+    id: PoolId,
+    amm: Option<PoolSnapshot>,
+    orders: HashSet<BookOrder>,
+    tie_break: TieBreak
+) -> OrderBook {
+    let mid = amm.as_ref().map(peg::mid_price);
+    let (bids, asks): (Vec<BookOrder>, Vec<BookOrder>) = orders
+        .into_iter()
+        .map(|order| peg::resolve_peg(order, mid))
+        .partition(|o| o.is_bid);
+
+    let strategy = if tie_break.is_empty() {
+        SortStrategy::ByPriceByVolume
+    } else {
+        SortStrategy::ByPriceByVolumeWithTieBreak(tie_break)
+    };
+    OrderBook::new(id, amm, bids, asks, Some(strategy))
+}
 
-    // assert bids decreasing and asks increasing
-    bids.sort_by_key(|b| std::cmp::Reverse(b.limit_price()));
-    asks.sort_by_key(|a| a.limit_price());
+/// Reports how every pool cleared in the proposed `solutions`, see
+/// [`ClearingReport`]. The books are matched again, ranked like they were at
+/// `timestamp`, only for the derivation of the clearing prices, which is left
+/// out of the report of a solution the matching doesn't come to.
+pub fn clearing_reports(
+    block_number: BlockNumber,
+    limit: Vec<BookOrder>,
+    solutions: &[PoolSolution],
+    pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+    carry_over: &HashMap<B256, u64>,
+    timestamp: u64
+) -> Vec<ClearingReport> {
+    let mut books = limit
+        .into_iter()
+        .fold(HashMap::<_, HashSet<_>>::new(), |mut acc, order| {
+            acc.entry(order.pool_id).or_default().insert(order);
+            acc
+        });
 
-    OrderBook::new(id, amm, bids, asks, Some(book::sort::SortStrategy::ByPriceByVolume))
+    solutions
+        .iter()
+        .map(|solution| {
+            let orders = books.remove(&solution.id).unwrap_or_default();
+            let amm = pools.get(&solution.id).map(|pool| pool.2.clone());
+            let book = build_book_at(solution.id, amm, orders, carry_over, timestamp);
+            let derivation = SimpleCheckpointStrategy::run(&book);
+
+            matcher::solution_clearing_report(&book, solution, derivation.as_ref(), block_number)
+        })
+        .collect()
+}
+
+/// Times loading a pool is attempted at startup before it's left out.
+pub const POOL_INIT_ATTEMPTS: u32 = 5;
+/// Wait before the first retry of loading a pool, doubled on every retry.
+pub const POOL_INIT_BACKOFF: Duration = Duration::from_millis(500);
+
+const INITIAL_TICKS_PER_SIDE: u16 = 200;
+
+#[derive(Debug, Error)]
+pub enum PoolInitError {
+    #[error("pool {0:?} is missing from the registry")]
+    NotRegistered(PoolId),
+    #[error("failed to load pool {pool_id:?} after {attempts} attempts: {source}")]
+    Load {
+        pool_id:  PoolId,
+        attempts: u32,
+        #[source]
+        source:   PoolError
+    }
+}
+
+impl PoolInitError {
+    pub fn pool_id(&self) -> PoolId {
+        match self {
+            Self::NotRegistered(pool_id) | Self::Load { pool_id, .. } => *pool_id
+        }
+    }
 }
 
+/// The pool of the registry, yet to be loaded.
+pub fn uniswap_pool(
+    pool_id: PoolId,
+    uniswap_pool_registry: &UniswapPoolRegistry,
+    pool_manager_address: Address
+) -> Result<EnhancedUniswapPool<DataLoader<PoolId>, PoolId>, PoolInitError> {
+    let internal = *uniswap_pool_registry
+        .conversion_map
+        .get(&pool_id)
+        .ok_or(PoolInitError::NotRegistered(pool_id))?;
+
+    Ok(EnhancedUniswapPool::new(
+        DataLoader::new_with_registry(
+            internal,
+            uniswap_pool_registry.clone(),
+            pool_manager_address
+        ),
+        INITIAL_TICKS_PER_SIDE
+    ))
+}
+
+/// Loads the pool at `current_block`, retrying with backoff on failure.
+async fn initialize_pool(
+    pool_id: PoolId,
+    uniswap_pool_registry: &UniswapPoolRegistry,
+    current_block: BlockNumber,
+    pool_manager_address: Address,
+    provider: Arc<impl Provider + 'static>
+) -> Result<EnhancedUniswapPool<DataLoader<PoolId>, PoolId>, PoolInitError> {
+    let mut attempt = 1;
+    loop {
+        let mut pool = uniswap_pool(pool_id, uniswap_pool_registry, pool_manager_address)?;
+        match pool.initialize(Some(current_block), provider.clone()).await {
+            Ok(()) => return Ok(pool),
+            Err(source) if attempt >= POOL_INIT_ATTEMPTS => {
+                return Err(PoolInitError::Load { pool_id, attempts: attempt, source })
+            }
+            Err(error) => {
+                tracing::warn!(?pool_id, attempt, %error, "failed to load pool, retrying");
+                tokio::time::sleep(POOL_INIT_BACKOFF * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Loads every pool of the registry and starts syncing them.
+///
+/// A pool that still fails to load after [`POOL_INIT_ATTEMPTS`] keeps being
+/// loaded in the background by the manager, it's unavailable to validation
+/// and matching until it loads. The errors of those pools are returned next
+/// to the manager.
 pub async fn configure_uniswap_manager<BlockSync: BlockSyncConsumer>(
     provider: Arc<impl Provider + 'static>,
     state_notification: CanonStateNotifications,
@@ -58,44 +274,51 @@ pub async fn configure_uniswap_manager<BlockSync: BlockSyncConsumer>(
     current_block: BlockNumber,
     block_sync: BlockSync,
     pool_manager_address: Address
-) -> UniswapPoolManager<
-    CanonicalStateAdapter<impl Provider + 'static>,
-    BlockSync,
-    DataLoader<PoolId>,
-    PoolId
-> {
-    let mut uniswap_pools: Vec<_> = uniswap_pool_registry
-        .pools()
-        .keys()
-        .map(|pool_id| {
-            let internal = uniswap_pool_registry.conversion_map.get(pool_id).unwrap();
-
-            let initial_ticks_per_side = 200;
-            EnhancedUniswapPool::new(
-                DataLoader::new_with_registry(
-                    *internal,
-                    uniswap_pool_registry.clone(),
-                    pool_manager_address
-                ),
-                initial_ticks_per_side
-            )
-        })
-        .collect();
-
-    for pool in uniswap_pools.iter_mut() {
-        pool.initialize(Some(current_block), provider.clone())
-            .await
-            .unwrap();
+) -> (
+    UniswapPoolManager<
+        CanonicalStateAdapter<impl Provider + 'static>,
+        BlockSync,
+        DataLoader<PoolId>,
+        PoolId
+    >,
+    Vec<PoolInitError>
+) {
+    let mut uniswap_pools = Vec::new();
+    let mut unavailable = Vec::new();
+    let mut pending = Vec::new();
+    for pool_id in uniswap_pool_registry.pools().keys() {
+        match initialize_pool(
+            *pool_id,
+            &uniswap_pool_registry,
+            current_block,
+            pool_manager_address,
+            provider.clone()
+        )
+        .await
+        {
+            Ok(pool) => uniswap_pools.push(pool),
+            Err(error) => {
+                tracing::error!(%error, "pool is unavailable, loading it in the background");
+                if let Ok(pool) =
+                    uniswap_pool(error.pool_id(), &uniswap_pool_registry, pool_manager_address)
+                {
+                    pending.push(pool);
+                }
+                unavailable.push(error);
+            }
+        }
     }
 
     let notifier =
         Arc::new(CanonicalStateAdapter::new(state_notification, provider.clone(), current_block));
 
-    UniswapPoolManager::new(
+    let manager = UniswapPoolManager::new(
         uniswap_pools,
         uniswap_pool_registry.conversion_map,
         current_block,
         notifier,
         block_sync
     )
+    .with_pending_pools(pending);
+    (manager, unavailable)
 }
@@ -1,19 +1,26 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     pin::Pin,
     sync::Arc
 };
 
-use alloy_primitives::Address;
+use alloy_primitives::{Address, Keccak256, B256};
+use angstrom_metrics::BookMetricsWrapper;
 use angstrom_types::{
     consensus::PreProposal,
     contract_payloads::angstrom::{AngstromBundle, BundleGasDetails},
-    matching::{match_estimate_response::BundleEstimate, uniswap::PoolSnapshot},
-    orders::PoolSolution,
+    matching::{
+        match_estimate_response::BundleEstimate, uniswap::PoolSnapshot, PoolMatchingConfig, Ray
+    },
+    orders::{unwind_open_rings, CircuitBreaker, PoolSolution},
     primitive::PoolId,
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
-use futures::{stream::FuturesUnordered, Future};
+use futures::{
+    channel::mpsc::{unbounded, UnboundedSender},
+    stream::FuturesUnordered,
+    Future, StreamExt
+};
 use futures_util::FutureExt;
 use reth_tasks::TaskSpawner;
 use tokio::{
@@ -23,14 +30,17 @@ use tokio::{
     },
     task::JoinSet
 };
-use tracing::trace;
+use tracing::{trace, Instrument, Span};
 use validation::bundle::BundleValidatorHandle;
 
 use crate::{
-    book::{BookOrder, OrderBook},
-    build_book,
+    book::{
+        incremental::IncrementalBooks, peg::resolve_pegs, sort::expires_soon, BookOrder, OrderBook
+    },
+    build_book, build_book_at,
+    matcher::{solve_with_config, LpSurplusMatcher, MatcherBackend, RingMatcher, SelfTradePolicy},
     strategy::{MatchingStrategy, SimpleCheckpointStrategy},
-    MatchingEngineHandle
+    MatchingEngineHandle, PartialSolutions, SolveFuture
 };
 
 pub enum MatcherCommand {
@@ -38,19 +48,94 @@ pub enum MatcherCommand {
         Vec<BookOrder>,
         Vec<OrderWithStorageData<TopOfBlockOrder>>,
         HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
-        oneshot::Sender<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>>
+        /// rounds each order was carried over for
+        HashMap<B256, u64>,
+        /// timestamp of the block the books are ranked at
+        u64,
+        oneshot::Sender<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>>,
+        /// receives the solution of every book as soon as it's solved
+        Option<UnboundedSender<PoolSolution>>,
+        /// span of the caller, the solve runs on the matcher thread but is
+        /// recorded as part of the callers trace
+        Span
     ),
+    /// Runs the checks and the gas estimation of
+    /// [`MatcherCommand::BuildProposal`] over solutions that were matched
+    /// elsewhere
+    FinalizeSolutions {
+        limit:     Vec<BookOrder>,
+        solutions: Vec<PoolSolution>,
+        pools:     HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        tx:        oneshot::Sender<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>>
+    },
     EstimateGasPerPool {
         limit:    Vec<BookOrder>,
         searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
         pools:    HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
         tx:       oneshot::Sender<eyre::Result<BundleEstimate>>
+    },
+    /// Matches the book of `pool_id` the way the one of `previous` was, for
+    /// pools whose parameters changed on chain
+    MovePool { previous: PoolId, pool_id: PoolId }
+}
+
+/// How the matching engine runs.
+#[derive(Debug, Clone, Default)]
+pub struct MatcherOptions {
+    pub circuit_breaker:   CircuitBreaker,
+    /// keeps the books sorted across rounds and only applies what changed,
+    /// instead of building every book from scratch. the books, and so the
+    /// solutions, are the same either way
+    pub incremental_books: bool,
+    /// pools that aren't matched with the volume matcher
+    pub backends:          HashMap<PoolId, MatcherBackend>,
+    /// what the volume matcher does with a bid and an ask of the same
+    /// address, `None` matches them like any other pair
+    pub self_trade:        Option<SelfTradePolicy>,
+    /// pools that aren't matched with the default config, from the pool
+    /// registry
+    pub matching:          HashMap<PoolId, PoolMatchingConfig>
+}
+
+impl MatcherOptions {
+    /// Hash of everything that changes the solution of a book.
+    pub fn fingerprint(&self) -> B256 {
+        let mut hasher = Keccak256::new();
+
+        hasher.update(
+            self.circuit_breaker
+                .threshold_e6()
+                .unwrap_or(u32::MAX)
+                .to_be_bytes()
+        );
+
+        let backends = self.backends.iter().collect::<BTreeMap<_, _>>();
+        hasher.update((backends.len() as u64).to_be_bytes());
+        for (pool_id, backend) in backends {
+            hasher.update(pool_id);
+            hasher.update([*backend as u8]);
+        }
+
+        hasher.update([self.self_trade.map_or(u8::MAX, |policy| policy as u8)]);
+
+        let matching = self.matching.iter().collect::<BTreeMap<_, _>>();
+        hasher.update((matching.len() as u64).to_be_bytes());
+        for (pool_id, config) in matching {
+            hasher.update(pool_id);
+            hasher.update(config.tick_size.unwrap_or_default().0.to_be_bytes::<32>());
+            hasher.update([config.tick_size.is_some() as u8, config.rounding as u8]);
+            hasher.update(config.min_amm_spread_e6.to_be_bytes());
+            hasher.update(config.price_band_e6.unwrap_or(u32::MAX).to_be_bytes());
+        }
+
+        hasher.finalize()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct MatcherHandle {
-    pub sender: Sender<MatcherCommand>
+    pub sender: Sender<MatcherCommand>,
+    options:    Arc<MatcherOptions>
 }
 
 impl MatcherHandle {
@@ -62,6 +147,33 @@ impl MatcherHandle {
         self.send(cmd).await;
         rx.await.unwrap()
     }
+
+    /// Applies the circuit breaker to solutions that were matched outside of
+    /// the matching engine and estimates the gas of the bundle they make up.
+    pub async fn finalize_solutions(
+        &self,
+        limit: Vec<BookOrder>,
+        solutions: Vec<PoolSolution>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+    ) -> eyre::Result<(Vec<PoolSolution>, BundleGasDetails)> {
+        let (tx, rx) = oneshot::channel();
+        let cmd = MatcherCommand::FinalizeSolutions { limit, solutions, pools, tx };
+        self.send_request(rx, cmd).await
+    }
+
+    /// See [`MatcherCommand::MovePool`].
+    pub async fn move_pool(&self, previous: PoolId, pool_id: PoolId) {
+        self.send(MatcherCommand::MovePool { previous, pool_id })
+            .await
+    }
+}
+
+#[cfg(test)]
+impl MatcherHandle {
+    /// A handle whose commands go to `sender` instead of a matcher.
+    pub(crate) fn detached(sender: Sender<MatcherCommand>) -> Self {
+        Self { sender, options: Default::default() }
+    }
 }
 
 impl MatchingEngineHandle for MatcherHandle {
@@ -69,19 +181,90 @@ impl MatchingEngineHandle for MatcherHandle {
         &self,
         limit: Vec<BookOrder>,
         searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
-        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
     ) -> futures_util::future::BoxFuture<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>> {
-        Box::pin(async move {
+        let span = tracing::info_span!(
+            "solve_pools",
+            limit_orders = limit.len(),
+            searcher_orders = searcher.len(),
+            pools = pools.len(),
+            carried_over = carry_over.len(),
+            timestamp
+        );
+        Box::pin(
+            async move {
+                let (tx, rx) = oneshot::channel();
+                let cmd = MatcherCommand::BuildProposal(
+                    limit,
+                    searcher,
+                    pools,
+                    carry_over,
+                    timestamp,
+                    tx,
+                    None,
+                    Span::current()
+                );
+                self.send_request(rx, cmd).await
+            }
+            .instrument(span)
+        )
+    }
+
+    fn solve_pools_streaming(
+        &self,
+        limit: Vec<BookOrder>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        pools: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64
+    ) -> (PartialSolutions, SolveFuture) {
+        let span = tracing::info_span!(
+            "solve_pools",
+            limit_orders = limit.len(),
+            searcher_orders = searcher.len(),
+            pools = pools.len(),
+            carried_over = carry_over.len(),
+            timestamp,
+            streaming = true
+        );
+        let (partial_tx, partial_rx) = unbounded();
+        let this = self.clone();
+        let solved = async move {
             let (tx, rx) = oneshot::channel();
-            self.send_request(rx, MatcherCommand::BuildProposal(limit, searcher, pools, tx))
-                .await
-        })
+            let cmd = MatcherCommand::BuildProposal(
+                limit,
+                searcher,
+                pools,
+                carry_over,
+                timestamp,
+                tx,
+                Some(partial_tx),
+                Span::current()
+            );
+            this.send_request(rx, cmd).await
+        }
+        .instrument(span)
+        .boxed();
+
+        (partial_rx.boxed(), solved)
+    }
+
+    fn config_fingerprint(&self) -> B256 {
+        self.options.fingerprint()
     }
 }
 
 pub struct MatchingManager<TP: TaskSpawner, V> {
     _futures:          FuturesUnordered<Pin<Box<dyn Future<Output = ()> + Sync + Send + 'static>>>,
     validation_handle: V,
+    circuit_breaker:   CircuitBreaker,
+    books:             Option<IncrementalBooks>,
+    backends:          HashMap<PoolId, MatcherBackend>,
+    self_trade:        Option<SelfTradePolicy>,
+    matching:          HashMap<PoolId, PoolMatchingConfig>,
+    metrics:           BookMetricsWrapper,
     _tp:               Arc<TP>
 }
 
@@ -90,18 +273,51 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
         Self {
             _futures:          FuturesUnordered::default(),
             validation_handle: validation,
+            circuit_breaker:   CircuitBreaker::default(),
+            books:             None,
+            backends:          HashMap::new(),
+            self_trade:        None,
+            matching:          HashMap::new(),
+            metrics:           BookMetricsWrapper::new(),
             _tp:               tp.into()
         }
     }
 
     pub fn spawn(tp: TP, validation: V) -> MatcherHandle {
+        Self::spawn_with_circuit_breaker(tp, validation, CircuitBreaker::default())
+    }
+
+    pub fn spawn_with_circuit_breaker(
+        tp: TP,
+        validation: V,
+        circuit_breaker: CircuitBreaker
+    ) -> MatcherHandle {
+        Self::spawn_with_options(
+            tp,
+            validation,
+            MatcherOptions { circuit_breaker, ..Default::default() }
+        )
+    }
+
+    pub fn spawn_with_options(tp: TP, validation: V, options: MatcherOptions) -> MatcherHandle {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
         let tp = Arc::new(tp);
 
-        let fut = manager_thread(rx, tp.clone(), validation).boxed();
+        let handle = MatcherHandle { sender: tx, options: Arc::new(options.clone()) };
+        let fut = manager_thread(rx, tp.clone(), validation, options).boxed();
         tp.spawn_critical("matching_engine", fut);
 
-        MatcherHandle { sender: tx }
+        handle
+    }
+
+    /// See [`MatcherCommand::MovePool`].
+    fn move_pool(&mut self, previous: PoolId, pool_id: PoolId) {
+        if let Some(backend) = self.backends.remove(&previous) {
+            self.backends.insert(pool_id, backend);
+        }
+        if let Some(config) = self.matching.remove(&previous) {
+            self.matching.insert(pool_id, config);
+        }
     }
 
     pub fn orders_by_pool_id(preproposals: &[PreProposal]) -> HashMap<PoolId, HashSet<BookOrder>> {
@@ -118,6 +334,17 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
     pub fn build_non_proposal_books(
         limit: Vec<BookOrder>,
         pool_snapshots: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+    ) -> Vec<OrderBook> {
+        Self::build_non_proposal_books_with_carry_over(limit, pool_snapshots, &HashMap::new(), 0)
+    }
+
+    /// Builds the books ranked like they are at `timestamp`, see
+    /// [`build_book_at`].
+    pub fn build_non_proposal_books_with_carry_over(
+        limit: Vec<BookOrder>,
+        pool_snapshots: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: &HashMap<B256, u64>,
+        timestamp: u64
     ) -> Vec<OrderBook> {
         let book_sources = Self::orders_sorted_by_pool_id(limit);
 
@@ -125,7 +352,7 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
             .into_iter()
             .map(|(id, orders)| {
                 let amm = pool_snapshots.get(&id).map(|value| value.2.clone());
-                build_book(id, amm, orders)
+                build_book_at(id, amm, orders, carry_over, timestamp)
             })
             .collect()
     }
@@ -148,40 +375,103 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
     }
 
     pub async fn build_proposal(
-        &self,
+        &mut self,
         limit: Vec<BookOrder>,
         searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
-        pool_snapshots: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+        pool_snapshots: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        carry_over: HashMap<B256, u64>,
+        timestamp: u64,
+        partial: Option<UnboundedSender<PoolSolution>>
     ) -> eyre::Result<(Vec<PoolSolution>, BundleGasDetails)> {
-        tracing::info!("starting to build proposal");
-        // Pull all the orders out of all the preproposals and build OrderPools out of
-        // them.  This is ugly and inefficient right now
-        let books = Self::build_non_proposal_books(limit.clone(), &pool_snapshots);
+        tracing::info!(
+            limit_orders = limit.len(),
+            searcher_orders = searcher.len(),
+            carried_over = carry_over.len(),
+            "starting to build proposal"
+        );
+        let books = match self.books.as_mut() {
+            Some(books) => books.sync(limit.clone(), &pool_snapshots, &carry_over, timestamp),
+            None => Self::build_non_proposal_books_with_carry_over(
+                limit.clone(),
+                &pool_snapshots,
+                &carry_over,
+                timestamp
+            )
+        };
+        self.record_expiring_orders(&books, timestamp);
+        let books = books.into_iter().map(|book| {
+            let config = self.matching.get(&book.id()).copied().unwrap_or_default();
+            book.with_matching_config(config)
+        });
 
-        let searcher_orders: HashMap<PoolId, OrderWithStorageData<TopOfBlockOrder>> =
-            searcher.into_iter().fold(HashMap::new(), |mut acc, order| {
-                acc.entry(order.pool_id).or_insert(order);
-                acc
-            });
+        let searcher_orders = Self::best_searcher_orders(searcher);
 
         let mut solution_set = JoinSet::new();
-        books.into_iter().for_each(|b| {
+        books.for_each(|b| {
             let searcher = searcher_orders.get(&b.id()).cloned();
+            let backend = self.backends.get(&b.id()).copied().unwrap_or_default();
+            let self_trade = self.self_trade;
             // Using spawn-blocking here is not BAD but it might be suboptimal as it allows
             // us to spawn many more tasks that the CPu has threads.  Better solution is a
             // dedicated threadpool and some suggest the `rayon` crate.  This is probably
             // not a problem while I'm testing, but leaving this note here as it may be
             // important for future efficiency gains
+            let span = tracing::info_span!("solve_book", pool_id = ?b.id(), ?backend);
             solution_set.spawn_blocking(move || {
-                SimpleCheckpointStrategy::run(&b).map(|s| s.solution(searcher))
+                let _solve = span.entered();
+                solve_with_config(&b, |book| match backend {
+                    MatcherBackend::Volume => {
+                        SimpleCheckpointStrategy::run_with_self_trade_policy(book, self_trade)
+                            .map(|s| s.solution(searcher.clone()))
+                    }
+                    MatcherBackend::Lp => {
+                        Some(LpSurplusMatcher::solve(book).solution(searcher.clone()))
+                    }
+                })
             });
         });
         let mut solutions = Vec::new();
         while let Some(res) = solution_set.join_next().await {
             if let Ok(Some(r)) = res {
+                // the receiver going away only means nobody waits on them anymore
+                if let Some(partial) = partial.as_ref() {
+                    let _ = partial.unbounded_send(r.clone());
+                }
                 solutions.push(r);
             }
         }
+        // ends the stream before the slower checks of the full output
+        drop(partial);
+        // cycles of orders across pools that didn't clear between each other
+        // are settled as rings. they are priced off the same books, the bundle
+        // still carries the signed orders
+        let priced = resolve_pegs(&limit, &pool_snapshots);
+        solutions = RingMatcher::settle(&priced, solutions, &pool_snapshots);
+
+        self.finalize_solutions(limit, solutions, pool_snapshots)
+            .await
+    }
+
+    pub async fn finalize_solutions(
+        &self,
+        limit: Vec<BookOrder>,
+        solutions: Vec<PoolSolution>,
+        pool_snapshots: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+    ) -> eyre::Result<(Vec<PoolSolution>, BundleGasDetails)> {
+        // pools whose price moved too far are halted before anything of them makes it
+        // into the bundle
+        let mut solutions = solutions
+            .into_iter()
+            .map(|solution| match pool_snapshots.get(&solution.id) {
+                Some((_, _, snapshot, _)) => {
+                    let spot_price = Ray::from(snapshot.current_price());
+                    self.circuit_breaker.apply(solution, spot_price)
+                }
+                None => solution
+            })
+            .collect::<Vec<_>>();
+        // a ring with a halted pool would pay its other orders with nothing
+        unwind_open_rings(&mut solutions);
 
         // generate bundle without final gas known.
         trace!("Building bundle for gas finalization");
@@ -194,6 +484,47 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
         Ok((solutions, gas_response))
     }
 
+    /// Counts the standing orders of every book that get priority for being
+    /// close to their deadline.
+    fn record_expiring_orders(&self, books: &[OrderBook], timestamp: u64) {
+        let expiring = books
+            .iter()
+            .map(|book| {
+                let count = book
+                    .bids()
+                    .iter()
+                    .chain(book.asks())
+                    .filter(|order| expires_soon(order, timestamp))
+                    .count();
+                (book.id().to_string(), count)
+            })
+            .collect::<Vec<_>>();
+        self.metrics.record_books(
+            expiring
+                .iter()
+                .map(|(pool_id, count)| (pool_id.as_str(), *count))
+        );
+    }
+
+    /// The top of block order with the highest reward of every pool, ties are
+    /// broken by the order hash so that every node picks the same one.
+    pub fn best_searcher_orders(
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>
+    ) -> HashMap<PoolId, OrderWithStorageData<TopOfBlockOrder>> {
+        searcher.into_iter().fold(HashMap::new(), |mut acc, order| {
+            let key = |order: &OrderWithStorageData<TopOfBlockOrder>| {
+                (order.tob_reward, std::cmp::Reverse(order.order_id.hash))
+            };
+            match acc.get(&order.pool_id) {
+                Some(best) if key(best) >= key(&order) => {}
+                _ => {
+                    acc.insert(order.pool_id, order);
+                }
+            }
+            acc
+        })
+    }
+
     pub fn orders_sorted_by_pool_id(limit: Vec<BookOrder>) -> HashMap<PoolId, HashSet<BookOrder>> {
         limit.into_iter().fold(HashMap::new(), |mut acc, order| {
             acc.entry(order.pool_id).or_default().insert(order);
@@ -246,20 +577,122 @@ impl<TP: TaskSpawner + 'static, V: BundleValidatorHandle> MatchingManager<TP, V>
 pub async fn manager_thread<TP: TaskSpawner + 'static, V: BundleValidatorHandle>(
     mut input: Receiver<MatcherCommand>,
     tp: Arc<TP>,
-    validation_handle: V
+    validation_handle: V,
+    options: MatcherOptions
 ) {
-    let manager =
-        MatchingManager { _futures: FuturesUnordered::default(), _tp: tp, validation_handle };
+    let mut manager = MatchingManager {
+        _futures: FuturesUnordered::default(),
+        _tp: tp,
+        validation_handle,
+        circuit_breaker: options.circuit_breaker,
+        books: options.incremental_books.then(IncrementalBooks::default),
+        backends: options.backends,
+        self_trade: options.self_trade,
+        matching: options.matching,
+        metrics: BookMetricsWrapper::new()
+    };
 
     while let Some(c) = input.recv().await {
         match c {
-            MatcherCommand::BuildProposal(limit, searcher, snapshot, r) => {
-                r.send(manager.build_proposal(limit, searcher, snapshot).await)
-                    .unwrap();
+            MatcherCommand::BuildProposal(
+                limit,
+                searcher,
+                snapshot,
+                carry_over,
+                timestamp,
+                r,
+                partial,
+                caller
+            ) => {
+                let span = tracing::info_span!(parent: &caller, "build_proposal");
+                r.send(
+                    manager
+                        .build_proposal(limit, searcher, snapshot, carry_over, timestamp, partial)
+                        .instrument(span)
+                        .await
+                )
+                .unwrap();
+            }
+            MatcherCommand::FinalizeSolutions { limit, solutions, pools, tx } => {
+                let _ = tx.send(manager.finalize_solutions(limit, solutions, pools).await);
             }
             MatcherCommand::EstimateGasPerPool { .. } => {
                 todo!()
             }
+            MatcherCommand::MovePool { previous, pool_id } => manager.move_pool(previous, pool_id)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy::primitives::Uint;
+    use reth_tasks::TokioTaskExecutor;
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    /// Never prices a bundle, holding the solve at its last step.
+    #[derive(Clone)]
+    struct PendingGas;
+
+    impl BundleValidatorHandle for PendingGas {
+        async fn fetch_gas_for_bundle(&self, _: AngstromBundle) -> eyre::Result<BundleGasDetails> {
+            std::future::pending().await
+        }
+    }
+
+    /// A bid at 2 and an ask at 1 for 10 T0 each, which cross.
+    fn crossing_orders(pool_id: PoolId) -> Vec<BookOrder> {
+        [true, false]
+            .into_iter()
+            .map(|is_bid| {
+                let builder = UserOrderBuilder::new()
+                    .exact()
+                    .is_bid(is_bid)
+                    .amount(10)
+                    .exact_in(!is_bid);
+                let builder = if is_bid {
+                    builder.bid_min_price(Ray::from(Uint::from(2 * 10u128.pow(27))))
+                } else {
+                    builder.min_price(Ray::from(Uint::from(10u128.pow(27))))
+                };
+                let mut order = builder.with_storage().is_bid(is_bid).build();
+                order.pool_id = pool_id;
+                order.order_id.pool_id = pool_id;
+                order
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn streams_every_solved_book_ahead_of_the_output() {
+        let pools = [PoolId::random(), PoolId::random()];
+        let limit = pools.into_iter().flat_map(crossing_orders).collect();
+        let mut manager = MatchingManager::new(TokioTaskExecutor::default(), PendingGas);
+
+        let (tx, rx) = unbounded();
+        let solving = tokio::spawn(async move {
+            manager
+                .build_proposal(limit, vec![], HashMap::new(), HashMap::new(), 0, Some(tx))
+                .await
+        });
+
+        // the stream ends with the books, it doesn't wait on the bundle to be priced
+        let streamed = tokio::time::timeout(Duration::from_secs(5), rx.collect::<Vec<_>>())
+            .await
+            .expect("the stream ended");
+        assert_eq!(
+            streamed
+                .iter()
+                .map(|solution| solution.id)
+                .collect::<HashSet<_>>(),
+            HashSet::from(pools)
+        );
+        assert!(streamed.iter().all(|solution| solution.limit.len() == 2));
+
+        solving.abort();
+    }
+}
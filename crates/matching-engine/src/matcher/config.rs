@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use angstrom_types::{
+    matching::Ray,
+    orders::{OrderFillState, OrderOutcome, PoolSolution},
+    sol_bindings::RawPoolOrder
+};
+
+use crate::book::{BookOrder, OrderBook};
+
+/// Solves `book` with `solve` under the [`PoolMatchingConfig`] of its pool.
+///
+/// Orders limited outside of the price band around the AMM can't set the
+/// clearing price, so a wash cross far from the market doesn't move the pool.
+/// Orders that can't fill inside the band are left out of the match, and if the
+/// clearing price still ends up outside of it the orders that pushed it there
+/// are too. Left out orders get an [`OrderFillState::OutsidePriceBand`]
+/// outcome.
+///
+/// A solution that moves the AMM by less than the minimum spread is replaced
+/// by one that matches the orders only against each other. The clearing price
+/// of a solution that doesn't touch the AMM is rounded onto the tick of the
+/// pool, as long as every filled order still fills at the rounded price.
+///
+/// [`PoolMatchingConfig`]: angstrom_types::matching::PoolMatchingConfig
+pub fn solve_with_config(
+    book: &OrderBook,
+    solve: impl Fn(&OrderBook) -> Option<PoolSolution>
+) -> Option<PoolSolution> {
+    let config = book.matching_config();
+    let band = book
+        .amm()
+        .and_then(|amm| config.price_band(Ray::from(amm.current_price())));
+
+    let mut solution = match band {
+        Some(band) => solve_in_band(book, band, &solve)?,
+        None => solve_in_spread(book, &solve)?
+    };
+
+    let rounded = config.round_price(solution.ucp);
+    if rounded != solution.ucp
+        && band.map_or(true, |band| in_band(rounded, band))
+        && fills_at(book, &solution, rounded)
+    {
+        solution.ucp = rounded;
+    }
+
+    Some(solution)
+}
+
+fn solve_in_band(
+    book: &OrderBook,
+    band: (Ray, Ray),
+    solve: &impl Fn(&OrderBook) -> Option<PoolSolution>
+) -> Option<PoolSolution> {
+    let mut solution =
+        solve_in_spread(&book.retain_orders(|order| fills_in_band(order, band)), solve)?;
+
+    if solution.limit.iter().any(|outcome| outcome.is_filled()) && !in_band(solution.ucp, band) {
+        tracing::debug!(
+            pool_id = ?book.id(),
+            ucp = ?solution.ucp,
+            ?band,
+            "clearing price is outside the price band, matching without the orders limited past it"
+        );
+        solution =
+            solve_in_spread(&book.retain_orders(|order| limited_in_band(order, band)), solve)?;
+    }
+
+    // every order of the book needs an outcome, in book order
+    let mut matched = solution
+        .limit
+        .drain(..)
+        .map(|outcome| (outcome.id.hash, outcome))
+        .collect::<HashMap<_, _>>();
+    solution.limit = book
+        .bids()
+        .iter()
+        .chain(book.asks())
+        .map(|order| {
+            matched
+                .remove(&order.order_id.hash)
+                .unwrap_or(OrderOutcome {
+                    id:      order.order_id,
+                    outcome: OrderFillState::OutsidePriceBand
+                })
+        })
+        .collect();
+
+    Some(solution)
+}
+
+fn solve_in_spread(
+    book: &OrderBook,
+    solve: &impl Fn(&OrderBook) -> Option<PoolSolution>
+) -> Option<PoolSolution> {
+    let config = book.matching_config();
+    let mut solution = solve(book)?;
+
+    if let (Some(amm), Some(_)) = (book.amm(), solution.amm_quantity.as_ref()) {
+        let spot_price = Ray::from(amm.current_price());
+        if !config.crosses_amm(solution.ucp, spot_price) {
+            tracing::debug!(
+                pool_id = ?book.id(),
+                ucp = ?solution.ucp,
+                ?spot_price,
+                "clearing price is within the minimum spread of the amm, matching without it"
+            );
+            solution = solve(&book.without_amm())?;
+        }
+    }
+
+    Some(solution)
+}
+
+fn in_band(price: Ray, (lower, upper): (Ray, Ray)) -> bool {
+    lower <= price && price <= upper
+}
+
+/// Whether `order` can fill at some price inside the band.
+fn fills_in_band(order: &BookOrder, (lower, upper): (Ray, Ray)) -> bool {
+    if order.is_bid {
+        order.price_for_book_side(true) >= lower
+    } else {
+        order.price_for_book_side(false) <= upper
+    }
+}
+
+/// Whether the limit price of `order` is inside the band.
+fn limited_in_band(order: &BookOrder, band: (Ray, Ray)) -> bool {
+    in_band(order.price_for_book_side(order.is_bid), band)
+}
+
+/// Whether the filled orders of `solution` fill at `price` just like they do
+/// at its clearing price.
+fn fills_at(book: &OrderBook, solution: &PoolSolution, price: Ray) -> bool {
+    // the amm swap was sized for the clearing price
+    if solution.amm_quantity.is_some() || solution.ring.is_some() {
+        return false
+    }
+
+    let orders = book
+        .bids()
+        .iter()
+        .chain(book.asks())
+        .map(|order| (order.order_id.hash, order))
+        .collect::<HashMap<_, _>>();
+
+    solution
+        .limit
+        .iter()
+        .filter(|outcome| outcome.is_filled())
+        .all(|outcome| {
+            let Some(order) = orders.get(&outcome.id.hash) else { return false };
+            // quantities in T1 are worth a different amount of T0 at another price, the
+            // book wouldn't balance anymore
+            if order.is_bid() == order.exact_in() {
+                return false
+            }
+
+            if order.is_bid {
+                price <= order.price_for_book_side(true)
+            } else {
+                price >= order.price_for_book_side(false)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use angstrom_types::{
+        matching::{PoolMatchingConfig, UcpRounding},
+        orders::{OrderFillState, OrderOutcome}
+    };
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+    use crate::simulation::amm::single_position_amm;
+
+    fn exact_t0(is_bid: bool, amount: u128, price: Ray) -> BookOrder {
+        let builder = UserOrderBuilder::new()
+            .exact()
+            .amount(amount)
+            .exact_in(!is_bid);
+        let builder = if is_bid {
+            builder.bid().bid_min_price(price)
+        } else {
+            builder.ask().min_price(price)
+        };
+        builder.with_storage().is_bid(is_bid).build()
+    }
+
+    fn book(bid: u64, ask: u64, tick: u64) -> OrderBook {
+        OrderBook::new(
+            Default::default(),
+            None,
+            vec![exact_t0(true, 100, Ray::from(U256::from(bid)))],
+            vec![exact_t0(false, 100, Ray::from(U256::from(ask)))],
+            None
+        )
+        .with_matching_config(PoolMatchingConfig {
+            tick_size: Some(Ray::from(U256::from(tick))),
+            rounding: UcpRounding::Down,
+            ..Default::default()
+        })
+    }
+
+    fn filled_at(book: &OrderBook, ucp: u64) -> PoolSolution {
+        fill_all(book, Ray::from(U256::from(ucp)))
+    }
+
+    fn fill_all(book: &OrderBook, ucp: Ray) -> PoolSolution {
+        PoolSolution {
+            id: book.id(),
+            ucp,
+            limit: book
+                .bids()
+                .iter()
+                .chain(book.asks())
+                .map(|order| OrderOutcome {
+                    id:      order.order_id,
+                    outcome: OrderFillState::CompleteFill
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rounds_the_clearing_price_onto_the_tick() {
+        let book = book(1_000_000_000, 900_000_000, 100_000_000);
+        let solution = solve_with_config(&book, |book| Some(filled_at(book, 950_000_000)));
+        assert_eq!(solution.unwrap().ucp, Ray::from(U256::from(900_000_000)));
+    }
+
+    #[test]
+    fn keeps_the_clearing_price_off_the_tick_of_the_limits() {
+        // rounding down to 900 would pay the ask under its limit
+        let book = book(1_000_000_000, 950_000_000, 100_000_000);
+        let solution = solve_with_config(&book, |book| Some(filled_at(book, 970_000_000)));
+        assert_eq!(solution.unwrap().ucp, Ray::from(U256::from(970_000_000)));
+    }
+
+    /// A book around an AMM at a price of one, banded to 1%.
+    fn banded_book(bids: &[&str], asks: &[&str]) -> OrderBook {
+        let order = |is_bid| move |price: &&str| exact_t0(is_bid, 100, price.parse().unwrap());
+        OrderBook::new(
+            Default::default(),
+            single_position_amm(0, 1_000, 1_000_000_000_000),
+            bids.iter().map(order(true)).collect(),
+            asks.iter().map(order(false)).collect(),
+            None
+        )
+        .with_matching_config(PoolMatchingConfig {
+            price_band_e6: Some(10_000),
+            ..Default::default()
+        })
+    }
+
+    /// Fills the whole book at the limit of its best bid
+    fn fill_at_best_bid(book: &OrderBook) -> Option<PoolSolution> {
+        if book.asks().is_empty() {
+            return None
+        }
+        let best_bid = book.bids().first()?.price_for_book_side(true);
+        Some(fill_all(book, best_bid))
+    }
+
+    fn outcomes(solution: &PoolSolution) -> Vec<OrderFillState> {
+        solution
+            .limit
+            .iter()
+            .map(|outcome| outcome.outcome)
+            .collect()
+    }
+
+    #[test]
+    fn leaves_out_orders_that_cant_fill_in_the_band() {
+        let book = banded_book(&["1.005"], &["0.5", "1.5"]);
+        let solution = solve_with_config(&book, fill_at_best_bid).unwrap();
+
+        assert_eq!(
+            outcomes(&solution),
+            vec![
+                OrderFillState::CompleteFill,
+                OrderFillState::CompleteFill,
+                OrderFillState::OutsidePriceBand
+            ]
+        );
+    }
+
+    #[test]
+    fn orders_limited_past_the_band_cant_set_the_clearing_price() {
+        // a wash cross at twice the market
+        let book = banded_book(&["2", "1"], &["0.999"]);
+        let solution = solve_with_config(&book, fill_at_best_bid).unwrap();
+
+        assert_eq!(solution.ucp, book.bids()[1].price_for_book_side(true));
+        assert_eq!(
+            outcomes(&solution),
+            vec![
+                OrderFillState::OutsidePriceBand,
+                OrderFillState::CompleteFill,
+                OrderFillState::CompleteFill
+            ]
+        );
+    }
+}
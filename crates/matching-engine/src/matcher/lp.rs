@@ -0,0 +1,690 @@
+use angstrom_types::{
+    matching::{
+        uniswap::{Direction, PoolPriceVec},
+        Ray, SqrtPriceX96
+    },
+    orders::{NetAmmOrder, OrderFillState, OrderOutcome, PoolSolution},
+    sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
+};
+use tracing::{debug, trace};
+
+use crate::book::{BookOrder, OrderBook};
+
+/// Rounds of trimming partial fills after rounding before a price is given up
+/// on.
+const REBALANCE_ROUNDS: usize = 4;
+/// Most exact orders at one price that are searched over every combination,
+/// the ones with the least to gain past them are left unfilled.
+pub const MAX_EXACT_ORDERS: usize = 8;
+
+/// Matches a book by solving the uniform price auction for the most surplus.
+///
+/// With a fixed clearing price every order fills at that price, so what is
+/// left is to pick the fills that maximize the total surplus of all orders,
+/// subject to every fill being within the order's quantity, exact orders
+/// filling completely or not at all and both sides of the book trading the
+/// same amount of T0. The AMM is a partially fillable order that gains
+/// nothing, on whichever side of its price the clearing price is.
+///
+/// Once the exact orders that fill are picked, every partial order gains a
+/// fixed amount on each unit of T0 it trades, so the most surplus trades as
+/// much as both sides can and fills the partial orders with the most to gain
+/// first. Every combination of the
+/// exact orders is tried, up to [`MAX_EXACT_ORDERS`] of them. This is done at
+/// every limit price in the book and at the AMM price, and the best of them
+/// wins. All of it is exact integer math with a bounded number of steps, so
+/// every node picks the same fills.
+///
+/// Every price tries every combination of its exact orders, which makes this
+/// a lot more expensive than the
+/// [`VolumeFillMatcher`](super::VolumeFillMatcher). It is meant for checking
+/// the volume matcher and for pools with books small enough to afford it.
+#[derive(Clone, Debug)]
+pub struct LpSurplusMatcher<'a> {
+    book:             &'a OrderBook,
+    pub bid_outcomes: Vec<OrderFillState>,
+    pub ask_outcomes: Vec<OrderFillState>,
+    price:            Option<Ray>,
+    amm_outcome:      Option<NetAmmOrder>,
+    /// total surplus of the fills in T1, `0` if nothing crossed
+    surplus:          u128
+}
+
+/// A crossing order and the most T0 it can trade at the candidate price.
+struct Crossing<'a> {
+    is_bid: bool,
+    idx:    usize,
+    order:  &'a BookOrder,
+    cap_t0: u128
+}
+
+/// Fills of the crossing orders at one price, in T0.
+struct Fill {
+    /// index in the crossing orders and the T0 it trades
+    orders:  Vec<(usize, u128)>,
+    amm:     u128,
+    surplus: u128,
+    volume:  u128
+}
+
+/// Fills of all orders at one price, before they are turned into outcomes.
+struct Candidate {
+    price:   Ray,
+    /// side, index in the side and the filled quantity in the order's own
+    /// token
+    fills:   Vec<(bool, usize, u128)>,
+    amm:     Option<(u128, Direction)>,
+    surplus: u128,
+    volume:  u128
+}
+
+impl<'a> LpSurplusMatcher<'a> {
+    pub fn new(book: &'a OrderBook) -> Self {
+        Self {
+            book,
+            bid_outcomes: vec![OrderFillState::Unfilled; book.bids().len()],
+            ask_outcomes: vec![OrderFillState::Unfilled; book.asks().len()],
+            price: None,
+            amm_outcome: None,
+            surplus: 0
+        }
+    }
+
+    /// Solves the book, nothing is filled when no price clears it.
+    pub fn solve(book: &'a OrderBook) -> Self {
+        let mut matcher = Self::new(book);
+        matcher.run_match();
+        matcher
+    }
+
+    pub fn price(&self) -> Option<Ray> {
+        self.price
+    }
+
+    pub fn surplus(&self) -> u128 {
+        self.surplus
+    }
+
+    /// Tries every candidate price and applies the fills of the best one.
+    /// Ties go to the higher volume, then to the lower price. Returns whether
+    /// anything was filled.
+    pub fn run_match(&mut self) -> bool {
+        let Some(best) = self
+            .candidate_prices()
+            .into_iter()
+            .filter_map(|price| self.solve_at(price))
+            .filter(|candidate| candidate.volume > 0)
+            .reduce(|best, candidate| {
+                if (candidate.surplus, candidate.volume) > (best.surplus, best.volume) {
+                    candidate
+                } else {
+                    best
+                }
+            })
+        else {
+            debug!(pool_id = ?self.book.id(), "no price clears the book");
+            return false
+        };
+
+        for (is_bid, idx, filled) in best.fills {
+            let (orders, outcomes) = if is_bid {
+                (self.book.bids(), &mut self.bid_outcomes)
+            } else {
+                (self.book.asks(), &mut self.ask_outcomes)
+            };
+            outcomes[idx] = match filled {
+                0 => OrderFillState::Unfilled,
+                q if q >= orders[idx].max_q() => OrderFillState::CompleteFill,
+                q => OrderFillState::PartialFill(q)
+            };
+        }
+        self.amm_outcome = best.amm.and_then(|(t0, direction)| {
+            let start = self.book.amm()?.current_price();
+            let end = start.d_t0(t0, direction).ok()?;
+            let moved = PoolPriceVec::from_price_range(start, end).ok()?;
+            let mut order = NetAmmOrder::new(direction);
+            order.add_quantity(moved.d_t0, moved.d_t1);
+            Some(order)
+        });
+        self.price = Some(best.price);
+        self.surplus = best.surplus;
+
+        true
+    }
+
+    pub fn solution(
+        &self,
+        searcher: Option<OrderWithStorageData<TopOfBlockOrder>>
+    ) -> PoolSolution {
+        let limit = self
+            .book
+            .bids()
+            .iter()
+            .zip(&self.bid_outcomes)
+            .chain(self.book.asks().iter().zip(&self.ask_outcomes))
+            .map(|(order, outcome)| OrderOutcome { id: order.order_id, outcome: *outcome })
+            .collect();
+
+        PoolSolution {
+            id: self.book.id(),
+            ucp: self.price.unwrap_or_default(),
+            amm_quantity: self.amm_outcome.clone(),
+            searcher,
+            limit,
+            ring: None
+        }
+    }
+
+    /// Every limit price in the book and the AMM price, ascending.
+    fn candidate_prices(&self) -> Vec<Ray> {
+        let mut prices = self
+            .book
+            .bids()
+            .iter()
+            .map(|order| order.price_for_book_side(true))
+            .chain(
+                self.book
+                    .asks()
+                    .iter()
+                    .map(|order| order.price_for_book_side(false))
+            )
+            .chain(self.book.amm().map(|amm| amm.current_price().as_ray()))
+            .collect::<Vec<_>>();
+        prices.sort_unstable();
+        prices.dedup();
+
+        prices
+    }
+
+    /// T0 traded by filling `q` of the order's own token at `price`.
+    fn t0_quantity(order: &BookOrder, q: u128, price: Ray) -> u128 {
+        // bids that pay exactly and asks that receive exactly are in T1
+        if order.is_bid == order.exact_in() {
+            price.inverse_quantity(q, false)
+        } else {
+            q
+        }
+    }
+
+    /// Quantity of the order's own token that trades `t0` at `price`. T1 is
+    /// rounded up so that converting it back doesn't come out short of `t0`.
+    fn own_quantity(order: &BookOrder, t0: u128, price: Ray) -> u128 {
+        if order.is_bid == order.exact_in() {
+            price.quantity(t0, true).min(order.max_q())
+        } else {
+            t0.min(order.max_q())
+        }
+    }
+
+    fn crossing_orders(&self, price: Ray) -> Vec<Crossing<'a>> {
+        let book = self.book;
+        let bids = book
+            .bids()
+            .iter()
+            .enumerate()
+            .map(|(idx, o)| (true, idx, o));
+        let asks = book
+            .asks()
+            .iter()
+            .enumerate()
+            .map(|(idx, o)| (false, idx, o));
+
+        bids.chain(asks)
+            .filter(|(is_bid, _, order)| {
+                let limit = order.price_for_book_side(*is_bid);
+                if *is_bid {
+                    limit >= price
+                } else {
+                    limit <= price
+                }
+            })
+            .map(|(is_bid, idx, order)| Crossing {
+                is_bid,
+                idx,
+                order,
+                cap_t0: Self::t0_quantity(order, order.max_q(), price)
+            })
+            .filter(|crossing| crossing.cap_t0 > 0)
+            .collect()
+    }
+
+    /// The side the AMM trades on at `price` and the most T0 it can trade
+    /// before its price reaches `price`.
+    fn amm_capacity(&self, price: Ray) -> Option<(bool, u128, Direction)> {
+        let start = self.book.amm()?.current_price();
+        let amm_price = start.as_ray();
+        if price == amm_price {
+            return None
+        }
+        // above its price the AMM sells T0 to bids, below it buys from asks
+        let (is_bid, direction) = if price > amm_price {
+            (false, Direction::BuyingT0)
+        } else {
+            (true, Direction::SellingT0)
+        };
+        let cap = start.vec_to(SqrtPriceX96::from(price)).ok()?.d_t0;
+
+        (cap > 0).then_some((is_bid, cap, direction))
+    }
+
+    /// How much better than its limit `o` trades at `price`.
+    fn improvement(o: &Crossing, price: Ray) -> Ray {
+        let limit = o.order.price_for_book_side(o.is_bid);
+        if o.is_bid {
+            Ray(limit.0.saturating_sub(price.0))
+        } else {
+            Ray(price.0.saturating_sub(limit.0))
+        }
+    }
+
+    /// Fills at `price` with the exact orders picked by the bits of `chosen`
+    /// and as much of the partial orders and the AMM as both sides can trade,
+    /// the ones with the most to gain first. `None` if the partial orders
+    /// can't balance the exact ones or nothing trades.
+    fn fill_with(
+        price: Ray,
+        orders: &[Crossing],
+        exact: &[usize],
+        chosen: u32,
+        partial: &[usize],
+        amm: Option<(bool, u128, Direction)>
+    ) -> Option<Fill> {
+        let chosen = exact
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| chosen & (1 << bit) != 0)
+            .map(|(_, i)| *i)
+            .collect::<Vec<_>>();
+        let side_t0 = |indices: &[usize], is_bid: bool| {
+            indices
+                .iter()
+                .map(|i| &orders[*i])
+                .filter(|o| o.is_bid == is_bid)
+                .fold(0u128, |sum, o| sum.saturating_add(o.cap_t0))
+        };
+        let amm_cap = |is_bid: bool| {
+            amm.filter(|(side, ..)| *side == is_bid)
+                .map_or(0, |(_, cap, _)| cap)
+        };
+
+        let (exact_bids, exact_asks) = (side_t0(&chosen, true), side_t0(&chosen, false));
+        let volume = exact_bids
+            .saturating_add(side_t0(partial, true))
+            .saturating_add(amm_cap(true))
+            .min(
+                exact_asks
+                    .saturating_add(side_t0(partial, false))
+                    .saturating_add(amm_cap(false))
+            );
+        if volume == 0 || volume < exact_bids.max(exact_asks) {
+            return None
+        }
+
+        // what the exact orders leave of each side, by `is_bid`
+        let mut left = [volume - exact_asks, volume - exact_bids];
+        let mut fills = chosen
+            .iter()
+            .map(|i| (*i, orders[*i].cap_t0))
+            .collect::<Vec<_>>();
+        for i in partial {
+            let left = &mut left[orders[*i].is_bid as usize];
+            let t0 = orders[*i].cap_t0.min(*left);
+            if t0 > 0 {
+                *left -= t0;
+                fills.push((*i, t0));
+            }
+        }
+        let amm = amm.map_or(0, |(is_bid, ..)| left[is_bid as usize]);
+        let surplus = fills.iter().fold(0u128, |sum, (i, t0)| {
+            sum.saturating_add(Self::improvement(&orders[*i], price).quantity(*t0, false))
+        });
+
+        Some(Fill { orders: fills, amm, surplus, volume })
+    }
+
+    /// Solves the fills at a fixed clearing price. `None` if nothing can be
+    /// filled or the rounded fills can't be balanced.
+    fn solve_at(&self, price: Ray) -> Option<Candidate> {
+        let orders = self.crossing_orders(price);
+        let amm = self.amm_capacity(price);
+        if !orders.iter().any(|o| o.is_bid) && !matches!(amm, Some((true, ..))) {
+            return None
+        }
+        if !orders.iter().any(|o| !o.is_bid) && !matches!(amm, Some((false, ..))) {
+            return None
+        }
+
+        // most to gain first, the position in the book breaks ties
+        let by_gain = |a: &usize, b: &usize| {
+            let (a, b) = (&orders[*a], &orders[*b]);
+            Self::improvement(b, price)
+                .cmp(&Self::improvement(a, price))
+                .then_with(|| (a.is_bid, a.idx).cmp(&(b.is_bid, b.idx)))
+        };
+        let (mut exact, mut partial): (Vec<_>, Vec<_>) =
+            (0..orders.len()).partition(|i| !orders[*i].order.is_partial());
+        exact.sort_by(by_gain);
+        exact.truncate(MAX_EXACT_ORDERS);
+        partial.sort_by(by_gain);
+
+        let best = (0..1u32 << exact.len())
+            .filter_map(|chosen| Self::fill_with(price, &orders, &exact, chosen, &partial, amm))
+            .reduce(|best, fill| {
+                if (fill.surplus, fill.volume) > (best.surplus, best.volume) {
+                    fill
+                } else {
+                    best
+                }
+            })?;
+
+        let mut fills = best
+            .orders
+            .iter()
+            .map(|&(i, t0)| {
+                let o = &orders[i];
+                let filled = if o.order.is_partial() {
+                    Self::own_quantity(o.order, t0, price)
+                } else {
+                    o.order.max_q()
+                };
+                (o, filled)
+            })
+            .collect::<Vec<_>>();
+        let mut amm_t0 = best.amm;
+
+        self.rebalance(price, &mut fills, amm, &mut amm_t0)?;
+
+        let mut surplus = 0u128;
+        let mut volume = 0;
+        for (o, filled) in &fills {
+            let t0 = Self::t0_quantity(o.order, *filled, price);
+            surplus = surplus.saturating_add(Self::improvement(o, price).quantity(t0, false));
+            if o.is_bid {
+                volume += t0;
+            }
+        }
+        if matches!(amm, Some((true, ..))) {
+            volume += amm_t0;
+        }
+
+        Some(Candidate {
+            price,
+            fills: fills
+                .into_iter()
+                .filter(|(_, filled)| *filled > 0)
+                .map(|(o, filled)| (o.is_bid, o.idx, filled))
+                .collect(),
+            amm: amm
+                .filter(|_| amm_t0 > 0)
+                .map(|(_, _, direction)| (amm_t0, direction)),
+            surplus,
+            volume
+        })
+    }
+
+    /// Converting the fills of orders in T1 rounds, which leaves the sides a
+    /// few units apart. Trims the AMM and then the partial fills with the
+    /// least surplus on the heavier side until both trade the same T0.
+    fn rebalance(
+        &self,
+        price: Ray,
+        fills: &mut [(&Crossing, u128)],
+        amm: Option<(bool, u128, Direction)>,
+        amm_t0: &mut u128
+    ) -> Option<()> {
+        // least surplus first on both sides
+        let mut by_surplus = (0..fills.len()).collect::<Vec<_>>();
+        by_surplus.sort_by(|a, b| {
+            let (a, b) = (fills[*a].0, fills[*b].0);
+            let (limit_a, limit_b) =
+                (a.order.price_for_book_side(a.is_bid), b.order.price_for_book_side(b.is_bid));
+            a.is_bid.cmp(&b.is_bid).then_with(|| {
+                if a.is_bid {
+                    limit_a.cmp(&limit_b)
+                } else {
+                    limit_b.cmp(&limit_a)
+                }
+            })
+        });
+
+        for _ in 0..REBALANCE_ROUNDS {
+            let side_t0 = |is_bid: bool, fills: &[(&Crossing, u128)], amm_t0: u128| {
+                let amm =
+                    if matches!(amm, Some((side, ..)) if side == is_bid) { amm_t0 } else { 0 };
+                fills
+                    .iter()
+                    .filter(|(o, _)| o.is_bid == is_bid)
+                    .map(|(o, filled)| Self::t0_quantity(o.order, *filled, price))
+                    .sum::<u128>()
+                    + amm
+            };
+            let (bids, asks) = (side_t0(true, &*fills, *amm_t0), side_t0(false, &*fills, *amm_t0));
+            if bids == asks {
+                return Some(())
+            }
+            let heavy = bids > asks;
+            let mut excess = bids.abs_diff(asks);
+
+            if matches!(amm, Some((side, ..)) if side == heavy) {
+                let cut = excess.min(*amm_t0);
+                *amm_t0 -= cut;
+                excess -= cut;
+            }
+            for idx in &by_surplus {
+                if excess == 0 {
+                    break
+                }
+                let (o, filled) = &mut fills[*idx];
+                if o.is_bid != heavy || !o.order.is_partial() || *filled == 0 {
+                    continue
+                }
+                let t0 = Self::t0_quantity(o.order, *filled, price);
+                let keep = t0 - excess.min(t0);
+                *filled = Self::own_quantity(o.order, keep, price);
+                excess -= t0 - Self::t0_quantity(o.order, *filled, price).min(t0);
+            }
+        }
+
+        trace!(?price, "unable to balance the rounded fills");
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::primitive::PoolId;
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+    use crate::strategy::{MatchingStrategy, SimpleCheckpointStrategy};
+
+    fn ray(price: &str) -> Ray {
+        price.parse().unwrap()
+    }
+
+    fn partial(is_bid: bool, amount: u128, price: &str) -> BookOrder {
+        let builder = UserOrderBuilder::new().partial().amount(amount);
+        let builder = if is_bid {
+            builder.bid().bid_min_price(ray(price))
+        } else {
+            builder.ask().min_price(ray(price))
+        };
+        builder.with_storage().is_bid(is_bid).build()
+    }
+
+    fn exact_t0(is_bid: bool, amount: u128, price: &str) -> BookOrder {
+        let builder = UserOrderBuilder::new()
+            .exact()
+            .amount(amount)
+            .exact_in(!is_bid);
+        let builder = if is_bid {
+            builder.bid().bid_min_price(ray(price))
+        } else {
+            builder.ask().min_price(ray(price))
+        };
+        builder.with_storage().is_bid(is_bid).build()
+    }
+
+    /// Surplus in T1 the way [`ClearingReport`] counts it.
+    ///
+    /// [`ClearingReport`]: angstrom_types::orders::ClearingReport
+    fn surplus(book: &OrderBook, solution: &PoolSolution) -> u128 {
+        let sides = book
+            .bids()
+            .iter()
+            .map(|order| (order, true))
+            .chain(book.asks().iter().map(|order| (order, false)));
+
+        sides
+            .zip(&solution.limit)
+            .map(|((order, is_bid), outcome)| {
+                let limit = order.price_for_book_side(is_bid);
+                let improvement = if is_bid {
+                    limit.0.saturating_sub(solution.ucp.0)
+                } else {
+                    solution.ucp.0.saturating_sub(limit.0)
+                };
+                Ray(improvement).quantity(outcome.fill_amount(order.max_q()), false)
+            })
+            .sum()
+    }
+
+    fn traded_t0(book: &OrderBook, matcher: &LpSurplusMatcher, is_bid: bool) -> u128 {
+        let (orders, outcomes) = if is_bid {
+            (book.bids(), &matcher.bid_outcomes)
+        } else {
+            (book.asks(), &matcher.ask_outcomes)
+        };
+        let price = matcher.price().unwrap();
+        orders
+            .iter()
+            .zip(outcomes)
+            .map(|(order, outcome)| {
+                let filled = OrderOutcome { id: order.order_id, outcome: *outcome }
+                    .fill_amount(order.max_q());
+                LpSurplusMatcher::t0_quantity(order, filled, price)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn leaves_uncrossed_book_unfilled() {
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![partial(true, 100, "1")],
+            vec![partial(false, 100, "2")],
+            None
+        );
+        let matcher = LpSurplusMatcher::solve(&book);
+
+        assert!(matcher.price().is_none());
+        let solution = matcher.solution(None);
+        assert_eq!(solution.ucp, Ray::default());
+        assert!(solution.limit.iter().all(|outcome| !outcome.is_filled()));
+    }
+
+    #[test]
+    fn fills_the_cheapest_asks() {
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![exact_t0(true, 100, "3")],
+            vec![partial(false, 100, "1"), partial(false, 100, "2")],
+            None
+        );
+        let matcher = LpSurplusMatcher::solve(&book);
+
+        assert_eq!(matcher.bid_outcomes, vec![OrderFillState::CompleteFill]);
+        let cheap = book
+            .asks()
+            .iter()
+            .position(|o| o.price() == ray("1"))
+            .unwrap();
+        assert_eq!(matcher.ask_outcomes[cheap], OrderFillState::CompleteFill);
+        assert_eq!(matcher.ask_outcomes[1 - cheap], OrderFillState::Unfilled);
+        assert_eq!(traded_t0(&book, &matcher, true), traded_t0(&book, &matcher, false));
+    }
+
+    #[test]
+    fn balances_orders_denominated_in_t1() {
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![partial(true, 1000, "2")],
+            vec![partial(false, 300, "1")],
+            None
+        );
+        let matcher = LpSurplusMatcher::solve(&book);
+
+        assert_eq!(matcher.ask_outcomes, vec![OrderFillState::CompleteFill]);
+        assert!(matches!(matcher.bid_outcomes[0], OrderFillState::PartialFill(_)));
+        assert_eq!(traded_t0(&book, &matcher, true), traded_t0(&book, &matcher, false));
+    }
+
+    #[test]
+    fn fills_the_exact_orders_that_balance() {
+        // the ask with the most to gain can't balance the bid, alone or with
+        // another ask, only the other two together can
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![exact_t0(true, 100, "3")],
+            vec![exact_t0(false, 70, "0.9"), exact_t0(false, 60, "1"), exact_t0(false, 40, "1.5")],
+            None
+        );
+        let matcher = LpSurplusMatcher::solve(&book);
+
+        assert_eq!(matcher.bid_outcomes, vec![OrderFillState::CompleteFill]);
+        for (order, outcome) in book.asks().iter().zip(&matcher.ask_outcomes) {
+            let expected = if order.max_q() == 70 {
+                OrderFillState::Unfilled
+            } else {
+                OrderFillState::CompleteFill
+            };
+            assert_eq!(*outcome, expected);
+        }
+        assert_eq!(traded_t0(&book, &matcher, true), traded_t0(&book, &matcher, false));
+    }
+
+    #[test]
+    fn searches_a_bounded_number_of_exact_orders() {
+        let asks = |count: usize| (0..count).map(|_| exact_t0(false, 10, "1")).collect();
+        let bid = |count: usize| vec![exact_t0(true, 10 * count as u128, "3")];
+
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            bid(MAX_EXACT_ORDERS - 1),
+            asks(MAX_EXACT_ORDERS - 1),
+            None
+        );
+        assert!(LpSurplusMatcher::solve(&book).price().is_some());
+
+        // the bid needs every ask, which with the bid is one more exact order
+        // than are searched
+        let count = MAX_EXACT_ORDERS;
+        let book = OrderBook::new(PoolId::random(), None, bid(count), asks(count), None);
+        assert!(LpSurplusMatcher::solve(&book).price().is_none());
+    }
+
+    #[test]
+    fn matches_the_volume_matcher_surplus() {
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![exact_t0(true, 100, "3"), exact_t0(true, 50, "1.5")],
+            vec![partial(false, 100, "1"), partial(false, 100, "2")],
+            None
+        );
+
+        let volume = SimpleCheckpointStrategy::run(&book).unwrap().solution(None);
+        let lp = LpSurplusMatcher::solve(&book).solution(None);
+
+        // both clear at a uniform price, the surplus is only split differently
+        // between the sides, up to rounding of the inverted bid prices
+        let (volume, lp) = (surplus(&book, &volume), surplus(&book, &lp));
+        assert!(lp + 2 >= volume, "lp surplus {lp} below volume matcher surplus {volume}");
+        assert!(lp > 0);
+    }
+}
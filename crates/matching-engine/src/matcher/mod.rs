@@ -1,9 +1,27 @@
+mod config;
+mod lp;
+mod ring;
 mod volume;
+pub use angstrom_types::matching::SelfTradePolicy;
 use angstrom_types::{
     matching::SqrtPriceX96,
-    orders::{OrderPrice, OrderVolume}
+    orders::{ClearingStep, OrderPrice, OrderVolume}
 };
-pub use volume::VolumeFillMatcher;
+pub use config::solve_with_config;
+pub use lp::LpSurplusMatcher;
+pub use ring::{RingMatcher, MAX_RING_LEGS};
+pub use volume::{solution_clearing_report, VolumeFillMatcher};
+
+/// Algorithm the book of a pool is matched with. Every validator has to use
+/// the same one for a pool, otherwise they don't agree on its solution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatcherBackend {
+    /// [`VolumeFillMatcher`] rolled back to its last valid checkpoint
+    #[default]
+    Volume,
+    /// [`LpSurplusMatcher`]
+    Lp
+}
 
 /// Preliminary implementation of a struct that captures all the information
 /// we'd want to get out of a finished match for us to use for heurestics and
@@ -23,5 +41,7 @@ pub struct Solution {
     /// Final AMM price
     pub amm_final_price:   Option<SqrtPriceX96>,
     /// Final average price of execution for the AMM
-    pub amm_average_price: Option<SqrtPriceX96>
+    pub amm_average_price: Option<SqrtPriceX96>,
+    /// Matches that moved the clearing price
+    pub steps:             Vec<ClearingStep>
 }
@@ -0,0 +1,456 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use alloy_primitives::{Address, B256, U256};
+use angstrom_types::{
+    matching::{uniswap::PoolSnapshot, Ray},
+    orders::{OrderFillState, PoolSolution, RingLeg},
+    primitive::PoolId
+};
+
+use crate::book::BookOrder;
+
+/// Longest cycle of orders that is settled as a ring.
+pub const MAX_RING_LEGS: usize = 4;
+
+/// Times the fill of a ring is started over with less from its first order
+/// before a ring that would keep rounding dust is given up on.
+const MAX_FILL_ROUNDS: usize = 3;
+
+/// Settles cycles of orders across pools that share tokens between each
+/// other, A sells X for Y, B sells Y for Z and C sells Z for X.
+///
+/// Only pools whose own book didn't clear take part, so the clearing price of
+/// every pool in a ring is the price of its leg and the amm is left untouched.
+/// As a pool has a single clearing price it settles at most one ring, rings
+/// through a pool another ring already settles in are skipped. Rings are only
+/// settled when they leave a surplus, which the order that closes the ring
+/// receives. Every order receives all of what the next one gives, so no
+/// rounding dust is kept. Only partial orders are used as their quantity is in
+/// the token they give and any amount between their bounds fills them.
+pub struct RingMatcher;
+
+#[derive(Debug, Clone)]
+struct Edge<'a> {
+    order:     &'a BookOrder,
+    pool_id:   PoolId,
+    token_in:  Address,
+    token_out: Address,
+    /// least `token_out` per `token_in` the order accepts
+    rate:      Ray
+}
+
+#[derive(Debug, Clone)]
+struct Ring<'a> {
+    id:      B256,
+    edges:   Vec<Edge<'a>>,
+    /// what every order gives
+    amounts: Vec<u128>,
+    /// clearing price of the pool of every order
+    prices:  Vec<Ray>,
+    /// what every order receives at the price of its pool
+    outputs: Vec<u128>,
+    /// product of the limit prices around the ring, below one ray
+    rate:    Ray
+}
+
+impl RingMatcher {
+    /// Settles the rings found among the unfilled orders of `limit` in the
+    /// solutions of their pools. Pools in a ring are only used by one of them,
+    /// rings with the most surplus per unit are settled first.
+    pub fn settle(
+        limit: &[BookOrder],
+        mut solutions: Vec<PoolSolution>,
+        pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+    ) -> Vec<PoolSolution> {
+        let idle = solutions
+            .iter()
+            .enumerate()
+            .filter(|(_, solution)| Self::is_idle(solution))
+            .map(|(idx, solution)| (solution.id, idx))
+            .collect::<HashMap<_, _>>();
+
+        let edges = Self::best_edges(limit, &solutions, &idle, pools);
+        let mut rings = Self::cycles(&edges)
+            .into_iter()
+            .filter_map(Self::fill)
+            .collect::<Vec<_>>();
+        rings.sort_by(|a, b| a.rate.cmp(&b.rate).then_with(|| a.id.cmp(&b.id)));
+
+        let mut used = HashSet::new();
+        for ring in rings {
+            if let Some(edge) = ring.edges.iter().find(|edge| used.contains(&edge.pool_id)) {
+                tracing::debug!(
+                    ring = ?ring.id,
+                    pool_id = ?edge.pool_id,
+                    "skipping ring through a pool that already settles a ring"
+                );
+                continue
+            }
+            used.extend(ring.edges.iter().map(|edge| edge.pool_id));
+
+            tracing::debug!(ring = ?ring.id, legs = ring.edges.len(), "settling ring trade");
+            for (i, edge) in ring.edges.iter().enumerate() {
+                let solution = &mut solutions[idle[&edge.pool_id]];
+                let fill = if ring.amounts[i] == edge.order.max_q() {
+                    OrderFillState::CompleteFill
+                } else {
+                    OrderFillState::PartialFill(ring.amounts[i])
+                };
+                solution
+                    .limit
+                    .iter_mut()
+                    .filter(|outcome| outcome.id.hash == edge.order.order_id.hash)
+                    .for_each(|outcome| outcome.outcome = fill);
+                solution.ucp = ring.prices[i];
+                solution.ring = Some(RingLeg {
+                    ring:         ring.id,
+                    legs:         ring.edges.len() as u8,
+                    order:        edge.order.order_id.hash,
+                    token_in:     edge.token_in,
+                    token_out:    edge.token_out,
+                    quantity_in:  ring.amounts[i],
+                    quantity_out: ring.outputs[i]
+                });
+            }
+        }
+
+        solutions
+    }
+
+    /// Nothing of the pool was matched, so its price is free to be set by a
+    /// ring.
+    fn is_idle(solution: &PoolSolution) -> bool {
+        solution.amm_quantity.is_none()
+            && solution.searcher.is_none()
+            && solution.ring.is_none()
+            && !solution.limit.iter().any(|outcome| outcome.is_filled())
+    }
+
+    /// The order with the lowest limit price for every direction between two
+    /// tokens, the order hash breaks ties so every validator picks the same.
+    fn best_edges<'a>(
+        limit: &'a [BookOrder],
+        solutions: &[PoolSolution],
+        idle: &HashMap<PoolId, usize>,
+        pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+    ) -> BTreeMap<(Address, Address), Edge<'a>> {
+        let mut edges = BTreeMap::<(Address, Address), Edge<'a>>::new();
+        for order in limit.iter().filter(|order| order.is_partial()) {
+            let Some(idx) = idle.get(&order.pool_id) else { continue };
+            let Some((t0, t1, ..)) = pools.get(&order.pool_id) else { continue };
+            let unfilled = solutions[*idx].limit.iter().any(|outcome| {
+                outcome.id.hash == order.order_id.hash
+                    && outcome.outcome == OrderFillState::Unfilled
+            });
+            if !unfilled {
+                continue
+            }
+
+            let (token_in, token_out) = if order.is_bid { (*t1, *t0) } else { (*t0, *t1) };
+            let edge =
+                Edge { order, pool_id: order.pool_id, token_in, token_out, rate: order.price() };
+            let key = |edge: &Edge| (edge.rate, edge.order.order_id.hash);
+            match edges.get(&(token_in, token_out)) {
+                Some(best) if key(best) <= key(&edge) => {}
+                _ => {
+                    edges.insert((token_in, token_out), edge);
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Every cycle of at least three tokens, starting at its lowest token so
+    /// that each is found once.
+    fn cycles<'a>(edges: &BTreeMap<(Address, Address), Edge<'a>>) -> Vec<Vec<Edge<'a>>> {
+        let mut out_edges = BTreeMap::<Address, Vec<&Edge<'a>>>::new();
+        edges
+            .values()
+            .for_each(|edge| out_edges.entry(edge.token_in).or_default().push(edge));
+
+        let mut cycles = Vec::new();
+        for start in out_edges.keys() {
+            let mut path = Vec::new();
+            Self::walk(*start, *start, &out_edges, &mut path, &mut cycles);
+        }
+
+        cycles
+    }
+
+    fn walk<'a>(
+        start: Address,
+        token: Address,
+        out_edges: &BTreeMap<Address, Vec<&Edge<'a>>>,
+        path: &mut Vec<Edge<'a>>,
+        cycles: &mut Vec<Vec<Edge<'a>>>
+    ) {
+        for edge in out_edges.get(&token).into_iter().flatten() {
+            if edge.token_out == start {
+                if path.len() + 1 >= 3 {
+                    let mut cycle = path.clone();
+                    cycle.push((*edge).clone());
+                    cycles.push(cycle);
+                }
+                continue
+            }
+            let visited = path.iter().any(|step| step.token_in == edge.token_out);
+            if edge.token_out < start || visited || path.len() + 1 >= MAX_RING_LEGS {
+                continue
+            }
+
+            path.push((*edge).clone());
+            Self::walk(start, edge.token_out, out_edges, path, cycles);
+            path.pop();
+        }
+    }
+
+    /// The largest fill of the cycle, every order but the last one receives
+    /// its limit price and the last one the rest. `None` if the cycle leaves
+    /// no surplus, can't fill every order within its bounds or would keep
+    /// rounding dust.
+    fn fill(edges: Vec<Edge<'_>>) -> Option<Ring<'_>> {
+        let one = Ray::scale_to_ray(U256::from(1));
+        let rate = edges.iter().fold(one, |acc, edge| acc.mul_ray(edge.rate));
+        if rate >= one {
+            return None
+        }
+
+        // what the first order can give without overfilling any order after it
+        let mut first = edges[0].order.max_q();
+        for i in 1..edges.len() {
+            let bound = edges[..i]
+                .iter()
+                .rev()
+                .fold(edges[i].order.max_q(), |amount, edge| {
+                    edge.rate.inverse_quantity(amount, false)
+                });
+            first = first.min(bound);
+        }
+
+        // dust the last order isn't paid is given back to the first one by
+        // starting over with what the last order receives
+        let mut fill = None;
+        for _ in 0..MAX_FILL_ROUNDS {
+            let (amounts, prices, outputs) = Self::fill_from(&edges, first)?;
+            let received = outputs[outputs.len() - 1];
+            if received == amounts[0] {
+                fill = Some((amounts, prices, outputs));
+                break
+            }
+            first = received;
+        }
+        let (amounts, prices, outputs) = fill?;
+
+        let last = edges.len() - 1;
+        if outputs[last] <= edges[last].rate.quantity(amounts[last], true) {
+            return None
+        }
+
+        let hashes = edges
+            .iter()
+            .map(|edge| edge.order.order_id.hash)
+            .collect::<Vec<_>>();
+        Some(Ring { id: RingLeg::ring_id(&hashes), edges, amounts, prices, outputs, rate })
+    }
+
+    /// What every order gives and receives, and the price of its leg, when the
+    /// first order gives `first`. Every order gives exactly what the order
+    /// before it receives, the dust of a leg that can't be priced to pay all of
+    /// it is given back to the next order.
+    fn fill_from(edges: &[Edge<'_>], first: u128) -> Option<(Vec<u128>, Vec<Ray>, Vec<u128>)> {
+        let mut amounts = vec![first];
+        let mut prices = Vec::with_capacity(edges.len());
+        let mut outputs = Vec::with_capacity(edges.len());
+        for (i, edge) in edges.iter().enumerate() {
+            let given = amounts[i];
+            if given == 0 || given < edge.order.min_q() {
+                return None
+            }
+
+            let received = match edges.get(i + 1) {
+                Some(next) => edge.rate.quantity(given, true).min(next.order.max_q()),
+                None => amounts[0]
+            };
+            if received == 0 {
+                return None
+            }
+            let (price, output) = Self::price(edge, given, received);
+            if output < edge.rate.quantity(given, true) {
+                return None
+            }
+            if i + 1 < edges.len() {
+                amounts.push(output);
+            }
+            prices.push(price);
+            outputs.push(output);
+        }
+
+        Some((amounts, prices, outputs))
+    }
+
+    /// The price of the leg that pays the order `received` for `given`, or as
+    /// close below it as the precision of the price allows.
+    fn price(edge: &Edge<'_>, given: u128, received: u128) -> (Ray, u128) {
+        let leg = |favor_order: bool| {
+            if edge.order.is_bid {
+                let price = Ray::calc_price_generic(received, given, !favor_order);
+                (price, price.inverse_quantity(given, false))
+            } else {
+                let price = Ray::calc_price_generic(given, received, favor_order);
+                (price, price.mul_quantity(U256::from(given)).saturating_to())
+            }
+        };
+
+        // rounding in favor of the order pays it all of `received` unless the
+        // rounding error of the price outgrows a unit of `given`
+        let (price, output) = leg(true);
+        if output <= received {
+            (price, output)
+        } else {
+            leg(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::orders::OrderOutcome;
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    fn token(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    /// Pools between 1/2, 2/3 and 3/1, all at a price of one.
+    fn pools() -> HashMap<PoolId, (Address, Address, PoolSnapshot, u16)> {
+        [(1, 2), (2, 3), (1, 3)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (t0, t1))| {
+                (
+                    PoolId::repeat_byte(i as u8 + 1),
+                    (token(t0), token(t1), PoolSnapshot::default(), i as u16)
+                )
+            })
+            .collect()
+    }
+
+    /// Partial order giving `amount` that takes at least `price` of the other
+    /// token for each.
+    fn order(pool: u8, is_bid: bool, amount: u128, price: &str) -> BookOrder {
+        let builder = UserOrderBuilder::new()
+            .partial()
+            .amount(amount)
+            .min_price(price.parse().unwrap());
+        let builder = if is_bid { builder.bid() } else { builder.ask() };
+        builder
+            .with_storage()
+            .pool_id(PoolId::repeat_byte(pool))
+            .is_bid(is_bid)
+            .build()
+    }
+
+    fn idle_solutions(limit: &[BookOrder]) -> Vec<PoolSolution> {
+        pools()
+            .into_keys()
+            .map(|id| PoolSolution {
+                id,
+                limit: limit
+                    .iter()
+                    .filter(|order| order.pool_id == id)
+                    .map(|order| OrderOutcome {
+                        id:      order.order_id,
+                        outcome: OrderFillState::Unfilled
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn ring_legs(solutions: &[PoolSolution]) -> Vec<&RingLeg> {
+        solutions
+            .iter()
+            .filter_map(|solution| solution.ring.as_ref())
+            .collect()
+    }
+
+    /// 1 -> 2 in the 1/2 pool, 2 -> 3 in the 2/3 pool and 3 -> 1 in the 1/3
+    /// pool.
+    fn ring(last_price: &str) -> Vec<BookOrder> {
+        vec![
+            order(1, false, 1_000_000, "0.9"),
+            order(2, false, 2_000_000, "0.9"),
+            order(3, true, 2_000_000, last_price),
+        ]
+    }
+
+    #[test]
+    fn settles_a_ring_with_surplus() {
+        let limit = ring("0.9");
+        let solutions = RingMatcher::settle(&limit, idle_solutions(&limit), &pools());
+
+        let legs = ring_legs(&solutions);
+        assert_eq!(legs.len(), 3);
+        assert!(legs
+            .iter()
+            .all(|leg| leg.ring == legs[0].ring && leg.legs == 3));
+        for solution in &solutions {
+            assert!(solution.amm_quantity.is_none());
+            assert!(solution.limit.iter().all(|outcome| outcome.is_filled()));
+        }
+
+        // every token is paid out exactly what is paid in, no dust is kept
+        let mut balances = HashMap::<Address, i128>::new();
+        for leg in legs {
+            *balances.entry(leg.token_in).or_default() += leg.quantity_in as i128;
+            *balances.entry(leg.token_out).or_default() -= leg.quantity_out as i128;
+        }
+        assert!(balances.values().all(|balance| *balance == 0), "{balances:?}");
+    }
+
+    #[test]
+    fn settles_one_ring_per_pool() {
+        // the same pools in the other direction: 1 -> 3, 3 -> 2 and 2 -> 1
+        let mut limit = ring("0.9");
+        limit.extend([
+            order(3, false, 1_000_000, "0.9"),
+            order(2, true, 2_000_000, "0.9"),
+            order(1, true, 2_000_000, "0.9")
+        ]);
+        let solutions = RingMatcher::settle(&limit, idle_solutions(&limit), &pools());
+
+        let legs = ring_legs(&solutions);
+        assert_eq!(legs.len(), 3);
+        assert!(legs.iter().all(|leg| leg.ring == legs[0].ring));
+        for solution in &solutions {
+            let filled = solution.limit.iter().filter(|outcome| outcome.is_filled());
+            assert_eq!(filled.count(), 1);
+        }
+    }
+
+    #[test]
+    fn skips_rings_without_surplus() {
+        let limit = ring("1.3");
+        let solutions = idle_solutions(&limit);
+        let expected = solutions.clone();
+        assert_eq!(RingMatcher::settle(&limit, solutions, &pools()), expected);
+    }
+
+    #[test]
+    fn skips_pools_that_cleared() {
+        let limit = ring("0.9");
+        let mut solutions = idle_solutions(&limit);
+        let cleared = solutions
+            .iter_mut()
+            .find(|solution| solution.id == PoolId::repeat_byte(2))
+            .unwrap();
+        cleared.limit[0].outcome = OrderFillState::CompleteFill;
+        let expected = solutions.clone();
+
+        assert_eq!(RingMatcher::settle(&limit, solutions, &pools()), expected);
+    }
+}
@@ -1,22 +1,27 @@
 use std::{
     cell::Cell,
-    cmp::{max, Ordering}
+    cmp::{max, Ordering},
+    collections::HashMap
 };
 
-use alloy::primitives::U256;
+use alloy::primitives::{BlockNumber, U256};
+use angstrom_metrics::check_invariants;
 use angstrom_types::{
     matching::{
         uniswap::{Direction, PoolPrice, PoolPriceVec},
-        CompositeOrder, Debt, Ray
+        CheckedMath, CompositeOrder, Debt, Ray
+    },
+    orders::{
+        ClearingReport, ClearingStep, FillRationale, NetAmmOrder, OrderClearing, OrderFillState,
+        OrderOutcome, OrderPrice, PoolSolution
     },
-    orders::{NetAmmOrder, OrderFillState, OrderOutcome, PoolSolution},
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
 use base64::Engine;
 use eyre::eyre;
 use tracing::{debug, info, trace, warn};
 
-use super::Solution;
+use super::{SelfTradePolicy, Solution};
 use crate::book::{order::OrderContainer, BookOrder, OrderBook};
 
 #[derive(Debug)]
@@ -26,6 +31,9 @@ pub enum VolumeFillMatchEndReason {
     BothSidesAMM,
     NoLongerCross,
     ZeroQuantity,
+    /// Both sides belong to the same address and neither of them can be
+    /// cancelled
+    SelfTrade,
     /// This SHOULDN'T happen but I'm using it to clean up problem spots in the
     /// code
     ErrorEncountered
@@ -42,8 +50,39 @@ pub struct VolumeFillMatcher<'a> {
     amm_price:        Option<PoolPrice<'a>>,
     amm_outcome:      Option<NetAmmOrder>,
     results:          Solution,
-    // A checkpoint should never have a checkpoint stored within itself, otherwise this gets gnarly
-    checkpoint:       Option<Box<Self>>
+    self_trade:       Option<SelfTradePolicy>,
+    // Quantity taken out of each order by self-trade prevention, it's part of the fill state
+    // while matching and gets subtracted again when the outcomes are settled
+    bid_decrements:   Vec<u128>,
+    ask_decrements:   Vec<u128>,
+    checkpoint:       Option<Checkpoint<'a>>,
+    /// whether the prices are taken from the ladder of the book
+    price_ladder:     bool
+}
+
+/// The solve state of the matcher at its last good solve.  Only the small
+/// parts of the state are copied, the fills of the book are undone from a log
+/// of the writes made since, so saving a checkpoint doesn't copy the book
+#[derive(Clone)]
+struct Checkpoint<'a> {
+    bid_idx:     usize,
+    ask_idx:     usize,
+    debt:        Option<Debt>,
+    amm_price:   Option<PoolPrice<'a>>,
+    amm_outcome: Option<NetAmmOrder>,
+    /// the results without their steps, which are only ever pushed to
+    results:     Solution,
+    steps:       usize,
+    undo:        Vec<FillUndo>
+}
+
+/// The fill state an order had before a write to it
+#[derive(Clone, Copy, Debug)]
+struct FillUndo {
+    is_bid:    bool,
+    idx:       usize,
+    outcome:   OrderFillState,
+    decrement: u128
 }
 
 impl<'a> VolumeFillMatcher<'a> {
@@ -64,13 +103,33 @@ impl<'a> VolumeFillMatcher<'a> {
             amm_price,
             amm_outcome: None,
             results: Solution::default(),
-            checkpoint: None
+            self_trade: None,
+            bid_decrements: vec![0; book.bids().len()],
+            ask_decrements: vec![0; book.asks().len()],
+            checkpoint: None,
+            price_ladder: true
         };
         // We can checkpoint our initial state as valid
         new_element.save_checkpoint();
         new_element
     }
 
+    /// Converts the prices of the orders on every lookup instead of taking
+    /// them from the [`PriceLadder`](crate::book::PriceLadder) of the book,
+    /// for benchmarking the ladder.
+    #[doc(hidden)]
+    pub fn without_price_ladder(mut self) -> Self {
+        self.price_ladder = false;
+        self
+    }
+
+    /// Applies `policy` whenever a bid and an ask of the same address meet
+    /// instead of matching them
+    pub fn with_self_trade_policy(mut self, policy: Option<SelfTradePolicy>) -> Self {
+        self.self_trade = policy;
+        self
+    }
+
     pub fn results(&self) -> &Solution {
         &self.results
     }
@@ -79,44 +138,281 @@ impl<'a> VolumeFillMatcher<'a> {
         self.debt.as_ref()
     }
 
-    /// Save our current solve state to an internal checkpoint
+    /// Save our current solve state to an internal checkpoint.  A state where a
+    /// partial order is filled below its minimum is never a good solve state
+    /// so it isn't saved
     fn save_checkpoint(&mut self) {
-        let checkpoint = Self {
-            book:         self.book,
-            bid_idx:      self.bid_idx.clone(),
-            bid_outcomes: self.bid_outcomes.clone(),
-            ask_idx:      self.ask_idx.clone(),
-            ask_outcomes: self.ask_outcomes.clone(),
-            debt:         self.debt,
-            amm_price:    self.amm_price.clone(),
-            amm_outcome:  self.amm_outcome.clone(),
-            results:      self.results.clone(),
-            checkpoint:   None
-        };
-        self.checkpoint = Some(Box::new(checkpoint));
+        if self.below_min_fill() {
+            debug!("Partial order below its minimum fill, not saving checkpoint");
+            return
+        }
+        let Solution {
+            price,
+            total_volume,
+            partial_volume,
+            amm_is_bid,
+            amm_volume,
+            amm_final_price,
+            amm_average_price,
+            ref steps
+        } = self.results;
+        // The writes so far are part of the new checkpoint, only the log is reused
+        let mut undo = self
+            .checkpoint
+            .take()
+            .map(|checkpoint| checkpoint.undo)
+            .unwrap_or_default();
+        undo.clear();
+        self.checkpoint = Some(Checkpoint {
+            bid_idx: self.bid_idx.get(),
+            ask_idx: self.ask_idx.get(),
+            debt: self.debt,
+            amm_price: self.amm_price.clone(),
+            amm_outcome: self.amm_outcome.clone(),
+            results: Solution {
+                price,
+                total_volume,
+                partial_volume,
+                amm_is_bid,
+                amm_volume,
+                amm_final_price,
+                amm_average_price,
+                steps: Vec::new()
+            },
+            steps: steps.len(),
+            undo
+        });
     }
 
     /// Spawn a new VolumeFillBookSolver from our checkpoint
     pub fn from_checkpoint(&self) -> Option<Self> {
-        self.checkpoint.as_ref().map(|cp| *cp.clone())
+        self.checkpoint.as_ref()?;
+        let mut restored = self.clone();
+        restored.restore_checkpoint();
+        Some(restored)
     }
 
-    /// Restore our checkpoint into this VolumeFillBookSolver - not sure if we
-    /// ever want to do this but we can!
-    #[allow(dead_code)]
+    /// Restore our checkpoint into this VolumeFillBookSolver, the checkpoint
+    /// itself is kept around
     fn restore_checkpoint(&mut self) -> bool {
-        let Some(checkpoint) = self.checkpoint.take() else {
+        let Some(checkpoint) = self.checkpoint.as_mut() else {
             return false;
         };
-        let Self { bid_idx, bid_outcomes, ask_idx, ask_outcomes, amm_price, .. } = *checkpoint;
-        self.bid_idx = bid_idx;
-        self.bid_outcomes = bid_outcomes;
-        self.ask_idx = ask_idx;
-        self.ask_outcomes = ask_outcomes;
-        self.amm_price = amm_price;
+        // Undo the writes newest first so every order ends up with the state
+        // it had at the checkpoint
+        for undo in checkpoint.undo.drain(..).rev() {
+            let (outcomes, decrements) = if undo.is_bid {
+                (&mut self.bid_outcomes, &mut self.bid_decrements)
+            } else {
+                (&mut self.ask_outcomes, &mut self.ask_decrements)
+            };
+            outcomes[undo.idx] = undo.outcome;
+            decrements[undo.idx] = undo.decrement;
+        }
+        self.bid_idx.set(checkpoint.bid_idx);
+        self.ask_idx.set(checkpoint.ask_idx);
+        self.debt = checkpoint.debt;
+        self.amm_price = checkpoint.amm_price.clone();
+        self.amm_outcome = checkpoint.amm_outcome.clone();
+        let mut steps = std::mem::take(&mut self.results.steps);
+        steps.truncate(checkpoint.steps);
+        self.results = Solution { steps, ..checkpoint.results.clone() };
+        true
+    }
+
+    /// Sets the fill state of an order, logging the state it had so the
+    /// checkpoint can undo the write
+    fn set_fill(&mut self, is_bid: bool, idx: usize, outcome: OrderFillState, decrement: u128) {
+        let (outcomes, decrements) = if is_bid {
+            (&mut self.bid_outcomes, &mut self.bid_decrements)
+        } else {
+            (&mut self.ask_outcomes, &mut self.ask_decrements)
+        };
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.undo.push(FillUndo {
+                is_bid,
+                idx,
+                outcome: outcomes[idx],
+                decrement: decrements[idx]
+            });
+        }
+        outcomes[idx] = outcome;
+        decrements[idx] = decrement;
+    }
+
+    /// Sets the outcome of the current order on one side
+    fn set_outcome(&mut self, is_bid: bool, outcome: OrderFillState) {
+        let (idx, decrement) = if is_bid {
+            (self.bid_idx.get(), self.bid_decrements[self.bid_idx.get()])
+        } else {
+            (self.ask_idx.get(), self.ask_decrements[self.ask_idx.get()])
+        };
+        self.set_fill(is_bid, idx, outcome, decrement);
+    }
+
+    /// Adds `quantity` to the fill of the current order on one side
+    fn partial_fill(&mut self, is_bid: bool, quantity: u128) {
+        let outcome = if is_bid {
+            self.bid_outcomes[self.bid_idx.get()]
+        } else {
+            self.ask_outcomes[self.ask_idx.get()]
+        };
+        self.set_outcome(is_bid, outcome.partial_fill(quantity));
+    }
+
+    /// Whether the order currently being filled on either side is a partial
+    /// order that hasn't reached its minimum fill yet.  Only the current
+    /// orders have to be checked as a partially filled order always stays the
+    /// next order on its side until it's complete
+    fn below_min_fill(&self) -> bool {
+        let below =
+            |orders: &[BookOrder], outcomes: &[OrderFillState], decrements: &[u128], idx| {
+                orders
+                    .get(idx)
+                    .zip(outcomes.get(idx))
+                    .is_some_and(|(order, outcome)| {
+                        let filled = outcome
+                            .partial_q()
+                            .unwrap_or_default()
+                            .saturating_sub(decrements[idx]);
+                        order.is_partial() && filled > 0 && filled < order.min_q()
+                    })
+            };
+        below(self.book.bids(), &self.bid_outcomes, &self.bid_decrements, self.bid_idx.get())
+            || below(self.book.asks(), &self.ask_outcomes, &self.ask_decrements, self.ask_idx.get())
+    }
+
+    /// Stops filling the current order on one side, `quantity` only takes that
+    /// much out of the order.  Fills that already happened can't be undone, so
+    /// an order that is partially filled can only be stopped if the fill is a
+    /// valid outcome for it, and an order can only be decremented if it can
+    /// still settle its minimum.  Returns `false` if nothing was changed
+    fn retire(&mut self, is_bid: bool, quantity: Option<u128>) -> bool {
+        let (orders, outcomes, decrements, idx) = if is_bid {
+            (self.book.bids(), &self.bid_outcomes, &self.bid_decrements, self.bid_idx.get())
+        } else {
+            (self.book.asks(), &self.ask_outcomes, &self.ask_decrements, self.ask_idx.get())
+        };
+        let (Some(order), Some(&outcome), Some(&decrement)) =
+            (orders.get(idx), outcomes.get(idx), decrements.get(idx))
+        else {
+            return false
+        };
+        let filled = match outcome {
+            OrderFillState::Unfilled => 0,
+            OrderFillState::PartialFill(q) => q,
+            OrderFillState::CompleteFill
+            | OrderFillState::Killed
+            | OrderFillState::OutsidePriceBand => return false
+        };
+        let remaining = order.max_q().saturating_sub(filled);
+        let taken = quantity.map_or(remaining, |q| q.min(remaining));
+        let net_filled = filled.saturating_sub(decrement);
+
+        let (outcome, decrement) = if taken < remaining {
+            // Only a partial order can keep matching with less than it asked for, and only
+            // if filling the rest of it still nets out at least its minimum
+            if !order.is_partial() || order.max_q() - (decrement + taken) < order.min_q() {
+                return false
+            }
+            (outcome.partial_fill(taken), decrement + taken)
+        } else if net_filled == 0 {
+            (OrderFillState::Killed, 0)
+        } else if order.is_partial() && net_filled >= order.min_q() {
+            (OrderFillState::CompleteFill, decrement + taken)
+        } else {
+            return false
+        };
+        self.set_fill(is_bid, idx, outcome, decrement);
         true
     }
 
+    /// Applies the self-trade policy to a bid and an ask of the same address
+    fn prevent_self_trade(
+        &mut self,
+        policy: SelfTradePolicy,
+        bid: &OrderContainer,
+        ask: &OrderContainer,
+        bid_q: u128,
+        ask_q: u128
+    ) -> Option<VolumeFillMatchEndReason> {
+        let (
+            OrderContainer::BookOrder { order: bid_order, .. },
+            OrderContainer::BookOrder { order: ask_order, .. }
+        ) = (bid, ask)
+        else {
+            return Some(VolumeFillMatchEndReason::ErrorEncountered)
+        };
+        let (bid_hash, ask_hash) = (bid_order.order_id.hash, ask_order.order_id.hash);
+        debug!(?policy, ?bid_hash, ?ask_hash, "Preventing self-trade");
+        let bid_is_newest = (bid_order.valid_block, bid_hash) > (ask_order.valid_block, ask_hash);
+
+        let retired = match policy {
+            SelfTradePolicy::CancelNewest => {
+                self.retire(bid_is_newest, None) || self.retire(!bid_is_newest, None)
+            }
+            SelfTradePolicy::CancelOldest => {
+                self.retire(!bid_is_newest, None) || self.retire(bid_is_newest, None)
+            }
+            SelfTradePolicy::DecrementBoth if bid_q == ask_q => {
+                let bid_retired = self.retire(true, None);
+                self.retire(false, None) || bid_retired
+            }
+            SelfTradePolicy::DecrementBoth => {
+                let bid_is_larger = bid_q > ask_q;
+                let (larger, smaller) = if bid_is_larger { (bid, ask) } else { (ask, bid) };
+                // The overlap is in T1 if both orders are, otherwise it's in T0 and has to be
+                // converted for an order that is denominated in T1
+                let overlap = bid_q.min(ask_q);
+                let decrement = if !larger.inverse_order() || smaller.inverse_order() {
+                    overlap
+                } else {
+                    larger
+                        .max_t1_for_t0(overlap, self.debt.as_ref())
+                        .unwrap_or_default()
+                };
+                if self.retire(!bid_is_larger, None) {
+                    if !self.retire(bid_is_larger, Some(decrement)) {
+                        self.retire(bid_is_larger, None);
+                    }
+                    true
+                } else {
+                    self.retire(bid_is_larger, None)
+                }
+            }
+        };
+
+        (!retired).then_some(VolumeFillMatchEndReason::SelfTrade)
+    }
+
+    /// Outcomes of one side with the self-trade decrements taken out again
+    fn settled_outcomes(&self, is_bid: bool) -> Vec<OrderFillState> {
+        let (orders, outcomes, decrements) = if is_bid {
+            (self.book.bids(), &self.bid_outcomes, &self.bid_decrements)
+        } else {
+            (self.book.asks(), &self.ask_outcomes, &self.ask_decrements)
+        };
+        orders
+            .iter()
+            .zip(outcomes)
+            .zip(decrements)
+            .map(|((order, outcome), decrement)| {
+                if *decrement == 0 {
+                    return *outcome
+                }
+                let filled = match outcome {
+                    OrderFillState::CompleteFill => order.max_q(),
+                    OrderFillState::PartialFill(q) => *q,
+                    other => return *other
+                };
+                match filled.saturating_sub(*decrement) {
+                    0 => OrderFillState::Killed,
+                    net => OrderFillState::PartialFill(net)
+                }
+            })
+            .collect()
+    }
+
     fn fill_amm(
         amm: &mut PoolPrice<'a>,
         results: &mut Solution,
@@ -147,15 +443,23 @@ impl<'a> VolumeFillMatcher<'a> {
     }
 
     pub fn run_match(&mut self) -> VolumeFillMatchEndReason {
-        // Output our book data so we can do stuff with it
-        let json = serde_json::to_string(self.book).unwrap();
-        let b64_output = base64::prelude::BASE64_STANDARD.encode(json.as_bytes());
-        trace!(data = b64_output, "Raw book data");
+        // Output our book data so we can do stuff with it, serializing a deep book
+        // costs more than matching it so it's only done when it's logged
+        if tracing::enabled!(tracing::Level::TRACE) {
+            let json = serde_json::to_string(self.book).unwrap();
+            let b64_output = base64::prelude::BASE64_STANDARD.encode(json.as_bytes());
+            trace!(data = b64_output, "Raw book data");
+        }
         // Run our match over and over until we get an end reason
         let mut i: usize = 0;
         loop {
             if let Some(r) = self.single_match() {
                 tracing::debug!(?r);
+                // Nothing else can top up the partial order, so its fill is rejected
+                if self.below_min_fill() {
+                    debug!("Partial order can't reach its minimum fill, restoring checkpoint");
+                    self.restore_checkpoint();
+                }
                 return r
             }
             i += 1;
@@ -166,25 +470,33 @@ impl<'a> VolumeFillMatcher<'a> {
     }
 
     pub fn single_match(&mut self) -> Option<VolumeFillMatchEndReason> {
-        tracing::info!("single match");
+        trace!("single match");
+        let book = self.book;
+        let ladder = self.price_ladder.then(|| book.price_ladder());
+        let (bid_prices, ask_prices) = (
+            ladder.map(|ladder| ladder.bids.as_slice()),
+            ladder.map(|ladder| ladder.asks.as_slice())
+        );
         // Get the bid order
-        let Some(bid) = Self::next_order(
+        let Some(bid) = Self::next_order_priced(
             true,
             &self.bid_idx,
             &mut self.debt,
             self.amm_price.as_ref(),
             self.book.bids(),
+            bid_prices,
             &self.bid_outcomes
         ) else {
             return Some(VolumeFillMatchEndReason::NoMoreBids);
         };
         // Get the ask order
-        let Some(ask) = Self::next_order(
+        let Some(ask) = Self::next_order_priced(
             false,
             &self.ask_idx,
             &mut self.debt,
             self.amm_price.as_ref(),
             self.book.asks(),
+            ask_prices,
             &self.ask_outcomes
         ) else {
             return Some(VolumeFillMatchEndReason::NoMoreAsks)
@@ -211,13 +523,20 @@ impl<'a> VolumeFillMatcher<'a> {
 
         debug!(bid_q, ask_q, bid_price = ?bid.price(), ask_price = ?ask.price(), "Bid and ask stats");
 
+        // Orders of the same address are never matched against each other
+        if let Some(policy) = self.self_trade {
+            if matches!((bid.id(), ask.id()), (Some(b), Some(a)) if b.address == a.address) {
+                return self.prevent_self_trade(policy, &bid, &ask, bid_q, ask_q)
+            }
+        }
+
         // Check to see if we have a 0-quantity ask and need to do an ask-side fill
         // This is only applicable if our ask order has the debt in it
         if ask_q == 0 && ask.is_debt() {
             debug!("Executing ask-side backmatch");
 
             // Ind our next available order
-            let Some(next_ask) = Self::next_order(
+            let Some(next_ask) = Self::next_order_priced(
                 false,
                 &self.ask_idx,
                 // Deliberately no debt here, we want what the next available order would be
@@ -225,6 +544,7 @@ impl<'a> VolumeFillMatcher<'a> {
                 &mut None,
                 self.amm_price.as_ref(),
                 self.book.asks(),
+                ask_prices,
                 &self.ask_outcomes
             ) else {
                 return Some(VolumeFillMatchEndReason::NoMoreAsks);
@@ -337,10 +657,16 @@ impl<'a> VolumeFillMatcher<'a> {
                 Ordering::Equal => {
                     debug!("Equal match quantities");
                     // We annihilated in which case the debt price has moved to the next_ask price
-                    self.results.price = Some(next_ask.price());
+                    Self::record_price(
+                        &mut self.results,
+                        next_ask.price(),
+                        &bid,
+                        &next_ask,
+                        matched
+                    );
                     // Mark as filled if non-AMM order
                     if !next_ask.is_amm() && !next_ask.is_composite() {
-                        self.ask_outcomes[self.ask_idx.get()] = OrderFillState::CompleteFill
+                        self.set_outcome(false, OrderFillState::CompleteFill)
                     }
                     // Set the Debt's current price to the target price
                     self.debt = self.debt.map(|d| d.set_price(next_ask.price().into()));
@@ -352,13 +678,18 @@ impl<'a> VolumeFillMatcher<'a> {
                     // Our next order is greater than our debt.  The debt has been moved to next_ask
                     // price without consuming the entirety of next_ask
                     // The end point is our next ask's price
-                    self.results.price = Some(next_ask.price());
+                    Self::record_price(
+                        &mut self.results,
+                        next_ask.price(),
+                        &bid,
+                        &next_ask,
+                        matched
+                    );
                     // Set the Debt's current price to the target price
                     self.debt = self.debt.map(|d| d.set_price(next_ask.price().into()));
                     // Set our order outcome as partially filled
                     if !next_ask.is_amm() && !next_ask.is_composite() {
-                        self.ask_outcomes[self.ask_idx.get()] =
-                            self.ask_outcomes[self.ask_idx.get()].partial_fill(matched);
+                        self.partial_fill(false, matched);
                     }
                     // This is not a valid end state because next_ask is not
                     // completely filled
@@ -370,12 +701,18 @@ impl<'a> VolumeFillMatcher<'a> {
                     if let Some(cur_debt) = self.debt.as_mut() {
                         let new_debt = cur_debt.partial_fill(matched);
                         // Our new final price is the last moved price of our debt
-                        self.results.price = Some(new_debt.price().into());
+                        Self::record_price(
+                            &mut self.results,
+                            new_debt.price().into(),
+                            &bid,
+                            &next_ask,
+                            matched
+                        );
                         *cur_debt = new_debt;
                     }
                     // Mark as filled if non-AMM order
                     if !next_ask.is_amm() && !next_ask.is_composite() {
-                        self.ask_outcomes[self.ask_idx.get()] = OrderFillState::CompleteFill
+                        self.set_outcome(false, OrderFillState::CompleteFill)
                     }
                     // This is NOT a good solve state - if we didn't backfill
                     // all the way we are unstable beacuse our final price isn't
@@ -403,14 +740,14 @@ impl<'a> VolumeFillMatcher<'a> {
 
         // --- Instrumentation for benchmarking needs updating ---
         // Store the amount we matched
-        self.results.total_volume += matched;
+        self.results.total_volume = self.results.total_volume.saturating_add(matched);
 
         // Record partial fills
         if bid.is_partial() {
-            self.results.partial_volume.0 += matched;
+            self.results.partial_volume.0 = self.results.partial_volume.0.saturating_add(matched);
         }
         if ask.is_partial() {
-            self.results.partial_volume.1 += matched;
+            self.results.partial_volume.1 = self.results.partial_volume.1.saturating_add(matched);
         }
         // --- End instrumentation ---
 
@@ -437,10 +774,12 @@ impl<'a> VolumeFillMatcher<'a> {
                     self.debt.unwrap().freed_t0(matched)
                 } else {
                     // Move the AMM by the portion of the matched T0
-                    // Can unwrap here as we've checked to be sure the order is valid
                     let quantities = a_o.composite_t0_quantities(matched, direction);
                     debug!(quantities = ?quantities, "Found mixed quantities");
-                    quantities.0.unwrap()
+                    let Some(quantity) = quantities.0 else {
+                        return Some(VolumeFillMatchEndReason::ErrorEncountered);
+                    };
+                    quantity
                 };
                 if Self::fill_amm(
                     amm,
@@ -464,17 +803,18 @@ impl<'a> VolumeFillMatcher<'a> {
         } else {
             // Our matched quantity is in T0 so we have to convert it into the appropriate
             // T1 quantity for our book order
-            match (bid.inverse_order(), ask.inverse_order()) {
+            let t1 = match (bid.inverse_order(), ask.inverse_order()) {
                 // For an inverse bid the listed quantity is the T1
-                (true, false) => bid
-                    .max_t1_for_t0(matched, self.debt.as_ref())
-                    .expect("Somehow no T1 available"),
+                (true, false) => bid.max_t1_for_t0(matched, self.debt.as_ref()),
                 // For an inverse ask the listed quantity is the
-                (false, true) => ask
-                    .max_t1_for_t0(matched, self.debt.as_ref())
-                    .expect("Somehow no T1 available"),
-                _ => 0
-            }
+                (false, true) => ask.max_t1_for_t0(matched, self.debt.as_ref()),
+                _ => Some(0)
+            };
+            // the t1 of an order at the edge of the range doesn't fit
+            let Some(t1) = t1 else {
+                return Some(VolumeFillMatchEndReason::ErrorEncountered);
+            };
+            t1
         };
 
         // Adjust our debt
@@ -502,7 +842,9 @@ impl<'a> VolumeFillMatcher<'a> {
                     }
                 }
             } else {
-                let quantity = d_o.composite_t0_quantities(matched, direction).1.unwrap();
+                let Some(quantity) = d_o.composite_t0_quantities(matched, direction).1 else {
+                    return Some(VolumeFillMatchEndReason::ErrorEncountered);
+                };
                 if let Some(d) = self.debt.as_mut() {
                     *d = d.partial_fill(quantity);
                 }
@@ -530,18 +872,23 @@ impl<'a> VolumeFillMatcher<'a> {
 
                 // If we have a debt price, this is our current price, otherwise we get a price
                 // from our order outcomes
-                let new_price = self
-                    .debt
-                    .map(|d| d.price())
-                    .unwrap_or_else(|| (*(ask.price() + bid.price()) / U256::from(2)).into());
-                self.results.price = Some(new_price.into());
+                let midpoint = || {
+                    let sum = (*ask.price())
+                        .try_add(*bid.price(), "midpoint price")
+                        .ok()?;
+                    Some(Ray::from(sum / U256::from(2)))
+                };
+                let Some(new_price) = self.debt.map(|d| d.price()).or_else(midpoint) else {
+                    return Some(VolumeFillMatchEndReason::ErrorEncountered);
+                };
+                Self::record_price(&mut self.results, new_price.into(), &bid, &ask, matched);
 
                 // Mark book orders as CompletelyFilled
                 if ask.is_book() {
-                    self.ask_outcomes[self.ask_idx.get()] = OrderFillState::CompleteFill
+                    self.set_outcome(false, OrderFillState::CompleteFill)
                 }
                 if bid.is_book() {
-                    self.bid_outcomes[self.bid_idx.get()] = OrderFillState::CompleteFill
+                    self.set_outcome(true, OrderFillState::CompleteFill)
                 }
 
                 // Take a snapshot as a good solve state
@@ -551,16 +898,15 @@ impl<'a> VolumeFillMatcher<'a> {
             }
             Ordering::Greater => {
                 debug!("Greater than match");
-                self.results.price = Some(bid.price());
+                Self::record_price(&mut self.results, bid.price(), &bid, &ask, matched);
                 // Ask was completely filled, remainder bid
                 if ask.is_book() {
-                    self.ask_outcomes[self.ask_idx.get()] = OrderFillState::CompleteFill
+                    self.set_outcome(false, OrderFillState::CompleteFill)
                 }
                 // Set our bid outcome to be partial
                 if bid.is_book() {
                     let partial_q = if bid.inverse_order() { t1_matched } else { matched };
-                    self.bid_outcomes[self.bid_idx.get()] =
-                        self.bid_outcomes[self.bid_idx.get()].partial_fill(partial_q);
+                    self.partial_fill(true, partial_q);
                     // A partial fill of a partial-safe order is checkpointable
                     if bid.is_partial() {
                         self.save_checkpoint();
@@ -572,16 +918,15 @@ impl<'a> VolumeFillMatcher<'a> {
             }
             Ordering::Less => {
                 debug!("Less than match");
-                self.results.price = Some(ask.price());
+                Self::record_price(&mut self.results, ask.price(), &bid, &ask, matched);
                 // Bid was completely filled, remainder ask
                 if bid.is_book() {
-                    self.bid_outcomes[self.bid_idx.get()] = OrderFillState::CompleteFill
+                    self.set_outcome(true, OrderFillState::CompleteFill)
                 }
                 // Set our ask outcome to be partial
                 if ask.is_book() {
                     let partial_q = if ask.inverse_order() { t1_matched } else { matched };
-                    self.ask_outcomes[self.ask_idx.get()] =
-                        self.ask_outcomes[self.ask_idx.get()].partial_fill(partial_q);
+                    self.partial_fill(false, partial_q);
                     // A partial fill of a partial-safe order is checkpointable
                     if ask.is_partial() {
                         self.save_checkpoint();
@@ -596,6 +941,23 @@ impl<'a> VolumeFillMatcher<'a> {
         None
     }
 
+    /// Sets the clearing price and records the match that moved it there
+    fn record_price(
+        results: &mut Solution,
+        price: OrderPrice,
+        bid: &OrderContainer,
+        ask: &OrderContainer,
+        quantity: u128
+    ) {
+        results.price = Some(price);
+        results.steps.push(ClearingStep {
+            bid: bid.id().map(|id| id.hash),
+            ask: ask.id().map(|id| id.hash),
+            quantity,
+            price: price.into()
+        });
+    }
+
     /// Returns (bid_q, ask_q)
     fn get_match_quantities(
         bid: &OrderContainer,
@@ -629,6 +991,21 @@ impl<'a> VolumeFillMatcher<'a> {
         amm: Option<&PoolPrice<'a>>,
         book: &'a [BookOrder],
         fill_state: &[OrderFillState]
+    ) -> Option<OrderContainer<'a>> {
+        Self::next_order_priced(bid, book_idx, debt, amm, book, None, fill_state)
+    }
+
+    /// Like [`Self::next_order`], with the prices of `book` taken from its
+    /// [`PriceLadder`](crate::book::PriceLadder) instead of being converted
+    /// on every call.
+    fn next_order_priced(
+        bid: bool,
+        book_idx: &Cell<usize>,
+        debt: &mut Option<Debt>,
+        amm: Option<&PoolPrice<'a>>,
+        book: &'a [BookOrder],
+        prices: Option<&[Ray]>,
+        fill_state: &[OrderFillState]
     ) -> Option<OrderContainer<'a>> {
         debug!(is_bid = bid, debt = ?debt, "Getting next order");
         // If we have a fragment, that takes priority
@@ -655,6 +1032,13 @@ impl<'a> VolumeFillMatcher<'a> {
             cur_idx += 1;
         }
         let book_order = book.get(cur_idx);
+        // both prices are compared several times below, they're looked up once
+        let book_price = book_order.map(|b| {
+            prices
+                .and_then(|prices| prices.get(cur_idx).copied())
+                .unwrap_or_else(|| b.price_for_book_side(bid))
+        });
+        let amm_price = amm.map(|a| a.as_ray());
 
         let this_side_debt = debt.filter(|d| d.bid_side() == bid);
         // If we have some debt that is at a better price, then we're going to be making
@@ -662,9 +1046,8 @@ impl<'a> VolumeFillMatcher<'a> {
         if let Some(mut d) = this_side_debt {
             // Compare our debt to our book price, debt is more advantageous if there's no
             // book order
-            let debt_book_cmp = book_order
-                .map(|b| {
-                    let book_price = b.price_for_book_side(bid);
+            let debt_book_cmp = book_price
+                .map(|book_price| {
                     debug!(debt_price = ?d.price(), book_price = ?book_price, "Comparing debt with book price");
                     if d.validate_and_set_price(book_price) {
                         debug!("Debt and book equal");
@@ -687,7 +1070,7 @@ impl<'a> VolumeFillMatcher<'a> {
                 (Ordering::Equal, _) => (),
                 // Debt == AMM -> CompositeOrder(Debt, Amm) bound to the next book order
                 (_, Ordering::Equal) => {
-                    let bound_price = book_order.map(|b| b.price_for_book_side(bid));
+                    let bound_price = book_price;
                     return Some(OrderContainer::Composite(CompositeOrder::new(
                         *debt,
                         amm.cloned(),
@@ -697,12 +1080,9 @@ impl<'a> VolumeFillMatcher<'a> {
                 // Debt more advantageous than AMM -> CompositeOrder(Debt), bound to the closer of
                 // the AMM or the next book order
                 (_, dac) if dac == more_advantageous => {
-                    let bound_price = book_order
-                        .map(|b| {
-                            amm.map(|a| max(b.price_for_book_side(bid), a.as_ray()))
-                                .unwrap_or_else(|| b.price_for_book_side(bid))
-                        })
-                        .or_else(|| amm.map(|a| a.as_ray()));
+                    let bound_price = book_price
+                        .map(|b| amm_price.map(|a| max(b, a)).unwrap_or(b))
+                        .or(amm_price);
                     return Some(OrderContainer::Composite(CompositeOrder::new(
                         *debt,
                         None,
@@ -721,32 +1101,33 @@ impl<'a> VolumeFillMatcher<'a> {
         }
 
         // If we have an AMM price, see if it takes precedence over our book order
-        amm.and_then(|a| {
-            debug!("Comparing AMM to book");
-            let bound_price = if let Some(o) = book_order {
-                debug!(amm_price = ?a.as_ray(), book_price = ?o.price_for_book_side(bid), "Amm and book prices");
-                if o.price_for_book_side(bid).cmp(&a.as_ray()) != less_advantageous {
-                    debug!("Book order better than AMM");
-                    return None
+        amm.zip(amm_price)
+            .and_then(|(a, a_price)| {
+                debug!("Comparing AMM to book");
+                let bound_price = if let Some(o_price) = book_price {
+                    debug!(amm_price = ?a_price, book_price = ?o_price, "Amm and book prices");
+                    if o_price.cmp(&a_price) != less_advantageous {
+                        debug!("Book order better than AMM");
+                        return None
+                    } else {
+                        debug!("AMM order better than book");
+                    }
+                    Some(o_price)
                 } else {
-                    debug!("AMM order better than book");
-                }
-                Some(o.price_for_book_side(bid))
-            } else {
-                None
-            };
-            // Otherwise, my AMM price is better than my book price and we should make an
-            // AMM order
-            Some(CompositeOrder::new(None, Some(a.clone()), bound_price))
-        })
-        .map(OrderContainer::Composite)
-        .or_else(|| {
-            book_idx.set(cur_idx);
-            book_order.map(|order| {
-                let state = fill_state[cur_idx];
-                OrderContainer::BookOrder { order, state }
+                    None
+                };
+                // Otherwise, my AMM price is better than my book price and we should make an
+                // AMM order
+                Some(CompositeOrder::new(None, Some(a.clone()), bound_price))
+            })
+            .map(OrderContainer::Composite)
+            .or_else(|| {
+                book_idx.set(cur_idx);
+                book_order.map(|order| {
+                    let state = fill_state[cur_idx];
+                    OrderContainer::BookOrder { order, state }
+                })
             })
-        })
     }
 
     pub fn solution(
@@ -754,27 +1135,115 @@ impl<'a> VolumeFillMatcher<'a> {
         searcher: Option<OrderWithStorageData<TopOfBlockOrder>>
     ) -> PoolSolution {
         let limit = self
-            .bid_outcomes
+            .book
+            .bids()
             .iter()
-            .enumerate()
-            .map(|(idx, outcome)| (self.book.bids()[idx].order_id, outcome))
-            .chain(
-                self.ask_outcomes
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, outcome)| (self.book.asks()[idx].order_id, outcome))
-            )
-            .map(|(id, outcome)| OrderOutcome { id, outcome: *outcome })
-            .collect();
+            .zip(self.settled_outcomes(true))
+            .chain(self.book.asks().iter().zip(self.settled_outcomes(false)))
+            .map(|(order, outcome)| OrderOutcome { id: order.order_id, outcome })
+            .collect::<Vec<_>>();
+        check_invariants("order_book", || self.book.outcome_violations(&limit));
+
         let ucp: Ray = self.results.price.map(Into::into).unwrap_or_default();
         PoolSolution {
             id: self.book.id(),
             ucp,
             amm_quantity: self.amm_outcome.clone(),
             searcher,
-            limit
+            limit,
+            ring: None
         }
     }
+
+    /// Explains how the clearing price of the current state was found and why
+    /// every order got its outcome.
+    pub fn clearing_report(&self, block_number: BlockNumber) -> ClearingReport {
+        solution_clearing_report(self.book, &self.solution(None), Some(self), block_number)
+    }
+
+    /// Whether the matching came to `solution`, irrespective of the order its
+    /// outcomes are in.
+    fn reproduces(&self, solution: &PoolSolution) -> bool {
+        let mut ours = self.solution(None);
+        let mut theirs = solution.clone();
+        ours.canonicalize();
+        theirs.canonicalize();
+
+        ours.ucp == theirs.ucp
+            && ours.amm_quantity == theirs.amm_quantity
+            && ours.limit == theirs.limit
+    }
+}
+
+/// Explains why every order of `book` got its outcome in `solution`. The steps
+/// the clearing price was derived in and the end price of the AMM come from
+/// `derivation`, a matching of the same book, and are only reported if it came
+/// to the same solution.
+pub fn solution_clearing_report(
+    book: &OrderBook,
+    solution: &PoolSolution,
+    derivation: Option<&VolumeFillMatcher>,
+    block_number: BlockNumber
+) -> ClearingReport {
+    let ucp = solution.ucp;
+    let derivation = derivation.filter(|matcher| matcher.reproduces(solution));
+    let outcomes = solution
+        .limit
+        .iter()
+        .map(|outcome| (outcome.id.hash, outcome.outcome))
+        .collect::<HashMap<_, _>>();
+
+    let bids = book.bids().iter().map(|order| (order, true));
+    let asks = book.asks().iter().map(|order| (order, false));
+    let orders =
+        bids.chain(asks)
+            .map(|(order, is_bid)| {
+                let outcome = outcomes
+                    .get(&order.order_id.hash)
+                    .copied()
+                    .unwrap_or_default();
+                let limit_price = order.price_for_book_side(is_bid);
+                let crosses = (!ucp.is_zero()).then(|| {
+                    if is_bid {
+                        limit_price >= ucp
+                    } else {
+                        limit_price <= ucp
+                    }
+                });
+                let improvement = if is_bid {
+                    limit_price.0.saturating_sub(ucp.0)
+                } else {
+                    ucp.0.saturating_sub(limit_price.0)
+                };
+                let filled =
+                    OrderOutcome { id: order.order_id, outcome }.fill_amount(order.max_q());
+
+                OrderClearing {
+                    order_hash: order.order_id.hash,
+                    is_bid,
+                    limit_price,
+                    outcome,
+                    surplus: U256::from(Ray(improvement).quantity(filled, false)),
+                    rationale: FillRationale::new(outcome, crosses)
+                }
+            })
+            .collect::<Vec<_>>();
+
+    ClearingReport {
+        block_number,
+        pool_id: book.id(),
+        order_set_hash: ClearingReport::order_set_hash(orders.iter().map(|order| order.order_hash)),
+        ucp,
+        steps: derivation
+            .map(|matcher| matcher.results.steps.clone())
+            .unwrap_or_default(),
+        amm_start_price: book.amm().map(|amm| *amm.current_price().price()),
+        amm_end_price: derivation
+            .and_then(|matcher| matcher.amm_price.as_ref())
+            .map(|amm| *amm.price()),
+        total_surplus: orders.iter().map(|order| order.surplus).sum(),
+        orders
+    }
 }
 
 #[cfg(test)]
@@ -782,18 +1251,21 @@ mod tests {
     use std::{cell::Cell, cmp::max};
 
     use alloy::primitives::Uint;
-    use alloy_primitives::FixedBytes;
+    use alloy_primitives::{Address, FixedBytes};
     use angstrom_types::{
         matching::{uniswap::PoolSnapshot, Debt, DebtType, Ray, SqrtPriceX96},
-        orders::OrderFillState,
+        orders::{ClearingReport, FillRationale, OrderFillState},
         primitive::PoolId
     };
     use testing_tools::type_generator::{
         amm::generate_single_position_amm_at_tick, orders::UserOrderBuilder
     };
 
-    use super::VolumeFillMatcher;
-    use crate::book::{order::OrderContainer, BookOrder, OrderBook};
+    use super::{solution_clearing_report, VolumeFillMatcher};
+    use crate::{
+        book::{order::OrderContainer, BookOrder, OrderBook},
+        matcher::SelfTradePolicy
+    };
 
     #[test]
     fn runs_cleanly_on_empty_book() {
@@ -846,6 +1318,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clearing_report_explains_outcomes() {
+        let bid_price = Ray::from(Uint::from(1_000_000_000_u128)).inv_ray_round(true);
+        let bid_order = UserOrderBuilder::new()
+            .partial()
+            .bid()
+            .amount(100)
+            .min_price(bid_price)
+            .with_storage()
+            .bid()
+            .build();
+        let ask_order = UserOrderBuilder::new()
+            .exact()
+            .ask()
+            .amount(10)
+            .exact_in(true)
+            .min_price(Ray::from(Uint::from(1_000_u128)))
+            .with_storage()
+            .ask()
+            .build();
+        let book = OrderBook::new(
+            PoolId::random(),
+            None,
+            vec![bid_order.clone()],
+            vec![ask_order.clone()],
+            None
+        );
+        let mut matcher = VolumeFillMatcher::new(&book);
+        matcher.run_match();
+        let solved = matcher.from_checkpoint().unwrap();
+
+        let report = solved.clearing_report(1);
+        assert_eq!(report.ucp, solved.solution(None).ucp);
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(
+            report.order_set_hash,
+            ClearingReport::order_set_hash([ask_order.order_id.hash, bid_order.order_id.hash])
+        );
+        let rationales = report
+            .orders
+            .iter()
+            .map(|order| order.rationale)
+            .collect::<Vec<_>>();
+        assert_eq!(rationales, vec![FillRationale::PartiallyFilled, FillRationale::Filled]);
+
+        // a proposed solution the matching doesn't come to is reported as proposed
+        let mut proposed = solved.solution(None);
+        proposed
+            .limit
+            .iter_mut()
+            .for_each(|outcome| outcome.outcome = OrderFillState::Unfilled);
+        let report = solution_clearing_report(&book, &proposed, Some(&solved), 1);
+        assert!(report.steps.is_empty());
+        assert!(report.orders.iter().all(|order| order.surplus.is_zero()));
+    }
+
     #[test]
     fn ask_outweighs_bid_sets_price() {
         let pool_id = PoolId::random();
@@ -877,6 +1405,190 @@ mod tests {
         );
     }
 
+    /// A partial bid for 100 T1 against a single ask worth about 10 T1
+    fn min_fill_book(min_amount: u128) -> OrderBook {
+        let bid_order = UserOrderBuilder::new()
+            .partial()
+            .bid()
+            .amount(100)
+            .min_amount(min_amount)
+            .min_price(Ray::from(Uint::from(1_000_000_000_u128)).inv_ray_round(true))
+            .with_storage()
+            .bid()
+            .build();
+        let ask_order = UserOrderBuilder::new()
+            .exact()
+            .ask()
+            .amount(10_000_000_000_000_000_000)
+            .exact_in(true)
+            .min_price(Ray::from(Uint::from(1_000_u128)))
+            .with_storage()
+            .ask()
+            .build();
+        OrderBook::new(PoolId::random(), None, vec![bid_order], vec![ask_order], None)
+    }
+
+    #[test]
+    fn partial_fill_below_minimum_is_rejected() {
+        let book = min_fill_book(100);
+        let mut matcher = VolumeFillMatcher::new(&book);
+        matcher.run_match();
+
+        // The only ask can't fill the bid up to its minimum so nothing matches
+        assert_eq!(matcher.bid_outcomes, vec![OrderFillState::Unfilled]);
+        assert_eq!(matcher.ask_outcomes, vec![OrderFillState::Unfilled]);
+        assert_eq!(matcher.results().total_volume, 0);
+        let solved = matcher.from_checkpoint().unwrap();
+        assert_eq!(solved.bid_outcomes, matcher.bid_outcomes);
+        assert!(solved.solution(None).amm_quantity.is_none());
+    }
+
+    #[test]
+    fn partial_fill_above_minimum_is_kept() {
+        let book = min_fill_book(5);
+        let mut matcher = VolumeFillMatcher::new(&book);
+        matcher.run_match();
+        let solved = matcher.from_checkpoint().unwrap();
+
+        let Some(filled) = solved.bid_outcomes[0].partial_q() else {
+            panic!("Bid wasn't partially filled: {:?}", solved.bid_outcomes[0])
+        };
+        assert!(filled >= book.bids()[0].min_q());
+        assert_eq!(solved.ask_outcomes, vec![OrderFillState::CompleteFill]);
+    }
+
+    #[test]
+    fn restoring_undoes_the_fills_since_the_checkpoint() {
+        let book = min_fill_book(5);
+        let mut matcher = VolumeFillMatcher::new(&book);
+        matcher.partial_fill(true, 10);
+        matcher.save_checkpoint();
+
+        // An order written to more than once gets the state it had at the
+        // checkpoint back
+        matcher.partial_fill(true, 20);
+        matcher.retire(true, Some(30));
+        matcher.set_outcome(false, OrderFillState::CompleteFill);
+        let solved = matcher.from_checkpoint().unwrap();
+        assert_eq!(solved.bid_outcomes, vec![OrderFillState::PartialFill(10)]);
+        assert_eq!(solved.ask_outcomes, vec![OrderFillState::Unfilled]);
+        assert_eq!(solved.bid_decrements, vec![0]);
+
+        assert!(matcher.restore_checkpoint());
+        assert_eq!(matcher.bid_outcomes, solved.bid_outcomes);
+        assert_eq!(matcher.ask_outcomes, solved.ask_outcomes);
+        assert!(matcher.checkpoint.as_ref().unwrap().undo.is_empty());
+    }
+
+    fn own_exact_order(is_bid: bool, block: u64, price: u128) -> BookOrder {
+        let builder = UserOrderBuilder::new()
+            .exact()
+            .is_bid(is_bid)
+            .amount(10)
+            .exact_in(!is_bid)
+            .block(block);
+        let builder = if is_bid {
+            builder.bid_min_price(Ray::from(Uint::from(price)))
+        } else {
+            builder.min_price(Ray::from(Uint::from(price)))
+        };
+        builder.with_storage().is_bid(is_bid).build()
+    }
+
+    fn others_exact_order(is_bid: bool, price: u128) -> BookOrder {
+        let mut order = own_exact_order(is_bid, 1, price);
+        order.order_id.address = Address::random();
+        order
+    }
+
+    fn self_trade_outcomes(
+        policy: SelfTradePolicy,
+        bids: Vec<BookOrder>,
+        asks: Vec<BookOrder>
+    ) -> (Vec<OrderFillState>, Vec<OrderFillState>) {
+        let book = OrderBook::new(PoolId::random(), None, bids, asks, None);
+        let mut matcher = VolumeFillMatcher::new(&book).with_self_trade_policy(Some(policy));
+        matcher.run_match();
+        let solved = matcher.from_checkpoint().unwrap();
+        (solved.settled_outcomes(true), solved.settled_outcomes(false))
+    }
+
+    #[test]
+    fn self_trade_policies_cancel_orders() {
+        let bids = vec![own_exact_order(true, 2, 1_000_000_000)];
+        let asks = vec![own_exact_order(false, 1, 1_000), others_exact_order(false, 2_000)];
+        let filled = |outcomes: Vec<OrderFillState>| {
+            outcomes
+                .iter()
+                .map(OrderFillState::is_filled)
+                .collect::<Vec<_>>()
+        };
+
+        // The bid is the newest order and nothing else is left to match
+        let (bid_outcomes, ask_outcomes) =
+            self_trade_outcomes(SelfTradePolicy::CancelNewest, bids.clone(), asks.clone());
+        assert_eq!(filled(bid_outcomes), vec![false]);
+        assert_eq!(filled(ask_outcomes), vec![false, false]);
+
+        // Cancelling our own ask lets the bid match the other address
+        let (bid_outcomes, ask_outcomes) =
+            self_trade_outcomes(SelfTradePolicy::CancelOldest, bids.clone(), asks.clone());
+        assert_eq!(bid_outcomes, vec![OrderFillState::CompleteFill]);
+        assert_eq!(ask_outcomes, vec![OrderFillState::Killed, OrderFillState::CompleteFill]);
+
+        // Both orders are the same size so both of them are used up
+        let (bid_outcomes, ask_outcomes) =
+            self_trade_outcomes(SelfTradePolicy::DecrementBoth, bids, asks);
+        assert_eq!(filled(bid_outcomes), vec![false]);
+        assert_eq!(filled(ask_outcomes), vec![false, false]);
+    }
+
+    #[test]
+    fn decrement_both_nets_out_the_overlap() {
+        let bids =
+            vec![own_exact_order(true, 2, 1_000_000_000), others_exact_order(true, 999_000_000)];
+        let own_ask = UserOrderBuilder::new()
+            .partial()
+            .ask()
+            .amount(100)
+            .min_price(Ray::from(Uint::from(1_000_u128)))
+            .block(1)
+            .with_storage()
+            .ask()
+            .build();
+
+        let (bid_outcomes, ask_outcomes) =
+            self_trade_outcomes(SelfTradePolicy::DecrementBoth, bids, vec![own_ask]);
+
+        // Our own bid is taken out of our ask, which then only settles what the other
+        // address bought
+        assert_eq!(bid_outcomes, vec![OrderFillState::Killed, OrderFillState::CompleteFill]);
+        assert_eq!(ask_outcomes, vec![OrderFillState::PartialFill(10)]);
+    }
+
+    #[test]
+    fn decrement_both_never_settles_below_the_minimum() {
+        let bids =
+            vec![own_exact_order(true, 2, 1_000_000_000), others_exact_order(true, 999_000_000)];
+        // Taking our bid out of the ask would leave it 90 to settle at most
+        let own_ask = UserOrderBuilder::new()
+            .partial()
+            .ask()
+            .amount(100)
+            .min_amount(95)
+            .min_price(Ray::from(Uint::from(1_000_u128)))
+            .block(1)
+            .with_storage()
+            .ask()
+            .build();
+
+        let (bid_outcomes, ask_outcomes) =
+            self_trade_outcomes(SelfTradePolicy::DecrementBoth, bids, vec![own_ask]);
+
+        assert_eq!(bid_outcomes, vec![OrderFillState::Killed, OrderFillState::Unfilled]);
+        assert_eq!(ask_outcomes, vec![OrderFillState::Killed]);
+    }
+
     fn basic_order_book(
         is_bid: bool,
         count: usize,
@@ -922,6 +1634,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn price_ladder_picks_the_same_orders() {
+        let market: PoolSnapshot =
+            generate_single_position_amm_at_tick(100000, 100, 1_000_000_000_000_000_u128);
+        let amm_price = market.current_price();
+        let (orders, fill_state) =
+            basic_order_book(true, 10, Ray::from(SqrtPriceX96::at_tick(100000).unwrap()), 10);
+        let book = OrderBook::new(PoolId::random(), None, orders.clone(), vec![], None);
+        assert_eq!(
+            book.price_ladder().bids,
+            book.bids()
+                .iter()
+                .map(|order| order.price_for_book_side(true))
+                .collect::<Vec<_>>()
+        );
+
+        for amm in [None, Some(&amm_price)] {
+            let (index, ladder_index) = (Cell::new(0), Cell::new(0));
+            let next = VolumeFillMatcher::next_order(
+                true,
+                &index,
+                &mut None,
+                amm,
+                book.bids(),
+                &fill_state
+            );
+            let from_ladder = VolumeFillMatcher::next_order_priced(
+                true,
+                &ladder_index,
+                &mut None,
+                amm,
+                book.bids(),
+                Some(&book.price_ladder().bids),
+                &fill_state
+            );
+            assert_eq!(next.map(|o| o.price()), from_ladder.map(|o| o.price()));
+            assert_eq!(index.get(), ladder_index.get());
+        }
+    }
+
     #[test]
     fn bid_side_amm_overrides_book_order() {
         let market: PoolSnapshot =
@@ -1,2 +1,3 @@
 pub mod amm;
+#[cfg(feature = "float-prices")]
 pub mod orders;
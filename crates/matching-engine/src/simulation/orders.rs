@@ -64,7 +64,11 @@ pub fn order_distribution(
                 },
                 pool_id: FixedBytes::default(),
                 valid_block: 0,
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }
         })
         .take(number)
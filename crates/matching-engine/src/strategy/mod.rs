@@ -7,7 +7,10 @@
 /// The intent is to implement several different strategies here and compare
 /// them via a suite of tests that will help us determine what the optimal
 /// matching strategy could be.
-use crate::{book::OrderBook, matcher::VolumeFillMatcher};
+use crate::{
+    book::OrderBook,
+    matcher::{SelfTradePolicy, VolumeFillMatcher}
+};
 
 mod simplecheckpoint;
 pub use simplecheckpoint::SimpleCheckpointStrategy;
@@ -18,7 +21,16 @@ pub trait MatchingStrategy<'a> {
     /// book's standard fill operation and then attempts to run the provided
     /// `finalize()` method to do our "last mile" computation
     fn run(book: &'a OrderBook) -> Option<VolumeFillMatcher<'a>> {
-        let mut solver = VolumeFillMatcher::new(book);
+        Self::run_with_self_trade_policy(book, None)
+    }
+
+    /// Same as [`MatchingStrategy::run`] but bids and asks of the same address
+    /// are handled by `self_trade` instead of being matched against each other
+    fn run_with_self_trade_policy(
+        book: &'a OrderBook,
+        self_trade: Option<SelfTradePolicy>
+    ) -> Option<VolumeFillMatcher<'a>> {
+        let mut solver = VolumeFillMatcher::new(book).with_self_trade_policy(self_trade);
         solver.run_match();
         Self::finalize(solver)
     }
@@ -0,0 +1,73 @@
+//! What the checkpoints of the volume matcher allocate on a large book. A
+//! checkpoint only logs the fills written since the last one, where it used to
+//! clone the fill state of the whole book on nearly every step.
+//!
+//! `cargo test -p matching-engine --test checkpoint_allocations -- --nocapture`
+//! prints the measurement.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering}
+};
+
+use alloy_primitives::FixedBytes;
+use matching_engine::matcher::VolumeFillMatcher;
+use testing_tools::type_generator::book::generate_simple_cross_book;
+
+/// Counts the bytes allocated, the only test of this binary is the only thing
+/// allocating while it measures.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let result = f();
+
+    (result, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+#[test]
+fn checkpoints_allocate_a_fraction_of_cloning_the_book_state() {
+    // the matcher takes at most 1000 steps
+    let book = generate_simple_cross_book(FixedBytes::random(), 450, 100_000_000.0);
+    book.price_ladder();
+    let mut matcher = VolumeFillMatcher::new(&book);
+
+    let (steps, matched) = allocated_by(|| {
+        let mut steps = 0;
+        while matcher.single_match().is_none() {
+            steps += 1;
+        }
+        steps
+    });
+    // what the checkpoints took when every step cloned the state
+    let (_, cloned) = allocated_by(|| {
+        (0..steps).for_each(|_| drop(std::hint::black_box(matcher.clone())));
+    });
+    println!("{steps} steps: {matched} bytes with the undo log, {cloned} cloning the state");
+
+    assert!(steps > 10, "the book crosses for {steps} steps only");
+    assert!(
+        matched * 10 < cloned,
+        "{matched} bytes with the undo log against {cloned} cloning the state"
+    );
+}
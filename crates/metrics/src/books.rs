@@ -0,0 +1,73 @@
+use std::fmt::Debug;
+
+use prometheus::IntGaugeVec;
+
+use crate::METRICS_ENABLED;
+
+#[derive(Clone)]
+struct BookMetrics {
+    // standing orders of the book of each pool that are about to expire
+    expiring_orders: IntGaugeVec
+}
+
+impl Default for BookMetrics {
+    fn default() -> Self {
+        let expiring_orders = prometheus::register_int_gauge_vec!(
+            "book_expiring_orders",
+            "standing orders of the last book of each pool that are about to expire",
+            &["pool_id"]
+        )
+        .unwrap();
+
+        Self { expiring_orders }
+    }
+}
+
+impl BookMetrics {
+    fn record_books<'a>(&self, books: impl IntoIterator<Item = (&'a str, usize)>) {
+        // pools without a book this round shouldn't keep an older count
+        self.expiring_orders.reset();
+        for (pool_id, expiring) in books {
+            self.expiring_orders
+                .with_label_values(&[pool_id])
+                .set(expiring as i64);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BookMetricsWrapper(Option<BookMetrics>);
+
+impl Default for BookMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for BookMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BookMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl BookMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(BookMetrics::default)
+        )
+    }
+
+    /// Records the standing orders about to expire in the books of a round,
+    /// by pool.
+    pub fn record_books<'a>(&self, books: impl IntoIterator<Item = (&'a str, usize)>) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_books(books)
+        }
+    }
+}
@@ -1 +1,227 @@
+use std::fmt::Debug;
 
+use angstrom_types::{mev_boost::SubmissionPath, orders::GasReconciliation};
+use prometheus::{GaugeVec, Histogram, IntCounterVec, IntGauge};
+
+use crate::METRICS_ENABLED;
+
+#[derive(Clone)]
+struct BundleBuildingMetrics {
+    // block of the last bundle we built
+    block_number:    IntGauge,
+    // size of the encoded `execute` calldata of the last bundle
+    encoded_bytes:   IntGauge,
+    // estimated calldata gas of the last bundle
+    calldata_gas:    IntGauge,
+    // token0 traded by the filled user orders of each pool in the last bundle
+    matched_volume:  GaugeVec,
+    // surplus of the filled orders of each pool over swapping with the AMM
+    // alone
+    surplus:         GaugeVec,
+    // reward the top of block order of each pool paid in the last bundle
+    searcher_reward: GaugeVec,
+    // gas the last landed bundle was estimated to take
+    gas_estimated:   IntGauge,
+    // gas the last landed bundle took
+    gas_used:        IntGauge,
+    // (charged - actual) / actual gas fee of the orders of the landed bundles,
+    // in percent
+    gas_fee_error:   Histogram,
+    // bundles sent out on each path, by whether the path accepted them
+    submissions:     IntCounterVec,
+    // bundles that landed, by the path they were sent on
+    landed:          IntCounterVec
+}
+
+impl Default for BundleBuildingMetrics {
+    fn default() -> Self {
+        let block_number = prometheus::register_int_gauge!(
+            "bundle_block_number",
+            "block of the last bundle we built"
+        )
+        .unwrap();
+        let encoded_bytes = prometheus::register_int_gauge!(
+            "bundle_encoded_bytes",
+            "size of the encoded execute calldata of the last bundle"
+        )
+        .unwrap();
+        let calldata_gas = prometheus::register_int_gauge!(
+            "bundle_calldata_gas",
+            "estimated calldata gas of the last bundle"
+        )
+        .unwrap();
+        let matched_volume = prometheus::register_gauge_vec!(
+            "bundle_matched_volume",
+            "token0 traded by the filled user orders of each pool in the last bundle",
+            &["pool_id"]
+        )
+        .unwrap();
+        let surplus = prometheus::register_gauge_vec!(
+            "bundle_surplus",
+            "surplus of the filled orders of each pool over swapping with the AMM alone, in token0",
+            &["pool_id"]
+        )
+        .unwrap();
+        let searcher_reward = prometheus::register_gauge_vec!(
+            "bundle_searcher_reward",
+            "reward the top of block order of each pool paid in the last bundle, in token0",
+            &["pool_id"]
+        )
+        .unwrap();
+        let gas_estimated = prometheus::register_int_gauge!(
+            "bundle_gas_estimated",
+            "gas the last landed bundle was estimated to take"
+        )
+        .unwrap();
+        let gas_used =
+            prometheus::register_int_gauge!("bundle_gas_used", "gas the last landed bundle took")
+                .unwrap();
+        let gas_fee_error = prometheus::register_histogram!(
+            "bundle_gas_fee_error",
+            "(charged - actual) / actual gas fee of the orders of the landed bundles, in percent",
+            vec![-50.0, -20.0, -10.0, -5.0, -1.0, 0.0, 1.0, 5.0, 10.0, 20.0, 50.0, 100.0]
+        )
+        .unwrap();
+        let submissions = prometheus::register_int_counter_vec!(
+            "bundle_submissions",
+            "bundles sent out on each path, by whether the path accepted them",
+            &["path", "accepted"]
+        )
+        .unwrap();
+        let landed = prometheus::register_int_counter_vec!(
+            "bundle_landed",
+            "bundles that landed, by the path they were sent on",
+            &["path"]
+        )
+        .unwrap();
+
+        Self {
+            block_number,
+            encoded_bytes,
+            calldata_gas,
+            matched_volume,
+            surplus,
+            searcher_reward,
+            gas_estimated,
+            gas_used,
+            gas_fee_error,
+            submissions,
+            landed
+        }
+    }
+}
+
+impl BundleBuildingMetrics {
+    fn record_bundle(&self, block_number: u64, encoded_bytes: usize, calldata_gas: u64) {
+        self.block_number.set(block_number as i64);
+        self.encoded_bytes.set(encoded_bytes as i64);
+        self.calldata_gas.set(calldata_gas as i64);
+        // pools that weren't in this bundle shouldn't keep the values of an
+        // older one
+        self.matched_volume.reset();
+        self.surplus.reset();
+        self.searcher_reward.reset();
+    }
+
+    fn record_pool(&self, pool_id: &str, matched_volume: u128, surplus: u128, reward: u128) {
+        self.matched_volume
+            .with_label_values(&[pool_id])
+            .set(matched_volume as f64);
+        self.surplus
+            .with_label_values(&[pool_id])
+            .set(surplus as f64);
+        self.searcher_reward
+            .with_label_values(&[pool_id])
+            .set(reward as f64);
+    }
+
+    fn record_gas_reconciliation(&self, reconciliation: &GasReconciliation) {
+        self.gas_estimated.set(reconciliation.estimated_gas as i64);
+        self.gas_used.set(reconciliation.gas_used as i64);
+        reconciliation
+            .orders
+            .iter()
+            .filter(|order| order.actual_asset0 > 0)
+            .for_each(|order| {
+                let error = order.overcharge() as f64 / order.actual_asset0 as f64;
+                self.gas_fee_error.observe(error * 100.0);
+            });
+    }
+
+    fn record_submission(&self, path: SubmissionPath, accepted: bool) {
+        self.submissions
+            .with_label_values(&[path.as_str(), if accepted { "true" } else { "false" }])
+            .inc();
+    }
+
+    fn record_landed(&self, path: SubmissionPath) {
+        self.landed.with_label_values(&[path.as_str()]).inc();
+    }
+}
+
+#[derive(Clone)]
+pub struct BundleBuildingMetricsWrapper(Option<BundleBuildingMetrics>);
+
+impl Default for BundleBuildingMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for BundleBuildingMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BundleBuildingMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl BundleBuildingMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(BundleBuildingMetrics::default)
+        )
+    }
+
+    /// Records the size of a bundle we built, clearing the pools of the last
+    /// one.
+    pub fn record_bundle(&self, block_number: u64, encoded_bytes: usize, calldata_gas: u64) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_bundle(block_number, encoded_bytes, calldata_gas)
+        }
+    }
+
+    /// Records the economics of a pool in the last bundle, all in token0 of
+    /// the pool.
+    pub fn record_pool(&self, pool_id: &str, matched_volume: u128, surplus: u128, reward: u128) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_pool(pool_id, matched_volume, surplus, reward)
+        }
+    }
+
+    /// Records what the orders of a landed bundle were charged for gas
+    /// against what it cost.
+    pub fn record_gas_reconciliation(&self, reconciliation: &GasReconciliation) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_gas_reconciliation(reconciliation)
+        }
+    }
+
+    /// Records a bundle sent out on `path`.
+    pub fn record_submission(&self, path: SubmissionPath, accepted: bool) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_submission(path, accepted)
+        }
+    }
+
+    /// Records a bundle sent out on `path` that landed.
+    pub fn record_landed(&self, path: SubmissionPath) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_landed(path)
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use std::{collections::HashMap, time::Instant};
 
-use prometheus::{IntGauge, IntGaugeVec};
+use angstrom_types::{
+    consensus::{InclusionAudit, RoundParticipation},
+    primitive::PeerId
+};
+use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
 
 use crate::METRICS_ENABLED;
 
@@ -14,6 +18,21 @@ struct ConsensusMetrics {
     proposal_build_time_per_block: IntGaugeVec,
     // time (ms) it takes proposal verification per block
     proposal_verification_time_per_block: IntGaugeVec,
+    // time (ms) a pre-proposal arrived after we stopped collecting them
+    late_pre_proposal_delay: Histogram,
+    // orders carried over from late pre-proposals into the next round
+    carried_over_orders: IntCounter,
+    // top of block orders dropped as their claimed reward didn't match the
+    // simulated one
+    rejected_tob_orders: IntCounter,
+    // rounds each validator was part of the set
+    validator_rounds: IntCounterVec,
+    // faults of each validator, by kind
+    validator_faults: IntCounterVec,
+    // orders we validated by the cutoff of the rounds each leader led
+    leader_expected_orders: IntCounterVec,
+    // of those, the ones the proposals of each leader left out
+    leader_excluded_orders: IntCounterVec,
     // map of block numbers to their consensus start times
     block_consensus_start_times: HashMap<u64, Instant>
 }
@@ -45,8 +64,62 @@ impl Default for ConsensusMetrics {
         )
         .unwrap();
 
+        let late_pre_proposal_delay = prometheus::register_histogram!(
+            "consensus_late_pre_proposal_delay",
+            "time (ms) a pre-proposal arrived after we stopped collecting them",
+            vec![10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0]
+        )
+        .unwrap();
+
+        let carried_over_orders = prometheus::register_int_counter!(
+            "consensus_carried_over_orders",
+            "orders carried over from late pre-proposals into the next round"
+        )
+        .unwrap();
+
+        let rejected_tob_orders = prometheus::register_int_counter!(
+            "consensus_rejected_tob_orders",
+            "top of block orders dropped as their claimed reward didn't match the simulated one"
+        )
+        .unwrap();
+
+        let validator_rounds = prometheus::register_int_counter_vec!(
+            "consensus_validator_rounds",
+            "rounds each validator was part of the set",
+            &["peer"]
+        )
+        .unwrap();
+
+        let validator_faults = prometheus::register_int_counter_vec!(
+            "consensus_validator_faults",
+            "faults of each validator, by kind",
+            &["peer", "fault"]
+        )
+        .unwrap();
+
+        let leader_expected_orders = prometheus::register_int_counter_vec!(
+            "consensus_leader_expected_orders",
+            "orders we validated by the cutoff of the rounds each leader led",
+            &["leader"]
+        )
+        .unwrap();
+
+        let leader_excluded_orders = prometheus::register_int_counter_vec!(
+            "consensus_leader_excluded_orders",
+            "orders validated by the cutoff the proposals of each leader left out",
+            &["leader"]
+        )
+        .unwrap();
+
         Self {
             block_height,
+            late_pre_proposal_delay,
+            carried_over_orders,
+            rejected_tob_orders,
+            validator_rounds,
+            validator_faults,
+            leader_expected_orders,
+            leader_excluded_orders,
             proposal_build_time_per_block,
             completion_time_per_block,
             proposal_verification_time_per_block,
@@ -77,6 +150,48 @@ impl ConsensusMetrics {
             .set(time as i64);
     }
 
+    pub fn record_late_pre_proposal(&self, delay: u128) {
+        self.late_pre_proposal_delay.observe(delay as f64);
+    }
+
+    pub fn add_carried_over_orders(&self, count: usize) {
+        self.carried_over_orders.inc_by(count as u64);
+    }
+
+    pub fn add_rejected_tob_orders(&self, count: usize) {
+        self.rejected_tob_orders.inc_by(count as u64);
+    }
+
+    pub fn record_validator_round(&self, peer_id: PeerId, round: &RoundParticipation) {
+        let peer = peer_id.to_string();
+        self.validator_rounds.with_label_values(&[&peer]).inc();
+
+        [
+            ("missed_pre_proposal", round.missed_pre_proposal),
+            ("late_pre_proposal", round.late_pre_proposal),
+            ("late_aggregation", round.late_aggregation),
+            ("missed_proposal", round.missed_proposal),
+            ("failed_proposal", round.failed_proposal)
+        ]
+        .into_iter()
+        .filter(|(_, faulted)| *faulted)
+        .for_each(|(fault, _)| {
+            self.validator_faults
+                .with_label_values(&[&peer, fault])
+                .inc()
+        });
+    }
+
+    pub fn record_inclusion_audit(&self, audit: &InclusionAudit) {
+        let leader = audit.leader.to_string();
+        self.leader_expected_orders
+            .with_label_values(&[&leader])
+            .inc_by(audit.expected as u64);
+        self.leader_excluded_orders
+            .with_label_values(&[&leader])
+            .inc_by(audit.excluded.len() as u64);
+    }
+
     pub fn set_block_height(&mut self, block_number: u64) {
         self.block_height.set(block_number as i64);
         self.block_consensus_start_times
@@ -135,6 +250,36 @@ impl ConsensusMetricsWrapper {
         }
     }
 
+    pub fn record_late_pre_proposal(&self, delay: u128) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_late_pre_proposal(delay)
+        }
+    }
+
+    pub fn add_carried_over_orders(&self, count: usize) {
+        if let Some(this) = self.0.as_ref() {
+            this.add_carried_over_orders(count)
+        }
+    }
+
+    pub fn add_rejected_tob_orders(&self, count: usize) {
+        if let Some(this) = self.0.as_ref() {
+            this.add_rejected_tob_orders(count)
+        }
+    }
+
+    pub fn record_validator_round(&self, peer_id: PeerId, round: &RoundParticipation) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_validator_round(peer_id, round)
+        }
+    }
+
+    pub fn record_inclusion_audit(&self, audit: &InclusionAudit) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_inclusion_audit(audit)
+        }
+    }
+
     pub fn set_block_height(&mut self, block_number: u64) {
         if let Some(this) = self.0.as_mut() {
             this.set_block_height(block_number)
@@ -0,0 +1,408 @@
+//! Health and readiness endpoints
+//!
+//! Subsystems report their state into the process wide [`NodeHealth`] which is
+//! served as json over http. `/live` only checks that the process is up,
+//! `/ready` returns a 503 until every subsystem is within its
+//! [`HealthThresholds`] and `/health` always returns the full report.
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration
+};
+
+use angstrom_types::block_sync::BlockSyncStatus;
+use eyre::WrapErr;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode
+};
+use serde::Serialize;
+
+static NODE_HEALTH: OnceLock<NodeHealth> = OnceLock::new();
+
+/// Returns the process wide health state.
+pub fn node_health() -> &'static NodeHealth {
+    NODE_HEALTH.get_or_init(NodeHealth::default)
+}
+
+/// Bounds a subsystem has to be within for the node to be considered ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HealthThresholds {
+    /// max amount of blocks the global block sync can be behind the chain tip
+    pub max_block_lag:             u64,
+    /// min amount of connected peers
+    pub min_peers:                 usize,
+    /// min amount of validators we are connected to
+    pub min_connected_validators:  usize,
+    /// max amount of consecutive failed bundle submissions to the relays
+    pub max_relay_failures_in_row: u64
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_block_lag:             2,
+            min_peers:                 1,
+            min_connected_validators:  1,
+            max_relay_failures_in_row: 3
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BlockSyncHealth {
+    /// block number all modules have signed off on
+    pub height:         u64,
+    /// latest block known by the node
+    pub chain_tip:      u64,
+    pub lag:            u64,
+    /// how long the pending transition has been waiting on sign offs
+    pub pending_for_ms: Option<u64>,
+    /// modules that haven't signed off on the pending transition yet
+    pub waiting_on:     Vec<String>,
+    /// modules that stalled the pending transition for longer than the stall
+    /// timeout
+    pub stalled:        Vec<String>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NetworkHealth {
+    pub peer_count:           usize,
+    pub connected_validators: usize,
+    /// validators in the set besides us
+    pub total_validators:     usize
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PoolsHealth {
+    /// pools that failed to load and are left out of matching, the node runs
+    /// degraded while there are any
+    pub unavailable: Vec<String>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OrderPoolHealth {
+    /// amount of orders currently resting in the pool
+    pub depth: usize
+}
+
+/// How the last consensus round we took part in ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundOutcome {
+    /// we were leader and the bundle landed on chain
+    BundleIncluded,
+    /// we were leader and the bundle was submitted but didn't land
+    BundleNotIncluded,
+    /// we were leader but failed to build or submit the bundle
+    BuildFailed,
+    /// we verified the leaders proposal
+    ProposalVerified,
+    /// the leaders proposal didn't match our own solution
+    ProposalMismatch,
+    /// we failed to solve the round to verify the leaders proposal
+    VerificationFailed,
+    /// the leaders proposal left out orders a quorum flagged for inclusion
+    ForcedInclusionViolated
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConsensusHealth {
+    pub last_round_block:   Option<u64>,
+    pub last_round_outcome: Option<RoundOutcome>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RelayHealth {
+    pub last_submission_ok:   Option<bool>,
+    pub consecutive_failures: u64
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub ready:      bool,
+    /// subsystems that are outside of their thresholds
+    pub failing:    Vec<&'static str>,
+    pub block_sync: BlockSyncHealth,
+    pub network:    NetworkHealth,
+    pub pools:      PoolsHealth,
+    pub order_pool: OrderPoolHealth,
+    pub consensus:  ConsensusHealth,
+    pub relays:     RelayHealth
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    thresholds: HealthThresholds,
+    block_sync: BlockSyncHealth,
+    network:    NetworkHealth,
+    pools:      PoolsHealth,
+    order_pool: OrderPoolHealth,
+    consensus:  ConsensusHealth,
+    relays:     RelayHealth
+}
+
+impl HealthState {
+    fn failing(&self) -> Vec<&'static str> {
+        let mut failing = Vec::new();
+        if self.block_sync.height == 0
+            || self.block_sync.lag > self.thresholds.max_block_lag
+            || !self.block_sync.stalled.is_empty()
+        {
+            failing.push("block_sync");
+        }
+        if self.network.peer_count < self.thresholds.min_peers {
+            failing.push("network");
+        }
+        // we can't be connected to more validators than the set has besides us
+        let min_connected_validators = self
+            .thresholds
+            .min_connected_validators
+            .min(self.network.total_validators);
+        if self.network.connected_validators < min_connected_validators {
+            failing.push("validator_connectivity");
+        }
+        if self.relays.consecutive_failures > self.thresholds.max_relay_failures_in_row {
+            failing.push("relays");
+        }
+
+        failing
+    }
+
+    fn report(&self) -> HealthReport {
+        let failing = self.failing();
+        HealthReport {
+            ready: failing.is_empty(),
+            failing,
+            block_sync: self.block_sync.clone(),
+            network: self.network.clone(),
+            pools: self.pools.clone(),
+            order_pool: self.order_pool.clone(),
+            consensus: self.consensus.clone(),
+            relays: self.relays.clone()
+        }
+    }
+}
+
+/// Shared handle subsystems use to report their state.
+#[derive(Debug, Clone, Default)]
+pub struct NodeHealth(Arc<RwLock<HealthState>>);
+
+impl NodeHealth {
+    pub fn set_thresholds(&self, thresholds: HealthThresholds) {
+        self.0.write().unwrap().thresholds = thresholds;
+    }
+
+    pub fn set_block_sync(&self, height: u64, chain_tip: u64) {
+        self.0.write().unwrap().block_sync = BlockSyncHealth {
+            height,
+            chain_tip,
+            lag: chain_tip.saturating_sub(height),
+            ..Default::default()
+        };
+    }
+
+    /// Same as [`NodeHealth::set_block_sync`] with the sign off state of the
+    /// modules.
+    pub fn set_block_sync_status(
+        &self,
+        status: &BlockSyncStatus,
+        chain_tip: u64,
+        stall_timeout: Duration
+    ) {
+        let chain_tip = chain_tip.max(status.latest_block);
+        self.0.write().unwrap().block_sync = BlockSyncHealth {
+            height: status.current_block,
+            chain_tip,
+            lag: chain_tip.saturating_sub(status.current_block),
+            pending_for_ms: status.pending_for_ms,
+            waiting_on: status
+                .modules
+                .iter()
+                .filter(|module| !module.signed_off)
+                .map(|module| module.module.clone())
+                .collect(),
+            stalled: status
+                .stalled_modules(stall_timeout)
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        };
+    }
+
+    pub fn set_network(&self, peer_count: usize, connected_validators: usize, total: usize) {
+        self.0.write().unwrap().network =
+            NetworkHealth { peer_count, connected_validators, total_validators: total };
+    }
+
+    /// Pools that didn't load, they don't fail readiness as the other pools
+    /// keep being served.
+    pub fn set_unavailable_pools(&self, pools: Vec<String>) {
+        self.0.write().unwrap().pools.unavailable = pools;
+    }
+
+    pub fn set_order_pool_depth(&self, depth: usize) {
+        self.0.write().unwrap().order_pool.depth = depth;
+    }
+
+    pub fn set_round_outcome(&self, block: u64, outcome: RoundOutcome) {
+        self.0.write().unwrap().consensus =
+            ConsensusHealth { last_round_block: Some(block), last_round_outcome: Some(outcome) };
+    }
+
+    pub fn record_relay_submission(&self, success: bool) {
+        let relays = &mut self.0.write().unwrap().relays;
+        relays.last_submission_ok = Some(success);
+        if success {
+            relays.consecutive_failures = 0;
+        } else {
+            relays.consecutive_failures += 1;
+        }
+    }
+
+    pub fn report(&self) -> HealthReport {
+        self.0.read().unwrap().report()
+    }
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn handle_request(health: &NodeHealth, req: Request<Body>) -> Response<Body> {
+    match req.uri().path() {
+        "/live" => json_response(StatusCode::OK, r#"{"alive":true}"#.to_string()),
+        "/ready" => {
+            let report = health.report();
+            let status =
+                if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+            json_response(status, serde_json::to_string(&report).unwrap())
+        }
+        "/health" => {
+            json_response(StatusCode::OK, serde_json::to_string(&health.report()).unwrap())
+        }
+        _ => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.to_string())
+    }
+}
+
+/// Serves the health endpoints of the process wide [`NodeHealth`] on the
+/// given port.
+pub async fn initialize_health_endpoint(port: u16) -> eyre::Result<()> {
+    let listen_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::from([0, 0, 0, 0])), port);
+    let health = node_health().clone();
+
+    let make_svc = make_service_fn(move |_| {
+        let health = health.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let response = handle_request(&health, req);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&listen_addr)
+        .wrap_err("Could not bind to address")?
+        .serve(make_svc);
+
+    tokio::spawn(async move { server.await.expect("Health endpoint crashed") });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readiness_follows_thresholds() {
+        let health = NodeHealth::default();
+        let report = health.report();
+        assert!(!report.ready);
+        assert!(report.failing.contains(&"block_sync"));
+
+        health.set_block_sync(100, 101);
+        health.set_network(3, 2, 3);
+        let report = health.report();
+        assert!(report.ready, "{:?}", report.failing);
+        assert_eq!(report.block_sync.lag, 1);
+
+        health.set_block_sync(100, 110);
+        assert_eq!(health.report().failing, vec!["block_sync"]);
+
+        health.set_block_sync(110, 110);
+        for _ in 0..4 {
+            health.record_relay_submission(false);
+        }
+        assert_eq!(health.report().failing, vec!["relays"]);
+
+        health.record_relay_submission(true);
+        assert!(health.report().ready);
+
+        health.set_unavailable_pools(vec!["0x01".to_string()]);
+        let report = health.report();
+        assert!(report.ready);
+        assert_eq!(report.pools.unavailable, vec!["0x01".to_string()]);
+    }
+
+    #[test]
+    fn lone_validator_is_ready() {
+        let health = NodeHealth::default();
+        health.set_block_sync(100, 100);
+        health.set_network(1, 0, 0);
+        let report = health.report();
+        assert!(report.ready, "{:?}", report.failing);
+
+        health.set_network(1, 0, 2);
+        assert_eq!(health.report().failing, vec!["validator_connectivity"]);
+    }
+
+    #[test]
+    fn stalled_block_sync_is_not_ready() {
+        use angstrom_types::block_sync::{GlobalBlockState, ModuleSyncStatus};
+
+        let health = NodeHealth::default();
+        health.set_network(3, 2, 3);
+        let module = |module: &str, signed_off| ModuleSyncStatus {
+            module: module.to_string(),
+            signed_off,
+            behind_by: usize::from(!signed_off),
+            last_sign_off: None
+        };
+        let status = BlockSyncStatus {
+            current_block:  100,
+            latest_block:   101,
+            lag:            1,
+            pending:        Some(GlobalBlockState::PendingProgression(101)),
+            pending_for_ms: Some(30_000),
+            modules:        vec![module("consensus", true), module("order pool", false)]
+        };
+
+        health.set_block_sync_status(&status, 101, Duration::from_secs(60));
+        let report = health.report();
+        assert!(report.ready, "{:?}", report.failing);
+        assert_eq!(report.block_sync.waiting_on, vec!["order pool".to_string()]);
+
+        health.set_block_sync_status(&status, 101, Duration::from_secs(10));
+        let report = health.report();
+        assert_eq!(report.failing, vec!["block_sync"]);
+        assert_eq!(report.block_sync.stalled, vec!["order pool".to_string()]);
+    }
+
+    #[test]
+    fn ready_endpoint_returns_unavailable() {
+        let health = NodeHealth::default();
+        let req = Request::builder()
+            .uri("/ready")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(handle_request(&health, req).status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let req = Request::builder().uri("/live").body(Body::empty()).unwrap();
+        assert_eq!(handle_request(&health, req).status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,86 @@
+//! Consistency checks of the order storage and the books.
+//!
+//! The checks are only run if [`INVARIANT_MODE`] is set to something other
+//! than [`InvariantMode::Disabled`], as they walk every order after each
+//! mutation.
+use std::sync::OnceLock;
+
+use angstrom_types::orders::{InvariantMode, InvariantViolation};
+use prometheus::IntCounterVec;
+
+use crate::METRICS_ENABLED;
+
+pub static INVARIANT_MODE: OnceLock<InvariantMode> = OnceLock::new();
+
+static INVARIANT_METRICS: OnceLock<InvariantMetricsWrapper> = OnceLock::new();
+
+/// Runs `check` if invariant checking is enabled and handles the violations
+/// it found according to the [`INVARIANT_MODE`]. `source` names what was
+/// checked.
+pub fn check_invariants(source: &'static str, check: impl FnOnce() -> Vec<InvariantViolation>) {
+    let mode = INVARIANT_MODE.get().copied().unwrap_or_default();
+    if !mode.is_enabled() {
+        return
+    }
+
+    let violations = check();
+    if violations.is_empty() {
+        return
+    }
+
+    if mode == InvariantMode::Panic {
+        panic!("{source} invariants violated: {violations:?}");
+    }
+
+    let metrics = INVARIANT_METRICS.get_or_init(InvariantMetricsWrapper::new);
+    for violation in violations {
+        tracing::error!(source, %violation, "invariant violated");
+        metrics.incr_violations(source, violation.kind());
+    }
+}
+
+#[derive(Clone)]
+struct InvariantMetrics {
+    // number of invariant violations per checked component and kind
+    violations: IntCounterVec
+}
+
+impl Default for InvariantMetrics {
+    fn default() -> Self {
+        let violations = prometheus::register_int_counter_vec!(
+            "invariant_violations",
+            "number of invariant violations per checked component and kind",
+            &["source", "kind"]
+        )
+        .unwrap();
+
+        Self { violations }
+    }
+}
+
+impl InvariantMetrics {
+    fn incr_violations(&self, source: &str, kind: &str) {
+        self.violations.with_label_values(&[source, kind]).inc();
+    }
+}
+
+#[derive(Clone)]
+struct InvariantMetricsWrapper(Option<InvariantMetrics>);
+
+impl InvariantMetricsWrapper {
+    fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(InvariantMetrics::default)
+        )
+    }
+
+    fn incr_violations(&self, source: &str, kind: &str) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_violations(source, kind)
+        }
+    }
+}
@@ -4,6 +4,16 @@ use std::sync::OnceLock;
 pub use exporter::*;
 
 mod bundle_building;
+pub use bundle_building::*;
+
+mod books;
+pub use books::*;
+
+mod health;
+pub use health::*;
+
+mod invariants;
+pub use invariants::*;
 
 pub mod validation;
 
@@ -13,4 +23,16 @@ pub use order_pool::*;
 mod consensus;
 pub use consensus::*;
 
+mod network;
+pub use network::*;
+
+mod otlp;
+pub use otlp::*;
+
+mod pools;
+pub use pools::*;
+
+mod rpc;
+pub use rpc::*;
+
 pub static METRICS_ENABLED: OnceLock<bool> = OnceLock::new();
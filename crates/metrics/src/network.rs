@@ -0,0 +1,218 @@
+use std::{fmt::Debug, sync::OnceLock, time::Duration};
+
+use prometheus::{HistogramVec, IntCounter, IntGauge};
+
+use crate::METRICS_ENABLED;
+
+/// Registered once, every protocol handler shares them.
+static SESSION_METRICS: OnceLock<SessionMetrics> = OnceLock::new();
+
+#[derive(Clone)]
+struct SessionMetrics {
+    // time (ns) a outbound message spent queued in a session, per message class
+    outbound_queueing_delay: HistogramVec
+}
+
+impl Default for SessionMetrics {
+    fn default() -> Self {
+        let buckets = prometheus::exponential_buckets(1000.0, 2.0, 20).unwrap();
+
+        let outbound_queueing_delay = prometheus::register_histogram_vec!(
+            "strom_session_outbound_queueing_delay",
+            "time (ns) a outbound message spent queued in a session before being sent",
+            &["class"],
+            buckets
+        )
+        .unwrap();
+
+        Self { outbound_queueing_delay }
+    }
+}
+
+impl SessionMetrics {
+    fn queueing_delay(&self, class: &str, queued_for: Duration) {
+        self.outbound_queueing_delay
+            .with_label_values(&[class])
+            .observe(queued_for.as_nanos() as f64);
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionMetricsWrapper(Option<SessionMetrics>);
+
+impl Default for SessionMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for SessionMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SessionMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl SessionMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(|| SESSION_METRICS.get_or_init(SessionMetrics::default).clone())
+        )
+    }
+
+    pub fn queueing_delay(&self, class: &str, queued_for: Duration) {
+        if let Some(this) = self.0.as_ref() {
+            this.queueing_delay(class, queued_for)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PropagationMetrics {
+    // time (ms) from an order entering the network until it arrived, per hop count
+    propagation_delay: HistogramVec
+}
+
+impl Default for PropagationMetrics {
+    fn default() -> Self {
+        let buckets = prometheus::exponential_buckets(1.0, 2.0, 16).unwrap();
+
+        let propagation_delay = prometheus::register_histogram_vec!(
+            "strom_order_propagation_delay",
+            "time (ms) from a traced order entering the network until it arrived at this node",
+            &["hops"],
+            buckets
+        )
+        .unwrap();
+
+        Self { propagation_delay }
+    }
+}
+
+impl PropagationMetrics {
+    fn arrived(&self, hops: u8, delay: Duration) {
+        self.propagation_delay
+            .with_label_values(&[&hops.to_string()])
+            .observe(delay.as_millis() as f64);
+    }
+}
+
+#[derive(Clone)]
+pub struct PropagationMetricsWrapper(Option<PropagationMetrics>);
+
+impl Default for PropagationMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PropagationMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PropagationMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PropagationMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(PropagationMetrics::default)
+        )
+    }
+
+    /// Records a traced order that arrived after `hops` hops.
+    pub fn arrived(&self, hops: u8, delay: Duration) {
+        if let Some(this) = self.0.as_ref() {
+            this.arrived(hops, delay)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PinnedPeerMetrics {
+    // pinned peers that were unreachable for longer than the alert threshold
+    unreachable: IntGauge,
+    // dials of pinned peers that weren't connected
+    dials:       IntCounter
+}
+
+impl Default for PinnedPeerMetrics {
+    fn default() -> Self {
+        let unreachable = prometheus::register_int_gauge!(
+            "strom_pinned_peers_unreachable",
+            "pinned peers that were unreachable for longer than the alert threshold"
+        )
+        .unwrap();
+        let dials = prometheus::register_int_counter!(
+            "strom_pinned_peer_dials",
+            "dials of pinned peers that weren't connected"
+        )
+        .unwrap();
+
+        Self { unreachable, dials }
+    }
+}
+
+#[derive(Clone)]
+pub struct PinnedPeerMetricsWrapper(Option<PinnedPeerMetrics>);
+
+impl Default for PinnedPeerMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PinnedPeerMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PinnedPeerMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PinnedPeerMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(PinnedPeerMetrics::default)
+        )
+    }
+
+    pub fn unreachable(&self, peers: usize) {
+        if let Some(this) = self.0.as_ref() {
+            this.unreachable.set(peers as i64)
+        }
+    }
+
+    pub fn dialed(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.dials.inc()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_metrics_are_registered_once() {
+        let _ = METRICS_ENABLED.set(true);
+        // one per protocol handler
+        let handlers = [SessionMetricsWrapper::new(), SessionMetricsWrapper::new()];
+        assert!(handlers.iter().all(|metrics| metrics.0.is_some()));
+    }
+}
@@ -1,4 +1,4 @@
-use prometheus::IntGauge;
+use prometheus::{IntCounter, IntGauge};
 
 use crate::METRICS_ENABLED;
 
@@ -17,7 +17,21 @@ struct OrderStorageMetrics {
     // number of cancelled composable orders
     cancelled_composable_orders: IntGauge,
     // number of cancelled searcher orders
-    cancelled_searcher_orders:   IntGauge
+    cancelled_searcher_orders:   IntGauge,
+    // number of orders rejected because their hash is taken by another order
+    order_hash_collisions:       IntCounter,
+    // number of orders rejected because their address hit its caps
+    account_cap_rejections:      IntCounter,
+    // number of parked orders evicted for paying too little gas
+    evicted_parked_orders:       IntCounter,
+    // number of dust orders evicted to make room for larger ones
+    evicted_dust_orders:         IntCounter,
+    // number of filled order hashes remembered
+    filled_order_hashes:         IntGauge,
+    // number of filled order hashes forgotten once their ttl passed
+    expired_filled_order_hashes: IntCounter,
+    // number of filled order hashes forgotten early to stay within capacity
+    evicted_filled_order_hashes: IntCounter
 }
 
 impl Default for OrderStorageMetrics {
@@ -64,6 +78,48 @@ impl Default for OrderStorageMetrics {
         )
         .unwrap();
 
+        let order_hash_collisions = prometheus::register_int_counter!(
+            "order_storage_order_hash_collisions",
+            "number of orders rejected because their hash is taken by another order",
+        )
+        .unwrap();
+
+        let account_cap_rejections = prometheus::register_int_counter!(
+            "order_storage_account_cap_rejections",
+            "number of orders rejected because their address hit its caps",
+        )
+        .unwrap();
+
+        let evicted_parked_orders = prometheus::register_int_counter!(
+            "order_storage_evicted_parked_orders",
+            "number of parked orders evicted for paying too little gas",
+        )
+        .unwrap();
+
+        let evicted_dust_orders = prometheus::register_int_counter!(
+            "order_storage_evicted_dust_orders",
+            "number of dust orders evicted to make room for larger ones",
+        )
+        .unwrap();
+
+        let filled_order_hashes = prometheus::register_int_gauge!(
+            "order_storage_filled_order_hashes",
+            "number of filled order hashes remembered",
+        )
+        .unwrap();
+
+        let expired_filled_order_hashes = prometheus::register_int_counter!(
+            "order_storage_expired_filled_order_hashes",
+            "number of filled order hashes forgotten once their ttl passed",
+        )
+        .unwrap();
+
+        let evicted_filled_order_hashes = prometheus::register_int_counter!(
+            "order_storage_evicted_filled_order_hashes",
+            "number of filled order hashes forgotten early to stay within capacity",
+        )
+        .unwrap();
+
         Self {
             vanilla_limit_orders,
             searcher_orders,
@@ -71,7 +127,14 @@ impl Default for OrderStorageMetrics {
             composable_limit_orders,
             cancelled_vanilla_orders,
             cancelled_composable_orders,
-            cancelled_searcher_orders
+            cancelled_searcher_orders,
+            order_hash_collisions,
+            account_cap_rejections,
+            evicted_parked_orders,
+            evicted_dust_orders,
+            filled_order_hashes,
+            expired_filled_order_hashes,
+            evicted_filled_order_hashes
         }
     }
 }
@@ -120,6 +183,28 @@ impl OrderStorageMetrics {
     pub fn incr_cancelled_searcher_orders(&self, count: usize) {
         self.cancelled_searcher_orders.add(count as i64);
     }
+
+    pub fn incr_order_hash_collisions(&self) {
+        self.order_hash_collisions.inc();
+    }
+
+    pub fn incr_account_cap_rejections(&self) {
+        self.account_cap_rejections.inc();
+    }
+
+    pub fn incr_evicted_parked_orders(&self) {
+        self.evicted_parked_orders.inc();
+    }
+
+    pub fn incr_evicted_dust_orders(&self) {
+        self.evicted_dust_orders.inc();
+    }
+
+    pub fn record_filled_order_hashes(&self, remembered: usize, expired: usize, evicted: usize) {
+        self.filled_order_hashes.set(remembered as i64);
+        self.expired_filled_order_hashes.inc_by(expired as u64);
+        self.evicted_filled_order_hashes.inc_by(evicted as u64);
+    }
 }
 
 #[derive(Clone)]
@@ -178,6 +263,38 @@ impl OrderStorageMetricsWrapper {
         }
     }
 
+    pub fn incr_order_hash_collisions(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_order_hash_collisions()
+        }
+    }
+
+    pub fn incr_account_cap_rejections(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_account_cap_rejections()
+        }
+    }
+
+    pub fn incr_evicted_parked_orders(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_evicted_parked_orders()
+        }
+    }
+
+    pub fn incr_evicted_dust_orders(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_evicted_dust_orders()
+        }
+    }
+
+    /// Sets how many filled order hashes are remembered and counts the ones
+    /// that were forgotten since the last time.
+    pub fn record_filled_order_hashes(&self, remembered: usize, expired: usize, evicted: usize) {
+        if let Some(this) = self.0.as_ref() {
+            this.record_filled_order_hashes(remembered, expired, evicted)
+        }
+    }
+
     pub fn decr_composable_limit_orders(&self, count: usize) {
         if let Some(this) = self.0.as_ref() {
             this.decr_composable_limit_orders(count)
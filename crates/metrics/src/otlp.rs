@@ -0,0 +1,101 @@
+//! OpenTelemetry trace export
+//!
+//! Turns the spans that make up the life of an order and of a consensus round
+//! into distributed traces that are shipped to an OTLP collector. Only the
+//! spans listed in [`EXPORTED_SPANS`] are exported, everything else is left to
+//! the regular log output.
+use opentelemetry::{
+    trace::{TraceResult, TracerProvider as _},
+    KeyValue
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::{filter::filter_fn, registry::LookupSpan, Layer};
+
+/// Spans that are converted into otlp spans.
+pub const EXPORTED_SPANS: &[&str] = &[
+    // order-pool -> validation
+    "new_order",
+    "pool_order",
+    "validate_order",
+    // matching engine
+    "solve_pools",
+    "build_proposal",
+    "solve_book",
+    // consensus
+    "consensus_round",
+    "bundle_submission"
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpConfig {
+    /// grpc endpoint of the collector, e.g. `http://localhost:4317`
+    pub endpoint:     String,
+    pub service_name: String
+}
+
+impl OtlpConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into(), service_name: "angstrom".to_string() }
+    }
+
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+}
+
+/// Flushes the remaining spans to the collector on drop, needs to be held
+/// for as long as traces should be exported.
+#[derive(Debug)]
+pub struct OtlpGuard(TracerProvider);
+
+impl OtlpGuard {
+    pub fn shutdown(&self) -> TraceResult<()> {
+        self.0.shutdown()
+    }
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            eprintln!("failed to flush otlp traces - {:?}", e);
+        }
+    }
+}
+
+/// Builds a tracing layer that exports [`EXPORTED_SPANS`] to the configured
+/// collector. Spans are exported in batches from the tokio runtime so this has
+/// to be called from within one.
+pub fn otlp_layer<S>(config: &OtlpConfig) -> eyre::Result<(impl Layer<S>, OtlpGuard)>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>
+{
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(config.endpoint.clone())
+        .build()?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            config.service_name.clone()
+        )]))
+        .build();
+    let tracer = provider.tracer("angstrom");
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(filter_fn(|meta| {
+            if meta.is_span() {
+                EXPORTED_SPANS.contains(&meta.name())
+            } else {
+                // events are attached to their span
+                *meta.level() <= Level::INFO
+            }
+        }));
+
+    Ok((layer, OtlpGuard(provider)))
+}
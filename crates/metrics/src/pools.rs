@@ -0,0 +1,71 @@
+use std::fmt::Debug;
+
+use prometheus::IntCounter;
+
+use crate::METRICS_ENABLED;
+
+#[derive(Clone)]
+struct PoolIntegrityMetrics {
+    // synced pools compared against their on chain slot0
+    checks:      IntCounter,
+    // synced pools whose price or tick diverged from the chain
+    divergences: IntCounter
+}
+
+impl Default for PoolIntegrityMetrics {
+    fn default() -> Self {
+        let checks = prometheus::register_int_counter!(
+            "amm_integrity_checks",
+            "synced pools compared against their on chain slot0"
+        )
+        .unwrap();
+        let divergences = prometheus::register_int_counter!(
+            "amm_integrity_divergences",
+            "synced pools whose price or tick diverged from the chain"
+        )
+        .unwrap();
+
+        Self { checks, divergences }
+    }
+}
+
+#[derive(Clone)]
+pub struct PoolIntegrityMetricsWrapper(Option<PoolIntegrityMetrics>);
+
+impl Default for PoolIntegrityMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PoolIntegrityMetricsWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PoolIntegrityMetricsWrapper")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PoolIntegrityMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(PoolIntegrityMetrics::default)
+        )
+    }
+
+    pub fn checked(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.checks.inc()
+        }
+    }
+
+    pub fn diverged(&self) {
+        if let Some(this) = self.0.as_ref() {
+            this.divergences.inc()
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use prometheus::IntCounterVec;
+
+use crate::METRICS_ENABLED;
+
+#[derive(Clone)]
+struct RpcGatewayMetrics {
+    // calls to the public rpc, per api key, method and whether they were served
+    calls: IntCounterVec
+}
+
+impl Default for RpcGatewayMetrics {
+    fn default() -> Self {
+        let calls = prometheus::register_int_counter_vec!(
+            "rpc_gateway_calls",
+            "calls to the public rpc, per api key, method and whether they were served",
+            &["key", "method", "outcome"]
+        )
+        .unwrap();
+
+        Self { calls }
+    }
+}
+
+impl RpcGatewayMetrics {
+    fn incr_calls(&self, key: &str, method: &str, outcome: &str) {
+        self.calls.with_label_values(&[key, method, outcome]).inc();
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcGatewayMetricsWrapper(Option<RpcGatewayMetrics>);
+
+impl Default for RpcGatewayMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RpcGatewayMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(RpcGatewayMetrics::default)
+        )
+    }
+
+    /// `key` is the name of the api key, never the key itself.
+    pub fn incr_calls(&self, key: &str, method: &str, outcome: &str) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_calls(key, method, outcome)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct OrderFeedMetrics {
+    // orders pulled from external order flow feeds, per feed and outcome
+    orders: IntCounterVec
+}
+
+impl Default for OrderFeedMetrics {
+    fn default() -> Self {
+        let orders = prometheus::register_int_counter_vec!(
+            "order_feed_orders",
+            "orders pulled from external order flow feeds, per feed and outcome",
+            &["feed", "outcome"]
+        )
+        .unwrap();
+
+        Self { orders }
+    }
+}
+
+impl OrderFeedMetrics {
+    fn incr_orders(&self, feed: &str, outcome: &str) {
+        self.orders.with_label_values(&[feed, outcome]).inc();
+    }
+}
+
+#[derive(Clone)]
+pub struct OrderFeedMetricsWrapper(Option<OrderFeedMetrics>);
+
+impl Default for OrderFeedMetricsWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderFeedMetricsWrapper {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(OrderFeedMetrics::default)
+        )
+    }
+
+    pub fn incr_orders(&self, feed: &str, outcome: &str) {
+        if let Some(this) = self.0.as_ref() {
+            this.incr_orders(feed, outcome)
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{future::Future, pin::Pin, time::Instant};
 
-use prometheus::{Histogram, HistogramVec, IntGauge};
+use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
 
 use crate::METRICS_ENABLED;
 
@@ -15,6 +15,9 @@ struct ValidationMetricsInner {
     // simulation
     simulate_bundle:            Histogram,
     fetch_gas_for_user:         HistogramVec,
+    failed_simulations:         IntCounter,
+    /// block of the last failed simulation, its trace id starts with it
+    last_failed_simulation:     IntGauge,
     // state
     loading_balances:           Histogram,
     loading_approvals:          Histogram,
@@ -68,6 +71,18 @@ impl Default for ValidationMetricsInner {
         )
         .unwrap();
 
+        let failed_simulations = prometheus::register_int_counter!(
+            "failed_bundle_simulations",
+            "bundle simulations that reverted or halted"
+        )
+        .unwrap();
+
+        let last_failed_simulation = prometheus::register_int_gauge!(
+            "last_failed_bundle_simulation_block",
+            "block of the last failed bundle simulation, the id of its trace starts with it"
+        )
+        .unwrap();
+
         let loading_balances = prometheus::register_histogram!(
             "loading_balance_time",
             "time to load balanace from db",
@@ -96,6 +111,8 @@ impl Default for ValidationMetricsInner {
             processing_time,
             simulate_bundle,
             fetch_gas_for_user,
+            failed_simulations,
+            last_failed_simulation,
             loading_balances,
             loading_approvals,
             applying_state_transitions
@@ -249,4 +266,88 @@ impl ValidationMetrics {
 
         f()
     }
+
+    pub fn failed_bundle_simulation(&self, block_number: u64) {
+        if let Some(inner) = self.0.as_ref() {
+            inner.failed_simulations.inc();
+            inner.last_failed_simulation.set(block_number as i64);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct StateReadMetricsInner {
+    // time (ns) a read of the validation state took, per backend and read
+    read_time: HistogramVec,
+    // reads the local database failed that were retried over rpc
+    fallbacks: IntCounterVec
+}
+
+impl Default for StateReadMetricsInner {
+    fn default() -> Self {
+        let buckets = prometheus::exponential_buckets(1000.0, 2.0, 20).unwrap();
+
+        let read_time = prometheus::register_histogram_vec!(
+            "validation_state_read_time",
+            "time (ns) a read of the validation state took, per backend and read",
+            &["backend", "read"],
+            buckets
+        )
+        .unwrap();
+        let fallbacks = prometheus::register_int_counter_vec!(
+            "validation_state_read_fallbacks",
+            "reads the local database failed that were retried over rpc",
+            &["read"]
+        )
+        .unwrap();
+
+        Self { read_time, fallbacks }
+    }
+}
+
+/// Latency of the state reads of validation, to compare the backends with.
+#[derive(Clone)]
+pub struct StateReadMetrics(Option<StateReadMetricsInner>);
+
+impl Default for StateReadMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateReadMetrics {
+    pub fn new() -> Self {
+        Self(
+            METRICS_ENABLED
+                .get()
+                .copied()
+                .unwrap_or_default()
+                .then(StateReadMetricsInner::default)
+        )
+    }
+
+    pub fn measure_read<T>(
+        &self,
+        backend: &'static str,
+        read: &'static str,
+        f: impl FnOnce() -> T
+    ) -> T {
+        let Some(inner) = self.0.as_ref() else { return f() };
+
+        let start = Instant::now();
+        let r = f();
+        let elapsed = start.elapsed().as_nanos() as f64;
+        inner
+            .read_time
+            .with_label_values(&[backend, read])
+            .observe(elapsed);
+
+        r
+    }
+
+    pub fn fell_back(&self, read: &'static str) {
+        if let Some(inner) = self.0.as_ref() {
+            inner.fallbacks.with_label_values(&[read]).inc();
+        }
+    }
 }
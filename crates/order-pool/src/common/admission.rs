@@ -0,0 +1,50 @@
+use angstrom_types::sol_bindings::{grouped_orders::OrderWithStorageData, RawPoolOrder};
+
+/// Share of the max size the pool is evicted down to once it's full.
+pub const LOW_WATERMARK_PCT_DEFAULT: usize = 90;
+
+/// What the limit pool does with a new order once it's full. Without any
+/// eviction the order is rejected.
+///
+/// Evicting frees more than the new order needs, down to the low watermark,
+/// so a pool under pressure doesn't evict on every single insert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdmissionPolicy {
+    /// evicts the parked order that pays the least per unit of gas. a parked
+    /// order only makes room for another one if it pays less
+    pub evict_parked:      bool,
+    /// orders whose notional is below this count as dust and the oldest of
+    /// them is evicted, dust orders don't evict each other
+    pub dust_notional:     Option<u128>,
+    /// percent of the max size the pool is evicted down to
+    pub low_watermark_pct: usize
+}
+
+impl Default for AdmissionPolicy {
+    fn default() -> Self {
+        Self {
+            evict_parked:      false,
+            dust_notional:     None,
+            low_watermark_pct: LOW_WATERMARK_PCT_DEFAULT
+        }
+    }
+}
+
+impl AdmissionPolicy {
+    pub fn evicts(&self) -> bool {
+        self.evict_parked || self.dust_notional.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// parked order with the lowest fee per gas
+    LowFeeParked,
+    /// oldest order below the dust notional
+    OldestDust
+}
+
+/// Gas fee an order pays per unit of gas, in token0.
+pub fn fee_per_gas<O: RawPoolOrder>(order: &OrderWithStorageData<O>) -> u128 {
+    order.max_gas_token_0() / u128::from(order.priority_data.gas_units.max(1))
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use angstrom_types::{orders::OrderId, primitive::PoolId};
+
+/// How much of the pool a single address can take up. A bot that runs away
+/// shouldn't be able to crowd everyone else out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountLimits {
+    /// open orders an address can have across all pools
+    pub max_open_orders:       Option<usize>,
+    /// combined size of the open orders of an address in a single pool, in
+    /// that pool's token0
+    pub max_notional_per_pool: Option<u128>
+}
+
+/// An order that would take its address over one of the [`AccountLimits`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AccountCapExceeded {
+    #[error("{address} already has {open} open orders, the limit is {max}")]
+    OpenOrders { address: Address, open: usize, max: usize },
+    #[error(
+        "order of {address} would take its notional in pool {pool_id:?} to {notional}, the limit \
+         is {max}"
+    )]
+    Notional { address: Address, pool_id: PoolId, notional: u128, max: u128 }
+}
+
+/// Open orders and notional of every address that has orders in the pool.
+#[derive(Debug, Default)]
+pub struct AccountCaps {
+    limits:      AccountLimits,
+    open_orders: HashMap<Address, usize>,
+    notional:    HashMap<(Address, PoolId), u128>
+}
+
+impl AccountCaps {
+    pub fn new(limits: AccountLimits) -> Self {
+        Self { limits, ..Default::default() }
+    }
+
+    /// Checks that an order of `notional` fits under the limits of its
+    /// address, without counting it yet.
+    pub fn check(&self, id: &OrderId, notional: u128) -> Result<(), AccountCapExceeded> {
+        let address = id.address;
+        if let Some(max) = self.limits.max_open_orders {
+            let open = self.open_orders.get(&address).copied().unwrap_or_default();
+            if open >= max {
+                return Err(AccountCapExceeded::OpenOrders { address, open, max })
+            }
+        }
+        if let Some(max) = self.limits.max_notional_per_pool {
+            let notional = self
+                .notional
+                .get(&(address, id.pool_id))
+                .copied()
+                .unwrap_or_default()
+                .saturating_add(notional);
+            if notional > max {
+                return Err(AccountCapExceeded::Notional {
+                    address,
+                    pool_id: id.pool_id,
+                    notional,
+                    max
+                })
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, id: &OrderId, notional: u128) {
+        *self.open_orders.entry(id.address).or_default() += 1;
+        *self.notional.entry((id.address, id.pool_id)).or_default() += notional;
+    }
+
+    pub fn remove(&mut self, id: &OrderId, notional: u128) {
+        if let Some(open) = self.open_orders.get_mut(&id.address) {
+            *open = open.saturating_sub(1);
+            if *open == 0 {
+                self.open_orders.remove(&id.address);
+            }
+        }
+        if let Some(total) = self.notional.get_mut(&(id.address, id.pool_id)) {
+            *total = total.saturating_sub(notional);
+            if *total == 0 {
+                self.notional.remove(&(id.address, id.pool_id));
+            }
+        }
+    }
+}
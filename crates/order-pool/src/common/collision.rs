@@ -0,0 +1,35 @@
+use alloy::primitives::B256;
+use angstrom_types::orders::OrderId;
+
+/// An order that is inserted under a hash the pool already holds an order for.
+///
+/// The pools are keyed by order hash alone, so a second order under the same
+/// hash would silently replace the first one and leave its indices dangling.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OrderHashConflict {
+    #[error("order {0:?} is already in the pool")]
+    Duplicate(B256),
+    #[error("order hash {hash:?} of {incoming:?} is already taken by {existing:?}")]
+    Collision { hash: B256, existing: Box<OrderId>, incoming: Box<OrderId> }
+}
+
+impl OrderHashConflict {
+    /// Checks `incoming` against the order that is stored under its hash. The
+    /// same order is a duplicate, anything else that hashes the same is a
+    /// collision.
+    pub fn check(existing: Option<OrderId>, incoming: &OrderId) -> Result<(), Self> {
+        match existing {
+            None => Ok(()),
+            Some(existing) if existing == *incoming => Err(Self::Duplicate(incoming.hash)),
+            Some(existing) => Err(Self::Collision {
+                hash:     incoming.hash,
+                existing: Box::new(existing),
+                incoming: Box::new(*incoming)
+            })
+        }
+    }
+
+    pub fn is_collision(&self) -> bool {
+        matches!(self, Self::Collision { .. })
+    }
+}
@@ -1,2 +1,8 @@
+mod admission;
+mod caps;
+mod collision;
 mod size;
+pub use admission::*;
+pub use caps::*;
+pub use collision::*;
 pub use size::*;
@@ -24,8 +24,7 @@ impl SizeTracker {
         }
     }
 
-    #[allow(dead_code)]
     pub fn remove_order(&mut self, size: usize) {
-        self.current -= size;
+        self.current = self.current.saturating_sub(size);
     }
 }
@@ -1,5 +1,9 @@
+use std::{path::PathBuf, time::Duration};
+
 use angstrom_types::primitive::PoolId;
 
+use crate::{AccountLimits, AdmissionPolicy};
+
 /// Guarantees max orders per sender
 pub const ORDER_POOL_MAX_ACCOUNT_SLOTS_PER_SENDER: usize = 16;
 
@@ -15,35 +19,65 @@ pub const SEARCHER_SUBPOOL_MAX_ORDERS_DEFAULT: usize = 100;
 /// The default maximum allowed size of the searcher subpool.
 pub const SEARCHER_SUBPOOL_MAX_SIZE_MB_DEFAULT: usize = 5;
 
+/// The default time the hashes of filled orders are remembered for.
+pub const FILLED_ORDERS_TTL_DEFAULT: Duration = Duration::from_secs(60 * 60);
+
+/// The default maximum amount of filled order hashes that are remembered.
+pub const FILLED_ORDERS_CAPACITY_DEFAULT: usize = 100_000;
+
+/// The default interval expired filled order hashes are forgotten at, on top
+/// of every new block.
+pub const FILLED_ORDERS_GC_INTERVAL_DEFAULT: Duration = Duration::from_secs(60);
+
 /// Configuration options for the Transaction pool.
 #[derive(Debug, Clone)]
 pub struct PoolConfig {
     /// pool ids
-    pub ids:               Vec<PoolId>,
+    pub ids: Vec<PoolId>,
     /// Max number of transaction in the pending sub-pool
-    pub lo_pending_limit:  LimitSubPoolLimit,
+    pub lo_pending_limit: LimitSubPoolLimit,
     /// Max number of transaction in the queued sub-pool
-    pub lo_queued_limit:   LimitSubPoolLimit,
+    pub lo_queued_limit: LimitSubPoolLimit,
     /// Max number of transaction in the parked sub-pool
-    pub lo_parked_limit:   LimitSubPoolLimit,
+    pub lo_parked_limit: LimitSubPoolLimit,
     /// Max number of transaction in the composable limit sub-pool
-    pub cl_pending_limit:  LimitSubPoolLimit,
+    pub cl_pending_limit: LimitSubPoolLimit,
     /// Max number of transaction in the searcher & composable searcher sub-pool
-    pub s_pending_limit:   SearcherSubPoolLimit,
+    pub s_pending_limit: SearcherSubPoolLimit,
     /// Max number of executable transaction slots guaranteed per account
-    pub max_account_slots: usize
+    pub max_account_slots: usize,
+    /// Caps on the open orders and notional of a single address
+    pub account_limits: AccountLimits,
+    /// What limit orders are evicted for new ones once the pool is full
+    pub admission: AdmissionPolicy,
+    /// Time filled orders are rejected as duplicates for
+    pub filled_orders_ttl: Duration,
+    /// Max number of filled orders remembered, the ones closest to expiry are
+    /// forgotten first past it
+    pub filled_orders_capacity: usize,
+    /// How often filled orders whose ttl passed are forgotten
+    pub filled_orders_gc_interval: Duration,
+    /// File the recently filled and cancelled orders are kept in across
+    /// restarts, they are only kept in memory if unset
+    pub settled_orders_path: Option<PathBuf>
 }
 
 impl Default for PoolConfig {
     fn default() -> Self {
         Self {
-            ids:               vec![],
-            lo_pending_limit:  Default::default(),
-            lo_queued_limit:   Default::default(),
-            lo_parked_limit:   Default::default(),
-            cl_pending_limit:  Default::default(),
-            s_pending_limit:   Default::default(),
-            max_account_slots: ORDER_POOL_MAX_ACCOUNT_SLOTS_PER_SENDER
+            ids: vec![],
+            lo_pending_limit: Default::default(),
+            lo_queued_limit: Default::default(),
+            lo_parked_limit: Default::default(),
+            cl_pending_limit: Default::default(),
+            s_pending_limit: Default::default(),
+            max_account_slots: ORDER_POOL_MAX_ACCOUNT_SLOTS_PER_SENDER,
+            account_limits: AccountLimits::default(),
+            admission: AdmissionPolicy::default(),
+            filled_orders_ttl: FILLED_ORDERS_TTL_DEFAULT,
+            filled_orders_capacity: FILLED_ORDERS_CAPACITY_DEFAULT,
+            filled_orders_gc_interval: FILLED_ORDERS_GC_INTERVAL_DEFAULT,
+            settled_orders_path: None
         }
     }
 }
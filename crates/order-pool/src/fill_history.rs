@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use alloy::primitives::{BlockNumber, B256};
+use angstrom_types::{
+    orders::{BlockFills, OrderId},
+    primitive::PoolId
+};
+
+/// Rounds the fills of a pool are kept for.
+const MAX_ROUNDS: usize = 64;
+
+/// How many of the limit orders offered to the last rounds of every pool got
+/// filled.
+#[derive(Debug, Default)]
+pub struct FillHistory {
+    pools: HashMap<PoolId, VecDeque<BlockFills>>
+}
+
+impl FillHistory {
+    /// Records the orders offered to the round of `block` and the ones of them
+    /// that got filled. A round recorded again for the same block replaces
+    /// the first one.
+    pub fn record_round<'a>(
+        &mut self,
+        block: BlockNumber,
+        offered: impl IntoIterator<Item = &'a OrderId>,
+        filled: &HashSet<B256>
+    ) {
+        let mut rounds = HashMap::<PoolId, BlockFills>::new();
+        for id in offered {
+            let round = rounds
+                .entry(id.pool_id)
+                .or_insert(BlockFills { block, ..Default::default() });
+            round.offered += 1;
+            round.filled += filled.contains(&id.hash) as usize;
+        }
+
+        for (pool_id, round) in rounds {
+            let history = self.pools.entry(pool_id).or_default();
+            if history.back().is_some_and(|last| last.block == block) {
+                history.pop_back();
+            }
+            if history.len() == MAX_ROUNDS {
+                history.pop_front();
+            }
+            history.push_back(round);
+        }
+    }
+
+    /// Fills of the last rounds of `pool_id`, oldest first.
+    pub fn of_pool(&self, pool_id: &PoolId) -> Vec<BlockFills> {
+        self.pools
+            .get(pool_id)
+            .map(|history| history.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn remove_pool(&mut self, pool_id: &PoolId) {
+        self.pools.remove(pool_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(pool: u8, hash: u8) -> OrderId {
+        OrderId {
+            pool_id: PoolId::repeat_byte(pool),
+            hash: B256::repeat_byte(hash),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keeps_the_fills_of_the_last_rounds_per_pool() {
+        let mut history = FillHistory::default();
+        let (a, b, c) = (id(1, 1), id(1, 2), id(2, 3));
+
+        history.record_round(10, [&a, &b, &c], &HashSet::from([a.hash]));
+        // a reorged round replaces the first one of the block
+        history.record_round(11, [&a, &b], &HashSet::new());
+        history.record_round(11, [&b], &HashSet::from([b.hash]));
+
+        assert_eq!(
+            history.of_pool(&a.pool_id),
+            vec![
+                BlockFills { block: 10, offered: 2, filled: 1 },
+                BlockFills { block: 11, offered: 1, filled: 1 }
+            ]
+        );
+        assert_eq!(
+            history.of_pool(&c.pool_id),
+            vec![BlockFills { block: 10, offered: 1, filled: 0 }]
+        );
+
+        for block in 12..100 {
+            history.record_round(block, [&a], &HashSet::new());
+        }
+        let fills = history.of_pool(&a.pool_id);
+        assert_eq!(fills.len(), MAX_ROUNDS);
+        assert_eq!(fills[0].block, 100 - MAX_ROUNDS as u64);
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration
+};
+
+use alloy::primitives::B256;
+
+/// Hashes of the recently filled orders, so that replays of them aren't
+/// validated again.
+///
+/// A hash is forgotten once its ttl passed. The cache is bounded on top of
+/// that, once it's full the hashes that expire the soonest are evicted first.
+#[derive(Debug, Clone)]
+pub struct FilledOrders {
+    /// order hash to the unix time it expires at
+    expiries:  HashMap<B256, u64>,
+    by_expiry: BTreeSet<(u64, B256)>,
+    ttl:       Duration,
+    capacity:  usize
+}
+
+impl FilledOrders {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self { expiries: HashMap::new(), by_expiry: BTreeSet::new(), ttl, capacity }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.expiries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expiries.is_empty()
+    }
+
+    pub fn contains(&self, order_hash: &B256) -> bool {
+        self.expiries.contains_key(order_hash)
+    }
+
+    /// Order hashes mapped to the unix time they expire at.
+    pub fn expiries(&self) -> &HashMap<B256, u64> {
+        &self.expiries
+    }
+
+    /// Remembers the orders filled at the unix time `now`, returns how many
+    /// hashes were evicted to make room for them.
+    pub fn insert(&mut self, order_hashes: impl IntoIterator<Item = B256>, now: u64) -> usize {
+        let expires_at = now + self.ttl.as_secs();
+        self.extend(order_hashes.into_iter().map(|hash| (hash, expires_at)))
+    }
+
+    /// Remembers every hash until the unix time it's paired with, returns how
+    /// many hashes were evicted to make room for them.
+    pub fn extend(&mut self, expiries: impl IntoIterator<Item = (B256, u64)>) -> usize {
+        for (hash, expires_at) in expiries {
+            if let Some(previous) = self.expiries.insert(hash, expires_at) {
+                self.by_expiry.remove(&(previous, hash));
+            }
+            self.by_expiry.insert((expires_at, hash));
+        }
+
+        let mut evicted = 0;
+        while self.expiries.len() > self.capacity {
+            let Some((_, hash)) = self.by_expiry.pop_first() else { break };
+            self.expiries.remove(&hash);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    pub fn remove(&mut self, order_hash: &B256) -> bool {
+        let Some(expires_at) = self.expiries.remove(order_hash) else { return false };
+        self.by_expiry.remove(&(expires_at, *order_hash));
+        true
+    }
+
+    /// Forgets the hashes that expired before the unix time `now`, returns how
+    /// many there were.
+    pub fn remove_expired(&mut self, now: u64) -> usize {
+        let mut expired = 0;
+        while let Some(&(expires_at, hash)) = self.by_expiry.first() {
+            if expires_at >= now {
+                break
+            }
+            self.by_expiry.pop_first();
+            self.expiries.remove(&hash);
+            expired += 1;
+        }
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_hashes_once_they_expire() {
+        let mut filled = FilledOrders::new(Duration::from_secs(10), 10);
+        let (a, b) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        filled.insert([a], 100);
+        filled.insert([b], 105);
+
+        assert_eq!(filled.remove_expired(110), 0);
+        assert_eq!(filled.remove_expired(111), 1);
+        assert!(!filled.contains(&a) && filled.contains(&b));
+
+        // filled again, the hash lives on from the new fill
+        filled.insert([b], 110);
+        assert_eq!(filled.remove_expired(116), 0);
+        assert!(filled.remove(&b));
+        assert!(filled.is_empty());
+        assert_eq!(filled.remove_expired(u64::MAX), 0);
+    }
+
+    #[test]
+    fn evicts_the_hashes_closest_to_expiry_once_full() {
+        let mut filled = FilledOrders::new(Duration::from_secs(10), 2);
+        let hashes = (1..=3).map(B256::repeat_byte).collect::<Vec<_>>();
+
+        assert_eq!(filled.insert(hashes[..2].iter().copied(), 100), 0);
+        assert_eq!(filled.insert([hashes[2]], 101), 1);
+        assert_eq!(filled.len(), 2);
+        assert!(filled.contains(&hashes[2]));
+        // of the two filled at the same time, one is kept
+        assert!(filled.contains(&hashes[0]) != filled.contains(&hashes[1]));
+    }
+}
@@ -48,6 +48,10 @@ impl FinalizationPool {
         self.id_to_orders.contains_key(order)
     }
 
+    pub fn order_hashes(&self) -> impl Iterator<Item = FixedBytes<32>> + '_ {
+        self.id_to_orders.keys().copied()
+    }
+
     pub fn reorg(
         &mut self,
         orders: Vec<FixedBytes<32>>
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use alloy::primitives::{BlockNumber, B256};
+
+/// Blocks an order can be gone from the pool for before its first seen block
+/// is forgotten, enough to be validated again after a state change.
+const FORGET_AFTER_BLOCKS: u64 = 2;
+
+/// The block every limit order was first added to the pool in.
+///
+/// Orders are removed from the pool and added back whenever they are validated
+/// again, which moves their valid block. The first seen block stays, so that
+/// an order ages from the block it arrived in.
+#[derive(Debug, Default)]
+pub struct FirstSeenBlocks {
+    /// order hash -> (block it was first added in, last block it was pending)
+    blocks: HashMap<B256, (BlockNumber, BlockNumber)>
+}
+
+impl FirstSeenBlocks {
+    pub fn on_added(&mut self, hash: B256, block: BlockNumber) {
+        let (_, last) = self.blocks.entry(hash).or_insert((block, block));
+        *last = (*last).max(block);
+    }
+
+    /// Marks the orders as still pending at `block` and forgets the ones that
+    /// weren't for a while.
+    pub fn prune(&mut self, block: BlockNumber, mut is_pending: impl FnMut(&B256) -> bool) {
+        self.blocks.retain(|hash, (_, last)| {
+            if is_pending(hash) {
+                *last = block;
+            }
+            *last + FORGET_AFTER_BLOCKS >= block
+        });
+    }
+
+    pub fn first_seen(&self) -> HashMap<B256, BlockNumber> {
+        self.blocks
+            .iter()
+            .map(|(hash, (first, _))| (*hash, *first))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revalidation_keeps_the_first_block() {
+        let (order, gone) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        let mut blocks = FirstSeenBlocks::default();
+        blocks.on_added(order, 10);
+        blocks.on_added(gone, 10);
+
+        // validated again a few blocks later
+        blocks.on_added(order, 13);
+        assert_eq!(blocks.first_seen()[&order], 10);
+
+        blocks.prune(13, |hash| *hash == order);
+        assert_eq!(blocks.first_seen().len(), 1);
+        blocks.prune(20, |hash| *hash == order);
+        assert_eq!(blocks.first_seen(), HashMap::from([(order, 10)]));
+    }
+}
@@ -1,24 +1,36 @@
 mod common;
 mod config;
+mod fill_history;
+mod filled_orders;
 mod finalization_pool;
+mod first_seen;
 mod limit;
 mod order_indexer;
+pub mod order_set_diff;
 pub mod order_storage;
 
 mod searcher;
+mod settled_orders;
 mod validator;
 
 use std::future::Future;
 
 use alloy::primitives::{Address, FixedBytes, B256};
 use angstrom_types::{
-    orders::{CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus},
-    primitive::OrderPoolNewOrderResult,
+    orders::{
+        CancelAllOrdersRequest, CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus,
+        PoolAnalytics, PricePeg
+    },
+    primitive::{OrderPoolNewOrderResult, PoolId},
     sol_bindings::grouped_orders::{AllOrders, OrderWithStorageData}
 };
 pub use angstrom_utils::*;
-pub use config::PoolConfig;
+pub use common::{
+    AccountCapExceeded, AccountLimits, AdmissionPolicy, EvictionReason, LOW_WATERMARK_PCT_DEFAULT
+};
+pub use config::{PoolConfig, FILLED_ORDERS_CAPACITY_DEFAULT, FILLED_ORDERS_TTL_DEFAULT};
 pub use order_indexer::*;
+pub use settled_orders::{SettledOrders, SettledOrdersWriter};
 use tokio_stream::wrappers::BroadcastStream;
 
 #[derive(Debug, Clone)]
@@ -26,6 +38,7 @@ pub enum PoolManagerUpdate {
     NewOrder(OrderWithStorageData<AllOrders>),
     FilledOrder(u64, OrderWithStorageData<AllOrders>),
     UnfilledOrders(OrderWithStorageData<AllOrders>),
+    ExpiredOrder(OrderWithStorageData<AllOrders>),
     CancelledOrder { user: Address, pool_id: FixedBytes<32>, order_hash: B256 }
 }
 
@@ -39,12 +52,33 @@ pub trait OrderPoolHandle: Send + Sync + Clone + Unpin + 'static {
         order: AllOrders
     ) -> impl Future<Output = OrderPoolNewOrderResult> + Send;
 
+    /// Submits a standing order that is priced off the AMM mid by `peg`.
+    fn new_pegged_order(
+        &self,
+        order: AllOrders,
+        peg: PricePeg
+    ) -> impl Future<Output = OrderPoolNewOrderResult> + Send;
+
+    /// Submits the tranches of an iceberg order, only the first is shown
+    /// until it's filled. Returns the result of every tranche.
+    fn new_iceberg_order(
+        &self,
+        tranches: Vec<AllOrders>
+    ) -> impl Future<Output = Vec<OrderPoolNewOrderResult>> + Send;
+
     fn subscribe_orders(&self) -> BroadcastStream<PoolManagerUpdate>;
 
     fn pending_orders(&self, sender: Address) -> impl Future<Output = Vec<AllOrders>> + Send;
 
     fn cancel_order(&self, req: CancelOrderRequest) -> impl Future<Output = bool> + Send;
 
+    /// Cancels all resting orders of the signer, returns the hashes of the
+    /// cancelled orders or [`None`] if the request is invalid.
+    fn cancel_all_orders(
+        &self,
+        req: CancelAllOrdersRequest
+    ) -> impl Future<Output = Option<Vec<B256>>> + Send;
+
     fn fetch_orders_from_pool(
         &self,
         pool_id: FixedBytes<32>,
@@ -55,4 +89,12 @@ pub trait OrderPoolHandle: Send + Sync + Clone + Unpin + 'static {
         &self,
         order_hash: B256
     ) -> impl Future<Output = Option<OrderStatus>> + Send;
+
+    /// How the orders of the pool are made up, with its pending limit orders
+    /// split into `price_buckets` by their price.
+    fn fetch_pool_analytics(
+        &self,
+        pool_id: PoolId,
+        price_buckets: usize
+    ) -> impl Future<Output = PoolAnalytics> + Send;
 }
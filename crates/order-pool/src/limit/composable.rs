@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
+use alloy::primitives::B256;
 use angstrom_metrics::ComposableLimitOrderPoolMetricsWrapper;
 use angstrom_types::{
+    orders::OrderId,
     primitive::{NewInitializedPool, PoolId},
     sol_bindings::grouped_orders::{GroupedComposableOrder, OrderWithStorageData}
 };
@@ -31,6 +33,11 @@ impl ComposableLimitPool {
             .and_then(|pool| pool.get_order(order_id))
     }
 
+    /// Id of the order stored under `hash` in any pool.
+    pub fn get_order_id(&self, hash: B256) -> Option<OrderId> {
+        self.map.values().find_map(|pool| pool.get_order_id(hash))
+    }
+
     pub fn add_order(
         &mut self,
         order: OrderWithStorageData<GroupedComposableOrder>
@@ -1,8 +1,8 @@
-use std::fmt::Debug;
+use std::{collections::HashSet, fmt::Debug, mem};
 
 use alloy::primitives::{FixedBytes, B256};
 use angstrom_types::{
-    orders::{OrderId, OrderStatus},
+    orders::{InvariantViolation, OrderId, OrderStatus},
     primitive::{NewInitializedPool, PoolId},
     sol_bindings::grouped_orders::{
         AllOrders, GroupedComposableOrder, GroupedUserOrder, GroupedVanillaOrder,
@@ -11,7 +11,10 @@ use angstrom_types::{
 };
 
 use self::{composable::ComposableLimitPool, standard::LimitPool};
-use crate::common::SizeTracker;
+use crate::common::{
+    fee_per_gas, AccountCapExceeded, AccountCaps, AccountLimits, AdmissionPolicy, EvictionReason,
+    OrderHashConflict, SizeTracker
+};
 mod composable;
 mod parked;
 mod pending;
@@ -24,7 +27,13 @@ pub struct LimitOrderPool {
     /// Sub-pool of all composable orders
     composable_orders: ComposableLimitPool,
     /// The size of the current transactions.
-    size:              SizeTracker
+    size:              SizeTracker,
+    /// Open orders and notional of every address
+    caps:              AccountCaps,
+    /// What is evicted to admit an order once the pool is full
+    admission:         AdmissionPolicy,
+    /// Orders evicted since the last [`LimitOrderPool::take_evicted`]
+    evicted:           Vec<(EvictionReason, OrderWithStorageData<GroupedUserOrder>)>
 }
 
 impl LimitOrderPool {
@@ -32,10 +41,30 @@ impl LimitOrderPool {
         Self {
             composable_orders: ComposableLimitPool::new(ids),
             limit_orders:      LimitPool::new(ids),
-            size:              SizeTracker { max: max_size, current: 0 }
+            size:              SizeTracker { max: max_size, current: 0 },
+            caps:              AccountCaps::default(),
+            admission:         AdmissionPolicy::default(),
+            evicted:           vec![]
         }
     }
 
+    pub fn with_admission_policy(mut self, admission: AdmissionPolicy) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Orders that were evicted to admit new ones since the last call.
+    pub fn take_evicted(
+        &mut self
+    ) -> Vec<(EvictionReason, OrderWithStorageData<GroupedUserOrder>)> {
+        mem::take(&mut self.evicted)
+    }
+
+    pub fn with_account_limits(mut self, limits: AccountLimits) -> Self {
+        self.caps = AccountCaps::new(limits);
+        self
+    }
+
     pub fn get_order(&self, id: &OrderId) -> Option<OrderWithStorageData<GroupedUserOrder>> {
         self.limit_orders
             .get_order(id.pool_id, id.hash)
@@ -56,9 +85,22 @@ impl LimitOrderPool {
     }
 
     pub fn remove_pool(&mut self, key: &PoolId) {
-        let _ = self.composable_orders.map.remove(key);
-        let _ = self.limit_orders.parked_orders.remove(key);
-        let _ = self.limit_orders.pending_orders.remove(key);
+        if let Some(pool) = self.composable_orders.map.remove(key) {
+            pool.iter().for_each(|order| {
+                self.caps.remove(&order.order_id, order.quantity());
+                self.size.remove_order(order.size());
+            });
+        }
+        let parked = self.limit_orders.parked_orders.remove(key);
+        let pending = self.limit_orders.pending_orders.remove(key);
+        parked
+            .iter()
+            .flat_map(|pool| pool.iter())
+            .chain(pending.iter().flat_map(|pool| pool.iter()))
+            .for_each(|order| {
+                self.caps.remove(&order.order_id, order.quantity());
+                self.size.remove_order(order.size());
+            });
     }
 
     pub fn get_order_status(&self, order_hash: B256) -> Option<OrderStatus> {
@@ -69,29 +111,174 @@ impl LimitOrderPool {
         &mut self,
         order: OrderWithStorageData<GroupedComposableOrder>
     ) -> Result<(), LimitPoolError> {
-        let size = order.size();
-        if !self.size.has_space(size) {
-            return Err(LimitPoolError::MaxSize)
-        }
+        self.check_hash_conflict(&order.order_id)?;
+        let (id, notional) = (order.order_id, order.quantity());
+        self.caps.check(&id, notional)?;
+        self.admit(order.size(), false, fee_per_gas(&order), notional)?;
+
+        self.composable_orders.add_order(order)?;
+        self.caps.insert(&id, notional);
 
-        self.composable_orders.add_order(order)
+        Ok(())
     }
 
     pub fn add_vanilla_order(
         &mut self,
         order: OrderWithStorageData<GroupedVanillaOrder>
     ) -> Result<(), LimitPoolError> {
-        let size = order.size();
-        if !self.size.has_space(size) {
+        self.check_hash_conflict(&order.order_id)?;
+        let (id, notional) = (order.order_id, order.quantity());
+        self.caps.check(&id, notional)?;
+        self.admit(order.size(), !order.is_currently_valid, fee_per_gas(&order), notional)?;
+
+        self.limit_orders.add_order(order)?;
+        self.caps.insert(&id, notional);
+
+        Ok(())
+    }
+
+    /// Makes room for an order of `size`. Once the pool is full the orders the
+    /// admission policy considers less valuable than the new one are evicted,
+    /// nothing is evicted if that still wouldn't make enough room.
+    fn admit(
+        &mut self,
+        size: usize,
+        parked: bool,
+        fee: u128,
+        notional: u128
+    ) -> Result<(), LimitPoolError> {
+        if self.size.has_space(size) {
+            return Ok(())
+        }
+        let Some(max) = self.size.max.filter(|_| self.admission.evicts()) else {
             return Err(LimitPoolError::MaxSize)
+        };
+
+        let candidates = self.eviction_candidates(parked, fee, notional);
+        let freeable = candidates.iter().map(|(.., size)| size).sum::<usize>();
+        if self.size.current + size > max + freeable {
+            return Err(LimitPoolError::MaxSize)
+        }
+
+        // evict past what's needed so the next inserts find room right away
+        let low_watermark = max.saturating_mul(self.admission.low_watermark_pct.min(100)) / 100;
+        for (id, reason, _) in candidates {
+            if self.size.current + size <= low_watermark {
+                break
+            }
+            if let Some(order) = self.remove_order(&id) {
+                tracing::debug!(order_hash = ?id.hash, ?reason, "evicted order to admit a new one");
+                self.evicted.push((reason, order));
+            }
         }
 
-        self.limit_orders.add_order(order)
+        if self.size.has_space(size) {
+            Ok(())
+        } else {
+            Err(LimitPoolError::MaxSize)
+        }
+    }
+
+    /// Orders that can be evicted for a new one, in the order they go in.
+    fn eviction_candidates(
+        &self,
+        parked: bool,
+        fee: u128,
+        notional: u128
+    ) -> Vec<(OrderId, EvictionReason, usize)> {
+        let mut candidates = vec![];
+        if self.admission.evict_parked {
+            let mut parked_orders = self
+                .limit_orders
+                .parked_orders
+                .values()
+                .flat_map(|pool| pool.iter())
+                // a parked order only makes way for one that pays more
+                .filter(|order| !parked || fee_per_gas(order) < fee)
+                .map(|order| {
+                    let key = (fee_per_gas(order), order.valid_block, order.order_id.hash);
+                    (key, order.order_id, order.size())
+                })
+                .collect::<Vec<_>>();
+            parked_orders.sort_unstable_by_key(|(key, ..)| *key);
+            candidates.extend(
+                parked_orders
+                    .into_iter()
+                    .map(|(_, id, size)| (id, EvictionReason::LowFeeParked, size))
+            );
+        }
+
+        // dust orders don't make way for each other
+        if let Some(dust) = self
+            .admission
+            .dust_notional
+            .filter(|dust| notional >= *dust)
+        {
+            let vanilla = self
+                .limit_orders
+                .pending_orders
+                .values()
+                .flat_map(|pool| pool.iter())
+                .chain(
+                    self.limit_orders
+                        .parked_orders
+                        .values()
+                        .flat_map(|pool| pool.iter())
+                )
+                .filter(|order| order.quantity() < dust)
+                .map(|order| {
+                    ((order.valid_block, order.order_id.hash), order.order_id, order.size())
+                });
+            let composable = self
+                .composable_orders
+                .map
+                .values()
+                .flat_map(|pool| pool.iter())
+                .filter(|order| order.quantity() < dust)
+                .map(|order| {
+                    ((order.valid_block, order.order_id.hash), order.order_id, order.size())
+                });
+
+            let listed = candidates
+                .iter()
+                .map(|(id, ..)| id.hash)
+                .collect::<HashSet<_>>();
+            let mut dust_orders = vanilla
+                .chain(composable)
+                .filter(|(_, id, _)| !listed.contains(&id.hash))
+                .collect::<Vec<_>>();
+            dust_orders.sort_unstable_by_key(|(key, ..)| *key);
+            candidates.extend(
+                dust_orders
+                    .into_iter()
+                    .map(|(_, id, size)| (id, EvictionReason::OldestDust, size))
+            );
+        }
+
+        candidates
+    }
+
+    /// Vanilla and composable orders share the hash space, an order can't be
+    /// inserted under a hash that either of them already holds.
+    fn check_hash_conflict(&self, id: &OrderId) -> Result<(), OrderHashConflict> {
+        let existing = self
+            .limit_orders
+            .get_order_id(id.hash)
+            .or_else(|| self.composable_orders.get_order_id(id.hash));
+
+        OrderHashConflict::check(existing, id)
     }
 
     pub fn remove_order(&mut self, id: &OrderId) -> Option<OrderWithStorageData<GroupedUserOrder>> {
-        self.limit_orders
+        let vanilla = self
+            .limit_orders
             .remove_order(id.pool_id, id.hash)
+            .map(|value| {
+                self.caps.remove(&value.order_id, value.quantity());
+                self.size.remove_order(value.size());
+                value
+            });
+        vanilla
             .and_then(|value| {
                 value
                     .try_map_inner(|this| Ok(GroupedUserOrder::Vanilla(this)))
@@ -101,6 +288,8 @@ impl LimitOrderPool {
                 self.composable_orders
                     .remove_order(id.pool_id, id.hash)
                     .and_then(|value| {
+                        self.caps.remove(&value.order_id, value.quantity());
+                        self.size.remove_order(value.size());
                         value
                             .try_map_inner(|this| Ok(GroupedUserOrder::Composable(this)))
                             .ok()
@@ -112,6 +301,20 @@ impl LimitOrderPool {
         self.limit_orders.get_all_orders()
     }
 
+    /// The pending vanilla orders of `pool`, the ones its book is built from.
+    pub fn pending_orders_of_pool(
+        &self,
+        pool: &PoolId
+    ) -> Vec<OrderWithStorageData<GroupedVanillaOrder>> {
+        self.limit_orders
+            .pending_orders
+            .get(pool)
+            .map(|pool| pool.get_all_orders())
+            .unwrap_or_default()
+    }
+
+    /// The pending vanilla orders of the pool that are listed publicly,
+    /// hidden tranches and private orders aren't.
     pub fn get_all_orders_from_pool(&self, pool: FixedBytes<32>) -> Vec<AllOrders> {
         self.limit_orders
             .pending_orders
@@ -119,6 +322,7 @@ impl LimitOrderPool {
             .map(|pool| {
                 pool.get_all_orders()
                     .into_iter()
+                    .filter(|p| !p.is_hidden && !p.is_private)
                     .map(|p| p.order.into())
                     .collect::<Vec<_>>()
             })
@@ -129,6 +333,37 @@ impl LimitOrderPool {
         self.limit_orders.park_order(id);
     }
 
+    /// Hashes of all vanilla and composable orders.
+    pub fn order_hashes(&self) -> HashSet<B256> {
+        self.limit_orders
+            .order_hashes()
+            .chain(
+                self.composable_orders
+                    .map
+                    .values()
+                    .flat_map(|pool| pool.iter().map(|order| order.order_id.hash))
+            )
+            .collect()
+    }
+
+    pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+        let mut violations = self.limit_orders.invariant_violations();
+
+        let vanilla = self.limit_orders.order_hashes().collect::<HashSet<_>>();
+        let mut composable = HashSet::new();
+        for (pool_id, pool) in &self.composable_orders.map {
+            violations.extend(pool.invariant_violations(*pool_id));
+            violations.extend(
+                pool.iter()
+                    .map(|order| order.order_id.hash)
+                    .filter(|hash| vanilla.contains(hash) || !composable.insert(*hash))
+                    .map(InvariantViolation::DuplicateOrder)
+            );
+        }
+
+        violations
+    }
+
     pub fn new_pool(&mut self, pool: NewInitializedPool) {
         self.limit_orders.new_pool(pool);
         self.composable_orders.new_pool(pool);
@@ -142,5 +377,272 @@ pub enum LimitPoolError {
     #[error("No pool was found for address: {0} ")]
     NoPool(PoolId),
     #[error(transparent)]
+    HashConflict(#[from] OrderHashConflict),
+    #[error(transparent)]
+    AccountCap(#[from] AccountCapExceeded),
+    #[error(transparent)]
     Unknown(#[from] eyre::Error)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use alloy::primitives::Address;
+    use rand::Rng;
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    /// Stands in for a broken order hash, squashes the real one into a space
+    /// small enough that distinct orders collide all the time.
+    fn weak_hash(hash: B256) -> B256 {
+        B256::with_last_byte(hash[0] % 16)
+    }
+
+    fn order(pool_id: PoolId, amount: u128) -> OrderWithStorageData<GroupedVanillaOrder> {
+        let mut order = UserOrderBuilder::new()
+            .partial()
+            .amount(amount)
+            .with_storage()
+            .pool_id(pool_id)
+            .build();
+        order.order_id.hash = weak_hash(order.order_id.hash);
+        order.order_id.address = Address::random();
+        order
+    }
+
+    #[test]
+    fn rejects_colliding_hashes() {
+        let pools = [PoolId::random(), PoolId::random()];
+        let mut pool = LimitOrderPool::new(&pools, None);
+        let mut rng = rand::thread_rng();
+        // the order each hash resolves to
+        let mut stored = HashMap::<B256, OrderWithStorageData<GroupedVanillaOrder>>::new();
+
+        for amount in 1..=500 {
+            let order = if rng.gen_bool(0.1) && !stored.is_empty() {
+                // resubmission of an order that is already in
+                stored.values().next().cloned().unwrap()
+            } else {
+                order(pools[rng.gen_range(0..pools.len())], amount)
+            };
+            let hash = order.order_id.hash;
+            let expected = stored.get(&hash).map(|existing| {
+                if existing.order_id == order.order_id {
+                    OrderHashConflict::Duplicate(hash)
+                } else {
+                    OrderHashConflict::Collision {
+                        hash,
+                        existing: Box::new(existing.order_id),
+                        incoming: Box::new(order.order_id)
+                    }
+                }
+            });
+
+            match (pool.add_vanilla_order(order.clone()), expected) {
+                (Ok(()), None) => {
+                    stored.insert(hash, order);
+                }
+                (Err(LimitPoolError::HashConflict(conflict)), Some(expected)) => {
+                    assert_eq!(conflict, expected)
+                }
+                (res, expected) => panic!("inserted with {res:?}, expected {expected:?}")
+            }
+
+            if rng.gen_bool(0.2) {
+                let hash = *stored.keys().next().unwrap();
+                let removed = pool.remove_order(&stored.remove(&hash).unwrap().order_id);
+                assert!(removed.is_some());
+            }
+        }
+
+        for (hash, order) in &stored {
+            let found = pool.get_order(&order.order_id).unwrap();
+            assert_eq!(found.order_id, order.order_id, "order under {hash:?} was replaced");
+        }
+        assert_eq!(pool.order_hashes(), stored.keys().copied().collect());
+        assert!(pool.invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn enforces_account_limits() {
+        let pool_id = PoolId::random();
+        let mut pool = LimitOrderPool::new(&[pool_id], None).with_account_limits(AccountLimits {
+            max_open_orders:       Some(2),
+            max_notional_per_pool: Some(25)
+        });
+        let address = Address::random();
+        let account_order = |amount| {
+            let mut order = UserOrderBuilder::new()
+                .partial()
+                .amount(amount)
+                .with_storage()
+                .pool_id(pool_id)
+                .build();
+            order.order_id.address = address;
+            order
+        };
+
+        let first = account_order(10);
+        pool.add_vanilla_order(first.clone()).unwrap();
+        assert!(matches!(
+            pool.add_vanilla_order(account_order(20)),
+            Err(LimitPoolError::AccountCap(AccountCapExceeded::Notional {
+                notional: 30,
+                max: 25,
+                ..
+            }))
+        ));
+        pool.add_vanilla_order(account_order(15)).unwrap();
+        assert!(matches!(
+            pool.add_vanilla_order(account_order(1)),
+            Err(LimitPoolError::AccountCap(AccountCapExceeded::OpenOrders { open: 2, max: 2, .. }))
+        ));
+
+        // other addresses have their own caps
+        let mut other = account_order(25);
+        other.order_id.address = Address::random();
+        pool.add_vanilla_order(other).unwrap();
+
+        // removing an order frees its slot and its notional
+        pool.remove_order(&first.order_id).unwrap();
+        pool.add_vanilla_order(account_order(10)).unwrap();
+    }
+
+    fn admission_order(
+        pool_id: PoolId,
+        amount: u128,
+        gas_units: u64,
+        parked: bool
+    ) -> OrderWithStorageData<GroupedVanillaOrder> {
+        let mut order = order(pool_id, amount);
+        order.order_id.hash = B256::random();
+        // the builder puts the whole amount up for gas
+        order.priority_data.gas_units = gas_units;
+        order.is_currently_valid = !parked;
+        order
+    }
+
+    fn evicted_hashes(pool: &mut LimitOrderPool) -> Vec<(EvictionReason, B256)> {
+        pool.take_evicted()
+            .into_iter()
+            .map(|(reason, order)| (reason, order.order_id.hash))
+            .collect()
+    }
+
+    #[test]
+    fn evicts_low_fee_parked_orders() {
+        let pool_id = PoolId::random();
+        let slot = mem::size_of::<GroupedVanillaOrder>();
+        let mut pool = LimitOrderPool::new(&[pool_id], Some(3 * slot)).with_admission_policy(
+            AdmissionPolicy {
+                evict_parked:      true,
+                dust_notional:     None,
+                low_watermark_pct: 100
+            }
+        );
+        let cheap = admission_order(pool_id, 1_000, 100, true);
+        pool.add_vanilla_order(cheap.clone()).unwrap();
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 10, true))
+            .unwrap();
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 1, false))
+            .unwrap();
+
+        // a parked order has to pay more than the one it replaces
+        assert!(matches!(
+            pool.add_vanilla_order(admission_order(pool_id, 1_000, 1_000, true)),
+            Err(LimitPoolError::MaxSize)
+        ));
+        assert!(evicted_hashes(&mut pool).is_empty());
+
+        let better = admission_order(pool_id, 1_000, 20, true);
+        pool.add_vanilla_order(better.clone()).unwrap();
+        assert_eq!(
+            evicted_hashes(&mut pool),
+            vec![(EvictionReason::LowFeeParked, cheap.order_id.hash)]
+        );
+
+        // pending orders are admitted over any parked one
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 1_000, false))
+            .unwrap();
+        assert_eq!(
+            evicted_hashes(&mut pool),
+            vec![(EvictionReason::LowFeeParked, better.order_id.hash)]
+        );
+        assert!(pool.get_order(&cheap.order_id).is_none());
+        assert!(pool.invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn evicts_oldest_dust_down_to_the_low_watermark() {
+        let pool_id = PoolId::random();
+        let slot = mem::size_of::<GroupedVanillaOrder>();
+        let mut pool = LimitOrderPool::new(&[pool_id], Some(4 * slot)).with_admission_policy(
+            AdmissionPolicy {
+                evict_parked:      false,
+                dust_notional:     Some(100),
+                low_watermark_pct: 50
+            }
+        );
+        let dust = (1..=3)
+            .rev()
+            .map(|block| {
+                let mut order = admission_order(pool_id, 50, 1, false);
+                order.valid_block = block;
+                pool.add_vanilla_order(order.clone()).unwrap();
+                order
+            })
+            .collect::<Vec<_>>();
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 1, false))
+            .unwrap();
+
+        // dust doesn't make way for dust
+        assert!(matches!(
+            pool.add_vanilla_order(admission_order(pool_id, 10, 1, false)),
+            Err(LimitPoolError::MaxSize)
+        ));
+
+        // one slot is needed but the pool is evicted down to half of its size,
+        // oldest first
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 1, false))
+            .unwrap();
+        let expected = dust
+            .iter()
+            .rev()
+            .map(|order| (EvictionReason::OldestDust, order.order_id.hash))
+            .collect::<Vec<_>>();
+        assert_eq!(evicted_hashes(&mut pool), expected);
+
+        // and the next order gets in without evicting anything
+        pool.add_vanilla_order(admission_order(pool_id, 1_000, 1, false))
+            .unwrap();
+        assert!(evicted_hashes(&mut pool).is_empty());
+        assert_eq!(pool.order_hashes().len(), 3);
+    }
+
+    #[test]
+    fn composable_and_vanilla_share_hashes() {
+        let pool_id = PoolId::random();
+        let mut pool = LimitOrderPool::new(&[pool_id], None);
+        let vanilla = order(pool_id, 10);
+        pool.add_vanilla_order(vanilla.clone()).unwrap();
+
+        let mut composable = order(pool_id, 20)
+            .try_map_inner(|order| {
+                Ok(match order {
+                    GroupedVanillaOrder::Standing(order) => GroupedComposableOrder::Partial(order),
+                    GroupedVanillaOrder::KillOrFill(order) => {
+                        GroupedComposableOrder::KillOrFill(order)
+                    }
+                })
+            })
+            .unwrap();
+        composable.order_id.hash = vanilla.order_id.hash;
+
+        assert!(matches!(
+            pool.add_composable_order(composable),
+            Err(LimitPoolError::HashConflict(OrderHashConflict::Collision { .. }))
+        ));
+    }
+}
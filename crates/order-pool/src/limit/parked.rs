@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
 use alloy::primitives::FixedBytes;
-use angstrom_types::sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData};
+use angstrom_types::{
+    orders::{InvariantViolation, OrderId},
+    primitive::PoolId,
+    sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
+};
 
 pub struct ParkedPool(HashMap<FixedBytes<32>, OrderWithStorageData<GroupedVanillaOrder>>);
 
@@ -18,6 +22,10 @@ impl ParkedPool {
         self.0.get(&order_id).cloned()
     }
 
+    pub fn get_order_id(&self, hash: FixedBytes<32>) -> Option<OrderId> {
+        self.0.get(&hash).map(|order| order.order_id)
+    }
+
     pub fn remove_order(
         &mut self,
         order_id: FixedBytes<32>
@@ -26,6 +34,19 @@ impl ParkedPool {
     }
 
     pub fn new_order(&mut self, order: OrderWithStorageData<GroupedVanillaOrder>) {
-        self.0.insert(order.hash(), order);
+        self.0.insert(order.order_id.hash, order);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OrderWithStorageData<GroupedVanillaOrder>> {
+        self.0.values()
+    }
+
+    /// Checks that every order belongs to `pool_id`.
+    pub fn invariant_violations(&self, pool_id: PoolId) -> Vec<InvariantViolation> {
+        self.0
+            .iter()
+            .filter(|(_, order)| order.pool_id != pool_id)
+            .map(|(hash, _)| InvariantViolation::WrongPool { hash: *hash, stored_in: pool_id })
+            .collect()
     }
 }
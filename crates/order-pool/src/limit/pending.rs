@@ -5,7 +5,9 @@ use std::{
 
 use alloy::primitives::FixedBytes;
 use angstrom_types::{
-    orders::OrderPriorityData, sol_bindings::grouped_orders::OrderWithStorageData
+    orders::{InvariantViolation, OrderId, OrderPriorityData},
+    primitive::PoolId,
+    sol_bindings::grouped_orders::OrderWithStorageData
 };
 
 pub struct PendingPool<Order: Clone> {
@@ -29,6 +31,10 @@ impl<Order: Clone> PendingPool<Order> {
         self.orders.get(&id).cloned()
     }
 
+    pub fn get_order_id(&self, hash: FixedBytes<32>) -> Option<OrderId> {
+        self.orders.get(&hash).map(|order| order.order_id)
+    }
+
     pub fn add_order(&mut self, order: OrderWithStorageData<Order>) {
         if order.is_bid {
             self.bids
@@ -55,4 +61,38 @@ impl<Order: Clone> PendingPool<Order> {
     pub fn get_all_orders(&self) -> Vec<OrderWithStorageData<Order>> {
         self.orders.values().cloned().collect()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OrderWithStorageData<Order>> {
+        self.orders.values()
+    }
+
+    /// Checks that every order belongs to `pool_id` and is indexed by its
+    /// priority on its side of the book.
+    pub fn invariant_violations(&self, pool_id: PoolId) -> Vec<InvariantViolation> {
+        let mut violations = self
+            .orders
+            .iter()
+            .filter_map(|(hash, order)| {
+                if order.pool_id != pool_id {
+                    return Some(InvariantViolation::WrongPool {
+                        hash:      *hash,
+                        stored_in: pool_id
+                    })
+                }
+                let indexed = if order.is_bid {
+                    self.bids.get(&Reverse(order.priority_data))
+                } else {
+                    self.asks.get(&order.priority_data)
+                };
+
+                (indexed != Some(hash)).then_some(InvariantViolation::PriceIndexMismatch(pool_id))
+            })
+            .collect::<Vec<_>>();
+
+        if self.bids.len() + self.asks.len() != self.orders.len() {
+            violations.push(InvariantViolation::PriceIndexMismatch(pool_id));
+        }
+
+        violations
+    }
 }
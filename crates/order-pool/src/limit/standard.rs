@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use alloy::primitives::B256;
 use angstrom_metrics::VanillaLimitOrderPoolMetricsWrapper;
 use angstrom_types::{
-    orders::{OrderId, OrderStatus},
+    orders::{InvariantViolation, OrderId, OrderStatus},
     primitive::{NewInitializedPool, PoolId},
     sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
 };
@@ -115,6 +115,65 @@ impl LimitPool {
             .collect()
     }
 
+    /// Id of the pending or parked order stored under `hash` in any pool.
+    pub fn get_order_id(&self, hash: B256) -> Option<OrderId> {
+        self.pending_orders
+            .values()
+            .find_map(|pool| pool.get_order_id(hash))
+            .or_else(|| {
+                self.parked_orders
+                    .values()
+                    .find_map(|pool| pool.get_order_id(hash))
+            })
+    }
+
+    /// Hashes of all pending and parked orders.
+    pub fn order_hashes(&self) -> impl Iterator<Item = B256> + '_ {
+        self.pending_orders
+            .values()
+            .flat_map(|pool| pool.iter())
+            .chain(self.parked_orders.values().flat_map(|pool| pool.iter()))
+            .map(|order| order.order_id.hash)
+    }
+
+    /// Checks that every order is stored only once, pending orders are
+    /// currently valid and parked ones are not.
+    pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+
+        let mut pending = HashSet::new();
+        for (pool_id, pool) in &self.pending_orders {
+            violations.extend(pool.invariant_violations(*pool_id));
+            for order in pool.iter() {
+                let hash = order.order_id.hash;
+                if !pending.insert(hash) {
+                    violations.push(InvariantViolation::DuplicateOrder(hash));
+                }
+                if !order.is_currently_valid {
+                    violations.push(InvariantViolation::InvalidPending(hash));
+                }
+            }
+        }
+
+        let mut parked = HashSet::new();
+        for (pool_id, pool) in &self.parked_orders {
+            violations.extend(pool.invariant_violations(*pool_id));
+            for order in pool.iter() {
+                let hash = order.order_id.hash;
+                if pending.contains(&hash) {
+                    violations.push(InvariantViolation::ParkedAndPending(hash));
+                } else if !parked.insert(hash) {
+                    violations.push(InvariantViolation::DuplicateOrder(hash));
+                }
+                if order.is_currently_valid {
+                    violations.push(InvariantViolation::ValidParked(hash));
+                }
+            }
+        }
+
+        violations
+    }
+
     pub fn park_order(&mut self, order_id: &OrderId) {
         let Some(mut order) = self.remove_order(order_id.pool_id, order_id.hash) else { return };
         order.is_currently_valid = false;
@@ -134,3 +193,33 @@ impl LimitPool {
         assert!(old_is_none);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    #[test]
+    fn detects_orders_both_parked_and_pending() {
+        let pool_id = PoolId::random();
+        let mut pool = LimitPool::new(&[pool_id]);
+        let order = UserOrderBuilder::new()
+            .partial()
+            .amount(10)
+            .with_storage()
+            .pool_id(pool_id)
+            .build();
+
+        pool.add_order(order.clone()).unwrap();
+        pool.park_order(&order.order_id);
+        assert!(pool.invariant_violations().is_empty());
+
+        // a parked order that was added again without being unparked
+        pool.add_order(order.clone()).unwrap();
+        assert_eq!(
+            pool.invariant_violations(),
+            vec![InvariantViolation::ParkedAndPending(order.order_id.hash)]
+        );
+    }
+}
@@ -1,23 +1,28 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::{Duration, SystemTime, UNIX_EPOCH}
+    time::Duration
 };
 
 use alloy::primitives::{Address, BlockNumber, FixedBytes, B256, U256};
 use angstrom_types::{
-    orders::{OrderId, OrderLocation, OrderOrigin, OrderSet, OrderStatus},
-    primitive::{NewInitializedPool, PeerId, PoolId},
+    orders::{
+        validate_iceberg, CancelAllOrdersRequest, OrderId, OrderLocation, OrderOrigin, OrderSet,
+        OrderStatus, PoolAnalytics, PricePeg
+    },
+    primitive::{NewInitializedPool, PeerId, PoolId, BLOCK_TIME},
     sol_bindings::{
         grouped_orders::{AllOrders, OrderWithStorageData, *},
         rpc_orders::TopOfBlockOrder,
         RawPoolOrder
     }
 };
+use angstrom_utils::clock::Clock;
 use futures_util::{Stream, StreamExt};
-use tokio::sync::oneshot::Sender;
+use tokio::{sync::oneshot::Sender, task::JoinHandle};
 use tracing::{error, trace};
 use validation::order::{
     state::{account::user::UserAddress, pools::AngstromPoolsTracker},
@@ -25,14 +30,13 @@ use validation::order::{
 };
 
 use crate::{
+    config::{FILLED_ORDERS_CAPACITY_DEFAULT, FILLED_ORDERS_TTL_DEFAULT},
+    filled_orders::FilledOrders,
     order_storage::OrderStorage,
     validator::{OrderValidator, OrderValidatorRes},
-    PoolManagerUpdate
+    PoolManagerUpdate, SettledOrders, SettledOrdersWriter
 };
 
-/// This is used to remove validated orders. During validation
-/// the same check wil be ran but with more accuracy
-const ETH_BLOCK_TIME: Duration = Duration::from_secs(12);
 /// mostly arbitrary
 const SEEN_INVALID_ORDERS_CAPACITY: usize = 10000;
 /// represents the maximum number of blocks that we allow for new orders to not
@@ -59,8 +63,27 @@ pub struct OrderIndexer<V: OrderValidatorHandle> {
     order_hash_to_peer_id:  HashMap<B256, Vec<PeerId>>,
     /// Used to avoid unnecessary computation on order spam
     seen_invalid_orders:    HashSet<B256>,
+    /// Orders submitted as private that are being validated
+    private_orders:         HashSet<B256>,
+    /// Pegs of the pegged orders that are being validated
+    pegged_orders:          HashMap<B256, PricePeg>,
+    /// Hidden tranches of iceberg orders that are being validated
+    hidden_tranches:        HashSet<B256>,
+    /// The visible tranche of every iceberg order mapped to its hidden
+    /// tranches, in the order they're revealed in
+    icebergs:               HashMap<B256, VecDeque<B256>>,
     /// Used to protect against late order propagation
     cancelled_orders:       HashMap<B256, CancelOrderRequest>,
+    /// Filled order hashes, so that replays of them aren't validated again
+    recently_filled:        FilledOrders,
+    /// where the filled and cancelled orders are kept across restarts
+    settled_orders:         Option<SettledOrdersWriter>,
+    /// Cancel all requests that have been applied, mapped to their expiry.
+    /// Used to ignore replays of the same request
+    cancel_all_requests:    HashMap<B256, u64>,
+    /// unix time every order was first received at, cancel all requests only
+    /// cancel the orders received by the time they were issued
+    received_at:            HashMap<B256, u64>,
     /// Order Validator
     validator:              OrderValidator<V>,
     /// a mapping of tokens to pool_id
@@ -68,7 +91,9 @@ pub struct OrderIndexer<V: OrderValidatorHandle> {
     /// List of subscribers for order validation result
     order_validation_subs:  HashMap<B256, Vec<Sender<OrderValidationResults>>>,
     /// List of subscribers for order state change notifications
-    orders_subscriber_tx:   tokio::sync::broadcast::Sender<PoolManagerUpdate>
+    orders_subscriber_tx:   tokio::sync::broadcast::Sender<PoolManagerUpdate>,
+    /// time deadlines and expiries are checked against
+    clock:                  Clock
 }
 
 impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
@@ -86,14 +111,93 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
             order_hash_to_order_id: HashMap::new(),
             order_hash_to_peer_id: HashMap::new(),
             seen_invalid_orders: HashSet::with_capacity(SEEN_INVALID_ORDERS_CAPACITY),
+            private_orders: HashSet::new(),
+            pegged_orders: HashMap::new(),
+            hidden_tranches: HashSet::new(),
+            icebergs: HashMap::new(),
             pool_id_map: angstrom_pools,
             cancelled_orders: HashMap::new(),
+            recently_filled: FilledOrders::new(
+                FILLED_ORDERS_TTL_DEFAULT,
+                FILLED_ORDERS_CAPACITY_DEFAULT
+            ),
+            settled_orders: None,
+            cancel_all_requests: HashMap::new(),
+            received_at: HashMap::new(),
             order_validation_subs: HashMap::new(),
             validator: OrderValidator::new(validator),
-            orders_subscriber_tx
+            orders_subscriber_tx,
+            clock: Clock::system()
         }
     }
 
+    /// Checks deadlines and expiries against the given clock instead of the
+    /// system one, set it before loading the settled orders.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Remembers at most `capacity` filled orders, the ones closest to expiry
+    /// are forgotten first once there are more. Set it before loading the
+    /// settled orders.
+    pub fn with_filled_orders_capacity(mut self, capacity: usize) -> Self {
+        self.recently_filled = FilledOrders::new(self.recently_filled.ttl(), capacity);
+        self
+    }
+
+    /// Remembers filled orders for the given time and keeps them, with the
+    /// cancelled orders, in the file across restarts. The orders settled
+    /// before the last shutdown are loaded right away.
+    pub fn with_settled_orders(
+        mut self,
+        path: Option<PathBuf>,
+        filled_orders_ttl: Duration
+    ) -> Self {
+        self.recently_filled =
+            FilledOrders::new(filled_orders_ttl, self.recently_filled.capacity());
+        if let Some(path) = path.as_ref() {
+            match SettledOrders::load(path, self.unix_now()) {
+                Ok(settled) => {
+                    tracing::info!(
+                        filled = settled.filled.len(),
+                        cancelled = settled.cancelled.len(),
+                        "loaded settled orders"
+                    );
+                    let evicted = self.recently_filled.extend(settled.filled);
+                    self.record_filled_orders(0, evicted);
+                    self.cancelled_orders
+                        .extend(settled.cancelled.into_iter().map(
+                            |(hash, (from, valid_until))| {
+                                (hash, CancelOrderRequest { from, valid_until })
+                            }
+                        ));
+                }
+                Err(error) => tracing::warn!(?path, %error, "failed to load settled orders")
+            }
+        }
+        self.settled_orders = path.map(SettledOrdersWriter::new);
+
+        self
+    }
+
+    fn persist_settled_orders(&self) -> Option<JoinHandle<()>> {
+        let writer = self.settled_orders.as_ref()?;
+        let settled = SettledOrders {
+            filled:    self.recently_filled.expiries().clone(),
+            cancelled: self
+                .cancelled_orders
+                .iter()
+                .map(|(hash, request)| (*hash, (request.from, request.valid_until)))
+                .collect()
+        };
+
+        Some(writer.store(settled))
+    }
+
+    /// The pending orders of the address, except for its private ones and the
+    /// hidden tranches of its icebergs. The address isn't authenticated,
+    /// anyone can ask for them.
     pub fn pending_orders_for_address(
         &self,
         address: Address
@@ -101,26 +205,10 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         let mut orders = Vec::new();
         if let Some(order_ids) = self.address_to_orders.get(&address) {
             for order_id in order_ids {
-                let order = match order_id.location {
-                    angstrom_types::orders::OrderLocation::Limit => self
-                        .order_storage
-                        .limit_orders
-                        .lock()
-                        .expect("lock poisoned")
-                        .get_order(order_id)
-                        .and_then(|order| order.try_map_inner(|inner| Ok(inner.into())).ok()),
-                    angstrom_types::orders::OrderLocation::Searcher => self
-                        .order_storage
-                        .searcher_orders
-                        .lock()
-                        .expect("lock poisoned")
-                        .get_order(order_id.pool_id, order_id.hash)
-                        .and_then(|order| {
-                            order.try_map_inner(|inner| Ok(AllOrders::TOB(inner))).ok()
-                        })
-                };
-
-                if let Some(order) = order {
+                if let Some(order) = self
+                    .order_by_id(order_id)
+                    .filter(|order| !order.is_private && !order.is_hidden)
+                {
                     orders.push(order);
                 }
             }
@@ -128,6 +216,42 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         orders
     }
 
+    /// The order to hand out to a peer that asked for it. Private and pegged
+    /// orders and hidden tranches are never handed out.
+    pub fn order_by_hash(&self, order_hash: B256) -> Option<AllOrders> {
+        let order_id = self.order_hash_to_order_id.get(&order_hash)?;
+        self.order_by_id(order_id)
+            .filter(|order| !order.is_leader_only() && !order.is_hidden)
+            .map(|order| order.order)
+    }
+
+    fn order_by_id(&self, order_id: &OrderId) -> Option<OrderWithStorageData<AllOrders>> {
+        match order_id.location {
+            angstrom_types::orders::OrderLocation::Limit => self
+                .order_storage
+                .limit_orders
+                .lock()
+                .expect("lock poisoned")
+                .get_order(order_id)
+                .and_then(|order| order.try_map_inner(|inner| Ok(inner.into())).ok()),
+            angstrom_types::orders::OrderLocation::Searcher => self
+                .order_storage
+                .searcher_orders
+                .lock()
+                .expect("lock poisoned")
+                .get_order(order_id.pool_id, order_id.hash)
+                .and_then(|order| order.try_map_inner(|inner| Ok(AllOrders::TOB(inner))).ok())
+        }
+    }
+
+    /// Whether the order is in the pool, being validated or was settled,
+    /// cancelled or found invalid recently.
+    pub fn knows_order(&self, order_hash: &B256) -> bool {
+        self.is_duplicate(order_hash)
+            || self.is_cancelled(order_hash)
+            || self.order_hash_to_peer_id.contains_key(order_hash)
+    }
+
     pub fn orders_by_pool(
         &self,
         pool_id: FixedBytes<32>,
@@ -149,6 +273,11 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         }
     }
 
+    pub fn pool_analytics(&self, pool_id: PoolId, price_buckets: usize) -> PoolAnalytics {
+        self.order_storage
+            .pool_analytics(pool_id, self.block_number, price_buckets)
+    }
+
     pub fn order_status(&self, order_hash: B256) -> Option<OrderStatus> {
         self.order_storage.fetch_status_of_order(order_hash)
     }
@@ -170,7 +299,9 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
     }
 
     fn is_duplicate(&self, order_hash: &B256) -> bool {
-        if self.order_hash_to_order_id.contains_key(order_hash) || self.is_seen_invalid(order_hash)
+        if self.order_hash_to_order_id.contains_key(order_hash)
+            || self.is_seen_invalid(order_hash)
+            || self.recently_filled.contains(order_hash)
         {
             trace!(?order_hash, "got duplicate order");
             return true
@@ -188,10 +319,73 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         self.new_order(None, origin, order, Some(validation_tx))
     }
 
+    /// Submits an order that is priced off the AMM mid by `peg`, the peg is
+    /// checked against the order before it's validated. Like private orders,
+    /// pegged orders aren't gossiped, peers would only learn of the signed
+    /// price. They are included in the rounds we lead instead.
+    pub fn new_pegged_rpc_order(
+        &mut self,
+        order: AllOrders,
+        peg: PricePeg,
+        validation_tx: tokio::sync::oneshot::Sender<OrderValidationResults>
+    ) {
+        let hash = order.order_hash();
+        if let Err(error) = peg.validate(&order) {
+            trace!(order_hash = %hash, %error, "rejecting pegged order");
+            let _ = validation_tx.send(OrderValidationResults::Invalid(hash));
+            return
+        }
+        self.pegged_orders.insert(hash, peg);
+        self.new_order(None, OrderOrigin::External, order, Some(validation_tx))
+    }
+
+    /// Submits the tranches of an iceberg order, each with its own
+    /// subscriber. Only the first tranche is gossiped and shown in the depth
+    /// of the pool, the others are kept hidden like private orders and
+    /// revealed one at a time as the visible one is filled.
+    pub fn new_iceberg_rpc_order(
+        &mut self,
+        tranches: Vec<AllOrders>,
+        validation_txs: Vec<tokio::sync::oneshot::Sender<OrderValidationResults>>
+    ) {
+        if let Err(error) = validate_iceberg(&tranches) {
+            trace!(%error, "rejecting iceberg order");
+            tranches
+                .iter()
+                .zip(validation_txs)
+                .for_each(|(tranche, tx)| {
+                    let _ = tx.send(OrderValidationResults::Invalid(tranche.order_hash()));
+                });
+            return
+        }
+
+        let hashes = tranches
+            .iter()
+            .map(|tranche| tranche.order_hash())
+            .collect::<VecDeque<_>>();
+        self.hidden_tranches.extend(hashes.iter().skip(1));
+        self.icebergs
+            .insert(hashes[0], hashes.into_iter().skip(1).collect());
+
+        tranches
+            .into_iter()
+            .zip(validation_txs)
+            .for_each(|(tranche, tx)| {
+                self.new_order(None, OrderOrigin::External, tranche, Some(tx))
+            });
+    }
+
     pub fn new_network_order(&mut self, peer_id: PeerId, origin: OrderOrigin, order: AllOrders) {
         self.new_order(Some(peer_id), origin, order, None)
     }
 
+    /// Submits an order of a late pre-proposal. It's validated like any other
+    /// network order, but isn't held against the node that proposed it, the
+    /// node never sent it to us.
+    pub fn new_carried_over_order(&mut self, order: AllOrders) {
+        self.new_order(None, OrderOrigin::External, order, None)
+    }
+
     pub fn cancel_order(&mut self, request: &angstrom_types::orders::CancelOrderRequest) -> bool {
         // ensure validity
         if !request.is_valid() {
@@ -206,11 +400,7 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         // nothing more needs to be done, since new_order() will return early
         if self.is_missing(&request.order_id) {
             // optimistically assuming that orders won't take longer than a day to propagate
-            let deadline = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                + MAX_NEW_ORDER_DELAY_PROPAGATION * ETH_BLOCK_TIME.as_secs();
+            let deadline = self.unix_now() + MAX_NEW_ORDER_DELAY_PROPAGATION * BLOCK_TIME.as_secs();
             self.insert_cancel_request_with_deadline(
                 request.user_address,
                 &request.order_id,
@@ -228,6 +418,11 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
                 &request.order_id,
                 order.deadline()
             );
+            self.cancel_hidden_tranches(
+                request.user_address,
+                &order.order_hash(),
+                order.deadline()
+            );
 
             self.notify_order_subscribers(PoolManagerUpdate::CancelledOrder {
                 order_hash: order.order_hash(),
@@ -240,6 +435,131 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         false
     }
 
+    /// Removes all resting orders of the user across every pool that were
+    /// received by the time the request was issued. Returns the hashes of the
+    /// cancelled orders, or [`None`] if the request is invalid.
+    pub fn cancel_all_orders(&mut self, request: &CancelAllOrdersRequest) -> Option<Vec<B256>> {
+        if !request.is_valid() {
+            return None
+        }
+
+        // a replayed request must not cancel orders placed after it was applied
+        if self
+            .cancel_all_requests
+            .insert(request.request_hash(), request.valid_until)
+            .is_some()
+        {
+            return Some(vec![])
+        }
+
+        let user = request.user_address;
+        let mut cancelled = Vec::new();
+        let mut remaining = Vec::new();
+        for id in self.address_to_orders.remove(&user).unwrap_or_default() {
+            // placed after the request was issued
+            if self
+                .received_at
+                .get(&id.hash)
+                .is_some_and(|received_at| !request.cancels_received_at(*received_at))
+            {
+                remaining.push(id);
+                continue
+            }
+            // orders that are pending finalization can't be cancelled
+            let Some(order) = self.order_storage.cancel_order(&id) else {
+                remaining.push(id);
+                continue
+            };
+            let order_hash = order.order_hash();
+            self.order_hash_to_order_id.remove(&order_hash);
+            self.order_hash_to_peer_id.remove(&order_hash);
+            self.insert_cancel_request_with_deadline(user, &order_hash, order.deadline());
+
+            self.notify_order_subscribers(PoolManagerUpdate::CancelledOrder {
+                order_hash,
+                user,
+                pool_id: order.pool_id
+            });
+            cancelled.push(order_hash);
+            cancelled.extend(self.cancel_hidden_tranches(user, &order_hash, order.deadline()));
+        }
+
+        if !remaining.is_empty() {
+            self.address_to_orders.insert(user, remaining);
+        }
+        tracing::debug!(?user, cancelled = cancelled.len(), "cancelled all orders of user");
+
+        Some(cancelled)
+    }
+
+    /// Cancels the hidden tranches of the iceberg whose visible tranche was
+    /// cancelled, so none of them is revealed later on. Tranches that are
+    /// still being validated are dropped once they are. Returns the hashes of
+    /// the tranches that were resting.
+    fn cancel_hidden_tranches(
+        &mut self,
+        user: Address,
+        visible: &B256,
+        deadline: Option<U256>
+    ) -> Vec<B256> {
+        let Some(tranches) = self.icebergs.remove(visible) else { return vec![] };
+
+        let mut cancelled = Vec::new();
+        for hash in tranches {
+            self.insert_cancel_request_with_deadline(user, &hash, deadline);
+            let Some(id) = self.order_hash_to_order_id.remove(&hash) else { continue };
+            self.order_hash_to_peer_id.remove(&hash);
+            if let Some(orders) = self.address_to_orders.get_mut(&id.address) {
+                orders.retain(|order| order.hash != hash);
+            }
+            let Some(order) = self.order_storage.cancel_order(&id) else { continue };
+
+            self.notify_order_subscribers(PoolManagerUpdate::CancelledOrder {
+                order_hash: hash,
+                user,
+                pool_id: order.pool_id
+            });
+            cancelled.push(hash);
+        }
+        trace!(order_hash = %visible, tranches = cancelled.len(), "cancelled iceberg tranches");
+
+        cancelled
+    }
+
+    /// Drops every resting order, for when the chain moved onto a new fork id
+    /// and the orders might not be valid on it anymore. They aren't marked as
+    /// invalid, users and peers can submit them again to have them validated
+    /// under the new rules. Returns the hashes of the dropped orders.
+    pub fn invalidate_all_orders(&mut self) -> Vec<B256> {
+        let mut dropped = Vec::new();
+        for (user, ids) in std::mem::take(&mut self.address_to_orders) {
+            let mut remaining = Vec::new();
+            for id in ids {
+                // orders that are pending finalization are already on chain
+                let Some(order) = self.order_storage.cancel_order(&id) else {
+                    remaining.push(id);
+                    continue
+                };
+                let order_hash = order.order_hash();
+                self.order_hash_to_order_id.remove(&order_hash);
+                self.order_hash_to_peer_id.remove(&order_hash);
+
+                self.notify_order_subscribers(PoolManagerUpdate::CancelledOrder {
+                    order_hash,
+                    user,
+                    pool_id: order.pool_id
+                });
+                dropped.push(order_hash);
+            }
+
+            if !remaining.is_empty() {
+                self.address_to_orders.insert(user, remaining);
+            }
+        }
+
+        dropped
+    }
+
     fn insert_cancel_request_with_deadline(
         &mut self,
         from: Address,
@@ -250,10 +570,7 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
             || {
                 // if no deadline is provided the cancellation request is valid until block
                 // transition
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
+                self.unix_now()
             },
             |deadline| {
                 let bytes: [u8; U256::BYTES] = deadline.to_le_bytes();
@@ -273,6 +590,7 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         validation_res_sub: Option<Sender<OrderValidationResults>>
     ) {
         let hash = order.order_hash();
+        let _span = tracing::info_span!("new_order", order_hash = %hash, ?peer_id).entered();
         if let Some(validation_tx) = validation_res_sub {
             self.order_validation_subs
                 .entry(hash)
@@ -311,15 +629,24 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
                 .or_default()
                 .push(peer);
         }
+        if origin == OrderOrigin::Private {
+            self.private_orders.insert(hash);
+        }
+        let now = self.unix_now();
+        self.received_at.entry(hash).or_insert(now);
 
         self.validator.validate_order(origin, order);
     }
 
+    fn unix_now(&self) -> u64 {
+        self.clock.unix_now().as_secs()
+    }
+
     /// used to remove orders that expire before the next ethereum block
     fn remove_expired_orders(&mut self, block_number: BlockNumber) -> Vec<B256> {
         self.block_number = block_number;
-        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        let expiry_deadline = U256::from((time + ETH_BLOCK_TIME).as_secs()); // grab all expired hashes
+        let time = self.clock.unix_now();
+        let expiry_deadline = U256::from((time + BLOCK_TIME).as_secs()); // grab all expired hashes
         let hashes = self
             .order_hash_to_order_id
             .iter()
@@ -332,8 +659,7 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
             .map(|(k, _)| *k)
             .collect::<Vec<_>>();
 
-        // TODO: notify rpc of dead orders
-        let _expired_orders = hashes
+        let expired_orders = hashes
             .iter()
             // remove hash from id
             .map(|hash| self.order_hash_to_order_id.remove(hash).unwrap())
@@ -349,26 +675,45 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
                 OrderLocation::Limit => self.order_storage.remove_limit_order(&id)
             })
             .collect::<Vec<_>>();
+        expired_orders.into_iter().for_each(|order| {
+            self.notify_order_subscribers(PoolManagerUpdate::ExpiredOrder(order))
+        });
 
         hashes
     }
 
     fn eoa_state_change(&mut self, eoas: &[Address]) {
-        eoas.iter()
+        let orders = eoas
+            .iter()
             .filter_map(|eoa| self.address_to_orders.remove(eoa))
-            .for_each(|order_ids| {
-                order_ids.into_iter().for_each(|id| {
-                    let Some(order) = (match id.location {
-                        OrderLocation::Limit => self.order_storage.remove_limit_order(&id),
-                        OrderLocation::Searcher => self.order_storage.remove_searcher_order(&id)
-                    }) else {
-                        return
-                    };
-
-                    self.validator
-                        .validate_order(OrderOrigin::Local, order.order);
-                })
-            });
+            .flatten()
+            .filter_map(|id| match id.location {
+                OrderLocation::Limit => self.order_storage.remove_limit_order(&id),
+                OrderLocation::Searcher => self.order_storage.remove_searcher_order(&id)
+            })
+            .collect::<Vec<_>>();
+
+        orders
+            .into_iter()
+            .for_each(|order| self.revalidate_order(order));
+    }
+
+    /// Validates an order of the pool again, it keeps being private, pegged or
+    /// hidden.
+    fn revalidate_order(&mut self, order: OrderWithStorageData<AllOrders>) {
+        let hash = order.order_hash();
+        if order.is_private {
+            self.private_orders.insert(hash);
+        }
+        if order.is_hidden {
+            self.hidden_tranches.insert(hash);
+        }
+        if let Some(peg) = order.peg {
+            self.pegged_orders.insert(hash, peg);
+        }
+
+        self.validator
+            .validate_order(OrderOrigin::Local, order.order);
     }
 
     pub fn finalized_block(&mut self, block_number: BlockNumber) {
@@ -376,22 +721,62 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
     }
 
     pub fn reorg(&mut self, orders: Vec<B256>) {
+        orders.iter().for_each(|hash| {
+            self.recently_filled.remove(hash);
+        });
         self.order_storage
             .reorg(orders)
             .into_iter()
             .for_each(|order| {
                 self.notify_order_subscribers(PoolManagerUpdate::UnfilledOrders(order.clone()));
-                self.validator
-                    .validate_order(OrderOrigin::Local, order.order)
+                self.revalidate_order(order)
             });
     }
 
+    /// Reveals the next hidden tranche of every iceberg order whose visible
+    /// tranche was filled. The tranche is validated again as a regular order,
+    /// which gossips it.
+    fn replenish_icebergs(&mut self, filled: &[B256]) {
+        let revealed = filled
+            .iter()
+            .filter_map(|hash| self.icebergs.remove(hash))
+            .filter_map(|tranches| self.reveal_next_tranche(tranches))
+            .collect::<Vec<_>>();
+        self.icebergs.extend(revealed);
+    }
+
+    fn reveal_next_tranche(
+        &mut self,
+        mut tranches: VecDeque<B256>
+    ) -> Option<(B256, VecDeque<B256>)> {
+        // tranches that were filled, cancelled or expired are skipped
+        while let Some(hash) = tranches.pop_front() {
+            let Some(id) = self.order_hash_to_order_id.remove(&hash) else { continue };
+            if let Some(orders) = self.address_to_orders.get_mut(&id.address) {
+                orders.retain(|order| order.hash != hash);
+            }
+            let Some(order) = self.order_storage.remove_limit_order(&id) else { continue };
+
+            trace!(order_hash = %hash, "revealing iceberg tranche");
+            self.validator
+                .validate_order(OrderOrigin::Local, order.order);
+            return Some((hash, tranches))
+        }
+
+        None
+    }
+
     /// Removes all filled orders from the pools and moves to regular pool
     fn filled_orders(&mut self, block_number: BlockNumber, orders: &[B256]) {
         if orders.is_empty() {
             return
         }
 
+        let evicted = self
+            .recently_filled
+            .insert(orders.iter().copied(), self.unix_now());
+        self.record_filled_orders(0, evicted);
+
         let filled_orders = orders
             .iter()
             .filter_map(|hash| self.order_hash_to_order_id.remove(hash))
@@ -411,6 +796,28 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
             .add_filled_orders(block_number, filled_orders);
     }
 
+    /// Forgets the filled orders whose ttl passed. Ran with every new block and
+    /// periodically in between, so that the cache doesn't grow while blocks
+    /// aren't processed.
+    pub fn collect_filled_orders(&mut self) {
+        let expired = self.recently_filled.remove_expired(self.unix_now());
+        if expired != 0 {
+            trace!(expired, remembered = self.recently_filled.len(), "forgot filled orders");
+        }
+        self.record_filled_orders(expired, 0);
+    }
+
+    fn record_filled_orders(&self, expired: usize, evicted: usize) {
+        if evicted != 0 {
+            tracing::warn!(evicted, "filled order cache is full, forgetting the oldest fills");
+        }
+        self.order_storage.metrics.record_filled_order_hashes(
+            self.recently_filled.len(),
+            expired,
+            evicted
+        );
+    }
+
     /// Given the nonce ordering rule. Sometimes new transactions can park old
     /// transactions.
     fn park_transactions(&mut self, txes: &[B256]) {
@@ -426,11 +833,30 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         res: OrderValidationResults
     ) -> eyre::Result<PoolInnerEvent> {
         match res {
-            OrderValidationResults::Valid(valid) => {
+            OrderValidationResults::Valid(mut valid) => {
                 let hash = valid.order_hash();
+                valid.is_hidden = self.hidden_tranches.remove(&hash);
+                valid.is_private = self.private_orders.remove(&hash);
+                valid.peg = self.pegged_orders.remove(&hash);
+
+                // the iceberg was cancelled while its tranche was validated
+                if valid.is_hidden && self.is_cancelled(&hash) {
+                    trace!(order_hash = %hash, "dropping tranche of cancelled iceberg");
+                    self.notify_validation_subscribers(
+                        &hash,
+                        OrderValidationResults::Invalid(hash)
+                    );
+                    return Ok(PoolInnerEvent::None)
+                }
 
                 // what about the deadline?
                 if valid.valid_block != self.block_number {
+                    trace!(
+                        order_hash = %hash,
+                        valid_block = valid.valid_block,
+                        block_number = self.block_number,
+                        "validated order against stale block"
+                    );
                     self.notify_validation_subscribers(
                         &hash,
                         OrderValidationResults::Invalid(hash)
@@ -441,6 +867,20 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
                     return Ok(PoolInnerEvent::BadOrderMessages(peers))
                 }
 
+                // inserted first, an order whose hash is already taken must not
+                // replace the tracking of the order that holds it
+                let evicted = match self.insert_order(valid.clone()) {
+                    Ok(evicted) => evicted,
+                    Err(e) => {
+                        error!(order_hash = %hash, %e, "failed to insert validated order");
+                        self.notify_validation_subscribers(
+                            &hash,
+                            OrderValidationResults::Invalid(hash)
+                        );
+                        return Err(e)
+                    }
+                };
+
                 self.notify_order_subscribers(PoolManagerUpdate::NewOrder(valid.clone()));
                 self.notify_validation_subscribers(
                     &hash,
@@ -449,12 +889,21 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
 
                 let to_propagate = valid.order.clone();
                 self.update_order_tracking(&hash, valid.from(), valid.order_id);
+                self.untrack_evicted_orders(evicted);
                 self.park_transactions(&valid.invalidates);
-                self.insert_order(valid)?;
 
+                // private and pegged orders and hidden tranches stay with us
+                if valid.is_leader_only() || valid.is_hidden {
+                    return Ok(PoolInnerEvent::None)
+                }
                 Ok(PoolInnerEvent::Propagation(to_propagate))
             }
             OrderValidationResults::Invalid(bad_hash) => {
+                trace!(order_hash = %bad_hash, "order failed validation");
+                self.private_orders.remove(&bad_hash);
+                self.pegged_orders.remove(&bad_hash);
+                self.hidden_tranches.remove(&bad_hash);
+                self.icebergs.remove(&bad_hash);
                 self.notify_validation_subscribers(
                     &bad_hash,
                     OrderValidationResults::Invalid(bad_hash)
@@ -478,13 +927,18 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         if let Some(subscribers) = self.order_validation_subs.remove(hash) {
             for subscriber in subscribers {
                 if let Err(e) = subscriber.send(result.clone()) {
-                    error!("Failed to send order validation result to subscriber: {:?}", e);
+                    error!(
+                        order_hash = %hash,
+                        ?e,
+                        "failed to send order validation result to subscriber"
+                    );
                 }
             }
         }
     }
 
-    fn insert_order(&mut self, res: OrderWithStorageData<AllOrders>) -> eyre::Result<()> {
+    /// Inserts the order into storage, returns the orders evicted for it.
+    fn insert_order(&mut self, res: OrderWithStorageData<AllOrders>) -> eyre::Result<Vec<OrderId>> {
         match res.order_id.location {
             angstrom_types::orders::OrderLocation::Searcher => self
                 .order_storage
@@ -516,6 +970,27 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         }
     }
 
+    /// Orders evicted from a full pool are gone like cancelled ones, except
+    /// that their owner can submit them again.
+    fn untrack_evicted_orders(&mut self, evicted: Vec<OrderId>) {
+        for id in evicted {
+            self.order_hash_to_order_id.remove(&id.hash);
+            self.order_hash_to_peer_id.remove(&id.hash);
+            if let Some(orders) = self.address_to_orders.get_mut(&id.address) {
+                orders.retain(|order| order.hash != id.hash);
+                if orders.is_empty() {
+                    self.address_to_orders.remove(&id.address);
+                }
+            }
+
+            self.notify_order_subscribers(PoolManagerUpdate::CancelledOrder {
+                order_hash: id.hash,
+                user:       id.address,
+                pool_id:    id.pool_id
+            });
+        }
+    }
+
     fn update_order_tracking(&mut self, hash: &B256, user: UserAddress, id: OrderId) {
         self.order_hash_to_peer_id.remove(hash);
         self.order_hash_to_order_id.insert(*hash, id);
@@ -553,15 +1028,27 @@ impl<V: OrderValidatorHandle<Order = AllOrders>> OrderIndexer<V> {
         self.eoa_state_change(&address_changes);
         // deal with filled orders
         self.filled_orders(block_number, &completed_orders);
+        self.replenish_icebergs(&completed_orders);
         // add expired orders to completed
-        completed_orders.extend(self.remove_expired_orders(block_number));
+        let expired = self.remove_expired_orders(block_number);
+        expired.iter().for_each(|hash| {
+            self.icebergs.remove(hash);
+        });
+        completed_orders.extend(expired);
 
-        let time_now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let time_now = self.unix_now();
         self.cancelled_orders
             .retain(|_, request| request.valid_until >= time_now);
+        self.cancel_all_requests
+            .retain(|_, valid_until| *valid_until >= time_now);
+        // orders still being validated are kept for a block
+        let order_hash_to_order_id = &self.order_hash_to_order_id;
+        self.received_at.retain(|hash, received_at| {
+            order_hash_to_order_id.contains_key(hash)
+                || *received_at + BLOCK_TIME.as_secs() >= time_now
+        });
+        self.collect_filled_orders();
+        self.persist_settled_orders();
 
         self.validator.notify_validation_on_changes(
             block_number,
@@ -584,7 +1071,10 @@ where
             match next {
                 OrderValidatorRes::EnsureClearForTransition { block, orders, addresses } => {
                     tracing::info!(
-                        "ensure clear for transition. pruning all old + invalid txes from the pool"
+                        block_number = block,
+                        completed_orders = orders.len(),
+                        changed_addresses = addresses.len(),
+                        "pruning completed and invalid orders from the pool"
                     );
                     self.finish_new_block_processing(block, orders, addresses);
                 }
@@ -629,17 +1119,20 @@ pub enum PoolError {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH}
+    };
 
-    use alloy::{primitives::U256, signers::SignerSync, sol_types::SolValue};
+    use alloy::{primitives::U256, signers::SignerSync};
     use angstrom_types::{
+        consensus::PreProposal,
         contract_bindings::angstrom::Angstrom::PoolKey,
         contract_payloads::angstrom::AngstromPoolConfigStore,
         orders::OrderId,
         primitive::AngstromSigner,
         sol_bindings::{grouped_orders::GroupedVanillaOrder, RespendAvoidanceMethod}
     };
-    use revm::primitives::keccak256;
     use testing_tools::{
         mocks::validator::MockValidator, type_generator::orders::UserOrderBuilder
     };
@@ -711,6 +1204,62 @@ mod tests {
         }
     }
 
+    /// The order as the validator hands it back, valid at block 1.
+    fn validated(
+        order: &AllOrders,
+        from: Address,
+        pool_id: PoolId,
+        nonce: u64
+    ) -> OrderWithStorageData<AllOrders> {
+        OrderWithStorageData {
+            order: order.clone(),
+            order_id: OrderId {
+                address: from,
+                reuse_avoidance: RespendAvoidanceMethod::Nonce(nonce),
+                hash: order.order_hash(),
+                pool_id,
+                location: OrderLocation::Limit,
+                deadline: order.deadline(),
+                flash_block: None
+            },
+            valid_block: 1,
+            pool_id,
+            is_bid: true,
+            is_currently_valid: true,
+            is_valid: true,
+            priority_data: Default::default(),
+            invalidates: vec![],
+            tob_reward: U256::ZERO,
+            bond_tier: 0,
+            is_private: false,
+            is_hidden: false,
+            peg: None
+        }
+    }
+
+    /// The tranches of an iceberg order of the signer, one per nonce.
+    fn iceberg_tranches(
+        signer: &AngstromSigner,
+        pool_key: &PoolKey,
+        deadline: U256
+    ) -> Vec<AllOrders> {
+        (1..=3)
+            .map(|nonce| {
+                let order = UserOrderBuilder::new()
+                    .standing()
+                    .asset_in(pool_key.currency0)
+                    .asset_out(pool_key.currency1)
+                    .amount(300)
+                    .deadline(deadline)
+                    .nonce(nonce)
+                    .signing_key(Some(signer.clone()))
+                    .build();
+                let GroupedVanillaOrder::Standing(order) = order else { unreachable!() };
+                AllOrders::Standing(order)
+            })
+            .collect()
+    }
+
     #[tokio::test]
     async fn test_expired_orders_handling() {
         let mut indexer = setup_test_indexer();
@@ -771,7 +1320,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -842,7 +1395,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -856,6 +1413,246 @@ mod tests {
         assert!(!indexer.order_hash_to_order_id.contains_key(&order_hash));
     }
 
+    #[tokio::test]
+    async fn private_orders_are_not_propagated() {
+        let mut indexer = setup_test_indexer();
+        let from = Address::random();
+        let pool_key = PoolKey {
+            currency0: Address::random(),
+            currency1: Address::random(),
+            ..Default::default()
+        };
+        let pool_id = PoolId::from(pool_key.clone());
+        indexer.new_pool(NewInitializedPool {
+            currency_out: pool_key.currency0,
+            currency_in:  pool_key.currency1,
+            id:           pool_id
+        });
+
+        let order = create_test_order(from, pool_key, None, None);
+        let order_hash = order.order_hash();
+        let (tx, _) = tokio::sync::oneshot::channel();
+        indexer.new_rpc_order(OrderOrigin::Private, order.clone(), tx);
+
+        let event = indexer
+            .handle_validated_order(OrderValidationResults::Valid(validated(
+                &order, from, pool_id, 1
+            )))
+            .unwrap();
+
+        assert!(matches!(event, PoolInnerEvent::None));
+        assert!(indexer.private_orders.is_empty());
+        let stored = indexer.get_all_orders().limit;
+        assert!(stored.iter().all(|order| order.is_private) && !stored.is_empty());
+        // peers can't pull it either, nor is it listed over the rpc
+        assert_eq!(indexer.order_by_hash(order_hash), None);
+        assert!(indexer
+            .orders_by_pool(pool_id, OrderLocation::Limit)
+            .is_empty());
+        assert!(indexer.pending_orders_for_address(from).is_empty());
+    }
+
+    #[tokio::test]
+    async fn pegged_orders_keep_their_peg() {
+        let mut indexer = setup_test_indexer();
+        let signer = AngstromSigner::random();
+        let from = signer.address();
+        let pool_key = PoolKey {
+            currency0: Address::random(),
+            currency1: Address::random(),
+            ..Default::default()
+        };
+        let pool_id = PoolId::from(pool_key.clone());
+        indexer.new_pool(NewInitializedPool {
+            currency_out: pool_key.currency0,
+            currency_in:  pool_key.currency1,
+            id:           pool_id
+        });
+
+        let deadline = U256::from(indexer.unix_now() + 1000);
+        let validity =
+            OrderValidity { valid_until: Some(deadline), flash_block: None, is_standing: true };
+        let order = create_test_order(from, pool_key, Some(validity), Some(signer.clone()));
+        let order_hash = order.order_hash();
+        let peg_hash = PricePeg::signing_payload(order_hash, 10);
+        let peg = PricePeg {
+            order_hash,
+            offset_bps: 10,
+            signature: signer.sign_hash_sync(&peg_hash).unwrap()
+        };
+
+        // a peg that the signer of the order didn't sign is turned away
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let forged = PricePeg { offset_bps: -10, ..peg.clone() };
+        indexer.new_pegged_rpc_order(order.clone(), forged, tx);
+        assert!(matches!(rx.await, Ok(OrderValidationResults::Invalid(_))));
+        assert!(indexer.pegged_orders.is_empty());
+
+        let (tx, _) = tokio::sync::oneshot::channel();
+        indexer.new_pegged_rpc_order(order.clone(), peg.clone(), tx);
+        let event = indexer
+            .handle_validated_order(OrderValidationResults::Valid(validated(
+                &order, from, pool_id, 1
+            )))
+            .unwrap();
+
+        assert!(matches!(event, PoolInnerEvent::None));
+        assert!(indexer.pegged_orders.is_empty());
+        let stored = indexer.get_all_orders().limit;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].peg, Some(peg));
+        assert_eq!(indexer.order_by_hash(order_hash), None);
+    }
+
+    #[tokio::test]
+    async fn iceberg_orders_reveal_one_tranche_at_a_time() {
+        let mut indexer = setup_test_indexer();
+        let signer = AngstromSigner::random();
+        let from = signer.address();
+        let pool_key = PoolKey {
+            currency0: Address::random(),
+            currency1: Address::random(),
+            ..Default::default()
+        };
+        let pool_id = PoolId::from(pool_key.clone());
+        indexer.new_pool(NewInitializedPool {
+            currency_out: pool_key.currency0,
+            currency_in:  pool_key.currency1,
+            id:           pool_id
+        });
+
+        let deadline = U256::from(indexer.unix_now() + 1000);
+        let tranches = iceberg_tranches(&signer, &pool_key, deadline);
+        let hashes = tranches
+            .iter()
+            .map(|tranche| tranche.order_hash())
+            .collect::<Vec<_>>();
+        let valid = |order: &AllOrders, nonce: u64| {
+            OrderValidationResults::Valid(validated(order, from, pool_id, nonce))
+        };
+
+        let txs = tranches
+            .iter()
+            .map(|_| tokio::sync::oneshot::channel().0)
+            .collect();
+        indexer.new_iceberg_rpc_order(tranches.clone(), txs);
+        let events = tranches
+            .iter()
+            .zip(1..)
+            .map(|(tranche, nonce)| {
+                indexer
+                    .handle_validated_order(valid(tranche, nonce))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // only the first tranche is gossiped and listed
+        assert!(matches!(&events[0], PoolInnerEvent::Propagation(order) if *order == tranches[0]));
+        assert!(events[1..]
+            .iter()
+            .all(|event| matches!(event, PoolInnerEvent::None)));
+        assert!(indexer.hidden_tranches.is_empty());
+        assert!(indexer
+            .get_all_orders()
+            .limit
+            .iter()
+            .all(|order| !order.is_private));
+        assert_eq!(
+            indexer.orders_by_pool(pool_id, OrderLocation::Limit),
+            vec![tranches[0].clone()]
+        );
+        assert_eq!(indexer.order_by_hash(hashes[1]), None);
+        // nor does it go into our pre-proposals, the rounds we lead included
+        assert_eq!(indexer.get_all_orders().limit.len(), 3);
+        let pre_proposal = PreProposal::new(
+            1,
+            &AngstromSigner::random(),
+            indexer.get_all_orders(),
+            &HashMap::new(),
+            true
+        );
+        assert_eq!(
+            pre_proposal
+                .limit
+                .iter()
+                .map(|order| order.order_id.hash)
+                .collect::<Vec<_>>(),
+            vec![hashes[0]]
+        );
+
+        // filling the visible tranche reveals the next one, which is validated
+        // again and gossiped
+        indexer.filled_orders(2, &hashes[..1]);
+        indexer.replenish_icebergs(&hashes[..1]);
+        assert!(!indexer.order_hash_to_order_id.contains_key(&hashes[1]));
+        assert_eq!(indexer.icebergs.get(&hashes[1]), Some(&VecDeque::from([hashes[2]])));
+
+        let event = indexer
+            .handle_validated_order(valid(&tranches[1], 2))
+            .unwrap();
+        assert!(matches!(event, PoolInnerEvent::Propagation(order) if order == tranches[1]));
+        assert_eq!(
+            indexer.orders_by_pool(pool_id, OrderLocation::Limit),
+            vec![tranches[1].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_an_iceberg_cancels_its_hidden_tranches() {
+        let mut indexer = setup_test_indexer();
+        let signer = AngstromSigner::random();
+        let from = signer.address();
+        let pool_key = PoolKey {
+            currency0: Address::random(),
+            currency1: Address::random(),
+            ..Default::default()
+        };
+        let pool_id = PoolId::from(pool_key.clone());
+        indexer.new_pool(NewInitializedPool {
+            currency_out: pool_key.currency0,
+            currency_in:  pool_key.currency1,
+            id:           pool_id
+        });
+
+        let deadline = U256::from(indexer.unix_now() + 1000);
+        let tranches = iceberg_tranches(&signer, &pool_key, deadline);
+        let valid = |order: &AllOrders, nonce: u64| {
+            OrderValidationResults::Valid(validated(order, from, pool_id, nonce))
+        };
+
+        let txs = tranches
+            .iter()
+            .map(|_| tokio::sync::oneshot::channel().0)
+            .collect();
+        indexer.new_iceberg_rpc_order(tranches.clone(), txs);
+        // the last tranche is still being validated when the iceberg is cancelled
+        for (tranche, nonce) in tranches[..2].iter().zip(1..) {
+            indexer
+                .handle_validated_order(valid(tranche, nonce))
+                .unwrap();
+        }
+
+        let visible = tranches[0].order_hash();
+        let hash = angstrom_types::orders::CancelOrderRequest::signing_payload(from, visible);
+        let cancel_request = angstrom_types::orders::CancelOrderRequest {
+            order_id:     visible,
+            user_address: from,
+            signature:    signer.sign_hash_sync(&hash).unwrap()
+        };
+        assert!(indexer.cancel_order(&cancel_request));
+        assert!(indexer.get_all_orders().limit.is_empty());
+        assert!(indexer.icebergs.is_empty());
+        assert!(tranches
+            .iter()
+            .all(|tranche| indexer.is_cancelled(&tranche.order_hash())));
+
+        let event = indexer
+            .handle_validated_order(valid(&tranches[2], 3))
+            .unwrap();
+        assert!(matches!(event, PoolInnerEvent::None));
+        assert!(indexer.get_all_orders().limit.is_empty());
+    }
+
     #[tokio::test]
     async fn test_network_order_handling() {
         let mut indexer = setup_test_indexer();
@@ -915,7 +1712,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -923,6 +1724,27 @@ mod tests {
         assert!(!indexer.order_hash_to_peer_id.contains_key(&order_hash));
     }
 
+    #[tokio::test]
+    async fn carried_over_orders_are_not_held_against_their_proposer() {
+        let mut indexer = setup_test_indexer();
+        let pool_key = PoolKey {
+            currency0: Address::random(),
+            currency1: Address::random(),
+            ..Default::default()
+        };
+        let order = create_test_order(Address::random(), pool_key, None, None);
+
+        let order_hash = order.order_hash();
+
+        indexer.new_carried_over_order(order);
+        assert!(indexer.received_at.contains_key(&order_hash));
+
+        let event = indexer
+            .handle_validated_order(OrderValidationResults::Invalid(order_hash))
+            .unwrap();
+        assert!(matches!(event, PoolInnerEvent::BadOrderMessages(peers) if peers.is_empty()));
+    }
+
     #[tokio::test]
     async fn test_invalid_orders() {
         let mut indexer = setup_test_indexer();
@@ -1005,7 +1827,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -1078,7 +1904,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -1131,11 +1961,15 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
-        let hash = keccak256((from, order_hash).abi_encode());
+        let hash = angstrom_types::orders::CancelOrderRequest::signing_payload(from, order_hash);
         let sig = signer.sign_hash_sync(&hash).unwrap();
 
         // Cancel the order
@@ -1151,6 +1985,165 @@ mod tests {
         assert!(!indexer.order_hash_to_order_id.contains_key(&order_hash));
     }
 
+    #[tokio::test]
+    async fn test_cancel_all_orders() {
+        let mut indexer = setup_test_indexer();
+        let signer = AngstromSigner::random();
+        let from = signer.address();
+
+        let mut order_hashes = vec![];
+        let mut placed_after = None;
+        for nonce in 0..3 {
+            let pool_key = PoolKey {
+                currency0: Address::random(),
+                currency1: Address::random(),
+                ..Default::default()
+            };
+            let pool_id = PoolId::from(pool_key.clone());
+            indexer.new_pool(NewInitializedPool {
+                currency_out: pool_key.currency0,
+                currency_in:  pool_key.currency1,
+                id:           pool_id
+            });
+
+            let order = create_test_order(from, pool_key, None, Some(signer.clone()));
+            let order_hash = order.order_hash();
+            let (tx, _) = tokio::sync::oneshot::channel();
+            indexer.new_rpc_order(OrderOrigin::Local, order.clone(), tx);
+            indexer
+                .handle_validated_order(OrderValidationResults::Valid(OrderWithStorageData {
+                    order,
+                    order_id: OrderId {
+                        address: from,
+                        reuse_avoidance: RespendAvoidanceMethod::Nonce(nonce),
+                        hash: order_hash,
+                        pool_id,
+                        location: OrderLocation::Limit,
+                        deadline: None,
+                        flash_block: None
+                    },
+                    valid_block: 1,
+                    pool_id,
+                    is_bid: true,
+                    is_currently_valid: true,
+                    is_valid: true,
+                    priority_data: Default::default(),
+                    invalidates: vec![],
+                    tob_reward: U256::ZERO,
+                    bond_tier: 0,
+                    is_private: false,
+                    is_hidden: false,
+                    peg: None
+                }))
+                .unwrap();
+            if nonce == 2 {
+                placed_after = indexer.address_to_orders[&from].last().copied();
+            } else {
+                order_hashes.push(order_hash);
+            }
+        }
+
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let valid_until = issued_at + 60;
+        // received after the request was signed
+        let placed_after = placed_after.unwrap();
+        indexer.received_at.insert(placed_after.hash, issued_at + 1);
+
+        let hash = CancelAllOrdersRequest::signing_payload(from, issued_at, valid_until);
+        let request = CancelAllOrdersRequest {
+            signature: signer.sign_hash_sync(&hash).unwrap(),
+            user_address: from,
+            issued_at,
+            valid_until
+        };
+
+        // signed by someone else
+        let mut forged = request.clone();
+        forged.user_address = Address::random();
+        assert_eq!(indexer.cancel_all_orders(&forged), None);
+
+        let mut cancelled = indexer.cancel_all_orders(&request).unwrap();
+        cancelled.sort();
+        order_hashes.sort();
+        assert_eq!(cancelled, order_hashes);
+        assert_eq!(indexer.address_to_orders[&from], vec![placed_after]);
+        assert!(order_hashes
+            .iter()
+            .all(|hash| indexer.cancelled_orders.contains_key(hash)
+                && !indexer.order_hash_to_order_id.contains_key(hash)));
+
+        // replays are ignored
+        assert_eq!(indexer.cancel_all_orders(&request), Some(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_all_orders() {
+        let mut indexer = setup_test_indexer();
+
+        let mut order_hashes = vec![];
+        for _ in 0..2 {
+            let signer = AngstromSigner::random();
+            let from = signer.address();
+            let pool_key = PoolKey {
+                currency0: Address::random(),
+                currency1: Address::random(),
+                ..Default::default()
+            };
+            let pool_id = PoolId::from(pool_key.clone());
+            indexer.new_pool(NewInitializedPool {
+                currency_out: pool_key.currency0,
+                currency_in:  pool_key.currency1,
+                id:           pool_id
+            });
+
+            let order = create_test_order(from, pool_key, None, Some(signer));
+            let order_hash = order.order_hash();
+            let (tx, _) = tokio::sync::oneshot::channel();
+            indexer.new_rpc_order(OrderOrigin::Local, order.clone(), tx);
+            indexer
+                .handle_validated_order(OrderValidationResults::Valid(OrderWithStorageData {
+                    order,
+                    order_id: OrderId {
+                        address: from,
+                        reuse_avoidance: RespendAvoidanceMethod::Nonce(0),
+                        hash: order_hash,
+                        pool_id,
+                        location: OrderLocation::Limit,
+                        deadline: None,
+                        flash_block: None
+                    },
+                    valid_block: 1,
+                    pool_id,
+                    is_bid: true,
+                    is_currently_valid: true,
+                    is_valid: true,
+                    priority_data: Default::default(),
+                    invalidates: vec![],
+                    tob_reward: U256::ZERO,
+                    bond_tier: 0,
+                    is_private: false,
+                    is_hidden: false,
+                    peg: None
+                }))
+                .unwrap();
+            order_hashes.push(order_hash);
+        }
+
+        let mut dropped = indexer.invalidate_all_orders();
+        dropped.sort();
+        order_hashes.sort();
+        assert_eq!(dropped, order_hashes);
+        assert!(indexer.address_to_orders.is_empty());
+        assert!(indexer.get_all_orders().limit.is_empty());
+        // they can be submitted again
+        assert!(order_hashes
+            .iter()
+            .all(|hash| indexer.is_missing(hash) && !indexer.is_seen_invalid(hash)));
+    }
+
     #[tokio::test]
     async fn test_duplicate_order_rejection() {
         let mut indexer = setup_test_indexer();
@@ -1194,7 +2187,11 @@ mod tests {
                 is_valid: true,
                 priority_data: Default::default(),
                 invalidates: vec![],
-                tob_reward: U256::ZERO
+                tob_reward: U256::ZERO,
+                bond_tier: 0,
+                is_private: false,
+                is_hidden: false,
+                peg: None
             }))
             .unwrap();
 
@@ -1208,4 +2205,48 @@ mod tests {
             _ => panic!("Expected invalid order result")
         }
     }
+
+    #[tokio::test]
+    async fn test_settled_orders_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settled_orders");
+        let (filled, cancelled) = (B256::random(), B256::random());
+
+        let mut indexer =
+            setup_test_indexer().with_settled_orders(Some(path.clone()), Duration::from_secs(60));
+        indexer.filled_orders(1, &[filled]);
+        indexer.insert_cancel_request_with_deadline(
+            Address::random(),
+            &cancelled,
+            Some(U256::from(indexer.unix_now() + 60))
+        );
+        assert!(indexer.is_duplicate(&filled));
+        indexer.persist_settled_orders().unwrap().await.unwrap();
+
+        let restarted =
+            setup_test_indexer().with_settled_orders(Some(path), Duration::from_secs(60));
+        assert!(restarted.is_duplicate(&filled));
+        assert!(restarted.is_cancelled(&cancelled));
+
+        // a reorg makes the order fillable again
+        indexer.reorg(vec![filled]);
+        assert!(!indexer.is_duplicate(&filled));
+    }
+
+    #[tokio::test]
+    async fn filled_orders_are_bounded() {
+        let mut indexer = setup_test_indexer()
+            .with_filled_orders_capacity(2)
+            .with_settled_orders(None, Duration::from_secs(60));
+        let filled = (0..3).map(|_| B256::random()).collect::<Vec<_>>();
+
+        indexer.filled_orders(1, &filled[..2]);
+        indexer.filled_orders(2, &filled[2..]);
+        assert_eq!(indexer.recently_filled.len(), 2);
+        assert!(indexer.is_duplicate(&filled[2]));
+
+        // nothing expired yet
+        indexer.collect_filled_orders();
+        assert_eq!(indexer.recently_filled.len(), 2);
+    }
 }
@@ -0,0 +1,178 @@
+//! Incremental updates of the pending limit orders, so that consumers that
+//! need the whole set at a deadline don't have to snapshot the storage then.
+use std::collections::HashMap;
+
+use alloy::primitives::B256;
+use angstrom_types::{
+    primitive::PoolId,
+    sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
+};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::order_storage::OrderStorage;
+
+/// A change to the pending vanilla limit orders of the [`OrderStorage`].
+#[derive(Debug, Clone)]
+pub enum OrderSetDiff {
+    Added(OrderWithStorageData<GroupedVanillaOrder>),
+    /// the order was removed, parked or evicted
+    Removed(B256),
+    /// all orders of the pool were removed
+    RemovedPool(PoolId)
+}
+
+/// Copy of the pending vanilla limit orders of the [`OrderStorage`], kept up
+/// to date from its diffs.
+#[derive(Debug)]
+pub struct OrderSetMirror {
+    orders: HashMap<B256, OrderWithStorageData<GroupedVanillaOrder>>,
+    diffs:  UnboundedReceiver<OrderSetDiff>
+}
+
+impl OrderSetMirror {
+    pub fn new(storage: &OrderStorage) -> Self {
+        let (orders, diffs) = storage.subscribe_order_set_diffs();
+        let orders = orders
+            .into_iter()
+            .map(|order| (order.order_id.hash, order))
+            .collect();
+
+        Self { orders, diffs }
+    }
+
+    /// Applies the diffs that came in since the last sync, returns how many
+    /// there were.
+    pub fn sync(&mut self) -> usize {
+        let mut applied = 0;
+        while let Ok(diff) = self.diffs.try_recv() {
+            match diff {
+                OrderSetDiff::Added(order) => {
+                    self.orders.insert(order.order_id.hash, order);
+                }
+                OrderSetDiff::Removed(hash) => {
+                    self.orders.remove(&hash);
+                }
+                OrderSetDiff::RemovedPool(pool_id) => {
+                    self.orders.retain(|_, order| order.pool_id != pool_id);
+                }
+            }
+            applied += 1;
+        }
+
+        applied
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// The pending vanilla limit orders, same as the limit orders of
+    /// [`OrderStorage::get_all_orders`].
+    pub fn limit_orders(&mut self) -> Vec<OrderWithStorageData<GroupedVanillaOrder>> {
+        self.sync();
+        self.orders.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::{
+        primitive::NewInitializedPool, sol_bindings::grouped_orders::GroupedUserOrder
+    };
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+    use crate::PoolConfig;
+
+    fn pool(id: u8) -> NewInitializedPool {
+        NewInitializedPool {
+            currency_in:  Default::default(),
+            currency_out: Default::default(),
+            id:           PoolId::repeat_byte(id)
+        }
+    }
+
+    fn order(pool_id: PoolId, nonce: u64, valid: bool) -> OrderWithStorageData<GroupedUserOrder> {
+        let mut order = UserOrderBuilder::new()
+            .standing()
+            .exact()
+            .nonce(nonce)
+            .amount(100)
+            .with_storage()
+            .pool_id(pool_id)
+            .build();
+        order.is_currently_valid = valid;
+        order
+            .try_map_inner(|order| Ok(GroupedUserOrder::Vanilla(order)))
+            .unwrap()
+    }
+
+    fn hashes(orders: Vec<OrderWithStorageData<GroupedVanillaOrder>>) -> Vec<B256> {
+        let mut hashes = orders
+            .into_iter()
+            .map(|order| order.order_id.hash)
+            .collect::<Vec<_>>();
+        hashes.sort();
+        hashes
+    }
+
+    #[test]
+    fn mirrors_the_pending_limit_orders() {
+        let storage = OrderStorage::new(&PoolConfig::default());
+        let (a, b) = (pool(1), pool(2));
+        storage.new_pool(a);
+        storage.new_pool(b);
+        let first = order(a.id, 0, true);
+        storage.add_new_limit_order(first.clone()).unwrap();
+
+        let mut mirror = OrderSetMirror::new(&storage);
+        assert_eq!(mirror.len(), 1);
+
+        let second = order(a.id, 1, true);
+        let parked = order(a.id, 2, false);
+        let other_pool = order(b.id, 3, true);
+        for order in [&second, &parked, &other_pool] {
+            storage.add_new_limit_order(order.clone()).unwrap();
+        }
+        storage.park_orders(vec![&first.order_id]);
+        storage.remove_pool(b.id);
+
+        assert_eq!(mirror.sync(), 4);
+        assert_eq!(hashes(mirror.limit_orders()), hashes(storage.get_all_orders().limit));
+        assert_eq!(hashes(mirror.limit_orders()), vec![second.order_id.hash]);
+
+        // validated again, it comes back parked
+        storage.remove_limit_order(&second.order_id);
+        let mut revalidated = second.clone();
+        revalidated.is_currently_valid = false;
+        storage.add_new_limit_order(revalidated).unwrap();
+        assert!(mirror.limit_orders().is_empty());
+
+        // validated again, it is still valid
+        storage.remove_limit_order(&second.order_id);
+        storage.add_new_limit_order(second.clone()).unwrap();
+        assert_eq!(hashes(mirror.limit_orders()), vec![second.order_id.hash]);
+
+        // cancelled by the user
+        assert!(storage.cancel_order(&second.order_id).is_some());
+        assert!(mirror.limit_orders().is_empty());
+        assert_eq!(hashes(mirror.limit_orders()), hashes(storage.get_all_orders().limit));
+    }
+
+    #[test]
+    fn stops_publishing_to_dropped_mirrors() {
+        let storage = OrderStorage::new(&PoolConfig::default());
+        let pool = pool(1);
+        storage.new_pool(pool);
+
+        drop(OrderSetMirror::new(&storage));
+        storage
+            .add_new_limit_order(order(pool.id, 0, true))
+            .unwrap();
+        assert!(!storage.has_order_set_listeners());
+    }
+}
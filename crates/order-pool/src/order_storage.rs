@@ -1,25 +1,32 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     default::Default,
     fmt::Debug,
-    sync::{Arc, Mutex},
-    time::Instant
+    sync::{Arc, Mutex}
 };
 
 use alloy::primitives::{BlockNumber, FixedBytes, B256};
-use angstrom_metrics::OrderStorageMetricsWrapper;
+use angstrom_metrics::{check_invariants, OrderStorageMetricsWrapper};
 use angstrom_types::{
-    orders::{OrderId, OrderLocation, OrderSet, OrderStatus},
+    orders::{
+        AgeBucket, InvariantViolation, OrderId, OrderLocation, OrderSet, OrderStatus,
+        PoolAnalytics, PriceBucket
+    },
     primitive::{NewInitializedPool, PoolId},
     sol_bindings::{
         grouped_orders::{AllOrders, GroupedUserOrder, GroupedVanillaOrder, OrderWithStorageData},
         rpc_orders::TopOfBlockOrder
     }
 };
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
 use crate::{
+    common::{EvictionReason, OrderHashConflict},
+    fill_history::FillHistory,
     finalization_pool::FinalizationPool,
+    first_seen::FirstSeenBlocks,
     limit::{LimitOrderPool, LimitPoolError},
+    order_set_diff::OrderSetDiff,
     searcher::{SearcherPool, SearcherPoolError},
     PoolConfig
 };
@@ -27,13 +34,16 @@ use crate::{
 /// The Storage of all verified orders.
 #[derive(Clone)]
 pub struct OrderStorage {
-    pub limit_orders:                Arc<Mutex<LimitOrderPool>>,
-    pub searcher_orders:             Arc<Mutex<SearcherPool>>,
+    pub limit_orders: Arc<Mutex<LimitOrderPool>>,
+    pub searcher_orders: Arc<Mutex<SearcherPool>>,
     pub pending_finalization_orders: Arc<Mutex<FinalizationPool>>,
-    /// we store filled order hashes until they are expired time wise to ensure
-    /// we don't waste processing power in the validator.
-    pub filled_orders:               Arc<Mutex<HashMap<B256, Instant>>>,
-    pub metrics:                     OrderStorageMetricsWrapper
+    /// limit orders offered and filled in the last rounds of every pool
+    fill_history: Arc<Mutex<FillHistory>>,
+    /// block every pending limit order was first added in
+    first_seen: Arc<Mutex<FirstSeenBlocks>>,
+    /// subscribers to the changes of the pending vanilla limit orders
+    order_set_listeners: Arc<Mutex<Vec<UnboundedSender<OrderSetDiff>>>>,
+    pub metrics: OrderStorageMetricsWrapper
 }
 
 impl Debug for OrderStorage {
@@ -45,17 +55,20 @@ impl Debug for OrderStorage {
 
 impl OrderStorage {
     pub fn new(config: &PoolConfig) -> Self {
-        let limit_orders = Arc::new(Mutex::new(LimitOrderPool::new(
-            &config.ids,
-            Some(config.lo_pending_limit.max_size)
-        )));
+        let limit_orders = Arc::new(Mutex::new(
+            LimitOrderPool::new(&config.ids, Some(config.lo_pending_limit.max_size))
+                .with_account_limits(config.account_limits)
+                .with_admission_policy(config.admission)
+        ));
         let searcher_orders = Arc::new(Mutex::new(SearcherPool::new(
             &config.ids,
             Some(config.s_pending_limit.max_size)
         )));
         let pending_finalization_orders = Arc::new(Mutex::new(FinalizationPool::new()));
         Self {
-            filled_orders: Arc::new(Mutex::new(HashMap::default())),
+            fill_history: Arc::new(Mutex::new(FillHistory::default())),
+            first_seen: Default::default(),
+            order_set_listeners: Default::default(),
             limit_orders,
             searcher_orders,
             pending_finalization_orders,
@@ -65,24 +78,15 @@ impl OrderStorage {
 
     pub fn remove_pool(&self, key: PoolId) {
         self.searcher_orders.lock().unwrap().remove_pool(&key);
-        self.limit_orders.lock().unwrap().remove_pool(&key);
+        let mut limit_orders = self.limit_orders.lock().unwrap();
+        limit_orders.remove_pool(&key);
+        self.publish_order_set_diffs([OrderSetDiff::RemovedPool(key)]);
+        drop(limit_orders);
+        self.fill_history.lock().unwrap().remove_pool(&key);
+        self.check_invariants();
     }
 
     pub fn fetch_status_of_order(&self, order: B256) -> Option<OrderStatus> {
-        if self
-            .filled_orders
-            .lock()
-            .expect("poisoned")
-            .contains_key(&order)
-            && self
-                .pending_finalization_orders
-                .lock()
-                .expect("poisoned")
-                .has_order(&order)
-        {
-            return Some(OrderStatus::Filled)
-        }
-
         if self
             .searcher_orders
             .lock()
@@ -117,8 +121,7 @@ impl OrderStorage {
         {
             return None
         }
-
-        match order_id.location {
+        let order = match order_id.location {
             angstrom_types::orders::OrderLocation::Limit => self
                 .limit_orders
                 .lock()
@@ -129,7 +132,10 @@ impl OrderStorage {
                         GroupedUserOrder::Composable(_) => {
                             self.metrics.incr_cancelled_composable_orders()
                         }
-                        GroupedUserOrder::Vanilla(_) => self.metrics.incr_cancelled_vanilla_orders()
+                        GroupedUserOrder::Vanilla(_) => {
+                            self.metrics.incr_cancelled_vanilla_orders();
+                            self.publish_order_set_diffs([OrderSetDiff::Removed(order_id.hash)]);
+                        }
                     }
                     order.try_map_inner(|inner| Ok(inner.into())).ok()
                 }),
@@ -144,7 +150,10 @@ impl OrderStorage {
                         .try_map_inner(|inner| Ok(AllOrders::TOB(inner)))
                         .unwrap()
                 })
-        }
+        };
+        self.check_invariants();
+
+        order
     }
 
     /// moves all orders to the parked location if there not already.
@@ -156,11 +165,15 @@ impl OrderStorage {
             .for_each(|order| match order.location {
                 angstrom_types::orders::OrderLocation::Limit => {
                     limit_lock.park_order(order);
+                    self.publish_order_set_diffs([OrderSetDiff::Removed(order.hash)]);
                 }
                 angstrom_types::orders::OrderLocation::Searcher => {
                     tracing::debug!("tried to park searcher order. this is not supported");
                 }
             });
+        drop(limit_lock);
+
+        self.check_invariants();
     }
 
     pub fn top_tob_orders(&self) -> Vec<OrderWithStorageData<TopOfBlockOrder>> {
@@ -182,10 +195,13 @@ impl OrderStorage {
         top_orders
     }
 
+    /// Adds a new limit order, returns the orders that were evicted to make
+    /// room for it.
     pub fn add_new_limit_order(
         &self,
         order: OrderWithStorageData<GroupedUserOrder>
-    ) -> Result<(), LimitPoolError> {
+    ) -> Result<Vec<OrderId>, LimitPoolError> {
+        let mut limit_orders = self.limit_orders.lock().expect("lock poisoned");
         if order.is_vanilla() {
             let mapped_order = order.try_map_inner(|this| {
                 let GroupedUserOrder::Vanilla(order) = this else {
@@ -194,10 +210,23 @@ impl OrderStorage {
                 Ok(order)
             })?;
 
-            self.limit_orders
+            // an order validated again can come back parked, mirrors drop it then
+            let diff = self.has_order_set_listeners().then(|| {
+                if mapped_order.is_currently_valid {
+                    OrderSetDiff::Added(mapped_order.clone())
+                } else {
+                    OrderSetDiff::Removed(mapped_order.order_id.hash)
+                }
+            });
+            let (hash, valid_block) = (mapped_order.order_id.hash, mapped_order.valid_block);
+            limit_orders
+                .add_vanilla_order(mapped_order)
+                .inspect_err(|e| self.record_limit_rejection(e))?;
+            self.first_seen
                 .lock()
-                .expect("lock poisoned")
-                .add_vanilla_order(mapped_order)?;
+                .expect("poisoned")
+                .on_added(hash, valid_block);
+            self.publish_order_set_diffs(diff);
             self.metrics.incr_vanilla_limit_orders(1);
         } else {
             let mapped_order = order.try_map_inner(|this| {
@@ -207,28 +236,79 @@ impl OrderStorage {
                 Ok(order)
             })?;
 
-            self.limit_orders
-                .lock()
-                .expect("lock poisoned")
-                .add_composable_order(mapped_order)?;
+            limit_orders
+                .add_composable_order(mapped_order)
+                .inspect_err(|e| self.record_limit_rejection(e))?;
             self.metrics.incr_composable_limit_orders(1);
         }
+        let evicted = limit_orders.take_evicted();
+        self.publish_order_set_diffs(
+            evicted
+                .iter()
+                .map(|(_, order)| OrderSetDiff::Removed(order.order_id.hash))
+        );
+        drop(limit_orders);
 
-        Ok(())
+        let evicted = evicted
+            .into_iter()
+            .map(|(reason, order)| {
+                if order.is_vanilla() {
+                    self.metrics.decr_vanilla_limit_orders(1);
+                } else {
+                    self.metrics.decr_composable_limit_orders(1);
+                }
+                match reason {
+                    EvictionReason::LowFeeParked => self.metrics.incr_evicted_parked_orders(),
+                    EvictionReason::OldestDust => self.metrics.incr_evicted_dust_orders()
+                }
+                order.order_id
+            })
+            .collect();
+        self.check_invariants();
+
+        Ok(evicted)
     }
 
+    /// Adds a new searcher order, returns the orders of searchers with a lower
+    /// bond tier that were evicted to make room for it.
     pub fn add_new_searcher_order(
         &self,
         order: OrderWithStorageData<TopOfBlockOrder>
-    ) -> Result<(), SearcherPoolError> {
-        self.searcher_orders
+    ) -> Result<Vec<OrderId>, SearcherPoolError> {
+        let evicted = self
+            .searcher_orders
             .lock()
             .expect("lock poisoned")
-            .add_searcher_order(order)?;
+            .add_searcher_order(order)
+            .inspect_err(|e| {
+                if let SearcherPoolError::HashConflict(conflict) = e {
+                    self.record_conflict(conflict);
+                }
+            })?;
 
         self.metrics.incr_searcher_orders(1);
+        self.metrics.decr_searcher_orders(evicted.len());
+        self.check_invariants();
+
+        Ok(evicted.into_iter().map(|order| order.order_id).collect())
+    }
+
+    fn record_limit_rejection(&self, e: &LimitPoolError) {
+        match e {
+            LimitPoolError::HashConflict(conflict) => self.record_conflict(conflict),
+            LimitPoolError::AccountCap(exceeded) => {
+                tracing::debug!(%exceeded, "rejected order over its account cap");
+                self.metrics.incr_account_cap_rejections();
+            }
+            _ => {}
+        }
+    }
 
-        Ok(())
+    fn record_conflict(&self, conflict: &OrderHashConflict) {
+        if conflict.is_collision() {
+            tracing::warn!(%conflict, "rejected order with a colliding hash");
+            self.metrics.incr_order_hash_collisions();
+        }
     }
 
     pub fn add_filled_orders(
@@ -243,6 +323,7 @@ impl OrderStorage {
             .new_orders(block_number, orders);
 
         self.metrics.incr_pending_finalization_orders(num_orders);
+        self.check_invariants();
     }
 
     pub fn finalized_block(&self, block_number: BlockNumber) {
@@ -253,6 +334,7 @@ impl OrderStorage {
             .finalized(block_number);
 
         self.metrics.decr_pending_finalization_orders(orders.len());
+        self.check_invariants();
     }
 
     pub fn reorg(&self, order_hashes: Vec<FixedBytes<32>>) -> Vec<OrderWithStorageData<AllOrders>> {
@@ -264,6 +346,8 @@ impl OrderStorage {
             .collect::<Vec<_>>();
 
         self.metrics.decr_pending_finalization_orders(orders.len());
+        self.check_invariants();
+
         orders
     }
 
@@ -281,24 +365,108 @@ impl OrderStorage {
                     })
                     .unwrap()
             });
+        self.check_invariants();
 
         order
     }
 
     pub fn remove_limit_order(&self, id: &OrderId) -> Option<OrderWithStorageData<AllOrders>> {
-        self.limit_orders
+        let mut limit_orders = self.limit_orders.lock().expect("poisoned");
+        let order = limit_orders.remove_order(id);
+        if order.as_ref().is_some_and(|order| order.is_vanilla()) {
+            self.publish_order_set_diffs([OrderSetDiff::Removed(id.hash)]);
+        }
+        drop(limit_orders);
+
+        let order = order.and_then(|order| {
+            if order.is_vanilla() {
+                self.metrics.decr_vanilla_limit_orders(1);
+            } else if order.is_composable() {
+                self.metrics.decr_composable_limit_orders(1);
+            }
+
+            order.try_map_inner(|inner| Ok(inner.into())).ok()
+        });
+        self.check_invariants();
+
+        order
+    }
+
+    /// Records the limit orders a rounds proposal offered to the matching
+    /// engine and the ones it filled.
+    pub fn record_committed_round<'a>(
+        &self,
+        block_number: BlockNumber,
+        offered: impl IntoIterator<Item = &'a OrderId>,
+        filled: &HashSet<B256>
+    ) {
+        self.fill_history
             .lock()
             .expect("poisoned")
-            .remove_order(id)
-            .and_then(|order| {
-                if order.is_vanilla() {
-                    self.metrics.decr_vanilla_limit_orders(1);
-                } else if order.is_composable() {
-                    self.metrics.decr_composable_limit_orders(1);
-                }
+            .record_round(block_number, offered, filled);
 
-                order.try_map_inner(|inner| Ok(inner.into())).ok()
-            })
+        let limit_orders = self.limit_orders.lock().expect("poisoned");
+        self.first_seen
+            .lock()
+            .expect("poisoned")
+            .prune(block_number, |hash| limit_orders.get_order_status(*hash).is_some());
+    }
+
+    /// The block every pending limit order was first added in. Unlike its
+    /// valid block it stays the same when the order is validated again.
+    pub fn first_seen_blocks(&self) -> HashMap<B256, BlockNumber> {
+        self.first_seen.lock().expect("poisoned").first_seen()
+    }
+
+    /// How the orders of `pool_id` are made up at `block_number`, with its
+    /// pending limit orders split into `price_buckets` by their price.
+    pub fn pool_analytics(
+        &self,
+        pool_id: PoolId,
+        block_number: BlockNumber,
+        price_buckets: usize
+    ) -> PoolAnalytics {
+        // hidden tranches of iceberg orders aren't part of the depth
+        let mut limit = self
+            .limit_orders
+            .lock()
+            .expect("poisoned")
+            .pending_orders_of_pool(&pool_id);
+        limit.retain(|order| !order.is_hidden);
+        let searcher_orders = self
+            .searcher_orders
+            .lock()
+            .expect("poisoned")
+            .get_orders_for_pool(&pool_id)
+            .map_or(0, |orders| orders.len());
+
+        let prices = limit
+            .iter()
+            .map(|order| (*order.order.price_for_book_side(order.is_bid), order.is_bid))
+            .collect::<Vec<_>>();
+        let partial_orders = limit
+            .iter()
+            .filter(|order| order.order.is_partial())
+            .count();
+
+        PoolAnalytics {
+            pool_id,
+            block: block_number,
+            price_buckets: PriceBucket::bucket(&prices, price_buckets),
+            ages: AgeBucket::bucket(
+                limit
+                    .iter()
+                    .map(|order| block_number.saturating_sub(order.valid_block))
+            ),
+            partial_orders,
+            exact_orders: limit.len() - partial_orders,
+            searcher_orders,
+            fills: self
+                .fill_history
+                .lock()
+                .expect("poisoned")
+                .of_pool(&pool_id)
+        }
     }
 
     pub fn get_all_orders(&self) -> OrderSet<GroupedVanillaOrder, TopOfBlockOrder> {
@@ -308,11 +476,84 @@ impl OrderStorage {
         OrderSet { limit, searcher }
     }
 
+    /// Subscribes to the changes of the pending vanilla limit orders, returns
+    /// them as they are when subscribing along with the changes from then on.
+    pub fn subscribe_order_set_diffs(
+        &self
+    ) -> (Vec<OrderWithStorageData<GroupedVanillaOrder>>, UnboundedReceiver<OrderSetDiff>) {
+        // holding the lock, no change can fall between the snapshot and the
+        // subscription
+        let limit_orders = self.limit_orders.lock().expect("poisoned");
+        let (tx, rx) = unbounded_channel();
+        self.order_set_listeners.lock().expect("poisoned").push(tx);
+
+        (limit_orders.get_all_orders(), rx)
+    }
+
+    pub fn has_order_set_listeners(&self) -> bool {
+        !self
+            .order_set_listeners
+            .lock()
+            .expect("poisoned")
+            .is_empty()
+    }
+
+    /// Sends the diffs to every subscriber, dropping the ones that went away.
+    /// Called with the limit orders locked so the diffs keep their order.
+    fn publish_order_set_diffs(&self, diffs: impl IntoIterator<Item = OrderSetDiff>) {
+        let mut listeners = self.order_set_listeners.lock().expect("poisoned");
+        if listeners.is_empty() {
+            return
+        }
+        for diff in diffs {
+            listeners.retain(|listener| listener.send(diff.clone()).is_ok());
+        }
+    }
+
     pub fn new_pool(&self, pool: NewInitializedPool) {
         self.limit_orders.lock().expect("poisoned").new_pool(pool);
         self.searcher_orders
             .lock()
             .expect("poisoned")
             .new_pool(pool);
+        self.check_invariants();
+    }
+
+    /// Consistency checks over all stored orders: no order is stored twice,
+    /// parked orders are never pending and filled orders are no longer pooled.
+    pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+        let limit = self.limit_orders.lock().expect("poisoned");
+        let mut violations = limit.invariant_violations();
+        let pooled = limit.order_hashes();
+        drop(limit);
+
+        let mut searcher = HashSet::new();
+        for hash in self
+            .searcher_orders
+            .lock()
+            .expect("poisoned")
+            .order_hashes()
+        {
+            if pooled.contains(&hash) || !searcher.insert(hash) {
+                violations.push(InvariantViolation::DuplicateOrder(hash));
+            }
+        }
+
+        violations.extend(
+            self.pending_finalization_orders
+                .lock()
+                .expect("poisoned")
+                .order_hashes()
+                .filter(|hash| pooled.contains(hash) || searcher.contains(hash))
+                .map(InvariantViolation::FilledStillPooled)
+        );
+
+        violations
+    }
+
+    /// Runs after every mutation, only does work if invariant checking is
+    /// enabled.
+    fn check_invariants(&self) {
+        check_invariants("order_storage", || self.invariant_violations());
     }
 }
@@ -10,7 +10,10 @@ use angstrom_types::{
 use angstrom_utils::map::OwnedMap;
 use pending::PendingPool;
 
-use crate::{common::SizeTracker, AllOrders};
+use crate::{
+    common::{OrderHashConflict, SizeTracker},
+    AllOrders
+};
 
 mod pending;
 
@@ -42,6 +45,7 @@ impl SearcherPool {
             .map(|pool| {
                 pool.get_all_orders()
                     .into_iter()
+                    .filter(|p| !p.is_private)
                     .map(|p| p.order.into())
                     .collect::<Vec<_>>()
             })
@@ -66,31 +70,82 @@ impl SearcherPool {
             .and_then(|pool| pool.get_order(order_id))
     }
 
+    /// Adds a searcher order, returns the orders evicted to make room for it.
+    /// A full pool makes room by evicting the orders of searchers with a
+    /// lower bond tier than the one of the new order.
     pub fn add_searcher_order(
         &mut self,
         order: OrderWithStorageData<TopOfBlockOrder>
-    ) -> Result<(), SearcherPoolError> {
+    ) -> Result<Vec<OrderWithStorageData<TopOfBlockOrder>>, SearcherPoolError> {
+        let existing = self
+            .searcher_orders
+            .values()
+            .find_map(|pool| pool.get_order_id(order.order_id.hash));
+        OrderHashConflict::check(existing, &order.order_id)?;
+
+        let pool_id = order.pool_id;
+        if !self.searcher_orders.contains_key(&pool_id) {
+            return Err(SearcherPoolError::NoPool(pool_id))
+        }
+
         let size = order.size();
+        let mut evicted = vec![];
         if !self.size.has_space(size) {
-            return Err(SearcherPoolError::MaxSize)
+            let evictions = self
+                .evictions_for(&order)
+                .ok_or(SearcherPoolError::MaxSize)?;
+            evicted = evictions
+                .iter()
+                .filter_map(|id| self.remove_order(id))
+                .collect();
+            let fits = self.size.has_space(size);
+            debug_assert!(fits, "evicted the orders to make room");
         }
 
-        let pool_id = order.pool_id;
         self.searcher_orders
             .get_mut(&pool_id)
-            .ok_or_else(|| SearcherPoolError::NoPool(pool_id))?
+            .expect("checked above")
             .add_order(order);
 
         self.metrics.incr_all_orders(pool_id, 1);
 
-        Ok(())
+        Ok(evicted)
+    }
+
+    /// The orders to evict to fit `order` into the full pool, the ones of the
+    /// lowest bond tier and reward first. None if evicting every order of a
+    /// lower tier than its own doesn't make room.
+    fn evictions_for(&self, order: &OrderWithStorageData<TopOfBlockOrder>) -> Option<Vec<OrderId>> {
+        let max = self.size.max?;
+        let mut lower_tiers = self
+            .get_all_orders()
+            .into_iter()
+            .filter(|pooled| pooled.bond_tier < order.bond_tier)
+            .collect::<Vec<_>>();
+        lower_tiers.sort_by_key(|pooled| (pooled.bond_tier, pooled.tob_reward));
+
+        let mut missing = (self.size.current + order.size()).saturating_sub(max);
+        let mut evictions = vec![];
+        for pooled in lower_tiers {
+            if missing == 0 {
+                break
+            }
+            missing = missing.saturating_sub(pooled.size());
+            evictions.push(pooled.order_id);
+        }
+
+        (missing == 0).then_some(evictions)
     }
 
     pub fn remove_order(&mut self, id: &OrderId) -> Option<OrderWithStorageData<TopOfBlockOrder>> {
-        self.searcher_orders
+        let order = self
+            .searcher_orders
             .get_mut(&id.pool_id)
             .and_then(|pool| pool.remove_order(id.hash))
-            .owned_map(|| self.metrics.decr_all_orders(id.pool_id, 1))
+            .owned_map(|| self.metrics.decr_all_orders(id.pool_id, 1))?;
+        self.size.remove_order(order.size());
+
+        Some(order)
     }
 
     pub fn get_all_pool_ids(&self) -> Vec<PoolId> {
@@ -113,6 +168,14 @@ impl SearcherPool {
             .collect()
     }
 
+    pub fn order_hashes(&self) -> Vec<B256> {
+        self.searcher_orders
+            .values()
+            .flat_map(|pool| pool.get_all_orders())
+            .map(|order| order.order_id.hash)
+            .collect()
+    }
+
     pub fn new_pool(&mut self, pool: NewInitializedPool) {
         let old_is_none = self
             .searcher_orders
@@ -133,5 +196,81 @@ pub enum SearcherPoolError {
     #[error("No pool was found for address: {0} ")]
     NoPool(PoolId),
     #[error(transparent)]
+    HashConflict(#[from] OrderHashConflict),
+    #[error(transparent)]
     Unknown(#[from] eyre::Error)
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+    use testing_tools::type_generator::orders::generate_top_of_block_order;
+
+    use super::*;
+
+    #[test]
+    fn rejects_colliding_hashes_without_taking_space() {
+        let pool_id = PoolId::random();
+        let mut rng = rand::thread_rng();
+        let first = generate_top_of_block_order(&mut rng, true, Some(pool_id), None, None, None);
+        let mut second =
+            generate_top_of_block_order(&mut rng, true, Some(pool_id), None, Some(1), None);
+        second.order_id.hash = first.order_id.hash;
+        second.order_id.address = Address::random();
+
+        let mut pool = SearcherPool::new(&[pool_id], Some(first.size() + second.size()));
+        pool.add_searcher_order(first.clone()).unwrap();
+
+        assert!(matches!(
+            pool.add_searcher_order(first.clone()),
+            Err(SearcherPoolError::HashConflict(OrderHashConflict::Duplicate(_)))
+        ));
+        assert!(matches!(
+            pool.add_searcher_order(second.clone()),
+            Err(SearcherPoolError::HashConflict(OrderHashConflict::Collision { .. }))
+        ));
+        assert_eq!(pool.get_all_orders(), vec![first.clone()]);
+
+        // the rejected orders left the space for a real one
+        second.order_id.hash = B256::random();
+        pool.add_searcher_order(second).unwrap();
+    }
+
+    #[test]
+    fn full_pool_makes_room_for_searchers_of_a_higher_tier() {
+        let pool_id = PoolId::random();
+        let mut rng = rand::thread_rng();
+        let mut order = |quantity_in, bond_tier, tob_reward| OrderWithStorageData {
+            bond_tier,
+            tob_reward: U256::from(tob_reward),
+            ..generate_top_of_block_order(
+                &mut rng,
+                true,
+                Some(pool_id),
+                None,
+                Some(quantity_in),
+                None
+            )
+        };
+        let low = order(1, 1, 10);
+        let lower_reward = order(2, 1, 5);
+        let mut pool = SearcherPool::new(&[pool_id], Some(low.size() * 2));
+        assert!(pool.add_searcher_order(low.clone()).unwrap().is_empty());
+        assert!(pool
+            .add_searcher_order(lower_reward.clone())
+            .unwrap()
+            .is_empty());
+
+        // the same tier doesn't get in
+        assert!(matches!(
+            pool.add_searcher_order(order(3, 1, 100)),
+            Err(SearcherPoolError::MaxSize)
+        ));
+
+        // a higher one takes the place of the lowest reward of the lowest tier
+        let high = order(4, 2, 1);
+        assert_eq!(pool.add_searcher_order(high.clone()).unwrap(), vec![lower_reward]);
+        assert!(pool.has_order(high.order_id.hash));
+        assert!(pool.has_order(low.order_id.hash));
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use alloy::primitives::FixedBytes;
 use angstrom_types::{
-    orders::OrderPriorityData,
+    orders::{OrderId, OrderPriorityData},
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
 
@@ -30,6 +30,10 @@ impl PendingPool {
         self.orders.get(&id).cloned()
     }
 
+    pub fn get_order_id(&self, hash: FixedBytes<32>) -> Option<OrderId> {
+        self.orders.get(&hash).map(|order| order.order_id)
+    }
+
     pub fn add_order(&mut self, order: OrderWithStorageData<TopOfBlockOrder>) {
         if order.is_bid {
             self.bids
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc
+};
+
+use alloy::primitives::{Address, B256};
+use parking_lot::Mutex;
+use tokio::task::JoinHandle;
+
+/// hash, expiry, canceller
+const ENTRY_LEN: usize = 32 + 8 + 20;
+/// canceller of filled orders, no valid signer recovers to it
+const FILLED: Address = Address::ZERO;
+
+/// Hashes of the recently filled and cancelled orders, as they are written to
+/// disk. Loaded on startup so that a restarted node doesn't accept and
+/// propagate orders again that were settled right before it went down.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SettledOrders {
+    /// order hash to the unix time the entry expires at
+    pub filled:    HashMap<B256, u64>,
+    /// order hash to the canceller and the unix time the cancel expires at
+    pub cancelled: HashMap<B256, (Address, u64)>
+}
+
+impl SettledOrders {
+    /// Loads the orders that didn't expire by `now`. A missing file is an
+    /// empty set.
+    pub fn load(path: &Path, now: u64) -> std::io::Result<Self> {
+        match std::fs::read(path) {
+            Ok(bytes) => Self::decode(&bytes, now),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Replaces the file, a crash halfway leaves the previous one intact.
+    pub fn store(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, self.encode())?;
+
+        std::fs::rename(tmp, path)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let filled = self
+            .filled
+            .iter()
+            .map(|(hash, expires_at)| (hash, *expires_at, FILLED));
+        let cancelled = self
+            .cancelled
+            .iter()
+            .map(|(hash, (from, expires_at))| (hash, *expires_at, *from));
+
+        filled.chain(cancelled).fold(
+            Vec::with_capacity(self.len() * ENTRY_LEN),
+            |mut bytes, (hash, expires_at, from)| {
+                bytes.extend_from_slice(hash.as_slice());
+                bytes.extend_from_slice(&expires_at.to_be_bytes());
+                bytes.extend_from_slice(from.as_slice());
+                bytes
+            }
+        )
+    }
+
+    fn decode(bytes: &[u8], now: u64) -> std::io::Result<Self> {
+        if bytes.len() % ENTRY_LEN != 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "truncated settled orders file"))
+        }
+
+        let mut this = Self::default();
+        for entry in bytes.chunks_exact(ENTRY_LEN) {
+            let hash = B256::from_slice(&entry[..32]);
+            let expires_at = u64::from_be_bytes(entry[32..40].try_into().unwrap());
+            let from = Address::from_slice(&entry[40..]);
+            if expires_at < now {
+                continue
+            }
+
+            if from == FILLED {
+                this.filled.insert(hash, expires_at);
+            } else {
+                this.cancelled.insert(hash, (from, expires_at));
+            }
+        }
+
+        Ok(this)
+    }
+
+    pub fn len(&self) -> usize {
+        self.filled.len() + self.cancelled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Stores the settled orders off the async runtime. Writes that pile up only
+/// store the latest snapshot.
+#[derive(Debug, Clone)]
+pub struct SettledOrdersWriter {
+    path:    Arc<PathBuf>,
+    pending: Arc<Mutex<Option<SettledOrders>>>,
+    /// held for the whole write so that two of them never share the tmp file
+    file:    Arc<Mutex<()>>
+}
+
+impl SettledOrdersWriter {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path: Arc::new(path), pending: Default::default(), file: Default::default() }
+    }
+
+    pub fn store(&self, settled: SettledOrders) -> JoinHandle<()> {
+        *self.pending.lock() = Some(settled);
+
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _file = this.file.lock();
+            // an earlier write already took it
+            let Some(settled) = this.pending.lock().take() else { return };
+            if let Err(error) = settled.store(&this.path) {
+                tracing::warn!(path = ?this.path, %error, "failed to persist settled orders");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_drops_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settled").join("orders.bin");
+        assert!(SettledOrders::load(&path, 0).unwrap().is_empty());
+
+        let (filled, expired, cancelled) = (B256::random(), B256::random(), B256::random());
+        let canceller = Address::random();
+        let orders = SettledOrders {
+            filled:    HashMap::from([(filled, 200), (expired, 50)]),
+            cancelled: HashMap::from([(cancelled, (canceller, 100))])
+        };
+        orders.store(&path).unwrap();
+
+        let loaded = SettledOrders::load(&path, 100).unwrap();
+        assert_eq!(loaded.filled, HashMap::from([(filled, 200)]));
+        assert_eq!(loaded.cancelled, HashMap::from([(cancelled, (canceller, 100))]));
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        assert!(SettledOrders::decode(&[0; ENTRY_LEN + 1], 0).is_err());
+    }
+}
@@ -7,7 +7,7 @@ use std::{
 use alloy::primitives::{Address, B256};
 use angstrom_types::{orders::OrderOrigin, sol_bindings::grouped_orders::AllOrders};
 use futures_util::{stream::FuturesUnordered, Future, FutureExt, Stream, StreamExt};
-use tracing::info;
+use tracing::{info, Instrument};
 use validation::order::{OrderValidationResults, OrderValidatorHandle};
 
 type ValidationFuture = Pin<Box<dyn Future<Output = OrderValidationResults> + Send + Sync>>;
@@ -77,7 +77,7 @@ where
                 >)
         });
 
-        tracing::info!("clearing for block");
+        tracing::info!(block_number, "clearing for new block");
         *self = Self::ClearingForNewBlock {
             validator: validator.clone(),
             waiting_for_new_block: VecDeque::default(),
@@ -94,13 +94,20 @@ where
         orders: Vec<B256>,
         changed_addresses: Vec<Address>
     ) {
-        tracing::info!("notify validation on changes");
         let Self::WaitingForStorageCleanup { validator, waiting_for_new_block } = self else {
-            tracing::error!("should not happen");
+            tracing::error!(
+                block_number,
+                "notified of changes while not waiting for storage cleanup"
+            );
             return
         };
         let validator_clone = validator.clone();
-        tracing::info!("informing validation that we got a new block");
+        tracing::info!(
+            block_number,
+            completed_orders = orders.len(),
+            changed_addresses = changed_addresses.len(),
+            "informing validation of new block"
+        );
         let fut = Box::pin(async move {
             validator_clone
                 .new_block(block_number, orders, changed_addresses)
@@ -118,8 +125,14 @@ where
         match self {
             Self::RegularProcessing { remaining_futures, validator } => {
                 let val = validator.clone();
-                remaining_futures
-                    .push(Box::pin(async move { val.validate_order(origin, order).await }))
+                let span = tracing::info_span!(
+                    "pool_order",
+                    order_hash = %order.order_hash(),
+                    ?origin
+                );
+                remaining_futures.push(Box::pin(
+                    async move { val.validate_order(origin, order).await }.instrument(span)
+                ))
             }
             Self::WaitingForStorageCleanup { waiting_for_new_block, .. } => {
                 waiting_for_new_block.push_back((origin, order));
@@ -133,6 +146,15 @@ where
         }
     }
 
+    /// amount of orders currently being validated
+    fn pending_orders(&self) -> usize {
+        match self {
+            Self::RegularProcessing { remaining_futures, .. }
+            | Self::ClearingForNewBlock { remaining_futures, .. } => remaining_futures.len(),
+            _ => 0
+        }
+    }
+
     fn is_transitioning(&self) -> bool {
         matches!(self, Self::ClearingForNewBlock { .. } | Self::InformState { .. })
     }
@@ -185,13 +207,14 @@ where
                     return Poll::Pending
                 }
 
-                info!(
-                    "clearing for new block done. triggering clearing and starting to validate \
-                     state for current block"
-                );
                 let completed_orders = std::mem::take(completed_orders);
                 let revalidation_addresses = std::mem::take(revalidation_addresses);
                 let block = *block_number;
+                info!(
+                    block_number = block,
+                    queued_orders = waiting_for_new_block.len(),
+                    "clearing for new block done, triggering storage cleanup"
+                );
 
                 *this = Self::WaitingForStorageCleanup {
                     validator:             validator.clone(),
@@ -212,7 +235,10 @@ where
                     return Poll::Pending
                 };
 
-                tracing::info!("starting regular processing");
+                tracing::info!(
+                    queued_orders = new_state.pending_orders(),
+                    "starting regular processing"
+                );
                 *this = new_state;
                 cx.waker().wake_by_ref();
 
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    block_sync::BlockSyncStatus,
+    primitive::{PeerId, PoolId}
+};
+use consensus::{rounds::KeyRotation, ConsensusTiming};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Operator controls of the node. Only served on the authenticated admin
+/// server, never next to the public namespaces.
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstromAdmin"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstromAdmin"))]
+#[async_trait::async_trait]
+pub trait AdminApi {
+    /// Stops taking new orders from users and peers, or resumes it
+    #[method(name = "pauseOrderIntake")]
+    async fn pause_order_intake(&self, paused: bool) -> RpcResult<()>;
+
+    /// Disconnects the peer and refuses its sessions until it is unbanned
+    #[method(name = "banPeer")]
+    async fn ban_peer(&self, peer_id: PeerId) -> RpcResult<()>;
+
+    #[method(name = "unbanPeer")]
+    async fn unban_peer(&self, peer_id: PeerId) -> RpcResult<()>;
+
+    /// Reloads the pool from the chain, returns the block it was loaded at
+    #[method(name = "resyncPool")]
+    async fn resync_pool(&self, pool_id: PoolId) -> RpcResult<BlockNumber>;
+
+    /// Hands consensus messages over to the key in the file. The handover is
+    /// announced to the other validators right away and the key is signed
+    /// with from the activation block on. The peer id of the node in the
+    /// validator set stays the one of the key it was started with, but that
+    /// key is retired: bundles are sent from the new key from the activation
+    /// block on, it has to be a node of the angstrom contract by then. The key
+    /// file is read by the node, the key itself never goes over the wire
+    #[method(name = "rotateSigningKey")]
+    async fn rotate_signing_key(&self, key_path: PathBuf) -> RpcResult<KeyRotation>;
+
+    /// Where every module is at with the block transition the node is in
+    #[method(name = "blockSyncStatus")]
+    async fn block_sync_status(&self) -> RpcResult<BlockSyncStatus>;
+
+    /// Times the rounds from the next one on. Times left out are taken from
+    /// the defaults, not from the ones in use. Rejected unless they are
+    /// consistent with each other
+    #[method(name = "setConsensusTiming")]
+    async fn set_consensus_timing(&self, timing: ConsensusTiming) -> RpcResult<()>;
+
+    /// Replaces the filter of the logs, in the syntax of `RUST_LOG`, e.g.
+    /// `info,consensus=debug`
+    #[method(name = "setLogFilter")]
+    async fn set_log_filter(&self, filter: String) -> RpcResult<()>;
+}
@@ -0,0 +1,26 @@
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    orders::{ArchivedBook, ArchivedRound},
+    primitive::PoolId
+};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstromArchive"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstromArchive"))]
+#[async_trait::async_trait]
+pub trait ArchiveApi {
+    /// First and last block that has an archived round
+    #[method(name = "blockRange")]
+    async fn block_range(&self) -> RpcResult<Option<(BlockNumber, BlockNumber)>>;
+
+    /// Books and solutions of the round of the block
+    #[method(name = "round")]
+    async fn round(&self, block_number: BlockNumber) -> RpcResult<Option<ArchivedRound>>;
+
+    #[method(name = "book")]
+    async fn book(
+        &self,
+        block_number: BlockNumber,
+        pool_id: PoolId
+    ) -> RpcResult<Option<ArchivedBook>>;
+}
@@ -0,0 +1,15 @@
+use angstrom_types::{orders::TrippedBreaker, primitive::PoolId};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstrom"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstrom"))]
+#[async_trait::async_trait]
+pub trait CircuitBreakerApi {
+    /// Pools that were halted because their clearing price strayed from the AMM
+    #[method(name = "circuitBreakers")]
+    async fn circuit_breakers(&self) -> RpcResult<Vec<TrippedBreaker>>;
+
+    /// Forgets the last trip of the pool, false if it wasn't halted
+    #[method(name = "clearCircuitBreaker")]
+    async fn clear_circuit_breaker(&self, pool_id: PoolId) -> RpcResult<bool>;
+}
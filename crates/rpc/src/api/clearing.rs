@@ -0,0 +1,30 @@
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    orders::{ClearingReport, GasReconciliation},
+    primitive::PoolId
+};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstrom"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstrom"))]
+#[async_trait::async_trait]
+pub trait ClearingApi {
+    /// Clearing reports of every pool that cleared in the block
+    #[method(name = "clearingReports")]
+    async fn clearing_reports(&self, block_number: BlockNumber) -> RpcResult<Vec<ClearingReport>>;
+
+    #[method(name = "clearingReport")]
+    async fn clearing_report(
+        &self,
+        block_number: BlockNumber,
+        pool_id: PoolId
+    ) -> RpcResult<Option<ClearingReport>>;
+
+    /// Gas the orders of the bundle that landed in the block were charged
+    /// against what it actually cost, if it was one of ours
+    #[method(name = "gasReconciliation")]
+    async fn gas_reconciliation(
+        &self,
+        block_number: BlockNumber
+    ) -> RpcResult<Option<GasReconciliation>>;
+}
@@ -1,5 +1,17 @@
+mod admin;
+mod archive;
+mod circuit_breaker;
+mod clearing;
 mod orders;
 mod quoting;
+mod validators;
+mod webhooks;
 
+pub use admin::*;
+pub use archive::*;
+pub use circuit_breaker::*;
+pub use clearing::*;
 pub use orders::*;
 pub use quoting::*;
+pub use validators::*;
+pub use webhooks::*;
@@ -2,9 +2,13 @@ use std::collections::HashSet;
 
 use alloy_primitives::{Address, B256, U256};
 use angstrom_types::{
-    orders::{CancelOrderRequest, OrderLocation, OrderStatus},
+    contract_payloads::tob::{TobSimulation, TobStateOverrides},
+    orders::{
+        CancelAllOrdersRequest, CancelOrderRequest, OrderLocation, OrderStatus, PoolAnalytics,
+        PricePeg
+    },
     primitive::{OrderPoolNewOrderResult, PoolId},
-    sol_bindings::grouped_orders::AllOrders
+    sol_bindings::{grouped_orders::AllOrders, rpc_orders::TopOfBlockOrder}
 };
 use futures::StreamExt;
 use jsonrpsee::{
@@ -29,15 +33,58 @@ pub trait OrderApi {
     #[method(name = "sendOrder")]
     async fn send_order(&self, order: AllOrders) -> RpcResult<OrderPoolNewOrderResult>;
 
+    /// Submit an order that is kept private to this node. It's never gossiped
+    /// and is only included in the rounds this node leads
+    #[method(name = "sendPrivateOrder")]
+    async fn send_private_order(&self, order: AllOrders) -> RpcResult<OrderPoolNewOrderResult>;
+
+    /// Submit a standing order whose limit price follows the AMM mid, see
+    /// [`PricePeg`]. Like private orders, it isn't gossiped
+    #[method(name = "sendPeggedOrder")]
+    async fn send_pegged_order(
+        &self,
+        order: AllOrders,
+        peg: PricePeg
+    ) -> RpcResult<OrderPoolNewOrderResult>;
+
+    /// Submit the tranches of an iceberg order, standing orders of the same
+    /// signer, pair and price. Only the first tranche is gossiped and shown in
+    /// the depth of the pool, the next one once it's filled
+    #[method(name = "sendIcebergOrder")]
+    async fn send_iceberg_order(
+        &self,
+        tranches: Vec<AllOrders>
+    ) -> RpcResult<Vec<OrderPoolNewOrderResult>>;
+
     #[method(name = "pendingOrder")]
     async fn pending_order(&self, from: Address) -> RpcResult<Vec<AllOrders>>;
 
     #[method(name = "cancelOrder")]
     async fn cancel_order(&self, request: CancelOrderRequest) -> RpcResult<bool>;
 
+    /// Cancels every resting order of the signer across all pools, returns the
+    /// hashes of the cancelled orders
+    #[method(name = "cancelAllOrders")]
+    async fn cancel_all_orders(&self, request: CancelAllOrdersRequest) -> RpcResult<Vec<B256>>;
+
     #[method(name = "estimateGas")]
     async fn estimate_gas(&self, order: AllOrders) -> RpcResult<GasEstimateResponse>;
 
+    /// Simulates a top of block order against the state of the node with the
+    /// overrides applied, returning its reward, the price it leaves the pool
+    /// at and whether it would be accepted. The order isn't submitted
+    #[method(name = "simulateTopOfBlock")]
+    async fn simulate_top_of_block(
+        &self,
+        order: TopOfBlockOrder,
+        overrides: Option<TobStateOverrides>
+    ) -> RpcResult<TobSimulation>;
+
+    /// Lowest nonce that isn't used on chain or reserved by a pending order of
+    /// the user, to be used for the next standing order
+    #[method(name = "nextNonce")]
+    async fn next_nonce(&self, user: Address) -> RpcResult<u64>;
+
     #[method(name = "orderStatus")]
     async fn order_status(&self, order_hash: B256) -> RpcResult<Option<OrderStatus>>;
 
@@ -48,6 +95,16 @@ pub trait OrderApi {
         location: OrderLocation
     ) -> RpcResult<Vec<AllOrders>>;
 
+    /// How the orders of the pool are made up: its pending limit orders by
+    /// price and age, how many are partial, its searcher orders and the fills
+    /// of its last rounds. Orders are split into 10 price buckets by default
+    #[method(name = "poolAnalytics")]
+    async fn pool_analytics(
+        &self,
+        pool_id: PoolId,
+        price_buckets: Option<usize>
+    ) -> RpcResult<PoolAnalytics>;
+
     #[subscription(
         name = "subscribeOrders",
         unsubscribe = "unsubscribeOrders",
@@ -0,0 +1,34 @@
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    consensus::{InclusionAudit, InclusionFairness, ValidatorPerformance},
+    primitive::PeerId
+};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstrom"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstrom"))]
+#[async_trait::async_trait]
+pub trait ValidatorsApi {
+    /// Consensus performance of every validator since the node started
+    #[method(name = "validatorPerformance")]
+    async fn validator_performance(&self) -> RpcResult<Vec<ValidatorPerformance>>;
+
+    #[method(name = "validatorPerformanceOf")]
+    async fn validator_performance_of(
+        &self,
+        peer_id: PeerId
+    ) -> RpcResult<Option<ValidatorPerformance>>;
+
+    /// How fairly every leader included the orders we validated in its
+    /// proposals since the node started
+    #[method(name = "inclusionFairness")]
+    async fn inclusion_fairness(&self) -> RpcResult<Vec<InclusionFairness>>;
+
+    #[method(name = "inclusionFairnessOf")]
+    async fn inclusion_fairness_of(&self, leader: PeerId) -> RpcResult<Option<InclusionFairness>>;
+
+    /// The orders the proposal of the block left out, for the last rounds
+    #[method(name = "inclusionAudit")]
+    async fn inclusion_audit(&self, block_number: BlockNumber)
+        -> RpcResult<Option<InclusionAudit>>;
+}
@@ -0,0 +1,19 @@
+use alloy_primitives::B256;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+use crate::webhooks::WebhookRegistration;
+
+#[cfg_attr(not(feature = "client"), rpc(server, namespace = "angstrom"))]
+#[cfg_attr(feature = "client", rpc(server, client, namespace = "angstrom"))]
+#[async_trait::async_trait]
+pub trait WebhookApi {
+    /// Posts signed notifications of the subscribed events to the url, returns
+    /// the id of the webhook
+    #[method(name = "registerWebhook")]
+    async fn register_webhook(&self, registration: WebhookRegistration) -> RpcResult<B256>;
+
+    /// Stops the notifications of the webhook, false if there's no webhook
+    /// with the id and secret
+    #[method(name = "unregisterWebhook")]
+    async fn unregister_webhook(&self, id: B256, secret: String) -> RpcResult<bool>;
+}
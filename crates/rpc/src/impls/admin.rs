@@ -0,0 +1,252 @@
+use std::{marker::PhantomData, net::SocketAddr, path::PathBuf};
+
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    block_sync::BlockSyncStatus,
+    primitive::{PeerId, PoolId}
+};
+use consensus::{rounds::KeyRotation, ConsensusTiming};
+use hyper::{
+    header::{HeaderValue, AUTHORIZATION},
+    Request, Response, StatusCode
+};
+use jsonrpsee::{
+    core::RpcResult,
+    server::{Server, ServerHandle}
+};
+use subtle::ConstantTimeEq;
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+use tower_http::validate_request::{ValidateRequest, ValidateRequestHeaderLayer};
+
+use crate::{
+    api::AdminApiServer,
+    impls::{invalid_params_rpc_err, rpc_err},
+    types::AdminCommand
+};
+
+/// Serves the admin namespace by handing the commands to the node.
+pub struct AdminApi {
+    commands: UnboundedSender<AdminCommand>
+}
+
+impl AdminApi {
+    pub fn new(commands: UnboundedSender<AdminCommand>) -> Self {
+        Self { commands }
+    }
+
+    fn send(&self, command: AdminCommand) -> Result<(), AdminApiError> {
+        self.commands
+            .send(command)
+            .map_err(|_| AdminApiError::NotRunning)
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<Result<T, String>>) -> AdminCommand
+    ) -> Result<T, AdminApiError> {
+        let (tx, rx) = oneshot::channel();
+        self.send(command(tx))?;
+
+        rx.await
+            .map_err(|_| AdminApiError::NotRunning)?
+            .map_err(AdminApiError::Failed)
+    }
+}
+
+#[async_trait::async_trait]
+impl AdminApiServer for AdminApi {
+    async fn pause_order_intake(&self, paused: bool) -> RpcResult<()> {
+        Ok(self.send(AdminCommand::PauseOrderIntake(paused))?)
+    }
+
+    async fn ban_peer(&self, peer_id: PeerId) -> RpcResult<()> {
+        Ok(self.send(AdminCommand::BanPeer(peer_id))?)
+    }
+
+    async fn unban_peer(&self, peer_id: PeerId) -> RpcResult<()> {
+        Ok(self.send(AdminCommand::UnbanPeer(peer_id))?)
+    }
+
+    async fn resync_pool(&self, pool_id: PoolId) -> RpcResult<BlockNumber> {
+        Ok(self
+            .request(|tx| AdminCommand::ResyncPool(pool_id, tx))
+            .await?)
+    }
+
+    async fn rotate_signing_key(&self, key_path: PathBuf) -> RpcResult<KeyRotation> {
+        Ok(self
+            .request(|tx| AdminCommand::RotateSigningKey(key_path, tx))
+            .await?)
+    }
+
+    async fn block_sync_status(&self) -> RpcResult<BlockSyncStatus> {
+        Ok(self.request(AdminCommand::BlockSyncStatus).await?)
+    }
+
+    async fn set_consensus_timing(&self, timing: ConsensusTiming) -> RpcResult<()> {
+        timing
+            .validate()
+            .map_err(|e| AdminApiError::Failed(e.to_string()))?;
+        Ok(self.send(AdminCommand::SetConsensusTiming(timing))?)
+    }
+
+    async fn set_log_filter(&self, filter: String) -> RpcResult<()> {
+        Ok(self
+            .request(|tx| AdminCommand::SetLogFilter(filter, tx))
+            .await?)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminApiError {
+    #[error("the node modules are not running")]
+    NotRunning,
+    #[error("{0}")]
+    Failed(String)
+}
+
+impl From<AdminApiError> for jsonrpsee::types::ErrorObjectOwned {
+    fn from(error: AdminApiError) -> Self {
+        match error {
+            AdminApiError::NotRunning => {
+                rpc_err(jsonrpsee::types::error::INTERNAL_ERROR_CODE, error.to_string(), None)
+            }
+            AdminApiError::Failed(e) => invalid_params_rpc_err(e)
+        }
+    }
+}
+
+/// Admits the requests carrying the bearer token. The token is compared in
+/// constant time so the time a rejection takes doesn't tell how much of the
+/// token was right.
+struct BearerToken<ResBody> {
+    expected: HeaderValue,
+    _body:    PhantomData<fn() -> ResBody>
+}
+
+impl<ResBody> BearerToken<ResBody> {
+    fn new(token: &str) -> Self {
+        let expected = HeaderValue::try_from(format!("Bearer {token}"))
+            .expect("the admin token is a valid header value");
+        Self { expected, _body: PhantomData }
+    }
+}
+
+impl<ResBody> Clone for BearerToken<ResBody> {
+    fn clone(&self) -> Self {
+        Self { expected: self.expected.clone(), _body: PhantomData }
+    }
+}
+
+impl<B, ResBody: Default> ValidateRequest<B> for BearerToken<ResBody> {
+    type ResponseBody = ResBody;
+
+    fn validate(&mut self, request: &mut Request<B>) -> Result<(), Response<ResBody>> {
+        let authorized = request
+            .headers()
+            .get(AUTHORIZATION)
+            .is_some_and(|value| value.as_bytes().ct_eq(self.expected.as_bytes()).into());
+        if authorized {
+            return Ok(())
+        }
+
+        let mut response = Response::new(ResBody::default());
+        *response.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(response)
+    }
+}
+
+/// Serves the admin namespace on its own server. Every request, websocket
+/// upgrades included, has to carry the token as `Authorization: Bearer`.
+///
+/// The server speaks plain http, mTLS is left to a proxy in front of it: bind
+/// the server to a local or otherwise private interface and terminate the
+/// client certificates there.
+pub async fn start_admin_server(
+    addr: SocketAddr,
+    token: &str,
+    api: AdminApi
+) -> std::io::Result<ServerHandle> {
+    let middleware = tower::ServiceBuilder::new()
+        .layer(ValidateRequestHeaderLayer::custom(BearerToken::new(token)));
+    let server = Server::builder()
+        .set_http_middleware(middleware)
+        .build(addr)
+        .await?;
+    tracing::info!(local_addr = ?server.local_addr(), "started admin rpc server");
+
+    Ok(server.start(api.into_rpc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn forwards_commands_to_the_node() {
+        let (tx, mut rx) = unbounded_channel();
+        let api = AdminApi::new(tx);
+
+        let node = tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                if let AdminCommand::ResyncPool(_, tx) = command {
+                    let _ = tx.send(Err("unknown pool".to_string()));
+                }
+            }
+        });
+
+        api.ban_peer(PeerId::random()).await.unwrap();
+        let error = api.resync_pool(PoolId::random()).await.unwrap_err();
+        assert_eq!(error.message(), "unknown pool");
+
+        drop(api);
+        node.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_inconsistent_timing() {
+        let (tx, mut rx) = unbounded_channel();
+        let api = AdminApi::new(tx);
+
+        let late = ConsensusTiming {
+            proposal_deadline: std::time::Duration::from_secs(13),
+            ..Default::default()
+        };
+        assert!(api.set_consensus_timing(late).await.is_err());
+        assert!(rx.try_recv().is_err());
+
+        api.set_consensus_timing(ConsensusTiming::default())
+            .await
+            .unwrap();
+        assert!(matches!(rx.try_recv(), Ok(AdminCommand::SetConsensusTiming(_))));
+    }
+
+    #[test]
+    fn admits_only_the_bearer_token() {
+        let mut validator = BearerToken::<()>::new("secret");
+        let mut request = |authorization: Option<&str>| {
+            let mut request = Request::builder();
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            validator.validate(&mut request.body(()).unwrap())
+        };
+
+        assert!(request(Some("Bearer secret")).is_ok());
+        for authorization in [None, Some("Bearer secre"), Some("Bearer secreT"), Some("secret")] {
+            let response = request(authorization).unwrap_err();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_without_node() {
+        let (tx, rx) = unbounded_channel();
+        drop(rx);
+
+        let api = AdminApi::new(tx);
+        assert!(api.pause_order_intake(true).await.is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::net::SocketAddr;
+
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    orders::{ArchivedBook, ArchivedRound, OrderArchive},
+    primitive::PoolId
+};
+use jsonrpsee::{
+    core::RpcResult,
+    server::{Server, ServerHandle},
+    types::error::INTERNAL_ERROR_CODE,
+    Methods
+};
+
+use crate::{api::ArchiveApiServer, impls::rpc_err};
+
+/// Serves the archived rounds from disk, so that analytical queries don't
+/// have to hit the validators.
+pub struct ArchiveApi {
+    archive: OrderArchive
+}
+
+impl ArchiveApi {
+    pub fn new(archive: OrderArchive) -> Self {
+        Self { archive }
+    }
+
+    async fn read<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&OrderArchive) -> eyre::Result<T> + Send + 'static
+    ) -> RpcResult<T> {
+        let archive = self.archive.clone();
+        tokio::task::spawn_blocking(move || f(&archive))
+            .await
+            .map_err(|e| rpc_err(INTERNAL_ERROR_CODE, e.to_string(), None))?
+            .map_err(|e| rpc_err(INTERNAL_ERROR_CODE, e.to_string(), None))
+    }
+}
+
+#[async_trait::async_trait]
+impl ArchiveApiServer for ArchiveApi {
+    async fn block_range(&self) -> RpcResult<Option<(BlockNumber, BlockNumber)>> {
+        self.read(|archive| archive.range()).await
+    }
+
+    async fn round(&self, block_number: BlockNumber) -> RpcResult<Option<ArchivedRound>> {
+        self.read(move |archive| archive.round(block_number)).await
+    }
+
+    async fn book(
+        &self,
+        block_number: BlockNumber,
+        pool_id: PoolId
+    ) -> RpcResult<Option<ArchivedBook>> {
+        self.read(move |archive| {
+            Ok(archive
+                .round(block_number)?
+                .map(|round| round.book(pool_id)))
+        })
+        .await
+    }
+}
+
+/// Starts the read only server of an archive node, `methods` are the archive
+/// and whatever else it serves from disk.
+pub async fn start_archive_server(
+    addr: SocketAddr,
+    methods: impl Into<Methods>
+) -> std::io::Result<ServerHandle> {
+    let server = Server::builder().build(addr).await?;
+    tracing::info!(local_addr = ?server.local_addr(), "started archive rpc server");
+
+    Ok(server.start(methods))
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::orders::PoolSolution;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_the_books_of_archived_rounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = OrderArchive::new(dir.path().to_path_buf());
+        let pool_id = PoolId::random();
+        archive
+            .insert(&ArchivedRound {
+                block_number: 5,
+                limit:        vec![],
+                searcher:     vec![],
+                solutions:    vec![PoolSolution { id: pool_id, ..Default::default() }]
+            })
+            .unwrap();
+        let api = ArchiveApi::new(archive);
+
+        assert_eq!(api.block_range().await.unwrap(), Some((5, 5)));
+        assert!(api.round(4).await.unwrap().is_none());
+        let book = api.book(5, pool_id).await.unwrap().unwrap();
+        assert_eq!(book.solution.map(|solution| solution.id), Some(pool_id));
+    }
+}
@@ -0,0 +1,30 @@
+use angstrom_types::{
+    orders::{CircuitBreaker, TrippedBreaker},
+    primitive::PoolId
+};
+use jsonrpsee::core::RpcResult;
+
+use crate::api::CircuitBreakerApiServer;
+
+/// Lets operators inspect the pools that were halted and clear the trips they
+/// looked into.
+pub struct CircuitBreakerApi {
+    breaker: CircuitBreaker
+}
+
+impl CircuitBreakerApi {
+    pub fn new(breaker: CircuitBreaker) -> Self {
+        Self { breaker }
+    }
+}
+
+#[async_trait::async_trait]
+impl CircuitBreakerApiServer for CircuitBreakerApi {
+    async fn circuit_breakers(&self) -> RpcResult<Vec<TrippedBreaker>> {
+        Ok(self.breaker.tripped())
+    }
+
+    async fn clear_circuit_breaker(&self, pool_id: PoolId) -> RpcResult<bool> {
+        Ok(self.breaker.clear(&pool_id))
+    }
+}
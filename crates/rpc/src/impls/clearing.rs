@@ -0,0 +1,49 @@
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    orders::{ClearingReport, ClearingReportStore, GasReconciliation, GasReconciliationStore},
+    primitive::PoolId
+};
+use jsonrpsee::core::RpcResult;
+
+use crate::api::ClearingApiServer;
+
+/// Serves the clearing reports of the recent blocks, so that third parties can
+/// verify the auctions.
+pub struct ClearingApi {
+    reports:         ClearingReportStore,
+    reconciliations: GasReconciliationStore
+}
+
+impl ClearingApi {
+    pub fn new(reports: ClearingReportStore) -> Self {
+        Self { reports, reconciliations: GasReconciliationStore::default() }
+    }
+
+    /// Also serves the gas reconciliations of the bundles we landed.
+    pub fn with_gas_reconciliations(mut self, reconciliations: GasReconciliationStore) -> Self {
+        self.reconciliations = reconciliations;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ClearingApiServer for ClearingApi {
+    async fn clearing_reports(&self, block_number: BlockNumber) -> RpcResult<Vec<ClearingReport>> {
+        Ok(self.reports.block(block_number))
+    }
+
+    async fn clearing_report(
+        &self,
+        block_number: BlockNumber,
+        pool_id: PoolId
+    ) -> RpcResult<Option<ClearingReport>> {
+        Ok(self.reports.pool(block_number, pool_id))
+    }
+
+    async fn gas_reconciliation(
+        &self,
+        block_number: BlockNumber
+    ) -> RpcResult<Option<GasReconciliation>> {
+        Ok(self.reconciliations.block(block_number))
+    }
+}
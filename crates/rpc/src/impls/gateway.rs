@@ -0,0 +1,334 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::{ready, Ready},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant
+};
+
+use angstrom_metrics::RpcGatewayMetricsWrapper;
+use futures::future::Either;
+use jsonrpsee::{
+    server::{
+        middleware::rpc::{RpcServiceBuilder, RpcServiceT},
+        HttpRequest, Server, ServerHandle
+    },
+    types::{ErrorObjectOwned, Request},
+    MethodResponse, Methods
+};
+
+use crate::{
+    impls::rpc_err,
+    types::{ApiKeyConfig, GatewayConfig, GatewayConfigError}
+};
+
+/// Header the api key is sent in, on every http request or the websocket
+/// upgrade.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+const UNAUTHORIZED_CODE: i32 = -32001;
+/// EIP-1474 limit exceeded
+pub(crate) const LIMIT_EXCEEDED_CODE: i32 = -32005;
+
+/// Admits the calls to the public rpc. Every call needs a known api key, has
+/// to be to a method the key may call and counts against the quota of the key.
+/// The calls of a batch are admitted one by one.
+#[derive(Clone)]
+pub struct Gateway {
+    keys:    Arc<HashMap<String, KeyState>>,
+    metrics: RpcGatewayMetricsWrapper
+}
+
+struct KeyState {
+    name:    String,
+    methods: HashSet<String>,
+    quota:   Mutex<TokenBucket>
+}
+
+impl Gateway {
+    pub fn new(config: GatewayConfig) -> Result<Self, GatewayConfigError> {
+        config.validate()?;
+
+        let now = Instant::now();
+        let keys = config
+            .keys
+            .into_iter()
+            .map(|api_key| {
+                let quota = TokenBucket::new(api_key.requests_per_second, api_key.burst(), now);
+                let ApiKeyConfig { name, key, methods, .. } = api_key;
+                let methods = methods
+                    .unwrap_or_else(|| config.methods.clone())
+                    .into_iter()
+                    .collect();
+
+                (key, KeyState { name, methods, quota: Mutex::new(quota) })
+            })
+            .collect();
+
+        Ok(Self { keys: Arc::new(keys), metrics: RpcGatewayMetricsWrapper::new() })
+    }
+
+    fn admit(&self, key: Option<&str>, method: &str) -> Result<(), GatewayError> {
+        self.admit_at(key, method, Instant::now())
+    }
+
+    fn admit_at(&self, key: Option<&str>, method: &str, now: Instant) -> Result<(), GatewayError> {
+        let key = key.ok_or(GatewayError::MissingKey)?;
+        let Some(state) = self.keys.get(key) else {
+            tracing::debug!(method, "call with an unknown api key");
+            return Err(GatewayError::UnknownKey)
+        };
+
+        if !state.methods.contains(method) {
+            // not labeled with the method, anyone can make those up
+            self.metrics.incr_calls(&state.name, "other", "not_allowed");
+            return Err(GatewayError::MethodNotAllowed(method.to_string()))
+        }
+        if !state.quota.lock().unwrap().try_take(now) {
+            self.metrics.incr_calls(&state.name, method, "rate_limited");
+            return Err(GatewayError::RateLimited)
+        }
+
+        self.metrics.incr_calls(&state.name, method, "served");
+        Ok(())
+    }
+}
+
+/// Refills at `per_second` up to `capacity`, every call takes one token.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity:   f64,
+    per_second: f64,
+    tokens:     f64,
+    updated:    Instant
+}
+
+impl TokenBucket {
+    fn new(per_second: u32, burst: u32, now: Instant) -> Self {
+        let capacity = burst as f64;
+        Self { capacity, per_second: per_second as f64, tokens: capacity, updated: now }
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = self.updated.max(now);
+
+        if self.tokens < 1.0 {
+            return false
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GatewayError {
+    #[error("missing api key, it's sent in the {API_KEY_HEADER} header")]
+    MissingKey,
+    #[error("unknown api key")]
+    UnknownKey,
+    #[error("method {0} is not available")]
+    MethodNotAllowed(String),
+    #[error("rate limit of the api key exceeded")]
+    RateLimited
+}
+
+impl From<GatewayError> for ErrorObjectOwned {
+    fn from(error: GatewayError) -> Self {
+        let code = match error {
+            GatewayError::MissingKey | GatewayError::UnknownKey => UNAUTHORIZED_CODE,
+            GatewayError::MethodNotAllowed(_) => jsonrpsee::types::error::METHOD_NOT_FOUND_CODE,
+            GatewayError::RateLimited => LIMIT_EXCEEDED_CODE
+        };
+        rpc_err(code, error.to_string(), None)
+    }
+}
+
+/// Key the http request carried, handed from the http to the rpc middleware.
+#[derive(Debug, Clone)]
+struct RequestApiKey(Option<String>);
+
+fn extract_api_key(mut request: HttpRequest) -> HttpRequest {
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    request.extensions_mut().insert(RequestApiKey(key));
+    request
+}
+
+/// Rpc middleware that only passes the calls the [`Gateway`] admits.
+#[derive(Clone)]
+pub struct GatewayService<S> {
+    service: S,
+    gateway: Gateway
+}
+
+impl<'a, S> RpcServiceT<'a> for GatewayService<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static
+{
+    type Future = Either<S::Future, Ready<MethodResponse>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let key = request
+            .extensions()
+            .get::<RequestApiKey>()
+            .and_then(|key| key.0.as_deref());
+
+        match self.gateway.admit(key, request.method_name()) {
+            Ok(()) => Either::Left(self.service.call(request)),
+            Err(error) => Either::Right(ready(MethodResponse::error(request.id, error)))
+        }
+    }
+}
+
+/// Serves `methods` to the public behind the [`Gateway`]. Only the modules
+/// that are meant to be public should be handed to it, the allowlists of the
+/// keys are a second line of defence.
+pub async fn start_gateway_server(
+    addr: SocketAddr,
+    gateway: Gateway,
+    methods: impl Into<Methods>
+) -> std::io::Result<ServerHandle> {
+    let http_middleware = tower::ServiceBuilder::new().map_request(extract_api_key);
+    let rpc_middleware = RpcServiceBuilder::new()
+        .layer_fn(move |service| GatewayService { service, gateway: gateway.clone() });
+
+    let server = Server::builder()
+        .set_http_middleware(http_middleware)
+        .set_rpc_middleware(rpc_middleware)
+        .build(addr)
+        .await?;
+    tracing::info!(local_addr = ?server.local_addr(), "started public rpc gateway");
+
+    Ok(server.start(methods))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn gateway() -> Gateway {
+        Gateway::new(GatewayConfig {
+            methods: vec!["angstrom_sendOrder".to_string(), "angstrom_orderStatus".to_string()],
+            keys:    vec![
+                ApiKeyConfig {
+                    name:                "market-maker".to_string(),
+                    key:                 "mm-secret".to_string(),
+                    requests_per_second: 2,
+                    burst:               Some(4),
+                    methods:             None
+                },
+                ApiKeyConfig {
+                    name:                "explorer".to_string(),
+                    key:                 "explorer-secret".to_string(),
+                    requests_per_second: 10,
+                    burst:               None,
+                    methods:             Some(vec!["angstrom_orderStatus".to_string()])
+                },
+            ]
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn only_known_keys_are_admitted() {
+        let gateway = gateway();
+        let now = Instant::now();
+        assert_eq!(
+            gateway.admit_at(None, "angstrom_sendOrder", now),
+            Err(GatewayError::MissingKey)
+        );
+        assert_eq!(
+            gateway.admit_at(Some("guess"), "angstrom_sendOrder", now),
+            Err(GatewayError::UnknownKey)
+        );
+        assert_eq!(gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", now), Ok(()));
+    }
+
+    #[test]
+    fn keys_only_call_their_methods() {
+        let gateway = gateway();
+        let now = Instant::now();
+        assert_eq!(
+            gateway.admit_at(Some("mm-secret"), "angstromAdmin_banPeer", now),
+            Err(GatewayError::MethodNotAllowed("angstromAdmin_banPeer".to_string()))
+        );
+        // the key's own list replaces the gateway's
+        assert!(gateway
+            .admit_at(Some("explorer-secret"), "angstrom_sendOrder", now)
+            .is_err());
+        assert_eq!(gateway.admit_at(Some("explorer-secret"), "angstrom_orderStatus", now), Ok(()));
+    }
+
+    #[test]
+    fn quota_refills_over_time() {
+        let gateway = gateway();
+        let start = Instant::now();
+        for _ in 0..4 {
+            assert_eq!(gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", start), Ok(()));
+        }
+        assert_eq!(
+            gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", start),
+            Err(GatewayError::RateLimited)
+        );
+        // quotas are per key
+        assert_eq!(
+            gateway.admit_at(Some("explorer-secret"), "angstrom_orderStatus", start),
+            Ok(())
+        );
+
+        // 2 per second
+        let later = start + Duration::from_millis(500);
+        assert_eq!(gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", later), Ok(()));
+        assert_eq!(
+            gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", later),
+            Err(GatewayError::RateLimited)
+        );
+
+        // never refills past the burst
+        let idle = start + Duration::from_secs(60);
+        for _ in 0..4 {
+            assert_eq!(gateway.admit_at(Some("mm-secret"), "angstrom_sendOrder", idle), Ok(()));
+        }
+        assert!(gateway
+            .admit_at(Some("mm-secret"), "angstrom_sendOrder", idle)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_ambiguous_configs() {
+        let mut config = GatewayConfig { methods: vec![], keys: gateway_keys() };
+        config.keys[1].key = config.keys[0].key.clone();
+        assert_eq!(
+            Gateway::new(config).err(),
+            Some(GatewayConfigError::DuplicateKey("explorer".to_string()))
+        );
+
+        let mut config = GatewayConfig { methods: vec![], keys: gateway_keys() };
+        config.keys[0].requests_per_second = 0;
+        config.keys[0].burst = None;
+        assert_eq!(
+            Gateway::new(config).err(),
+            Some(GatewayConfigError::ZeroQuota("market-maker".to_string()))
+        );
+    }
+
+    fn gateway_keys() -> Vec<ApiKeyConfig> {
+        ["market-maker", "explorer"]
+            .into_iter()
+            .map(|name| ApiKeyConfig {
+                name:                name.to_string(),
+                key:                 format!("{name}-secret"),
+                requests_per_second: 1,
+                burst:               None,
+                methods:             None
+            })
+            .collect()
+    }
+}
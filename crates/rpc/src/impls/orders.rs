@@ -2,18 +2,31 @@ use std::collections::HashSet;
 
 use alloy_primitives::{Address, B256};
 use angstrom_types::{
-    orders::{CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus},
+    contract_payloads::tob::{TobSimulation, TobStateOverrides},
+    orders::{
+        validate_iceberg, CancelAllOrdersRequest, CancelOrderRequest, IcebergError, OrderLocation,
+        OrderOrigin, OrderStatus, PoolAnalytics, PricePeg, PricePegError, DEFAULT_PRICE_BUCKETS,
+        MAX_PRICE_BUCKETS
+    },
     primitive::{OrderPoolNewOrderResult, PoolId},
-    sol_bindings::grouped_orders::AllOrders
+    sol_bindings::{
+        ext::{RawPoolOrder, RespendAvoidanceMethod},
+        grouped_orders::AllOrders,
+        rpc_orders::TopOfBlockOrder
+    }
 };
 use futures::StreamExt;
 use jsonrpsee::{core::RpcResult, PendingSubscriptionSink, SubscriptionMessage};
 use order_pool::{OrderPoolHandle, PoolManagerUpdate};
 use reth_tasks::TaskSpawner;
-use validation::order::OrderValidatorHandle;
+use validation::{
+    order::{state::account::NonceCollision, OrderValidatorHandle},
+    queue::ValidationBackpressure
+};
 
 use crate::{
     api::{GasEstimateResponse, OrderApiServer},
+    impls::gateway::LIMIT_EXCEEDED_CODE,
     types::{OrderSubscriptionFilter, OrderSubscriptionKind, OrderSubscriptionResult},
     OrderApiError::GasEstimationError
 };
@@ -30,6 +43,51 @@ impl<OrderPool, Spawner, Validator> OrderApi<OrderPool, Spawner, Validator> {
     }
 }
 
+impl<OrderPool, Spawner, Validator> OrderApi<OrderPool, Spawner, Validator>
+where
+    OrderPool: OrderPoolHandle,
+    Validator: OrderValidatorHandle
+{
+    async fn submit_order(
+        &self,
+        origin: OrderOrigin,
+        order: AllOrders,
+        peg: Option<PricePeg>
+    ) -> RpcResult<OrderPoolNewOrderResult> {
+        if let Some(peg) = &peg {
+            peg.validate(&order).map_err(OrderApiError::InvalidPeg)?;
+        }
+        self.admit(&order).await?;
+
+        match peg {
+            Some(peg) => Ok(self.pool.new_pegged_order(order, peg).await),
+            None => Ok(self.pool.new_order(origin, order).await)
+        }
+    }
+
+    /// Checks the order can be taken in before it's sent to the pool.
+    async fn admit(&self, order: &AllOrders) -> RpcResult<()> {
+        // a full queue would only grow the latency of every order behind it
+        self.validator
+            .backpressure()
+            .map_err(OrderApiError::Backpressure)?;
+
+        // reject nonce collisions before the order hits the pool so the user gets
+        // a error that says which order holds the nonce. Orders that would
+        // replace the pending one are left to the pool
+        if let AllOrders::Standing(standing) = order {
+            if let RespendAvoidanceMethod::Nonce(nonce) = standing.respend_avoidance_strategy() {
+                self.validator
+                    .check_nonce(standing.from(), nonce, standing.order_hash())
+                    .await
+                    .map_err(OrderApiError::NonceCollision)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
 impl<OrderPool, Spawner, Validator> OrderApiServer for OrderApi<OrderPool, Spawner, Validator>
 where
@@ -38,7 +96,32 @@ where
     Validator: OrderValidatorHandle
 {
     async fn send_order(&self, order: AllOrders) -> RpcResult<OrderPoolNewOrderResult> {
-        Ok(self.pool.new_order(OrderOrigin::External, order).await)
+        self.submit_order(OrderOrigin::External, order, None).await
+    }
+
+    async fn send_private_order(&self, order: AllOrders) -> RpcResult<OrderPoolNewOrderResult> {
+        self.submit_order(OrderOrigin::Private, order, None).await
+    }
+
+    async fn send_pegged_order(
+        &self,
+        order: AllOrders,
+        peg: PricePeg
+    ) -> RpcResult<OrderPoolNewOrderResult> {
+        self.submit_order(OrderOrigin::External, order, Some(peg))
+            .await
+    }
+
+    async fn send_iceberg_order(
+        &self,
+        tranches: Vec<AllOrders>
+    ) -> RpcResult<Vec<OrderPoolNewOrderResult>> {
+        validate_iceberg(&tranches).map_err(OrderApiError::InvalidIceberg)?;
+        for tranche in &tranches {
+            self.admit(tranche).await?;
+        }
+
+        Ok(self.pool.new_iceberg_order(tranches).await)
     }
 
     async fn pending_order(&self, from: Address) -> RpcResult<Vec<AllOrders>> {
@@ -49,6 +132,14 @@ where
         Ok(self.pool.cancel_order(request).await)
     }
 
+    async fn cancel_all_orders(&self, request: CancelAllOrdersRequest) -> RpcResult<Vec<B256>> {
+        Ok(self
+            .pool
+            .cancel_all_orders(request)
+            .await
+            .ok_or(OrderApiError::InvalidSignature)?)
+    }
+
     async fn estimate_gas(&self, order: AllOrders) -> RpcResult<GasEstimateResponse> {
         let (gas_limit, gas) = self
             .validator
@@ -58,6 +149,25 @@ where
         Ok(GasEstimateResponse { gas, gas_units: gas_limit })
     }
 
+    async fn simulate_top_of_block(
+        &self,
+        order: TopOfBlockOrder,
+        overrides: Option<TobStateOverrides>
+    ) -> RpcResult<TobSimulation> {
+        Ok(self
+            .validator
+            .simulate_tob(order, overrides.unwrap_or_default())
+            .await)
+    }
+
+    async fn next_nonce(&self, user: Address) -> RpcResult<u64> {
+        Ok(self
+            .validator
+            .next_nonce(user)
+            .await
+            .ok_or(OrderApiError::NoFreeNonce)?)
+    }
+
     async fn order_status(&self, order_hash: B256) -> RpcResult<Option<OrderStatus>> {
         Ok(self.pool.fetch_order_status(order_hash).await)
     }
@@ -70,6 +180,17 @@ where
         Ok(self.pool.fetch_orders_from_pool(pool_id, location).await)
     }
 
+    async fn pool_analytics(
+        &self,
+        pool_id: PoolId,
+        price_buckets: Option<usize>
+    ) -> RpcResult<PoolAnalytics> {
+        let price_buckets = price_buckets
+            .unwrap_or(DEFAULT_PRICE_BUCKETS)
+            .min(MAX_PRICE_BUCKETS);
+        Ok(self.pool.fetch_pool_analytics(pool_id, price_buckets).await)
+    }
+
     async fn subscribe_orders(
         &self,
         pending: PendingSubscriptionSink,
@@ -114,7 +235,17 @@ pub enum OrderApiError {
     #[error("failed to recover signer from signature")]
     SignatureRecoveryError,
     #[error("failed to estimate gas: {0}")]
-    GasEstimationError(String)
+    GasEstimationError(String),
+    #[error("{0}")]
+    NonceCollision(NonceCollision),
+    #[error("no free nonce found for user")]
+    NoFreeNonce,
+    #[error("{0}")]
+    Backpressure(ValidationBackpressure),
+    #[error("invalid peg: {0}")]
+    InvalidPeg(PricePegError),
+    #[error("invalid iceberg order: {0}")]
+    InvalidIceberg(IcebergError)
 }
 
 impl From<OrderApiError> for jsonrpsee::types::ErrorObjectOwned {
@@ -122,7 +253,20 @@ impl From<OrderApiError> for jsonrpsee::types::ErrorObjectOwned {
         match error {
             OrderApiError::InvalidSignature => invalid_params_rpc_err(error.to_string()),
             OrderApiError::SignatureRecoveryError => invalid_params_rpc_err(error.to_string()),
-            OrderApiError::GasEstimationError(e) => invalid_params_rpc_err(e)
+            OrderApiError::GasEstimationError(e) => invalid_params_rpc_err(e),
+            OrderApiError::NonceCollision(collision) => jsonrpsee::types::ErrorObject::owned(
+                jsonrpsee::types::error::INVALID_PARAMS_CODE,
+                collision.to_string(),
+                Some(collision)
+            ),
+            OrderApiError::NoFreeNonce => invalid_params_rpc_err(error.to_string()),
+            OrderApiError::Backpressure(backpressure) => jsonrpsee::types::ErrorObject::owned(
+                LIMIT_EXCEEDED_CODE,
+                backpressure.to_string(),
+                Some(backpressure)
+            ),
+            OrderApiError::InvalidPeg(_) => invalid_params_rpc_err(error.to_string()),
+            OrderApiError::InvalidIceberg(_) => invalid_params_rpc_err(error.to_string())
         }
     }
 }
@@ -161,8 +305,10 @@ impl OrderFilterMatching for PoolManagerUpdate {
         filter: &HashSet<OrderSubscriptionFilter>
     ) -> Option<OrderSubscriptionResult> {
         match self {
+            // private orders are only revealed once they are filled
             PoolManagerUpdate::NewOrder(order)
-                if kind.contains(&OrderSubscriptionKind::NewOrders)
+                if !order.is_private
+                    && kind.contains(&OrderSubscriptionKind::NewOrders)
                     && (filter.contains(&OrderSubscriptionFilter::ByPair(order.pool_id))
                         || filter.contains(&OrderSubscriptionFilter::ByAddress(order.from()))
                         || filter.contains(&OrderSubscriptionFilter::None)) =>
@@ -202,18 +348,26 @@ impl OrderFilterMatching for PoolManagerUpdate {
 mod tests {
     use std::{future, future::Future};
 
+    use alloy::signers::SignerSync;
     use alloy_primitives::{Address, B256, U256};
     use angstrom_network::pool_manager::OrderCommand;
     use angstrom_types::{
-        orders::{OrderOrigin, OrderStatus},
-        sol_bindings::grouped_orders::{AllOrders, FlashVariants, StandingVariants}
+        orders::{OrderOrigin, OrderStatus, MAX_PEG_OFFSET_BPS},
+        primitive::AngstromSigner,
+        sol_bindings::{
+            grouped_orders::{AllOrders, FlashVariants, StandingVariants},
+            rpc_orders::PartialStandingOrder
+        }
     };
     use futures::FutureExt;
     use order_pool::PoolManagerUpdate;
     use reth_tasks::TokioTaskExecutor;
     use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
     use tokio_stream::wrappers::BroadcastStream;
-    use validation::order::{GasEstimationFuture, ValidationFuture};
+    use validation::order::{
+        GasEstimationFuture, NextNonceFuture, NonceCheckFuture, OrderValidationResults,
+        TobSimulationFuture, ValidationFuture
+    };
 
     use super::*;
 
@@ -259,6 +413,83 @@ mod tests {
             .is_valid());
     }
 
+    #[tokio::test]
+    async fn sends_private_orders_as_private() {
+        let (mut handle, api) = setup_order_api();
+        assert!(api
+            .send_private_order(create_standing_order())
+            .await
+            .expect("to not throw error")
+            .is_valid());
+
+        assert!(matches!(
+            handle._from_api.try_recv(),
+            Ok(OrderCommand::NewOrder(OrderOrigin::Private, ..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn checks_the_peg_of_pegged_orders() {
+        let (mut handle, api) = setup_order_api();
+        let signer = AngstromSigner::random();
+        let mut order = PartialStandingOrder::default();
+        order.meta.from = signer.address();
+        let order = AllOrders::Standing(StandingVariants::Partial(order));
+        let order_hash = order.order_hash();
+        let sign = |offset_bps| PricePeg {
+            order_hash,
+            offset_bps,
+            signature: signer
+                .sign_hash_sync(&PricePeg::signing_payload(order_hash, offset_bps))
+                .unwrap()
+        };
+
+        assert!(api
+            .send_pegged_order(order.clone(), sign(MAX_PEG_OFFSET_BPS + 1))
+            .await
+            .is_err());
+        assert!(api
+            .send_pegged_order(create_flash_order(), sign(10))
+            .await
+            .is_err());
+        assert!(handle._from_api.try_recv().is_err());
+
+        assert!(api
+            .send_pegged_order(order, sign(10))
+            .await
+            .expect("to not throw error")
+            .is_valid());
+        assert!(matches!(handle._from_api.try_recv(), Ok(OrderCommand::NewPeggedOrder(..))));
+    }
+
+    #[tokio::test]
+    async fn checks_the_tranches_of_iceberg_orders() {
+        let (mut handle, api) = setup_order_api();
+        let tranche = |nonce| {
+            AllOrders::Standing(StandingVariants::Partial(PartialStandingOrder {
+                nonce,
+                ..Default::default()
+            }))
+        };
+
+        assert!(api.send_iceberg_order(vec![tranche(1)]).await.is_err());
+        assert!(api
+            .send_iceberg_order(vec![tranche(1), create_flash_order()])
+            .await
+            .is_err());
+        assert!(handle._from_api.try_recv().is_err());
+
+        let results = api
+            .send_iceberg_order(vec![tranche(1), tranche(2)])
+            .await
+            .expect("to not throw error");
+        assert!(results.len() == 2 && results.iter().all(|result| result.is_valid()));
+        assert!(matches!(
+            handle._from_api.try_recv(),
+            Ok(OrderCommand::NewIcebergOrder(tranches, _)) if tranches.len() == 2
+        ));
+    }
+
     fn setup_order_api(
     ) -> (OrderApiTestHandle, OrderApi<MockOrderPoolHandle, TokioTaskExecutor, MockValidator>) {
         let (to_pool, pool_rx) = unbounded_channel();
@@ -306,6 +537,35 @@ mod tests {
             future::ready(OrderPoolNewOrderResult::Valid)
         }
 
+        fn new_pegged_order(
+            &self,
+            order: AllOrders,
+            peg: PricePeg
+        ) -> impl Future<Output = OrderPoolNewOrderResult> + Send {
+            let (tx, _) = tokio::sync::oneshot::channel();
+            let _ = self
+                .sender
+                .send(OrderCommand::NewPeggedOrder(order, peg, tx))
+                .is_ok();
+            future::ready(OrderPoolNewOrderResult::Valid)
+        }
+
+        fn new_iceberg_order(
+            &self,
+            tranches: Vec<AllOrders>
+        ) -> impl Future<Output = Vec<OrderPoolNewOrderResult>> + Send {
+            let results = vec![OrderPoolNewOrderResult::Valid; tranches.len()];
+            let txs = tranches
+                .iter()
+                .map(|_| tokio::sync::oneshot::channel().0)
+                .collect();
+            let _ = self
+                .sender
+                .send(OrderCommand::NewIcebergOrder(tranches, txs))
+                .is_ok();
+            future::ready(results)
+        }
+
         fn subscribe_orders(&self) -> BroadcastStream<PoolManagerUpdate> {
             unimplemented!("Not needed for this test")
         }
@@ -316,6 +576,18 @@ mod tests {
             future::ready(true)
         }
 
+        fn cancel_all_orders(
+            &self,
+            req: CancelAllOrdersRequest
+        ) -> impl Future<Output = Option<Vec<B256>>> + Send {
+            let (tx, _) = tokio::sync::oneshot::channel();
+            let _ = self
+                .sender
+                .send(OrderCommand::CancelAllOrders(req, tx))
+                .is_ok();
+            future::ready(Some(vec![]))
+        }
+
         fn pending_orders(&self, address: Address) -> impl Future<Output = Vec<AllOrders>> + Send {
             let (tx, rx) = tokio::sync::oneshot::channel();
             let _ = self
@@ -328,6 +600,14 @@ mod tests {
         fn fetch_order_status(&self, _: B256) -> impl Future<Output = Option<OrderStatus>> + Send {
             future::ready(None)
         }
+
+        fn fetch_pool_analytics(
+            &self,
+            pool_id: PoolId,
+            _: usize
+        ) -> impl Future<Output = PoolAnalytics> + Send {
+            future::ready(PoolAnalytics { pool_id, ..Default::default() })
+        }
     }
 
     #[derive(Debug, Clone)]
@@ -352,5 +632,25 @@ mod tests {
         fn estimate_gas(&self, _order: AllOrders) -> GasEstimationFuture {
             Box::pin(future::ready(Ok((21_000u64, U256::from(250_000u64)))))
         }
+
+        fn check_nonce(&self, _user: Address, _nonce: u64, _hash: B256) -> NonceCheckFuture {
+            Box::pin(future::ready(Ok(())))
+        }
+
+        fn next_nonce(&self, _user: Address) -> NextNonceFuture {
+            Box::pin(future::ready(Some(0)))
+        }
+
+        fn simulate_tob(
+            &self,
+            _order: TopOfBlockOrder,
+            _overrides: TobStateOverrides
+        ) -> TobSimulationFuture {
+            Box::pin(future::ready(TobSimulation::invalid("the mock doesn't simulate orders")))
+        }
+
+        fn check_order(&self, order: Self::Order) -> ValidationFuture {
+            Box::pin(future::ready(OrderValidationResults::Invalid(order.order_hash())))
+        }
     }
 }
@@ -0,0 +1,54 @@
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    consensus::{
+        InclusionAudit, InclusionFairness, InclusionFairnessStore, ValidatorPerformance,
+        ValidatorPerformanceStore
+    },
+    primitive::PeerId
+};
+use jsonrpsee::core::RpcResult;
+
+use crate::api::ValidatorsApiServer;
+
+/// Serves the missed and late rounds of the validators, so that operators can
+/// spot the ones that underperform, and how fairly the leaders included the
+/// orders, so that they can spot the ones that censor.
+pub struct ValidatorsApi {
+    performance: ValidatorPerformanceStore,
+    fairness:    InclusionFairnessStore
+}
+
+impl ValidatorsApi {
+    pub fn new(performance: ValidatorPerformanceStore, fairness: InclusionFairnessStore) -> Self {
+        Self { performance, fairness }
+    }
+}
+
+#[async_trait::async_trait]
+impl ValidatorsApiServer for ValidatorsApi {
+    async fn validator_performance(&self) -> RpcResult<Vec<ValidatorPerformance>> {
+        Ok(self.performance.summary())
+    }
+
+    async fn validator_performance_of(
+        &self,
+        peer_id: PeerId
+    ) -> RpcResult<Option<ValidatorPerformance>> {
+        Ok(self.performance.validator(&peer_id))
+    }
+
+    async fn inclusion_fairness(&self) -> RpcResult<Vec<InclusionFairness>> {
+        Ok(self.fairness.summary())
+    }
+
+    async fn inclusion_fairness_of(&self, leader: PeerId) -> RpcResult<Option<InclusionFairness>> {
+        Ok(self.fairness.leader(&leader))
+    }
+
+    async fn inclusion_audit(
+        &self,
+        block_number: BlockNumber
+    ) -> RpcResult<Option<InclusionAudit>> {
+        Ok(self.fairness.audit(block_number))
+    }
+}
@@ -0,0 +1,34 @@
+use alloy_primitives::B256;
+use jsonrpsee::core::RpcResult;
+
+use crate::{
+    api::WebhookApiServer,
+    impls::invalid_params_rpc_err,
+    webhooks::{WebhookRegistration, WebhookRegistry}
+};
+
+/// Lets the operator manage the webhooks fills are pushed to. Only served on
+/// the rpc of the node, see [`crate::webhooks`].
+pub struct WebhookApi {
+    registry: WebhookRegistry
+}
+
+impl WebhookApi {
+    pub fn new(registry: WebhookRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookApiServer for WebhookApi {
+    async fn register_webhook(&self, registration: WebhookRegistration) -> RpcResult<B256> {
+        self.registry
+            .register(registration)
+            .await
+            .map_err(|error| invalid_params_rpc_err(error.to_string()))
+    }
+
+    async fn unregister_webhook(&self, id: B256, secret: String) -> RpcResult<bool> {
+        Ok(self.registry.unregister(id, &secret))
+    }
+}
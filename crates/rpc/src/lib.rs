@@ -2,6 +2,8 @@
 
 pub mod api;
 pub mod impls;
+pub mod ofa;
 pub mod types;
+pub mod webhooks;
 
 pub use impls::*;
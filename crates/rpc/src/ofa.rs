@@ -0,0 +1,416 @@
+//! Ingestion of orders from external order flow auctions.
+//!
+//! An [`OrderFeed`] yields the raw orders of an auction, an [`OrderNormalizer`]
+//! turns them into [`AllOrders`] and [`run_order_feed`] hands them to the pool
+//! like any other order, tagged as [`OrderOrigin::OrderFlowAuction`]. The feed
+//! itself isn't trusted, the orders go through the same validation as the ones
+//! of users and peers.
+use std::{sync::OnceLock, time::Duration};
+
+use angstrom_metrics::OrderFeedMetricsWrapper;
+use angstrom_types::{orders::OrderOrigin, sol_bindings::grouped_orders::AllOrders};
+use futures::future::join_all;
+use jsonrpsee::{
+    core::{
+        client::{ClientT, Subscription, SubscriptionClientT},
+        params::ArrayParams,
+        ClientError
+    },
+    http_client::{HttpClient, HttpClientBuilder},
+    ws_client::{WsClient, WsClientBuilder}
+};
+use order_pool::OrderPoolHandle;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::{Interval, MissedTickBehavior};
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+/// wait before reconnecting to a websocket feed that dropped
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(5);
+
+static ORDER_FEED_METRICS: OnceLock<OrderFeedMetricsWrapper> = OnceLock::new();
+
+/// Source of the signed orders of an external auction.
+#[async_trait::async_trait]
+pub trait OrderFeed: Send + 'static {
+    /// what the orders of the feed are counted under
+    fn name(&self) -> &str;
+
+    /// The next orders of the feed, [`None`] once it ended. It's polled again
+    /// after an error, backing off is up to the feed.
+    async fn next_batch(&mut self) -> Option<Result<Vec<Value>, OrderFeedError>>;
+}
+
+/// Turns the raw orders of a feed into angstrom orders.
+pub trait OrderNormalizer: Send + Sync + 'static {
+    fn normalize(&self, raw: Value) -> Result<AllOrders, OrderFeedError>;
+}
+
+/// Orders in the format `angstrom_sendOrder` takes them in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AngstromOrderFormat;
+
+impl OrderNormalizer for AngstromOrderFormat {
+    fn normalize(&self, raw: Value) -> Result<AllOrders, OrderFeedError> {
+        Ok(serde_json::from_value(raw)?)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OrderFeedError {
+    #[error(transparent)]
+    Transport(#[from] ClientError),
+    #[error("malformed order - {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("unsupported feed url {0}, it has to be http(s) or ws(s)")]
+    UnsupportedUrl(String)
+}
+
+/// What happened to the orders of a feed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderFeedStats {
+    pub received:  u64,
+    pub malformed: u64,
+    pub accepted:  u64,
+    pub rejected:  u64
+}
+
+/// Submits the orders of `feed` to the pool until the feed ends.
+pub async fn run_order_feed<Pool: OrderPoolHandle>(
+    pool: Pool,
+    mut feed: Box<dyn OrderFeed>,
+    normalizer: impl OrderNormalizer
+) -> OrderFeedStats {
+    let metrics = ORDER_FEED_METRICS.get_or_init(OrderFeedMetricsWrapper::new);
+    let mut stats = OrderFeedStats::default();
+
+    while let Some(batch) = feed.next_batch().await {
+        let name = feed.name();
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(error) => {
+                tracing::warn!(feed = name, %error, "order feed failed");
+                metrics.incr_orders(name, "feed_error");
+                continue
+            }
+        };
+
+        stats.received += batch.len() as u64;
+        let orders = batch
+            .into_iter()
+            .filter_map(|raw| {
+                normalizer
+                    .normalize(raw)
+                    .inspect_err(|error| {
+                        tracing::debug!(feed = name, %error, "dropping order of feed");
+                        stats.malformed += 1;
+                        metrics.incr_orders(name, "malformed");
+                    })
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        let results = join_all(
+            orders
+                .into_iter()
+                .map(|order| pool.new_order(OrderOrigin::OrderFlowAuction, order))
+        )
+        .await;
+        for result in results {
+            if result.is_valid() {
+                stats.accepted += 1;
+                metrics.incr_orders(name, "accepted");
+            } else {
+                stats.rejected += 1;
+                metrics.incr_orders(name, "rejected");
+            }
+        }
+    }
+
+    tracing::info!(feed = feed.name(), ?stats, "order feed ended");
+    stats
+}
+
+/// A feed as it is configured by the operator.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderFeedConfig {
+    pub name:             String,
+    /// http(s) feeds are polled, ws(s) feeds subscribed to
+    pub url:              String,
+    /// method that is polled or subscribed to
+    pub method:           String,
+    #[serde(default)]
+    pub params:           Vec<Value>,
+    /// unsubscribe method of a ws feed, defaults to `method` with the
+    /// `subscribe` replaced
+    pub unsubscribe:      Option<String>,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64
+}
+
+/// The feeds file, a `[[feed]]` table per feed.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderFeedsConfig {
+    #[serde(default, rename = "feed")]
+    pub feeds: Vec<OrderFeedConfig>
+}
+
+fn default_poll_interval_ms() -> u64 {
+    DEFAULT_POLL_INTERVAL_MS
+}
+
+impl OrderFeedConfig {
+    pub fn build(&self) -> Result<Box<dyn OrderFeed>, OrderFeedError> {
+        let url = self.url.as_str();
+        if url.starts_with("http://") || url.starts_with("https://") {
+            let feed = JsonRpcPollingFeed::new(
+                &self.name,
+                url,
+                &self.method,
+                Duration::from_millis(self.poll_interval_ms)
+            )?
+            .with_params(self.params.clone());
+            return Ok(Box::new(feed))
+        }
+        if url.starts_with("ws://") || url.starts_with("wss://") {
+            let unsubscribe = self
+                .unsubscribe
+                .clone()
+                .unwrap_or_else(|| self.method.replace("subscribe", "unsubscribe"));
+            let feed = JsonRpcSubscriptionFeed::new(&self.name, url, &self.method, &unsubscribe)
+                .with_params(self.params.clone());
+            return Ok(Box::new(feed))
+        }
+
+        Err(OrderFeedError::UnsupportedUrl(self.url.clone()))
+    }
+}
+
+fn array_params(params: &[Value]) -> ArrayParams {
+    let mut array = ArrayParams::new();
+    for param in params {
+        array.insert(param).expect("json values always serialize");
+    }
+    array
+}
+
+/// Feeds send single orders or lists of them.
+fn into_batch(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(orders) => orders,
+        order => vec![order]
+    }
+}
+
+/// Calls `method` on a json rpc endpoint every interval, which responds with
+/// the orders that came in since.
+pub struct JsonRpcPollingFeed {
+    name:     String,
+    client:   HttpClient,
+    method:   String,
+    params:   Vec<Value>,
+    interval: Interval
+}
+
+impl JsonRpcPollingFeed {
+    pub fn new(
+        name: &str,
+        url: &str,
+        method: &str,
+        poll_interval: Duration
+    ) -> Result<Self, OrderFeedError> {
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Ok(Self {
+            name: name.to_string(),
+            client: HttpClientBuilder::default().build(url)?,
+            method: method.to_string(),
+            params: vec![],
+            interval
+        })
+    }
+
+    pub fn with_params(mut self, params: Vec<Value>) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderFeed for JsonRpcPollingFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next_batch(&mut self) -> Option<Result<Vec<Value>, OrderFeedError>> {
+        self.interval.tick().await;
+        let response = self
+            .client
+            .request::<Value, _>(&self.method, array_params(&self.params))
+            .await;
+
+        Some(response.map(into_batch).map_err(Into::into))
+    }
+}
+
+/// Subscribes to `method` on a json rpc websocket, every notification carries
+/// orders. Reconnects when the connection drops, so it never ends.
+pub struct JsonRpcSubscriptionFeed {
+    name:        String,
+    url:         String,
+    subscribe:   String,
+    unsubscribe: String,
+    params:      Vec<Value>,
+    /// the client has to be kept around for the subscription to stay open
+    connection:  Option<(WsClient, Subscription<Value>)>
+}
+
+impl JsonRpcSubscriptionFeed {
+    pub fn new(name: &str, url: &str, subscribe: &str, unsubscribe: &str) -> Self {
+        Self {
+            name:        name.to_string(),
+            url:         url.to_string(),
+            subscribe:   subscribe.to_string(),
+            unsubscribe: unsubscribe.to_string(),
+            params:      vec![],
+            connection:  None
+        }
+    }
+
+    pub fn with_params(mut self, params: Vec<Value>) -> Self {
+        self.params = params;
+        self
+    }
+
+    async fn connect(&self) -> Result<(WsClient, Subscription<Value>), OrderFeedError> {
+        let client = WsClientBuilder::default().build(&self.url).await?;
+        let subscription = client
+            .subscribe(&self.subscribe, array_params(&self.params), &self.unsubscribe)
+            .await?;
+
+        Ok((client, subscription))
+    }
+}
+
+#[async_trait::async_trait]
+impl OrderFeed for JsonRpcSubscriptionFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next_batch(&mut self) -> Option<Result<Vec<Value>, OrderFeedError>> {
+        loop {
+            if self.connection.is_none() {
+                match self.connect().await {
+                    Ok(connection) => self.connection = Some(connection),
+                    Err(error) => {
+                        tokio::time::sleep(RECONNECT_BACKOFF).await;
+                        return Some(Err(error))
+                    }
+                }
+            }
+
+            let (_, subscription) = self.connection.as_mut().unwrap();
+            match subscription.next().await {
+                Some(notification) => return Some(notification.map(into_batch).map_err(Into::into)),
+                None => {
+                    tracing::debug!(feed = %self.name, "order feed disconnected, reconnecting");
+                    self.connection = None;
+                    tokio::time::sleep(RECONNECT_BACKOFF).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use angstrom_network::pool_manager::{OrderCommand, PoolHandle};
+    use angstrom_types::sol_bindings::{
+        grouped_orders::{
+            FlashVariants, GroupedVanillaOrder, OrderWithStorageData, StandingVariants
+        },
+        RawPoolOrder
+    };
+    use tokio::sync::mpsc::unbounded_channel;
+    use validation::order::OrderValidationResults;
+
+    use super::*;
+
+    struct MockFeed(VecDeque<Result<Vec<Value>, OrderFeedError>>);
+
+    #[async_trait::async_trait]
+    impl OrderFeed for MockFeed {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        async fn next_batch(&mut self) -> Option<Result<Vec<Value>, OrderFeedError>> {
+            self.0.pop_front()
+        }
+    }
+
+    #[tokio::test]
+    async fn feed_orders_go_through_the_pool() {
+        let standing = AllOrders::Standing(StandingVariants::Partial(Default::default()));
+        let flash = AllOrders::Flash(FlashVariants::Exact(Default::default()));
+        let flash_hash = flash.order_hash();
+
+        let feed = MockFeed(VecDeque::from([
+            Ok(vec![
+                serde_json::to_value(&standing).unwrap(),
+                serde_json::json!({ "not": "an order" }),
+            ]),
+            Err(OrderFeedError::UnsupportedUrl("ftp://feed".to_string())),
+            Ok(vec![serde_json::to_value(&flash).unwrap()])
+        ]));
+
+        let (manager_tx, mut manager_rx) = unbounded_channel();
+        let (pool_manager_tx, _) = tokio::sync::broadcast::channel(1);
+        let pool = PoolHandle { manager_tx, pool_manager_tx };
+
+        // the pool only lets the flash order in
+        let origins = tokio::spawn(async move {
+            let mut origins = vec![];
+            while let Some(OrderCommand::NewOrder(origin, order, tx)) = manager_rx.recv().await {
+                origins.push(origin);
+                let hash = order.order_hash();
+                let _ = tx.send(if hash == flash_hash {
+                    OrderValidationResults::Valid(
+                        OrderWithStorageData::<GroupedVanillaOrder>::default()
+                            .try_map_inner(|_| Ok(order))
+                            .unwrap()
+                    )
+                } else {
+                    OrderValidationResults::Invalid(hash)
+                });
+            }
+            origins
+        });
+
+        let stats = run_order_feed(pool, Box::new(feed), AngstromOrderFormat).await;
+        assert_eq!(stats, OrderFeedStats { received: 3, malformed: 1, accepted: 1, rejected: 1 });
+        assert_eq!(origins.await.unwrap(), vec![OrderOrigin::OrderFlowAuction; 2]);
+    }
+
+    #[test]
+    fn feeds_are_picked_by_url() {
+        let mut config = OrderFeedConfig {
+            name:             "auction".to_string(),
+            url:              "ftp://auction.example".to_string(),
+            method:           "auction_subscribeOrders".to_string(),
+            params:           vec![],
+            unsubscribe:      None,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS
+        };
+        assert!(matches!(config.build(), Err(OrderFeedError::UnsupportedUrl(_))));
+
+        config.url = "wss://auction.example".to_string();
+        assert_eq!(config.build().unwrap().name(), "auction");
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use alloy_primitives::BlockNumber;
+use angstrom_types::{
+    block_sync::BlockSyncStatus,
+    primitive::{PeerId, PoolId}
+};
+use consensus::{rounds::KeyRotation, ConsensusTiming};
+use tokio::sync::oneshot;
+
+/// Command of the admin namespace. They are carried out by the node once its
+/// modules are running.
+#[derive(Debug)]
+pub enum AdminCommand {
+    PauseOrderIntake(bool),
+    BanPeer(PeerId),
+    UnbanPeer(PeerId),
+    /// responds with the block the pool was reloaded at
+    ResyncPool(PoolId, oneshot::Sender<Result<BlockNumber, String>>),
+    /// loads the key from the file, responds with the scheduled handover
+    RotateSigningKey(PathBuf, oneshot::Sender<Result<KeyRotation, String>>),
+    BlockSyncStatus(oneshot::Sender<Result<BlockSyncStatus, String>>),
+    /// times the rounds from the next one on, validated already
+    SetConsensusTiming(ConsensusTiming),
+    /// replaces the filter of the logs
+    SetLogFilter(String, oneshot::Sender<Result<(), String>>)
+}
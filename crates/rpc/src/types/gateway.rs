@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+/// Methods a key that doesn't list its own may call. Submitting and managing
+/// orders, none of the admin or bulk query methods.
+pub const DEFAULT_GATEWAY_METHODS: [&str; 7] = [
+    "angstrom_sendOrder",
+    "angstrom_cancelOrder",
+    "angstrom_cancelAllOrders",
+    "angstrom_orderStatus",
+    "angstrom_pendingOrder",
+    "angstrom_nextNonce",
+    "angstrom_estimateGas"
+];
+
+/// Who may call the public rpc, how often and what.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewayConfig {
+    /// methods of keys that don't list their own
+    #[serde(default = "default_gateway_methods")]
+    pub methods: Vec<String>,
+    #[serde(default, rename = "key")]
+    pub keys:    Vec<ApiKeyConfig>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// what the key shows up as in the logs and metrics
+    pub name:                String,
+    pub key:                 String,
+    /// sustained calls per second
+    pub requests_per_second: u32,
+    /// calls that can be made at once after being idle. defaults to a second
+    /// worth of calls
+    pub burst:               Option<u32>,
+    /// replaces the methods of the gateway for this key
+    pub methods:             Option<Vec<String>>
+}
+
+impl ApiKeyConfig {
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or(self.requests_per_second)
+    }
+}
+
+impl GatewayConfig {
+    pub fn validate(&self) -> Result<(), GatewayConfigError> {
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        for key in &self.keys {
+            if key.key.is_empty() {
+                return Err(GatewayConfigError::EmptyKey(key.name.clone()))
+            }
+            if key.requests_per_second == 0 || key.burst() == 0 {
+                return Err(GatewayConfigError::ZeroQuota(key.name.clone()))
+            }
+            if !names.insert(&key.name) {
+                return Err(GatewayConfigError::DuplicateName(key.name.clone()))
+            }
+            if !keys.insert(&key.key) {
+                return Err(GatewayConfigError::DuplicateKey(key.name.clone()))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn default_gateway_methods() -> Vec<String> {
+    DEFAULT_GATEWAY_METHODS.map(str::to_owned).to_vec()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GatewayConfigError {
+    #[error("api key {0} is empty")]
+    EmptyKey(String),
+    #[error("api key {0} has no quota")]
+    ZeroQuota(String),
+    #[error("api key name {0} is used twice")]
+    DuplicateName(String),
+    #[error("api key {0} is the same as another one")]
+    DuplicateKey(String)
+}
@@ -1,5 +1,9 @@
+pub mod admin;
+pub mod gateway;
 pub mod quoting;
 pub mod subscriptions;
 
+pub use admin::*;
+pub use gateway::*;
 pub use quoting::*;
 pub use subscriptions::*;
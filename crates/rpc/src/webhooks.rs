@@ -0,0 +1,666 @@
+//! Notifications of settled orders, pushed to the urls the operator registers
+//! for integrators.
+//!
+//! Every notification is POSTed as JSON and signed with the secret of its
+//! webhook: [`SIGNATURE_HEADER`] holds the hex HMAC-SHA256 of
+//! `<timestamp>.<body>`, with the unix timestamp of [`TIMESTAMP_HEADER`].
+//! Deliveries that fail are retried with an exponential backoff, so
+//! integrators without a websocket subscription still learn of their fills.
+//!
+//! Webhooks are only ever delivered to public addresses. Hosts are resolved
+//! when the webhook is registered and again on every delivery, so a name that
+//! later points to the network of the node isn't posted to either.
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use alloy_primitives::{hex, keccak256, Address, BlockNumber, TxHash, B256};
+use angstrom_types::{orders::GasReconciliation, primitive::PoolId};
+use futures::{future, StreamExt};
+use hmac::{Hmac, Mac};
+use order_pool::{OrderPoolHandle, PoolManagerUpdate};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Url
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tokio::sync::Semaphore;
+use tokio_stream::wrappers::BroadcastStream;
+
+/// Webhooks the node keeps at most.
+pub const MAX_WEBHOOKS: usize = 1024;
+/// Attempts at delivering a notification before it's dropped.
+pub const MAX_WEBHOOK_ATTEMPTS: u32 = 5;
+/// Wait before the first retry, doubled for every one after it.
+pub const WEBHOOK_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+/// Deliveries in flight at once, notifications past it are dropped.
+pub const MAX_PENDING_DELIVERIES: usize = 4096;
+pub const SIGNATURE_HEADER: &str = "x-angstrom-signature";
+pub const TIMESTAMP_HEADER: &str = "x-angstrom-timestamp";
+/// how long an endpoint has to answer a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebhookError {
+    #[error("unsupported webhook url {0}, it has to be http(s)")]
+    UnsupportedUrl(String),
+    #[error("webhook host {0} doesn't resolve")]
+    UnresolvedHost(String),
+    #[error("webhook host {0} isn't a public address")]
+    PrivateHost(String),
+    #[error("the webhook needs a secret to sign its notifications with")]
+    EmptySecret,
+    #[error("the webhook has to subscribe to at least one event")]
+    NoEvents,
+    #[error("the node already serves {0} webhooks")]
+    TooManyWebhooks(usize)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEventKind {
+    OrderFilled,
+    OrderExpired,
+    BundleLanded
+}
+
+/// What an integrator registers a webhook with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WebhookRegistration {
+    /// http(s) url the notifications are posted to
+    pub url:       String,
+    /// key the notifications are signed with
+    pub secret:    String,
+    pub events:    HashSet<WebhookEventKind>,
+    /// only notifies of the orders of these users, of all orders if empty.
+    /// Landed bundles aren't filtered
+    #[serde(default)]
+    pub addresses: HashSet<Address>
+}
+
+impl WebhookRegistration {
+    /// The id the webhook is known by, derived from its url and secret.
+    pub fn id(&self) -> B256 {
+        keccak256(format!("{}\n{}", self.url, self.secret))
+    }
+
+    /// Checks the registration and resolves the host of its url, all of the
+    /// addresses it resolves to have to be public.
+    async fn validate(&self) -> Result<(), WebhookError> {
+        let unsupported = || WebhookError::UnsupportedUrl(self.url.clone());
+        let url = Url::parse(&self.url).map_err(|_| unsupported())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(unsupported())
+        }
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return Err(unsupported())
+        };
+        if self.secret.is_empty() {
+            return Err(WebhookError::EmptySecret)
+        }
+        if self.events.is_empty() {
+            return Err(WebhookError::NoEvents)
+        }
+
+        // ipv6 hosts come in brackets
+        let host = host.trim_matches(['[', ']']);
+        let addrs = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| WebhookError::UnresolvedHost(host.to_string()))?
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            return Err(WebhookError::UnresolvedHost(host.to_string()))
+        }
+        if addrs.iter().any(|addr| !is_public(addr.ip())) {
+            return Err(WebhookError::PrivateHost(host.to_string()))
+        }
+
+        Ok(())
+    }
+
+    fn wants(&self, event: &WebhookEvent) -> bool {
+        self.events.contains(&event.kind())
+            && event
+                .user()
+                .map_or(true, |user| self.addresses.is_empty() || self.addresses.contains(&user))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    #[serde(rename_all = "camelCase")]
+    OrderFilled {
+        block_number: BlockNumber,
+        order_hash:   B256,
+        pool_id:      PoolId,
+        user:         Address
+    },
+    #[serde(rename_all = "camelCase")]
+    OrderExpired { order_hash: B256, pool_id: PoolId, user: Address },
+    /// a bundle of ours landed with the given orders
+    #[serde(rename_all = "camelCase")]
+    BundleLanded { block_number: BlockNumber, tx_hash: TxHash, orders: Vec<B256> }
+}
+
+impl WebhookEvent {
+    /// The event of an update of the pool, if webhooks are notified of it.
+    /// Private orders that expire are never revealed.
+    pub fn from_pool_update(update: PoolManagerUpdate) -> Option<Self> {
+        match update {
+            PoolManagerUpdate::FilledOrder(block_number, order) => Some(Self::OrderFilled {
+                block_number,
+                order_hash: order.order_hash(),
+                pool_id: order.pool_id,
+                user: order.from()
+            }),
+            PoolManagerUpdate::ExpiredOrder(order) if !order.is_private => {
+                Some(Self::OrderExpired {
+                    order_hash: order.order_hash(),
+                    pool_id:    order.pool_id,
+                    user:       order.from()
+                })
+            }
+            _ => None
+        }
+    }
+
+    pub fn bundle_landed(reconciliation: &GasReconciliation) -> Self {
+        Self::BundleLanded {
+            block_number: reconciliation.block_number,
+            tx_hash:      reconciliation.tx_hash,
+            orders:       reconciliation
+                .orders
+                .iter()
+                .map(|order| order.order_hash)
+                .collect()
+        }
+    }
+
+    pub fn kind(&self) -> WebhookEventKind {
+        match self {
+            Self::OrderFilled { .. } => WebhookEventKind::OrderFilled,
+            Self::OrderExpired { .. } => WebhookEventKind::OrderExpired,
+            Self::BundleLanded { .. } => WebhookEventKind::BundleLanded
+        }
+    }
+
+    fn user(&self) -> Option<Address> {
+        match self {
+            Self::OrderFilled { user, .. } | Self::OrderExpired { user, .. } => Some(*user),
+            Self::BundleLanded { .. } => None
+        }
+    }
+}
+
+/// The body of a delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookNotification {
+    pub webhook_id: B256,
+    /// unix timestamp the notification was created at, in seconds
+    pub timestamp:  u64,
+    #[serde(flatten)]
+    pub event:      WebhookEvent
+}
+
+/// Whether the address is reachable from the internet, as opposed to the
+/// loopback, private, link local and other special purpose ranges.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // shared address space of carrier grade nats
+                || (a == 100 && (b & 0xc0) == 64)
+                || a == 0
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip))
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local
+                || (first & 0xfe00) == 0xfc00
+                // link local
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Resolves the hosts of the deliveries to their public addresses only.
+#[derive(Debug)]
+struct PublicResolver;
+
+impl PublicResolver {
+    async fn public_addrs(host: String) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+        let addrs = tokio::net::lookup_host((host.as_str(), 0))
+            .await?
+            .filter(|addr| is_public(addr.ip()))
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            return Err(format!("{host} has no public address").into())
+        }
+
+        Ok(Box::new(addrs.into_iter()))
+    }
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(Self::public_addrs(name.as_str().to_string()))
+    }
+}
+
+/// The hex HMAC-SHA256 a notification is signed with.
+pub fn sign_notification(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any size");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// The registered webhooks, shared between the RPC, which manages them, and
+/// the [`WebhookDispatcher`].
+#[derive(Debug, Clone, Default)]
+pub struct WebhookRegistry {
+    webhooks: Arc<RwLock<HashMap<B256, WebhookRegistration>>>
+}
+
+impl WebhookRegistry {
+    /// Registers the webhook, returns its id. Registering the same url and
+    /// secret again replaces the events and users it's notified of.
+    pub async fn register(&self, registration: WebhookRegistration) -> Result<B256, WebhookError> {
+        registration.validate().await?;
+        let id = registration.id();
+        let mut webhooks = self.webhooks.write().expect("poisoned");
+        if !webhooks.contains_key(&id) && webhooks.len() >= MAX_WEBHOOKS {
+            return Err(WebhookError::TooManyWebhooks(MAX_WEBHOOKS))
+        }
+        webhooks.insert(id, registration);
+
+        Ok(id)
+    }
+
+    /// Removes the webhook, the secret proves the caller is the one that
+    /// registered it. Returns false if there's no such webhook.
+    pub fn unregister(&self, id: B256, secret: &str) -> bool {
+        let mut webhooks = self.webhooks.write().expect("poisoned");
+        if !webhooks
+            .get(&id)
+            .is_some_and(|webhook| webhook.secret.as_bytes().ct_eq(secret.as_bytes()).into())
+        {
+            return false
+        }
+
+        webhooks.remove(&id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.webhooks.read().expect("poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn subscribers(&self, event: &WebhookEvent) -> Vec<(B256, WebhookRegistration)> {
+        self.webhooks
+            .read()
+            .expect("poisoned")
+            .iter()
+            .filter(|(_, webhook)| webhook.wants(event))
+            .map(|(id, webhook)| (*id, webhook.clone()))
+            .collect()
+    }
+}
+
+/// Delivers the events to the webhooks that subscribed to them. Every delivery
+/// runs on its own, so a slow endpoint doesn't hold back the others.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    registry:     WebhookRegistry,
+    client:       reqwest::Client,
+    max_attempts: u32,
+    backoff:      Duration,
+    pending:      Arc<Semaphore>,
+    /// only delivers to public addresses, off in tests that serve the webhook
+    /// on the loopback
+    public_only:  bool
+}
+
+impl WebhookDispatcher {
+    pub fn new(registry: WebhookRegistry) -> Self {
+        Self {
+            registry,
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .dns_resolver(Arc::new(PublicResolver))
+                // a redirect could point anywhere
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("failed to build the webhook client"),
+            max_attempts: MAX_WEBHOOK_ATTEMPTS,
+            backoff: WEBHOOK_RETRY_BACKOFF,
+            pending: Arc::new(Semaphore::new(MAX_PENDING_DELIVERIES)),
+            public_only: true
+        }
+    }
+
+    pub fn with_retries(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn dispatch(&self, event: WebhookEvent) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        for (webhook_id, webhook) in self.registry.subscribers(&event) {
+            let Ok(permit) = self.pending.clone().try_acquire_owned() else {
+                tracing::warn!(url = %webhook.url, "too many pending webhook deliveries, dropping");
+                continue
+            };
+            let notification = WebhookNotification { webhook_id, timestamp, event: event.clone() };
+            let this = self.clone();
+            tokio::spawn(async move {
+                this.deliver(&webhook, &notification).await;
+                drop(permit);
+            });
+        }
+    }
+
+    async fn deliver(&self, webhook: &WebhookRegistration, notification: &WebhookNotification) {
+        // the resolver only sees names, addresses in the url are checked here
+        let literal = Url::parse(&webhook.url).ok().and_then(|url| {
+            url.host_str()?
+                .trim_matches(['[', ']'])
+                .parse::<IpAddr>()
+                .ok()
+        });
+        if self.public_only && literal.is_some_and(|ip| !is_public(ip)) {
+            tracing::warn!(url = %webhook.url, "not delivering to a private address");
+            return
+        }
+        let Ok(body) = serde_json::to_vec(notification) else { return };
+        let signature = sign_notification(&webhook.secret, notification.timestamp, &body);
+
+        for attempt in 0..self.max_attempts {
+            if attempt != 0 {
+                tokio::time::sleep(self.backoff * 2u32.pow(attempt - 1)).await;
+            }
+            let response = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(TIMESTAMP_HEADER, notification.timestamp.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+            match response {
+                Ok(response) if response.status().is_success() => return,
+                Ok(response) => {
+                    let status = response.status();
+                    tracing::debug!(url = %webhook.url, %status, attempt, "webhook refused delivery");
+                }
+                Err(error) => {
+                    tracing::debug!(url = %webhook.url, %error, attempt, "webhook delivery failed");
+                }
+            }
+        }
+
+        let attempts = self.max_attempts;
+        tracing::warn!(url = %webhook.url, attempts, "giving up on webhook delivery");
+    }
+}
+
+/// Notifies the webhooks of the fills and expiries of the pool and of the
+/// bundles of ours that landed, until the pool shuts down.
+pub async fn run_webhook_dispatcher<Pool: OrderPoolHandle>(
+    pool: Pool,
+    landed: tokio::sync::broadcast::Receiver<GasReconciliation>,
+    dispatcher: WebhookDispatcher
+) {
+    let updates = pool
+        .subscribe_orders()
+        .filter_map(|update| future::ready(update.ok().and_then(WebhookEvent::from_pool_update)));
+    let landed = BroadcastStream::new(landed).filter_map(|reconciliation| {
+        future::ready(
+            reconciliation
+                .ok()
+                .map(|reconciliation| WebhookEvent::bundle_landed(&reconciliation))
+        )
+    });
+
+    let mut events = futures::stream::select(updates, landed);
+    while let Some(event) = events.next().await {
+        dispatcher.dispatch(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener
+    };
+
+    use super::*;
+
+    fn registration(events: &[WebhookEventKind], addresses: &[Address]) -> WebhookRegistration {
+        WebhookRegistration {
+            url:       "https://1.1.1.1/fills".to_string(),
+            secret:    "secret".to_string(),
+            events:    events.iter().copied().collect(),
+            addresses: addresses.iter().copied().collect()
+        }
+    }
+
+    /// Serves a webhook on the loopback that answers the deliveries with
+    /// `statuses`, one after the other, and records their signature headers.
+    async fn serve(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(vec![]));
+        let log = received.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buf = [0; 4096];
+                // the body is small, the whole request comes before the
+                // client waits for the response
+                while !String::from_utf8_lossy(&request).contains("}") {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let signature = String::from_utf8_lossy(&request)
+                    .lines()
+                    .find_map(|line| line.strip_prefix(&format!("{SIGNATURE_HEADER}: ")))
+                    .unwrap_or_default()
+                    .to_string();
+                log.lock().unwrap().push(signature);
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, received)
+    }
+
+    fn notification() -> WebhookNotification {
+        WebhookNotification {
+            webhook_id: B256::ZERO,
+            timestamp:  1,
+            event:      WebhookEvent::OrderExpired {
+                order_hash: B256::ZERO,
+                pool_id:    PoolId::ZERO,
+                user:       Address::ZERO
+            }
+        }
+    }
+
+    fn loopback_dispatcher(max_attempts: u32) -> WebhookDispatcher {
+        WebhookDispatcher {
+            public_only: false,
+            ..WebhookDispatcher::new(WebhookRegistry::default())
+                .with_retries(max_attempts, Duration::from_millis(1))
+        }
+    }
+
+    #[tokio::test]
+    async fn notifies_the_webhooks_that_want_the_event() {
+        let user = Address::repeat_byte(1);
+        let registry = WebhookRegistry::default();
+        let fills = registry
+            .register(registration(&[WebhookEventKind::OrderFilled], &[user]))
+            .await
+            .unwrap();
+        let mut bundles = registration(&[WebhookEventKind::BundleLanded], &[user]);
+        bundles.url = "http://8.8.8.8:8080".to_string();
+        let bundles = registry.register(bundles).await.unwrap();
+
+        let filled = |user| WebhookEvent::OrderFilled {
+            block_number: 1,
+            order_hash: B256::ZERO,
+            pool_id: PoolId::ZERO,
+            user
+        };
+        let subscribers = |event| {
+            registry
+                .subscribers(&event)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(subscribers(filled(user)), vec![fills]);
+        assert!(subscribers(filled(Address::repeat_byte(2))).is_empty());
+        let landed = WebhookEvent::BundleLanded {
+            block_number: 1,
+            tx_hash:      TxHash::ZERO,
+            orders:       vec![]
+        };
+        assert_eq!(subscribers(landed), vec![bundles]);
+
+        // only the holder of the secret can remove it
+        assert!(!registry.unregister(fills, "guess"));
+        assert!(registry.unregister(fills, "secret"));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_registrations() {
+        let registry = WebhookRegistry::default();
+        let mut ftp = registration(&[WebhookEventKind::OrderFilled], &[]);
+        ftp.url = "ftp://example.com".to_string();
+        assert_eq!(
+            registry.register(ftp).await,
+            Err(WebhookError::UnsupportedUrl("ftp://example.com".to_string()))
+        );
+        assert_eq!(registry.register(registration(&[], &[])).await, Err(WebhookError::NoEvents));
+    }
+
+    #[tokio::test]
+    async fn rejects_hosts_on_the_network_of_the_node() {
+        let registry = WebhookRegistry::default();
+        for (url, host) in [
+            ("http://127.0.0.1:8545", "127.0.0.1"),
+            ("http://localhost:8545", "localhost"),
+            ("http://10.1.2.3/hook", "10.1.2.3"),
+            // cloud metadata
+            ("http://169.254.169.254/latest", "169.254.169.254"),
+            ("http://[::1]/hook", "::1"),
+            ("http://[::ffff:192.168.0.1]/hook", "::ffff:c0a8:1")
+        ] {
+            let mut webhook = registration(&[WebhookEventKind::OrderFilled], &[]);
+            webhook.url = url.to_string();
+            assert_eq!(
+                registry.register(webhook).await,
+                Err(WebhookError::PrivateHost(host.to_string())),
+                "{url}"
+            );
+        }
+        assert!(registry.is_empty());
+
+        assert!(is_public("1.1.1.1".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+        assert!(!is_public("fd00::1".parse().unwrap()));
+        assert!(!is_public("fe80::1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_webhook_takes_the_delivery() {
+        let (url, received) = serve(vec![500, 503, 200, 200]).await;
+        let mut webhook = registration(&[WebhookEventKind::OrderExpired], &[]);
+        webhook.url = url;
+        let notification = notification();
+
+        loopback_dispatcher(5)
+            .deliver(&webhook, &notification)
+            .await;
+
+        // stops at the first delivery that's taken
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 3);
+        let body = serde_json::to_vec(&notification).unwrap();
+        let signature = sign_notification(&webhook.secret, notification.timestamp, &body);
+        assert!(received.iter().all(|header| *header == signature));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let (url, received) = serve(vec![500; 4]).await;
+        let mut webhook = registration(&[WebhookEventKind::OrderExpired], &[]);
+        webhook.url = url;
+
+        loopback_dispatcher(2)
+            .deliver(&webhook, &notification())
+            .await;
+        assert_eq!(received.lock().unwrap().len(), 2);
+
+        // and never posts to the loopback outside of tests
+        let dispatcher = WebhookDispatcher::new(WebhookRegistry::default());
+        dispatcher.deliver(&webhook, &notification()).await;
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn signs_the_timestamp_along_with_the_body() {
+        let signature = sign_notification("secret", 1, b"{}");
+        assert_eq!(signature, sign_notification("secret", 1, b"{}"));
+        assert_ne!(signature, sign_notification("secret", 2, b"{}"));
+        assert_ne!(signature, sign_notification("other", 1, b"{}"));
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(b"1.{}");
+        assert!(mac.verify_slice(&hex::decode(signature).unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,61 @@
+use alloy::{
+    consensus::Transaction,
+    eips::eip1559::{calc_next_block_base_fee, BaseFeeParams}
+};
+use futures::{Stream, StreamExt};
+use reth_primitives_traits::BlockBody;
+use reth_provider::CanonStateNotificationStream;
+
+/// The fees paid in a canonical block. Feeds the gas price model that prices
+/// the gas of bundles and orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockFees {
+    pub block_num:    u64,
+    pub base_fee:     u64,
+    pub gas_used:     u64,
+    pub gas_limit:    u64,
+    /// median tip over the base fee the transactions of the block paid. None
+    /// for empty blocks
+    pub priority_fee: Option<u128>
+}
+
+impl BlockFees {
+    /// Base fee of the following block, as set by EIP-1559.
+    pub fn next_base_fee(&self) -> u64 {
+        calc_next_block_base_fee(
+            self.gas_used,
+            self.gas_limit,
+            self.base_fee,
+            BaseFeeParams::ethereum()
+        )
+    }
+
+    /// The fees of the tip of every new canonical chain. Blocks from before
+    /// London have no base fee and are skipped.
+    pub fn into_fee_update_stream(
+        stream: CanonStateNotificationStream
+    ) -> impl Stream<Item = Self> + Send + Sync {
+        stream.filter_map(|notification| {
+            let tip = notification.tip();
+            let fees = tip.base_fee_per_gas.map(|base_fee| {
+                let mut tips = tip
+                    .body()
+                    .transactions()
+                    .iter()
+                    .filter_map(|tx| tx.effective_tip_per_gas(base_fee))
+                    .collect::<Vec<_>>();
+                tips.sort_unstable();
+
+                Self {
+                    block_num: tip.number,
+                    base_fee,
+                    gas_used: tip.gas_used,
+                    gas_limit: tip.gas_limit,
+                    priority_fee: tips.get(tips.len() / 2).copied()
+                }
+            });
+
+            futures::future::ready(fees)
+        })
+    }
+}
@@ -6,10 +6,12 @@ use std::{
         atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, RwLock
     },
-    task::Waker
+    task::Waker,
+    time::{Duration, Instant}
 };
 
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 
 /// Producer to block syncing events
 pub trait BlockSyncProducer: Debug + Clone + Send + Sync + Unpin + 'static {
@@ -64,7 +66,13 @@ pub struct GlobalBlockSync {
     registered_modules:     Arc<DashMap<&'static str, VecDeque<SignOffState>>>,
     /// Avoids having a module join the set while running. This is to ensure
     /// no race conditions.
-    all_modules_registered: Arc<AtomicBool>
+    all_modules_registered: Arc<AtomicBool>,
+    /// the latest block that was proposed
+    latest_block:           Arc<AtomicU64>,
+    /// when the proposal at the front of the pending state was first waited on
+    proposal_since:         Arc<RwLock<Option<Instant>>>,
+    /// the last proposal each module signed off on
+    last_sign_offs:         Arc<DashMap<&'static str, GlobalBlockState>>
 }
 
 impl GlobalBlockSync {
@@ -73,16 +81,63 @@ impl GlobalBlockSync {
             block_number:           Arc::new(AtomicU64::new(block_number)),
             pending_state:          Arc::new(RwLock::new(VecDeque::with_capacity(2))),
             registered_modules:     Arc::new(DashMap::default()),
-            all_modules_registered: AtomicBool::new(false).into()
+            all_modules_registered: AtomicBool::new(false).into(),
+            latest_block:           Arc::new(AtomicU64::new(block_number)),
+            proposal_since:         Arc::new(RwLock::new(None)),
+            last_sign_offs:         Arc::new(DashMap::default())
         }
     }
 
+    /// Snapshot of where every module is at with the pending proposals.
+    pub fn status(&self) -> BlockSyncStatus {
+        let pending = self.pending_state.read().unwrap().clone();
+        let pending_for_ms = self
+            .proposal_since
+            .read()
+            .unwrap()
+            .filter(|_| !pending.is_empty())
+            .map(|since| since.elapsed().as_millis() as u64);
+        let current_block = self.current_block_number();
+        let latest_block = self.latest_block.load(Ordering::SeqCst).max(current_block);
+
+        let mut modules = self
+            .registered_modules
+            .iter()
+            .map(|entry| {
+                let signed = entry.value().len();
+                ModuleSyncStatus {
+                    module:        entry.key().to_string(),
+                    signed_off:    signed > 0 || pending.is_empty(),
+                    behind_by:     pending.len().saturating_sub(signed),
+                    last_sign_off: self.last_sign_offs.get(entry.key()).map(|s| s.clone())
+                }
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by(|a, b| a.module.cmp(&b.module));
+
+        BlockSyncStatus {
+            current_block,
+            latest_block,
+            lag: latest_block - current_block,
+            pending: pending.front().cloned(),
+            pending_for_ms,
+            modules
+        }
+    }
+
+    /// Starts the clock on the proposal at the front once the previous one
+    /// was handled.
+    fn restart_proposal_clock(&self, pending: &VecDeque<GlobalBlockState>) {
+        *self.proposal_since.write().unwrap() = (!pending.is_empty()).then(Instant::now);
+    }
+
     fn proper_proposal(&self, proposal: &GlobalBlockState) -> bool {
         self.pending_state.read().unwrap().contains(proposal)
     }
 
     pub fn clear(&self) {
         self.pending_state.write().unwrap().clear();
+        *self.proposal_since.write().unwrap() = None;
     }
 
     pub fn set_block(&self, block_number: u64) {
@@ -99,15 +154,20 @@ impl BlockSyncProducer for GlobalBlockSync {
             return
         }
 
+        self.latest_block.fetch_max(block_number, Ordering::SeqCst);
+        if lock.is_empty() {
+            *self.proposal_since.write().unwrap() = Some(Instant::now());
+        }
         lock.push_back(GlobalBlockState::PendingProgression(block_number));
         tracing::info!(?self.pending_state, "current pending state");
     }
 
     fn reorg(&self, reorg_range: RangeInclusive<u64>) {
-        self.pending_state
-            .write()
-            .unwrap()
-            .push_back(GlobalBlockState::PendingReorg(reorg_range));
+        let mut lock = self.pending_state.write().unwrap();
+        if lock.is_empty() {
+            *self.proposal_since.write().unwrap() = Some(Instant::now());
+        }
+        lock.push_back(GlobalBlockState::PendingReorg(reorg_range));
     }
 
     fn finalize_modules(&self) {
@@ -132,6 +192,8 @@ impl BlockSyncConsumer for GlobalBlockSync {
         }
 
         let check = SignOffState::HandledReorg(waker);
+        self.last_sign_offs
+            .insert(module, GlobalBlockState::PendingReorg(block_range));
 
         self.registered_modules
             .entry(module)
@@ -151,6 +213,7 @@ impl BlockSyncConsumer for GlobalBlockSync {
                 // are racing and someone beat us to it!
                 return
             };
+            self.restart_proposal_clock(&lock);
             drop(lock);
 
             tracing::info!(handled_state=?new_state, "detected reorg has been handled successfully");
@@ -181,6 +244,8 @@ impl BlockSyncConsumer for GlobalBlockSync {
         }
 
         let check = SignOffState::ReadyForNextBlock(waker);
+        self.last_sign_offs
+            .insert(module, GlobalBlockState::PendingProgression(block_number));
 
         self.registered_modules
             .entry(module)
@@ -200,7 +265,7 @@ impl BlockSyncConsumer for GlobalBlockSync {
                 // are racing and someone beat us to it!
                 return
             };
-
+            self.restart_proposal_clock(&lock);
             drop(lock);
 
             if let GlobalBlockState::PendingProgression(block_number) = new_state {
@@ -253,7 +318,52 @@ impl BlockSyncConsumer for GlobalBlockSync {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where a [`GlobalBlockSync`] is at, for operators to see what a transition is
+/// waiting on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSyncStatus {
+    /// block every module has signed off on
+    pub current_block:  u64,
+    /// latest block that was proposed
+    pub latest_block:   u64,
+    pub lag:            u64,
+    /// proposal the modules are signing off on
+    pub pending:        Option<GlobalBlockState>,
+    /// how long the pending proposal has been waiting on sign offs
+    pub pending_for_ms: Option<u64>,
+    pub modules:        Vec<ModuleSyncStatus>
+}
+
+impl BlockSyncStatus {
+    /// Modules that haven't signed off on a proposal that has been pending for
+    /// longer than `timeout`.
+    pub fn stalled_modules(&self, timeout: Duration) -> Vec<&str> {
+        let timed_out = self
+            .pending_for_ms
+            .is_some_and(|pending_for| pending_for >= timeout.as_millis() as u64);
+        if !timed_out {
+            return vec![]
+        }
+
+        self.modules
+            .iter()
+            .filter(|module| !module.signed_off)
+            .map(|module| module.module.as_str())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleSyncStatus {
+    pub module:        String,
+    /// whether the module signed off on the pending proposal
+    pub signed_off:    bool,
+    /// pending proposals the module hasn't signed off on yet
+    pub behind_by:     usize,
+    pub last_sign_off: Option<GlobalBlockState>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GlobalBlockState {
     /// current block number processing
     Processing(u64),
@@ -299,7 +409,9 @@ impl Eq for SignOffState {}
 pub mod test {
     use std::{sync::Arc, thread, time::Duration};
 
-    use crate::block_sync::{BlockSyncConsumer, BlockSyncProducer, GlobalBlockSync};
+    use crate::block_sync::{
+        BlockSyncConsumer, BlockSyncProducer, GlobalBlockState, GlobalBlockSync
+    };
 
     const MOD1: &str = "Sick Module";
     const MOD2: &str = "Sick Module Two";
@@ -595,6 +707,43 @@ pub mod test {
         global_sync.sign_off_on_block(MOD1, 11, None);
     }
 
+    #[test]
+    fn test_status_reports_waiting_modules() {
+        let global_sync = GlobalBlockSync::new(10);
+
+        global_sync.register(MOD1);
+        global_sync.register(MOD2);
+        global_sync.finalize_modules();
+
+        let status = global_sync.status();
+        assert_eq!(status.pending, None);
+        assert!(status.modules.iter().all(|module| module.signed_off));
+
+        global_sync.new_block(11);
+        global_sync.new_block(12);
+        global_sync.sign_off_on_block(MOD1, 11, None);
+
+        let status = global_sync.status();
+        assert_eq!(status.current_block, 10);
+        assert_eq!(status.lag, 2);
+        assert_eq!(status.pending, Some(GlobalBlockState::PendingProgression(11)));
+        assert!(status.pending_for_ms.is_some());
+        let waiting = status
+            .modules
+            .iter()
+            .map(|module| (module.module.as_str(), module.signed_off, module.behind_by))
+            .collect::<Vec<_>>();
+        assert_eq!(waiting, vec![(MOD1, true, 1), (MOD2, false, 2)]);
+        assert_eq!(status.stalled_modules(Duration::ZERO), vec![MOD2]);
+        assert!(status.stalled_modules(Duration::from_secs(60)).is_empty());
+
+        global_sync.sign_off_on_block(MOD2, 11, None);
+        let status = global_sync.status();
+        assert_eq!(status.current_block, 11);
+        assert_eq!(status.pending, Some(GlobalBlockState::PendingProgression(12)));
+        assert_eq!(status.modules[1].last_sign_off, Some(GlobalBlockState::PendingProgression(11)));
+    }
+
     #[test]
     fn test_clear_pending_state() {
         let global_sync = GlobalBlockSync::new(10);
@@ -0,0 +1,156 @@
+use alloy::{
+    primitives::{keccak256, BlockNumber, Bytes, U256},
+    signers::{Signature, SignerSync}
+};
+use reth_network_peers::PeerId;
+use serde::{Deserialize, Serialize};
+
+use super::canonical::{
+    decode_header, encode_header, encode_list, CanonicalBytes, CanonicalEncoding, CanonicalError
+};
+use crate::primitive::{AngstromSigner, SigningDomain};
+
+const BUNDLE_HANDOFF_DOMAIN: &str = SigningDomain::BundleHandoff.tag();
+
+/// The leaders bundle, handed to the backup submitters of the round.
+///
+/// The leader sends it out before submitting the bundle and once more with
+/// `submitted` set after the relays accepted it. If a backup doesn't see a
+/// submission before its deadline, it submits the bundle itself and announces
+/// that the same way.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
+pub struct BundleHandoff {
+    pub block_height: BlockNumber,
+    pub source:       PeerId,
+    /// Backup submitters, in the order they take over
+    pub backups:      Vec<PeerId>,
+    /// `execute` calldata of the bundle
+    pub calldata:     Bytes,
+    /// whether `source` already submitted the bundle
+    pub submitted:    bool,
+    /// The signature is over the canonical encoding of all other fields
+    pub signature:    Signature
+}
+
+impl Default for BundleHandoff {
+    fn default() -> Self {
+        Self {
+            block_height: Default::default(),
+            source:       Default::default(),
+            backups:      Default::default(),
+            calldata:     Default::default(),
+            submitted:    Default::default(),
+            signature:    Signature::new(U256::ZERO, U256::ZERO, false)
+        }
+    }
+}
+
+impl BundleHandoff {
+    pub fn new(
+        block_height: BlockNumber,
+        sk: &AngstromSigner,
+        backups: Vec<PeerId>,
+        calldata: Bytes,
+        submitted: bool
+    ) -> Self {
+        let payload =
+            Self::serialize_payload(&block_height, &sk.id(), &backups, &calldata, submitted);
+        let signature = sk.sign_hash_sync(&keccak256(payload)).unwrap();
+
+        Self { block_height, source: sk.id(), backups, calldata, submitted, signature }
+    }
+
+    /// Announces that we submitted the bundle of this handoff.
+    pub fn to_submitted(&self, sk: &AngstromSigner) -> Self {
+        Self::new(self.block_height, sk, self.backups.clone(), self.calldata.clone(), true)
+    }
+
+    /// Position of the validator in the backup order.
+    pub fn backup_position(&self, validator: &PeerId) -> Option<usize> {
+        self.backups.iter().position(|backup| backup == validator)
+    }
+
+    pub fn is_valid(&self, block_height: &BlockNumber) -> bool {
+        let hash = keccak256(self.payload());
+        let Ok(source) = self.signature.recover_from_prehash(&hash) else {
+            return false;
+        };
+        let source = AngstromSigner::public_key_to_peer_id(&source);
+
+        source == self.source && &self.block_height == block_height
+    }
+
+    fn serialize_payload(
+        block_height: &BlockNumber,
+        source: &PeerId,
+        backups: &[PeerId],
+        calldata: &Bytes,
+        submitted: bool
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_header(BUNDLE_HANDOFF_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        source.canonical_encode(&mut buf);
+        encode_list(backups, &mut buf);
+        calldata.canonical_encode(&mut buf);
+        submitted.canonical_encode(&mut buf);
+        buf
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        Self::serialize_payload(
+            &self.block_height,
+            &self.source,
+            &self.backups,
+            &self.calldata,
+            self.submitted
+        )
+    }
+}
+
+impl CanonicalEncoding for BundleHandoff {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend(self.payload());
+        self.signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(BUNDLE_HANDOFF_DOMAIN, buf)?;
+        Ok(Self {
+            block_height: CanonicalEncoding::canonical_decode(buf)?,
+            source:       CanonicalEncoding::canonical_decode(buf)?,
+            backups:      CanonicalEncoding::canonical_decode(buf)?,
+            calldata:     CanonicalEncoding::canonical_decode(buf)?,
+            submitted:    CanonicalEncoding::canonical_decode(buf)?,
+            signature:    CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_and_round_trips() {
+        let (leader, backup) = (AngstromSigner::random(), AngstromSigner::random());
+        let handoff =
+            BundleHandoff::new(7, &leader, vec![backup.id()], Bytes::from_static(&[1, 2]), false);
+        assert!(handoff.is_valid(&7));
+        assert!(!handoff.is_valid(&8));
+        assert_eq!(handoff.backup_position(&backup.id()), Some(0));
+
+        let submitted = handoff.to_submitted(&backup);
+        assert!(submitted.is_valid(&7));
+        assert_eq!(submitted.source, backup.id());
+        assert_eq!(submitted.calldata, handoff.calldata);
+
+        let decoded = BundleHandoff::from_canonical_bytes(&submitted.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, submitted);
+
+        let mut forged = handoff;
+        forged.submitted = true;
+        assert!(!forged.is_valid(&7));
+    }
+}
@@ -0,0 +1,758 @@
+//! Canonical encoding of consensus payloads
+//!
+//! Consensus messages are signed over and sent to peers in this encoding. It
+//! is fully specified here, so the bytes don't depend on the serde
+//! implementation of any type:
+//!
+//! - messages start with their domain tag as a byte string followed by
+//!   [`CONSENSUS_ENCODING_VERSION`]
+//! - integers are big endian and fixed width, signed ones in two's complement,
+//!   [`U256`] is 32 bytes
+//! - `bool` is a single `0` or `1` byte
+//! - addresses, hashes and peer ids are their raw 20, 32 and 64 bytes
+//! - byte strings and lists are prefixed with their length as a `u32`
+//! - options are a `0` byte, or a `1` byte followed by the value
+//! - enums are a `u8` variant tag followed by the fields of the variant
+//! - orders are their ABI encoding, as a byte string
+//! - signatures are `r | s | y_parity`, BLS signatures their compressed 96
+//!   bytes
+//! - structs are their fields in declaration order
+use alloy::{
+    primitives::{Address, Bytes, FixedBytes, U256},
+    signers::Signature,
+    sol_types::SolValue
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    matching::Ray,
+    orders::{
+        NetAmmOrder, OrderFillState, OrderId, OrderLocation, OrderOutcome, OrderPriorityData,
+        PoolSolution, PricePeg, RingLeg
+    },
+    sol_bindings::{
+        grouped_orders::{
+            FlashVariants, GroupedVanillaOrder, OrderWithStorageData, StandingVariants
+        },
+        rpc_orders::{
+            ExactFlashOrder, ExactStandingOrder, PartialFlashOrder, PartialStandingOrder,
+            TopOfBlockOrder
+        },
+        RespendAvoidanceMethod
+    }
+};
+
+/// Version of the encoding, bumped whenever the layout of a message changes.
+pub const CONSENSUS_ENCODING_VERSION: u8 = 9;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CanonicalError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("{0} trailing bytes")]
+    TrailingBytes(usize),
+    #[error("invalid {0} tag: {1}")]
+    InvalidTag(&'static str, u8),
+    #[error("expected a {0} message")]
+    InvalidDomain(&'static str),
+    #[error("unsupported encoding version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid {0} length: {1}")]
+    InvalidLength(&'static str, usize),
+    #[error("failed to decode order: {0}")]
+    Order(String)
+}
+
+pub trait CanonicalEncoding: Sized {
+    fn canonical_encode(&self, out: &mut Vec<u8>);
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError>;
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.canonical_encode(&mut out);
+        out
+    }
+
+    /// Decodes a value that has to span the whole buffer.
+    fn from_canonical_bytes(mut buf: &[u8]) -> Result<Self, CanonicalError> {
+        let this = Self::canonical_decode(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(CanonicalError::TrailingBytes(buf.len()))
+        }
+
+        Ok(this)
+    }
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], CanonicalError> {
+    if buf.len() < len {
+        return Err(CanonicalError::UnexpectedEnd)
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+
+    Ok(head)
+}
+
+fn encode_len(len: usize, out: &mut Vec<u8>) {
+    (u32::try_from(len).expect("length fits in a u32")).canonical_encode(out);
+}
+
+fn decode_len(buf: &mut &[u8]) -> Result<usize, CanonicalError> {
+    u32::canonical_decode(buf).map(|len| len as usize)
+}
+
+fn decode_tag(buf: &mut &[u8]) -> Result<u8, CanonicalError> {
+    u8::canonical_decode(buf)
+}
+
+/// Writes the domain tag and version every message starts with.
+pub(crate) fn encode_header(domain: &'static str, out: &mut Vec<u8>) {
+    encode_len(domain.len(), out);
+    out.extend_from_slice(domain.as_bytes());
+    out.push(CONSENSUS_ENCODING_VERSION);
+}
+
+pub(crate) fn decode_header(domain: &'static str, buf: &mut &[u8]) -> Result<(), CanonicalError> {
+    let len = decode_len(buf)?;
+    if take(buf, len)? != domain.as_bytes() {
+        return Err(CanonicalError::InvalidDomain(domain))
+    }
+    match decode_tag(buf)? {
+        CONSENSUS_ENCODING_VERSION => Ok(()),
+        version => Err(CanonicalError::UnsupportedVersion(version))
+    }
+}
+
+macro_rules! canonical_uint {
+    ($($ty:ty),*) => {
+        $(
+            impl CanonicalEncoding for $ty {
+                fn canonical_encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+                    let bytes = take(buf, std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_be_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+canonical_uint!(u8, u32, u64, u128, i32);
+
+/// Implements the encoding for a struct as its fields in order.
+macro_rules! canonical_struct {
+    ($ty:ty { $($field:ident),* }) => {
+        impl CanonicalEncoding for $ty {
+            fn canonical_encode(&self, out: &mut Vec<u8>) {
+                $(self.$field.canonical_encode(out);)*
+            }
+
+            fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+                Ok(Self { $($field: CanonicalEncoding::canonical_decode(buf)?),* })
+            }
+        }
+    };
+}
+
+/// Implements the encoding for a order as its ABI encoding.
+macro_rules! canonical_order {
+    ($($ty:ty),*) => {
+        $(
+            impl CanonicalEncoding for $ty {
+                fn canonical_encode(&self, out: &mut Vec<u8>) {
+                    Bytes::from(self.abi_encode()).canonical_encode(out);
+                }
+
+                fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+                    let bytes = Bytes::canonical_decode(buf)?;
+                    <$ty as SolValue>::abi_decode(&bytes, true)
+                        .map_err(|e| CanonicalError::Order(e.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+canonical_order!(
+    PartialStandingOrder,
+    ExactStandingOrder,
+    PartialFlashOrder,
+    ExactFlashOrder,
+    TopOfBlockOrder
+);
+
+impl CanonicalEncoding for bool {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(CanonicalError::InvalidTag("bool", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for U256 {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes::<32>());
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        Ok(U256::from_be_slice(take(buf, 32)?))
+    }
+}
+
+impl<const N: usize> CanonicalEncoding for FixedBytes<N> {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_slice());
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        Ok(FixedBytes::from_slice(take(buf, N)?))
+    }
+}
+
+impl CanonicalEncoding for Address {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_slice());
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        Ok(Address::from_slice(take(buf, 20)?))
+    }
+}
+
+impl CanonicalEncoding for Bytes {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        encode_len(self.len(), out);
+        out.extend_from_slice(self);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        let len = decode_len(buf)?;
+        Ok(Bytes::copy_from_slice(take(buf, len)?))
+    }
+}
+
+/// Encodes a list, same as the encoding of a [`Vec`].
+pub(crate) fn encode_list<T: CanonicalEncoding>(items: &[T], out: &mut Vec<u8>) {
+    encode_len(items.len(), out);
+    items.iter().for_each(|item| item.canonical_encode(out));
+}
+
+impl<T: CanonicalEncoding> CanonicalEncoding for Vec<T> {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        encode_list(self, out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        let len = decode_len(buf)?;
+        // every item is at least one byte, don't trust the length for the allocation
+        if len > buf.len() {
+            return Err(CanonicalError::InvalidLength("list", len))
+        }
+
+        (0..len).map(|_| T::canonical_decode(buf)).collect()
+    }
+}
+
+impl<T: CanonicalEncoding> CanonicalEncoding for Option<T> {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(value) => {
+                out.push(1);
+                value.canonical_encode(out);
+            }
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => Ok(None),
+            1 => T::canonical_decode(buf).map(Some),
+            tag => Err(CanonicalError::InvalidTag("option", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for Signature {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        self.r().canonical_encode(out);
+        self.s().canonical_encode(out);
+        self.v().canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        let r = U256::canonical_decode(buf)?;
+        let s = U256::canonical_decode(buf)?;
+        let y_parity = bool::canonical_decode(buf)?;
+
+        Ok(Signature::new(r, s, y_parity))
+    }
+}
+
+impl CanonicalEncoding for Ray {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        self.0.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        U256::canonical_decode(buf).map(Ray)
+    }
+}
+
+impl CanonicalEncoding for OrderLocation {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            OrderLocation::Limit => out.push(0),
+            OrderLocation::Searcher => out.push(1)
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => Ok(OrderLocation::Limit),
+            1 => Ok(OrderLocation::Searcher),
+            tag => Err(CanonicalError::InvalidTag("order location", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for RespendAvoidanceMethod {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        let (tag, value) = match self {
+            RespendAvoidanceMethod::Nonce(nonce) => (0u8, nonce),
+            RespendAvoidanceMethod::Block(block) => (1u8, block)
+        };
+        out.push(tag);
+        value.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => u64::canonical_decode(buf).map(RespendAvoidanceMethod::Nonce),
+            1 => u64::canonical_decode(buf).map(RespendAvoidanceMethod::Block),
+            tag => Err(CanonicalError::InvalidTag("respend avoidance", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for OrderFillState {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            OrderFillState::Unfilled => out.push(0),
+            OrderFillState::CompleteFill => out.push(1),
+            OrderFillState::PartialFill(quantity) => {
+                out.push(2);
+                quantity.canonical_encode(out);
+            }
+            OrderFillState::Killed => out.push(3),
+            OrderFillState::OutsidePriceBand => out.push(4)
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => Ok(OrderFillState::Unfilled),
+            1 => Ok(OrderFillState::CompleteFill),
+            2 => u128::canonical_decode(buf).map(OrderFillState::PartialFill),
+            3 => Ok(OrderFillState::Killed),
+            4 => Ok(OrderFillState::OutsidePriceBand),
+            tag => Err(CanonicalError::InvalidTag("fill state", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for NetAmmOrder {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        let (tag, a, b) = match self {
+            NetAmmOrder::Buy(a, b) => (0u8, a, b),
+            NetAmmOrder::Sell(a, b) => (1u8, a, b)
+        };
+        out.push(tag);
+        a.canonical_encode(out);
+        b.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        let tag = decode_tag(buf)?;
+        let a = u128::canonical_decode(buf)?;
+        let b = u128::canonical_decode(buf)?;
+        match tag {
+            0 => Ok(NetAmmOrder::Buy(a, b)),
+            1 => Ok(NetAmmOrder::Sell(a, b)),
+            tag => Err(CanonicalError::InvalidTag("amm order", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for StandingVariants {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            StandingVariants::Partial(order) => {
+                out.push(0);
+                order.canonical_encode(out);
+            }
+            StandingVariants::Exact(order) => {
+                out.push(1);
+                order.canonical_encode(out);
+            }
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => CanonicalEncoding::canonical_decode(buf).map(StandingVariants::Partial),
+            1 => CanonicalEncoding::canonical_decode(buf).map(StandingVariants::Exact),
+            tag => Err(CanonicalError::InvalidTag("standing order", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for FlashVariants {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            FlashVariants::Partial(order) => {
+                out.push(0);
+                order.canonical_encode(out);
+            }
+            FlashVariants::Exact(order) => {
+                out.push(1);
+                order.canonical_encode(out);
+            }
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => CanonicalEncoding::canonical_decode(buf).map(FlashVariants::Partial),
+            1 => CanonicalEncoding::canonical_decode(buf).map(FlashVariants::Exact),
+            tag => Err(CanonicalError::InvalidTag("flash order", tag))
+        }
+    }
+}
+
+impl CanonicalEncoding for GroupedVanillaOrder {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        match self {
+            GroupedVanillaOrder::Standing(order) => {
+                out.push(0);
+                order.canonical_encode(out);
+            }
+            GroupedVanillaOrder::KillOrFill(order) => {
+                out.push(1);
+                order.canonical_encode(out);
+            }
+        }
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        match decode_tag(buf)? {
+            0 => CanonicalEncoding::canonical_decode(buf).map(GroupedVanillaOrder::Standing),
+            1 => CanonicalEncoding::canonical_decode(buf).map(GroupedVanillaOrder::KillOrFill),
+            tag => Err(CanonicalError::InvalidTag("vanilla order", tag))
+        }
+    }
+}
+
+canonical_struct!(OrderPriorityData { price, volume, gas, gas_units });
+canonical_struct!(OrderId {
+    address,
+    pool_id,
+    hash,
+    reuse_avoidance,
+    deadline,
+    flash_block,
+    location
+});
+canonical_struct!(OrderOutcome { id, outcome });
+canonical_struct!(RingLeg { ring, legs, order, token_in, token_out, quantity_in, quantity_out });
+canonical_struct!(PoolSolution { id, ucp, searcher, amm_quantity, limit, ring });
+canonical_struct!(PricePeg { order_hash, offset_bps, signature });
+
+impl<O: CanonicalEncoding> CanonicalEncoding for OrderWithStorageData<O> {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        self.order.canonical_encode(out);
+        self.priority_data.canonical_encode(out);
+        self.invalidates.canonical_encode(out);
+        self.pool_id.canonical_encode(out);
+        self.is_currently_valid.canonical_encode(out);
+        self.is_bid.canonical_encode(out);
+        self.is_valid.canonical_encode(out);
+        self.valid_block.canonical_encode(out);
+        self.order_id.canonical_encode(out);
+        self.tob_reward.canonical_encode(out);
+        self.peg.canonical_encode(out);
+        self.bond_tier.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        Ok(Self {
+            order:              CanonicalEncoding::canonical_decode(buf)?,
+            priority_data:      CanonicalEncoding::canonical_decode(buf)?,
+            invalidates:        CanonicalEncoding::canonical_decode(buf)?,
+            pool_id:            CanonicalEncoding::canonical_decode(buf)?,
+            is_currently_valid: CanonicalEncoding::canonical_decode(buf)?,
+            is_bid:             CanonicalEncoding::canonical_decode(buf)?,
+            is_valid:           CanonicalEncoding::canonical_decode(buf)?,
+            valid_block:        CanonicalEncoding::canonical_decode(buf)?,
+            order_id:           CanonicalEncoding::canonical_decode(buf)?,
+            tob_reward:         CanonicalEncoding::canonical_decode(buf)?,
+            peg:                CanonicalEncoding::canonical_decode(buf)?,
+            bond_tier:          CanonicalEncoding::canonical_decode(buf)?,
+            // listed by the pre-proposal that carries the order instead
+            is_private:         false,
+            is_hidden:          false
+        })
+    }
+}
+
+/// Serde representation of the consensus messages, makes the network codec
+/// send the canonical encoding instead of deriving one from the fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonicalBytes(pub Vec<u8>);
+
+macro_rules! canonical_serde {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for CanonicalBytes {
+                fn from(value: $ty) -> Self {
+                    CanonicalBytes(value.to_canonical_bytes())
+                }
+            }
+
+            impl TryFrom<CanonicalBytes> for $ty {
+                type Error = CanonicalError;
+
+                fn try_from(value: CanonicalBytes) -> Result<Self, Self::Error> {
+                    <$ty>::from_canonical_bytes(&value.0)
+                }
+            }
+        )*
+    };
+}
+
+canonical_serde!(
+    super::PreProposal,
+    super::PreProposalAggregation,
+    super::Proposal,
+    super::BundleHandoff,
+    super::KeyHandover,
+    super::ProposalShare
+);
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, b256, hex, B256};
+
+    use super::*;
+    use crate::{
+        consensus::{PreProposal, PreProposalAggregation, Proposal},
+        primitive::{AngstromSigner, PeerId}
+    };
+
+    fn signature() -> Signature {
+        Signature::new(U256::from(2), U256::from(3), true)
+    }
+
+    fn pre_proposal() -> PreProposal {
+        PreProposal {
+            block_height:     100,
+            source:           PeerId::repeat_byte(0x11),
+            limit:            vec![],
+            searcher:         vec![],
+            forced_inclusion: vec![],
+            private_orders:   vec![],
+            first_seen:       vec![],
+            signature:        signature()
+        }
+    }
+
+    #[test]
+    fn pre_proposal_test_vector() {
+        let expected = hex::decode(concat!(
+            // domain
+            "0000000c",
+            "7072655f70726f706f73616c",
+            // version
+            "09",
+            // block height
+            "0000000000000064",
+            // source
+            "11111111111111111111111111111111111111111111111111111111111111111111111111111111",
+            "111111111111111111111111111111111111111111111111",
+            // limit, searcher, forced inclusion, private orders, first seen
+            "00000000",
+            "00000000",
+            "00000000",
+            "00000000",
+            "00000000",
+            // signature
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "01"
+        ))
+        .unwrap();
+
+        let pre_proposal = pre_proposal();
+        assert_eq!(pre_proposal.to_canonical_bytes(), expected);
+        assert_eq!(PreProposal::from_canonical_bytes(&expected).unwrap(), pre_proposal);
+    }
+
+    #[test]
+    fn proposal_test_vector() {
+        let proposal = Proposal {
+            block_height: 7,
+            source:       PeerId::ZERO,
+            preproposals: vec![],
+            solutions:    vec![PoolSolution {
+                id:           b256!(
+                    "00000000000000000000000000000000000000000000000000000000000000aa"
+                ),
+                ucp:          Ray(U256::from(1)),
+                searcher:     None,
+                amm_quantity: Some(NetAmmOrder::Sell(5, 6)),
+                limit:        vec![],
+                ring:         None
+            }],
+            signature:    signature()
+        };
+
+        let expected = hex::decode(concat!(
+            // domain, version
+            "00000008",
+            "70726f706f73616c",
+            "09",
+            // block height
+            "0000000000000007",
+            // source
+            "00000000000000000000000000000000000000000000000000000000000000000000000000000000",
+            "000000000000000000000000000000000000000000000000",
+            // preproposals
+            "00000000",
+            // solutions
+            "00000001",
+            "00000000000000000000000000000000000000000000000000000000000000aa",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00",
+            "01",
+            "01",
+            "00000000000000000000000000000005",
+            "00000000000000000000000000000006",
+            "00000000",
+            "00",
+            // signature
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "01"
+        ))
+        .unwrap();
+
+        assert_eq!(proposal.to_canonical_bytes(), expected);
+        assert_eq!(Proposal::from_canonical_bytes(&expected).unwrap(), proposal);
+    }
+
+    #[test]
+    fn orders_round_trip() {
+        let order = PartialStandingOrder {
+            max_amount_in: 100,
+            asset_in: address!("1111111111111111111111111111111111111111"),
+            nonce: 4,
+            ..Default::default()
+        };
+        let order = OrderWithStorageData {
+            order: GroupedVanillaOrder::Standing(StandingVariants::Partial(order)),
+            invalidates: vec![B256::repeat_byte(3)],
+            is_bid: true,
+            valid_block: 9,
+            order_id: OrderId {
+                reuse_avoidance: RespendAvoidanceMethod::Nonce(4),
+                deadline: Some(U256::from(10)),
+                location: OrderLocation::Limit,
+                ..Default::default()
+            },
+            peg: Some(PricePeg {
+                order_hash: B256::repeat_byte(5),
+                offset_bps: -25,
+                signature:  signature()
+            }),
+            ..Default::default()
+        };
+
+        let searcher = OrderWithStorageData { bond_tier: 2, ..Default::default() };
+
+        let sk = AngstromSigner::random();
+        let pre_proposal =
+            PreProposal::generate_pre_proposal(100, &sk, vec![order], vec![searcher]);
+        let decoded =
+            PreProposal::from_canonical_bytes(&pre_proposal.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, pre_proposal);
+        assert!(decoded.is_valid(&100));
+
+        let aggregation = PreProposalAggregation::new(100, &sk, vec![pre_proposal]);
+        let decoded =
+            PreProposalAggregation::from_canonical_bytes(&aggregation.to_canonical_bytes())
+                .unwrap();
+        assert_eq!(decoded, aggregation);
+        assert!(decoded.is_valid(&100));
+
+        // network encoding goes through the canonical bytes
+        let wire = bincode::serialize(&aggregation).unwrap();
+        assert_eq!(bincode::deserialize::<PreProposalAggregation>(&wire).unwrap(), aggregation);
+    }
+
+    #[test]
+    fn fill_states_round_trip() {
+        let states = [
+            OrderFillState::Unfilled,
+            OrderFillState::CompleteFill,
+            OrderFillState::PartialFill(5),
+            OrderFillState::Killed,
+            OrderFillState::OutsidePriceBand
+        ];
+        for (tag, state) in states.into_iter().enumerate() {
+            let mut out = vec![];
+            state.canonical_encode(&mut out);
+            assert_eq!(out[0], tag as u8);
+            assert_eq!(OrderFillState::canonical_decode(&mut out.as_slice()), Ok(state));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let mut bytes = pre_proposal().to_canonical_bytes();
+
+        assert_eq!(
+            Proposal::from_canonical_bytes(&bytes),
+            Err(CanonicalError::InvalidDomain("proposal"))
+        );
+
+        bytes.push(0);
+        assert_eq!(
+            PreProposal::from_canonical_bytes(&bytes),
+            Err(CanonicalError::TrailingBytes(1))
+        );
+
+        let version = 4 + "pre_proposal".len();
+        bytes[version] = CONSENSUS_ENCODING_VERSION + 1;
+        assert_eq!(
+            PreProposal::from_canonical_bytes(&bytes),
+            Err(CanonicalError::UnsupportedVersion(CONSENSUS_ENCODING_VERSION + 1))
+        );
+
+        assert_eq!(
+            PreProposal::from_canonical_bytes(&bytes[..10]),
+            Err(CanonicalError::UnexpectedEnd)
+        );
+    }
+}
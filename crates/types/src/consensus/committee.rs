@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path
+};
+
+use alloy::primitives::{BlockNumber, B256};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    threshold::{self, BlsPublicKey, BlsSignature, ThresholdPublicKey},
+    ProposalShare
+};
+use crate::primitive::PeerId;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CommitteeError {
+    #[error("the committee has no members")]
+    Empty,
+    #[error("a threshold of {threshold} can't be met by {members} members")]
+    InvalidThreshold { threshold: usize, members: usize },
+    #[error("{0:?} is in the committee twice")]
+    DuplicateMember(PeerId),
+    #[error("the committee key has {shares} shares for {members} members")]
+    KeyShares { shares: usize, members: usize }
+}
+
+/// The validators that co-sign the proposals of the rounds.
+///
+/// The members share a threshold BLS key, see [`threshold`]. The proposal of
+/// a round is only submitted and counted once `threshold` members verified
+/// and signed it, their shares combine into a single signature of the
+/// committee key over the proposal. The committee is part of the config every
+/// validator runs with, along with the validator set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedCommittee")]
+pub struct ProposalCommittee {
+    members:    Vec<PeerId>,
+    threshold:  usize,
+    /// the share of the member at position `i` has index `i + 1`
+    public_key: ThresholdPublicKey
+}
+
+#[derive(Deserialize)]
+struct UncheckedCommittee {
+    members:    Vec<PeerId>,
+    threshold:  usize,
+    public_key: ThresholdPublicKey
+}
+
+impl TryFrom<UncheckedCommittee> for ProposalCommittee {
+    type Error = CommitteeError;
+
+    fn try_from(committee: UncheckedCommittee) -> Result<Self, Self::Error> {
+        Self::new(committee.members, committee.threshold, committee.public_key)
+    }
+}
+
+impl ProposalCommittee {
+    pub fn new(
+        members: Vec<PeerId>,
+        threshold: usize,
+        public_key: ThresholdPublicKey
+    ) -> Result<Self, CommitteeError> {
+        if members.is_empty() {
+            return Err(CommitteeError::Empty)
+        }
+        if threshold == 0 || threshold > members.len() {
+            return Err(CommitteeError::InvalidThreshold { threshold, members: members.len() })
+        }
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = members.iter().find(|member| !seen.insert(**member)) {
+            return Err(CommitteeError::DuplicateMember(*duplicate))
+        }
+        if public_key.shares.len() != members.len() {
+            return Err(CommitteeError::KeyShares {
+                shares:  public_key.shares.len(),
+                members: members.len()
+            })
+        }
+
+        Ok(Self { members, threshold, public_key })
+    }
+
+    /// Reads a committee file, as written by the bootstrap tooling of the
+    /// testing tools.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+
+    pub fn members(&self) -> &[PeerId] {
+        &self.members
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The key the signatures of the committee verify against.
+    pub fn public_key(&self) -> &BlsPublicKey {
+        &self.public_key.public_key
+    }
+
+    pub fn is_member(&self, validator: &PeerId) -> bool {
+        self.members.contains(validator)
+    }
+
+    /// Index of the key share of the member.
+    pub fn index_of(&self, member: &PeerId) -> Option<u64> {
+        let position = self.members.iter().position(|m| m == member)?;
+        Some(position as u64 + 1)
+    }
+
+    /// Public key of the key share of the member.
+    pub fn share_key(&self, member: &PeerId) -> Option<&BlsPublicKey> {
+        self.public_key.share(self.index_of(member)?)
+    }
+}
+
+/// The shares collected on a proposal, by the member that signed them, and the
+/// signature of the committee they combine into.
+///
+/// Members are known by their validator ids, the shares can be sent by any
+/// key the member signs with at the time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdCertificate {
+    pub block_height:  BlockNumber,
+    pub proposal_hash: B256,
+    pub shares:        HashMap<PeerId, ProposalShare>,
+    /// the signature of the committee, once `threshold` members signed
+    pub signature:     Option<BlsSignature>
+}
+
+impl ThresholdCertificate {
+    pub fn new(block_height: BlockNumber, proposal_hash: B256) -> Self {
+        Self { block_height, proposal_hash, shares: HashMap::new(), signature: None }
+    }
+
+    /// Adds the share of `member`, returns whether it's a new valid share of
+    /// the proposal. Combines the shares once there are enough of them.
+    pub fn add_share(
+        &mut self,
+        committee: &ProposalCommittee,
+        member: PeerId,
+        share: ProposalShare
+    ) -> bool {
+        let Some(share_key) = committee.share_key(&member) else { return false };
+        if share.proposal_hash != self.proposal_hash
+            || self.shares.contains_key(&member)
+            || !share.is_valid(&self.block_height, share_key)
+        {
+            return false
+        }
+
+        self.shares.insert(member, share);
+        if self.signature.is_none() && self.shares.len() >= committee.threshold() {
+            self.signature = self.combine(committee);
+        }
+        true
+    }
+
+    /// Whether enough members of the committee signed the proposal.
+    pub fn is_complete(&self) -> bool {
+        self.signature.is_some()
+    }
+
+    /// Combines the first `threshold` shares by index, every share was
+    /// checked so the result only fails to verify if the committee key is
+    /// wrong.
+    fn combine(&self, committee: &ProposalCommittee) -> Option<BlsSignature> {
+        let mut shares = self
+            .shares
+            .iter()
+            .filter_map(|(member, share)| Some((committee.index_of(member)?, share.signature)))
+            .collect::<Vec<_>>();
+        shares.sort_unstable_by_key(|(index, _)| *index);
+        shares.truncate(committee.threshold());
+
+        let signature = threshold::combine(&shares)?;
+        let payload = ProposalShare::signing_payload(self.block_height, self.proposal_hash);
+        if !threshold::verify(committee.public_key(), &payload, &signature) {
+            tracing::error!(
+                proposal_hash = ?self.proposal_hash,
+                "the shares of the committee don't combine into a signature of its key"
+            );
+            return None
+        }
+
+        Some(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::threshold::{generate, KeyShare};
+
+    fn committee(threshold: usize, members: usize) -> (ProposalCommittee, Vec<KeyShare>) {
+        let (public_key, key_shares) = generate(threshold, members);
+        let members = (0..members).map(|_| PeerId::random()).collect();
+        (ProposalCommittee::new(members, threshold, public_key).unwrap(), key_shares)
+    }
+
+    #[test]
+    fn rejects_invalid_committees() {
+        let (public_key, _) = generate(2, 3);
+        let members = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+        let new = |members: Vec<PeerId>, threshold| {
+            ProposalCommittee::new(members, threshold, public_key.clone())
+        };
+        assert!(new(members.clone(), 2).is_ok());
+        assert_eq!(new(vec![], 1), Err(CommitteeError::Empty));
+        assert_eq!(
+            new(members.clone(), 4),
+            Err(CommitteeError::InvalidThreshold { threshold: 4, members: 3 })
+        );
+        assert_eq!(
+            new(vec![members[0], members[0], members[1]], 1),
+            Err(CommitteeError::DuplicateMember(members[0]))
+        );
+        assert_eq!(
+            new(members[..2].to_vec(), 2),
+            Err(CommitteeError::KeyShares { shares: 3, members: 2 })
+        );
+    }
+
+    #[test]
+    fn completes_with_threshold_distinct_members() {
+        let (committee, key_shares) = committee(2, 3);
+        let members = committee.members().to_vec();
+        let hash = B256::repeat_byte(1);
+        let share = |i: usize| ProposalShare::new(5, members[i], &key_shares[i], hash);
+
+        let mut certificate = ThresholdCertificate::new(5, hash);
+        assert!(certificate.add_share(&committee, members[0], share(0)));
+        assert!(!certificate.add_share(&committee, members[0], share(0)));
+        assert!(!certificate.is_complete());
+
+        // shares of another proposal, by outsiders or signed with the key
+        // share of another member don't count
+        let other = ProposalShare::new(5, members[1], &key_shares[1], B256::repeat_byte(2));
+        assert!(!certificate.add_share(&committee, members[1], other));
+        assert!(!certificate.add_share(&committee, PeerId::random(), share(1)));
+        assert!(!certificate.add_share(&committee, members[2], share(1)));
+        assert!(!certificate.is_complete());
+
+        assert!(certificate.add_share(&committee, members[2], share(2)));
+        let signature = certificate.signature.unwrap();
+        let payload = ProposalShare::signing_payload(5, hash);
+        assert!(threshold::verify(committee.public_key(), &payload, &signature));
+    }
+}
@@ -1,14 +1,34 @@
 use thiserror::Error;
 
+use super::PreProposal;
+use crate::primitive::PeerId;
+
 #[derive(Debug, Error)]
 pub enum EvidenceError {
     #[error("invalid evidence")]
-    InvalidEvidence
+    InvalidEvidence,
+    #[error("pre-proposals are not from the same validator and height")]
+    DifferentRound,
+    #[error("pre-proposals don't conflict")]
+    NotConflicting,
+    #[error("pre-proposal signature doesn't match its source")]
+    InvalidSignature
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Evidence {
-    DuplicateVoteEvidence(DuplicateVoteEvidence)
+    DuplicateVoteEvidence(DuplicateVoteEvidence),
+    PreProposalEquivocation(PreProposalEquivocation)
+}
+
+impl Evidence {
+    /// The validator that misbehaved, if the evidence names one.
+    pub fn offender(&self) -> Option<PeerId> {
+        match self {
+            Self::DuplicateVoteEvidence(_) => None,
+            Self::PreProposalEquivocation(e) => Some(e.validator())
+        }
+    }
 }
 
 /// Duplicate vote evidence
@@ -26,3 +46,77 @@ impl DuplicateVoteEvidence {
         Ok(Self { total_voting_power: Default::default(), validator_power: Default::default() })
     }
 }
+
+/// Two signed pre-proposals from the same validator for the same height with
+/// different contents. Both signatures are checked on construction so the
+/// evidence can be handed to anyone without them having to trust us.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PreProposalEquivocation {
+    pub first:  PreProposal,
+    pub second: PreProposal
+}
+
+impl PreProposalEquivocation {
+    pub fn new(first: PreProposal, second: PreProposal) -> Result<Self, EvidenceError> {
+        if first.source != second.source || first.block_height != second.block_height {
+            return Err(EvidenceError::DifferentRound)
+        }
+        if first.content() == second.content() {
+            return Err(EvidenceError::NotConflicting)
+        }
+        if !first.is_valid(&first.block_height) || !second.is_valid(&second.block_height) {
+            return Err(EvidenceError::InvalidSignature)
+        }
+
+        Ok(Self { first, second })
+    }
+
+    pub fn validator(&self) -> PeerId {
+        self.first.source
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.first.block_height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitive::AngstromSigner,
+        sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
+    };
+
+    #[test]
+    fn equivocation_requires_conflicting_signed_pre_proposals() {
+        let sk = AngstromSigner::random();
+        let first = PreProposal::generate_pre_proposal(10, &sk, vec![], vec![]);
+        let mut order = OrderWithStorageData::<TopOfBlockOrder>::default();
+        order.order_id.hash = [1; 32].into();
+        let second = PreProposal::generate_pre_proposal(10, &sk, vec![], vec![order]);
+
+        let evidence = PreProposalEquivocation::new(first.clone(), second.clone()).unwrap();
+        assert_eq!(evidence.validator(), sk.id());
+        assert_eq!(evidence.block_height(), 10);
+
+        assert!(matches!(
+            PreProposalEquivocation::new(first.clone(), first.clone()),
+            Err(EvidenceError::NotConflicting)
+        ));
+
+        let other_height = PreProposal::generate_pre_proposal(11, &sk, vec![], vec![]);
+        assert!(matches!(
+            PreProposalEquivocation::new(first.clone(), other_height),
+            Err(EvidenceError::DifferentRound)
+        ));
+
+        let mut forged = second;
+        forged.searcher.clear();
+        forged.limit.push(Default::default());
+        assert!(matches!(
+            PreProposalEquivocation::new(first, forged),
+            Err(EvidenceError::InvalidSignature)
+        ));
+    }
+}
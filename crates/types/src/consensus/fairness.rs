@@ -0,0 +1,175 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, RwLock}
+};
+
+use alloy::primitives::{BlockNumber, B256};
+use serde::{Deserialize, Serialize};
+
+use super::Proposal;
+use crate::primitive::PeerId;
+
+/// Audits kept for the RPC, the scores of the leaders cover all rounds.
+const KEPT_AUDITS: usize = 256;
+
+/// What the proposal of a round included of the orders we had validated by
+/// the aggregation cutoff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionAudit {
+    pub block_number: BlockNumber,
+    pub leader:       PeerId,
+    /// orders we had validated by the cutoff
+    pub expected:     usize,
+    /// hashes of the expected orders no pre-proposal of the proposal carried
+    pub excluded:     Vec<B256>
+}
+
+impl InclusionAudit {
+    pub fn new(leader: PeerId, expected: &HashSet<B256>, proposal: &Proposal) -> Self {
+        let included = proposal
+            .flattened_pre_proposals()
+            .iter()
+            .flat_map(|pre| {
+                pre.limit
+                    .iter()
+                    .map(|order| order.order_id.hash)
+                    .chain(pre.searcher.iter().map(|order| order.order_id.hash))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>();
+        let mut excluded = expected.difference(&included).copied().collect::<Vec<_>>();
+        excluded.sort_unstable();
+
+        Self { block_number: proposal.block_height, leader, expected: expected.len(), excluded }
+    }
+
+    /// Share of the expected orders the proposal included, 1 if we expected
+    /// none.
+    pub fn score(&self) -> f64 {
+        if self.expected == 0 {
+            return 1.0
+        }
+        1.0 - self.excluded.len() as f64 / self.expected as f64
+    }
+}
+
+/// How fairly a leader included the orders in its proposals, over all rounds
+/// it led since the node started. A leader that keeps leaving out orders
+/// every other validator saw is censoring them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InclusionFairness {
+    pub leader:          PeerId,
+    pub rounds_audited:  u64,
+    pub expected_orders: u64,
+    pub excluded_orders: u64,
+    /// share of the expected orders the proposals of the leader included
+    pub score:           f64,
+    pub last_round:      BlockNumber
+}
+
+impl InclusionFairness {
+    fn record(&mut self, audit: &InclusionAudit) {
+        self.rounds_audited += 1;
+        self.expected_orders += audit.expected as u64;
+        self.excluded_orders += audit.excluded.len() as u64;
+        self.score = if self.expected_orders == 0 {
+            1.0
+        } else {
+            1.0 - self.excluded_orders as f64 / self.expected_orders as f64
+        };
+        self.last_round = self.last_round.max(audit.block_number);
+    }
+}
+
+/// Inclusion fairness of every leader, shared between the consensus, which
+/// audits the rounds, and the RPC, which serves the scores.
+#[derive(Debug, Clone, Default)]
+pub struct InclusionFairnessStore {
+    leaders: Arc<RwLock<HashMap<PeerId, InclusionFairness>>>,
+    audits:  Arc<RwLock<VecDeque<InclusionAudit>>>
+}
+
+impl InclusionFairnessStore {
+    pub fn record(&self, audit: InclusionAudit) {
+        self.leaders
+            .write()
+            .expect("poisoned")
+            .entry(audit.leader)
+            .or_insert_with(|| InclusionFairness { leader: audit.leader, ..Default::default() })
+            .record(&audit);
+
+        let mut audits = self.audits.write().expect("poisoned");
+        if audits.len() == KEPT_AUDITS {
+            audits.pop_front();
+        }
+        audits.push_back(audit);
+    }
+
+    pub fn leader(&self, leader: &PeerId) -> Option<InclusionFairness> {
+        self.leaders.read().expect("poisoned").get(leader).cloned()
+    }
+
+    /// All leaders, ordered by peer id.
+    pub fn summary(&self) -> Vec<InclusionFairness> {
+        let mut summary = self
+            .leaders
+            .read()
+            .expect("poisoned")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        summary.sort_unstable_by_key(|fairness| fairness.leader);
+
+        summary
+    }
+
+    /// The audit of the round of the block, if it's one of the last ones.
+    pub fn audit(&self, block_number: BlockNumber) -> Option<InclusionAudit> {
+        self.audits
+            .read()
+            .expect("poisoned")
+            .iter()
+            .find(|audit| audit.block_number == block_number)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        consensus::{PreProposal, PreProposalAggregation},
+        primitive::AngstromSigner,
+        sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
+    };
+
+    #[test]
+    fn scores_the_orders_the_leader_left_out() {
+        let (leader, validator) = (AngstromSigner::random(), AngstromSigner::random());
+        let order = |hash: B256| {
+            let mut order = OrderWithStorageData::<GroupedVanillaOrder>::default();
+            order.order_id.hash = hash;
+            order
+        };
+        let (included, excluded) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        let pre_proposal =
+            PreProposal::generate_pre_proposal(7, &validator, vec![order(included)], vec![]);
+        let aggregation = PreProposalAggregation::new(7, &leader, vec![pre_proposal]);
+        let proposal = Proposal::generate_proposal(7, &leader, vec![aggregation], vec![]);
+
+        let audit =
+            InclusionAudit::new(leader.id(), &HashSet::from([included, excluded]), &proposal);
+        assert_eq!(audit.excluded, vec![excluded]);
+        assert_eq!(audit.score(), 0.5);
+
+        let store = InclusionFairnessStore::default();
+        store.record(audit.clone());
+        store.record(InclusionAudit::new(leader.id(), &HashSet::from([included]), &proposal));
+
+        let fairness = store.leader(&leader.id()).unwrap();
+        assert_eq!((fairness.rounds_audited, fairness.expected_orders), (2, 3));
+        assert_eq!(fairness.excluded_orders, 1);
+        assert_eq!(store.audit(7), Some(audit));
+        assert_eq!(store.summary().len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use alloy::primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matching::{PoolMatchingConfig, SelfTradePolicy},
+    primitive::{HookPolicy, PoolId}
+};
+
+/// The parameters of the protocol every validator has to run with, as they
+/// change the solutions the validators agree on.
+///
+/// Validators exchange the hash of their genesis when they connect and only
+/// admit peers on the same one, so a validator can't match with parameters of
+/// its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolGenesis {
+    /// pools that aren't matched with the default config
+    pub pool_matching: Vec<PoolMatchingEntry>,
+    /// hooks the pools can have, pools with other hooks aren't matched. the
+    /// angstrom hook is always allowed
+    pub hook_policy:   HookPolicy,
+    /// pools matched by solving for the most surplus instead of with the
+    /// volume matcher
+    pub lp_pools:      Vec<PoolId>,
+    /// keeps bids and asks of the same address from matching each other,
+    /// unset lets them match
+    pub self_trade:    Option<SelfTradePolicy>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolMatchingEntry {
+    pub pool_id: PoolId,
+    #[serde(default)]
+    pub config:  PoolMatchingConfig
+}
+
+impl ProtocolGenesis {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+
+    /// Hash of the parameters, the same for every ordering of the pools.
+    pub fn hash(&self) -> B256 {
+        let mut genesis = self.clone();
+        genesis
+            .pool_matching
+            .sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+        genesis.lp_pools.sort();
+
+        keccak256(serde_json::to_vec(&genesis).expect("genesis serializes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+
+    use super::*;
+    use crate::matching::Ray;
+
+    fn entry(byte: u8, tick: u64) -> PoolMatchingEntry {
+        PoolMatchingEntry {
+            pool_id: PoolId::repeat_byte(byte),
+            config:  PoolMatchingConfig {
+                tick_size: Some(Ray::from(U256::from(tick))),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn hashes_the_parameters_not_their_order() {
+        let genesis = |pool_matching| ProtocolGenesis { pool_matching, ..Default::default() };
+        let ours = genesis(vec![entry(1, 100), entry(2, 10)]);
+        assert_eq!(ours.hash(), genesis(vec![entry(2, 10), entry(1, 100)]).hash());
+
+        assert_ne!(ours.hash(), genesis(vec![entry(1, 100), entry(2, 20)]).hash());
+        assert_ne!(ours.hash(), ProtocolGenesis::default().hash());
+        let denied = ProtocolGenesis {
+            hook_policy: HookPolicy { denied: vec![Address::repeat_byte(1)], ..Default::default() },
+            ..ours.clone()
+        };
+        assert_ne!(ours.hash(), denied.hash());
+        let lp = |lp_pools| ProtocolGenesis { lp_pools, ..ours.clone() };
+        assert_eq!(
+            lp(vec![PoolId::repeat_byte(1), PoolId::repeat_byte(2)]).hash(),
+            lp(vec![PoolId::repeat_byte(2), PoolId::repeat_byte(1)]).hash()
+        );
+        assert_ne!(ours.hash(), lp(vec![PoolId::repeat_byte(1)]).hash());
+        let self_trade = |self_trade| ProtocolGenesis { self_trade, ..ours.clone() };
+        assert_ne!(ours.hash(), self_trade(Some(SelfTradePolicy::CancelNewest)).hash());
+        assert_ne!(
+            self_trade(Some(SelfTradePolicy::CancelNewest)).hash(),
+            self_trade(Some(SelfTradePolicy::DecrementBoth)).hash()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("genesis.json");
+        let ours = self_trade(Some(SelfTradePolicy::DecrementBoth));
+        ours.save(&path).unwrap();
+        assert_eq!(ProtocolGenesis::load(&path).unwrap(), ours);
+    }
+}
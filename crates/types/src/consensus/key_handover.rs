@@ -0,0 +1,172 @@
+use alloy::{
+    primitives::{keccak256, BlockNumber, U256},
+    signers::{Signature, SignerSync}
+};
+use reth_network_peers::PeerId;
+use serde::{Deserialize, Serialize};
+
+use super::canonical::{
+    decode_header, encode_header, CanonicalBytes, CanonicalEncoding, CanonicalError
+};
+use crate::primitive::{AngstromSigner, SigningDomain};
+
+const KEY_HANDOVER_DOMAIN: &str = SigningDomain::KeyHandover.tag();
+
+/// Blocks ahead of its activation a handover has to be announced, so that
+/// every validator knows the new key before it signs anything.
+pub const KEY_HANDOVER_LEAD_BLOCKS: u64 = 8;
+/// Blocks the old key stays valid for once the new one activated, so that
+/// messages signed right before the switch still count.
+pub const KEY_HANDOVER_GRACE_BLOCKS: u64 = 2;
+
+/// Hands the consensus messages of a validator over from the key it signs
+/// with to a new one, from `activation_block` on.
+///
+/// It's signed by both keys, the old one authorizing the new one and the new
+/// one proving it's held by the same operator.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
+pub struct KeyHandover {
+    /// block the handover is announced in
+    pub block_height:      BlockNumber,
+    /// the key the validator signs with until the handover
+    pub source:            PeerId,
+    pub new_key:           PeerId,
+    pub activation_block:  BlockNumber,
+    /// The signatures are over the canonical encoding of all other fields
+    pub signature:         Signature,
+    pub new_key_signature: Signature
+}
+
+impl Default for KeyHandover {
+    fn default() -> Self {
+        Self {
+            block_height:      Default::default(),
+            source:            Default::default(),
+            new_key:           Default::default(),
+            activation_block:  Default::default(),
+            signature:         Signature::new(U256::ZERO, U256::ZERO, false),
+            new_key_signature: Signature::new(U256::ZERO, U256::ZERO, false)
+        }
+    }
+}
+
+impl KeyHandover {
+    pub fn new(
+        block_height: BlockNumber,
+        sk: &AngstromSigner,
+        new_sk: &AngstromSigner,
+        activation_block: BlockNumber
+    ) -> Self {
+        let payload =
+            Self::serialize_payload(&block_height, &sk.id(), &new_sk.id(), &activation_block);
+        let hash = keccak256(payload);
+
+        Self {
+            block_height,
+            source: sk.id(),
+            new_key: new_sk.id(),
+            activation_block,
+            signature: sk.sign_hash_sync(&hash).unwrap(),
+            new_key_signature: new_sk.sign_hash_sync(&hash).unwrap()
+        }
+    }
+
+    /// Checks that it's announced by `block_height`, far enough in advance of
+    /// its activation, and both signatures. It stays valid once it activated,
+    /// validators that join later still have to learn it.
+    pub fn is_valid(&self, block_height: &BlockNumber) -> bool {
+        let hash = keccak256(self.payload());
+        let signed_by = |signature: &Signature| {
+            signature
+                .recover_from_prehash(&hash)
+                .map(|key| AngstromSigner::public_key_to_peer_id(&key))
+                .ok()
+        };
+
+        self.block_height <= *block_height
+            && self.activation_block >= self.block_height + KEY_HANDOVER_LEAD_BLOCKS
+            && self.source != self.new_key
+            && signed_by(&self.signature) == Some(self.source)
+            && signed_by(&self.new_key_signature) == Some(self.new_key)
+    }
+
+    /// First block the old key is no longer valid in.
+    pub fn grace_end(&self) -> BlockNumber {
+        self.activation_block + KEY_HANDOVER_GRACE_BLOCKS
+    }
+
+    fn serialize_payload(
+        block_height: &BlockNumber,
+        source: &PeerId,
+        new_key: &PeerId,
+        activation_block: &BlockNumber
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_header(KEY_HANDOVER_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        source.canonical_encode(&mut buf);
+        new_key.canonical_encode(&mut buf);
+        activation_block.canonical_encode(&mut buf);
+        buf
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        Self::serialize_payload(
+            &self.block_height,
+            &self.source,
+            &self.new_key,
+            &self.activation_block
+        )
+    }
+}
+
+impl CanonicalEncoding for KeyHandover {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend(self.payload());
+        self.signature.canonical_encode(out);
+        self.new_key_signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(KEY_HANDOVER_DOMAIN, buf)?;
+        Ok(Self {
+            block_height:      CanonicalEncoding::canonical_decode(buf)?,
+            source:            CanonicalEncoding::canonical_decode(buf)?,
+            new_key:           CanonicalEncoding::canonical_decode(buf)?,
+            activation_block:  CanonicalEncoding::canonical_decode(buf)?,
+            signature:         CanonicalEncoding::canonical_decode(buf)?,
+            new_key_signature: CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_by_both_keys_and_round_trips() {
+        let (old, new) = (AngstromSigner::random(), AngstromSigner::random());
+        let handover = KeyHandover::new(10, &old, &new, 10 + KEY_HANDOVER_LEAD_BLOCKS);
+        assert!(handover.is_valid(&10));
+        assert!(handover.is_valid(&(10 + KEY_HANDOVER_LEAD_BLOCKS - 1)));
+        assert!(!handover.is_valid(&9));
+        assert!(handover.is_valid(&(10 + KEY_HANDOVER_LEAD_BLOCKS + 100)));
+        assert_eq!(handover.grace_end(), 10 + KEY_HANDOVER_LEAD_BLOCKS + KEY_HANDOVER_GRACE_BLOCKS);
+
+        let decoded = KeyHandover::from_canonical_bytes(&handover.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, handover);
+
+        // announced too late
+        assert!(!KeyHandover::new(10, &old, &new, 11).is_valid(&10));
+
+        // the new key has to sign as well
+        let mut forged = handover.clone();
+        forged.new_key = AngstromSigner::random().id();
+        assert!(!forged.is_valid(&10));
+        let mut forged = handover;
+        forged.new_key_signature = forged.signature;
+        assert!(!forged.is_valid(&10));
+    }
+}
@@ -1,9 +1,26 @@
+pub mod bundle_handoff;
+pub mod canonical;
+pub mod committee;
 pub mod evidence;
+pub mod fairness;
+pub mod genesis;
+pub mod key_handover;
+pub mod performance;
 pub mod pre_prepose;
 pub mod pre_propose_agg;
 pub mod proposal;
+pub mod proposal_share;
+pub mod threshold;
 
+pub use bundle_handoff::*;
+pub use canonical::{CanonicalEncoding, CanonicalError, CONSENSUS_ENCODING_VERSION};
+pub use committee::*;
 pub use evidence::*;
+pub use fairness::*;
+pub use genesis::*;
+pub use key_handover::*;
+pub use performance::*;
 pub use pre_prepose::*;
 pub use pre_propose_agg::*;
 pub use proposal::*;
+pub use proposal_share::*;
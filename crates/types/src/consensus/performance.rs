@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock}
+};
+
+use alloy::primitives::BlockNumber;
+use serde::{Deserialize, Serialize};
+
+use crate::primitive::PeerId;
+
+/// How a validator took part in a single round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoundParticipation {
+    pub led:                 bool,
+    /// we never got a pre-proposal of the validator
+    pub missed_pre_proposal: bool,
+    /// the pre-proposal of the validator arrived after we stopped collecting
+    /// them
+    pub late_pre_proposal:   bool,
+    /// the aggregation of the validator arrived once we moved on to the
+    /// proposal
+    pub late_aggregation:    bool,
+    /// the validator led the round without us seeing its proposal
+    pub missed_proposal:     bool,
+    /// the validator led the round and its proposal failed our verification
+    pub failed_proposal:     bool
+}
+
+/// Consensus performance of a validator over all rounds we saw since the node
+/// started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorPerformance {
+    pub peer_id:              PeerId,
+    pub rounds:               u64,
+    pub rounds_led:           u64,
+    pub missed_pre_proposals: u64,
+    pub late_pre_proposals:   u64,
+    pub late_aggregations:    u64,
+    pub missed_proposals:     u64,
+    pub failed_proposals:     u64,
+    /// last round the validator was part of the set
+    pub last_round:           BlockNumber
+}
+
+impl ValidatorPerformance {
+    fn record(&mut self, block_number: BlockNumber, round: RoundParticipation) {
+        self.rounds += 1;
+        self.rounds_led += round.led as u64;
+        self.missed_pre_proposals += round.missed_pre_proposal as u64;
+        self.late_pre_proposals += round.late_pre_proposal as u64;
+        self.late_aggregations += round.late_aggregation as u64;
+        self.missed_proposals += round.missed_proposal as u64;
+        self.failed_proposals += round.failed_proposal as u64;
+        self.last_round = self.last_round.max(block_number);
+    }
+}
+
+/// Performance of every validator, shared between the consensus, which records
+/// the rounds, and the RPC, which serves the summary.
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorPerformanceStore {
+    validators: Arc<RwLock<HashMap<PeerId, ValidatorPerformance>>>
+}
+
+impl ValidatorPerformanceStore {
+    pub fn record_round(
+        &self,
+        block_number: BlockNumber,
+        participation: impl IntoIterator<Item = (PeerId, RoundParticipation)>
+    ) {
+        let mut validators = self.validators.write().expect("poisoned");
+        for (peer_id, round) in participation {
+            validators
+                .entry(peer_id)
+                .or_insert_with(|| ValidatorPerformance { peer_id, ..Default::default() })
+                .record(block_number, round);
+        }
+    }
+
+    pub fn validator(&self, peer_id: &PeerId) -> Option<ValidatorPerformance> {
+        self.validators
+            .read()
+            .expect("poisoned")
+            .get(peer_id)
+            .cloned()
+    }
+
+    /// All validators, ordered by peer id.
+    pub fn summary(&self) -> Vec<ValidatorPerformance> {
+        let mut summary = self
+            .validators
+            .read()
+            .expect("poisoned")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        summary.sort_unstable_by_key(|performance| performance.peer_id);
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_rounds_per_validator() {
+        let store = ValidatorPerformanceStore::default();
+        let (leader, late) = (PeerId::random(), PeerId::random());
+
+        store.record_round(
+            10,
+            [
+                (
+                    leader,
+                    RoundParticipation { led: true, failed_proposal: true, ..Default::default() }
+                ),
+                (late, RoundParticipation { late_pre_proposal: true, ..Default::default() })
+            ]
+        );
+        store.record_round(
+            11,
+            [(late, RoundParticipation { missed_pre_proposal: true, ..Default::default() })]
+        );
+
+        let leader_performance = store.validator(&leader).unwrap();
+        assert_eq!((leader_performance.rounds_led, leader_performance.failed_proposals), (1, 1));
+
+        let late_performance = store.validator(&late).unwrap();
+        assert_eq!(late_performance.rounds, 2);
+        assert_eq!(late_performance.late_pre_proposals, 1);
+        assert_eq!(late_performance.missed_pre_proposals, 1);
+        assert_eq!(late_performance.last_round, 11);
+
+        assert_eq!(store.summary().len(), 2);
+    }
+}
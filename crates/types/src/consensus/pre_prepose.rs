@@ -4,54 +4,79 @@ use std::{
 };
 
 use alloy::{
-    primitives::{keccak256, BlockNumber},
+    primitives::{keccak256, BlockNumber, B256},
     signers::{Signature, SignerSync}
 };
 use alloy_primitives::U256;
-use bytes::Bytes;
 use reth_network_peers::PeerId;
 use serde::{Deserialize, Serialize};
 
+use super::canonical::{
+    decode_header, encode_header, encode_list, CanonicalBytes, CanonicalEncoding, CanonicalError
+};
 use crate::{
     orders::OrderSet,
-    primitive::{AngstromSigner, PoolId},
+    primitive::{AngstromSigner, PoolId, SigningDomain},
     sol_bindings::{
         grouped_orders::{GroupedVanillaOrder, OrderWithStorageData},
         rpc_orders::TopOfBlockOrder
     }
 };
 
+const PRE_PROPOSAL_DOMAIN: &str = SigningDomain::PreProposal.tag();
+
+/// Blocks a limit order has to be pending for before a validator flags it as
+/// one the proposal has to include.
+pub const FORCED_INCLUSION_AGE_BLOCKS: u64 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
 pub struct PreProposal {
-    pub block_height: BlockNumber,
-    pub source:       PeerId,
+    pub block_height:     BlockNumber,
+    pub source:           PeerId,
     // TODO: this really should be HashMap<PoolId, GroupedVanillaOrder>
-    pub limit:        Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+    pub limit:            Vec<OrderWithStorageData<GroupedVanillaOrder>>,
     // TODO: this really should be another type with HashMap<PoolId, {order, tob_reward}>
-    pub searcher:     Vec<OrderWithStorageData<TopOfBlockOrder>>,
-    /// The signature is over the ethereum height as well as the limit and
-    /// searcher sets
-    pub signature:    Signature
+    pub searcher:         Vec<OrderWithStorageData<TopOfBlockOrder>>,
+    /// hashes of the limit orders of the pre-proposal that have been pending
+    /// long enough that the proposal has to include them, sorted
+    pub forced_inclusion: Vec<B256>,
+    /// hashes of the orders of the pre-proposal that were submitted to the
+    /// source privately, only carried when the source leads the round, sorted
+    pub private_orders:   Vec<B256>,
+    /// block the source first saw each of the limit orders in, in the order
+    /// of `limit`
+    pub first_seen:       Vec<BlockNumber>,
+    /// The signature is over the canonical encoding of the ethereum height,
+    /// source, the limit and searcher sets, the forced inclusion and private
+    /// lists as well as the first seen blocks
+    pub signature:        Signature
 }
 
 impl Default for PreProposal {
     fn default() -> Self {
         Self {
-            signature:    Signature::new(U256::ZERO, U256::ZERO, false),
-            block_height: Default::default(),
-            source:       Default::default(),
-            limit:        Default::default(),
-            searcher:     Default::default()
+            signature:        Signature::new(U256::ZERO, U256::ZERO, false),
+            block_height:     Default::default(),
+            source:           Default::default(),
+            limit:            Default::default(),
+            searcher:         Default::default(),
+            forced_inclusion: Default::default(),
+            private_orders:   Default::default(),
+            first_seen:       Default::default()
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PreProposalContent {
-    pub block_height: BlockNumber,
-    pub source:       PeerId,
-    pub limit:        Vec<OrderWithStorageData<GroupedVanillaOrder>>,
-    pub searcher:     Vec<OrderWithStorageData<TopOfBlockOrder>>
+    pub block_height:     BlockNumber,
+    pub source:           PeerId,
+    pub limit:            Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+    pub searcher:         Vec<OrderWithStorageData<TopOfBlockOrder>>,
+    pub forced_inclusion: Vec<B256>,
+    pub private_orders:   Vec<B256>,
+    pub first_seen:       Vec<BlockNumber>
 }
 
 // the reason for the manual implementation is because EcDSA signatures are not
@@ -64,16 +89,22 @@ impl std::hash::Hash for PreProposalContent {
         self.source.hash(state);
         self.limit.hash(state);
         self.searcher.hash(state);
+        self.forced_inclusion.hash(state);
+        self.private_orders.hash(state);
+        self.first_seen.hash(state);
     }
 }
 
 impl PreProposal {
     pub fn content(&self) -> PreProposalContent {
         PreProposalContent {
-            block_height: self.block_height,
-            source:       self.source,
-            limit:        self.limit.clone(),
-            searcher:     self.searcher.clone()
+            block_height:     self.block_height,
+            source:           self.source,
+            limit:            self.limit.clone(),
+            searcher:         self.searcher.clone(),
+            forced_inclusion: self.forced_inclusion.clone(),
+            private_orders:   self.private_orders.clone(),
+            first_seen:       self.first_seen.clone()
         }
     }
 }
@@ -90,26 +121,190 @@ impl PreProposal {
         limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>,
         searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>
     ) -> Self {
-        let payload = Self::serialize_payload(&ethereum_height, &limit, &searcher);
+        Self::generate_with_forced_inclusion(ethereum_height, sk, limit, searcher, vec![])
+    }
+
+    /// Generates a pre-proposal that flags the given limit orders as ones the
+    /// proposal has to include.
+    pub fn generate_with_forced_inclusion(
+        ethereum_height: BlockNumber,
+        sk: &AngstromSigner,
+        limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        forced_inclusion: Vec<B256>
+    ) -> Self {
+        Self::generate_with_flags(ethereum_height, sk, limit, searcher, forced_inclusion, vec![])
+    }
+
+    /// Generates a pre-proposal that flags the given orders, the forced ones
+    /// as ones the proposal has to include and the private ones as ones only
+    /// the source holds. The limit orders are attested as first seen in their
+    /// valid block.
+    pub fn generate_with_flags(
+        ethereum_height: BlockNumber,
+        sk: &AngstromSigner,
+        limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        forced_inclusion: Vec<B256>,
+        private_orders: Vec<B256>
+    ) -> Self {
+        let first_seen = limit.iter().map(|order| order.valid_block).collect();
+        Self::generate_signed(
+            ethereum_height,
+            sk,
+            limit,
+            searcher,
+            forced_inclusion,
+            private_orders,
+            first_seen
+        )
+    }
+
+    fn generate_signed(
+        ethereum_height: BlockNumber,
+        sk: &AngstromSigner,
+        limit: Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+        searcher: Vec<OrderWithStorageData<TopOfBlockOrder>>,
+        mut forced_inclusion: Vec<B256>,
+        mut private_orders: Vec<B256>,
+        first_seen: Vec<BlockNumber>
+    ) -> Self {
+        forced_inclusion.sort_unstable();
+        forced_inclusion.dedup();
+        private_orders.sort_unstable();
+        private_orders.dedup();
+        let payload = Self::serialize_payload(
+            &ethereum_height,
+            &sk.id(),
+            &limit,
+            &searcher,
+            &forced_inclusion,
+            &private_orders,
+            &first_seen
+        );
         let signature = Self::sign_payload(sk, payload);
 
-        Self { limit, source: sk.id(), searcher, block_height: ethereum_height, signature }
+        Self {
+            limit,
+            source: sk.id(),
+            searcher,
+            forced_inclusion,
+            private_orders,
+            first_seen,
+            block_height: ethereum_height,
+            signature
+        }
     }
 
+    /// Our pre-proposal over the given orders. Private and pegged orders are
+    /// only revealed in the rounds we lead, as no other leader can include
+    /// them. Orders are attested and flagged for forced inclusion by the block
+    /// we first saw them in, their valid block for the ones missing from
+    /// `first_seen`.
     pub fn new(
         ethereum_height: u64,
         sk: &AngstromSigner,
-        orders: OrderSet<GroupedVanillaOrder, TopOfBlockOrder>
+        orders: OrderSet<GroupedVanillaOrder, TopOfBlockOrder>,
+        first_seen: &HashMap<B256, BlockNumber>,
+        is_leader: bool
     ) -> Self {
-        let OrderSet { limit, searcher } = orders;
+        let OrderSet { mut limit, mut searcher } = orders;
+        // only the visible tranche of an iceberg is up for matching, the
+        // hidden ones wait until it's filled
+        limit.retain(|order| !order.is_hidden);
+        if !is_leader {
+            limit.retain(|order| !order.is_leader_only());
+            searcher.retain(|order| !order.is_leader_only());
+        }
         let limit_orders = limit.len();
         let searcher_orders = searcher.len();
-        tracing::info!(%limit_orders,%searcher_orders, %ethereum_height,"building my pre_proposal");
-        Self::generate_pre_proposal(ethereum_height, sk, limit, searcher)
+        let seen = limit
+            .iter()
+            .map(|order| {
+                first_seen
+                    .get(&order.order_id.hash)
+                    .copied()
+                    .unwrap_or(order.valid_block)
+            })
+            .collect::<Vec<_>>();
+        // orders this old have had the time to reach every validator
+        let forced_inclusion = limit
+            .iter()
+            .zip(&seen)
+            .filter(|(order, _)| !order.is_leader_only())
+            .filter(|(_, seen)| *seen + FORCED_INCLUSION_AGE_BLOCKS <= ethereum_height)
+            .map(|(order, _)| order.order_id.hash)
+            .collect::<Vec<_>>();
+        let private_orders = limit
+            .iter()
+            .filter(|order| order.is_leader_only())
+            .map(|order| order.order_id.hash)
+            .chain(
+                searcher
+                    .iter()
+                    .filter(|order| order.is_leader_only())
+                    .map(|order| order.order_id.hash)
+            )
+            .collect::<Vec<_>>();
+        let forced_orders = forced_inclusion.len();
+        tracing::info!(
+            %limit_orders,
+            %searcher_orders,
+            %forced_orders,
+            private_orders = private_orders.len(),
+            %ethereum_height,
+            "building my pre_proposal"
+        );
+        Self::generate_signed(
+            ethereum_height,
+            sk,
+            limit,
+            searcher,
+            forced_inclusion,
+            private_orders,
+            seen
+        )
+    }
+
+    /// The flagged orders the pre-proposal carries, a validator can't force
+    /// the inclusion of an order it doesn't attest to itself.
+    pub fn forced_orders(&self) -> impl Iterator<Item = B256> + '_ {
+        let carried = self
+            .limit
+            .iter()
+            .map(|order| order.order_id.hash)
+            .collect::<HashSet<_>>();
+        self.forced_inclusion
+            .iter()
+            .copied()
+            .filter(move |hash| carried.contains(hash))
+    }
+
+    /// The flagged private orders the pre-proposal carries, by the hash of
+    /// the order.
+    pub fn private_orders(&self) -> HashSet<B256> {
+        let flagged = self.private_orders.iter().collect::<HashSet<_>>();
+        self.limit
+            .iter()
+            .map(|order| order.order_id.hash)
+            .chain(self.searcher.iter().map(|order| order.order_id.hash))
+            .filter(|hash| flagged.contains(hash))
+            .collect()
+    }
+
+    /// The block the source attests it first saw each of the limit orders in.
+    pub fn first_seen_blocks(
+        &self
+    ) -> impl Iterator<Item = (&OrderWithStorageData<GroupedVanillaOrder>, BlockNumber)> + '_ {
+        self.limit.iter().zip(self.first_seen.iter().copied())
     }
 
-    /// ensures block height is correct as-well as validates the signature.
+    /// ensures block height is correct, that every limit order has its first
+    /// seen block as-well as validates the signature.
     pub fn is_valid(&self, block_height: &BlockNumber) -> bool {
+        if self.first_seen.len() != self.limit.len() {
+            return false
+        }
         let hash = keccak256(self.payload());
         let Ok(source) = self.signature.recover_from_prehash(&hash) else {
             return false;
@@ -121,18 +316,35 @@ impl PreProposal {
 
     fn serialize_payload(
         block_height: &BlockNumber,
-        limit: &Vec<OrderWithStorageData<GroupedVanillaOrder>>,
-        searcher: &Vec<OrderWithStorageData<TopOfBlockOrder>>
+        source: &PeerId,
+        limit: &[OrderWithStorageData<GroupedVanillaOrder>],
+        searcher: &[OrderWithStorageData<TopOfBlockOrder>],
+        forced_inclusion: &[B256],
+        private_orders: &[B256],
+        first_seen: &[BlockNumber]
     ) -> Vec<u8> {
         let mut buf = Vec::new();
-        buf.extend(bincode::serialize(block_height).unwrap());
-        buf.extend(bincode::serialize(limit).unwrap());
-        buf.extend(bincode::serialize(searcher).unwrap());
+        encode_header(PRE_PROPOSAL_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        source.canonical_encode(&mut buf);
+        encode_list(limit, &mut buf);
+        encode_list(searcher, &mut buf);
+        encode_list(forced_inclusion, &mut buf);
+        encode_list(private_orders, &mut buf);
+        encode_list(first_seen, &mut buf);
         buf
     }
 
-    fn payload(&self) -> Bytes {
-        Bytes::from(Self::serialize_payload(&self.block_height, &self.limit, &self.searcher))
+    fn payload(&self) -> Vec<u8> {
+        Self::serialize_payload(
+            &self.block_height,
+            &self.source,
+            &self.limit,
+            &self.searcher,
+            &self.forced_inclusion,
+            &self.private_orders,
+            &self.first_seen
+        )
     }
 
     pub fn orders_by_pool_id(
@@ -149,11 +361,31 @@ impl PreProposal {
     }
 }
 
+impl CanonicalEncoding for PreProposal {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend(self.payload());
+        self.signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(PRE_PROPOSAL_DOMAIN, buf)?;
+        Ok(Self {
+            block_height:     CanonicalEncoding::canonical_decode(buf)?,
+            source:           CanonicalEncoding::canonical_decode(buf)?,
+            limit:            CanonicalEncoding::canonical_decode(buf)?,
+            searcher:         CanonicalEncoding::canonical_decode(buf)?,
+            forced_inclusion: CanonicalEncoding::canonical_decode(buf)?,
+            private_orders:   CanonicalEncoding::canonical_decode(buf)?,
+            first_seen:       CanonicalEncoding::canonical_decode(buf)?,
+            signature:        CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::PreProposal;
-    use crate::primitive::AngstromSigner;
+    use super::*;
 
     #[test]
     fn can_be_constructed() {
@@ -175,4 +407,23 @@ mod tests {
 
         assert!(preproposal.is_valid(&ethereum_height), "Unable to validate self");
     }
+
+    #[test]
+    fn flags_orders_by_the_block_they_were_first_seen() {
+        let order = |hash: u8| {
+            let mut order = OrderWithStorageData::<GroupedVanillaOrder>::default();
+            order.order_id.hash = B256::repeat_byte(hash);
+            // validated again in the last block
+            order.valid_block = 10;
+            order
+        };
+        let orders = OrderSet { limit: vec![order(1), order(2)], searcher: vec![] };
+        let first_seen = HashMap::from([(B256::repeat_byte(1), 10 - FORCED_INCLUSION_AGE_BLOCKS)]);
+
+        let pre_proposal =
+            PreProposal::new(10, &AngstromSigner::random(), orders, &first_seen, false);
+        assert_eq!(pre_proposal.forced_inclusion, vec![B256::repeat_byte(1)]);
+        assert_eq!(pre_proposal.first_seen, vec![10 - FORCED_INCLUSION_AGE_BLOCKS, 10]);
+        assert!(pre_proposal.is_valid(&10));
+    }
 }
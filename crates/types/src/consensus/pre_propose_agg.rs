@@ -2,13 +2,21 @@ use alloy::{
     primitives::{keccak256, BlockNumber, U256},
     signers::{Signature, SignerSync}
 };
-use bytes::Bytes;
 use reth_network_peers::PeerId;
 use serde::{Deserialize, Serialize};
 
-use crate::{consensus::PreProposal, primitive::AngstromSigner};
+use super::canonical::{
+    decode_header, encode_header, encode_list, CanonicalBytes, CanonicalEncoding, CanonicalError
+};
+use crate::{
+    consensus::PreProposal,
+    primitive::{AngstromSigner, SigningDomain}
+};
+
+const PRE_PROPOSAL_AGGREGATION_DOMAIN: &str = SigningDomain::PreProposalAggregation.tag();
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
 pub struct PreProposalAggregation {
     pub block_height:  BlockNumber,
     pub source:        PeerId,
@@ -33,7 +41,7 @@ impl PreProposalAggregation {
         sk: &AngstromSigner,
         pre_proposals: Vec<PreProposal>
     ) -> Self {
-        let payload = Self::serialize_payload(&block_height, &pre_proposals);
+        let payload = Self::serialize_payload(&block_height, &sk.id(), &pre_proposals);
         let signature = Self::sign_payload(sk, payload);
         Self { block_height, source: sk.id(), pre_proposals, signature }
     }
@@ -44,15 +52,21 @@ impl PreProposalAggregation {
         sk.sign_hash_sync(&hash).unwrap()
     }
 
-    fn serialize_payload(block_height: &BlockNumber, pre_proposals: &[PreProposal]) -> Vec<u8> {
+    fn serialize_payload(
+        block_height: &BlockNumber,
+        source: &PeerId,
+        pre_proposals: &[PreProposal]
+    ) -> Vec<u8> {
         let mut buf = Vec::new();
-        buf.extend(bincode::serialize(block_height).unwrap());
-        buf.extend(bincode::serialize(pre_proposals).unwrap());
+        encode_header(PRE_PROPOSAL_AGGREGATION_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        source.canonical_encode(&mut buf);
+        encode_list(pre_proposals, &mut buf);
         buf
     }
 
-    fn payload(&self) -> Bytes {
-        Bytes::from(Self::serialize_payload(&self.block_height, &self.pre_proposals))
+    fn payload(&self) -> Vec<u8> {
+        Self::serialize_payload(&self.block_height, &self.source, &self.pre_proposals)
     }
 
     pub fn is_valid(&self, block_height: &BlockNumber) -> bool {
@@ -72,3 +86,20 @@ impl PreProposalAggregation {
         source == self.source
     }
 }
+
+impl CanonicalEncoding for PreProposalAggregation {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend(self.payload());
+        self.signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(PRE_PROPOSAL_AGGREGATION_DOMAIN, buf)?;
+        Ok(Self {
+            block_height:  CanonicalEncoding::canonical_decode(buf)?,
+            source:        CanonicalEncoding::canonical_decode(buf)?,
+            pre_proposals: CanonicalEncoding::canonical_decode(buf)?,
+            signature:     CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
+}
@@ -1,29 +1,39 @@
+use std::collections::HashMap;
+
 use alloy::{
-    primitives::{BlockNumber, U256},
+    primitives::{BlockNumber, B256, U256},
     signers::{Signature, SignerSync}
 };
 use alloy_primitives::keccak256;
-use bytes::Bytes;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use super::{PreProposal, PreProposalAggregation};
+use super::{
+    canonical::{
+        decode_header, encode_header, encode_list, CanonicalBytes, CanonicalEncoding,
+        CanonicalError
+    },
+    PreProposal, PreProposalAggregation
+};
 use crate::{
-    orders::PoolSolution,
-    primitive::{AngstromSigner, PeerId}
+    orders::{are_canonical_solutions, canonicalize_solutions, PoolSolution},
+    primitive::{AngstromSigner, PeerId, SigningDomain}
 };
 
+const PROPOSAL_DOMAIN: &str = SigningDomain::Proposal.tag();
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
 pub struct Proposal {
     // Might not be necessary as this is encoded in all the proposals anyways
     pub block_height: BlockNumber,
     pub source:       PeerId,
     /// PreProposals sorted by source
     pub preproposals: Vec<PreProposalAggregation>,
-    /// PoolSolutions sorted by PoolId
+    /// PoolSolutions sorted by PoolId, each in its canonical form
     pub solutions:    Vec<PoolSolution>,
-    /// This signature is over (etheruem_block | hash(vanilla_bundle) |
-    /// hash(order_buffer) | hash(lower_bound))
+    /// This signature is over the canonical encoding of the ethereum height,
+    /// source, preproposals and solutions
     pub signature:    Signature
 }
 
@@ -46,15 +56,10 @@ impl Proposal {
         preproposals: Vec<PreProposalAggregation>,
         mut solutions: Vec<PoolSolution>
     ) -> Self {
-        // Sort our solutions
-        solutions.sort_by_key(|sol| sol.id);
+        canonicalize_solutions(&mut solutions);
 
         // Build our hash and sign
-        let mut buf = Vec::new();
-        buf.extend(bincode::serialize(&ethereum_height).unwrap());
-        buf.extend(&sk.id());
-        buf.extend(bincode::serialize(&preproposals).unwrap());
-        buf.extend(bincode::serialize(&solutions).unwrap());
+        let buf = Self::serialize_payload(&ethereum_height, &sk.id(), &preproposals, &solutions);
         let hash = keccak256(buf);
         let sig = sk.sign_hash_sync(&hash).unwrap();
 
@@ -67,6 +72,11 @@ impl Proposal {
         }
     }
 
+    /// Hash of everything the leader signed, what the committee signs over.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.payload())
+    }
+
     pub fn preproposals(&self) -> &Vec<PreProposalAggregation> {
         &self.preproposals
     }
@@ -80,6 +90,12 @@ impl Proposal {
         {
             return false
         }
+        // Solutions have to be in their canonical order, so that every node
+        // compares and signs over the same bytes. Proposals are only exchanged
+        // with peers on strom v2, v1 leaders sort their solutions by pool only
+        if !are_canonical_solutions(&self.solutions) {
+            return false
+        }
         // Then our own signature has to be valid
         let hash = keccak256(self.payload());
         let Ok(source) = self.signature.recover_from_prehash(&hash) else {
@@ -90,14 +106,28 @@ impl Proposal {
         source == self.source
     }
 
-    fn payload(&self) -> Bytes {
-        let mut buf = vec![];
-        buf.extend(bincode::serialize(&self.block_height).unwrap());
-        buf.extend(*self.source);
-        buf.extend(bincode::serialize(&self.preproposals).unwrap());
-        buf.extend(bincode::serialize(&self.solutions).unwrap());
+    fn serialize_payload(
+        block_height: &BlockNumber,
+        source: &PeerId,
+        preproposals: &[PreProposalAggregation],
+        solutions: &[PoolSolution]
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_header(PROPOSAL_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        source.canonical_encode(&mut buf);
+        encode_list(preproposals, &mut buf);
+        encode_list(solutions, &mut buf);
+        buf
+    }
 
-        Bytes::from_iter(buf)
+    fn payload(&self) -> Vec<u8> {
+        Self::serialize_payload(
+            &self.block_height,
+            &self.source,
+            &self.preproposals,
+            &self.solutions
+        )
     }
 
     pub fn flattened_pre_proposals(&self) -> Vec<PreProposal> {
@@ -107,12 +137,66 @@ impl Proposal {
             .unique_by(|proposal| proposal.source)
             .collect::<Vec<_>>()
     }
+
+    /// The gas every order of the proposal was estimated to take on its own.
+    pub fn order_gas_units(&self) -> HashMap<B256, u64> {
+        self.flattened_pre_proposals()
+            .iter()
+            .flat_map(|pre_proposal| {
+                pre_proposal
+                    .limit
+                    .iter()
+                    .map(|order| (order.order_id.hash, order.priority_data.gas_units))
+                    .chain(
+                        pre_proposal
+                            .searcher
+                            .iter()
+                            .map(|order| (order.order_id.hash, order.priority_data.gas_units))
+                    )
+            })
+            .collect()
+    }
+}
+
+impl CanonicalEncoding for Proposal {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        out.extend(self.payload());
+        self.signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(PROPOSAL_DOMAIN, buf)?;
+        Ok(Self {
+            block_height: CanonicalEncoding::canonical_decode(buf)?,
+            source:       CanonicalEncoding::canonical_decode(buf)?,
+            preproposals: CanonicalEncoding::canonical_decode(buf)?,
+            solutions:    CanonicalEncoding::canonical_decode(buf)?,
+            signature:    CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use alloy::{primitives::B256, signers::SignerSync};
+    use alloy_primitives::keccak256;
+
     use super::Proposal;
-    use crate::primitive::AngstromSigner;
+    use crate::{
+        orders::{OrderFillState, OrderId, OrderOutcome, PoolSolution},
+        primitive::AngstromSigner
+    };
+
+    fn solution(pool: u8, orders: &[u8]) -> PoolSolution {
+        let limit = orders
+            .iter()
+            .map(|hash| OrderOutcome {
+                id:      OrderId { hash: B256::repeat_byte(*hash), ..Default::default() },
+                outcome: OrderFillState::CompleteFill
+            })
+            .collect();
+        PoolSolution { id: B256::repeat_byte(pool), limit, ..Default::default() }
+    }
 
     #[test]
     fn can_be_constructed() {
@@ -134,4 +218,39 @@ mod tests {
 
         assert!(proposal.is_valid(&ethereum_height), "Unable to validate self");
     }
+
+    #[test]
+    fn signs_over_the_canonical_order_of_solutions() {
+        let sk = AngstromSigner::random();
+        let a = Proposal::generate_proposal(
+            100,
+            &sk,
+            vec![],
+            vec![solution(2, &[3, 1]), solution(1, &[2, 1])]
+        );
+        let b = Proposal::generate_proposal(
+            100,
+            &sk,
+            vec![],
+            vec![solution(1, &[1, 2]), solution(2, &[1, 3])]
+        );
+        assert_eq!(a.solutions, b.solutions);
+        assert!(a.is_valid(&100));
+
+        // a leader can't sign over its solutions in another order
+        let signed = |solutions: Vec<PoolSolution>| {
+            let payload = Proposal::serialize_payload(&100, &sk.id(), &[], &solutions);
+            Proposal {
+                block_height: 100,
+                source: sk.id(),
+                preproposals: vec![],
+                signature: sk.sign_hash_sync(&keccak256(payload)).unwrap(),
+                solutions
+            }
+        };
+        assert!(signed(b.solutions.clone()).is_valid(&100));
+        assert!(!signed(vec![solution(1, &[2, 1])]).is_valid(&100));
+        assert!(!signed(vec![solution(2, &[1]), solution(1, &[1])]).is_valid(&100));
+        assert!(!signed(vec![solution(1, &[1, 1])]).is_valid(&100));
+    }
 }
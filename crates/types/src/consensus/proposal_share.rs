@@ -0,0 +1,103 @@
+use alloy::primitives::{BlockNumber, B256};
+use reth_network_peers::PeerId;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    canonical::{decode_header, encode_header, CanonicalBytes, CanonicalEncoding, CanonicalError},
+    threshold::{self, BlsPublicKey, BlsSignature, KeyShare}
+};
+use crate::primitive::SigningDomain;
+
+const PROPOSAL_SHARE_DOMAIN: &str = SigningDomain::ProposalShare.tag();
+
+/// The share of the committee signature of a member over a proposal it
+/// verified, see [`ProposalCommittee`](super::ProposalCommittee).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "CanonicalBytes", try_from = "CanonicalBytes")]
+pub struct ProposalShare {
+    pub block_height:  BlockNumber,
+    pub source:        PeerId,
+    /// [`Proposal::hash`](super::Proposal::hash) of the signed proposal
+    pub proposal_hash: B256,
+    /// signed with the key share of the member over
+    /// [`Self::signing_payload`], which is the same for every member
+    pub signature:     BlsSignature
+}
+
+impl ProposalShare {
+    pub fn new(
+        block_height: BlockNumber,
+        source: PeerId,
+        key_share: &KeyShare,
+        proposal_hash: B256
+    ) -> Self {
+        let signature = key_share.sign(&Self::signing_payload(block_height, proposal_hash));
+
+        Self { block_height, source, proposal_hash, signature }
+    }
+
+    /// What the members sign, the combined signature of the committee is over
+    /// it as well.
+    pub fn signing_payload(block_height: BlockNumber, proposal_hash: B256) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_header(PROPOSAL_SHARE_DOMAIN, &mut buf);
+        block_height.canonical_encode(&mut buf);
+        proposal_hash.canonical_encode(&mut buf);
+        buf
+    }
+
+    /// Whether the share is for the block and signed by the key share with
+    /// the public key `share_key`.
+    pub fn is_valid(&self, block_height: &BlockNumber, share_key: &BlsPublicKey) -> bool {
+        &self.block_height == block_height
+            && threshold::verify(
+                share_key,
+                &Self::signing_payload(self.block_height, self.proposal_hash),
+                &self.signature
+            )
+    }
+}
+
+impl CanonicalEncoding for ProposalShare {
+    fn canonical_encode(&self, out: &mut Vec<u8>) {
+        encode_header(PROPOSAL_SHARE_DOMAIN, out);
+        self.block_height.canonical_encode(out);
+        self.source.canonical_encode(out);
+        self.proposal_hash.canonical_encode(out);
+        self.signature.canonical_encode(out);
+    }
+
+    fn canonical_decode(buf: &mut &[u8]) -> Result<Self, CanonicalError> {
+        decode_header(PROPOSAL_SHARE_DOMAIN, buf)?;
+        Ok(Self {
+            block_height:  CanonicalEncoding::canonical_decode(buf)?,
+            source:        CanonicalEncoding::canonical_decode(buf)?,
+            proposal_hash: CanonicalEncoding::canonical_decode(buf)?,
+            signature:     CanonicalEncoding::canonical_decode(buf)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::threshold::{finish_key_share, Dealing};
+
+    #[test]
+    fn signed_and_round_trips() {
+        let dealing = Dealing::new(2, 2);
+        let key_share = |index| finish_key_share(index, &[dealing.share_for(index).unwrap()]);
+        let (member, other) = (key_share(1).unwrap(), key_share(2).unwrap());
+        let share = ProposalShare::new(3, PeerId::random(), &member, B256::repeat_byte(7));
+        assert!(share.is_valid(&3, &member.public_key()));
+        assert!(!share.is_valid(&4, &member.public_key()));
+        assert!(!share.is_valid(&3, &other.public_key()));
+
+        let decoded = ProposalShare::from_canonical_bytes(&share.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded, share);
+
+        let mut forged = share;
+        forged.proposal_hash = B256::repeat_byte(8);
+        assert!(!forged.is_valid(&3, &member.public_key()));
+    }
+}
@@ -0,0 +1,460 @@
+//! Threshold BLS signatures of the proposal committee.
+//!
+//! The committee shares a single BLS12-381 key, public keys in G1 and
+//! signatures in G2 like the beacon chain. The secret key is never assembled:
+//! it's the constant of a polynomial of degree `threshold - 1` that every
+//! member holds a point of, the [`KeyShare`]. Any `threshold` signatures of
+//! members over the same message interpolate to the signature of the shared
+//! key, which verifies against [`ThresholdPublicKey::public_key`] like any
+//! other BLS signature, so the contract can check it with the BLS precompiles.
+//!
+//! The shared key comes out of a distributed key generation in which every
+//! member deals a [`Dealing`], see the bootstrap in the testing tools. Nobody
+//! learns the key as long as one of the dealers is honest.
+use std::path::Path;
+
+use alloy::primitives::{FixedBytes, B256};
+use blst::{
+    blst_bendian_from_scalar, blst_fr, blst_fr_add, blst_fr_from_scalar, blst_fr_from_uint64,
+    blst_fr_inverse, blst_fr_mul, blst_fr_sub, blst_p1, blst_p1_add_or_double, blst_p1_affine,
+    blst_p1_compress, blst_p1_from_affine, blst_p1_generator, blst_p1_mult, blst_p1_uncompress,
+    blst_p2, blst_p2_add_or_double, blst_p2_affine, blst_p2_compress, blst_p2_from_affine,
+    blst_p2_mult, blst_p2_uncompress, blst_scalar, blst_scalar_fr_check, blst_scalar_from_bendian,
+    blst_scalar_from_fr,
+    min_pk::{PublicKey, SecretKey, Signature},
+    BLST_ERROR
+};
+use serde::{Deserialize, Serialize};
+
+/// Ciphersuite of the signatures, the basic scheme of the BLS signature draft.
+pub const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// A compressed public key, a point of G1.
+pub type BlsPublicKey = FixedBytes<48>;
+/// A compressed signature, a point of G2.
+pub type BlsSignature = FixedBytes<96>;
+
+/// Scalars are multiplied into points by their 255 bits.
+const SCALAR_BITS: usize = 255;
+
+/// The share of the committee key a member signs with.
+///
+/// The share of the member with index `i` is the point at `x = i` of the
+/// polynomial of the shared key, so indices start at 1.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub index: u64,
+    secret:    B256
+}
+
+impl std::fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyShare {
+    /// Reads a key share, as written by the bootstrap tooling of the testing
+    /// tools.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec(self)?)?)
+    }
+
+    pub fn public_key(&self) -> BlsPublicKey {
+        self.secret_key().sk_to_pk().compress().into()
+    }
+
+    pub fn sign(&self, message: &[u8]) -> BlsSignature {
+        self.secret_key()
+            .sign(message, BLS_DST, &[])
+            .compress()
+            .into()
+    }
+
+    fn secret_key(&self) -> SecretKey {
+        SecretKey::from_bytes(self.secret.as_slice()).expect("key shares are never zero")
+    }
+}
+
+/// The public side of the committee key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdPublicKey {
+    /// the key the combined signatures verify against
+    pub public_key: BlsPublicKey,
+    /// the key of every share, the one at position `i` is of index `i + 1`
+    pub shares:     Vec<BlsPublicKey>
+}
+
+impl ThresholdPublicKey {
+    /// Public key of the share with this index.
+    pub fn share(&self, index: u64) -> Option<&BlsPublicKey> {
+        let position = usize::try_from(index).ok()?.checked_sub(1)?;
+        self.shares.get(position)
+    }
+}
+
+/// Whether `signature` is a signature of `message` by `public_key`.
+pub fn verify(public_key: &BlsPublicKey, message: &[u8], signature: &BlsSignature) -> bool {
+    let (Ok(public_key), Ok(signature)) =
+        (PublicKey::from_bytes(public_key.as_slice()), Signature::from_bytes(signature.as_slice()))
+    else {
+        return false
+    };
+
+    signature.verify(true, message, BLS_DST, &[], &public_key, true) == BLST_ERROR::BLST_SUCCESS
+}
+
+/// Interpolates the signatures of the shares with the given indices into the
+/// signature of the shared key. There have to be exactly `threshold` of them
+/// with distinct indices for the result to verify.
+pub fn combine(shares: &[(u64, BlsSignature)]) -> Option<BlsSignature> {
+    let indices = shares.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+    let mut combined = blst_p2::default();
+    for (index, signature) in shares {
+        let point = p2_uncompress(signature)?;
+        combined = p2_add(&combined, &p2_mult(&point, &lagrange_at_zero(&indices, *index)?));
+    }
+
+    Some(p2_compress(&combined))
+}
+
+/// The part of the key generation a member deals to the others.
+///
+/// The dealer picks a random polynomial of degree `threshold - 1` and gives
+/// the member with index `i` its value at `i`. It broadcasts the
+/// [`DealingCommitments`] to the coefficients, which let every member check
+/// the value it got without learning the polynomial. The committee key is the
+/// sum of the polynomials of all dealers.
+#[derive(Clone)]
+pub struct Dealing {
+    pub commitments: DealingCommitments,
+    /// the value of the polynomial at the index of every member, each is sent
+    /// to the member it's for alone
+    shares:          Vec<B256>
+}
+
+impl std::fmt::Debug for Dealing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dealing")
+            .field("commitments", &self.commitments)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dealing {
+    pub fn new(threshold: usize, members: usize) -> Self {
+        let coefficients = (0..threshold).map(|_| random_fr()).collect::<Vec<_>>();
+        let commitments = coefficients
+            .iter()
+            .map(|coefficient| p1_compress(&g1_mult(coefficient)))
+            .collect();
+        let shares = (1..=members as u64)
+            .map(|index| fr_to_bytes(&evaluate(&coefficients, index)))
+            .collect();
+
+        Self { commitments: DealingCommitments(commitments), shares }
+    }
+
+    /// The secret value the dealer sends to the member with this index.
+    pub fn share_for(&self, index: u64) -> Option<B256> {
+        let position = usize::try_from(index).ok()?.checked_sub(1)?;
+        self.shares.get(position).copied()
+    }
+}
+
+/// `g1 * coefficient` of every coefficient of the polynomial of a dealer, the
+/// constant first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DealingCommitments(pub Vec<BlsPublicKey>);
+
+impl DealingCommitments {
+    /// Whether the value sent to the member with this index is the one the
+    /// dealer committed to.
+    pub fn verify_share(&self, index: u64, share: &B256) -> bool {
+        let Some(share) = fr_from_bytes(share) else { return false };
+        let Some(expected) = self.public_share(index) else { return false };
+
+        p1_compress(&g1_mult(&share)) == expected
+    }
+
+    /// `g1 * value` of the value at this index, computed from the
+    /// commitments alone.
+    fn public_share(&self, index: u64) -> Option<BlsPublicKey> {
+        let (x, mut power) = (fr_from_u64(index), fr_from_u64(1));
+        let mut sum = blst_p1::default();
+        for commitment in &self.0 {
+            sum = p1_add(&sum, &p1_mult(&p1_uncompress(commitment)?, &power));
+            power = fr_mul(&power, &x);
+        }
+
+        Some(p1_compress(&sum))
+    }
+}
+
+/// The key share of the member with `index` from the values it got from every
+/// dealer, each already checked with [`DealingCommitments::verify_share`].
+pub fn finish_key_share(index: u64, received: &[B256]) -> Option<KeyShare> {
+    let mut secret = blst_fr::default();
+    for share in received {
+        secret = fr_add(&secret, &fr_from_bytes(share)?);
+    }
+
+    let secret = fr_to_bytes(&secret);
+    SecretKey::from_bytes(secret.as_slice()).ok()?;
+    Some(KeyShare { index, secret })
+}
+
+/// The public committee key of the commitments of every dealer for `members`
+/// members.
+pub fn finish_public_key(
+    dealings: &[DealingCommitments],
+    members: usize
+) -> Option<ThresholdPublicKey> {
+    let sum = |points: Vec<BlsPublicKey>| -> Option<BlsPublicKey> {
+        let mut sum = blst_p1::default();
+        for point in points {
+            sum = p1_add(&sum, &p1_uncompress(&point)?);
+        }
+        Some(p1_compress(&sum))
+    };
+
+    let public_key = sum(dealings
+        .iter()
+        .map(|dealing| dealing.0.first().copied())
+        .collect::<Option<_>>()?)?;
+    let shares = (1..=members as u64)
+        .map(|index| {
+            sum(dealings
+                .iter()
+                .map(|dealing| dealing.public_share(index))
+                .collect::<Option<_>>()?)
+        })
+        .collect::<Option<_>>()?;
+
+    Some(ThresholdPublicKey { public_key, shares })
+}
+
+fn random_fr() -> blst_fr {
+    let secret = SecretKey::key_gen(B256::random().as_slice(), &[]).expect("32 bytes of ikm");
+    fr_from_bytes(&secret.to_bytes().into()).expect("secret keys are scalars")
+}
+
+fn fr_from_u64(value: u64) -> blst_fr {
+    let mut fr = blst_fr::default();
+    unsafe { blst_fr_from_uint64(&mut fr, [value, 0, 0, 0].as_ptr()) };
+    fr
+}
+
+/// Reads a big endian scalar, `None` if it isn't below the group order.
+fn fr_from_bytes(bytes: &B256) -> Option<blst_fr> {
+    let mut scalar = blst_scalar::default();
+    let mut fr = blst_fr::default();
+    unsafe {
+        blst_scalar_from_bendian(&mut scalar, bytes.as_ptr());
+        if !blst_scalar_fr_check(&scalar) {
+            return None
+        }
+        blst_fr_from_scalar(&mut fr, &scalar);
+    }
+
+    Some(fr)
+}
+
+fn fr_to_bytes(fr: &blst_fr) -> B256 {
+    let mut out = B256::ZERO;
+    unsafe { blst_bendian_from_scalar(out.as_mut_ptr(), &fr_to_scalar(fr)) };
+    out
+}
+
+fn fr_to_scalar(fr: &blst_fr) -> blst_scalar {
+    let mut scalar = blst_scalar::default();
+    unsafe { blst_scalar_from_fr(&mut scalar, fr) };
+    scalar
+}
+
+/// Value of the polynomial with the coefficients, constant first, at `x`.
+fn evaluate(coefficients: &[blst_fr], x: u64) -> blst_fr {
+    let x = fr_from_u64(x);
+    coefficients
+        .iter()
+        .rev()
+        .fold(blst_fr::default(), |value, coefficient| fr_add(&fr_mul(&value, &x), coefficient))
+}
+
+/// Lagrange coefficient of `index` at `x = 0` among the `indices`, `None`
+/// if the indices aren't distinct and non zero.
+fn lagrange_at_zero(indices: &[u64], index: u64) -> Option<blst_fr> {
+    if indices.contains(&0) || indices.iter().filter(|other| **other == index).count() != 1 {
+        return None
+    }
+
+    let x_i = fr_from_u64(index);
+    let (numerator, denominator) = indices
+        .iter()
+        .filter(|other| **other != index)
+        .map(|other| fr_from_u64(*other))
+        .fold((fr_from_u64(1), fr_from_u64(1)), |(numerator, denominator), x_j| {
+            (fr_mul(&numerator, &x_j), fr_mul(&denominator, &fr_sub(&x_j, &x_i)))
+        });
+
+    let mut inverse = blst_fr::default();
+    unsafe { blst_fr_inverse(&mut inverse, &denominator) };
+    Some(fr_mul(&numerator, &inverse))
+}
+
+fn fr_add(a: &blst_fr, b: &blst_fr) -> blst_fr {
+    let mut out = blst_fr::default();
+    unsafe { blst_fr_add(&mut out, a, b) };
+    out
+}
+
+fn fr_sub(a: &blst_fr, b: &blst_fr) -> blst_fr {
+    let mut out = blst_fr::default();
+    unsafe { blst_fr_sub(&mut out, a, b) };
+    out
+}
+
+fn fr_mul(a: &blst_fr, b: &blst_fr) -> blst_fr {
+    let mut out = blst_fr::default();
+    unsafe { blst_fr_mul(&mut out, a, b) };
+    out
+}
+
+fn p1_add(a: &blst_p1, b: &blst_p1) -> blst_p1 {
+    let mut out = blst_p1::default();
+    unsafe { blst_p1_add_or_double(&mut out, a, b) };
+    out
+}
+
+fn p2_add(a: &blst_p2, b: &blst_p2) -> blst_p2 {
+    let mut out = blst_p2::default();
+    unsafe { blst_p2_add_or_double(&mut out, a, b) };
+    out
+}
+
+fn g1_mult(scalar: &blst_fr) -> blst_p1 {
+    p1_mult(unsafe { &*blst_p1_generator() }, scalar)
+}
+
+fn p1_mult(point: &blst_p1, scalar: &blst_fr) -> blst_p1 {
+    let mut out = blst_p1::default();
+    unsafe { blst_p1_mult(&mut out, point, fr_to_scalar(scalar).b.as_ptr(), SCALAR_BITS) };
+    out
+}
+
+fn p2_mult(point: &blst_p2, scalar: &blst_fr) -> blst_p2 {
+    let mut out = blst_p2::default();
+    unsafe { blst_p2_mult(&mut out, point, fr_to_scalar(scalar).b.as_ptr(), SCALAR_BITS) };
+    out
+}
+
+fn p1_compress(point: &blst_p1) -> BlsPublicKey {
+    let mut out = BlsPublicKey::ZERO;
+    unsafe { blst_p1_compress(out.as_mut_ptr(), point) };
+    out
+}
+
+fn p2_compress(point: &blst_p2) -> BlsSignature {
+    let mut out = BlsSignature::ZERO;
+    unsafe { blst_p2_compress(out.as_mut_ptr(), point) };
+    out
+}
+
+fn p1_uncompress(bytes: &BlsPublicKey) -> Option<blst_p1> {
+    let mut affine = blst_p1_affine::default();
+    let mut point = blst_p1::default();
+    unsafe {
+        if blst_p1_uncompress(&mut affine, bytes.as_ptr()) != BLST_ERROR::BLST_SUCCESS {
+            return None
+        }
+        blst_p1_from_affine(&mut point, &affine);
+    }
+
+    Some(point)
+}
+
+fn p2_uncompress(bytes: &BlsSignature) -> Option<blst_p2> {
+    let mut affine = blst_p2_affine::default();
+    let mut point = blst_p2::default();
+    unsafe {
+        if blst_p2_uncompress(&mut affine, bytes.as_ptr()) != BLST_ERROR::BLST_SUCCESS {
+            return None
+        }
+        blst_p2_from_affine(&mut point, &affine);
+    }
+
+    Some(point)
+}
+
+/// Runs the key generation with every member dealing honestly.
+#[cfg(test)]
+pub(crate) fn generate(threshold: usize, members: usize) -> (ThresholdPublicKey, Vec<KeyShare>) {
+    let dealings = (0..members)
+        .map(|_| Dealing::new(threshold, members))
+        .collect::<Vec<_>>();
+    let shares = (1..=members as u64)
+        .map(|index| {
+            let received = dealings
+                .iter()
+                .map(|dealing| dealing.share_for(index).unwrap())
+                .collect::<Vec<_>>();
+            assert!(dealings
+                .iter()
+                .zip(&received)
+                .all(|(dealing, share)| dealing.commitments.verify_share(index, share)));
+            finish_key_share(index, &received).unwrap()
+        })
+        .collect();
+
+    let commitments = dealings
+        .into_iter()
+        .map(|dealing| dealing.commitments)
+        .collect::<Vec<_>>();
+    (finish_public_key(&commitments, members).unwrap(), shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_threshold_of_shares_signs_for_the_committee() {
+        let (public, shares) = generate(3, 5);
+        let message = b"proposal";
+        let sign = |share: &KeyShare| (share.index, share.sign(message));
+        for share in &shares {
+            assert_eq!(public.share(share.index), Some(&share.public_key()));
+            assert!(verify(&share.public_key(), message, &sign(share).1));
+        }
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let signatures = subset.map(|member| sign(&shares[member]));
+            let combined = combine(&signatures).unwrap();
+            assert!(verify(&public.public_key, message, &combined));
+            assert!(!verify(&public.public_key, b"another proposal", &combined));
+        }
+
+        // short of the threshold the shares don't add up to the key
+        let combined = combine(&[sign(&shares[0]), sign(&shares[1])]).unwrap();
+        assert!(!verify(&public.public_key, message, &combined));
+        // and a share can't be counted twice
+        assert_eq!(combine(&[sign(&shares[0]), sign(&shares[0]), sign(&shares[1])]), None);
+    }
+
+    #[test]
+    fn catches_shares_the_dealer_didnt_commit_to() {
+        let dealing = Dealing::new(2, 3);
+        let share = dealing.share_for(2).unwrap();
+        assert!(dealing.commitments.verify_share(2, &share));
+        assert!(!dealing.commitments.verify_share(1, &share));
+        assert!(!dealing
+            .commitments
+            .verify_share(2, &B256::with_last_byte(1)));
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy::primitives::{Address, U256};
+
+use super::AngstromBundle;
+use crate::contract_payloads::{Asset, Pair};
+
+impl AngstromBundle {
+    /// Merges the assets and pairs that appear more than once and points the
+    /// pool updates and orders at the entries that are left. The merged assets
+    /// are sorted by address, pairs keep the order they first appeared in.
+    ///
+    /// The order hashes don't change, they are derived from the addresses the
+    /// indices resolve to, not from the indices themselves.
+    pub fn dedup_assets_and_pairs(&mut self) -> eyre::Result<()> {
+        self.verify_indices()?;
+
+        let mut merged = BTreeMap::<Address, Asset>::new();
+        for asset in &self.assets {
+            let entry = merged
+                .entry(asset.addr)
+                .or_insert_with(|| Asset { addr: asset.addr, ..Default::default() });
+            entry.save = checked_sum(asset.addr, entry.save, asset.save)?;
+            entry.take = checked_sum(asset.addr, entry.take, asset.take)?;
+            entry.settle = checked_sum(asset.addr, entry.settle, asset.settle)?;
+        }
+        let asset_idx = merged
+            .keys()
+            .enumerate()
+            .map(|(idx, addr)| (*addr, idx as u16))
+            .collect::<HashMap<_, _>>();
+        let asset_map = self
+            .assets
+            .iter()
+            .map(|asset| asset_idx[&asset.addr])
+            .collect::<Vec<_>>();
+
+        let mut pairs = Vec::new();
+        let mut pair_idx = HashMap::<(u16, u16, u16, U256), u16>::new();
+        let mut pair_map = Vec::with_capacity(self.pairs.len());
+        for pair in &self.pairs {
+            let index0 = asset_map[pair.index0 as usize];
+            let index1 = asset_map[pair.index1 as usize];
+            let idx = *pair_idx
+                .entry((index0, index1, pair.store_index, pair.price_1over0))
+                .or_insert_with(|| {
+                    pairs.push(Pair {
+                        index0,
+                        index1,
+                        store_index: pair.store_index,
+                        price_1over0: pair.price_1over0
+                    });
+                    (pairs.len() - 1) as u16
+                });
+            pair_map.push(idx);
+        }
+
+        self.pool_updates
+            .iter_mut()
+            .for_each(|update| update.pair_index = pair_map[update.pair_index as usize]);
+        self.top_of_block_orders
+            .iter_mut()
+            .for_each(|order| order.pairs_index = pair_map[order.pairs_index as usize]);
+        self.user_orders
+            .iter_mut()
+            .for_each(|order| order.pair_index = pair_map[order.pair_index as usize]);
+        self.assets = merged.into_values().collect();
+        self.pairs = pairs;
+
+        self.verify_indices()
+    }
+
+    /// Checks that every pair points at two distinct assets and that every
+    /// pool update and order points at a pair of the bundle.
+    pub fn verify_indices(&self) -> eyre::Result<()> {
+        if self.assets.len() > u16::MAX as usize + 1 || self.pairs.len() > u16::MAX as usize + 1 {
+            return Err(eyre::eyre!("bundle has more assets or pairs than can be indexed"))
+        }
+
+        for (idx, pair) in self.pairs.iter().enumerate() {
+            if pair.index0 as usize >= self.assets.len()
+                || pair.index1 as usize >= self.assets.len()
+            {
+                return Err(eyre::eyre!(
+                    "pair {idx} points at assets ({}, {}) of {}",
+                    pair.index0,
+                    pair.index1,
+                    self.assets.len()
+                ))
+            }
+            if pair.index0 == pair.index1 {
+                return Err(eyre::eyre!("pair {idx} trades asset {} against itself", pair.index0))
+            }
+        }
+
+        let pair_indices = self
+            .pool_updates
+            .iter()
+            .map(|update| ("pool update", update.pair_index))
+            .chain(
+                self.top_of_block_orders
+                    .iter()
+                    .map(|order| ("top of block order", order.pairs_index))
+            )
+            .chain(
+                self.user_orders
+                    .iter()
+                    .map(|order| ("user order", order.pair_index))
+            );
+        for (kind, pair_index) in pair_indices {
+            if pair_index as usize >= self.pairs.len() {
+                return Err(eyre::eyre!(
+                    "{kind} points at pair {pair_index} of {}",
+                    self.pairs.len()
+                ))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn checked_sum(asset: Address, a: u128, b: u128) -> eyre::Result<u128> {
+    a.checked_add(b)
+        .ok_or_else(|| eyre::eyre!("merged amounts of asset {asset:?} overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pade::PadeEncode;
+
+    use super::*;
+    use crate::contract_payloads::{
+        angstrom::{OrderQuantities, TopOfBlockOrder, UserOrder},
+        rewards::{PoolUpdate, RewardsUpdate},
+        Signature
+    };
+
+    fn user_order(pair_index: u16, quantity: u128) -> UserOrder {
+        UserOrder {
+            ref_id: 0,
+            use_internal: false,
+            pair_index,
+            min_price: U256::from(1),
+            recipient: None,
+            hook_data: None,
+            zero_for_one: true,
+            standing_validation: None,
+            order_quantities: OrderQuantities::Exact { quantity },
+            max_extra_fee_asset0: 0,
+            extra_fee_asset0: 0,
+            pool_fee_asset0: 0,
+            exact_in: true,
+            signature: Signature::default()
+        }
+    }
+
+    /// Every pool gets its own copy of both assets and every order its own
+    /// copy of the pair, as a naive encoder would emit them.
+    fn redundant_bundle(pools: usize, orders_per_pool: usize) -> AngstromBundle {
+        let tokens = (0..pools + 1)
+            .map(|_| Address::random())
+            .collect::<Vec<_>>();
+        let mut bundle = AngstromBundle::new(vec![], vec![], vec![], vec![], vec![]);
+        for pool in 0..pools {
+            let index0 = bundle.assets.len() as u16;
+            for addr in [tokens[pool], tokens[pool + 1]] {
+                bundle
+                    .assets
+                    .push(Asset { addr, save: 1, take: 2, settle: 3 });
+            }
+            for order in 0..orders_per_pool {
+                bundle.pairs.push(Pair {
+                    index0,
+                    index1: index0 + 1,
+                    store_index: pool as u16,
+                    price_1over0: U256::from(pool + 1)
+                });
+                let pair_index = (bundle.pairs.len() - 1) as u16;
+                bundle
+                    .user_orders
+                    .push(user_order(pair_index, (order + 1) as u128));
+                if order == 0 {
+                    bundle.pool_updates.push(PoolUpdate {
+                        zero_for_one: true,
+                        pair_index,
+                        swap_in_quantity: 0,
+                        rewards_update: RewardsUpdate::CurrentOnly { amount: 0 }
+                    });
+                    bundle
+                        .top_of_block_orders
+                        .push(TopOfBlockOrder { pairs_index: pair_index, ..Default::default() });
+                }
+            }
+        }
+
+        bundle
+    }
+
+    #[test]
+    fn dedup_shrinks_calldata_and_keeps_hashes() {
+        let mut bundle = redundant_bundle(50, 10);
+        let size_before = bundle.pade_encode().len();
+        let hashes_before = bundle.get_order_hashes(1).collect::<Vec<_>>();
+
+        bundle.dedup_assets_and_pairs().unwrap();
+
+        assert_eq!(bundle.assets.len(), 51);
+        assert_eq!(bundle.pairs.len(), 50);
+        assert!(bundle.assets.windows(2).all(|w| w[0].addr < w[1].addr));
+        // inner tokens were shared by two pools
+        assert!(bundle.assets.iter().any(|asset| asset.take == 4));
+        assert_eq!(bundle.get_order_hashes(1).collect::<Vec<_>>(), hashes_before);
+
+        // only the dropped entries are saved, the orders encode to the same size
+        let asset_len = Asset::default().pade_encode().len();
+        let pair_len = Pair::default().pade_encode().len();
+        assert_eq!(
+            size_before - bundle.pade_encode().len(),
+            (100 - 51) * asset_len + (500 - 50) * pair_len
+        );
+    }
+
+    #[test]
+    fn dedup_of_deduped_bundle_is_noop() {
+        let mut bundle = redundant_bundle(5, 3);
+        bundle.dedup_assets_and_pairs().unwrap();
+        let encoded = bundle.pade_encode();
+
+        bundle.dedup_assets_and_pairs().unwrap();
+        assert_eq!(bundle.pade_encode(), encoded);
+    }
+
+    #[test]
+    fn rejects_dangling_indices() {
+        let mut bundle = redundant_bundle(2, 1);
+        bundle.user_orders[0].pair_index = 2;
+        assert!(bundle.dedup_assets_and_pairs().is_err());
+
+        let mut bundle = redundant_bundle(2, 1);
+        bundle.pairs[0].index1 = 4;
+        assert!(bundle.verify_indices().is_err());
+
+        let mut bundle = redundant_bundle(2, 1);
+        bundle.pairs[1].index0 = bundle.pairs[1].index1;
+        assert!(bundle.verify_indices().is_err());
+    }
+}
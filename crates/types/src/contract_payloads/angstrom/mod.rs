@@ -2,7 +2,7 @@ use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
     ops::Deref,
-    sync::Arc
+    sync::{Arc, RwLock}
 };
 
 use alloy::{
@@ -20,6 +20,7 @@ use tracing::{debug, trace, warn};
 
 use super::{
     asset::builder::{AssetBuilder, AssetBuilderStage},
+    fees::{FeeConfig, PoolFees},
     rewards::PoolUpdate,
     tob::ToBOutcome,
     Asset, Pair, CONFIG_STORE_SLOT, POOL_CONFIG_STORE_ENTRY_SIZE
@@ -27,8 +28,8 @@ use super::{
 use crate::{
     consensus::{PreProposal, Proposal},
     contract_bindings::angstrom::Angstrom::PoolKey,
-    matching::{uniswap::PoolSnapshot, Ray},
-    orders::{OrderFillState, OrderOutcome, PoolSolution},
+    matching::{narrow_u128, uniswap::PoolSnapshot, CheckedMath, Ray},
+    orders::{check_rings, OrderFillState, OrderOutcome, PoolSolution},
     primitive::{PoolId, UniswapPoolRegistry},
     sol_bindings::{
         grouped_orders::{GroupedVanillaOrder, OrderWithStorageData},
@@ -38,10 +39,13 @@ use crate::{
     testnet::TestnetStateOverrides
 };
 
+mod dedup;
 mod order;
 mod tob;
+mod version;
 pub use order::{OrderQuantities, StandingValidation, UserOrder};
 pub use tob::*;
+pub use version::ContractVersion;
 
 #[derive(Debug, PadeEncode, PadeDecode)]
 pub struct AngstromBundle {
@@ -213,7 +217,7 @@ impl AngstromBundle {
         });
 
         // Get our list of user orders, if we have any
-        top_of_block_orders.push(TopOfBlockOrder::of_max_gas(user_order, 0));
+        top_of_block_orders.push(TopOfBlockOrder::of_max_gas(user_order, 0)?);
 
         Ok(Self::new(
             asset_builder.get_asset_array(),
@@ -276,7 +280,7 @@ impl AngstromBundle {
                 user_order,
                 &outcome,
                 pair_idx as u16
-            ));
+            )?);
         }
 
         Ok(Self::new(
@@ -353,7 +357,8 @@ impl AngstromBundle {
                 *t0,
                 *t1,
                 *store_index,
-                None
+                None,
+                &FeeConfig::default()
             )?;
         }
         Ok(Self::new(
@@ -425,8 +430,48 @@ impl AngstromBundle {
         t0: Address,
         t1: Address,
         store_index: u16,
-        shared_gas: Option<U256>
-    ) -> eyre::Result<()> {
+        shared_gas: Option<U256>,
+        fees: &FeeConfig
+    ) -> eyre::Result<PoolFees> {
+        Self::process_solution_with_tob_outcome(
+            pairs,
+            asset_builder,
+            user_orders,
+            orders_by_pool,
+            top_of_block_orders,
+            pool_updates,
+            solution,
+            snapshot,
+            t0,
+            t1,
+            store_index,
+            shared_gas,
+            None,
+            fees
+        )
+    }
+
+    /// Like [`Self::process_solution`], with the outcome of the top of block
+    /// order of the solution against `snapshot` if it was simulated already.
+    pub fn process_solution_with_tob_outcome(
+        pairs: &mut Vec<Pair>,
+        asset_builder: &mut AssetBuilder,
+        user_orders: &mut Vec<UserOrder>,
+        orders_by_pool: &HashMap<
+            FixedBytes<32>,
+            HashSet<OrderWithStorageData<GroupedVanillaOrder>>
+        >,
+        top_of_block_orders: &mut Vec<TopOfBlockOrder>,
+        pool_updates: &mut Vec<PoolUpdate>,
+        solution: &PoolSolution,
+        snapshot: &PoolSnapshot,
+        t0: Address,
+        t1: Address,
+        store_index: u16,
+        shared_gas: Option<U256>,
+        tob_outcome: Option<&ToBOutcome>,
+        fees: &FeeConfig
+    ) -> eyre::Result<PoolFees> {
         // Dump the solution
         let json = serde_json::to_string(&(
             solution,
@@ -466,7 +511,9 @@ impl AngstromBundle {
             .as_ref()
             .map(|tob| {
                 trace!(tob_order = ?tob, "Mapping TOB Swap");
-                let outcome = ToBOutcome::from_tob_and_snapshot(tob, snapshot).ok();
+                let outcome = tob_outcome
+                    .cloned()
+                    .or_else(|| ToBOutcome::from_tob_and_snapshot(tob, snapshot).ok());
                 // Make sure the input for our swap is precisely what's used in the swap portion
                 let input = if let Some(ref o) = outcome {
                     o.total_cost.clone().saturating_to()
@@ -488,13 +535,28 @@ impl AngstromBundle {
             (Some(amm), Some(tob)) => {
                 if amm.0 == tob.0 {
                     // If they're in the same direction we just sum them
-                    Some((amm.0, amm.1, (amm.2 + tob.2), (amm.3 + tob.3)))
+                    Some((
+                        amm.0,
+                        amm.1,
+                        amm.2.try_add(tob.2, "merged amm input")?,
+                        amm.3.try_add(tob.3, "merged amm output")?
+                    ))
                 } else {
                     // If they're in opposite directions then we see if we have to flip them
                     if tob.2 > amm.3 {
-                        Some((tob.0, tob.1, tob.2 - amm.3, tob.3 - amm.2))
+                        Some((
+                            tob.0,
+                            tob.1,
+                            tob.2 - amm.3,
+                            tob.3.try_sub(amm.2, "merged amm output")?
+                        ))
                     } else {
-                        Some((amm.0, amm.1, amm.2 - tob.3, amm.3 - tob.2))
+                        Some((
+                            amm.0,
+                            amm.1,
+                            amm.2.try_sub(tob.3, "merged amm input")?,
+                            amm.3 - tob.2
+                        ))
                     }
                 }
             }
@@ -506,6 +568,7 @@ impl AngstromBundle {
             merged_amm_swap.unwrap_or((t0_idx, t1_idx, 0_u128, 0_u128));
         // If we don't have a rewards update, we insert a default "empty" struct
         let tob_outcome = tob_rewards.unwrap_or_default();
+        let searcher_reward: u128 = tob_outcome.total_reward.saturating_to();
 
         // Determine whether our net AMM order is zero_for_one
         let zero_for_one = asset_in_index == t0_idx;
@@ -528,6 +591,7 @@ impl AngstromBundle {
             swap_in_quantity: quantity_in,
             rewards_update
         });
+        let update_idx = pool_updates.len() - 1;
 
         // Add the ToB order to our tob order list - This is currently converting
         // between two ToB order formats
@@ -545,7 +609,7 @@ impl AngstromBundle {
             let contract_tob = if let Some(g) = shared_gas {
                 TopOfBlockOrder::of(tob, g, pair_idx as u16)?
             } else {
-                TopOfBlockOrder::of_max_gas(tob, pair_idx as u16)
+                TopOfBlockOrder::of_max_gas(tob, pair_idx as u16)?
             };
             top_of_block_orders.push(contract_tob);
         }
@@ -565,6 +629,9 @@ impl AngstromBundle {
         // Loop through our filled user orders, do accounting, and add them to our user
         // order list
         let ray_ucp = Ray::from(ucp);
+        let (mut surplus, mut charged) = (0u128, 0u128);
+        let (mut bid_volume, mut ask_volume) = (0u128, 0u128);
+        let (mut bid_t1, mut ask_t1) = (0u128, 0u128);
         for (outcome, order) in solution
             .limit
             .iter()
@@ -573,37 +640,136 @@ impl AngstromBundle {
         {
             // Calculate our final amounts based on whether the order is in T0 or T1 context
             let inverse_order = order.is_bid() == order.exact_in();
-            assert_eq!(outcome.id.hash, order.order_id.hash, "Order and outcome mismatched");
+            if outcome.id.hash != order.order_id.hash {
+                return Err(eyre::eyre!("order and outcome mismatched"))
+            }
             let (t0_moving, t1_moving) = if inverse_order {
                 let t1_moving = outcome.fill_amount(order.max_q());
-                let t0_moving = ray_ucp.inverse_quantity(t1_moving, !order.is_bid());
+                let t0_moving = ray_ucp.checked_inverse_quantity(t1_moving, !order.is_bid())?;
                 (U256::from(t0_moving), U256::from(t1_moving))
             } else {
                 let t0_moving = U256::from(outcome.fill_amount(order.max_q()));
-                let t1_moving = Ray::from(ucp).mul_quantity(t0_moving);
+                let t1_moving = ray_ucp.checked_mul_quantity(t0_moving)?;
                 (t0_moving, t1_moving)
             };
+            // the contract pays the order of a ring leg at the clearing price like
+            // any other, which has to be what the ring pays it
+            if let Some(leg) = solution
+                .ring
+                .as_ref()
+                .filter(|leg| leg.order == outcome.id.hash)
+            {
+                let (given, received) =
+                    if order.is_bid { (t1_moving, t0_moving) } else { (t0_moving, t1_moving) };
+                let tokens = if order.is_bid { (t1, t0) } else { (t0, t1) };
+                if tokens != (leg.token_in, leg.token_out)
+                    || given != U256::from(leg.quantity_in)
+                    || received != U256::from(leg.quantity_out)
+                {
+                    return Err(eyre::eyre!(
+                        "ring leg of order {:?} doesn't settle at the clearing price of its pool",
+                        leg.order
+                    ))
+                }
+            }
+
+            let order_surplus = PoolFees::order_surplus(
+                ray_ucp,
+                order.price_for_book_side(order.is_bid),
+                order.is_bid,
+                t0_moving.saturating_to()
+            );
+            surplus = surplus.try_add(order_surplus, "pool surplus")?;
+            let (side_volume, side_t1) = if order.is_bid {
+                (&mut bid_volume, &mut bid_t1)
+            } else {
+                (&mut ask_volume, &mut ask_t1)
+            };
+            *side_volume = side_volume.saturating_add(t0_moving.saturating_to());
+            *side_t1 = side_t1.saturating_add(t1_moving.saturating_to());
 
-            let (quantity_in, quantity_out) =
-                if order.is_bid { (t1_moving, t0_moving) } else { (t0_moving, t1_moving) };
+            let mut user_order = if let Some(g) = shared_gas {
+                UserOrder::from_internal_order(order, outcome, g, pair_idx as u16)?
+            } else {
+                UserOrder::from_internal_order_max_gas(order, outcome, pair_idx as u16)?
+            };
+            // the pool fee is charged in token0 on top of the gas, as far as the
+            // order allows for
+            let fee = fees
+                .fee_on(solution.id, order_surplus)
+                .min(
+                    user_order
+                        .max_extra_fee_asset0
+                        .saturating_sub(user_order.extra_fee_asset0)
+                )
+                .min(t0_moving.saturating_to());
+            user_order.pool_fee_asset0 = fee;
+            charged = charged.try_add(fee, "pool fees")?;
+
+            // asks pay the fee on top of their token0, bids receive that much less
+            let (quantity_in, quantity_out) = if order.is_bid {
+                (t1_moving, t0_moving - U256::from(fee))
+            } else {
+                (t0_moving + U256::from(fee), t1_moving)
+            };
 
-            trace!(quantity_in = ?quantity_in, quantity_out = ?quantity_out, is_bid = order.is_bid, exact_in = order.exact_in(), "Processing user order");
+            trace!(quantity_in = ?quantity_in, quantity_out = ?quantity_out, fee, is_bid = order.is_bid, exact_in = order.exact_in(), "Processing user order");
             // Account for our user order
             let (asset_in, asset_out) = if order.is_bid { (t1, t0) } else { (t0, t1) };
             asset_builder.external_swap(
                 AssetBuilderStage::UserOrder,
                 asset_in,
                 asset_out,
-                quantity_in.to(),
-                quantity_out.to()
+                narrow_u128(quantity_in, "user order quantity in")?,
+                narrow_u128(quantity_out, "user order quantity out")?
             );
-            let user_order = if let Some(g) = shared_gas {
-                UserOrder::from_internal_order(order, outcome, g, pair_idx as u16)?
-            } else {
-                UserOrder::from_internal_order_max_gas(order, outcome, pair_idx as u16)
-            };
             user_orders.push(user_order);
         }
+
+        // what both sides saved over swapping with the AMM alone
+        let amm_surplus = PoolFees::amm_only_surplus(snapshot, ray_ucp, true, bid_volume, bid_t1)
+            .saturating_add(PoolFees::amm_only_surplus(
+                snapshot, ray_ucp, false, ask_volume, ask_t1
+            ));
+        // the fees the orders were charged are split, the LPs part is donated
+        // along with the rewards of the pool
+        let pool_fees = PoolFees {
+            matched_volume: bid_volume.max(ask_volume),
+            amm_surplus,
+            searcher_reward,
+            ..fees.split(solution.id, t0, charged, surplus)
+        };
+        if pool_fees.total() > 0 {
+            asset_builder.allocate(AssetBuilderStage::Reward, t0, pool_fees.lp);
+            asset_builder.save(AssetBuilderStage::Reward, t0, pool_fees.protocol);
+            pool_updates[update_idx]
+                .rewards_update
+                .add_donation(pool_fees.lp);
+        }
+
+        Ok(pool_fees)
+    }
+
+    /// Checks that the bundle saves exactly the protocol fees of each asset.
+    pub fn reconcile_fees(&self, fees: &[PoolFees]) -> eyre::Result<()> {
+        let mut expected = HashMap::<Address, u128>::new();
+        fees.iter()
+            .for_each(|fee| *expected.entry(fee.asset).or_default() += fee.protocol);
+
+        for asset in &self.assets {
+            let protocol = expected.remove(&asset.addr).unwrap_or_default();
+            if asset.save != protocol {
+                return Err(eyre::eyre!(
+                    "asset {:?} saves {} but the protocol fees are {protocol}",
+                    asset.addr,
+                    asset.save
+                ))
+            }
+        }
+        if let Some((asset, _)) = expected.into_iter().find(|(_, protocol)| *protocol > 0) {
+            return Err(eyre::eyre!("protocol fees in {asset:?} which is not in the bundle"))
+        }
+
         Ok(())
     }
 
@@ -612,7 +778,34 @@ impl AngstromBundle {
         gas_details: BundleGasDetails,
         pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
     ) -> eyre::Result<Self> {
+        Self::from_proposal_with_fees(proposal, gas_details, pools, &FeeConfig::default())
+            .map(|(bundle, _)| bundle)
+    }
+
+    /// Builds the bundle with the fees of every pool taken from its matched
+    /// surplus. Returns the fees that were taken next to the bundle.
+    pub fn from_proposal_with_fees(
+        proposal: &Proposal,
+        gas_details: BundleGasDetails,
+        pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        fees: &FeeConfig
+    ) -> eyre::Result<(Self, Vec<PoolFees>)> {
+        Self::from_proposal_with_tob_outcomes(proposal, gas_details, pools, fees, &HashMap::new())
+    }
+
+    /// Like [`Self::from_proposal_with_fees`], reusing the outcomes of the top
+    /// of block orders, by order hash, that were simulated against `pools`
+    /// already.
+    pub fn from_proposal_with_tob_outcomes(
+        proposal: &Proposal,
+        gas_details: BundleGasDetails,
+        pools: &HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        fees: &FeeConfig,
+        tob_outcomes: &HashMap<B256, ToBOutcome>
+    ) -> eyre::Result<(Self, Vec<PoolFees>)> {
         trace!("Starting from_proposal");
+        // the orders of a ring are only paid by each other
+        check_rings(&proposal.solutions)?;
         let mut top_of_block_orders = Vec::new();
         let mut pool_updates = Vec::new();
         let mut pairs = Vec::new();
@@ -631,16 +824,18 @@ impl AngstromBundle {
 
         // this should never underflow. if it does. means that there is underlying
         // problem with the gas delegation module
-        assert!(
-            gas_details.total_gas_cost_wei > total_gas,
-            "Total gas cost '{}' greater than total gas '{}'",
-            gas_details.total_gas_cost_wei,
-            total_gas
-        );
+        if gas_details.total_gas_cost_wei <= total_gas {
+            return Err(eyre::eyre!(
+                "total gas cost '{}' doesn't cover the gas delegated to orders '{}'",
+                gas_details.total_gas_cost_wei,
+                total_gas
+            ))
+        }
         if total_swaps == 0 {
             return Err(eyre::eyre!("have a total swaps count of 0"));
         }
         let shared_gas_in_wei = (gas_details.total_gas_cost_wei - total_gas) / total_swaps;
+        let mut pool_fees = Vec::new();
 
         // fetch gas used
         // Walk through our solutions to add them to the structure
@@ -658,19 +853,26 @@ impl AngstromBundle {
             };
 
             // Get our shared gas information
-            let conversion_rate_to_token0 =
-                gas_details.token_price_per_wei.get(&(*t0, *t1)).expect(
-                    "don't have price for a critical pair. should be unreachable since no orders \
-                     would get validated. this would always be skipped"
-                );
+            // should be unreachable since no orders would get validated without it
+            let conversion_rate_to_token0 = gas_details
+                .token_price_per_wei
+                .get(&(*t0, *t1))
+                .ok_or_else(|| eyre::eyre!("don't have price for the pair {t0:?}/{t1:?}"))?;
 
             // calculate the shared amount of gas in token 0 to share over this pool
-            let shared_gas = Some(
-                (*conversion_rate_to_token0 * U256::from(shared_gas_in_wei)).scale_out_of_ray()
-            );
+            let shared_gas_cost = U256::from(shared_gas_in_wei)
+                .try_mul(U256::from(gas_details.gas_price_wei), "shared gas cost")?;
+            let shared_gas_t0 = conversion_rate_to_token0
+                .0
+                .try_mul(shared_gas_cost, "shared gas in token0")?;
+            let shared_gas = Some(Ray(shared_gas_t0).scale_out_of_ray());
 
             // Call our processing function with a fixed amount of shared gas
-            Self::process_solution(
+            let tob_outcome = solution
+                .searcher
+                .as_ref()
+                .and_then(|tob| tob_outcomes.get(&tob.order_id.hash));
+            pool_fees.push(Self::process_solution_with_tob_outcome(
                 &mut pairs,
                 &mut asset_builder,
                 &mut user_orders,
@@ -682,34 +884,63 @@ impl AngstromBundle {
                 *t0,
                 *t1,
                 *store_index,
-                shared_gas
-            )?;
+                shared_gas,
+                tob_outcome,
+                fees
+            )?);
         }
-        Ok(Self::new(
+        let mut bundle = Self::new(
             asset_builder.get_asset_array(),
             pairs,
             pool_updates,
             top_of_block_orders,
             user_orders
-        ))
+        );
+        bundle.dedup_assets_and_pairs()?;
+
+        Ok((bundle, pool_fees))
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct BundleGasDetails {
     /// a map (sorted tokens) of how much of token0 in gas is needed per unit of
     /// gas
     token_price_per_wei: HashMap<(Address, Address), Ray>,
     /// total gas to execute the bundle on angstrom
-    total_gas_cost_wei:  u64
+    total_gas_cost_wei:  u64,
+    /// forecast price of a unit of gas in the block the bundle lands in
+    gas_price_wei:       u128
+}
+
+impl Default for BundleGasDetails {
+    fn default() -> Self {
+        Self::new(HashMap::default(), 0)
+    }
 }
 
 impl BundleGasDetails {
+    /// Gas is priced at a single wei until a price is set with
+    /// [`Self::with_gas_price`].
     pub fn new(
         token_price_per_wei: HashMap<(Address, Address), Ray>,
         total_gas_cost_wei: u64
     ) -> Self {
-        Self { token_price_per_wei, total_gas_cost_wei }
+        Self { token_price_per_wei, total_gas_cost_wei, gas_price_wei: 1 }
+    }
+
+    pub fn with_gas_price(mut self, gas_price_wei: u128) -> Self {
+        self.gas_price_wei = gas_price_wei;
+        self
+    }
+
+    /// Gas the bundle was estimated to take.
+    pub fn gas(&self) -> u64 {
+        self.total_gas_cost_wei
+    }
+
+    pub fn gas_price_wei(&self) -> u128 {
+        self.gas_price_wei
     }
 }
 
@@ -786,10 +1017,39 @@ impl AngstromPoolConfigStore {
         self.entries.len()
     }
 
-    pub fn remove_pair(&self, asset0: Address, asset1: Address) {
+    /// Removes the pool of the pair. The contract moves every entry that
+    /// follows it down by one, so do their store indices.
+    pub fn remove_pair(&self, asset0: Address, asset1: Address) -> Option<AngPoolConfigEntry> {
         let key = Self::derive_store_key(asset0, asset1);
 
-        self.entries.remove(&key);
+        let (_, removed) = self.entries.remove(&key)?;
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.store_index > removed.store_index)
+            .for_each(|mut entry| entry.store_index -= 1);
+
+        Some(removed)
+    }
+
+    /// Sets the parameters of the pool of the pair. A pair that is configured
+    /// again keeps its store index, a new one is appended to the store.
+    /// Returns the entry the pair had before.
+    pub fn configure_pool(
+        &self,
+        asset0: Address,
+        asset1: Address,
+        tick_spacing: u16,
+        fee_in_e6: u32
+    ) -> Option<AngPoolConfigEntry> {
+        let key = Self::derive_store_key(asset0, asset1);
+        let previous = self.entries.get(&key).map(|entry| *entry);
+        let store_index = previous.map_or_else(|| self.length(), |entry| entry.store_index);
+
+        self.entries.insert(
+            key,
+            AngPoolConfigEntry { pool_partial_key: key, tick_spacing, fee_in_e6, store_index }
+        );
+        previous
     }
 
     pub fn new_pool(&self, asset0: Address, asset1: Address, pool: AngPoolConfigEntry) {
@@ -852,9 +1112,11 @@ impl TryFrom<&[u8]> for AngstromPoolConfigStore {
     }
 }
 
+/// The pools of uniswap next to their entries in the angstrom config store.
+/// Clones share the pools, keys updated on one are seen by all of them.
 #[derive(Default, Clone)]
 pub struct UniswapAngstromRegistry {
-    uniswap_pools:         UniswapPoolRegistry,
+    uniswap_pools:         Arc<RwLock<UniswapPoolRegistry>>,
     angstrom_config_store: Arc<AngstromPoolConfigStore>
 }
 
@@ -863,11 +1125,26 @@ impl UniswapAngstromRegistry {
         uniswap_pools: UniswapPoolRegistry,
         angstrom_config_store: Arc<AngstromPoolConfigStore>
     ) -> Self {
-        UniswapAngstromRegistry { uniswap_pools, angstrom_config_store }
+        UniswapAngstromRegistry {
+            uniswap_pools: Arc::new(RwLock::new(uniswap_pools)),
+            angstrom_config_store
+        }
     }
 
     pub fn get_uni_pool(&self, pool_id: &PoolId) -> Option<PoolKey> {
-        self.uniswap_pools.get(pool_id).cloned()
+        self.uniswap_pools.read().unwrap().get(pool_id).cloned()
+    }
+
+    pub fn uniswap_pools(&self) -> UniswapPoolRegistry {
+        self.uniswap_pools.read().unwrap().clone()
+    }
+
+    /// See [`UniswapPoolRegistry::update_pool`].
+    pub fn update_pool(&self, previous: &PoolId, pool: PoolKey) -> PoolId {
+        self.uniswap_pools
+            .write()
+            .unwrap()
+            .update_pool(previous, pool)
     }
 
     pub fn get_ang_entry(&self, pool_id: &PoolId) -> Option<AngPoolConfigEntry> {
@@ -875,11 +1152,35 @@ impl UniswapAngstromRegistry {
         self.angstrom_config_store
             .get_entry(uni_entry.currency0, uni_entry.currency1)
     }
+
+    /// Whether the key of the pool still has the parameters the contract has
+    /// for its pair. Once they are changed on chain the pool id the key hashes
+    /// to isn't the one the contract settles, bundles can't include it.
+    pub fn is_current(&self, pool_id: &PoolId) -> bool {
+        let (Some(key), Some(entry)) = (self.get_uni_pool(pool_id), self.get_ang_entry(pool_id))
+        else {
+            return false
+        };
+
+        key.fee.to::<u32>() == entry.fee_in_e6
+            && key.tickSpacing.as_i32() == i32::from(entry.tick_spacing)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::AngstromBundle;
+    use std::sync::Arc;
+
+    use alloy::primitives::{
+        aliases::{I24, U24},
+        Address
+    };
+
+    use super::{AngstromBundle, AngstromPoolConfigStore, UniswapAngstromRegistry};
+    use crate::{
+        contract_bindings::angstrom::Angstrom::PoolKey,
+        primitive::{PoolId, UniswapPoolRegistry}
+    };
 
     #[test]
     fn can_be_constructed() {
@@ -941,4 +1242,80 @@ mod test {
         let user = bundle.user_orders.remove(0);
         println!("{user:?}");
     }
+
+    #[test]
+    fn keeps_store_indices_in_line_with_the_contract() {
+        let store = AngstromPoolConfigStore::default();
+        let (a, b, c) =
+            (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        assert!(store.configure_pool(a, b, 10, 0).is_none());
+        assert!(store.configure_pool(a, c, 60, 0).is_none());
+        assert!(store.configure_pool(b, c, 60, 0).is_none());
+
+        // configuring a pair again keeps its place in the store
+        let previous = store.configure_pool(a, c, 30, 500).unwrap();
+        assert_eq!((previous.tick_spacing, previous.store_index), (60, 1));
+        let entry = store.get_entry(a, c).unwrap();
+        assert_eq!((entry.tick_spacing, entry.fee_in_e6, entry.store_index), (30, 500, 1));
+
+        // removing one moves the ones after it down
+        assert_eq!(store.remove_pair(a, b).unwrap().store_index, 0);
+        assert_eq!(store.get_entry(a, c).unwrap().store_index, 0);
+        assert_eq!(store.get_entry(b, c).unwrap().store_index, 1);
+        assert!(store.remove_pair(a, b).is_none());
+    }
+
+    #[test]
+    fn keys_with_changed_parameters_are_not_current() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let key = PoolKey {
+            currency0:   a,
+            currency1:   b,
+            fee:         U24::from(500),
+            tickSpacing: I24::unchecked_from(10),
+            hooks:       Address::ZERO
+        };
+        let store = Arc::new(AngstromPoolConfigStore::default());
+        store.configure_pool(a, b, 10, 500);
+        let registry = UniswapAngstromRegistry::new(
+            UniswapPoolRegistry::from(vec![key.clone()]),
+            store.clone()
+        );
+        let pool_id = PoolId::from(key);
+
+        assert!(registry.is_current(&pool_id));
+        store.configure_pool(a, b, 10, 3000);
+        assert!(!registry.is_current(&pool_id));
+        store.remove_pair(a, b);
+        assert!(!registry.is_current(&pool_id));
+    }
+
+    #[test]
+    fn updated_keys_are_current_for_every_clone() {
+        let (a, b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let key = PoolKey {
+            currency0:   a,
+            currency1:   b,
+            fee:         U24::from(500),
+            tickSpacing: I24::unchecked_from(10),
+            hooks:       Address::ZERO
+        };
+        let store = Arc::new(AngstromPoolConfigStore::default());
+        store.configure_pool(a, b, 10, 500);
+        let registry = UniswapAngstromRegistry::new(
+            UniswapPoolRegistry::from(vec![key.clone()]),
+            store.clone()
+        );
+        let previous = PoolId::from(key.clone());
+
+        store.configure_pool(a, b, 60, 500);
+        let updated = PoolKey { tickSpacing: I24::unchecked_from(60), ..key };
+        let pool_id = registry.clone().update_pool(&previous, updated.clone());
+
+        assert_eq!(pool_id, PoolId::from(updated.clone()));
+        assert!(registry.is_current(&pool_id));
+        assert_eq!(registry.get_uni_pool(&previous), None);
+        let private_id = PoolId::from(PoolKey { fee: U24::from(0x800000), ..updated });
+        assert_eq!(registry.uniswap_pools().conversion_map.get(&pool_id), Some(&private_id));
+    }
 }
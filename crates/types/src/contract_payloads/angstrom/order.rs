@@ -1,5 +1,4 @@
 use alloy::primitives::{aliases::U40, Address, Bytes, B256, U256};
-use pade::PadeDecode;
 use pade_macro::{PadeDecode, PadeEncode};
 
 use crate::{
@@ -65,7 +64,11 @@ pub struct UserOrder {
     pub standing_validation:  Option<StandingValidation>,
     pub order_quantities:     OrderQuantities,
     pub max_extra_fee_asset0: u128,
+    /// gas and referral fee the order is charged
     pub extra_fee_asset0:     u128,
+    /// fee of the pool the order is charged on its surplus, on top of the
+    /// extra fee and within the same max
+    pub pool_fee_asset0:      u128,
     pub exact_in:             bool,
     pub signature:            Signature
 }
@@ -236,16 +239,16 @@ impl UserOrder {
 
         let user = order.from();
         let recipient = (user != recipient).then_some(recipient);
-        let gas_used: u128 = (order.priority_data.gas + shared_gas).to();
+        let gas_used: u128 = order
+            .priority_data
+            .gas
+            .saturating_add(shared_gas)
+            .saturating_to();
         if gas_used > order.max_gas_token_0() {
             return Err(eyre::eyre!("order used more gas than allocated"))
         }
 
-        let sig_bytes = order.signature().clone().0.to_vec();
-        let decoded_signature =
-            alloy::primitives::PrimitiveSignature::pade_decode(&mut sig_bytes.as_slice(), None)
-                .unwrap();
-        let signature = Signature::from(decoded_signature);
+        let signature = Signature::from_order_signature(order.signature())?;
 
         Ok(Self {
             ref_id: 0,
@@ -259,6 +262,7 @@ impl UserOrder {
             order_quantities,
             max_extra_fee_asset0: order.max_gas_token_0(),
             extra_fee_asset0: gas_used,
+            pool_fee_asset0: 0,
             exact_in: order.exact_in(),
             signature
         })
@@ -268,7 +272,7 @@ impl UserOrder {
         order: &OrderWithStorageData<GroupedVanillaOrder>,
         outcome: &OrderOutcome,
         pair_index: u16
-    ) -> Self {
+    ) -> eyre::Result<Self> {
         let (order_quantities, standing_validation, recipient) = match &order.order {
             GroupedVanillaOrder::KillOrFill(o) => match o {
                 FlashVariants::Exact(e) => {
@@ -313,15 +317,12 @@ impl UserOrder {
             GroupedVanillaOrder::Standing(ref o) => o.hook_data().clone()
         };
         let hook_data = if hook_bytes.is_empty() { None } else { Some(hook_bytes) };
-        let sig_bytes = order.signature().to_vec();
-        let decoded_signature =
-            alloy::primitives::PrimitiveSignature::pade_decode(&mut sig_bytes.as_slice(), None)
-                .unwrap();
+        let signature = Signature::from_order_signature(order.signature())?;
 
         let user = order.from();
         let recipient = (user != recipient).then_some(recipient);
 
-        Self {
+        Ok(Self {
             ref_id: 0,
             use_internal: order.use_internal(),
             pair_index,
@@ -333,8 +334,9 @@ impl UserOrder {
             order_quantities,
             max_extra_fee_asset0: order.max_gas_token_0(),
             extra_fee_asset0: order.max_gas_token_0(),
+            pool_fee_asset0: 0,
             exact_in: order.exact_in(),
-            signature: Signature::from(decoded_signature)
-        }
+            signature
+        })
     }
 }
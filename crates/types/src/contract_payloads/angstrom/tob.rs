@@ -1,5 +1,4 @@
 use alloy::primitives::{Address, B256, U256};
-use pade::PadeDecode;
 use pade_macro::{PadeDecode, PadeEncode};
 use serde::{Deserialize, Serialize};
 
@@ -57,18 +56,14 @@ impl TopOfBlockOrder {
     pub fn of_max_gas(
         internal: &OrderWithStorageData<RpcTopOfBlockOrder>,
         pairs_index: u16
-    ) -> Self {
+    ) -> eyre::Result<Self> {
         let quantity_in = internal.quantity_in;
         let quantity_out = internal.quantity_out;
         let recipient = Some(internal.recipient);
         // Zero_for_1 is an Ask, an Ask is NOT a bid
         let zero_for_1 = !internal.is_bid;
-        let sig_bytes = internal.meta.signature.to_vec();
-        let decoded_signature =
-            alloy::primitives::PrimitiveSignature::pade_decode(&mut sig_bytes.as_slice(), None)
-                .unwrap();
-        let signature = Signature::from(decoded_signature);
-        Self {
+        let signature = Signature::from_order_signature(&internal.meta.signature)?;
+        Ok(Self {
             use_internal: false,
             quantity_in,
             quantity_out,
@@ -79,7 +74,7 @@ impl TopOfBlockOrder {
             zero_for_1,
             recipient,
             signature
-        }
+        })
     }
 
     pub fn of(
@@ -92,11 +87,7 @@ impl TopOfBlockOrder {
         let recipient = Some(internal.recipient);
         // Zero_for_1 is an Ask, an Ask is NOT a bid
         let zero_for_1 = !internal.is_bid;
-        let sig_bytes = internal.meta.signature.to_vec();
-        let decoded_signature =
-            alloy::primitives::PrimitiveSignature::pade_decode(&mut sig_bytes.as_slice(), None)
-                .unwrap();
-        let signature = Signature::from(decoded_signature);
+        let signature = Signature::from_order_signature(&internal.meta.signature)?;
         let used_gas: u128 = (internal.priority_data.gas + shared_gas).saturating_to();
 
         if used_gas > internal.max_gas_asset0 {
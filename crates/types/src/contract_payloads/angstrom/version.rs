@@ -0,0 +1,119 @@
+//! Which payload layouts the deployed Angstrom contract decodes.
+//!
+//! The payload format changes with contract upgrades, so bundles are only
+//! built for versions this node knows the layout of. A contract of a version
+//! this node doesn't know gets no bundles at all, as anything built for it
+//! could revert.
+use alloy::{
+    network::{Network, TransactionBuilder},
+    primitives::Address,
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+    transports::RpcError
+};
+use pade::PadeEncode;
+use serde::{Deserialize, Serialize};
+
+use super::AngstromBundle;
+
+sol! {
+    function version() external view returns (uint32);
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContractVersion {
+    /// the first release, without a version getter. Decodes the PADE layout
+    #[default]
+    V1,
+    /// the release with the version getter, decodes the PADE layout as well
+    V2,
+    /// a release this node doesn't know the payload format of
+    Unknown(u32)
+}
+
+impl ContractVersion {
+    pub fn from_raw(version: u32) -> Self {
+        match version {
+            1 => Self::V1,
+            2 => Self::V2,
+            version => Self::Unknown(version)
+        }
+    }
+
+    /// The version of the contract at `angstrom_contract`. Contracts that
+    /// revert the getter predate it, any other failure of the call is
+    /// returned as we can't tell the version from it.
+    pub async fn probe<N, P>(angstrom_contract: Address, provider: &P) -> Result<Self, String>
+    where
+        N: Network,
+        P: Provider<N>
+    {
+        let call = N::TransactionRequest::default()
+            .with_to(angstrom_contract)
+            .with_input(versionCall {}.abi_encode());
+
+        match provider.call(&call).await {
+            Ok(output) => versionCall::abi_decode_returns(&output, true)
+                .map(|version| Self::from_raw(version._0))
+                .map_err(|e| format!("Error decoding the contract version: {}", e)),
+            Err(RpcError::ErrorResp(err)) if is_revert(err.code, &err.message) => Ok(Self::V1),
+            Err(e) => Err(format!("Error probing the contract version: {}", e))
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Unknown(_))
+    }
+}
+
+/// Whether the error of an `eth_call` is the call reverting, as the getter does
+/// on contracts without it, rather than the node failing to run it.
+fn is_revert(code: i64, message: &str) -> bool {
+    // code 3 is the revert code of geth and reth, some nodes only set the
+    // message
+    code == 3 || message.contains("execution reverted")
+}
+
+impl AngstromBundle {
+    /// Encodes the bundle for a contract of `version`, refusing versions we
+    /// don't know the layout of.
+    pub fn encode_for(&self, version: ContractVersion) -> eyre::Result<Vec<u8>> {
+        if !version.is_known() {
+            return Err(eyre::eyre!("unknown payload layout of contract {:?}", version))
+        }
+
+        Ok(self.pade_encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_encodes_for_known_versions() {
+        let bundle = AngstromBundle {
+            assets:              vec![],
+            pairs:               vec![],
+            pool_updates:        vec![],
+            top_of_block_orders: vec![],
+            user_orders:         vec![]
+        };
+
+        assert_eq!(bundle.encode_for(ContractVersion::from_raw(1)).unwrap(), bundle.pade_encode());
+        assert!(bundle.encode_for(ContractVersion::from_raw(2)).is_ok());
+
+        let unknown = ContractVersion::from_raw(3);
+        assert_eq!(unknown, ContractVersion::Unknown(3));
+        assert!(bundle.encode_for(unknown).is_err());
+    }
+
+    #[test]
+    fn only_reverts_mean_v1() {
+        assert!(is_revert(3, "execution reverted"));
+        assert!(is_revert(-32000, "execution reverted"));
+        assert!(!is_revert(-32005, "rate limit exceeded"));
+        assert!(!is_revert(-32603, "internal error"));
+    }
+}
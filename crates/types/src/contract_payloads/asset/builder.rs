@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use alloy::primitives::Address;
 use itertools::Itertools;
 
@@ -16,7 +18,11 @@ pub struct AssetBuilder {
     rewards:      StageTracker,
     top_of_block: StageTracker,
     user_orders:  StageTracker,
-    assets:       AssetArray
+    assets:       AssetArray,
+    /// amounts the contract keeps for the protocol
+    saved:        HashMap<Address, u128>,
+    /// tokens each asset moves into the contract, less those moved out of it
+    deltas:       HashMap<Address, i128>
 }
 
 impl AssetBuilder {
@@ -44,6 +50,7 @@ impl AssetBuilder {
     ) {
         let asset_in_addr = self.assets.get_asset_addr(asset_in);
         let asset_out_addr = self.assets.get_asset_addr(asset_out);
+        self.moved(asset_out_addr, quantity_out, asset_in_addr, quantity_in);
         self.get_stage(stage).uniswap_swap(
             asset_in_addr,
             asset_out_addr,
@@ -62,6 +69,7 @@ impl AssetBuilder {
         quantity_in: u128,
         quantity_out: u128
     ) {
+        self.moved(asset_out, quantity_out, asset_in, quantity_in);
         self.get_stage(stage)
             .uniswap_swap(asset_in, asset_out, quantity_in, quantity_out);
     }
@@ -75,12 +83,33 @@ impl AssetBuilder {
         quantity_in: u128,
         quantity_out: u128
     ) {
+        self.moved(asset_in, quantity_in, asset_out, quantity_out);
         self.get_stage(stage)
             .external_swap(asset_in, asset_out, quantity_in, quantity_out);
     }
 
     pub fn allocate(&mut self, stage: AssetBuilderStage, asset: Address, quantity: u128) {
+        *self.deltas.entry(asset).or_default() -= quantity as i128;
+        self.get_stage(stage).allocate(asset, quantity);
+    }
+
+    /// Fees the contract keeps for the protocol, paid out of our contract
+    /// liquidity
+    pub fn save(&mut self, stage: AssetBuilderStage, asset: Address, quantity: u128) {
+        *self.deltas.entry(asset).or_default() -= quantity as i128;
         self.get_stage(stage).allocate(asset, quantity);
+        *self.saved.entry(asset).or_default() += quantity;
+    }
+
+    /// Tokens of `asset` the bundle leaves in the contract, negative if it
+    /// moves more out of the contract than into it.
+    pub fn net_delta(&self, asset: Address) -> i128 {
+        self.deltas.get(&asset).copied().unwrap_or_default()
+    }
+
+    fn moved(&mut self, into: Address, quantity_in: u128, out_of: Address, quantity_out: u128) {
+        *self.deltas.entry(into).or_default() += quantity_in as i128;
+        *self.deltas.entry(out_of).or_default() -= quantity_out as i128;
     }
 
     pub fn add_or_get_asset(&mut self, asset: Address) -> usize {
@@ -101,6 +130,7 @@ impl AssetBuilder {
                     asset.take = tracker.take;
                     asset.settle = tracker.settle;
                 }
+                asset.save = self.saved.get(&asset.addr).copied().unwrap_or_default();
                 asset
             })
             .sorted_by_key(|a| a.addr)
@@ -115,7 +145,9 @@ impl Default for AssetBuilder {
             rewards:      StageTracker::new(),
             top_of_block: StageTracker::new(),
             user_orders:  StageTracker::new(),
-            assets:       AssetArray::new()
+            assets:       AssetArray::new(),
+            saved:        HashMap::new(),
+            deltas:       HashMap::new()
         }
     }
 }
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+
+use crate::{
+    matching::{
+        uniswap::{Direction, PoolPriceVec, PoolSnapshot},
+        Ray
+    },
+    primitive::PoolId
+};
+
+/// Denominator of the fee rates, they are expressed in millionths.
+pub const FEE_RATE_SCALE: u128 = 1_000_000;
+
+/// How much of the matched surplus each pool takes as a fee and how it is
+/// split between the protocol and the liquidity providers of the pool.
+///
+/// Without any pool fees set nothing is taken, which is the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// fee each pool takes from the surplus of its filled orders, in 1e-6
+    pub pool_fees_e6:      HashMap<PoolId, u32>,
+    /// part of the fees that goes to the protocol, the rest is donated to the
+    /// LPs of the pool. in 1e-6
+    pub protocol_share_e6: u32
+}
+
+impl FeeConfig {
+    pub fn new(protocol_share_e6: u32) -> Self {
+        Self { pool_fees_e6: HashMap::new(), protocol_share_e6 }
+    }
+
+    pub fn with_pool_fee(mut self, pool_id: PoolId, fee_e6: u32) -> Self {
+        self.pool_fees_e6.insert(pool_id, fee_e6);
+        self
+    }
+
+    /// Splits the fee the pool takes from `surplus`, which is in token0 of
+    /// the pool. Fees are rounded down, rounding dust is left to the LPs.
+    pub fn pool_fees(&self, pool_id: PoolId, asset: Address, surplus: u128) -> PoolFees {
+        self.split(pool_id, asset, self.fee_on(pool_id, surplus), surplus)
+    }
+
+    /// Fee the pool takes from the `surplus` of an order, rounded down.
+    pub fn fee_on(&self, pool_id: PoolId, surplus: u128) -> u128 {
+        let fee_e6 = self.pool_fees_e6.get(&pool_id).copied().unwrap_or_default();
+        Self::apply_rate(surplus, fee_e6)
+    }
+
+    /// Splits the `total` fee the pool charged its orders on their `surplus`
+    /// between the protocol and the LPs.
+    pub fn split(&self, pool_id: PoolId, asset: Address, total: u128, surplus: u128) -> PoolFees {
+        let protocol = Self::apply_rate(total, self.protocol_share_e6);
+
+        PoolFees { pool_id, asset, protocol, lp: total - protocol, surplus, ..Default::default() }
+    }
+
+    fn apply_rate(quantity: u128, rate_e6: u32) -> u128 {
+        let rate = (rate_e6 as u128).min(FEE_RATE_SCALE);
+        // split up so that large quantities don't overflow
+        (quantity / FEE_RATE_SCALE) * rate + (quantity % FEE_RATE_SCALE) * rate / FEE_RATE_SCALE
+    }
+}
+
+/// Fees a pool took from its matched surplus in a bundle, along with the
+/// economics of the pool in the bundle they were taken in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolFees {
+    pub pool_id:         PoolId,
+    /// token0 of the pool, all fees and amounts are in it
+    pub asset:           Address,
+    /// saved by the contract for the protocol
+    pub protocol:        u128,
+    /// donated to the LPs of the pool
+    pub lp:              u128,
+    /// surplus of the filled orders over their limit prices, what the fees
+    /// are taken from
+    pub surplus:         u128,
+    /// surplus of the filled orders over swapping their token0 with the AMM
+    /// alone
+    pub amm_surplus:     u128,
+    /// token0 traded by the filled user orders, the larger of the two sides
+    pub matched_volume:  u128,
+    /// reward the top of block order paid to the pool
+    pub searcher_reward: u128
+}
+
+impl PoolFees {
+    pub fn total(&self) -> u128 {
+        self.protocol + self.lp
+    }
+
+    /// Surplus of a filled order in token0: the difference between its limit
+    /// price and the clearing price over the token0 it moved, converted at the
+    /// clearing price. `limit_price` is in the same direction as the clearing
+    /// price.
+    pub fn order_surplus(ucp: Ray, limit_price: Ray, is_bid: bool, t0_moving: u128) -> u128 {
+        let improvement = if is_bid {
+            limit_price.0.saturating_sub(ucp.0)
+        } else {
+            ucp.0.saturating_sub(limit_price.0)
+        };
+        if ucp.is_zero() {
+            return 0
+        }
+
+        ucp.inverse_quantity(Ray(improvement).quantity(t0_moving, false), false)
+    }
+
+    /// Surplus in token0 of the filled orders of one side of a pool over
+    /// swapping their token0 with the AMM of `snapshot` alone. `t0` and `t1`
+    /// are what the side traded at the clearing price: bids save the token1
+    /// the AMM would have charged them over `t1`, asks get `t1` over what the
+    /// AMM would have paid them. Zero if the AMM can't take the whole side.
+    pub fn amm_only_surplus(
+        snapshot: &PoolSnapshot,
+        ucp: Ray,
+        is_bid: bool,
+        t0: u128,
+        t1: u128
+    ) -> u128 {
+        if t0 == 0 || ucp.is_zero() {
+            return 0
+        }
+        let start = snapshot.current_price();
+        let Ok(swap) = start
+            .d_t0(t0, Direction::from_is_bid(is_bid))
+            .and_then(|end| PoolPriceVec::from_price_range(start, end))
+        else {
+            return 0
+        };
+        let improvement =
+            if is_bid { swap.d_t1.saturating_sub(t1) } else { t1.saturating_sub(swap.d_t1) };
+
+        ucp.inverse_quantity(improvement, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+
+    use super::*;
+    use crate::matching::{uniswap::LiqRange, SqrtPriceX96};
+
+    #[test]
+    fn splits_pool_fee_between_protocol_and_lps() {
+        let pool_id = PoolId::random();
+        let config = FeeConfig::new(250_000).with_pool_fee(pool_id, 10_000);
+
+        // 1% of the surplus, a quarter of it to the protocol
+        let fees = config.pool_fees(pool_id, Address::ZERO, 1_000_003);
+        assert_eq!(fees.total(), 10_000);
+        assert_eq!((fees.protocol, fees.lp), (2_500, 7_500));
+
+        // pools without a fee take nothing
+        assert_eq!(
+            config
+                .pool_fees(PoolId::random(), Address::ZERO, 1_000_003)
+                .total(),
+            0
+        );
+
+        // no overflow on large surplus
+        let fees = config.pool_fees(pool_id, Address::ZERO, u128::MAX);
+        assert_eq!(fees.total(), u128::MAX / 100);
+    }
+
+    #[test]
+    fn surplus_is_zero_at_the_limit_price() {
+        let ucp = Ray::from(U256::from(2_000_000_000_000_000_000_000_000_000_u128));
+        assert_eq!(PoolFees::order_surplus(ucp, ucp, true, 100), 0);
+
+        // the bid was willing to pay twice the clearing price
+        let bid_limit = Ray::from(U256::from(4_000_000_000_000_000_000_000_000_000_u128));
+        assert_eq!(PoolFees::order_surplus(ucp, bid_limit, true, 100), 100);
+        // asks only have surplus below the clearing price
+        assert_eq!(PoolFees::order_surplus(ucp, bid_limit, false, 100), 0);
+    }
+
+    #[test]
+    fn amm_only_surplus_is_the_price_impact_the_orders_saved() {
+        let snapshot = PoolSnapshot::new(
+            vec![LiqRange::new(90_000, 110_000, 1_000_000_000_000_000_000).unwrap()],
+            SqrtPriceX96::at_tick(100_000).unwrap()
+        )
+        .unwrap();
+        let price = snapshot.current_price().as_ray();
+        let t0 = 1_000_000_000_000_000_u128;
+        let t1 = price.quantity(t0, false);
+
+        // trading at the price the AMM starts at saves its price impact
+        let bid = PoolFees::amm_only_surplus(&snapshot, price, true, t0, t1);
+        let ask = PoolFees::amm_only_surplus(&snapshot, price, false, t0, t1);
+        assert!(bid > 0 && ask > 0);
+        // bids paying more than the AMM would have charged saved nothing
+        assert_eq!(PoolFees::amm_only_surplus(&snapshot, price, true, t0, t1 * 2), 0);
+        assert_eq!(PoolFees::amm_only_surplus(&snapshot, price, true, 0, t1), 0);
+    }
+}
@@ -0,0 +1,162 @@
+//! Bundles compared byte for byte against the golden vectors in `golden/`.
+//!
+//! The vectors are encoded by the reference encoder of the contract tests in
+//! `contracts/test/_reference/Bundle.sol`, with the same bundles built in
+//! `contracts/script/GoldenBundles.s.sol`. A mismatch means the encoding of
+//! the node diverged from what the contract decodes. Rerun the script only
+//! when the payload format itself changes.
+use alloy::primitives::{aliases::I24, hex, Address, Bytes, FixedBytes, U256};
+use pade::{PadeDecode, PadeEncode};
+
+use super::{
+    angstrom::{AngstromBundle, OrderQuantities, StandingValidation, TopOfBlockOrder, UserOrder},
+    rewards::{PoolUpdate, RewardsUpdate},
+    Asset, Pair, Signature
+};
+
+fn ray() -> U256 {
+    U256::from(10).pow(U256::from(27))
+}
+
+fn assets() -> Vec<Asset> {
+    vec![
+        Asset { addr: Address::repeat_byte(0x11), save: 1, take: 2, settle: 3 },
+        Asset { addr: Address::repeat_byte(0x22), save: 0, take: 0, settle: 0 },
+    ]
+}
+
+fn pairs() -> Vec<Pair> {
+    vec![Pair { index0: 0, index1: 1, store_index: 5, price_1over0: ray() }]
+}
+
+fn empty() -> AngstromBundle {
+    AngstromBundle {
+        assets:              vec![],
+        pairs:               vec![],
+        pool_updates:        vec![],
+        top_of_block_orders: vec![],
+        user_orders:         vec![]
+    }
+}
+
+fn top_of_block() -> AngstromBundle {
+    AngstromBundle {
+        assets:              assets(),
+        pairs:               pairs(),
+        pool_updates:        vec![PoolUpdate {
+            zero_for_one:     true,
+            pair_index:       0,
+            swap_in_quantity: 1_000,
+            rewards_update:   RewardsUpdate::CurrentOnly { amount: 7 }
+        }],
+        top_of_block_orders: vec![TopOfBlockOrder {
+            use_internal:     false,
+            quantity_in:      100,
+            quantity_out:     90,
+            max_gas_asset_0:  5,
+            gas_used_asset_0: 4,
+            pairs_index:      0,
+            zero_for_1:       true,
+            recipient:        Some(Address::repeat_byte(0x33)),
+            signature:        Signature::Ecdsa {
+                v: 27,
+                r: FixedBytes::repeat_byte(0xaa),
+                s: FixedBytes::repeat_byte(0xbb)
+            }
+        }],
+        user_orders:         vec![]
+    }
+}
+
+fn user_orders() -> AngstromBundle {
+    // exact standing order with a recipient, hook and contract signature
+    let standing = UserOrder {
+        ref_id:               7,
+        use_internal:         true,
+        pair_index:           0,
+        min_price:            ray() + U256::from(1),
+        recipient:            Some(Address::repeat_byte(0x44)),
+        hook_data:            Some(Bytes::from(
+            [Address::repeat_byte(0x66).as_slice(), &[0xde, 0xad, 0xbe, 0xef]].concat()
+        )),
+        zero_for_one:         false,
+        standing_validation:  Some(StandingValidation::new(3, 1_700_000_000)),
+        order_quantities:     OrderQuantities::Exact { quantity: 500 },
+        max_extra_fee_asset0: 10,
+        extra_fee_asset0:     9,
+        pool_fee_asset0:      1,
+        exact_in:             true,
+        signature:            Signature::Contract {
+            from:      Address::repeat_byte(0x55),
+            signature: Bytes::from_static(&[1, 2, 3])
+        }
+    };
+    // partial flash order signed by an EOA
+    let flash = UserOrder {
+        ref_id:               0,
+        use_internal:         false,
+        pair_index:           0,
+        min_price:            ray() * U256::from(2),
+        recipient:            None,
+        hook_data:            None,
+        zero_for_one:         true,
+        standing_validation:  None,
+        order_quantities:     OrderQuantities::Partial {
+            min_quantity_in: 1,
+            max_quantity_in: 1_000,
+            filled_quantity: 600
+        },
+        max_extra_fee_asset0: 0,
+        extra_fee_asset0:     0,
+        pool_fee_asset0:      0,
+        // partial orders are always exact in
+        exact_in:             true,
+        signature:            Signature::Ecdsa {
+            v: 28,
+            r: FixedBytes::repeat_byte(0xcc),
+            s: FixedBytes::repeat_byte(0xdd)
+        }
+    };
+
+    AngstromBundle {
+        assets:              assets(),
+        pairs:               pairs(),
+        pool_updates:        vec![PoolUpdate {
+            zero_for_one:     false,
+            pair_index:       0,
+            swap_in_quantity: 2_500,
+            rewards_update:   RewardsUpdate::MultiTick {
+                start_tick:      I24::try_from(-120).unwrap(),
+                start_liquidity: 1_000_000_000_000_000_000,
+                quantities:      vec![1, 2, 3]
+            }
+        }],
+        top_of_block_orders: vec![],
+        user_orders:         vec![standing, flash]
+    }
+}
+
+fn assert_matches_golden(bundle: AngstromBundle, golden: &str) {
+    let golden = hex::decode(golden.trim()).unwrap();
+    let encoded = bundle.pade_encode();
+    assert_eq!(hex::encode(&encoded), hex::encode(&golden));
+
+    // the vector decodes into the same bundle
+    let decoded = AngstromBundle::pade_decode(&mut golden.as_slice(), None).unwrap();
+    assert_eq!(decoded.pade_encode(), encoded);
+}
+
+#[test]
+fn empty_bundle_matches_golden() {
+    assert_matches_golden(empty(), include_str!("golden/empty.hex"));
+}
+
+#[test]
+fn top_of_block_bundle_matches_golden() {
+    assert_matches_golden(top_of_block(), include_str!("golden/top_of_block.hex"));
+}
+
+#[test]
+fn user_order_bundle_matches_golden() {
+    assert_matches_golden(user_orders(), include_str!("golden/user_orders.hex"));
+}
@@ -3,17 +3,33 @@ use alloy::{
     sol
 };
 use alloy_primitives::B256;
+use pade::PadeDecode;
 use pade_macro::{PadeDecode, PadeEncode};
 use serde::{Deserialize, Serialize};
 
 pub mod angstrom;
 pub mod asset;
+pub mod fees;
 pub mod rewards;
 pub mod tob;
 
+#[cfg(test)]
+mod golden;
+#[cfg(test)]
+mod proptests;
+
 pub const CONFIG_STORE_SLOT: u32 = 3;
 pub const POOL_CONFIG_STORE_ENTRY_SIZE: usize = 32;
 
+/// Gas the calldata of a transaction costs on top of its execution, as priced
+/// by EIP-2028.
+pub fn calldata_gas(calldata: &[u8]) -> u64 {
+    calldata
+        .iter()
+        .map(|byte| if *byte == 0 { 4 } else { 16 })
+        .sum()
+}
+
 sol! {
     #[derive(Debug, Default, PadeEncode, PadeDecode)]
     struct Asset {
@@ -39,6 +55,14 @@ pub enum Signature {
 }
 
 impl Signature {
+    /// Decodes the signature an order was submitted with, malformed bytes are
+    /// an error rather than a panic since they come straight from the user.
+    pub fn from_order_signature(bytes: &[u8]) -> eyre::Result<Self> {
+        let signature = alloy::primitives::PrimitiveSignature::pade_decode(&mut &*bytes, None)
+            .map_err(|e| eyre::eyre!("malformed order signature - {:?}", e))?;
+        Ok(Self::from(signature))
+    }
+
     pub fn recover_signer(&self, hash: B256) -> Address {
         match self {
             Self::Contract { from, .. } => *from,
@@ -0,0 +1,266 @@
+//! Round trips of everything that goes into a bundle. Every strict prefix of an
+//! encoding has to fail to decode, the contract payloads come from peers and
+//! users so a truncated one must never panic or decode into something else.
+use alloy::primitives::{aliases::I24, Address, Bytes, FixedBytes, U256};
+use pade::{PadeDecode, PadeEncode};
+use proptest::{collection::vec, option, prelude::*};
+
+use super::{
+    angstrom::{AngstromBundle, OrderQuantities, StandingValidation, TopOfBlockOrder, UserOrder},
+    rewards::{PoolUpdate, RewardsUpdate},
+    Asset, Pair, Signature
+};
+use crate::{
+    orders::{OrderFillState, OrderOutcome},
+    sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
+};
+
+fn address() -> impl Strategy<Value = Address> {
+    any::<[u8; 20]>().prop_map(Address::from)
+}
+
+fn u256() -> impl Strategy<Value = U256> {
+    any::<[u64; 4]>().prop_map(U256::from_limbs)
+}
+
+fn bytes() -> impl Strategy<Value = Bytes> {
+    vec(any::<u8>(), 0..96).prop_map(Bytes::from)
+}
+
+fn signature() -> impl Strategy<Value = Signature> {
+    prop_oneof![
+        (address(), bytes()).prop_map(|(from, signature)| Signature::Contract { from, signature }),
+        (any::<u8>(), any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(v, r, s)| {
+            Signature::Ecdsa { v, r: FixedBytes::from(r), s: FixedBytes::from(s) }
+        })
+    ]
+}
+
+fn order_quantities() -> impl Strategy<Value = OrderQuantities> {
+    prop_oneof![
+        any::<u128>().prop_map(|quantity| OrderQuantities::Exact { quantity }),
+        any::<(u128, u128, u128)>().prop_map(
+            |(min_quantity_in, max_quantity_in, filled_quantity)| OrderQuantities::Partial {
+                min_quantity_in,
+                max_quantity_in,
+                filled_quantity
+            }
+        )
+    ]
+}
+
+fn standing_validation() -> impl Strategy<Value = StandingValidation> {
+    // the deadline is 40 bits wide on chain
+    (any::<u64>(), 0..(1u64 << 40))
+        .prop_map(|(nonce, deadline)| StandingValidation::new(nonce, deadline))
+}
+
+fn user_order() -> impl Strategy<Value = UserOrder> {
+    (
+        (
+            any::<u32>(),
+            any::<bool>(),
+            any::<u16>(),
+            u256(),
+            option::of(address()),
+            option::of(bytes()),
+            any::<bool>()
+        ),
+        (
+            option::of(standing_validation()),
+            order_quantities(),
+            any::<u128>(),
+            any::<u128>(),
+            any::<bool>(),
+            signature()
+        )
+    )
+        .prop_map(
+            |(
+                (ref_id, use_internal, pair_index, min_price, recipient, hook_data, zero_for_one),
+                (
+                    standing_validation,
+                    order_quantities,
+                    max_extra_fee_asset0,
+                    extra_fee_asset0,
+                    exact_in,
+                    signature
+                )
+            )| UserOrder {
+                ref_id,
+                use_internal,
+                pair_index,
+                min_price,
+                recipient,
+                hook_data,
+                zero_for_one,
+                standing_validation,
+                order_quantities,
+                max_extra_fee_asset0,
+                extra_fee_asset0,
+                exact_in,
+                signature
+            }
+        )
+}
+
+fn top_of_block_order() -> impl Strategy<Value = TopOfBlockOrder> {
+    (
+        any::<bool>(),
+        any::<(u128, u128, u128, u128)>(),
+        any::<u16>(),
+        any::<bool>(),
+        option::of(address()),
+        signature()
+    )
+        .prop_map(
+            |(
+                use_internal,
+                (quantity_in, quantity_out, max_gas_asset_0, gas_used_asset_0),
+                pairs_index,
+                zero_for_1,
+                recipient,
+                signature
+            )| TopOfBlockOrder {
+                use_internal,
+                quantity_in,
+                quantity_out,
+                max_gas_asset_0,
+                gas_used_asset_0,
+                pairs_index,
+                zero_for_1,
+                recipient,
+                signature
+            }
+        )
+}
+
+fn asset() -> impl Strategy<Value = Asset> {
+    (address(), any::<(u128, u128, u128)>()).prop_map(|(addr, (save, take, settle))| Asset {
+        addr,
+        save,
+        take,
+        settle
+    })
+}
+
+fn pair() -> impl Strategy<Value = Pair> {
+    (any::<(u16, u16, u16)>(), u256()).prop_map(|((index0, index1, store_index), price_1over0)| {
+        Pair { index0, index1, store_index, price_1over0 }
+    })
+}
+
+fn rewards_update() -> impl Strategy<Value = RewardsUpdate> {
+    prop_oneof![
+        (-(1i32 << 23)..(1i32 << 23), any::<u128>(), vec(any::<u128>(), 0..8)).prop_map(
+            |(tick, start_liquidity, quantities)| RewardsUpdate::MultiTick {
+                start_tick: I24::try_from(tick).unwrap(),
+                start_liquidity,
+                quantities
+            }
+        ),
+        any::<u128>().prop_map(|amount| RewardsUpdate::CurrentOnly { amount })
+    ]
+}
+
+fn pool_update() -> impl Strategy<Value = PoolUpdate> {
+    (any::<bool>(), any::<u16>(), any::<u128>(), rewards_update()).prop_map(
+        |(zero_for_one, pair_index, swap_in_quantity, rewards_update)| PoolUpdate {
+            zero_for_one,
+            pair_index,
+            swap_in_quantity,
+            rewards_update
+        }
+    )
+}
+
+fn bundle() -> impl Strategy<Value = AngstromBundle> {
+    (
+        vec(asset(), 0..4),
+        vec(pair(), 0..4),
+        vec(pool_update(), 0..4),
+        vec(top_of_block_order(), 0..3),
+        vec(user_order(), 0..4)
+    )
+        .prop_map(|(assets, pairs, pool_updates, top_of_block_orders, user_orders)| {
+            AngstromBundle::new(assets, pairs, pool_updates, top_of_block_orders, user_orders)
+        })
+}
+
+/// Decodes the encoding of `value` back into the same bytes, and fails to
+/// decode any prefix of them.
+fn assert_round_trip<T: PadeEncode + PadeDecode>(value: &T) -> Result<(), TestCaseError> {
+    let encoded = value.pade_encode();
+    let mut slice = encoded.as_slice();
+    let decoded = T::pade_decode(&mut slice, None)
+        .map_err(|e| TestCaseError::fail(format!("failed to decode - {e:?}")))?;
+    prop_assert!(slice.is_empty(), "{} bytes left after decoding", slice.len());
+    prop_assert_eq!(decoded.pade_encode(), encoded.clone());
+
+    for len in 0..encoded.len() {
+        prop_assert!(
+            T::pade_decode(&mut &encoded[..len], None).is_err(),
+            "decoded {} of {} bytes",
+            len,
+            encoded.len()
+        );
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn signature_round_trips(signature in signature()) {
+        assert_round_trip(&signature)?;
+    }
+
+    #[test]
+    fn order_quantities_round_trip(quantities in order_quantities()) {
+        assert_round_trip(&quantities)?;
+    }
+
+    #[test]
+    fn standing_validation_round_trips(validation in standing_validation()) {
+        assert_round_trip(&validation)?;
+    }
+
+    #[test]
+    fn user_order_round_trips(order in user_order()) {
+        assert_round_trip(&order)?;
+    }
+
+    #[test]
+    fn top_of_block_order_round_trips(order in top_of_block_order()) {
+        assert_round_trip(&order)?;
+    }
+
+    #[test]
+    fn pool_update_round_trips(update in pool_update()) {
+        assert_round_trip(&update)?;
+    }
+
+    #[test]
+    fn assets_and_pairs_round_trip(assets in vec(asset(), 0..8), pairs in vec(pair(), 0..8)) {
+        assert_round_trip(&assets)?;
+        assert_round_trip(&pairs)?;
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn bundle_round_trips(bundle in bundle()) {
+        assert_round_trip(&bundle)?;
+    }
+}
+
+#[test]
+fn malformed_order_signature_is_an_error() {
+    let order = OrderWithStorageData::<GroupedVanillaOrder>::default();
+    let outcome = OrderOutcome { id: order.order_id, outcome: OrderFillState::CompleteFill };
+
+    assert!(UserOrder::from_internal_order(&order, &outcome, U256::ZERO, 0).is_err());
+    assert!(UserOrder::from_internal_order_max_gas(&order, &outcome, 0).is_err());
+}
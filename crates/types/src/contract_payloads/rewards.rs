@@ -1,4 +1,4 @@
-use alloy::primitives::aliases::I24;
+use alloy::primitives::{aliases::I24, U256};
 use pade_macro::{PadeDecode, PadeEncode};
 
 use super::{Asset, Pair};
@@ -9,6 +9,37 @@ pub enum RewardsUpdate {
     CurrentOnly { amount: u128 }
 }
 
+impl RewardsUpdate {
+    /// Spreads `amount` over the rewarded ticks, pro rata to what each tick is
+    /// rewarded already or evenly if none is. The rounding dust goes to the
+    /// current tick, which is the last one.
+    pub fn add_donation(&mut self, amount: u128) {
+        match self {
+            Self::CurrentOnly { amount: current } => *current += amount,
+            Self::MultiTick { quantities, .. } => {
+                let rewarded = quantities
+                    .iter()
+                    .fold(U256::ZERO, |total, quantity| total + U256::from(*quantity));
+                let ticks = quantities.len() as u128;
+
+                let mut donated = 0u128;
+                for quantity in quantities.iter_mut() {
+                    let share = if rewarded.is_zero() {
+                        amount / ticks
+                    } else {
+                        (U256::from(amount) * U256::from(*quantity) / rewarded).to::<u128>()
+                    };
+                    *quantity += share;
+                    donated += share;
+                }
+                if let Some(current) = quantities.last_mut() {
+                    *current += amount - donated;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, PadeEncode, PadeDecode)]
 pub struct PoolUpdate {
     pub zero_for_one:     bool,
@@ -23,3 +54,26 @@ pub struct MockContractMessage {
     pub pairs:  Vec<Pair>,
     pub update: PoolUpdate
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multi_tick(quantities: Vec<u128>) -> RewardsUpdate {
+        RewardsUpdate::MultiTick { start_tick: I24::ZERO, start_liquidity: 1, quantities }
+    }
+
+    #[test]
+    fn donation_is_spread_over_the_rewarded_ticks() {
+        let mut update = multi_tick(vec![100, 300, 0, 600]);
+        update.add_donation(1_001);
+        let RewardsUpdate::MultiTick { quantities, .. } = update else { unreachable!() };
+        // pro rata, the dust on the current tick
+        assert_eq!(quantities, vec![200, 600, 0, 1_201]);
+
+        let mut update = multi_tick(vec![0, 0, 0]);
+        update.add_donation(10);
+        let RewardsUpdate::MultiTick { quantities, .. } = update else { unreachable!() };
+        assert_eq!(quantities, vec![3, 3, 4]);
+    }
+}
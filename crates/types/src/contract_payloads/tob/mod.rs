@@ -5,10 +5,30 @@ use eyre::eyre;
 
 use super::rewards::RewardsUpdate;
 use crate::{
-    matching::uniswap::{PoolSnapshot, Quantity, Tick},
+    matching::{
+        uniswap::{PoolSnapshot, Quantity, Tick},
+        SqrtPriceX96
+    },
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
 
+mod simulation;
+
+pub use simulation::{TobSimulation, TobStateOverrides};
+
+/// Deviation, in millionths of the simulated reward, the claimed reward of a
+/// top of block order can have before it's rejected. Part of the protocol,
+/// every node has to drop the same orders from a round.
+pub const TOB_REWARD_TOLERANCE_E6: u32 = 10_000;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TobRewardError {
+    #[error("order can't be executed against the pool: {0}")]
+    Unexecutable(String),
+    #[error("claimed reward {claimed} deviates from the simulated {simulated}")]
+    Deviates { claimed: U256, simulated: U256 }
+}
+
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct ToBOutcome {
     pub start_tick:      i32,
@@ -16,7 +36,10 @@ pub struct ToBOutcome {
     pub tribute:         U256,
     pub total_cost:      U256,
     pub total_reward:    U256,
-    pub tick_donations:  HashMap<Tick, U256>
+    pub tick_donations:  HashMap<Tick, U256>,
+    /// price and tick the swap of the order leaves the pool at
+    pub end_price:       SqrtPriceX96,
+    pub end_tick:        Tick
 }
 
 impl ToBOutcome {
@@ -53,11 +76,33 @@ impl ToBOutcome {
             tribute:         U256::from(donation.tribute),
             total_cost:      U256::from(pricevec.input()),
             total_reward:    U256::from(donation.total_donated),
-            tick_donations:  donation.tick_donations
+            tick_donations:  donation.tick_donations,
+            end_price:       pricevec.end_bound.as_sqrtpricex96(),
+            end_tick:        pricevec.end_bound.tick()
         };
         Ok(rewards)
     }
 
+    /// Simulates the order against the snapshot and checks its claimed
+    /// `tob_reward` is within `tolerance_e6` millionths of the simulated one.
+    pub fn verify_claimed_reward(
+        tob: &OrderWithStorageData<TopOfBlockOrder>,
+        snapshot: &PoolSnapshot,
+        tolerance_e6: u32
+    ) -> Result<Self, TobRewardError> {
+        let outcome = Self::from_tob_and_snapshot(tob, snapshot)
+            .map_err(|e| TobRewardError::Unexecutable(e.to_string()))?;
+
+        let simulated = outcome.total_reward;
+        let deviation = tob.tob_reward.abs_diff(simulated);
+        let allowed = simulated.saturating_mul(U256::from(tolerance_e6)) / U256::from(1_000_000);
+        if deviation > allowed {
+            return Err(TobRewardError::Deviates { claimed: tob.tob_reward, simulated })
+        }
+
+        Ok(outcome)
+    }
+
     pub fn to_rewards_update(&self) -> RewardsUpdate {
         let mut donations = self.tick_donations.iter().collect::<Vec<_>>();
         // Will sort from lowest to highest (donations[0] will be the lowest tick
@@ -0,0 +1,134 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use super::ToBOutcome;
+use crate::matching::{
+    uniswap::{LiqRange, PoolSnapshot},
+    SqrtPriceX96
+};
+
+/// Most liquidity ranges a simulation can replace the ones of the pool with.
+pub const MAX_OVERRIDE_RANGES: usize = 1024;
+
+/// State a top of block order is simulated against instead of the one the node
+/// is at, the way `eth_call` overrides the state of accounts. Unset fields
+/// keep the state of the node.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TobStateOverrides {
+    /// price the pool starts at
+    pub sqrt_price_x96:   Option<U256>,
+    /// liquidity ranges replacing the ones loaded for the pool, at most
+    /// [`MAX_OVERRIDE_RANGES`]
+    pub ranges:           Option<Vec<LiqRange>>,
+    /// balance of the searcher in the token it sells
+    pub balance:          Option<U256>,
+    /// approval of angstrom by the searcher in the token it sells
+    pub approval:         Option<U256>,
+    /// balance of the searcher held in angstrom in the token it sells
+    pub angstrom_balance: Option<U256>
+}
+
+impl TobStateOverrides {
+    pub fn overrides_pool(&self) -> bool {
+        self.sqrt_price_x96.is_some() || self.ranges.is_some()
+    }
+
+    /// The snapshot of the pool with the overrides applied.
+    pub fn apply(&self, snapshot: PoolSnapshot) -> eyre::Result<PoolSnapshot> {
+        if !self.overrides_pool() {
+            return Ok(snapshot)
+        }
+        if self
+            .ranges
+            .as_ref()
+            .is_some_and(|ranges| ranges.len() > MAX_OVERRIDE_RANGES)
+        {
+            return Err(eyre::eyre!("more than {MAX_OVERRIDE_RANGES} liquidity ranges"))
+        }
+
+        let price = self
+            .sqrt_price_x96
+            .map(SqrtPriceX96::from)
+            .unwrap_or(snapshot.sqrt_price_x96);
+        let ranges = self.ranges.clone().unwrap_or(snapshot.ranges);
+
+        PoolSnapshot::new(ranges, price)
+    }
+}
+
+/// What a top of block order would do if it was submitted now.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TobSimulation {
+    /// whether the node would accept the order as is
+    pub valid:                     bool,
+    /// why it wouldn't, if it isn't valid
+    pub invalid_reason:            Option<String>,
+    /// what the order donates to the liquidity providers
+    pub reward:                    U256,
+    /// what's left of the input once the swap and the donations are paid
+    pub tribute:                   U256,
+    /// input the swap of the order takes
+    pub total_cost:                U256,
+    /// price the order leaves the pool at
+    pub post_trade_sqrt_price_x96: U256,
+    pub post_trade_tick:           i32
+}
+
+impl TobSimulation {
+    pub fn invalid(reason: impl ToString) -> Self {
+        Self { invalid_reason: Some(reason.to_string()), ..Default::default() }
+    }
+
+    pub fn from_outcome(outcome: &ToBOutcome) -> Self {
+        Self {
+            valid:                     true,
+            invalid_reason:            None,
+            reward:                    outcome.total_reward,
+            tribute:                   outcome.tribute,
+            total_cost:                outcome.total_cost,
+            post_trade_sqrt_price_x96: outcome.end_price.into(),
+            post_trade_tick:           outcome.end_tick
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_the_pool_overrides() {
+        let ranges = vec![LiqRange::new(99_000, 101_000, 1_000_000_000).unwrap()];
+        let snapshot =
+            PoolSnapshot::new(ranges.clone(), SqrtPriceX96::at_tick(100_000).unwrap()).unwrap();
+        assert_eq!(
+            TobStateOverrides::default()
+                .apply(snapshot.clone())
+                .unwrap(),
+            snapshot
+        );
+
+        let moved = TobStateOverrides {
+            sqrt_price_x96: Some(SqrtPriceX96::at_tick(100_500).unwrap().into()),
+            ..Default::default()
+        };
+        let overridden = moved.apply(snapshot.clone()).unwrap();
+        assert_eq!(overridden.current_price().tick(), 100_500);
+        assert_eq!(overridden.ranges, ranges);
+
+        // the price has to be in the ranges
+        let out_of_range = TobStateOverrides {
+            ranges: Some(vec![LiqRange::new(0, 1_000, 1_000).unwrap()]),
+            ..Default::default()
+        };
+        assert!(out_of_range.apply(snapshot.clone()).is_err());
+
+        let too_many = TobStateOverrides {
+            ranges: Some(vec![ranges[0]; MAX_OVERRIDE_RANGES + 1]),
+            ..Default::default()
+        };
+        assert!(too_many.apply(snapshot).is_err());
+    }
+}
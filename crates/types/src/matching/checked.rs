@@ -0,0 +1,145 @@
+//! Amount math that reports overflows instead of wrapping or panicking.
+//!
+//! Amounts and prices come from signed orders, so anything a bundle is built
+//! from can be adversarial. Matching and building the bundle goes through
+//! these so an order at the edge of the integer range fails the round with an
+//! error rather than taking the validator down with it.
+use alloy::primitives::U256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum MathError {
+    #[error("{0} overflowed")]
+    Overflow(&'static str),
+    #[error("{0} underflowed")]
+    Underflow(&'static str),
+    #[error("{0} divided by zero")]
+    DivisionByZero(&'static str)
+}
+
+/// Checked arithmetic on amounts, naming what is computed in the error.
+pub trait CheckedMath: Sized {
+    fn try_add(self, rhs: Self, what: &'static str) -> Result<Self, MathError>;
+
+    fn try_sub(self, rhs: Self, what: &'static str) -> Result<Self, MathError>;
+
+    fn try_mul(self, rhs: Self, what: &'static str) -> Result<Self, MathError>;
+
+    fn try_div(self, rhs: Self, what: &'static str) -> Result<Self, MathError>;
+}
+
+macro_rules! checked_math {
+    ($($ty:ty),*) => {$(
+        impl CheckedMath for $ty {
+            fn try_add(self, rhs: Self, what: &'static str) -> Result<Self, MathError> {
+                self.checked_add(rhs).ok_or(MathError::Overflow(what))
+            }
+
+            fn try_sub(self, rhs: Self, what: &'static str) -> Result<Self, MathError> {
+                self.checked_sub(rhs).ok_or(MathError::Underflow(what))
+            }
+
+            fn try_mul(self, rhs: Self, what: &'static str) -> Result<Self, MathError> {
+                self.checked_mul(rhs).ok_or(MathError::Overflow(what))
+            }
+
+            fn try_div(self, rhs: Self, what: &'static str) -> Result<Self, MathError> {
+                self.checked_div(rhs).ok_or(MathError::DivisionByZero(what))
+            }
+        }
+    )*};
+}
+
+checked_math!(u64, u128, U256);
+
+/// Narrows an amount to the u128 the contract takes.
+pub fn narrow_u128(value: U256, what: &'static str) -> Result<u128, MathError> {
+    value.try_into().map_err(|_| MathError::Overflow(what))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{U256, U512};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::matching::Ray;
+
+    /// Mostly values at the edges of the range, where the math breaks.
+    fn extreme_u128() -> impl Strategy<Value = u128> {
+        prop_oneof![
+            Just(0),
+            Just(1),
+            Just(u128::MAX),
+            Just(u128::MAX - 1),
+            (0..128u32).prop_map(|shift| 1u128 << shift),
+            any::<u128>()
+        ]
+    }
+
+    fn extreme_u256() -> impl Strategy<Value = U256> {
+        prop_oneof![
+            Just(U256::ZERO),
+            Just(U256::from(1)),
+            Just(U256::MAX),
+            (0..256usize).prop_map(|shift| U256::from(1) << shift),
+            any::<[u64; 4]>().prop_map(U256::from_limbs)
+        ]
+    }
+
+    fn one() -> U512 {
+        U512::from(10).pow(U512::from(27))
+    }
+
+    proptest! {
+        #[test]
+        fn amount_math_matches_wide_math(a in extreme_u128(), b in extreme_u128()) {
+            let fits = |wide: U256| (wide <= U256::from(u128::MAX)).then_some(wide);
+            let (wide_a, wide_b) = (U256::from(a), U256::from(b));
+
+            prop_assert_eq!(a.try_add(b, "a").ok().map(U256::from), fits(wide_a + wide_b));
+            prop_assert_eq!(a.try_sub(b, "a").is_err(), b > a);
+            prop_assert_eq!(a.try_mul(b, "a").ok().map(U256::from), fits(wide_a * wide_b));
+            prop_assert_eq!(a.try_div(b, "a").is_err(), b == 0);
+        }
+
+        #[test]
+        fn quantities_error_instead_of_saturating(price in extreme_u256(), q in extreme_u128()) {
+            let ray = Ray(price);
+            let product = U512::from(price) * U512::from(q);
+
+            let (down, up) = (product / one(), product.div_ceil(one()));
+            prop_assert_eq!(
+                ray.checked_quantity(q, false).ok().map(U512::from),
+                Some(down).filter(|down| *down <= U512::from(u128::MAX))
+            );
+            prop_assert_eq!(
+                ray.checked_quantity(q, true).ok().map(U512::from),
+                Some(up).filter(|up| *up <= U512::from(u128::MAX))
+            );
+            prop_assert_eq!(
+                ray.checked_mul_quantity(U256::from(q)).ok().map(U512::from),
+                Some(down).filter(|down| *down <= U512::from(U256::MAX))
+            );
+
+            let inverse = ray.checked_inverse_quantity(q, false);
+            if price.is_zero() {
+                prop_assert_eq!(inverse, Err(MathError::DivisionByZero("inverse quantity")));
+            } else {
+                let expected = U512::from(q) * one() / U512::from(price);
+                prop_assert_eq!(
+                    inverse.ok().map(U512::from),
+                    Some(expected).filter(|t0| *t0 <= U512::from(u128::MAX))
+                );
+            }
+        }
+
+        #[test]
+        fn wide_quantities_never_panic(price in extreme_u256(), q in extreme_u256()) {
+            let product = U512::from(price) * U512::from(q) / one();
+            prop_assert_eq!(
+                Ray(price).checked_mul_quantity(q).ok().map(U512::from),
+                Some(product).filter(|product| *product <= U512::from(U256::MAX))
+            );
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::{
 
 use alloy::primitives::U256;
 
+mod checked;
+pub use checked::{narrow_u128, CheckedMath, MathError};
 mod composite;
 pub use composite::CompositeOrder;
 pub mod debt;
@@ -12,6 +14,10 @@ pub use debt::{Debt, DebtType};
 pub mod match_estimate_response;
 mod math;
 pub use math::max_t1_for_t0;
+mod pool_config;
+pub use pool_config::{PoolMatchingConfig, UcpRounding};
+mod self_trade;
+pub use self_trade::SelfTradePolicy;
 mod sqrtprice;
 mod tokens;
 pub mod uniswap;
@@ -0,0 +1,142 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use super::Ray;
+use crate::orders::CircuitBreaker;
+
+/// How the clearing price of a pool is rounded onto its tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UcpRounding {
+    #[default]
+    Down,
+    Up,
+    /// to the closer tick, halfway rounds up
+    Nearest
+}
+
+/// Matching parameters of a single pool. Stable pairs want fine ticks and
+/// cross the AMM for the smallest spread, volatile ones coarse ticks and only
+/// pay the gas of an AMM swap for a meaningful move.
+///
+/// Every validator has to match a pool with the same config, otherwise they
+/// don't agree on its solution, so it's part of the
+/// [`ProtocolGenesis`](crate::consensus::ProtocolGenesis).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoolMatchingConfig {
+    /// clearing prices are multiples of this, unset leaves them as matched
+    pub tick_size:         Option<Ray>,
+    pub rounding:          UcpRounding,
+    /// least the clearing price has to move the AMM by, in millionths of its
+    /// spot price, for the book to be matched against it
+    pub min_amm_spread_e6: u32,
+    /// how far from the spot price of the AMM, in millionths of it, the
+    /// orders can set the clearing price. Orders limited outside of it are
+    /// left out of the match, unset doesn't bound the clearing price
+    pub price_band_e6:     Option<u32>
+}
+
+impl PoolMatchingConfig {
+    /// `price` on the tick of the pool. Prices that can't be rounded, because
+    /// they'd round to zero or overflow, are left as they are.
+    pub fn round_price(&self, price: Ray) -> Ray {
+        let Some(tick) = self.tick_size.filter(|tick| !tick.is_zero()) else { return price };
+
+        let remainder = price.0 % tick.0;
+        if remainder.is_zero() {
+            return price
+        }
+        let down = price.0 - remainder;
+        let round_up = match self.rounding {
+            UcpRounding::Down => false,
+            UcpRounding::Up => true,
+            UcpRounding::Nearest => remainder.saturating_mul(U256::from(2)) >= tick.0
+        };
+
+        let rounded = if round_up { down.checked_add(tick.0) } else { Some(down) };
+        rounded
+            .filter(|rounded| !rounded.is_zero())
+            .map(Ray::from)
+            .unwrap_or(price)
+    }
+
+    /// Whether clearing at `ucp` moves the AMM from `spot_price` by enough to
+    /// be worth a swap.
+    pub fn crosses_amm(&self, ucp: Ray, spot_price: Ray) -> bool {
+        CircuitBreaker::deviation_e6(ucp, spot_price) >= self.min_amm_spread_e6 as u64
+    }
+
+    /// Lowest and highest clearing price the band allows around `spot_price`.
+    pub fn price_band(&self, spot_price: Ray) -> Option<(Ray, Ray)> {
+        let band = U256::from(self.price_band_e6?);
+        let scale = U256::from(1_000_000);
+        let lower = spot_price.0.saturating_mul(scale.saturating_sub(band)) / scale;
+        let upper = spot_price.0.saturating_mul(scale + band) / scale;
+        Some((Ray::from(lower), Ray::from(upper)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(tick: u64, rounding: UcpRounding) -> PoolMatchingConfig {
+        PoolMatchingConfig {
+            tick_size: Some(Ray::from(U256::from(tick))),
+            rounding,
+            ..Default::default()
+        }
+    }
+
+    fn ray(value: u64) -> Ray {
+        Ray::from(U256::from(value))
+    }
+
+    #[test]
+    fn rounds_onto_the_tick() {
+        assert_eq!(config(100, UcpRounding::Down).round_price(ray(1_250)), ray(1_200));
+        assert_eq!(config(100, UcpRounding::Up).round_price(ray(1_201)), ray(1_300));
+        assert_eq!(config(100, UcpRounding::Nearest).round_price(ray(1_249)), ray(1_200));
+        assert_eq!(config(100, UcpRounding::Nearest).round_price(ray(1_250)), ray(1_300));
+        assert_eq!(config(100, UcpRounding::Up).round_price(ray(1_300)), ray(1_300));
+
+        // a price below the first tick has nowhere to round down to
+        assert_eq!(config(100, UcpRounding::Down).round_price(ray(50)), ray(50));
+        assert_eq!(PoolMatchingConfig::default().round_price(ray(1_234)), ray(1_234));
+    }
+
+    #[test]
+    fn only_crosses_the_amm_past_the_spread() {
+        let config = PoolMatchingConfig { min_amm_spread_e6: 1_000, ..Default::default() };
+        assert!(!config.crosses_amm(ray(1_000_900), ray(1_000_000)));
+        assert!(config.crosses_amm(ray(1_001_000), ray(1_000_000)));
+        assert!(config.crosses_amm(ray(998_000), ray(1_000_000)));
+        assert!(PoolMatchingConfig::default().crosses_amm(ray(1), ray(1)));
+    }
+
+    #[test]
+    fn bands_the_spot_price() {
+        let config = PoolMatchingConfig { price_band_e6: Some(5_000), ..Default::default() };
+        assert_eq!(config.price_band(ray(1_000_000)), Some((ray(995_000), ray(1_005_000))));
+        // a band wider than the price reaches down to zero
+        let config = PoolMatchingConfig { price_band_e6: Some(2_000_000), ..Default::default() };
+        assert_eq!(config.price_band(ray(1_000_000)), Some((ray(0), ray(3_000_000))));
+        assert_eq!(PoolMatchingConfig::default().price_band(ray(1_000_000)), None);
+    }
+
+    #[test]
+    fn unset_fields_default() {
+        let config: PoolMatchingConfig =
+            serde_json::from_str(r#"{ "tick_size": "0x3e8", "rounding": "nearest" }"#).unwrap();
+        assert_eq!(
+            config,
+            PoolMatchingConfig {
+                tick_size:         Some(ray(1_000)),
+                rounding:          UcpRounding::Nearest,
+                min_amm_spread_e6: 0,
+                price_band_e6:     None
+            }
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// What the matcher does instead of matching a bid and an ask signed by the
+/// same address. Self-crosses only cost gas and inflate the volume.
+///
+/// Every validator has to prevent self-trades the same way, otherwise they
+/// don't agree on the solutions, so it's part of the
+/// [`ProtocolGenesis`](crate::consensus::ProtocolGenesis).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelfTradePolicy {
+    /// cancel the order validated for the later block, the order hash breaks
+    /// ties so every validator cancels the same one
+    CancelNewest,
+    /// cancel the order validated for the earlier block
+    CancelOldest,
+    /// take the overlap out of both orders, the smaller one is cancelled.
+    /// exact orders can't be decremented so they're cancelled as well
+    DecrementBoth
+}
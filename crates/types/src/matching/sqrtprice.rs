@@ -30,7 +30,9 @@ impl SqrtPriceX96 {
         res
     }
 
-    /// Convert a floating point price `P` into a SqrtPriceX96 `sqrt(P)`
+    /// Convert a floating point price `P` into a SqrtPriceX96 `sqrt(P)`. Exact
+    /// prices are converted from a [`Ray`] instead
+    #[cfg(any(test, feature = "float-prices"))]
     pub fn from_float_price(price: f64) -> Self {
         SqrtPriceX96(U160::from(price.sqrt() * (2.0_f64.pow(96))))
     }
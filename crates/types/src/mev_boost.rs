@@ -1,4 +1,4 @@
-use std::{ops::Deref, pin::Pin, sync::Arc};
+use std::{fmt, ops::Deref, pin::Pin, sync::Arc, time::Duration};
 
 use alloy::{
     eips::eip2718::Encodable2718,
@@ -8,7 +8,7 @@ use alloy::{
     rpc::types::TransactionRequest,
     transports::http::reqwest::Url
 };
-use futures::{Future, FutureExt};
+use futures::{future::join_all, Future, FutureExt};
 
 use crate::primitive::AngstromSigner;
 
@@ -41,9 +41,92 @@ impl SubmitTx for RootProvider {
     }
 }
 
+/// How long a relay has to accept a bundle by default
+pub const DEFAULT_RELAY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The way a bundle was sent out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubmissionPath {
+    /// privately, through the relays
+    Relays,
+    /// through the public mempool of the node, after every relay rejected the
+    /// bundle or timed out
+    PublicMempool
+}
+
+impl SubmissionPath {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Relays => "relays",
+            Self::PublicMempool => "public_mempool"
+        }
+    }
+}
+
+impl fmt::Display for SubmissionPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Outcome of sending a bundle out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Submission {
+    /// hash of the transaction sent on the path, the public one is signed
+    /// with its own priority fee
+    pub tx_hash:  TxHash,
+    pub path:     SubmissionPath,
+    /// whether the path took the transaction
+    pub accepted: bool
+}
+
+/// Priority fee of a bundle sent to the public mempool. Without the relays
+/// the bundle competes with every other transaction for its place, so the
+/// estimated priority fee is scaled and kept within bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeePolicy {
+    /// percentage of the estimated priority fee
+    pub multiplier_pct:   u64,
+    pub min_priority_fee: u128,
+    pub max_priority_fee: Option<u128>
+}
+
+impl Default for PriorityFeePolicy {
+    fn default() -> Self {
+        Self { multiplier_pct: 200, min_priority_fee: 1_000_000_000, max_priority_fee: None }
+    }
+}
+
+impl PriorityFeePolicy {
+    pub fn priority_fee(&self, estimated: u128) -> u128 {
+        let fee = estimated.saturating_mul(self.multiplier_pct as u128) / 100;
+        let fee = fee.max(self.min_priority_fee);
+        self.max_priority_fee.map_or(fee, |max| fee.min(max))
+    }
+
+    /// Sets the priority fee of the transaction, raising its max fee by as
+    /// much so that it still covers the base fee it was estimated for.
+    pub fn apply(&self, tx: &mut TransactionRequest) {
+        let estimated = tx.max_priority_fee_per_gas.unwrap_or_default();
+        let priority_fee = self.priority_fee(estimated);
+        let max_fee = tx
+            .max_fee_per_gas
+            .unwrap_or_default()
+            .saturating_sub(estimated)
+            .saturating_add(priority_fee);
+
+        tx.set_max_priority_fee_per_gas(priority_fee);
+        tx.set_max_fee_per_gas(max_fee);
+    }
+}
+
 pub struct MevBoostProvider<P> {
     mev_boost_providers: Vec<Arc<Box<dyn SubmitTx>>>,
-    node_provider:       Arc<P>
+    node_provider:       Arc<P>,
+    relay_timeout:       Duration,
+    /// priority fees of bundles sent to the public mempool, bundles are only
+    /// sent there if it's set
+    public_fallback:     Option<PriorityFeePolicy>
 }
 
 impl<P> MevBoostProvider<P>
@@ -54,7 +137,12 @@ where
         node_provider: Arc<P>,
         mev_boost_providers: Vec<Arc<Box<dyn SubmitTx>>>
     ) -> Self {
-        Self { node_provider, mev_boost_providers }
+        Self {
+            node_provider,
+            mev_boost_providers,
+            relay_timeout: DEFAULT_RELAY_TIMEOUT,
+            public_fallback: None
+        }
     }
 
     pub fn new_from_urls(node_provider: Arc<P>, urls: &[Url]) -> Self {
@@ -66,7 +154,21 @@ where
             })
             .collect::<Vec<_>>();
 
-        Self { mev_boost_providers, node_provider }
+        Self::new_from_raw(node_provider, mev_boost_providers)
+    }
+
+    /// Counts relays that didn't accept a bundle within `timeout` as
+    /// rejecting it.
+    pub fn with_relay_timeout(mut self, timeout: Duration) -> Self {
+        self.relay_timeout = timeout;
+        self
+    }
+
+    /// Sends bundles no relay accepted to the public mempool, with the
+    /// priority fee of the policy. See [`Self::sign_and_send`].
+    pub fn with_public_fallback(mut self, policy: PriorityFeePolicy) -> Self {
+        self.public_fallback = Some(policy);
+        self
     }
 
     pub async fn populate_gas_nonce_chain_id(&self, tx_from: Address, tx: &mut TransactionRequest) {
@@ -88,21 +190,69 @@ where
         tx.set_chain_id(1);
     }
 
-    // has as consumption here due to weird to general error
+    /// Sends the transaction to every relay at once. If none of them accepts
+    /// it in time, it's sent to the public mempool when the fallback is set
+    /// and the caller `may_go_public`.
+    ///
+    /// The public mempool has no revert protection, a bundle that lands there
+    /// after another one for the block pays its gas for nothing. So only one
+    /// of the submitters of a bundle may go public, and only with a bundle
+    /// that doesn't revert against the latest state.
     pub async fn sign_and_send(
         &self,
         signer: AngstromSigner,
-        tx: TransactionRequest
-    ) -> (TxHash, bool) {
-        let mut submitted = true;
-        let mut phash = None;
-        for provider in self.mev_boost_providers.clone() {
-            let (hash, sent) = provider.submit_transaction(&signer, tx.clone()).await;
-            phash = Some(hash);
-            submitted &= sent;
+        tx: TransactionRequest,
+        may_go_public: bool
+    ) -> Submission {
+        let relays = self.mev_boost_providers.iter().map(|provider| {
+            let tx = tx.clone();
+            let signer = &signer;
+            async move {
+                tokio::time::timeout(self.relay_timeout, provider.submit_transaction(signer, tx))
+                    .await
+                    .ok()
+            }
+        });
+        let results = join_all(relays).await;
+        let accepted = results.iter().flatten().any(|(_, sent)| *sent);
+        let tx_hash = results.iter().flatten().map(|(hash, _)| *hash).next();
+
+        let Some(policy) = self.public_fallback.filter(|_| !accepted && may_go_public) else {
+            // every relay timed out, the hash is the one they would have sent
+            let tx_hash = match tx_hash {
+                Some(hash) => hash,
+                None => *tx.build(&signer).await.unwrap().tx_hash()
+            };
+            return Submission { tx_hash, path: SubmissionPath::Relays, accepted }
+        };
+
+        tracing::warn!(
+            relays = self.mev_boost_providers.len(),
+            "no relay accepted the bundle, sending it to the public mempool"
+        );
+        let mut tx = tx;
+        policy.apply(&mut tx);
+        let reverts = self
+            .node_provider
+            .call(&tx)
+            .await
+            .inspect_err(|error| {
+                tracing::error!(%error, "bundle reverts, not sending it to the public mempool")
+            })
+            .is_err();
+        let signed = tx.build(&signer).await.unwrap();
+        let tx_hash = *signed.tx_hash();
+        if reverts {
+            return Submission { tx_hash, path: SubmissionPath::PublicMempool, accepted: false }
         }
+        let accepted = self
+            .node_provider
+            .send_raw_transaction(&signed.encoded_2718())
+            .await
+            .inspect_err(|error| tracing::error!(%error, "public mempool rejected the bundle"))
+            .is_ok();
 
-        (phash.expect("no mev boost endpoint was set"), submitted)
+        Submission { tx_hash, path: SubmissionPath::PublicMempool, accepted }
     }
 }
 
@@ -113,3 +263,132 @@ impl<P> Deref for MevBoostProvider<P> {
         &self.node_provider
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::Ethereum,
+        primitives::{Bytes, B256},
+        providers::mock::Asserter
+    };
+
+    use super::*;
+
+    /// A relay that takes the bundles with `accepts`, or never answers.
+    struct Relay {
+        accepts: Option<bool>
+    }
+
+    impl SubmitTx for Relay {
+        fn submit_transaction<'a>(
+            &'a self,
+            signer: &'a AngstromSigner,
+            tx: TransactionRequest
+        ) -> Pin<Box<dyn Future<Output = (TxHash, bool)> + Send + 'a>> {
+            async move {
+                let Some(accepts) = self.accepts else { return futures::future::pending().await };
+                let tx = tx.build(&signer).await.unwrap();
+                (*tx.tx_hash(), accepts)
+            }
+            .boxed()
+        }
+    }
+
+    fn provider(
+        relays: Vec<Option<bool>>,
+        asserter: &Asserter
+    ) -> MevBoostProvider<impl Provider + 'static> {
+        let node = ProviderBuilder::<_, _, Ethereum>::default().on_mocked_client(asserter.clone());
+        let relays = relays
+            .into_iter()
+            .map(|accepts| Arc::new(Box::new(Relay { accepts }) as Box<dyn SubmitTx>))
+            .collect();
+
+        MevBoostProvider::new_from_raw(Arc::new(node), relays)
+            .with_relay_timeout(Duration::from_millis(50))
+            .with_public_fallback(PriorityFeePolicy::default())
+    }
+
+    fn bundle(signer: &AngstromSigner) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_from(signer.address())
+            .with_to(Address::repeat_byte(1))
+            .with_nonce(0)
+            .with_gas_limit(30_000_000)
+            .with_max_fee_per_gas(10_000_000_000)
+            .with_max_priority_fee_per_gas(1_000_000_000)
+            .with_chain_id(1)
+    }
+
+    #[tokio::test]
+    async fn counts_relays_that_time_out_as_rejecting() {
+        let signer = AngstromSigner::random();
+        let asserter = Asserter::new();
+        let provider = provider(vec![None, Some(true)], &asserter);
+
+        // one relay taking it is enough, the stuck one doesn't hold it up
+        let submission = provider
+            .sign_and_send(signer.clone(), bundle(&signer), true)
+            .await;
+        assert_eq!(submission.path, SubmissionPath::Relays);
+        assert!(submission.accepted);
+
+        // a backup doesn't go public when every relay times out
+        let provider = provider(vec![None, None], &asserter);
+        let submission = provider
+            .sign_and_send(signer.clone(), bundle(&signer), false)
+            .await;
+        assert_eq!(submission.path, SubmissionPath::Relays);
+        assert!(!submission.accepted);
+        assert_eq!(submission.tx_hash, *bundle(&signer).build(&signer).await.unwrap().tx_hash());
+    }
+
+    #[tokio::test]
+    async fn only_sends_bundles_that_dont_revert_to_the_public_mempool() {
+        let signer = AngstromSigner::random();
+        let asserter = Asserter::new();
+        let provider = provider(vec![Some(false), None], &asserter);
+
+        asserter.push_success(&Bytes::new());
+        asserter.push_success(&B256::ZERO);
+        let submission = provider
+            .sign_and_send(signer.clone(), bundle(&signer), true)
+            .await;
+        assert_eq!(submission.path, SubmissionPath::PublicMempool);
+        assert!(submission.accepted);
+        // signed with the priority fee of the fallback
+        let mut public = bundle(&signer);
+        PriorityFeePolicy::default().apply(&mut public);
+        assert_eq!(submission.tx_hash, *public.build(&signer).await.unwrap().tx_hash());
+
+        // the simulation reverting keeps it out of the mempool, which would
+        // have taken it
+        asserter.push_failure_msg("execution reverted");
+        asserter.push_success(&B256::ZERO);
+        let submission = provider
+            .sign_and_send(signer.clone(), bundle(&signer), true)
+            .await;
+        assert_eq!(submission.path, SubmissionPath::PublicMempool);
+        assert!(!submission.accepted);
+    }
+
+    #[test]
+    fn keeps_the_priority_fee_within_the_policy() {
+        let policy = PriorityFeePolicy {
+            multiplier_pct:   150,
+            min_priority_fee: 2,
+            max_priority_fee: Some(300)
+        };
+        assert_eq!(policy.priority_fee(1), 2);
+        assert_eq!(policy.priority_fee(100), 150);
+        assert_eq!(policy.priority_fee(1_000), 300);
+
+        let mut tx = TransactionRequest::default()
+            .max_fee_per_gas(1_100)
+            .max_priority_fee_per_gas(100);
+        policy.apply(&mut tx);
+        assert_eq!(tx.max_priority_fee_per_gas, Some(150));
+        // the base fee it was estimated for is still covered
+        assert_eq!(tx.max_fee_per_gas, Some(1_150));
+    }
+}
@@ -0,0 +1,162 @@
+//! How the liquidity of a pool is made up, for integrators to assess it.
+use alloy::primitives::{BlockNumber, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::primitive::PoolId;
+
+/// Price buckets the orders of a pool are counted in by default.
+pub const DEFAULT_PRICE_BUCKETS: usize = 10;
+/// Most price buckets the orders of a pool can be counted in.
+pub const MAX_PRICE_BUCKETS: usize = 100;
+
+/// Upper bounds, in blocks, of the buckets the age of orders is counted in.
+pub const AGE_BUCKETS: [u64; 7] = [0, 1, 2, 5, 10, 50, u64::MAX];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolAnalytics {
+    pub pool_id:         PoolId,
+    /// block the analytics were taken at
+    pub block:           BlockNumber,
+    /// pending limit orders by the price they are booked at
+    pub price_buckets:   Vec<PriceBucket>,
+    /// pending limit orders by the blocks since they were last validated
+    pub ages:            Vec<AgeBucket>,
+    pub partial_orders:  usize,
+    pub exact_orders:    usize,
+    pub searcher_orders: usize,
+    /// limit orders offered to and filled by the last rounds, oldest first
+    pub fills:           Vec<BlockFills>
+}
+
+/// Orders booked at a price in `[min_price, max_price]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceBucket {
+    pub min_price: U256,
+    pub max_price: U256,
+    pub bids:      usize,
+    pub asks:      usize
+}
+
+impl PriceBucket {
+    /// Splits the range of the booked `prices` into `count` buckets of equal
+    /// width. Prices come with whether they're of a bid.
+    pub fn bucket(prices: &[(U256, bool)], count: usize) -> Vec<Self> {
+        let (Some(min), Some(max)) = (
+            prices.iter().map(|(price, _)| *price).min(),
+            prices.iter().map(|(price, _)| *price).max()
+        ) else {
+            return vec![]
+        };
+        // no more buckets than there are prices in the range
+        let count = U256::from(count.max(1))
+            .min((max - min).saturating_add(U256::from(1)))
+            .to::<usize>();
+        let width = (max - min) / U256::from(count) + U256::from(1);
+
+        let mut buckets = (0..count)
+            .map(|i| {
+                let min_price = min.saturating_add(width * U256::from(i));
+                Self {
+                    min_price,
+                    max_price: min_price.saturating_add(width - U256::from(1)).min(max),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+        for (price, is_bid) in prices {
+            let bucket = &mut buckets[((*price - min) / width).to::<usize>()];
+            if *is_bid {
+                bucket.bids += 1;
+            } else {
+                bucket.asks += 1;
+            }
+        }
+
+        buckets
+    }
+}
+
+/// Orders at most `max_age` blocks old.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgeBucket {
+    pub max_age: u64,
+    pub orders:  usize
+}
+
+impl AgeBucket {
+    /// Counts `ages` into the [`AGE_BUCKETS`].
+    pub fn bucket(ages: impl IntoIterator<Item = u64>) -> Vec<Self> {
+        let mut buckets = AGE_BUCKETS
+            .iter()
+            .map(|max_age| Self { max_age: *max_age, orders: 0 })
+            .collect::<Vec<_>>();
+        for age in ages {
+            if let Some(bucket) = buckets.iter_mut().find(|bucket| age <= bucket.max_age) {
+                bucket.orders += 1;
+            }
+        }
+
+        buckets
+    }
+}
+
+/// Limit orders of a pool offered to the round of `block` and how many of them
+/// were filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFills {
+    pub block:   BlockNumber,
+    pub offered: usize,
+    pub filled:  usize
+}
+
+impl BlockFills {
+    pub fn fill_ratio(&self) -> f64 {
+        if self.offered == 0 {
+            return 0.0
+        }
+        self.filled as f64 / self.offered as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_prices_over_their_range() {
+        let prices = [
+            (U256::from(100), true),
+            (U256::from(104), true),
+            (U256::from(150), false),
+            (U256::from(199), false)
+        ];
+        let buckets = PriceBucket::bucket(&prices, 2);
+
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(
+            (buckets[0].min_price, buckets[0].max_price),
+            (U256::from(100), U256::from(149))
+        );
+        assert_eq!((buckets[0].bids, buckets[0].asks), (2, 0));
+        assert_eq!(
+            (buckets[1].min_price, buckets[1].max_price),
+            (U256::from(150), U256::from(199))
+        );
+        assert_eq!((buckets[1].bids, buckets[1].asks), (0, 2));
+
+        let buckets = PriceBucket::bucket(&prices[..1], 4);
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].bids, 1);
+        assert!(PriceBucket::bucket(&[], 4).is_empty());
+    }
+
+    #[test]
+    fn buckets_ages() {
+        let buckets = AgeBucket::bucket([0, 1, 3, 3, 1_000]);
+        let orders = buckets.iter().map(|b| b.orders).collect::<Vec<_>>();
+        assert_eq!(orders, vec![1, 1, 0, 2, 0, 0, 1]);
+    }
+}
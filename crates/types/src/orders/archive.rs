@@ -0,0 +1,233 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf}
+};
+
+use alloy::primitives::BlockNumber;
+use serde::{Deserialize, Serialize};
+
+use super::PoolSolution;
+use crate::{
+    primitive::PoolId,
+    sol_bindings::{
+        grouped_orders::{GroupedVanillaOrder, OrderWithStorageData},
+        rpc_orders::TopOfBlockOrder
+    }
+};
+
+/// Amount of blocks the validators keep the archived rounds for by default,
+/// about a week.
+pub const ORDER_ARCHIVE_BLOCKS_KEPT: u64 = 50_400;
+
+/// The books a round was matched over along with the solutions of its
+/// proposal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedRound {
+    pub block_number: BlockNumber,
+    /// limit orders that reached quorum
+    pub limit:        Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+    /// top of block orders that reached quorum
+    pub searcher:     Vec<OrderWithStorageData<TopOfBlockOrder>>,
+    pub solutions:    Vec<PoolSolution>
+}
+
+/// The book of a single pool in an [`ArchivedRound`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedBook {
+    pub block_number: BlockNumber,
+    pub pool_id:      PoolId,
+    pub bids:         Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+    pub asks:         Vec<OrderWithStorageData<GroupedVanillaOrder>>,
+    pub searcher:     Vec<OrderWithStorageData<TopOfBlockOrder>>,
+    pub solution:     Option<PoolSolution>
+}
+
+impl ArchivedRound {
+    pub fn book(&self, pool_id: PoolId) -> ArchivedBook {
+        let (bids, asks) = self
+            .limit
+            .iter()
+            .filter(|order| order.pool_id == pool_id)
+            .cloned()
+            .partition(|order| order.is_bid);
+
+        ArchivedBook {
+            block_number: self.block_number,
+            pool_id,
+            bids,
+            asks,
+            searcher: self
+                .searcher
+                .iter()
+                .filter(|order| order.pool_id == pool_id)
+                .cloned()
+                .collect(),
+            solution: self
+                .solutions
+                .iter()
+                .find(|solution| solution.id == pool_id)
+                .cloned()
+        }
+    }
+}
+
+/// Rounds written to `<dir>/<block_number>.json` by the validators and read
+/// back by archive nodes, which can share the directory with them.
+#[derive(Debug, Clone)]
+pub struct OrderArchive {
+    dir:         PathBuf,
+    /// blocks a round is kept for after it's written, forever if unset
+    blocks_kept: Option<u64>
+}
+
+impl OrderArchive {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, blocks_kept: None }
+    }
+
+    /// Removes the rounds that are `blocks` or more blocks older than the
+    /// last one written.
+    pub fn with_retention(mut self, blocks: u64) -> Self {
+        self.blocks_kept = Some(blocks);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes the round, a crash halfway leaves no partial file behind. The
+    /// rounds that fell out of the retention go with it.
+    pub fn insert(&self, round: &ArchivedRound) -> eyre::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(round.block_number);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(round)?)?;
+        std::fs::rename(tmp, path)?;
+
+        if let Some(kept) = self.blocks_kept {
+            let oldest = round.block_number.saturating_sub(kept.saturating_sub(1));
+            for block in self.blocks()?.into_iter().filter(|block| *block < oldest) {
+                std::fs::remove_file(self.path(block))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The round of the block, [`None`] if none was archived.
+    pub fn round(&self, block_number: BlockNumber) -> eyre::Result<Option<ArchivedRound>> {
+        match std::fs::read(self.path(block_number)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into())
+        }
+    }
+
+    /// First and last block that has an archived round.
+    pub fn range(&self) -> eyre::Result<Option<(BlockNumber, BlockNumber)>> {
+        let blocks = self.blocks()?;
+
+        Ok(blocks
+            .iter()
+            .min()
+            .copied()
+            .zip(blocks.iter().max().copied()))
+    }
+
+    /// Blocks that have an archived round, in no particular order.
+    fn blocks(&self) -> eyre::Result<Vec<BlockNumber>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into())
+        };
+
+        let mut blocks = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension() != Some("json".as_ref()) {
+                continue
+            }
+            let Some(block) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<BlockNumber>().ok())
+            else {
+                continue
+            };
+            blocks.push(block);
+        }
+
+        Ok(blocks)
+    }
+
+    fn path(&self, block_number: BlockNumber) -> PathBuf {
+        self.dir.join(format!("{block_number}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(block_number: BlockNumber, pool_id: PoolId) -> ArchivedRound {
+        let order = |is_bid| OrderWithStorageData {
+            pool_id,
+            is_bid,
+            ..OrderWithStorageData::<GroupedVanillaOrder>::default()
+        };
+
+        ArchivedRound {
+            block_number,
+            limit: vec![order(true), order(false), order(true)],
+            searcher: vec![],
+            solutions: vec![PoolSolution { id: pool_id, ..Default::default() }]
+        }
+    }
+
+    #[test]
+    fn reads_back_the_archived_rounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = OrderArchive::new(dir.path().join("rounds"));
+        assert_eq!(archive.range().unwrap(), None);
+        assert_eq!(archive.round(7).unwrap(), None);
+
+        let pool_id = PoolId::random();
+        archive.insert(&round(7, pool_id)).unwrap();
+        archive.insert(&round(9, pool_id)).unwrap();
+
+        assert_eq!(archive.round(7).unwrap(), Some(round(7, pool_id)));
+        assert_eq!(archive.round(8).unwrap(), None);
+        assert_eq!(archive.range().unwrap(), Some((7, 9)));
+    }
+
+    #[test]
+    fn prunes_the_rounds_out_of_the_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = OrderArchive::new(dir.path().to_path_buf()).with_retention(3);
+
+        let pool_id = PoolId::random();
+        for block in [4, 5, 6] {
+            archive.insert(&round(block, pool_id)).unwrap();
+        }
+        assert_eq!(archive.range().unwrap(), Some((4, 6)));
+
+        archive.insert(&round(8, pool_id)).unwrap();
+        assert_eq!(archive.range().unwrap(), Some((6, 8)));
+        assert_eq!(archive.round(5).unwrap(), None);
+    }
+
+    #[test]
+    fn splits_the_book_of_a_pool() {
+        let pool_id = PoolId::random();
+        let round = round(1, pool_id);
+
+        let book = round.book(pool_id);
+        assert_eq!((book.bids.len(), book.asks.len()), (2, 1));
+        assert!(book.solution.is_some());
+
+        let other = round.book(PoolId::random());
+        assert!(other.bids.is_empty() && other.asks.is_empty() && other.solution.is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock}
+};
+
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use super::{OrderFillState, PoolSolution};
+use crate::{matching::Ray, primitive::PoolId};
+
+/// Why the breaker of a pool tripped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrippedBreaker {
+    pub pool_id:      PoolId,
+    /// the clearing price the matching came up with
+    pub ucp:          Ray,
+    /// the AMM spot price before the matching
+    pub spot_price:   Ray,
+    /// deviation between the two, in millionths of the spot price
+    pub deviation_e6: u64
+}
+
+/// Halts the matching of a pool in the rounds its clearing price moved too far
+/// from the AMM spot price.
+///
+/// A halted pool gets a solution that doesn't touch the AMM and doesn't fill
+/// any order. Every validator verifies the solutions of the leader, so whether
+/// a pool is halted only depends on the solution and the spot price of the
+/// round and the threshold every validator runs with, never on what the node
+/// saw in earlier rounds. The trips are recorded for operators to look into,
+/// the record is shared with the RPC that lists and clears them. Without a
+/// threshold, no breaker ever trips.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    threshold_e6: Option<u32>,
+    tripped:      Arc<RwLock<HashMap<PoolId, TrippedBreaker>>>
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold_e6: u32) -> Self {
+        Self { threshold_e6: Some(threshold_e6), tripped: Default::default() }
+    }
+
+    /// The breaker with the threshold, sharing the record of trips.
+    pub fn with_threshold(self, threshold_e6: Option<u32>) -> Self {
+        Self { threshold_e6, ..self }
+    }
+
+    /// Deviation of the price from the reference, in millionths of the
+    /// reference.
+    pub fn deviation_e6(price: Ray, reference: Ray) -> u64 {
+        if reference.is_zero() {
+            return u64::MAX
+        }
+        let deviation = price
+            .0
+            .abs_diff(reference.0)
+            .saturating_mul(U256::from(1_000_000));
+
+        (deviation / reference.0).saturating_to()
+    }
+
+    /// Deviation the breaker trips at, [`None`] if it's disabled.
+    pub fn threshold_e6(&self) -> Option<u32> {
+        self.threshold_e6
+    }
+
+    /// Whether the breaker of the pool tripped since it was last cleared.
+    pub fn is_tripped(&self, pool_id: &PoolId) -> bool {
+        self.tripped.read().expect("poisoned").contains_key(pool_id)
+    }
+
+    /// Halts the pool for the round if the clearing price of its solution
+    /// deviates too much from the spot price, the solution is replaced by one
+    /// that leaves the pool untouched.
+    pub fn apply(&self, solution: PoolSolution, spot_price: Ray) -> PoolSolution {
+        let Some(threshold_e6) = self.threshold_e6 else { return solution };
+
+        // a book that didn't clear has no price to check
+        if solution.ucp.is_zero() {
+            return solution
+        }
+        let deviation_e6 = Self::deviation_e6(solution.ucp, spot_price);
+        if deviation_e6 <= threshold_e6 as u64 {
+            return solution
+        }
+
+        tracing::warn!(
+            pool_id = ?solution.id,
+            ucp = ?solution.ucp,
+            ?spot_price,
+            deviation_e6,
+            "clearing price deviates from the spot price, halting the pool"
+        );
+        self.tripped.write().expect("poisoned").insert(
+            solution.id,
+            TrippedBreaker { pool_id: solution.id, ucp: solution.ucp, spot_price, deviation_e6 }
+        );
+
+        Self::halted(solution, spot_price)
+    }
+
+    fn halted(mut solution: PoolSolution, spot_price: Ray) -> PoolSolution {
+        solution.ucp = spot_price;
+        solution.searcher = None;
+        solution.amm_quantity = None;
+        // the rest of the ring is unwound with `unwind_open_rings`
+        solution.ring = None;
+        solution
+            .limit
+            .iter_mut()
+            .for_each(|outcome| outcome.outcome = OrderFillState::Unfilled);
+
+        solution
+    }
+
+    pub fn tripped(&self) -> Vec<TrippedBreaker> {
+        let mut tripped = self
+            .tripped
+            .read()
+            .expect("poisoned")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        tripped.sort_unstable_by_key(|breaker| breaker.pool_id);
+
+        tripped
+    }
+
+    /// Forgets the last trip of the pool once an operator looked into it.
+    /// Returns false if its breaker wasn't tripped.
+    pub fn clear(&self, pool_id: &PoolId) -> bool {
+        let cleared = self
+            .tripped
+            .write()
+            .expect("poisoned")
+            .remove(pool_id)
+            .is_some();
+        if cleared {
+            tracing::info!(?pool_id, "cleared the circuit breaker of the pool");
+        }
+
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::{OrderId, OrderOutcome};
+
+    fn solution(pool_id: PoolId, ucp: u64) -> PoolSolution {
+        PoolSolution {
+            id: pool_id,
+            ucp: Ray::from(U256::from(ucp)),
+            limit: vec![OrderOutcome {
+                id:      OrderId::default(),
+                outcome: OrderFillState::CompleteFill
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn halts_pool_in_the_rounds_it_strays() {
+        let breaker = CircuitBreaker::new(100_000);
+        let pool_id = PoolId::random();
+        let spot = Ray::from(U256::from(1000));
+
+        assert_eq!(breaker.apply(solution(pool_id, 1099), spot), solution(pool_id, 1099));
+        assert!(!breaker.is_tripped(&pool_id));
+
+        let halted = breaker.apply(solution(pool_id, 1200), spot);
+        assert_eq!(halted.ucp, spot);
+        assert!(!halted.limit[0].is_filled());
+        assert_eq!(breaker.tripped()[0].deviation_e6, 200_000);
+
+        // the next round is decided on its own price, a node that didn't see
+        // the trip comes to the same solution
+        let fresh = CircuitBreaker::new(100_000);
+        assert_eq!(
+            breaker.apply(solution(pool_id, 1000), spot),
+            fresh.apply(solution(pool_id, 1000), spot)
+        );
+        assert!(breaker.apply(solution(pool_id, 1000), spot).limit[0].is_filled());
+
+        assert!(breaker.clear(&pool_id));
+        assert!(!breaker.clear(&pool_id));
+    }
+
+    #[test]
+    fn never_trips_without_threshold() {
+        let breaker = CircuitBreaker::default();
+        let pool_id = PoolId::random();
+
+        breaker.apply(solution(pool_id, 5000), Ray::from(U256::from(1000)));
+        assert!(!breaker.is_tripped(&pool_id));
+    }
+}
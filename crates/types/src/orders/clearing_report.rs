@@ -0,0 +1,229 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock}
+};
+
+use alloy::primitives::{keccak256, BlockNumber, B256, U256};
+use serde::{Deserialize, Serialize};
+
+use super::OrderFillState;
+use crate::{
+    matching::{Ray, SqrtPriceX96},
+    primitive::PoolId
+};
+
+/// Amount of blocks the reports are kept in memory for.
+pub const CLEARING_REPORTS_KEPT: usize = 256;
+
+/// Machine-readable record of how the uniform clearing price of a pool was
+/// found for a block.
+///
+/// It holds everything needed to re-run the auction for the pool and to check
+/// every order got the outcome it should have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClearingReport {
+    pub block_number:    BlockNumber,
+    pub pool_id:         PoolId,
+    /// hash over the sorted hashes of all orders in the book
+    pub order_set_hash:  B256,
+    pub ucp:             Ray,
+    /// the matches that moved the clearing price, in the order they happened
+    pub steps:           Vec<ClearingStep>,
+    pub amm_start_price: Option<SqrtPriceX96>,
+    pub amm_end_price:   Option<SqrtPriceX96>,
+    /// sum of the surplus of all orders, in token1
+    pub total_surplus:   U256,
+    pub orders:          Vec<OrderClearing>
+}
+
+impl ClearingReport {
+    pub fn order_set_hash(orders: impl IntoIterator<Item = B256>) -> B256 {
+        let mut hashes = orders.into_iter().collect::<Vec<_>>();
+        hashes.sort_unstable();
+
+        keccak256(hashes.iter().flat_map(|hash| hash.0).collect::<Vec<_>>())
+    }
+}
+
+/// A single match of the auction. Sides without a hash are filled by the AMM
+/// or the debt of earlier matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClearingStep {
+    pub bid:      Option<B256>,
+    pub ask:      Option<B256>,
+    pub quantity: u128,
+    /// the clearing price after the match
+    pub price:    Ray
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderClearing {
+    pub order_hash:  B256,
+    pub is_bid:      bool,
+    /// limit price of the order, in the same direction as the clearing price
+    pub limit_price: Ray,
+    pub outcome:     OrderFillState,
+    /// difference between the limit and clearing price over the filled
+    /// quantity, in token1
+    pub surplus:     U256,
+    pub rationale:   FillRationale
+}
+
+/// Why an order got the outcome it got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FillRationale {
+    /// crossed the clearing price and was filled in full
+    Filled,
+    /// crossed the clearing price, the opposite side ran out before it was
+    /// filled in full
+    PartiallyFilled,
+    /// crossed the clearing price, the opposite side ran out before it was
+    /// reached
+    VolumeExhausted,
+    /// the limit price doesn't cross the clearing price
+    PriceNotCrossed,
+    /// the book didn't clear at all
+    NoClearingPrice,
+    /// the order can't be filled at all
+    Killed,
+    /// the limit price is outside the price band around the AMM
+    OutsidePriceBand
+}
+
+impl FillRationale {
+    pub fn new(outcome: OrderFillState, crosses: Option<bool>) -> Self {
+        match (outcome, crosses) {
+            (OrderFillState::Killed, _) => Self::Killed,
+            (OrderFillState::OutsidePriceBand, _) => Self::OutsidePriceBand,
+            (OrderFillState::CompleteFill, _) => Self::Filled,
+            (OrderFillState::PartialFill(_), _) => Self::PartiallyFilled,
+            (OrderFillState::Unfilled, None) => Self::NoClearingPrice,
+            (OrderFillState::Unfilled, Some(true)) => Self::VolumeExhausted,
+            (OrderFillState::Unfilled, Some(false)) => Self::PriceNotCrossed
+        }
+    }
+}
+
+/// Clearing reports of the most recent blocks, shared between the consensus,
+/// which creates them, and the RPC, which serves them.
+///
+/// If a directory is set, the reports of every block are also written to
+/// `<dir>/<block_number>.json`, and the reports of blocks that are no longer
+/// kept in memory are read back from it.
+#[derive(Debug, Clone, Default)]
+pub struct ClearingReportStore {
+    reports: Arc<RwLock<BTreeMap<BlockNumber, Vec<ClearingReport>>>>,
+    dir:     Option<PathBuf>
+}
+
+impl ClearingReportStore {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { reports: Default::default(), dir }
+    }
+
+    /// Replaces the reports of the block.
+    pub fn insert(&self, block_number: BlockNumber, reports: Vec<ClearingReport>) {
+        if let Some(dir) = self.dir.as_ref() {
+            if let Err(error) = Self::persist(dir, block_number, &reports) {
+                tracing::warn!(block_number, %error, "failed to persist clearing reports");
+            }
+        }
+
+        let mut stored = self.reports.write().expect("poisoned");
+        stored.insert(block_number, reports);
+        while stored.len() > CLEARING_REPORTS_KEPT {
+            stored.pop_first();
+        }
+    }
+
+    pub fn block(&self, block_number: BlockNumber) -> Vec<ClearingReport> {
+        if let Some(reports) = self.reports.read().expect("poisoned").get(&block_number) {
+            return reports.clone()
+        }
+
+        self.dir
+            .as_ref()
+            .and_then(|dir| Self::load(dir, block_number))
+            .unwrap_or_default()
+    }
+
+    pub fn pool(&self, block_number: BlockNumber, pool_id: PoolId) -> Option<ClearingReport> {
+        self.block(block_number)
+            .into_iter()
+            .find(|report| report.pool_id == pool_id)
+    }
+
+    fn load(dir: &Path, block_number: BlockNumber) -> Option<Vec<ClearingReport>> {
+        let bytes = std::fs::read(dir.join(format!("{block_number}.json"))).ok()?;
+        serde_json::from_slice(&bytes)
+            .inspect_err(|error| {
+                tracing::warn!(block_number, %error, "failed to read persisted clearing reports")
+            })
+            .ok()
+    }
+
+    fn persist(
+        dir: &Path,
+        block_number: BlockNumber,
+        reports: &[ClearingReport]
+    ) -> eyre::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(
+            dir.join(format!("{block_number}.json")),
+            serde_json::to_vec_pretty(reports)?
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(block_number: BlockNumber, pool_id: PoolId) -> ClearingReport {
+        ClearingReport {
+            block_number,
+            pool_id,
+            order_set_hash: ClearingReport::order_set_hash([]),
+            ucp: Ray::default(),
+            steps: vec![],
+            amm_start_price: None,
+            amm_end_price: None,
+            total_surplus: U256::ZERO,
+            orders: vec![]
+        }
+    }
+
+    #[test]
+    fn order_set_hash_ignores_order() {
+        let (a, b) = (B256::repeat_byte(1), B256::repeat_byte(2));
+        assert_eq!(ClearingReport::order_set_hash([a, b]), ClearingReport::order_set_hash([b, a]));
+    }
+
+    #[test]
+    fn keeps_most_recent_blocks() {
+        let store = ClearingReportStore::default();
+        let pool = PoolId::random();
+        for block in 0..=CLEARING_REPORTS_KEPT as u64 {
+            store.insert(block, vec![report(block, pool)]);
+        }
+
+        assert!(store.block(0).is_empty());
+        assert_eq!(store.pool(1, pool), Some(report(1, pool)));
+        assert_eq!(store.pool(1, PoolId::random()), None);
+    }
+
+    #[test]
+    fn reads_evicted_blocks_back_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = PoolId::random();
+        ClearingReportStore::new(Some(dir.path().to_path_buf())).insert(3, vec![report(3, pool)]);
+
+        // e.g. an archive node sharing the directory
+        let store = ClearingReportStore::new(Some(dir.path().to_path_buf()));
+        assert_eq!(store.pool(3, pool), Some(report(3, pool)));
+        assert!(store.block(4).is_empty());
+    }
+}
@@ -2,7 +2,9 @@ use serde::{Deserialize, Serialize};
 
 use super::OrderVolume;
 
-#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+    Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
 pub enum OrderFillState {
     /// The order has not yet been processed
     #[default]
@@ -12,7 +14,10 @@ pub enum OrderFillState {
     /// The order has been partially filled (and how much)
     PartialFill(OrderVolume),
     /// We have dropped this order, it can not or should not be filled.
-    Killed
+    Killed,
+    /// The limit price of the order is outside the price band of its pool, it
+    /// was left out of the match
+    OutsidePriceBand
 }
 
 impl OrderFillState {
@@ -31,7 +36,7 @@ impl OrderFillState {
         match self {
             Self::Unfilled => Self::PartialFill(quantity),
             Self::PartialFill(f) => Self::PartialFill(f + quantity),
-            Self::CompleteFill | Self::Killed => *self
+            Self::CompleteFill | Self::Killed | Self::OutsidePriceBand => *self
         }
     }
 }
@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock}
+};
+
+use alloy::primitives::{BlockNumber, TxHash, B256, U256};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::contract_payloads::angstrom::{AngstromBundle, BundleGasDetails};
+
+/// Amount of blocks the reconciliations are kept in memory for.
+pub const GAS_RECONCILIATIONS_KEPT: usize = 256;
+
+/// The gas the orders of a landed bundle were charged at matching time against
+/// what the bundle actually cost on chain.
+///
+/// The charges are in token0 of the pool of each order, reckoned from the
+/// estimated gas of the bundle at the forecast gas price. An order is charged
+/// the gas it was estimated to take on its own and an even share of the rest
+/// of the bundle. Its actual share is its own gas and an even share of what the
+/// receipt says the rest of the bundle took, at the effective gas price. The
+/// token prices are the ones of matching time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasReconciliation {
+    /// block the bundle landed in
+    pub block_number:            BlockNumber,
+    pub tx_hash:                 TxHash,
+    pub estimated_gas:           u64,
+    pub estimated_gas_price_wei: u128,
+    pub gas_used:                u64,
+    pub effective_gas_price_wei: u128,
+    pub orders:                  Vec<OrderGasCharge>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderGasCharge {
+    pub order_hash:     B256,
+    /// gas fee the bundle charged the order
+    pub charged_asset0: u128,
+    /// share of the order in the actual cost of the bundle
+    pub actual_asset0:  u128
+}
+
+impl OrderGasCharge {
+    /// What the order was charged over its actual share, negative if it was
+    /// charged too little.
+    pub fn overcharge(&self) -> i128 {
+        let signed = |amount: u128| i128::try_from(amount).unwrap_or(i128::MAX);
+        signed(self.charged_asset0).saturating_sub(signed(self.actual_asset0))
+    }
+}
+
+impl GasReconciliation {
+    /// `gas_units` is the gas every order of the bundle was estimated to take
+    /// on its own, by its hash.
+    pub fn new(
+        block_number: BlockNumber,
+        tx_hash: TxHash,
+        bundle: &AngstromBundle,
+        estimate: &BundleGasDetails,
+        gas_units: &HashMap<B256, u64>,
+        gas_used: u64,
+        effective_gas_price_wei: u128
+    ) -> Self {
+        let order_hashes = bundle.get_order_hashes(block_number).collect::<Vec<_>>();
+        let own_gas = |order_hash: &B256| gas_units.get(order_hash).copied().unwrap_or_default();
+        let total_own_gas = order_hashes
+            .iter()
+            .fold(0u64, |total, order_hash| total.saturating_add(own_gas(order_hash)));
+        let shared_gas = |bundle_gas: u64| {
+            bundle_gas.saturating_sub(total_own_gas) / order_hashes.len().max(1) as u64
+        };
+        let (estimated_shared_gas, actual_shared_gas) =
+            (shared_gas(estimate.gas()), shared_gas(gas_used));
+
+        let actual_share = |charged: u128, own_gas: u64| {
+            let estimated_cost = U256::from(own_gas.saturating_add(estimated_shared_gas))
+                * U256::from(estimate.gas_price_wei());
+            if estimated_cost.is_zero() {
+                return charged
+            }
+            let actual_cost = U256::from(own_gas.saturating_add(actual_shared_gas))
+                * U256::from(effective_gas_price_wei);
+            (U256::from(charged) * actual_cost / estimated_cost).saturating_to()
+        };
+
+        // the hashes come in the same order, top of block orders first
+        let charges = bundle
+            .top_of_block_orders
+            .iter()
+            .map(|order| order.gas_used_asset_0)
+            .chain(
+                bundle
+                    .user_orders
+                    .iter()
+                    .map(|order| order.extra_fee_asset0)
+            );
+        let orders = order_hashes
+            .iter()
+            .zip(charges)
+            .map(|(order_hash, charged_asset0)| OrderGasCharge {
+                order_hash: *order_hash,
+                charged_asset0,
+                actual_asset0: actual_share(charged_asset0, own_gas(order_hash))
+            })
+            .collect();
+
+        Self {
+            block_number,
+            tx_hash,
+            estimated_gas: estimate.gas(),
+            estimated_gas_price_wei: estimate.gas_price_wei(),
+            gas_used,
+            effective_gas_price_wei,
+            orders
+        }
+    }
+
+    /// What the orders were charged over their actual shares in total,
+    /// summed over the token0 of every pool.
+    pub fn total_overcharge(&self) -> i128 {
+        self.orders
+            .iter()
+            .fold(0i128, |total, order| total.saturating_add(order.overcharge()))
+    }
+}
+
+/// Gas reconciliations of the bundles that landed in the most recent blocks,
+/// shared between the consensus, which creates them, and the RPC, which
+/// serves them.
+#[derive(Debug, Clone)]
+pub struct GasReconciliationStore {
+    reconciliations: Arc<RwLock<BTreeMap<BlockNumber, GasReconciliation>>>,
+    /// every inserted reconciliation, a bundle of ours landed
+    landed:          broadcast::Sender<GasReconciliation>
+}
+
+impl Default for GasReconciliationStore {
+    fn default() -> Self {
+        Self {
+            reconciliations: Default::default(),
+            landed:          broadcast::channel(GAS_RECONCILIATIONS_KEPT).0
+        }
+    }
+}
+
+impl GasReconciliationStore {
+    pub fn insert(&self, reconciliation: GasReconciliation) {
+        let _ = self.landed.send(reconciliation.clone());
+        let mut stored = self.reconciliations.write().expect("poisoned");
+        stored.insert(reconciliation.block_number, reconciliation);
+        while stored.len() > GAS_RECONCILIATIONS_KEPT {
+            stored.pop_first();
+        }
+    }
+
+    /// The reconciliations inserted from now on, one per bundle that landed.
+    pub fn subscribe(&self) -> broadcast::Receiver<GasReconciliation> {
+        self.landed.subscribe()
+    }
+
+    pub fn block(&self, block_number: BlockNumber) -> Option<GasReconciliation> {
+        self.reconciliations
+            .read()
+            .expect("poisoned")
+            .get(&block_number)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract_payloads::angstrom::TopOfBlockOrder;
+
+    #[test]
+    fn splits_the_actual_cost_by_the_gas_of_each_order() {
+        let tob = |quantity_in, gas_used_asset_0| TopOfBlockOrder {
+            quantity_in,
+            gas_used_asset_0,
+            ..Default::default()
+        };
+        let bundle = AngstromBundle::new(
+            vec![Default::default()],
+            vec![Default::default()],
+            vec![],
+            vec![tob(1, 300), tob(2, 1_000)],
+            vec![]
+        );
+        let hashes = bundle.get_order_hashes(7).collect::<Vec<_>>();
+        let gas_units = HashMap::from([(hashes[0], 20_000), (hashes[1], 60_000)]);
+
+        // the rest of the bundle took 10k more gas than the 20k it was estimated
+        // at, at half the forecast price
+        let estimate = BundleGasDetails::new(HashMap::new(), 100_000).with_gas_price(20);
+        let reconciliation =
+            GasReconciliation::new(7, TxHash::ZERO, &bundle, &estimate, &gas_units, 110_000, 10);
+
+        // each order takes half the extra gas, which the cheaper one feels more
+        let charges = reconciliation
+            .orders
+            .iter()
+            .map(|order| (order.charged_asset0, order.actual_asset0))
+            .collect::<Vec<_>>();
+        assert_eq!(charges, vec![(300, 175), (1_000, 535)]);
+        assert_eq!(reconciliation.total_overcharge(), 590);
+
+        let store = GasReconciliationStore::default();
+        store.insert(reconciliation.clone());
+        assert_eq!(store.block(7), Some(reconciliation));
+        assert_eq!(store.block(8), None);
+    }
+}
@@ -0,0 +1,92 @@
+use thiserror::Error;
+
+use crate::sol_bindings::{grouped_orders::AllOrders, RawPoolOrder};
+
+/// Most tranches an iceberg order can be split into.
+pub const MAX_ICEBERG_TRANCHES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IcebergError {
+    #[error("an iceberg order needs at least two tranches")]
+    TooFewTranches,
+    #[error("{0} tranches are more than the {MAX_ICEBERG_TRANCHES} an iceberg order can have")]
+    TooManyTranches(usize),
+    #[error("tranche {0} isn't a standing order")]
+    NotStanding(usize),
+    #[error("tranche {0} differs from the first in its signer, pair or price")]
+    MismatchedTranche(usize)
+}
+
+/// Checks the tranches of an iceberg order.
+///
+/// An iceberg order is a run of standing orders of the same signer, on the
+/// same pair and at the same price. Only the first tranche is shown to peers
+/// and in the depth of the book, the next one is shown once it's filled. The
+/// hidden tranches are only revealed to the matcher of the rounds led by the
+/// node that holds them.
+pub fn validate_iceberg(tranches: &[AllOrders]) -> Result<(), IcebergError> {
+    let [first, rest @ ..] = tranches else { return Err(IcebergError::TooFewTranches) };
+    if rest.is_empty() {
+        return Err(IcebergError::TooFewTranches)
+    }
+    if tranches.len() > MAX_ICEBERG_TRANCHES {
+        return Err(IcebergError::TooManyTranches(tranches.len()))
+    }
+
+    tranches.iter().enumerate().try_for_each(|(i, tranche)| {
+        if !matches!(tranche, AllOrders::Standing(_)) {
+            return Err(IcebergError::NotStanding(i))
+        }
+        let same = tranche.from() == first.from()
+            && tranche.token_in() == first.token_in()
+            && tranche.token_out() == first.token_out()
+            && tranche.limit_price() == first.limit_price();
+        if !same {
+            return Err(IcebergError::MismatchedTranche(i))
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+
+    use super::*;
+    use crate::sol_bindings::{
+        grouped_orders::{FlashVariants, StandingVariants},
+        rpc_orders::{ExactFlashOrder, PartialStandingOrder}
+    };
+
+    fn tranche(nonce: u64) -> AllOrders {
+        AllOrders::Standing(StandingVariants::Partial(PartialStandingOrder {
+            max_amount_in: 100,
+            min_price: U256::from(5),
+            nonce,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn validates_tranches() {
+        assert_eq!(validate_iceberg(&[tranche(1), tranche(2)]), Ok(()));
+        assert_eq!(validate_iceberg(&[tranche(1)]), Err(IcebergError::TooFewTranches));
+        let many = (0..=MAX_ICEBERG_TRANCHES as u64)
+            .map(tranche)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            validate_iceberg(&many),
+            Err(IcebergError::TooManyTranches(MAX_ICEBERG_TRANCHES + 1))
+        );
+
+        let flash = AllOrders::Flash(FlashVariants::Exact(ExactFlashOrder::default()));
+        assert_eq!(validate_iceberg(&[tranche(1), flash]), Err(IcebergError::NotStanding(1)));
+
+        let AllOrders::Standing(StandingVariants::Partial(mut other)) = tranche(2) else {
+            unreachable!()
+        };
+        other.asset_in = Address::repeat_byte(1);
+        let other = AllOrders::Standing(StandingVariants::Partial(other));
+        assert_eq!(validate_iceberg(&[tranche(1), other]), Err(IcebergError::MismatchedTranche(1)));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fmt, str::FromStr};
+
+use alloy::primitives::B256;
+
+use crate::primitive::PoolId;
+
+/// What to do when a consistency check of the order storage or the books
+/// fails. Checking is meant for debug and staging deployments, it walks every
+/// order after each mutation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvariantMode {
+    /// don't run the checks
+    #[default]
+    Disabled,
+    /// log the violations and count them in the metrics
+    Report,
+    /// panic on the first check that fails
+    Panic
+}
+
+impl InvariantMode {
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Self::Disabled)
+    }
+}
+
+impl FromStr for InvariantMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disabled" => Ok(Self::Disabled),
+            "report" => Ok(Self::Report),
+            "panic" => Ok(Self::Panic),
+            other => Err(format!("unknown invariant mode {other}, expected disabled|report|panic"))
+        }
+    }
+}
+
+impl fmt::Display for InvariantMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disabled => write!(f, "disabled"),
+            Self::Report => write!(f, "report"),
+            Self::Panic => write!(f, "panic")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvariantViolation {
+    #[error("order {0:?} is stored more than once")]
+    DuplicateOrder(B256),
+    #[error("order {0:?} is both parked and pending")]
+    ParkedAndPending(B256),
+    #[error("order {hash:?} is stored in pool {stored_in:?} but belongs to another pool")]
+    WrongPool { hash: B256, stored_in: PoolId },
+    #[error("order {0:?} is pending but not currently valid")]
+    InvalidPending(B256),
+    #[error("order {0:?} is parked but currently valid")]
+    ValidParked(B256),
+    #[error("the price index of pool {0:?} doesn't match its orders")]
+    PriceIndexMismatch(PoolId),
+    #[error("side of book {pool:?} is not sorted at order {hash:?}")]
+    UnsortedBook { pool: PoolId, hash: B256 },
+    #[error("order {0:?} is on the wrong side of the book")]
+    WrongSide(B256),
+    #[error("order {0:?} was filled but is still in the pool")]
+    FilledStillPooled(B256),
+    #[error("outcome for order {0:?} that is not in the book")]
+    UnknownOutcome(B256),
+    #[error("order {0:?} has more than one outcome")]
+    DuplicateOutcome(B256),
+    #[error("order {0:?} is filled over its max quantity")]
+    Overfilled(B256)
+}
+
+impl InvariantViolation {
+    /// Label of the violation for the metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::DuplicateOrder(_) => "duplicate_order",
+            Self::ParkedAndPending(_) => "parked_and_pending",
+            Self::WrongPool { .. } => "wrong_pool",
+            Self::InvalidPending(_) => "invalid_pending",
+            Self::ValidParked(_) => "valid_parked",
+            Self::PriceIndexMismatch(_) => "price_index_mismatch",
+            Self::UnsortedBook { .. } => "unsorted_book",
+            Self::WrongSide(_) => "wrong_side",
+            Self::FilledStillPooled(_) => "filled_still_pooled",
+            Self::UnknownOutcome(_) => "unknown_outcome",
+            Self::DuplicateOutcome(_) => "duplicate_outcome",
+            Self::Overfilled(_) => "overfilled"
+        }
+    }
+}
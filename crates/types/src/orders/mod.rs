@@ -1,15 +1,35 @@
+mod analytics;
+mod archive;
+mod circuit_breaker;
+mod clearing_report;
 mod fillstate;
+mod gas_reconciliation;
+mod iceberg;
+mod invariants;
 mod origin;
+mod price_peg;
+mod ring;
+mod versioned;
 use alloy::{
     primitives::{keccak256, Address, FixedBytes, PrimitiveSignature, B256},
     sol_types::SolValue
 };
 pub mod orderpool;
 
+pub use analytics::*;
+pub use archive::*;
+pub use circuit_breaker::*;
+pub use clearing_report::*;
 pub use fillstate::*;
+pub use gas_reconciliation::*;
+pub use iceberg::*;
+pub use invariants::*;
 pub use orderpool::*;
 pub use origin::*;
+pub use price_peg::*;
+pub use ring::*;
 use serde::{Deserialize, Serialize};
+pub use versioned::*;
 
 pub type BookID = u128;
 pub type OrderID = u128;
@@ -18,7 +38,7 @@ pub type OrderPrice = MatchingPrice;
 
 use crate::{
     matching::{uniswap::Direction, MatchingPrice, Ray},
-    primitive::PoolId,
+    primitive::{PoolId, SigningDomain},
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
 
@@ -34,7 +54,7 @@ impl<Limit, Searcher> OrderSet<Limit, Searcher> {
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum NetAmmOrder {
     /// A NetAmmOrder that is Buying will be purchasing T0 from the AMM
     Buy(u128, u128),
@@ -95,7 +115,9 @@ impl NetAmmOrder {
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+/// Outcomes are ordered by the id of their order first, which is the order
+/// they have in a canonical [`PoolSolution`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct OrderOutcome {
     pub id:      OrderId,
     pub outcome: OrderFillState
@@ -127,9 +149,45 @@ pub struct PoolSolution {
     pub amm_quantity: Option<NetAmmOrder>,
     /// IDs of limit orders to be executed - it might be easier to just use
     /// hashes here
-    pub limit:        Vec<OrderOutcome>
+    pub limit:        Vec<OrderOutcome>,
+    /// Leg of a ring trade settled in this pool, the order of the leg is filled
+    /// at `ucp` without the amm. As the leg sets the clearing price a pool
+    /// settles at most one ring
+    #[serde(default)]
+    pub ring:         Option<RingLeg>
 }
 
+impl PoolSolution {
+    /// Puts the solution in its canonical form, with the outcomes of its limit
+    /// orders sorted. Solutions are compared and signed over in this form, so
+    /// the order the matching engine settled them in doesn't matter.
+    pub fn canonicalize(&mut self) {
+        self.limit.sort();
+    }
+
+    /// Whether the solution is in its canonical form, which also means no order
+    /// has two outcomes.
+    pub fn is_canonical(&self) -> bool {
+        self.limit.windows(2).all(|pair| pair[0].id < pair[1].id)
+    }
+}
+
+/// Puts `solutions` in the order of a proposal, by their pool, with every one
+/// in its canonical form.
+pub fn canonicalize_solutions(solutions: &mut [PoolSolution]) {
+    solutions.iter_mut().for_each(PoolSolution::canonicalize);
+    solutions.sort();
+}
+
+/// Whether `solutions` are in the order of a proposal, with at most one
+/// solution per pool.
+pub fn are_canonical_solutions(solutions: &[PoolSolution]) -> bool {
+    solutions.windows(2).all(|pair| pair[0].id < pair[1].id)
+        && solutions.iter().all(PoolSolution::is_canonical)
+}
+
+/// Solutions are ordered by their pool, of which a proposal has one solution
+/// at most.
 impl PartialOrd for PoolSolution {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -151,13 +209,92 @@ pub struct CancelOrderRequest {
     pub order_id:     B256
 }
 
+/// Unix timestamp (seconds) until which cancels signed over
+/// [`CancelOrderRequest::legacy_signing_payload`] are still accepted, so
+/// clients have time to move to the domain separated digest.
+pub const LEGACY_CANCEL_DIGEST_SUNSET: u64 = 1_798_761_600;
+
 impl CancelOrderRequest {
-    fn signing_payload(&self) -> FixedBytes<32> {
-        keccak256((self.user_address, self.order_id).abi_encode())
+    pub fn signing_payload(user_address: Address, order_id: B256) -> FixedBytes<32> {
+        SigningDomain::CancelOrder.signing_hash(&(user_address, order_id).abi_encode())
+    }
+
+    /// The digest cancels were signed over before they were domain separated,
+    /// accepted until [`LEGACY_CANCEL_DIGEST_SUNSET`].
+    pub fn legacy_signing_payload(user_address: Address, order_id: B256) -> FixedBytes<32> {
+        keccak256((user_address, order_id).abi_encode())
     }
 
     pub fn is_valid(&self) -> bool {
-        let hash = self.signing_payload();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.is_valid_at(now)
+    }
+
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
+        let signed_by_user = |hash: FixedBytes<32>| {
+            self.signature
+                .recover_address_from_prehash(&hash)
+                .is_ok_and(|sender| sender == self.user_address)
+        };
+
+        signed_by_user(Self::signing_payload(self.user_address, self.order_id))
+            || (timestamp < LEGACY_CANCEL_DIGEST_SUNSET
+                && signed_by_user(Self::legacy_signing_payload(self.user_address, self.order_id)))
+    }
+}
+
+/// Cancels every resting order of `user_address` across all pools that was
+/// received by `issued_at`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CancelAllOrdersRequest {
+    pub signature:    PrimitiveSignature,
+    pub user_address: Address,
+    /// unix timestamp (seconds) the request was signed at, orders received
+    /// after it aren't cancelled, so a replay can't cancel newer orders
+    pub issued_at:    u64,
+    /// unix timestamp (seconds) after which the request is no longer accepted,
+    /// limits how long a signed request can be replayed
+    pub valid_until:  u64
+}
+
+impl CancelAllOrdersRequest {
+    pub fn signing_payload(
+        user_address: Address,
+        issued_at: u64,
+        valid_until: u64
+    ) -> FixedBytes<32> {
+        SigningDomain::CancelAllOrders
+            .signing_hash(&(user_address, issued_at, valid_until).abi_encode())
+    }
+
+    /// Unique id of the request, used to deduplicate it while gossiping.
+    pub fn request_hash(&self) -> B256 {
+        Self::signing_payload(self.user_address, self.issued_at, self.valid_until)
+    }
+
+    /// Whether the order received at the unix timestamp `received_at` is
+    /// cancelled by the request.
+    pub fn cancels_received_at(&self, received_at: u64) -> bool {
+        received_at <= self.issued_at
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.is_valid_at(now)
+    }
+
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
+        if timestamp > self.valid_until {
+            return false
+        }
+
+        let hash = self.request_hash();
         let Ok(sender) = self.signature.recover_address_from_prehash(&hash) else { return false };
 
         sender == self.user_address
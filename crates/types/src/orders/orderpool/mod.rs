@@ -16,7 +16,9 @@ pub enum OrderStatus {
     Blocked
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub struct OrderId {
     /// user address
     pub address:         Address,
@@ -73,7 +75,9 @@ impl Ord for OrderPriorityData {
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
 pub enum OrderLocation {
     #[default]
     Limit,
@@ -12,5 +12,8 @@ pub enum OrderOrigin {
     /// This type of Order should not be propagated to the network. It's
     /// meant for private usage within the local node, or other composable
     /// mev-angstroms.
-    Private
+    Private,
+    /// Order was pulled from an external order flow auction feed. It's as
+    /// untrusted as [`OrderOrigin::External`], only tagged apart for analytics.
+    OrderFlowAuction
 }
@@ -0,0 +1,151 @@
+use alloy::{
+    primitives::{Address, FixedBytes, PrimitiveSignature, B256, U256},
+    sol_types::SolValue
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    matching::Ray,
+    primitive::SigningDomain,
+    sol_bindings::{grouped_orders::AllOrders, RawPoolOrder}
+};
+
+/// Largest distance a peg can put the price of an order from the AMM mid, in
+/// basis points of the mid.
+pub const MAX_PEG_OFFSET_BPS: i32 = 5_000;
+
+const BPS: i32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PricePegError {
+    #[error("only standing orders can be pegged")]
+    NotStanding,
+    #[error("the peg is for order {0:?}")]
+    WrongOrder(B256),
+    #[error("peg offset of {0} bps is out of bounds")]
+    OffsetOutOfBounds(i32),
+    #[error("the peg isn't signed by the signer of the order")]
+    InvalidSignature
+}
+
+/// Pegs the limit price of a standing order to the mid price of the AMM of its
+/// pool.
+///
+/// Every block the order is priced `offset_bps` away from the mid, bids below
+/// and asks above it, a negative offset crosses the mid. The signed limit
+/// price of the order is the worst price the peg can ever resolve to, it's
+/// what the contract checks the fill against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PricePeg {
+    pub order_hash: B256,
+    pub offset_bps: i32,
+    /// signed by the signer of the order
+    pub signature:  PrimitiveSignature
+}
+
+impl PricePeg {
+    pub fn signing_payload(order_hash: B256, offset_bps: i32) -> FixedBytes<32> {
+        SigningDomain::PricePeg.signing_hash(&(order_hash, offset_bps).abi_encode())
+    }
+
+    /// Checks the peg can be attached to the order.
+    pub fn validate(&self, order: &AllOrders) -> Result<(), PricePegError> {
+        if !matches!(order, AllOrders::Standing(_)) {
+            return Err(PricePegError::NotStanding)
+        }
+
+        self.validate_for(order.order_hash(), order.from())
+    }
+
+    /// Checks the peg is within bounds and was signed by `from` for the order
+    /// with `order_hash`.
+    pub fn validate_for(&self, order_hash: B256, from: Address) -> Result<(), PricePegError> {
+        if self.order_hash != order_hash {
+            return Err(PricePegError::WrongOrder(self.order_hash))
+        }
+        if self.offset_bps.unsigned_abs() > MAX_PEG_OFFSET_BPS.unsigned_abs() {
+            return Err(PricePegError::OffsetOutOfBounds(self.offset_bps))
+        }
+
+        let hash = Self::signing_payload(self.order_hash, self.offset_bps);
+        match self.signature.recover_address_from_prehash(&hash) {
+            Ok(signer) if signer == from => Ok(()),
+            _ => Err(PricePegError::InvalidSignature)
+        }
+    }
+
+    /// Price of the order on its side of the book for the given mid.
+    pub fn book_price(&self, mid: Ray, is_bid: bool) -> Ray {
+        let offset = if is_bid { -self.offset_bps } else { self.offset_bps };
+        let scale = BPS.saturating_add(offset).max(0) as u64;
+        (mid * U256::from(scale)) / U256::from(BPS)
+    }
+
+    /// Limit price of the order as it's specified by the order for the given
+    /// mid, never below the signed `limit_price`.
+    pub fn limit_price(&self, mid: Ray, is_bid: bool, limit_price: U256) -> U256 {
+        let book_price = self.book_price(mid, is_bid);
+        // bids are specified in the inverse of the price of the book
+        let price = if is_bid { book_price.inv_ray_round(true) } else { book_price };
+
+        // a higher price asks for more out of the same amount in on both sides
+        price.0.max(limit_price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+
+    use super::*;
+    use crate::sol_bindings::{grouped_orders::StandingVariants, rpc_orders::ExactStandingOrder};
+
+    fn signed(signer: &PrivateKeySigner, order_hash: B256, offset_bps: i32) -> PricePeg {
+        let hash = PricePeg::signing_payload(order_hash, offset_bps);
+        PricePeg { order_hash, offset_bps, signature: signer.sign_hash_sync(&hash).unwrap() }
+    }
+
+    #[test]
+    fn validates_pegs() {
+        let signer = PrivateKeySigner::random();
+        let mut order = ExactStandingOrder::default();
+        order.meta.from = signer.address();
+        let order = AllOrders::Standing(StandingVariants::Exact(order));
+        let hash = order.order_hash();
+
+        assert_eq!(signed(&signer, hash, 25).validate(&order), Ok(()));
+        assert_eq!(signed(&signer, hash, -MAX_PEG_OFFSET_BPS).validate(&order), Ok(()));
+        assert_eq!(
+            signed(&signer, hash, MAX_PEG_OFFSET_BPS + 1).validate(&order),
+            Err(PricePegError::OffsetOutOfBounds(MAX_PEG_OFFSET_BPS + 1))
+        );
+        assert_eq!(
+            signed(&PrivateKeySigner::random(), hash, 25).validate(&order),
+            Err(PricePegError::InvalidSignature)
+        );
+        let mut tampered = signed(&signer, hash, 25);
+        tampered.offset_bps = 50;
+        assert_eq!(tampered.validate(&order), Err(PricePegError::InvalidSignature));
+        assert_eq!(
+            signed(&signer, B256::ZERO, 25).validate(&order),
+            Err(PricePegError::WrongOrder(B256::ZERO))
+        );
+    }
+
+    #[test]
+    fn resolves_around_the_mid_within_the_signed_limit() {
+        let signer = PrivateKeySigner::random();
+        let peg = signed(&signer, B256::ZERO, 100);
+        let mid = Ray::from(U256::from(1_000));
+
+        assert_eq!(peg.book_price(mid, false), Ray::from(U256::from(1_010)));
+        assert_eq!(peg.book_price(mid, true), Ray::from(U256::from(990)));
+
+        assert_eq!(peg.limit_price(mid, false, U256::ZERO), U256::from(1_010));
+        assert_eq!(peg.limit_price(mid, false, U256::from(2_000)), U256::from(2_000));
+        let bid = peg.limit_price(mid, true, U256::ZERO);
+        assert_eq!(bid, Ray::from(U256::from(990)).inv_ray_round(true).0);
+        assert_eq!(peg.limit_price(mid, true, bid + U256::from(1)), bid + U256::from(1));
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use alloy::primitives::{keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+
+use super::{OrderFillState, PoolSolution};
+
+/// The part of a ring trade one pool settles. A ring is a cycle of orders in
+/// pools that share tokens, every order is paid with what the one before it
+/// gives so the ring settles between its orders without the AMM.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingLeg {
+    /// id of the ring, the same for all of its legs
+    pub ring:         B256,
+    /// orders in the ring
+    pub legs:         u8,
+    pub order:        B256,
+    pub token_in:     Address,
+    pub token_out:    Address,
+    /// what the order gives, at the clearing price of its pool
+    pub quantity_in:  u128,
+    /// what the order receives, at the clearing price of its pool
+    pub quantity_out: u128
+}
+
+impl RingLeg {
+    /// Id of the ring made up of the orders with these hashes, in ring order.
+    pub fn ring_id(orders: &[B256]) -> B256 {
+        keccak256(orders.concat())
+    }
+}
+
+/// Unfills the legs of every ring that isn't whole anymore, e.g. because the
+/// circuit breaker halted one of its pools. A ring only pays its orders when
+/// all of them are in the bundle.
+pub fn unwind_open_rings(solutions: &mut [PoolSolution]) {
+    let mut legs = HashMap::<B256, (u8, usize)>::new();
+    solutions
+        .iter()
+        .filter_map(|solution| solution.ring.as_ref().filter(|_| leg_is_filled(solution)))
+        .for_each(|leg| legs.entry(leg.ring).or_insert((leg.legs, 0)).1 += 1);
+
+    for solution in solutions.iter_mut() {
+        let Some(leg) = solution.ring.as_ref() else { continue };
+        if legs
+            .get(&leg.ring)
+            .is_some_and(|(total, settled)| *total as usize == *settled)
+        {
+            continue
+        }
+
+        tracing::debug!(pool_id = ?solution.id, ring = ?leg.ring, "unwinding open ring");
+        let order = leg.order;
+        solution
+            .limit
+            .iter_mut()
+            .filter(|outcome| outcome.id.hash == order)
+            .for_each(|outcome| outcome.outcome = OrderFillState::Unfilled);
+        solution.ring = None;
+    }
+}
+
+/// Checks that every ring in the solutions is whole and closes, every token a
+/// leg pays out is put in by the next one.
+pub fn check_rings(solutions: &[PoolSolution]) -> eyre::Result<()> {
+    let mut rings = HashMap::<B256, Vec<&RingLeg>>::new();
+    solutions
+        .iter()
+        .filter_map(|solution| solution.ring.as_ref())
+        .for_each(|leg| rings.entry(leg.ring).or_default().push(leg));
+
+    for (ring, legs) in rings {
+        if legs.iter().any(|leg| leg.legs as usize != legs.len()) {
+            eyre::bail!("ring {ring:?} isn't whole")
+        }
+        // what the ring takes in and pays out of every token
+        let mut flows = HashMap::<Address, (u128, u128)>::new();
+        for leg in &legs {
+            let taken = &mut flows.entry(leg.token_in).or_default().0;
+            *taken = taken
+                .checked_add(leg.quantity_in)
+                .ok_or_else(|| eyre::eyre!("ring {ring:?} overflows"))?;
+            let paid = &mut flows.entry(leg.token_out).or_default().1;
+            *paid = paid
+                .checked_add(leg.quantity_out)
+                .ok_or_else(|| eyre::eyre!("ring {ring:?} overflows"))?;
+        }
+        if let Some(token) = flows.iter().find(|(_, (taken, paid))| taken != paid) {
+            eyre::bail!("ring {ring:?} doesn't close in {:?}", token.0)
+        }
+    }
+
+    Ok(())
+}
+
+fn leg_is_filled(solution: &PoolSolution) -> bool {
+    solution.ring.as_ref().is_some_and(|leg| {
+        solution
+            .limit
+            .iter()
+            .any(|outcome| outcome.id.hash == leg.order && outcome.is_filled())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        matching::Ray,
+        orders::{OrderId, OrderOutcome}
+    };
+
+    fn leg(ring: B256, order: B256) -> PoolSolution {
+        PoolSolution {
+            id: B256::random(),
+            ucp: Ray::from(alloy::primitives::U256::from(1)),
+            limit: vec![OrderOutcome {
+                id:      OrderId { hash: order, ..Default::default() },
+                outcome: OrderFillState::CompleteFill
+            }],
+            ring: Some(RingLeg { ring, legs: 3, order, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn whole_rings_are_kept() {
+        let orders = [B256::random(), B256::random(), B256::random()];
+        let ring = RingLeg::ring_id(&orders);
+        let mut solutions = orders.map(|order| leg(ring, order)).to_vec();
+        let expected = solutions.clone();
+
+        unwind_open_rings(&mut solutions);
+        assert_eq!(solutions, expected);
+    }
+
+    #[test]
+    fn rings_have_to_close() {
+        let orders = [B256::random(), B256::random(), B256::random()];
+        let ring = RingLeg::ring_id(&orders);
+        let token = Address::repeat_byte;
+        let flows = [(1, 2, 10, 9), (2, 3, 9, 8), (3, 1, 8, 10)];
+        let mut solutions = orders.map(|order| leg(ring, order)).to_vec();
+        for (solution, (token_in, token_out, quantity_in, quantity_out)) in
+            solutions.iter_mut().zip(flows)
+        {
+            let leg = solution.ring.as_mut().unwrap();
+            (leg.token_in, leg.token_out) = (token(token_in), token(token_out));
+            (leg.quantity_in, leg.quantity_out) = (quantity_in, quantity_out);
+        }
+        assert!(check_rings(&solutions).is_ok());
+
+        let mut overpaid = solutions.clone();
+        overpaid[2].ring.as_mut().unwrap().quantity_out += 1;
+        assert!(check_rings(&overpaid).is_err());
+        assert!(check_rings(&solutions[..2]).is_err());
+    }
+
+    #[test]
+    fn open_rings_are_unwound() {
+        let orders = [B256::random(), B256::random(), B256::random()];
+        let ring = RingLeg::ring_id(&orders);
+        let mut solutions = orders.map(|order| leg(ring, order)).to_vec();
+        // halted by the circuit breaker
+        solutions[1].limit[0].outcome = OrderFillState::Unfilled;
+
+        unwind_open_rings(&mut solutions);
+        for solution in solutions {
+            assert!(solution.ring.is_none());
+            assert!(!solution.limit[0].is_filled());
+        }
+    }
+}
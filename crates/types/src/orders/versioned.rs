@@ -0,0 +1,110 @@
+//! Versioned order envelopes
+//!
+//! Nodes running different releases gossip orders to each other. Every layout
+//! of [`AllOrders`] that has been sent over the wire gets a version and a
+//! migration into the next layout, so a node can always ingest orders that
+//! were encoded by an older peer.
+//!
+//! When the layout of a order type changes:
+//! 1. freeze the current types in a `vN` module and bump
+//!    [`CURRENT_ORDER_VERSION`]
+//! 2. add a `migrate_vN` function that converts them into the new layout
+//! 3. handle the version in [`OrderEnvelope::open`]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::sol_bindings::grouped_orders::AllOrders;
+
+/// Orders that were gossiped before envelopes were introduced.
+pub const LEGACY_ORDER_VERSION: u16 = 0;
+/// Layout of [`AllOrders`] produced by this release.
+pub const CURRENT_ORDER_VERSION: u16 = 1;
+
+/// v0 is the layout orders had when they were gossiped without a envelope.
+mod v0 {
+    pub type AllOrders = crate::sol_bindings::grouped_orders::AllOrders;
+}
+
+/// The layout didn't change between v0 and v1, only the envelope was added.
+fn migrate_v0(order: v0::AllOrders) -> AllOrders {
+    order
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OrderMigrationError {
+    #[error("order version {0} is newer than the supported version {CURRENT_ORDER_VERSION}")]
+    UnsupportedVersion(u16),
+    #[error("failed to decode v{version} order: {reason}")]
+    Decode { version: u16, reason: String }
+}
+
+/// A order tagged with the version of the layout it was encoded with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderEnvelope {
+    pub version: u16,
+    /// bincode encoded order in the layout of `version`
+    pub payload: Vec<u8>
+}
+
+impl OrderEnvelope {
+    pub fn new(order: &AllOrders) -> Self {
+        Self {
+            version: CURRENT_ORDER_VERSION,
+            payload: bincode::serialize(order).expect("orders always serialize")
+        }
+    }
+
+    /// Decodes the order and migrates it to the current layout.
+    pub fn open(&self) -> Result<AllOrders, OrderMigrationError> {
+        match self.version {
+            LEGACY_ORDER_VERSION => self.decode::<v0::AllOrders>().map(migrate_v0),
+            CURRENT_ORDER_VERSION => self.decode::<AllOrders>(),
+            version => Err(OrderMigrationError::UnsupportedVersion(version))
+        }
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self) -> Result<T, OrderMigrationError> {
+        bincode::deserialize(&self.payload).map_err(|e| OrderMigrationError::Decode {
+            version: self.version,
+            reason:  e.to_string()
+        })
+    }
+}
+
+impl From<&AllOrders> for OrderEnvelope {
+    fn from(order: &AllOrders) -> Self {
+        Self::new(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sol_bindings::grouped_orders::StandingVariants;
+
+    #[test]
+    fn opens_current_and_legacy_orders() {
+        let order = AllOrders::Standing(StandingVariants::Partial(Default::default()));
+
+        let envelope = OrderEnvelope::new(&order);
+        assert_eq!(envelope.open().unwrap(), order);
+
+        let legacy = OrderEnvelope {
+            version: LEGACY_ORDER_VERSION,
+            payload: bincode::serialize(&order).unwrap()
+        };
+        assert_eq!(legacy.open().unwrap(), order);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let envelope = OrderEnvelope { version: CURRENT_ORDER_VERSION + 1, payload: vec![] };
+        assert_eq!(
+            envelope.open(),
+            Err(OrderMigrationError::UnsupportedVersion(CURRENT_ORDER_VERSION + 1))
+        );
+
+        let garbage = OrderEnvelope { version: CURRENT_ORDER_VERSION, payload: vec![9; 3] };
+        assert!(matches!(garbage.open(), Err(OrderMigrationError::Decode { version: 1, .. })));
+    }
+}
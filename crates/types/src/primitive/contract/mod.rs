@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 use alloy::{
     dyn_abi::Eip712Domain,
-    primitives::{aliases::U24, Address},
+    primitives::{aliases::U24, Address, U256},
     sol,
     sol_types::eip712_domain
 };
 
-use crate::contract_bindings::angstrom::Angstrom::PoolKey;
+use crate::{contract_bindings::angstrom::Angstrom::PoolKey, matching::PoolMatchingConfig};
 
 sol! {
 #![sol(all_derives = true)]
@@ -30,10 +30,25 @@ pub const ANGSTROM_DOMAIN: Eip712Domain = eip712_domain!(
     verifying_contract: TESTNET_ANGSTROM_ADDRESS,
 );
 
+/// The domain orders for the angstrom contract at `angstrom_address` on chain
+/// `chain_id` are signed in. The contract only accepts orders signed for it, so
+/// validating against it keeps orders of other chains or deployments out.
+pub fn angstrom_domain(chain_id: u64, angstrom_address: Address) -> Eip712Domain {
+    Eip712Domain::new(
+        Some(Cow::Borrowed("Angstrom")),
+        Some(Cow::Borrowed("v1")),
+        Some(U256::from(chain_id)),
+        Some(angstrom_address),
+        None
+    )
+}
+
 #[derive(Default, Clone)]
 pub struct UniswapPoolRegistry {
     pools:              HashMap<PoolId, PoolKey>,
-    pub conversion_map: HashMap<PoolId, PoolId>
+    pub conversion_map: HashMap<PoolId, PoolId>,
+    /// pools that aren't matched with the default config
+    matching:           HashMap<PoolId, PoolMatchingConfig>
 }
 impl UniswapPoolRegistry {
     pub fn get(&self, pool_id: &PoolId) -> Option<&PoolKey> {
@@ -43,6 +58,41 @@ impl UniswapPoolRegistry {
     pub fn pools(&self) -> HashMap<PoolId, PoolKey> {
         self.pools.clone()
     }
+
+    /// Matches the pool with `config`, configs of pools that aren't in the
+    /// registry are ignored.
+    pub fn with_matching_config(mut self, pool_id: PoolId, config: PoolMatchingConfig) -> Self {
+        if self.pools.contains_key(&pool_id) {
+            self.matching.insert(pool_id, config);
+        }
+        self
+    }
+
+    pub fn matching_config(&self, pool_id: &PoolId) -> PoolMatchingConfig {
+        self.matching.get(pool_id).copied().unwrap_or_default()
+    }
+
+    pub fn matching_configs(&self) -> HashMap<PoolId, PoolMatchingConfig> {
+        self.matching.clone()
+    }
+
+    /// Keys the pool `previous` was registered under by `pool` instead, for
+    /// pools whose parameters changed on chain. The matching config of the
+    /// pool is kept. Returns the id of the pool under its new key.
+    pub fn update_pool(&mut self, previous: &PoolId, pool: PoolKey) -> PoolId {
+        let pool_id = PoolId::from(pool.clone());
+        let private_id = PoolId::from(PoolKey { fee: U24::from(0x800000), ..pool.clone() });
+
+        self.pools.remove(previous);
+        self.conversion_map.remove(previous);
+        if let Some(config) = self.matching.remove(previous) {
+            self.matching.insert(pool_id, config);
+        }
+        self.pools.insert(pool_id, pool);
+        self.conversion_map.insert(pool_id, private_id);
+
+        pool_id
+    }
 }
 impl From<Vec<PoolKey>> for UniswapPoolRegistry {
     fn from(pools: Vec<PoolKey>) -> Self {
@@ -63,6 +113,6 @@ impl From<Vec<PoolKey>> for UniswapPoolRegistry {
                 (pool_id_pub, pool_id_priv)
             })
             .collect();
-        Self { pools: pubmap, conversion_map: priv_map }
+        Self { pools: pubmap, conversion_map: priv_map, matching: HashMap::new() }
     }
 }
@@ -0,0 +1,189 @@
+//! Which Uniswap V4 hooks the pools we match can have.
+//!
+//! A V4 hook declares the callbacks it implements in the lowest bits of its
+//! address. The synced pools replay swaps with plain concentrated liquidity
+//! math, so a hook that changes what a swap pays out would have every solution
+//! of its pool built on the wrong amounts. Pools are checked against the
+//! [`HookPolicy`] of the [`ProtocolGenesis`](crate::consensus::ProtocolGenesis)
+//! when they are onboarded and refused if their hook doesn't pass it, so every
+//! validator matches the same pools.
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::contract_bindings::angstrom::Angstrom::PoolKey;
+
+/// A callback a V4 hook can implement, as named in `Hooks.sol`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookPermission {
+    BeforeInitialize,
+    AfterInitialize,
+    BeforeAddLiquidity,
+    AfterAddLiquidity,
+    BeforeRemoveLiquidity,
+    AfterRemoveLiquidity,
+    BeforeSwap,
+    AfterSwap,
+    BeforeDonate,
+    AfterDonate,
+    BeforeSwapReturnsDelta,
+    AfterSwapReturnsDelta,
+    AfterAddLiquidityReturnsDelta,
+    AfterRemoveLiquidityReturnsDelta
+}
+
+impl HookPermission {
+    pub const ALL: [Self; 14] = [
+        Self::BeforeInitialize,
+        Self::AfterInitialize,
+        Self::BeforeAddLiquidity,
+        Self::AfterAddLiquidity,
+        Self::BeforeRemoveLiquidity,
+        Self::AfterRemoveLiquidity,
+        Self::BeforeSwap,
+        Self::AfterSwap,
+        Self::BeforeDonate,
+        Self::AfterDonate,
+        Self::BeforeSwapReturnsDelta,
+        Self::AfterSwapReturnsDelta,
+        Self::AfterAddLiquidityReturnsDelta,
+        Self::AfterRemoveLiquidityReturnsDelta
+    ];
+
+    /// Bit of the hook address that declares the permission.
+    pub fn flag(self) -> u16 {
+        1 << (13 - self as u16)
+    }
+
+    /// The permissions the hook at `hooks` declares.
+    pub fn of(hooks: Address) -> impl Iterator<Item = Self> {
+        let bits = u16::from_be_bytes([hooks[18], hooks[19]]);
+        Self::ALL
+            .into_iter()
+            .filter(move |permission| bits & permission.flag() != 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HookRejection {
+    #[error("hook {0} is denied")]
+    Denied(Address),
+    #[error("hook {hooks} implements {permission:?}")]
+    RefusedPermission { hooks: Address, permission: HookPermission }
+}
+
+/// The hooks pools are matched with. Denied hooks are always refused, allowed
+/// ones always admitted and every other hook is refused if it declares any
+/// of the refused permissions. Pools without a hook declare none.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HookPolicy {
+    /// hooks that are admitted whatever they implement
+    pub allowed:             Vec<Address>,
+    pub denied:              Vec<Address>,
+    pub refused_permissions: Vec<HookPermission>
+}
+
+impl Default for HookPolicy {
+    /// Refuses the hooks that can move the price of a swap or change what it
+    /// pays out.
+    fn default() -> Self {
+        Self {
+            allowed:             vec![],
+            denied:              vec![],
+            refused_permissions: vec![
+                HookPermission::BeforeSwap,
+                HookPermission::BeforeSwapReturnsDelta,
+                HookPermission::AfterSwapReturnsDelta,
+                HookPermission::AfterAddLiquidityReturnsDelta,
+                HookPermission::AfterRemoveLiquidityReturnsDelta,
+            ]
+        }
+    }
+}
+
+impl HookPolicy {
+    pub fn with_allowed(mut self, hooks: Address) -> Self {
+        if !self.allowed.contains(&hooks) {
+            self.allowed.push(hooks);
+        }
+        self
+    }
+
+    pub fn check(&self, hooks: Address) -> Result<(), HookRejection> {
+        if self.denied.contains(&hooks) {
+            return Err(HookRejection::Denied(hooks))
+        }
+        if self.allowed.contains(&hooks) {
+            return Ok(())
+        }
+
+        match HookPermission::of(hooks)
+            .find(|permission| self.refused_permissions.contains(permission))
+        {
+            Some(permission) => Err(HookRejection::RefusedPermission { hooks, permission }),
+            None => Ok(())
+        }
+    }
+
+    /// The pools whose hook passes the policy, logging the refused ones.
+    pub fn admit(&self, pools: Vec<PoolKey>) -> Vec<PoolKey> {
+        pools
+            .into_iter()
+            .filter(|pool| match self.check(pool.hooks) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!(
+                        currency0 = ?pool.currency0,
+                        currency1 = ?pool.currency1,
+                        %e,
+                        "refusing pool with incompatible hook"
+                    );
+                    false
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    /// A hook declaring before swap and after swap returns delta.
+    const DELTA_HOOK: Address = address!("0000000000000000000000000000000000000084");
+    /// A hook declaring after initialize and after swap.
+    const PASSIVE_HOOK: Address = address!("0000000000000000000000000000000000001040");
+
+    #[test]
+    fn reads_the_permissions_off_the_address() {
+        assert_eq!(
+            HookPermission::of(DELTA_HOOK).collect::<Vec<_>>(),
+            vec![HookPermission::BeforeSwap, HookPermission::AfterSwapReturnsDelta]
+        );
+        assert_eq!(HookPermission::of(Address::ZERO).count(), 0);
+    }
+
+    #[test]
+    fn refuses_hooks_that_change_swaps_unless_allowed() {
+        let policy = HookPolicy::default();
+        assert_eq!(
+            policy.check(DELTA_HOOK),
+            Err(HookRejection::RefusedPermission {
+                hooks:      DELTA_HOOK,
+                permission: HookPermission::BeforeSwap
+            })
+        );
+        assert_eq!(policy.check(PASSIVE_HOOK), Ok(()));
+        assert_eq!(policy.check(Address::ZERO), Ok(()));
+
+        let policy = policy.with_allowed(DELTA_HOOK);
+        assert_eq!(policy.check(DELTA_HOOK), Ok(()));
+
+        // denying wins over allowing
+        let policy = HookPolicy { denied: vec![DELTA_HOOK], ..policy };
+        assert_eq!(policy.check(DELTA_HOOK), Err(HookRejection::Denied(DELTA_HOOK)));
+    }
+}
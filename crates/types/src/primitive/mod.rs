@@ -1,11 +1,20 @@
+use std::time::Duration;
+
 mod contract;
+mod hooks;
 mod peers;
 mod pool_state;
 mod signer;
+mod signing_domain;
 mod validation;
 
 pub use contract::*;
+pub use hooks::*;
 pub use peers::*;
 pub use pool_state::*;
 pub use signer::*;
+pub use signing_domain::*;
 pub use validation::*;
+
+/// Time between two blocks of the chain.
+pub const BLOCK_TIME: Duration = Duration::from_secs(12);
@@ -0,0 +1,241 @@
+use alloy::primitives::{keccak256, B256};
+
+use crate::{consensus::canonical::encode_header, primitive::ANGSTROM_DOMAIN};
+
+/// Everything that is signed in angstrom, and what its signed bytes start
+/// with.
+///
+/// No prefix is a prefix of another one, so the bytes signed in one domain can
+/// never be read as the bytes of another and a signature can't be replayed
+/// from one kind of message to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SigningDomain {
+    /// EIP-712 typed user orders
+    Order,
+    CancelOrder,
+    CancelAllOrders,
+    /// a user pegging the price of their order to the AMM
+    PricePeg,
+    PreProposal,
+    PreProposalAggregation,
+    Proposal,
+    /// a committee member co-signing a proposal
+    ProposalShare,
+    BundleHandoff,
+    /// a validator handing its consensus messages over to a new key
+    KeyHandover,
+    /// the handshake of the strom protocol
+    Status,
+    /// a staking key vouching for the network key of its node
+    StakeBinding
+}
+
+impl SigningDomain {
+    pub const ALL: [Self; 12] = [
+        Self::Order,
+        Self::CancelOrder,
+        Self::CancelAllOrders,
+        Self::PricePeg,
+        Self::PreProposal,
+        Self::PreProposalAggregation,
+        Self::Proposal,
+        Self::ProposalShare,
+        Self::BundleHandoff,
+        Self::KeyHandover,
+        Self::Status,
+        Self::StakeBinding
+    ];
+
+    pub const fn tag(&self) -> &'static str {
+        match self {
+            Self::Order => "Order",
+            Self::CancelOrder => "CancelOrder",
+            Self::CancelAllOrders => "CancelAllOrders",
+            Self::PricePeg => "PricePeg",
+            Self::PreProposal => "pre_proposal",
+            Self::PreProposalAggregation => "pre_proposal_aggregation",
+            Self::Proposal => "proposal",
+            Self::ProposalShare => "proposal_share",
+            Self::BundleHandoff => "bundle_handoff",
+            Self::KeyHandover => "key_handover",
+            Self::Status => "Status",
+            Self::StakeBinding => "StakeBinding"
+        }
+    }
+
+    /// Bytes every message signed in this domain starts with.
+    ///
+    /// Orders follow EIP-712, consensus messages start with the header of
+    /// their canonical encoding and the rest with the hash of their tag, as
+    /// the first word of their abi encoding.
+    pub fn prefix(&self) -> Vec<u8> {
+        match self {
+            Self::Order => [&[0x19, 0x01], ANGSTROM_DOMAIN.hash_struct().as_slice()].concat(),
+            Self::PreProposal
+            | Self::PreProposalAggregation
+            | Self::Proposal
+            | Self::ProposalShare
+            | Self::BundleHandoff
+            | Self::KeyHandover => {
+                let mut out = Vec::new();
+                encode_header(self.tag(), &mut out);
+                out
+            }
+            Self::CancelOrder
+            | Self::CancelAllOrders
+            | Self::PricePeg
+            | Self::Status
+            | Self::StakeBinding => keccak256(self.tag()).to_vec()
+        }
+    }
+
+    /// Hash that is signed for `message` in this domain.
+    pub fn signing_hash(&self, message: &[u8]) -> B256 {
+        keccak256([self.prefix().as_slice(), message].concat())
+    }
+
+    /// The domain the signed bytes belong to.
+    pub fn of(signed: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|domain| signed.starts_with(&domain.prefix()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Address, U256},
+        signers::SignerSync
+    };
+    use pade::PadeEncode;
+
+    use super::*;
+    use crate::{
+        consensus::{CanonicalEncoding, PreProposal, PreProposalAggregation},
+        orders::{CancelAllOrdersRequest, CancelOrderRequest, LEGACY_CANCEL_DIGEST_SUNSET},
+        primitive::{angstrom_domain, AngstromSigner, TESTNET_ANGSTROM_ADDRESS},
+        sol_bindings::{
+            rpc_orders::{ExactFlashOrder, OmitOrderMeta, OrderMeta},
+            RawPoolOrder
+        }
+    };
+
+    #[test]
+    fn prefixes_are_prefix_free() {
+        for domain in SigningDomain::ALL {
+            for other in SigningDomain::ALL
+                .into_iter()
+                .filter(|other| *other != domain)
+            {
+                assert!(
+                    !domain.prefix().starts_with(&other.prefix()),
+                    "{domain:?} messages start like {other:?} messages"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_message_hashes_differently_per_domain() {
+        let message = [7u8; 96];
+        for domain in SigningDomain::ALL {
+            let signed = [domain.prefix(), message.to_vec()].concat();
+            assert_eq!(SigningDomain::of(&signed), Some(domain));
+
+            for other in SigningDomain::ALL
+                .into_iter()
+                .filter(|other| *other != domain)
+            {
+                assert_ne!(domain.signing_hash(&message), other.signing_hash(&message));
+            }
+        }
+    }
+
+    #[test]
+    fn orders_are_signed_in_the_order_domain() {
+        let order = ExactFlashOrder { amount: 100, ..Default::default() };
+        assert_eq!(
+            order.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN),
+            SigningDomain::Order.signing_hash(order.eip712_hash_struct().as_slice())
+        );
+    }
+
+    #[test]
+    fn orders_are_pinned_to_their_chain_and_contract() {
+        let signer = AngstromSigner::random();
+        let mut order = ExactFlashOrder { amount: 100, ..Default::default() };
+        let signature = signer
+            .sign_hash_sync(&order.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN))
+            .unwrap();
+        order.meta = OrderMeta {
+            isEcdsa:   true,
+            from:      signer.address(),
+            signature: signature.pade_encode().into()
+        };
+
+        assert!(order.is_valid_signature());
+        assert!(order.is_valid_signature_in(&angstrom_domain(1, TESTNET_ANGSTROM_ADDRESS)));
+        // a fork or testnet with the same contract address
+        assert!(!order.is_valid_signature_in(&angstrom_domain(11155111, TESTNET_ANGSTROM_ADDRESS)));
+        assert!(!order.is_valid_signature_in(&angstrom_domain(1, Address::random())));
+    }
+
+    #[test]
+    fn cancel_all_signature_does_not_cancel_an_order() {
+        let signer = AngstromSigner::random();
+        let user_address = signer.address();
+        let (issued_at, valid_until) = (0, u64::MAX);
+        let signature = signer
+            .sign_hash_sync(&CancelAllOrdersRequest::signing_payload(
+                user_address,
+                issued_at,
+                valid_until
+            ))
+            .unwrap();
+        let cancel_all = CancelAllOrdersRequest { signature, user_address, issued_at, valid_until };
+        assert!(cancel_all.is_valid_at(0));
+
+        let order_id = B256::from(U256::from(valid_until));
+        let cancel = CancelOrderRequest { signature, user_address, order_id };
+        assert!(!cancel.is_valid());
+    }
+
+    #[test]
+    fn legacy_cancel_digest_is_accepted_until_the_sunset() {
+        let signer = AngstromSigner::random();
+        let (user_address, order_id) = (signer.address(), B256::random());
+        let signature = signer
+            .sign_hash_sync(&CancelOrderRequest::legacy_signing_payload(user_address, order_id))
+            .unwrap();
+        let cancel = CancelOrderRequest { signature, user_address, order_id };
+
+        assert!(cancel.is_valid_at(LEGACY_CANCEL_DIGEST_SUNSET - 1));
+        assert!(!cancel.is_valid_at(LEGACY_CANCEL_DIGEST_SUNSET));
+
+        let signature = signer
+            .sign_hash_sync(&CancelOrderRequest::signing_payload(user_address, order_id))
+            .unwrap();
+        let cancel = CancelOrderRequest { signature, user_address, order_id };
+        assert!(cancel.is_valid_at(LEGACY_CANCEL_DIGEST_SUNSET));
+    }
+
+    #[test]
+    fn pre_proposal_signature_does_not_sign_an_aggregation() {
+        let signer = AngstromSigner::random();
+        let pre_proposal = PreProposal::generate_pre_proposal(1, &signer, vec![], vec![]);
+        assert!(pre_proposal.is_valid(&1));
+
+        let mut encoded = Vec::new();
+        pre_proposal.canonical_encode(&mut encoded);
+        assert_eq!(SigningDomain::of(&encoded), Some(SigningDomain::PreProposal));
+
+        let aggregation = PreProposalAggregation {
+            block_height:  1,
+            source:        signer.id(),
+            pre_proposals: vec![],
+            signature:     pre_proposal.signature
+        };
+        assert!(!aggregation.is_valid(&1));
+    }
+}
@@ -0,0 +1,570 @@
+use alloy::{
+    primitives::{aliases::U40, Address, Bytes, B256, U256},
+    signers::{local::PrivateKeySigner, Signature, SignerSync},
+    sol_types::Eip712Domain
+};
+use pade::PadeEncode;
+use thiserror::Error;
+
+use super::{
+    grouped_orders::{AllOrders, FlashVariants, StandingVariants},
+    rpc_orders::{
+        ExactFlashOrder, ExactStandingOrder, OmitOrderMeta, OrderMeta, PartialFlashOrder,
+        PartialStandingOrder, TopOfBlockOrder
+    }
+};
+use crate::primitive::angstrom_domain;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum OrderBuilderError {
+    #[error("the assets of the order aren't set")]
+    MissingAssets,
+    #[error("the order swaps {0:?} for itself")]
+    SameAssets(Address),
+    #[error("the order has no amount")]
+    MissingAmount,
+    #[error("the order has a zero amount")]
+    ZeroAmount,
+    #[error("a {kind:?} order can't be sized by {amount}")]
+    AmountMismatch { kind: OrderKind, amount: &'static str },
+    #[error("the minimum amount in of a partial order is above its maximum")]
+    InvalidPartialAmounts,
+    #[error("a standing order needs a deadline")]
+    MissingDeadline,
+    #[error("a {0:?} order needs the block it's valid for")]
+    MissingBlock(OrderKind)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderKind {
+    /// rests in the book until its deadline
+    #[default]
+    Standing,
+    /// only valid for a single block
+    Flash,
+    TopOfBlock
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OrderAmount {
+    ExactIn(u128),
+    ExactOut(u128),
+    Partial { min_amount_in: u128, max_amount_in: u128 },
+    Quantities { quantity_in: u128, quantity_out: u128 }
+}
+
+impl OrderAmount {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::ExactIn(_) => "an exact amount in",
+            Self::ExactOut(_) => "an exact amount out",
+            Self::Partial { .. } => "a range of amounts in",
+            Self::Quantities { .. } => "quantities in and out"
+        }
+    }
+
+    /// Whether the order can't move anything. A partial order can still fill
+    /// from nothing, as long as it can fill something.
+    fn is_zero(&self) -> bool {
+        match *self {
+            Self::ExactIn(amount) | Self::ExactOut(amount) => amount == 0,
+            Self::Partial { max_amount_in, .. } => max_amount_in == 0,
+            Self::Quantities { quantity_in, quantity_out } => quantity_in == 0 || quantity_out == 0
+        }
+    }
+}
+
+/// Builds orders to be signed for the angstrom contract at an address on a
+/// chain, orders are only valid for the deployment they're signed for.
+///
+/// Orders are standing unless their kind is set. Fields that aren't set keep
+/// the defaults of the contract: no recipient means the signer, no hook, no
+/// fee and angstrom balances aren't used.
+///
+/// ```ignore
+/// let order = OrderBuilder::new(chain_id, angstrom_address)
+///     .assets(asset_in, asset_out)
+///     .exact_in(1_000)
+///     .min_price(price)
+///     .nonce(nonce)
+///     .deadline(deadline)
+///     .build()?
+///     .sign(&signer)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBuilder {
+    kind:            OrderKind,
+    domain:          Eip712Domain,
+    asset_in:        Option<Address>,
+    asset_out:       Option<Address>,
+    amount:          Option<OrderAmount>,
+    min_price:       U256,
+    recipient:       Address,
+    /// the extra fee of user orders, the gas of top of block orders
+    max_fee_asset0:  u128,
+    use_internal:    bool,
+    hook_data:       Bytes,
+    ref_id:          u32,
+    nonce:           u64,
+    deadline:        Option<u64>,
+    valid_for_block: Option<u64>
+}
+
+impl OrderBuilder {
+    /// Builds an order for the angstrom contract at `angstrom_address` on
+    /// `chain_id`.
+    pub fn new(chain_id: u64, angstrom_address: Address) -> Self {
+        Self {
+            kind:            OrderKind::Standing,
+            domain:          angstrom_domain(chain_id, angstrom_address),
+            asset_in:        None,
+            asset_out:       None,
+            amount:          None,
+            min_price:       U256::ZERO,
+            recipient:       Address::ZERO,
+            max_fee_asset0:  0,
+            use_internal:    false,
+            hook_data:       Bytes::new(),
+            ref_id:          0,
+            nonce:           0,
+            deadline:        None,
+            valid_for_block: None
+        }
+    }
+
+    pub fn standing(mut self) -> Self {
+        self.kind = OrderKind::Standing;
+        self
+    }
+
+    pub fn flash(mut self, valid_for_block: u64) -> Self {
+        self.kind = OrderKind::Flash;
+        self.valid_for_block(valid_for_block)
+    }
+
+    pub fn top_of_block(mut self, valid_for_block: u64) -> Self {
+        self.kind = OrderKind::TopOfBlock;
+        self.valid_for_block(valid_for_block)
+    }
+
+    pub fn assets(mut self, asset_in: Address, asset_out: Address) -> Self {
+        self.asset_in = Some(asset_in);
+        self.asset_out = Some(asset_out);
+        self
+    }
+
+    /// Puts in exactly `amount` of the asset in, not for top of block orders.
+    pub fn exact_in(mut self, amount: u128) -> Self {
+        self.amount = Some(OrderAmount::ExactIn(amount));
+        self
+    }
+
+    /// Takes out exactly `amount` of the asset out, not for top of block
+    /// orders.
+    pub fn exact_out(mut self, amount: u128) -> Self {
+        self.amount = Some(OrderAmount::ExactOut(amount));
+        self
+    }
+
+    /// Fills anywhere between `min_amount_in` and `max_amount_in` of the asset
+    /// in, not for top of block orders.
+    pub fn partial(mut self, min_amount_in: u128, max_amount_in: u128) -> Self {
+        self.amount = Some(OrderAmount::Partial { min_amount_in, max_amount_in });
+        self
+    }
+
+    /// The quantities of a top of block order.
+    pub fn quantities(mut self, quantity_in: u128, quantity_out: u128) -> Self {
+        self.amount = Some(OrderAmount::Quantities { quantity_in, quantity_out });
+        self
+    }
+
+    /// The limit price of a user order, as a ray of asset1 per asset0.
+    pub fn min_price(mut self, min_price: U256) -> Self {
+        self.min_price = min_price;
+        self
+    }
+
+    pub fn recipient(mut self, recipient: Address) -> Self {
+        self.recipient = recipient;
+        self
+    }
+
+    /// The most of asset0 the order pays in fees, its gas for a top of block
+    /// order.
+    pub fn max_fee_asset0(mut self, max_fee_asset0: u128) -> Self {
+        self.max_fee_asset0 = max_fee_asset0;
+        self
+    }
+
+    pub fn use_internal(mut self, use_internal: bool) -> Self {
+        self.use_internal = use_internal;
+        self
+    }
+
+    pub fn hook_data(mut self, hook_data: Bytes) -> Self {
+        self.hook_data = hook_data;
+        self
+    }
+
+    pub fn ref_id(mut self, ref_id: u32) -> Self {
+        self.ref_id = ref_id;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Unix timestamp (seconds) the standing order expires at.
+    pub fn deadline(mut self, deadline: u64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn valid_for_block(mut self, valid_for_block: u64) -> Self {
+        self.valid_for_block = Some(valid_for_block);
+        self
+    }
+
+    pub fn build(self) -> Result<UnsignedOrder, OrderBuilderError> {
+        let (Some(asset_in), Some(asset_out)) = (self.asset_in, self.asset_out) else {
+            return Err(OrderBuilderError::MissingAssets)
+        };
+        if asset_in == asset_out {
+            return Err(OrderBuilderError::SameAssets(asset_in))
+        }
+        let amount = self.amount.ok_or(OrderBuilderError::MissingAmount)?;
+        if amount.is_zero() {
+            return Err(OrderBuilderError::ZeroAmount)
+        }
+        if let OrderAmount::Partial { min_amount_in, max_amount_in } = amount {
+            if min_amount_in > max_amount_in {
+                return Err(OrderBuilderError::InvalidPartialAmounts)
+            }
+        }
+        let mismatch =
+            || OrderBuilderError::AmountMismatch { kind: self.kind, amount: amount.name() };
+
+        let order = match self.kind {
+            OrderKind::Standing => {
+                let deadline =
+                    U40::saturating_from(self.deadline.ok_or(OrderBuilderError::MissingDeadline)?);
+                match amount {
+                    OrderAmount::ExactIn(amount) | OrderAmount::ExactOut(amount) => {
+                        AllOrders::Standing(StandingVariants::Exact(ExactStandingOrder {
+                            ref_id: self.ref_id,
+                            exact_in: matches!(self.amount, Some(OrderAmount::ExactIn(_))),
+                            amount,
+                            max_extra_fee_asset0: self.max_fee_asset0,
+                            min_price: self.min_price,
+                            use_internal: self.use_internal,
+                            asset_in,
+                            asset_out,
+                            recipient: self.recipient,
+                            hook_data: self.hook_data.clone(),
+                            nonce: self.nonce,
+                            deadline,
+                            meta: OrderMeta::default()
+                        }))
+                    }
+                    OrderAmount::Partial { min_amount_in, max_amount_in } => {
+                        AllOrders::Standing(StandingVariants::Partial(PartialStandingOrder {
+                            ref_id: self.ref_id,
+                            min_amount_in,
+                            max_amount_in,
+                            max_extra_fee_asset0: self.max_fee_asset0,
+                            min_price: self.min_price,
+                            use_internal: self.use_internal,
+                            asset_in,
+                            asset_out,
+                            recipient: self.recipient,
+                            hook_data: self.hook_data.clone(),
+                            nonce: self.nonce,
+                            deadline,
+                            meta: OrderMeta::default()
+                        }))
+                    }
+                    OrderAmount::Quantities { .. } => return Err(mismatch())
+                }
+            }
+            OrderKind::Flash => {
+                let valid_for_block = self
+                    .valid_for_block
+                    .ok_or(OrderBuilderError::MissingBlock(self.kind))?;
+                match amount {
+                    OrderAmount::ExactIn(amount) | OrderAmount::ExactOut(amount) => {
+                        AllOrders::Flash(FlashVariants::Exact(ExactFlashOrder {
+                            ref_id: self.ref_id,
+                            exact_in: matches!(self.amount, Some(OrderAmount::ExactIn(_))),
+                            amount,
+                            max_extra_fee_asset0: self.max_fee_asset0,
+                            min_price: self.min_price,
+                            use_internal: self.use_internal,
+                            asset_in,
+                            asset_out,
+                            recipient: self.recipient,
+                            hook_data: self.hook_data.clone(),
+                            valid_for_block,
+                            meta: OrderMeta::default()
+                        }))
+                    }
+                    OrderAmount::Partial { min_amount_in, max_amount_in } => {
+                        AllOrders::Flash(FlashVariants::Partial(PartialFlashOrder {
+                            ref_id: self.ref_id,
+                            min_amount_in,
+                            max_amount_in,
+                            max_extra_fee_asset0: self.max_fee_asset0,
+                            min_price: self.min_price,
+                            use_internal: self.use_internal,
+                            asset_in,
+                            asset_out,
+                            recipient: self.recipient,
+                            hook_data: self.hook_data.clone(),
+                            valid_for_block,
+                            meta: OrderMeta::default()
+                        }))
+                    }
+                    OrderAmount::Quantities { .. } => return Err(mismatch())
+                }
+            }
+            OrderKind::TopOfBlock => {
+                let valid_for_block = self
+                    .valid_for_block
+                    .ok_or(OrderBuilderError::MissingBlock(self.kind))?;
+                let OrderAmount::Quantities { quantity_in, quantity_out } = amount else {
+                    return Err(mismatch())
+                };
+                AllOrders::TOB(TopOfBlockOrder {
+                    quantity_in,
+                    quantity_out,
+                    max_gas_asset0: self.max_fee_asset0,
+                    use_internal: self.use_internal,
+                    asset_in,
+                    asset_out,
+                    recipient: self.recipient,
+                    valid_for_block,
+                    meta: OrderMeta::default()
+                })
+            }
+        };
+
+        Ok(UnsignedOrder { order, domain: self.domain })
+    }
+}
+
+/// An order that's built but not signed yet, along with the domain it's to
+/// be signed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedOrder {
+    order:  AllOrders,
+    domain: Eip712Domain
+}
+
+impl UnsignedOrder {
+    pub fn order(&self) -> &AllOrders {
+        &self.order
+    }
+
+    pub fn domain(&self) -> &Eip712Domain {
+        &self.domain
+    }
+
+    /// The EIP-712 hash the order is signed over, without its meta.
+    pub fn signing_hash(&self) -> B256 {
+        self.order.signing_hash(&self.domain)
+    }
+
+    /// The order signed by `from`, with a signature over the
+    /// [`signing_hash`](Self::signing_hash) made elsewhere, e.g. by a wallet.
+    pub fn with_signature(self, from: Address, signature: Signature) -> AllOrders {
+        let mut order = self.order;
+        order.set_signature(from, signature);
+        order
+    }
+
+    /// Signs the order with a local signer.
+    pub fn sign(self, signer: &PrivateKeySigner) -> Result<AllOrders, alloy::signers::Error> {
+        let mut order = self.order;
+        order.sign_in(signer, &self.domain)?;
+        Ok(order)
+    }
+}
+
+/// Signs orders the way the angstrom contract verifies them.
+///
+/// The order is signed over its EIP-712 hash without the meta, the signer, its
+/// address and the signature are then written into the meta of the order.
+pub trait SignOrder {
+    /// The EIP-712 hash the order is signed over in the domain, without its
+    /// meta.
+    fn signing_hash(&self, domain: &Eip712Domain) -> B256;
+
+    /// Writes the signature of `from` over the
+    /// [`signing_hash`](Self::signing_hash) into the meta of the order.
+    fn set_signature(&mut self, from: Address, signature: Signature);
+
+    /// Signs the order with a local signer for the angstrom contract of the
+    /// domain.
+    fn sign_in(
+        &mut self,
+        signer: &PrivateKeySigner,
+        domain: &Eip712Domain
+    ) -> Result<(), alloy::signers::Error> {
+        let signature = signer.sign_hash_sync(&self.signing_hash(domain))?;
+        self.set_signature(signer.address(), signature);
+        Ok(())
+    }
+}
+
+macro_rules! sign_order {
+    ($($order:ty),*) => {
+        $(
+            impl SignOrder for $order {
+                fn signing_hash(&self, domain: &Eip712Domain) -> B256 {
+                    self.no_meta_eip712_signing_hash(domain)
+                }
+
+                fn set_signature(&mut self, from: Address, signature: Signature) {
+                    self.meta =
+                        OrderMeta { isEcdsa: true, from, signature: signature.pade_encode().into() };
+                }
+            }
+        )*
+    };
+}
+
+sign_order!(
+    ExactStandingOrder,
+    PartialStandingOrder,
+    ExactFlashOrder,
+    PartialFlashOrder,
+    TopOfBlockOrder
+);
+
+impl SignOrder for AllOrders {
+    fn signing_hash(&self, domain: &Eip712Domain) -> B256 {
+        match self {
+            AllOrders::Standing(StandingVariants::Exact(order)) => order.signing_hash(domain),
+            AllOrders::Standing(StandingVariants::Partial(order)) => order.signing_hash(domain),
+            AllOrders::Flash(FlashVariants::Exact(order)) => order.signing_hash(domain),
+            AllOrders::Flash(FlashVariants::Partial(order)) => order.signing_hash(domain),
+            AllOrders::TOB(order) => order.signing_hash(domain)
+        }
+    }
+
+    fn set_signature(&mut self, from: Address, signature: Signature) {
+        match self {
+            AllOrders::Standing(StandingVariants::Exact(order)) => {
+                order.set_signature(from, signature)
+            }
+            AllOrders::Standing(StandingVariants::Partial(order)) => {
+                order.set_signature(from, signature)
+            }
+            AllOrders::Flash(FlashVariants::Exact(order)) => order.set_signature(from, signature),
+            AllOrders::Flash(FlashVariants::Partial(order)) => order.set_signature(from, signature),
+            AllOrders::TOB(order) => order.set_signature(from, signature)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitive::{AngstromSigner, TESTNET_ANGSTROM_ADDRESS},
+        sol_bindings::RawPoolOrder
+    };
+
+    #[test]
+    fn builds_every_kind_of_order() {
+        let (asset_in, asset_out) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let standing = OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+            .assets(asset_in, asset_out)
+            .exact_out(100)
+            .deadline(10)
+            .build()
+            .unwrap();
+        let AllOrders::Standing(StandingVariants::Exact(order)) = standing.order() else {
+            panic!("not an exact standing order")
+        };
+        assert!(!order.exact_in);
+        assert_eq!(order.amount, 100);
+
+        let flash = OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+            .flash(5)
+            .assets(asset_in, asset_out)
+            .partial(10, 100)
+            .build()
+            .unwrap();
+        assert!(matches!(flash.order(), AllOrders::Flash(FlashVariants::Partial(_))));
+        assert_eq!(flash.order().flash_block(), Some(5));
+
+        let tob = OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+            .top_of_block(5)
+            .assets(asset_in, asset_out)
+            .quantities(10, 20)
+            .build()
+            .unwrap();
+        assert!(matches!(tob.order(), AllOrders::TOB(_)));
+
+        assert_eq!(
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+                .top_of_block(5)
+                .assets(asset_in, asset_out)
+                .exact_in(10)
+                .build(),
+            Err(OrderBuilderError::AmountMismatch {
+                kind:   OrderKind::TopOfBlock,
+                amount: "an exact amount in"
+            })
+        );
+        assert_eq!(
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+                .assets(asset_in, asset_out)
+                .exact_in(10)
+                .build(),
+            Err(OrderBuilderError::MissingDeadline)
+        );
+        assert_eq!(
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+                .assets(asset_in, asset_in)
+                .build(),
+            Err(OrderBuilderError::SameAssets(asset_in))
+        );
+        for zero in [
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS).exact_out(0),
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS).partial(0, 0),
+            OrderBuilder::new(1, TESTNET_ANGSTROM_ADDRESS)
+                .top_of_block(5)
+                .quantities(10, 0)
+        ] {
+            assert_eq!(
+                zero.assets(asset_in, asset_out).deadline(10).build(),
+                Err(OrderBuilderError::ZeroAmount)
+            );
+        }
+    }
+
+    #[test]
+    fn signs_in_the_domain_of_the_chain() {
+        let signer = AngstromSigner::random();
+        let unsigned = OrderBuilder::new(11155111, TESTNET_ANGSTROM_ADDRESS)
+            .flash(5)
+            .assets(Address::repeat_byte(1), Address::repeat_byte(2))
+            .exact_in(100)
+            .build()
+            .unwrap();
+        let hash = unsigned.signing_hash();
+
+        let order = unsigned.clone().sign(&signer).unwrap();
+        assert_eq!(order.from(), signer.address());
+        assert!(order.is_valid_signature_in(&angstrom_domain(11155111, TESTNET_ANGSTROM_ADDRESS)));
+        assert!(!order.is_valid_signature());
+
+        // a signature made elsewhere assembles the same order
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        assert_eq!(unsigned.with_signature(signer.address(), signature), order);
+    }
+}
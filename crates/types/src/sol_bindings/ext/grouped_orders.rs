@@ -2,7 +2,8 @@ use std::{hash::Hash, ops::Deref};
 
 use alloy::{
     primitives::{Address, Bytes, FixedBytes, TxHash, U256},
-    signers::Signature
+    signers::Signature,
+    sol_types::Eip712Domain
 };
 use alloy_primitives::{PrimitiveSignature, B256};
 use pade::PadeDecode;
@@ -11,8 +12,8 @@ use serde::{Deserialize, Serialize};
 use super::{GenerateFlippedOrder, RawPoolOrder, RespendAvoidanceMethod};
 use crate::{
     matching::{Debt, Ray},
-    orders::{OrderId, OrderLocation, OrderPriorityData},
-    primitive::{PoolId, ANGSTROM_DOMAIN},
+    orders::{OrderId, OrderLocation, OrderPriorityData, PricePeg},
+    primitive::PoolId,
     sol_bindings::rpc_orders::{
         ExactFlashOrder, ExactStandingOrder, OmitOrderMeta, PartialFlashOrder,
         PartialStandingOrder, TopOfBlockOrder
@@ -128,7 +129,7 @@ impl FlashVariants {
     pub fn min_q(&self) -> u128 {
         match self {
             Self::Exact(o) => o.amount,
-            Self::Partial(o) => o.max_amount_in
+            Self::Partial(o) => o.min_amount_in
         }
     }
 
@@ -241,7 +242,24 @@ pub struct OrderWithStorageData<Order> {
     pub valid_block:        u64,
     /// holds expiry data
     pub order_id:           OrderId,
-    pub tob_reward:         U256
+    pub tob_reward:         U256,
+    /// bond tier of the searcher behind a top of block order, zero for user
+    /// orders. Decides which searchers keep their place in a full pool
+    #[serde(default)]
+    pub bond_tier:          u8,
+    /// submitted as a private order, it's never gossiped and only included by
+    /// the node that holds it when it leads the round. Local to that node, it
+    /// isn't part of the canonical encoding
+    #[serde(default)]
+    pub is_private:         bool,
+    /// a hidden tranche of an iceberg order, left out of the depth the node
+    /// reports until the tranches before it are filled. Local to the node
+    /// that holds it, it isn't part of the canonical encoding
+    #[serde(default)]
+    pub is_hidden:          bool,
+    /// prices the order off the AMM mid instead of its signed limit price
+    #[serde(default)]
+    pub peg:                Option<PricePeg>
 }
 
 impl<O: GenerateFlippedOrder> GenerateFlippedOrder for OrderWithStorageData<O> {
@@ -288,6 +306,12 @@ impl<Order> OrderWithStorageData<Order> {
         std::mem::size_of::<Order>()
     }
 
+    /// Private and pegged orders are never gossiped, so only the node that
+    /// holds them can get them into a round, the ones it leads.
+    pub fn is_leader_only(&self) -> bool {
+        self.is_private || self.peg.is_some()
+    }
+
     pub fn try_map_inner<NewOrder>(
         self,
         mut f: impl FnMut(Order) -> eyre::Result<NewOrder>
@@ -304,7 +328,11 @@ impl<Order> OrderWithStorageData<Order> {
             is_currently_valid: self.is_currently_valid,
             is_valid:           self.is_valid,
             order_id:           self.order_id,
-            tob_reward:         U256::ZERO
+            tob_reward:         U256::ZERO,
+            bond_tier:          self.bond_tier,
+            is_private:         self.is_private,
+            is_hidden:          self.is_hidden,
+            peg:                self.peg
         })
     }
 }
@@ -407,10 +435,10 @@ impl RawPoolOrder for StandingVariants {
         None
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         match self {
-            StandingVariants::Exact(e) => e.is_valid_signature(),
-            StandingVariants::Partial(p) => p.is_valid_signature()
+            StandingVariants::Exact(e) => e.is_valid_signature_in(domain),
+            StandingVariants::Partial(p) => p.is_valid_signature_in(domain)
         }
     }
 
@@ -448,10 +476,10 @@ impl RawPoolOrder for FlashVariants {
         }
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         match self {
-            FlashVariants::Exact(e) => e.is_valid_signature(),
-            FlashVariants::Partial(p) => p.is_valid_signature()
+            FlashVariants::Exact(e) => e.is_valid_signature_in(domain),
+            FlashVariants::Partial(p) => p.is_valid_signature_in(domain)
         }
     }
 
@@ -557,6 +585,15 @@ impl GroupedVanillaOrder {
         }
     }
 
+    /// The hook the order composes with followed by what it's called with,
+    /// empty if the order doesn't have one.
+    pub fn hook_data(&self) -> &Bytes {
+        match self {
+            GroupedVanillaOrder::Standing(p) => p.hook_data(),
+            GroupedVanillaOrder::KillOrFill(p) => p.hook_data()
+        }
+    }
+
     /// Primarily used for debugging to work with price as an f64
     pub fn float_price(&self) -> f64 {
         match self {
@@ -596,6 +633,19 @@ impl GroupedVanillaOrder {
         }
     }
 
+    /// The order with its literal limit price replaced. The signature no
+    /// longer covers the order, it's only used to match the order at a price
+    /// stricter than the signed one.
+    pub fn with_limit_price(mut self, price: U256) -> Self {
+        match &mut self {
+            Self::Standing(StandingVariants::Exact(o)) => o.min_price = price,
+            Self::Standing(StandingVariants::Partial(o)) => o.min_price = price,
+            Self::KillOrFill(FlashVariants::Exact(o)) => o.min_price = price,
+            Self::KillOrFill(FlashVariants::Partial(o)) => o.min_price = price
+        }
+        self
+    }
+
     /// Maximum quantity fillable by this order
     pub fn max_q(&self) -> u128 {
         match self {
@@ -604,6 +654,23 @@ impl GroupedVanillaOrder {
         }
     }
 
+    /// Minimum quantity this order has to be filled with, if it's filled at
+    /// all
+    pub fn min_q(&self) -> u128 {
+        match self {
+            Self::Standing(o) => o.min_q(),
+            Self::KillOrFill(o) => o.min_q()
+        }
+    }
+
+    /// The whole quantity of this order in terms of T0
+    pub fn quantity(&self) -> u128 {
+        match self {
+            Self::Standing(o) => o.quantity(None),
+            Self::KillOrFill(o) => o.quantity()
+        }
+    }
+
     /// Quantity filled by this order in terms of T0
     pub fn quantity_t0(&self) -> u128 {
         0
@@ -632,6 +699,14 @@ pub enum GroupedComposableOrder {
 }
 
 impl GroupedComposableOrder {
+    /// The whole quantity of this order in terms of T0
+    pub fn quantity(&self) -> u128 {
+        match self {
+            Self::Partial(o) => o.quantity(None),
+            Self::KillOrFill(o) => o.quantity()
+        }
+    }
+
     pub fn hash(&self) -> B256 {
         match self {
             Self::Partial(p) => match p {
@@ -691,9 +766,9 @@ impl RawPoolOrder for TopOfBlockOrder {
         self.asset_out
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         let Ok(sig) = self.order_signature() else { return false };
-        let hash = self.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+        let hash = self.no_meta_eip712_signing_hash(domain);
 
         sig.recover_address_from_prehash(&hash)
             .map(|addr| addr == self.meta.from)
@@ -725,12 +800,12 @@ impl RawPoolOrder for PartialStandingOrder {
         self.max_extra_fee_asset0
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         let s = self.meta.signature.to_vec();
         let mut slice = s.as_slice();
 
         let Ok(sig) = Signature::pade_decode(&mut slice, None) else { return false };
-        let hash = self.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+        let hash = self.no_meta_eip712_signing_hash(domain);
 
         sig.recover_address_from_prehash(&hash)
             .map(|addr| addr == self.meta.from)
@@ -798,12 +873,12 @@ impl RawPoolOrder for ExactStandingOrder {
         self.max_extra_fee_asset0
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         let s = self.meta.signature.to_vec();
         let mut slice = s.as_slice();
 
         let Ok(sig) = Signature::pade_decode(&mut slice, None) else { return false };
-        let hash = self.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+        let hash = self.no_meta_eip712_signing_hash(domain);
 
         sig.recover_address_from_prehash(&hash)
             .map(|addr| addr == self.meta.from)
@@ -871,12 +946,12 @@ impl RawPoolOrder for PartialFlashOrder {
         self.max_extra_fee_asset0
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         let s = self.meta.signature.to_vec();
         let mut slice = s.as_slice();
 
         let Ok(sig) = Signature::pade_decode(&mut slice, None) else { return false };
-        let hash = self.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+        let hash = self.no_meta_eip712_signing_hash(domain);
 
         sig.recover_address_from_prehash(&hash)
             .map(|addr| addr == self.meta.from)
@@ -944,12 +1019,12 @@ impl RawPoolOrder for ExactFlashOrder {
         self.max_extra_fee_asset0
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         let s = self.meta.signature.to_vec();
         let mut slice = s.as_slice();
 
         let Ok(sig) = Signature::pade_decode(&mut slice, None) else { return false };
-        let hash = self.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+        let hash = self.no_meta_eip712_signing_hash(domain);
 
         sig.recover_address_from_prehash(&hash)
             .map(|addr| addr == self.meta.from)
@@ -1025,11 +1100,11 @@ impl RawPoolOrder for AllOrders {
         }
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         match self {
-            AllOrders::Standing(p) => p.is_valid_signature(),
-            AllOrders::Flash(kof) => kof.is_valid_signature(),
-            AllOrders::TOB(tob) => tob.is_valid_signature()
+            AllOrders::Standing(p) => p.is_valid_signature_in(domain),
+            AllOrders::Flash(kof) => kof.is_valid_signature_in(domain),
+            AllOrders::TOB(tob) => tob.is_valid_signature_in(domain)
         }
     }
 
@@ -1145,10 +1220,10 @@ impl RawPoolOrder for GroupedVanillaOrder {
         }
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         match self {
-            GroupedVanillaOrder::Standing(p) => p.is_valid_signature(),
-            GroupedVanillaOrder::KillOrFill(kof) => kof.is_valid_signature()
+            GroupedVanillaOrder::Standing(p) => p.is_valid_signature_in(domain),
+            GroupedVanillaOrder::KillOrFill(kof) => kof.is_valid_signature_in(domain)
         }
     }
 
@@ -1315,10 +1390,10 @@ impl RawPoolOrder for GroupedComposableOrder {
         }
     }
 
-    fn is_valid_signature(&self) -> bool {
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool {
         match self {
-            GroupedComposableOrder::Partial(p) => p.is_valid_signature(),
-            GroupedComposableOrder::KillOrFill(kof) => kof.is_valid_signature()
+            GroupedComposableOrder::Partial(p) => p.is_valid_signature_in(domain),
+            GroupedComposableOrder::KillOrFill(kof) => kof.is_valid_signature_in(domain)
         }
     }
 
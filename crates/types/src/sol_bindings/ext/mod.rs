@@ -1,11 +1,14 @@
 //! extension functionality to sol types
 use std::fmt;
 
-use alloy::primitives::{Address, TxHash, U256};
+use alloy::{
+    primitives::{Address, TxHash, U256},
+    sol_types::Eip712Domain
+};
 use alloy_primitives::PrimitiveSignature;
 use serde::{Deserialize, Serialize};
 
-use crate::orders::OrderLocation;
+use crate::{orders::OrderLocation, primitive::ANGSTROM_DOMAIN};
 
 pub mod flips;
 pub mod grouped_orders;
@@ -46,7 +49,15 @@ pub trait RawPoolOrder: fmt::Debug + Send + Sync + Clone + Unpin + 'static {
         self.token_in() > self.token_out()
     }
 
-    fn is_valid_signature(&self) -> bool;
+    /// Whether the order is signed by its `from` for the default
+    /// [`ANGSTROM_DOMAIN`].
+    fn is_valid_signature(&self) -> bool {
+        self.is_valid_signature_in(&ANGSTROM_DOMAIN)
+    }
+
+    /// Whether the order is signed by its `from` for the angstrom contract of
+    /// the given domain. Orders signed for another chain or contract aren't.
+    fn is_valid_signature_in(&self, domain: &Eip712Domain) -> bool;
 
     fn order_location(&self) -> OrderLocation;
 
@@ -64,7 +75,7 @@ pub trait GenerateFlippedOrder: Send + Sync + Clone + Unpin + 'static {
         Self: Sized;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash, Copy)]
 pub enum RespendAvoidanceMethod {
     Nonce(u64),
     Block(u64)
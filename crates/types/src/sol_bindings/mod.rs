@@ -1,3 +1,4 @@
+pub mod builder;
 pub mod ext;
 pub mod rpc_orders;
 #[cfg(feature = "testnet")]
@@ -1,6 +1,7 @@
 use std::{
     iter::Sum,
     ops::{Add, AddAssign, Deref, Sub, SubAssign},
+    str::FromStr,
     sync::OnceLock
 };
 
@@ -8,7 +9,7 @@ use alloy::primitives::{aliases::U320, Uint, U256, U512};
 use alloy_primitives::U160;
 use malachite::{
     num::{
-        arithmetic::traits::{DivRound, Mod, Pow},
+        arithmetic::traits::{DivRound, Mod},
         conversion::traits::{RoundingInto, SaturatingFrom}
     },
     rounding_modes::RoundingMode,
@@ -18,9 +19,13 @@ use serde::{Deserialize, Serialize};
 use uniswap_v3_math::tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
 
 use crate::matching::{
-    const_1e27, const_1e54, const_2_192, uniswap::PoolPrice, MatchingPrice, SqrtPriceX96
+    const_1e27, const_1e54, const_2_192, uniswap::PoolPrice, CheckedMath, MatchingPrice, MathError,
+    SqrtPriceX96
 };
 
+/// Decimals of the fixed point of a ray.
+const RAY_DECIMALS: u8 = 27;
+
 fn max_tick_ray() -> &'static Ray {
     static MAX_TICK_PRICE: OnceLock<Ray> = OnceLock::new();
     MAX_TICK_PRICE.get_or_init(|| Ray::from(SqrtPriceX96::from(MAX_SQRT_RATIO)))
@@ -141,8 +146,13 @@ impl From<usize> for Ray {
     }
 }
 
+/// Floats round differently across architectures, so prices are only built
+/// from them in tests.
+#[cfg(any(test, feature = "float-prices"))]
 impl From<f64> for Ray {
     fn from(value: f64) -> Self {
+        use malachite::num::arithmetic::traits::Pow;
+
         Self(U256::from((value * (10.0_f64.pow(27))).floor()))
     }
 }
@@ -198,6 +208,28 @@ impl<'a> From<PoolPrice<'a>> for Ray {
     }
 }
 
+/// Parses a decimal number like `1.005` exactly, without going through a
+/// float.
+impl FromStr for Ray {
+    type Err = eyre::ErrReport;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() && frac.is_empty() {
+            eyre::bail!("{s:?} is not a decimal number")
+        }
+        if frac.len() > RAY_DECIMALS as usize {
+            eyre::bail!("{s} has more than {RAY_DECIMALS} decimals")
+        }
+        if !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+            eyre::bail!("{s:?} is not a decimal number")
+        }
+
+        let digits = format!("{int}{frac:0<width$}", width = RAY_DECIMALS as usize);
+        Ok(Ray(U256::from_str_radix(&digits, 10)?))
+    }
+}
+
 impl Serialize for Ray {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -220,6 +252,17 @@ impl<'de> Deserialize<'de> for Ray {
 impl Ray {
     pub const ZERO: Ray = Ray(U256::ZERO);
 
+    /// mantissa / 10^decimals, decimals past the 27 of a ray are truncated
+    pub fn from_decimal(mantissa: u128, decimals: u8) -> Ray {
+        let mantissa = U256::from(mantissa);
+        let ten = U256::from(10);
+        if decimals <= RAY_DECIMALS {
+            Ray(mantissa * ten.pow(U256::from(RAY_DECIMALS - decimals)))
+        } else {
+            Ray(mantissa / ten.pow(U256::from(decimals - RAY_DECIMALS)))
+        }
+    }
+
     /// value * 1e27
     pub fn scale_to_ray(value: U256) -> Ray {
         let value = Natural::from_limbs_asc(value.as_limbs()) * const_1e27();
@@ -348,6 +391,15 @@ impl Ray {
         Uint::from_limbs_slice(&reslimbs)
     }
 
+    /// [`Self::mul_quantity`], erroring instead of panicking when the t1
+    /// doesn't fit a U256
+    pub fn checked_mul_quantity(&self, q: U256) -> Result<U256, MathError> {
+        let p: U512 = self.0.widening_mul(q);
+        let (res, _) =
+            Natural::from_limbs_asc(p.as_limbs()).div_round(const_1e27(), RoundingMode::Floor);
+        Self::natural_to_u256(res, "t1 quantity")
+    }
+
     /// Given a price ration t1/t0 calculates how much t1 would be needed to
     /// output the provided amount of t0 (q).  Rounding determined by parameter
     pub fn quantity(&self, q: u128, round_up: bool) -> u128 {
@@ -358,6 +410,15 @@ impl Ray {
         u128::saturating_from(&res)
     }
 
+    /// [`Self::quantity`], erroring instead of saturating when the t1 doesn't
+    /// fit a u128
+    pub fn checked_quantity(&self, q: u128, round_up: bool) -> Result<u128, MathError> {
+        let rm = if round_up { RoundingMode::Ceiling } else { RoundingMode::Floor };
+        let product: U512 = self.0.widening_mul(U256::from(q));
+        let (res, _) = Natural::from_limbs_asc(product.as_limbs()).div_round(const_1e27(), rm);
+        u128::try_from(&res).map_err(|_| MathError::Overflow("t1 quantity"))
+    }
+
     /// Given a price ratio t1/t0 calculates how much t0 would be needed to
     /// output the provided amount of t1 (q).  Rounding determined by parameter
     pub fn inverse_quantity(&self, q: u128, round_up: bool) -> u128 {
@@ -368,6 +429,36 @@ impl Ray {
         u128::saturating_from(&res)
     }
 
+    /// [`Self::inverse_quantity`], erroring on a zero price or a t0 that
+    /// doesn't fit a u128 instead of panicking or saturating
+    pub fn checked_inverse_quantity(&self, q: u128, round_up: bool) -> Result<u128, MathError> {
+        if self.0.is_zero() {
+            return Err(MathError::DivisionByZero("inverse quantity"))
+        }
+        let rm = if round_up { RoundingMode::Ceiling } else { RoundingMode::Floor };
+        let numerator = Natural::from(q) * const_1e27();
+        let (res, _) = numerator.div_round(Natural::from(*self), rm);
+        u128::try_from(&res).map_err(|_| MathError::Overflow("inverse quantity"))
+    }
+
+    /// self + other, erroring instead of panicking on overflow
+    pub fn checked_add(self, other: Ray, what: &'static str) -> Result<Ray, MathError> {
+        self.0.try_add(other.0, what).map(Ray)
+    }
+
+    /// self - other, erroring instead of panicking on underflow
+    pub fn checked_sub(self, other: Ray, what: &'static str) -> Result<Ray, MathError> {
+        self.0.try_sub(other.0, what).map(Ray)
+    }
+
+    fn natural_to_u256(value: Natural, what: &'static str) -> Result<U256, MathError> {
+        let limbs = value.into_limbs_asc();
+        if limbs.len() > 4 {
+            return Err(MathError::Overflow(what))
+        }
+        Ok(U256::from_limbs_slice(&limbs))
+    }
+
     /// Given a price ratio t1/t0 calculates the amount of excess T1 left after
     /// dividing out an even amount of T0
     pub fn inverse_remainder(&self, q: u128) -> u128 {
@@ -644,6 +735,21 @@ mod tests {
         // );
     }
 
+    #[test]
+    fn parses_decimals_exactly() {
+        let ray: Ray = "1.005".parse().unwrap();
+        assert_eq!(ray, Ray::from_decimal(1005, 3));
+        assert_eq!(ray.0, U256::from(1_005_000_000_000_000_000_000_000_000_u128));
+        assert_eq!("42".parse::<Ray>().unwrap(), Ray::scale_to_ray(U256::from(42)));
+        assert_eq!(".5".parse::<Ray>().unwrap(), Ray::from_decimal(5, 1));
+        assert_eq!(Ray::from_decimal(15, 28), Ray::from(U256::from(1)));
+
+        assert!("1.0000000000000000000000000001".parse::<Ray>().is_err());
+        assert!("1e5".parse::<Ray>().is_err());
+        assert!("-1".parse::<Ray>().is_err());
+        assert!(".".parse::<Ray>().is_err());
+    }
+
     #[test]
     fn converts_from_sqrtpricex96() {
         let mut rng = thread_rng();
@@ -2,8 +2,19 @@
 
 mod solutionlib;
 
-use angstrom_types::contract_payloads::{angstrom::AngstromBundle, asset::builder::AssetBuilder};
+use alloy::primitives::{Address, U256};
+use angstrom_types::{
+    contract_payloads::{
+        angstrom::{AngstromBundle, UserOrder},
+        asset::builder::AssetBuilder,
+        fees::{FeeConfig, PoolFees},
+        tob::ToBOutcome
+    },
+    matching::uniswap::PoolSnapshot,
+    orders::PoolSolution
+};
 use base64::Engine;
+use pade::PadeEncode;
 use solutionlib::DEMO_SOLUTION;
 use tracing::Level;
 
@@ -45,7 +56,8 @@ fn build_bundle() {
             t0,
             t1,
             store_index,
-            shared_gas
+            shared_gas,
+            &FeeConfig::default()
         )
         .expect("Bundle processing failed");
 
@@ -59,3 +71,127 @@ fn build_bundle() {
         println!("Bundle: {:#?}", bundle);
     })
 }
+
+/// Builds the bundle of the demo solution with `fees`, returning the tokens
+/// of the pool the bundle leaves in the contract next to it.
+fn demo_bundle(
+    fees: impl FnOnce(&PoolSolution) -> FeeConfig
+) -> (AngstromBundle, PoolFees, [i128; 2]) {
+    demo_bundle_with_tob_outcome(fees, |_, _| None)
+}
+
+/// Like [`demo_bundle`], with the outcome of the top of block order simulated
+/// ahead of the bundle, as it is for the solutions streamed to the leader.
+fn demo_bundle_with_tob_outcome(
+    fees: impl FnOnce(&PoolSolution) -> FeeConfig,
+    tob_outcome: impl FnOnce(&PoolSolution, &PoolSnapshot) -> Option<ToBOutcome>
+) -> (AngstromBundle, PoolFees, [i128; 2]) {
+    let bytes = base64::prelude::BASE64_STANDARD
+        .decode(DEMO_SOLUTION)
+        .unwrap();
+    let (solution, orders_by_pool, snapshot, t0, t1, store_index, shared_gas) =
+        serde_json::from_slice::<(PoolSolution, _, _, Address, Address, u16, _)>(&bytes).unwrap();
+
+    let mut top_of_block_orders = Vec::new();
+    let mut pool_updates = Vec::new();
+    let mut pairs = Vec::new();
+    let mut user_orders = Vec::new();
+    let mut asset_builder = AssetBuilder::new();
+
+    let tob_outcome = tob_outcome(&solution, &snapshot);
+    let pool_fees = AngstromBundle::process_solution_with_tob_outcome(
+        &mut pairs,
+        &mut asset_builder,
+        &mut user_orders,
+        &orders_by_pool,
+        &mut top_of_block_orders,
+        &mut pool_updates,
+        &solution,
+        &snapshot,
+        t0,
+        t1,
+        store_index,
+        shared_gas,
+        tob_outcome.as_ref(),
+        &fees(&solution)
+    )
+    .expect("Bundle processing failed");
+    let deltas = [asset_builder.net_delta(t0), asset_builder.net_delta(t1)];
+
+    let bundle = AngstromBundle::new(
+        asset_builder.get_asset_array(),
+        pairs,
+        pool_updates,
+        top_of_block_orders,
+        user_orders
+    );
+    (bundle, pool_fees, deltas)
+}
+
+#[test]
+fn bundle_saves_protocol_fees() {
+    let (bundle, pool_fees, _) =
+        demo_bundle(|solution| FeeConfig::new(500_000).with_pool_fee(solution.id, 100_000));
+    assert_eq!(pool_fees.asset, bundle.assets[bundle.pairs[0].index0 as usize].addr);
+    assert!(pool_fees.protocol <= pool_fees.lp);
+    assert!(pool_fees.total() <= pool_fees.surplus);
+    assert!(pool_fees.matched_volume > 0);
+
+    bundle.reconcile_fees(&[pool_fees]).unwrap();
+
+    // fees that didn't make it into the bundle are caught
+    let missing = PoolFees { protocol: pool_fees.protocol + 1, ..pool_fees };
+    assert!(bundle.reconcile_fees(&[missing]).is_err());
+}
+
+#[test]
+fn fees_are_paid_by_the_users() {
+    let (plain, no_fees, without) = demo_bundle(|_| FeeConfig::default());
+    let (bundle, pool_fees, with) =
+        demo_bundle(|solution| FeeConfig::new(500_000).with_pool_fee(solution.id, 100_000));
+    assert_eq!(no_fees.total(), 0);
+
+    // what the users are charged is exactly what is saved and donated, so the
+    // fees don't move any tokens of the contract
+    assert_eq!(with, without);
+    let charged = |bundle: &AngstromBundle, fee: fn(&UserOrder) -> u128| {
+        bundle.user_orders.iter().map(fee).sum::<u128>()
+    };
+    assert_eq!(charged(&bundle, |order| order.pool_fee_asset0), pool_fees.total());
+    assert_eq!(charged(&plain, |order| order.pool_fee_asset0), 0);
+    // the gas they are charged stays apart from the fees
+    assert_eq!(
+        charged(&bundle, |order| order.extra_fee_asset0),
+        charged(&plain, |order| order.extra_fee_asset0)
+    );
+}
+
+#[test]
+fn reuses_the_streamed_tob_outcome() {
+    let simulated = |solution: &PoolSolution, snapshot: &PoolSnapshot| {
+        let tob = solution
+            .searcher
+            .as_ref()
+            .expect("the demo solution has a searcher");
+        ToBOutcome::from_tob_and_snapshot(tob, snapshot).unwrap()
+    };
+    let (plain, ..) = demo_bundle(|_| FeeConfig::default());
+
+    // the outcome simulated while the other books were solved builds the same
+    // bundle
+    let (streamed, ..) = demo_bundle_with_tob_outcome(
+        |_| FeeConfig::default(),
+        |solution, snapshot| Some(simulated(solution, snapshot))
+    );
+    assert_eq!(streamed.pade_encode(), plain.pade_encode());
+
+    // and is taken as it is instead of simulated again
+    let (reused, ..) = demo_bundle_with_tob_outcome(
+        |_| FeeConfig::default(),
+        |solution, snapshot| {
+            let outcome = simulated(solution, snapshot);
+            Some(ToBOutcome { total_cost: outcome.total_cost + U256::from(1), ..outcome })
+        }
+    );
+    assert_ne!(reused.pade_encode(), plain.pade_encode());
+}
@@ -100,6 +100,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 610941543648688290660352 },
                 max_extra_fee_asset0: 610941543648688290660352,
                 extra_fee_asset0:     610941543648688290660352,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -125,6 +126,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 583425122153890188361728 },
                 max_extra_fee_asset0: 583425122153890188361728,
                 extra_fee_asset0:     583425122153890188361728,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 27,
@@ -150,6 +152,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 416848468343109802000384 },
                 max_extra_fee_asset0: 416848468343109802000384,
                 extra_fee_asset0:     416848468343109802000384,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -175,6 +178,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 474485885312458955948032 },
                 max_extra_fee_asset0: 474485885312458955948032,
                 extra_fee_asset0:     474485885312458955948032,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -200,6 +204,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 682395203791910248382464 },
                 max_extra_fee_asset0: 682395203791910248382464,
                 extra_fee_asset0:     682395203791910248382464,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 27,
@@ -227,6 +232,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 },
                 max_extra_fee_asset0: 1193274575280400119103488,
                 extra_fee_asset0:     1193274575280400119103488,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -254,6 +260,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 },
                 max_extra_fee_asset0: 1121857342254363640332288,
                 extra_fee_asset0:     1121857342254363640332288,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 27,
@@ -279,6 +286,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 852536190206042982842368 },
                 max_extra_fee_asset0: 852536190206042982842368,
                 extra_fee_asset0:     852536190206042982842368,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -304,6 +312,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 order_quantities:     OrderQuantities::Exact { quantity: 505725768839177229565952 },
                 max_extra_fee_asset0: 505725768839177229565952,
                 extra_fee_asset0:     505725768839177229565952,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 28,
@@ -333,6 +342,7 @@ fn raw_bundle(t0: Address, t1: Address) -> AngstromBundle {
                 },
                 max_extra_fee_asset0: 1026907610798494993874944,
                 extra_fee_asset0:     1026907610798494993874944,
+                pool_fee_asset0:      0,
                 exact_in:             false,
                 signature:            Signature::Ecdsa {
                     v: 27,
@@ -38,6 +38,19 @@ pub struct TickInfo {
     pub initialized:     bool
 }
 
+/// The price and tick a pool is at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Slot0 {
+    pub sqrt_price: U256,
+    pub tick:       i32
+}
+
+impl From<&PoolData> for Slot0 {
+    fn from(data: &PoolData) -> Self {
+        Self { sqrt_price: U256::from(data.sqrtPrice), tick: data.tick.as_i32() }
+    }
+}
+
 // at around 190 is when "max code size exceeded" comes up
 // const MAX_TICKS_PER_REQUEST: u16 = 150;
 
@@ -601,12 +614,12 @@ where
         Ok(())
     }
 
-    pub fn data_is_populated(&self) -> bool {
-        !(self.token0.is_zero() || self.token1.is_zero())
+    pub fn slot0(&self) -> Slot0 {
+        Slot0 { sqrt_price: self.sqrt_price, tick: self.tick }
     }
 
-    pub(crate) fn event_signatures(&self) -> Vec<B256> {
-        Loader::event_signatures()
+    pub fn data_is_populated(&self) -> bool {
+        !(self.token0.is_zero() || self.token1.is_zero())
     }
 
     pub(crate) fn update_position(
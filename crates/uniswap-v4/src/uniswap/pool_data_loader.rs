@@ -69,10 +69,8 @@ impl PoolData {
         let this = SqrtPriceX96::from(self.sqrtPrice);
         tracing::debug!(sqrt_price=?this);
         let tick = this.to_tick().expect("should never fail");
-        // TODO: not a fan of this given precision will be lost. could cause problems
-        // down the road.
-        let normalized_price = 1.0001_f64.powi(tick);
-        Ray::from(normalized_price)
+        // 1.0001^tick, exactly as the pool prices the tick
+        Ray::from(SqrtPriceX96::at_tick(tick).expect("should never fail"))
     }
 }
 
@@ -3,9 +3,9 @@ use std::{
     fmt::Debug,
     future::Future,
     hash::Hash,
-    ops::Deref,
-    sync::{Arc, RwLock, RwLockReadGuard},
-    task::Poll
+    sync::{Arc, RwLock},
+    task::Poll,
+    time::Duration
 };
 
 use alloy::{
@@ -14,22 +14,33 @@ use alloy::{
     transports::{RpcError, TransportErrorKind}
 };
 use alloy_primitives::Log;
+use angstrom_metrics::PoolIntegrityMetricsWrapper;
 use angstrom_types::{
     block_sync::BlockSyncConsumer,
-    contract_payloads::tob::ToBOutcome,
+    contract_payloads::tob::{ToBOutcome, TobStateOverrides},
     matching::uniswap::PoolSnapshot,
     primitive::PoolId,
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
 use arraydeque::ArrayDeque;
 use futures::FutureExt;
-use futures_util::{stream::BoxStream, StreamExt};
+use futures_util::{
+    future::BoxFuture,
+    stream::{BoxStream, FuturesUnordered},
+    StreamExt
+};
 use thiserror::Error;
-use tokio::sync::Notify;
+use tokio::{
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender},
+        oneshot, Notify
+    },
+    time::{Interval, MissedTickBehavior}
+};
 
 use super::{pool::PoolError, pool_providers::PoolMangerBlocks};
 use crate::uniswap::{
-    pool::EnhancedUniswapPool,
+    pool::{EnhancedUniswapPool, Slot0},
     pool_data_loader::{DataLoader, PoolDataLoader},
     pool_providers::PoolManagerProvider
 };
@@ -41,6 +52,97 @@ pub type SyncedUniswapPool<A = PoolId, Loader = DataLoader<A>> =
 
 const MODULE_NAME: &str = "UniswapV4";
 
+type ResyncResponse = oneshot::Sender<Result<BlockNumber, PoolManagerError>>;
+
+/// How long to wait before loading a pool that isn't synced again, doubled
+/// on every failed attempt up to [`MAX_LOAD_RETRY_DELAY`].
+const LOAD_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_LOAD_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// A pool loaded at a block, next to the result of loading it.
+struct PoolLoad<Loader, A> {
+    pool_id:  A,
+    block:    BlockNumber,
+    pool:     EnhancedUniswapPool<Loader, A>,
+    loaded:   Result<(), PoolError>,
+    /// the failed attempts before this one
+    attempts: u32,
+    response: ResyncResponse
+}
+
+type PendingResync<Loader, A> = BoxFuture<'static, PoolLoad<Loader, A>>;
+
+enum PoolCommand<Loader, A> {
+    Resync(A, ResyncResponse),
+    Replace {
+        previous: A,
+        pool_id:  A,
+        pool:     Box<EnhancedUniswapPool<Loader, A>>,
+        response: ResyncResponse
+    }
+}
+
+/// The slot0 a pool was synced to, next to the one the chain has at the block.
+type PendingIntegrityCheck<A> =
+    BoxFuture<'static, (A, BlockNumber, Slot0, Result<Slot0, PoolError>)>;
+
+/// Reloads pools from the chain, for when their synced state is suspected to
+/// have drifted or their key changed.
+pub struct PoolResyncHandle<A = PoolId, Loader = DataLoader<A>> {
+    tx: UnboundedSender<PoolCommand<Loader, A>>
+}
+
+impl<A, Loader> Clone for PoolResyncHandle<A, Loader> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+
+impl<A, Loader> Debug for PoolResyncHandle<A, Loader> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolResyncHandle").finish_non_exhaustive()
+    }
+}
+
+impl<A, Loader> PoolResyncHandle<A, Loader> {
+    /// Reloads the pool at the latest synced block and returns that block.
+    pub async fn resync(&self, pool_id: A) -> Result<BlockNumber, PoolManagerError> {
+        self.send(|response| PoolCommand::Resync(pool_id, response))
+            .await
+    }
+
+    /// Stops syncing `previous` and syncs `pool` under `pool_id` instead, for
+    /// pools whose parameters changed on chain. The pool is loaded at the
+    /// latest synced block, it's only available once that's done, the block
+    /// is returned.
+    pub async fn replace(
+        &self,
+        previous: A,
+        pool_id: A,
+        pool: EnhancedUniswapPool<Loader, A>
+    ) -> Result<BlockNumber, PoolManagerError> {
+        self.send(|response| PoolCommand::Replace {
+            previous,
+            pool_id,
+            pool: Box::new(pool),
+            response
+        })
+        .await
+    }
+
+    async fn send(
+        &self,
+        command: impl FnOnce(ResyncResponse) -> PoolCommand<Loader, A>
+    ) -> Result<BlockNumber, PoolManagerError> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(command(tx))
+            .map_err(|_| PoolManagerError::NotRunning)?;
+
+        rx.await.map_err(|_| PoolManagerError::NotRunning)?
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct TickRangeToLoad<A = PoolId> {
     pub pool_id:    A,
@@ -49,7 +151,9 @@ pub struct TickRangeToLoad<A = PoolId> {
     pub tick_count: u16
 }
 
-type PoolMap<Loader, A> = Arc<HashMap<A, Arc<RwLock<EnhancedUniswapPool<Loader, A>>>>>;
+/// The synced pools by their public id. Pools are added and removed while the
+/// manager runs, every clone sees the same pools.
+type PoolMap<Loader, A> = Arc<RwLock<HashMap<A, SyncedUniswapPool<A, Loader>>>>;
 
 #[derive(Clone)]
 pub struct SyncedUniswapPools<A = PoolId, Loader = DataLoader<A>>
@@ -60,17 +164,6 @@ where
     tx:    tokio::sync::mpsc::Sender<(TickRangeToLoad<A>, Arc<Notify>)>
 }
 
-impl<A, Loader> Deref for SyncedUniswapPools<A, Loader>
-where
-    Loader: PoolDataLoader<A>
-{
-    type Target = PoolMap<Loader, A>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.pools
-    }
-}
-
 /// Amount of ticks to load when we go out of scope;
 const OUT_OF_SCOPE_TICKS: u16 = 20;
 
@@ -88,6 +181,33 @@ where
         Self { pools, tx }
     }
 
+    pub fn get(&self, pool_id: &A) -> Option<SyncedUniswapPool<A, Loader>> {
+        self.pools.read().unwrap().get(pool_id).cloned()
+    }
+
+    pub fn contains(&self, pool_id: &A) -> bool {
+        self.pools.read().unwrap().contains_key(pool_id)
+    }
+
+    pub fn pool_ids(&self) -> Vec<A> {
+        self.pools.read().unwrap().keys().copied().collect()
+    }
+
+    /// The pools that are synced right now.
+    pub fn all(&self) -> Vec<(A, SyncedUniswapPool<A, Loader>)> {
+        self.pools
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(pool_id, pool)| (*pool_id, pool.clone()))
+            .collect()
+    }
+
+    fn synced(&self, pool_id: &A) -> eyre::Result<SyncedUniswapPool<A, Loader>> {
+        self.get(pool_id)
+            .ok_or_else(|| eyre::eyre!("pool {pool_id:?} isn't synced"))
+    }
+
     /// Will calculate the tob rewards that this order specifies. More Notably,
     /// this function is async and will make sure that we always have the
     /// needed ticks loaded in order to ensure we can always properly
@@ -98,13 +218,10 @@ where
         tob: &OrderWithStorageData<TopOfBlockOrder>
     ) -> eyre::Result<ToBOutcome> {
         tracing::info!("calculate_rewards function");
-
         let mut cnt = ATTEMPTS;
         loop {
-            let market_snapshot = {
-                let pool = self.pools.get(&pool_id).unwrap().read().unwrap();
-                pool.fetch_pool_snapshot().map(|v| v.2).unwrap()
-            };
+            let pool = self.synced(&pool_id)?;
+            let market_snapshot = pool.read().unwrap().fetch_pool_snapshot()?.2;
 
             let outcome = ToBOutcome::from_tob_and_snapshot(tob, &market_snapshot);
 
@@ -113,7 +230,7 @@ where
                 let not = Arc::new(Notify::new());
                 // scope for awaits
                 let start_tick = {
-                    let pool = self.pools.get(&pool_id).unwrap().read().unwrap();
+                    let pool = pool.read().unwrap();
                     if zfo {
                         pool.fetch_lowest_tick()
                     } else {
@@ -148,13 +265,29 @@ where
             return outcome
         }
     }
+
+    /// Calculates the tob rewards of the order against the pool with the
+    /// overrides applied. Simulations only see the ticks that are loaded
+    /// already, they never load more into the live pool.
+    pub fn simulate_rewards(
+        &self,
+        pool_id: A,
+        tob: &OrderWithStorageData<TopOfBlockOrder>,
+        overrides: &TobStateOverrides
+    ) -> eyre::Result<ToBOutcome> {
+        let pool = self.synced(&pool_id)?;
+        let market_snapshot = pool.read().unwrap().fetch_pool_snapshot()?.2;
+
+        ToBOutcome::from_tob_and_snapshot(tob, &overrides.apply(market_snapshot)?)
+    }
 }
 
 pub struct UniswapPoolManager<P, BlockSync, Loader: PoolDataLoader<A>, A = Address>
 where
     A: Debug + Copy
 {
-    /// the poolId with the fee to the dynamic fee poolId
+    /// the poolId with the fee to the dynamic fee poolId, the logs of the pools
+    /// come by the latter
     conversion_map:      HashMap<A, A>,
     pools:               SyncedUniswapPools<A, Loader>,
     latest_synced_block: u64,
@@ -162,7 +295,15 @@ where
     provider:            Arc<P>,
     block_sync:          BlockSync,
     block_stream:        BoxStream<'static, Option<PoolMangerBlocks>>,
-    rx:                  tokio::sync::mpsc::Receiver<(TickRangeToLoad<A>, Arc<Notify>)>
+    rx:                  tokio::sync::mpsc::Receiver<(TickRangeToLoad<A>, Arc<Notify>)>,
+    resync_tx:           UnboundedSender<PoolCommand<Loader, A>>,
+    resync_rx:           UnboundedReceiver<PoolCommand<Loader, A>>,
+    resyncs:             FuturesUnordered<PendingResync<Loader, A>>,
+    /// how often the synced pools are compared against the chain, never if
+    /// unset
+    integrity_interval:  Option<Interval>,
+    integrity_checks:    FuturesUnordered<PendingIntegrityCheck<A>>,
+    integrity_metrics:   PoolIntegrityMetricsWrapper
 }
 
 impl<P, BlockSync, Loader, A> UniswapPoolManager<P, BlockSync, Loader, A>
@@ -183,78 +324,79 @@ where
 
         let rwlock_pools = pools
             .into_iter()
-            .map(|pool| (pool.address(), Arc::new(RwLock::new(pool))))
+            .filter_map(|pool| {
+                let pool_id = Self::pub_id(&conversion_map, &pool.address())?;
+                Some((pool_id, Arc::new(RwLock::new(pool))))
+            })
             .collect();
 
         let block_stream = <P as Clone>::clone(&provider);
         let block_stream = block_stream.subscribe_blocks();
         let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let (resync_tx, resync_rx) = tokio::sync::mpsc::unbounded_channel();
 
         Self {
             conversion_map,
-            pools: SyncedUniswapPools::new(Arc::new(rwlock_pools), tx),
+            pools: SyncedUniswapPools::new(Arc::new(RwLock::new(rwlock_pools)), tx),
             latest_synced_block,
             state_change_cache: Arc::new(RwLock::new(HashMap::new())),
             block_stream,
             provider,
             block_sync,
-            rx
+            rx,
+            resync_tx,
+            resync_rx,
+            resyncs: FuturesUnordered::new(),
+            integrity_interval: None,
+            integrity_checks: FuturesUnordered::new(),
+            integrity_metrics: PoolIntegrityMetricsWrapper::new()
         }
     }
 
+    /// Compares the price and tick of every pool against the chain each
+    /// `interval`, resyncing the pools that diverged. Catches the state
+    /// tracking going wrong before solutions are built on it.
+    pub fn with_integrity_check(mut self, interval: Duration) -> Self {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        self.integrity_interval = Some(interval);
+        self
+    }
+
+    pub fn resync_handle(&self) -> PoolResyncHandle<A, Loader> {
+        PoolResyncHandle { tx: self.resync_tx.clone() }
+    }
+
     pub fn fetch_pool_snapshots(&self) -> HashMap<A, PoolSnapshot> {
         self.pools
-            .iter()
+            .all()
+            .into_iter()
             .filter_map(|(key, pool)| {
-                // gotta
-                Some((
-                    self.convert_to_pub_id(key),
-                    pool.read().unwrap().fetch_pool_snapshot().ok()?.2
-                ))
+                Some((key, pool.read().unwrap().fetch_pool_snapshot().ok()?.2))
             })
             .collect()
     }
 
-    pub fn pool_addresses(&self) -> impl Iterator<Item = A> + '_ {
-        self.pools.keys().map(|k| self.convert_to_pub_id(k))
+    pub fn pool_addresses(&self) -> impl Iterator<Item = A> {
+        self.pools.pool_ids().into_iter()
     }
 
     pub fn pools(&self) -> SyncedUniswapPools<A, Loader> {
-        let mut c = self.pools.clone();
-        c.pools = Arc::new(
-            c.pools
-                .iter()
-                .map(|(k, v)| (self.convert_to_pub_id(k), v.clone()))
-                .collect()
-        );
-
-        c
+        self.pools.clone()
     }
 
-    fn convert_to_pub_id(&self, key: &A) -> A {
-        self.conversion_map
+    fn pub_id(conversion_map: &HashMap<A, A>, key: &A) -> Option<A> {
+        conversion_map
             .iter()
-            .find_map(|(r, m)| {
-                if m == key {
-                    return Some(r)
-                }
-                None
-            })
-            .copied()
-            .unwrap()
+            .find_map(|(r, m)| (m == key).then_some(*r))
     }
 
-    pub fn pool(&self, address: &A) -> Option<RwLockReadGuard<'_, EnhancedUniswapPool<Loader, A>>> {
-        let addr = self.conversion_map.get(address)?;
-        let pool = self.pools.get(addr)?;
-        Some(pool.read().unwrap())
+    pub fn pool(&self, address: &A) -> Option<SyncedUniswapPool<A, Loader>> {
+        self.pools.get(address)
     }
 
     pub fn filter(&self) -> Filter {
-        // it should crash given that no pools makes no sense
-        let pool = self.pools.values().next().unwrap();
-        let pool = pool.read().unwrap();
-        Filter::new().event_signature(pool.event_signatures())
+        Filter::new().event_signature(Loader::event_signatures())
     }
 
     /// Unwinds the state changes cache for every block from the most recent
@@ -359,16 +501,26 @@ where
             .expect("should never fail");
 
         if is_reorg {
+            let mut lost = Vec::new();
             // scope for locks
-            let mut state_change_cache = self.state_change_cache.write().unwrap();
-            for pool in self.pools.values() {
-                let mut pool_guard = pool.write().unwrap();
-                Self::unwind_state_changes(
-                    &mut pool_guard,
-                    &mut state_change_cache,
-                    chain_head_block_number
-                )
-                .expect("should never fail");
+            {
+                let mut state_change_cache = self.state_change_cache.write().unwrap();
+                for (pool_id, pool) in self.pools.all() {
+                    let mut pool_guard = pool.write().unwrap();
+                    if let Err(e) = Self::unwind_state_changes(
+                        &mut pool_guard,
+                        &mut state_change_cache,
+                        chain_head_block_number
+                    ) {
+                        // pools loaded after the block have nothing to unwind to
+                        tracing::warn!(?pool_id, %e, "can't unwind pool, reloading it");
+                        lost.push(pool_id);
+                    }
+                }
+            }
+            for pool_id in lost {
+                let (response, _) = oneshot::channel();
+                self.start_resync(pool_id, response);
             }
         }
 
@@ -379,7 +531,9 @@ where
                 continue
             }
 
-            let Some(pool) = self.pools.get(&addr) else {
+            let Some(pool) = Self::pub_id(&self.conversion_map, &addr)
+                .and_then(|pool_id| self.pools.get(&pool_id))
+            else {
                 continue;
             };
 
@@ -405,6 +559,180 @@ where
         }
     }
 
+    /// Loads the pool from scratch at the latest synced block. The pool keeps
+    /// being synced from the logs while it loads.
+    fn start_resync(&mut self, pool_id: A, response: ResyncResponse) {
+        let Some(pool) = self.pools.get(&pool_id) else {
+            let _ = response.send(Err(PoolManagerError::UnknownPool));
+            return
+        };
+        let pool = Self::unloaded(&pool.read().unwrap());
+
+        tracing::info!(?pool_id, block = self.latest_synced_block, "resyncing pool");
+        self.start_load(pool_id, pool, 0, response);
+    }
+
+    /// Loads pools that failed to load at startup, retrying with a backoff
+    /// until they do. They only become available once they're loaded.
+    pub fn with_pending_pools(mut self, pools: Vec<EnhancedUniswapPool<Loader, A>>) -> Self {
+        for pool in pools {
+            let Some(pool_id) = Self::pub_id(&self.conversion_map, &pool.address()) else {
+                continue
+            };
+            let (response, _) = oneshot::channel();
+            self.start_load(pool_id, pool, 0, response);
+        }
+        self
+    }
+
+    /// Drops `previous` and loads `pool` in its place, see
+    /// [`PoolResyncHandle::replace`].
+    fn start_replace(
+        &mut self,
+        previous: A,
+        pool_id: A,
+        pool: EnhancedUniswapPool<Loader, A>,
+        response: ResyncResponse
+    ) {
+        if let Some(removed) = self.pools.pools.write().unwrap().remove(&previous) {
+            self.state_change_cache
+                .write()
+                .unwrap()
+                .remove(&removed.read().unwrap().address());
+        }
+        self.conversion_map.remove(&previous);
+        self.conversion_map.insert(pool_id, pool.address());
+
+        tracing::info!(?previous, ?pool_id, block = self.latest_synced_block, "replacing pool");
+        self.start_load(pool_id, pool, 0, response);
+    }
+
+    /// Loads the pool at the latest synced block. Pools that aren't synced are
+    /// only loaded after waiting out the backoff of their failed `attempts`.
+    fn start_load(
+        &mut self,
+        pool_id: A,
+        mut pool: EnhancedUniswapPool<Loader, A>,
+        attempts: u32,
+        response: ResyncResponse
+    ) {
+        let delay = (attempts > 0 && !self.pools.contains(&pool_id)).then(|| {
+            LOAD_RETRY_DELAY
+                .saturating_mul(1 << attempts.min(16))
+                .min(MAX_LOAD_RETRY_DELAY)
+        });
+        let block = self.latest_synced_block;
+        let provider = self.provider.clone();
+        self.resyncs.push(Box::pin(async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            let loaded = pool.initialize(Some(block), provider.provider()).await;
+
+            PoolLoad { pool_id, block, pool, loaded, attempts, response }
+        }));
+    }
+
+    fn finish_resync(&mut self, load: PoolLoad<Loader, A>) {
+        let PoolLoad { pool_id, block, pool, loaded, attempts, response } = load;
+        // the pool was replaced while it loaded
+        if self.conversion_map.get(&pool_id) != Some(&pool.address()) {
+            let _ = response.send(Err(PoolManagerError::UnknownPool));
+            return
+        }
+        if let Err(e) = loaded {
+            tracing::warn!(?pool_id, attempts, %e, "failed to load pool");
+            // the synced state stays in use, the caller decides whether to
+            // try again
+            if self.pools.contains(&pool_id) {
+                let _ = response.send(Err(e.into()));
+                return
+            }
+            // pools that aren't synced are unavailable until they load
+            self.start_load(pool_id, Self::unloaded(&pool), attempts + 1, response);
+            return
+        }
+        // blocks applied to the old state in the meantime would be lost
+        if block != self.latest_synced_block {
+            self.start_load(pool_id, Self::unloaded(&pool), attempts, response);
+            return
+        }
+
+        let mut pools = self.pools.pools.write().unwrap();
+        match pools.get(&pool_id) {
+            Some(synced) => *synced.write().unwrap() = pool,
+            None => {
+                pools.insert(pool_id, Arc::new(RwLock::new(pool)));
+            }
+        }
+        tracing::info!(?pool_id, block, "loaded pool");
+        let _ = response.send(Ok(block));
+    }
+
+    /// A pool to load the pool from scratch with.
+    fn unloaded(pool: &EnhancedUniswapPool<Loader, A>) -> EnhancedUniswapPool<Loader, A> {
+        let mut unloaded =
+            EnhancedUniswapPool::new(pool.data_loader(), pool.initial_ticks_per_side());
+        unloaded.set_sim_swap_sync(pool.is_sync_swap_with_sim());
+        unloaded
+    }
+
+    /// Loads the slot0 of every pool at the latest synced block, unless the
+    /// last checks or a resync are still running.
+    fn start_integrity_checks(&mut self) {
+        if !self.integrity_checks.is_empty() || !self.resyncs.is_empty() {
+            return
+        }
+
+        let block = self.latest_synced_block;
+        for (pool_id, pool) in self.pools.all() {
+            let (loader, synced) = {
+                let pool = pool.read().unwrap();
+                (pool.data_loader(), pool.slot0())
+            };
+            let provider = self.provider.clone();
+            self.integrity_checks.push(Box::pin(async move {
+                let onchain = loader
+                    .load_pool_data(Some(block), provider.provider())
+                    .await
+                    .map(|data| Slot0::from(&data));
+
+                (pool_id, block, synced, onchain)
+            }));
+        }
+    }
+
+    fn finish_integrity_check(
+        &mut self,
+        pool_id: A,
+        block: BlockNumber,
+        synced: Slot0,
+        onchain: Result<Slot0, PoolError>
+    ) {
+        let onchain = match onchain {
+            Ok(onchain) => onchain,
+            Err(e) => {
+                tracing::warn!(?pool_id, block, %e, "failed to load slot0 of pool");
+                return
+            }
+        };
+        // the pool was synced past the block it was checked at
+        if block != self.latest_synced_block {
+            return
+        }
+
+        self.integrity_metrics.checked();
+        if synced == onchain {
+            return
+        }
+
+        tracing::error!(?pool_id, block, ?synced, ?onchain, "synced pool diverged from the chain");
+        self.integrity_metrics.diverged();
+        // nothing waits on the resync, it's reported on by the logs
+        let (response, _) = oneshot::channel();
+        self.start_resync(pool_id, response);
+    }
+
     #[allow(clippy::await_holding_lock)]
     async fn load_more_ticks(
         notifier: Arc<Notify>,
@@ -413,7 +741,11 @@ where
         tick_req: TickRangeToLoad<A>
     ) {
         let node_provider = provider.provider();
-        let mut pool = pools.get(&tick_req.pool_id).unwrap().write().unwrap();
+        let Some(pool) = pools.get(&tick_req.pool_id) else {
+            notifier.notify_one();
+            return
+        };
+        let mut pool = pool.write().unwrap();
 
         // given we force this to resolve, should'nt be problematic
         let ticks = pool
@@ -452,6 +784,30 @@ where
 
             while f.poll_unpin(cx).is_pending() {}
         }
+        // diverged pools are resynced below
+        while self
+            .integrity_interval
+            .as_mut()
+            .is_some_and(|interval| interval.poll_tick(cx).is_ready())
+        {
+            self.start_integrity_checks();
+        }
+        while let Poll::Ready(Some((pool_id, block, synced, onchain))) =
+            self.integrity_checks.poll_next_unpin(cx)
+        {
+            self.finish_integrity_check(pool_id, block, synced, onchain);
+        }
+        while let Poll::Ready(Some(command)) = self.resync_rx.poll_recv(cx) {
+            match command {
+                PoolCommand::Resync(pool_id, response) => self.start_resync(pool_id, response),
+                PoolCommand::Replace { previous, pool_id, pool, response } => {
+                    self.start_replace(previous, pool_id, *pool, response)
+                }
+            }
+        }
+        while let Poll::Ready(Some(load)) = self.resyncs.poll_next_unpin(cx) {
+            self.finish_resync(load);
+        }
 
         Poll::Pending
     }
@@ -489,6 +845,10 @@ pub enum PoolManagerError {
     JoinError(#[from] tokio::task::JoinError),
     #[error("Synchronization has already been started")]
     SyncAlreadyStarted,
+    #[error("Unknown pool")]
+    UnknownPool,
+    #[error("Pool manager is not running")]
+    NotRunning,
     #[error(transparent)]
     RpcTransportError(#[from] RpcError<TransportErrorKind>)
 }
@@ -498,7 +858,7 @@ mod annoying_tests {
     use std::{sync::Arc, task::Waker};
 
     use alloy::{
-        primitives::Address,
+        primitives::{Address, U256},
         providers::{fillers::*, network::Ethereum, Provider, ProviderBuilder, RootProvider, *}
     };
     use alloy_primitives::LogData;
@@ -713,4 +1073,168 @@ mod annoying_tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn resyncs_pools_that_diverged_from_the_chain() {
+        let provider = Arc::new(MockProvider::new().await);
+        let pool_id = PoolId::default();
+        let mut manager = UniswapPoolManager::new(
+            vec![EnhancedUniswapPool::<DataLoader<PoolId>, PoolId>::default()],
+            HashMap::from([(pool_id, pool_id)]),
+            100,
+            provider,
+            MockBlockSync
+        );
+        let synced = Slot0 { sqrt_price: U256::from(1_000_000), tick: 10 };
+
+        manager.finish_integrity_check(pool_id, 100, synced, Ok(synced));
+        assert!(manager.resyncs.is_empty());
+
+        // checks of a block the pool was synced past are dropped
+        let onchain = Slot0 { tick: 11, ..synced };
+        manager.finish_integrity_check(pool_id, 99, synced, Ok(onchain));
+        assert!(manager.resyncs.is_empty());
+
+        manager.finish_integrity_check(pool_id, 100, synced, Ok(onchain));
+        assert_eq!(manager.resyncs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn replaces_pools_whose_parameters_changed() {
+        let provider = Arc::new(MockProvider::new().await);
+        let (previous, pool_id) = (PoolId::default(), PoolId::repeat_byte(1));
+        let mut manager = UniswapPoolManager::new(
+            vec![EnhancedUniswapPool::<DataLoader<PoolId>, PoolId>::default()],
+            HashMap::from([(previous, previous)]),
+            100,
+            provider,
+            MockBlockSync
+        );
+        let pools = manager.pools();
+        let replacement = || {
+            EnhancedUniswapPool::new(
+                DataLoader::new_with_registry(
+                    PoolId::repeat_byte(2),
+                    Default::default(),
+                    Address::ZERO
+                ),
+                10
+            )
+        };
+
+        let (response, _) = oneshot::channel();
+        manager.start_replace(previous, pool_id, replacement(), response);
+        // the previous pool is gone right away, the new one once it's loaded
+        assert!(pools.pool_ids().is_empty());
+        assert_eq!(manager.conversion_map, HashMap::from([(pool_id, PoolId::repeat_byte(2))]));
+
+        // loaded at a block the manager synced past, it's loaded again
+        manager.latest_synced_block = 101;
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id,
+            block: 100,
+            pool: replacement(),
+            loaded: Ok(()),
+            attempts: 0,
+            response
+        });
+        assert!(rx.try_recv().is_err());
+        assert!(!pools.contains(&pool_id));
+
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id,
+            block: 101,
+            pool: replacement(),
+            loaded: Ok(()),
+            attempts: 0,
+            response
+        });
+        assert_eq!(rx.try_recv().unwrap().unwrap(), 101);
+        assert_eq!(pools.pool_ids(), vec![pool_id]);
+
+        // loads of a pool that was replaced in the meantime are dropped
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id: previous,
+            block: 101,
+            pool: Default::default(),
+            loaded: Ok(()),
+            attempts: 0,
+            response
+        });
+        assert!(rx.try_recv().unwrap().is_err());
+        assert!(!pools.contains(&previous));
+    }
+
+    #[tokio::test]
+    async fn retries_pools_that_failed_to_load() {
+        let provider = Arc::new(MockProvider::new().await);
+        let (synced, pending) = (PoolId::default(), PoolId::repeat_byte(1));
+        let pending_pool = || {
+            EnhancedUniswapPool::new(
+                DataLoader::new_with_registry(
+                    PoolId::repeat_byte(2),
+                    Default::default(),
+                    Address::ZERO
+                ),
+                10
+            )
+        };
+        let mut manager = UniswapPoolManager::new(
+            vec![EnhancedUniswapPool::<DataLoader<PoolId>, PoolId>::default()],
+            HashMap::from([(synced, synced), (pending, PoolId::repeat_byte(2))]),
+            100,
+            provider,
+            MockBlockSync
+        )
+        .with_pending_pools(vec![pending_pool()]);
+        let pools = manager.pools();
+        assert_eq!(manager.resyncs.len(), 1);
+        assert_eq!(pools.pool_ids(), vec![synced]);
+        manager.resyncs.clear();
+
+        // a pool that isn't synced is tried again, waiting on the response
+        let failed = || Err(PoolError::PoolNotInitialized);
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id: pending,
+            block: 100,
+            pool: pending_pool(),
+            loaded: failed(),
+            attempts: 3,
+            response
+        });
+        assert_eq!(manager.resyncs.len(), 1);
+        assert!(rx.try_recv().is_err());
+        assert!(!pools.contains(&pending));
+        manager.resyncs.clear();
+
+        // failed resyncs of a synced pool keep its state and report the error
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id: synced,
+            block: 100,
+            pool: Default::default(),
+            loaded: failed(),
+            attempts: 0,
+            response
+        });
+        assert!(manager.resyncs.is_empty());
+        assert!(rx.try_recv().unwrap().is_err());
+        assert!(pools.contains(&synced));
+
+        let (response, mut rx) = oneshot::channel();
+        manager.finish_resync(PoolLoad {
+            pool_id: pending,
+            block: 100,
+            pool: pending_pool(),
+            loaded: Ok(()),
+            attempts: 4,
+            response
+        });
+        assert_eq!(rx.try_recv().unwrap().unwrap(), 100);
+        assert!(pools.contains(&pending));
+    }
 }
@@ -15,9 +15,12 @@ pub fn calculate_reward(
 #[cfg(test)]
 mod test {
     use alloy::primitives::Uint;
-    use angstrom_types::matching::{
-        uniswap::{LiqRange, PoolSnapshot},
-        SqrtPriceX96
+    use angstrom_types::{
+        contract_payloads::tob::{ToBOutcome, TobRewardError},
+        matching::{
+            uniswap::{LiqRange, PoolSnapshot},
+            SqrtPriceX96
+        }
     };
     use rand::thread_rng;
     use testing_tools::type_generator::orders::generate_top_of_block_order;
@@ -55,6 +58,31 @@ mod test {
         );
     }
 
+    #[test]
+    fn rejects_deviating_claimed_reward() {
+        let mut rng = thread_rng();
+        let snapshot = generate_amm_market(100000);
+        let mut tob = generate_top_of_block_order(
+            &mut rng,
+            true,
+            None,
+            None,
+            Some(10_000_000_000_000_u128),
+            Some(100000000_u128)
+        );
+        let simulated = calculate_reward(&tob, &snapshot).unwrap().total_reward;
+
+        tob.tob_reward = simulated + simulated / Uint::from(200);
+        let outcome = ToBOutcome::verify_claimed_reward(&tob, &snapshot, 10_000).unwrap();
+        assert_eq!(outcome.total_reward, simulated);
+
+        tob.tob_reward = simulated * Uint::from(2);
+        assert_eq!(
+            ToBOutcome::verify_claimed_reward(&tob, &snapshot, 10_000),
+            Err(TobRewardError::Deviates { claimed: tob.tob_reward, simulated })
+        );
+    }
+
     #[test]
     fn handles_insufficient_funds() {
         let mut rng = thread_rng();
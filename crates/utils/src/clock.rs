@@ -0,0 +1,268 @@
+//! Time source of the node.
+//!
+//! Everything that waits on, or compares against, the time takes it from a
+//! [`Clock`]. Outside of tests that is the system clock. With the `simulation`
+//! feature it can be a [`LogicalClock`] instead, which only moves when it is
+//! advanced, so that a node run is the same every time it is repeated.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
+#[cfg(feature = "simulation")]
+use std::{
+    sync::{Arc, Mutex},
+    task::Waker
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Clock(Source);
+
+#[derive(Debug, Clone, Default)]
+enum Source {
+    #[default]
+    System,
+    #[cfg(feature = "simulation")]
+    Logical(LogicalClock)
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self(Source::System)
+    }
+
+    #[cfg(feature = "simulation")]
+    pub fn logical(clock: LogicalClock) -> Self {
+        Self(Source::Logical(clock))
+    }
+
+    pub fn now(&self) -> Instant {
+        match &self.0 {
+            Source::System => Instant::now(),
+            #[cfg(feature = "simulation")]
+            Source::Logical(clock) => clock.now()
+        }
+    }
+
+    /// Time since the unix epoch.
+    pub fn unix_now(&self) -> Duration {
+        match &self.0 {
+            Source::System => SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+            #[cfg(feature = "simulation")]
+            Source::Logical(clock) => clock.unix_now()
+        }
+    }
+
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleep_until(self.now() + duration)
+    }
+
+    pub fn sleep_until(&self, deadline: Instant) -> Sleep {
+        match &self.0 {
+            Source::System => Sleep::System(Box::pin(tokio::time::sleep_until(deadline.into()))),
+            #[cfg(feature = "simulation")]
+            Source::Logical(clock) => Sleep::Logical { clock: clock.clone(), deadline }
+        }
+    }
+
+    /// Ticks every `period`, the first tick completes right away.
+    pub fn interval(&self, period: Duration) -> Interval {
+        match &self.0 {
+            Source::System => Interval::System(tokio::time::interval(period)),
+            #[cfg(feature = "simulation")]
+            Source::Logical(clock) => {
+                Interval::Logical { clock: clock.clone(), period, next: clock.now() }
+            }
+        }
+    }
+}
+
+/// Resolves once the deadline passed on the clock it was created from.
+#[derive(Debug)]
+pub enum Sleep {
+    System(Pin<Box<tokio::time::Sleep>>),
+    #[cfg(feature = "simulation")]
+    Logical {
+        clock:    LogicalClock,
+        deadline: Instant
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut() {
+            Self::System(sleep) => sleep.as_mut().poll(cx),
+            #[cfg(feature = "simulation")]
+            Self::Logical { clock, deadline } => clock.poll_deadline(*deadline, cx)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Interval {
+    System(tokio::time::Interval),
+    #[cfg(feature = "simulation")]
+    Logical {
+        clock:  LogicalClock,
+        period: Duration,
+        next:   Instant
+    }
+}
+
+impl Interval {
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        match self {
+            Self::System(interval) => interval.poll_tick(cx).map(Into::into),
+            #[cfg(feature = "simulation")]
+            Self::Logical { clock, period, next } => {
+                let tick = *next;
+                if clock.poll_deadline(tick, cx).is_pending() {
+                    return Poll::Pending
+                }
+                // missed ticks are skipped, jumping the clock doesn't burst
+                *next = clock.now() + *period;
+                Poll::Ready(tick)
+            }
+        }
+    }
+}
+
+/// A clock that stands still until it is advanced, wakes the tasks sleeping on
+/// it as their deadlines pass.
+#[cfg(feature = "simulation")]
+#[derive(Debug, Clone)]
+pub struct LogicalClock(Arc<Mutex<LogicalState>>);
+
+#[cfg(feature = "simulation")]
+#[derive(Debug)]
+struct LogicalState {
+    /// the instant the clock started at, only the elapsed time is ever
+    /// observed
+    origin:      Instant,
+    unix_origin: Duration,
+    elapsed:     Duration,
+    sleepers:    Vec<(Instant, Waker)>
+}
+
+#[cfg(feature = "simulation")]
+impl LogicalClock {
+    /// A clock that starts at `unix_start` after the unix epoch.
+    pub fn new(unix_start: Duration) -> Self {
+        Self(Arc::new(Mutex::new(LogicalState {
+            origin:      Instant::now(),
+            unix_origin: unix_start,
+            elapsed:     Duration::ZERO,
+            sleepers:    vec![]
+        })))
+    }
+
+    pub fn now(&self) -> Instant {
+        let state = self.0.lock().unwrap();
+        state.origin + state.elapsed
+    }
+
+    pub fn unix_now(&self) -> Duration {
+        let state = self.0.lock().unwrap();
+        state.unix_origin + state.elapsed
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap().elapsed
+    }
+
+    /// Earliest deadline a task is sleeping on.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let state = self.0.lock().unwrap();
+        state.sleepers.iter().map(|(deadline, _)| *deadline).min()
+    }
+
+    /// Moves the clock forward, waking everyone whose deadline passed.
+    pub fn advance(&self, by: Duration) {
+        let woken = {
+            let mut state = self.0.lock().unwrap();
+            state.elapsed += by;
+            let now = state.origin + state.elapsed;
+            let (woken, sleeping) = std::mem::take(&mut state.sleepers)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= now);
+            state.sleepers = sleeping;
+            woken
+        };
+
+        // woken outside of the lock, the tasks might be polled right away
+        for (_, waker) in woken {
+            waker.wake();
+        }
+    }
+
+    /// Jumps to the earliest deadline a task sleeps on, returns how far the
+    /// clock moved.
+    pub fn advance_to_next_deadline(&self) -> Option<Duration> {
+        let by = self.next_deadline()?.saturating_duration_since(self.now());
+        self.advance(by);
+        Some(by)
+    }
+
+    fn poll_deadline(&self, deadline: Instant, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.0.lock().unwrap();
+        if deadline <= state.origin + state.elapsed {
+            return Poll::Ready(())
+        }
+
+        let waker = cx.waker();
+        if !state
+            .sleepers
+            .iter()
+            .any(|(at, sleeper)| *at == deadline && sleeper.will_wake(waker))
+        {
+            state.sleepers.push((deadline, waker.clone()));
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(all(test, feature = "simulation"))]
+mod tests {
+    use futures::{task::noop_waker_ref, FutureExt};
+
+    use super::*;
+
+    #[test]
+    fn sleeps_until_the_clock_is_advanced() {
+        let logical = LogicalClock::new(Duration::from_secs(1_000));
+        let clock = Clock::logical(logical.clone());
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let start = clock.now();
+        let mut sleep = clock.sleep(Duration::from_secs(12));
+        assert!(sleep.poll_unpin(&mut cx).is_pending());
+        assert_eq!(logical.next_deadline(), Some(start + Duration::from_secs(12)));
+
+        logical.advance(Duration::from_secs(11));
+        assert!(sleep.poll_unpin(&mut cx).is_pending());
+
+        assert_eq!(logical.advance_to_next_deadline(), Some(Duration::from_secs(1)));
+        assert!(sleep.poll_unpin(&mut cx).is_ready());
+        assert_eq!(clock.unix_now(), Duration::from_secs(1_012));
+        assert_eq!(logical.next_deadline(), None);
+    }
+
+    #[test]
+    fn interval_skips_missed_ticks() {
+        let logical = LogicalClock::new(Duration::ZERO);
+        let clock = Clock::logical(logical.clone());
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut interval = clock.interval(Duration::from_millis(10));
+
+        assert!(interval.poll_tick(&mut cx).is_ready());
+        assert!(interval.poll_tick(&mut cx).is_pending());
+
+        logical.advance(Duration::from_millis(35));
+        assert!(interval.poll_tick(&mut cx).is_ready());
+        assert!(interval.poll_tick(&mut cx).is_pending());
+        assert_eq!(logical.next_deadline(), Some(clock.now() + Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,66 @@
+use angstrom_types::{
+    primitive::{AngstromSigner, ANGSTROM_DOMAIN},
+    sol_bindings::{
+        grouped_orders::{GroupedVanillaOrder, StandingVariants},
+        RawPoolOrder
+    }
+};
+use testing_tools::type_generator::orders::UserOrderBuilder;
+use validation::order::signatures::SignatureVerifier;
+
+const ORDER_COUNT: &[usize] = &[10, 100, 1_000];
+
+fn main() {
+    divan::main();
+}
+
+fn signed_orders(count: usize) -> Vec<GroupedVanillaOrder> {
+    (0..count as u64)
+        .map(|nonce| {
+            UserOrderBuilder::new()
+                .standing()
+                .exact()
+                .nonce(nonce)
+                .amount(100)
+                .signing_key(Some(AngstromSigner::random()))
+                .build()
+        })
+        .collect()
+}
+
+/// Every order on its own, as validations did before batching.
+#[divan::bench(consts = ORDER_COUNT)]
+fn serial<const N: usize>(bencher: divan::Bencher) {
+    bencher
+        .with_inputs(|| signed_orders(N))
+        .bench_refs(|orders| {
+            orders
+                .iter()
+                .all(|order| order.is_valid_signature_in(&ANGSTROM_DOMAIN))
+        });
+}
+
+#[divan::bench(consts = ORDER_COUNT)]
+fn batch<const N: usize>(bencher: divan::Bencher) {
+    bencher
+        .with_inputs(|| (SignatureVerifier::new(&ANGSTROM_DOMAIN), signed_orders(N)))
+        .bench_refs(|(verifier, orders)| verifier.verify_batch(orders));
+}
+
+/// A batch with a bad signature in every chunk, the worst case of isolating
+/// them.
+#[divan::bench(consts = ORDER_COUNT)]
+fn batch_with_bad_signatures<const N: usize>(bencher: divan::Bencher) {
+    bencher
+        .with_inputs(|| {
+            let mut orders = signed_orders(N);
+            for order in orders.iter_mut().step_by(32) {
+                let GroupedVanillaOrder::Standing(StandingVariants::Exact(order)) = order else {
+                    unreachable!()
+                };
+                order.meta.from = AngstromSigner::random().address();
+            }
+            (SignatureVerifier::new(&ANGSTROM_DOMAIN), orders)
+        })
+        .bench_refs(|(verifier, orders)| verifier.verify_batch(orders));
+}
@@ -1,7 +1,7 @@
 use std::{fmt::Debug, pin::Pin, sync::Arc};
 
 use alloy::{
-    primitives::{Address, U256},
+    primitives::{Address, Bytes, U256},
     sol_types::SolCall
 };
 use angstrom_metrics::validation::ValidationMetrics;
@@ -15,12 +15,11 @@ use revm::{
 };
 use tokio::runtime::Handle;
 
-use crate::{
-    common::{key_split_threadpool::KeySplitThreadpool, TokenPriceGenerator},
-    order::sim::console_log::CallDataInspector
-};
+use crate::common::{key_split_threadpool::KeySplitThreadpool, TokenPriceGenerator};
 
+pub mod trace;
 pub mod validator;
+pub use trace::*;
 pub use validator::*;
 
 pub struct BundleValidator<DB> {
@@ -28,7 +27,9 @@ pub struct BundleValidator<DB> {
     angstrom_address: Address,
     /// the address associated with this node.
     /// this will ensure the  node has access and the simulation can pass
-    node_address:     Address
+    node_address:     Address,
+    /// where the traces of failed simulations are written, if anywhere
+    traces:           Option<SimulationTraces>
 }
 
 impl<DB> BundleValidator<DB>
@@ -37,7 +38,14 @@ where
     <DB as revm::DatabaseRef>::Error: Send + Sync + Debug
 {
     pub fn new(db: Arc<DB>, angstrom_address: Address, node_address: Address) -> Self {
-        Self { db, angstrom_address, node_address }
+        Self { db, angstrom_address, node_address, traces: None }
+    }
+
+    /// Writes the traces of failed simulations into `traces`, they are only
+    /// logged otherwise.
+    pub fn with_traces(mut self, traces: SimulationTraces) -> Self {
+        self.traces = Some(traces);
+        self
     }
 
     pub fn simulate_bundle(
@@ -51,23 +59,31 @@ where
             Handle
         >,
         metrics: ValidationMetrics,
-        number: u64
+        number: u64,
+        gas_price_wei: u128
     ) {
         let node_address = self.node_address;
         let angstrom_address = self.angstrom_address;
         let db = self.db.clone();
+        let traces = self.traces.clone();
 
         let conversion_lookup = price_gen.generate_lookup_map();
 
         thread_pool.spawn_raw(Box::pin(async move {
             metrics.simulate_bundle(|| {
                 let bundle = bundle.pade_encode();
+                let calldata: Bytes =
+                    angstrom_types::contract_bindings::angstrom::Angstrom::executeCall::new((
+                        bundle.into(),
+                    ))
+                    .abi_encode()
+                    .into();
 
-                let mut console_log_inspector = CallDataInspector {};
+                let mut tracer = CallTracer::default();
 
                 let mut evm = revm::Evm::builder()
                     .with_ref_db(db.clone())
-                    .with_external_context(&mut console_log_inspector)
+                    .with_external_context(&mut tracer)
                     .with_env_with_handler_cfg(EnvWithHandlerCfg::default())
                     .append_handler_register(inspector_handle_register)
                     .modify_env(|env| {
@@ -79,12 +95,7 @@ where
                     .modify_tx_env(|tx| {
                         tx.caller = node_address;
                         tx.transact_to = TxKind::Call(angstrom_address);
-                        tx.data =
-                        angstrom_types::contract_bindings::angstrom::Angstrom::executeCall::new((
-                            bundle.into(),
-                        ))
-                        .abi_encode()
-                        .into();
+                        tx.data = calldata.clone();
                     })
                     .build();
 
@@ -103,12 +114,28 @@ where
                 };
 
                 if !result.result.is_success() {
-                    tracing::warn!(?result.result);
-                    let _ = sender.send(Err(eyre!("transaction simulation failed")));
+                    drop(evm);
+                    let trace = SimulationTrace::new(number, calldata, tracer, &result);
+                    metrics.failed_bundle_simulation(number);
+                    tracing::warn!(
+                        trace_id = %trace.id,
+                        outcome = %trace.outcome,
+                        revert_data = %trace.revert_data,
+                        failing_call = ?trace.failing_frame().map(|frame| frame.target),
+                        "bundle simulation failed"
+                    );
+                    match traces.as_ref().map(|traces| traces.insert(&trace)) {
+                        Some(Ok(path)) => tracing::info!(?path, "wrote the simulation trace"),
+                        Some(Err(e)) => tracing::error!(%e, "failed to write the simulation trace"),
+                        None => tracing::debug!(?trace, "simulation trace")
+                    }
+                    let _ = sender
+                        .send(Err(eyre!("transaction simulation failed, trace {}", trace.id)));
                     return
                 }
 
-                let res = BundleGasDetails::new(conversion_lookup, result.result.gas_used());
+                let res = BundleGasDetails::new(conversion_lookup, result.result.gas_used())
+                    .with_gas_price(gas_price_wei);
                 let _ = sender.send(Ok(res));
             });
         }))
@@ -0,0 +1,265 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf}
+};
+
+use alloy::primitives::{keccak256, Address, Bytes, U256};
+use revm::{
+    interpreter::{CallInputs, CallOutcome},
+    primitives::{ExecutionResult, ResultAndState},
+    Database, EvmContext, Inspector
+};
+use serde::{Deserialize, Serialize};
+
+use crate::order::sim::console_log::CallDataInspector;
+
+/// A call made while simulating, in the order the calls were made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallFrame {
+    /// 0 for the call into the contract
+    pub depth:    usize,
+    pub caller:   Address,
+    pub target:   Address,
+    pub input:    Bytes,
+    pub success:  bool,
+    /// what the call returned, the revert data if it reverted
+    pub output:   Bytes,
+    pub gas_used: u64
+}
+
+/// A storage slot the simulation read or wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TouchedSlot {
+    pub address: Address,
+    pub slot:    U256,
+    /// value at the block simulated on
+    pub value:   U256
+}
+
+/// What a failed bundle simulation did, to tell what went wrong in the bundle
+/// without rebuilding the state it was simulated on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationTrace {
+    /// the block the bundle was simulated on top of, then the first bytes of
+    /// the hash of the calldata
+    pub id:           String,
+    pub block_number: u64,
+    /// the calldata of the call into the contract
+    pub calldata:     Bytes,
+    /// how the simulation ended, the revert or halt reason
+    pub outcome:      String,
+    pub revert_data:  Bytes,
+    pub frames:       Vec<CallFrame>,
+    pub storage:      Vec<TouchedSlot>
+}
+
+impl SimulationTrace {
+    pub fn new(
+        block_number: u64,
+        calldata: Bytes,
+        tracer: CallTracer,
+        result: &ResultAndState
+    ) -> Self {
+        let id = format!("{block_number}-{}", alloy::hex::encode(&keccak256(&calldata)[..8]));
+        let (outcome, revert_data) = match &result.result {
+            ExecutionResult::Revert { output, .. } => ("revert".to_string(), output.clone()),
+            ExecutionResult::Halt { reason, .. } => (format!("halt: {reason:?}"), Bytes::new()),
+            ExecutionResult::Success { .. } => ("success".to_string(), Bytes::new())
+        };
+
+        let mut storage = result
+            .state
+            .iter()
+            .flat_map(|(address, account)| {
+                account.storage.iter().map(|(slot, value)| TouchedSlot {
+                    address: *address,
+                    slot:    *slot,
+                    value:   value.original_value()
+                })
+            })
+            .collect::<Vec<_>>();
+        storage.sort_by_key(|slot| (slot.address, slot.slot));
+
+        Self {
+            id,
+            block_number,
+            calldata,
+            outcome,
+            revert_data,
+            frames: tracer.into_frames(),
+            storage
+        }
+    }
+
+    /// The deepest call that failed, where the revert started.
+    pub fn failing_frame(&self) -> Option<&CallFrame> {
+        self.frames
+            .iter()
+            .filter(|frame| !frame.success)
+            .max_by_key(|frame| frame.depth)
+    }
+}
+
+/// Records every call of the simulation. Console logs of the contract are
+/// printed like the [`CallDataInspector`] does.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    frames: Vec<CallFrame>,
+    /// the frames of the calls that haven't returned yet
+    open:   Vec<usize>
+}
+
+impl CallTracer {
+    pub fn into_frames(self) -> Vec<CallFrame> {
+        self.frames
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallTracer {
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs
+    ) -> Option<CallOutcome> {
+        CallDataInspector.call(context, inputs);
+
+        self.open.push(self.frames.len());
+        self.frames.push(CallFrame {
+            depth:    self.open.len() - 1,
+            caller:   inputs.caller,
+            target:   inputs.target_address,
+            input:    inputs.input.clone(),
+            success:  false,
+            output:   Bytes::new(),
+            gas_used: 0
+        });
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome
+    ) -> CallOutcome {
+        if let Some(frame) = self.open.pop().and_then(|i| self.frames.get_mut(i)) {
+            frame.success = outcome.result.is_ok();
+            frame.output = outcome.result.output.clone();
+            frame.gas_used = outcome.result.gas.spent();
+        }
+        outcome
+    }
+}
+
+/// Where the traces of the failed simulations are kept, one file per trace
+/// named by its id.
+#[derive(Debug, Clone)]
+pub struct SimulationTraces {
+    dir: PathBuf
+}
+
+impl SimulationTraces {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes the trace, returns the file it's in.
+    pub fn insert(&self, trace: &SimulationTrace) -> eyre::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(&trace.id);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(trace)?)?;
+        std::fs::rename(tmp, &path)?;
+
+        Ok(path)
+    }
+
+    pub fn trace(&self, id: &str) -> eyre::Result<Option<SimulationTrace>> {
+        match std::fs::read(self.path(id)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into())
+        }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, hex};
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        inspector_handle_register,
+        primitives::{AccountInfo, Bytecode, TxKind}
+    };
+
+    use super::*;
+
+    const CONTRACT: Address = address!("00000000000000000000000000000000000000aa");
+
+    fn simulate(tracer: &mut CallTracer, calldata: Bytes) -> ResultAndState {
+        // reads slot 1, then reverts with 42
+        let code = hex!("60015450602a60005260206000fd");
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            CONTRACT,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&code)))
+        );
+        db.insert_account_storage(CONTRACT, U256::from(1), U256::from(7))
+            .unwrap();
+
+        let mut evm = revm::Evm::builder()
+            .with_db(db)
+            .with_external_context(tracer)
+            .append_handler_register(inspector_handle_register)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TxKind::Call(CONTRACT);
+                tx.data = calldata;
+            })
+            .build();
+        evm.transact().unwrap()
+    }
+
+    #[test]
+    fn captures_the_revert_of_the_simulation() {
+        let calldata = Bytes::from_static(&[1, 2, 3, 4]);
+        let mut tracer = CallTracer::default();
+        let result = simulate(&mut tracer, calldata.clone());
+        let trace = SimulationTrace::new(10, calldata.clone(), tracer, &result);
+
+        assert!(trace.id.starts_with("10-"));
+        assert_eq!(trace.outcome, "revert");
+        assert_eq!(trace.revert_data, Bytes::from(U256::from(42).to_be_bytes::<32>()));
+
+        let frame = trace.failing_frame().unwrap();
+        assert_eq!((frame.depth, frame.target), (0, CONTRACT));
+        assert_eq!(frame.input, calldata);
+        assert_eq!(frame.output, trace.revert_data);
+        assert!(trace.storage.contains(&TouchedSlot {
+            address: CONTRACT,
+            slot:    U256::from(1),
+            value:   U256::from(7)
+        }));
+    }
+
+    #[test]
+    fn persists_traces_by_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let traces = SimulationTraces::new(dir.path().join("traces"));
+        let mut tracer = CallTracer::default();
+        let result = simulate(&mut tracer, Bytes::new());
+        let trace = SimulationTrace::new(10, Bytes::new(), tracer, &result);
+
+        assert_eq!(traces.trace(&trace.id).unwrap(), None);
+        let path = traces.insert(&trace).unwrap();
+        assert!(path.ends_with(format!("{}.json", trace.id)));
+        assert_eq!(traces.trace(&trace.id).unwrap(), Some(trace));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+use angstrom_types::block_fees::BlockFees;
+
+/// Amount of blocks the fee history is kept for.
+const FEE_HISTORY_BLOCKS: usize = 20;
+/// Percentile the gas of bundles is priced at. Bundles land in the next block,
+/// so they don't need much of a margin.
+pub const BUNDLE_GAS_PERCENTILE: u8 = 50;
+/// Percentile the gas of orders is priced at. Orders can sit in the book for a
+/// couple of blocks before they are filled.
+pub const ORDER_GAS_PERCENTILE: u8 = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPriceForecast {
+    pub base_fee:     u64,
+    pub priority_fee: u128
+}
+
+impl GasPriceForecast {
+    /// Wei a unit of gas costs.
+    pub fn gas_price(&self) -> u128 {
+        self.base_fee as u128 + self.priority_fee
+    }
+}
+
+/// Forecasts the gas price from the fees of the recent canonical blocks.
+///
+/// The base fee is never forecast below what EIP-1559 sets for the next block,
+/// on top of that the recent base and priority fees are taken at the requested
+/// percentile.
+#[derive(Debug, Clone)]
+pub struct GasPriceModel {
+    history: VecDeque<BlockFees>,
+    blocks:  usize
+}
+
+impl Default for GasPriceModel {
+    fn default() -> Self {
+        Self::new(FEE_HISTORY_BLOCKS)
+    }
+}
+
+impl GasPriceModel {
+    pub fn new(blocks: usize) -> Self {
+        Self { history: VecDeque::with_capacity(blocks), blocks: blocks.max(1) }
+    }
+
+    /// Adds the fees of a new canonical block. On a reorg, the blocks the new
+    /// one replaces are dropped.
+    pub fn on_block(&mut self, fees: BlockFees) {
+        while self
+            .history
+            .back()
+            .is_some_and(|last| last.block_num >= fees.block_num)
+        {
+            self.history.pop_back();
+        }
+
+        self.history.push_back(fees);
+        while self.history.len() > self.blocks {
+            self.history.pop_front();
+        }
+    }
+
+    /// [`None`] until the first block was seen.
+    pub fn forecast(&self, percentile: u8) -> Option<GasPriceForecast> {
+        let next_base_fee = self.history.back()?.next_base_fee();
+        let base_fee = nearest_rank(self.history.iter().map(|fees| fees.base_fee), percentile)
+            .unwrap_or_default()
+            .max(next_base_fee);
+        let priority_fee =
+            nearest_rank(self.history.iter().filter_map(|fees| fees.priority_fee), percentile)
+                .unwrap_or_default();
+
+        Some(GasPriceForecast { base_fee, priority_fee })
+    }
+
+    /// Wei a unit of gas is expected to cost. Until the first block was seen,
+    /// gas is priced at a single wei.
+    pub fn gas_price(&self, percentile: u8) -> u128 {
+        self.forecast(percentile)
+            .map(|forecast| forecast.gas_price())
+            .unwrap_or(1)
+    }
+}
+
+fn nearest_rank<T: Ord>(values: impl Iterator<Item = T>, percentile: u8) -> Option<T> {
+    let mut values = values.collect::<Vec<_>>();
+    if values.is_empty() {
+        return None
+    }
+    values.sort_unstable();
+
+    let rank = (values.len() * percentile.min(100) as usize).div_ceil(100);
+    Some(values.swap_remove(rank.saturating_sub(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(block_num: u64, base_fee: u64, priority_fee: Option<u128>) -> BlockFees {
+        // exactly at the target, the next base fee stays the same
+        BlockFees { block_num, base_fee, gas_used: 15_000_000, gas_limit: 30_000_000, priority_fee }
+    }
+
+    #[test]
+    fn forecasts_percentiles_of_history() {
+        let mut model = GasPriceModel::new(4);
+        assert_eq!(model.forecast(50), None);
+        assert_eq!(model.gas_price(50), 1);
+
+        for (block, base_fee) in [(1, 100), (2, 40), (3, 30), (4, 20), (5, 10)] {
+            model.on_block(fees(block, base_fee, Some(base_fee as u128 / 10)));
+        }
+        // the first block fell out of the history
+        assert_eq!(model.forecast(100), Some(GasPriceForecast { base_fee: 40, priority_fee: 4 }));
+        assert_eq!(model.forecast(0), Some(GasPriceForecast { base_fee: 10, priority_fee: 1 }));
+
+        // a full block raises the next base fee by 12.5%, which is the floor
+        model.on_block(BlockFees { gas_used: 30_000_000, ..fees(5, 10, Some(1)) });
+        assert_eq!(model.forecast(0), Some(GasPriceForecast { base_fee: 11, priority_fee: 1 }));
+    }
+
+    #[test]
+    fn reorg_replaces_blocks() {
+        let mut model = GasPriceModel::new(4);
+        model.on_block(fees(1, 10, None));
+        model.on_block(fees(2, 1000, None));
+        model.on_block(fees(2, 10, None));
+
+        assert_eq!(model.forecast(100), Some(GasPriceForecast { base_fee: 10, priority_fee: 0 }));
+    }
+}
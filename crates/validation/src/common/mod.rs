@@ -2,7 +2,7 @@ use std::{pin::Pin, task::Poll};
 
 use alloy::primitives::Address;
 use angstrom_metrics::validation::ValidationMetrics;
-use angstrom_types::pair_with_price::PairsWithPrice;
+use angstrom_types::{block_fees::BlockFees, pair_with_price::PairsWithPrice};
 use futures::{Future, Stream, StreamExt};
 use tokio::runtime::Handle;
 
@@ -12,6 +12,12 @@ use key_split_threadpool::KeySplitThreadpool;
 pub mod db;
 pub use db::*;
 
+pub mod gas_model;
+pub use gas_model::*;
+
+pub mod state_backend;
+pub use state_backend::*;
+
 pub mod token_pricing;
 pub use token_pricing::*;
 
@@ -20,6 +26,8 @@ pub use token_pricing::*;
 pub struct SharedTools {
     pub token_pricing:   TokenPriceGenerator,
     token_price_updater: Pin<Box<dyn Stream<Item = Vec<PairsWithPrice>> + Send + Sync + 'static>>,
+    pub gas_model:       GasPriceModel,
+    gas_price_updater:   Option<Pin<Box<dyn Stream<Item = BlockFees> + Send + Sync + 'static>>>,
     pub thread_pool:
         KeySplitThreadpool<Address, Pin<Box<dyn Future<Output = ()> + Send + Sync>>, Handle>,
     pub metrics:         ValidationMetrics
@@ -37,7 +45,24 @@ impl SharedTools {
             Handle
         >
     ) -> Self {
-        Self { token_price_updater, token_pricing, thread_pool, metrics: ValidationMetrics::new() }
+        Self {
+            token_price_updater,
+            token_pricing,
+            gas_model: GasPriceModel::default(),
+            gas_price_updater: None,
+            thread_pool,
+            metrics: ValidationMetrics::new()
+        }
+    }
+
+    /// Prices gas by the fees of the blocks of the stream. Without it, a unit
+    /// of gas is priced at a single wei.
+    pub fn with_gas_price_updates(
+        mut self,
+        updates: Pin<Box<dyn Stream<Item = BlockFees> + Send + Sync + 'static>>
+    ) -> Self {
+        self.gas_price_updater = Some(updates);
+        self
     }
 
     pub fn token_pricing_ref(&self) -> &TokenPriceGenerator {
@@ -51,8 +76,16 @@ impl SharedTools {
         &mut self.thread_pool
     }
 
+    /// Token prices for validating orders, with gas priced at
+    /// [`ORDER_GAS_PERCENTILE`].
     pub fn token_pricing_snapshot(&self) -> TokenPriceGenerator {
-        self.token_pricing.clone()
+        self.token_pricing
+            .clone()
+            .with_gas_price(self.gas_model.gas_price(ORDER_GAS_PERCENTILE))
+    }
+
+    pub fn bundle_gas_price(&self) -> u128 {
+        self.gas_model.gas_price(BUNDLE_GAS_PERCENTILE)
     }
 }
 
@@ -70,6 +103,12 @@ impl Future for SharedTools {
             self.token_pricing.apply_update(updates);
         }
 
+        if let Some(updater) = self.gas_price_updater.as_mut() {
+            while let Poll::Ready(Some(fees)) = updater.poll_next_unpin(cx) {
+                self.gas_model.on_block(fees);
+            }
+        }
+
         Poll::Pending
     }
 }
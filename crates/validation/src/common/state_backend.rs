@@ -0,0 +1,376 @@
+//! The state orders are validated against.
+//!
+//! Validation reads accounts, balances and approvals straight from the
+//! database of the reth node it runs in, so a read doesn't cost a trip through
+//! the rpc server. Reads the database fails, for state it pruned for example,
+//! are retried against a remote backend if one is configured. Every read is
+//! timed per backend, so the two can be compared.
+//!
+//! The slots a wave of orders is validated against are read in batches, in a
+//! single view of the state locally and in chunked rpc batches remotely.
+use std::{fmt::Debug, future::IntoFuture, sync::Arc, time::Duration};
+
+use alloy::{
+    eips::BlockHashOrNumber,
+    primitives::{Address, BlockNumber, B256, U256},
+    providers::Provider,
+    rpc::types::{BlockNumberOrTag, BlockTransactionsKind}
+};
+use angstrom_metrics::validation::StateReadMetrics;
+use angstrom_types::reth_db_wrapper::RethDbWrapper;
+use reth_chainspec::ChainInfo;
+use reth_provider::{
+    BlockHashReader, BlockNumReader, ProviderResult, StateProvider, StateProviderFactory
+};
+use revm::{
+    primitives::{AccountInfo, Bytecode},
+    DatabaseRef
+};
+
+const LOCAL: &str = "local";
+const REMOTE: &str = "remote";
+
+/// Most slots read in a single rpc batch.
+pub const MAX_BATCH_SLOTS: usize = 256;
+/// Times an rpc batch is sent before its read fails.
+const BATCH_ATTEMPTS: u32 = 3;
+/// Wait before resending a failed rpc batch, multiplied by the attempt.
+const BATCH_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A database that reads many storage slots in one go.
+pub trait BatchStorageRead: DatabaseRef {
+    /// Reads `slots`, returning their values in the same order. Reads them one
+    /// by one unless the backend has a cheaper way.
+    fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        slots
+            .iter()
+            .map(|(address, index)| self.storage_ref(*address, *index))
+            .collect()
+    }
+}
+
+impl<DB> BatchStorageRead for RethDbWrapper<DB>
+where
+    DB: StateProviderFactory + Unpin + Clone + 'static
+{
+    fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        // one view of the state for the batch, instead of one per slot
+        let state = self.latest()?;
+        slots
+            .iter()
+            .map(|(address, index)| {
+                Ok(state
+                    .storage(*address, B256::new(index.to_be_bytes()))?
+                    .unwrap_or_default())
+            })
+            .collect()
+    }
+}
+
+/// Reads the state from `local`, falling back to `remote` for the reads it
+/// fails. Block numbers and hashes are always read from `local`.
+#[derive(Clone)]
+pub struct FallbackStateDb<Local, Remote> {
+    local:   Local,
+    remote:  Option<Remote>,
+    metrics: StateReadMetrics
+}
+
+impl<Local, Remote> FallbackStateDb<Local, Remote>
+where
+    Local: DatabaseRef,
+    Local::Error: Debug,
+    Remote: DatabaseRef,
+    Remote::Error: Debug
+{
+    pub fn new(local: Local) -> Self {
+        Self { local, remote: None, metrics: StateReadMetrics::new() }
+    }
+
+    pub fn with_remote(mut self, remote: Remote) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    fn read<T>(
+        &self,
+        read: &'static str,
+        local: impl FnOnce(&Local) -> Result<T, Local::Error>,
+        remote: impl FnOnce(&Remote) -> Result<T, Remote::Error>
+    ) -> eyre::Result<T> {
+        let local_err = match self
+            .metrics
+            .measure_read(LOCAL, read, || local(&self.local))
+        {
+            Ok(value) => return Ok(value),
+            Err(e) => e
+        };
+        let Some(backend) = self.remote.as_ref() else {
+            return Err(eyre::eyre!("{read} read failed: {local_err:?}"))
+        };
+
+        tracing::debug!(read, err = ?local_err, "local state read failed, retrying remotely");
+        self.metrics.fell_back(read);
+        self.metrics
+            .measure_read(REMOTE, read, || remote(backend))
+            .map_err(|e| {
+                eyre::eyre!("{read} read failed locally ({local_err:?}) and remotely ({e:?})")
+            })
+    }
+}
+
+impl<Local, Remote> DatabaseRef for FallbackStateDb<Local, Remote>
+where
+    Local: DatabaseRef,
+    Local::Error: Debug,
+    Remote: DatabaseRef,
+    Remote::Error: Debug
+{
+    type Error = eyre::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.read("account", |db| db.basic_ref(address), |db| db.basic_ref(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.read("code", |db| db.code_by_hash_ref(code_hash), |db| db.code_by_hash_ref(code_hash))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.read(
+            "storage",
+            |db| db.storage_ref(address, index),
+            |db| db.storage_ref(address, index)
+        )
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.read("block_hash", |db| db.block_hash_ref(number), |db| db.block_hash_ref(number))
+    }
+}
+
+impl<Local, Remote> BatchStorageRead for FallbackStateDb<Local, Remote>
+where
+    Local: BatchStorageRead,
+    Local::Error: Debug,
+    Remote: BatchStorageRead,
+    Remote::Error: Debug
+{
+    fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        self.read("storage_batch", |db| db.storage_batch(slots), |db| db.storage_batch(slots))
+    }
+}
+
+impl<Local: BlockNumReader, Remote: Send + Sync> BlockNumReader for FallbackStateDb<Local, Remote> {
+    fn chain_info(&self) -> ProviderResult<ChainInfo> {
+        self.local.chain_info()
+    }
+
+    fn block_number(&self, hash: B256) -> ProviderResult<Option<BlockNumber>> {
+        self.local.block_number(hash)
+    }
+
+    fn convert_number(&self, id: BlockHashOrNumber) -> ProviderResult<Option<B256>> {
+        self.local.convert_number(id)
+    }
+
+    fn best_block_number(&self) -> ProviderResult<BlockNumber> {
+        self.local.best_block_number()
+    }
+
+    fn last_block_number(&self) -> ProviderResult<BlockNumber> {
+        self.local.last_block_number()
+    }
+
+    fn convert_hash_or_number(&self, id: BlockHashOrNumber) -> ProviderResult<Option<BlockNumber>> {
+        self.local.convert_hash_or_number(id)
+    }
+}
+
+impl<Local: BlockHashReader, Remote: Send + Sync> BlockHashReader
+    for FallbackStateDb<Local, Remote>
+{
+    fn block_hash(&self, number: BlockNumber) -> ProviderResult<Option<B256>> {
+        self.local.block_hash(number)
+    }
+
+    fn convert_block_hash(
+        &self,
+        hash_or_number: BlockHashOrNumber
+    ) -> ProviderResult<Option<B256>> {
+        self.local.convert_block_hash(hash_or_number)
+    }
+
+    fn canonical_hashes_range(
+        &self,
+        start: BlockNumber,
+        end: BlockNumber
+    ) -> ProviderResult<Vec<B256>> {
+        self.local.canonical_hashes_range(start, end)
+    }
+}
+
+/// Reads the state over rpc at the block the `local` node is at, so the
+/// reads it falls back to are of the same block as the ones it didn't. Has to
+/// be read from a multi threaded runtime, the reads block the thread they're
+/// made on.
+#[derive(Clone)]
+pub struct RpcStateDb<P, B> {
+    provider: Arc<P>,
+    local:    B
+}
+
+impl<P: Provider, B: BlockNumReader> RpcStateDb<P, B> {
+    pub fn new(provider: Arc<P>, local: B) -> Self {
+        Self { provider, local }
+    }
+
+    /// The block of the local node.
+    fn block(&self) -> eyre::Result<BlockNumber> {
+        Ok(self.local.best_block_number()?)
+    }
+
+    fn block_on<F: IntoFuture>(f: F) -> F::Output {
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::block_in_place(|| handle.block_on(f.into_future()))
+    }
+
+    /// Reads `chunk` at `block` in a single rpc batch, resending it if it
+    /// fails.
+    async fn storage_chunk(
+        &self,
+        chunk: &[(Address, U256)],
+        block: BlockNumber
+    ) -> eyre::Result<Vec<U256>> {
+        let mut attempt = 1;
+        loop {
+            match self.send_storage_batch(chunk, block).await {
+                Ok(values) => return Ok(values),
+                Err(e) if attempt < BATCH_ATTEMPTS => {
+                    tracing::debug!(attempt, slots = chunk.len(), err = ?e, "storage batch failed");
+                    tokio::time::sleep(BATCH_RETRY_BACKOFF * attempt).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e)
+            }
+        }
+    }
+
+    async fn send_storage_batch(
+        &self,
+        chunk: &[(Address, U256)],
+        block: BlockNumber
+    ) -> eyre::Result<Vec<U256>> {
+        let client = self.provider.client();
+        let mut batch = client.new_batch();
+        let values = chunk
+            .iter()
+            .map(|(address, index)| {
+                batch.add_call::<_, U256>(
+                    "eth_getStorageAt",
+                    &(*address, *index, BlockNumberOrTag::Number(block))
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.send().await?;
+
+        Ok(futures::future::try_join_all(values).await?)
+    }
+}
+
+impl<P: Provider, B: BlockNumReader> DatabaseRef for RpcStateDb<P, B> {
+    type Error = eyre::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let block = self.block()?;
+        let account = Self::block_on(self.provider.get_account(address).number(block))?;
+        let code = Self::block_on(self.provider.get_code_at(address).number(block))?;
+
+        Ok(Some(AccountInfo {
+            balance:   account.balance,
+            nonce:     account.nonce,
+            code_hash: account.code_hash,
+            code:      Some(Bytecode::new_raw(code))
+        }))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // the code is loaded along with its account
+        Err(eyre::eyre!("code {code_hash} can't be read by its hash over rpc"))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let block = self.block()?;
+        Ok(Self::block_on(self.provider.get_storage_at(address, index).number(block))?)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let block =
+            Self::block_on(self.provider.get_block_by_number(
+                BlockNumberOrTag::Number(number),
+                BlockTransactionsKind::Hashes
+            ))?
+            .ok_or_else(|| eyre::eyre!("block {number} not found"))?;
+
+        Ok(block.header.hash)
+    }
+}
+
+impl<P: Provider, B: BlockNumReader> BatchStorageRead for RpcStateDb<P, B> {
+    fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        // every chunk of the batch is read at the same block
+        let block = self.block()?;
+        let mut values = Vec::with_capacity(slots.len());
+        for chunk in slots.chunks(MAX_BATCH_SLOTS) {
+            values.extend(Self::block_on(self.storage_chunk(chunk, block))?);
+        }
+
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::db::{CacheDB, EmptyDB};
+
+    use super::*;
+
+    /// A database that fails every read.
+    #[derive(Clone)]
+    struct Unavailable;
+
+    impl DatabaseRef for Unavailable {
+        type Error = &'static str;
+
+        fn basic_ref(&self, _: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            Err("unavailable")
+        }
+
+        fn code_by_hash_ref(&self, _: B256) -> Result<Bytecode, Self::Error> {
+            Err("unavailable")
+        }
+
+        fn storage_ref(&self, _: Address, _: U256) -> Result<U256, Self::Error> {
+            Err("unavailable")
+        }
+
+        fn block_hash_ref(&self, _: u64) -> Result<B256, Self::Error> {
+            Err("unavailable")
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_remote_backend() {
+        let address = Address::random();
+        let mut remote = CacheDB::new(EmptyDB::default());
+        remote
+            .insert_account_storage(address, U256::from(1), U256::from(7))
+            .unwrap();
+
+        let db = FallbackStateDb::new(Unavailable);
+        assert!(db.storage_ref(address, U256::from(1)).is_err());
+
+        let db = db.with_remote(remote);
+        assert_eq!(db.storage_ref(address, U256::from(1)).unwrap(), U256::from(7));
+    }
+}
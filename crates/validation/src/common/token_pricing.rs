@@ -28,7 +28,10 @@ pub struct TokenPriceGenerator {
     prev_prices:         HashMap<PoolId, VecDeque<PairsWithPrice>>,
     pair_to_pool:        HashMap<(Address, Address), PoolId>,
     cur_block:           u64,
-    blocks_to_avg_price: u64
+    blocks_to_avg_price: u64,
+    /// wei a unit of gas costs, from the gas price model. a single wei if
+    /// unset
+    gas_price_wei:       Option<u128>
 }
 
 impl TokenPriceGenerator {
@@ -44,7 +47,8 @@ impl TokenPriceGenerator {
         Loader: PoolDataLoader<PoolId> + Default + Clone + Send + Sync + 'static
     {
         let mut pair_to_pool = HashMap::default();
-        for (key, pool) in uni.iter() {
+        let uni = uni.all();
+        for (key, pool) in &uni {
             let pool = pool.read().unwrap();
             pair_to_pool.insert((pool.token0, pool.token1), *key);
         }
@@ -96,7 +100,22 @@ impl TokenPriceGenerator {
             })
             .await;
 
-        Ok(Self { prev_prices: pools, cur_block: current_block, pair_to_pool, blocks_to_avg_price })
+        Ok(Self {
+            prev_prices: pools,
+            cur_block: current_block,
+            pair_to_pool,
+            blocks_to_avg_price,
+            gas_price_wei: None
+        })
+    }
+
+    pub fn with_gas_price(mut self, gas_price_wei: u128) -> Self {
+        self.gas_price_wei = Some(gas_price_wei);
+        self
+    }
+
+    pub fn gas_price_wei(&self) -> u128 {
+        self.gas_price_wei.unwrap_or(1)
     }
 
     pub fn generate_lookup_map(&self) -> HashMap<(Address, Address), Ray> {
@@ -353,7 +372,8 @@ pub mod test {
             cur_block:           0,
             prev_prices:         prices,
             pair_to_pool:        pairs_to_key,
-            blocks_to_avg_price: BLOCKS_TO_AVG_PRICE
+            blocks_to_avg_price: BLOCKS_TO_AVG_PRICE,
+            gas_price_wei:       None
         }
     }
 
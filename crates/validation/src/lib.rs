@@ -1,18 +1,21 @@
 pub mod bundle;
 pub mod common;
 pub mod order;
+pub mod queue;
 pub mod validator;
 
 use std::{
     fmt::Debug,
+    path::PathBuf,
     sync::{atomic::AtomicU64, Arc}
 };
 
 use alloy::primitives::Address;
 use angstrom_types::{
-    contract_payloads::angstrom::AngstromPoolConfigStore, pair_with_price::PairsWithPrice
+    block_fees::BlockFees, contract_payloads::angstrom::AngstromPoolConfigStore,
+    pair_with_price::PairsWithPrice, primitive::angstrom_domain
 };
-use bundle::BundleValidator;
+use bundle::{BundleValidator, SimulationTraces};
 use common::SharedTools;
 use reth_provider::CanonStateNotificationStream;
 use tokio::sync::mpsc::UnboundedReceiver;
@@ -20,11 +23,14 @@ use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 use validator::Validator;
 
 use crate::{
-    common::{key_split_threadpool::KeySplitThreadpool, TokenPriceGenerator},
+    common::{key_split_threadpool::KeySplitThreadpool, BatchStorageRead, TokenPriceGenerator},
     order::{
         order_validator::OrderValidator,
         sim::SimValidation,
-        state::{db_state_utils::FetchUtils, pools::AngstromPoolsTracker}
+        state::{
+            bond::SearcherBondConfig, db_state_utils::FetchUtils, hooks::HookAllowlist,
+            pools::AngstromPoolsTracker
+        }
     },
     validator::{ValidationClient, ValidationRequest}
 };
@@ -33,16 +39,21 @@ const MAX_VALIDATION_PER_ADDR: usize = 2;
 
 #[allow(clippy::too_many_arguments)]
 pub fn init_validation<
-    DB: Unpin + Clone + 'static + reth_provider::BlockNumReader + revm::DatabaseRef + Send + Sync
+    DB: Unpin + Clone + 'static + reth_provider::BlockNumReader + BatchStorageRead + Send + Sync
 >(
     db: DB,
     current_block: u64,
+    chain_id: u64,
     angstrom_address: Address,
     node_address: Address,
     state_notification: CanonStateNotificationStream,
+    fee_notification: CanonStateNotificationStream,
     uniswap_pools: SyncedUniswapPools,
     price_generator: TokenPriceGenerator,
     pool_store: Arc<AngstromPoolConfigStore>,
+    searcher_bond: Option<SearcherBondConfig>,
+    hook_allowlist: Option<HookAllowlist>,
+    sim_trace_dir: Option<PathBuf>,
     validator_rx: UnboundedReceiver<ValidationRequest>
 ) where
     <DB as revm::DatabaseRef>::Error: Send + Sync + Debug
@@ -68,12 +79,24 @@ pub fn init_validation<
         let update_stream =
             PairsWithPrice::into_price_update_stream(angstrom_address, state_notification);
 
-        let order_validator =
-            rt.block_on(OrderValidator::new(sim, current_block, pools, fetch, uniswap_pools));
+        let mut order_validator = rt
+            .block_on(OrderValidator::new(sim, current_block, pools, fetch, uniswap_pools))
+            .with_domain(angstrom_domain(chain_id, angstrom_address));
+        if let Some(bond) = searcher_bond {
+            order_validator = order_validator.with_searcher_bond(bond);
+        }
+        if let Some(allowlist) = hook_allowlist {
+            order_validator = order_validator.with_hook_allowlist(allowlist);
+        }
 
-        let bundle_validator =
+        let mut bundle_validator =
             BundleValidator::new(revm_lru.clone(), angstrom_address, node_address);
-        let shared_utils = SharedTools::new(price_generator, Box::pin(update_stream), thread_pool);
+        if let Some(dir) = sim_trace_dir {
+            bundle_validator = bundle_validator.with_traces(SimulationTraces::new(dir));
+        }
+        let fee_stream = BlockFees::into_fee_update_stream(fee_notification);
+        let shared_utils = SharedTools::new(price_generator, Box::pin(update_stream), thread_pool)
+            .with_gas_price_updates(Box::pin(fee_stream));
 
         rt.block_on(async {
             Validator::new(validator_rx, order_validator, bundle_validator, shared_utils).await
@@ -2,6 +2,7 @@ use std::{fmt::Debug, future::Future, pin::Pin};
 
 use alloy::primitives::{Address, B256, U256};
 use angstrom_types::{
+    contract_payloads::tob::{TobSimulation, TobStateOverrides},
     orders::OrderOrigin,
     primitive::OrderPoolNewOrderResult,
     sol_bindings::{
@@ -13,11 +14,15 @@ use angstrom_types::{
     }
 };
 use sim::SimValidation;
+use state::account::NonceCollision;
 use tokio::sync::oneshot::{channel, Sender};
 
-use crate::{common::TokenPriceGenerator, validator::ValidationRequest};
+use crate::{
+    common::TokenPriceGenerator, queue::ValidationBackpressure, validator::ValidationRequest
+};
 
 pub mod order_validator;
+pub mod signatures;
 pub mod sim;
 pub mod state;
 
@@ -32,6 +37,13 @@ pub type ValidationsFuture<'a> =
 pub type GasEstimationFuture<'a> =
     Pin<Box<dyn Future<Output = Result<(u64, U256), String>> + Send + Sync + 'a>>;
 
+pub type NonceCheckFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), NonceCollision>> + Send + Sync + 'a>>;
+
+pub type NextNonceFuture<'a> = Pin<Box<dyn Future<Output = Option<u64>> + Send + Sync + 'a>>;
+
+pub type TobSimulationFuture<'a> = Pin<Box<dyn Future<Output = TobSimulation> + Send + Sync + 'a>>;
+
 pub enum OrderValidationRequest {
     ValidateOrder(Sender<OrderValidationResults>, AllOrders, OrderOrigin)
 }
@@ -243,6 +255,31 @@ pub trait OrderValidatorHandle: Send + Sync + Clone + Debug + Unpin + 'static {
 
     /// estimates gas usage for order
     fn estimate_gas(&self, order: AllOrders) -> GasEstimationFuture;
+
+    /// checks that the nonce is free to be used by a new standing order with
+    /// the hash, following the replacement rule of the pool
+    fn check_nonce(&self, user: Address, nonce: u64, order_hash: B256) -> NonceCheckFuture;
+
+    /// suggests the next free nonce for a standing order of the user
+    fn next_nonce(&self, user: Address) -> NextNonceFuture;
+
+    /// simulates the top of block order against the pool with the overrides
+    /// applied, nothing of the order is kept
+    fn simulate_tob(
+        &self,
+        order: TopOfBlockOrder,
+        overrides: TobStateOverrides
+    ) -> TobSimulationFuture;
+
+    /// validates an order that only another node holds against the state on
+    /// chain alone, so every node comes to the same result. nothing of the
+    /// order is kept
+    fn check_order(&self, order: Self::Order) -> ValidationFuture;
+
+    /// errors while the validator is too backed up to take in new orders
+    fn backpressure(&self) -> Result<(), ValidationBackpressure> {
+        Ok(())
+    }
 }
 
 impl OrderValidatorHandle for ValidationClient {
@@ -268,7 +305,9 @@ impl OrderValidatorHandle for ValidationClient {
     }
 
     fn validate_order(&self, origin: OrderOrigin, transaction: Self::Order) -> ValidationFuture {
+        let queued = self.1.enter();
         Box::pin(async move {
+            let _queued = queued;
             let (tx, rx) = channel();
             let _ = self
                 .0
@@ -295,4 +334,56 @@ impl OrderValidatorHandle for ValidationClient {
             }
         })
     }
+
+    fn check_nonce(&self, user: Address, nonce: u64, order_hash: B256) -> NonceCheckFuture {
+        Box::pin(async move {
+            let (tx, rx) = channel();
+            let _ =
+                self.0
+                    .send(ValidationRequest::CheckNonce { sender: tx, user, nonce, order_hash });
+
+            rx.await.unwrap()
+        })
+    }
+
+    fn next_nonce(&self, user: Address) -> NextNonceFuture {
+        Box::pin(async move {
+            let (tx, rx) = channel();
+            let _ = self
+                .0
+                .send(ValidationRequest::NextNonce { sender: tx, user });
+
+            rx.await.unwrap()
+        })
+    }
+
+    fn simulate_tob(
+        &self,
+        order: TopOfBlockOrder,
+        overrides: TobStateOverrides
+    ) -> TobSimulationFuture {
+        Box::pin(async move {
+            let (tx, rx) = channel();
+            let _ = self
+                .0
+                .send(ValidationRequest::SimulateTob { sender: tx, order, overrides });
+
+            rx.await.unwrap()
+        })
+    }
+
+    fn check_order(&self, order: Self::Order) -> ValidationFuture {
+        Box::pin(async move {
+            let (tx, rx) = channel();
+            let _ = self
+                .0
+                .send(ValidationRequest::CheckOrder { sender: tx, order });
+
+            rx.await.unwrap()
+        })
+    }
+
+    fn backpressure(&self) -> Result<(), ValidationBackpressure> {
+        self.1.admit()
+    }
 }
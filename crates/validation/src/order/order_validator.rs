@@ -4,16 +4,29 @@ use std::{
     sync::{atomic::AtomicU64, Arc}
 };
 
-use alloy::primitives::{Address, BlockNumber, B256};
+use alloy::{
+    primitives::{Address, BlockNumber, B256},
+    sol_types::Eip712Domain
+};
 use angstrom_metrics::validation::ValidationMetrics;
+use angstrom_types::{
+    contract_payloads::tob::{TobSimulation, TobStateOverrides},
+    sol_bindings::{grouped_orders::AllOrders, rpc_orders::TopOfBlockOrder, RawPoolOrder}
+};
 use futures::Future;
 use tokio::runtime::Handle;
+use tracing::Instrument;
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 
 use super::{
+    signatures::MIN_BATCH,
     sim::SimValidation,
     state::{
-        account::user::UserAddress, db_state_utils::StateFetchUtils, pools::PoolsTracker,
+        account::{user::UserAddress, NonceCollision},
+        bond::SearcherBondConfig,
+        db_state_utils::StateFetchUtils,
+        hooks::HookAllowlist,
+        pools::PoolsTracker,
         StateValidation
     },
     OrderValidationRequest
@@ -48,6 +61,25 @@ where
         Self { state, sim, block_number }
     }
 
+    /// Only accepts orders signed in `domain`.
+    pub fn with_domain(mut self, domain: Eip712Domain) -> Self {
+        self.state = self.state.with_domain(domain);
+        self
+    }
+
+    /// Only accepts top of block orders of searchers that posted `config`'s
+    /// bond.
+    pub fn with_searcher_bond(mut self, config: SearcherBondConfig) -> Self {
+        self.state = self.state.with_searcher_bond(config);
+        self
+    }
+
+    /// Only accepts composable orders that call the hooks of the allowlist.
+    pub fn with_hook_allowlist(mut self, allowlist: HookAllowlist) -> Self {
+        self.state = self.state.with_hook_allowlist(allowlist);
+        self
+    }
+
     pub fn on_new_block(
         &mut self,
         block_number: BlockNumber,
@@ -59,6 +91,139 @@ where
         self.state.new_block(completed_orders, address_changes);
     }
 
+    pub fn check_nonce(
+        &self,
+        user: Address,
+        nonce: u64,
+        order_hash: B256
+    ) -> Result<(), NonceCollision> {
+        self.state.check_nonce(user, nonce, order_hash)
+    }
+
+    pub fn next_free_nonce(&self, user: Address) -> Option<u64> {
+        self.state.next_free_nonce(user)
+    }
+
+    /// Reads the state of a wave of orders in the background, so the reads of
+    /// their validations are served from the cache once they get a slot, which
+    /// can take a while when a sender sends a burst.
+    pub fn prefetch(
+        &self,
+        orders: Vec<AllOrders>,
+        thread_pool: &mut KeySplitThreadpool<
+            UserAddress,
+            Pin<Box<dyn Future<Output = ()> + Send + Sync>>,
+            Handle
+        >
+    ) {
+        if orders.is_empty() {
+            return
+        }
+        let state = self.state.clone();
+        thread_pool.spawn_raw(Box::pin(async move { state.prefetch(&orders) }));
+    }
+
+    /// Verifies the signatures of a wave of orders in one batch ahead of their
+    /// validations, which then don't recover them again. The batch runs on
+    /// the blocking pool, validations that get a slot before it's done recover
+    /// their signature themselves.
+    pub fn verify_signatures(
+        &self,
+        orders: Vec<AllOrders>,
+        thread_pool: &mut KeySplitThreadpool<
+            UserAddress,
+            Pin<Box<dyn Future<Output = ()> + Send + Sync>>,
+            Handle
+        >
+    ) {
+        if orders.len() < MIN_BATCH {
+            return
+        }
+        let state = self.state.clone();
+        thread_pool.spawn_raw(Box::pin(async move {
+            let count = orders.len();
+            let Ok(invalid) =
+                tokio::task::spawn_blocking(move || state.verify_signatures(&orders)).await
+            else {
+                return
+            };
+            if invalid > 0 {
+                tracing::debug!(orders = count, invalid, "wave had invalid signatures");
+            }
+        }));
+    }
+
+    /// Simulates the top of block order for a searcher, nothing of it is kept.
+    pub fn simulate_tob_order(
+        &self,
+        sender: tokio::sync::oneshot::Sender<TobSimulation>,
+        order: TopOfBlockOrder,
+        overrides: TobStateOverrides,
+        thread_pool: &mut KeySplitThreadpool<
+            UserAddress,
+            Pin<Box<dyn Future<Output = ()> + Send + Sync>>,
+            Handle
+        >
+    ) {
+        let block_number = self.block_number.load(std::sync::atomic::Ordering::SeqCst);
+        let span = tracing::info_span!(
+            "simulate_tob_order",
+            order_hash = %order.order_hash(),
+            block_number
+        );
+        let state = self.state.clone();
+
+        thread_pool.add_new_task(
+            order.from(),
+            Box::pin(
+                async move {
+                    let simulation = state
+                        .simulate_tob_order(order, block_number, &overrides)
+                        .await;
+                    let _ = sender.send(simulation);
+                }
+                .instrument(span)
+            )
+        );
+    }
+
+    /// Checks an order that only another node holds, see
+    /// [`StateValidation::check_order`]. Nothing of the order is kept.
+    pub fn check_order(
+        &self,
+        sender: tokio::sync::oneshot::Sender<OrderValidationResults>,
+        order: AllOrders,
+        token_conversion: TokenPriceGenerator,
+        thread_pool: &mut KeySplitThreadpool<
+            UserAddress,
+            Pin<Box<dyn Future<Output = ()> + Send + Sync>>,
+            Handle
+        >
+    ) {
+        let block_number = self.block_number.load(std::sync::atomic::Ordering::SeqCst);
+        let span =
+            tracing::info_span!("check_order", order_hash = %order.order_hash(), block_number);
+        let (state, sim) = (self.state.clone(), self.sim.clone());
+        let (user, is_limit) = (order.from(), !matches!(order, AllOrders::TOB(_)));
+
+        thread_pool.add_new_task(
+            user,
+            Box::pin(
+                async move {
+                    let mut results = state.check_order(order, block_number);
+                    results.add_gas_cost_or_invalidate(
+                        &sim,
+                        &token_conversion,
+                        is_limit,
+                        block_number
+                    );
+                    let _ = sender.send(results);
+                }
+                .instrument(span)
+            )
+        );
+    }
+
     /// only checks state
     pub fn validate_order(
         &mut self,
@@ -72,6 +237,13 @@ where
         metrics: ValidationMetrics
     ) {
         let block_number = self.block_number.load(std::sync::atomic::Ordering::SeqCst);
+        let OrderValidationRequest::ValidateOrder(_, ref raw_order, origin) = order;
+        let span = tracing::info_span!(
+            "validate_order",
+            order_hash = %raw_order.order_hash(),
+            ?origin,
+            block_number
+        );
         let order_validation: OrderValidation = order.into();
         let user = order_validation.user();
         let cloned_state = self.state.clone();
@@ -79,48 +251,51 @@ where
 
         thread_pool.add_new_task(
             user,
-            Box::pin(async move {
-                match order_validation {
-                    OrderValidation::Limit(tx, order, _) => {
-                        metrics
-                            .new_order(false, || async {
-                                let mut results = cloned_state.handle_regular_order(
-                                    order,
-                                    block_number,
-                                    metrics.clone()
-                                );
-                                results.add_gas_cost_or_invalidate(
-                                    &cloned_sim,
-                                    &token_conversion,
-                                    true,
-                                    block_number
-                                );
-
-                                let _ = tx.send(results);
-                            })
-                            .await;
-                    }
-                    OrderValidation::Searcher(tx, order, _) => {
-                        metrics
-                            .new_order(true, || async {
-                                let mut results = cloned_state
-                                    .handle_tob_order(order, block_number, metrics.clone())
-                                    .await;
-
-                                results.add_gas_cost_or_invalidate(
-                                    &cloned_sim,
-                                    &token_conversion,
-                                    false,
-                                    block_number
-                                );
-
-                                let _ = tx.send(results);
-                            })
-                            .await;
+            Box::pin(
+                async move {
+                    match order_validation {
+                        OrderValidation::Limit(tx, order, _) => {
+                            metrics
+                                .new_order(false, || async {
+                                    let mut results = cloned_state.handle_limit_order(
+                                        order,
+                                        block_number,
+                                        metrics.clone()
+                                    );
+                                    results.add_gas_cost_or_invalidate(
+                                        &cloned_sim,
+                                        &token_conversion,
+                                        true,
+                                        block_number
+                                    );
+
+                                    let _ = tx.send(results);
+                                })
+                                .await;
+                        }
+                        OrderValidation::Searcher(tx, order, _) => {
+                            metrics
+                                .new_order(true, || async {
+                                    let mut results = cloned_state
+                                        .handle_tob_order(order, block_number, metrics.clone())
+                                        .await;
+
+                                    results.add_gas_cost_or_invalidate(
+                                        &cloned_sim,
+                                        &token_conversion,
+                                        false,
+                                        block_number
+                                    );
+
+                                    let _ = tx.send(results);
+                                })
+                                .await;
+                        }
+                        _ => unreachable!()
                     }
-                    _ => unreachable!()
                 }
-            })
+                .instrument(span)
+            )
         );
     }
 }
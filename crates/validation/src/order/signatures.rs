@@ -0,0 +1,170 @@
+//! Verification of the order signatures of a wave in one batch.
+//!
+//! secp256k1 has no batch equation for recoverable signatures that fits the
+//! contract's `ecrecover` check, every signature still needs its own
+//! recovery. What a batch does share is the domain separator, hashed once for
+//! all orders instead of once per order, and the cores: the recoveries are
+//! spread over the rayon pool in chunks. A chunk is checked as a whole and
+//! stops at its first bad signature, only then are its orders checked one by
+//! one to isolate the culprits.
+use std::{collections::HashSet, sync::Arc};
+
+use alloy::{
+    primitives::{keccak256, B256},
+    sol_types::Eip712Domain
+};
+use angstrom_types::sol_bindings::ext::RawPoolOrder;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+
+/// Orders per chunk of a batch, small enough for a bad signature to only cost
+/// a few serial checks, large enough for the chunks to not be dominated by
+/// scheduling them.
+const BATCH_CHUNK: usize = 32;
+/// Waves smaller than this are verified by their validations.
+pub const MIN_BATCH: usize = 4;
+
+/// Verifies the signatures of orders for the domain, remembering the ones a
+/// batch verified until their validation asks for them.
+#[derive(Debug, Clone)]
+pub struct SignatureVerifier {
+    domain_separator: B256,
+    /// signatures verified ahead of the validation of their order
+    verified:         Arc<Mutex<HashSet<B256>>>
+}
+
+impl SignatureVerifier {
+    pub fn new(domain: &Eip712Domain) -> Self {
+        Self { domain_separator: domain.separator(), verified: Default::default() }
+    }
+
+    /// Verifies the signatures of the orders, returns how many are invalid.
+    /// The valid ones aren't verified again by [`Self::is_valid`].
+    pub fn verify_batch<O: RawPoolOrder + Sync>(&self, orders: &[O]) -> usize {
+        let verified = orders
+            .par_chunks(BATCH_CHUNK)
+            .flat_map_iter(|chunk| {
+                chunk
+                    .iter()
+                    .map(|order| self.verify(order))
+                    .collect::<Option<Vec<_>>>()
+                    .unwrap_or_else(|| {
+                        chunk
+                            .iter()
+                            .filter_map(|order| self.verify(order))
+                            .collect()
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let invalid = orders.len() - verified.len();
+        self.verified.lock().extend(verified);
+        invalid
+    }
+
+    /// Whether the order is signed by its `from`, same as
+    /// [`RawPoolOrder::is_valid_signature_in`] for the domain. Orders a batch
+    /// verified aren't recovered again.
+    pub fn is_valid<O: RawPoolOrder>(&self, order: &O) -> bool {
+        let Some(key) = Self::key(order) else { return false };
+        if self.verified.lock().remove(&key) {
+            return true
+        }
+        self.verify(order).is_some()
+    }
+
+    /// Forgets the signatures verified for orders that weren't validated.
+    pub fn clear(&self) {
+        self.verified.lock().clear();
+    }
+
+    /// Verifies the signature of the order, returns its key if it's valid.
+    fn verify<O: RawPoolOrder>(&self, order: &O) -> Option<B256> {
+        let signature = order.order_signature().ok()?;
+        // the order hash is the hash struct of the order
+        let mut digest_input = [0u8; 2 + 32 + 32];
+        digest_input[0] = 0x19;
+        digest_input[1] = 0x01;
+        digest_input[2..34].copy_from_slice(self.domain_separator.as_slice());
+        digest_input[34..66].copy_from_slice(order.order_hash().as_slice());
+
+        let signer = signature
+            .recover_address_from_prehash(&keccak256(digest_input))
+            .ok()?;
+        (signer == order.from()).then(|| Self::key(order)).flatten()
+    }
+
+    /// The signature is part of the key, two copies of an order signed
+    /// differently are verified each on their own.
+    fn key<O: RawPoolOrder>(order: &O) -> Option<B256> {
+        let signature = order.order_signature().ok()?;
+        Some(keccak256(
+            [order.order_hash().as_slice(), order.from().as_slice(), &signature.as_bytes()]
+                .concat()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::{
+        primitive::{AngstromSigner, ANGSTROM_DOMAIN},
+        sol_bindings::{
+            grouped_orders::{GroupedVanillaOrder, StandingVariants},
+            rpc_orders::OrderMeta
+        }
+    };
+    use testing_tools::type_generator::orders::UserOrderBuilder;
+
+    use super::*;
+
+    fn orders(count: u64) -> Vec<GroupedVanillaOrder> {
+        (0..count)
+            .map(|nonce| {
+                UserOrderBuilder::new()
+                    .standing()
+                    .exact()
+                    .nonce(nonce)
+                    .amount(100)
+                    .signing_key(Some(AngstromSigner::random()))
+                    .build()
+            })
+            .collect()
+    }
+
+    fn meta(order: &mut GroupedVanillaOrder) -> &mut OrderMeta {
+        let GroupedVanillaOrder::Standing(StandingVariants::Exact(order)) = order else {
+            unreachable!()
+        };
+        &mut order.meta
+    }
+
+    #[test]
+    fn isolates_the_bad_signatures_of_a_batch() {
+        let verifier = SignatureVerifier::new(&ANGSTROM_DOMAIN);
+        let mut orders = orders(100);
+        meta(&mut orders[3]).from = AngstromSigner::random().address();
+        meta(&mut orders[70]).from = AngstromSigner::random().address();
+
+        assert_eq!(verifier.verify_batch(&orders), 2);
+        for (i, order) in orders.iter().enumerate() {
+            assert_eq!(verifier.is_valid(order), order.is_valid_signature_in(&ANGSTROM_DOMAIN));
+            assert_eq!(verifier.is_valid(order), i != 3 && i != 70);
+        }
+        assert!(verifier.verified.lock().is_empty());
+    }
+
+    #[test]
+    fn keys_verified_orders_by_their_signature() {
+        let verifier = SignatureVerifier::new(&ANGSTROM_DOMAIN);
+        let orders = orders(1);
+        assert_eq!(verifier.verify_batch(&orders), 0);
+
+        // the same order with the signature of another one
+        let mut copy = orders[0].clone();
+        meta(&mut copy).signature = meta(&mut self::orders(1)[0]).signature.clone();
+
+        assert!(!verifier.is_valid(&copy));
+        assert!(verifier.is_valid(&orders[0]));
+    }
+}
@@ -60,7 +60,9 @@ where
                 let conversion_factor =
                     conversion.get_eth_conversion_price(token0, token1).unwrap();
 
-                Ok((gas_in_wei, (conversion_factor * U256::from(gas_in_wei)).scale_out_of_ray()))
+                let gas_cost_wei = U256::from(gas_in_wei) * U256::from(conversion.gas_price_wei());
+
+                Ok((gas_in_wei, (conversion_factor * gas_cost_wei).scale_out_of_ray()))
             })
         })
     }
@@ -88,7 +90,9 @@ where
                 let conversion_factor =
                     conversion.get_eth_conversion_price(token0, token1).unwrap();
 
-                Ok((gas_in_wei, (conversion_factor * U256::from(gas_in_wei)).scale_out_of_ray()))
+                let gas_cost_wei = U256::from(gas_in_wei) * U256::from(conversion.gas_price_wei());
+
+                Ok((gas_in_wei, (conversion_factor * gas_cost_wei).scale_out_of_ray()))
             })
         })
     }
@@ -2,13 +2,21 @@
 
 use alloy::primitives::{Address, B256, U256};
 use angstrom_types::{
+    contract_payloads::tob::TobStateOverrides,
     orders::OrderId,
-    sol_bindings::{ext::RawPoolOrder, grouped_orders::OrderWithStorageData}
+    sol_bindings::{
+        ext::RawPoolOrder, grouped_orders::OrderWithStorageData, RespendAvoidanceMethod
+    }
 };
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use user::UserAccounts;
+use user::{LiveState, UserAccounts};
 
-use super::{db_state_utils::StateFetchUtils, pools::UserOrderPoolInfo};
+use super::{
+    bond::SearcherBondConfig,
+    db_state_utils::{Prefetch, StateFetchUtils},
+    pools::UserOrderPoolInfo
+};
 
 pub mod user;
 
@@ -29,9 +37,27 @@ impl<S: StateFetchUtils> UserAccountProcessor<S> {
     }
 
     pub fn prepare_for_new_block(&self, users: Vec<Address>, orders: Vec<B256>) {
+        self.fetch_utils.on_new_block();
         self.user_accounts.new_block(users, orders);
     }
 
+    /// Reads the state a wave of orders is verified against ahead of their
+    /// verification.
+    pub fn prefetch<O: RawPoolOrder>(&self, orders: &[O]) {
+        let wave = orders
+            .iter()
+            .map(|order| Prefetch {
+                user:  order.from(),
+                token: order.token_in(),
+                nonce: match order.respend_avoidance_strategy() {
+                    RespendAvoidanceMethod::Nonce(nonce) => Some(nonce),
+                    RespendAvoidanceMethod::Block(_) => None
+                }
+            })
+            .collect::<Vec<_>>();
+        self.fetch_utils.prefetch(&wave);
+    }
+
     pub fn verify_order<O: RawPoolOrder>(
         &self,
         order: O,
@@ -40,31 +66,20 @@ impl<S: StateFetchUtils> UserAccountProcessor<S> {
     ) -> Result<OrderWithStorageData<O>, UserAccountVerificationError<O>> {
         let user = order.from();
         let order_hash = order.order_hash();
-
-        // very nonce hasn't been used historically
-        //
         let respend = order.respend_avoidance_strategy();
-        match respend {
-            angstrom_types::sol_bindings::RespendAvoidanceMethod::Nonce(nonce) => {
-                if !self.fetch_utils.is_valid_nonce(user, nonce) {
-                    return Err(UserAccountVerificationError::DuplicateNonce(order_hash))
-                }
-            }
-            angstrom_types::sol_bindings::RespendAvoidanceMethod::Block(order_block) => {
-                // order should be for block + 1
-                if block + 1 != order_block {
-                    return Err(UserAccountVerificationError::BadBlock(block + 1, order_block))
-                }
-            }
-        }
+        self.check_respend(&order, block)?;
 
         // very we don't have a respend conflict
         let conflicting_orders = self.user_accounts.respend_conflicts(user, respend);
-        if conflicting_orders
+        if let Some(winning) = conflicting_orders
             .iter()
-            .any(|o| o.order_hash <= order_hash)
+            .find(|o| o.order_hash <= order_hash)
         {
-            return Err(UserAccountVerificationError::DuplicateNonce(order_hash))
+            let collision = NonceCollision::Reserved {
+                nonce:      winning.respend.get_ord_for_pending_orders(),
+                order_hash: winning.order_hash
+            };
+            return Err(UserAccountVerificationError::DuplicateNonce(order_hash, collision))
         }
         tracing::trace!(?conflicting_orders);
 
@@ -99,6 +114,148 @@ impl<S: StateFetchUtils> UserAccountProcessor<S> {
 
         Ok(order.into_order_storage_with_data(block, is_cur_valid, true, pool_info, invalid_orders))
     }
+
+    /// Verifies the order like [`Self::verify_order`] without reserving
+    /// anything for it or cancelling the orders it conflicts with. The state
+    /// of the user is read with the overrides applied.
+    pub fn dry_run_order<O: RawPoolOrder>(
+        &self,
+        order: O,
+        pool_info: UserOrderPoolInfo,
+        block: u64,
+        overrides: &TobStateOverrides
+    ) -> Result<OrderWithStorageData<O>, UserAccountVerificationError<O>> {
+        let user = order.from();
+        let order_hash = order.order_hash();
+        let respend = order.respend_avoidance_strategy();
+        self.check_respend(&order, block)?;
+
+        if let Some(winning) = self
+            .user_accounts
+            .respend_conflicts(user, respend)
+            .iter()
+            .find(|o| o.order_hash < order_hash)
+        {
+            let collision = NonceCollision::Reserved {
+                nonce:      winning.respend.get_ord_for_pending_orders(),
+                order_hash: winning.order_hash
+            };
+            return Err(UserAccountVerificationError::DuplicateNonce(order_hash, collision))
+        }
+
+        let mut live_state = self.user_accounts.get_live_state_for_order(
+            user,
+            pool_info.token,
+            respend,
+            &self.fetch_utils
+        );
+        live_state.balance = overrides.balance.unwrap_or(live_state.balance);
+        live_state.approval = overrides.approval.unwrap_or(live_state.approval);
+        live_state.angstrom_balance = overrides
+            .angstrom_balance
+            .unwrap_or(live_state.angstrom_balance);
+        let is_cur_valid = live_state.can_support_order(&order, &pool_info).is_some();
+
+        Ok(order.into_order_storage_with_data(block, is_cur_valid, true, pool_info, vec![]))
+    }
+
+    /// Verifies the order on its own against the state on chain, for orders
+    /// that only another node holds. Our pending orders don't count against
+    /// it and nothing is reserved for it, so every node comes to the same
+    /// result for the same block.
+    pub fn check_order<O: RawPoolOrder>(
+        &self,
+        order: O,
+        pool_info: UserOrderPoolInfo,
+        block: u64
+    ) -> Result<OrderWithStorageData<O>, UserAccountVerificationError<O>> {
+        self.check_respend(&order, block)?;
+
+        let (user, token) = (order.from(), pool_info.token);
+        let chain_state = LiveState {
+            token,
+            approval: self
+                .fetch_utils
+                .fetch_approval_balance_for_token(user, token)
+                .unwrap_or_default(),
+            balance: self.fetch_utils.fetch_balance_for_token(user, token),
+            angstrom_balance: self
+                .fetch_utils
+                .fetch_token_balance_in_angstrom(user, token)
+        };
+        let is_cur_valid = chain_state.can_support_order(&order, &pool_info).is_some();
+
+        Ok(order.into_order_storage_with_data(block, is_cur_valid, true, pool_info, vec![]))
+    }
+
+    /// Checks the nonce of the order wasn't used on chain, or that a flash
+    /// order is for the next block.
+    fn check_respend<O: RawPoolOrder>(
+        &self,
+        order: &O,
+        block: u64
+    ) -> Result<(), UserAccountVerificationError<O>> {
+        match order.respend_avoidance_strategy() {
+            RespendAvoidanceMethod::Nonce(nonce) => {
+                if !self.fetch_utils.is_valid_nonce(order.from(), nonce) {
+                    return Err(UserAccountVerificationError::DuplicateNonce(
+                        order.order_hash(),
+                        NonceCollision::Used(nonce)
+                    ))
+                }
+            }
+            RespendAvoidanceMethod::Block(order_block) => {
+                // order should be for block + 1
+                if block + 1 != order_block {
+                    return Err(UserAccountVerificationError::BadBlock(block + 1, order_block))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a new standing order from the user could use the nonce. A
+    /// pending order only blocks it if it would win the nonce, the one with
+    /// the lower hash replaces the other in the pool.
+    pub fn check_nonce(
+        &self,
+        user: Address,
+        nonce: u64,
+        order_hash: B256
+    ) -> Result<(), NonceCollision> {
+        if !self.fetch_utils.is_valid_nonce(user, nonce) {
+            return Err(NonceCollision::Used(nonce))
+        }
+
+        let respend = RespendAvoidanceMethod::Nonce(nonce);
+        if let Some(pending) = self
+            .user_accounts
+            .respend_conflicts(user, respend)
+            .into_iter()
+            .filter(|o| o.order_hash <= order_hash)
+            .min_by_key(|o| o.order_hash)
+        {
+            return Err(NonceCollision::Reserved { nonce, order_hash: pending.order_hash })
+        }
+
+        Ok(())
+    }
+
+    /// Returns the lowest nonce that is neither invalidated on chain nor
+    /// reserved by one of the users pending orders.
+    pub fn next_free_nonce(&self, user: Address) -> Option<u64> {
+        let reserved = self.user_accounts.pending_nonces(user);
+        self.fetch_utils.next_free_nonce(user, 0, &reserved)
+    }
+
+    /// Tier of the bond `searcher` posted, zero if it isn't bonded.
+    pub fn searcher_bond_tier(&self, searcher: Address, config: &SearcherBondConfig) -> u8 {
+        config.tier(
+            self.fetch_utils
+                .fetch_searcher_bond(searcher, &config.source)
+        )
+    }
 }
 
 impl<T: RawPoolOrder> StorageWithData for T {}
@@ -127,7 +284,11 @@ pub trait StorageWithData: RawPoolOrder {
             order_id: OrderId::from_all_orders(&self, pool_info.pool_id),
             invalidates,
             order: self,
-            tob_reward: U256::ZERO
+            tob_reward: U256::ZERO,
+            bond_tier: 0,
+            is_private: false,
+            is_hidden: false,
+            peg: None
         }
     }
 }
@@ -138,18 +299,29 @@ pub enum UserAccountVerificationError<O: RawPoolOrder> {
     BlockMissMatch { requested: u64, current: u64, order: O, pool_info: UserOrderPoolInfo },
     #[error("order hash has been cancelled {0:?}")]
     OrderIsCancelled(B256),
-    #[error("Nonce exists for a current order hash: {0:?}")]
-    DuplicateNonce(B256),
+    #[error("Nonce exists for a current order hash: {0:?}, {1}")]
+    DuplicateNonce(B256, NonceCollision),
     #[error("block for flash order is not for next block. next_block: {0}, requested_block: {1}.")]
     BadBlock(u64, u64)
 }
 
+/// Why a nonce can't be used by a new standing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum NonceCollision {
+    #[error("nonce {0} has already been used on chain")]
+    Used(u64),
+    #[error("nonce {nonce} is reserved by pending order {order_hash:?}")]
+    Reserved { nonce: u64, order_hash: B256 }
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::collections::HashSet;
 
-    use alloy::primitives::{Address, U256};
+    use alloy::primitives::{Address, B256, U256};
     use angstrom_types::{
+        contract_payloads::tob::TobStateOverrides,
         primitive::{AngstromSigner, PoolId},
         sol_bindings::{grouped_orders::GroupedVanillaOrder, RawPoolOrder}
     };
@@ -157,8 +329,9 @@ pub mod tests {
     use tracing::info;
     use tracing_subscriber::{fmt, EnvFilter};
 
-    use super::{UserAccountProcessor, UserAccountVerificationError, UserAccounts};
+    use super::{NonceCollision, UserAccountProcessor, UserAccountVerificationError, UserAccounts};
     use crate::order::state::{
+        bond::{BondSource, SearcherBondConfig},
         db_state_utils::test_fetching::MockFetch,
         pools::{pool_tracker_mock::MockPoolTracker, PoolsTracker}
     };
@@ -226,6 +399,58 @@ pub mod tests {
             .expect("order should be valid");
     }
 
+    #[test]
+    fn dry_run_reads_the_overrides_and_reserves_nothing() {
+        let processor = setup_test_account_processor();
+        let sk = AngstromSigner::random();
+        let user = sk.address();
+        let (token0, token1) = (Address::random(), Address::random());
+        let mock_pool = MockPoolTracker::default();
+        mock_pool.add_pool(token0, token1, PoolId::default());
+
+        let order: GroupedVanillaOrder = UserOrderBuilder::new()
+            .standing()
+            .asset_in(token0)
+            .asset_out(token1)
+            .nonce(420)
+            .signing_key(Some(sk.clone()))
+            .recipient(user)
+            .build();
+        let pool_info = mock_pool.fetch_pool_info_for_order(&order).unwrap();
+        let amount = U256::from(order.amount_in());
+        processor
+            .fetch_utils
+            .set_balance_for_user(user, token0, amount);
+        processor
+            .fetch_utils
+            .set_approval_for_user(user, token0, amount);
+        let dry_run = |overrides: &TobStateOverrides| {
+            processor
+                .dry_run_order(order.clone(), pool_info.clone(), 420, overrides)
+                .unwrap()
+                .is_currently_valid
+        };
+
+        assert!(dry_run(&TobStateOverrides::default()));
+        assert!(!dry_run(&TobStateOverrides { balance: Some(U256::ZERO), ..Default::default() }));
+        assert!(!dry_run(&TobStateOverrides {
+            approval: Some(amount - U256::from(1)),
+            ..Default::default()
+        }));
+
+        // nothing was reserved for the dry runs, so the order still goes in once
+        assert!(
+            processor
+                .verify_order(order.clone(), pool_info.clone(), 420)
+                .unwrap()
+                .is_currently_valid
+        );
+        assert!(matches!(
+            processor.verify_order(order, pool_info, 420),
+            Err(UserAccountVerificationError::DuplicateNonce(..))
+        ));
+    }
+
     #[test]
     fn test_failure_on_duplicate_pending_nonce() {
         let processor = setup_test_account_processor();
@@ -280,6 +505,45 @@ pub mod tests {
         assert!(matches!(e, UserAccountVerificationError::DuplicateNonce(..)));
     }
 
+    #[test]
+    fn nonce_check_defers_to_replacement() {
+        let processor = setup_test_account_processor();
+        let sk = AngstromSigner::random();
+        let user = sk.address();
+        let (token0, token1) = (Address::random(), Address::random());
+        let mock_pool = MockPoolTracker::default();
+        mock_pool.add_pool(token0, token1, PoolId::default());
+
+        let order: GroupedVanillaOrder = UserOrderBuilder::new()
+            .standing()
+            .asset_in(token0)
+            .asset_out(token1)
+            .nonce(420)
+            .signing_key(Some(sk.clone()))
+            .recipient(user)
+            .build();
+        let pool_info = mock_pool.fetch_pool_info_for_order(&order).unwrap();
+        processor
+            .fetch_utils
+            .set_balance_for_user(user, token0, U256::from(order.amount_in()));
+        processor
+            .fetch_utils
+            .set_approval_for_user(user, token0, U256::from(order.amount_in()));
+        let pending = order.order_hash();
+        processor.verify_order(order, pool_info, 420).unwrap();
+
+        // a higher hash loses to the pending order, a lower one replaces it
+        let shift = |by: fn(U256, U256) -> U256| {
+            B256::from(by(U256::from_be_bytes(pending.0), U256::from(1)).to_be_bytes::<32>())
+        };
+        let (higher, lower) = (shift(U256::saturating_add), shift(U256::saturating_sub));
+        assert_eq!(
+            processor.check_nonce(user, 420, higher),
+            Err(NonceCollision::Reserved { nonce: 420, order_hash: pending })
+        );
+        assert_eq!(processor.check_nonce(user, 420, lower), Ok(()));
+    }
+
     #[test]
     fn proper_nonce_invalidation_with_lower_nonce_order() {
         let processor = setup_test_account_processor();
@@ -733,6 +997,63 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn checked_orders_only_count_the_chain_state() {
+        let processor = setup_test_account_processor();
+        let sk = AngstromSigner::random();
+        let user = sk.address();
+        let (token0, token1) = (Address::random(), Address::random());
+        let mock_pool = MockPoolTracker::default();
+        mock_pool.add_pool(token0, token1, PoolId::default());
+        let order = |nonce, amount| -> GroupedVanillaOrder {
+            UserOrderBuilder::new()
+                .standing()
+                .asset_in(token0)
+                .asset_out(token1)
+                .nonce(nonce)
+                .amount(amount)
+                .recipient(user)
+                .signing_key(Some(sk.clone()))
+                .build()
+        };
+        let pool_info = mock_pool
+            .fetch_pool_info_for_order(&order(100, 600))
+            .unwrap();
+        processor
+            .fetch_utils
+            .set_balance_for_user(user, token0, U256::from(800));
+        processor
+            .fetch_utils
+            .set_approval_for_user(user, token0, U256::from(1500));
+
+        assert!(
+            processor
+                .verify_order(order(100, 600), pool_info.clone(), 420)
+                .unwrap()
+                .is_currently_valid
+        );
+        // our pending order doesn't count against an order another node holds
+        assert!(
+            processor
+                .check_order(order(101, 500), pool_info.clone(), 420)
+                .unwrap()
+                .is_currently_valid
+        );
+        assert!(
+            !processor
+                .check_order(order(102, 900), pool_info.clone(), 420)
+                .unwrap()
+                .is_currently_valid
+        );
+        // and nothing was reserved for the checked one
+        assert!(
+            processor
+                .verify_order(order(103, 200), pool_info, 420)
+                .unwrap()
+                .is_currently_valid
+        );
+    }
+
     #[test]
     fn test_flash_order_sequence() {
         let processor = setup_test_account_processor();
@@ -894,4 +1215,20 @@ pub mod tests {
             result
         );
     }
+
+    #[test]
+    fn tiers_searchers_by_their_bond() {
+        let processor = setup_test_account_processor();
+        let config = SearcherBondConfig {
+            source: BondSource::Staking { contract: Address::random(), slot_index: 0 },
+            tiers:  vec![U256::from(1_000)]
+        };
+        let searcher = Address::random();
+        assert_eq!(processor.searcher_bond_tier(searcher, &config), 0);
+
+        processor
+            .fetch_utils
+            .set_bond_for_searcher(searcher, U256::from(1_000));
+        assert_eq!(processor.searcher_bond_tier(searcher, &config), 1);
+    }
 }
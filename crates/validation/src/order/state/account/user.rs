@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc
+};
 
 use alloy::primitives::{Address, B256, U256};
 use angstrom_types::sol_bindings::{ext::RawPoolOrder, RespendAvoidanceMethod};
@@ -148,6 +151,22 @@ impl UserAccounts {
         }
     }
 
+    /// all nonces that are reserved by the users pending orders
+    pub fn pending_nonces(&self, user: UserAddress) -> HashSet<u64> {
+        self.pending_actions
+            .get(&user)
+            .map(|v| {
+                v.value()
+                    .iter()
+                    .filter_map(|pending_order| match pending_order.respend {
+                        RespendAvoidanceMethod::Nonce(nonce) => Some(nonce),
+                        RespendAvoidanceMethod::Block(_) => None
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn get_live_state_for_order<S: StateFetchUtils>(
         &self,
         user: UserAddress,
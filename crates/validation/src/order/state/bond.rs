@@ -0,0 +1,53 @@
+use alloy::primitives::{Address, U256};
+use serde::Deserialize;
+
+/// Where the bond a searcher posted is read from.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BondSource {
+    /// balance of `token` the searcher deposited into angstrom
+    Angstrom { token: Address },
+    /// stake in a contract that keeps it in a `mapping(address => uint256)`
+    /// at `slot_index`
+    Staking { contract: Address, slot_index: u8 }
+}
+
+/// The bond searchers have to post before their top of block orders are
+/// accepted. Searchers are tiered by how much they posted, so that unbacked
+/// orders can't crowd the auction.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct SearcherBondConfig {
+    pub source: BondSource,
+    /// minimum bond of every tier, ascending. A searcher under the first one
+    /// isn't bonded
+    pub tiers:  Vec<U256>
+}
+
+impl SearcherBondConfig {
+    /// Tier of a searcher that posted `bond`, zero if it isn't bonded.
+    pub fn tier(&self, bond: U256) -> u8 {
+        self.tiers
+            .iter()
+            .take_while(|minimum| bond >= **minimum)
+            .count()
+            .try_into()
+            .unwrap_or(u8::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_by_the_posted_bond() {
+        let config = SearcherBondConfig {
+            source: BondSource::Angstrom { token: Address::ZERO },
+            tiers:  vec![U256::from(100), U256::from(1_000)]
+        };
+        assert_eq!(config.tier(U256::from(99)), 0);
+        assert_eq!(config.tier(U256::from(100)), 1);
+        assert_eq!(config.tier(U256::from(999)), 1);
+        assert_eq!(config.tier(U256::MAX), 2);
+    }
+}
@@ -18,6 +18,17 @@ impl Approvals {
         Self { angstrom_address, slots: DashMap::default() }
     }
 
+    /// Where the approval of `user` to angstrom is stored, none if the slot of
+    /// `token` wasn't found yet.
+    pub fn storage_slot(&self, user: Address, token: Address) -> Option<(Address, U256)> {
+        let slot = self
+            .slots
+            .get(&token)?
+            .generate_slot(user, self.angstrom_address)
+            .ok()?;
+        Some((token, slot))
+    }
+
     pub fn fetch_approval_balance_for_token_overrides<DB: revm::DatabaseRef>(
         &self,
         user: Address,
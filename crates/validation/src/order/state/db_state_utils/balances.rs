@@ -22,6 +22,13 @@ impl Balances {
         Self { tokens: DashMap::default(), angstrom_address }
     }
 
+    /// Where the balance of `user` is stored, none if the slot of `token`
+    /// wasn't found yet.
+    pub fn storage_slot(&self, user: Address, token: Address) -> Option<(Address, U256)> {
+        let slot = self.tokens.get(&token)?.generate_slot(user).ok()?;
+        Some((token, slot))
+    }
+
     pub fn fetch_balance_for_token_overrides<DB: revm::DatabaseRef>(
         &self,
         user: Address,
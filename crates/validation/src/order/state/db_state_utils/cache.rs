@@ -0,0 +1,233 @@
+//! Storage of the current block that orders were validated against or are
+//! about to be.
+//!
+//! When an order arrives its sender's nonce, balance and approval slots are
+//! read into the cache while it waits for a validation slot, so the reads of
+//! its validation are served from memory instead of waiting on the database.
+//! The orders that arrive together are prefetched together, with the slots
+//! that aren't cached yet read in a single batch.
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc
+    }
+};
+
+use alloy::primitives::{Address, B256, U256};
+use dashmap::DashMap;
+use revm::{
+    primitives::{AccountInfo, Bytecode},
+    DatabaseRef
+};
+
+use crate::common::BatchStorageRead;
+
+pub struct StorageCache<DB> {
+    db:         Arc<DB>,
+    slots:      DashMap<(Address, U256), U256>,
+    /// bumped every block, reads that started in a past block aren't cached
+    generation: AtomicU64
+}
+
+impl<DB: DatabaseRef> StorageCache<DB> {
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db, slots: DashMap::new(), generation: AtomicU64::new(0) }
+    }
+
+    /// Drops the storage of the last block.
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.slots.clear();
+    }
+}
+
+fn read_failed(e: impl Debug) -> eyre::Error {
+    eyre::eyre!("state read failed: {e:?}")
+}
+
+impl<DB> DatabaseRef for StorageCache<DB>
+where
+    DB: DatabaseRef,
+    DB::Error: Debug
+{
+    type Error = eyre::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.db.basic_ref(address).map_err(read_failed)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash_ref(code_hash).map_err(read_failed)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.slots.get(&(address, index)) {
+            return Ok(*value)
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let value = self.db.storage_ref(address, index).map_err(read_failed)?;
+        if generation == self.generation.load(Ordering::SeqCst) {
+            self.slots.insert((address, index), value);
+        }
+
+        Ok(value)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash_ref(number).map_err(read_failed)
+    }
+}
+
+impl<DB> BatchStorageRead for StorageCache<DB>
+where
+    DB: BatchStorageRead,
+    DB::Error: Debug
+{
+    fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+        let values = slots
+            .iter()
+            .map(|slot| self.slots.get(slot).map(|value| *value))
+            .collect::<Vec<_>>();
+        let misses = slots
+            .iter()
+            .zip(&values)
+            .filter(|(_, value)| value.is_none())
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<_>>();
+        if misses.is_empty() {
+            return Ok(values.into_iter().flatten().collect())
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let read = self.db.storage_batch(&misses).map_err(read_failed)?;
+        if read.len() != misses.len() {
+            return Err(eyre::eyre!("read {} of a batch of {} slots", read.len(), misses.len()))
+        }
+        let cache = generation == self.generation.load(Ordering::SeqCst);
+
+        let mut read = read.into_iter();
+        Ok(slots
+            .iter()
+            .zip(values)
+            .map(|(slot, value)| {
+                value.unwrap_or_else(|| {
+                    let value = read.next().expect("a value for every miss");
+                    if cache {
+                        self.slots.insert(*slot, value);
+                    }
+                    value
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    /// Counts the storage reads that reach it.
+    #[derive(Default)]
+    struct CountingDb(AtomicUsize);
+
+    impl DatabaseRef for CountingDb {
+        type Error = &'static str;
+
+        fn basic_ref(&self, _: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            Ok(None)
+        }
+
+        fn code_by_hash_ref(&self, _: B256) -> Result<Bytecode, Self::Error> {
+            Ok(Bytecode::default())
+        }
+
+        fn storage_ref(&self, _: Address, _: U256) -> Result<U256, Self::Error> {
+            Ok(U256::from(self.0.fetch_add(1, Ordering::SeqCst)))
+        }
+
+        fn block_hash_ref(&self, _: u64) -> Result<B256, Self::Error> {
+            Ok(B256::ZERO)
+        }
+    }
+
+    impl BatchStorageRead for CountingDb {}
+
+    /// Drops the last slot of every batch.
+    #[derive(Default)]
+    struct ShortBatchDb(CountingDb);
+
+    impl DatabaseRef for ShortBatchDb {
+        type Error = &'static str;
+
+        fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+            self.0.basic_ref(address)
+        }
+
+        fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+            self.0.code_by_hash_ref(code_hash)
+        }
+
+        fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+            self.0.storage_ref(address, index)
+        }
+
+        fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+            self.0.block_hash_ref(number)
+        }
+    }
+
+    impl BatchStorageRead for ShortBatchDb {
+        fn storage_batch(&self, slots: &[(Address, U256)]) -> Result<Vec<U256>, Self::Error> {
+            self.0.storage_batch(&slots[..slots.len() - 1])
+        }
+    }
+
+    #[test]
+    fn serves_reads_of_the_block_from_memory() {
+        let db = Arc::new(CountingDb::default());
+        let cache = StorageCache::new(db.clone());
+        let token = Address::random();
+
+        assert_eq!(cache.storage_ref(token, U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(cache.storage_ref(token, U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(db.0.load(Ordering::SeqCst), 1);
+
+        // the next block reads the slot again
+        cache.clear();
+        assert_eq!(cache.storage_ref(token, U256::from(1)).unwrap(), U256::from(1));
+        assert_eq!(db.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn batches_only_the_slots_that_arent_cached() {
+        let db = Arc::new(CountingDb::default());
+        let cache = StorageCache::new(db.clone());
+        let token = Address::random();
+        let slots = [(token, U256::from(1)), (token, U256::from(2)), (token, U256::from(3))];
+
+        assert_eq!(cache.storage_ref(token, U256::from(2)).unwrap(), U256::ZERO);
+        assert_eq!(
+            cache.storage_batch(&slots).unwrap(),
+            vec![U256::from(1), U256::ZERO, U256::from(2)]
+        );
+        assert_eq!(db.0.load(Ordering::SeqCst), 3);
+
+        assert_eq!(cache.storage_ref(token, U256::from(3)).unwrap(), U256::from(2));
+        assert_eq!(db.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn fails_batches_that_come_back_short() {
+        let cache = StorageCache::new(Arc::new(ShortBatchDb::default()));
+        let token = Address::random();
+        let slots = [(token, U256::from(1)), (token, U256::from(2))];
+
+        assert!(cache.storage_batch(&slots).is_err());
+        // nothing of the batch is cached
+        assert_eq!(cache.storage_ref(token, U256::from(1)).unwrap(), U256::from(1));
+    }
+}
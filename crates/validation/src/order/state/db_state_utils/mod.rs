@@ -1,19 +1,45 @@
 pub mod approvals;
 pub mod balances;
+pub mod cache;
 pub mod nonces;
 
 pub mod finders;
 
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc
+};
 
 use alloy::primitives::{Address, U256};
 use angstrom_metrics::validation::ValidationMetrics;
 
-use self::{approvals::Approvals, balances::Balances, nonces::Nonces};
+use self::{approvals::Approvals, balances::Balances, cache::StorageCache, nonces::Nonces};
+use super::{bond::BondSource, config::TokenBalanceSlot};
+use crate::common::BatchStorageRead;
+
+/// Max amount of nonces that are checked when searching for a free one.
+pub const MAX_NONCE_SEARCH: u64 = 256 * 16;
+
+/// The state an order is validated against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prefetch {
+    pub user:  Address,
+    pub token: Address,
+    /// none for orders that don't use a nonce
+    pub nonce: Option<u64>
+}
 
 pub trait StateFetchUtils: Clone + Send + Unpin {
     fn is_valid_nonce(&self, user: Address, nonce: u64) -> bool;
 
+    /// Returns the first nonce at or above `from` that hasn't been used on
+    /// chain and isn't in `reserved`.
+    fn next_free_nonce(&self, user: Address, from: u64, reserved: &HashSet<u64>) -> Option<u64> {
+        (from..from.saturating_add(MAX_NONCE_SEARCH))
+            .find(|nonce| !reserved.contains(nonce) && self.is_valid_nonce(user, *nonce))
+    }
+
     fn fetch_approval_balance_for_token_overrides(
         &self,
         user: Address,
@@ -33,6 +59,16 @@ pub trait StateFetchUtils: Clone + Send + Unpin {
     fn fetch_balance_for_token(&self, user: Address, token: Address) -> U256;
 
     fn fetch_token_balance_in_angstrom(&self, user: Address, token: Address) -> U256;
+
+    /// Bond `searcher` posted at `source`.
+    fn fetch_searcher_bond(&self, searcher: Address, source: &BondSource) -> U256;
+
+    /// Reads the state a wave of orders is validated against ahead of their
+    /// validation.
+    fn prefetch(&self, _wave: &[Prefetch]) {}
+
+    /// Drops the state prefetched in the last block.
+    fn on_new_block(&self) {}
 }
 
 #[derive(Debug)]
@@ -51,13 +87,13 @@ pub struct FetchUtils<DB> {
     pub approvals: Approvals,
     pub balances:  Balances,
     pub nonces:    Nonces,
-    pub db:        Arc<DB>,
+    pub db:        Arc<StorageCache<DB>>,
     metrics:       ValidationMetrics
 }
 
 impl<DB> StateFetchUtils for FetchUtils<DB>
 where
-    DB: revm::DatabaseRef + Clone + Sync + Send,
+    DB: BatchStorageRead + Clone + Sync + Send,
     <DB as revm::DatabaseRef>::Error: Sync + Send + 'static + Debug
 {
     fn is_valid_nonce(&self, user: Address, nonce: u64) -> bool {
@@ -65,6 +101,10 @@ where
         self.nonces.is_valid_nonce(user, nonce, db)
     }
 
+    fn next_free_nonce(&self, user: Address, from: u64, reserved: &HashSet<u64>) -> Option<u64> {
+        self.nonces.next_free_nonce(user, from, reserved, &*self.db)
+    }
+
     fn fetch_approval_balance_for_token_overrides(
         &self,
         user: Address,
@@ -109,16 +149,63 @@ where
         self.metrics
             .loading_balances(|| self.balances.fetch_balance_for_token(user, token, &self.db))
     }
+
+    fn fetch_searcher_bond(&self, searcher: Address, source: &BondSource) -> U256 {
+        self.metrics.loading_balances(|| match source {
+            BondSource::Angstrom { token } => self
+                .balances
+                .fetch_balance_in_angstrom(*token, searcher, &self.db),
+            BondSource::Staking { contract, slot_index } => {
+                TokenBalanceSlot::new(*contract, *slot_index)
+                    .load_balance(searcher, &*self.db)
+                    .unwrap_or_default()
+            }
+        })
+    }
+
+    fn prefetch(&self, wave: &[Prefetch]) {
+        let mut slots = Vec::with_capacity(wave.len() * 3);
+        for order in wave {
+            if let Some(nonce) = order.nonce {
+                slots.push(self.nonces.storage_slot(order.user, nonce));
+            }
+            // the slots of a token are searched for the first time it's seen,
+            // which reads its storage one slot at a time
+            match self.approvals.storage_slot(order.user, order.token) {
+                Some(slot) => slots.push(slot),
+                None => {
+                    self.fetch_approval_balance_for_token(order.user, order.token);
+                }
+            }
+            match self.balances.storage_slot(order.user, order.token) {
+                Some(slot) => slots.push(slot),
+                None => {
+                    self.fetch_balance_for_token(order.user, order.token);
+                }
+            }
+        }
+
+        if let Err(e) = self
+            .metrics
+            .loading_balances(|| self.db.storage_batch(&slots))
+        {
+            tracing::debug!(slots = slots.len(), err = ?e, "failed to prefetch a wave of orders");
+        }
+    }
+
+    fn on_new_block(&self) {
+        self.db.clear();
+    }
 }
 
 impl<DB: revm::DatabaseRef> FetchUtils<DB> {
     pub fn new(angstrom_address: Address, db: Arc<DB>) -> Self {
         Self {
             approvals: Approvals::new(angstrom_address),
-            balances: Balances::new(angstrom_address),
-            nonces: Nonces::new(angstrom_address),
-            db,
-            metrics: ValidationMetrics::new()
+            balances:  Balances::new(angstrom_address),
+            nonces:    Nonces::new(angstrom_address),
+            db:        Arc::new(StorageCache::new(db)),
+            metrics:   ValidationMetrics::new()
         }
     }
 }
@@ -160,6 +247,10 @@ impl StateFetchUtils for AutoMaxFetchUtils {
     fn fetch_token_balance_in_angstrom(&self, _: Address, _: Address) -> U256 {
         U256::MAX
     }
+
+    fn fetch_searcher_bond(&self, _: Address, _: &BondSource) -> U256 {
+        U256::MAX
+    }
 }
 
 #[cfg(test)]
@@ -176,7 +267,8 @@ pub mod test_fetching {
         balance_values:  DashMap<Address, HashMap<Address, U256>>,
         angstrom_values: DashMap<Address, HashMap<Address, U256>>,
         approval_values: DashMap<Address, HashMap<Address, U256>>,
-        used_nonces:     DashMap<Address, HashSet<u64>>
+        used_nonces:     DashMap<Address, HashSet<u64>>,
+        bonds:           DashMap<Address, U256>
     }
 
     impl MockFetch {
@@ -197,6 +289,10 @@ pub mod test_fetching {
         pub fn set_used_nonces(&self, user: Address, nonces: HashSet<u64>) {
             self.used_nonces.entry(user).or_default().extend(nonces);
         }
+
+        pub fn set_bond_for_searcher(&self, searcher: Address, bond: U256) {
+            self.bonds.insert(searcher, bond);
+        }
     }
 
     impl StateFetchUtils for MockFetch {
@@ -244,6 +340,13 @@ pub mod test_fetching {
                 .and_then(|inner| inner.value().get(&token).cloned())
                 .unwrap_or_default()
         }
+
+        fn fetch_searcher_bond(&self, searcher: Address, _: &BondSource) -> U256 {
+            self.bonds
+                .get(&searcher)
+                .map(|bond| *bond.value())
+                .unwrap_or_default()
+        }
     }
 
     fn setup_mock_fetch() -> MockFetch {
@@ -304,6 +407,19 @@ pub mod test_fetching {
         assert!(mock.is_valid_nonce(user, 3));
     }
 
+    #[test]
+    fn test_next_free_nonce_skips_used_and_reserved() {
+        let mock = setup_mock_fetch();
+        let user = address!("1234567890123456789012345678901234567890");
+
+        assert_eq!(mock.next_free_nonce(user, 0, &HashSet::new()), Some(0));
+
+        mock.set_used_nonces(user, HashSet::from([0, 1, 3]));
+        assert_eq!(mock.next_free_nonce(user, 0, &HashSet::new()), Some(2));
+        assert_eq!(mock.next_free_nonce(user, 0, &HashSet::from([2])), Some(4));
+        assert_eq!(mock.next_free_nonce(user, 5, &HashSet::new()), Some(5));
+    }
+
     #[test]
     fn test_angstrom_balance() {
         let mock = setup_mock_fetch();
@@ -1,8 +1,10 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::HashSet, fmt::Debug, sync::Arc};
 
 use alloy::primitives::{hex, keccak256, Address, B256, U256};
 use reth_revm::DatabaseRef;
 
+use super::MAX_NONCE_SEARCH;
+
 /// The nonce location for quick db lookup
 const ANGSTROM_NONCE_SLOT_CONST: [u8; 4] = hex!("daa050e9");
 
@@ -23,6 +25,11 @@ impl Nonces {
         keccak256(arry)
     }
 
+    /// Where the word holding `nonce` of `user` is stored.
+    pub fn storage_slot(&self, user: Address, nonce: u64) -> (Address, U256) {
+        (self.0, self.get_nonce_word_slot(user, nonce).into())
+    }
+
     pub fn is_valid_nonce<DB: revm::DatabaseRef>(
         &self,
         user: Address,
@@ -42,4 +49,37 @@ impl Nonces {
         tracing::debug!(?word, %out);
         out
     }
+
+    /// Walks the users nonce bitmap one word at a time, returning the first
+    /// nonce at or above `from` whose bit isn't set and that isn't `reserved`.
+    pub fn next_free_nonce<DB: revm::DatabaseRef>(
+        &self,
+        user: Address,
+        from: u64,
+        reserved: &HashSet<u64>,
+        db: &DB
+    ) -> Option<u64>
+    where
+        <DB as DatabaseRef>::Error: Sync + Send + 'static + Debug
+    {
+        let mut word_start = from & !0xff;
+        let mut first_bit = from & 0xff;
+
+        for _ in 0..MAX_NONCE_SEARCH / 256 {
+            let slot = self.get_nonce_word_slot(user, word_start);
+            let word = db.storage_ref(self.0, slot.into()).unwrap();
+
+            let free = (first_bit..256)
+                .map(|bit| word_start | bit)
+                .find(|nonce| !word.bit((nonce & 0xff) as usize) && !reserved.contains(nonce));
+            if free.is_some() {
+                return free
+            }
+
+            word_start = word_start.checked_add(256)?;
+            first_bit = 0;
+        }
+
+        None
+    }
 }
@@ -0,0 +1,156 @@
+use alloy::primitives::{Address, FixedBytes};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Hook data is the address of the hook followed by the payload it's called
+/// with.
+const HOOK_ADDRESS_LEN: usize = 20;
+const PREFIX_LEN: usize = 4;
+
+fn default_max_hook_data_len() -> usize {
+    1024
+}
+
+/// A hook composable orders can call.
+///
+/// The contract always calls a hook with `compose(from, payload)`, the hook
+/// decodes the payload however it likes. The prefixes are only a check of the
+/// first bytes of the payload, they don't select a function of the hook.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct AllowedHook {
+    pub address:          Address,
+    /// 4 bytes the payload can start with, any payload is allowed if empty
+    #[serde(default)]
+    pub payload_prefixes: Vec<FixedBytes<4>>
+}
+
+/// The hooks composable orders can call. Orders calling anything else are
+/// rejected when they are validated instead of failing the simulation of the
+/// bundle they end up in.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct HookAllowlist {
+    #[serde(default)]
+    pub hooks:             Vec<AllowedHook>,
+    /// max length of the hook data, address included
+    #[serde(default = "default_max_hook_data_len")]
+    pub max_hook_data_len: usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HookDataError {
+    /// the contract would read the missing bytes of the address from
+    /// whatever follows in memory
+    #[error("hook data of {0} bytes is shorter than the hook address")]
+    MissingAddress(usize),
+    #[error("hook data of {len} bytes is over the max of {max}")]
+    TooLong { len: usize, max: usize },
+    #[error("hook {0:?} isn't allowed")]
+    UnknownHook(Address),
+    #[error("hook {hook:?} isn't allowed to be called with a payload starting with {prefix:?}")]
+    UnknownPrefix { hook: Address, prefix: Option<FixedBytes<4>> }
+}
+
+impl HookAllowlist {
+    /// Checks the hook data of an order, data of an order without hook is
+    /// empty and always passes. The payload is only checked against the
+    /// prefixes of its hook, nothing after them.
+    pub fn check(&self, hook_data: &[u8]) -> Result<(), HookDataError> {
+        if hook_data.is_empty() {
+            return Ok(())
+        }
+        if hook_data.len() > self.max_hook_data_len {
+            return Err(HookDataError::TooLong { len: hook_data.len(), max: self.max_hook_data_len })
+        }
+        if hook_data.len() < HOOK_ADDRESS_LEN {
+            return Err(HookDataError::MissingAddress(hook_data.len()))
+        }
+
+        let (address, payload) = hook_data.split_at(HOOK_ADDRESS_LEN);
+        let hook = Address::from_slice(address);
+        let allowed = self
+            .hooks
+            .iter()
+            .find(|allowed| allowed.address == hook)
+            .ok_or(HookDataError::UnknownHook(hook))?;
+        if allowed.payload_prefixes.is_empty() {
+            return Ok(())
+        }
+
+        let prefix = payload.get(..PREFIX_LEN).map(FixedBytes::from_slice);
+        if !prefix.is_some_and(|prefix| allowed.payload_prefixes.contains(&prefix)) {
+            return Err(HookDataError::UnknownPrefix { hook, prefix })
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, fixed_bytes};
+
+    use super::*;
+
+    const HOOK: Address = address!("00000000000000000000000000000000000000aa");
+    const OPEN: Address = address!("00000000000000000000000000000000000000bb");
+
+    fn allowlist() -> HookAllowlist {
+        HookAllowlist {
+            hooks:             vec![
+                AllowedHook {
+                    address:          HOOK,
+                    payload_prefixes: vec![fixed_bytes!("12345678")]
+                },
+                AllowedHook { address: OPEN, payload_prefixes: vec![] },
+            ],
+            max_hook_data_len: 64
+        }
+    }
+
+    fn hook_data(hook: Address, payload: &[u8]) -> Vec<u8> {
+        [hook.as_slice(), payload].concat()
+    }
+
+    #[test]
+    fn allows_the_listed_prefixes_of_the_listed_hooks() {
+        let allowlist = allowlist();
+        assert_eq!(allowlist.check(&[]), Ok(()));
+        assert_eq!(allowlist.check(&hook_data(HOOK, &[0x12, 0x34, 0x56, 0x78, 1])), Ok(()));
+        assert_eq!(allowlist.check(&hook_data(OPEN, &[])), Ok(()));
+
+        assert_eq!(
+            allowlist.check(&hook_data(HOOK, &[0x12, 0x34, 0x56, 0x79])),
+            Err(HookDataError::UnknownPrefix {
+                hook:   HOOK,
+                prefix: Some(fixed_bytes!("12345679"))
+            })
+        );
+        assert_eq!(
+            allowlist.check(&hook_data(HOOK, &[0x12])),
+            Err(HookDataError::UnknownPrefix { hook: HOOK, prefix: None })
+        );
+        assert_eq!(
+            allowlist.check(&hook_data(Address::ZERO, &[])),
+            Err(HookDataError::UnknownHook(Address::ZERO))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_hook_data() {
+        let allowlist = allowlist();
+        assert_eq!(allowlist.check(&HOOK.as_slice()[..12]), Err(HookDataError::MissingAddress(12)));
+        assert_eq!(
+            allowlist.check(&hook_data(OPEN, &[0; 45])),
+            Err(HookDataError::TooLong { len: 65, max: 64 })
+        );
+    }
+
+    #[test]
+    fn defaults_the_max_length() {
+        let allowlist: HookAllowlist =
+            toml::from_str("[[hooks]]\naddress = \"0x00000000000000000000000000000000000000aa\"")
+                .unwrap();
+        assert_eq!(allowlist.max_hook_data_len, default_max_hook_data_len());
+        assert!(allowlist.hooks[0].payload_prefixes.is_empty());
+    }
+}
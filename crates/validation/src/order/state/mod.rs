@@ -1,21 +1,34 @@
 use std::sync::Arc;
 
-use account::UserAccountProcessor;
-use alloy::primitives::{Address, B256};
+use account::{NonceCollision, UserAccountProcessor};
+use alloy::{
+    primitives::{Address, B256},
+    sol_types::Eip712Domain
+};
 use angstrom_metrics::validation::ValidationMetrics;
-use angstrom_types::sol_bindings::{
-    ext::RawPoolOrder, grouped_orders::AllOrders, rpc_orders::TopOfBlockOrder
+use angstrom_types::{
+    contract_payloads::tob::{TobSimulation, TobStateOverrides},
+    primitive::ANGSTROM_DOMAIN,
+    sol_bindings::{
+        ext::RawPoolOrder,
+        grouped_orders::{AllOrders, GroupedVanillaOrder},
+        rpc_orders::TopOfBlockOrder
+    }
 };
+use bond::SearcherBondConfig;
 use db_state_utils::StateFetchUtils;
+use hooks::HookAllowlist;
 use parking_lot::RwLock;
 use pools::PoolsTracker;
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 
-use super::OrderValidationResults;
+use super::{signatures::SignatureVerifier, OrderValidationResults};
 
 pub mod account;
+pub mod bond;
 pub mod config;
 pub mod db_state_utils;
+pub mod hooks;
 pub mod pools;
 
 /// State validation is all validation that requires reading from the Ethereum
@@ -24,13 +37,25 @@ pub mod pools;
 /// 2) checking token balances
 /// 3) checking token approvals
 /// 4) deals with possible pending state
+/// 5) checking the bond of searchers
+/// 6) checking the hooks composable orders call
 pub struct StateValidation<Pools, Fetch> {
     /// tracks everything user related.
     user_account_tracker: Arc<UserAccountProcessor<Fetch>>,
     /// tracks all info about the current angstrom pool state.
     pool_tacker:          Arc<RwLock<Pools>>,
     /// keeps up-to-date with the on-chain pool
-    uniswap_pools:        SyncedUniswapPools
+    uniswap_pools:        SyncedUniswapPools,
+    /// the domain orders have to be signed in, pins them to the chain and
+    /// contract we are running against
+    domain:               Eip712Domain,
+    /// checks the signatures for the domain, in batches when orders arrive
+    /// together
+    signatures:           SignatureVerifier,
+    /// bond searchers have to post, unset accepts unbonded searchers
+    searcher_bond:        Option<Arc<SearcherBondConfig>>,
+    /// hooks composable orders can call, unset doesn't check them
+    hook_allowlist:       Option<Arc<HookAllowlist>>
 }
 
 impl<Pools, Fetch> Clone for StateValidation<Pools, Fetch> {
@@ -38,7 +63,11 @@ impl<Pools, Fetch> Clone for StateValidation<Pools, Fetch> {
         Self {
             user_account_tracker: Arc::clone(&self.user_account_tracker),
             pool_tacker:          Arc::clone(&self.pool_tacker),
-            uniswap_pools:        self.uniswap_pools.clone()
+            uniswap_pools:        self.uniswap_pools.clone(),
+            domain:               self.domain.clone(),
+            signatures:           self.signatures.clone(),
+            searcher_bond:        self.searcher_bond.clone(),
+            hook_allowlist:       self.hook_allowlist.clone()
         }
     }
 }
@@ -52,15 +81,60 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
         Self {
             pool_tacker: Arc::new(RwLock::new(pools)),
             user_account_tracker: Arc::new(user_account_tracker),
-            uniswap_pools
+            uniswap_pools,
+            signatures: SignatureVerifier::new(&ANGSTROM_DOMAIN),
+            domain: ANGSTROM_DOMAIN,
+            searcher_bond: None,
+            hook_allowlist: None
         }
     }
 
+    pub fn with_domain(mut self, domain: Eip712Domain) -> Self {
+        self.signatures = SignatureVerifier::new(&domain);
+        self.domain = domain;
+        self
+    }
+
+    pub fn with_searcher_bond(mut self, config: SearcherBondConfig) -> Self {
+        self.searcher_bond = Some(Arc::new(config));
+        self
+    }
+
+    pub fn with_hook_allowlist(mut self, allowlist: HookAllowlist) -> Self {
+        self.hook_allowlist = Some(Arc::new(allowlist));
+        self
+    }
+
     pub fn new_block(&self, completed_orders: Vec<B256>, address_changes: Vec<Address>) {
+        self.signatures.clear();
         self.user_account_tracker
             .prepare_for_new_block(address_changes, completed_orders)
     }
 
+    pub fn prefetch(&self, orders: &[AllOrders]) {
+        self.user_account_tracker.prefetch(orders);
+    }
+
+    /// Verifies the signatures of a wave of orders at once, returns how many
+    /// are invalid.
+    pub fn verify_signatures(&self, orders: &[AllOrders]) -> usize {
+        self.signatures.verify_batch(orders)
+    }
+
+    pub fn check_nonce(
+        &self,
+        user: Address,
+        nonce: u64,
+        order_hash: B256
+    ) -> Result<(), NonceCollision> {
+        self.user_account_tracker
+            .check_nonce(user, nonce, order_hash)
+    }
+
+    pub fn next_free_nonce(&self, user: Address) -> Option<u64> {
+        self.user_account_tracker.next_free_nonce(user)
+    }
+
     pub fn handle_regular_order<O: RawPoolOrder + Into<AllOrders>>(
         &self,
         order: O,
@@ -69,7 +143,7 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
     ) -> OrderValidationResults {
         metrics.applying_state_transitions(|| {
             let order_hash = order.order_hash();
-            if !order.is_valid_signature() {
+            if !self.signatures.is_valid(&order) {
                 tracing::debug!("order had invalid hash");
                 return OrderValidationResults::Invalid(order_hash)
             }
@@ -78,6 +152,11 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
                 tracing::debug!("order requested a invalid pool");
                 return OrderValidationResults::Invalid(order_hash);
             };
+            // pools that failed to load can't be matched until they do
+            if !self.uniswap_pools.contains(&pool_info.pool_id) {
+                tracing::debug!(pool_id = ?pool_info.pool_id, "order is for a pool that isn't synced");
+                return OrderValidationResults::Invalid(order_hash)
+            }
 
             self.user_account_tracker
                 .verify_order::<O>(order, pool_info, block)
@@ -93,6 +172,25 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
         })
     }
 
+    /// Validates a user order, the hook data of composable orders is checked
+    /// against the allowlist first.
+    pub fn handle_limit_order(
+        &self,
+        order: GroupedVanillaOrder,
+        block: u64,
+        metrics: ValidationMetrics
+    ) -> OrderValidationResults {
+        if let Some(allowlist) = self.hook_allowlist.as_deref() {
+            if let Err(error) = allowlist.check(order.hook_data()) {
+                let order_hash = order.order_hash();
+                tracing::debug!(?order_hash, %error, "order calls a disallowed hook");
+                return OrderValidationResults::Invalid(order_hash)
+            }
+        }
+
+        self.handle_regular_order(order, block, metrics)
+    }
+
     pub async fn handle_tob_order(
         &self,
         order: TopOfBlockOrder,
@@ -102,6 +200,17 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
         let mut results = self.handle_regular_order(order, block, metrics);
 
         if let OrderValidationResults::Valid(ref mut order_with_storage) = results {
+            if let Some(bond) = self.searcher_bond.as_deref() {
+                let tier = self
+                    .user_account_tracker
+                    .searcher_bond_tier(order_with_storage.from(), bond);
+                if tier == 0 {
+                    tracing::debug!(searcher = ?order_with_storage.from(), "searcher isn't bonded");
+                    return OrderValidationResults::Invalid(order_with_storage.order_id.hash)
+                }
+                order_with_storage.bond_tier = tier;
+            }
+
             let tob_order = order_with_storage
                 .clone()
                 .try_map_inner(|inner| {
@@ -110,15 +219,117 @@ impl<Pools: PoolsTracker, Fetch: StateFetchUtils> StateValidation<Pools, Fetch>
                 })
                 .expect("should be unreachable");
             let pool_address = order_with_storage.pool_id;
-            let rewards = self
+            let rewards = match self
                 .uniswap_pools
                 .calculate_rewards(pool_address, &tob_order)
                 .await
-                .unwrap();
+            {
+                Ok(rewards) => rewards,
+                Err(error) => {
+                    tracing::debug!(pool_id = ?pool_address, %error, "can't simulate the order");
+                    return OrderValidationResults::Invalid(order_with_storage.order_id.hash)
+                }
+            };
 
             order_with_storage.tob_reward = rewards.total_reward;
         }
 
         results
     }
+
+    /// Validates an order that only another node holds against the state on
+    /// chain alone, see [`UserAccountProcessor::check_order`]. Nothing of the
+    /// order is kept, and a valid order that the state can't support is
+    /// returned as not currently valid.
+    pub fn check_order(&self, order: AllOrders, block: u64) -> OrderValidationResults {
+        let order_hash = order.order_hash();
+        if !self.signatures.is_valid(&order) {
+            return OrderValidationResults::Invalid(order_hash)
+        }
+        let hook_data = match &order {
+            AllOrders::Standing(order) => Some(order.hook_data()),
+            AllOrders::Flash(order) => Some(order.hook_data()),
+            AllOrders::TOB(_) => None
+        };
+        if let (Some(allowlist), Some(hook_data)) = (self.hook_allowlist.as_deref(), hook_data) {
+            if allowlist.check(hook_data).is_err() {
+                return OrderValidationResults::Invalid(order_hash)
+            }
+        }
+        let Some(pool_info) = self.pool_tacker.read().fetch_pool_info_for_order(&order) else {
+            return OrderValidationResults::Invalid(order_hash)
+        };
+
+        let mut checked = match self
+            .user_account_tracker
+            .check_order(order, pool_info, block)
+        {
+            Ok(checked) => checked,
+            Err(e) => {
+                tracing::debug!(%e, ?order_hash, "order failed its check");
+                return OrderValidationResults::Invalid(order_hash)
+            }
+        };
+        if let (Some(bond), AllOrders::TOB(_)) = (self.searcher_bond.as_deref(), &checked.order) {
+            checked.bond_tier = self
+                .user_account_tracker
+                .searcher_bond_tier(checked.from(), bond);
+            if checked.bond_tier == 0 {
+                return OrderValidationResults::Invalid(order_hash)
+            }
+        }
+
+        OrderValidationResults::Valid(checked)
+    }
+
+    /// Runs the checks of [`Self::handle_tob_order`] on the order and
+    /// simulates it against the pool, with the overrides applied, without
+    /// the order touching any state.
+    pub async fn simulate_tob_order(
+        &self,
+        order: TopOfBlockOrder,
+        block: u64,
+        overrides: &TobStateOverrides
+    ) -> TobSimulation {
+        if !order.is_valid_signature_in(&self.domain) {
+            return TobSimulation::invalid("invalid signature")
+        }
+        let Some(pool_info) = self.pool_tacker.read().fetch_pool_info_for_order(&order) else {
+            return TobSimulation::invalid("no pool for the pair of the order")
+        };
+
+        let mut order = match self
+            .user_account_tracker
+            .dry_run_order(order, pool_info, block, overrides)
+        {
+            Ok(order) => order,
+            Err(e) => return TobSimulation::invalid(e)
+        };
+        if let Some(bond) = self.searcher_bond.as_deref() {
+            order.bond_tier = self
+                .user_account_tracker
+                .searcher_bond_tier(order.from(), bond);
+        }
+
+        let outcome = self
+            .uniswap_pools
+            .simulate_rewards(order.pool_id, &order, overrides);
+        let mut simulation = match outcome {
+            Ok(outcome) => TobSimulation::from_outcome(&outcome),
+            Err(e) => return TobSimulation::invalid(e)
+        };
+        let invalid_reason = if !order.is_currently_valid {
+            Some("insufficient balance or approval")
+        } else if self.searcher_bond.is_some() && order.bond_tier == 0 {
+            Some("searcher isn't bonded")
+        } else {
+            None
+        };
+        if let Some(reason) = invalid_reason {
+            simulation.valid = false;
+            simulation.invalid_reason = Some(reason.to_string());
+        }
+
+        simulation
+    }
 }
@@ -0,0 +1,111 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Order validations the queue holds before new orders from the rpc are
+/// turned away.
+pub const DEFAULT_VALIDATION_QUEUE_LIMIT: usize = 4096;
+/// How long rejected submitters are told to wait before retrying.
+pub const DEFAULT_VALIDATION_RETRY_AFTER: Duration = Duration::from_millis(500);
+
+/// Why an order wasn't taken in for validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+#[error("validation queue is full ({depth}/{limit}), retry after {retry_after_ms}ms")]
+pub struct ValidationBackpressure {
+    pub depth:          usize,
+    pub limit:          usize,
+    pub retry_after_ms: u64
+}
+
+/// The order validations that were requested but haven't returned yet, shared
+/// by every client of a validator.
+///
+/// Orders of peers are always validated, only new orders from the rpc are
+/// turned away once the queue is full, so that the worst case latency of a
+/// validation stays bounded.
+#[derive(Debug, Clone)]
+pub struct ValidationQueue {
+    depth:       Arc<AtomicUsize>,
+    limit:       usize,
+    retry_after: Duration
+}
+
+impl Default for ValidationQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_VALIDATION_QUEUE_LIMIT, DEFAULT_VALIDATION_RETRY_AFTER)
+    }
+}
+
+impl ValidationQueue {
+    pub fn new(limit: usize, retry_after: Duration) -> Self {
+        Self { depth: Arc::new(AtomicUsize::new(0)), limit, retry_after }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Errors if the queue is too deep to take in another order.
+    pub fn admit(&self) -> Result<(), ValidationBackpressure> {
+        let depth = self.depth();
+        if depth < self.limit {
+            return Ok(())
+        }
+
+        Err(ValidationBackpressure {
+            depth,
+            limit: self.limit,
+            retry_after_ms: self.retry_after.as_millis() as u64
+        })
+    }
+
+    /// Counts a validation until the returned guard is dropped.
+    pub fn enter(&self) -> QueuedValidation {
+        self.depth.fetch_add(1, Ordering::Relaxed);
+        QueuedValidation { depth: self.depth.clone() }
+    }
+}
+
+/// A validation counted in the [`ValidationQueue`].
+#[derive(Debug)]
+pub struct QueuedValidation {
+    depth: Arc<AtomicUsize>
+}
+
+impl Drop for QueuedValidation {
+    fn drop(&mut self) {
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_orders_away_while_full() {
+        let queue = ValidationQueue::new(2, Duration::from_secs(1));
+        let first = queue.enter();
+        let _second = queue.clone().enter();
+        assert_eq!(
+            queue.admit(),
+            Err(ValidationBackpressure {
+                depth:          2,
+                limit:          2,
+                retry_after_ms: 1000
+            })
+        );
+
+        drop(first);
+        assert_eq!(queue.depth(), 1);
+        assert!(queue.admit().is_ok());
+    }
+}
@@ -1,7 +1,13 @@
 use std::{fmt::Debug, task::Poll};
 
 use alloy::primitives::{Address, B256};
-use angstrom_types::contract_payloads::angstrom::{AngstromBundle, BundleGasDetails};
+use angstrom_types::{
+    contract_payloads::{
+        angstrom::{AngstromBundle, BundleGasDetails},
+        tob::{TobSimulation, TobStateOverrides}
+    },
+    sol_bindings::{grouped_orders::AllOrders, rpc_orders::TopOfBlockOrder}
+};
 use futures_util::{Future, FutureExt};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
@@ -10,9 +16,10 @@ use crate::{
     common::SharedTools,
     order::{
         order_validator::OrderValidator,
-        state::{db_state_utils::StateFetchUtils, pools::PoolsTracker},
+        state::{account::NonceCollision, db_state_utils::StateFetchUtils, pools::PoolsTracker},
         OrderValidationRequest, OrderValidationResults
-    }
+    },
+    queue::ValidationQueue
 };
 
 pub enum ValidationRequest {
@@ -29,11 +36,39 @@ pub enum ValidationRequest {
         block_number: u64,
         orders:       Vec<B256>,
         addresses:    Vec<Address>
+    },
+    /// checks that a standing order nonce isn't used on chain or won by a
+    /// pending order
+    CheckNonce {
+        sender:     tokio::sync::oneshot::Sender<Result<(), NonceCollision>>,
+        user:       Address,
+        nonce:      u64,
+        order_hash: B256
+    },
+    /// lowest nonce the user can use for a new standing order
+    NextNonce {
+        sender: tokio::sync::oneshot::Sender<Option<u64>>,
+        user:   Address
+    },
+    /// simulates a top of block order against the pool, with the overrides
+    /// applied, without keeping anything of it
+    SimulateTob {
+        sender:    tokio::sync::oneshot::Sender<TobSimulation>,
+        order:     TopOfBlockOrder,
+        overrides: TobStateOverrides
+    },
+    /// validates an order that only another node holds against the state on
+    /// chain, without keeping anything of it
+    CheckOrder {
+        sender: tokio::sync::oneshot::Sender<OrderValidationResults>,
+        order:  AllOrders
     }
 }
 
+/// Sends requests to the validator, counting its order validations in the
+/// queue shared with the other clients.
 #[derive(Debug, Clone)]
-pub struct ValidationClient(pub UnboundedSender<ValidationRequest>);
+pub struct ValidationClient(pub UnboundedSender<ValidationRequest>, pub ValidationQueue);
 
 pub struct Validator<DB, Pools, Fetch> {
     rx:               UnboundedReceiver<ValidationRequest>,
@@ -78,7 +113,8 @@ where
                     &self.utils.token_pricing,
                     &mut self.utils.thread_pool,
                     self.utils.metrics.clone(),
-                    bn
+                    bn,
+                    self.utils.bundle_gas_price()
                 );
             }
             ValidationRequest::NewBlock { sender, block_number, orders, addresses } => {
@@ -91,6 +127,28 @@ where
                     .send(OrderValidationResults::TransitionedToBlock)
                     .unwrap();
             }
+            ValidationRequest::CheckNonce { sender, user, nonce, order_hash } => {
+                let _ = sender.send(self.order_validator.check_nonce(user, nonce, order_hash));
+            }
+            ValidationRequest::NextNonce { sender, user } => {
+                let _ = sender.send(self.order_validator.next_free_nonce(user));
+            }
+            ValidationRequest::SimulateTob { sender, order, overrides } => {
+                self.order_validator.simulate_tob_order(
+                    sender,
+                    order,
+                    overrides,
+                    &mut self.utils.thread_pool
+                );
+            }
+            ValidationRequest::CheckOrder { sender, order } => {
+                self.order_validator.check_order(
+                    sender,
+                    order,
+                    self.utils.token_pricing_snapshot(),
+                    &mut self.utils.thread_pool
+                );
+            }
         }
     }
 }
@@ -108,7 +166,29 @@ where
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>
     ) -> std::task::Poll<Self::Output> {
+        let mut requests = vec![];
         while let Poll::Ready(Some(req)) = self.rx.poll_recv(cx) {
+            requests.push(req);
+        }
+
+        // the orders that arrived together have their signatures verified and
+        // their state read in one batch
+        let wave: Vec<_> = requests
+            .iter()
+            .filter_map(|req| match req {
+                ValidationRequest::Order(OrderValidationRequest::ValidateOrder(_, order, _)) => {
+                    Some(order.clone())
+                }
+                _ => None
+            })
+            .collect();
+        let this = &mut *self;
+        this.order_validator
+            .verify_signatures(wave.clone(), &mut this.utils.thread_pool);
+        this.order_validator
+            .prefetch(wave, &mut this.utils.thread_pool);
+
+        for req in requests {
             self.on_new_validation_request(req);
         }
 
@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 
+use alloy::sol_types::Eip712Domain;
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 
 use crate::providers::{AnvilStateProvider, WalletProvider};
@@ -12,5 +13,7 @@ pub struct AgentConfig {
     pub rpc_address:    SocketAddr,
     pub agent_id:       u64,
     pub current_block:  u64,
-    pub state_provider: AnvilStateProvider<WalletProvider>
+    pub state_provider: AnvilStateProvider<WalletProvider>,
+    /// what the orders of the testnet are signed for
+    pub domain:         Eip712Domain
 }
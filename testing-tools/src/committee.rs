@@ -0,0 +1,257 @@
+//! Bootstrap of the committee that co-signs the proposals, for testnets.
+//!
+//! Every member generates its validator key on its own and registers with a
+//! signature of its index. The members then run a distributed key generation
+//! for the threshold key of the committee: each one deals a random polynomial,
+//! broadcasts the commitments to it and sends every other member its value
+//! privately. Dealers that send a member a value they didn't commit to are
+//! disqualified, the committee key is the sum of the polynomials of the
+//! others. No member ever sees the committee key or the share of another one.
+use std::{collections::HashSet, path::Path};
+
+use alloy::{
+    hex,
+    primitives::{keccak256, B256},
+    signers::{Signature, SignerSync}
+};
+use angstrom_types::{
+    consensus::{
+        threshold::{
+            finish_key_share, finish_public_key, Dealing, DealingCommitments, KeyShare,
+            ThresholdPublicKey
+        },
+        ProposalCommittee
+    },
+    primitive::{AngstromSigner, PeerId}
+};
+
+/// A member announcing the key it co-signs proposals with.
+#[derive(Debug, Clone)]
+pub struct CommitteeRegistration {
+    pub index:     usize,
+    pub member:    PeerId,
+    /// over the index, proves the member holds the key
+    pub signature: Signature
+}
+
+impl CommitteeRegistration {
+    pub fn new(index: usize, signer: &AngstromSigner) -> Self {
+        let signature = signer.sign_hash_sync(&Self::signing_hash(index)).unwrap();
+
+        Self { index, member: signer.id(), signature }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.signature
+            .recover_from_prehash(&Self::signing_hash(self.index))
+            .is_ok_and(|key| AngstromSigner::public_key_to_peer_id(&key) == self.member)
+    }
+
+    fn signing_hash(index: usize) -> B256 {
+        keccak256(
+            [b"angstrom proposal committee".as_slice(), &(index as u64).to_be_bytes()].concat()
+        )
+    }
+}
+
+/// The members of the valid registrations, in the order of their indices.
+pub fn members_from_registrations(
+    mut registrations: Vec<CommitteeRegistration>
+) -> eyre::Result<Vec<PeerId>> {
+    if let Some(invalid) = registrations
+        .iter()
+        .find(|registration| !registration.is_valid())
+    {
+        return Err(eyre::eyre!("registration {} isn't signed by its member", invalid.index))
+    }
+    registrations.sort_by_key(|registration| registration.index);
+
+    Ok(registrations
+        .into_iter()
+        .map(|registration| registration.member)
+        .collect())
+}
+
+/// Finishes the key generation from the commitments every dealer broadcast
+/// and the values every member got, `received[member][dealer]`.
+///
+/// Returns the public key of the committee and the key share of every member.
+pub fn finish_key_generation(
+    commitments: &[DealingCommitments],
+    received: &[Vec<B256>]
+) -> eyre::Result<(ThresholdPublicKey, Vec<KeyShare>)> {
+    let members = received.len();
+    let disqualified = (0..commitments.len())
+        .filter(|&dealer| {
+            received.iter().enumerate().any(|(member, values)| {
+                values.get(dealer).map_or(true, |value| {
+                    !commitments[dealer].verify_share(member as u64 + 1, value)
+                })
+            })
+        })
+        .collect::<HashSet<_>>();
+    if disqualified.len() == commitments.len() {
+        return Err(eyre::eyre!("every dealer was disqualified"))
+    }
+    for dealer in &disqualified {
+        tracing::warn!(dealer, "disqualified a dealer of the committee key");
+    }
+
+    let qualified = |dealer: &usize| !disqualified.contains(dealer);
+    let public_key = finish_public_key(
+        &commitments
+            .iter()
+            .enumerate()
+            .filter(|(dealer, _)| qualified(dealer))
+            .map(|(_, commitments)| commitments.clone())
+            .collect::<Vec<_>>(),
+        members
+    )
+    .ok_or_else(|| eyre::eyre!("invalid commitments"))?;
+    let key_shares = received
+        .iter()
+        .enumerate()
+        .map(|(member, values)| {
+            let values = values
+                .iter()
+                .enumerate()
+                .filter(|(dealer, _)| qualified(dealer))
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>();
+            finish_key_share(member as u64 + 1, &values)
+                .ok_or_else(|| eyre::eyre!("invalid key share of member {member}"))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    Ok((public_key, key_shares))
+}
+
+/// A bootstrapped committee, with the keys of its members.
+#[derive(Debug, Clone)]
+pub struct CommitteeBootstrap {
+    pub committee:  ProposalCommittee,
+    /// the validator key of every member
+    pub signers:    Vec<AngstromSigner>,
+    /// the share of the committee key of every member
+    pub key_shares: Vec<KeyShare>
+}
+
+/// Bootstraps a committee of `members` new validators, `threshold` of which
+/// sign a proposal.
+pub fn bootstrap_committee(members: usize, threshold: usize) -> eyre::Result<CommitteeBootstrap> {
+    let signers = (0..members)
+        .map(|_| AngstromSigner::random())
+        .collect::<Vec<_>>();
+    let registrations = signers
+        .iter()
+        .enumerate()
+        .map(|(index, signer)| CommitteeRegistration::new(index, signer))
+        .collect();
+    let member_ids = members_from_registrations(registrations)?;
+
+    let dealings = (0..members)
+        .map(|_| Dealing::new(threshold, members))
+        .collect::<Vec<_>>();
+    let received = (1..=members as u64)
+        .map(|index| {
+            dealings
+                .iter()
+                .map(|dealing| dealing.share_for(index).expect("dealt for every member"))
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    let commitments = dealings
+        .into_iter()
+        .map(|dealing| dealing.commitments)
+        .collect::<Vec<_>>();
+    let (public_key, key_shares) = finish_key_generation(&commitments, &received)?;
+
+    Ok(CommitteeBootstrap {
+        committee: ProposalCommittee::new(member_ids, threshold, public_key)?,
+        signers,
+        key_shares
+    })
+}
+
+/// Writes the committee to `dir/committee.json`, for the `proposal_committee`
+/// of the node config, the key of every member to `dir/member-<index>.key`,
+/// for `--secret-key-location`, and its key share to
+/// `dir/member-<index>.share`, for `--committee-key-share-location`.
+pub fn write_committee(dir: &Path, bootstrap: &CommitteeBootstrap) -> eyre::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    bootstrap.committee.save(&dir.join("committee.json"))?;
+    for (index, (signer, key_share)) in bootstrap
+        .signers
+        .iter()
+        .zip(&bootstrap.key_shares)
+        .enumerate()
+    {
+        std::fs::write(dir.join(format!("member-{index}.key")), hex::encode(signer.to_bytes()))?;
+        key_share.save(&dir.join(format!("member-{index}.share")))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use angstrom_types::consensus::{threshold, ProposalShare};
+
+    use super::*;
+
+    #[test]
+    fn bootstraps_a_committee_that_loads_back() {
+        let bootstrap = bootstrap_committee(4, 3).unwrap();
+        let committee = &bootstrap.committee;
+        assert_eq!(committee.threshold(), 3);
+        assert_eq!(
+            committee.members(),
+            bootstrap.signers.iter().map(|s| s.id()).collect::<Vec<_>>()
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        write_committee(dir.path(), &bootstrap).unwrap();
+        assert_eq!(
+            &ProposalCommittee::load(&dir.path().join("committee.json")).unwrap(),
+            committee
+        );
+        let key_share = KeyShare::load(&dir.path().join("member-2.share")).unwrap();
+        assert_eq!(committee.share_key(&committee.members()[2]), Some(&key_share.public_key()));
+
+        let mut forged = CommitteeRegistration::new(0, &bootstrap.signers[0]);
+        forged.index = 1;
+        assert!(members_from_registrations(vec![forged]).is_err());
+    }
+
+    #[test]
+    fn disqualifies_dealers_that_send_values_they_didnt_commit_to() {
+        let dealings = (0..3).map(|_| Dealing::new(2, 3)).collect::<Vec<_>>();
+        let mut received = (1..=3)
+            .map(|index| {
+                dealings
+                    .iter()
+                    .map(|dealing| dealing.share_for(index).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // the second dealer sends the first member the value of the third
+        received[0][1] = dealings[1].share_for(3).unwrap();
+        let commitments = dealings
+            .iter()
+            .map(|dealing| dealing.commitments.clone())
+            .collect::<Vec<_>>();
+
+        let (public_key, key_shares) = finish_key_generation(&commitments, &received).unwrap();
+        let honest = finish_public_key(&[commitments[0].clone(), commitments[2].clone()], 3);
+        assert_eq!(Some(&public_key), honest.as_ref());
+
+        // the shares of the honest dealers still sign for the committee
+        let payload = ProposalShare::signing_payload(1, B256::repeat_byte(1));
+        let signature = threshold::combine(&[
+            (1, key_shares[0].sign(&payload)),
+            (3, key_shares[2].sign(&payload))
+        ])
+        .unwrap();
+        assert!(threshold::verify(&public_key.public_key, &payload, &signature));
+    }
+}
@@ -265,8 +265,9 @@ mod tests {
         };
         let outcome =
             OrderOutcome { id: user_order.order_id, outcome: OrderFillState::CompleteFill };
-        let _encode =
-            UserOrder::from_internal_order_max_gas(&user_order, &outcome, 0).pade_encode();
+        let _encode = UserOrder::from_internal_order_max_gas(&user_order, &outcome, 0)
+            .unwrap()
+            .pade_encode();
     }
 
     #[tokio::test]
@@ -81,6 +81,7 @@ where
                 )
                 .await?;
                 let provider = initializer.provider_mut().provider_mut();
+                provider.deploy_pool_fulls(node_config.pool_keys()).await?;
                 let initial_state = provider.initialize_state().await?;
                 initial_angstrom_state = Some(initial_state);
 
@@ -0,0 +1,240 @@
+//! Lifecycle of orders from signing to their fill on chain.
+//!
+//! A crossing pair of user orders and a searcher order are submitted to the
+//! pool of a devnet node. The nodes validate and gossip them, run the round
+//! over them and the leader lands the bundle on its anvil. The balances of
+//! every user have to move by exactly what the [`PoolSolution`] of the round
+//! says.
+use std::collections::HashMap;
+
+use alloy::{
+    primitives::{keccak256, Address, B256, I256, U256},
+    providers::Provider,
+    sol_types::Eip712Domain
+};
+use angstrom_types::{
+    matching::{Ray, SqrtPriceX96},
+    orders::PoolSolution,
+    primitive::AngstromSigner,
+    sol_bindings::{
+        grouped_orders::{AllOrders, GroupedVanillaOrder},
+        rpc_orders::TopOfBlockOrder,
+        RawPoolOrder
+    }
+};
+use reth_provider::test_utils::NoopProvider;
+
+use super::{AngstromTestnet, LandedBundle};
+use crate::{
+    type_generator::orders::{ToBOrderBuilder, UserOrderBuilder},
+    types::{config::DevnetConfig, initial_state::PartialConfigPoolKey}
+};
+
+const LIQUIDITY: u128 = 1_000_000_000_000_000_000_000;
+const AMOUNT: u128 = 1_000_000_000_000_000_000;
+
+fn signed(amount: u128) -> I256 {
+    I256::from_raw(U256::from(amount))
+}
+
+/// What a user order moves of token0 and token1 when it's filled with `q` at
+/// the clearing price and charged `fee` in token0, the gas and the pool fee,
+/// the same way the contract settles it. Positive for what the user receives.
+fn user_order_moves(order: &GroupedVanillaOrder, q: u128, ucp: Ray, fee: u128) -> (I256, I256) {
+    let (t0, t1) = match (order.is_bid(), order.exact_in()) {
+        (true, true) => (ucp.inverse_quantity(q, false) - fee, q),
+        (true, false) => (q, ucp.quantity(q + fee, true)),
+        (false, true) => (q, ucp.quantity(q - fee, false)),
+        (false, false) => (ucp.inverse_quantity(q, true) + fee, q)
+    };
+
+    if order.is_bid() {
+        (signed(t0), -signed(t1))
+    } else {
+        (-signed(t0), signed(t1))
+    }
+}
+
+/// What the searcher moves of token0 and token1 when it's charged `gas` in
+/// token0.
+fn searcher_moves(order: &TopOfBlockOrder, gas: u128) -> (I256, I256) {
+    if order.is_bid() {
+        (signed(order.quantity_out - gas), -signed(order.quantity_in))
+    } else {
+        (-signed(order.quantity_in + gas), signed(order.quantity_out))
+    }
+}
+
+/// What the signer of an order with the struct hash `order_hash` signed.
+fn signing_hash(domain: &Eip712Domain, order_hash: B256) -> B256 {
+    keccak256([&[0x19, 0x01], &domain.hash_struct()[..], &order_hash[..]].concat())
+}
+
+/// What the bundle charged the signer of every order in token0, the gas and
+/// for user orders the pool fee.
+fn charged_fees(landed: &LandedBundle, domain: &Eip712Domain) -> HashMap<Address, u128> {
+    let bundle = &landed.bundle;
+    let users = bundle.user_orders.iter().map(|order| {
+        let hash = order.order_hash(&bundle.pairs, &bundle.assets, landed.block);
+        (
+            order.signature.recover_signer(signing_hash(domain, hash)),
+            order.extra_fee_asset0 + order.pool_fee_asset0
+        )
+    });
+    let searchers = bundle.top_of_block_orders.iter().map(|order| {
+        let hash = order.order_hash(&bundle.pairs, &bundle.assets, landed.block);
+        (order.signature.recover_signer(signing_hash(domain, hash)), order.gas_used_asset_0)
+    });
+
+    users.chain(searchers).collect()
+}
+
+fn moved(landed: &LandedBundle, (t0, t1): (Address, Address), user: Address) -> (I256, I256) {
+    let moved = |token| {
+        landed
+            .transfers
+            .get(&(token, user))
+            .copied()
+            .unwrap_or_default()
+    };
+    (moved(t0), moved(t1))
+}
+
+fn filled(solution: &PoolSolution, order: &GroupedVanillaOrder) -> u128 {
+    let outcome = solution
+        .limit
+        .iter()
+        .find(|outcome| outcome.id.hash == order.order_hash())
+        .expect("order is in the solution");
+    assert!(outcome.is_filled(), "order {:?} wasn't filled", order.order_hash());
+
+    outcome.fill_amount(order.max_q())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn settles_orders_to_the_balances_of_the_solution() {
+    let config = DevnetConfig {
+        intial_node_count: 3,
+        pool_keys: vec![PartialConfigPoolKey::new(
+            0,
+            60,
+            LIQUIDITY,
+            SqrtPriceX96::at_tick(100_020).unwrap()
+        )],
+        ..Default::default()
+    };
+    let testnet = AngstromTestnet::spawn_devnet(NoopProvider::default(), config)
+        .await
+        .unwrap();
+
+    // the round of the new block settles the orders for the block after it
+    testnet.all_peers_update_state(0).await.unwrap();
+    let block = testnet
+        .get_peer(0)
+        .state_provider()
+        .rpc_provider()
+        .get_block_number()
+        .await
+        .unwrap();
+
+    let (pool_id, pool) = testnet
+        .get_peer(0)
+        .uniswap_pools()
+        .all()
+        .pop()
+        .expect("the devnet deployed the pool");
+    let (tokens, price, searcher_quantities) = {
+        let pool = pool.read().unwrap();
+        // the searcher buys token0 until the pool is a few ticks up
+        let target = SqrtPriceX96::at_tick(pool.tick + 3 * pool.tick_spacing).unwrap();
+        let (amount0, amount1) = pool
+            .simulate_swap(pool.token1, I256::MIN + I256::ONE, Some(target.into()))
+            .unwrap();
+        (
+            (pool.token0, pool.token1),
+            Ray::from(SqrtPriceX96::from(pool.sqrt_price)),
+            (u128::try_from(amount1.abs()).unwrap(), u128::try_from(amount0.abs()).unwrap())
+        )
+    };
+    let (t0, t1) = tokens;
+    let (quantity_in, quantity_out) = searcher_quantities;
+    let domain = testnet.get_peer(0).domain().clone();
+
+    let bid = UserOrderBuilder::new()
+        .kill_or_fill()
+        .exact()
+        .exact_in(true)
+        .asset_in(t1)
+        .asset_out(t0)
+        .amount(price.quantity(AMOUNT, false))
+        .bid_min_price(Ray::from(price.0 * U256::from(11) / U256::from(10)))
+        .block(block + 1)
+        .signing_key(Some(AngstromSigner::random()))
+        .domain(domain.clone())
+        .build();
+    let ask = UserOrderBuilder::new()
+        .kill_or_fill()
+        .exact()
+        .exact_in(true)
+        .asset_in(t0)
+        .asset_out(t1)
+        .amount(AMOUNT)
+        .min_price(Ray::from(price.0 * U256::from(9) / U256::from(10)))
+        .block(block + 1)
+        .signing_key(Some(AngstromSigner::random()))
+        .domain(domain.clone())
+        .build();
+    let searcher_key = AngstromSigner::random();
+    let searcher = ToBOrderBuilder::new()
+        .recipient(searcher_key.address())
+        .asset_in(t1)
+        .asset_out(t0)
+        .quantity_in(quantity_in)
+        .quantity_out(quantity_out)
+        .max_gas(quantity_out)
+        .valid_block(block + 1)
+        .signing_key(Some(searcher_key))
+        .domain(domain.clone())
+        .build();
+
+    let orders = vec![
+        AllOrders::from(bid.clone()),
+        AllOrders::from(ask.clone()),
+        AllOrders::from(searcher.clone()),
+    ];
+    assert!(testnet.submit_orders(Some(0), orders).await.unwrap());
+    assert!(testnet.check_solution_equality(block).unwrap());
+
+    let solution = testnet
+        .peers
+        .values()
+        .find_map(|peer| peer.proposal_verification(block))
+        .and_then(|verification| {
+            verification
+                .proposed
+                .into_iter()
+                .find(|solution| solution.id == pool_id)
+        })
+        .expect("the round has a solution for the pool");
+    assert_eq!(
+        solution.searcher.as_ref().map(|tob| tob.order_id.hash),
+        Some(searcher.order_hash())
+    );
+
+    let landed = testnet.landed_bundle(block).await.unwrap();
+    let fees = charged_fees(&landed, &domain);
+    for order in [&bid, &ask] {
+        let q = filled(&solution, order);
+        assert_eq!(
+            moved(&landed, tokens, order.from()),
+            user_order_moves(order, q, solution.ucp, fees[&order.from()]),
+            "balances of {:?}",
+            order.from()
+        );
+    }
+    assert_eq!(
+        moved(&landed, tokens, searcher.from()),
+        searcher_moves(&searcher, fees[&searcher.from()]),
+        "balances of the searcher"
+    );
+}
@@ -1,17 +1,33 @@
 mod devnet;
+#[cfg(test)]
+mod lifecycle;
 mod state_machine;
 mod testnet;
 use std::{
     collections::{HashMap, HashSet},
-    future::Future
+    future::Future,
+    time::Duration
 };
 
-use alloy::{node_bindings::AnvilInstance, providers::Provider};
+use alloy::{
+    consensus::Transaction,
+    network::TransactionResponse,
+    node_bindings::AnvilInstance,
+    primitives::{Address, I256},
+    providers::Provider,
+    sol_types::SolCall
+};
+use alloy_rpc_types::BlockTransactionsKind;
 use angstrom_network::{
     manager::StromConsensusEvent, NetworkOrderEvent, StromMessage, StromNetworkManager
 };
-use angstrom_types::sol_bindings::grouped_orders::AllOrders;
+use angstrom_types::{
+    contract_bindings::angstrom::Angstrom, contract_payloads::angstrom::AngstromBundle,
+    orders::OrderOrigin, sol_bindings::grouped_orders::AllOrders
+};
 use futures::TryFutureExt;
+use order_pool::OrderPoolHandle;
+use pade::PadeDecode;
 use rand::Rng;
 use reth_chainspec::Hardforks;
 use reth_metrics::common::mpsc::{
@@ -23,11 +39,77 @@ use tokio_stream::StreamExt;
 use tracing::{span, Instrument, Level};
 
 use crate::{
+    contracts::anvil::WalletProviderRpc,
     controllers::strom::TestnetNode,
+    order_generator::OrderGenerator,
     providers::{utils::async_to_sync, TestnetBlockProvider},
     types::{GlobalTestingConfig, WithWalletProvider}
 };
 
+/// How long the peers get to verify the proposal of a round.
+const SOLUTION_EQUALITY_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the leader of a round gets to land its bundle.
+const BUNDLE_LANDING_TIMEOUT: Duration = Duration::from_secs(30);
+
+alloy::sol!(
+    event Transfer(address indexed from, address indexed to, uint256 amount);
+);
+
+/// A bundle that landed on the chain of a peer.
+#[derive(Debug)]
+pub struct LandedBundle {
+    /// block the bundle landed in
+    pub block:     u64,
+    pub bundle:    AngstromBundle,
+    /// what the bundle moved, by token and account. positive for what the
+    /// account received
+    pub transfers: HashMap<(Address, Address), I256>
+}
+
+impl LandedBundle {
+    /// The first bundle in the blocks after `block`, it has to have executed.
+    async fn find(provider: &WalletProviderRpc, block: u64) -> eyre::Result<Option<Self>> {
+        let latest = provider.get_block_number().await?;
+        for number in block + 1..=latest {
+            let Some(landed) = provider
+                .get_block(number.into(), BlockTransactionsKind::Full)
+                .await?
+            else {
+                continue
+            };
+            let Some((tx_hash, call)) = landed.transactions.txns().find_map(|tx| {
+                let call = Angstrom::executeCall::abi_decode(tx.input(), true).ok()?;
+                Some((tx.tx_hash(), call))
+            }) else {
+                continue
+            };
+
+            let receipt = provider
+                .get_transaction_receipt(tx_hash)
+                .await?
+                .ok_or_else(|| eyre::eyre!("bundle {tx_hash:?} has no receipt"))?;
+            if !receipt.status() {
+                eyre::bail!("bundle {tx_hash:?} reverted")
+            }
+            let bundle = AngstromBundle::pade_decode(&mut call.encoded.as_ref(), None)
+                .map_err(|e| eyre::eyre!("bundle {tx_hash:?} doesn't decode: {e:?}"))?;
+
+            let mut transfers = HashMap::<_, I256>::new();
+            for log in receipt.inner.logs() {
+                let Ok(transfer) = log.log_decode::<Transfer>() else { continue };
+                let (token, Transfer { from, to, amount }) =
+                    (transfer.inner.address, transfer.inner.data);
+                *transfers.entry((token, from)).or_default() -= I256::from_raw(amount);
+                *transfers.entry((token, to)).or_default() += I256::from_raw(amount);
+            }
+
+            return Ok(Some(Self { block: number, bundle, transfers }))
+        }
+
+        Ok(None)
+    }
+}
+
 pub struct AngstromTestnet<C, G, P> {
     block_provider:      TestnetBlockProvider,
     _anvil_instance:     Option<AnvilInstance>,
@@ -307,4 +389,130 @@ where
 
         Ok(blocks.into_iter().all(|(_, b)| b == expected_block_num))
     }
+
+    /// submits a random set of orders over the pools of the peer to its pool,
+    /// returning whether all of them were accepted. if id is None, then a
+    /// random id is used
+    pub async fn submit_random_orders(&self, id: Option<u64>) -> eyre::Result<bool> {
+        let id = id.unwrap_or_else(|| self.random_valid_id());
+        let peer = self.get_peer(id);
+        let block = peer
+            .state_provider()
+            .rpc_provider()
+            .get_block_number()
+            .await?;
+
+        let orders = OrderGenerator::new(
+            peer.uniswap_pools().clone(),
+            block,
+            peer.domain().clone(),
+            7..10,
+            0.1..0.6
+        )
+        .generate_orders()
+        .into_iter()
+        .flat_map(|pool| {
+            std::iter::once(AllOrders::from(pool.tob))
+                .chain(pool.book.into_iter().map(AllOrders::from))
+        })
+        .collect::<Vec<_>>();
+        tracing::info!(id, orders = orders.len(), "submitting random orders");
+
+        self.submit_orders(Some(id), orders).await
+    }
+
+    /// submits the orders to the pool of the peer, returning whether all of
+    /// them were accepted. if id is None, then a random id is used
+    pub async fn submit_orders(
+        &self,
+        id: Option<u64>,
+        orders: Vec<AllOrders>
+    ) -> eyre::Result<bool> {
+        let peer = self.get_peer(id.unwrap_or_else(|| self.random_valid_id()));
+
+        // the pool handle futures aren't sync
+        let submitted = orders.into_iter().map(|order| {
+            let pool = peer.pool_handle().clone();
+            tokio::spawn(async move { pool.new_order(OrderOrigin::External, order).await })
+        });
+
+        Ok(futures::future::join_all(submitted)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .all(|res| res.is_valid()))
+    }
+
+    /// waits for the bundle of the round of the block to land on the chain of
+    /// a peer. the leader of the round submits it to its own anvil, which
+    /// mines it right away
+    pub async fn landed_bundle(&self, block: u64) -> eyre::Result<LandedBundle> {
+        let deadline = tokio::time::Instant::now() + BUNDLE_LANDING_TIMEOUT;
+        loop {
+            for peer in self.peers.values() {
+                let provider = peer.state_provider().rpc_provider();
+                if let Some(landed) = LandedBundle::find(&provider, block).await? {
+                    return Ok(landed)
+                }
+            }
+            if tokio::time::Instant::now() >= deadline {
+                eyre::bail!("no bundle landed after block {block}")
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// checks that every peer but the leader verified the proposal for the
+    /// block, and that the solutions they computed from its pre-proposals
+    /// match the proposed ones exactly
+    pub(crate) fn check_solution_equality(&self, block: u64) -> eyre::Result<bool> {
+        let verifications = async_to_sync(async {
+            let deadline = tokio::time::Instant::now() + SOLUTION_EQUALITY_TIMEOUT;
+            loop {
+                let verifications = self
+                    .peers
+                    .values()
+                    .filter_map(|peer| {
+                        let verification = peer.proposal_verification(block)?;
+                        Some((peer.testnet_node_id(), verification))
+                    })
+                    .collect::<Vec<_>>();
+                if verifications.len() + 1 >= self.peers.len()
+                    || tokio::time::Instant::now() >= deadline
+                {
+                    break verifications
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+
+        if verifications.len() + 1 < self.peers.len() {
+            eyre::bail!(
+                "only {} of {} peers verified the proposal for block {block}",
+                verifications.len(),
+                self.peers.len() - 1
+            );
+        }
+
+        let mut passed = true;
+        for (id, verification) in &verifications {
+            if !verification.passed() {
+                tracing::error!(
+                    id,
+                    block,
+                    leader = ?verification.leader,
+                    proposed = ?verification.proposed,
+                    computed = ?verification.computed,
+                    "peer computed different solutions than the leader proposed"
+                );
+                passed = false;
+            }
+        }
+
+        Ok(passed
+            && verifications
+                .windows(2)
+                .all(|pair| pair[0].1.proposed == pair[1].1.proposed))
+    }
 }
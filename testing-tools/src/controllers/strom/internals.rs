@@ -1,5 +1,6 @@
 use std::{pin::Pin, sync::Arc};
 
+use alloy::{providers::Provider, sol_types::Eip712Domain};
 use alloy_rpc_types::{BlockId, Transaction};
 use angstrom::components::StromHandles;
 use angstrom_eth::handle::Eth;
@@ -10,7 +11,7 @@ use angstrom_types::{
     contract_payloads::angstrom::{AngstromPoolConfigStore, UniswapAngstromRegistry},
     mev_boost::{MevBoostProvider, SubmitTx},
     pair_with_price::PairsWithPrice,
-    primitive::UniswapPoolRegistry,
+    primitive::{angstrom_domain, UniswapPoolRegistry},
     sol_bindings::testnet::TestnetHub,
     testnet::InitialTestnetState
 };
@@ -23,6 +24,7 @@ use reth_provider::{BlockNumReader, CanonStateSubscriptions};
 use reth_tasks::TokioTaskExecutor;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::{span, Instrument};
+use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 use validation::{
     common::TokenPriceGenerator, order::state::pools::AngstromPoolsTracker,
     validator::ValidationClient
@@ -47,7 +49,10 @@ pub struct AngstromDevnetNodeInternals<P> {
     pub order_storage:    Arc<OrderStorage>,
     pub pool_handle:      PoolHandle,
     pub tx_strom_handles: SendingStromHandles,
-    pub testnet_hub:      StromContractInstance
+    pub testnet_hub:      StromContractInstance,
+    pub uniswap_pools:    SyncedUniswapPools,
+    /// what the orders for the angstrom contract of the node are signed for
+    pub domain:           Eip712Domain
 }
 
 impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
@@ -77,7 +82,8 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
         let executor: TokioTaskExecutor = Default::default();
         let tx_strom_handles = (&strom_handles).into();
 
-        let validation_client = ValidationClient(strom_handles.validator_tx);
+        let validation_client =
+            ValidationClient(strom_handles.validator_tx, strom_handles.validation_queue.clone());
         let matching_handle = MatchingManager::spawn(executor.clone(), validation_client.clone());
 
         let order_api = OrderApi::new(pool.clone(), executor.clone(), validation_client.clone());
@@ -115,7 +121,7 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             .expect("startup sequence failed");
 
         block_sync.clear();
-        let block_number = b.tip().number;
+        let (block_number, block_timestamp) = (b.tip().number, b.tip().timestamp);
 
         tracing::debug!(node_id = node_config.node_id, block_number, "creating strom internals");
 
@@ -131,7 +137,7 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             .map_err(|e| eyre::eyre!("{e}"))?
         );
 
-        let uniswap_pool_manager = configure_uniswap_manager(
+        let (uniswap_pool_manager, unavailable_pools) = configure_uniswap_manager(
             state_provider.rpc_provider().into(),
             state_provider
                 .state_provider()
@@ -142,6 +148,9 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             inital_angstrom_state.pool_manager_addr
         )
         .await;
+        if let Some(error) = unavailable_pools.into_iter().next() {
+            return Err(error.into())
+        }
 
         let uniswap_pools = uniswap_pool_manager.pools();
         tokio::spawn(uniswap_pool_manager.instrument(span!(
@@ -170,6 +179,10 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             pool_config_store.clone()
         );
 
+        let domain = angstrom_domain(
+            state_provider.rpc_provider().get_chain_id().await?,
+            inital_angstrom_state.angstrom_addr
+        );
         let validator = TestOrderValidator::new(
             state_provider.state_provider(),
             validation_client.clone(),
@@ -180,6 +193,7 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             token_conversion,
             token_price_update_stream,
             pool_storage.clone(),
+            domain.clone(),
             node_config.node_id
         )
         .await?;
@@ -255,15 +269,18 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
             mev_boost_provider,
             matching_handle,
             block_sync.clone()
-        );
+        )
+        .with_block_timestamp(block_timestamp)
+        .with_order_validator(validation_client.clone());
 
         // init agents
         let agent_config = AgentConfig {
-            uniswap_pools,
-            agent_id: node_config.node_id,
-            rpc_address: addr,
-            current_block: block_number,
-            state_provider: state_provider.state_provider()
+            uniswap_pools:  uniswap_pools.clone(),
+            agent_id:       node_config.node_id,
+            rpc_address:    addr,
+            current_block:  block_number,
+            state_provider: state_provider.state_provider(),
+            domain:         domain.clone()
         };
 
         futures::stream::iter(agents.into_iter())
@@ -284,7 +301,9 @@ impl<P: WithWalletProvider> AngstromDevnetNodeInternals<P> {
                 order_storage,
                 pool_handle,
                 tx_strom_handles,
-                testnet_hub
+                testnet_hub,
+                uniswap_pools,
+                domain
             },
             consensus,
             validator
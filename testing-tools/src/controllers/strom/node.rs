@@ -6,10 +6,12 @@ use std::{
     task::Poll
 };
 
+use alloy::sol_types::Eip712Domain;
 use alloy_primitives::Address;
 use angstrom::components::initialize_strom_handles;
 use angstrom_network::{
-    NetworkOrderEvent, StromNetworkEvent, StromNetworkHandle, StromNetworkManager
+    pool_manager::PoolHandle, NetworkOrderEvent, StromNetworkEvent, StromNetworkHandle,
+    StromNetworkManager
 };
 use angstrom_types::{
     block_sync::GlobalBlockSync,
@@ -17,7 +19,7 @@ use angstrom_types::{
     sol_bindings::{grouped_orders::AllOrders, testnet::random::RandomValues},
     testnet::InitialTestnetState
 };
-use consensus::{AngstromValidator, ConsensusManager};
+use consensus::{AngstromValidator, ConsensusManager, ProposalVerification};
 use futures::Future;
 use matching_engine::manager::MatcherHandle;
 use parking_lot::RwLock;
@@ -30,6 +32,7 @@ use reth_network::{
 use reth_provider::{BlockReader, ChainSpecProvider, HeaderProvider, ReceiptProvider};
 use tokio_stream::wrappers::{BroadcastStream, UnboundedReceiverStream};
 use tracing::instrument;
+use uniswap_v4::uniswap::pool_manager::SyncedUniswapPools;
 
 use super::internals::AngstromDevnetNodeInternals;
 use crate::{
@@ -134,6 +137,18 @@ where
         &self.strom.state_provider
     }
 
+    pub fn pool_handle(&self) -> &PoolHandle {
+        &self.strom.pool_handle
+    }
+
+    pub fn uniswap_pools(&self) -> &SyncedUniswapPools {
+        &self.strom.uniswap_pools
+    }
+
+    pub fn domain(&self) -> &Eip712Domain {
+        &self.strom.domain
+    }
+
     /// Eth
     /// -------------------------------------
     pub fn eth_peer_handle(&self) -> &PeerHandle<EthPeerPool> {
@@ -255,6 +270,18 @@ where
         self.state_lock.strom_consensus_mut(f)
     }
 
+    /// How the node verified the proposal of the round at `block`, none if it
+    /// led the round or didn't get to verify it.
+    pub fn proposal_verification(&self, block: u64) -> Option<ProposalVerification> {
+        self.strom_consensus(|consensus| consensus.proposal_verifications().get(block))
+    }
+
+    /// Faults to inject into the consensus of the node.
+    #[cfg(feature = "chaos")]
+    pub fn chaos_hooks(&self) -> consensus::ChaosHooks {
+        self.strom_consensus(|consensus| consensus.chaos_hooks().clone())
+    }
+
     pub fn start_conensus(&self) {
         self.state_lock.set_consensus(true);
     }
@@ -6,6 +6,8 @@
 /// for example a order generator that pushes orders to the nodes rpc
 /// and then checks for fills
 pub mod agents;
+/// Bootstrap of the committee that co-signs the proposals
+pub mod committee;
 /// mocks utils for different modules
 pub mod mocks;
 /// Tools for testing network setup
@@ -22,6 +24,9 @@ pub mod validation;
 
 pub mod providers;
 
+/// Deterministic runs of node components on a logical clock
+pub mod simulation;
+
 /// Tools for contract deployment and testing
 pub mod contracts;
 
@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    time::Duration
+};
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256};
 use angstrom_types::{
     contract_payloads::angstrom::BundleGasDetails,
     matching::uniswap::PoolSnapshot,
@@ -8,19 +15,116 @@ use angstrom_types::{
     primitive::PoolId,
     sol_bindings::{grouped_orders::OrderWithStorageData, rpc_orders::TopOfBlockOrder}
 };
+use angstrom_utils::clock::Clock;
 use futures::{future::BoxFuture, FutureExt};
 use matching_engine::{book::BookOrder, MatchingEngineHandle};
+use parking_lot::Mutex;
+
+type SolveOutput = eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>;
+
+/// Solves pools with canned outputs instead of matching the books.
+///
+/// Every solve takes the next of the queued outputs, once they run out it
+/// returns the default solutions, which are none unless set.
+#[derive(Clone, Default)]
+pub struct MockMatchingEngine {
+    solutions: Vec<PoolSolution>,
+    latency:   Duration,
+    /// where the latency elapses on
+    clock:     Clock,
+    outputs:   Arc<Mutex<VecDeque<SolveOutput>>>,
+    solves:    Arc<AtomicUsize>
+}
 
-#[derive(Clone)]
-pub struct MockMatchingEngine {}
+impl MockMatchingEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Solutions of the solves that have no queued output.
+    pub fn with_solutions(mut self, solutions: Vec<PoolSolution>) -> Self {
+        self.solutions = solutions;
+        self
+    }
+
+    /// Resolves every solve only after `latency` passed on `clock`.
+    pub fn with_latency(mut self, latency: Duration, clock: Clock) -> Self {
+        self.latency = latency;
+        self.clock = clock;
+        self
+    }
+
+    /// Queues the output of the next solve that has none yet.
+    pub fn push_output(&self, output: SolveOutput) {
+        self.outputs.lock().push_back(output);
+    }
+
+    /// How many times pools were solved.
+    pub fn solves(&self) -> usize {
+        self.solves.load(Ordering::Relaxed)
+    }
+}
 
 impl MatchingEngineHandle for MockMatchingEngine {
     fn solve_pools(
         &self,
         _: Vec<BookOrder>,
         _: Vec<OrderWithStorageData<TopOfBlockOrder>>,
-        _: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>
+        _: HashMap<PoolId, (Address, Address, PoolSnapshot, u16)>,
+        _: HashMap<B256, u64>,
+        _: u64
     ) -> BoxFuture<eyre::Result<(Vec<PoolSolution>, BundleGasDetails)>> {
-        async move { Ok((vec![], BundleGasDetails::default())) }.boxed()
+        self.solves.fetch_add(1, Ordering::Relaxed);
+        let output = self
+            .outputs
+            .lock()
+            .pop_front()
+            .unwrap_or_else(|| Ok((self.solutions.clone(), BundleGasDetails::default())));
+
+        if self.latency.is_zero() {
+            return async move { output }.boxed()
+        }
+        let sleep = self.clock.sleep(self.latency);
+        async move {
+            sleep.await;
+            output
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use angstrom_utils::clock::LogicalClock;
+    use futures::task::noop_waker_ref;
+
+    use super::*;
+
+    fn solve(engine: &MockMatchingEngine) -> BoxFuture<'_, SolveOutput> {
+        engine.solve_pools(vec![], vec![], HashMap::new(), HashMap::new(), 0)
+    }
+
+    #[test]
+    fn serves_the_queued_outputs_after_the_latency() {
+        let clock = LogicalClock::new(Duration::from_secs(1_000));
+        let engine = MockMatchingEngine::new()
+            .with_latency(Duration::from_secs(1), Clock::logical(clock.clone()));
+        let mut cx = Context::from_waker(noop_waker_ref());
+        engine.push_output(Err(eyre::eyre!("no solution")));
+
+        let mut first = solve(&engine);
+        assert!(first.poll_unpin(&mut cx).is_pending());
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(first.poll_unpin(&mut cx), Poll::Ready(Err(_))));
+
+        let mut second = solve(&engine);
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            second.poll_unpin(&mut cx),
+            Poll::Ready(Ok((solutions, _))) if solutions.is_empty()
+        ));
+        assert_eq!(engine.solves(), 2);
     }
 }
@@ -3,4 +3,5 @@ pub mod consensus;
 pub mod eth_events;
 pub mod matching_engine;
 pub mod network_events;
+pub mod order_pool;
 pub mod validator;
@@ -0,0 +1,239 @@
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use alloy::primitives::{Address, B256};
+use angstrom_types::{
+    orders::{
+        CancelAllOrdersRequest, CancelOrderRequest, OrderLocation, OrderOrigin, OrderStatus,
+        PoolAnalytics, PricePeg
+    },
+    primitive::{OrderPoolNewOrderResult, PoolId},
+    sol_bindings::{ext::RawPoolOrder, grouped_orders::AllOrders}
+};
+use angstrom_utils::clock::Clock;
+use order_pool::{OrderPoolHandle, PoolManagerUpdate};
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+#[derive(Debug, Default)]
+struct Canned {
+    /// result of every new order, valid unless set
+    new_order_result: Option<OrderPoolNewOrderResult>,
+    /// orders that were sent to the pool, in order
+    received:         Vec<(OrderOrigin, AllOrders)>,
+    pool_orders:      HashMap<(PoolId, OrderLocation), Vec<AllOrders>>,
+    statuses:         HashMap<B256, OrderStatus>,
+    analytics:        HashMap<PoolId, PoolAnalytics>
+}
+
+/// An order pool that answers with canned responses.
+///
+/// Orders sent to it are recorded and, as long as they weren't cancelled, are
+/// the pending orders of their signer. Updates to subscribers are sent with
+/// [`MockOrderPoolHandle::send_update`].
+#[derive(Debug, Clone)]
+pub struct MockOrderPoolHandle {
+    canned:  Arc<Mutex<Canned>>,
+    updates: broadcast::Sender<PoolManagerUpdate>,
+    latency: Duration,
+    /// where the latency elapses on
+    clock:   Clock
+}
+
+impl Default for MockOrderPoolHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockOrderPoolHandle {
+    pub fn new() -> Self {
+        Self {
+            canned:  Arc::default(),
+            updates: broadcast::channel(100).0,
+            latency: Duration::ZERO,
+            clock:   Clock::system()
+        }
+    }
+
+    /// Answers every request only after `latency` passed on `clock`.
+    pub fn with_latency(mut self, latency: Duration, clock: Clock) -> Self {
+        self.latency = latency;
+        self.clock = clock;
+        self
+    }
+
+    pub fn set_new_order_result(&self, result: OrderPoolNewOrderResult) {
+        self.canned.lock().new_order_result = Some(result);
+    }
+
+    pub fn set_pool_orders(
+        &self,
+        pool_id: PoolId,
+        location: OrderLocation,
+        orders: Vec<AllOrders>
+    ) {
+        self.canned
+            .lock()
+            .pool_orders
+            .insert((pool_id, location), orders);
+    }
+
+    pub fn set_order_status(&self, order_hash: B256, status: OrderStatus) {
+        self.canned.lock().statuses.insert(order_hash, status);
+    }
+
+    pub fn set_pool_analytics(&self, analytics: PoolAnalytics) {
+        self.canned
+            .lock()
+            .analytics
+            .insert(analytics.pool_id, analytics);
+    }
+
+    /// The orders sent to the pool so far.
+    pub fn received_orders(&self) -> Vec<(OrderOrigin, AllOrders)> {
+        self.canned.lock().received.clone()
+    }
+
+    /// Sends the update to every subscriber, returns how many got it.
+    pub fn send_update(&self, update: PoolManagerUpdate) -> usize {
+        self.updates.send(update).unwrap_or_default()
+    }
+
+    fn respond<T: Send + 'static>(&self, value: T) -> impl Future<Output = T> + Send {
+        let sleep = (!self.latency.is_zero()).then(|| self.clock.sleep(self.latency));
+        async move {
+            if let Some(sleep) = sleep {
+                sleep.await;
+            }
+            value
+        }
+    }
+}
+
+impl OrderPoolHandle for MockOrderPoolHandle {
+    fn new_order(
+        &self,
+        origin: OrderOrigin,
+        order: AllOrders
+    ) -> impl Future<Output = OrderPoolNewOrderResult> + Send {
+        let mut canned = self.canned.lock();
+        let result = canned
+            .new_order_result
+            .clone()
+            .unwrap_or(OrderPoolNewOrderResult::Valid);
+        if result.is_valid() {
+            canned.received.push((origin, order));
+        }
+
+        self.respond(result)
+    }
+
+    /// Recorded like any rpc order, the peg itself isn't kept.
+    fn new_pegged_order(
+        &self,
+        order: AllOrders,
+        _: PricePeg
+    ) -> impl Future<Output = OrderPoolNewOrderResult> + Send {
+        self.new_order(OrderOrigin::External, order)
+    }
+
+    /// Every tranche is recorded like any rpc order.
+    fn new_iceberg_order(
+        &self,
+        tranches: Vec<AllOrders>
+    ) -> impl Future<Output = Vec<OrderPoolNewOrderResult>> + Send {
+        futures::future::join_all(
+            tranches
+                .into_iter()
+                .map(|tranche| self.new_order(OrderOrigin::External, tranche))
+                .collect::<Vec<_>>()
+        )
+    }
+
+    fn subscribe_orders(&self) -> BroadcastStream<PoolManagerUpdate> {
+        BroadcastStream::new(self.updates.subscribe())
+    }
+
+    fn pending_orders(&self, sender: Address) -> impl Future<Output = Vec<AllOrders>> + Send {
+        let orders = self
+            .canned
+            .lock()
+            .received
+            .iter()
+            .filter(|(_, order)| order.from() == sender)
+            .map(|(_, order)| order.clone())
+            .collect();
+
+        self.respond(orders)
+    }
+
+    fn cancel_order(&self, req: CancelOrderRequest) -> impl Future<Output = bool> + Send {
+        let mut canned = self.canned.lock();
+        let before = canned.received.len();
+        canned.received.retain(|(_, order)| {
+            order.order_hash() != req.order_id || order.from() != req.user_address
+        });
+        let cancelled = canned.received.len() != before;
+
+        self.respond(cancelled)
+    }
+
+    fn cancel_all_orders(
+        &self,
+        req: CancelAllOrdersRequest
+    ) -> impl Future<Output = Option<Vec<B256>>> + Send {
+        let mut canned = self.canned.lock();
+        let (cancelled, kept) = std::mem::take(&mut canned.received)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, order)| order.from() == req.user_address);
+        canned.received = kept;
+
+        self.respond(Some(
+            cancelled
+                .iter()
+                .map(|(_, order)| order.order_hash())
+                .collect()
+        ))
+    }
+
+    fn fetch_orders_from_pool(
+        &self,
+        pool_id: PoolId,
+        location: OrderLocation
+    ) -> impl Future<Output = Vec<AllOrders>> + Send {
+        let orders = self
+            .canned
+            .lock()
+            .pool_orders
+            .get(&(pool_id, location))
+            .cloned()
+            .unwrap_or_default();
+
+        self.respond(orders)
+    }
+
+    fn fetch_order_status(
+        &self,
+        order_hash: B256
+    ) -> impl Future<Output = Option<OrderStatus>> + Send {
+        let status = self.canned.lock().statuses.get(&order_hash).cloned();
+        self.respond(status)
+    }
+
+    fn fetch_pool_analytics(
+        &self,
+        pool_id: PoolId,
+        _: usize
+    ) -> impl Future<Output = PoolAnalytics> + Send {
+        let analytics = self
+            .canned
+            .lock()
+            .analytics
+            .get(&pool_id)
+            .cloned()
+            .unwrap_or(PoolAnalytics { pool_id, ..Default::default() });
+
+        self.respond(analytics)
+    }
+}
@@ -3,16 +3,22 @@ use std::{collections::HashMap, sync::Arc};
 use alloy_primitives::{keccak256, Address, FixedBytes};
 use angstrom_types::{
     self,
-    contract_payloads::angstrom::{AngstromBundle, BundleGasDetails},
+    contract_payloads::{
+        angstrom::{AngstromBundle, BundleGasDetails},
+        tob::{TobSimulation, TobStateOverrides}
+    },
     orders::OrderOrigin,
-    sol_bindings::{ext::RawPoolOrder, grouped_orders::AllOrders}
+    sol_bindings::{ext::RawPoolOrder, grouped_orders::AllOrders, rpc_orders::TopOfBlockOrder}
 };
 use eyre::OptionExt;
 use pade::PadeEncode;
 use parking_lot::Mutex;
 use validation::{
     bundle::BundleValidatorHandle,
-    order::{GasEstimationFuture, OrderValidationResults, OrderValidatorHandle}
+    order::{
+        GasEstimationFuture, NextNonceFuture, NonceCheckFuture, OrderValidationResults,
+        OrderValidatorHandle, TobSimulationFuture
+    }
 };
 
 // all keys are the signer of the order
@@ -78,6 +84,30 @@ impl OrderValidatorHandle for MockValidator {
             }
         })
     }
+
+    fn check_nonce(&self, _: Address, _: u64, _: alloy_primitives::B256) -> NonceCheckFuture {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn next_nonce(&self, _: Address) -> NextNonceFuture {
+        Box::pin(async move { Some(0) })
+    }
+
+    fn simulate_tob(&self, _: TopOfBlockOrder, _: TobStateOverrides) -> TobSimulationFuture {
+        Box::pin(async move { TobSimulation::invalid("the mock doesn't simulate orders") })
+    }
+
+    /// Checks the order like the result queued for its signer, without taking
+    /// the result.
+    fn check_order(&self, order: Self::Order) -> validation::order::ValidationFuture {
+        let res = self
+            .limit_orders
+            .lock()
+            .get(&order.from())
+            .cloned()
+            .unwrap_or_else(|| OrderValidationResults::Invalid(order.order_hash()));
+        Box::pin(async move { res })
+    }
 }
 
 impl BundleValidatorHandle for MockValidator {
@@ -68,7 +68,11 @@ impl TestnetNodeNetwork {
             status:       state,
             has_sent:     false,
             has_received: false,
-            secret_key:   node_config.angstrom_signer()
+            secret_key:   node_config.angstrom_signer(),
+            staking_key:  None,
+            observer:     false,
+            genesis:      Default::default(),
+            observers:    Default::default()
         };
 
         let validators = Arc::new(RwLock::new(HashSet::default()));
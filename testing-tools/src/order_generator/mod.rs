@@ -1,5 +1,6 @@
 use std::ops::Range;
 
+use alloy::sol_types::Eip712Domain;
 use angstrom_types::{
     primitive::PoolId,
     sol_bindings::{grouped_orders::GroupedVanillaOrder, rpc_orders::TopOfBlockOrder}
@@ -20,16 +21,19 @@ pub struct OrderGenerator {
 }
 
 impl OrderGenerator {
+    /// Generates orders signed for the angstrom contract of `domain`.
     pub fn new(
         pool_data: SyncedUniswapPools,
         block_number: u64,
+        domain: Eip712Domain,
         order_amt_range: Range<usize>,
         partial_pct_range: Range<f64>
     ) -> Self {
         let pools = pool_data
-            .iter()
+            .all()
+            .into_iter()
             .map(|(pool_id, pool_data)| {
-                PoolOrderGenerator::new(*pool_id, pool_data.clone(), block_number)
+                PoolOrderGenerator::new(pool_id, pool_data, block_number, domain.clone())
             })
             .collect::<Vec<_>>();
 
@@ -1,6 +1,8 @@
-use alloy::primitives::{I256, U256};
+use alloy::{
+    primitives::{I256, U256},
+    sol_types::Eip712Domain
+};
 use angstrom_types::{
-    matching::{Ray, SqrtPriceX96},
     primitive::AngstromSigner,
     sol_bindings::{grouped_orders::GroupedVanillaOrder, rpc_orders::TopOfBlockOrder}
 };
@@ -8,24 +10,29 @@ use rand::Rng;
 use uniswap_v3_math::tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPool;
 
-use crate::type_generator::orders::{ToBOrderBuilder, UserOrderBuilder};
+use crate::type_generator::{
+    orders::{ToBOrderBuilder, UserOrderBuilder},
+    ray_from_float, sqrt_price_from_float
+};
 
 pub struct OrderBuilder {
     keys:      Vec<AngstromSigner>,
     /// pools to based orders off of
-    pool_data: SyncedUniswapPool
+    pool_data: SyncedUniswapPool,
+    /// what the orders are signed for
+    domain:    Eip712Domain
 }
 
 impl OrderBuilder {
-    pub fn new(pool_data: SyncedUniswapPool) -> Self {
-        Self { keys: vec![AngstromSigner::random(); 10], pool_data }
+    pub fn new(pool_data: SyncedUniswapPool, domain: Eip712Domain) -> Self {
+        Self { keys: vec![AngstromSigner::random(); 10], pool_data, domain }
     }
 
     pub fn build_tob_order(&self, cur_price: f64, block_number: u64) -> TopOfBlockOrder {
         let pool = self.pool_data.read().unwrap();
 
         // convert price to sqrtx96
-        let price: U256 = SqrtPriceX96::from_float_price(cur_price).into();
+        let price: U256 = sqrt_price_from_float(cur_price).into();
         let price = price.clamp(MIN_SQRT_RATIO, MAX_SQRT_RATIO);
         let sqrt_price = pool.sqrt_price;
 
@@ -47,6 +54,7 @@ impl OrderBuilder {
 
         ToBOrderBuilder::new()
             .signing_key(self.keys.get(rng.gen_range(0..10)).cloned())
+            .domain(self.domain.clone())
             .asset_in(if zfo { token0 } else { token1 })
             .asset_out(if !zfo { token0 } else { token1 })
             .quantity_in(amount_in)
@@ -66,13 +74,12 @@ impl OrderBuilder {
 
         let pool = self.pool_data.read().unwrap();
 
-        let mut unshifted_price = Ray::from(
-            pool.calculate_price_unshifted(SqrtPriceX96::from_float_price(cur_price).into())
-        );
+        let mut unshifted_price =
+            ray_from_float(pool.calculate_price_unshifted(sqrt_price_from_float(cur_price).into()));
         // if the pool price > than price we want. given t1 / t0 -> more t0 less t1 ->
         // cur_price
 
-        let price: U256 = SqrtPriceX96::from_float_price(cur_price).into();
+        let price: U256 = sqrt_price_from_float(cur_price).into();
         let price = price.clamp(MIN_SQRT_RATIO, MAX_SQRT_RATIO);
 
         let sqrt_price = pool.sqrt_price;
@@ -104,6 +111,7 @@ impl OrderBuilder {
 
         UserOrderBuilder::new()
             .signing_key(self.keys.get(rng.gen_range(0..10)).cloned())
+            .domain(self.domain.clone())
             .is_exact(!is_partial)
             .asset_in(if direction { token0 } else { token1 })
             .asset_out(if !direction { token0 } else { token1 })
@@ -1,3 +1,4 @@
+use alloy::sol_types::Eip712Domain;
 use angstrom_types::primitive::PoolId;
 use uniswap_v4::uniswap::pool_manager::SyncedUniswapPool;
 
@@ -18,14 +19,19 @@ pub struct PoolOrderGenerator {
 }
 
 impl PoolOrderGenerator {
-    pub fn new(pool_id: PoolId, pool_data: SyncedUniswapPool, block_number: u64) -> Self {
+    pub fn new(
+        pool_id: PoolId,
+        pool_data: SyncedUniswapPool,
+        block_number: u64,
+        domain: Eip712Domain
+    ) -> Self {
         let price = pool_data.read().unwrap().calculate_price();
 
         // bounds of 50% from start with a std of 10%
         let mut price_distribution =
             PriceDistribution::new(price, f64::INFINITY, f64::NEG_INFINITY, 5.0);
         let cur_price = price_distribution.generate_price();
-        let builder = OrderBuilder::new(pool_data);
+        let builder = OrderBuilder::new(pool_data, domain);
 
         Self { block_number, price_distribution, cur_price, builder, pool_id }
     }
@@ -0,0 +1,497 @@
+//! Providers that inject failures into the reads of the node.
+//!
+//! [`FaultyStateProviderFactory`] wraps the state the validation reads and
+//! [`FaultyPoolManagerProvider`] the one the pool data is loaded from. Both
+//! draw their failures from a shared [`FaultInjector`], either scripted read
+//! by read or at random rates, so that the retry and fallback paths of every
+//! subsystem can be driven in tests. The state factory is a database of the
+//! validation as well, if the state it wraps is one.
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    time::Duration
+};
+
+use alloy::{
+    primitives::{Address, BlockNumber, StorageKey, StorageValue, B256, U256},
+    providers::Provider,
+    rpc::types::Filter,
+    transports::TransportErrorKind
+};
+use alloy_primitives::Log;
+use parking_lot::Mutex;
+use rand::Rng;
+use reth_primitives::Account;
+use reth_provider::{ProviderError, ProviderResult};
+use revm::{
+    primitives::{AccountInfo, Bytecode},
+    DatabaseRef
+};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use uniswap_v4::uniswap::{
+    pool_manager::PoolManagerError,
+    pool_providers::{PoolManagerProvider, PoolMangerBlocks}
+};
+use validation::common::{
+    db::{BlockStateProvider, BlockStateProviderFactory},
+    state_backend::BatchStorageRead
+};
+
+/// A failure injected into a single read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// the read hangs for the duration, then fails
+    Timeout(Duration),
+    /// the read fails right away
+    Transient,
+    /// the read is served as of the given number of blocks ago
+    Stale(u64)
+}
+
+#[derive(Debug, Default)]
+struct FaultSchedule {
+    scripted:     VecDeque<Fault>,
+    timeout_rate: f64,
+    timeout:      Duration,
+    error_rate:   f64,
+    stale_rate:   f64,
+    staleness:    u64
+}
+
+/// Decides which reads fail and how. Clones share the same schedule.
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    schedule: Arc<Mutex<FaultSchedule>>,
+    injected: Arc<AtomicUsize>
+}
+
+impl FaultInjector {
+    /// An injector that doesn't fail any read until it's told to.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails a share of the reads, after hanging on them for `timeout`.
+    pub fn with_timeouts(self, rate: f64, timeout: Duration) -> Self {
+        {
+            let mut schedule = self.schedule.lock();
+            schedule.timeout_rate = rate;
+            schedule.timeout = timeout;
+        }
+        self
+    }
+
+    /// Fails a share of the reads right away.
+    pub fn with_errors(self, rate: f64) -> Self {
+        self.schedule.lock().error_rate = rate;
+        self
+    }
+
+    /// Serves a share of the reads as of `staleness` blocks ago.
+    pub fn with_stale_reads(self, rate: f64, staleness: u64) -> Self {
+        {
+            let mut schedule = self.schedule.lock();
+            schedule.stale_rate = rate;
+            schedule.staleness = staleness;
+        }
+        self
+    }
+
+    /// Injects `fault` into the next read that isn't scripted already, ahead
+    /// of the random ones. A stale fault waits for a read that can be served
+    /// stale.
+    pub fn push(&self, fault: Fault) {
+        self.schedule.lock().scripted.push_back(fault);
+    }
+
+    /// Stops failing reads, drops the scripted faults along with the rates.
+    pub fn clear(&self) {
+        *self.schedule.lock() = FaultSchedule::default();
+    }
+
+    /// How many faults were injected so far.
+    pub fn injected(&self) -> usize {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    /// Fault to inject into the read that's made now, if any.
+    pub fn next_fault(&self) -> Option<Fault> {
+        self.draw(true)
+    }
+
+    /// Draws the fault of a read, stale faults only for a read that
+    /// `can_be_stale`.
+    fn draw(&self, can_be_stale: bool) -> Option<Fault> {
+        let fault = {
+            let mut schedule = self.schedule.lock();
+            match schedule.scripted.front() {
+                Some(Fault::Stale(_)) if !can_be_stale => None,
+                Some(_) => schedule.scripted.pop_front(),
+                None => {
+                    let mut rng = rand::thread_rng();
+                    let mut roll = |rate: f64| rate > 0.0 && rng.gen_bool(rate.min(1.0));
+                    if roll(schedule.timeout_rate) {
+                        Some(Fault::Timeout(schedule.timeout))
+                    } else if roll(schedule.error_rate) {
+                        Some(Fault::Transient)
+                    } else if can_be_stale && roll(schedule.stale_rate) {
+                        Some(Fault::Stale(schedule.staleness))
+                    } else {
+                        None
+                    }
+                }
+            }
+        };
+        if fault.is_some() {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+        }
+        fault
+    }
+
+    /// Injects the next fault into a read that can't be served stale, returns
+    /// whether the read has to fail.
+    fn fails(&self) -> bool {
+        match self.draw(false) {
+            Some(Fault::Timeout(timeout)) => {
+                hang(timeout);
+                true
+            }
+            Some(Fault::Transient) => true,
+            Some(Fault::Stale(_)) | None => false
+        }
+    }
+}
+
+/// Hangs the read for `timeout`. The reads are sync, so on a multi threaded
+/// runtime the worker hands its other tasks off before it sleeps. A current
+/// thread runtime is blocked for the timeout.
+fn hang(timeout: Duration) {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| std::thread::sleep(timeout))
+        }
+        _ => std::thread::sleep(timeout)
+    }
+}
+
+/// Wraps the state the validation reads with injected failures.
+#[derive(Debug, Clone)]
+pub struct FaultyStateProviderFactory<F> {
+    inner:  F,
+    faults: FaultInjector
+}
+
+impl<F> FaultyStateProviderFactory<F> {
+    pub fn new(inner: F, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: BlockStateProviderFactory> BlockStateProviderFactory for FaultyStateProviderFactory<F> {
+    type Provider = FaultyStateProvider<F::Provider>;
+
+    fn state_by_block(&self, block: u64) -> ProviderResult<Self::Provider> {
+        let block = match self.faults.next_fault() {
+            Some(Fault::Timeout(timeout)) => {
+                hang(timeout);
+                return Err(ProviderError::StateForNumberNotFound(block))
+            }
+            Some(Fault::Transient) => return Err(ProviderError::StateForNumberNotFound(block)),
+            Some(Fault::Stale(staleness)) => block.saturating_sub(staleness),
+            None => block
+        };
+
+        Ok(FaultyStateProvider {
+            inner: self.inner.state_by_block(block)?,
+            block,
+            faults: self.faults.clone()
+        })
+    }
+
+    fn best_block_number(&self) -> ProviderResult<BlockNumber> {
+        match self.faults.next_fault() {
+            Some(Fault::Timeout(timeout)) => {
+                hang(timeout);
+                Err(ProviderError::BestBlockNotFound)
+            }
+            Some(Fault::Transient) => Err(ProviderError::BestBlockNotFound),
+            Some(Fault::Stale(staleness)) => self
+                .inner
+                .best_block_number()
+                .map(|block| block.saturating_sub(staleness)),
+            None => self.inner.best_block_number()
+        }
+    }
+}
+
+/// The state the validation reads, failing some of its reads. None of them
+/// can be served stale.
+impl<F> DatabaseRef for FaultyStateProviderFactory<F>
+where
+    F: DatabaseRef,
+    F::Error: Debug
+{
+    type Error = eyre::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.read(|db| db.basic_ref(address))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.read(|db| db.code_by_hash_ref(code_hash))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.read(|db| db.storage_ref(address, index))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.read(|db| db.block_hash_ref(number))
+    }
+}
+
+impl<F> BatchStorageRead for FaultyStateProviderFactory<F>
+where
+    F: DatabaseRef,
+    F::Error: Debug
+{
+}
+
+impl<F> FaultyStateProviderFactory<F>
+where
+    F: DatabaseRef,
+    F::Error: Debug
+{
+    fn read<T>(&self, read: impl FnOnce(&F) -> Result<T, F::Error>) -> eyre::Result<T> {
+        if self.faults.fails() {
+            return Err(eyre::eyre!("injected fault"))
+        }
+        read(&self.inner).map_err(|e| eyre::eyre!("{e:?}"))
+    }
+}
+
+/// State of a block that fails some of its reads. A stale read is decided by
+/// the factory for the whole state.
+#[derive(Debug, Clone)]
+pub struct FaultyStateProvider<P> {
+    inner:  P,
+    block:  u64,
+    faults: FaultInjector
+}
+
+impl<P: BlockStateProvider> BlockStateProvider for FaultyStateProvider<P> {
+    fn get_basic_account(&self, address: Address) -> ProviderResult<Option<Account>> {
+        if self.faults.fails() {
+            return Err(ProviderError::AccountChangesetNotFound {
+                block_number: self.block,
+                address
+            })
+        }
+        self.inner.get_basic_account(address)
+    }
+
+    fn get_storage(
+        &self,
+        address: Address,
+        key: StorageKey
+    ) -> ProviderResult<Option<StorageValue>> {
+        if self.faults.fails() {
+            return Err(ProviderError::StorageChangesetNotFound {
+                block_number: self.block,
+                address,
+                storage_key: Box::new(key)
+            })
+        }
+        self.inner.get_storage(address, key)
+    }
+}
+
+/// Wraps the provider the pools are loaded and updated from with injected
+/// failures. A stale read of the logs misses every log, as a node that's
+/// behind would.
+#[derive(Debug, Clone)]
+pub struct FaultyPoolManagerProvider<P> {
+    inner:  P,
+    faults: FaultInjector
+}
+
+impl<P> FaultyPoolManagerProvider<P> {
+    pub fn new(inner: P, faults: FaultInjector) -> Self {
+        Self { inner, faults }
+    }
+
+    pub fn faults(&self) -> &FaultInjector {
+        &self.faults
+    }
+}
+
+impl<P: PoolManagerProvider> PoolManagerProvider for FaultyPoolManagerProvider<P> {
+    fn subscribe_blocks(self) -> futures::stream::BoxStream<'static, Option<PoolMangerBlocks>> {
+        self.inner.subscribe_blocks()
+    }
+
+    fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, PoolManagerError> {
+        let injected = || {
+            PoolManagerError::RpcTransportError(TransportErrorKind::custom_str("injected fault"))
+        };
+        match self.faults.next_fault() {
+            Some(Fault::Timeout(timeout)) => {
+                hang(timeout);
+                Err(injected())
+            }
+            Some(Fault::Transient) => Err(injected()),
+            Some(Fault::Stale(_)) => Ok(vec![]),
+            None => self.inner.get_logs(filter)
+        }
+    }
+
+    fn provider(&self) -> Arc<impl Provider> {
+        self.inner.provider()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::db::{CacheDB, EmptyDB};
+    use validation::common::state_backend::FallbackStateDb;
+
+    use super::*;
+
+    /// Chain at the given block, every account has the number of the block
+    /// its state is of as its nonce.
+    #[derive(Debug, Clone)]
+    struct Chain(u64);
+
+    struct State(u64);
+
+    impl BlockStateProvider for State {
+        fn get_basic_account(&self, _: Address) -> ProviderResult<Option<Account>> {
+            Ok(Some(Account {
+                nonce:         self.0,
+                balance:       U256::ZERO,
+                bytecode_hash: None
+            }))
+        }
+
+        fn get_storage(&self, _: Address, _: StorageKey) -> ProviderResult<Option<StorageValue>> {
+            Ok(None)
+        }
+    }
+
+    impl BlockStateProviderFactory for Chain {
+        type Provider = State;
+
+        fn state_by_block(&self, block: u64) -> ProviderResult<State> {
+            Ok(State(block))
+        }
+
+        fn best_block_number(&self) -> ProviderResult<BlockNumber> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn injects_scripted_faults_in_order() {
+        let faults = FaultInjector::new();
+        let provider = FaultyStateProviderFactory::new(Chain(10), faults.clone());
+
+        faults.push(Fault::Transient);
+        faults.push(Fault::Stale(3));
+        faults.push(Fault::Timeout(Duration::from_millis(1)));
+        assert!(provider.best_block_number().is_err());
+        assert_eq!(provider.best_block_number().unwrap(), 7);
+
+        // the timeout is taken by the state, the account read goes through
+        assert!(provider.state_by_block(10).is_err());
+        let state = provider.state_by_block(10).unwrap();
+        assert_eq!(
+            state
+                .get_basic_account(Address::ZERO)
+                .unwrap()
+                .unwrap()
+                .nonce,
+            10
+        );
+
+        faults.push(Fault::Transient);
+        assert!(state.get_basic_account(Address::ZERO).is_err());
+        assert_eq!(faults.injected(), 4);
+    }
+
+    #[test]
+    fn serves_stale_state_at_random() {
+        let faults = FaultInjector::new().with_stale_reads(1.0, 2);
+        let provider = FaultyStateProviderFactory::new(Chain(10), faults.clone());
+
+        let state = provider.state_by_block(10).unwrap();
+        assert_eq!(
+            state
+                .get_basic_account(Address::ZERO)
+                .unwrap()
+                .unwrap()
+                .nonce,
+            8
+        );
+
+        faults.clear();
+        assert_eq!(provider.best_block_number().unwrap(), 10);
+        assert_eq!(faults.injected(), 2);
+    }
+
+    #[test]
+    fn keeps_stale_faults_for_reads_that_can_be_stale() {
+        let faults = FaultInjector::new();
+        let provider = FaultyStateProviderFactory::new(Chain(10), faults.clone());
+        let state = provider.state_by_block(10).unwrap();
+
+        faults.push(Fault::Stale(3));
+        assert!(state.get_basic_account(Address::ZERO).is_ok());
+        assert_eq!(faults.injected(), 0);
+
+        assert_eq!(provider.best_block_number().unwrap(), 7);
+        assert_eq!(faults.injected(), 1);
+    }
+
+    #[test]
+    fn validation_state_falls_back_from_failed_reads() {
+        let address = Address::random();
+        let slot = U256::from(1);
+        let db = |value: u64| {
+            let mut db = CacheDB::new(EmptyDB::default());
+            db.insert_account_storage(address, slot, U256::from(value))
+                .unwrap();
+            db
+        };
+        let faults = FaultInjector::new();
+        let state = FallbackStateDb::new(FaultyStateProviderFactory::new(db(1), faults.clone()))
+            .with_remote(db(2));
+        assert_eq!(state.storage_ref(address, slot).unwrap(), U256::from(1));
+
+        faults.push(Fault::Transient);
+        assert_eq!(state.storage_ref(address, slot).unwrap(), U256::from(2));
+        faults.push(Fault::Timeout(Duration::from_millis(1)));
+        assert_eq!(
+            state
+                .storage_batch(&[(address, slot), (address, slot)])
+                .unwrap(),
+            vec![U256::from(2); 2]
+        );
+
+        // the reads of the validation can't be served stale
+        faults.push(Fault::Stale(1));
+        assert_eq!(state.storage_ref(address, slot).unwrap(), U256::from(1));
+        assert_eq!(faults.injected(), 2);
+    }
+}
@@ -10,6 +10,8 @@ pub use anvil_cleanser::*;
 pub use anvil_provider::*;
 pub use state_provider::*;
 mod block_provider;
+mod fault_injection;
+pub use fault_injection::*;
 pub mod utils;
 pub use block_provider::*;
 mod initializer;
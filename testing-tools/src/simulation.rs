@@ -0,0 +1,208 @@
+//! Runs node components on a [`LogicalClock`] so that a run can be repeated
+//! exactly.
+//!
+//! Tasks are only ever polled from [`Simulation::step`], one after another in
+//! the order they were spawned, and time only moves once every task is
+//! waiting on it. Given the same tasks the same events happen at the same
+//! logical time on every run.
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc
+    },
+    task::{Context, Poll},
+    time::Duration
+};
+
+use angstrom_utils::clock::{Clock, LogicalClock};
+use futures::task::{waker, ArcWake};
+
+/// Steps a task can take without time moving before the run is considered
+/// stuck.
+const MAX_STEPS_PER_INSTANT: usize = 100_000;
+
+pub struct Simulation {
+    clock:   LogicalClock,
+    /// entered while polling, so the tokio primitives the tasks construct
+    /// have a runtime. it is never driven itself
+    runtime: tokio::runtime::Runtime,
+    tasks:   Vec<SimTask>
+}
+
+struct SimTask {
+    name:   &'static str,
+    future: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    woken:  Arc<Woken>
+}
+
+struct Woken(AtomicBool);
+
+impl ArcWake for Woken {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Simulation {
+    /// A simulation whose clock starts at `unix_start` after the unix epoch.
+    pub fn new(unix_start: Duration) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the simulation runtime");
+
+        Self { clock: LogicalClock::new(unix_start), runtime, tasks: vec![] }
+    }
+
+    /// Clock to hand to the components under simulation.
+    pub fn clock(&self) -> Clock {
+        Clock::logical(self.clock.clone())
+    }
+
+    pub fn logical_clock(&self) -> &LogicalClock {
+        &self.clock
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    /// Runs `f` inside of the simulation runtime, for building components that
+    /// construct tokio primitives.
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.runtime.enter();
+        f()
+    }
+
+    /// Adds a task, it's first polled on the next step.
+    pub fn spawn(&mut self, name: &'static str, future: impl Future<Output = ()> + 'static) {
+        self.tasks.push(SimTask {
+            name,
+            future: Some(Box::pin(future)),
+            woken: Arc::new(Woken(AtomicBool::new(true)))
+        });
+    }
+
+    /// Names of the tasks that haven't finished yet.
+    pub fn pending_tasks(&self) -> Vec<&'static str> {
+        self.tasks
+            .iter()
+            .filter(|task| task.future.is_some())
+            .map(|task| task.name)
+            .collect()
+    }
+
+    /// Polls every woken task once, in spawn order. Returns how many were
+    /// polled.
+    pub fn step(&mut self) -> usize {
+        let _guard = self.runtime.enter();
+        let mut polled = 0;
+        for task in &mut self.tasks {
+            let Some(future) = task.future.as_mut() else { continue };
+            if !task.woken.0.swap(false, Ordering::SeqCst) {
+                continue
+            }
+
+            polled += 1;
+            let waker = waker(task.woken.clone());
+            if let Poll::Ready(()) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                tracing::trace!(task = task.name, elapsed = ?self.clock.elapsed(), "finished");
+                task.future = None;
+            }
+        }
+
+        polled
+    }
+
+    /// Steps until no task is woken anymore, without moving the clock.
+    pub fn run_until_idle(&mut self) {
+        for _ in 0..MAX_STEPS_PER_INSTANT {
+            if self.step() == 0 {
+                return
+            }
+        }
+        panic!(
+            "tasks {:?} didn't settle at {:?}, the simulation is live locked",
+            self.pending_tasks(),
+            self.clock.elapsed()
+        );
+    }
+
+    /// Runs the tasks for `duration` of logical time. The clock jumps from
+    /// one deadline to the next, so idle time doesn't cost anything.
+    pub fn run_for(&mut self, duration: Duration) {
+        let end = self.clock.elapsed() + duration;
+        loop {
+            self.run_until_idle();
+
+            let now = self.clock.now();
+            let remaining = end.saturating_sub(self.clock.elapsed());
+            match self.clock.next_deadline() {
+                Some(deadline) if deadline.saturating_duration_since(now) <= remaining => {
+                    self.clock.advance_to_next_deadline();
+                }
+                _ => {
+                    self.clock.advance(remaining);
+                    self.run_until_idle();
+                    return
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn ping_pong() -> Vec<(Duration, String)> {
+        let mut sim = Simulation::new(Duration::from_secs(1_700_000_000));
+        let trace = Rc::new(RefCell::new(vec![]));
+        let (ping_tx, mut ping_rx) = mpsc::unbounded_channel();
+        let (pong_tx, mut pong_rx) = mpsc::unbounded_channel();
+
+        let (clock, log) = (sim.clock(), trace.clone());
+        sim.spawn("pinger", async move {
+            for round in 0..5u64 {
+                clock.sleep(Duration::from_millis(250 * (round + 1))).await;
+                ping_tx.send(round).unwrap();
+                let pong = pong_rx.recv().await.unwrap();
+                log.borrow_mut()
+                    .push((clock.unix_now(), format!("pong {pong}")));
+            }
+        });
+
+        let (clock, log) = (sim.clock(), trace.clone());
+        sim.spawn("ponger", async move {
+            while let Some(ping) = ping_rx.recv().await {
+                log.borrow_mut()
+                    .push((clock.unix_now(), format!("ping {ping}")));
+                clock.sleep(Duration::from_millis(100)).await;
+                if pong_tx.send(ping).is_err() {
+                    return
+                }
+            }
+        });
+
+        sim.run_for(Duration::from_secs(12));
+        assert!(sim.pending_tasks().is_empty());
+        assert_eq!(sim.elapsed(), Duration::from_secs(12));
+
+        Rc::try_unwrap(trace).unwrap().into_inner()
+    }
+
+    #[test]
+    fn runs_are_reproducible() {
+        let first = ping_pong();
+        assert_eq!(first.len(), 10);
+        // 250ms to the first ping, 100ms for its pong
+        assert_eq!(first[1].0 - first[0].0, Duration::from_millis(100));
+        assert_eq!(first, ping_pong());
+    }
+}
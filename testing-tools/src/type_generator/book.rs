@@ -1,5 +1,5 @@
 use angstrom_types::{
-    matching::{uniswap::PoolSnapshot, SqrtPriceX96},
+    matching::uniswap::PoolSnapshot,
     primitive::PoolId,
     sol_bindings::grouped_orders::{GroupedVanillaOrder, OrderWithStorageData}
 };
@@ -8,7 +8,8 @@ use uniswap_v3_math::tick_math::get_tick_at_sqrt_ratio;
 
 use super::{
     amm::generate_single_position_amm_at_tick,
-    orders::{DistributionParameters, OrderDistributionBuilder}
+    orders::{DistributionParameters, OrderDistributionBuilder},
+    sqrt_price_from_float
 };
 
 // What are the parameters of an order builder?  A set of orders can be from
@@ -83,7 +84,7 @@ pub fn generate_simple_cross_book(pool_id: PoolId, order_count: usize, price: f6
         .valid_block(valid_block)
         .build()
         .unwrap();
-    let amm_tick = get_tick_at_sqrt_ratio(SqrtPriceX96::from_float_price(price).into()).unwrap();
+    let amm_tick = get_tick_at_sqrt_ratio(sqrt_price_from_float(price).into()).unwrap();
     let amm = generate_single_position_amm_at_tick(amm_tick, 10000, 2e18 as u128);
     BookBuilder::new()
         .poolid(pool_id)
@@ -128,7 +129,7 @@ pub fn generate_one_sided_book(
             .build()
             .unwrap()
     };
-    let amm_tick = get_tick_at_sqrt_ratio(SqrtPriceX96::from_float_price(price).into()).unwrap();
+    let amm_tick = get_tick_at_sqrt_ratio(sqrt_price_from_float(price).into()).unwrap();
     let amm = generate_single_position_amm_at_tick(amm_tick, 10000, 2e18 as u128);
     BookBuilder::new()
         .poolid(pool_id)
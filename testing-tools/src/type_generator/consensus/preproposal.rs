@@ -126,7 +126,11 @@ impl PreproposalBuilder {
                     order_id,
                     pool_id: pool_id.id(),
                     valid_block: block,
-                    tob_reward: U256::ZERO
+                    tob_reward: U256::ZERO,
+                    bond_tier: 0,
+                    is_private: false,
+                    is_hidden: false,
+                    peg: None
                 }
             })
             .collect();
@@ -1,4 +1,19 @@
+use angstrom_types::matching::{Ray, SqrtPriceX96};
+
 pub mod amm;
 pub mod book;
 pub mod consensus;
 pub mod orders;
+
+/// The price of a sampled float price. It goes through the decimal digits of
+/// the float, which are the same on every architecture, instead of float math.
+pub fn ray_from_float(price: f64) -> Ray {
+    format!("{price:.27}")
+        .parse()
+        .expect("price is positive and finite")
+}
+
+/// The sqrt price of a sampled float price, see [`ray_from_float`].
+pub fn sqrt_price_from_float(price: f64) -> SqrtPriceX96 {
+    SqrtPriceX96::from(ray_from_float(price))
+}
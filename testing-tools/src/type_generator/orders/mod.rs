@@ -114,7 +114,11 @@ impl StoredOrderBuilder {
             order_id,
             pool_id,
             valid_block,
-            tob_reward
+            tob_reward,
+            bond_tier: 0,
+            is_private: false,
+            is_hidden: false,
+            peg: None
         }
     }
 }
@@ -197,7 +201,11 @@ pub fn generate_top_of_block_order(
         order_id,
         pool_id,
         valid_block,
-        tob_reward: U256::ZERO
+        tob_reward: U256::ZERO,
+        bond_tier: 0,
+        is_private: false,
+        is_hidden: false,
+        peg: None
     }
 }
 
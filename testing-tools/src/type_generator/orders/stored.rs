@@ -73,7 +73,11 @@ impl<Order: RawPoolOrder> StoredOrderBuilder<Order> {
             order_id,
             pool_id,
             valid_block,
-            tob_reward
+            tob_reward,
+            bond_tier: 0,
+            is_private: false,
+            is_hidden: false,
+            peg: None
         }
     }
 }
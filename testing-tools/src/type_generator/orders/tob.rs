@@ -1,4 +1,4 @@
-use alloy::{primitives::Address, signers::SignerSync};
+use alloy::{primitives::Address, signers::SignerSync, sol_types::Eip712Domain};
 use angstrom_types::{
     primitive::{AngstromSigner, ANGSTROM_DOMAIN},
     sol_bindings::rpc_orders::{OmitOrderMeta, OrderMeta, TopOfBlockOrder}
@@ -13,7 +13,10 @@ pub struct ToBOrderBuilder {
     quantity_in:  Option<u128>,
     quantity_out: Option<u128>,
     valid_block:  Option<u64>,
-    signing_key:  Option<AngstromSigner>
+    max_gas:      Option<u128>,
+    signing_key:  Option<AngstromSigner>,
+    /// what the order is signed for, [`ANGSTROM_DOMAIN`] if unset
+    domain:       Option<Eip712Domain>
 }
 
 impl ToBOrderBuilder {
@@ -45,10 +48,20 @@ impl ToBOrderBuilder {
         Self { valid_block: Some(valid_block), ..self }
     }
 
+    pub fn max_gas(self, max_gas: u128) -> Self {
+        Self { max_gas: Some(max_gas), ..self }
+    }
+
     pub fn signing_key(self, signing_key: Option<AngstromSigner>) -> Self {
         Self { signing_key, ..self }
     }
 
+    /// Signs the order for the angstrom contract of `domain`, see
+    /// [`angstrom_domain`](angstrom_types::primitive::angstrom_domain).
+    pub fn domain(self, domain: Eip712Domain) -> Self {
+        Self { domain: Some(domain), ..self }
+    }
+
     pub fn build(self) -> TopOfBlockOrder {
         let mut order = TopOfBlockOrder {
             asset_in: self.asset_in.unwrap_or_default(),
@@ -57,11 +70,12 @@ impl ToBOrderBuilder {
             quantity_out: self.quantity_out.unwrap_or_default(),
             valid_for_block: self.valid_block.unwrap_or_default(),
             recipient: self.recipient.unwrap_or_else(|| Address::random()),
-            max_gas_asset0: 0,
+            max_gas_asset0: self.max_gas.unwrap_or_default(),
             ..Default::default()
         };
         if let Some(signer) = self.signing_key {
-            let hash = order.no_meta_eip712_signing_hash(&ANGSTROM_DOMAIN);
+            let domain = self.domain.unwrap_or(ANGSTROM_DOMAIN);
+            let hash = order.no_meta_eip712_signing_hash(&domain);
             let sig = signer.sign_hash_sync(&hash).unwrap();
             order.meta = OrderMeta {
                 isEcdsa:   true,
@@ -6,7 +6,9 @@ pub struct DevnetConfig {
     pub intial_node_count: u64,
     pub initial_rpc_port:  u16,
     pub fork_block_number: Option<u64>,
-    pub fork_url:          Option<String>
+    pub fork_url:          Option<String>,
+    /// pools the leader deploys before the nodes start
+    pub pool_keys:         Vec<PartialConfigPoolKey>
 }
 
 impl DevnetConfig {
@@ -16,7 +18,13 @@ impl DevnetConfig {
         fork_block_number: Option<u64>,
         fork_url: Option<String>
     ) -> Self {
-        Self { intial_node_count, initial_rpc_port, fork_block_number, fork_url }
+        Self {
+            intial_node_count,
+            initial_rpc_port,
+            fork_block_number,
+            fork_url,
+            pool_keys: Vec::new()
+        }
     }
 
     pub fn rpc_port_with_node_id(&self, node_id: Option<u64>) -> u64 {
@@ -34,7 +42,8 @@ impl Default for DevnetConfig {
             intial_node_count: 5,
             initial_rpc_port:  4200,
             fork_block_number: None,
-            fork_url:          None
+            fork_url:          None,
+            pool_keys:         Vec::new()
         }
     }
 }
@@ -65,7 +74,7 @@ impl GlobalTestingConfig for DevnetConfig {
     }
 
     fn pool_keys(&self) -> Vec<PartialConfigPoolKey> {
-        Vec::new()
+        self.pool_keys.clone()
     }
 
     fn leader_eth_rpc_port(&self) -> u16 {
@@ -31,6 +31,8 @@ where
     fn send_propose(&mut self, proposal: Proposal);
 
     fn send_prepropose(&mut self, preproposal: PreProposal);
+
+    fn send_random_orders(&mut self, id: Option<u64>);
 }
 
 impl<'a, C> WithCheckedAction<'a, C> for DevnetStateMachine<'a, C>
@@ -75,6 +77,13 @@ where
         };
         self.add_checked_action("send prepropose", f);
     }
+
+    fn send_random_orders(&mut self, id: Option<u64>) {
+        let f = move |testnet: &'a mut AngstromTestnet<C, DevnetConfig, WalletProvider>| {
+            pin_action(testnet.submit_random_orders(id))
+        };
+        self.add_checked_action("send random orders", f);
+    }
 }
 
 fn pin_action<'a, F>(fut: F) -> Pin<Box<dyn Future<Output = eyre::Result<bool>> + Send + Sync + 'a>>
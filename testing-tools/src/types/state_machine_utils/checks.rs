@@ -20,6 +20,8 @@ where
     type FunctionOutput = StateMachineCheckHookFn<C>;
 
     fn check_block(&mut self, block_number: u64);
+
+    fn check_solution_equality(&mut self, block_number: u64);
 }
 
 impl<'a, C> WithCheck<C> for DevnetStateMachine<'a, C>
@@ -38,4 +40,11 @@ where
         };
         self.add_check("check block", f);
     }
+
+    fn check_solution_equality(&mut self, block_number: u64) {
+        let f = move |testnet: &mut AngstromTestnet<C, DevnetConfig, WalletProvider>| {
+            testnet.check_solution_equality(block_number)
+        };
+        self.add_check("check solution equality", f);
+    }
 }
@@ -6,6 +6,7 @@ use std::{
     time::Duration
 };
 
+use alloy::sol_types::Eip712Domain;
 use alloy_primitives::{Address, U256};
 use angstrom_types::pair_with_price::PairsWithPrice;
 use futures::{FutureExt, Stream};
@@ -61,6 +62,7 @@ where
         token_conversion: TokenPriceGenerator,
         token_updates: Pin<Box<dyn Stream<Item = Vec<PairsWithPrice>> + Send + Sync + 'static>>,
         pool_storage: AngstromPoolsTracker,
+        domain: Eip712Domain,
         node_id: u64
     ) -> eyre::Result<Self> {
         let current_block = Arc::new(AtomicU64::new(BlockNumReader::best_block_number(&db)?));
@@ -73,7 +75,9 @@ where
         let sim = SimValidation::new(db.clone(), angstrom_address, node_address);
 
         let order_validator =
-            OrderValidator::new(sim, current_block, pool_storage, fetch, uniswap_pools).await;
+            OrderValidator::new(sim, current_block, pool_storage, fetch, uniswap_pools)
+                .await
+                .with_domain(domain);
 
         let bundle_validator = BundleValidator::new(db.clone(), angstrom_address, node_address);
         let shared_utils = SharedTools::new(token_conversion, token_updates, thread_pool);